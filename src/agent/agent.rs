@@ -4,6 +4,7 @@ use crate::agent::dispatcher::{
 use crate::agent::memory_loader::{DefaultMemoryLoader, MemoryLoader};
 use crate::agent::prompt::{PromptContext, SystemPromptBuilder};
 use crate::config::Config;
+use crate::cost::guard::BudgetGuard;
 use crate::memory::{self, Memory, MemoryCategory};
 use crate::observability::{self, Observer, ObserverEvent};
 use crate::providers::{self, ChatMessage, ChatRequest, ConversationMessage, Provider};
@@ -301,6 +302,7 @@ impl Agent {
             &config.model_routes,
             &model_name,
         )?;
+        let provider = BudgetGuard::wrap(provider, &config.cost, &config.workspace_dir)?;
 
         let dispatcher_choice = config.agent.tool_dispatcher.as_str();
         let tool_dispatcher: Box<dyn ToolDispatcher> = match dispatcher_choice {
@@ -392,11 +394,12 @@ impl Agent {
         let result = if let Some(tool) = self.tools.iter().find(|t| t.name() == call.name) {
             match tool.execute(call.arguments.clone()).await {
                 Ok(r) => {
-                    self.observer.record_event(&ObserverEvent::ToolCall {
-                        tool: call.name.clone(),
-                        duration: start.elapsed(),
-                        success: r.success,
-                    });
+                    self.observer
+                        .record_event(&crate::agent::loop_::tool_result_event(
+                            &call.name,
+                            start.elapsed(),
+                            &r,
+                        ));
                     if r.success {
                         r.output
                     } else {
@@ -573,7 +576,7 @@ impl Agent {
         println!("Type /quit to exit.\n");
 
         let (tx, mut rx) = tokio::sync::mpsc::channel(32);
-        let cli = crate::channels::CliChannel::new();
+        let cli = crate::channels::CliChannel::with_workspace(self.workspace_dir.clone());
 
         let listen_handle = tokio::spawn(async move {
             let _ = crate::channels::Channel::listen(&cli, tx).await;
@@ -1,5 +1,7 @@
 use crate::approval::{ApprovalManager, ApprovalRequest, ApprovalResponse};
+use crate::channels::cli::CliCommand;
 use crate::config::Config;
+use crate::cost::guard::BudgetGuard;
 use crate::memory::{self, Memory, MemoryCategory};
 use crate::multimodal;
 use crate::observability::{self, runtime_trace, Observer, ObserverEvent};
@@ -1820,7 +1822,7 @@ impl std::fmt::Display for ToolLoopCancelled {
 
 impl std::error::Error for ToolLoopCancelled {}
 
-pub(crate) fn is_tool_loop_cancelled(err: &anyhow::Error) -> bool {
+pub fn is_tool_loop_cancelled(err: &anyhow::Error) -> bool {
     err.chain().any(|source| source.is::<ToolLoopCancelled>())
 }
 
@@ -1839,6 +1841,8 @@ pub(crate) async fn agent_turn(
     silent: bool,
     multimodal_config: &crate::config::MultimodalConfig,
     max_tool_iterations: usize,
+    cancellation_token: Option<CancellationToken>,
+    approval: Option<&ApprovalManager>,
 ) -> Result<String> {
     run_tool_call_loop(
         provider,
@@ -1849,11 +1853,11 @@ pub(crate) async fn agent_turn(
         model,
         temperature,
         silent,
-        None,
+        approval,
         "channel",
         multimodal_config,
         max_tool_iterations,
-        None,
+        cancellation_token,
         None,
         None,
         &[],
@@ -1874,6 +1878,31 @@ async fn execute_one_tool(
     tools_registry: &[Box<dyn Tool>],
     observer: &dyn Observer,
     cancellation_token: Option<&CancellationToken>,
+) -> Result<ToolExecutionOutcome> {
+    let traced_arguments = tool_trace_active().then(|| call_arguments.clone());
+    if let Some(arguments) = traced_arguments.as_ref() {
+        record_tool_start(call_name, arguments);
+    }
+    let outcome = run_one_tool(
+        call_name,
+        call_arguments,
+        tools_registry,
+        observer,
+        cancellation_token,
+    )
+    .await?;
+    if let Some(arguments) = traced_arguments {
+        record_tool_trace(call_name, &arguments, &outcome);
+    }
+    Ok(outcome)
+}
+
+async fn run_one_tool(
+    call_name: &str,
+    call_arguments: serde_json::Value,
+    tools_registry: &[Box<dyn Tool>],
+    observer: &dyn Observer,
+    cancellation_token: Option<&CancellationToken>,
 ) -> Result<ToolExecutionOutcome> {
     observer.record_event(&ObserverEvent::ToolCallStart {
         tool: call_name.to_string(),
@@ -1883,8 +1912,9 @@ async fn execute_one_tool(
     let Some(tool) = find_tool(tools_registry, call_name) else {
         let reason = format!("Unknown tool: {call_name}");
         let duration = start.elapsed();
+        // Model-invented names would make metric labels unbounded.
         observer.record_event(&ObserverEvent::ToolCall {
-            tool: call_name.to_string(),
+            tool: UNKNOWN_TOOL_LABEL.to_string(),
             duration,
             success: false,
         });
@@ -1909,11 +1939,7 @@ async fn execute_one_tool(
     match tool_result {
         Ok(r) => {
             let duration = start.elapsed();
-            observer.record_event(&ObserverEvent::ToolCall {
-                tool: call_name.to_string(),
-                duration,
-                success: r.success,
-            });
+            observer.record_event(&tool_result_event(call_name, duration, &r));
             if r.success {
                 Ok(ToolExecutionOutcome {
                     output: scrub_credentials(&r.output),
@@ -1949,6 +1975,47 @@ async fn execute_one_tool(
     }
 }
 
+/// Tool label recorded for calls naming a tool that is not registered.
+const UNKNOWN_TOOL_LABEL: &str = "unknown";
+
+/// Error prefixes and phrases tools use when the security policy refuses an
+/// action before any work is done.
+const POLICY_REJECTION_MARKERS: &[&str] = &[
+    "Action blocked:",
+    "by security policy",
+    "Security policy:",
+    "Rate limit exceeded",
+];
+
+fn is_policy_rejection(result: &crate::tools::ToolResult) -> bool {
+    !result.success
+        && result.error.as_deref().is_some_and(|error| {
+            POLICY_REJECTION_MARKERS
+                .iter()
+                .any(|marker| error.contains(marker))
+        })
+}
+
+/// Observer event for a finished `Tool::execute`; policy refusals are
+/// reported as blocked rather than failed.
+pub(crate) fn tool_result_event(
+    tool: &str,
+    duration: Duration,
+    result: &crate::tools::ToolResult,
+) -> ObserverEvent {
+    if is_policy_rejection(result) {
+        ObserverEvent::ToolCallBlocked {
+            tool: tool.to_string(),
+        }
+    } else {
+        ObserverEvent::ToolCall {
+            tool: tool.to_string(),
+            duration,
+            success: result.success,
+        }
+    }
+}
+
 struct ToolExecutionOutcome {
     output: String,
     success: bool,
@@ -1956,6 +2023,173 @@ struct ToolExecutionOutcome {
     duration: Duration,
 }
 
+// ── Tool-call trace ───────────────────────────────────────────────────────
+// `process_message_traced` scopes a collector over one agent run; every tool
+// execution inside that task appends a redacted, truncated entry.
+
+/// Longest string kept per argument value in a trace entry.
+const TRACE_ARG_MAX_CHARS: usize = 300;
+/// Longest tool output kept in a trace entry.
+const TRACE_OUTPUT_MAX_CHARS: usize = 1000;
+/// Secret env values shorter than this are not worth matching.
+const TRACE_SECRET_MIN_CHARS: usize = 8;
+const TRACE_REDACTED: &str = "[REDACTED]";
+
+/// One tool invocation recorded during a traced agent run.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ToolTraceEntry {
+    pub tool: String,
+    pub args: serde_json::Value,
+    pub success: bool,
+    pub duration_ms: u64,
+    pub output: String,
+}
+
+/// Tool invocations made while producing one reply, in execution order.
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct AgentTrace {
+    pub tool_calls: Vec<ToolTraceEntry>,
+}
+
+/// Tool lifecycle event delivered to a trace listener as it happens.
+#[derive(Debug, Clone)]
+pub enum ToolTraceEvent {
+    Started {
+        tool: String,
+        args: serde_json::Value,
+    },
+    Finished(ToolTraceEntry),
+}
+
+type ToolTraceListener = Arc<dyn Fn(&ToolTraceEvent) + Send + Sync>;
+
+tokio::task_local! {
+    static TOOL_TRACE: Arc<parking_lot::Mutex<Vec<ToolTraceEntry>>>;
+    static TOOL_TRACE_LISTENER: ToolTraceListener;
+}
+
+fn tool_trace_active() -> bool {
+    TOOL_TRACE.try_with(|_| ()).is_ok() || TOOL_TRACE_LISTENER.try_with(|_| ()).is_ok()
+}
+
+fn notify_tool_trace(event: &ToolTraceEvent) {
+    let _ = TOOL_TRACE_LISTENER.try_with(|listener| listener(event));
+}
+
+fn record_tool_start(call_name: &str, arguments: &serde_json::Value) {
+    if TOOL_TRACE_LISTENER.try_with(|_| ()).is_err() {
+        return;
+    }
+    notify_tool_trace(&ToolTraceEvent::Started {
+        tool: call_name.to_string(),
+        args: redact_trace_value(arguments, &secret_env_values()),
+    });
+}
+
+fn record_tool_trace(
+    call_name: &str,
+    arguments: &serde_json::Value,
+    outcome: &ToolExecutionOutcome,
+) {
+    let secrets = secret_env_values();
+    let entry = ToolTraceEntry {
+        tool: call_name.to_string(),
+        args: redact_trace_value(arguments, &secrets),
+        success: outcome.success,
+        duration_ms: u64::try_from(outcome.duration.as_millis()).unwrap_or(u64::MAX),
+        output: truncate_with_ellipsis(
+            &redact_trace_text(&outcome.output, &secrets),
+            TRACE_OUTPUT_MAX_CHARS,
+        ),
+    };
+    notify_tool_trace(&ToolTraceEvent::Finished(entry.clone()));
+    let _ = TOOL_TRACE.try_with(|trace| trace.lock().push(entry));
+}
+
+/// Values of environment variables whose names look like credentials.
+fn secret_env_values() -> Vec<String> {
+    std::env::vars()
+        .filter(|(name, value)| {
+            SENSITIVE_KEY_PATTERNS.is_match(name) && value.chars().count() >= TRACE_SECRET_MIN_CHARS
+        })
+        .map(|(_, value)| value)
+        .collect()
+}
+
+fn redact_trace_text(text: &str, secrets: &[String]) -> String {
+    let mut out = text.to_string();
+    for secret in secrets {
+        if out.contains(secret.as_str()) {
+            out = out.replace(secret.as_str(), TRACE_REDACTED);
+        }
+    }
+    scrub_credentials(&out)
+}
+
+/// Redact and truncate tool arguments: values under credential-like keys are
+/// dropped, and known secret env values are masked wherever they appear.
+fn redact_trace_value(value: &serde_json::Value, secrets: &[String]) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(map) => serde_json::Value::Object(
+            map.iter()
+                .map(|(key, inner)| {
+                    let redacted = if SENSITIVE_KEY_PATTERNS.is_match(key) {
+                        serde_json::Value::String(TRACE_REDACTED.into())
+                    } else {
+                        redact_trace_value(inner, secrets)
+                    };
+                    (key.clone(), redacted)
+                })
+                .collect(),
+        ),
+        serde_json::Value::Array(items) => serde_json::Value::Array(
+            items
+                .iter()
+                .map(|item| redact_trace_value(item, secrets))
+                .collect(),
+        ),
+        serde_json::Value::String(text) => serde_json::Value::String(truncate_with_ellipsis(
+            &redact_trace_text(text, secrets),
+            TRACE_ARG_MAX_CHARS,
+        )),
+        other => other.clone(),
+    }
+}
+
+/// Run `fut` with tool tracing enabled and return its output with the trace.
+async fn with_tool_trace<F>(fut: F) -> (F::Output, AgentTrace)
+where
+    F: std::future::Future,
+{
+    let collector = Arc::new(parking_lot::Mutex::new(Vec::new()));
+    let output = TOOL_TRACE.scope(collector.clone(), fut).await;
+    let tool_calls = std::mem::take(&mut *collector.lock());
+    (output, AgentTrace { tool_calls })
+}
+
+/// Run `fut` with `listener` called for each tool start and finish, using the
+/// same redacted entries as [`AgentTrace`].
+pub async fn with_tool_trace_listener<F, L>(listener: L, fut: F) -> F::Output
+where
+    F: std::future::Future,
+    L: Fn(&ToolTraceEvent) + Send + Sync + 'static,
+{
+    TOOL_TRACE_LISTENER.scope(Arc::new(listener), fut).await
+}
+
+tokio::task_local! {
+    static SYSTEM_PROMPT_SUFFIX: String;
+}
+
+/// Run `fut` with `suffix` appended to the system prompt of each
+/// [`process_message_with_cancellation`] run inside it.
+pub async fn with_system_prompt_suffix<F>(suffix: String, fut: F) -> F::Output
+where
+    F: std::future::Future,
+{
+    SYSTEM_PROMPT_SUFFIX.scope(suffix, fut).await
+}
+
 fn should_execute_tools_in_parallel(
     tool_calls: &[ParsedToolCall],
     approval: Option<&ApprovalManager>,
@@ -2425,17 +2659,24 @@ pub(crate) async fn run_tool_call_loop(
                         arguments: tool_args.clone(),
                     };
 
-                    // Only prompt interactively on CLI; auto-approve on other channels.
-                    let decision = if channel_name == "cli" {
-                        mgr.prompt_cli(&request)
+                    // Prompt on CLI, park chat runs until the thread decides,
+                    // and auto-approve elsewhere.
+                    let (decision, denial) = if channel_name == "cli" {
+                        (mgr.prompt_cli(&request), None)
+                    } else if mgr.parks_requests() {
+                        mgr.request_chat_approval(&request, cancellation_token.as_ref())
+                            .await
                     } else {
-                        ApprovalResponse::Yes
+                        (ApprovalResponse::Yes, None)
                     };
 
                     mgr.record_decision(&tool_name, &tool_args, decision, channel_name);
 
                     if decision == ApprovalResponse::No {
-                        let denied = "Denied by user.".to_string();
+                        let denied = denial.unwrap_or_else(|| "Denied by user.".to_string());
+                        observer.record_event(&ObserverEvent::ToolCallBlocked {
+                            tool: tool_name.clone(),
+                        });
                         runtime_trace::record_event(
                             "tool_call_result",
                             Some(channel_name),
@@ -2715,6 +2956,14 @@ pub(crate) fn build_tool_instructions(tools_registry: &[Box<dyn Tool>]) -> Strin
     instructions
 }
 
+/// Approval gate for runs nobody answers at a terminal. `requires_approval`
+/// tools are parked for the originating chat or, with no chat to ask, the
+/// approvals API.
+fn unattended_approval_manager(config: &Config) -> Option<ApprovalManager> {
+    (!config.autonomy.requires_approval.is_empty())
+        .then(|| ApprovalManager::for_chat(&config.autonomy, &config.workspace_dir))
+}
+
 // ── CLI Entrypoint ───────────────────────────────────────────────────────
 // Wires up all subsystems (observer, runtime, security, memory, tools,
 // provider) and enters either single-shot or
@@ -2802,6 +3051,7 @@ pub async fn run(
         model_name,
         &provider_runtime_options,
     )?;
+    let provider = BudgetGuard::wrap(provider, &config.cost, &config.workspace_dir)?;
 
     observer.record_event(&ObserverEvent::AgentStart {
         provider: provider_name.to_string(),
@@ -2837,7 +3087,7 @@ pub async fn run(
     let approval_manager = if interactive {
         Some(ApprovalManager::from_config(&config.autonomy))
     } else {
-        None
+        unattended_approval_manager(&config)
     };
     let channel_name = if interactive { "cli" } else { "daemon" };
 
@@ -2897,49 +3147,65 @@ pub async fn run(
         println!("🦀 ZeroClaw Interactive Mode");
         println!("Type /help for commands.\n");
         let cli = crate::channels::CliChannel::new();
+        let mut input = crate::channels::cli::CliInput::open(Some(&config.workspace_dir));
+        let mut active_model = model_name.to_string();
 
         // Persistent conversation history across turns
         let mut history = vec![ChatMessage::system(&system_prompt)];
 
         loop {
-            print!("> ");
-            let _ = std::io::stdout().flush();
-
-            let mut input = String::new();
-            match std::io::stdin().read_line(&mut input) {
-                Ok(0) => break,
-                Ok(_) => {}
+            let command = match input.next_command() {
+                Ok(Some(command)) => command,
+                Ok(None) => break,
                 Err(e) => {
                     eprintln!("\nError reading input: {e}\n");
                     break;
                 }
-            }
+            };
 
-            let user_input = input.trim().to_string();
-            if user_input.is_empty() {
-                continue;
-            }
-            match user_input.as_str() {
-                "/quit" | "/exit" => break,
-                "/help" => {
-                    println!("Available commands:");
-                    println!("  /help        Show this help message");
-                    println!("  /clear /new  Clear conversation history");
-                    println!("  /quit /exit  Exit interactive mode\n");
+            let user_input = match command {
+                CliCommand::Message(text) | CliCommand::Unknown(text) => text,
+                CliCommand::Quit => break,
+                CliCommand::Help => {
+                    println!("{}", crate::channels::cli::HELP_TEXT);
+                    continue;
+                }
+                CliCommand::Memory(query) => {
+                    let summary =
+                        crate::channels::cli::memory_summary(mem.as_ref(), query.as_deref()).await;
+                    match summary {
+                        Ok(summary) => println!("{summary}\n"),
+                        Err(e) => eprintln!("\nError reading memory: {e}\n"),
+                    }
+                    continue;
+                }
+                CliCommand::Jobs => {
+                    println!(
+                        "{}\n",
+                        crate::channels::cli::jobs_summary(&config.workspace_dir)
+                    );
+                    continue;
+                }
+                CliCommand::Model(None) => {
+                    println!("Model: {active_model}\n");
+                    continue;
+                }
+                CliCommand::Model(Some(name)) => {
+                    println!("Model switched from {active_model} to {name}.\n");
+                    active_model = name;
                     continue;
                 }
-                "/clear" | "/new" => {
+                // `next_command` folds multi-line input into a message.
+                CliCommand::Multiline => continue,
+                CliCommand::Clear => {
                     println!(
                         "This will clear the current conversation and delete all session memory."
                     );
                     println!("Core memories (long-term facts/preferences) will be preserved.");
-                    print!("Continue? [y/N] ");
-                    let _ = std::io::stdout().flush();
-
-                    let mut confirm = String::new();
-                    if std::io::stdin().read_line(&mut confirm).is_err() {
-                        continue;
-                    }
+                    let confirm = match input.read_line("Continue? [y/N] ") {
+                        Ok(Some(confirm)) => confirm,
+                        _ => continue,
+                    };
                     if !matches!(confirm.trim().to_lowercase().as_str(), "y" | "yes") {
                         println!("Cancelled.\n");
                         continue;
@@ -2964,8 +3230,7 @@ pub async fn run(
                     }
                     continue;
                 }
-                _ => {}
-            }
+            };
 
             // Auto-save conversation turns (skip short/trivial messages)
             if config.memory.auto_save && user_input.chars().count() >= AUTOSAVE_MIN_MESSAGE_CHARS {
@@ -2988,13 +3253,13 @@ pub async fn run(
 
             history.push(ChatMessage::user(&enriched));
 
-            let response = match run_tool_call_loop(
+            let turn = run_tool_call_loop(
                 provider.as_ref(),
                 &mut history,
                 &tools_registry,
                 observer.as_ref(),
                 provider_name,
-                model_name,
+                &active_model,
                 temperature,
                 false,
                 approval_manager.as_ref(),
@@ -3005,9 +3270,13 @@ pub async fn run(
                 None,
                 None,
                 &[],
-            )
-            .await
-            {
+            );
+            let turn = if input.is_interactive() {
+                with_tool_trace_listener(crate::channels::cli::print_tool_event, turn).await
+            } else {
+                turn.await
+            };
+            let response = match turn {
                 Ok(resp) => resp,
                 Err(e) => {
                     eprintln!("\nError: {e}\n");
@@ -3029,7 +3298,7 @@ pub async fn run(
             if let Ok(compacted) = auto_compact_history(
                 &mut history,
                 provider.as_ref(),
-                model_name,
+                &active_model,
                 config.agent.max_history_messages,
             )
             .await
@@ -3061,6 +3330,21 @@ pub async fn process_message_with_profile(
     config: Config,
     message: &str,
     profile: ToolProfile,
+) -> Result<String> {
+    Box::pin(process_message_with_cancellation(
+        config, message, profile, None,
+    ))
+    .await
+}
+
+/// Like [`process_message_with_profile`], aborting with a cancellation error
+/// (see [`is_tool_loop_cancelled`]) once `cancellation_token` fires. Running
+/// tools are dropped, which kills their child processes.
+pub async fn process_message_with_cancellation(
+    config: Config,
+    message: &str,
+    profile: ToolProfile,
+    cancellation_token: Option<CancellationToken>,
 ) -> Result<String> {
     let observer: Arc<dyn Observer> =
         Arc::from(observability::create_observer(&config.observability));
@@ -3122,6 +3406,7 @@ pub async fn process_message_with_profile(
         &model_name,
         &provider_runtime_options,
     )?;
+    let provider = BudgetGuard::wrap(provider, &config.cost, &config.workspace_dir)?;
 
     let skills = crate::skills::load_skills_with_config(&config.workspace_dir, &config);
     let tool_descs = prompt_tool_summaries(&tools_registry);
@@ -3144,6 +3429,13 @@ pub async fn process_message_with_profile(
     if !native_tools {
         system_prompt.push_str(&build_tool_instructions(&tools_registry));
     }
+    let _ = SYSTEM_PROMPT_SUFFIX.try_with(|suffix| {
+        if !suffix.trim().is_empty() {
+            system_prompt.push_str("\n## Thread Instructions\n\n");
+            system_prompt.push_str(suffix.trim());
+            system_prompt.push('\n');
+        }
+    });
 
     let mem_context = build_context(mem.as_ref(), message, config.memory.min_relevance_score).await;
     let context = mem_context;
@@ -3158,6 +3450,7 @@ pub async fn process_message_with_profile(
         ChatMessage::system(&system_prompt),
         ChatMessage::user(&enriched),
     ];
+    let approval_manager = unattended_approval_manager(&config);
 
     agent_turn(
         provider.as_ref(),
@@ -3170,6 +3463,8 @@ pub async fn process_message_with_profile(
         true,
         &config.multimodal,
         config.agent.max_tool_iterations,
+        cancellation_token,
+        approval_manager.as_ref(),
     )
     .await
 }
@@ -3180,6 +3475,24 @@ pub async fn process_message(config: Config, message: &str) -> Result<String> {
     process_message_with_profile(config, message, ToolProfile::Full).await
 }
 
+/// Like [`process_message_with_profile`], but also returns the tool
+/// invocations made while producing the reply.
+pub async fn process_message_traced(
+    config: Config,
+    message: &str,
+    profile: ToolProfile,
+    cancellation_token: Option<CancellationToken>,
+) -> Result<(String, AgentTrace)> {
+    let (reply, trace) = with_tool_trace(Box::pin(process_message_with_cancellation(
+        config,
+        message,
+        profile,
+        cancellation_token,
+    )))
+    .await;
+    Ok((reply?, trace))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -3211,13 +3524,7 @@ mod tests {
 
     #[test]
     fn should_disable_native_tools_for_openrouter_free_models() {
-        let provider = MockProvider {
-            capabilities: ProviderCapabilities {
-                native_tool_calling: true,
-                vision: false,
-            },
-            ..Default::default()
-        };
+        let provider = ScriptedProvider::from_text_responses(vec![]).with_native_tool_support();
         let tool_specs = vec![crate::tools::ToolSpec {
             name: "file_write".to_string(),
             description: "Write a file".to_string(),
@@ -3825,6 +4132,224 @@ mod tests {
         assert!(tool_results.content.contains("Skipped duplicate tool call"));
     }
 
+    #[tokio::test]
+    async fn run_tool_call_loop_cancellation_aborts_running_tool() {
+        let provider = ScriptedProvider::from_text_responses(vec![
+            r#"<tool_call>
+{"name":"slow_tool","arguments":{"value":"A"}}
+</tool_call>"#,
+            "done",
+        ]);
+        let active = Arc::new(AtomicUsize::new(0));
+        let tools_registry: Vec<Box<dyn Tool>> = vec![Box::new(DelayTool::new(
+            "slow_tool",
+            60_000,
+            Arc::clone(&active),
+            Arc::new(AtomicUsize::new(0)),
+        ))];
+        let mut history = vec![ChatMessage::system("test-system"), ChatMessage::user("go")];
+        let observer = NoopObserver;
+        let token = CancellationToken::new();
+        let canceller = token.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            canceller.cancel();
+        });
+
+        let err = tokio::time::timeout(
+            Duration::from_secs(5),
+            run_tool_call_loop(
+                &provider,
+                &mut history,
+                &tools_registry,
+                &observer,
+                "mock-provider",
+                "mock-model",
+                0.0,
+                true,
+                None,
+                "cli",
+                &crate::config::MultimodalConfig::default(),
+                4,
+                Some(token),
+                None,
+                None,
+                &[],
+            ),
+        )
+        .await
+        .expect("cancellation should stop the loop promptly")
+        .expect_err("cancelled loop should not produce a reply");
+
+        assert!(is_tool_loop_cancelled(&err));
+        // The tool future was dropped mid-sleep, so it never finished.
+        assert_eq!(active.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn tool_trace_records_redacted_calls_only_inside_scope() {
+        let secret = "trace-secret-value-9f3a";
+        let script = format!(
+            r#"<tool_call>
+{{"name":"count_tool","arguments":{{"value":"token={secret}","api_key":"sk-abcdef"}}}}
+</tool_call>"#
+        );
+        let invocations = Arc::new(AtomicUsize::new(0));
+        let tools_registry: Vec<Box<dyn Tool>> = vec![Box::new(CountingTool::new(
+            "count_tool",
+            Arc::clone(&invocations),
+        ))];
+        let observer = NoopObserver;
+
+        let (result, trace) = with_tool_trace(async {
+            let provider = ScriptedProvider::from_text_responses(vec![&script, "done"]);
+            let mut history = vec![ChatMessage::system("test-system"), ChatMessage::user("go")];
+            run_tool_call_loop(
+                &provider,
+                &mut history,
+                &tools_registry,
+                &observer,
+                "mock-provider",
+                "mock-model",
+                0.0,
+                true,
+                None,
+                "cli",
+                &crate::config::MultimodalConfig::default(),
+                4,
+                None,
+                None,
+                None,
+                &[],
+            )
+            .await
+        })
+        .await;
+
+        assert_eq!(result.unwrap(), "done");
+        assert_eq!(trace.tool_calls.len(), 1);
+        let entry = &trace.tool_calls[0];
+        assert_eq!(entry.tool, "count_tool");
+        assert!(entry.success);
+        assert_eq!(entry.args["api_key"], TRACE_REDACTED);
+        assert_eq!(entry.args["value"], "token=trac*[REDACTED]");
+        assert_eq!(entry.output, "counted:token=trac*[REDACTED]");
+        assert!(!serde_json::to_string(&trace).unwrap().contains(secret));
+
+        assert!(!tool_trace_active());
+    }
+
+    #[test]
+    fn tool_result_event_reports_policy_refusals_as_blocked() {
+        let result = |success: bool, error: Option<&str>| crate::tools::ToolResult {
+            success,
+            output: String::new(),
+            error: error.map(str::to_string),
+        };
+        let duration = Duration::from_millis(3);
+        let read_only = result(false, Some("Action blocked: autonomy is read-only"));
+        let outside = result(false, Some("Path not allowed by security policy: /etc"));
+
+        assert!(matches!(
+            tool_result_event("shell", duration, &read_only),
+            ObserverEvent::ToolCallBlocked { ref tool } if tool == "shell"
+        ));
+        assert!(matches!(
+            tool_result_event("file_read", duration, &outside),
+            ObserverEvent::ToolCallBlocked { .. }
+        ));
+        assert!(matches!(
+            tool_result_event("shell", duration, &result(false, Some("exit status 1"))),
+            ObserverEvent::ToolCall { success: false, .. }
+        ));
+        assert!(matches!(
+            tool_result_event("shell", duration, &result(true, None)),
+            ObserverEvent::ToolCall { success: true, .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn tool_trace_listener_sees_start_then_finish() {
+        let script = r#"<tool_call>
+{"name":"count_tool","arguments":{"value":"x","api_key":"sk-abcdef"}}
+</tool_call>"#;
+        let invocations = Arc::new(AtomicUsize::new(0));
+        let tools_registry: Vec<Box<dyn Tool>> = vec![Box::new(CountingTool::new(
+            "count_tool",
+            Arc::clone(&invocations),
+        ))];
+        let observer = NoopObserver;
+        let events = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let sink = Arc::clone(&events);
+
+        let result = with_tool_trace_listener(
+            move |event: &ToolTraceEvent| sink.lock().push(event.clone()),
+            async {
+                let provider = ScriptedProvider::from_text_responses(vec![script, "done"]);
+                let mut history = vec![ChatMessage::system("test-system"), ChatMessage::user("go")];
+                run_tool_call_loop(
+                    &provider,
+                    &mut history,
+                    &tools_registry,
+                    &observer,
+                    "mock-provider",
+                    "mock-model",
+                    0.0,
+                    true,
+                    None,
+                    "cli",
+                    &crate::config::MultimodalConfig::default(),
+                    4,
+                    None,
+                    None,
+                    None,
+                    &[],
+                )
+                .await
+            },
+        )
+        .await;
+
+        assert_eq!(result.unwrap(), "done");
+        let events = events.lock();
+        assert_eq!(events.len(), 2);
+        let ToolTraceEvent::Started { tool, args } = &events[0] else {
+            panic!("expected start event, got {:?}", events[0]);
+        };
+        assert_eq!(tool, "count_tool");
+        assert_eq!(args["api_key"], TRACE_REDACTED);
+        let ToolTraceEvent::Finished(entry) = &events[1] else {
+            panic!("expected finish event, got {:?}", events[1]);
+        };
+        assert_eq!(entry.tool, "count_tool");
+        assert!(entry.success);
+        assert!(!tool_trace_active());
+    }
+
+    #[test]
+    fn redact_trace_value_truncates_long_strings_and_recurses() {
+        let long = "x".repeat(TRACE_ARG_MAX_CHARS + 50);
+        let args = serde_json::json!({
+            "files": [{ "path": long, "password": "hunter2" }],
+            "count": 3
+        });
+        let redacted = redact_trace_value(&args, &[]);
+        assert_eq!(redacted["files"][0]["password"], TRACE_REDACTED);
+        assert_eq!(redacted["count"], 3);
+        let path = redacted["files"][0]["path"].as_str().unwrap();
+        assert!(path.chars().count() <= TRACE_ARG_MAX_CHARS + 3);
+
+        let secret = "env-secret-value-51c0".to_string();
+        let masked = redact_trace_value(
+            &serde_json::json!({ "command": format!("curl -H {secret} example.com") }),
+            &[secret],
+        );
+        assert_eq!(
+            masked["command"],
+            format!("curl -H {TRACE_REDACTED} example.com")
+        );
+    }
+
     #[tokio::test]
     async fn run_tool_call_loop_native_mode_preserves_fallback_tool_call_ids() {
         let provider = ScriptedProvider::from_text_responses(vec![
@@ -5553,4 +6078,16 @@ Let me check the result."#;
         assert_eq!(parsed["content"].as_str(), Some("answer"));
         assert!(parsed.get("reasoning_content").is_none());
     }
+
+    #[test]
+    fn unattended_runs_gate_requires_approval_tools() {
+        let mut config = Config::default();
+        assert!(unattended_approval_manager(&config).is_none());
+
+        config.autonomy.requires_approval = vec!["bluesky_post".into()];
+        let mgr = unattended_approval_manager(&config).expect("gated tools need a manager");
+        assert!(mgr.parks_requests());
+        assert!(mgr.needs_approval("bluesky_post"));
+        assert!(!mgr.needs_approval("shell"));
+    }
 }
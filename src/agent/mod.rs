@@ -12,4 +12,9 @@ mod tests;
 #[allow(unused_imports)]
 pub use agent::{Agent, AgentBuilder};
 #[allow(unused_imports)]
-pub use loop_::{process_message, process_message_with_profile, run};
+pub use loop_::{
+    is_tool_loop_cancelled, process_message, process_message_traced,
+    process_message_with_cancellation, process_message_with_profile, run,
+    with_system_prompt_suffix, with_tool_trace_listener, AgentTrace, ToolTraceEntry,
+    ToolTraceEvent,
+};
@@ -0,0 +1,522 @@
+//! Chat approvals for tools listed in `autonomy.requires_approval`.
+//!
+//! When a chat-originated run calls a gated tool, the call is parked in the
+//! local store `tool_approvals` table with status `pending`, mirrored to
+//! `state/approvals/<id>.json`, and announced in the originating thread with
+//! its approval id. The run waits until someone answers `approve <id>` or
+//! `deny <id>` in that same chat, or calls
+//! `POST /api/approvals/{id}/approve|deny`.
+//! Calls nobody answers within `autonomy.approval_timeout_secs` are denied
+//! with status `expired`.
+
+use super::{summarize_args, ApprovalRequest, ApprovalResponse};
+use crate::channels::context::{current_channel_execution_context, ChannelExecutionContext};
+use crate::channels::outbound::send_to_origin;
+use crate::gateway::local_store::{self, ToolApprovalInput};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio_util::sync::CancellationToken;
+
+pub const APPROVALS_DIR: &str = "state/approvals";
+
+/// How often a waiting run re-reads its record, so decisions written by
+/// another process are noticed without a wake-up.
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Result of an approve or deny request.
+#[derive(Debug)]
+pub enum Decision {
+    Missing,
+    /// The approval is no longer pending; carries its current status.
+    Conflict(String),
+    Done(Value),
+}
+
+/// How a parked call was settled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Approved,
+    Denied,
+    Expired,
+    Cancelled,
+}
+
+fn status_of(record: &Value) -> &str {
+    record.get("status").and_then(Value::as_str).unwrap_or("")
+}
+
+fn id_of(record: &Value) -> &str {
+    record.get("id").and_then(Value::as_str).unwrap_or("")
+}
+
+fn is_overdue(record: &Value) -> bool {
+    record
+        .get("expiresAt")
+        .and_then(Value::as_str)
+        .and_then(|at| DateTime::parse_from_rfc3339(at).ok())
+        .is_some_and(|at| at <= Utc::now())
+}
+
+fn write_mirror(workspace_dir: &Path, record: &Value) {
+    let id = id_of(record);
+    if id.is_empty() {
+        return;
+    }
+    let dir = workspace_dir.join(APPROVALS_DIR);
+    let result = std::fs::create_dir_all(&dir)
+        .map_err(anyhow::Error::from)
+        .and_then(|()| {
+            let body = serde_json::to_vec_pretty(record)?;
+            crate::workspace::atomic_write(&dir.join(format!("{id}.json")), body)?;
+            Ok(())
+        });
+    if let Err(err) = result {
+        tracing::warn!("Failed to mirror tool approval {id}: {err}");
+    }
+}
+
+/// Wake-ups for runs waiting in this process, keyed by approval id.
+fn waiters() -> &'static Mutex<HashMap<String, Arc<Notify>>> {
+    static WAITERS: OnceLock<Mutex<HashMap<String, Arc<Notify>>>> = OnceLock::new();
+    WAITERS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn waiter(id: &str) -> Arc<Notify> {
+    Arc::clone(waiters().lock().entry(id.to_string()).or_default())
+}
+
+/// Move a pending approval to `status`, mirror it and wake its run.
+fn settle(workspace_dir: &Path, id: &str, status: &str, decided_by: &str) -> Result<bool> {
+    if !local_store::decide_tool_approval(workspace_dir, id, status, decided_by)? {
+        return Ok(false);
+    }
+    if let Some(record) = local_store::get_tool_approval(workspace_dir, id)? {
+        write_mirror(workspace_dir, &record);
+    }
+    if let Some(notify) = waiters().lock().get(id) {
+        notify.notify_one();
+    }
+    Ok(true)
+}
+
+/// Store a pending approval for `request`, asked in `origin`.
+pub fn park(
+    workspace_dir: &Path,
+    request: &ApprovalRequest,
+    origin: Option<&ChannelExecutionContext>,
+    timeout: Duration,
+) -> Result<Value> {
+    local_store::initialize(workspace_dir)?;
+    let timeout =
+        chrono::Duration::from_std(timeout).unwrap_or_else(|_| chrono::Duration::hours(1));
+    let (origin_channel, origin_thread) = origin
+        .map(|ctx| {
+            let [(_, channel), (_, thread)] = ctx.subprocess_env();
+            (channel, thread)
+        })
+        .unwrap_or_default();
+    let record = local_store::create_tool_approval(
+        workspace_dir,
+        &ToolApprovalInput {
+            tool_name: request.tool_name.clone(),
+            arguments: request.arguments.clone(),
+            summary: summarize_args(&request.arguments),
+            origin_channel,
+            origin_thread,
+            expires_at: (Utc::now() + timeout).to_rfc3339(),
+        },
+    )?;
+    write_mirror(workspace_dir, &record);
+    Ok(record)
+}
+
+/// Approve or deny a pending call on behalf of `decided_by` (`api`, or the
+/// chat channel the answer came from). Overdue calls are expired instead.
+pub fn decide(workspace_dir: &Path, id: &str, approve: bool, decided_by: &str) -> Result<Decision> {
+    local_store::initialize(workspace_dir)?;
+    let Some(record) = local_store::get_tool_approval(workspace_dir, id)? else {
+        return Ok(Decision::Missing);
+    };
+    if status_of(&record) == "pending" && is_overdue(&record) {
+        settle(workspace_dir, id, "expired", "timeout")?;
+        return Ok(Decision::Conflict("expired".to_string()));
+    }
+    let status = if approve { "approved" } else { "denied" };
+    if !settle(workspace_dir, id, status, decided_by)? {
+        return Ok(Decision::Conflict(status_of(&record).to_string()));
+    }
+    let record = local_store::get_tool_approval(workspace_dir, id)?
+        .with_context(|| format!("Tool approval {id} disappeared"))?;
+    Ok(Decision::Done(record))
+}
+
+/// Expire pending approvals past their deadline, including ones whose run
+/// died with the process. Returns how many expired.
+pub fn expire_overdue(workspace_dir: &Path) -> Result<usize> {
+    let overdue = local_store::overdue_tool_approvals(workspace_dir, &Utc::now().to_rfc3339())?;
+    let mut expired = 0;
+    for id in overdue {
+        if settle(workspace_dir, &id, "expired", "timeout")? {
+            expired += 1;
+        }
+    }
+    Ok(expired)
+}
+
+/// Newest approvals first, after expiring overdue ones.
+pub fn list(workspace_dir: &Path, limit: usize, status: Option<&str>) -> Result<Vec<Value>> {
+    local_store::initialize(workspace_dir)?;
+    expire_overdue(workspace_dir)?;
+    local_store::list_tool_approvals(workspace_dir, limit, status)
+}
+
+async fn cancelled(token: Option<&CancellationToken>) {
+    match token {
+        Some(token) => token.cancelled().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Wait until the approval leaves `pending`. Expires it at its deadline and
+/// marks it `cancelled` when `cancellation` fires first.
+pub async fn wait_for_decision(
+    workspace_dir: &Path,
+    id: &str,
+    cancellation: Option<&CancellationToken>,
+) -> Outcome {
+    let notify = waiter(id);
+    let outcome = loop {
+        let record = match local_store::get_tool_approval(workspace_dir, id) {
+            Ok(Some(record)) => record,
+            Ok(None) => break Outcome::Denied,
+            Err(err) => {
+                tracing::warn!("Tool approval {id} could not be read: {err}");
+                break Outcome::Denied;
+            }
+        };
+        match status_of(&record) {
+            "approved" => break Outcome::Approved,
+            "expired" => break Outcome::Expired,
+            "cancelled" => break Outcome::Cancelled,
+            "pending" => {}
+            _ => break Outcome::Denied,
+        }
+        let remaining = record
+            .get("expiresAt")
+            .and_then(Value::as_str)
+            .and_then(|at| DateTime::parse_from_rfc3339(at).ok())
+            .and_then(|at| (at.with_timezone(&Utc) - Utc::now()).to_std().ok())
+            .unwrap_or_default();
+        if remaining.is_zero() {
+            if let Err(err) = settle(workspace_dir, id, "expired", "timeout") {
+                tracing::warn!("Tool approval {id} could not be expired: {err}");
+                break Outcome::Expired;
+            }
+            continue;
+        }
+        tokio::select! {
+            () = notify.notified() => {}
+            () = tokio::time::sleep(remaining.min(POLL_INTERVAL)) => {}
+            () = cancelled(cancellation) => {
+                if let Err(err) = settle(workspace_dir, id, "cancelled", "run") {
+                    tracing::warn!("Tool approval {id} could not be cancelled: {err}");
+                }
+                break Outcome::Cancelled;
+            }
+        }
+    };
+    waiters().lock().remove(id);
+    outcome
+}
+
+/// Message asking the originating chat to decide on a parked call.
+pub fn request_message(record: &Value, timeout: Duration) -> String {
+    let id = id_of(record);
+    format!(
+        "Approval needed: `{}` ({}). Reply `approve {id}` or `deny {id}` within {} min; \
+         otherwise it will not run.",
+        record
+            .get("toolName")
+            .and_then(Value::as_str)
+            .unwrap_or("?"),
+        record.get("summary").and_then(Value::as_str).unwrap_or(""),
+        timeout.as_secs().div_ceil(60).max(1),
+    )
+}
+
+/// Post the approval request to the chat the run came from. The gateway UI
+/// chat (`local`) gets an assistant message in its thread.
+async fn announce(workspace_dir: &Path, origin: Option<&ChannelExecutionContext>, message: String) {
+    let Some(origin) = origin else {
+        tracing::info!("{message} (no chat to ask in; use /api/approvals)");
+        return;
+    };
+    let result = if origin.channel == "local" {
+        local_store::create_chat_message(
+            workspace_dir,
+            &origin.recipient,
+            "assistant",
+            &message,
+            "done",
+            "slowclaw",
+            None,
+            None,
+        )
+        .map(|_| true)
+    } else {
+        send_to_origin(origin, message, Some(workspace_dir)).await
+    };
+    match result {
+        Ok(true) => {}
+        Ok(false) => tracing::info!(
+            "Channel {} cannot carry approval requests; use /api/approvals",
+            origin.channel
+        ),
+        Err(err) => tracing::warn!("Approval request could not be posted: {err}"),
+    }
+}
+
+/// Park `request`, ask the current chat and wait for the answer. Returns the
+/// decision and, for denials, the reason reported to the model.
+pub async fn request_approval(
+    workspace_dir: &Path,
+    request: &ApprovalRequest,
+    timeout: Duration,
+    cancellation: Option<&CancellationToken>,
+) -> (ApprovalResponse, Option<String>) {
+    let tool = &request.tool_name;
+    let origin = current_channel_execution_context();
+    let record = match park(workspace_dir, request, origin.as_ref(), timeout) {
+        Ok(record) => record,
+        Err(err) => {
+            tracing::warn!("Could not park {tool} for approval: {err}");
+            return (
+                ApprovalResponse::No,
+                Some(format!(
+                    "Denied: {tool} requires approval, which is unavailable: {err}"
+                )),
+            );
+        }
+    };
+    let id = id_of(&record).to_string();
+    announce(
+        workspace_dir,
+        origin.as_ref(),
+        request_message(&record, timeout),
+    )
+    .await;
+    let denial = match wait_for_decision(workspace_dir, &id, cancellation).await {
+        Outcome::Approved => return (ApprovalResponse::Yes, None),
+        Outcome::Denied => format!("Denied: approval {id} for {tool} was denied."),
+        Outcome::Expired => format!("Denied: approval {id} for {tool} was not answered in time."),
+        Outcome::Cancelled => format!("Denied: the run was cancelled while {id} was pending."),
+    };
+    (ApprovalResponse::No, Some(denial))
+}
+
+/// Parse an `approve <id>` or `deny <id>` chat message (a leading `/` is
+/// accepted). Returns whether it approves, and the id.
+pub fn parse_chat_command(message: &str) -> Option<(bool, &str)> {
+    let mut words = message.split_whitespace();
+    let verb = words.next()?.trim_start_matches('/').to_ascii_lowercase();
+    let id = words.next()?;
+    if words.next().is_some() || !id.starts_with("ap_") {
+        return None;
+    }
+    match verb.as_str() {
+        "approve" => Some((true, id)),
+        "deny" => Some((false, id)),
+        _ => None,
+    }
+}
+
+/// Whether `record` was asked in the chat `from`. Calls parked outside any
+/// chat can only be decided over the approvals API.
+fn asked_in(record: &Value, from: &ChannelExecutionContext) -> bool {
+    let [(_, channel), (_, thread)] = from.subprocess_env();
+    let field = |name| record.get(name).and_then(Value::as_str).unwrap_or("");
+    !channel.is_empty() && field("originChannel") == channel && field("originThread") == thread
+}
+
+/// Apply an `approve <id>` / `deny <id>` chat message sent in `from` and
+/// return the reply, or `None` when `message` is not such a command. Only
+/// the chat that was asked can decide; other chats are told no such
+/// approval is waiting.
+pub fn handle_chat_command(
+    workspace_dir: &Path,
+    message: &str,
+    from: &ChannelExecutionContext,
+) -> Option<String> {
+    let (approve, id) = parse_chat_command(message)?;
+    let reply = chat_decision_reply(workspace_dir, id, approve, from).unwrap_or_else(|err| {
+        tracing::warn!("Chat decision on {id} failed: {err}");
+        format!("Could not update approval {id}.")
+    });
+    Some(reply)
+}
+
+fn chat_decision_reply(
+    workspace_dir: &Path,
+    id: &str,
+    approve: bool,
+    from: &ChannelExecutionContext,
+) -> Result<String> {
+    local_store::initialize(workspace_dir)?;
+    let asked_here = local_store::get_tool_approval(workspace_dir, id)?
+        .is_some_and(|record| asked_in(&record, from));
+    if !asked_here {
+        return Ok(format!("No approval {id} is waiting in this chat."));
+    }
+    let reply = match decide(
+        workspace_dir,
+        id,
+        approve,
+        &format!("chat:{}", from.channel),
+    )? {
+        Decision::Done(record) => {
+            let tool = record
+                .get("toolName")
+                .and_then(Value::as_str)
+                .unwrap_or("?");
+            if approve {
+                format!("Approved {id}: `{tool}` will run now.")
+            } else {
+                format!("Denied {id}: `{tool}` will not run.")
+            }
+        }
+        Decision::Missing => format!("No approval {id} found."),
+        Decision::Conflict(status) => format!("Approval {id} is already {status}."),
+    };
+    Ok(reply)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn request() -> ApprovalRequest {
+        ApprovalRequest {
+            tool_name: "bluesky_post".into(),
+            arguments: json!({"text": "hello"}),
+        }
+    }
+
+    #[test]
+    fn parses_chat_commands() {
+        assert_eq!(
+            parse_chat_command("approve ap_1234"),
+            Some((true, "ap_1234"))
+        );
+        assert_eq!(
+            parse_chat_command("/Deny ap_1234"),
+            Some((false, "ap_1234"))
+        );
+        assert_eq!(parse_chat_command("approve the plan"), None);
+        assert_eq!(parse_chat_command("approve ap_1 now"), None);
+    }
+
+    #[test]
+    fn park_mirrors_and_first_decision_wins() {
+        let tmp = tempfile::tempdir().unwrap();
+        let origin = ChannelExecutionContext::new("telegram", "42", None);
+        let record = park(
+            tmp.path(),
+            &request(),
+            Some(&origin),
+            Duration::from_secs(60),
+        )
+        .unwrap();
+        let id = id_of(&record).to_string();
+        assert_eq!(record["originChannel"], "telegram");
+        assert_eq!(record["originThread"], "42");
+        assert!(
+            request_message(&record, Duration::from_secs(3600)).contains(&format!("approve {id}"))
+        );
+
+        let other_chat = ChannelExecutionContext::new("telegram", "43", None);
+        let reply = handle_chat_command(tmp.path(), &format!("approve {id}"), &other_chat).unwrap();
+        assert!(reply.starts_with("No approval"), "{reply}");
+        let gateway_thread = ChannelExecutionContext::new("local", "42", Some("42".into()));
+        let reply =
+            handle_chat_command(tmp.path(), &format!("approve {id}"), &gateway_thread).unwrap();
+        assert!(reply.starts_with("No approval"), "{reply}");
+
+        let reply = handle_chat_command(tmp.path(), &format!("deny {id}"), &origin).unwrap();
+        assert!(reply.starts_with("Denied"), "{reply}");
+        assert!(matches!(
+            decide(tmp.path(), &id, true, "api").unwrap(),
+            Decision::Conflict(status) if status == "denied"
+        ));
+
+        let mirror = tmp.path().join(APPROVALS_DIR).join(format!("{id}.json"));
+        let mirrored: Value = serde_json::from_slice(&std::fs::read(mirror).unwrap()).unwrap();
+        assert_eq!(mirrored["status"], "denied");
+        assert_eq!(mirrored["decidedBy"], "chat:telegram");
+    }
+
+    #[test]
+    fn calls_parked_outside_a_chat_cannot_be_decided_in_chat() {
+        let tmp = tempfile::tempdir().unwrap();
+        let record = park(tmp.path(), &request(), None, Duration::from_secs(60)).unwrap();
+        let id = id_of(&record);
+        let chat = ChannelExecutionContext::new("telegram", "42", None);
+        let reply = handle_chat_command(tmp.path(), &format!("approve {id}"), &chat).unwrap();
+        assert!(reply.starts_with("No approval"), "{reply}");
+        assert_eq!(list(tmp.path(), 10, None).unwrap()[0]["status"], "pending");
+    }
+
+    #[tokio::test]
+    async fn waiting_run_resumes_on_approval() {
+        let tmp = tempfile::tempdir().unwrap();
+        let record = park(tmp.path(), &request(), None, Duration::from_secs(60)).unwrap();
+        let id = id_of(&record).to_string();
+        let workspace = tmp.path().to_path_buf();
+        let waiting_id = id.clone();
+        let waiting =
+            tokio::spawn(async move { wait_for_decision(&workspace, &waiting_id, None).await });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(matches!(
+            decide(tmp.path(), &id, true, "api").unwrap(),
+            Decision::Done(_)
+        ));
+        let outcome = tokio::time::timeout(Duration::from_secs(2), waiting)
+            .await
+            .expect("approval should wake the run")
+            .unwrap();
+        assert_eq!(outcome, Outcome::Approved);
+    }
+
+    #[tokio::test]
+    async fn unanswered_approval_is_denied_at_the_deadline() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (decision, reason) =
+            request_approval(tmp.path(), &request(), Duration::from_millis(50), None).await;
+        assert_eq!(decision, ApprovalResponse::No);
+        assert!(reason.unwrap().contains("not answered in time"));
+        let items = list(tmp.path(), 10, None).unwrap();
+        assert_eq!(items[0]["status"], "expired");
+        assert_eq!(items[0]["decidedBy"], "timeout");
+    }
+
+    #[tokio::test]
+    async fn cancelled_run_cancels_its_approval() {
+        let tmp = tempfile::tempdir().unwrap();
+        let record = park(tmp.path(), &request(), None, Duration::from_secs(60)).unwrap();
+        let token = CancellationToken::new();
+        token.cancel();
+        let outcome = wait_for_decision(tmp.path(), id_of(&record), Some(&token)).await;
+        assert_eq!(outcome, Outcome::Cancelled);
+        assert!(matches!(
+            decide(tmp.path(), id_of(&record), true, "api").unwrap(),
+            Decision::Conflict(status) if status == "cancelled"
+        ));
+    }
+}
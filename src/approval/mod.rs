@@ -1,7 +1,11 @@
 //! Interactive approval workflow for supervised mode.
 //!
 //! Provides a pre-execution hook that prompts the user before tool calls,
-//! with session-scoped "Always" allowlists and audit logging.
+//! with session-scoped "Always" allowlists and audit logging. Chat runs
+//! gate only `requires_approval` tools and ask in the originating thread
+//! (see [`chat`]).
+
+pub mod chat;
 
 use crate::config::AutonomyConfig;
 use crate::security::AutonomyLevel;
@@ -10,6 +14,9 @@ use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
 
 // ── Types ────────────────────────────────────────────────────────
 
@@ -46,7 +53,7 @@ pub struct ApprovalLogEntry {
 
 /// Manages the interactive approval workflow.
 ///
-/// - Checks config-level `auto_approve` / `always_ask` lists
+/// - Checks config-level `auto_approve` / `always_ask` / `requires_approval` lists
 /// - Maintains a session-scoped "always" allowlist
 /// - Records an audit trail of all decisions
 pub struct ApprovalManager {
@@ -54,6 +61,13 @@ pub struct ApprovalManager {
     auto_approve: HashSet<String>,
     /// Tools that always need approval, ignoring session allowlist.
     always_ask: HashSet<String>,
+    /// Tools that need approval at every autonomy level.
+    requires_approval: HashSet<String>,
+    /// Workspace whose approval store parks chat requests; `None` for the
+    /// interactive CLI.
+    chat_workspace: Option<PathBuf>,
+    /// How long a parked chat request waits before it is denied.
+    chat_timeout: Duration,
     /// Autonomy level from config.
     autonomy_level: AutonomyLevel,
     /// Session-scoped allowlist built from "Always" responses.
@@ -68,16 +82,45 @@ impl ApprovalManager {
         Self {
             auto_approve: config.auto_approve.iter().cloned().collect(),
             always_ask: config.always_ask.iter().cloned().collect(),
+            requires_approval: config.requires_approval.iter().cloned().collect(),
+            chat_workspace: None,
+            chat_timeout: Duration::from_secs(config.approval_timeout_secs),
             autonomy_level: config.level,
             session_allowlist: Mutex::new(HashSet::new()),
             audit_log: Mutex::new(Vec::new()),
         }
     }
 
+    /// Create for chat runs: only `requires_approval` tools are gated, and
+    /// their calls are parked in `workspace_dir` until someone decides.
+    pub fn for_chat(config: &AutonomyConfig, workspace_dir: &Path) -> Self {
+        Self {
+            chat_workspace: Some(workspace_dir.to_path_buf()),
+            ..Self::from_config(config)
+        }
+    }
+
+    /// Whether requests are parked for a chat decision instead of prompted.
+    pub fn parks_requests(&self) -> bool {
+        self.chat_workspace.is_some()
+    }
+
     /// Check whether a tool call requires interactive approval.
     ///
     /// Returns `true` if the call needs a prompt, `false` if it can proceed.
     pub fn needs_approval(&self, tool_name: &str) -> bool {
+        // requires_approval applies at every level that can run tools.
+        if self.requires_approval.contains(tool_name)
+            && self.autonomy_level != AutonomyLevel::ReadOnly
+        {
+            return true;
+        }
+
+        // Chat runs gate nothing else.
+        if self.parks_requests() {
+            return false;
+        }
+
         // Full autonomy never prompts.
         if self.autonomy_level == AutonomyLevel::Full {
             return false;
@@ -152,6 +195,19 @@ impl ApprovalManager {
     pub fn prompt_cli(&self, request: &ApprovalRequest) -> ApprovalResponse {
         prompt_cli_interactive(request)
     }
+
+    /// Park the request and wait for a decision from the originating chat or
+    /// the approvals API. Denials carry the reason reported to the model.
+    pub async fn request_chat_approval(
+        &self,
+        request: &ApprovalRequest,
+        cancellation: Option<&CancellationToken>,
+    ) -> (ApprovalResponse, Option<String>) {
+        let Some(workspace_dir) = self.chat_workspace.as_deref() else {
+            return (ApprovalResponse::No, None);
+        };
+        chat::request_approval(workspace_dir, request, self.chat_timeout, cancellation).await
+    }
 }
 
 // ── CLI prompt ───────────────────────────────────────────────────
@@ -277,6 +333,30 @@ mod tests {
         assert!(!mgr.needs_approval("shell"));
     }
 
+    #[test]
+    fn requires_approval_overrides_full_autonomy() {
+        let config = AutonomyConfig {
+            requires_approval: vec!["bluesky_post".into()],
+            ..full_config()
+        };
+        let mgr = ApprovalManager::from_config(&config);
+        assert!(mgr.needs_approval("bluesky_post"));
+        assert!(!mgr.needs_approval("shell"));
+    }
+
+    #[test]
+    fn chat_manager_gates_only_required_tools() {
+        let config = AutonomyConfig {
+            requires_approval: vec!["bluesky_post".into()],
+            ..supervised_config()
+        };
+        let mgr = ApprovalManager::for_chat(&config, Path::new("/tmp"));
+        assert!(mgr.parks_requests());
+        assert!(mgr.needs_approval("bluesky_post"));
+        assert!(!mgr.needs_approval("shell"));
+        assert!(!mgr.needs_approval("file_write"));
+    }
+
     // ── session allowlist ────────────────────────────────────
 
     #[test]
@@ -0,0 +1,723 @@
+//! Outbound Bluesky posting over XRPC.
+//!
+//! Credentials follow the desktop app contract: the handle, app password, and
+//! service URL arrive as `ZEROCLAW_BLUESKY_*` environment variables, and an
+//! existing session may be handed over in `ZEROCLAW_BLUESKY_SESSION` using the
+//! same JSON shape the app keeps in its `bluesky.session` keyring entry.
+//! When the variables are unset, both are read from the app's keyring entries
+//! instead (see [`crate::security::credentials`]).
+//! Sessions created or refreshed here are written back in that shape to
+//! `<workspace>/state/bluesky_session.json` (mode 0600 on Unix) so restarts
+//! reuse them.
+
+use anyhow::{Context, Result};
+use chrono::{SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::{Component, Path, PathBuf};
+use unicode_segmentation::UnicodeSegmentation;
+
+pub const DEFAULT_SERVICE_URL: &str = "https://bsky.social";
+
+/// Bluesky rejects post text longer than this many graphemes.
+pub const MAX_POST_GRAPHEMES: usize = 300;
+
+/// Images allowed in a single `app.bsky.embed.images` embed.
+pub const MAX_POST_IMAGES: usize = 4;
+
+/// Upload limit the PDS enforces for post images.
+const MAX_IMAGE_BYTES: u64 = 1_000_000;
+
+const SESSION_STATE_FILE: &str = "state/bluesky_session.json";
+
+/// Login details for `com.atproto.server.createSession`.
+#[derive(Debug, Clone)]
+pub struct BlueskyCredentials {
+    pub service_url: String,
+    pub handle: String,
+    pub app_password: String,
+}
+
+impl BlueskyCredentials {
+    /// Read `ZEROCLAW_BLUESKY_HANDLE` / `ZEROCLAW_BLUESKY_APP_PASSWORD` and the
+    /// optional `ZEROCLAW_BLUESKY_SERVICE_URL`. Returns `None` unless both the
+    /// handle and password are set.
+    pub fn from_env() -> Option<Self> {
+        let var = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+        Some(Self {
+            service_url: var("ZEROCLAW_BLUESKY_SERVICE_URL")
+                .unwrap_or_else(|| DEFAULT_SERVICE_URL.to_string()),
+            handle: var("ZEROCLAW_BLUESKY_HANDLE")?,
+            app_password: var("ZEROCLAW_BLUESKY_APP_PASSWORD")?,
+        })
+    }
+}
+
+/// Session tokens, serialized like the desktop app's `bluesky.session` entry.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlueskySession {
+    pub access_jwt: String,
+    #[serde(default)]
+    pub refresh_jwt: String,
+    pub did: String,
+    pub handle: String,
+}
+
+/// Optional `app.bsky.embed.external` link card.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LinkCard {
+    pub uri: String,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+}
+
+/// Image already resolved to a file inside the workspace.
+#[derive(Debug, Clone)]
+pub struct PostImage {
+    pub path: PathBuf,
+    pub alt: String,
+}
+
+/// Everything needed to publish one post.
+#[derive(Debug, Clone, Default)]
+pub struct PostRequest {
+    pub text: String,
+    pub images: Vec<PostImage>,
+    pub link: Option<LinkCard>,
+}
+
+impl PostRequest {
+    pub fn text(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Check the limits Bluesky enforces before anything is uploaded.
+    pub fn validate(&self) -> Result<()> {
+        let graphemes = self.text.graphemes(true).count();
+        if graphemes > MAX_POST_GRAPHEMES {
+            anyhow::bail!(
+                "Post text is {graphemes} graphemes; Bluesky allows at most {MAX_POST_GRAPHEMES}. \
+                 Shorten it by {} or split it into a thread.",
+                graphemes - MAX_POST_GRAPHEMES
+            );
+        }
+        if self.text.trim().is_empty() && self.images.is_empty() {
+            anyhow::bail!("Post needs text or at least one image");
+        }
+        if self.images.len() > MAX_POST_IMAGES {
+            anyhow::bail!(
+                "Bluesky posts can carry at most {MAX_POST_IMAGES} images ({} given)",
+                self.images.len()
+            );
+        }
+        if !self.images.is_empty() && self.link.is_some() {
+            anyhow::bail!("Attach either images or a link card, not both");
+        }
+        Ok(())
+    }
+
+    /// Record that would be created, with image blobs replaced by their
+    /// workspace paths. Used for dry runs.
+    pub fn preview(&self) -> Value {
+        let images = self
+            .images
+            .iter()
+            .map(|image| {
+                json!({
+                    "path": image.path.display().to_string(),
+                    "alt": image.alt,
+                })
+            })
+            .collect();
+        build_post_record(&self.text, self.link.as_ref(), images)
+    }
+}
+
+/// Resolve a workspace-relative image path, rejecting traversal, symlink
+/// escapes, unsupported formats, and files over the upload limit.
+pub fn resolve_workspace_image(workspace_dir: &Path, rel_path: &str) -> Result<PathBuf> {
+    let rel_path = rel_path.trim().trim_start_matches('/');
+    let relative = Path::new(rel_path);
+    if rel_path.is_empty()
+        || !relative
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+    {
+        anyhow::bail!("Image path must be workspace-relative without '..': {rel_path}");
+    }
+    let workspace_root = workspace_dir
+        .canonicalize()
+        .unwrap_or_else(|_| workspace_dir.to_path_buf());
+    let path = workspace_root
+        .join(relative)
+        .canonicalize()
+        .with_context(|| format!("Image not found in workspace: {rel_path}"))?;
+    if !path.starts_with(&workspace_root) {
+        anyhow::bail!("Image path resolves outside the workspace: {rel_path}");
+    }
+    image_mime(&path)?;
+    let size = std::fs::metadata(&path)?.len();
+    if size > MAX_IMAGE_BYTES {
+        anyhow::bail!(
+            "Image {rel_path} is {size} bytes; Bluesky accepts at most {MAX_IMAGE_BYTES}"
+        );
+    }
+    Ok(path)
+}
+
+fn image_mime(path: &Path) -> Result<&'static str> {
+    let ext = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_ascii_lowercase)
+        .unwrap_or_default();
+    match ext.as_str() {
+        "jpg" | "jpeg" => Ok("image/jpeg"),
+        "png" => Ok("image/png"),
+        "webp" => Ok("image/webp"),
+        "gif" => Ok("image/gif"),
+        _ => anyhow::bail!(
+            "Unsupported image type for Bluesky: {} (use jpg, png, webp, or gif)",
+            path.display()
+        ),
+    }
+}
+
+/// `app.bsky.richtext.facet#link` entries for bare http(s) URLs in `text`.
+/// Offsets are UTF-8 byte positions as the lexicon requires.
+fn link_facets(text: &str) -> Vec<Value> {
+    let mut facets = Vec::new();
+    let mut offset = 0;
+    for token in text.split_inclusive(char::is_whitespace) {
+        let start = offset;
+        offset += token.len();
+        let word = token
+            .trim_end()
+            .trim_end_matches(['.', ',', ';', ':', '!', '?', ')', '"', '\'']);
+        if !(word.starts_with("https://") || word.starts_with("http://")) {
+            continue;
+        }
+        facets.push(json!({
+            "index": { "byteStart": start, "byteEnd": start + word.len() },
+            "features": [{ "$type": "app.bsky.richtext.facet#link", "uri": word }],
+        }));
+    }
+    facets
+}
+
+fn build_post_record(text: &str, link: Option<&LinkCard>, images: Vec<Value>) -> Value {
+    let mut record = json!({
+        "$type": "app.bsky.feed.post",
+        "text": text,
+        "createdAt": Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+    });
+    let facets = link_facets(text);
+    if !facets.is_empty() {
+        record["facets"] = Value::Array(facets);
+    }
+    if !images.is_empty() {
+        record["embed"] = json!({ "$type": "app.bsky.embed.images", "images": images });
+    } else if let Some(link) = link {
+        record["embed"] = json!({
+            "$type": "app.bsky.embed.external",
+            "external": {
+                "uri": link.uri,
+                "title": link.title,
+                "description": link.description,
+            },
+        });
+    }
+    record
+}
+
+/// `at://` URI and CID of a created post.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PostResult {
+    pub uri: String,
+    pub cid: String,
+}
+
+enum XrpcBody<'a> {
+    Json(&'a Value),
+    Blob { bytes: Vec<u8>, mime: &'static str },
+}
+
+/// XRPC client that owns one account session and refreshes it on expiry.
+pub struct BlueskyClient {
+    http: reqwest::Client,
+    credentials: BlueskyCredentials,
+    session_path: Option<PathBuf>,
+    session: tokio::sync::Mutex<Option<BlueskySession>>,
+}
+
+impl BlueskyClient {
+    pub fn new(credentials: BlueskyCredentials, session_path: Option<PathBuf>) -> Self {
+        let stored = session_path
+            .as_deref()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|raw| serde_json::from_str::<BlueskySession>(&raw).ok())
+            .filter(|session| !session.access_jwt.is_empty())
+            .or_else(crate::security::credentials::load_bluesky_session);
+        Self {
+            http: crate::config::build_runtime_proxy_client_with_timeouts(
+                "channel.bluesky",
+                30,
+                10,
+            ),
+            credentials: BlueskyCredentials {
+                service_url: credentials.service_url.trim_end_matches('/').to_string(),
+                ..credentials
+            },
+            session_path,
+            session: tokio::sync::Mutex::new(stored),
+        }
+    }
+
+    /// Client for the environment (or desktop app keyring) credentials,
+    /// persisting sessions under `workspace_dir`. Returns `None` when no
+    /// credentials are configured.
+    pub fn from_env(workspace_dir: &Path) -> Option<Self> {
+        crate::security::credentials::load_bluesky_credentials()
+            .map(|credentials| Self::new(credentials, Some(workspace_dir.join(SESSION_STATE_FILE))))
+    }
+
+    pub fn handle(&self) -> &str {
+        &self.credentials.handle
+    }
+
+    fn xrpc_url(&self, nsid: &str) -> String {
+        format!("{}/xrpc/{nsid}", self.credentials.service_url)
+    }
+
+    async fn create_session(&self) -> Result<BlueskySession> {
+        let response = self
+            .http
+            .post(self.xrpc_url("com.atproto.server.createSession"))
+            .json(&json!({
+                "identifier": self.credentials.handle,
+                "password": self.credentials.app_password,
+            }))
+            .send()
+            .await
+            .context("Bluesky createSession request failed")?;
+        parse_session(response, "createSession").await
+    }
+
+    async fn refresh_session(&self, refresh_jwt: &str) -> Result<BlueskySession> {
+        let response = self
+            .http
+            .post(self.xrpc_url("com.atproto.server.refreshSession"))
+            .bearer_auth(refresh_jwt)
+            .send()
+            .await
+            .context("Bluesky refreshSession request failed")?;
+        parse_session(response, "refreshSession").await
+    }
+
+    async fn store_session(&self, session: &BlueskySession) {
+        let Some(path) = &self.session_path else {
+            return;
+        };
+        if let Some(parent) = path.parent() {
+            let _ = tokio::fs::create_dir_all(parent).await;
+        }
+        match serde_json::to_vec(session) {
+            Ok(body) => {
+                if let Err(err) = write_private(path, &body).await {
+                    tracing::warn!("Failed to persist Bluesky session: {err}");
+                }
+            }
+            Err(err) => tracing::warn!("Failed to serialize Bluesky session: {err}"),
+        }
+    }
+
+    /// Current session, logging in first if there is none.
+    pub async fn session(&self) -> Result<BlueskySession> {
+        let mut guard = self.session.lock().await;
+        if let Some(session) = guard.as_ref() {
+            return Ok(session.clone());
+        }
+        let session = self.create_session().await?;
+        self.store_session(&session).await;
+        *guard = Some(session.clone());
+        Ok(session)
+    }
+
+    /// Confirm the session with `com.atproto.server.getSession`, renewing it
+    /// once if the access token has expired. Returns the account handle.
+    pub async fn check_session(&self) -> Result<String> {
+        let session = self.session().await?;
+        let (mut status, mut text) = self.get_session(&session).await?;
+        if is_expired_token(status, &text) {
+            let renewed = self.renew_session(&session).await?;
+            (status, text) = self.get_session(&renewed).await?;
+        }
+        if !status.is_success() {
+            anyhow::bail!(
+                "Bluesky getSession failed ({status}): {}",
+                crate::util::truncate_with_ellipsis(&text, 300)
+            );
+        }
+        let body: Value =
+            serde_json::from_str(&text).context("Invalid Bluesky getSession response")?;
+        Ok(body["handle"]
+            .as_str()
+            .unwrap_or(&session.handle)
+            .to_string())
+    }
+
+    async fn get_session(&self, session: &BlueskySession) -> Result<(reqwest::StatusCode, String)> {
+        let response = self
+            .http
+            .get(self.xrpc_url("com.atproto.server.getSession"))
+            .bearer_auth(&session.access_jwt)
+            .send()
+            .await
+            .context("Bluesky getSession request failed")?;
+        let status = response.status();
+        Ok((status, response.text().await.unwrap_or_default()))
+    }
+
+    /// Replace an expired session: refresh when possible, otherwise log in.
+    async fn renew_session(&self, expired: &BlueskySession) -> Result<BlueskySession> {
+        let mut guard = self.session.lock().await;
+        if let Some(current) = guard.as_ref() {
+            if current.access_jwt != expired.access_jwt {
+                return Ok(current.clone());
+            }
+        }
+        let session = if expired.refresh_jwt.is_empty() {
+            self.create_session().await?
+        } else {
+            match self.refresh_session(&expired.refresh_jwt).await {
+                Ok(session) => session,
+                Err(err) => {
+                    tracing::debug!("Bluesky refresh failed, logging in again: {err}");
+                    self.create_session().await?
+                }
+            }
+        };
+        self.store_session(&session).await;
+        *guard = Some(session.clone());
+        Ok(session)
+    }
+
+    async fn send_xrpc(
+        &self,
+        nsid: &str,
+        session: &BlueskySession,
+        body: &XrpcBody<'_>,
+    ) -> Result<(reqwest::StatusCode, String)> {
+        let request = self
+            .http
+            .post(self.xrpc_url(nsid))
+            .bearer_auth(&session.access_jwt);
+        let request = match body {
+            XrpcBody::Json(value) => request.json(value),
+            XrpcBody::Blob { bytes, mime } => request
+                .header(reqwest::header::CONTENT_TYPE, *mime)
+                .body(bytes.clone()),
+        };
+        let response = request
+            .send()
+            .await
+            .with_context(|| format!("Bluesky {nsid} request failed"))?;
+        let status = response.status();
+        Ok((status, response.text().await.unwrap_or_default()))
+    }
+
+    /// Authenticated procedure call, retried once with a renewed session when
+    /// the access token has expired.
+    async fn procedure(&self, nsid: &str, body: XrpcBody<'_>) -> Result<Value> {
+        let session = self.session().await?;
+        let (mut status, mut text) = self.send_xrpc(nsid, &session, &body).await?;
+        if is_expired_token(status, &text) {
+            let renewed = self.renew_session(&session).await?;
+            (status, text) = self.send_xrpc(nsid, &renewed, &body).await?;
+        }
+        if !status.is_success() {
+            anyhow::bail!(
+                "Bluesky {nsid} failed ({status}): {}",
+                crate::util::truncate_with_ellipsis(&text, 300)
+            );
+        }
+        serde_json::from_str(&text).with_context(|| format!("Invalid Bluesky {nsid} response"))
+    }
+
+    async fn upload_image(&self, image: &PostImage) -> Result<Value> {
+        let mime = image_mime(&image.path)?;
+        let bytes = tokio::fs::read(&image.path)
+            .await
+            .with_context(|| format!("Failed to read {}", image.path.display()))?;
+        let response = self
+            .procedure(
+                "com.atproto.repo.uploadBlob",
+                XrpcBody::Blob { bytes, mime },
+            )
+            .await?;
+        response
+            .get("blob")
+            .cloned()
+            .context("Bluesky uploadBlob response is missing 'blob'")
+    }
+
+    /// Upload any images and create the post record.
+    pub async fn post(&self, request: &PostRequest) -> Result<PostResult> {
+        request.validate()?;
+        let mut images = Vec::with_capacity(request.images.len());
+        for image in &request.images {
+            let blob = self.upload_image(image).await?;
+            images.push(json!({ "image": blob, "alt": image.alt }));
+        }
+        let record = build_post_record(&request.text, request.link.as_ref(), images);
+        let repo = self.session().await?.did;
+        let created = self
+            .procedure(
+                "com.atproto.repo.createRecord",
+                XrpcBody::Json(&json!({
+                    "repo": repo,
+                    "collection": "app.bsky.feed.post",
+                    "record": record,
+                })),
+            )
+            .await?;
+        serde_json::from_value(created).context("Invalid Bluesky createRecord response")
+    }
+}
+
+async fn parse_session(response: reqwest::Response, nsid: &str) -> Result<BlueskySession> {
+    let status = response.status();
+    if !status.is_success() {
+        let text = response.text().await.unwrap_or_default();
+        anyhow::bail!(
+            "Bluesky {nsid} failed ({status}): {}",
+            crate::util::truncate_with_ellipsis(&text, 300)
+        );
+    }
+    response
+        .json::<BlueskySession>()
+        .await
+        .with_context(|| format!("Invalid Bluesky {nsid} response"))
+}
+
+/// PDSes answer expired tokens with 400 `ExpiredToken` or a bare 401.
+fn is_expired_token(status: reqwest::StatusCode, body: &str) -> bool {
+    if status == reqwest::StatusCode::UNAUTHORIZED {
+        return true;
+    }
+    status == reqwest::StatusCode::BAD_REQUEST
+        && serde_json::from_str::<Value>(body)
+            .ok()
+            .and_then(|body| {
+                body.get("error")
+                    .and_then(Value::as_str)
+                    .map(str::to_string)
+            })
+            .is_some_and(|error| error == "ExpiredToken" || error == "InvalidToken")
+}
+
+/// Write `body` to `path` readable only by the owner, since it holds the
+/// session's access and refresh tokens.
+async fn write_private(path: &Path, body: &[u8]) -> std::io::Result<()> {
+    use tokio::io::AsyncWriteExt;
+
+    let mut options = tokio::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let mut file = options.open(path).await?;
+    file.write_all(body).await?;
+    file.flush().await?;
+    // `mode` only applies on creation; tighten a file left by older builds.
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        tokio::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600)).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn credentials(server: &MockServer) -> BlueskyCredentials {
+        BlueskyCredentials {
+            service_url: format!("{}/", server.uri()),
+            handle: "alice.test".into(),
+            app_password: "app-pass".into(),
+        }
+    }
+
+    fn session_json(access: &str) -> Value {
+        json!({
+            "accessJwt": access,
+            "refreshJwt": "refresh-1",
+            "did": "did:plc:alice",
+            "handle": "alice.test",
+        })
+    }
+
+    fn created() -> ResponseTemplate {
+        ResponseTemplate::new(200).set_body_json(json!({
+            "uri": "at://did:plc:alice/app.bsky.feed.post/1",
+            "cid": "bafy1",
+        }))
+    }
+
+    #[test]
+    fn validate_counts_graphemes_not_bytes() {
+        let thumbs = "👍🏽".repeat(MAX_POST_GRAPHEMES);
+        assert!(PostRequest::text(thumbs.clone()).validate().is_ok());
+
+        let err = PostRequest::text(format!("{thumbs}!"))
+            .validate()
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("301 graphemes"), "{err}");
+        assert!(err.contains("Shorten it by 1"), "{err}");
+    }
+
+    #[test]
+    fn link_facets_use_byte_offsets_and_drop_trailing_punctuation() {
+        let facets = link_facets("café https://example.test/a. done");
+        assert_eq!(facets.len(), 1);
+        assert_eq!(facets[0]["index"]["byteStart"], 6);
+        assert_eq!(
+            facets[0]["index"]["byteEnd"],
+            6 + "https://example.test/a".len()
+        );
+        assert_eq!(facets[0]["features"][0]["uri"], "https://example.test/a");
+    }
+
+    #[tokio::test]
+    async fn post_logs_in_and_persists_the_session() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/xrpc/com.atproto.server.createSession"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(session_json("access-1")))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/xrpc/com.atproto.repo.createRecord"))
+            .and(header("authorization", "Bearer access-1"))
+            .respond_with(created())
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let session_path = dir.path().join("state/bluesky_session.json");
+        let client = BlueskyClient::new(credentials(&server), Some(session_path.clone()));
+        let result = client
+            .post(&PostRequest::text("hello https://example.test"))
+            .await
+            .unwrap();
+
+        assert_eq!(result.cid, "bafy1");
+        let stored: BlueskySession =
+            serde_json::from_str(&std::fs::read_to_string(&session_path).unwrap()).unwrap();
+        assert_eq!(stored.access_jwt, "access-1");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&session_path)
+                .unwrap()
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        let requests = server.received_requests().await.unwrap();
+        let body: Value = serde_json::from_slice(&requests[1].body).unwrap();
+        assert_eq!(body["repo"], "did:plc:alice");
+        assert_eq!(body["record"]["facets"][0]["index"]["byteStart"], 6);
+    }
+
+    #[tokio::test]
+    async fn expired_access_token_is_refreshed_and_retried() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/xrpc/com.atproto.repo.createRecord"))
+            .and(header("authorization", "Bearer stale"))
+            .respond_with(ResponseTemplate::new(400).set_body_json(json!({
+                "error": "ExpiredToken",
+                "message": "Token has expired",
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/xrpc/com.atproto.server.refreshSession"))
+            .and(header("authorization", "Bearer refresh-1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(session_json("fresh")))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/xrpc/com.atproto.repo.createRecord"))
+            .and(header("authorization", "Bearer fresh"))
+            .respond_with(created())
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let session_path = dir.path().join("bluesky_session.json");
+        std::fs::write(&session_path, session_json("stale").to_string()).unwrap();
+        let client = BlueskyClient::new(credentials(&server), Some(session_path.clone()));
+        client.post(&PostRequest::text("hi")).await.unwrap();
+
+        let stored: BlueskySession =
+            serde_json::from_str(&std::fs::read_to_string(session_path).unwrap()).unwrap();
+        assert_eq!(stored.access_jwt, "fresh");
+    }
+
+    #[tokio::test]
+    async fn check_session_renews_an_expired_token() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/xrpc/com.atproto.server.getSession"))
+            .and(header("authorization", "Bearer stale"))
+            .respond_with(ResponseTemplate::new(401))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/xrpc/com.atproto.server.refreshSession"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(session_json("fresh")))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/xrpc/com.atproto.server.getSession"))
+            .and(header("authorization", "Bearer fresh"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "did": "did:plc:alice",
+                "handle": "alice.test",
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let session_path = dir.path().join("bluesky_session.json");
+        std::fs::write(&session_path, session_json("stale").to_string()).unwrap();
+        let client = BlueskyClient::new(credentials(&server), Some(session_path));
+        assert_eq!(client.check_session().await.unwrap(), "alice.test");
+    }
+}
@@ -0,0 +1,133 @@
+use crate::bluesky::{BlueskyClient, PostRequest, MAX_POST_GRAPHEMES};
+use crate::channels::traits::{Channel, ChannelMessage, SendMessage};
+use crate::security::SecurityPolicy;
+use anyhow::Result;
+use async_trait::async_trait;
+use std::sync::Arc;
+
+/// Outbound-only channel that publishes each message as a Bluesky post.
+///
+/// The recipient is ignored: posts always go to the configured account. Text
+/// longer than one post is split into numbered posts. Every post is charged
+/// against the security policy's action budget, and in dry-run mode the
+/// rendered records are logged instead of posted.
+pub struct BlueskyChannel {
+    client: Arc<BlueskyClient>,
+    security: Arc<SecurityPolicy>,
+    dry_run: bool,
+}
+
+impl BlueskyChannel {
+    pub fn new(client: Arc<BlueskyClient>, security: Arc<SecurityPolicy>) -> Self {
+        Self {
+            client,
+            security,
+            dry_run: false,
+        }
+    }
+
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+}
+
+#[async_trait]
+impl Channel for BlueskyChannel {
+    fn name(&self) -> &str {
+        "bluesky"
+    }
+
+    fn max_message_chars(&self) -> Option<usize> {
+        Some(MAX_POST_GRAPHEMES)
+    }
+
+    async fn send(&self, message: &SendMessage) -> Result<()> {
+        let requests = self
+            .format_outbound(message.content.trim())
+            .iter()
+            .map(|part| {
+                let request = PostRequest::text(part);
+                request.validate().map(|()| request)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if self.dry_run {
+            for request in &requests {
+                tracing::info!(
+                    handle = self.client.handle(),
+                    record = %request.preview(),
+                    "Bluesky dry run: post not published"
+                );
+            }
+            return Ok(());
+        }
+        for request in &requests {
+            if !self.security.can_act() {
+                anyhow::bail!("Security policy: read-only mode, cannot post to Bluesky");
+            }
+            if !self.security.record_action() {
+                anyhow::bail!("Rate limit exceeded: action budget exhausted");
+            }
+            let posted = self.client.post(request).await?;
+            tracing::info!(uri = %posted.uri, "Published Bluesky post");
+        }
+        Ok(())
+    }
+
+    async fn listen(&self, _tx: tokio::sync::mpsc::Sender<ChannelMessage>) -> Result<()> {
+        anyhow::bail!("Bluesky channel is outbound-only")
+    }
+
+    async fn health_check(&self) -> bool {
+        self.client.check_session().await.is_ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bluesky::BlueskyCredentials;
+    use crate::security::AutonomyLevel;
+
+    fn offline_client() -> Arc<BlueskyClient> {
+        Arc::new(BlueskyClient::new(
+            BlueskyCredentials {
+                service_url: "http://127.0.0.1:9".into(),
+                handle: "alice.test".into(),
+                app_password: "app-pass".into(),
+            },
+            None,
+        ))
+    }
+
+    #[tokio::test]
+    async fn dry_run_validates_without_posting() {
+        let channel = BlueskyChannel::new(offline_client(), Arc::new(SecurityPolicy::default()))
+            .with_dry_run(true);
+        channel
+            .send(&SendMessage::new("scheduled hello", ""))
+            .await
+            .unwrap();
+        assert!(channel.send(&SendMessage::new("   ", "")).await.is_err());
+
+        let long = "A short sentence to fill the thread. ".repeat(12);
+        let parts = channel.format_outbound(&long);
+        assert_eq!(parts.len(), 2);
+        assert!(parts[0].ends_with("1/2"));
+        channel.send(&SendMessage::new(long, "")).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn read_only_policy_blocks_posting() {
+        let security = SecurityPolicy {
+            autonomy: AutonomyLevel::ReadOnly,
+            ..SecurityPolicy::default()
+        };
+        let channel = BlueskyChannel::new(offline_client(), Arc::new(security));
+        let err = channel
+            .send(&SendMessage::new("hello", ""))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("read-only"));
+    }
+}
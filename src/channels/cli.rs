@@ -1,14 +1,357 @@
 use super::traits::{Channel, ChannelMessage, SendMessage};
+use crate::agent::ToolTraceEvent;
+use crate::memory::{Memory, MemoryCategory};
+use crate::util::truncate_with_ellipsis;
 use async_trait::async_trait;
+use rustyline::error::ReadlineError;
+use rustyline::DefaultEditor;
+use std::fmt::Write as _;
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
 use tokio::io::{self, AsyncBufReadExt, BufReader};
 use uuid::Uuid;
 
+const HISTORY_MAX_ENTRIES: usize = 1000;
+const PROMPT: &str = "> ";
+const MULTILINE_PROMPT: &str = ". ";
+/// Ends multi-line input early; a blank line does the same.
+const MULTILINE_SEND: &str = "/send";
+const TOOL_ARGS_MAX_CHARS: usize = 80;
+const MEMORY_LIST_LIMIT: usize = 10;
+const JOBS_LIST_LIMIT: usize = 10;
+
+pub const HELP_TEXT: &str = "Available commands:
+  /help          Show this help message
+  /multi         Enter multi-line input; finish with a blank line or /send
+  /memory [q]    Show core memories, or search memory for q
+  /jobs          List recent background jobs
+  /model [name]  Show or switch the model for this session
+  /clear /new    Clear conversation history
+  /quit /exit    Exit interactive mode
+";
+
+/// One line of interactive input, classified.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CliCommand {
+    Message(String),
+    Quit,
+    Help,
+    Clear,
+    Multiline,
+    Memory(Option<String>),
+    Jobs,
+    Model(Option<String>),
+    /// A slash-command this REPL does not know; sent to the agent as text.
+    Unknown(String),
+}
+
+/// Classify a line of input. Returns `None` for blank lines.
+pub fn parse_command(line: &str) -> Option<CliCommand> {
+    let line = line.trim();
+    if line.is_empty() {
+        return None;
+    }
+    let Some(rest) = line.strip_prefix('/') else {
+        return Some(CliCommand::Message(line.to_string()));
+    };
+    let (name, arg) = match rest.split_once(char::is_whitespace) {
+        Some((name, arg)) => (name, Some(arg.trim()).filter(|arg| !arg.is_empty())),
+        None => (rest, None),
+    };
+    let command = match name {
+        "quit" | "exit" => CliCommand::Quit,
+        "help" => CliCommand::Help,
+        "clear" | "new" => CliCommand::Clear,
+        "multi" => CliCommand::Multiline,
+        "memory" => CliCommand::Memory(arg.map(str::to_string)),
+        "jobs" => CliCommand::Jobs,
+        "model" => CliCommand::Model(arg.map(str::to_string)),
+        _ => CliCommand::Unknown(line.to_string()),
+    };
+    Some(command)
+}
+
+/// Line source for the interactive CLI: a line editor with persistent
+/// history on a terminal, plain stdin lines otherwise so piped scripts see
+/// no prompts or escape codes beyond what they always did.
+pub struct CliInput {
+    editor: Option<DefaultEditor>,
+    history_path: Option<PathBuf>,
+}
+
+impl CliInput {
+    /// Open the editor when stdin and stdout are terminals, keeping history
+    /// in `<workspace>/state/cli_history` when a workspace is given.
+    pub fn open(workspace_dir: Option<&Path>) -> Self {
+        if !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal() {
+            return Self::plain();
+        }
+        let config = rustyline::Config::builder()
+            .max_history_size(HISTORY_MAX_ENTRIES)
+            .map(|builder| builder.auto_add_history(false).build());
+        let editor = match config.and_then(DefaultEditor::with_config) {
+            Ok(editor) => editor,
+            Err(e) => {
+                tracing::warn!("Line editor unavailable, using plain input: {e}");
+                return Self::plain();
+            }
+        };
+        let mut input = Self {
+            editor: Some(editor),
+            history_path: workspace_dir.map(|dir| dir.join("state").join("cli_history")),
+        };
+        if let (Some(editor), Some(path)) = (input.editor.as_mut(), input.history_path.as_ref()) {
+            if path.exists() {
+                if let Err(e) = editor.load_history(path) {
+                    tracing::warn!("Failed to load CLI history: {e}");
+                }
+            }
+        }
+        input
+    }
+
+    /// Plain stdin lines without an editor or history.
+    pub fn plain() -> Self {
+        Self {
+            editor: None,
+            history_path: None,
+        }
+    }
+
+    pub fn is_interactive(&self) -> bool {
+        self.editor.is_some()
+    }
+
+    /// Read one line after showing `prompt`. `None` means end of input;
+    /// Ctrl-C discards the current line and yields an empty string.
+    pub fn read_line(&mut self, prompt: &str) -> anyhow::Result<Option<String>> {
+        let Some(editor) = self.editor.as_mut() else {
+            print!("{prompt}");
+            let _ = std::io::stdout().flush();
+            let mut line = String::new();
+            return match std::io::stdin().read_line(&mut line)? {
+                0 => Ok(None),
+                _ => Ok(Some(line.trim_end_matches(['\r', '\n']).to_string())),
+            };
+        };
+        match editor.readline(prompt) {
+            Ok(line) => Ok(Some(line)),
+            Err(ReadlineError::Interrupted) => Ok(Some(String::new())),
+            Err(ReadlineError::Eof) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Read the next command, collecting `/multi` input into one message.
+    /// Blank lines are skipped; `None` means end of input. Without a
+    /// terminal only `/quit`, `/help` and `/clear` are interpreted, as
+    /// before, and other slash-lines reach the agent as text.
+    pub fn next_command(&mut self) -> anyhow::Result<Option<CliCommand>> {
+        loop {
+            let Some(line) = self.read_line(PROMPT)? else {
+                return Ok(None);
+            };
+            let command = match parse_command(&line) {
+                Some(CliCommand::Multiline) if self.is_interactive() => {
+                    let Some(text) = self.read_multiline()? else {
+                        return Ok(None);
+                    };
+                    parse_command(&text).map(|_| CliCommand::Message(text))
+                }
+                Some(
+                    CliCommand::Multiline
+                    | CliCommand::Memory(_)
+                    | CliCommand::Jobs
+                    | CliCommand::Model(_),
+                ) if !self.is_interactive() => Some(CliCommand::Unknown(line.trim().to_string())),
+                other => other,
+            };
+            let Some(command) = command else {
+                continue;
+            };
+            if let CliCommand::Message(text) = &command {
+                self.remember(text);
+            } else {
+                self.remember(line.trim());
+            }
+            return Ok(Some(command));
+        }
+    }
+
+    /// Collect lines until a blank line or `/send`. Returns `None` when
+    /// input ends before anything was entered.
+    fn read_multiline(&mut self) -> anyhow::Result<Option<String>> {
+        println!("(multi-line: finish with a blank line or {MULTILINE_SEND})");
+        let mut lines = Vec::new();
+        loop {
+            let Some(line) = self.read_line(MULTILINE_PROMPT)? else {
+                return Ok((!lines.is_empty()).then(|| lines.join("\n")));
+            };
+            if line.trim().is_empty() || line.trim() == MULTILINE_SEND {
+                return Ok(Some(lines.join("\n")));
+            }
+            lines.push(line);
+        }
+    }
+
+    fn remember(&mut self, entry: &str) {
+        let (Some(editor), Some(path)) = (self.editor.as_mut(), self.history_path.as_ref()) else {
+            return;
+        };
+        if !matches!(editor.add_history_entry(entry), Ok(true)) {
+            return;
+        }
+        if let Some(parent) = path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        if let Err(e) = editor.save_history(path) {
+            tracing::warn!("Failed to save CLI history: {e}");
+        }
+    }
+}
+
+/// One progress line for a tool starting or finishing.
+pub fn format_tool_event(event: &ToolTraceEvent) -> String {
+    match event {
+        ToolTraceEvent::Started { tool, args } => {
+            let args = match args {
+                serde_json::Value::Object(map) if map.is_empty() => String::new(),
+                serde_json::Value::Null => String::new(),
+                other => format!(
+                    " {}",
+                    truncate_with_ellipsis(&other.to_string(), TOOL_ARGS_MAX_CHARS)
+                ),
+            };
+            format!("  ⚙ {tool}{args}")
+        }
+        ToolTraceEvent::Finished(entry) if entry.success => {
+            format!("  ✓ {} ({} ms)", entry.tool, entry.duration_ms)
+        }
+        ToolTraceEvent::Finished(entry) => {
+            format!("  ✗ {} failed ({} ms)", entry.tool, entry.duration_ms)
+        }
+    }
+}
+
+/// Print tool progress as it happens. Used as a trace listener.
+pub fn print_tool_event(event: &ToolTraceEvent) {
+    println!("{}", format_tool_event(event));
+    let _ = std::io::stdout().flush();
+}
+
+/// `/memory` output: core memories, or recall results for `query`.
+pub async fn memory_summary(mem: &dyn Memory, query: Option<&str>) -> anyhow::Result<String> {
+    let (heading, entries) = match query {
+        Some(query) => (
+            format!("Memories matching \"{query}\":"),
+            mem.recall(query, MEMORY_LIST_LIMIT, None).await?,
+        ),
+        None => {
+            let mut entries = mem.list(Some(&MemoryCategory::Core), None).await?;
+            entries.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+            entries.truncate(MEMORY_LIST_LIMIT);
+            (
+                format!(
+                    "Core memories ({} entries stored in total):",
+                    mem.count().await?
+                ),
+                entries,
+            )
+        }
+    };
+    if entries.is_empty() {
+        return Ok(format!("{heading}\n  (none)"));
+    }
+    let mut out = heading;
+    for entry in entries {
+        let _ = write!(
+            out,
+            "\n  {} [{}]: {}",
+            entry.key,
+            entry.category,
+            truncate_with_ellipsis(&entry.content.replace('\n', " "), TOOL_ARGS_MAX_CHARS)
+        );
+    }
+    Ok(out)
+}
+
+/// `/jobs` output: the workspace's most recent background jobs.
+pub fn jobs_summary(workspace_dir: &Path) -> String {
+    let jobs = crate::jobs::JobManager::for_workspace(workspace_dir).list(JOBS_LIST_LIMIT);
+    if jobs.is_empty() {
+        return "No background jobs.".to_string();
+    }
+    let mut out = "Recent jobs:".to_string();
+    for job in jobs {
+        let status = format!("{:?}", job.status).to_lowercase();
+        let _ = write!(
+            out,
+            "\n  {} {} {} ({})",
+            job.id, job.kind, status, job.updated_at
+        );
+    }
+    out
+}
+
 /// CLI channel — stdin/stdout, always available, zero deps
-pub struct CliChannel;
+pub struct CliChannel {
+    workspace_dir: Option<PathBuf>,
+}
 
 impl CliChannel {
     pub fn new() -> Self {
-        Self
+        Self {
+            workspace_dir: None,
+        }
+    }
+
+    /// Keep line-editor history under `<workspace>/state/` on a terminal.
+    pub fn with_workspace(workspace_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            workspace_dir: Some(workspace_dir.into()),
+        }
+    }
+
+    fn message(content: String) -> ChannelMessage {
+        ChannelMessage {
+            id: Uuid::new_v4().to_string(),
+            sender: "user".to_string(),
+            reply_target: "user".to_string(),
+            content,
+            channel: "cli".to_string(),
+            timestamp: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            thread_ts: None,
+            attachments: Vec::new(),
+        }
+    }
+
+    /// Terminal input through the line editor; runs on a blocking thread.
+    fn listen_interactive(
+        workspace_dir: Option<PathBuf>,
+        tx: &tokio::sync::mpsc::Sender<ChannelMessage>,
+    ) -> anyhow::Result<()> {
+        let mut input = CliInput::open(workspace_dir.as_deref());
+        while let Some(command) = input.next_command()? {
+            let content = match command {
+                CliCommand::Quit => break,
+                CliCommand::Message(text) | CliCommand::Unknown(text) => text,
+                CliCommand::Help => {
+                    println!("{HELP_TEXT}");
+                    continue;
+                }
+                _ => {
+                    println!("That command is not available here.\n");
+                    continue;
+                }
+            };
+            if tx.blocking_send(Self::message(content)).is_err() {
+                break;
+            }
+        }
+        Ok(())
     }
 }
 
@@ -24,6 +367,14 @@ impl Channel for CliChannel {
     }
 
     async fn listen(&self, tx: tokio::sync::mpsc::Sender<ChannelMessage>) -> anyhow::Result<()> {
+        if std::io::stdin().is_terminal() && std::io::stdout().is_terminal() {
+            let workspace_dir = self.workspace_dir.clone();
+            return tokio::task::spawn_blocking(move || {
+                Self::listen_interactive(workspace_dir, &tx)
+            })
+            .await?;
+        }
+
         let stdin = io::stdin();
         let reader = BufReader::new(stdin);
         let mut lines = reader.lines();
@@ -37,20 +388,7 @@ impl Channel for CliChannel {
                 break;
             }
 
-            let msg = ChannelMessage {
-                id: Uuid::new_v4().to_string(),
-                sender: "user".to_string(),
-                reply_target: "user".to_string(),
-                content: line,
-                channel: "cli".to_string(),
-                timestamp: std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap_or_default()
-                    .as_secs(),
-                thread_ts: None,
-            };
-
-            if tx.send(msg).await.is_err() {
+            if tx.send(Self::message(line)).await.is_err() {
                 break;
             }
         }
@@ -62,6 +400,85 @@ impl Channel for CliChannel {
 mod tests {
     use super::*;
 
+    #[test]
+    fn parse_command_classifies_messages_and_slash_commands() {
+        assert_eq!(parse_command("   "), None);
+        assert_eq!(
+            parse_command("  hello there "),
+            Some(CliCommand::Message("hello there".into()))
+        );
+        assert_eq!(parse_command("/quit"), Some(CliCommand::Quit));
+        assert_eq!(parse_command("/exit"), Some(CliCommand::Quit));
+        assert_eq!(parse_command("/help"), Some(CliCommand::Help));
+        assert_eq!(parse_command("/new"), Some(CliCommand::Clear));
+        assert_eq!(parse_command("/multi"), Some(CliCommand::Multiline));
+        assert_eq!(parse_command("/jobs"), Some(CliCommand::Jobs));
+    }
+
+    #[test]
+    fn parse_command_reads_optional_arguments() {
+        assert_eq!(parse_command("/memory"), Some(CliCommand::Memory(None)));
+        assert_eq!(
+            parse_command("/memory  coffee order "),
+            Some(CliCommand::Memory(Some("coffee order".into())))
+        );
+        assert_eq!(parse_command("/model"), Some(CliCommand::Model(None)));
+        assert_eq!(parse_command("/model   "), Some(CliCommand::Model(None)));
+        assert_eq!(
+            parse_command("/model openai/gpt-4o"),
+            Some(CliCommand::Model(Some("openai/gpt-4o".into())))
+        );
+    }
+
+    #[test]
+    fn parse_command_keeps_unknown_slash_lines_verbatim() {
+        assert_eq!(
+            parse_command("/summarize this please"),
+            Some(CliCommand::Unknown("/summarize this please".into()))
+        );
+        assert_eq!(
+            parse_command("/quitting"),
+            Some(CliCommand::Unknown("/quitting".into()))
+        );
+    }
+
+    #[test]
+    fn format_tool_event_shows_start_and_outcome() {
+        let started = ToolTraceEvent::Started {
+            tool: "shell".into(),
+            args: serde_json::json!({"command": "ls"}),
+        };
+        assert_eq!(format_tool_event(&started), r#"  ⚙ shell {"command":"ls"}"#);
+
+        let bare = ToolTraceEvent::Started {
+            tool: "memory_recall".into(),
+            args: serde_json::json!({}),
+        };
+        assert_eq!(format_tool_event(&bare), "  ⚙ memory_recall");
+
+        let mut entry = crate::agent::ToolTraceEntry {
+            tool: "shell".into(),
+            args: serde_json::json!({}),
+            success: true,
+            duration_ms: 12,
+            output: String::new(),
+        };
+        assert_eq!(
+            format_tool_event(&ToolTraceEvent::Finished(entry.clone())),
+            "  ✓ shell (12 ms)"
+        );
+        entry.success = false;
+        assert_eq!(
+            format_tool_event(&ToolTraceEvent::Finished(entry)),
+            "  ✗ shell failed (12 ms)"
+        );
+    }
+
+    #[test]
+    fn plain_input_has_no_editor() {
+        assert!(!CliInput::plain().is_interactive());
+    }
+
     #[test]
     fn cli_channel_name() {
         assert_eq!(CliChannel::new().name(), "cli");
@@ -111,6 +528,7 @@ mod tests {
             channel: "cli".into(),
             timestamp: 1_234_567_890,
             thread_ts: None,
+            attachments: Vec::new(),
         };
         assert_eq!(msg.id, "test-id");
         assert_eq!(msg.sender, "user");
@@ -130,6 +548,7 @@ mod tests {
             channel: "ch".into(),
             timestamp: 0,
             thread_ts: None,
+            attachments: Vec::new(),
         };
         let cloned = msg.clone();
         assert_eq!(cloned.id, msg.id);
@@ -1,4 +1,8 @@
+use crate::channels::traits::Channel;
+use parking_lot::Mutex;
+use std::collections::HashMap;
 use std::future::Future;
+use std::sync::{Arc, OnceLock};
 
 #[derive(Debug, Clone)]
 pub struct ChannelExecutionContext {
@@ -19,8 +23,35 @@ impl ChannelExecutionContext {
             thread_ts,
         }
     }
+
+    /// Whether results can be announced in this chat outside the request
+    /// that started the work: PocketBase threads, or any channel with a
+    /// registered live instance (Telegram, Email, ...).
+    pub fn is_deliverable(&self) -> bool {
+        self.channel == "pocketbase" || live_channel(&self.channel).is_some()
+    }
+
+    /// Environment variables describing this chat for tool subprocesses, so
+    /// wrapper scripts can post progress back to it. The thread falls back
+    /// to the recipient for channels without threads.
+    pub fn subprocess_env(&self) -> [(&'static str, String); 2] {
+        [
+            (ORIGIN_CHANNEL_ENV, self.channel.clone()),
+            (
+                ORIGIN_THREAD_ENV,
+                self.thread_ts
+                    .clone()
+                    .unwrap_or_else(|| self.recipient.clone()),
+            ),
+        ]
+    }
 }
 
+/// Channel that started the agent run, exported to tool subprocesses.
+pub const ORIGIN_CHANNEL_ENV: &str = "SLOWCLAW_ORIGIN_CHANNEL";
+/// Thread (or chat) that started the agent run, exported to tool subprocesses.
+pub const ORIGIN_THREAD_ENV: &str = "SLOWCLAW_ORIGIN_THREAD";
+
 tokio::task_local! {
     static CHANNEL_EXECUTION_CONTEXT: ChannelExecutionContext;
 }
@@ -36,6 +67,38 @@ pub fn current_channel_execution_context() -> Option<ChannelExecutionContext> {
     CHANNEL_EXECUTION_CONTEXT.try_with(Clone::clone).ok()
 }
 
+/// Origin env vars for a subprocess spawned by the current task; empty
+/// outside a chat-originated run.
+pub fn channel_context_env() -> Vec<(&'static str, String)> {
+    current_channel_execution_context()
+        .map(|ctx| ctx.subprocess_env().to_vec())
+        .unwrap_or_default()
+}
+
+fn live_channels() -> &'static Mutex<HashMap<String, Arc<dyn Channel>>> {
+    static LIVE: OnceLock<Mutex<HashMap<String, Arc<dyn Channel>>>> = OnceLock::new();
+    LIVE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Make a running channel available for out-of-band delivery (job completion
+/// notices, queued reply retries) under its [`Channel::name`].
+pub fn register_live_channel(channel: Arc<dyn Channel>) {
+    live_channels()
+        .lock()
+        .insert(channel.name().to_string(), channel);
+}
+
+pub fn live_channel(name: &str) -> Option<Arc<dyn Channel>> {
+    live_channels().lock().get(name).cloned()
+}
+
+/// Where work scheduled from the current conversation should announce its
+/// result: the current chat, when its channel can deliver messages outside
+/// the request (PocketBase, or a registered live channel such as Telegram).
+pub fn default_cron_delivery_for_current_channel() -> Option<ChannelExecutionContext> {
+    current_channel_execution_context().filter(ChannelExecutionContext::is_deliverable)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -52,4 +115,88 @@ mod tests {
         assert_eq!(retrieved.channel, "pocketbase");
         assert_eq!(retrieved.recipient, "thread-123");
     }
+
+    #[tokio::test]
+    async fn channel_context_survives_tool_await_points() {
+        async fn slow_tool() -> Option<ChannelExecutionContext> {
+            tokio::task::yield_now().await;
+            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+            let inner = async {
+                tokio::task::yield_now().await;
+                current_channel_execution_context()
+            };
+            inner.await
+        }
+
+        let ctx = ChannelExecutionContext::new("telegram", "42", Some("7".into()));
+        let (before, after) = with_channel_execution_context(ctx, async {
+            let before = slow_tool().await;
+            let results = futures_util::future::join_all([slow_tool(), slow_tool()]).await;
+            assert!(results.iter().all(Option::is_some));
+            (before, slow_tool().await)
+        })
+        .await;
+
+        assert_eq!(before.unwrap().channel, "telegram");
+        assert_eq!(after.unwrap().thread_ts.as_deref(), Some("7"));
+        assert!(slow_tool().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn subprocess_env_names_origin_channel_and_thread() {
+        assert!(channel_context_env().is_empty());
+
+        let threaded = ChannelExecutionContext::new("pocketbase", "t-1", Some("t-1".into()));
+        let env = with_channel_execution_context(threaded, async { channel_context_env() }).await;
+        assert_eq!(
+            env,
+            vec![
+                (ORIGIN_CHANNEL_ENV, "pocketbase".to_string()),
+                (ORIGIN_THREAD_ENV, "t-1".to_string()),
+            ]
+        );
+
+        let unthreaded = ChannelExecutionContext::new("telegram", "42", None);
+        assert_eq!(
+            unthreaded.subprocess_env()[1],
+            (ORIGIN_THREAD_ENV, "42".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn cron_delivery_targets_only_deliverable_channels() {
+        struct Live;
+
+        #[async_trait::async_trait]
+        impl Channel for Live {
+            fn name(&self) -> &str {
+                "context-test-live"
+            }
+
+            async fn send(&self, _message: &crate::channels::SendMessage) -> anyhow::Result<()> {
+                Ok(())
+            }
+
+            async fn listen(
+                &self,
+                _tx: tokio::sync::mpsc::Sender<crate::channels::traits::ChannelMessage>,
+            ) -> anyhow::Result<()> {
+                Ok(())
+            }
+        }
+
+        let delivery = |channel: &'static str| {
+            with_channel_execution_context(
+                ChannelExecutionContext::new(channel, "42", None),
+                async { default_cron_delivery_for_current_channel() },
+            )
+        };
+        assert!(default_cron_delivery_for_current_channel().is_none());
+        assert!(delivery("pocketbase").await.is_some());
+        assert!(delivery("context-test-live").await.is_none());
+
+        register_live_channel(Arc::new(Live));
+        let target = delivery("context-test-live").await.unwrap();
+        assert_eq!(target.recipient, "42");
+    }
 }
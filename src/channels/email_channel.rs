@@ -0,0 +1,477 @@
+//! Outbound-only email channel for digests and scheduled output.
+//!
+//! Messages are Markdown; each one is mailed as `multipart/alternative` with
+//! the Markdown as the plain-text part and rendered HTML alongside it, wrapped
+//! in `multipart/mixed` when small files are attached. Nothing is read back:
+//! [`Channel::listen`] returns immediately and the IMAP fields of
+//! [`EmailConfig`] are only kept so older configs still parse.
+
+use crate::channels::traits::{Channel, ChannelMessage, SendMessage};
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use lettre::message::header::ContentType;
+use lettre::message::{Attachment, Mailbox, MultiPart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Duration;
+
+/// Largest file accepted by [`EmailAttachment::from_path`].
+pub const MAX_ATTACHMENT_BYTES: u64 = 5 * 1024 * 1024;
+const SMTP_TIMEOUT: Duration = Duration::from_secs(30);
+const MAX_DERIVED_SUBJECT_CHARS: usize = 78;
+const DEFAULT_SUBJECT: &str = "SlowClaw";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct EmailConfig {
+    /// Unused: the email channel does not read mail. Kept for older configs.
+    #[serde(default)]
+    pub imap_host: String,
+    #[serde(default = "default_imap_port")]
+    pub imap_port: u16,
+    #[serde(default = "default_imap_folder")]
+    pub imap_folder: String,
+    pub smtp_host: String,
+    /// `465` uses implicit TLS; any other port upgrades with STARTTLS.
+    #[serde(default = "default_smtp_port")]
+    pub smtp_port: u16,
+    /// `false` sends in plain text, for a relay on localhost only.
+    #[serde(default = "default_true")]
+    pub smtp_tls: bool,
+    /// SMTP login; leave empty for a relay that needs no authentication.
+    #[serde(default)]
+    pub username: String,
+    #[serde(default)]
+    pub password: String,
+    pub from_address: String,
+    /// Subject line; `{subject}` is the message's own subject (or its first
+    /// line) and `{date}` today's local date.
+    #[serde(default = "default_subject_template")]
+    pub subject_template: String,
+    #[serde(default = "default_idle_timeout", alias = "poll_interval_secs")]
+    pub idle_timeout_secs: u64,
+    #[serde(default)]
+    pub allowed_senders: Vec<String>,
+}
+
+impl crate::config::traits::ChannelConfig for EmailConfig {
+    fn name() -> &'static str {
+        "Email"
+    }
+    fn desc() -> &'static str {
+        "outbound digests over SMTP"
+    }
+}
+
+fn default_imap_port() -> u16 {
+    993
+}
+fn default_smtp_port() -> u16 {
+    465
+}
+fn default_imap_folder() -> String {
+    "INBOX".into()
+}
+fn default_subject_template() -> String {
+    "{subject}".into()
+}
+fn default_idle_timeout() -> u64 {
+    1740
+}
+fn default_true() -> bool {
+    true
+}
+
+/// A file sent along with a message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmailAttachment {
+    pub filename: String,
+    pub content_type: String,
+    pub bytes: Vec<u8>,
+}
+
+impl EmailAttachment {
+    /// Read a file, refusing anything over [`MAX_ATTACHMENT_BYTES`].
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let size = std::fs::metadata(path)
+            .with_context(|| format!("Failed to read attachment {}", path.display()))?
+            .len();
+        if size > MAX_ATTACHMENT_BYTES {
+            bail!(
+                "Attachment {} is {size} bytes (limit {MAX_ATTACHMENT_BYTES})",
+                path.display()
+            );
+        }
+        let bytes = std::fs::read(path)
+            .with_context(|| format!("Failed to read attachment {}", path.display()))?;
+        let filename = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "attachment".into());
+        let content_type = mime_guess::from_path(path)
+            .first_or_octet_stream()
+            .essence_str()
+            .to_string();
+        Ok(Self {
+            filename,
+            content_type,
+            bytes,
+        })
+    }
+}
+
+/// Render Markdown to an HTML document. Raw HTML in the source is escaped
+/// rather than passed through, since message text is model output.
+pub fn markdown_to_html(markdown: &str) -> String {
+    use pulldown_cmark::{Event, Options, Parser};
+
+    let options =
+        Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+    let events = Parser::new_ext(markdown, options).map(|event| match event {
+        Event::Html(raw) | Event::InlineHtml(raw) => Event::Text(raw),
+        other => other,
+    });
+    let mut body = String::new();
+    pulldown_cmark::html::push_html(&mut body, events);
+    format!(
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"></head>\n<body>\n{body}</body></html>\n"
+    )
+}
+
+/// First non-empty line with Markdown heading marks stripped, shortened to
+/// fit a subject line.
+fn derived_subject(content: &str) -> String {
+    let line = content
+        .lines()
+        .map(|line| line.trim().trim_start_matches('#').trim())
+        .find(|line| !line.is_empty())
+        .unwrap_or(DEFAULT_SUBJECT);
+    crate::util::truncate_with_ellipsis(line, MAX_DERIVED_SUBJECT_CHARS)
+}
+
+/// Fill `{subject}` and `{date}` in the configured template.
+pub fn render_subject(template: &str, message: &SendMessage, date: &str) -> String {
+    let subject = message
+        .subject
+        .as_deref()
+        .map(str::trim)
+        .filter(|subject| !subject.is_empty())
+        .map_or_else(|| derived_subject(&message.content), str::to_string);
+    let rendered = template
+        .replace("{subject}", &subject)
+        .replace("{date}", date);
+    let rendered = rendered.trim();
+    if rendered.is_empty() {
+        subject
+    } else {
+        rendered.to_string()
+    }
+}
+
+/// Comma-separated recipients, e.g. `me@example.com, Ada <ada@example.com>`.
+fn parse_recipients(raw: &str) -> Result<Vec<Mailbox>> {
+    let recipients = raw
+        .split(',')
+        .map(str::trim)
+        .filter(|addr| !addr.is_empty())
+        .map(|addr| {
+            addr.parse::<Mailbox>()
+                .with_context(|| format!("Invalid email recipient '{addr}'"))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    if recipients.is_empty() {
+        bail!("Email message has no recipient");
+    }
+    Ok(recipients)
+}
+
+#[derive(Debug, Clone)]
+pub struct EmailChannel {
+    config: EmailConfig,
+    from: Mailbox,
+}
+
+impl EmailChannel {
+    pub fn from_config(config: &EmailConfig) -> Result<Self> {
+        if config.smtp_host.trim().is_empty() {
+            bail!("channels_config.email.smtp_host is empty");
+        }
+        let from = config
+            .from_address
+            .trim()
+            .parse::<Mailbox>()
+            .with_context(|| {
+                format!(
+                    "Invalid channels_config.email.from_address '{}'",
+                    config.from_address
+                )
+            })?;
+        Ok(Self {
+            config: config.clone(),
+            from,
+        })
+    }
+
+    /// Build the MIME message for `message`, with `attachments` appended.
+    pub fn build_message(
+        &self,
+        message: &SendMessage,
+        attachments: &[EmailAttachment],
+    ) -> Result<Message> {
+        let date = chrono::Local::now().format("%Y-%m-%d").to_string();
+        let subject = render_subject(&self.config.subject_template, message, &date);
+        let alternative = MultiPart::alternative_plain_html(
+            message.content.clone(),
+            markdown_to_html(&message.content),
+        );
+        let body = if attachments.is_empty() {
+            alternative
+        } else {
+            let mut mixed = MultiPart::mixed().multipart(alternative);
+            for attachment in attachments {
+                let content_type = ContentType::parse(&attachment.content_type)
+                    .or_else(|_| ContentType::parse("application/octet-stream"))
+                    .context("Invalid attachment content type")?;
+                mixed = mixed.singlepart(
+                    Attachment::new(attachment.filename.clone())
+                        .body(attachment.bytes.clone(), content_type),
+                );
+            }
+            mixed
+        };
+
+        let mut builder = Message::builder()
+            .from(self.from.clone())
+            .subject(subject)
+            .date_now();
+        for recipient in parse_recipients(&message.recipient)? {
+            builder = builder.to(recipient);
+        }
+        builder
+            .multipart(body)
+            .context("Failed to build email message")
+    }
+
+    fn transport(&self) -> Result<AsyncSmtpTransport<Tokio1Executor>> {
+        let host = self.config.smtp_host.trim();
+        let builder = if !self.config.smtp_tls {
+            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host)
+        } else if self.config.smtp_port == 465 {
+            AsyncSmtpTransport::<Tokio1Executor>::relay(host)?
+        } else {
+            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)?
+        };
+        let mut builder = builder
+            .port(self.config.smtp_port)
+            .timeout(Some(SMTP_TIMEOUT));
+        if !self.config.username.is_empty() {
+            builder = builder.credentials(Credentials::new(
+                self.config.username.clone(),
+                self.config.password.clone(),
+            ));
+        }
+        Ok(builder.build())
+    }
+
+    /// Connect, negotiate TLS, and log in without sending anything.
+    pub async fn test_connection(&self) -> Result<()> {
+        let host = &self.config.smtp_host;
+        let accepted = self
+            .transport()
+            .context("Invalid SMTP settings")?
+            .test_connection()
+            .await
+            .with_context(|| format!("SMTP check against {host} failed"))?;
+        if !accepted {
+            bail!("SMTP server {host} did not accept the connection");
+        }
+        Ok(())
+    }
+
+    /// Send `message` with files attached.
+    pub async fn send_with_attachments(
+        &self,
+        message: &SendMessage,
+        attachments: &[EmailAttachment],
+    ) -> Result<()> {
+        let email = self.build_message(message, attachments)?;
+        self.transport()?
+            .send(email)
+            .await
+            .with_context(|| format!("SMTP delivery via {} failed", self.config.smtp_host))?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Channel for EmailChannel {
+    fn name(&self) -> &str {
+        "email"
+    }
+
+    async fn send(&self, message: &SendMessage) -> Result<()> {
+        self.send_with_attachments(message, &[]).await
+    }
+
+    /// Outbound only: there is no inbox to watch.
+    async fn listen(&self, _tx: tokio::sync::mpsc::Sender<ChannelMessage>) -> Result<()> {
+        Ok(())
+    }
+
+    async fn health_check(&self) -> bool {
+        match self.test_connection().await {
+            Ok(()) => true,
+            Err(err) => {
+                tracing::warn!("Email: {err:#}");
+                false
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn channel(subject_template: &str) -> EmailChannel {
+        EmailChannel::from_config(&EmailConfig {
+            smtp_host: "smtp.example.com".into(),
+            smtp_port: 465,
+            smtp_tls: true,
+            from_address: "SlowClaw <digest@example.com>".into(),
+            subject_template: subject_template.into(),
+            ..EmailConfig::default()
+        })
+        .unwrap()
+    }
+
+    fn formatted(message: &Message) -> String {
+        String::from_utf8(message.formatted()).unwrap()
+    }
+
+    #[test]
+    fn config_defaults_allow_an_smtp_only_section() {
+        let config: EmailConfig = toml::from_str(
+            "smtp_host = \"smtp.example.com\"\nfrom_address = \"digest@example.com\"\n",
+        )
+        .unwrap();
+        assert_eq!(config.smtp_port, 465);
+        assert!(config.smtp_tls);
+        assert_eq!(config.subject_template, "{subject}");
+        assert!(EmailChannel::from_config(&config).is_ok());
+    }
+
+    #[test]
+    fn from_config_rejects_missing_host_and_bad_sender() {
+        let mut config = EmailConfig {
+            from_address: "digest@example.com".into(),
+            ..EmailConfig::default()
+        };
+        assert!(EmailChannel::from_config(&config).is_err());
+        config.smtp_host = "smtp.example.com".into();
+        config.from_address = "not an address".into();
+        assert!(EmailChannel::from_config(&config).is_err());
+    }
+
+    #[test]
+    fn subject_uses_message_subject_or_first_line() {
+        let with_subject = SendMessage::with_subject("body", "me@example.com", "Weekly notes");
+        assert_eq!(
+            render_subject("[SlowClaw] {subject} ({date})", &with_subject, "2026-03-01"),
+            "[SlowClaw] Weekly notes (2026-03-01)"
+        );
+
+        let heading = SendMessage::new("\n# Journal digest — 2026-03-01\n\nText", "me@example.com");
+        assert_eq!(
+            render_subject("{subject}", &heading, "2026-03-01"),
+            "Journal digest — 2026-03-01"
+        );
+
+        let empty = SendMessage::new("   ", "me@example.com");
+        assert_eq!(render_subject("", &empty, "2026-03-01"), DEFAULT_SUBJECT);
+    }
+
+    #[test]
+    fn markdown_renders_to_html_with_raw_html_escaped() {
+        let html = markdown_to_html("# Title\n\n- **bold** item\n\n<script>alert(1)</script>\n");
+        assert!(html.contains("<h1>Title</h1>"));
+        assert!(html.contains("<li><strong>bold</strong> item</li>"));
+        assert!(!html.contains("<script>"));
+        assert!(html.contains("&lt;script&gt;"));
+    }
+
+    #[test]
+    fn builds_plain_and_html_alternatives() {
+        let message = SendMessage::with_subject(
+            "# Digest\n\nYou walked **twice**.",
+            "me@example.com, Ada <ada@example.com>",
+            "Daily digest",
+        );
+        let raw = formatted(
+            &channel("[SlowClaw] {subject}")
+                .build_message(&message, &[])
+                .unwrap(),
+        );
+
+        assert!(raw.contains("From: SlowClaw <digest@example.com>"));
+        assert!(raw.contains("me@example.com"));
+        assert!(raw.contains("Ada <ada@example.com>"));
+        assert!(raw.contains("Subject: [SlowClaw] Daily digest"));
+        assert!(raw.contains("multipart/alternative"));
+        assert!(!raw.contains("multipart/mixed"));
+        assert!(raw.contains("Content-Type: text/plain; charset=utf-8"));
+        assert!(raw.contains("Content-Type: text/html; charset=utf-8"));
+        assert!(raw.contains("You walked **twice**."));
+        assert!(raw.contains("<strong>twice</strong>"));
+    }
+
+    #[test]
+    fn attachments_wrap_the_body_in_multipart_mixed() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("export.csv");
+        std::fs::write(&path, "date,mood\n2026-03-01,calm\n").unwrap();
+        let attachment = EmailAttachment::from_path(&path).unwrap();
+        assert_eq!(attachment.filename, "export.csv");
+        assert_eq!(attachment.content_type, "text/csv");
+
+        let message = SendMessage::new("See attached.", "me@example.com");
+        let raw = formatted(
+            &channel("{subject}")
+                .build_message(&message, &[attachment])
+                .unwrap(),
+        );
+        assert!(raw.contains("multipart/mixed"));
+        assert!(raw.contains("multipart/alternative"));
+        assert!(raw.contains("Content-Disposition: attachment; filename=\"export.csv\""));
+        assert!(raw.contains("Content-Type: text/csv"));
+    }
+
+    #[test]
+    fn oversized_attachments_are_refused() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("big.bin");
+        let file = std::fs::File::create(&path).unwrap();
+        file.set_len(MAX_ATTACHMENT_BYTES + 1).unwrap();
+        let err = EmailAttachment::from_path(&path).unwrap_err();
+        assert!(err.to_string().contains("limit"));
+    }
+
+    #[test]
+    fn invalid_recipients_are_rejected() {
+        let channel = channel("{subject}");
+        assert!(channel
+            .build_message(&SendMessage::new("hi", "nobody"), &[])
+            .is_err());
+        assert!(channel
+            .build_message(&SendMessage::new("hi", " , "), &[])
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn listen_returns_immediately() {
+        let (tx, _rx) = tokio::sync::mpsc::channel(1);
+        assert!(channel("{subject}").listen(tx).await.is_ok());
+    }
+}
@@ -1,75 +1,44 @@
 //! Minimal channel subsystem for the workspace-only fork.
 //!
-//! External channel integrations are disabled. This module keeps the CLI channel,
-//! channel traits, prompt builders, and small compatibility stubs required by
-//! the gateway/config codepaths so the rest of the application can compile.
+//! Most external channel integrations are disabled. This module keeps the CLI
+//! and PocketBase channels, Telegram, outbound-only email, Nextcloud Talk
+//! (behind the `channel-nextcloud` feature), WhatsApp Cloud API (behind
+//! `channel-whatsapp`), channel traits, prompt builders, and small
+//! compatibility stubs required by the gateway/config codepaths.
 
+pub mod bluesky;
 pub mod cli;
 pub mod context;
+pub mod email_channel;
+#[cfg(feature = "channel-nextcloud")]
+pub mod nextcloud_talk;
+pub mod outbound;
 pub mod pocketbase;
+pub mod pocketbase_auth;
+pub mod pocketbase_client;
+pub mod pocketbase_schema;
+pub mod telegram;
 pub mod traits;
+#[cfg(feature = "channel-whatsapp")]
+pub mod whatsapp;
 
 use anyhow::Result;
 use async_trait::async_trait;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 
 pub use cli::CliChannel;
-pub use context::{with_channel_execution_context, ChannelExecutionContext};
+pub use context::{register_live_channel, with_channel_execution_context, ChannelExecutionContext};
+pub use email_channel::EmailChannel;
+#[cfg(feature = "channel-nextcloud")]
+pub use nextcloud_talk::NextcloudTalkChannel;
+pub use outbound::OutboundDispatcher;
 pub use pocketbase::PocketBaseChannel;
-pub use traits::{Channel, SendMessage};
-
-pub mod email_channel {
-    use schemars::JsonSchema;
-    use serde::{Deserialize, Serialize};
-
-    /// Retained config type for backward-compatible config parsing/masking.
-    #[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
-    pub struct EmailConfig {
-        pub imap_host: String,
-        #[serde(default = "default_imap_port")]
-        pub imap_port: u16,
-        #[serde(default = "default_imap_folder")]
-        pub imap_folder: String,
-        pub smtp_host: String,
-        #[serde(default = "default_smtp_port")]
-        pub smtp_port: u16,
-        #[serde(default = "default_true")]
-        pub smtp_tls: bool,
-        pub username: String,
-        pub password: String,
-        pub from_address: String,
-        #[serde(default = "default_idle_timeout", alias = "poll_interval_secs")]
-        pub idle_timeout_secs: u64,
-        #[serde(default)]
-        pub allowed_senders: Vec<String>,
-    }
-
-    impl crate::config::traits::ChannelConfig for EmailConfig {
-        fn name() -> &'static str {
-            "Email"
-        }
-        fn desc() -> &'static str {
-            "disabled in this fork"
-        }
-    }
-
-    fn default_imap_port() -> u16 {
-        993
-    }
-    fn default_smtp_port() -> u16 {
-        465
-    }
-    fn default_imap_folder() -> String {
-        "INBOX".into()
-    }
-    fn default_idle_timeout() -> u64 {
-        1740
-    }
-    fn default_true() -> bool {
-        true
-    }
-}
+pub use telegram::TelegramChannel;
+pub use traits::{Channel, SendMessage, VerifyResult};
+#[cfg(feature = "channel-whatsapp")]
+pub use whatsapp::WhatsAppChannel;
 
 pub mod clawdtalk {
     use schemars::JsonSchema;
@@ -96,22 +65,36 @@ pub mod clawdtalk {
     }
 }
 
+/// The gateway refuses to start when `[channels_config.linq]` carries a
+/// `signing_secret`, because this stub cannot check it.
 pub mod linq {
+    use super::VerifyResult;
+
     /// External Linq verification is disabled in this fork.
-    pub fn verify_linq_signature(_secret: &str, _body: &str, _timestamp: &str, _signature: &str) -> bool {
-        false
+    pub fn verify_linq_signature(
+        _secret: &str,
+        _body: &str,
+        _timestamp: &str,
+        _signature: &str,
+    ) -> VerifyResult {
+        VerifyResult::Unsupported
     }
 }
 
+/// Without the `channel-nextcloud` feature the gateway refuses to start when
+/// `[channels_config.nextcloud_talk]` is set, so these stubs are never reached.
+#[cfg(not(feature = "channel-nextcloud"))]
 pub mod nextcloud_talk {
-    /// External Nextcloud Talk verification is disabled in this fork.
+    use super::VerifyResult;
+
+    /// Nextcloud Talk needs the `channel-nextcloud` feature.
     pub fn verify_nextcloud_talk_signature(
         _secret: &str,
         _random: &str,
         _body: &str,
         _signature: &str,
-    ) -> bool {
-        false
+    ) -> VerifyResult {
+        VerifyResult::Unsupported
     }
 }
 
@@ -121,11 +104,15 @@ struct DisabledExternalChannelConfigMarker {
     _disabled: bool,
 }
 
+/// Without the `channel-whatsapp` feature the gateway warns about
+/// `[channels_config.whatsapp]` at startup and `/whatsapp` answers 404.
+#[cfg(not(feature = "channel-whatsapp"))]
 #[derive(Debug, Clone)]
 pub struct WhatsAppChannel {
     verify_token: String,
 }
 
+#[cfg(not(feature = "channel-whatsapp"))]
 impl WhatsAppChannel {
     pub fn new(
         _access_token: String,
@@ -185,9 +172,11 @@ impl WatiChannel {
     }
 }
 
+#[cfg(not(feature = "channel-nextcloud"))]
 #[derive(Debug, Clone, Default)]
 pub struct NextcloudTalkChannel;
 
+#[cfg(not(feature = "channel-nextcloud"))]
 impl NextcloudTalkChannel {
     pub fn new(_base_url: String, _app_token: String, _allowed_users: Vec<String>) -> Self {
         Self
@@ -227,23 +216,27 @@ macro_rules! impl_disabled_channel {
     };
 }
 
+#[cfg(not(feature = "channel-whatsapp"))]
 impl_disabled_channel!(WhatsAppChannel, "whatsapp");
 impl_disabled_channel!(LinqChannel, "linq");
 impl_disabled_channel!(WatiChannel, "wati");
+#[cfg(not(feature = "channel-nextcloud"))]
 impl_disabled_channel!(NextcloudTalkChannel, "nextcloud-talk");
 
-pub(crate) async fn handle_command(command: crate::ChannelCommands, _config: &crate::config::Config) -> Result<()> {
+pub(crate) async fn handle_command(command: crate::ChannelCommands, config: &crate::config::Config) -> Result<()> {
     match command {
         crate::ChannelCommands::Start => {
             anyhow::bail!("Channel runtime is disabled in this fork")
         }
-        crate::ChannelCommands::Doctor => {
-            anyhow::bail!("Channel doctor is disabled in this fork")
-        }
+        crate::ChannelCommands::Doctor { strict, json } => Box::pin(doctor_channels(config.clone(), strict, json)).await,
         crate::ChannelCommands::List => {
             println!("Channels:");
             println!("  ✅ CLI (always available)");
             println!("  ✅ PocketBase (internal app channel via gateway/PocketBase)");
+            let telegram = if config.channels_config.telegram.is_some() { "✅" } else { "⬜" };
+            println!("  {telegram} Telegram ([channels_config.telegram])");
+            let email = if config.channels_config.email.is_some() { "✅" } else { "⬜" };
+            println!("  {email} Email, outbound only ([channels_config.email])");
             println!("  🚫 Other external channel integrations are disabled in this fork.");
             println!("  ✅ Cron/script scheduling remains available.");
             Ok(())
@@ -254,23 +247,164 @@ pub(crate) async fn handle_command(command: crate::ChannelCommands, _config: &cr
         crate::ChannelCommands::Remove { name } => {
             anyhow::bail!("External channel integrations are disabled in this fork ({name})")
         }
-        crate::ChannelCommands::BindTelegram { .. } => {
-            anyhow::bail!("Telegram integration is disabled in this fork")
+        crate::ChannelCommands::BindTelegram { identity } => {
+            let identity = identity.trim().trim_start_matches('@').to_string();
+            if identity.is_empty() {
+                anyhow::bail!("Telegram identity is empty");
+            }
+            let mut config = config.clone();
+            let Some(telegram) = config.channels_config.telegram.as_mut() else {
+                anyhow::bail!("Configure [channels_config.telegram] with a bot_token first");
+            };
+            if telegram.allowed_users.iter().any(|user| user.trim_start_matches('@') == identity) {
+                println!("Telegram identity {identity} is already allowed.");
+                return Ok(());
+            }
+            telegram.allowed_users.push(identity.clone());
+            config.save().await?;
+            println!("✅ Telegram identity {identity} added to the allowlist.");
+            Ok(())
         }
     }
 }
 
-pub async fn doctor_channels(_config: crate::config::Config) -> Result<()> {
-    println!("Channel doctor is disabled: external channel integrations were removed in this fork.");
-    println!("Use `slowclaw cron ...` and PocketBase delivery instead.");
-    Ok(())
+/// Probe the gateway, PocketBase, and each configured channel; see
+/// [`crate::doctor::run_channels`].
+pub async fn doctor_channels(config: crate::config::Config, strict: bool, json: bool) -> Result<()> {
+    crate::doctor::run_channels(&config, strict, json).await
 }
 
-pub async fn start_channels(_config: crate::config::Config) -> Result<()> {
+/// Run the polling channels: currently Telegram in `getUpdates` mode. Each
+/// message is answered on its own task. Returns when the listener stops so
+/// the daemon supervisor can restart it.
+pub async fn start_channels(config: crate::config::Config) -> Result<()> {
     crate::health::mark_component_ok("channels");
-    println!("Channel runtime is disabled for external providers in this fork.");
-    println!("PocketBase chat runs via the gateway-integrated PocketBase channel worker.");
-    Ok(())
+    let Some(telegram) = config
+        .channels_config
+        .telegram
+        .clone()
+        .filter(|telegram| telegram.webhook_secret.is_none())
+    else {
+        println!("No polling channels configured.");
+        println!("PocketBase chat runs via the gateway-integrated PocketBase channel worker.");
+        return Ok(());
+    };
+
+    let channel = Arc::new(TelegramChannel::from_config(&telegram));
+    register_live_channel(channel.clone());
+    let (tx, mut rx) = tokio::sync::mpsc::channel(64);
+    let listener = {
+        let channel = Arc::clone(&channel);
+        tokio::spawn(async move { channel.listen(tx).await })
+    };
+    println!("Telegram channel listening (long polling).");
+    while let Some(message) = rx.recv().await {
+        let config = config.clone();
+        let channel = Arc::clone(&channel);
+        tokio::spawn(async move { telegram::answer(config, &channel, message).await });
+    }
+    listener.await??;
+    anyhow::bail!("Telegram listener stopped")
+}
+
+/// Workspace files injected into the system prompt, highest priority first.
+/// When the character budget runs short, later files are cut before earlier
+/// ones.
+const PROMPT_WORKSPACE_FILES: [&str; 8] = [
+    "IDENTITY.md",
+    "USER.md",
+    "MEMORY.md",
+    "AGENTS.md",
+    "SOUL.md",
+    "TOOLS.md",
+    "HEARTBEAT.md",
+    "BOOTSTRAP.md",
+];
+/// Characters of workspace files a prompt carries when the caller sets no
+/// `bootstrap_max_chars`.
+const PROMPT_CONTEXT_MAX_CHARS: usize = 20_000;
+/// Most characters any one workspace file may take.
+const PROMPT_FILE_MAX_CHARS: usize = 8_000;
+
+/// `text` cut to at most `max_chars` characters.
+fn truncate_chars(text: &str, max_chars: usize) -> &str {
+    text.char_indices()
+        .nth(max_chars)
+        .map_or(text, |(idx, _)| &text[..idx])
+}
+
+/// The "Project Context" section: the AIEOS identity when one is configured,
+/// then the workspace files in priority order. File contents share
+/// `max_chars`; each is also capped at `PROMPT_FILE_MAX_CHARS`. Missing and
+/// empty files are skipped.
+fn workspace_context_section(
+    workspace_dir: &std::path::Path,
+    identity_config: Option<&crate::config::IdentityConfig>,
+    max_chars: usize,
+) -> String {
+    use std::fmt::Write;
+
+    let aieos = identity_config
+        .filter(|config| crate::identity::is_aieos_configured(config))
+        .and_then(|config| {
+            crate::identity::load_aieos_identity(config, workspace_dir)
+                .ok()
+                .flatten()
+        })
+        .map(|identity| crate::identity::aieos_to_system_prompt(&identity))
+        .filter(|rendered| !rendered.trim().is_empty());
+    let has_aieos = aieos.is_some();
+    // An AIEOS identity stands in for IDENTITY.md, at the same priority.
+    let mut sources: Vec<(&str, String)> = aieos
+        .map(|rendered| ("Identity (AIEOS)", rendered))
+        .into_iter()
+        .collect();
+    for file in PROMPT_WORKSPACE_FILES {
+        if file == "IDENTITY.md" && has_aieos {
+            continue;
+        }
+        if let Ok(content) = std::fs::read_to_string(workspace_dir.join(file)) {
+            sources.push((file, content));
+        }
+    }
+
+    let mut remaining = max_chars;
+    let mut body = String::new();
+    let mut omitted = Vec::new();
+    for (label, content) in &sources {
+        let content = content.trim();
+        if content.is_empty() {
+            continue;
+        }
+        if remaining == 0 {
+            omitted.push(*label);
+            continue;
+        }
+        let kept = truncate_chars(content, remaining.min(PROMPT_FILE_MAX_CHARS));
+        remaining -= kept.chars().count();
+        let _ = writeln!(body, "### {label}\n");
+        body.push_str(kept);
+        if kept.len() < content.len() {
+            let _ = write!(body, "\n\n[... truncated — use `read` for the full {label}]");
+        }
+        body.push_str("\n\n");
+    }
+    if !omitted.is_empty() {
+        let _ = writeln!(
+            body,
+            "[Left out to fit the prompt: {} — use `read` to load them]\n",
+            omitted.join(", ")
+        );
+    }
+    if body.is_empty() {
+        return body;
+    }
+    let mut section = String::from("## Project Context\n\n");
+    section.push_str(
+        "The following workspace files define your identity, behavior, and context.\n\n",
+    );
+    section.push_str(&body);
+    section
 }
 
 pub fn build_system_prompt(
@@ -299,8 +433,8 @@ pub fn build_system_prompt_with_mode(
     model_name: &str,
     tools: &[(&str, &str)],
     skills: &[crate::skills::Skill],
-    _identity_config: Option<&crate::config::IdentityConfig>,
-    _bootstrap_max_chars: Option<usize>,
+    identity_config: Option<&crate::config::IdentityConfig>,
+    bootstrap_max_chars: Option<usize>,
     native_tools: bool,
     skills_prompt_mode: crate::config::SkillsPromptInjectionMode,
 ) -> String {
@@ -314,6 +448,11 @@ pub fn build_system_prompt_with_mode(
     let _ = writeln!(prompt, "Model: `{model_name}`");
     prompt.push_str("External messaging channels are disabled except the internal PocketBase app channel.\n");
     prompt.push_str("Prefer workspace-local tools and scheduled tasks.\n\n");
+    prompt.push_str(&workspace_context_section(
+        workspace_dir,
+        identity_config,
+        bootstrap_max_chars.unwrap_or(PROMPT_CONTEXT_MAX_CHARS),
+    ));
 
     if !tools.is_empty() {
         prompt.push_str("## Tools\n");
@@ -383,6 +522,64 @@ mod tests {
         assert!(prompt.contains("## Your Task"));
     }
 
+    fn prompt_for(workspace: &Path, bootstrap_max_chars: Option<usize>) -> String {
+        build_system_prompt_with_mode(
+            workspace,
+            "test-model",
+            &[],
+            &[],
+            None,
+            bootstrap_max_chars,
+            false,
+            SkillsPromptInjectionMode::Compact,
+        )
+    }
+
+    #[test]
+    fn workspace_files_fill_the_budget_in_priority_order() {
+        let tmp = tempfile::tempdir().unwrap();
+        std::fs::write(tmp.path().join("MEMORY.md"), "m".repeat(500)).unwrap();
+        std::fs::write(tmp.path().join("AGENTS.md"), "agents").unwrap();
+        std::fs::write(tmp.path().join("USER.md"), "u".repeat(500)).unwrap();
+        std::fs::write(tmp.path().join("IDENTITY.md"), "i".repeat(100)).unwrap();
+
+        let prompt = prompt_for(tmp.path(), Some(400));
+        assert!(prompt.contains("## Project Context"));
+        let identity = prompt.find("### IDENTITY.md").unwrap();
+        let user = prompt.find("### USER.md").unwrap();
+        assert!(identity < user);
+        assert!(prompt.contains(&format!("### IDENTITY.md\n\n{}\n\n", "i".repeat(100))));
+        // USER.md gets what IDENTITY.md left; MEMORY.md and AGENTS.md get nothing.
+        assert!(prompt.contains(&format!("{}\n\n[... truncated", "u".repeat(300))));
+        assert!(!prompt.contains(&"u".repeat(301)));
+        assert!(!prompt.contains("### MEMORY.md"));
+        assert!(prompt.contains("[Left out to fit the prompt: MEMORY.md, AGENTS.md"));
+
+        // Each file is capped even when the overall budget has room.
+        std::fs::write(tmp.path().join("USER.md"), "u".repeat(9_000)).unwrap();
+        let prompt = prompt_for(tmp.path(), None);
+        assert!(prompt.contains(&"u".repeat(8_000)));
+        assert!(!prompt.contains(&"u".repeat(8_001)));
+        assert!(prompt.contains(&format!("### MEMORY.md\n\n{}\n\n", "m".repeat(500))));
+        assert!(prompt.contains("### AGENTS.md\n\nagents"));
+    }
+
+    #[test]
+    fn missing_and_empty_workspace_files_are_skipped() {
+        let tmp = tempfile::tempdir().unwrap();
+        std::fs::write(tmp.path().join("USER.md"), "Prefers short answers.").unwrap();
+        std::fs::write(tmp.path().join("MEMORY.md"), "  \n").unwrap();
+
+        let prompt = prompt_for(tmp.path(), None);
+        assert!(prompt.contains("### USER.md\n\nPrefers short answers."));
+        assert!(!prompt.contains("### IDENTITY.md"));
+        assert!(!prompt.contains("### MEMORY.md"));
+        assert!(!prompt.contains("Left out"));
+
+        let empty = tempfile::tempdir().unwrap();
+        assert!(!prompt_for(empty.path(), None).contains("## Project Context"));
+    }
+
     #[test]
     fn system_prompt_omits_skills_block_when_empty() {
         let prompt = build_system_prompt_with_mode(
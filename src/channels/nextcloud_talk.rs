@@ -0,0 +1,346 @@
+//! Nextcloud Talk bot channel (cargo feature `channel-nextcloud`).
+//!
+//! Talk delivers bot events to the gateway's `/nextcloud-talk` route as
+//! Activity Streams JSON, signed with the bot's shared secret:
+//! `X-Nextcloud-Talk-Signature` is the hex HMAC-SHA256 of
+//! `X-Nextcloud-Talk-Random` followed by the raw body. Replies go out through
+//! the OCS bot API with the app token.
+
+use crate::channels::traits::{Channel, ChannelMessage, SendMessage, VerifyResult};
+use crate::security::SecretString;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+
+pub const SIGNATURE_HEADER: &str = "X-Nextcloud-Talk-Signature";
+pub const RANDOM_HEADER: &str = "X-Nextcloud-Talk-Random";
+const BOT_SIGNATURE_HEADER: &str = "X-Nextcloud-Talk-Bot-Signature";
+const BOT_RANDOM_HEADER: &str = "X-Nextcloud-Talk-Bot-Random";
+
+fn hmac_hex(secret: &str, random: &str, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC can take key of any size");
+    mac.update(random.as_bytes());
+    mac.update(body.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Check a webhook signature: hex HMAC-SHA256 over `random` + `body`, keyed
+/// with the bot secret. Compared in constant time; case-insensitive hex.
+pub fn verify_nextcloud_talk_signature(
+    secret: &str,
+    random: &str,
+    body: &str,
+    signature: &str,
+) -> VerifyResult {
+    if secret.is_empty() || random.is_empty() {
+        return VerifyResult::Invalid;
+    }
+    let Ok(expected) = hex::decode(signature.trim().to_ascii_lowercase()) else {
+        return VerifyResult::Invalid;
+    };
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC can take key of any size");
+    mac.update(random.as_bytes());
+    mac.update(body.as_bytes());
+    mac.verify_slice(&expected).is_ok().into()
+}
+
+/// Talk stores the message text and its placeholders as a JSON string in
+/// `object.content`; mentions arrive as `{mention-user1}` plus a parameter.
+fn render_message(content: &str) -> Option<String> {
+    let parsed: serde_json::Value = serde_json::from_str(content).ok()?;
+    let mut text = parsed.get("message")?.as_str()?.to_string();
+    // PHP encodes an empty parameter map as `[]`, so only objects matter.
+    if let Some(parameters) = parsed.get("parameters").and_then(|p| p.as_object()) {
+        for (key, parameter) in parameters {
+            let name = parameter
+                .get("name")
+                .and_then(|n| n.as_str())
+                .unwrap_or(key);
+            text = text.replace(&format!("{{{key}}}"), &format!("@{name}"));
+        }
+    }
+    Some(text)
+}
+
+#[derive(Clone)]
+pub struct NextcloudTalkChannel {
+    client: reqwest::Client,
+    base_url: String,
+    app_token: SecretString,
+    webhook_secret: Option<SecretString>,
+    allowed_users: Vec<String>,
+}
+
+impl NextcloudTalkChannel {
+    pub fn new(base_url: String, app_token: String, allowed_users: Vec<String>) -> Self {
+        Self {
+            client: crate::http_client::shared(),
+            base_url: base_url.trim().trim_end_matches('/').to_string(),
+            app_token: SecretString::new(app_token.trim()),
+            webhook_secret: None,
+            allowed_users,
+        }
+    }
+
+    /// Also sign outgoing messages with the bot secret, which Talk requires
+    /// for bots installed with `occ talk:bot:install`.
+    pub fn with_webhook_secret(mut self, secret: Option<SecretString>) -> Self {
+        self.webhook_secret = secret
+            .map(|secret| SecretString::new(secret.expose().trim()))
+            .filter(|secret| !secret.is_empty());
+        self
+    }
+
+    /// `[]` denies everyone and `"*"` allows everyone. Entries match the full
+    /// actor id (`users/ada`) or the bare user id (`ada`).
+    pub fn is_user_allowed(&self, actor_id: &str) -> bool {
+        let user_id = actor_id.strip_prefix("users/").unwrap_or(actor_id);
+        self.allowed_users.iter().any(|allowed| {
+            let allowed = allowed.trim();
+            allowed == "*" || allowed == actor_id || allowed == user_id
+        })
+    }
+
+    /// Turn a webhook payload into channel messages. Only new chat messages
+    /// (`Create` of a `Note`) from people on the allow list are kept;
+    /// reactions, joins, and messages from other bots yield nothing.
+    pub fn parse_webhook_payload(&self, payload: &serde_json::Value) -> Vec<ChannelMessage> {
+        let field = |value: &serde_json::Value, key: &str| {
+            value
+                .get(key)
+                .and_then(|v| v.as_str())
+                .map(str::trim)
+                .unwrap_or_default()
+                .to_string()
+        };
+        let actor = &payload["actor"];
+        let object = &payload["object"];
+        if field(payload, "type") != "Create"
+            || field(object, "type") != "Note"
+            || field(actor, "type") != "Person"
+        {
+            return Vec::new();
+        }
+        let actor_id = field(actor, "id");
+        let room = field(&payload["target"], "id");
+        if actor_id.is_empty() || room.is_empty() {
+            return Vec::new();
+        }
+        if !self.is_user_allowed(&actor_id) {
+            tracing::warn!("Nextcloud Talk: ignoring message from unauthorized user {actor_id}");
+            return Vec::new();
+        }
+        let Some(content) = render_message(&field(object, "content")) else {
+            return Vec::new();
+        };
+        if content.trim().is_empty() {
+            return Vec::new();
+        }
+        let id = field(object, "id");
+        vec![ChannelMessage {
+            id: id.clone(),
+            sender: actor_id
+                .strip_prefix("users/")
+                .unwrap_or(&actor_id)
+                .to_string(),
+            reply_target: room,
+            content,
+            channel: "nextcloud-talk".to_string(),
+            timestamp: u64::try_from(chrono::Utc::now().timestamp()).unwrap_or_default(),
+            thread_ts: (!id.is_empty()).then_some(id),
+            attachments: Vec::new(),
+        }]
+    }
+}
+
+#[async_trait]
+impl Channel for NextcloudTalkChannel {
+    fn name(&self) -> &str {
+        "nextcloud-talk"
+    }
+
+    /// Post `message.content` to the room token in `message.recipient`,
+    /// replying to `thread_ts` when it holds a Talk message id.
+    async fn send(&self, message: &SendMessage) -> Result<()> {
+        let url = format!(
+            "{}/ocs/v2.php/apps/spreed/api/v1/bot/{}/message",
+            self.base_url,
+            urlencoding::encode(message.recipient.trim())
+        );
+        let reference_id = hex::encode(Sha256::digest(
+            format!("{}\0{}", message.recipient, message.content).as_bytes(),
+        ));
+        let mut body = serde_json::json!({
+            "message": message.content,
+            "referenceId": reference_id,
+        });
+        if let Some(reply_to) = message
+            .thread_ts
+            .as_deref()
+            .and_then(|id| id.trim().parse::<u64>().ok())
+        {
+            body["replyTo"] = serde_json::json!(reply_to);
+        }
+        let mut request = self
+            .client
+            .post(url)
+            .bearer_auth(self.app_token.expose())
+            .header("OCS-APIRequest", "true")
+            .header("Accept", "application/json")
+            .json(&body);
+        if let Some(secret) = &self.webhook_secret {
+            let random = hex::encode(rand::random::<[u8; 32]>());
+            request = request
+                .header(
+                    BOT_SIGNATURE_HEADER,
+                    hmac_hex(secret.expose(), &random, &message.content),
+                )
+                .header(BOT_RANDOM_HEADER, random);
+        }
+        let response = request
+            .send()
+            .await
+            .context("Nextcloud Talk send request failed")?;
+        let status = response.status();
+        if !status.is_success() {
+            let detail = response.text().await.unwrap_or_default();
+            anyhow::bail!("Nextcloud Talk send failed ({status}): {detail}");
+        }
+        Ok(())
+    }
+
+    /// Talk pushes messages to the gateway webhook, so there is nothing to poll.
+    async fn listen(&self, _tx: tokio::sync::mpsc::Sender<ChannelMessage>) -> Result<()> {
+        tracing::info!("Nextcloud Talk: messages arrive via the gateway /nextcloud-talk webhook");
+        Ok(())
+    }
+
+    async fn health_check(&self) -> bool {
+        self.client
+            .get(format!("{}/status.php", self.base_url))
+            .send()
+            .await
+            .map(|resp| resp.status().is_success())
+            .unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_partial_json, header, header_exists, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const MESSAGE: &str = include_str!("../../tests/fixtures/nextcloud_talk/message.json");
+    const REACTION: &str = include_str!("../../tests/fixtures/nextcloud_talk/reaction.json");
+    const BOT_MESSAGE: &str = include_str!("../../tests/fixtures/nextcloud_talk/bot_message.json");
+    const SECRET: &str = "slowclaw-talk-secret";
+    const RANDOM: &str = "f3a1c6d2e8b94f0a7c5d1e2b3a4f6c8d9e0a1b2c3d4e5f60718293a4b5c6d7e8";
+    /// HMAC-SHA256(SECRET, RANDOM + message.json), computed independently.
+    const SIGNATURE: &str = "ddbacbcf7a93da40181cfed250b6641e077cbcfcb5c6449b69bd78ead3c56d3a";
+
+    fn channel(allowed: &[&str]) -> NextcloudTalkChannel {
+        NextcloudTalkChannel::new(
+            "https://cloud.example.com/".into(),
+            "app-token".into(),
+            allowed.iter().map(|u| (*u).to_string()).collect(),
+        )
+    }
+
+    #[test]
+    fn signature_matches_the_random_plus_body_scheme() {
+        assert_eq!(
+            verify_nextcloud_talk_signature(SECRET, RANDOM, MESSAGE, SIGNATURE),
+            VerifyResult::Valid
+        );
+        assert_eq!(
+            verify_nextcloud_talk_signature(
+                SECRET,
+                RANDOM,
+                MESSAGE,
+                &SIGNATURE.to_ascii_uppercase()
+            ),
+            VerifyResult::Valid
+        );
+        assert_eq!(hmac_hex(SECRET, RANDOM, MESSAGE), SIGNATURE);
+    }
+
+    #[test]
+    fn signature_rejects_tampering_and_wrong_secrets() {
+        let tampered = MESSAGE.replace("how did", "why did");
+        assert_eq!(
+            verify_nextcloud_talk_signature(SECRET, RANDOM, &tampered, SIGNATURE),
+            VerifyResult::Invalid
+        );
+        assert_eq!(
+            verify_nextcloud_talk_signature("other-secret", RANDOM, MESSAGE, SIGNATURE),
+            VerifyResult::Invalid
+        );
+        assert_eq!(
+            verify_nextcloud_talk_signature(SECRET, "", MESSAGE, SIGNATURE),
+            VerifyResult::Invalid
+        );
+        assert_eq!(
+            verify_nextcloud_talk_signature(SECRET, RANDOM, MESSAGE, "not-hex"),
+            VerifyResult::Invalid
+        );
+    }
+
+    #[test]
+    fn parses_a_chat_message_and_renders_mentions() {
+        let payload: serde_json::Value = serde_json::from_str(MESSAGE).unwrap();
+        let messages = channel(&["ada"]).parse_webhook_payload(&payload);
+        assert_eq!(messages.len(), 1);
+        let message = &messages[0];
+        assert_eq!(message.content, "hi @SlowClaw, how did the run go?");
+        assert_eq!(message.sender, "ada");
+        assert_eq!(message.reply_target, "n3xtc10ud");
+        assert_eq!(message.thread_ts.as_deref(), Some("1567"));
+        assert_eq!(message.channel, "nextcloud-talk");
+    }
+
+    #[test]
+    fn ignores_reactions_bots_and_unlisted_users() {
+        let reaction: serde_json::Value = serde_json::from_str(REACTION).unwrap();
+        let bot: serde_json::Value = serde_json::from_str(BOT_MESSAGE).unwrap();
+        let message: serde_json::Value = serde_json::from_str(MESSAGE).unwrap();
+        let open = channel(&["*"]);
+        assert!(open.parse_webhook_payload(&reaction).is_empty());
+        assert!(open.parse_webhook_payload(&bot).is_empty());
+        assert!(channel(&[]).parse_webhook_payload(&message).is_empty());
+        assert!(channel(&["grace"])
+            .parse_webhook_payload(&message)
+            .is_empty());
+        assert_eq!(
+            channel(&["users/ada"])
+                .parse_webhook_payload(&message)
+                .len(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn send_posts_to_the_ocs_bot_api() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/ocs/v2.php/apps/spreed/api/v1/bot/n3xtc10ud/message"))
+            .and(header("Authorization", "Bearer app-token"))
+            .and(header("OCS-APIRequest", "true"))
+            .and(header_exists(BOT_SIGNATURE_HEADER))
+            .and(body_partial_json(
+                serde_json::json!({"message": "done", "replyTo": 1567}),
+            ))
+            .respond_with(ResponseTemplate::new(201))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let channel = NextcloudTalkChannel::new(server.uri(), "app-token".into(), vec![])
+            .with_webhook_secret(Some(SECRET.into()));
+        let mut message = SendMessage::new("done", "n3xtc10ud");
+        message.thread_ts = Some("1567".into());
+        channel.send(&message).await.unwrap();
+    }
+}
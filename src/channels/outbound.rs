@@ -0,0 +1,477 @@
+//! Retry queue for channel replies that could not be sent.
+//!
+//! [`OutboundDispatcher::send`] tries a channel once and, on failure, writes
+//! the message to `<workspace>/state/outbox/<channel>/<id>.json`. A background
+//! loop calls [`OutboundDispatcher::retry_due`], which resends queued messages
+//! with exponential backoff. Messages older than the max age move to
+//! `dead/` under the same directory and raise an observer error. Ids come
+//! from the message content, so the same reply is never queued twice.
+
+use crate::channels::context::{live_channel, ChannelExecutionContext};
+use crate::channels::traits::{Channel, SendMessage};
+use crate::channels::PocketBaseChannel;
+use crate::observability::traits::ObserverMetric;
+use crate::observability::{Observer, ObserverEvent};
+use crate::workspace::atomic_write;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Workspace-relative root of the per-channel outbound queues.
+pub const OUTBOUND_DIR: &str = "state/outbox";
+const DEAD_LETTER_DIR: &str = "dead";
+const DEFAULT_BASE_DELAY: Duration = Duration::from_secs(30);
+const DEFAULT_MAX_DELAY: Duration = Duration::from_secs(60 * 60);
+const DEFAULT_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// What happened to a message handed to [`OutboundDispatcher::send`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Delivery {
+    Sent,
+    Queued,
+}
+
+/// Counts from one [`OutboundDispatcher::retry_due`] pass.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RetryReport {
+    pub delivered: usize,
+    pub dead: usize,
+    pub pending: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct QueuedMessage {
+    id: String,
+    channel: String,
+    content: String,
+    recipient: String,
+    #[serde(default)]
+    subject: Option<String>,
+    #[serde(default)]
+    thread_ts: Option<String>,
+    queued_at: DateTime<Utc>,
+    next_attempt_at: DateTime<Utc>,
+    attempts: u32,
+    #[serde(default)]
+    last_error: String,
+}
+
+impl QueuedMessage {
+    fn message(&self) -> SendMessage {
+        SendMessage {
+            content: self.content.clone(),
+            recipient: self.recipient.clone(),
+            subject: self.subject.clone(),
+            thread_ts: self.thread_ts.clone(),
+        }
+    }
+}
+
+/// Stable id for a message on a channel: the same reply to the same place
+/// always maps to the same queue file.
+fn message_id(channel: &str, message: &SendMessage) -> String {
+    let mut hasher = Sha256::new();
+    for part in [
+        channel,
+        &message.recipient,
+        message.thread_ts.as_deref().unwrap_or(""),
+        message.subject.as_deref().unwrap_or(""),
+        &message.content,
+    ] {
+        hasher.update(part.as_bytes());
+        hasher.update([0]);
+    }
+    hex::encode(&hasher.finalize()[..16])
+}
+
+/// Keep channel names usable as a directory name.
+fn channel_dir_name(channel: &str) -> String {
+    let name: String = channel
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if name.is_empty() {
+        "channel".to_string()
+    } else {
+        name
+    }
+}
+
+#[derive(Clone)]
+pub struct OutboundDispatcher {
+    root: PathBuf,
+    observer: Option<Arc<dyn Observer>>,
+    base_delay: Duration,
+    max_delay: Duration,
+    max_age: Duration,
+}
+
+impl OutboundDispatcher {
+    pub fn new(workspace_dir: &Path) -> Self {
+        Self {
+            root: workspace_dir.join(OUTBOUND_DIR),
+            observer: None,
+            base_delay: DEFAULT_BASE_DELAY,
+            max_delay: DEFAULT_MAX_DELAY,
+            max_age: DEFAULT_MAX_AGE,
+        }
+    }
+
+    /// Report queue depth and dead-lettered messages to `observer`.
+    pub fn with_observer(mut self, observer: Arc<dyn Observer>) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    /// Override the backoff schedule and how long a message may keep retrying.
+    pub fn with_retry_policy(
+        mut self,
+        base_delay: Duration,
+        max_delay: Duration,
+        max_age: Duration,
+    ) -> Self {
+        self.base_delay = base_delay;
+        self.max_delay = max_delay;
+        self.max_age = max_age;
+        self
+    }
+
+    fn channel_dir(&self, channel: &str) -> PathBuf {
+        self.root.join(channel_dir_name(channel))
+    }
+
+    fn queued_files(dir: &Path) -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                    .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+                    .collect()
+            })
+            .unwrap_or_default();
+        files.sort();
+        files
+    }
+
+    /// Messages waiting for redelivery on `channel`.
+    pub fn depth(&self, channel: &str) -> usize {
+        Self::queued_files(&self.channel_dir(channel)).len()
+    }
+
+    fn record_depth(&self, channel: &str) {
+        if let Some(observer) = &self.observer {
+            observer.record_metric(&ObserverMetric::OutboundQueueDepth {
+                channel: channel.to_string(),
+                depth: u64::try_from(self.depth(channel)).unwrap_or(u64::MAX),
+            });
+        }
+    }
+
+    /// Delay before retry number `attempt` (1-based), doubling up to the cap.
+    fn backoff(&self, attempt: u32) -> Duration {
+        self.base_delay
+            .saturating_mul(1 << attempt.saturating_sub(1).min(16))
+            .min(self.max_delay)
+    }
+
+    fn next_attempt_at(&self, now: DateTime<Utc>, attempt: u32) -> DateTime<Utc> {
+        now + chrono::Duration::from_std(self.backoff(attempt))
+            .unwrap_or_else(|_| chrono::Duration::days(1))
+    }
+
+    /// Send `message` on `channel`, queueing it for retry if the send fails.
+    /// Errors only when the message could neither be sent nor queued.
+    pub async fn send(&self, channel: &dyn Channel, message: &SendMessage) -> Result<Delivery> {
+        let err = match channel.send(message).await {
+            Ok(()) => return Ok(Delivery::Sent),
+            Err(err) => err,
+        };
+        let name = channel.name();
+        let dir = self.channel_dir(name);
+        let id = message_id(name, message);
+        let path = dir.join(format!("{id}.json"));
+        if path.exists() {
+            tracing::debug!(channel = name, id, "Reply already queued for redelivery");
+            return Ok(Delivery::Queued);
+        }
+        let now = Utc::now();
+        let queued = QueuedMessage {
+            id,
+            channel: name.to_string(),
+            content: message.content.clone(),
+            recipient: message.recipient.clone(),
+            subject: message.subject.clone(),
+            thread_ts: message.thread_ts.clone(),
+            queued_at: now,
+            next_attempt_at: self.next_attempt_at(now, 1),
+            attempts: 1,
+            last_error: format!("{err:#}"),
+        };
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create outbound queue {}", dir.display()))?;
+        atomic_write(&path, serde_json::to_vec_pretty(&queued)?)
+            .with_context(|| format!("Failed to queue reply for {name}"))?;
+        tracing::warn!(
+            channel = name,
+            "Reply send failed; queued for redelivery: {err:#}"
+        );
+        self.record_depth(name);
+        Ok(Delivery::Queued)
+    }
+
+    /// Resend every queued message for `channel` whose retry time has come.
+    /// Messages past the max age are moved to the dead-letter folder instead.
+    pub async fn retry_due(&self, channel: &dyn Channel) -> RetryReport {
+        let name = channel.name();
+        let dir = self.channel_dir(name);
+        let mut report = RetryReport::default();
+        let now = Utc::now();
+        let max_age = chrono::Duration::from_std(self.max_age)
+            .unwrap_or_else(|_| chrono::Duration::days(36_500));
+        for path in Self::queued_files(&dir) {
+            let Some(mut queued) = std::fs::read(&path)
+                .ok()
+                .and_then(|bytes| serde_json::from_slice::<QueuedMessage>(&bytes).ok())
+            else {
+                tracing::warn!("Skipping unreadable outbound entry {}", path.display());
+                report.dead += usize::from(self.dead_letter(name, &path, None));
+                continue;
+            };
+            if now - queued.queued_at > max_age {
+                report.dead += usize::from(self.dead_letter(name, &path, Some(&queued)));
+                continue;
+            }
+            if queued.next_attempt_at > now {
+                report.pending += 1;
+                continue;
+            }
+            match channel.send(&queued.message()).await {
+                Ok(()) => {
+                    let _ = std::fs::remove_file(&path);
+                    report.delivered += 1;
+                }
+                Err(err) => {
+                    queued.attempts += 1;
+                    queued.next_attempt_at = self.next_attempt_at(now, queued.attempts);
+                    queued.last_error = format!("{err:#}");
+                    let written = serde_json::to_vec_pretty(&queued)
+                        .map_err(anyhow::Error::from)
+                        .and_then(|bytes| atomic_write(&path, bytes).map_err(Into::into));
+                    if let Err(write_err) = written {
+                        tracing::warn!(
+                            "Failed to update outbound entry {}: {write_err:#}",
+                            path.display()
+                        );
+                    }
+                    report.pending += 1;
+                }
+            }
+        }
+        if report.delivered > 0 {
+            tracing::info!(
+                channel = name,
+                delivered = report.delivered,
+                "Delivered queued replies"
+            );
+        }
+        self.record_depth(name);
+        report
+    }
+
+    /// Move a queue file into `dead/` and raise an observer error. Returns
+    /// whether the file was moved.
+    fn dead_letter(&self, channel: &str, path: &Path, queued: Option<&QueuedMessage>) -> bool {
+        let dead_dir = self.channel_dir(channel).join(DEAD_LETTER_DIR);
+        let Some(file_name) = path.file_name() else {
+            return false;
+        };
+        if let Err(err) = std::fs::create_dir_all(&dead_dir)
+            .and_then(|()| std::fs::rename(path, dead_dir.join(file_name)))
+        {
+            tracing::warn!(
+                "Failed to dead-letter outbound entry {}: {err}",
+                path.display()
+            );
+            return false;
+        }
+        let detail = queued.map_or_else(
+            || "unreadable entry".to_string(),
+            |queued| {
+                format!(
+                    "gave up after {} attempts: {}",
+                    queued.attempts, queued.last_error
+                )
+            },
+        );
+        tracing::warn!(channel, "Queued reply moved to dead letters: {detail}");
+        if let Some(observer) = &self.observer {
+            observer.record_event(&ObserverEvent::Error {
+                component: format!("outbound.{channel}"),
+                message: format!(
+                    "Reply {} undeliverable, {detail}",
+                    file_name.to_string_lossy()
+                ),
+            });
+        }
+        true
+    }
+}
+
+/// Post `content` to the chat described by `origin`: a PocketBase thread or
+/// a registered live channel. With a workspace, a failed send is queued for
+/// retry. Returns `false` when the origin has no channel to deliver through.
+pub async fn send_to_origin(
+    origin: &ChannelExecutionContext,
+    content: String,
+    workspace_dir: Option<&Path>,
+) -> Result<bool> {
+    let channel: Arc<dyn Channel> = if origin.channel == "pocketbase" {
+        Arc::new(PocketBaseChannel::from_env_defaults()?)
+    } else if let Some(channel) = live_channel(&origin.channel) {
+        channel
+    } else {
+        return Ok(false);
+    };
+    let mut message = SendMessage::new(content, origin.recipient.clone());
+    message.thread_ts = origin.thread_ts.clone();
+    match workspace_dir {
+        Some(workspace_dir) => {
+            OutboundDispatcher::new(workspace_dir)
+                .send(channel.as_ref(), &message)
+                .await?;
+        }
+        None => channel.send(&message).await?,
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use parking_lot::Mutex;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    #[derive(Default)]
+    struct FlakyChannel {
+        down: AtomicBool,
+        sent: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl Channel for FlakyChannel {
+        fn name(&self) -> &str {
+            "flaky"
+        }
+
+        async fn send(&self, message: &SendMessage) -> Result<()> {
+            if self.down.load(Ordering::SeqCst) {
+                anyhow::bail!("service unavailable");
+            }
+            self.sent.lock().push(message.content.clone());
+            Ok(())
+        }
+
+        async fn listen(
+            &self,
+            _tx: tokio::sync::mpsc::Sender<crate::channels::traits::ChannelMessage>,
+        ) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    fn dispatcher(workspace: &Path, max_age: Duration) -> OutboundDispatcher {
+        OutboundDispatcher::new(workspace).with_retry_policy(
+            Duration::ZERO,
+            Duration::ZERO,
+            max_age,
+        )
+    }
+
+    #[tokio::test]
+    async fn failed_sends_are_queued_once_and_redelivered() {
+        let tmp = tempfile::tempdir().unwrap();
+        let outbox = dispatcher(tmp.path(), Duration::from_secs(3600));
+        let channel = FlakyChannel::default();
+        channel.down.store(true, Ordering::SeqCst);
+        let message = SendMessage::new("the expensive answer", "t1");
+
+        assert_eq!(
+            outbox.send(&channel, &message).await.unwrap(),
+            Delivery::Queued
+        );
+        assert_eq!(
+            outbox.send(&channel, &message).await.unwrap(),
+            Delivery::Queued
+        );
+        assert_eq!(outbox.depth("flaky"), 1, "the same reply is queued once");
+
+        let report = outbox.retry_due(&channel).await;
+        assert_eq!(
+            report,
+            RetryReport {
+                delivered: 0,
+                dead: 0,
+                pending: 1
+            }
+        );
+
+        // A new dispatcher picks the queue up from disk, as after a restart.
+        channel.down.store(false, Ordering::SeqCst);
+        let restarted = dispatcher(tmp.path(), Duration::from_secs(3600));
+        let report = restarted.retry_due(&channel).await;
+        assert_eq!(report.delivered, 1);
+        assert_eq!(restarted.depth("flaky"), 0);
+        assert_eq!(
+            *channel.sent.lock(),
+            vec!["the expensive answer".to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn expired_messages_move_to_dead_letters() {
+        let tmp = tempfile::tempdir().unwrap();
+        let outbox = dispatcher(tmp.path(), Duration::ZERO);
+        let channel = FlakyChannel::default();
+        channel.down.store(true, Ordering::SeqCst);
+        outbox
+            .send(&channel, &SendMessage::new("late", "t1"))
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(5)).await;
+
+        let report = outbox.retry_due(&channel).await;
+        assert_eq!(report.dead, 1);
+        assert_eq!(outbox.depth("flaky"), 0);
+        let dead = tmp
+            .path()
+            .join(OUTBOUND_DIR)
+            .join("flaky")
+            .join(DEAD_LETTER_DIR);
+        assert_eq!(std::fs::read_dir(dead).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        let outbox = OutboundDispatcher::new(Path::new("/tmp")).with_retry_policy(
+            Duration::from_secs(30),
+            Duration::from_secs(100),
+            DEFAULT_MAX_AGE,
+        );
+        assert_eq!(outbox.backoff(1), Duration::from_secs(30));
+        assert_eq!(outbox.backoff(2), Duration::from_secs(60));
+        assert_eq!(outbox.backoff(3), Duration::from_secs(100));
+    }
+}
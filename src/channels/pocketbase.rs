@@ -1,28 +1,84 @@
-use crate::channels::traits::{Channel, ChannelMessage, SendMessage};
+use crate::channels::pocketbase_auth::{token_claims, PocketBaseAuth};
+use crate::channels::pocketbase_client::{ensure_success, PocketBaseClient};
+use crate::channels::traits::{
+    AttachmentKind, AttachmentSource, Channel, ChannelAttachment, ChannelMessage, SendMessage,
+};
+use crate::observability::traits::ObserverMetric;
+use crate::observability::{Observer, ObserverEvent};
 use anyhow::{Context, Result};
 use async_trait::async_trait;
 use chrono::Utc;
 use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::Duration;
 
 const DEFAULT_CHAT_COLLECTION: &str = "chat_messages";
 const DEFAULT_POLL_MS: u64 = 1_500;
 const FETCH_PAGE_SIZE: usize = 30;
 const MAX_FETCH_PAGES: usize = 5;
+/// Longer replies are split into linked records the mobile UI renders well.
+const MAX_MESSAGE_CHARS: usize = 4_000;
+/// Total tries for a write that keeps failing transiently.
+const WRITE_ATTEMPTS: u32 = 4;
+const DEFAULT_RETRY_BASE_DELAY: Duration = Duration::from_millis(250);
+/// Workspace-relative directory for reply records PocketBase could not take.
+pub const OUTBOX_DIR: &str = "state/pb_outbox";
+/// Health component refreshed after every successful chat poll.
+pub const POLL_HEALTH_COMPONENT: &str = "channel:pocketbase";
+
+/// Why a PocketBase write failed.
+#[derive(Debug)]
+enum WriteError {
+    /// Connection failure or 5xx response; worth retrying.
+    Transient(anyhow::Error),
+    /// 4xx response; retrying would fail the same way.
+    Rejected(anyhow::Error),
+}
+
+impl WriteError {
+    fn into_inner(self) -> anyhow::Error {
+        match self {
+            Self::Transient(err) | Self::Rejected(err) => err,
+        }
+    }
+}
+
+/// Host and port of a local PocketBase from `ZEROCLAW_POCKETBASE_HOST` and
+/// `ZEROCLAW_POCKETBASE_PORT`, defaulting to `127.0.0.1:8090`.
+pub fn local_address_from_env() -> (String, u16) {
+    let host = std::env::var("ZEROCLAW_POCKETBASE_HOST")
+        .ok()
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+        .unwrap_or_else(|| "127.0.0.1".to_string());
+    let port = std::env::var("ZEROCLAW_POCKETBASE_PORT")
+        .ok()
+        .and_then(|v| v.trim().parse::<u16>().ok())
+        .unwrap_or(8090);
+    (host, port)
+}
+
+/// Exponential backoff for `attempt` (1-based) plus up to 50% random jitter.
+fn backoff_delay(base: Duration, attempt: u32) -> Duration {
+    let delay = base.saturating_mul(1 << (attempt - 1).min(16));
+    let jitter_ms = u64::try_from(delay.as_millis() / 2).unwrap_or(u64::MAX);
+    delay + Duration::from_millis(rand::random::<u64>() % (jitter_ms + 1))
+}
 
 #[derive(Clone)]
 pub struct PocketBaseChannel {
-    client: reqwest::Client,
-    base_url: String,
+    api: PocketBaseClient,
     collection: String,
-    token: Option<String>,
     poll_ms: u64,
+    retry_base_delay: Duration,
+    outbox_dir: Option<PathBuf>,
+    observer: Option<Arc<dyn Observer>>,
 }
 
 impl PocketBaseChannel {
     pub fn new(base_url: String, collection: String, token: Option<String>) -> Result<Self> {
-        let base_url = base_url.trim().trim_end_matches('/').to_string();
-        if base_url.is_empty() {
+        if base_url.trim().trim_end_matches('/').is_empty() {
             anyhow::bail!("PocketBase base URL is empty");
         }
         let collection = collection.trim().to_string();
@@ -30,63 +86,105 @@ impl PocketBaseChannel {
             anyhow::bail!("PocketBase collection is empty");
         }
         Ok(Self {
-            client: reqwest::Client::new(),
-            base_url,
+            api: PocketBaseClient::new(&base_url, token),
             collection,
-            token: token
-                .map(|v| v.trim().to_string())
-                .filter(|v| !v.is_empty()),
             poll_ms: std::env::var("ZEROCLAW_POCKETBASE_CHAT_POLL_MS")
                 .ok()
                 .and_then(|v| v.trim().parse::<u64>().ok())
                 .filter(|v| *v >= 250)
                 .unwrap_or(DEFAULT_POLL_MS),
+            retry_base_delay: DEFAULT_RETRY_BASE_DELAY,
+            outbox_dir: None,
+            observer: None,
         })
     }
 
+    /// Queue reply records that still fail after retries under
+    /// `<workspace>/state/pb_outbox/`, to be delivered by [`Self::flush_outbox`].
+    pub fn with_outbox(mut self, workspace_dir: &Path) -> Self {
+        self.outbox_dir = Some(workspace_dir.join(OUTBOX_DIR));
+        self
+    }
+
+    /// Authenticate with `auth` instead of the token given to [`Self::new`].
+    pub fn with_auth(mut self, auth: PocketBaseAuth) -> Self {
+        self.api = self.api.with_auth(auth);
+        self
+    }
+
+    /// Report write retries and outbox depth to `observer`.
+    pub fn with_observer(mut self, observer: Arc<dyn Observer>) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+
     pub fn from_env_defaults() -> Result<Self> {
         let base_url = std::env::var("ZEROCLAW_POCKETBASE_URL")
             .or_else(|_| std::env::var("POCKETBASE_URL"))
             .unwrap_or_else(|_| {
-                let host = std::env::var("ZEROCLAW_POCKETBASE_HOST")
-                    .ok()
-                    .map(|v| v.trim().to_string())
-                    .filter(|v| !v.is_empty())
-                    .unwrap_or_else(|| "127.0.0.1".to_string());
-                let port = std::env::var("ZEROCLAW_POCKETBASE_PORT")
-                    .ok()
-                    .and_then(|v| v.trim().parse::<u16>().ok())
-                    .unwrap_or(8090);
+                let (host, port) = local_address_from_env();
                 format!("http://{host}:{port}")
             });
         let collection = std::env::var("ZEROCLAW_POCKETBASE_CHAT_COLLECTION")
             .ok()
             .unwrap_or_else(|| DEFAULT_CHAT_COLLECTION.to_string());
-        let token = std::env::var("ZEROCLAW_POCKETBASE_TOKEN")
-            .ok()
-            .or_else(|| std::env::var("POCKETBASE_TOKEN").ok());
+        let auth = PocketBaseAuth::from_env(&base_url);
 
-        Self::new(base_url, collection, token)
+        Ok(Self::new(base_url, collection, None)?.with_auth(auth))
     }
 
     pub fn base_url(&self) -> &str {
-        &self.base_url
+        self.api.base_url()
     }
 
     pub fn collection(&self) -> &str {
         &self.collection
     }
 
-    async fn create_chat_record(
-        &self,
+    pub fn has_token(&self) -> bool {
+        self.api.has_credentials()
+    }
+
+    /// Status of a one-record list on the chat collection: 404 means the
+    /// collection is missing, 401/403 that the token may not read it.
+    pub async fn probe_collection(&self) -> Result<reqwest::StatusCode> {
+        let url = self.api.records_url(&self.collection);
+        let resp = self
+            .api
+            .execute(|| self.api.get(&url).query(&[("perPage", "1")]))
+            .await
+            .context("PocketBase collection probe failed")?;
+        Ok(resp.status())
+    }
+
+    /// Status of `auth-refresh` on the auth collection the token was issued
+    /// by, or `None` without credentials. Password auth logs in first.
+    pub async fn probe_token(&self) -> Result<Option<reqwest::StatusCode>> {
+        let Some(token) = self.api.bearer().await? else {
+            return Ok(None);
+        };
+        let collection_id =
+            token_collection_id(token.expose()).context("PocketBase token is not an auth token")?;
+        let url = format!("{}/auth-refresh", self.api.collection_url(&collection_id));
+        let resp = self
+            .api
+            .post(&url)
+            .bearer_auth(token.expose())
+            .send()
+            .await
+            .context("PocketBase token probe failed")?;
+        Ok(Some(resp.status()))
+    }
+
+    fn chat_record_payload(
         thread_id: &str,
         role: &str,
         content: &str,
         status: &str,
         source: &str,
         reply_to_id: Option<&str>,
-        error: Option<&str>,
-    ) -> Result<()> {
+        part_index: Option<usize>,
+    ) -> serde_json::Value {
         let now = Utc::now().to_rfc3339();
         let mut payload = serde_json::json!({
             "threadId": thread_id,
@@ -100,27 +198,156 @@ impl PocketBaseChannel {
         if let Some(reply_to_id) = reply_to_id {
             payload["replyToId"] = serde_json::Value::String(reply_to_id.to_string());
         }
-        if let Some(error_text) = error {
-            payload["error"] = serde_json::Value::String(error_text.to_string());
+        if let Some(part_index) = part_index {
+            payload["partIndex"] = serde_json::json!(part_index);
         }
-        let url = format!(
-            "{}/api/collections/{}/records",
-            self.base_url,
-            self.collection
-        );
-        let mut req = self.client.post(url).json(&payload);
-        if let Some(token) = self.token.as_deref() {
-            req = req.bearer_auth(token);
-        }
-        let resp = req.send().await.context("PocketBase channel send request failed")?;
+        payload
+    }
+
+    async fn write_once(
+        &self,
+        build: impl Fn() -> reqwest::RequestBuilder,
+        operation: &str,
+    ) -> Result<(), WriteError> {
+        let resp = match self.api.execute(build).await {
+            Ok(resp) => resp,
+            Err(err) => {
+                let transient = err
+                    .chain()
+                    .filter_map(|cause| cause.downcast_ref::<reqwest::Error>())
+                    .any(|cause| cause.is_connect() || cause.is_timeout());
+                let err = err.context(format!("PocketBase {operation} request failed"));
+                return Err(if transient {
+                    WriteError::Transient(err)
+                } else {
+                    WriteError::Rejected(err)
+                });
+            }
+        };
         let status = resp.status();
-        if !status.is_success() {
-            let body = resp.text().await.unwrap_or_default();
-            anyhow::bail!("PocketBase channel send failed ({status}): {}", body.trim());
+        match ensure_success(resp, &format!("PocketBase {operation}")).await {
+            Ok(_) => Ok(()),
+            Err(err) if status.is_server_error() => Err(WriteError::Transient(err)),
+            Err(err) => Err(WriteError::Rejected(err)),
         }
+    }
+
+    /// Run a write, retrying connection failures and 5xx responses with
+    /// exponential backoff. 4xx responses fail immediately.
+    async fn write_with_retry(
+        &self,
+        operation: &str,
+        build: impl Fn() -> reqwest::RequestBuilder,
+    ) -> Result<(), WriteError> {
+        let mut attempt = 1;
+        loop {
+            match self.write_once(&build, operation).await {
+                Err(WriteError::Transient(err)) if attempt < WRITE_ATTEMPTS => {
+                    let delay = backoff_delay(self.retry_base_delay, attempt);
+                    tracing::warn!("PocketBase {operation} attempt {attempt} failed, retrying in {delay:?}: {err:#}");
+                    if let Some(observer) = &self.observer {
+                        observer.record_event(&ObserverEvent::PocketBaseWriteRetry {
+                            operation: operation.to_string(),
+                        });
+                    }
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    async fn create_record(&self, payload: &serde_json::Value) -> Result<(), WriteError> {
+        let url = self.api.records_url(&self.collection);
+        self.write_with_retry("create", || self.api.post(&url).json(payload))
+            .await
+    }
+
+    fn outbox_files(dir: &Path) -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                    .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+                    .collect()
+            })
+            .unwrap_or_default();
+        files.sort();
+        files
+    }
+
+    fn record_outbox_depth(&self, dir: &Path) {
+        if let Some(observer) = &self.observer {
+            let depth = u64::try_from(Self::outbox_files(dir).len()).unwrap_or(u64::MAX);
+            observer.record_metric(&ObserverMetric::PocketBaseOutboxDepth(depth));
+        }
+    }
+
+    /// Persist record payloads, in order, for a later [`Self::flush_outbox`].
+    fn queue_in_outbox(&self, dir: &Path, payloads: &[serde_json::Value]) -> Result<()> {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create PocketBase outbox {}", dir.display()))?;
+        let stamp = Utc::now().timestamp_millis();
+        for (index, payload) in payloads.iter().enumerate() {
+            let name = format!(
+                "{stamp:013}-{index:03}-{}.json",
+                uuid::Uuid::new_v4().simple()
+            );
+            std::fs::write(dir.join(name), serde_json::to_vec(payload)?)
+                .context("Failed to write PocketBase outbox entry")?;
+        }
+        self.record_outbox_depth(dir);
         Ok(())
     }
 
+    /// Deliver queued reply records oldest first. Stops at the first transient
+    /// failure; entries PocketBase rejects are renamed to `*.rejected`.
+    /// Returns how many were delivered.
+    pub async fn flush_outbox(&self) -> usize {
+        let Some(dir) = self.outbox_dir.as_deref() else {
+            return 0;
+        };
+        let files = Self::outbox_files(dir);
+        if files.is_empty() {
+            return 0;
+        }
+        let mut delivered = 0;
+        for path in files {
+            let payload = std::fs::read(&path)
+                .ok()
+                .and_then(|bytes| serde_json::from_slice::<serde_json::Value>(&bytes).ok());
+            let result = match payload {
+                Some(payload) => self.create_record(&payload).await,
+                None => Err(WriteError::Rejected(anyhow::anyhow!(
+                    "unreadable outbox entry"
+                ))),
+            };
+            match result {
+                Ok(()) => {
+                    let _ = std::fs::remove_file(&path);
+                    delivered += 1;
+                }
+                Err(WriteError::Transient(err)) => {
+                    tracing::warn!("PocketBase outbox delivery paused: {err:#}");
+                    break;
+                }
+                Err(WriteError::Rejected(err)) => {
+                    tracing::warn!(
+                        "PocketBase rejected outbox entry {}: {err:#}",
+                        path.display()
+                    );
+                    let _ = std::fs::rename(&path, path.with_extension("rejected"));
+                }
+            }
+        }
+        if delivered > 0 {
+            tracing::info!(delivered, "Delivered queued PocketBase replies");
+        }
+        self.record_outbox_depth(dir);
+        delivered
+    }
+
     async fn patch_record_status(
         &self,
         record_id: &str,
@@ -128,8 +355,9 @@ impl PocketBaseChannel {
         error: Option<&str>,
     ) -> Result<()> {
         let url = format!(
-            "{}/api/collections/{}/records/{}",
-            self.base_url, self.collection, record_id
+            "{}/{}",
+            self.api.records_url(&self.collection),
+            urlencoding::encode(record_id)
         );
         let mut payload = serde_json::json!({
             "status": status_value,
@@ -140,69 +368,58 @@ impl PocketBaseChannel {
         } else {
             payload["error"] = serde_json::Value::String(String::new());
         }
-        let mut req = self.client.patch(url).json(&payload);
-        if let Some(token) = self.token.as_deref() {
-            req = req.bearer_auth(token);
-        }
-        let resp = req
-            .send()
+        self.write_with_retry("patch", || self.api.patch(&url).json(&payload))
             .await
-            .context("PocketBase channel patch request failed")?;
-        let status = resp.status();
-        if !status.is_success() {
-            let body = resp.text().await.unwrap_or_default();
-            anyhow::bail!("PocketBase channel patch failed ({status}): {}", body.trim());
-        }
-        Ok(())
+            .map_err(WriteError::into_inner)
     }
 
     async fn fetch_pending_user_messages(&self) -> Result<Vec<PocketBaseChatRecord>> {
-        let url = format!("{}/api/collections/{}/records", self.base_url, self.collection);
-        let per_page = FETCH_PAGE_SIZE.to_string();
-        let mut pending = Vec::new();
-
-        for page in 1..=MAX_FETCH_PAGES {
-            let page_str = page.to_string();
-            let mut req = self.client.get(&url).query(&[
-                ("page", page_str.as_str()),
-                ("perPage", per_page.as_str()),
-            ]);
-            if let Some(token) = self.token.as_deref() {
-                req = req.bearer_auth(token);
-            }
-
-            let resp = req
-                .send()
-                .await
-                .context("PocketBase channel poll request failed")?;
-            let status = resp.status();
-            if !status.is_success() {
-                let body = resp.text().await.unwrap_or_default();
-                anyhow::bail!(
-                    "PocketBase channel poll failed ({status}) for collection '{}': {}",
-                    self.collection,
-                    body.trim()
-                );
-            }
-            let list = resp
-                .json::<PocketBaseList<PocketBaseChatRecord>>()
-                .await
-                .context("PocketBase channel poll decode failed")?;
-            let count = list.items.len();
-            pending.extend(list.items.into_iter().filter(|r| {
+        let records = self
+            .api
+            .list_records::<PocketBaseChatRecord>(
+                &self.collection,
+                FETCH_PAGE_SIZE,
+                MAX_FETCH_PAGES,
+                &format!(
+                    "PocketBase channel poll of collection '{}'",
+                    self.collection
+                ),
+            )
+            .await?;
+        Ok(records
+            .into_iter()
+            .filter(|r| {
                 r.role
                     .as_deref()
                     .is_some_and(|role| role.eq_ignore_ascii_case("user"))
                     && r.status
                         .as_deref()
                         .is_some_and(|status| status.eq_ignore_ascii_case("pending"))
-            }));
-            if count < FETCH_PAGE_SIZE {
-                break;
-            }
-        }
+            })
+            .collect())
+    }
+}
 
-        Ok(pending)
+impl PocketBaseChannel {
+    /// Download URLs for a record's file field entries.
+    fn record_attachments(&self, record_id: &str, files: &[String]) -> Vec<ChannelAttachment> {
+        files
+            .iter()
+            .map(|name| name.trim())
+            .filter(|name| !name.is_empty())
+            .map(|name| {
+                let mime = mime_guess::from_path(name).first_raw();
+                ChannelAttachment {
+                    kind: mime.map_or(AttachmentKind::File, AttachmentKind::from_mime),
+                    source: AttachmentSource::Url(self.api.url(&format!(
+                        "/api/files/{}/{record_id}/{}",
+                        self.collection,
+                        urlencoding::encode(name)
+                    ))),
+                    mime: mime.map(str::to_string),
+                }
+            })
+            .collect()
     }
 }
 
@@ -212,21 +429,50 @@ impl Channel for PocketBaseChannel {
         "pocketbase"
     }
 
+    fn max_message_chars(&self) -> Option<usize> {
+        Some(MAX_MESSAGE_CHARS)
+    }
+
     async fn send(&self, message: &SendMessage) -> anyhow::Result<()> {
         let thread_id = message.recipient.trim();
         if thread_id.is_empty() {
             anyhow::bail!("PocketBase channel recipient (threadId) is required");
         }
-        self.create_chat_record(
-            thread_id,
-            "assistant",
-            message.content.trim(),
-            "done",
-            "slowclaw-channel",
-            message.thread_ts.as_deref(),
-            None,
-        )
-        .await
+        let parts = self.format_outbound(message.content.trim());
+        let multipart = parts.len() > 1;
+        let payloads: Vec<_> = parts
+            .iter()
+            .enumerate()
+            .map(|(index, part)| {
+                Self::chat_record_payload(
+                    thread_id,
+                    "assistant",
+                    part,
+                    "done",
+                    "slowclaw-channel",
+                    message.thread_ts.as_deref(),
+                    multipart.then_some(index + 1),
+                )
+            })
+            .collect();
+        for (index, payload) in payloads.iter().enumerate() {
+            match (
+                self.create_record(payload).await,
+                self.outbox_dir.as_deref(),
+            ) {
+                (Ok(()), _) => {}
+                (Err(WriteError::Transient(err)), Some(dir)) => {
+                    self.queue_in_outbox(dir, &payloads[index..])?;
+                    tracing::warn!(
+                        "PocketBase unavailable; queued {} reply record(s) for redelivery: {err:#}",
+                        payloads.len() - index
+                    );
+                    return Ok(());
+                }
+                (Err(err), _) => return Err(err.into_inner()),
+            }
+        }
+        Ok(())
     }
 
     async fn listen(&self, tx: tokio::sync::mpsc::Sender<ChannelMessage>) -> anyhow::Result<()> {
@@ -235,7 +481,14 @@ impl Channel for PocketBaseChannel {
 
         loop {
             interval.tick().await;
-            let records = self.fetch_pending_user_messages().await?;
+            self.flush_outbox().await;
+            let records = self
+                .fetch_pending_user_messages()
+                .await
+                .inspect_err(|err| {
+                    crate::health::mark_component_error(POLL_HEALTH_COMPONENT, format!("{err:#}"));
+                })?;
+            crate::health::mark_component_ok(POLL_HEALTH_COMPONENT);
             for record in records {
                 let thread_id = record
                     .thread_id
@@ -252,7 +505,8 @@ impl Channel for PocketBaseChannel {
                     continue;
                 }
 
-                self.patch_record_status(&record.id, "processing", None).await?;
+                self.patch_record_status(&record.id, "processing", None)
+                    .await?;
                 let msg = ChannelMessage {
                     id: record.id.clone(),
                     sender: record
@@ -265,6 +519,7 @@ impl Channel for PocketBaseChannel {
                     timestamp: Utc::now().timestamp().max(0) as u64,
                     // For PocketBase, `reply_target` is the thread; keep thread_ts aligned.
                     thread_ts: Some(thread_id),
+                    attachments: self.record_attachments(&record.id, &record.attachments),
                 };
                 tx.send(msg)
                     .await
@@ -274,21 +529,20 @@ impl Channel for PocketBaseChannel {
     }
 
     async fn health_check(&self) -> bool {
-        let url = format!("{}/api/health", self.base_url);
-        let mut req = self.client.get(url);
-        if let Some(token) = self.token.as_deref() {
-            req = req.bearer_auth(token);
-        }
-        req.send()
+        let url = self.api.url("/api/health");
+        self.api
+            .execute(|| self.api.get(&url))
             .await
             .map(|resp| resp.status().is_success())
             .unwrap_or(false)
     }
 }
 
-#[derive(Debug, Deserialize)]
-struct PocketBaseList<T> {
-    items: Vec<T>,
+/// `collectionId` claim of a PocketBase auth token (a JWT).
+fn token_collection_id(token: &str) -> Option<String> {
+    token_claims(token)?["collectionId"]
+        .as_str()
+        .map(str::to_string)
 }
 
 #[derive(Debug, Deserialize)]
@@ -301,4 +555,206 @@ struct PocketBaseChatRecord {
     status: Option<String>,
     #[serde(rename = "source")]
     sender: Option<String>,
+    /// File field; PocketBase returns the stored file names.
+    #[serde(default)]
+    attachments: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_files_become_download_attachments() {
+        let channel = PocketBaseChannel::new(
+            "http://127.0.0.1:8090/".into(),
+            "chat_messages".into(),
+            None,
+        )
+        .unwrap();
+        let attachments = channel.record_attachments(
+            "rec1",
+            &["shot one.png".to_string(), "notes.bin".to_string()],
+        );
+        assert_eq!(attachments[0].kind, AttachmentKind::Image);
+        assert_eq!(
+            attachments[0].source,
+            AttachmentSource::Url(
+                "http://127.0.0.1:8090/api/files/chat_messages/rec1/shot%20one.png".into()
+            )
+        );
+        assert_eq!(attachments[0].mime.as_deref(), Some("image/png"));
+        assert_eq!(attachments[1].kind, AttachmentKind::File);
+    }
+
+    #[tokio::test]
+    async fn long_replies_become_linked_part_records() {
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        for part in 1..=2 {
+            Mock::given(method("POST"))
+                .and(path("/api/collections/chat_messages/records"))
+                .and(body_partial_json(serde_json::json!({
+                    "threadId": "t1",
+                    "replyToId": "u1",
+                    "partIndex": part,
+                })))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({})))
+                .expect(1)
+                .mount(&server)
+                .await;
+        }
+        let channel = PocketBaseChannel::new(server.uri(), "chat_messages".into(), None).unwrap();
+        let text = format!("{}\n\n{}", "a".repeat(3_000), "b".repeat(3_000));
+        channel
+            .send(&SendMessage::new(text, "t1").in_thread(Some("u1".into())))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn token_probe_refreshes_against_the_issuing_collection() {
+        use base64::Engine as _;
+        use wiremock::matchers::{header, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let claims = base64::engine::general_purpose::URL_SAFE_NO_PAD
+            .encode(r#"{"collectionId":"pbc_3142635823","type":"auth"}"#);
+        let token = format!("eyJhbGciOiJIUzI1NiJ9.{claims}.sig");
+        assert_eq!(
+            token_collection_id(&token).as_deref(),
+            Some("pbc_3142635823")
+        );
+        assert_eq!(token_collection_id("not-a-jwt"), None);
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/collections/pbc_3142635823/auth-refresh"))
+            .and(header("authorization", format!("Bearer {token}").as_str()))
+            .respond_with(ResponseTemplate::new(401))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let channel =
+            PocketBaseChannel::new(server.uri(), "chat_messages".into(), Some(token)).unwrap();
+        assert_eq!(
+            channel.probe_token().await.unwrap(),
+            Some(reqwest::StatusCode::UNAUTHORIZED)
+        );
+
+        let anonymous = PocketBaseChannel::new(server.uri(), "chat_messages".into(), None).unwrap();
+        assert_eq!(anonymous.probe_token().await.unwrap(), None);
+    }
+
+    #[test]
+    fn polling_loops_share_one_http_client() {
+        // The gateway rebuilds the channel on every alert, heartbeat, and
+        // outbound retry tick; none of that may open a fresh connection pool.
+        for _ in 0..5 {
+            let channel = PocketBaseChannel::new(
+                "http://127.0.0.1:8090".into(),
+                "chat_messages".into(),
+                Some("tok".into()),
+            )
+            .unwrap();
+            assert!(channel.has_token());
+            let _ = crate::channels::pocketbase_schema::SchemaBootstrap::new(
+                channel.base_url(),
+                "admin",
+            );
+        }
+        assert_eq!(crate::http_client::clients_built(), 1);
+    }
+
+    fn fast_retry_channel(uri: String) -> PocketBaseChannel {
+        let mut channel = PocketBaseChannel::new(uri, "chat_messages".into(), None).unwrap();
+        channel.retry_base_delay = Duration::from_millis(1);
+        channel
+    }
+
+    #[tokio::test]
+    async fn writes_retry_transient_failures_then_succeed() {
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(2)
+            .expect(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({})))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let observer = Arc::new(crate::observability::PrometheusObserver::new());
+        let channel = fast_retry_channel(server.uri()).with_observer(observer.clone());
+
+        channel
+            .send(&SendMessage::new("hello", "t1"))
+            .await
+            .unwrap();
+        assert!(observer
+            .encode()
+            .contains("zeroclaw_pocketbase_write_retries_total{operation=\"create\"} 2"));
+    }
+
+    #[tokio::test]
+    async fn rejected_writes_fail_without_retry_or_queueing() {
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(400).set_body_string("bad field"))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let workspace = tempfile::tempdir().unwrap();
+        let channel = fast_retry_channel(server.uri()).with_outbox(workspace.path());
+
+        let err = channel
+            .send(&SendMessage::new("hello", "t1"))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("400"));
+        assert!(!workspace.path().join(OUTBOX_DIR).exists());
+    }
+
+    #[tokio::test]
+    async fn undeliverable_replies_wait_in_the_outbox() {
+        use wiremock::matchers::{body_partial_json, method};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(502))
+            .mount(&server)
+            .await;
+        let workspace = tempfile::tempdir().unwrap();
+        let channel = fast_retry_channel(server.uri()).with_outbox(workspace.path());
+
+        channel
+            .send(&SendMessage::new("the expensive answer", "t1"))
+            .await
+            .unwrap();
+        let outbox = workspace.path().join(OUTBOX_DIR);
+        assert_eq!(PocketBaseChannel::outbox_files(&outbox).len(), 1);
+
+        server.reset().await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(
+                serde_json::json!({ "content": "the expensive answer" }),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({})))
+            .expect(1)
+            .mount(&server)
+            .await;
+        assert_eq!(channel.flush_outbox().await, 1);
+        assert!(PocketBaseChannel::outbox_files(&outbox).is_empty());
+    }
 }
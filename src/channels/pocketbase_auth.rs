@@ -0,0 +1,358 @@
+//! Credentials for PocketBase requests: none, a static bearer token, or an
+//! identity and password exchanged for a token on demand. Password logins are
+//! cached per server and identity for the whole process, renewed shortly
+//! before the token expires, and redone once when PocketBase answers 401.
+
+use crate::security::SecretString;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+
+/// Admin email or service-user identity to log in as.
+pub const IDENTITY_ENV: &str = "ZEROCLAW_POCKETBASE_IDENTITY";
+/// Password for [`IDENTITY_ENV`]; never logged.
+pub const PASSWORD_ENV: &str = "ZEROCLAW_POCKETBASE_PASSWORD";
+/// Auth collection holding the identity. `admins` selects the pre-0.23
+/// `/api/admins` endpoint.
+pub const AUTH_COLLECTION_ENV: &str = "ZEROCLAW_POCKETBASE_AUTH_COLLECTION";
+const DEFAULT_AUTH_COLLECTION: &str = "_superusers";
+const LEGACY_ADMINS: &str = "admins";
+/// Log in again once the cached token is this close to its `exp` claim.
+const REFRESH_MARGIN_SECS: i64 = 60;
+
+#[derive(Clone, Debug, Default)]
+pub enum PocketBaseAuth {
+    #[default]
+    Anonymous,
+    Token(SecretString),
+    Password(Arc<PasswordLogin>),
+}
+
+#[derive(Debug)]
+pub struct PasswordLogin {
+    base_url: String,
+    collection: String,
+    identity: String,
+    password: SecretString,
+    cached: tokio::sync::Mutex<Option<CachedToken>>,
+}
+
+#[derive(Debug)]
+struct CachedToken {
+    token: SecretString,
+    expires_at: Option<DateTime<Utc>>,
+}
+
+impl CachedToken {
+    fn usable_at(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.map_or(true, |exp| {
+            now + chrono::Duration::seconds(REFRESH_MARGIN_SECS) < exp
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct AuthResponse {
+    token: String,
+}
+
+type LoginKey = (String, String, String);
+
+fn logins() -> &'static Mutex<HashMap<LoginKey, Arc<PasswordLogin>>> {
+    static LOGINS: OnceLock<Mutex<HashMap<LoginKey, Arc<PasswordLogin>>>> = OnceLock::new();
+    LOGINS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn env_value(name: &str) -> Option<String> {
+    std::env::var(name)
+        .ok()
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+impl PocketBaseAuth {
+    /// A static bearer token, or anonymous when `token` is empty.
+    pub fn token(token: Option<String>) -> Self {
+        token
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+            .map_or(Self::Anonymous, |v| Self::Token(SecretString::new(v)))
+    }
+
+    /// Log in to `collection` on `base_url` as `identity`. Every client of the
+    /// same server and identity shares one login, so the token is fetched once
+    /// per process rather than once per client.
+    pub fn password(
+        base_url: &str,
+        collection: &str,
+        identity: &str,
+        password: SecretString,
+    ) -> Self {
+        let key = (
+            base_url.trim().trim_end_matches('/').to_string(),
+            collection.trim().to_string(),
+            identity.trim().to_string(),
+        );
+        let mut logins = logins().lock();
+        if let Some(login) = logins.get(&key).filter(|login| login.password == password) {
+            return Self::Password(Arc::clone(login));
+        }
+        let login = Arc::new(PasswordLogin {
+            base_url: key.0.clone(),
+            collection: key.1.clone(),
+            identity: key.2.clone(),
+            password,
+            cached: tokio::sync::Mutex::new(None),
+        });
+        logins.insert(key, Arc::clone(&login));
+        Self::Password(login)
+    }
+
+    /// Password login from [`IDENTITY_ENV`] and [`PASSWORD_ENV`] when both are
+    /// set, else the `ZEROCLAW_POCKETBASE_TOKEN` bearer token, else anonymous.
+    pub fn from_env(base_url: &str) -> Self {
+        if let Some(login) = Self::password_from_env(base_url) {
+            return login;
+        }
+        Self::token(
+            std::env::var("ZEROCLAW_POCKETBASE_TOKEN")
+                .ok()
+                .or_else(|| std::env::var("POCKETBASE_TOKEN").ok()),
+        )
+    }
+
+    /// Password login from the environment, but only for a superuser
+    /// collection, as the collections admin API requires.
+    pub fn superuser_from_env(base_url: &str) -> Option<Self> {
+        let collection =
+            env_value(AUTH_COLLECTION_ENV).unwrap_or_else(|| DEFAULT_AUTH_COLLECTION.into());
+        if collection != DEFAULT_AUTH_COLLECTION && collection != LEGACY_ADMINS {
+            return None;
+        }
+        Self::password_from_env(base_url)
+    }
+
+    fn password_from_env(base_url: &str) -> Option<Self> {
+        let identity = env_value(IDENTITY_ENV)?;
+        let password = std::env::var(PASSWORD_ENV).ok().filter(|v| !v.is_empty())?;
+        let collection =
+            env_value(AUTH_COLLECTION_ENV).unwrap_or_else(|| DEFAULT_AUTH_COLLECTION.into());
+        Some(Self::password(
+            base_url,
+            &collection,
+            &identity,
+            SecretString::new(password),
+        ))
+    }
+
+    pub fn is_anonymous(&self) -> bool {
+        matches!(self, Self::Anonymous)
+    }
+
+    /// Whether a 401 is worth answering with a fresh login.
+    pub(crate) fn renews(&self) -> bool {
+        matches!(self, Self::Password(_))
+    }
+
+    /// Token to send as `Authorization: Bearer`, logging in first when the
+    /// cached one is missing or about to expire.
+    pub async fn bearer(&self) -> Result<Option<SecretString>> {
+        match self {
+            Self::Anonymous => Ok(None),
+            Self::Token(token) => Ok(Some(token.clone())),
+            Self::Password(login) => login.token().await.map(Some),
+        }
+    }
+
+    /// Forget `rejected` so the next [`Self::bearer`] logs in again. A token
+    /// another request already replaced is left alone.
+    pub(crate) async fn invalidate(&self, rejected: &SecretString) {
+        if let Self::Password(login) = self {
+            let mut cached = login.cached.lock().await;
+            if cached.as_ref().is_some_and(|c| c.token == *rejected) {
+                *cached = None;
+            }
+        }
+    }
+}
+
+impl PasswordLogin {
+    async fn token(&self) -> Result<SecretString> {
+        let mut cached = self.cached.lock().await;
+        if let Some(current) = cached.as_ref().filter(|c| c.usable_at(Utc::now())) {
+            return Ok(current.token.clone());
+        }
+        let fresh = self.login().await?;
+        let token = fresh.token.clone();
+        *cached = Some(fresh);
+        Ok(token)
+    }
+
+    async fn login(&self) -> Result<CachedToken> {
+        let url = if self.collection == LEGACY_ADMINS {
+            format!("{}/api/admins/auth-with-password", self.base_url)
+        } else {
+            format!(
+                "{}/api/collections/{}/auth-with-password",
+                self.base_url,
+                urlencoding::encode(&self.collection)
+            )
+        };
+        let what = format!("PocketBase login as {}", self.identity);
+        let resp = crate::http_client::shared()
+            .post(url)
+            .json(&serde_json::json!({
+                "identity": self.identity,
+                "password": self.password.expose(),
+            }))
+            .send()
+            .await
+            .with_context(|| format!("{what} request failed"))?;
+        let token = super::pocketbase_client::ensure_success(resp, &what)
+            .await?
+            .json::<AuthResponse>()
+            .await
+            .with_context(|| format!("{what} decode failed"))?
+            .token;
+        let expires_at = token_claims(&token)
+            .and_then(|claims| claims["exp"].as_i64())
+            .and_then(|exp| DateTime::from_timestamp(exp, 0));
+        tracing::info!(identity = %self.identity, ?expires_at, "Logged in to PocketBase");
+        Ok(CachedToken {
+            token: SecretString::new(token),
+            expires_at,
+        })
+    }
+}
+
+/// Decoded claims of a PocketBase auth token (a JWT).
+pub fn token_claims(token: &str) -> Option<serde_json::Value> {
+    use base64::Engine as _;
+
+    let payload = token.split('.').nth(1)?;
+    let claims = base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .decode(payload.trim_end_matches('='))
+        .ok()?;
+    serde_json::from_slice(&claims).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::channels::pocketbase_client::PocketBaseClient;
+    use wiremock::matchers::{body_partial_json, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn jwt(expires_in_secs: i64) -> String {
+        use base64::Engine as _;
+
+        let exp = Utc::now().timestamp() + expires_in_secs;
+        let claims = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(format!(
+            r#"{{"exp":{exp},"nonce":"{}"}}"#,
+            uuid::Uuid::new_v4()
+        ));
+        format!("eyJhbGciOiJIUzI1NiJ9.{claims}.sig")
+    }
+
+    /// Logins are shared per server and identity, and wiremock hands out
+    /// pooled servers, so each test logs in as its own `identity`.
+    async fn mount_login(server: &MockServer, identity: &str, token: &str, expected: u64) {
+        Mock::given(method("POST"))
+            .and(path("/api/collections/_superusers/auth-with-password"))
+            .and(body_partial_json(
+                serde_json::json!({ "identity": identity, "password": "hunter2" }),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "token": token,
+                "record": { "id": "su1" },
+            })))
+            .up_to_n_times(expected)
+            .expect(expected)
+            .mount(server)
+            .await;
+    }
+
+    fn client(server: &MockServer, identity: &str) -> PocketBaseClient {
+        PocketBaseClient::new(&server.uri(), None).with_auth(PocketBaseAuth::password(
+            &server.uri(),
+            "_superusers",
+            identity,
+            SecretString::new("hunter2"),
+        ))
+    }
+
+    #[tokio::test]
+    async fn password_login_is_cached_across_clients() {
+        let server = MockServer::start().await;
+        let token = jwt(3_600);
+        mount_login(&server, "cached@example.com", &token, 1).await;
+        Mock::given(method("GET"))
+            .and(path("/api/health"))
+            .and(header("authorization", format!("Bearer {token}").as_str()))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        for _ in 0..2 {
+            let api = client(&server, "cached@example.com");
+            api.send(|| api.get(&api.url("/api/health")), "health check")
+                .await
+                .unwrap();
+        }
+        let auth = format!("{:?}", client(&server, "cached@example.com"));
+        assert!(!auth.contains("hunter2"), "{auth}");
+    }
+
+    #[tokio::test]
+    async fn expired_token_is_replaced_after_one_401() {
+        let server = MockServer::start().await;
+        let (stale, fresh) = (jwt(3_600), jwt(3_600));
+        mount_login(&server, "expired@example.com", &stale, 1).await;
+        mount_login(&server, "expired@example.com", &fresh, 1).await;
+        Mock::given(method("GET"))
+            .and(header("authorization", format!("Bearer {stale}").as_str()))
+            .respond_with(ResponseTemplate::new(401))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(header("authorization", format!("Bearer {fresh}").as_str()))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let api = client(&server, "expired@example.com");
+        api.send(|| api.get(&api.url("/api/health")), "health check")
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn token_close_to_expiry_is_renewed_before_use() {
+        let server = MockServer::start().await;
+        mount_login(
+            &server,
+            "renewed@example.com",
+            &jwt(REFRESH_MARGIN_SECS / 2),
+            2,
+        )
+        .await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let api = client(&server, "renewed@example.com");
+        for _ in 0..2 {
+            api.send(|| api.get(&api.url("/api/health")), "health check")
+                .await
+                .unwrap();
+        }
+    }
+}
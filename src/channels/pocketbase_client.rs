@@ -0,0 +1,242 @@
+//! Thin PocketBase REST client shared by the chat channel and the schema
+//! bootstrap: base-URL joining, bearer auth, non-2xx error reporting, and
+//! record-list pagination, on top of the process-wide HTTP client.
+
+use super::pocketbase_auth::PocketBaseAuth;
+use crate::security::SecretString;
+use anyhow::{Context, Result};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::time::Duration;
+
+#[derive(Debug, Deserialize)]
+struct RecordList<T> {
+    items: Vec<T>,
+}
+
+#[derive(Clone, Debug)]
+pub struct PocketBaseClient {
+    http: reqwest::Client,
+    base_url: String,
+    auth: PocketBaseAuth,
+    timeout: Option<Duration>,
+}
+
+impl PocketBaseClient {
+    /// Client for the PocketBase server at `base_url`; a non-empty `token` is
+    /// sent as a bearer token on every request.
+    pub fn new(base_url: &str, token: Option<String>) -> Self {
+        Self {
+            http: crate::http_client::shared(),
+            base_url: base_url.trim().trim_end_matches('/').to_string(),
+            auth: PocketBaseAuth::token(token),
+            timeout: None,
+        }
+    }
+
+    /// Authenticate with `auth` instead of the token given to [`Self::new`].
+    pub fn with_auth(mut self, auth: PocketBaseAuth) -> Self {
+        self.auth = auth;
+        self
+    }
+
+    /// Bound every request by `timeout` instead of the shared client default.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    pub fn has_credentials(&self) -> bool {
+        !self.auth.is_anonymous()
+    }
+
+    /// Current bearer token, logging in first for password auth.
+    pub async fn bearer(&self) -> Result<Option<SecretString>> {
+        self.auth.bearer().await
+    }
+
+    /// Absolute URL for an API path such as `/api/health`.
+    pub fn url(&self, path: &str) -> String {
+        format!("{}/{}", self.base_url, path.trim_start_matches('/'))
+    }
+
+    pub fn collection_url(&self, collection: &str) -> String {
+        self.url(&format!(
+            "/api/collections/{}",
+            urlencoding::encode(collection)
+        ))
+    }
+
+    pub fn records_url(&self, collection: &str) -> String {
+        format!("{}/records", self.collection_url(collection))
+    }
+
+    /// Request to an absolute `url`. Credentials are added by
+    /// [`Self::execute`], which may have to build the request twice.
+    pub fn request(&self, method: reqwest::Method, url: &str) -> reqwest::RequestBuilder {
+        let req = self.http.request(method, url);
+        match self.timeout {
+            Some(timeout) => req.timeout(timeout),
+            None => req,
+        }
+    }
+
+    pub fn get(&self, url: &str) -> reqwest::RequestBuilder {
+        self.request(reqwest::Method::GET, url)
+    }
+
+    pub fn post(&self, url: &str) -> reqwest::RequestBuilder {
+        self.request(reqwest::Method::POST, url)
+    }
+
+    pub fn patch(&self, url: &str) -> reqwest::RequestBuilder {
+        self.request(reqwest::Method::PATCH, url)
+    }
+
+    /// Send the request `build` makes with the current credentials. With
+    /// password auth a 401 logs in again and sends it once more.
+    pub async fn execute(
+        &self,
+        build: impl Fn() -> reqwest::RequestBuilder,
+    ) -> Result<reqwest::Response> {
+        let bearer = self.auth.bearer().await?;
+        let resp = authorized(build(), bearer.as_ref()).send().await?;
+        let Some(rejected) = bearer
+            .filter(|_| resp.status() == reqwest::StatusCode::UNAUTHORIZED && self.auth.renews())
+        else {
+            return Ok(resp);
+        };
+        self.auth.invalidate(&rejected).await;
+        let bearer = self.auth.bearer().await?;
+        Ok(authorized(build(), bearer.as_ref()).send().await?)
+    }
+
+    /// Like [`Self::execute`], but fails on a non-2xx response; `what` names
+    /// the operation in errors.
+    pub async fn send(
+        &self,
+        build: impl Fn() -> reqwest::RequestBuilder,
+        what: &str,
+    ) -> Result<reqwest::Response> {
+        let resp = self
+            .execute(build)
+            .await
+            .with_context(|| format!("{what} request failed"))?;
+        ensure_success(resp, what).await
+    }
+
+    /// Every record of `collection`, `page_size` at a time, stopping at the
+    /// first short page or after `max_pages`.
+    pub async fn list_records<T: DeserializeOwned>(
+        &self,
+        collection: &str,
+        page_size: usize,
+        max_pages: usize,
+        what: &str,
+    ) -> Result<Vec<T>> {
+        let url = self.records_url(collection);
+        let per_page = page_size.to_string();
+        let mut records = Vec::new();
+        for page in 1..=max_pages {
+            let page = page.to_string();
+            let list = self
+                .send(
+                    || {
+                        self.get(&url)
+                            .query(&[("page", page.as_str()), ("perPage", per_page.as_str())])
+                    },
+                    what,
+                )
+                .await?
+                .json::<RecordList<T>>()
+                .await
+                .with_context(|| format!("{what} decode failed"))?;
+            let count = list.items.len();
+            records.extend(list.items);
+            if count < page_size {
+                break;
+            }
+        }
+        Ok(records)
+    }
+}
+
+fn authorized(
+    req: reqwest::RequestBuilder,
+    bearer: Option<&SecretString>,
+) -> reqwest::RequestBuilder {
+    match bearer {
+        Some(token) => req.bearer_auth(token.expose()),
+        None => req,
+    }
+}
+
+/// `resp` unchanged when it succeeded, else an error carrying the status and
+/// the trimmed response body.
+pub async fn ensure_success(resp: reqwest::Response, what: &str) -> Result<reqwest::Response> {
+    let status = resp.status();
+    if status.is_success() {
+        return Ok(resp);
+    }
+    let body = resp.text().await.unwrap_or_default();
+    anyhow::bail!("{what} failed ({status}): {}", body.trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{header, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn lists_pages_with_bearer_auth_until_a_short_page() {
+        let server = MockServer::start().await;
+        for (page, ids) in [("1", vec!["a", "b"]), ("2", vec!["c"])] {
+            let items: Vec<_> = ids
+                .iter()
+                .map(|id| serde_json::json!({ "id": id }))
+                .collect();
+            Mock::given(method("GET"))
+                .and(path("/api/collections/chat%20log/records"))
+                .and(query_param("page", page))
+                .and(header("authorization", "Bearer tok"))
+                .respond_with(
+                    ResponseTemplate::new(200).set_body_json(serde_json::json!({ "items": items })),
+                )
+                .expect(1)
+                .mount(&server)
+                .await;
+        }
+        let client = PocketBaseClient::new(&format!("{}/", server.uri()), Some(" tok ".into()));
+
+        let records: Vec<serde_json::Value> = client
+            .list_records("chat log", 2, 5, "listing")
+            .await
+            .unwrap();
+        let ids: Vec<_> = records.iter().map(|r| r["id"].as_str().unwrap()).collect();
+        assert_eq!(ids, ["a", "b", "c"]);
+    }
+
+    #[tokio::test]
+    async fn failures_carry_status_and_trimmed_body() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(403).set_body_string("  no access\n"))
+            .mount(&server)
+            .await;
+        let client = PocketBaseClient::new(&server.uri(), None);
+
+        let err = client
+            .send(|| client.get(&client.url("/api/health")), "health check")
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "health check failed (403 Forbidden): no access"
+        );
+    }
+}
@@ -0,0 +1,567 @@
+//! Expected PocketBase collections and the admin-API bootstrap that creates
+//! or repairs them. Missing fields are added to live collections, so adding
+//! one here is enough for existing installs to pick it up on the next
+//! bootstrap.
+
+use crate::channels::pocketbase_auth::{self, PocketBaseAuth};
+use crate::channels::pocketbase_client::{ensure_success, PocketBaseClient};
+use parking_lot::Mutex;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// Superuser token used for the collections admin API.
+pub const ADMIN_TOKEN_ENV: &str = "ZEROCLAW_POCKETBASE_ADMIN_TOKEN";
+/// Access rule for collections when the gateway signs in to PocketBase.
+const AUTHENTICATED_RULE: &str = "@request.auth.id != \"\"";
+/// Access rule when the gateway talks to PocketBase anonymously, matching
+/// `pocketbase/collections.example.json`.
+const PUBLIC_RULE: &str = "";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+const HEALTH_COMPONENT: &str = "pocketbase_schema";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldKind {
+    Text,
+    Number,
+    File,
+    Autodate,
+}
+
+impl FieldKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Text => "text",
+            Self::Number => "number",
+            Self::File => "file",
+            Self::Autodate => "autodate",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct FieldSpec {
+    pub name: &'static str,
+    pub kind: FieldKind,
+    pub required: bool,
+}
+
+impl FieldSpec {
+    const fn new(name: &'static str, kind: FieldKind) -> Self {
+        Self {
+            name,
+            kind,
+            required: false,
+        }
+    }
+
+    const fn required(mut self) -> Self {
+        self.required = true;
+        self
+    }
+
+    /// Field definition in the shape the collections API accepts.
+    fn to_json(self) -> Value {
+        let mut field = json!({
+            "name": self.name,
+            "type": self.kind.as_str(),
+            "required": self.required,
+        });
+        match self.kind {
+            FieldKind::File => {
+                field["maxSelect"] = json!(10);
+                field["maxSize"] = json!(25 * 1024 * 1024);
+            }
+            FieldKind::Autodate => {
+                field["onCreate"] = json!(true);
+                field["onUpdate"] = json!(false);
+            }
+            FieldKind::Text | FieldKind::Number => {}
+        }
+        field
+    }
+}
+
+const CHAT_MESSAGE_FIELDS: &[FieldSpec] = &[
+    FieldSpec::new("threadId", FieldKind::Text).required(),
+    FieldSpec::new("role", FieldKind::Text).required(),
+    FieldSpec::new("content", FieldKind::Text),
+    FieldSpec::new("status", FieldKind::Text),
+    FieldSpec::new("source", FieldKind::Text),
+    FieldSpec::new("error", FieldKind::Text),
+    FieldSpec::new("replyToId", FieldKind::Text),
+    FieldSpec::new("partIndex", FieldKind::Number),
+    FieldSpec::new("createdAtClient", FieldKind::Text),
+    FieldSpec::new("processedAt", FieldKind::Text),
+    FieldSpec::new("attachments", FieldKind::File),
+    FieldSpec::new("created", FieldKind::Autodate),
+];
+
+#[derive(Debug, Clone)]
+pub struct CollectionSpec {
+    pub name: String,
+    pub fields: &'static [FieldSpec],
+    /// Column lists, one index each.
+    pub indexes: &'static [&'static [&'static str]],
+    /// List, view, create, and update rule for new collections.
+    pub rule: &'static str,
+}
+
+impl CollectionSpec {
+    fn index_sql(&self) -> Vec<String> {
+        self.indexes
+            .iter()
+            .map(|columns| {
+                format!(
+                    "CREATE INDEX `idx_{}_{}` ON `{}` ({})",
+                    self.name,
+                    columns.join("_"),
+                    self.name,
+                    columns
+                        .iter()
+                        .map(|column| format!("`{column}`"))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            })
+            .collect()
+    }
+
+    fn create_payload(&self) -> Value {
+        json!({
+            "name": self.name,
+            "type": "base",
+            "fields": self.fields.iter().map(|field| field.to_json()).collect::<Vec<_>>(),
+            "indexes": self.index_sql(),
+            "listRule": self.rule,
+            "viewRule": self.rule,
+            "createRule": self.rule,
+            "updateRule": self.rule,
+            "deleteRule": null,
+        })
+    }
+}
+
+/// Collections the gateway talks to. Journal entries and media assets live
+/// in the local store, so only chat goes through PocketBase. New collections
+/// require sign-in only when the gateway itself signs in (`authenticated`);
+/// otherwise an authenticated rule would lock the gateway out.
+pub fn expected_collections(chat_collection: &str, authenticated: bool) -> Vec<CollectionSpec> {
+    vec![CollectionSpec {
+        name: chat_collection.to_string(),
+        fields: CHAT_MESSAGE_FIELDS,
+        indexes: &[&["threadId", "created"], &["status"]],
+        rule: if authenticated {
+            AUTHENTICATED_RULE
+        } else {
+            PUBLIC_RULE
+        },
+    }]
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SchemaStatus {
+    /// No admin token configured; nothing was checked.
+    Skipped,
+    /// Every collection matches, possibly after creating or extending it.
+    Ok,
+    /// Collections exist but some fields disagree and need manual repair.
+    Drift,
+    /// PocketBase could not be reached or refused the admin token.
+    Error,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SchemaReport {
+    pub status: SchemaStatus,
+    pub created: Vec<String>,
+    pub added_fields: Vec<String>,
+    pub problems: Vec<String>,
+    pub checked_at: String,
+}
+
+impl SchemaReport {
+    fn new() -> Self {
+        Self {
+            status: SchemaStatus::Ok,
+            created: Vec::new(),
+            added_fields: Vec::new(),
+            problems: Vec::new(),
+            checked_at: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+
+    fn failed(problem: String) -> Self {
+        let mut report = Self::new();
+        report.status = SchemaStatus::Error;
+        report.problems.push(problem);
+        report
+    }
+}
+
+static LAST_REPORT: OnceLock<Mutex<Option<SchemaReport>>> = OnceLock::new();
+
+fn last_report_slot() -> &'static Mutex<Option<SchemaReport>> {
+    LAST_REPORT.get_or_init(|| Mutex::new(None))
+}
+
+/// Most recent bootstrap result, for `/health`.
+pub fn last_report() -> Option<SchemaReport> {
+    last_report_slot().lock().clone()
+}
+
+fn record_report(report: &SchemaReport) {
+    *last_report_slot().lock() = Some(report.clone());
+    match report.status {
+        SchemaStatus::Ok => crate::health::mark_component_ok(HEALTH_COMPONENT),
+        SchemaStatus::Drift | SchemaStatus::Error => {
+            crate::health::mark_component_error(HEALTH_COMPONENT, report.problems.join("; "));
+        }
+        SchemaStatus::Skipped => {}
+    }
+}
+
+/// Fields a live collection definition lists, as `(name, type)` pairs.
+/// PocketBase 0.23+ calls the list `fields`; older releases call it `schema`.
+fn live_fields(collection: &Value) -> Vec<(String, String)> {
+    collection
+        .get("fields")
+        .or_else(|| collection.get("schema"))
+        .and_then(Value::as_array)
+        .map(|fields| {
+            fields
+                .iter()
+                .filter_map(|field| {
+                    Some((
+                        field.get("name")?.as_str()?.to_string(),
+                        field.get("type")?.as_str()?.to_string(),
+                    ))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Compare a live collection against its spec. Returns the missing fields
+/// and a description of each type mismatch.
+fn diff_collection(spec: &CollectionSpec, live: &Value) -> (Vec<FieldSpec>, Vec<String>) {
+    let fields = live_fields(live);
+    let mut missing = Vec::new();
+    let mut mismatched = Vec::new();
+    for expected in spec.fields {
+        match fields.iter().find(|(name, _)| name == expected.name) {
+            None => missing.push(*expected),
+            Some((_, kind)) if kind != expected.kind.as_str() => mismatched.push(format!(
+                "{} field {} is {kind}, expected {}",
+                spec.name,
+                expected.name,
+                expected.kind.as_str()
+            )),
+            Some(_) => {}
+        }
+    }
+    (missing, mismatched)
+}
+
+/// Creates missing collections and fields through the PocketBase admin API.
+pub struct SchemaBootstrap {
+    api: PocketBaseClient,
+}
+
+impl SchemaBootstrap {
+    pub fn new(base_url: &str, admin_token: &str) -> Self {
+        Self {
+            api: PocketBaseClient::new(base_url, Some(admin_token.to_string()))
+                .with_timeout(REQUEST_TIMEOUT),
+        }
+    }
+
+    /// Bootstrap configured from the environment, or `None` (with a warning)
+    /// when neither an admin token nor superuser credentials are set.
+    pub fn from_env(base_url: &str) -> Option<Self> {
+        let auth = std::env::var(ADMIN_TOKEN_ENV)
+            .ok()
+            .map(|token| PocketBaseAuth::token(Some(token)))
+            .filter(|auth| !auth.is_anonymous())
+            .or_else(|| PocketBaseAuth::superuser_from_env(base_url));
+        if auth.is_none() {
+            tracing::warn!(
+                "PocketBase schema bootstrap skipped: set {ADMIN_TOKEN_ENV} to a superuser token, or {} and {} to superuser credentials, to create and validate collections",
+                pocketbase_auth::IDENTITY_ENV,
+                pocketbase_auth::PASSWORD_ENV
+            );
+        }
+        auth.map(|auth| Self {
+            api: PocketBaseClient::new(base_url, None)
+                .with_auth(auth)
+                .with_timeout(REQUEST_TIMEOUT),
+        })
+    }
+
+    async fn fetch_collection(&self, name: &str) -> anyhow::Result<Option<Value>> {
+        let url = self.api.collection_url(name);
+        let resp = self.api.execute(|| self.api.get(&url)).await?;
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let resp = ensure_success(resp, &format!("fetching collection {name}")).await?;
+        Ok(Some(resp.json().await?))
+    }
+
+    async fn ensure_collection(&self, spec: &CollectionSpec, report: &mut SchemaReport) {
+        let live = match self.fetch_collection(&spec.name).await {
+            Ok(live) => live,
+            Err(err) => {
+                report.status = SchemaStatus::Error;
+                report.problems.push(format!("{err:#}"));
+                return;
+            }
+        };
+        let Some(live) = live else {
+            let url = self.api.url("/api/collections");
+            let payload = spec.create_payload();
+            match self
+                .api
+                .send(
+                    || self.api.post(&url).json(&payload),
+                    &format!("creating collection {}", spec.name),
+                )
+                .await
+            {
+                Ok(_) => report.created.push(spec.name.clone()),
+                Err(err) => {
+                    report.status = SchemaStatus::Error;
+                    report.problems.push(format!("{err:#}"));
+                }
+            }
+            return;
+        };
+
+        let (missing, mismatched) = diff_collection(spec, &live);
+        if !mismatched.is_empty() && report.status == SchemaStatus::Ok {
+            report.status = SchemaStatus::Drift;
+        }
+        report.problems.extend(mismatched);
+        if missing.is_empty() {
+            return;
+        }
+
+        // The API replaces the whole field list, so send the live fields back
+        // untouched with the missing ones appended.
+        let key = if live.get("fields").is_some() {
+            "fields"
+        } else {
+            "schema"
+        };
+        let mut fields = live
+            .get(key)
+            .and_then(Value::as_array)
+            .cloned()
+            .unwrap_or_default();
+        fields.extend(missing.iter().map(|field| field.to_json()));
+        let url = self.api.collection_url(&spec.name);
+        let payload = json!({ key: fields });
+        match self
+            .api
+            .send(
+                || self.api.patch(&url).json(&payload),
+                &format!("extending collection {}", spec.name),
+            )
+            .await
+        {
+            Ok(_) => report.added_fields.extend(
+                missing
+                    .iter()
+                    .map(|field| format!("{}.{}", spec.name, field.name)),
+            ),
+            Err(err) => {
+                report.status = SchemaStatus::Error;
+                report.problems.extend(
+                    missing
+                        .iter()
+                        .map(|field| format!("{} missing field {}", spec.name, field.name)),
+                );
+                report.problems.push(format!("{err:#}"));
+            }
+        }
+    }
+
+    /// Create or extend every expected collection and report what changed.
+    pub async fn run(&self, specs: &[CollectionSpec]) -> SchemaReport {
+        let mut report = SchemaReport::new();
+        for spec in specs {
+            self.ensure_collection(spec, &mut report).await;
+        }
+        report
+    }
+}
+
+/// Bootstrap the collections behind the PocketBase chat channel configured in
+/// the environment. A no-op report with status `skipped` when no admin token
+/// is configured.
+pub async fn bootstrap_from_env() -> SchemaReport {
+    let report = match crate::channels::PocketBaseChannel::from_env_defaults() {
+        Err(err) => SchemaReport::failed(format!("{err:#}")),
+        Ok(channel) => match SchemaBootstrap::from_env(channel.base_url()) {
+            None => {
+                let mut report = SchemaReport::new();
+                report.status = SchemaStatus::Skipped;
+                report
+            }
+            Some(bootstrap) => {
+                bootstrap
+                    .run(&expected_collections(
+                        channel.collection(),
+                        channel.has_token(),
+                    ))
+                    .await
+            }
+        },
+    };
+    for name in &report.created {
+        tracing::info!("Created PocketBase collection {name}");
+    }
+    for field in &report.added_fields {
+        tracing::info!("Added PocketBase field {field}");
+    }
+    for problem in &report.problems {
+        tracing::warn!("PocketBase schema: {problem}");
+    }
+    record_report(&report);
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_partial_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn chat_spec() -> Vec<CollectionSpec> {
+        expected_collections("chat_messages", true)
+    }
+
+    #[tokio::test]
+    async fn missing_collection_is_created_with_fields_and_indexes() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/collections/chat_messages"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/collections"))
+            .and(body_partial_json(json!({
+                "name": "chat_messages",
+                "listRule": AUTHENTICATED_RULE,
+                "indexes": ["CREATE INDEX `idx_chat_messages_threadId_created` ON `chat_messages` (`threadId`, `created`)", "CREATE INDEX `idx_chat_messages_status` ON `chat_messages` (`status`)"],
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({})))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let report = SchemaBootstrap::new(&server.uri(), "admin")
+            .run(&chat_spec())
+            .await;
+        assert_eq!(report.status, SchemaStatus::Ok);
+        assert_eq!(report.created, vec!["chat_messages".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn anonymous_gateway_gets_public_rules() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/collections/chat_messages"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/collections"))
+            .and(body_partial_json(json!({
+                "listRule": "",
+                "createRule": "",
+                "deleteRule": null,
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({})))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let report = SchemaBootstrap::new(&server.uri(), "admin")
+            .run(&expected_collections("chat_messages", false))
+            .await;
+        assert_eq!(report.status, SchemaStatus::Ok);
+    }
+
+    #[tokio::test]
+    async fn existing_collection_gains_missing_fields_and_reports_type_drift() {
+        let server = MockServer::start().await;
+        let live_fields: Vec<Value> = CHAT_MESSAGE_FIELDS
+            .iter()
+            .filter(|field| field.name != "replyToId")
+            .map(|field| {
+                let kind = if field.name == "partIndex" {
+                    "text"
+                } else {
+                    field.kind.as_str()
+                };
+                json!({ "id": format!("f_{}", field.name), "name": field.name, "type": kind })
+            })
+            .collect();
+        Mock::given(method("GET"))
+            .and(path("/api/collections/chat_messages"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({ "name": "chat_messages", "fields": live_fields })),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("PATCH"))
+            .and(path("/api/collections/chat_messages"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({})))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let report = SchemaBootstrap::new(&server.uri(), "admin")
+            .run(&chat_spec())
+            .await;
+        assert_eq!(report.status, SchemaStatus::Drift);
+        assert_eq!(
+            report.added_fields,
+            vec!["chat_messages.replyToId".to_string()]
+        );
+        assert_eq!(
+            report.problems,
+            vec!["chat_messages field partIndex is text, expected number".to_string()]
+        );
+
+        let requests = server.received_requests().await.unwrap();
+        let patch: Value = requests
+            .iter()
+            .find(|req| req.method.as_str() == "PATCH")
+            .map(|req| serde_json::from_slice(&req.body).unwrap())
+            .unwrap();
+        let fields = patch["fields"].as_array().unwrap();
+        assert_eq!(fields.len(), CHAT_MESSAGE_FIELDS.len());
+        assert_eq!(fields[0]["id"], "f_threadId");
+    }
+
+    #[tokio::test]
+    async fn unauthorized_admin_token_is_an_error() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(401).set_body_string("unauthorized"))
+            .mount(&server)
+            .await;
+
+        let report = SchemaBootstrap::new(&server.uri(), "wrong")
+            .run(&chat_spec())
+            .await;
+        assert_eq!(report.status, SchemaStatus::Error);
+        assert!(report.problems[0].contains("401"));
+    }
+}
@@ -0,0 +1,564 @@
+//! Telegram bot channel.
+//!
+//! Updates arrive either by long-polling `getUpdates` (see [`Channel::listen`],
+//! run by `start_channels`) or, when `webhook_secret` is configured, on the
+//! gateway's `POST /telegram` route. Replies are sent with `sendMessage` as
+//! MarkdownV2, escaped and split to Telegram's 4096-character limit.
+
+use crate::channels::context::{with_channel_execution_context, ChannelExecutionContext};
+use crate::channels::outbound::OutboundDispatcher;
+use crate::channels::traits::{Channel, ChannelMessage, SendMessage};
+use crate::config::{Config, TelegramConfig};
+use crate::security::SecretString;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use parking_lot::Mutex;
+use std::sync::Arc;
+use std::time::Duration;
+
+const DEFAULT_API_BASE: &str = "https://api.telegram.org";
+/// Header Telegram sends on webhook calls when the bot was registered with a
+/// `secret_token`.
+pub const SECRET_TOKEN_HEADER: &str = "X-Telegram-Bot-Api-Secret-Token";
+const MAX_MESSAGE_CHARS: usize = 4_096;
+const POLL_TIMEOUT_SECS: u64 = 30;
+const POLL_ERROR_DELAY: Duration = Duration::from_secs(5);
+/// Health component refreshed after every successful `getUpdates` poll.
+pub const POLL_HEALTH_COMPONENT: &str = "channel:telegram";
+
+/// Check the webhook secret header in constant time. An empty expected
+/// secret never matches.
+pub fn verify_webhook_secret(expected: &SecretString, header: Option<&str>) -> bool {
+    !expected.is_empty() && expected.matches(header.unwrap_or_default())
+}
+
+fn needs_escape(ch: char) -> bool {
+    matches!(
+        ch,
+        '_' | '*'
+            | '['
+            | ']'
+            | '('
+            | ')'
+            | '~'
+            | '`'
+            | '>'
+            | '#'
+            | '+'
+            | '-'
+            | '='
+            | '|'
+            | '{'
+            | '}'
+            | '.'
+            | '!'
+            | '\\'
+    )
+}
+
+/// Escape every MarkdownV2 special character so text renders literally.
+pub fn escape_markdown_v2(text: &str) -> String {
+    escaped_chunks(text, usize::MAX).concat()
+}
+
+/// Escape `text` and split it into pieces of at most `limit` characters,
+/// preferring to break after a newline and never inside an escape sequence.
+fn escaped_chunks(text: &str, limit: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    let mut current_len = 0;
+    // Byte offset and character count just past the last newline in `current`.
+    let mut last_newline: Option<(usize, usize)> = None;
+    for ch in text.chars() {
+        let piece_len = if needs_escape(ch) { 2 } else { 1 };
+        if current_len + piece_len > limit {
+            match last_newline.filter(|(_, len)| *len > limit / 2) {
+                Some((at, len)) => {
+                    let rest = current.split_off(at);
+                    chunks.push(std::mem::replace(&mut current, rest));
+                    current_len -= len;
+                }
+                None => {
+                    chunks.push(std::mem::take(&mut current));
+                    current_len = 0;
+                }
+            }
+            last_newline = None;
+        }
+        if needs_escape(ch) {
+            current.push('\\');
+        }
+        current.push(ch);
+        current_len += piece_len;
+        if ch == '\n' {
+            last_newline = Some((current.len(), current_len));
+        }
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+#[derive(Clone)]
+pub struct TelegramChannel {
+    client: reqwest::Client,
+    api_base: String,
+    bot_token: SecretString,
+    allowed_users: Vec<String>,
+    allowed_chats: Vec<String>,
+    mention_only: bool,
+    bot_username: Arc<Mutex<Option<String>>>,
+}
+
+impl TelegramChannel {
+    pub fn new(bot_token: String, allowed_users: Vec<String>) -> Self {
+        Self {
+            client: crate::http_client::shared(),
+            api_base: DEFAULT_API_BASE.to_string(),
+            bot_token: SecretString::new(bot_token.trim()),
+            allowed_users: allowed_users
+                .into_iter()
+                .map(|user| user.trim().trim_start_matches('@').to_string())
+                .filter(|user| !user.is_empty())
+                .collect(),
+            allowed_chats: Vec::new(),
+            mention_only: false,
+            bot_username: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    pub fn from_config(config: &TelegramConfig) -> Self {
+        let mut channel = Self::new(config.bot_token.clone(), config.allowed_users.clone());
+        channel.allowed_chats = config
+            .allowed_chats
+            .iter()
+            .map(|chat| chat.trim().to_string())
+            .filter(|chat| !chat.is_empty())
+            .collect();
+        channel.mention_only = config.mention_only;
+        channel
+    }
+
+    /// Point at a different Bot API server (self-hosted or a test double).
+    pub fn with_api_base(mut self, api_base: &str) -> Self {
+        self.api_base = api_base.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// `"*"` allows everyone; other entries match the numeric user id or the
+    /// username (with or without `@`). Empty denies everyone.
+    pub fn is_user_allowed(&self, user_id: &str, username: Option<&str>) -> bool {
+        self.allowed_users.iter().any(|allowed| {
+            allowed == "*"
+                || allowed == user_id
+                || username.is_some_and(|name| allowed.eq_ignore_ascii_case(name))
+        })
+    }
+
+    /// Empty `allowed_chats` places no restriction beyond the user list.
+    pub fn is_chat_allowed(&self, chat_id: &str) -> bool {
+        self.allowed_chats.is_empty() || self.allowed_chats.iter().any(|chat| chat == chat_id)
+    }
+
+    fn method_url(&self, method: &str) -> String {
+        format!("{}/bot{}/{method}", self.api_base, self.bot_token.expose())
+    }
+
+    /// Call a Bot API method and return its `result`.
+    async fn call(
+        &self,
+        method: &str,
+        body: &serde_json::Value,
+        timeout: Option<Duration>,
+    ) -> Result<serde_json::Value> {
+        let mut request = self.client.post(self.method_url(method)).json(body);
+        if let Some(timeout) = timeout {
+            request = request.timeout(timeout);
+        }
+        // The URL carries the bot token, so keep it out of error messages.
+        let response = request
+            .send()
+            .await
+            .map_err(reqwest::Error::without_url)
+            .with_context(|| format!("Telegram {method} request failed"))?;
+        let status = response.status();
+        let payload: serde_json::Value = response
+            .json()
+            .await
+            .map_err(reqwest::Error::without_url)
+            .with_context(|| format!("Telegram {method} returned invalid JSON"))?;
+        if !status.is_success() || payload["ok"] != serde_json::Value::Bool(true) {
+            anyhow::bail!(
+                "Telegram {method} failed ({status}): {}",
+                payload["description"].as_str().unwrap_or("no description")
+            );
+        }
+        Ok(payload["result"].clone())
+    }
+
+    /// Call `getMe` and return the bot's username.
+    pub async fn get_me(&self, timeout: Duration) -> Result<String> {
+        let me = self
+            .call("getMe", &serde_json::json!({}), Some(timeout))
+            .await?;
+        Ok(me["username"].as_str().unwrap_or_default().to_string())
+    }
+
+    /// Resolve and cache the bot's username, used for `mention_only` groups.
+    pub async fn load_bot_username(&self) -> Option<String> {
+        if let Some(username) = self.bot_username.lock().clone() {
+            return Some(username);
+        }
+        match self.call("getMe", &serde_json::json!({}), None).await {
+            Ok(me) => {
+                let username = me["username"].as_str().map(str::to_string);
+                self.bot_username.lock().clone_from(&username);
+                username
+            }
+            Err(err) => {
+                tracing::warn!("Telegram getMe failed: {err:#}");
+                None
+            }
+        }
+    }
+
+    /// Turn an update into a channel message. Only new text messages (or
+    /// captions) from allowed users in allowed chats are kept; edits, bot
+    /// authors, and unmentioned group messages under `mention_only` yield
+    /// nothing.
+    pub fn parse_update(&self, update: &serde_json::Value) -> Option<ChannelMessage> {
+        let message = update.get("message")?;
+        let from = &message["from"];
+        if from["is_bot"].as_bool().unwrap_or(false) {
+            return None;
+        }
+        let user_id = from["id"].as_i64()?.to_string();
+        let username = from["username"].as_str();
+        let chat_id = message["chat"]["id"].as_i64()?.to_string();
+        if !self.is_user_allowed(&user_id, username) {
+            tracing::warn!(
+                "Telegram: ignoring message from unauthorized user {}",
+                username.unwrap_or(&user_id)
+            );
+            return None;
+        }
+        if !self.is_chat_allowed(&chat_id) {
+            tracing::warn!("Telegram: ignoring message from unlisted chat {chat_id}");
+            return None;
+        }
+        let mut content = message["text"]
+            .as_str()
+            .or_else(|| message["caption"].as_str())?
+            .trim()
+            .to_string();
+        if self.mention_only && message["chat"]["type"].as_str() != Some("private") {
+            let bot_username = self.bot_username.lock().clone()?;
+            let mention = format!("@{bot_username}");
+            let lower = content.to_ascii_lowercase();
+            let at = lower.find(&mention.to_ascii_lowercase())?;
+            content.replace_range(at..at + mention.len(), "");
+            content = content.trim().to_string();
+        }
+        if content.is_empty() {
+            return None;
+        }
+        let message_id = message["message_id"].as_i64()?;
+        Some(ChannelMessage {
+            id: format!("telegram_{chat_id}_{message_id}"),
+            sender: username.map_or_else(|| user_id.clone(), str::to_string),
+            reply_target: chat_id,
+            content,
+            channel: "telegram".to_string(),
+            timestamp: message["date"].as_u64().unwrap_or_default(),
+            thread_ts: message["message_thread_id"]
+                .as_i64()
+                .map(|id| id.to_string()),
+            attachments: Vec::new(),
+        })
+    }
+}
+
+#[async_trait]
+impl Channel for TelegramChannel {
+    fn name(&self) -> &str {
+        "telegram"
+    }
+
+    /// Send to the chat id in `message.recipient`, inside the forum topic in
+    /// `thread_ts` when set. Long replies go out as several messages.
+    async fn send(&self, message: &SendMessage) -> Result<()> {
+        let thread_id = message
+            .thread_ts
+            .as_deref()
+            .and_then(|id| id.trim().parse::<i64>().ok());
+        for chunk in escaped_chunks(&message.content, MAX_MESSAGE_CHARS) {
+            let mut body = serde_json::json!({
+                "chat_id": message.recipient.trim(),
+                "text": chunk,
+                "parse_mode": "MarkdownV2",
+            });
+            if let Some(thread_id) = thread_id {
+                body["message_thread_id"] = serde_json::json!(thread_id);
+            }
+            self.call("sendMessage", &body, None).await?;
+        }
+        Ok(())
+    }
+
+    async fn listen(&self, tx: tokio::sync::mpsc::Sender<ChannelMessage>) -> Result<()> {
+        self.load_bot_username().await;
+        let mut offset: i64 = 0;
+        loop {
+            let body = serde_json::json!({
+                "offset": offset,
+                "timeout": POLL_TIMEOUT_SECS,
+                "allowed_updates": ["message"],
+            });
+            let timeout = Some(Duration::from_secs(POLL_TIMEOUT_SECS + 10));
+            let updates = match self.call("getUpdates", &body, timeout).await {
+                Ok(updates) => {
+                    crate::health::mark_component_ok(POLL_HEALTH_COMPONENT);
+                    updates
+                }
+                Err(err) => {
+                    crate::health::mark_component_error(POLL_HEALTH_COMPONENT, format!("{err:#}"));
+                    tracing::warn!("Telegram getUpdates failed: {err:#}");
+                    tokio::time::sleep(POLL_ERROR_DELAY).await;
+                    continue;
+                }
+            };
+            for update in updates.as_array().into_iter().flatten() {
+                if let Some(id) = update["update_id"].as_i64() {
+                    offset = offset.max(id + 1);
+                }
+                if let Some(message) = self.parse_update(update) {
+                    if tx.send(message).await.is_err() {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+    }
+
+    async fn health_check(&self) -> bool {
+        self.call("getMe", &serde_json::json!({}), None)
+            .await
+            .is_ok()
+    }
+}
+
+/// Run the agent on `message` and send the reply to its chat. The turn runs
+/// inside the chat's channel context, so background jobs it starts report
+/// back to the same chat.
+/// `approve <id>` / `deny <id>` messages settle a parked tool call instead.
+pub async fn answer(config: Config, channel: &TelegramChannel, message: ChannelMessage) {
+    let workspace_dir = config.workspace_dir.clone();
+    let ctx = ChannelExecutionContext::new(
+        "telegram",
+        message.reply_target.clone(),
+        message.thread_ts.clone(),
+    );
+    let reply = if let Some(reply) =
+        crate::approval::chat::handle_chat_command(&workspace_dir, &message.content, &ctx)
+    {
+        reply
+    } else {
+        match Box::pin(with_channel_execution_context(
+            ctx,
+            crate::agent::process_message(config, &message.content),
+        ))
+        .await
+        {
+            Ok(reply) => reply,
+            Err(err) => match crate::cost::guard::budget_exceeded(&err) {
+                Some(exceeded) => exceeded.to_string(),
+                None => {
+                    tracing::error!("Telegram message {} failed: {err:#}", message.id);
+                    return;
+                }
+            },
+        }
+    };
+    if reply.trim().is_empty() {
+        return;
+    }
+    let mut outgoing = SendMessage::new(reply, message.reply_target.clone());
+    outgoing.thread_ts = message.thread_ts;
+    if let Err(err) = OutboundDispatcher::new(&workspace_dir)
+        .send(channel, &outgoing)
+        .await
+    {
+        tracing::warn!("Telegram reply failed: {err:#}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_partial_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const PRIVATE: &str = include_str!("../../tests/fixtures/telegram/private_message.json");
+    const GROUP: &str = include_str!("../../tests/fixtures/telegram/group_mention.json");
+    const BOT: &str = include_str!("../../tests/fixtures/telegram/bot_message.json");
+    const EDITED: &str = include_str!("../../tests/fixtures/telegram/edited_message.json");
+
+    fn fixture(raw: &str) -> serde_json::Value {
+        serde_json::from_str(raw).unwrap()
+    }
+
+    fn channel(allowed: &[&str]) -> TelegramChannel {
+        TelegramChannel::new(
+            "123:ABC".into(),
+            allowed.iter().map(|u| (*u).to_string()).collect(),
+        )
+    }
+
+    #[test]
+    fn parses_a_private_message() {
+        let message = channel(&["@ada_l"])
+            .parse_update(&fixture(PRIVATE))
+            .unwrap();
+        assert_eq!(message.id, "telegram_123456789_42");
+        assert_eq!(message.sender, "ada_l");
+        assert_eq!(message.reply_target, "123456789");
+        assert_eq!(message.content, "remind me to water the plants at 6pm");
+        assert_eq!(message.timestamp, 1_760_500_000);
+        assert_eq!(message.thread_ts, None);
+    }
+
+    #[test]
+    fn filters_users_chats_bots_and_edits() {
+        let open = channel(&["*"]);
+        assert!(open.parse_update(&fixture(BOT)).is_none());
+        assert!(open.parse_update(&fixture(EDITED)).is_none());
+        assert!(channel(&[]).parse_update(&fixture(PRIVATE)).is_none());
+        assert!(channel(&["grace"])
+            .parse_update(&fixture(PRIVATE))
+            .is_none());
+        assert!(channel(&["123456789"])
+            .parse_update(&fixture(PRIVATE))
+            .is_some());
+
+        let mut limited = channel(&["*"]);
+        limited.allowed_chats = vec!["-1001987654321".into()];
+        assert!(limited.parse_update(&fixture(PRIVATE)).is_none());
+        assert!(limited.parse_update(&fixture(GROUP)).is_some());
+    }
+
+    #[test]
+    fn mention_only_groups_require_and_strip_the_mention() {
+        let mut group = channel(&["*"]);
+        group.mention_only = true;
+        // Without a known bot username no group message can match.
+        assert!(group.parse_update(&fixture(GROUP)).is_none());
+
+        *group.bot_username.lock() = Some("SlowClaw_Bot".into());
+        let message = group.parse_update(&fixture(GROUP)).unwrap();
+        assert_eq!(message.content, "what did I journal yesterday?");
+        assert_eq!(message.reply_target, "-1001987654321");
+        assert_eq!(message.thread_ts.as_deref(), Some("3"));
+        // Direct messages never need a mention.
+        assert!(group.parse_update(&fixture(PRIVATE)).is_some());
+    }
+
+    #[test]
+    fn markdown_v2_escapes_every_special_character() {
+        assert_eq!(
+            escape_markdown_v2("v1.2 (beta) - 50% off! a_b*c [x](y) `z` #1 {k=v} |p| ~s~ >q \\"),
+            "v1\\.2 \\(beta\\) \\- 50% off\\! a\\_b\\*c \\[x\\]\\(y\\) \\`z\\` \\#1 \\{k\\=v\\} \\|p\\| \\~s\\~ \\>q \\\\"
+        );
+    }
+
+    #[test]
+    fn chunks_respect_the_limit_and_keep_escapes_whole() {
+        let text = format!("{}\n{}", "a".repeat(3_000), ".".repeat(3_000));
+        let chunks = escaped_chunks(&text, MAX_MESSAGE_CHARS);
+        assert!(chunks
+            .iter()
+            .all(|chunk| chunk.chars().count() <= MAX_MESSAGE_CHARS));
+        assert_eq!(chunks[0], format!("{}\n", "a".repeat(3_000)));
+        assert!(chunks[1..].iter().all(|chunk| chunk
+            .chars()
+            .collect::<Vec<_>>()
+            .chunks(2)
+            .all(|pair| pair == ['\\', '.'])));
+        assert_eq!(chunks.concat(), escape_markdown_v2(&text));
+    }
+
+    #[test]
+    fn webhook_secret_must_match_exactly() {
+        let secret = SecretString::new("s3cret");
+        assert!(verify_webhook_secret(&secret, Some("s3cret")));
+        assert!(!verify_webhook_secret(&secret, Some("s3cret ")));
+        assert!(!verify_webhook_secret(&secret, None));
+        assert!(!verify_webhook_secret(&SecretString::default(), Some("")));
+    }
+
+    #[tokio::test]
+    async fn send_posts_each_chunk_as_markdown_v2() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/bot123:ABC/sendMessage"))
+            .and(body_partial_json(serde_json::json!({
+                "chat_id": "-1001987654321",
+                "parse_mode": "MarkdownV2",
+                "message_thread_id": 3,
+            })))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({"ok": true, "result": {}})),
+            )
+            .expect(2)
+            .mount(&server)
+            .await;
+        let channel = channel(&["*"]).with_api_base(&server.uri());
+        let mut message = SendMessage::new("x".repeat(5_000), "-1001987654321");
+        message.thread_ts = Some("3".into());
+        channel.send(&message).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn listen_forwards_polled_updates() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/bot123:ABC/getMe"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(
+                serde_json::json!({"ok": true, "result": {"username": "slowclaw_bot"}}),
+            ))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/bot123:ABC/getUpdates"))
+            .and(body_partial_json(serde_json::json!({"offset": 0})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "ok": true,
+                "result": [fixture(BOT), fixture(PRIVATE)],
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/bot123:ABC/getUpdates"))
+            .and(body_partial_json(
+                serde_json::json!({"offset": 817_263_543}),
+            ))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({"ok": true, "result": []}))
+                    .set_delay(Duration::from_millis(50)),
+            )
+            .mount(&server)
+            .await;
+
+        let channel = channel(&["*"]).with_api_base(&server.uri());
+        let (tx, mut rx) = tokio::sync::mpsc::channel(4);
+        let listener = tokio::spawn(async move { channel.listen(tx).await });
+        let message = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(message.id, "telegram_123456789_42");
+        listener.abort();
+    }
+}
@@ -1,4 +1,6 @@
 use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
 
 /// A message received from or sent to a channel
 #[derive(Debug, Clone)]
@@ -12,6 +14,332 @@ pub struct ChannelMessage {
     /// Platform thread identifier (e.g. Slack `ts`, Discord thread ID).
     /// When set, replies should be posted as threaded responses.
     pub thread_ts: Option<String>,
+    /// Media delivered with the message (images, voice notes, files).
+    pub attachments: Vec<ChannelAttachment>,
+}
+
+impl ChannelMessage {
+    /// Message text with attachments rendered for the agent; see
+    /// [`compose_prompt`].
+    pub fn prompt_content(&self, workspace_dir: &Path, allow_remote_fetch: bool) -> String {
+        compose_prompt(
+            &self.content,
+            &self.attachments,
+            workspace_dir,
+            allow_remote_fetch,
+        )
+    }
+}
+
+/// Broad media type of an attachment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AttachmentKind {
+    Image,
+    Audio,
+    Video,
+    File,
+}
+
+impl AttachmentKind {
+    /// Kind implied by a MIME type; unknown types are plain files.
+    pub fn from_mime(mime: &str) -> Self {
+        match mime.split('/').next().unwrap_or_default().trim() {
+            "image" => Self::Image,
+            "audio" => Self::Audio,
+            "video" => Self::Video,
+            _ => Self::File,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Image => "image",
+            Self::Audio => "audio",
+            Self::Video => "video",
+            Self::File => "file",
+        }
+    }
+}
+
+/// Where an attachment's bytes can be read from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AttachmentSource {
+    /// `http(s)` URL served by the platform.
+    Url(String),
+    /// Path relative to the workspace directory.
+    Path(String),
+}
+
+/// Media attached to a [`ChannelMessage`].
+///
+/// Serialized flat, e.g. `{"kind":"image","url":"https://…","mime":"image/png"}`
+/// or `{"kind":"file","path":"journals/media/a.pdf"}`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChannelAttachment {
+    pub kind: AttachmentKind,
+    #[serde(flatten)]
+    pub source: AttachmentSource,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mime: Option<String>,
+}
+
+impl ChannelAttachment {
+    fn location(&self) -> &str {
+        match &self.source {
+            AttachmentSource::Url(url) | AttachmentSource::Path(url) => url,
+        }
+    }
+
+    /// Reference the multimodal step can load, or `None` when this
+    /// attachment has to be described in text instead.
+    fn image_reference(&self, workspace_dir: &Path, allow_remote_fetch: bool) -> Option<String> {
+        if self.kind != AttachmentKind::Image {
+            return None;
+        }
+        match &self.source {
+            AttachmentSource::Url(url) => {
+                let remote = url.starts_with("https://") || url.starts_with("http://");
+                (remote && allow_remote_fetch).then(|| url.clone())
+            }
+            AttachmentSource::Path(rel_path) => {
+                let root = workspace_dir.canonicalize().ok()?;
+                let resolved = root
+                    .join(rel_path.trim_start_matches('/'))
+                    .canonicalize()
+                    .ok()?;
+                resolved
+                    .starts_with(&root)
+                    .then(|| resolved.to_string_lossy().into_owned())
+            }
+        }
+    }
+
+    fn text_note(&self) -> String {
+        let mime = self
+            .mime
+            .as_deref()
+            .filter(|mime| !mime.is_empty())
+            .map(|mime| format!(" ({mime})"))
+            .unwrap_or_default();
+        format!(
+            "[Attachment: {}{mime} at {}, not loaded]",
+            self.kind.as_str(),
+            self.location()
+        )
+    }
+}
+
+/// Append attachments to message text in the form the agent understands.
+///
+/// Images the multimodal step can load (workspace files, or remote URLs when
+/// `allow_remote_fetch` is on) become `[IMAGE:…]` markers; everything else is
+/// described in a one-line note so the agent still knows it was sent.
+pub fn compose_prompt(
+    content: &str,
+    attachments: &[ChannelAttachment],
+    workspace_dir: &Path,
+    allow_remote_fetch: bool,
+) -> String {
+    let mut prompt = content.trim().to_string();
+    for attachment in attachments {
+        if !prompt.is_empty() {
+            prompt.push('\n');
+        }
+        match attachment.image_reference(workspace_dir, allow_remote_fetch) {
+            Some(reference) => {
+                prompt.push_str("[IMAGE:");
+                prompt.push_str(&reference);
+                prompt.push(']');
+            }
+            None => prompt.push_str(&attachment.text_note()),
+        }
+    }
+    prompt
+}
+
+/// Separates a chunk from its "2/3" part label.
+const PART_LABEL_SEPARATOR: &str = "\n\n";
+
+/// Smallest text budget per chunk, so tiny limits still make progress.
+const MIN_CHUNK_CHARS: usize = 16;
+
+fn char_len(text: &str) -> usize {
+    text.chars().count()
+}
+
+/// Split `text` into parts of at most `max_chars` characters, each ending in a
+/// "2/3" label. Text that already fits is returned unchanged as one part.
+///
+/// Splits prefer paragraph breaks, then sentence ends, then whitespace.
+/// Fenced code blocks stay whole when they fit in a part; a longer block is
+/// split between lines and every piece is re-fenced, so no part ever ends
+/// inside an open fence.
+pub fn chunk_message(text: &str, max_chars: usize) -> Vec<String> {
+    let text = text.trim();
+    if max_chars == 0 || char_len(text) <= max_chars {
+        return vec![text.to_string()];
+    }
+    // Reserve room for the label, widening it if the part count gains a digit.
+    let mut digits = 1;
+    loop {
+        let reserve = PART_LABEL_SEPARATOR.len() + 2 * digits + 1;
+        let budget = max_chars.saturating_sub(reserve).max(MIN_CHUNK_CHARS);
+        let parts = pack_blocks(text, budget);
+        let total = parts.len();
+        if total.to_string().len() <= digits {
+            return parts
+                .into_iter()
+                .enumerate()
+                .map(|(index, part)| format!("{part}{PART_LABEL_SEPARATOR}{}/{total}", index + 1))
+                .collect();
+        }
+        digits += 1;
+    }
+}
+
+enum Block<'a> {
+    Prose(String),
+    /// Fenced code, with the fence marker (e.g. "```") that opened it.
+    Code(String, &'a str),
+}
+
+fn fence_marker(line: &str) -> Option<&str> {
+    let trimmed = line.trim_start();
+    let fence_char = trimmed.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let len = trimmed.chars().take_while(|c| *c == fence_char).count();
+    (len >= 3).then(|| &trimmed[..len])
+}
+
+/// Paragraphs and fenced code blocks, in order.
+fn split_blocks(text: &str) -> Vec<Block<'_>> {
+    let mut blocks = Vec::new();
+    let mut lines: Vec<&str> = Vec::new();
+    let mut open_fence: Option<&str> = None;
+    for line in text.lines() {
+        if let Some(marker) = open_fence {
+            lines.push(line);
+            if line.trim_start().starts_with(marker) {
+                blocks.push(Block::Code(lines.join("\n"), marker));
+                lines.clear();
+                open_fence = None;
+            }
+        } else if let Some(marker) = fence_marker(line) {
+            if !lines.is_empty() {
+                blocks.push(Block::Prose(lines.join("\n")));
+                lines.clear();
+            }
+            lines.push(line);
+            open_fence = Some(marker);
+        } else if line.trim().is_empty() {
+            if !lines.is_empty() {
+                blocks.push(Block::Prose(lines.join("\n")));
+                lines.clear();
+            }
+        } else {
+            lines.push(line);
+        }
+    }
+    if !lines.is_empty() {
+        let joined = lines.join("\n");
+        blocks.push(match open_fence {
+            Some(marker) => Block::Code(joined, marker),
+            None => Block::Prose(joined),
+        });
+    }
+    blocks
+}
+
+fn pack_blocks(text: &str, budget: usize) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    for block in split_blocks(text) {
+        let pieces = match block {
+            Block::Prose(prose) => split_prose(&prose, budget),
+            Block::Code(code, _) if char_len(&code) <= budget => vec![code],
+            Block::Code(code, marker) => split_code(&code, marker, budget),
+        };
+        for piece in pieces {
+            if current.is_empty() {
+                current = piece;
+            } else if char_len(&current) + 2 + char_len(&piece) <= budget {
+                current.push_str("\n\n");
+                current.push_str(&piece);
+            } else {
+                parts.push(std::mem::replace(&mut current, piece));
+            }
+        }
+    }
+    if !current.is_empty() {
+        parts.push(current);
+    }
+    parts
+}
+
+/// Greedily join `units` (which keep their trailing separators) into pieces of
+/// at most `budget` characters, handing oversized units to `finer`.
+fn pack_units(units: &[&str], budget: usize, finer: fn(&str, usize) -> Vec<String>) -> Vec<String> {
+    let mut pieces = Vec::new();
+    let mut current = String::new();
+    for unit in units {
+        if char_len(format!("{current}{unit}").trim()) <= budget {
+            current.push_str(unit);
+            continue;
+        }
+        if !current.trim().is_empty() {
+            pieces.push(current.trim().to_string());
+        }
+        current.clear();
+        if char_len(unit.trim()) <= budget {
+            current.push_str(unit);
+        } else {
+            pieces.extend(finer(unit.trim(), budget));
+        }
+    }
+    if !current.trim().is_empty() {
+        pieces.push(current.trim().to_string());
+    }
+    pieces
+}
+
+fn split_prose(text: &str, budget: usize) -> Vec<String> {
+    let sentences: Vec<&str> = text.split_inclusive(['.', '!', '?', '\n']).collect();
+    pack_units(&sentences, budget, split_words)
+}
+
+fn split_words(text: &str, budget: usize) -> Vec<String> {
+    let words: Vec<&str> = text.split_inclusive(char::is_whitespace).collect();
+    pack_units(&words, budget, split_chars)
+}
+
+fn split_chars(text: &str, budget: usize) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    chars
+        .chunks(budget.max(1))
+        .map(|chunk| chunk.iter().collect())
+        .collect()
+}
+
+/// Split an oversized fenced block between lines, re-fencing each piece.
+fn split_code(code: &str, marker: &str, budget: usize) -> Vec<String> {
+    let mut lines: Vec<&str> = code.lines().collect();
+    let opening = lines.remove(0);
+    if lines
+        .last()
+        .is_some_and(|line| line.trim_start().starts_with(marker))
+    {
+        lines.pop();
+    }
+    let body = lines.join("\n");
+    let body_lines: Vec<&str> = body.split_inclusive('\n').collect();
+    let inner_budget = budget
+        .saturating_sub(char_len(opening) + char_len(marker) + 2)
+        .max(MIN_CHUNK_CHARS);
+    pack_units(&body_lines, inner_budget, split_chars)
+        .into_iter()
+        .map(|piece| format!("{opening}\n{piece}\n{marker}"))
+        .collect()
 }
 
 /// Message to send through a channel
@@ -56,6 +384,25 @@ impl SendMessage {
     }
 }
 
+/// Outcome of checking a webhook signature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerifyResult {
+    Valid,
+    Invalid,
+    /// This build has no verifier for the channel, so no signature can pass.
+    Unsupported,
+}
+
+impl From<bool> for VerifyResult {
+    fn from(valid: bool) -> Self {
+        if valid {
+            Self::Valid
+        } else {
+            Self::Invalid
+        }
+    }
+}
+
 /// Core channel trait — implement for any messaging platform
 #[async_trait]
 pub trait Channel: Send + Sync {
@@ -68,6 +415,22 @@ pub trait Channel: Send + Sync {
     /// Start listening for incoming messages (long-running)
     async fn listen(&self, tx: tokio::sync::mpsc::Sender<ChannelMessage>) -> anyhow::Result<()>;
 
+    /// Longest message the platform renders well, in characters. `None`
+    /// means no limit.
+    fn max_message_chars(&self) -> Option<usize> {
+        None
+    }
+
+    /// Turn outgoing text into the messages actually sent. The default splits
+    /// it with [`chunk_message`] when `max_message_chars` is set and passes it
+    /// through unchanged otherwise.
+    fn format_outbound(&self, content: &str) -> Vec<String> {
+        match self.max_message_chars() {
+            Some(max_chars) => chunk_message(content, max_chars),
+            None => vec![content.to_string()],
+        }
+    }
+
     /// Check if channel is healthy
     async fn health_check(&self) -> bool {
         true
@@ -147,6 +510,7 @@ pub trait Channel: Send + Sync {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::fmt::Write as _;
 
     struct DummyChannel;
 
@@ -172,6 +536,7 @@ mod tests {
                 channel: "dummy".into(),
                 timestamp: 123,
                 thread_ts: None,
+                attachments: Vec::new(),
             })
             .await
             .map_err(|e| anyhow::anyhow!(e.to_string()))
@@ -188,6 +553,7 @@ mod tests {
             channel: "dummy".into(),
             timestamp: 999,
             thread_ts: None,
+            attachments: Vec::new(),
         };
 
         let cloned = message.clone();
@@ -256,4 +622,81 @@ mod tests {
         assert_eq!(received.content, "hello");
         assert_eq!(received.channel, "dummy");
     }
+
+    #[test]
+    fn chunk_message_splits_on_paragraphs_and_numbers_parts() {
+        assert_eq!(chunk_message("  short  ", 100), vec!["short"]);
+
+        let text = format!(
+            "{}\n\n{}\n\n{}",
+            "a".repeat(40),
+            "b".repeat(40),
+            "c".repeat(40)
+        );
+        let parts = chunk_message(&text, 60);
+        assert_eq!(parts.len(), 3);
+        assert_eq!(parts[0], format!("{}\n\n1/3", "a".repeat(40)));
+        assert!(parts[2].ends_with("\n\n3/3"));
+        assert!(parts.iter().all(|part| part.chars().count() <= 60));
+
+        let sentences = "One sentence here. Another one follows! And a third?".repeat(3);
+        for part in chunk_message(&sentences, 40) {
+            assert!(part.chars().count() <= 40, "{part:?}");
+        }
+    }
+
+    #[test]
+    fn chunk_message_never_leaves_a_fence_open() {
+        let code = (0..30).fold(String::new(), |mut code, i| {
+            let _ = writeln!(code, "let x{i} = {i};");
+            code
+        });
+        let text = format!("Intro paragraph.\n\n```rust\n{code}```\n\nOutro.");
+        let parts = chunk_message(&text, 120);
+        assert!(parts.len() > 2);
+        for part in &parts {
+            assert!(part.chars().count() <= 120, "{part:?}");
+            assert_eq!(part.matches("```").count() % 2, 0, "{part:?}");
+        }
+        assert!(parts[1].starts_with("```rust\nlet x"));
+
+        let small = "Look:\n\n```\nfn a() {}\n\nfn b() {}\n```";
+        let parts = chunk_message(&format!("{small}\n\n{}", "z".repeat(60)), 70);
+        assert!(parts[0].contains("fn a() {}\n\nfn b() {}\n```"));
+    }
+
+    #[test]
+    fn attachments_deserialize_flat_and_render_for_the_agent() {
+        let workspace = tempfile::tempdir().unwrap();
+        std::fs::write(workspace.path().join("photo.png"), b"png").unwrap();
+        let attachments: Vec<ChannelAttachment> = serde_json::from_value(serde_json::json!([
+            { "kind": "image", "path": "photo.png" },
+            { "kind": "image", "url": "https://cdn.example/a.jpg", "mime": "image/jpeg" },
+            { "kind": "audio", "url": "https://cdn.example/v.ogg", "mime": "audio/ogg" },
+            { "kind": "image", "path": "../outside.png" }
+        ]))
+        .unwrap();
+        assert_eq!(
+            attachments[1].source,
+            AttachmentSource::Url("https://cdn.example/a.jpg".into())
+        );
+
+        let prompt = compose_prompt("look", &attachments, workspace.path(), false);
+        let lines: Vec<&str> = prompt.lines().collect();
+        assert_eq!(lines[0], "look");
+        assert!(lines[1].starts_with("[IMAGE:") && lines[1].ends_with("photo.png]"));
+        assert_eq!(
+            lines[2],
+            "[Attachment: image (image/jpeg) at https://cdn.example/a.jpg, not loaded]"
+        );
+        assert!(lines[3].starts_with("[Attachment: audio (audio/ogg)"));
+        assert!(lines[4].contains("../outside.png, not loaded"));
+
+        let prompt = compose_prompt("", &attachments[1..2], workspace.path(), true);
+        assert_eq!(prompt, "[IMAGE:https://cdn.example/a.jpg]");
+        assert_eq!(
+            AttachmentKind::from_mime("video/mp4"),
+            AttachmentKind::Video
+        );
+    }
 }
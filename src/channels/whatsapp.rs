@@ -0,0 +1,710 @@
+//! WhatsApp Business Cloud API channel (cargo feature `channel-whatsapp`).
+//!
+//! Meta verifies the webhook with a `GET /whatsapp` challenge carrying the
+//! configured verify token, then delivers messages to `POST /whatsapp` signed
+//! with the app secret: `X-Hub-Signature-256` is `sha256=` followed by the
+//! hex HMAC-SHA256 of the raw body. Media arrives as Graph media ids that are
+//! fetched with the access token. Replies go out through the Cloud API
+//! `messages` endpoint; outside the 24-hour customer service window Meta
+//! refuses free-form text, and the configured reply template is sent instead.
+
+use crate::channels::traits::{
+    chunk_message, AttachmentKind, AttachmentSource, Channel, ChannelAttachment, ChannelMessage,
+    SendMessage, VerifyResult,
+};
+use crate::config::WhatsAppConfig;
+use crate::security::SecretString;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::time::Duration;
+
+const DEFAULT_API_BASE: &str = "https://graph.facebook.com/v21.0";
+pub const SIGNATURE_HEADER: &str = "X-Hub-Signature-256";
+const MAX_MESSAGE_CHARS: usize = 4_096;
+/// Template body parameters are capped by Meta at 1024 characters.
+const MAX_TEMPLATE_PARAM_CHARS: usize = 1_024;
+/// Cloud API error for free-form text sent outside the 24-hour window.
+const REENGAGEMENT_ERROR_CODE: i64 = 131_047;
+const DEFAULT_TEMPLATE_LANGUAGE: &str = "en_US";
+/// Largest media download accepted: WhatsApp's own cap for documents.
+const MAX_MEDIA_BYTES: u64 = 100 * 1024 * 1024;
+/// A full-size download outlasts the shared client's default timeout.
+const MEDIA_DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Check a webhook signature: `sha256=<hex>` HMAC-SHA256 of the raw body,
+/// keyed with the app secret. Compared in constant time; case-insensitive hex.
+pub fn verify_whatsapp_signature(app_secret: &str, body: &[u8], signature: &str) -> VerifyResult {
+    if app_secret.is_empty() {
+        return VerifyResult::Invalid;
+    }
+    let Some(hex_digest) = signature.trim().strip_prefix("sha256=") else {
+        return VerifyResult::Invalid;
+    };
+    let Ok(expected) = hex::decode(hex_digest.to_ascii_lowercase()) else {
+        return VerifyResult::Invalid;
+    };
+    let mut mac = Hmac::<Sha256>::new_from_slice(app_secret.as_bytes())
+        .expect("HMAC can take key of any size");
+    mac.update(body);
+    mac.verify_slice(&expected).is_ok().into()
+}
+
+/// Phone numbers compare by their digits, so `+1 650-555-1234` in the
+/// allow list matches the `16505551234` Meta reports.
+fn digits(number: &str) -> String {
+    number.chars().filter(char::is_ascii_digit).collect()
+}
+
+/// A media message waiting to be fetched: `attachments` on the parsed
+/// message point at `{api_base}/{media_id}` until then.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingMedia {
+    pub media_id: String,
+    pub kind: AttachmentKind,
+    pub mime: Option<String>,
+}
+
+/// A message the Cloud API refused, with Meta's error code.
+#[derive(Debug, thiserror::Error)]
+#[error("WhatsApp refused the message ({status}): {detail}")]
+struct CloudApiError {
+    status: reqwest::StatusCode,
+    code: Option<i64>,
+    detail: serde_json::Value,
+}
+
+impl CloudApiError {
+    fn is_window_closed(err: &anyhow::Error) -> bool {
+        err.downcast_ref::<Self>()
+            .is_some_and(|err| err.code == Some(REENGAGEMENT_ERROR_CODE))
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ReplyTemplate {
+    name: String,
+    language: String,
+}
+
+#[derive(Clone)]
+pub struct WhatsAppChannel {
+    client: reqwest::Client,
+    api_base: String,
+    access_token: SecretString,
+    phone_number_id: String,
+    verify_token: SecretString,
+    allowed_numbers: Vec<String>,
+    reply_template: Option<ReplyTemplate>,
+}
+
+impl WhatsAppChannel {
+    pub fn new(
+        access_token: String,
+        phone_number_id: String,
+        verify_token: String,
+        allowed_numbers: Vec<String>,
+    ) -> Self {
+        Self {
+            client: crate::http_client::shared(),
+            api_base: DEFAULT_API_BASE.to_string(),
+            access_token: SecretString::new(access_token.trim()),
+            phone_number_id: phone_number_id.trim().to_string(),
+            verify_token: SecretString::new(verify_token),
+            allowed_numbers,
+            reply_template: None,
+        }
+    }
+
+    /// The Cloud API channel for `config`, or `None` unless the access token,
+    /// phone number id, and verify token are all set.
+    pub fn from_config(config: &WhatsAppConfig) -> Option<Self> {
+        if !config.is_cloud_config() {
+            return None;
+        }
+        let channel = Self::new(
+            config.access_token.clone()?,
+            config.phone_number_id.clone()?,
+            config.verify_token.clone()?,
+            config.allowed_numbers.clone(),
+        )
+        .with_reply_template(
+            config.reply_template.clone(),
+            config.reply_template_language.clone(),
+        );
+        Some(match config.api_url.as_deref() {
+            Some(api_url) => channel.with_api_base(api_url),
+            None => channel,
+        })
+    }
+
+    pub fn with_api_base(mut self, api_base: &str) -> Self {
+        self.api_base = api_base.trim().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Template sent when a reply falls outside the 24-hour window. It must
+    /// take one body parameter, which receives the reply text.
+    pub fn with_reply_template(mut self, name: Option<String>, language: Option<String>) -> Self {
+        self.reply_template = name
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty())
+            .map(|name| ReplyTemplate {
+                name,
+                language: language
+                    .map(|language| language.trim().to_string())
+                    .filter(|language| !language.is_empty())
+                    .unwrap_or_else(|| DEFAULT_TEMPLATE_LANGUAGE.to_string()),
+            });
+        self
+    }
+
+    /// Answer Meta's subscription check: `hub.mode=subscribe` with the
+    /// configured verify token, compared in constant time.
+    pub fn verify_subscription(&self, mode: &str, token: &str) -> bool {
+        mode == "subscribe" && !self.verify_token.is_empty() && self.verify_token.matches(token)
+    }
+
+    /// `[]` denies everyone and `"*"` allows everyone.
+    pub fn is_number_allowed(&self, number: &str) -> bool {
+        let number = digits(number);
+        !number.is_empty()
+            && self.allowed_numbers.iter().any(|allowed| {
+                let allowed = allowed.trim();
+                allowed == "*" || digits(allowed) == number
+            })
+    }
+
+    /// Turn a webhook payload into channel messages. Text, button replies,
+    /// and media from allowed numbers to this phone number are kept; delivery
+    /// statuses, reactions, and unsupported types yield nothing. Media
+    /// attachments point at the Graph media URL; see [`Self::pending_media`].
+    pub fn parse_webhook_payload(&self, payload: &serde_json::Value) -> Vec<ChannelMessage> {
+        let mut messages = Vec::new();
+        let entries = payload["entry"].as_array().into_iter().flatten();
+        for change in entries.flat_map(|entry| entry["changes"].as_array().into_iter().flatten()) {
+            let value = &change["value"];
+            if change["field"].as_str() != Some("messages")
+                || value["metadata"]["phone_number_id"].as_str()
+                    != Some(self.phone_number_id.as_str())
+            {
+                continue;
+            }
+            for message in value["messages"].as_array().into_iter().flatten() {
+                if let Some(parsed) = self.parse_message(message) {
+                    messages.push(parsed);
+                }
+            }
+        }
+        messages
+    }
+
+    fn parse_message(&self, message: &serde_json::Value) -> Option<ChannelMessage> {
+        let from = message["from"].as_str()?.trim();
+        let id = message["id"].as_str()?.trim();
+        if from.is_empty() || id.is_empty() {
+            return None;
+        }
+        if !self.is_number_allowed(from) {
+            tracing::warn!("WhatsApp: ignoring message from unauthorized number {from}");
+            return None;
+        }
+        let kind = message["type"].as_str().unwrap_or_default();
+        let (content, attachments) = match kind {
+            "text" => (message["text"]["body"].as_str()?.to_string(), Vec::new()),
+            "button" => (message["button"]["text"].as_str()?.to_string(), Vec::new()),
+            "interactive" => {
+                let interactive = &message["interactive"];
+                let reply = interactive
+                    .get("button_reply")
+                    .or_else(|| interactive.get("list_reply"))?;
+                (reply["title"].as_str()?.to_string(), Vec::new())
+            }
+            "image" | "audio" | "video" | "document" | "sticker" => {
+                let media = &message[kind];
+                let media_id = media["id"].as_str()?.trim();
+                let mime = media["mime_type"].as_str().map(str::to_string);
+                let attachment = ChannelAttachment {
+                    kind: mime
+                        .as_deref()
+                        .map_or(AttachmentKind::File, AttachmentKind::from_mime),
+                    source: AttachmentSource::Url(format!("{}/{media_id}", self.api_base)),
+                    mime,
+                };
+                let caption = media["caption"].as_str().unwrap_or_default().to_string();
+                (caption, vec![attachment])
+            }
+            other => {
+                tracing::debug!("WhatsApp: skipping unsupported message type {other:?}");
+                return None;
+            }
+        };
+        if content.trim().is_empty() && attachments.is_empty() {
+            return None;
+        }
+        Some(ChannelMessage {
+            id: id.to_string(),
+            sender: from.to_string(),
+            reply_target: from.to_string(),
+            content: content.trim().to_string(),
+            channel: "whatsapp".to_string(),
+            timestamp: message["timestamp"]
+                .as_str()
+                .and_then(|ts| ts.parse().ok())
+                .unwrap_or_default(),
+            thread_ts: Some(id.to_string()),
+            attachments,
+        })
+    }
+
+    /// Media ids behind the Graph URLs [`Self::parse_webhook_payload`] put in
+    /// `message.attachments`, in attachment order.
+    pub fn pending_media(&self, message: &ChannelMessage) -> Vec<PendingMedia> {
+        let prefix = format!("{}/", self.api_base);
+        message
+            .attachments
+            .iter()
+            .filter_map(|attachment| {
+                let AttachmentSource::Url(url) = &attachment.source else {
+                    return None;
+                };
+                let media_id = url.strip_prefix(&prefix)?;
+                Some(PendingMedia {
+                    media_id: media_id.to_string(),
+                    kind: attachment.kind,
+                    mime: attachment.mime.clone(),
+                })
+            })
+            .collect()
+    }
+
+    /// Fetch a media object: look up its short-lived download URL, then
+    /// download it with the access token. Returns the bytes and MIME type.
+    /// Anything over [`MAX_MEDIA_BYTES`] is refused before or while reading.
+    pub async fn download_media(&self, media_id: &str) -> Result<(Vec<u8>, Option<String>)> {
+        let info: serde_json::Value = self
+            .client
+            .get(format!("{}/{media_id}", self.api_base))
+            .bearer_auth(self.access_token.expose())
+            .send()
+            .await
+            .context("WhatsApp media lookup failed")?
+            .error_for_status()
+            .context("WhatsApp media lookup was refused")?
+            .json()
+            .await
+            .context("WhatsApp media lookup returned invalid JSON")?;
+        let url = info["url"]
+            .as_str()
+            .context("WhatsApp media lookup returned no url")?;
+        if let Some(size) = info["file_size"].as_u64() {
+            anyhow::ensure!(
+                size <= MAX_MEDIA_BYTES,
+                "WhatsApp media is too large ({size} bytes)"
+            );
+        }
+        let mut response = self
+            .client
+            .get(url)
+            .bearer_auth(self.access_token.expose())
+            .timeout(MEDIA_DOWNLOAD_TIMEOUT)
+            .send()
+            .await
+            .context("WhatsApp media download failed")?
+            .error_for_status()
+            .context("WhatsApp media download was refused")?;
+        if let Some(size) = response.content_length() {
+            anyhow::ensure!(
+                size <= MAX_MEDIA_BYTES,
+                "WhatsApp media is too large ({size} bytes)"
+            );
+        }
+        let mut bytes = Vec::new();
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .context("WhatsApp media download was interrupted")?
+        {
+            anyhow::ensure!(
+                (bytes.len() + chunk.len()) as u64 <= MAX_MEDIA_BYTES,
+                "WhatsApp media is too large (over {MAX_MEDIA_BYTES} bytes)"
+            );
+            bytes.extend_from_slice(&chunk);
+        }
+        Ok((bytes, info["mime_type"].as_str().map(str::to_string)))
+    }
+
+    fn messages_url(&self) -> String {
+        format!("{}/{}/messages", self.api_base, self.phone_number_id)
+    }
+
+    /// POST one message body. A refusal is a [`CloudApiError`].
+    async fn post_message(&self, body: &serde_json::Value) -> Result<()> {
+        let response = self
+            .client
+            .post(self.messages_url())
+            .bearer_auth(self.access_token.expose())
+            .json(body)
+            .send()
+            .await
+            .context("WhatsApp send request failed")?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(());
+        }
+        let detail: serde_json::Value = response.json().await.unwrap_or_default();
+        Err(CloudApiError {
+            status,
+            code: detail["error"]["code"].as_i64(),
+            detail,
+        }
+        .into())
+    }
+
+    async fn send_template(&self, template: &ReplyTemplate, message: &SendMessage) -> Result<()> {
+        // Leave room for the ellipsis within Meta's parameter limit.
+        let text =
+            crate::util::truncate_with_ellipsis(&message.content, MAX_TEMPLATE_PARAM_CHARS - 3);
+        let body = serde_json::json!({
+            "messaging_product": "whatsapp",
+            "to": message.recipient.trim(),
+            "type": "template",
+            "template": {
+                "name": template.name,
+                "language": {"code": template.language},
+                "components": [{
+                    "type": "body",
+                    "parameters": [{"type": "text", "text": text}],
+                }],
+            },
+        });
+        self.post_message(&body)
+            .await
+            .with_context(|| format!("WhatsApp reply template {:?} failed", template.name))
+    }
+}
+
+#[async_trait]
+impl Channel for WhatsAppChannel {
+    fn name(&self) -> &str {
+        "whatsapp"
+    }
+
+    /// Send text to the number in `message.recipient`, quoting the message id
+    /// in `thread_ts` when set. Long replies go out as several messages. When
+    /// Meta refuses the text because the 24-hour window has closed, the reply
+    /// template is sent instead, or the send fails if there is none.
+    async fn send(&self, message: &SendMessage) -> Result<()> {
+        for (index, chunk) in chunk_message(&message.content, MAX_MESSAGE_CHARS)
+            .into_iter()
+            .enumerate()
+        {
+            let mut body = serde_json::json!({
+                "messaging_product": "whatsapp",
+                "recipient_type": "individual",
+                "to": message.recipient.trim(),
+                "type": "text",
+                "text": {"body": chunk, "preview_url": false},
+            });
+            if let Some(reply_to) = message.thread_ts.as_deref().filter(|id| !id.is_empty()) {
+                body["context"] = serde_json::json!({"message_id": reply_to});
+            }
+            match self.post_message(&body).await {
+                Ok(()) => {}
+                Err(err) if index == 0 && CloudApiError::is_window_closed(&err) => {
+                    let Some(template) = &self.reply_template else {
+                        return Err(err.context(
+                            "the 24-hour window is closed and no reply_template is configured",
+                        ));
+                    };
+                    return self.send_template(template, message).await;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(())
+    }
+
+    /// Meta pushes messages to the gateway webhook, so there is nothing to poll.
+    async fn listen(&self, _tx: tokio::sync::mpsc::Sender<ChannelMessage>) -> Result<()> {
+        tracing::info!("WhatsApp: messages arrive via the gateway /whatsapp webhook");
+        Ok(())
+    }
+
+    async fn health_check(&self) -> bool {
+        self.client
+            .get(format!("{}/{}", self.api_base, self.phone_number_id))
+            .bearer_auth(self.access_token.expose())
+            .send()
+            .await
+            .map(|resp| resp.status().is_success())
+            .unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_partial_json, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const TEXT: &str = include_str!("../../tests/fixtures/whatsapp/text_message.json");
+    const IMAGE_REPLY: &str = include_str!("../../tests/fixtures/whatsapp/image_reply.json");
+    const STATUS: &str = include_str!("../../tests/fixtures/whatsapp/status_update.json");
+    const SECRET: &str = "slowclaw-whatsapp-secret";
+    /// HMAC-SHA256(SECRET, text_message.json), computed independently.
+    const SIGNATURE: &str =
+        "sha256=954fa835f9f0d0c14defd7f8f5500b2ab94a86b94f52f893f96f6e730299fc21";
+    const PHONE_NUMBER_ID: &str = "106540352242922";
+
+    fn channel(allowed: &[&str]) -> WhatsAppChannel {
+        WhatsAppChannel::new(
+            "access-token".into(),
+            PHONE_NUMBER_ID.into(),
+            "verify-me".into(),
+            allowed.iter().map(|n| (*n).to_string()).collect(),
+        )
+    }
+
+    fn fixture(raw: &str) -> serde_json::Value {
+        serde_json::from_str(raw).unwrap()
+    }
+
+    #[test]
+    fn signature_covers_the_raw_body() {
+        assert_eq!(
+            verify_whatsapp_signature(SECRET, TEXT.as_bytes(), SIGNATURE),
+            VerifyResult::Valid
+        );
+        assert_eq!(
+            verify_whatsapp_signature(
+                SECRET,
+                TEXT.as_bytes(),
+                &SIGNATURE.to_ascii_uppercase().replace("SHA256=", "sha256=")
+            ),
+            VerifyResult::Valid
+        );
+        let tampered = TEXT.replace("morning walk", "evening walk");
+        assert_eq!(
+            verify_whatsapp_signature(SECRET, tampered.as_bytes(), SIGNATURE),
+            VerifyResult::Invalid
+        );
+        assert_eq!(
+            verify_whatsapp_signature("other", TEXT.as_bytes(), SIGNATURE),
+            VerifyResult::Invalid
+        );
+        assert_eq!(
+            verify_whatsapp_signature("", TEXT.as_bytes(), SIGNATURE),
+            VerifyResult::Invalid
+        );
+        assert_eq!(
+            verify_whatsapp_signature(
+                SECRET,
+                TEXT.as_bytes(),
+                SIGNATURE.trim_start_matches("sha256=")
+            ),
+            VerifyResult::Invalid
+        );
+    }
+
+    #[test]
+    fn subscription_check_needs_the_verify_token() {
+        let channel = channel(&["*"]);
+        assert!(channel.verify_subscription("subscribe", "verify-me"));
+        assert!(!channel.verify_subscription("subscribe", "verify-you"));
+        assert!(!channel.verify_subscription("unsubscribe", "verify-me"));
+    }
+
+    #[test]
+    fn parses_a_text_message() {
+        let messages = channel(&["+1 650-555-1234"]).parse_webhook_payload(&fixture(TEXT));
+        assert_eq!(messages.len(), 1);
+        let message = &messages[0];
+        assert_eq!(
+            message.content,
+            "How did the morning walk recording turn out?"
+        );
+        assert_eq!(message.sender, "16505551234");
+        assert_eq!(message.reply_target, "16505551234");
+        assert_eq!(message.channel, "whatsapp");
+        assert_eq!(message.timestamp, 1_760_000_000);
+        assert_eq!(
+            message.thread_ts.as_deref(),
+            Some("wamid.HBgLMTY1MDU1NTEyMzQVAgASGBQzQTRBNjU5OUFFRTAzODEwMTQ0RgA=")
+        );
+        assert!(message.attachments.is_empty());
+    }
+
+    #[test]
+    fn media_messages_point_at_their_graph_media_ids() {
+        let channel = channel(&["*"]).with_api_base("https://graph.example/v21.0/");
+        let messages = channel.parse_webhook_payload(&fixture(IMAGE_REPLY));
+        assert_eq!(messages.len(), 2);
+
+        let image = &messages[0];
+        assert_eq!(image.content, "Use this one for the post");
+        assert_eq!(
+            image.attachments,
+            vec![ChannelAttachment {
+                kind: AttachmentKind::Image,
+                source: AttachmentSource::Url(
+                    "https://graph.example/v21.0/1003383421387256".into()
+                ),
+                mime: Some("image/jpeg".into()),
+            }]
+        );
+        assert_eq!(
+            channel.pending_media(image),
+            vec![PendingMedia {
+                media_id: "1003383421387256".into(),
+                kind: AttachmentKind::Image,
+                mime: Some("image/jpeg".into()),
+            }]
+        );
+
+        let voice = &messages[1];
+        assert_eq!(voice.content, "");
+        assert_eq!(voice.attachments[0].kind, AttachmentKind::Audio);
+        assert_eq!(channel.pending_media(voice)[0].media_id, "1437590287327915");
+    }
+
+    #[test]
+    fn ignores_statuses_other_numbers_and_unlisted_senders() {
+        let open = channel(&["*"]);
+        assert!(open.parse_webhook_payload(&fixture(STATUS)).is_empty());
+        assert!(channel(&[])
+            .parse_webhook_payload(&fixture(TEXT))
+            .is_empty());
+        assert!(channel(&["+15550000000"])
+            .parse_webhook_payload(&fixture(TEXT))
+            .is_empty());
+
+        let mut other_number = fixture(TEXT);
+        other_number["entry"][0]["changes"][0]["value"]["metadata"]["phone_number_id"] =
+            "999999999999999".into();
+        assert!(open.parse_webhook_payload(&other_number).is_empty());
+
+        let mut reaction = fixture(TEXT);
+        reaction["entry"][0]["changes"][0]["value"]["messages"][0] = serde_json::json!({
+            "from": "16505551234",
+            "id": "wamid.reaction",
+            "timestamp": "1760000090",
+            "type": "reaction",
+            "reaction": {"message_id": "wamid.reply", "emoji": "👍"},
+        });
+        assert!(open.parse_webhook_payload(&reaction).is_empty());
+    }
+
+    #[tokio::test]
+    async fn send_replies_in_context_and_downloads_media() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path(format!("/{PHONE_NUMBER_ID}/messages")))
+            .and(header("Authorization", "Bearer access-token"))
+            .and(body_partial_json(serde_json::json!({
+                "to": "16505551234",
+                "type": "text",
+                "text": {"body": "done"},
+                "context": {"message_id": "wamid.abc"},
+            })))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({"messages": [{"id": "wamid.reply"}]})),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/1003383421387256"))
+            .and(header("Authorization", "Bearer access-token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "url": format!("{}/lookaside/1003383421387256", server.uri()),
+                "mime_type": "image/jpeg",
+                "id": "1003383421387256",
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/lookaside/1003383421387256"))
+            .and(header("Authorization", "Bearer access-token"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(b"jpeg-bytes".to_vec()))
+            .mount(&server)
+            .await;
+
+        let channel = channel(&["*"]).with_api_base(&server.uri());
+        let mut message = SendMessage::new("done", "16505551234");
+        message.thread_ts = Some("wamid.abc".into());
+        channel.send(&message).await.unwrap();
+
+        let (bytes, mime) = channel.download_media("1003383421387256").await.unwrap();
+        assert_eq!(bytes, b"jpeg-bytes");
+        assert_eq!(mime.as_deref(), Some("image/jpeg"));
+    }
+
+    #[tokio::test]
+    async fn refuses_media_larger_than_the_cap() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/1003383421387256"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "url": format!("{}/lookaside/1003383421387256", server.uri()),
+                "mime_type": "video/mp4",
+                "file_size": MAX_MEDIA_BYTES + 1,
+                "id": "1003383421387256",
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/lookaside/1003383421387256"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(b"mp4-bytes".to_vec()))
+            .expect(0)
+            .mount(&server)
+            .await;
+
+        let channel = channel(&["*"]).with_api_base(&server.uri());
+        let err = channel
+            .download_media("1003383421387256")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("too large"));
+    }
+
+    #[tokio::test]
+    async fn closed_window_falls_back_to_the_reply_template() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(serde_json::json!({"type": "text"})))
+            .respond_with(ResponseTemplate::new(400).set_body_json(serde_json::json!({
+                "error": {
+                    "message": "(#131047) Re-engagement message",
+                    "type": "OAuthException",
+                    "code": REENGAGEMENT_ERROR_CODE,
+                }
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(serde_json::json!({
+                "type": "template",
+                "template": {
+                    "name": "slowclaw_reply",
+                    "language": {"code": "en_GB"},
+                    "components": [{"type": "body", "parameters": [{"type": "text", "text": "late reply"}]}],
+                },
+            })))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let message = SendMessage::new("late reply", "16505551234");
+        let without_template = channel(&["*"]).with_api_base(&server.uri());
+        let err = without_template.send(&message).await.unwrap_err();
+        assert!(err.to_string().contains("reply_template"), "{err}");
+
+        let with_template = without_template
+            .with_reply_template(Some("slowclaw_reply".into()), Some("en_GB".into()));
+        with_template.send(&message).await.unwrap();
+    }
+}
@@ -22,6 +22,7 @@ const SUPPORTED_PROXY_SERVICE_KEYS: &[&str] = &[
     "provider.ollama",
     "provider.openai",
     "provider.openrouter",
+    "channel.bluesky",
     "channel.dingtalk",
     "channel.discord",
     "channel.feishu",
@@ -221,6 +222,14 @@ pub struct Config {
     /// Voice transcription configuration (Whisper API via Groq).
     #[serde(default)]
     pub transcription: TranscriptionConfig,
+
+    /// Uploaded media probing via ffprobe (`[media_probe]`).
+    #[serde(default)]
+    pub media_probe: MediaProbeConfig,
+
+    /// Workspace version control (`[workspace]`).
+    #[serde(default)]
+    pub workspace: WorkspaceConfig,
 }
 
 /// Named provider profile definition compatible with Codex app-server style config.
@@ -249,6 +258,17 @@ pub struct ProviderConfig {
     /// Provider reasoning profile.
     #[serde(default = "default_provider_reasoning_level")]
     pub reasoning_level: String,
+    /// Seconds a cached gateway response stays valid. `0` (default) disables
+    /// the response cache under `state/llm_cache/`.
+    #[serde(default)]
+    pub cache_ttl_secs: u64,
+    /// Highest temperature whose responses are cached. Default: `0.0`, so
+    /// only deterministic requests are answered from the cache.
+    #[serde(default)]
+    pub cache_max_temperature: f64,
+    /// Most responses kept in the cache; the oldest go first. Default: `1000`.
+    #[serde(default = "default_provider_cache_max_entries")]
+    pub cache_max_entries: usize,
 }
 
 fn default_provider_transport_mode() -> String {
@@ -259,11 +279,18 @@ fn default_provider_reasoning_level() -> String {
     "high".to_string()
 }
 
+fn default_provider_cache_max_entries() -> usize {
+    1000
+}
+
 impl Default for ProviderConfig {
     fn default() -> Self {
         Self {
             transport: default_provider_transport_mode(),
             reasoning_level: default_provider_reasoning_level(),
+            cache_ttl_secs: 0,
+            cache_max_temperature: 0.0,
+            cache_max_entries: default_provider_cache_max_entries(),
         }
     }
 }
@@ -448,6 +475,94 @@ impl Default for TranscriptionConfig {
     }
 }
 
+fn default_media_probe_ffprobe_path() -> String {
+    "ffprobe".into()
+}
+
+fn default_media_probe_timeout_secs() -> u64 {
+    10
+}
+
+/// Media metadata probing configuration (`[media_probe]` section).
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct MediaProbeConfig {
+    /// Probe uploaded audio/video for duration, resolution, and codec.
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// ffprobe binary name or absolute path.
+    #[serde(default = "default_media_probe_ffprobe_path")]
+    pub ffprobe_path: String,
+    /// Maximum seconds to wait for a single probe.
+    #[serde(default = "default_media_probe_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+impl Default for MediaProbeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            ffprobe_path: default_media_probe_ffprobe_path(),
+            timeout_secs: default_media_probe_timeout_secs(),
+        }
+    }
+}
+
+/// Workspace version control and external media (`[workspace]` section).
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct WorkspaceConfig {
+    /// Keep the workspace in a local git repository and commit journal,
+    /// library, and trash changes as they happen. Remotes are never used.
+    #[serde(default)]
+    pub git_autocommit: bool,
+    /// Files larger than this many bytes are never committed.
+    #[serde(default = "default_workspace_git_max_file_bytes")]
+    pub git_max_file_bytes: u64,
+    /// Directories outside the workspace that symlinks inside it may lead
+    /// into, e.g. `["/mnt/nas/journal-media"]`. Read-only unless given as
+    /// `{ path = "...", writable = true }`.
+    #[serde(default)]
+    pub external_roots: Vec<ExternalRoot>,
+}
+
+/// One `[workspace] external_roots` entry: a bare path (read-only) or a
+/// table with `path` and `writable`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum ExternalRoot {
+    Path(PathBuf),
+    Table {
+        path: PathBuf,
+        #[serde(default)]
+        writable: bool,
+    },
+}
+
+impl ExternalRoot {
+    pub fn path(&self) -> &Path {
+        match self {
+            Self::Path(path) | Self::Table { path, .. } => path,
+        }
+    }
+
+    pub fn writable(&self) -> bool {
+        matches!(self, Self::Table { writable: true, .. })
+    }
+}
+
+fn default_workspace_git_max_file_bytes() -> u64 {
+    5 * 1024 * 1024
+}
+
+impl Default for WorkspaceConfig {
+    fn default() -> Self {
+        Self {
+            git_autocommit: false,
+            git_max_file_bytes: default_workspace_git_max_file_bytes(),
+            external_roots: Vec::new(),
+        }
+    }
+}
+
 /// Agent orchestration configuration (`[agent]` section).
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct AgentConfig {
@@ -828,7 +943,7 @@ pub struct GatewayConfig {
     /// Allow binding to non-localhost without a tunnel (default: false)
     #[serde(default)]
     pub allow_public_bind: bool,
-    /// Paired bearer tokens (managed automatically, not user-edited)
+    /// Salted hashes of paired bearer tokens (managed automatically, not user-edited)
     #[serde(default)]
     pub paired_tokens: Vec<String>,
 
@@ -840,11 +955,17 @@ pub struct GatewayConfig {
     #[serde(default = "default_webhook_rate_limit")]
     pub webhook_rate_limit_per_minute: u32,
 
-    /// Trust proxy-forwarded client IP headers (`X-Forwarded-For`, `X-Real-IP`).
-    /// Disabled by default; enable only behind a trusted reverse proxy.
+    /// Legacy switch: trust proxy-forwarded client IP headers (`X-Forwarded-For`,
+    /// `X-Real-IP`) from every peer. Ignored when `trusted_proxies` lists any range.
     #[serde(default)]
     pub trust_forwarded_headers: bool,
 
+    /// Reverse proxies, as CIDR ranges or single addresses, whose forwarded client
+    /// IP headers are honored (e.g. `["10.0.0.2/32", "fd00::/8"]`). Requests from
+    /// any other peer are keyed by the peer address.
+    #[serde(default)]
+    pub trusted_proxies: Vec<String>,
+
     /// Maximum distinct client keys tracked by gateway rate limiter maps.
     #[serde(default = "default_gateway_rate_limit_max_keys")]
     pub rate_limit_max_keys: usize,
@@ -863,6 +984,128 @@ pub struct GatewayConfig {
     /// development. Use this only when you intentionally need extra origins.
     #[serde(default)]
     pub desktop_cors_allowed_origins: Vec<String>,
+
+    /// Days deleted library files stay in `.trash/` before being purged (0 keeps them forever).
+    #[serde(default = "default_gateway_trash_retention_days")]
+    pub trash_retention_days: u32,
+
+    /// Maximum total size of `journals/media` in bytes; uploads beyond it are rejected (0 = no quota).
+    #[serde(default)]
+    pub media_quota_bytes: u64,
+
+    /// Free disk space (bytes) uploads must leave on the workspace filesystem (0 disables the check).
+    #[serde(default = "default_gateway_min_free_disk_bytes")]
+    pub min_free_disk_bytes: u64,
+
+    /// Largest request body accepted by the JSON API and webhook endpoints, in bytes.
+    #[serde(default = "default_gateway_max_body_bytes")]
+    pub max_body_bytes: usize,
+
+    /// Largest request body accepted by media upload and import endpoints, in bytes.
+    #[serde(default = "default_gateway_max_media_upload_bytes")]
+    pub max_media_upload_bytes: usize,
+
+    /// Largest request body accepted by the library and journal text endpoints, in bytes.
+    #[serde(default = "default_gateway_max_text_bytes")]
+    pub max_text_bytes: usize,
+
+    /// Previous versions kept per file under `.versions/` when library text is overwritten (0 disables).
+    #[serde(default = "default_gateway_text_versions_keep")]
+    pub text_versions_keep: usize,
+
+    /// Watch `journals/` and `posts/` for files created or changed outside the gateway and keep
+    /// their library metadata in step.
+    #[serde(default = "default_true")]
+    pub library_watch: bool,
+
+    /// Seconds between full library rescans while watching, which also recover from dropped
+    /// watcher events (0 rescans only at startup and after an overflow).
+    #[serde(default = "default_gateway_library_rescan_interval_secs")]
+    pub library_rescan_interval_secs: u64,
+
+    /// Tools run as background jobs on each media upload of a matching kind.
+    #[serde(default)]
+    pub auto_process: Vec<AutoProcessRule>,
+
+    /// PocketBase chat thread that receives auto-process job results. Unset posts nothing.
+    #[serde(default)]
+    pub auto_process_thread: Option<String>,
+
+    /// Models a `/webhook` request may select via `model`, besides the default model.
+    #[serde(default)]
+    pub allowed_models: Vec<String>,
+
+    /// Lowest `temperature` a `/webhook` request may set.
+    #[serde(default)]
+    pub webhook_temperature_min: f64,
+
+    /// Highest `temperature` a `/webhook` request may set.
+    #[serde(default = "default_gateway_webhook_temperature_max")]
+    pub webhook_temperature_max: f64,
+
+    /// How long `/api/models` reuses the provider's model catalog, in seconds (0 disables caching).
+    #[serde(default = "default_gateway_models_cache_ttl_secs")]
+    pub models_cache_ttl_secs: u64,
+
+    /// Hours an agent-proposed post draft waits for approval before it is rejected (0 = never expires).
+    #[serde(default = "default_gateway_post_draft_expiry_hours")]
+    pub post_draft_expiry_hours: u64,
+
+    /// Demo mode: mutating endpoints answer 403, tools refuse writes, and
+    /// background maintenance that writes or posts stays off. Also set by
+    /// `slowclaw gateway --read-only`.
+    #[serde(default)]
+    pub read_only: bool,
+
+    /// Serve the embedded web UI at `/` and `/_app`. When false only the API,
+    /// webhook, pairing, health, and metrics routes answer; anything else is
+    /// a JSON 404. Also cleared by `--no-ui`.
+    #[serde(default = "default_true")]
+    pub serve_ui: bool,
+
+    /// Path prefix the gateway is mounted under behind a reverse proxy, such
+    /// as `/slowclaw`. Every route moves under it, generated `/api/media/...`
+    /// URLs include it, and unprefixed paths answer 404. Empty serves at `/`.
+    #[serde(default)]
+    pub base_path: String,
+
+    /// Listen on this Unix domain socket instead of `host:port`. A stale
+    /// socket file left by an earlier run is replaced; a live one is an error.
+    /// Also set by `--uds`.
+    #[serde(default)]
+    pub uds_path: Option<String>,
+
+    /// Permission bits for the socket file (default: `0o600`, owner only).
+    #[serde(default = "default_gateway_uds_mode")]
+    pub uds_mode: u32,
+
+    /// Treat every socket client as paired, leaving access control to the
+    /// socket file's permissions. Has no effect without `uds_path`.
+    #[serde(default)]
+    pub uds_trusted: bool,
+
+    /// Print one JSON line with the bound address and pairing state on stdout
+    /// once listening, and send the human banner to stderr. Set by
+    /// `--startup-json` or `ZEROCLAW_GATEWAY_STARTUP_JSON`, never from config.toml.
+    #[serde(skip)]
+    pub startup_json: bool,
+}
+
+/// Tool run on every media upload of one kind (`[[gateway.auto_process]]`).
+///
+/// ```toml
+/// [[gateway.auto_process]]
+/// match_kind = "audio"
+/// tool = "audio_to_video"
+/// ```
+///
+/// The tool is called with `{ "path", "asset_id" }` for the uploaded file.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct AutoProcessRule {
+    /// Upload kind the rule applies to (`audio`, `video`, `image`, ...)
+    pub match_kind: String,
+    /// Name of the tool to run
+    pub tool: String,
 }
 
 fn default_gateway_port() -> u16 {
@@ -893,6 +1136,50 @@ fn default_gateway_idempotency_max_keys() -> usize {
     10_000
 }
 
+fn default_gateway_trash_retention_days() -> u32 {
+    30
+}
+
+fn default_gateway_min_free_disk_bytes() -> u64 {
+    1024 * 1024 * 1024
+}
+
+fn default_gateway_max_body_bytes() -> usize {
+    64 * 1024
+}
+
+fn default_gateway_max_media_upload_bytes() -> usize {
+    1024 * 1024 * 1024
+}
+
+fn default_gateway_max_text_bytes() -> usize {
+    1024 * 1024
+}
+
+fn default_gateway_text_versions_keep() -> usize {
+    20
+}
+
+fn default_gateway_library_rescan_interval_secs() -> u64 {
+    900
+}
+
+fn default_gateway_webhook_temperature_max() -> f64 {
+    2.0
+}
+
+fn default_gateway_models_cache_ttl_secs() -> u64 {
+    600
+}
+
+fn default_gateway_post_draft_expiry_hours() -> u64 {
+    72
+}
+
+fn default_gateway_uds_mode() -> u32 {
+    0o600
+}
+
 fn default_true() -> bool {
     true
 }
@@ -908,12 +1195,55 @@ impl Default for GatewayConfig {
             pair_rate_limit_per_minute: default_pair_rate_limit(),
             webhook_rate_limit_per_minute: default_webhook_rate_limit(),
             trust_forwarded_headers: false,
+            trusted_proxies: Vec::new(),
             rate_limit_max_keys: default_gateway_rate_limit_max_keys(),
             idempotency_ttl_secs: default_idempotency_ttl_secs(),
             idempotency_max_keys: default_gateway_idempotency_max_keys(),
             desktop_cors_allowed_origins: Vec::new(),
+            trash_retention_days: default_gateway_trash_retention_days(),
+            media_quota_bytes: 0,
+            min_free_disk_bytes: default_gateway_min_free_disk_bytes(),
+            max_body_bytes: default_gateway_max_body_bytes(),
+            max_media_upload_bytes: default_gateway_max_media_upload_bytes(),
+            max_text_bytes: default_gateway_max_text_bytes(),
+            text_versions_keep: default_gateway_text_versions_keep(),
+            library_watch: true,
+            library_rescan_interval_secs: default_gateway_library_rescan_interval_secs(),
+            auto_process: Vec::new(),
+            auto_process_thread: None,
+            allowed_models: Vec::new(),
+            webhook_temperature_min: 0.0,
+            webhook_temperature_max: default_gateway_webhook_temperature_max(),
+            models_cache_ttl_secs: default_gateway_models_cache_ttl_secs(),
+            post_draft_expiry_hours: default_gateway_post_draft_expiry_hours(),
+            read_only: false,
+            serve_ui: true,
+            base_path: String::new(),
+            uds_path: None,
+            uds_mode: default_gateway_uds_mode(),
+            uds_trusted: false,
+            startup_json: false,
+        }
+    }
+}
+
+impl GatewayConfig {
+    /// `base_path` with one leading slash and no trailing slash, or an empty
+    /// string when the gateway is served at `/`.
+    pub fn route_prefix(&self) -> String {
+        let trimmed = self.base_path.trim().trim_matches('/');
+        if trimmed.is_empty() {
+            String::new()
+        } else {
+            format!("/{trimmed}")
         }
     }
+
+    /// Whether socket clients skip pairing: the gateway listens on a Unix
+    /// socket and `uds_trusted` is set.
+    pub fn uds_satisfies_pairing(&self) -> bool {
+        self.uds_path.is_some() && self.uds_trusted
+    }
 }
 
 // ── Composio (managed tool surface) ─────────────────────────────
@@ -1771,6 +2101,17 @@ impl Default for QdrantConfig {
     }
 }
 
+/// How [`MemoryConfig::dedupe_mode`] compares a new conversation entry with recent ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum MemoryDedupeMode {
+    /// Store every entry.
+    #[default]
+    Off,
+    /// Skip entries whose trimmed, lowercased, whitespace-collapsed content matches.
+    Exact,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[allow(clippy::struct_excessive_bools)]
 pub struct MemoryConfig {
@@ -1793,6 +2134,23 @@ pub struct MemoryConfig {
     /// For sqlite backend: prune conversation rows older than this many days
     #[serde(default = "default_conversation_retention_days")]
     pub conversation_retention_days: u32,
+    /// Delete conversation-category entries older than this many days, on any
+    /// backend. 0 = no expiry (default)
+    #[serde(default)]
+    pub conversation_ttl_days: u32,
+    /// Keep at most this many entries per category, evicting oldest first.
+    /// Enforced by the retention task even without a TTL; 0 = unlimited
+    #[serde(default)]
+    pub max_entries_per_category: usize,
+    /// Only log what the retention task would purge, never delete
+    #[serde(default)]
+    pub retention_dry_run: bool,
+    /// Skip storing conversation entries that duplicate a recent one: "off" | "exact"
+    #[serde(default)]
+    pub dedupe_mode: MemoryDedupeMode,
+    /// How far back (minutes) `dedupe_mode` looks for a duplicate
+    #[serde(default = "default_dedupe_window_minutes")]
+    pub dedupe_window_minutes: u32,
     /// Embedding provider: "builtin" | "none" | "openai" | "openrouter" | "custom:URL"
     #[serde(default = "default_embedding_provider")]
     pub embedding_provider: String,
@@ -1870,6 +2228,9 @@ fn default_purge_after_days() -> u32 {
 fn default_conversation_retention_days() -> u32 {
     30
 }
+fn default_dedupe_window_minutes() -> u32 {
+    1_440
+}
 fn default_embedding_model() -> String {
     "builtin-384-v1".into()
 }
@@ -1907,6 +2268,11 @@ impl Default for MemoryConfig {
             archive_after_days: default_archive_after_days(),
             purge_after_days: default_purge_after_days(),
             conversation_retention_days: default_conversation_retention_days(),
+            conversation_ttl_days: 0,
+            max_entries_per_category: 0,
+            retention_dry_run: false,
+            dedupe_mode: MemoryDedupeMode::Off,
+            dedupe_window_minutes: default_dedupe_window_minutes(),
             embedding_provider: default_embedding_provider(),
             embedding_model: default_embedding_model(),
             embedding_dimensions: default_embedding_dims(),
@@ -1958,6 +2324,11 @@ pub struct ObservabilityConfig {
     /// "none" | "log" | "prometheus" | "otel"
     pub backend: String,
 
+    /// Several backends at once (e.g. `["prometheus", "log"]`). When set,
+    /// this replaces `backend`.
+    #[serde(default)]
+    pub backends: Vec<String>,
+
     /// OTLP endpoint (e.g. "http://localhost:4318"). Only used when backend = "otel".
     #[serde(default)]
     pub otel_endpoint: Option<String>,
@@ -1966,6 +2337,46 @@ pub struct ObservabilityConfig {
     #[serde(default)]
     pub otel_service_name: Option<String>,
 
+    /// Extra HTTP headers sent with every OTLP export (e.g. collector auth).
+    #[serde(default)]
+    pub otel_headers: HashMap<String, String>,
+
+    /// Maximum spans buffered for export. When the collector is down the
+    /// queue fills and new spans are dropped instead of blocking requests.
+    #[serde(default = "default_otel_queue_size")]
+    pub otel_queue_size: usize,
+
+    /// Outbound webhook for runtime alerts (LLM failures, failed components,
+    /// PocketBase outages, disk limits). Unset disables alerting.
+    #[serde(default)]
+    pub alert_webhook_url: Option<String>,
+
+    /// Secret for the `X-Slowclaw-Signature` HMAC-SHA256 header on alert posts.
+    #[serde(default)]
+    pub alert_webhook_secret: Option<String>,
+
+    /// Consecutive failed LLM calls before an alert opens.
+    #[serde(default = "default_alert_llm_failure_threshold")]
+    pub alert_llm_failure_threshold: u32,
+
+    /// Minutes PocketBase must stay unreachable before an alert opens. `0` disables the check.
+    #[serde(default = "default_alert_pocketbase_down_minutes")]
+    pub alert_pocketbase_down_minutes: u64,
+
+    /// PocketBase chat thread that receives a periodic status summary
+    /// (uptime, message and tool counts, top errors). Unset disables it.
+    #[serde(default)]
+    pub heartbeat_thread: Option<String>,
+
+    /// Hours between status summaries posted to `heartbeat_thread`.
+    #[serde(default = "default_heartbeat_interval_hours")]
+    pub heartbeat_interval_hours: u64,
+
+    /// Keep full error text in the gateway activity timeline (`/api/activity`).
+    /// Off by default so the UI list only shows short summaries.
+    #[serde(default)]
+    pub debug_payloads: bool,
+
     /// Runtime trace storage mode: "none" | "rolling" | "full".
     /// Controls whether model replies and tool-call diagnostics are persisted.
     #[serde(default = "default_runtime_trace_mode")]
@@ -1984,8 +2395,18 @@ impl Default for ObservabilityConfig {
     fn default() -> Self {
         Self {
             backend: "none".into(),
+            backends: Vec::new(),
             otel_endpoint: None,
             otel_service_name: None,
+            otel_headers: HashMap::new(),
+            otel_queue_size: default_otel_queue_size(),
+            alert_webhook_url: None,
+            alert_webhook_secret: None,
+            alert_llm_failure_threshold: default_alert_llm_failure_threshold(),
+            alert_pocketbase_down_minutes: default_alert_pocketbase_down_minutes(),
+            heartbeat_thread: None,
+            heartbeat_interval_hours: default_heartbeat_interval_hours(),
+            debug_payloads: false,
             runtime_trace_mode: default_runtime_trace_mode(),
             runtime_trace_path: default_runtime_trace_path(),
             runtime_trace_max_entries: default_runtime_trace_max_entries(),
@@ -1993,6 +2414,22 @@ impl Default for ObservabilityConfig {
     }
 }
 
+fn default_alert_llm_failure_threshold() -> u32 {
+    3
+}
+
+fn default_alert_pocketbase_down_minutes() -> u64 {
+    10
+}
+
+fn default_heartbeat_interval_hours() -> u64 {
+    24
+}
+
+fn default_otel_queue_size() -> usize {
+    2_048
+}
+
 fn default_runtime_trace_mode() -> String {
     "none".to_string()
 }
@@ -2096,6 +2533,22 @@ pub struct AutonomyConfig {
     /// model in tool specs.
     #[serde(default)]
     pub non_cli_excluded_tools: Vec<String>,
+
+    /// Hourly budget cost per tool name, for tools heavier than a single
+    /// action (e.g. `audio_to_video = 10`). Unlisted tools cost 1.
+    #[serde(default)]
+    pub tool_action_weights: HashMap<String, u32>,
+
+    /// Tools that must be approved before they run, at any autonomy level.
+    /// Chat runs park the call and ask in the originating thread; the CLI
+    /// prompts.
+    #[serde(default)]
+    pub requires_approval: Vec<String>,
+
+    /// Seconds a parked chat approval waits before the call is denied.
+    /// Default: `3600`.
+    #[serde(default = "default_approval_timeout_secs")]
+    pub approval_timeout_secs: u64,
 }
 
 fn default_auto_approve() -> Vec<String> {
@@ -2106,6 +2559,10 @@ fn default_always_ask() -> Vec<String> {
     vec![]
 }
 
+fn default_approval_timeout_secs() -> u64 {
+    3600
+}
+
 fn is_valid_env_var_name(name: &str) -> bool {
     let mut chars = name.chars();
     match chars.next() {
@@ -2165,6 +2622,9 @@ impl Default for AutonomyConfig {
             always_ask: default_always_ask(),
             allowed_roots: Vec::new(),
             non_cli_excluded_tools: Vec::new(),
+            tool_action_weights: HashMap::new(),
+            requires_approval: Vec::new(),
+            approval_timeout_secs: default_approval_timeout_secs(),
         }
     }
 }
@@ -2778,6 +3238,14 @@ impl ChannelsConfig {
     }
 }
 
+/// Channel sections this build can serve; [`Config::validate`] rejects any
+/// other configured channel.
+fn channel_supported(name: &str) -> bool {
+    name == TelegramConfig::name()
+        || name == crate::channels::email_channel::EmailConfig::name()
+        || (cfg!(feature = "channel-nextcloud") && name == NextcloudTalkConfig::name())
+}
+
 fn default_channel_message_timeout_secs() -> u64 {
     300
 }
@@ -2847,6 +3315,14 @@ pub struct TelegramConfig {
     /// Direct messages are always processed.
     #[serde(default)]
     pub mention_only: bool,
+    /// Chat ids the bot answers in. Empty = any chat an allowed user writes from.
+    #[serde(default)]
+    pub allowed_chats: Vec<String>,
+    /// Secret token for webhook mode. When set, updates arrive on the gateway's
+    /// `POST /telegram` route with a matching `X-Telegram-Bot-Api-Secret-Token`
+    /// header and `getUpdates` polling is skipped.
+    #[serde(default)]
+    pub webhook_secret: Option<String>,
 }
 
 impl ChannelConfig for TelegramConfig {
@@ -3072,6 +3548,17 @@ pub struct WhatsAppConfig {
     /// Allowed phone numbers (E.164 format: +1234567890) or "*" for all
     #[serde(default)]
     pub allowed_numbers: Vec<String>,
+    /// Approved message template sent instead of a reply once the 24-hour
+    /// customer service window has closed (Cloud API mode). It must take one
+    /// body parameter, which receives the reply text.
+    #[serde(default)]
+    pub reply_template: Option<String>,
+    /// Language code of `reply_template` (default: `en_US`)
+    #[serde(default)]
+    pub reply_template_language: Option<String>,
+    /// Graph API base URL (default: `https://graph.facebook.com/v21.0`)
+    #[serde(default)]
+    pub api_url: Option<String>,
 }
 
 impl ChannelConfig for WhatsAppConfig {
@@ -3716,6 +4203,8 @@ impl Default for Config {
             hardware: HardwareConfig::default(),
             query_classification: QueryClassificationConfig::default(),
             transcription: TranscriptionConfig::default(),
+            media_probe: MediaProbeConfig::default(),
+            workspace: WorkspaceConfig::default(),
         }
     }
 }
@@ -4068,6 +4557,12 @@ impl Config {
                 "config.storage.provider.config.db_url",
             )?;
 
+            decrypt_optional_secret(
+                &store,
+                &mut config.observability.alert_webhook_secret,
+                "config.observability.alert_webhook_secret",
+            )?;
+
             for agent in config.agents.values_mut() {
                 decrypt_optional_secret(&store, &mut agent.api_key, "config.agents.*.api_key")?;
             }
@@ -4210,6 +4705,40 @@ impl Config {
         if self.gateway.host.trim().is_empty() {
             anyhow::bail!("gateway.host must not be empty");
         }
+        if self.gateway.max_body_bytes == 0 {
+            anyhow::bail!("gateway.max_body_bytes must be greater than 0");
+        }
+        if self.gateway.max_media_upload_bytes < self.gateway.max_body_bytes {
+            anyhow::bail!(
+                "gateway.max_media_upload_bytes must be greater than or equal to gateway.max_body_bytes"
+            );
+        }
+        let route_prefix = self.gateway.route_prefix();
+        if route_prefix.split('/').skip(1).any(|segment| {
+            segment.is_empty()
+                || !segment
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '~'))
+        }) {
+            anyhow::bail!(
+                "gateway.base_path must be plain path segments of letters, digits, '-', '_', '.', or '~'"
+            );
+        }
+        for (index, entry) in self.gateway.trusted_proxies.iter().enumerate() {
+            if crate::security::trusted_proxies::IpRange::parse(entry).is_none() {
+                anyhow::bail!(
+                    "gateway.trusted_proxies[{index}] must be an IP address or CIDR range, got {entry:?}"
+                );
+            }
+        }
+        if let Some(path) = &self.gateway.uds_path {
+            if path.trim().is_empty() {
+                anyhow::bail!("gateway.uds_path must not be empty when set");
+            }
+        }
+        if self.gateway.uds_mode > 0o777 {
+            anyhow::bail!("gateway.uds_mode must be permission bits no greater than 0o777");
+        }
         for (index, origin) in self.gateway.desktop_cors_allowed_origins.iter().enumerate() {
             if origin.trim().is_empty() {
                 anyhow::bail!(
@@ -4218,6 +4747,16 @@ impl Config {
             }
         }
 
+        // Provider response cache
+        if !(0.0..=2.0).contains(&self.provider.cache_max_temperature) {
+            anyhow::bail!("provider.cache_max_temperature must be between 0.0 and 2.0");
+        }
+        if self.provider.cache_ttl_secs > 0 && self.provider.cache_max_entries == 0 {
+            anyhow::bail!(
+                "provider.cache_max_entries must be greater than 0 when provider.cache_ttl_secs is set"
+            );
+        }
+
         // Autonomy
         if self.autonomy.max_actions_per_hour == 0 {
             anyhow::bail!("autonomy.max_actions_per_hour must be greater than 0");
@@ -4289,12 +4828,15 @@ impl Config {
             .channels_config
             .channels()
             .iter()
-            .any(|(_, configured)| *configured)
+            .any(|(handle, configured)| *configured && !channel_supported(handle.name()))
         {
             anyhow::bail!(
-                "External channel integrations (Telegram/Discord/Slack/etc. and webhook) are disabled in this fork"
+                "External channel integrations other than Telegram, Email (and Nextcloud Talk with the channel-nextcloud feature) are disabled in this fork"
             );
         }
+        if let Some(email) = &self.channels_config.email {
+            crate::channels::EmailChannel::from_config(email)?;
+        }
         if self.composio.enabled {
             anyhow::bail!("[composio] is not supported in this workspace-only build");
         }
@@ -4553,6 +5095,11 @@ impl Config {
             self.gateway.allow_public_bind = val == "1" || val.eq_ignore_ascii_case("true");
         }
 
+        // Machine-readable gateway startup line: ZEROCLAW_GATEWAY_STARTUP_JSON
+        if let Ok(val) = std::env::var("ZEROCLAW_GATEWAY_STARTUP_JSON") {
+            self.gateway.startup_json = val == "1" || val.eq_ignore_ascii_case("true");
+        }
+
         // Temperature: ZEROCLAW_TEMPERATURE
         if let Ok(temp_str) = std::env::var("ZEROCLAW_TEMPERATURE") {
             if let Ok(temp) = temp_str.parse::<f64>() {
@@ -4752,6 +5299,12 @@ impl Config {
             "config.storage.provider.config.db_url",
         )?;
 
+        encrypt_optional_secret(
+            &store,
+            &mut config_to_save.observability.alert_webhook_secret,
+            "config.observability.alert_webhook_secret",
+        )?;
+
         for agent in config_to_save.agents.values_mut() {
             encrypt_optional_secret(&store, &mut agent.api_key, "config.agents.*.api_key")?;
         }
@@ -4904,6 +5457,8 @@ mod tests {
         assert!((c.default_temperature - 0.2).abs() < f64::EPSILON);
         assert_eq!(c.provider.transport, "auto");
         assert_eq!(c.provider.reasoning_level, "high");
+        assert_eq!(c.provider.cache_ttl_secs, 0);
+        assert_eq!(c.provider.cache_max_entries, 1000);
         assert!(c.api_key.is_none());
         assert!(!c.skills.open_skills_enabled);
         assert_eq!(
@@ -5039,6 +5594,26 @@ recipient = "42"
         assert_eq!(parsed.to.as_deref(), Some("42"));
     }
 
+    #[test]
+    async fn workspace_external_roots_accept_paths_and_tables() {
+        let raw = r#"
+external_roots = ["/mnt/nas/journal-media", { path = "/mnt/nas/drafts", writable = true }]
+"#;
+        let parsed: WorkspaceConfig = toml::from_str(raw).unwrap();
+        let roots: Vec<_> = parsed
+            .external_roots
+            .iter()
+            .map(|root| (root.path().to_string_lossy().into_owned(), root.writable()))
+            .collect();
+        assert_eq!(
+            roots,
+            [
+                ("/mnt/nas/journal-media".to_string(), false),
+                ("/mnt/nas/drafts".to_string(), true),
+            ]
+        );
+    }
+
     #[test]
     async fn cron_config_default() {
         let c = CronConfig::default();
@@ -5083,6 +5658,11 @@ default_temperature = 0.7
         assert_eq!(m.archive_after_days, 7);
         assert_eq!(m.purge_after_days, 30);
         assert_eq!(m.conversation_retention_days, 30);
+        assert_eq!(m.conversation_ttl_days, 0);
+        assert_eq!(m.max_entries_per_category, 0);
+        assert!(!m.retention_dry_run);
+        assert_eq!(m.dedupe_mode, MemoryDedupeMode::Off);
+        assert_eq!(m.dedupe_window_minutes, 1_440);
         assert!(m.sqlite_open_timeout_secs.is_none());
     }
 
@@ -5159,6 +5739,9 @@ default_temperature = 0.7
                 always_ask: vec![],
                 allowed_roots: vec![],
                 non_cli_excluded_tools: vec![],
+                tool_action_weights: HashMap::new(),
+                requires_approval: vec!["bluesky_post".into()],
+                approval_timeout_secs: 600,
             },
             security: SecurityConfig::default(),
             runtime: RuntimeConfig {
@@ -5188,6 +5771,8 @@ default_temperature = 0.7
                     draft_update_interval_ms: default_draft_update_interval_ms(),
                     interrupt_on_new_message: false,
                     mention_only: false,
+                    allowed_chats: vec![],
+                    webhook_secret: None,
                 }),
                 discord: None,
                 slack: None,
@@ -5230,6 +5815,8 @@ default_temperature = 0.7
             hooks: HooksConfig::default(),
             hardware: HardwareConfig::default(),
             transcription: TranscriptionConfig::default(),
+            media_probe: MediaProbeConfig::default(),
+            workspace: WorkspaceConfig::default(),
         };
 
         let toml_str = toml::to_string_pretty(&config).unwrap();
@@ -5413,6 +6000,8 @@ tool_dispatcher = "xml"
             hooks: HooksConfig::default(),
             hardware: HardwareConfig::default(),
             transcription: TranscriptionConfig::default(),
+            media_probe: MediaProbeConfig::default(),
+            workspace: WorkspaceConfig::default(),
         };
 
         config.save().await.unwrap();
@@ -5560,6 +6149,8 @@ tool_dispatcher = "xml"
             draft_update_interval_ms: 500,
             interrupt_on_new_message: true,
             mention_only: false,
+            allowed_chats: vec![],
+            webhook_secret: None,
         };
         let json = serde_json::to_string(&tc).unwrap();
         let parsed: TelegramConfig = serde_json::from_str(&json).unwrap();
@@ -5871,6 +6462,9 @@ channel_id = "C123"
             pair_phone: None,
             pair_code: None,
             allowed_numbers: vec!["+1234567890".into(), "+9876543210".into()],
+            reply_template: None,
+            reply_template_language: None,
+            api_url: None,
         };
         let json = serde_json::to_string(&wc).unwrap();
         let parsed: WhatsAppConfig = serde_json::from_str(&json).unwrap();
@@ -5891,11 +6485,16 @@ channel_id = "C123"
             pair_phone: None,
             pair_code: None,
             allowed_numbers: vec!["+1".into()],
+            reply_template: Some("slowclaw_reply".into()),
+            reply_template_language: Some("en_GB".into()),
+            api_url: None,
         };
         let toml_str = toml::to_string(&wc).unwrap();
         let parsed: WhatsAppConfig = toml::from_str(&toml_str).unwrap();
         assert_eq!(parsed.phone_number_id, Some("12345".into()));
         assert_eq!(parsed.allowed_numbers, vec!["+1"]);
+        assert_eq!(parsed.reply_template.as_deref(), Some("slowclaw_reply"));
+        assert_eq!(parsed.reply_template_language.as_deref(), Some("en_GB"));
     }
 
     #[test]
@@ -5916,6 +6515,9 @@ channel_id = "C123"
             pair_phone: None,
             pair_code: None,
             allowed_numbers: vec!["*".into()],
+            reply_template: None,
+            reply_template_language: None,
+            api_url: None,
         };
         let toml_str = toml::to_string(&wc).unwrap();
         let parsed: WhatsAppConfig = toml::from_str(&toml_str).unwrap();
@@ -5933,6 +6535,9 @@ channel_id = "C123"
             pair_phone: None,
             pair_code: None,
             allowed_numbers: vec!["+1".into()],
+            reply_template: None,
+            reply_template_language: None,
+            api_url: None,
         };
         assert!(wc.is_ambiguous_config());
         assert_eq!(wc.backend_type(), "cloud");
@@ -5949,6 +6554,9 @@ channel_id = "C123"
             pair_phone: None,
             pair_code: None,
             allowed_numbers: vec![],
+            reply_template: None,
+            reply_template_language: None,
+            api_url: None,
         };
         assert!(!wc.is_ambiguous_config());
         assert_eq!(wc.backend_type(), "web");
@@ -5975,6 +6583,9 @@ channel_id = "C123"
                 pair_phone: None,
                 pair_code: None,
                 allowed_numbers: vec!["+1".into()],
+                reply_template: None,
+                reply_template_language: None,
+                api_url: None,
             }),
             linq: None,
             wati: None,
@@ -6070,6 +6681,7 @@ channel_id = "C123"
             pair_rate_limit_per_minute: 12,
             webhook_rate_limit_per_minute: 80,
             trust_forwarded_headers: true,
+            trusted_proxies: vec!["10.0.0.2/32".into(), "fd00::/8".into()],
             rate_limit_max_keys: 2048,
             idempotency_ttl_secs: 600,
             idempotency_max_keys: 4096,
@@ -6077,6 +6689,32 @@ channel_id = "C123"
                 "http://localhost:1420".into(),
                 "https://review.example".into(),
             ],
+            trash_retention_days: 14,
+            media_quota_bytes: 5_000_000_000,
+            min_free_disk_bytes: 0,
+            max_body_bytes: 131_072,
+            max_media_upload_bytes: 10_000_000,
+            max_text_bytes: 65_536,
+            text_versions_keep: 5,
+            library_watch: false,
+            library_rescan_interval_secs: 60,
+            auto_process: vec![AutoProcessRule {
+                match_kind: "audio".into(),
+                tool: "audio_to_video".into(),
+            }],
+            auto_process_thread: Some("pipeline-thread".into()),
+            allowed_models: vec!["openai/gpt-4o-mini".into()],
+            webhook_temperature_min: 0.1,
+            webhook_temperature_max: 1.2,
+            models_cache_ttl_secs: 120,
+            post_draft_expiry_hours: 24,
+            read_only: true,
+            serve_ui: false,
+            base_path: "/slowclaw".into(),
+            uds_path: Some("/run/slowclaw/gateway.sock".into()),
+            uds_mode: 0o660,
+            uds_trusted: true,
+            startup_json: true,
         };
         let toml_str = toml::to_string(&g).unwrap();
         let parsed: GatewayConfig = toml::from_str(&toml_str).unwrap();
@@ -6086,6 +6724,7 @@ channel_id = "C123"
         assert_eq!(parsed.pair_rate_limit_per_minute, 12);
         assert_eq!(parsed.webhook_rate_limit_per_minute, 80);
         assert!(parsed.trust_forwarded_headers);
+        assert_eq!(parsed.trusted_proxies, vec!["10.0.0.2/32", "fd00::/8"]);
         assert_eq!(parsed.rate_limit_max_keys, 2048);
         assert_eq!(parsed.idempotency_ttl_secs, 600);
         assert_eq!(parsed.idempotency_max_keys, 4096);
@@ -6093,6 +6732,38 @@ channel_id = "C123"
             parsed.desktop_cors_allowed_origins,
             vec!["http://localhost:1420", "https://review.example"]
         );
+        assert_eq!(parsed.media_quota_bytes, 5_000_000_000);
+        assert_eq!(parsed.min_free_disk_bytes, 0);
+        assert_eq!(parsed.max_body_bytes, 131_072);
+        assert_eq!(parsed.max_media_upload_bytes, 10_000_000);
+        assert_eq!(parsed.max_text_bytes, 65_536);
+        assert_eq!(parsed.text_versions_keep, 5);
+        assert!(!parsed.library_watch);
+        assert_eq!(parsed.library_rescan_interval_secs, 60);
+        assert_eq!(parsed.auto_process.len(), 1);
+        assert_eq!(parsed.auto_process[0].match_kind, "audio");
+        assert_eq!(parsed.auto_process[0].tool, "audio_to_video");
+        assert_eq!(
+            parsed.auto_process_thread.as_deref(),
+            Some("pipeline-thread")
+        );
+        assert_eq!(parsed.allowed_models, vec!["openai/gpt-4o-mini"]);
+        assert!((parsed.webhook_temperature_min - 0.1).abs() < f64::EPSILON);
+        assert!((parsed.webhook_temperature_max - 1.2).abs() < f64::EPSILON);
+        assert_eq!(parsed.models_cache_ttl_secs, 120);
+        assert!(parsed.read_only);
+        assert!(!parsed.serve_ui);
+        assert_eq!(parsed.base_path, "/slowclaw");
+        assert_eq!(
+            parsed.uds_path.as_deref(),
+            Some("/run/slowclaw/gateway.sock")
+        );
+        assert_eq!(parsed.uds_mode, 0o660);
+        assert!(parsed.uds_trusted);
+        assert!(
+            !toml_str.contains("startup_json") && !parsed.startup_json,
+            "startup_json is a runtime flag and must not round-trip through config.toml"
+        );
     }
 
     #[test]
@@ -6670,6 +7341,102 @@ requires_openai_auth = true
             .contains("wire_api must be one of: responses, chat_completions"));
     }
 
+    #[test]
+    async fn validate_rejects_media_upload_limit_below_body_limit() {
+        let mut config = Config::default();
+        config.gateway.max_body_bytes = 4096;
+        config.gateway.max_media_upload_bytes = 4096;
+        assert!(config.validate().is_ok());
+
+        config.gateway.max_media_upload_bytes = 4095;
+        let error = config.validate().expect_err("expected validation failure");
+        assert!(error.to_string().contains("gateway.max_media_upload_bytes"));
+    }
+
+    #[test]
+    async fn validate_rejects_malformed_trusted_proxies() {
+        let mut config = Config::default();
+        config.gateway.trusted_proxies = vec![
+            "10.0.0.2/32".into(),
+            "fd00::/8".into(),
+            "192.168.1.4".into(),
+        ];
+        assert!(config.validate().is_ok());
+
+        config.gateway.trusted_proxies.push("10.0.0.0/33".into());
+        let error = config.validate().expect_err("expected validation failure");
+        assert!(error.to_string().contains("gateway.trusted_proxies[3]"));
+    }
+
+    #[test]
+    async fn gateway_base_path_is_normalized_and_validated() {
+        let mut config = Config::default();
+        assert_eq!(config.gateway.route_prefix(), "");
+        for (raw, prefix) in [
+            ("/", ""),
+            ("slowclaw", "/slowclaw"),
+            ("/slowclaw/", "/slowclaw"),
+            (" /apps/slowclaw// ", "/apps/slowclaw"),
+        ] {
+            config.gateway.base_path = raw.into();
+            assert_eq!(config.gateway.route_prefix(), prefix, "{raw:?}");
+            assert!(config.validate().is_ok(), "{raw:?}");
+        }
+
+        for raw in ["/a//b", "/slow claw", "/{id}", "/x?y=1"] {
+            config.gateway.base_path = raw.into();
+            let error = config.validate().expect_err("expected validation failure");
+            assert!(error.to_string().contains("gateway.base_path"), "{raw:?}");
+        }
+    }
+
+    #[test]
+    async fn gateway_uds_settings_are_validated() {
+        let mut config = Config::default();
+        assert_eq!(config.gateway.uds_mode, 0o600);
+        assert!(!config.gateway.uds_satisfies_pairing());
+
+        config.gateway.uds_trusted = true;
+        assert!(!config.gateway.uds_satisfies_pairing());
+        config.gateway.uds_path = Some("/tmp/slowclaw.sock".into());
+        assert!(config.gateway.uds_satisfies_pairing());
+        assert!(config.validate().is_ok());
+
+        config.gateway.uds_mode = 0o1777;
+        let error = config.validate().expect_err("expected validation failure");
+        assert!(error.to_string().contains("gateway.uds_mode"));
+
+        config.gateway.uds_mode = 0o600;
+        config.gateway.uds_path = Some("  ".into());
+        let error = config.validate().expect_err("expected validation failure");
+        assert!(error.to_string().contains("gateway.uds_path"));
+
+        let parsed: GatewayConfig =
+            toml::from_str("uds_path = \"/tmp/g.sock\"\nuds_mode = 0o660").unwrap();
+        assert_eq!(parsed.uds_mode, 0o660);
+    }
+
+    #[test]
+    async fn provider_cache_settings_are_validated() {
+        let mut config = Config::default();
+        config.provider.cache_ttl_secs = 3600;
+        assert!(config.validate().is_ok());
+
+        config.provider.cache_max_entries = 0;
+        let error = config.validate().expect_err("expected validation failure");
+        assert!(error.to_string().contains("provider.cache_max_entries"));
+
+        config.provider.cache_max_entries = 10;
+        config.provider.cache_max_temperature = 2.5;
+        let error = config.validate().expect_err("expected validation failure");
+        assert!(error.to_string().contains("provider.cache_max_temperature"));
+
+        let parsed: ProviderConfig = toml::from_str("cache_ttl_secs = 600").unwrap();
+        assert_eq!(parsed.cache_ttl_secs, 600);
+        assert_eq!(parsed.cache_max_entries, 1000);
+        assert_eq!(parsed.transport, "auto");
+    }
+
     #[test]
     async fn env_override_model_fallback() {
         let _env_guard = env_override_lock().await;
@@ -7038,6 +7805,19 @@ default_model = "legacy-model"
         std::env::remove_var("HOST");
     }
 
+    #[test]
+    async fn env_override_gateway_startup_json() {
+        let _env_guard = env_override_lock().await;
+        let mut config = Config::default();
+        assert!(!config.gateway.startup_json);
+
+        std::env::set_var("ZEROCLAW_GATEWAY_STARTUP_JSON", "1");
+        config.apply_env_overrides();
+        assert!(config.gateway.startup_json);
+
+        std::env::remove_var("ZEROCLAW_GATEWAY_STARTUP_JSON");
+    }
+
     #[test]
     async fn env_override_temperature() {
         let _env_guard = env_override_lock().await;
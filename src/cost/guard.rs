@@ -0,0 +1,599 @@
+//! Spend caps from `[cost]`, checked before every provider call.
+//!
+//! With `[cost] enabled = true`, the gateway and agent runs wrap their
+//! provider in a [`BudgetGuard`]. Each call is priced from the prompt size
+//! (about four bytes per token) before it is sent, and refused with
+//! [`BudgetExceeded`] when it would take the day's or month's spend past its
+//! limit. What the call actually cost is recorded afterwards, from the token
+//! counts the provider reports or from the reply size when it reports none.
+//! Models missing from `[cost.prices]` are charged at the highest listed
+//! price, so an unpriced model name cannot slip past the cap.
+//!
+//! Spend lives in the workspace's cost log, so it survives restarts.
+//! Callers allowed to go over budget run inside [`with_budget_override`];
+//! it only has an effect when `[cost] allow_override` is set.
+
+use super::tracker::CostTracker;
+use super::types::{BudgetCheck, TokenUsage, UsagePeriod};
+use crate::config::schema::{CostConfig, ModelPricing};
+use crate::providers::traits::{
+    ChatMessage, ChatRequest, ChatResponse, ModelInfo, Provider, ProviderCapabilities,
+    ProviderIntrospection, StreamChunk, StreamError, StreamOptions, StreamResult, ToolsPayload,
+};
+use crate::tools::ToolSpec;
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use futures_util::{stream, StreamExt};
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::Path;
+
+tokio::task_local! {
+    static BUDGET_OVERRIDE: bool;
+}
+
+/// Run `fut` with the budget lifted when `enabled` is set and the config
+/// allows overrides. For emergencies; the spend is still recorded.
+pub async fn with_budget_override<F: Future>(enabled: bool, fut: F) -> F::Output {
+    BUDGET_OVERRIDE.scope(enabled, fut).await
+}
+
+fn override_requested() -> bool {
+    BUDGET_OVERRIDE
+        .try_with(|enabled| *enabled)
+        .unwrap_or(false)
+}
+
+/// A provider call refused because it would exceed a spend limit.
+#[derive(Debug, Clone)]
+pub struct BudgetExceeded {
+    pub period: UsagePeriod,
+    pub spent_usd: f64,
+    pub limit_usd: f64,
+    pub resets_at: DateTime<Utc>,
+}
+
+impl std::fmt::Display for BudgetExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let period = match self.period {
+            UsagePeriod::Month => "Monthly",
+            UsagePeriod::Day | UsagePeriod::Session => "Daily",
+        };
+        write!(
+            f,
+            "{period} budget exceeded (${:.2} of ${:.2} spent), resets at {} UTC",
+            self.spent_usd,
+            self.limit_usd,
+            self.resets_at.format("%Y-%m-%d %H:%M")
+        )
+    }
+}
+
+impl std::error::Error for BudgetExceeded {}
+
+/// The refusal behind `err`, if a budget check stopped it.
+pub fn budget_exceeded(err: &anyhow::Error) -> Option<&BudgetExceeded> {
+    err.chain()
+        .find_map(|source| source.downcast_ref::<BudgetExceeded>())
+}
+
+/// When the spend for `period` starts over: the next UTC midnight, or the
+/// first of next month.
+pub fn resets_at(period: UsagePeriod, now: DateTime<Utc>) -> DateTime<Utc> {
+    let today = now.date_naive();
+    let next = match period {
+        UsagePeriod::Month if today.month() == 12 => {
+            NaiveDate::from_ymd_opt(today.year() + 1, 1, 1)
+        }
+        UsagePeriod::Month => NaiveDate::from_ymd_opt(today.year(), today.month() + 1, 1),
+        UsagePeriod::Day | UsagePeriod::Session => today.succ_opt(),
+    };
+    next.and_then(|day| day.and_hms_opt(0, 0, 0))
+        .map_or(now, |midnight| midnight.and_utc())
+}
+
+/// Spend against one limit, as reported by `/health` and `/api/usage`.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct PeriodSpend {
+    pub spent_usd: f64,
+    pub limit_usd: f64,
+    pub resets_at: DateTime<Utc>,
+}
+
+/// Where the workspace stands against its `[cost]` limits.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct BudgetStatus {
+    pub daily: PeriodSpend,
+    pub monthly: PeriodSpend,
+    /// `"day"` or `"month"` once that limit is spent.
+    pub exceeded: Option<&'static str>,
+    pub allow_override: bool,
+}
+
+/// Current spend for `workspace_dir`, or `None` while cost tracking is off.
+pub fn budget_status(config: &CostConfig, workspace_dir: &Path) -> Result<Option<BudgetStatus>> {
+    if !config.enabled {
+        return Ok(None);
+    }
+    let summary = CostTracker::for_workspace(config.clone(), workspace_dir)?.get_summary()?;
+    let now = Utc::now();
+    let exceeded = if summary.daily_cost_usd >= config.daily_limit_usd {
+        Some("day")
+    } else if summary.monthly_cost_usd >= config.monthly_limit_usd {
+        Some("month")
+    } else {
+        None
+    };
+    Ok(Some(BudgetStatus {
+        daily: PeriodSpend {
+            spent_usd: summary.daily_cost_usd,
+            limit_usd: config.daily_limit_usd,
+            resets_at: resets_at(UsagePeriod::Day, now),
+        },
+        monthly: PeriodSpend {
+            spent_usd: summary.monthly_cost_usd,
+            limit_usd: config.monthly_limit_usd,
+            resets_at: resets_at(UsagePeriod::Month, now),
+        },
+        exceeded,
+        allow_override: config.allow_override,
+    }))
+}
+
+fn estimate_tokens(text: &str) -> u64 {
+    text.len().div_ceil(4) as u64
+}
+
+fn estimate_message_tokens(messages: &[ChatMessage]) -> u64 {
+    messages
+        .iter()
+        .map(|message| estimate_tokens(&message.content))
+        .sum()
+}
+
+fn estimate_response_tokens(response: &ChatResponse) -> u64 {
+    let arguments: u64 = response
+        .tool_calls
+        .iter()
+        .map(|call| estimate_tokens(&call.arguments))
+        .sum();
+    estimate_tokens(response.text_or_empty()) + arguments
+}
+
+/// A [`Provider`] that refuses calls once the `[cost]` limits are spent.
+pub struct BudgetGuard {
+    inner: Box<dyn Provider>,
+    tracker: CostTracker,
+    prices: HashMap<String, ModelPricing>,
+    allow_override: bool,
+}
+
+impl BudgetGuard {
+    /// Guard `inner` with the workspace's budget, or hand it back unchanged
+    /// while cost tracking is off.
+    pub fn wrap(
+        inner: Box<dyn Provider>,
+        config: &CostConfig,
+        workspace_dir: &Path,
+    ) -> Result<Box<dyn Provider>> {
+        if !config.enabled {
+            return Ok(inner);
+        }
+        Ok(Box::new(Self {
+            inner,
+            tracker: CostTracker::for_workspace(config.clone(), workspace_dir)?,
+            prices: config.prices.clone(),
+            allow_override: config.allow_override,
+        }))
+    }
+
+    /// (input, output) USD per million tokens for `model`, matching either
+    /// the full `vendor/model` key or its model part.
+    fn pricing(&self, model: &str) -> (f64, f64) {
+        let listed = self.prices.get(model).or_else(|| {
+            self.prices
+                .iter()
+                .find(|(key, _)| key.rsplit('/').next() == Some(model))
+                .map(|(_, pricing)| pricing)
+        });
+        match listed {
+            Some(pricing) => (pricing.input, pricing.output),
+            None => self
+                .prices
+                .values()
+                .fold((0.0, 0.0), |(input, output), pricing| {
+                    (input.max(pricing.input), output.max(pricing.output))
+                }),
+        }
+    }
+
+    /// Refuse the call when sending `input_tokens` would exceed a limit.
+    fn admit(&self, model: &str, input_tokens: u64) -> Result<()> {
+        if self.allow_override && override_requested() {
+            return Ok(());
+        }
+        let (input_price, _) = self.pricing(model);
+        let estimate = TokenUsage::new(model, input_tokens, 0, input_price, 0.0).cost_usd;
+        match self.tracker.check_budget(estimate)? {
+            BudgetCheck::Allowed => Ok(()),
+            BudgetCheck::Warning {
+                current_usd,
+                limit_usd,
+                period,
+            } => {
+                tracing::warn!(
+                    "Provider spend is at ${current_usd:.2} of the ${limit_usd:.2} {period:?} budget"
+                );
+                Ok(())
+            }
+            BudgetCheck::Exceeded {
+                current_usd,
+                limit_usd,
+                period,
+            } => Err(BudgetExceeded {
+                period,
+                spent_usd: current_usd,
+                limit_usd,
+                resets_at: resets_at(period, Utc::now()),
+            }
+            .into()),
+        }
+    }
+
+    fn record(&self, model: &str, input_tokens: u64, output_tokens: u64) {
+        let (input_price, output_price) = self.pricing(model);
+        let usage = TokenUsage::new(
+            model,
+            input_tokens,
+            output_tokens,
+            input_price,
+            output_price,
+        );
+        if let Err(err) = self.tracker.record_usage(usage) {
+            tracing::warn!("Failed to record provider spend: {err:#}");
+        }
+    }
+
+    fn record_response(&self, model: &str, estimated_input: u64, response: &ChatResponse) {
+        let usage = response.usage.as_ref();
+        let input_tokens = usage
+            .and_then(|usage| usage.input_tokens)
+            .unwrap_or(estimated_input);
+        let output_tokens = usage
+            .and_then(|usage| usage.output_tokens)
+            .unwrap_or_else(|| estimate_response_tokens(response));
+        self.record(model, input_tokens, output_tokens);
+    }
+
+    /// Admit a stream and charge its prompt up front; the reply is not
+    /// metered.
+    fn admit_stream(
+        &self,
+        model: &str,
+        input_tokens: u64,
+    ) -> Option<stream::BoxStream<'static, StreamResult<StreamChunk>>> {
+        if let Err(err) = self.admit(model, input_tokens) {
+            let refusal: StreamResult<StreamChunk> = Err(StreamError::Provider(err.to_string()));
+            return Some(stream::once(async move { refusal }).boxed());
+        }
+        self.record(model, input_tokens, 0);
+        None
+    }
+}
+
+#[async_trait]
+impl Provider for BudgetGuard {
+    fn capabilities(&self) -> ProviderCapabilities {
+        self.inner.capabilities()
+    }
+
+    fn convert_tools(&self, tools: &[ToolSpec]) -> ToolsPayload {
+        self.inner.convert_tools(tools)
+    }
+
+    async fn chat_with_system(
+        &self,
+        system_prompt: Option<&str>,
+        message: &str,
+        model: &str,
+        temperature: f64,
+    ) -> Result<String> {
+        let input_tokens =
+            estimate_tokens(system_prompt.unwrap_or_default()) + estimate_tokens(message);
+        self.admit(model, input_tokens)?;
+        let response = self
+            .inner
+            .chat_with_system(system_prompt, message, model, temperature)
+            .await?;
+        self.record(model, input_tokens, estimate_tokens(&response));
+        Ok(response)
+    }
+
+    async fn chat_with_history(
+        &self,
+        messages: &[ChatMessage],
+        model: &str,
+        temperature: f64,
+    ) -> Result<String> {
+        let input_tokens = estimate_message_tokens(messages);
+        self.admit(model, input_tokens)?;
+        let response = self
+            .inner
+            .chat_with_history(messages, model, temperature)
+            .await?;
+        self.record(model, input_tokens, estimate_tokens(&response));
+        Ok(response)
+    }
+
+    async fn chat(
+        &self,
+        request: ChatRequest<'_>,
+        model: &str,
+        temperature: f64,
+    ) -> Result<ChatResponse> {
+        let input_tokens = estimate_message_tokens(request.messages);
+        self.admit(model, input_tokens)?;
+        let response = self.inner.chat(request, model, temperature).await?;
+        self.record_response(model, input_tokens, &response);
+        Ok(response)
+    }
+
+    fn supports_native_tools(&self) -> bool {
+        self.inner.supports_native_tools()
+    }
+
+    fn supports_vision(&self) -> bool {
+        self.inner.supports_vision()
+    }
+
+    async fn warmup(&self) -> Result<()> {
+        self.inner.warmup().await
+    }
+
+    async fn list_models(&self, default_model: &str) -> Result<Vec<ModelInfo>> {
+        self.inner.list_models(default_model).await
+    }
+
+    fn introspection(&self) -> Option<&dyn ProviderIntrospection> {
+        self.inner.introspection()
+    }
+
+    async fn chat_with_tools(
+        &self,
+        messages: &[ChatMessage],
+        tools: &[serde_json::Value],
+        model: &str,
+        temperature: f64,
+    ) -> Result<ChatResponse> {
+        let input_tokens = estimate_message_tokens(messages);
+        self.admit(model, input_tokens)?;
+        let response = self
+            .inner
+            .chat_with_tools(messages, tools, model, temperature)
+            .await?;
+        self.record_response(model, input_tokens, &response);
+        Ok(response)
+    }
+
+    fn supports_streaming(&self) -> bool {
+        self.inner.supports_streaming()
+    }
+
+    fn stream_chat_with_system(
+        &self,
+        system_prompt: Option<&str>,
+        message: &str,
+        model: &str,
+        temperature: f64,
+        options: StreamOptions,
+    ) -> stream::BoxStream<'static, StreamResult<StreamChunk>> {
+        let input_tokens =
+            estimate_tokens(system_prompt.unwrap_or_default()) + estimate_tokens(message);
+        if let Some(refusal) = self.admit_stream(model, input_tokens) {
+            return refusal;
+        }
+        self.inner
+            .stream_chat_with_system(system_prompt, message, model, temperature, options)
+    }
+
+    fn stream_chat_with_history(
+        &self,
+        messages: &[ChatMessage],
+        model: &str,
+        temperature: f64,
+        options: StreamOptions,
+    ) -> stream::BoxStream<'static, StreamResult<StreamChunk>> {
+        if let Some(refusal) = self.admit_stream(model, estimate_message_tokens(messages)) {
+            return refusal;
+        }
+        self.inner
+            .stream_chat_with_history(messages, model, temperature, options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::traits::TokenUsage as ReportedUsage;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[derive(Default)]
+    struct CountingProvider {
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl Provider for CountingProvider {
+        async fn chat_with_system(
+            &self,
+            _system_prompt: Option<&str>,
+            _message: &str,
+            _model: &str,
+            _temperature: f64,
+        ) -> Result<String> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok("ok".into())
+        }
+
+        async fn chat(
+            &self,
+            _request: ChatRequest<'_>,
+            _model: &str,
+            _temperature: f64,
+        ) -> Result<ChatResponse> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(ChatResponse {
+                text: Some("ok".into()),
+                tool_calls: Vec::new(),
+                usage: Some(ReportedUsage {
+                    input_tokens: Some(1_000_000),
+                    output_tokens: Some(0),
+                }),
+                reasoning_content: None,
+            })
+        }
+    }
+
+    #[async_trait]
+    impl Provider for Arc<CountingProvider> {
+        async fn chat_with_system(
+            &self,
+            system_prompt: Option<&str>,
+            message: &str,
+            model: &str,
+            temperature: f64,
+        ) -> Result<String> {
+            self.as_ref()
+                .chat_with_system(system_prompt, message, model, temperature)
+                .await
+        }
+
+        async fn chat(
+            &self,
+            request: ChatRequest<'_>,
+            model: &str,
+            temperature: f64,
+        ) -> Result<ChatResponse> {
+            self.as_ref().chat(request, model, temperature).await
+        }
+    }
+
+    /// $1 per million input tokens for `test/model`, $2 a day.
+    fn config(allow_override: bool) -> CostConfig {
+        CostConfig {
+            enabled: true,
+            daily_limit_usd: 2.0,
+            monthly_limit_usd: 100.0,
+            allow_override,
+            prices: HashMap::from([(
+                "test/model".to_string(),
+                ModelPricing {
+                    input: 1.0,
+                    output: 1.0,
+                },
+            )]),
+            ..CostConfig::default()
+        }
+    }
+
+    fn guarded(
+        workspace: &Path,
+        config: &CostConfig,
+    ) -> (Box<dyn Provider>, Arc<CountingProvider>) {
+        let inner = Arc::new(CountingProvider::default());
+        let provider = BudgetGuard::wrap(Box::new(inner.clone()), config, workspace).unwrap();
+        (provider, inner)
+    }
+
+    async fn spend_a_dollar(provider: &dyn Provider) -> Result<ChatResponse> {
+        let messages = [ChatMessage::user("hi")];
+        let request = ChatRequest {
+            messages: &messages,
+            tools: None,
+        };
+        provider.chat(request, "model", 0.0).await
+    }
+
+    #[tokio::test]
+    async fn calls_stop_once_the_daily_limit_is_spent_and_stay_stopped_after_restart() {
+        let tmp = tempfile::tempdir().unwrap();
+        let config = config(false);
+        let (provider, inner) = guarded(tmp.path(), &config);
+
+        spend_a_dollar(provider.as_ref()).await.unwrap();
+        spend_a_dollar(provider.as_ref()).await.unwrap();
+        let err = spend_a_dollar(provider.as_ref()).await.unwrap_err();
+        let exceeded = budget_exceeded(&err).expect("budget refusal");
+        assert_eq!(exceeded.period, UsagePeriod::Day);
+        assert!(err
+            .to_string()
+            .starts_with("Daily budget exceeded ($2.00 of $2.00 spent), resets at "));
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 2);
+
+        // A fresh storage handle reads the spend back from the cost log.
+        let reopened = CostTracker::new(config.clone(), tmp.path()).unwrap();
+        assert!(matches!(
+            reopened.check_budget(0.01).unwrap(),
+            BudgetCheck::Exceeded { .. }
+        ));
+        let status = budget_status(&config, tmp.path()).unwrap().unwrap();
+        assert_eq!(status.exceeded, Some("day"));
+        assert!((status.daily.spent_usd - 2.0).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn overrides_only_count_when_the_config_allows_them() {
+        let tmp = tempfile::tempdir().unwrap();
+        let strict = config(false);
+        let (provider, _) = guarded(tmp.path(), &strict);
+        spend_a_dollar(provider.as_ref()).await.unwrap();
+        spend_a_dollar(provider.as_ref()).await.unwrap();
+
+        let refused = with_budget_override(true, spend_a_dollar(provider.as_ref())).await;
+        assert!(budget_exceeded(&refused.unwrap_err()).is_some());
+
+        let (lenient, inner) = guarded(tmp.path(), &config(true));
+        with_budget_override(true, spend_a_dollar(lenient.as_ref()))
+            .await
+            .unwrap();
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 1);
+        assert!(spend_a_dollar(lenient.as_ref()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn unpriced_models_are_estimated_from_the_prompt_at_the_top_price() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut config = config(false);
+        config.daily_limit_usd = 0.5;
+        let (provider, inner) = guarded(tmp.path(), &config);
+
+        // 4 MB of prompt is about a million tokens: $1 at the listed price.
+        let prompt = "x".repeat(4_000_000);
+        let err = provider
+            .chat_with_system(None, &prompt, "unlisted-model", 0.0)
+            .await
+            .unwrap_err();
+        assert!(budget_exceeded(&err).is_some());
+        provider
+            .chat_with_system(None, "short", "unlisted-model", 0.0)
+            .await
+            .unwrap();
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn limits_reset_at_the_next_utc_midnight_and_month() {
+        let now = "2026-12-31T18:30:00Z".parse::<DateTime<Utc>>().unwrap();
+        assert_eq!(
+            resets_at(UsagePeriod::Day, now).to_rfc3339(),
+            "2027-01-01T00:00:00+00:00"
+        );
+        let now = "2026-10-15T09:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        assert_eq!(
+            resets_at(UsagePeriod::Month, now).to_rfc3339(),
+            "2026-11-01T00:00:00+00:00"
+        );
+    }
+}
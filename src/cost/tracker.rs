@@ -7,7 +7,9 @@ use std::collections::HashMap;
 use std::fs::{self, File, OpenOptions};
 use std::io::{BufRead, BufReader, Write};
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
+
+static STORAGES: OnceLock<Mutex<HashMap<PathBuf, Arc<Mutex<CostStorage>>>>> = OnceLock::new();
 
 /// Cost tracker for API usage monitoring and budget enforcement.
 pub struct CostTracker {
@@ -34,6 +36,35 @@ impl CostTracker {
         })
     }
 
+    /// Tracker whose storage is shared with every other tracker for the same
+    /// workspace, so spend recorded by the gateway and by agent runs adds up
+    /// against one budget.
+    pub fn for_workspace(config: CostConfig, workspace_dir: &Path) -> Result<Self> {
+        let key = workspace_dir
+            .canonicalize()
+            .unwrap_or_else(|_| workspace_dir.to_path_buf());
+        let mut registry = STORAGES.get_or_init(|| Mutex::new(HashMap::new())).lock();
+        let storage = match registry.get(&key) {
+            Some(storage) => Arc::clone(storage),
+            None => {
+                let storage_path = resolve_storage_path(workspace_dir)?;
+                let storage = CostStorage::new(&storage_path).with_context(|| {
+                    format!("Failed to open cost storage at {}", storage_path.display())
+                })?;
+                let storage = Arc::new(Mutex::new(storage));
+                registry.insert(key, Arc::clone(&storage));
+                storage
+            }
+        };
+
+        Ok(Self {
+            config,
+            storage,
+            session_id: uuid::Uuid::new_v4().to_string(),
+            session_costs: Arc::new(Mutex::new(Vec::new())),
+        })
+    }
+
     /// Get the session ID.
     pub fn session_id(&self) -> &str {
         &self.session_id
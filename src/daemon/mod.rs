@@ -72,7 +72,10 @@ pub async fn run(config: Config, host: String, port: u16) -> Result<()> {
     }
 
     println!("🧠 ZeroClaw daemon started");
-    println!("   Gateway:  http://{host}:{port}");
+    match &config.gateway.uds_path {
+        Some(path) => println!("   Gateway:  unix:{path}"),
+        None => println!("   Gateway:  http://{host}:{port}"),
+    }
     println!("   Components: gateway, channels, heartbeat");
     println!("   Ctrl+C to stop");
 
@@ -199,14 +202,26 @@ async fn run_heartbeat_worker(config: Config) -> Result<()> {
                     } else {
                         output
                     };
-                    if let Some((_channel, _target)) = &delivery {
-                        // Delivery via external channels removed; heartbeat output logged only.
-                        tracing::info!("Heartbeat output: {announcement}");
+                    if let Some((channel, target)) = &delivery {
+                        if let Err(e) =
+                            deliver_heartbeat(&config, channel, target, announcement).await
+                        {
+                            tracing::warn!("Heartbeat delivery to {channel} failed: {e:#}");
+                        }
                     }
                 }
                 Err(e) => {
                     crate::health::mark_component_error("heartbeat", e.to_string());
                     tracing::warn!("Heartbeat task failed: {e}");
+                    // A budget stop is news for whoever gets the heartbeat.
+                    if let (Some(exceeded), Some((channel, target))) =
+                        (crate::cost::guard::budget_exceeded(&e), &delivery)
+                    {
+                        let notice = format!("Heartbeat task skipped: {exceeded}");
+                        if let Err(e) = deliver_heartbeat(&config, channel, target, notice).await {
+                            tracing::warn!("Heartbeat delivery to {channel} failed: {e:#}");
+                        }
+                    }
                 }
             }
         }
@@ -253,17 +268,71 @@ fn heartbeat_delivery_target(config: &Config) -> Result<Option<(String, String)>
     }
 }
 
-fn validate_heartbeat_channel_config(_config: &Config, channel: &str) -> Result<()> {
+fn validate_heartbeat_channel_config(config: &Config, channel: &str) -> Result<()> {
     match channel.to_ascii_lowercase().as_str() {
         "pocketbase" => {}
+        "telegram" => {
+            if config.channels_config.telegram.is_none() {
+                anyhow::bail!(
+                    "heartbeat.target is telegram but channels_config.telegram is not configured"
+                );
+            }
+        }
+        "email" => {
+            if config.channels_config.email.is_none() {
+                anyhow::bail!(
+                    "heartbeat.target is email but channels_config.email is not configured"
+                );
+            }
+        }
         other => anyhow::bail!("unsupported heartbeat.target channel: {other}"),
     }
 
     Ok(())
 }
 
-fn has_supervised_channels(_config: &Config) -> bool {
-    false
+/// Send heartbeat output to `target` on `channel`, queueing it for retry if
+/// the channel is unreachable.
+async fn deliver_heartbeat(
+    config: &Config,
+    channel: &str,
+    target: &str,
+    announcement: String,
+) -> Result<()> {
+    use crate::channels::{Channel, OutboundDispatcher, SendMessage};
+
+    let channel: Box<dyn Channel> = match channel.to_ascii_lowercase().as_str() {
+        "telegram" => {
+            let Some(telegram) = config.channels_config.telegram.as_ref() else {
+                anyhow::bail!("channels_config.telegram is not configured");
+            };
+            Box::new(crate::channels::TelegramChannel::from_config(telegram))
+        }
+        "email" => {
+            let Some(email) = config.channels_config.email.as_ref() else {
+                anyhow::bail!("channels_config.email is not configured");
+            };
+            Box::new(crate::channels::EmailChannel::from_config(email)?)
+        }
+        _ => Box::new(
+            crate::channels::PocketBaseChannel::from_env_defaults()?
+                .with_outbox(&config.workspace_dir),
+        ),
+    };
+    OutboundDispatcher::new(&config.workspace_dir)
+        .send(channel.as_ref(), &SendMessage::new(announcement, target))
+        .await
+        .map(|_| ())
+}
+
+/// Channels that need a long-running listener; webhook-mode Telegram bots
+/// are served by the gateway instead.
+fn has_supervised_channels(config: &Config) -> bool {
+    config
+        .channels_config
+        .telegram
+        .as_ref()
+        .is_some_and(|telegram| telegram.webhook_secret.is_none())
 }
 
 #[cfg(test)]
@@ -344,6 +413,8 @@ mod tests {
             draft_update_interval_ms: 1000,
             interrupt_on_new_message: false,
             mention_only: false,
+            allowed_chats: vec![],
+            webhook_secret: None,
         });
         assert!(has_supervised_channels(&config));
     }
@@ -445,8 +516,8 @@ mod tests {
     #[test]
     fn heartbeat_delivery_target_rejects_unsupported_channel() {
         let mut config = Config::default();
-        config.heartbeat.target = Some("email".into());
-        config.heartbeat.to = Some("ops@example.com".into());
+        config.heartbeat.target = Some("slack".into());
+        config.heartbeat.to = Some("C012345".into());
         let err = heartbeat_delivery_target(&config).unwrap_err();
         assert!(err
             .to_string()
@@ -476,9 +547,33 @@ mod tests {
             draft_update_interval_ms: 1000,
             interrupt_on_new_message: false,
             mention_only: false,
+            allowed_chats: vec![],
+            webhook_secret: None,
         });
 
         let target = heartbeat_delivery_target(&config).unwrap();
         assert_eq!(target, Some(("telegram".to_string(), "123456".to_string())));
     }
+
+    #[test]
+    fn heartbeat_delivery_target_accepts_email_configuration() {
+        let mut config = Config::default();
+        config.heartbeat.target = Some("email".into());
+        config.heartbeat.to = Some("ops@example.com".into());
+        let err = heartbeat_delivery_target(&config).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("channels_config.email is not configured"));
+
+        config.channels_config.email = Some(crate::channels::email_channel::EmailConfig {
+            smtp_host: "smtp.example.com".into(),
+            from_address: "digest@example.com".into(),
+            ..Default::default()
+        });
+        let target = heartbeat_delivery_target(&config).unwrap();
+        assert_eq!(
+            target,
+            Some(("email".to_string(), "ops@example.com".to_string()))
+        );
+    }
 }
@@ -0,0 +1,495 @@
+//! `slowclaw channel doctor`: live probes of the configured channels.
+//!
+//! Every probe runs under [`PROBE_TIMEOUT`]. Details name hosts, handles, and
+//! whether a secret is set, never the secret itself; remote error text is
+//! passed through [`redact`] before it is shown.
+
+use super::{parse_rfc3339, Severity, CHANNEL_STALE_SECONDS};
+use crate::channels::{Channel, EmailChannel, PocketBaseChannel, TelegramChannel};
+use crate::config::Config;
+use anyhow::Result;
+use chrono::Utc;
+use std::future::Future;
+use std::time::Duration;
+
+pub const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// One probe result, as printed in the table and emitted by `--json`.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ChannelCheck {
+    pub channel: &'static str,
+    pub check: &'static str,
+    pub severity: Severity,
+    pub detail: String,
+}
+
+struct Probes {
+    checks: Vec<ChannelCheck>,
+    secrets: Vec<String>,
+}
+
+impl Probes {
+    fn push(
+        &mut self,
+        channel: &'static str,
+        check: &'static str,
+        severity: Severity,
+        detail: impl Into<String>,
+    ) {
+        self.checks.push(ChannelCheck {
+            channel,
+            check,
+            severity,
+            detail: redact(&detail.into(), &self.secrets),
+        });
+    }
+    fn ok(&mut self, channel: &'static str, check: &'static str, detail: impl Into<String>) {
+        self.push(channel, check, Severity::Ok, detail);
+    }
+    fn warn(&mut self, channel: &'static str, check: &'static str, detail: impl Into<String>) {
+        self.push(channel, check, Severity::Warn, detail);
+    }
+    fn error(&mut self, channel: &'static str, check: &'static str, detail: impl Into<String>) {
+        self.push(channel, check, Severity::Error, detail);
+    }
+}
+
+/// Replace every occurrence of a configured secret with `***`.
+pub fn redact(text: &str, secrets: &[String]) -> String {
+    secrets
+        .iter()
+        .filter(|secret| !secret.trim().is_empty())
+        .fold(text.to_string(), |text, secret| {
+            text.replace(secret.as_str(), "***")
+        })
+}
+
+async fn timed<T>(probe: impl Future<Output = Result<T>>) -> Result<T> {
+    tokio::time::timeout(PROBE_TIMEOUT, probe)
+        .await
+        .map_err(|_| anyhow::anyhow!("timed out after {}s", PROBE_TIMEOUT.as_secs()))?
+}
+
+/// Secrets the probes may touch, so they can be scrubbed from the output.
+fn configured_secrets(config: &Config) -> Vec<String> {
+    let channels = &config.channels_config;
+    let mut secrets = Vec::new();
+    secrets.extend(channels.telegram.as_ref().map(|t| t.bot_token.clone()));
+    secrets.extend(
+        channels
+            .telegram
+            .as_ref()
+            .and_then(|t| t.webhook_secret.clone()),
+    );
+    secrets.extend(channels.email.as_ref().map(|e| e.password.clone()));
+    secrets.extend(
+        channels
+            .nextcloud_talk
+            .as_ref()
+            .map(|n| n.app_token.clone()),
+    );
+    secrets
+        .extend(crate::security::credentials::load_bluesky_credentials().map(|c| c.app_password));
+    for var in [
+        "ZEROCLAW_POCKETBASE_TOKEN",
+        "POCKETBASE_TOKEN",
+        crate::channels::pocketbase_schema::ADMIN_TOKEN_ENV,
+        crate::channels::pocketbase_auth::PASSWORD_ENV,
+    ] {
+        secrets.extend(std::env::var(var).ok());
+    }
+    secrets
+}
+
+/// Status and age in seconds of a listener's last successful poll, from the
+/// daemon state file; `None` when the component never reported.
+fn poll_freshness(config: &Config, component: &str) -> Option<(bool, i64)> {
+    let raw = std::fs::read_to_string(crate::daemon::state_file_path(config)).ok()?;
+    let snapshot: serde_json::Value = serde_json::from_str(&raw).ok()?;
+    let entry = snapshot.get("components")?.get(component)?;
+    let status_ok = entry.get("status").and_then(serde_json::Value::as_str) == Some("ok");
+    let age = entry
+        .get("last_ok")
+        .and_then(serde_json::Value::as_str)
+        .and_then(parse_rfc3339)
+        .map_or(i64::MAX, |dt| {
+            Utc::now().signed_duration_since(dt).num_seconds()
+        });
+    Some((status_ok, age))
+}
+
+fn check_poll(
+    config: &Config,
+    probes: &mut Probes,
+    channel: &'static str,
+    check: &'static str,
+    component: &str,
+) {
+    match poll_freshness(config, component) {
+        None => probes.warn(
+            channel,
+            check,
+            format!("no `{component}` poll recorded in the daemon state"),
+        ),
+        Some((true, age)) if age <= CHANNEL_STALE_SECONDS => {
+            probes.ok(channel, check, format!("last poll {age}s ago"));
+        }
+        Some((status_ok, age)) => probes.error(
+            channel,
+            check,
+            format!("stale (ok={status_ok}, last good poll {age}s ago)"),
+        ),
+    }
+}
+
+/// The gateway's own address as a client should dial it.
+fn gateway_dial_host(host: &str) -> String {
+    match host.trim() {
+        "" | "0.0.0.0" => "127.0.0.1".into(),
+        "::" | "[::]" => "[::1]".into(),
+        host if host.contains(':') && !host.starts_with('[') => format!("[{host}]"),
+        host => host.into(),
+    }
+}
+
+async fn check_gateway(config: &Config, probes: &mut Probes) {
+    let addr = format!(
+        "{}:{}",
+        gateway_dial_host(&config.gateway.host),
+        config.gateway.port
+    );
+    let url = format!("http://{addr}/health");
+    let status = timed(async {
+        let response = crate::http_client::shared().get(&url).send().await?;
+        Ok(response.status())
+    })
+    .await;
+    match status {
+        Ok(status) if status.is_success() => probes.ok("gateway", "reachable", addr),
+        Ok(status) => probes.error("gateway", "reachable", format!("{url} answered {status}")),
+        Err(err) => probes.error("gateway", "reachable", format!("{addr}: {err:#}")),
+    }
+}
+
+async fn check_pocketbase(config: &Config, probes: &mut Probes) {
+    let channel = match PocketBaseChannel::from_env_defaults() {
+        Ok(channel) => channel,
+        Err(err) => {
+            probes.error("pocketbase", "config", format!("{err:#}"));
+            return;
+        }
+    };
+    let base_url = channel.base_url().to_string();
+    let collection = channel.collection().to_string();
+    let healthy = timed(async { Ok(channel.health_check().await) })
+        .await
+        .unwrap_or(false);
+    if !healthy {
+        probes.error(
+            "pocketbase",
+            "health",
+            format!("{base_url}/api/health not reachable"),
+        );
+        return;
+    }
+    probes.ok("pocketbase", "health", format!("{base_url} is up"));
+
+    match timed(channel.probe_collection()).await {
+        Ok(status) if status.is_success() => {
+            probes.ok("pocketbase", "collection", format!("`{collection}` exists"));
+        }
+        Ok(reqwest::StatusCode::NOT_FOUND) => probes.error(
+            "pocketbase",
+            "collection",
+            format!(
+                "`{collection}` not found; start the gateway with {} (or superuser credentials) set to create it",
+                crate::channels::pocketbase_schema::ADMIN_TOKEN_ENV
+            ),
+        ),
+        Ok(status) => probes.error(
+            "pocketbase",
+            "collection",
+            format!("`{collection}` refused the list request ({status})"),
+        ),
+        Err(err) => probes.error("pocketbase", "collection", format!("{err:#}")),
+    }
+
+    match timed(channel.probe_token()).await {
+        Ok(None) => probes.warn(
+            "pocketbase",
+            "token",
+            "not set (ZEROCLAW_POCKETBASE_TOKEN or ZEROCLAW_POCKETBASE_IDENTITY/PASSWORD); requests are anonymous",
+        ),
+        Ok(Some(status)) if status.is_success() => {
+            probes.ok("pocketbase", "token", "present and accepted");
+        }
+        Ok(Some(status)) => probes.error(
+            "pocketbase",
+            "token",
+            format!("present but rejected ({status})"),
+        ),
+        Err(err) => probes.error("pocketbase", "token", format!("present but {err:#}")),
+    }
+
+    check_poll(
+        config,
+        probes,
+        "pocketbase",
+        "chat worker",
+        crate::channels::pocketbase::POLL_HEALTH_COMPONENT,
+    );
+}
+
+async fn check_email(config: &Config, probes: &mut Probes) {
+    let Some(email) = config.channels_config.email.as_ref() else {
+        return;
+    };
+    let channel = match EmailChannel::from_config(email) {
+        Ok(channel) => channel,
+        Err(err) => {
+            probes.error("email", "config", format!("{err:#}"));
+            return;
+        }
+    };
+    let login = if email.username.is_empty() {
+        "no login"
+    } else if email.password.is_empty() {
+        "login without password"
+    } else {
+        "login and password present"
+    };
+    let server = format!("{}:{}", email.smtp_host, email.smtp_port);
+    match timed(channel.test_connection()).await {
+        Ok(()) => probes.ok("email", "smtp", format!("{server} accepted ({login})")),
+        Err(err) => probes.error("email", "smtp", format!("{server} ({login}): {err:#}")),
+    }
+}
+
+async fn check_telegram(config: &Config, probes: &mut Probes) {
+    let Some(telegram) = config.channels_config.telegram.as_ref() else {
+        return;
+    };
+    let channel = TelegramChannel::from_config(telegram);
+    match timed(channel.get_me(PROBE_TIMEOUT)).await {
+        Ok(username) => probes.ok("telegram", "getMe", format!("bot @{username}")),
+        Err(err) => probes.error("telegram", "getMe", format!("{err:#}")),
+    }
+    if telegram.webhook_secret.is_some() {
+        probes.ok("telegram", "mode", "webhook (secret present)");
+    } else {
+        check_poll(
+            config,
+            probes,
+            "telegram",
+            "polling",
+            crate::channels::telegram::POLL_HEALTH_COMPONENT,
+        );
+    }
+}
+
+async fn check_bluesky(config: &Config, probes: &mut Probes) {
+    let Some(client) = crate::bluesky::BlueskyClient::from_env(&config.workspace_dir) else {
+        return;
+    };
+    match timed(client.check_session()).await {
+        Ok(handle) => probes.ok("bluesky", "session", format!("valid for @{handle}")),
+        Err(err) => probes.error("bluesky", "session", format!("{err:#}")),
+    }
+}
+
+#[cfg(feature = "channel-nextcloud")]
+async fn check_nextcloud_talk(config: &Config, probes: &mut Probes) {
+    let Some(talk) = config.channels_config.nextcloud_talk.as_ref() else {
+        return;
+    };
+    let channel = crate::channels::NextcloudTalkChannel::new(
+        talk.base_url.clone(),
+        talk.app_token.clone(),
+        talk.allowed_users.clone(),
+    );
+    let healthy = timed(async { Ok(channel.health_check().await) })
+        .await
+        .unwrap_or(false);
+    if healthy {
+        probes.ok("nextcloud", "status", talk.base_url.clone());
+    } else {
+        probes.error(
+            "nextcloud",
+            "status",
+            format!("{}/status.php not reachable", talk.base_url),
+        );
+    }
+}
+
+/// Probe the gateway, PocketBase, and every configured external channel.
+pub async fn diagnose_channels(config: &Config) -> Vec<ChannelCheck> {
+    let mut probes = Probes {
+        checks: Vec::new(),
+        secrets: configured_secrets(config),
+    };
+    check_gateway(config, &mut probes).await;
+    check_pocketbase(config, &mut probes).await;
+    check_email(config, &mut probes).await;
+    check_telegram(config, &mut probes).await;
+    check_bluesky(config, &mut probes).await;
+    #[cfg(feature = "channel-nextcloud")]
+    check_nextcloud_talk(config, &mut probes).await;
+    probes.checks
+}
+
+fn count(checks: &[ChannelCheck], severity: Severity) -> usize {
+    checks.iter().filter(|c| c.severity == severity).count()
+}
+
+/// Run the probes and print a table, or JSON with `json`. With `strict`,
+/// any ❌ check makes the command fail.
+pub async fn run_channels(config: &Config, strict: bool, json: bool) -> Result<()> {
+    let checks = diagnose_channels(config).await;
+    let (oks, warns, errors) = (
+        count(&checks, Severity::Ok),
+        count(&checks, Severity::Warn),
+        count(&checks, Severity::Error),
+    );
+
+    if json {
+        let report = serde_json::json!({
+            "checks": checks,
+            "summary": { "ok": oks, "warnings": warns, "errors": errors },
+        });
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        println!("🩺 Channel doctor");
+        println!();
+        for check in &checks {
+            let icon = match check.severity {
+                Severity::Ok => "✅",
+                Severity::Warn => "⚠️ ",
+                Severity::Error => "❌",
+            };
+            println!(
+                "  {icon} {:<10} {:<12} {}",
+                check.channel, check.check, check.detail
+            );
+        }
+        println!();
+        println!("  Summary: {oks} ok, {warns} warnings, {errors} errors");
+    }
+
+    if strict && errors > 0 {
+        anyhow::bail!("{errors} channel check(s) failed");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn probes() -> Probes {
+        Probes {
+            checks: Vec::new(),
+            secrets: vec!["123:ABC-secret".into(), String::new()],
+        }
+    }
+
+    #[test]
+    fn details_never_contain_configured_secrets() {
+        let mut probes = probes();
+        probes.error(
+            "telegram",
+            "getMe",
+            "request to https://api.telegram.org/bot123:ABC-secret/getMe failed",
+        );
+        assert_eq!(
+            probes.checks[0].detail,
+            "request to https://api.telegram.org/bot***/getMe failed"
+        );
+    }
+
+    #[test]
+    fn wildcard_gateway_hosts_dial_loopback() {
+        assert_eq!(gateway_dial_host("0.0.0.0"), "127.0.0.1");
+        assert_eq!(gateway_dial_host("::"), "[::1]");
+        assert_eq!(gateway_dial_host("fe80::1"), "[fe80::1]");
+        assert_eq!(gateway_dial_host("127.0.0.1"), "127.0.0.1");
+    }
+
+    #[tokio::test]
+    async fn gateway_check_reports_reachability() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/health"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+        let mut config = Config::default();
+        config.gateway.host = "127.0.0.1".into();
+        config.gateway.port = server.address().port();
+        let mut probes = probes();
+        check_gateway(&config, &mut probes).await;
+
+        // wiremock pools servers, so a dropped one may still answer; dial a
+        // port nothing listens on instead.
+        let closed = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        config.gateway.port = closed.local_addr().unwrap().port();
+        drop(closed);
+        check_gateway(&config, &mut probes).await;
+
+        assert_eq!(probes.checks[0].severity, Severity::Ok);
+        assert_eq!(probes.checks[1].severity, Severity::Error);
+    }
+
+    #[test]
+    fn poll_checks_read_the_daemon_state() {
+        let tmp = TempDir::new().unwrap();
+        let config = Config {
+            config_path: tmp.path().join("config.toml"),
+            ..Config::default()
+        };
+        let mut probes = probes();
+        check_poll(
+            &config,
+            &mut probes,
+            "telegram",
+            "polling",
+            "channel:telegram",
+        );
+
+        let fresh = Utc::now().to_rfc3339();
+        let stale = (Utc::now() - chrono::Duration::hours(1)).to_rfc3339();
+        std::fs::write(
+            crate::daemon::state_file_path(&config),
+            serde_json::json!({
+                "components": {
+                    "channel:telegram": {"status": "ok", "last_ok": fresh},
+                    "channel:pocketbase": {"status": "error", "last_ok": stale},
+                }
+            })
+            .to_string(),
+        )
+        .unwrap();
+        check_poll(
+            &config,
+            &mut probes,
+            "telegram",
+            "polling",
+            "channel:telegram",
+        );
+        check_poll(
+            &config,
+            &mut probes,
+            "pocketbase",
+            "chat worker",
+            "channel:pocketbase",
+        );
+
+        let severities: Vec<_> = probes.checks.iter().map(|c| c.severity).collect();
+        assert_eq!(
+            severities,
+            vec![Severity::Warn, Severity::Ok, Severity::Error]
+        );
+    }
+}
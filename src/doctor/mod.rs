@@ -2,12 +2,18 @@ use crate::config::Config;
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 use std::io::Write;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+mod channels;
+
+pub use channels::run_channels;
 
 const DAEMON_STALE_SECONDS: i64 = 30;
 const SCHEDULER_STALE_SECONDS: i64 = 120;
 const CHANNEL_STALE_SECONDS: i64 = 300;
 const COMMAND_VERSION_PREVIEW_CHARS: usize = 60;
+const PROVIDER_PROBE_TIMEOUT: Duration = Duration::from_secs(10);
 
 // ── Diagnostic item ──────────────────────────────────────────────
 
@@ -25,12 +31,16 @@ pub struct DiagResult {
     pub severity: Severity,
     pub category: String,
     pub message: String,
+    /// What to do about a warning or error.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hint: Option<String>,
 }
 
 struct DiagItem {
     severity: Severity,
     category: &'static str,
     message: String,
+    hint: Option<String>,
 }
 
 impl DiagItem {
@@ -39,6 +49,7 @@ impl DiagItem {
             severity: Severity::Ok,
             category,
             message: msg.into(),
+            hint: None,
         }
     }
     fn warn(category: &'static str, msg: impl Into<String>) -> Self {
@@ -46,6 +57,7 @@ impl DiagItem {
             severity: Severity::Warn,
             category,
             message: msg.into(),
+            hint: None,
         }
     }
     fn error(category: &'static str, msg: impl Into<String>) -> Self {
@@ -53,9 +65,15 @@ impl DiagItem {
             severity: Severity::Error,
             category,
             message: msg.into(),
+            hint: None,
         }
     }
 
+    fn hint(mut self, hint: impl Into<String>) -> Self {
+        self.hint = Some(hint.into());
+        self
+    }
+
     fn icon(&self) -> &'static str {
         match self.severity {
             Severity::Ok => "✅",
@@ -69,67 +87,107 @@ impl DiagItem {
             severity: self.severity,
             category: self.category.to_string(),
             message: self.message,
+            hint: self.hint,
         }
     }
 }
 
 // ── Public entry points ──────────────────────────────────────────
 
-/// Run diagnostics and return structured results (for API/web dashboard).
+/// Run the offline diagnostics and return structured results (for
+/// API/web dashboard). Nothing here touches the network.
 pub fn diagnose(config: &Config) -> Vec<DiagResult> {
     let mut items: Vec<DiagItem> = Vec::new();
 
     check_config_semantics(config, &mut items);
     check_workspace(config, &mut items);
     check_daemon_state(config, &mut items);
+    check_ports(config, &mut items);
+    check_pocketbase_binary(&mut items);
+    check_keyring(&mut items);
+    check_media_tools(config, &mut items);
     check_environment(&mut items);
     check_cli_tools(&mut items);
 
     items.into_iter().map(DiagItem::into_result).collect()
 }
 
-/// Run diagnostics and print human-readable report to stdout.
-pub fn run(config: &Config) -> Result<()> {
-    let results = diagnose(config);
+/// [`diagnose`] plus a live credential check against the default provider.
+pub async fn diagnose_with_probes(config: &Config) -> Vec<DiagResult> {
+    let mut results = diagnose(config);
+    let mut items = Vec::new();
+    check_provider(config, &mut items).await;
+    results.extend(items.into_iter().map(DiagItem::into_result));
+    results
+}
 
-    // Print report
-    println!("🩺 ZeroClaw Doctor (enhanced)");
-    println!();
+/// Run every check and print a report to stdout, or JSON with `json`. Fails
+/// when any check shows ❌, so scripts can rely on the exit code. Servers are
+/// never started.
+pub async fn run(config: &Config, json: bool) -> Result<()> {
+    report(&diagnose_with_probes(config).await, json)
+}
+
+/// `slowclaw doctor` when the config could not even be loaded: report that
+/// one failure the same way and exit non-zero.
+pub fn run_config_failure(error: &anyhow::Error, json: bool) -> Result<()> {
+    let item = DiagItem::error(
+        "config",
+        format!("cannot load config: {}", format_error_chain(error)),
+    )
+    .hint(
+        "Fix the reported key in config.toml, or move the file aside and run \
+         `slowclaw onboard` to write a fresh one",
+    );
+    report(&[item.into_result()], json)
+}
+
+fn report(results: &[DiagResult], json: bool) -> Result<()> {
+    let count = |severity| results.iter().filter(|i| i.severity == severity).count();
+    let (oks, warns, errors) = (
+        count(Severity::Ok),
+        count(Severity::Warn),
+        count(Severity::Error),
+    );
+
+    if json {
+        let report = serde_json::json!({
+            "checks": results,
+            "summary": { "ok": oks, "warnings": warns, "errors": errors },
+        });
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        println!("🩺 SlowClaw Doctor");
+        println!();
 
-    let mut current_cat = "";
-    for item in &results {
-        if item.category != current_cat {
-            current_cat = &item.category;
-            println!("  [{current_cat}]");
+        let mut current_cat = "";
+        for item in results {
+            if item.category != current_cat {
+                current_cat = &item.category;
+                println!("  [{current_cat}]");
+            }
+            let icon = match item.severity {
+                Severity::Ok => "✅",
+                Severity::Warn => "⚠️ ",
+                Severity::Error => "❌",
+            };
+            println!("    {} {}", icon, item.message);
+            if let Some(hint) = &item.hint {
+                println!("       💡 {hint}");
+            }
         }
-        let icon = match item.severity {
-            Severity::Ok => "✅",
-            Severity::Warn => "⚠️ ",
-            Severity::Error => "❌",
-        };
-        println!("    {} {}", icon, item.message);
-    }
 
-    let errors = results
-        .iter()
-        .filter(|i| i.severity == Severity::Error)
-        .count();
-    let warns = results
-        .iter()
-        .filter(|i| i.severity == Severity::Warn)
-        .count();
-    let oks = results
-        .iter()
-        .filter(|i| i.severity == Severity::Ok)
-        .count();
+        println!();
+        println!("  Summary: {oks} ok, {warns} warnings, {errors} errors");
 
-    println!();
-    println!("  Summary: {oks} ok, {warns} warnings, {errors} errors");
+        if errors > 0 {
+            println!("  💡 Fix the errors above, then run `slowclaw doctor` again.");
+        }
+    }
 
     if errors > 0 {
-        println!("  💡 Fix the errors above, then run `zeroclaw doctor` again.");
+        anyhow::bail!("{errors} doctor check(s) failed");
     }
-
     Ok(())
 }
 
@@ -669,10 +727,10 @@ fn check_workspace(config: &Config, items: &mut Vec<DiagItem>) {
             format!("directory exists: {}", ws.display()),
         ));
     } else {
-        items.push(DiagItem::error(
-            cat,
-            format!("directory missing: {}", ws.display()),
-        ));
+        items.push(
+            DiagItem::error(cat, format!("directory missing: {}", ws.display()))
+                .hint("Run `slowclaw onboard` to create and scaffold it"),
+        );
         return;
     }
 
@@ -718,25 +776,31 @@ fn check_workspace(config: &Config, items: &mut Vec<DiagItem>) {
         }
     }
 
-    // Key workspace files
-    check_file_exists(ws, "SOUL.md", false, cat, items);
-    check_file_exists(ws, "AGENTS.md", false, cat, items);
-}
-
-fn check_file_exists(
-    base: &Path,
-    name: &str,
-    required: bool,
-    cat: &'static str,
-    items: &mut Vec<DiagItem>,
-) {
-    let path = base.join(name);
-    if path.is_file() {
-        items.push(DiagItem::ok(cat, format!("{name} present")));
-    } else if required {
-        items.push(DiagItem::error(cat, format!("{name} missing")));
-    } else {
-        items.push(DiagItem::warn(cat, format!("{name} not found (optional)")));
+    // Skeleton written by `slowclaw onboard` and repaired on gateway startup
+    let skeleton = crate::workspace::verify_skeleton(ws);
+    if skeleton.is_clean() {
+        items.push(DiagItem::ok(cat, "workspace skeleton complete"));
+    }
+    if !skeleton.missing.is_empty() {
+        items.push(
+            DiagItem::warn(
+                cat,
+                format!(
+                    "workspace skeleton incomplete, missing: {}",
+                    skeleton.missing.join(", ")
+                ),
+            )
+            .hint("`slowclaw workspace repair` creates them without touching existing files"),
+        );
+    }
+    if !skeleton.wrong_kind.is_empty() {
+        items.push(DiagItem::warn(
+            cat,
+            format!(
+                "workspace entries have the wrong kind: {}",
+                skeleton.wrong_kind.join(", ")
+            ),
+        ));
     }
 }
 
@@ -777,13 +841,13 @@ fn check_daemon_state(config: &Config, items: &mut Vec<DiagItem>) {
     let state_file = crate::daemon::state_file_path(config);
 
     if !state_file.exists() {
-        items.push(DiagItem::error(
-            cat,
-            format!(
-                "state file not found: {} — is the daemon running?",
-                state_file.display()
-            ),
-        ));
+        items.push(
+            DiagItem::warn(
+                cat,
+                format!("state file not found: {}", state_file.display()),
+            )
+            .hint("The daemon is not running; start it with `slowclaw daemon`"),
+        );
         return;
     }
 
@@ -904,13 +968,274 @@ fn check_daemon_state(config: &Config, items: &mut Vec<DiagItem>) {
     }
 }
 
+/// Whether the daemon state file was refreshed within
+/// [`DAEMON_STALE_SECONDS`].
+fn daemon_heartbeat_fresh(config: &Config) -> bool {
+    std::fs::read_to_string(crate::daemon::state_file_path(config))
+        .ok()
+        .and_then(|raw| serde_json::from_str::<serde_json::Value>(&raw).ok())
+        .and_then(|snapshot| {
+            snapshot
+                .get("updated_at")
+                .and_then(serde_json::Value::as_str)
+                .and_then(parse_rfc3339)
+        })
+        .is_some_and(|ts| {
+            Utc::now().signed_duration_since(ts).num_seconds() <= DAEMON_STALE_SECONDS
+        })
+}
+
+// ── Ports ────────────────────────────────────────────────────────
+
+fn check_ports(config: &Config, items: &mut Vec<DiagItem>) {
+    let cat = "ports";
+
+    let (host, port) = (config.gateway.host.as_str(), config.gateway.port);
+    let gateway = format!("{host}:{port}");
+    match std::net::TcpListener::bind((host, port)) {
+        Ok(_) => items.push(DiagItem::ok(cat, format!("gateway {gateway} is free"))),
+        Err(_) if daemon_heartbeat_fresh(config) => items.push(DiagItem::ok(
+            cat,
+            format!("gateway {gateway} is held by the running daemon"),
+        )),
+        Err(err) => items.push(
+            DiagItem::error(cat, format!("gateway {gateway} unavailable: {err}")).hint(format!(
+                "Stop whatever holds it (`lsof -i :{port}`) or set `port` under [gateway]"
+            )),
+        ),
+    }
+
+    if let Some(url) = ["ZEROCLAW_POCKETBASE_URL", "POCKETBASE_URL"]
+        .into_iter()
+        .find_map(|var| std::env::var(var).ok())
+    {
+        items.push(DiagItem::ok(
+            cat,
+            format!("PocketBase configured at {url}; no local port needed"),
+        ));
+        return;
+    }
+    let (host, port) = crate::channels::pocketbase::local_address_from_env();
+    let pocketbase = format!("{host}:{port}");
+    match std::net::TcpListener::bind((host.as_str(), port)) {
+        Ok(_) => items.push(DiagItem::ok(
+            cat,
+            format!("PocketBase {pocketbase} is free"),
+        )),
+        Err(err) => items.push(
+            DiagItem::warn(cat, format!("PocketBase {pocketbase} is in use: {err}")).hint(
+                "Expected when PocketBase is already running there; otherwise free the port \
+                 or set ZEROCLAW_POCKETBASE_PORT",
+            ),
+        ),
+    }
+}
+
+// ── PocketBase binary ────────────────────────────────────────────
+
+const POCKETBASE_BIN_ENV: &str = "ZEROCLAW_POCKETBASE_BIN";
+const POCKETBASE_LOCAL_BINARIES: [&str; 2] = ["pocketbase/pocketbase", "pocketbase/pocketbase.exe"];
+
+/// The PocketBase binary, looked up as SECURITY.md describes:
+/// `ZEROCLAW_POCKETBASE_BIN`, then `pocketbase/` under the current
+/// directory, then `PATH`. `Err` carries a broken override.
+fn resolve_pocketbase_binary() -> std::result::Result<Option<PathBuf>, PathBuf> {
+    if let Some(path) = std::env::var_os(POCKETBASE_BIN_ENV).filter(|v| !v.is_empty()) {
+        let path = PathBuf::from(path);
+        return if path.is_file() {
+            Ok(Some(path))
+        } else {
+            Err(path)
+        };
+    }
+    Ok(POCKETBASE_LOCAL_BINARIES
+        .iter()
+        .map(PathBuf::from)
+        .find(|path| path.is_file())
+        .or_else(|| which::which("pocketbase").ok()))
+}
+
+fn check_pocketbase_binary(items: &mut Vec<DiagItem>) {
+    let cat = "pocketbase";
+    let download_hint = "Download a release from https://pocketbase.io/docs/ into \
+                         pocketbase/pocketbase or PATH, or set ZEROCLAW_POCKETBASE_BIN";
+
+    match resolve_pocketbase_binary() {
+        Ok(Some(path)) => check_command_available(
+            &path.to_string_lossy(),
+            &["--version"],
+            cat,
+            Some(download_hint),
+            items,
+        ),
+        Ok(None) => {
+            items.push(DiagItem::warn(cat, "pocketbase binary not found").hint(download_hint));
+        }
+        Err(path) => items.push(
+            DiagItem::error(
+                cat,
+                format!(
+                    "{POCKETBASE_BIN_ENV} points at {}, which is not a file",
+                    path.display()
+                ),
+            )
+            .hint(format!(
+                "Fix the path, or unset {POCKETBASE_BIN_ENV} to search pocketbase/ and PATH"
+            )),
+        ),
+    }
+}
+
+// ── Keyring ──────────────────────────────────────────────────────
+
+fn check_keyring(items: &mut Vec<DiagItem>) {
+    let cat = "keyring";
+
+    if !cfg!(feature = "keyring") {
+        items.push(DiagItem::ok(
+            cat,
+            "built without keyring support; secrets come from config and env",
+        ));
+        return;
+    }
+    match crate::security::credentials::read_keyring_secret(
+        crate::security::credentials::BLUESKY_SECRET_SERVICE,
+        crate::security::credentials::BLUESKY_CREDENTIALS_ACCOUNT,
+    ) {
+        Ok(_) => items.push(DiagItem::ok(cat, "OS keyring is readable")),
+        Err(err) => items.push(
+            DiagItem::warn(cat, format!("OS keyring is not accessible: {err:#}")).hint(
+                "Unlock the login keychain or start a Secret Service provider such as \
+                 gnome-keyring; env vars still work without it",
+            ),
+        ),
+    }
+}
+
+// ── Media tools ──────────────────────────────────────────────────
+
+fn check_media_tools(config: &Config, items: &mut Vec<DiagItem>) {
+    let cat = "media";
+
+    check_command_available(
+        "ffmpeg",
+        &["-version"],
+        cat,
+        Some("Install ffmpeg (`brew install ffmpeg`, `apt install ffmpeg`); thumbnails need it"),
+        items,
+    );
+    check_command_available(
+        &config.media_probe.ffprobe_path,
+        &["-version"],
+        cat,
+        Some(
+            "ffprobe ships with ffmpeg; set `ffprobe_path` under [media_probe] if it is elsewhere",
+        ),
+        items,
+    );
+    if config.transcription.enabled {
+        check_command_available(
+            &config.transcription.python_bin,
+            &["--version"],
+            cat,
+            Some(
+                "Install Python 3 with faster-whisper, or set `python_bin` under \
+                 [transcription]",
+            ),
+            items,
+        );
+    }
+}
+
+// ── Provider credentials ─────────────────────────────────────────
+
+/// One cheap authenticated request to the default provider (its connection
+/// warmup, which fails on a refused credential), bounded by
+/// [`PROVIDER_PROBE_TIMEOUT`].
+async fn check_provider(config: &Config, items: &mut Vec<DiagItem>) {
+    let cat = "provider";
+    // A missing or unknown provider is already reported under [config].
+    let Some(name) = config
+        .default_provider
+        .as_deref()
+        .filter(|name| provider_validation_error(name).is_none())
+    else {
+        return;
+    };
+
+    if name != "ollama"
+        && crate::providers::resolve_provider_credential(name, config.api_key.as_deref()).is_none()
+    {
+        items.push(
+            DiagItem::warn(cat, format!("no credential found for \"{name}\"")).hint(
+                "Set `api_key` in config.toml or the provider's API key env var \
+                 (see docs/providers-reference.md)",
+            ),
+        );
+        return;
+    }
+
+    let options = crate::providers::ProviderRuntimeOptions {
+        auth_profile_override: None,
+        provider_api_url: config.api_url.clone(),
+        zeroclaw_dir: config.config_path.parent().map(PathBuf::from),
+        secrets_encrypt: config.secrets.encrypt,
+        reasoning_enabled: config.runtime.reasoning_enabled,
+    };
+    let provider = match crate::providers::create_provider_with_url_and_options(
+        name,
+        config.api_key.as_deref(),
+        config.api_url.as_deref(),
+        &options,
+    ) {
+        Ok(provider) => provider,
+        Err(err) => {
+            items.push(DiagItem::error(
+                cat,
+                format!("cannot create \"{name}\": {}", format_error_chain(&err)),
+            ));
+            return;
+        }
+    };
+
+    match tokio::time::timeout(PROVIDER_PROBE_TIMEOUT, provider.warmup()).await {
+        Ok(Ok(())) => items.push(DiagItem::ok(
+            cat,
+            format!("\"{name}\" answered with the configured credential"),
+        )),
+        Ok(Err(err)) => items.push(
+            DiagItem::error(
+                cat,
+                format!(
+                    "\"{name}\" rejected the check: {}",
+                    crate::providers::sanitize_api_error(&format_error_chain(&err))
+                ),
+            )
+            .hint(format!(
+                "Check that the API key is current and has access; \
+                 `slowclaw doctor models --provider {name}` shows more"
+            )),
+        ),
+        Err(_) => items.push(
+            DiagItem::error(
+                cat,
+                format!(
+                    "\"{name}\" did not answer within {}s",
+                    PROVIDER_PROBE_TIMEOUT.as_secs()
+                ),
+            )
+            .hint("Check network access, proxy settings, and `api_url`"),
+        ),
+    }
+}
+
 // ── Environment checks ───────────────────────────────────────────
 
 fn check_environment(items: &mut Vec<DiagItem>) {
     let cat = "environment";
 
     // git
-    check_command_available("git", &["--version"], cat, items);
+    check_command_available("git", &["--version"], cat, None, items);
 
     // Shell
     let shell = std::env::var("SHELL").unwrap_or_default();
@@ -931,7 +1256,7 @@ fn check_environment(items: &mut Vec<DiagItem>) {
     }
 
     // Optional tools
-    check_command_available("curl", &["--version"], cat, items);
+    check_command_available("curl", &["--version"], cat, None, items);
 }
 
 fn check_cli_tools(items: &mut Vec<DiagItem>) {
@@ -960,7 +1285,15 @@ fn check_cli_tools(items: &mut Vec<DiagItem>) {
     }
 }
 
-fn check_command_available(cmd: &str, args: &[&str], cat: &'static str, items: &mut Vec<DiagItem>) {
+/// Run `cmd args` and report the first line of its output. `missing_hint`
+/// is attached when the command is absent or fails.
+fn check_command_available(
+    cmd: &str,
+    args: &[&str],
+    cat: &'static str,
+    missing_hint: Option<&str>,
+    items: &mut Vec<DiagItem>,
+) {
     match std::process::Command::new(cmd)
         .args(args)
         .stdout(std::process::Stdio::piped())
@@ -974,17 +1307,23 @@ fn check_command_available(cmd: &str, args: &[&str], cat: &'static str, items: &
             items.push(DiagItem::ok(cat, format!("{cmd}: {display}")));
         }
         Ok(_) => {
-            items.push(DiagItem::warn(
-                cat,
-                format!("{cmd} found but returned non-zero"),
-            ));
+            let item = DiagItem::warn(cat, format!("{cmd} found but returned non-zero"));
+            items.push(with_optional_hint(item, missing_hint));
         }
         Err(_) => {
-            items.push(DiagItem::warn(cat, format!("{cmd} not found in PATH")));
+            let item = DiagItem::warn(cat, format!("{cmd} not found in PATH"));
+            items.push(with_optional_hint(item, missing_hint));
         }
     }
 }
 
+fn with_optional_hint(item: DiagItem, hint: Option<&str>) -> DiagItem {
+    match hint {
+        Some(hint) => item.hint(hint),
+        None => item,
+    }
+}
+
 fn format_error_chain(error: &anyhow::Error) -> String {
     let mut parts = Vec::new();
     for cause in error.chain() {
@@ -1315,4 +1654,127 @@ mod tests {
         assert!(agent_messages[0].contains("agent \"alpha\""));
         assert!(agent_messages[1].contains("agent \"zeta\""));
     }
+
+    #[test]
+    fn workspace_check_lists_missing_skeleton_entries() {
+        let tmp = TempDir::new().unwrap();
+        let config = Config {
+            workspace_dir: tmp.path().to_path_buf(),
+            ..Config::default()
+        };
+        for dir in crate::workspace::CORE_WORKSPACE_DIRS {
+            std::fs::create_dir_all(tmp.path().join(dir)).unwrap();
+        }
+        for file in &crate::workspace::CORE_WORKSPACE_FILES[1..] {
+            std::fs::write(tmp.path().join(file), "").unwrap();
+        }
+
+        let mut items = Vec::new();
+        check_workspace(&config, &mut items);
+        let skeleton = items
+            .iter()
+            .find(|i| i.message.starts_with("workspace skeleton"))
+            .unwrap();
+        assert_eq!(skeleton.severity, Severity::Warn);
+        assert!(skeleton.message.ends_with("missing: IDENTITY.md"));
+        assert!(skeleton.hint.is_some());
+    }
+
+    #[test]
+    fn missing_daemon_state_is_a_warning_with_a_hint() {
+        let tmp = TempDir::new().unwrap();
+        let config = Config {
+            config_path: tmp.path().join("config.toml"),
+            ..Config::default()
+        };
+        let mut items = Vec::new();
+        check_daemon_state(&config, &mut items);
+        assert_eq!(items[0].severity, Severity::Warn);
+        assert!(items[0]
+            .hint
+            .as_deref()
+            .unwrap()
+            .contains("slowclaw daemon"));
+    }
+
+    #[test]
+    fn port_check_flags_a_busy_gateway_port() {
+        let tmp = TempDir::new().unwrap();
+        let held = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut config = Config {
+            config_path: tmp.path().join("config.toml"),
+            ..Config::default()
+        };
+        config.gateway.host = "127.0.0.1".into();
+        config.gateway.port = held.local_addr().unwrap().port();
+
+        let mut items = Vec::new();
+        check_ports(&config, &mut items);
+        assert_eq!(items[0].severity, Severity::Error);
+        assert!(items[0].hint.is_some());
+
+        drop(held);
+        let mut items = Vec::new();
+        check_ports(&config, &mut items);
+        assert_eq!(items[0].severity, Severity::Ok);
+    }
+
+    #[tokio::test]
+    async fn provider_check_reports_an_unreachable_endpoint() {
+        let closed_port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let config = Config {
+            default_provider: Some(format!("custom:http://127.0.0.1:{closed_port}/v1")),
+            api_key: Some("sk-test".into()),
+            ..Config::default()
+        };
+
+        let mut items = Vec::new();
+        check_provider(&config, &mut items).await;
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].severity, Severity::Error);
+        assert!(items[0].hint.is_some());
+    }
+
+    #[tokio::test]
+    async fn provider_check_reports_a_rejected_credential() {
+        use wiremock::matchers::{header, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/chat/completions"))
+            .and(header("Authorization", "Bearer sk-revoked"))
+            .respond_with(ResponseTemplate::new(401).set_body_json(serde_json::json!({
+                "error": {"message": "Incorrect API key provided"}
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let config = Config {
+            default_provider: Some(format!("custom:{}/v1", server.uri())),
+            api_key: Some("sk-revoked".into()),
+            ..Config::default()
+        };
+
+        let mut items = Vec::new();
+        check_provider(&config, &mut items).await;
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].severity, Severity::Error);
+        assert!(items[0].message.contains("401"), "{}", items[0].message);
+    }
+
+    #[test]
+    fn results_serialize_hints_only_when_present() {
+        let plain = serde_json::to_value(DiagItem::ok("t", "m").into_result()).unwrap();
+        assert!(plain.get("hint").is_none());
+
+        let hinted =
+            serde_json::to_value(DiagItem::warn("t", "m").hint("do x").into_result()).unwrap();
+        assert_eq!(hinted["hint"], "do x");
+        assert_eq!(hinted["severity"], "warn");
+    }
 }
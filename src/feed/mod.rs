@@ -1671,10 +1671,9 @@ async fn fetch_nostr_relay_metadata(relay_url: &str) -> Result<Option<nostr_sdk:
     let Some(http_url) = nostr_relay_http_url(relay_url) else {
         return Ok(None);
     };
-    let response = reqwest::Client::builder()
-        .timeout(Duration::from_secs(NOSTR_RELAY_METADATA_TIMEOUT_SECS))
-        .build()?
+    let response = crate::http_client::shared()
         .get(&http_url)
+        .timeout(Duration::from_secs(NOSTR_RELAY_METADATA_TIMEOUT_SECS))
         .header("Accept", "application/nostr+json")
         .send()
         .await
@@ -3732,10 +3731,9 @@ struct RemoteFeedFetchResult {
 }
 
 async fn fetch_remote_feed(source: &local_store::ContentSourceRecord) -> Result<RemoteFeedFetchResult> {
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(RSS_CONTENT_FETCH_TIMEOUT_SECS))
-        .build()?;
-    let mut request = client.get(source.xml_url.trim());
+    let mut request = crate::http_client::shared()
+        .get(source.xml_url.trim())
+        .timeout(Duration::from_secs(RSS_CONTENT_FETCH_TIMEOUT_SECS));
     if !source.etag.trim().is_empty() {
         request = request.header(reqwest::header::IF_NONE_MATCH, source.etag.trim());
     }
@@ -4210,10 +4208,9 @@ async fn fetch_bluesky_feed_generator_page(
     limit: usize,
 ) -> Result<(Vec<CandidateFeedGenerator>, Option<String>)> {
     let url = build_bluesky_feed_generator_discovery_endpoint(service_url, cursor, limit);
-    let response = reqwest::Client::builder()
-        .timeout(Duration::from_secs(BLUESKY_FETCH_TIMEOUT_SECS))
-        .build()?
+    let response = crate::http_client::shared()
         .get(url)
+        .timeout(Duration::from_secs(BLUESKY_FETCH_TIMEOUT_SECS))
         .bearer_auth(access_jwt.trim())
         .send()
         .await
@@ -4291,10 +4288,9 @@ async fn fetch_bluesky_candidate_page(
     limit: usize,
 ) -> Result<(Vec<CandidateFeedPost>, Option<String>)> {
     let url = build_bluesky_feed_endpoint(service_url, source, cursor, limit);
-    let response = reqwest::Client::builder()
-        .timeout(Duration::from_secs(BLUESKY_FETCH_TIMEOUT_SECS))
-        .build()?
+    let response = crate::http_client::shared()
         .get(url)
+        .timeout(Duration::from_secs(BLUESKY_FETCH_TIMEOUT_SECS))
         .bearer_auth(access_jwt.trim())
         .send()
         .await
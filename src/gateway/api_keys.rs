@@ -0,0 +1,474 @@
+//! Long-lived API keys for automations (`/api/keys`).
+//!
+//! A key is a bearer token like a paired device token, but limited to the
+//! routes its `routes` globs allow (`POST /webhook`, `GET /api/library/*`),
+//! optionally rate limited on its own, and optionally expiring. Keys live in
+//! `state/api_keys.json` as SHA-256 hashes; the plaintext is shown once, when
+//! the key is created. Use is recorded in the security audit log and as
+//! `lastUsedAt` in the listing.
+
+use crate::security::{AuditEvent, AuditEventType, AuditLogger};
+use crate::workspace::atomic_write;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Every API key starts with this, which is how the auth checks tell keys
+/// from paired device tokens (`zc_`).
+pub const KEY_PREFIX: &str = "sck_";
+const MAX_LABEL_CHARS: usize = 80;
+const MAX_ROUTES: usize = 32;
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+/// `lastUsedAt` is written back to disk at most this often per key.
+const LAST_USED_WRITE_SECS: i64 = 60;
+const METHODS: [&str; 6] = ["*", "GET", "POST", "PUT", "PATCH", "DELETE"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiKey {
+    pub id: String,
+    pub label: String,
+    /// `METHOD /path` globs; `*` matches one path segment, `**` any number.
+    pub routes: Vec<String>,
+    #[serde(default)]
+    pub rate_limit_per_minute: Option<u32>,
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub last_used_at: Option<DateTime<Utc>>,
+    /// Hex SHA-256 of the key. Keys carry 256 random bits, so no salt.
+    hash: String,
+}
+
+impl ApiKey {
+    fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_some_and(|expires| expires <= now)
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "id": self.id,
+            "label": self.label,
+            "routes": self.routes,
+            "rateLimitPerMinute": self.rate_limit_per_minute,
+            "expiresAt": self.expires_at,
+            "expired": self.is_expired(Utc::now()),
+            "createdAt": self.created_at,
+            "lastUsedAt": self.last_used_at,
+        })
+    }
+
+    fn allows(&self, method: &str, path: &str) -> bool {
+        self.routes
+            .iter()
+            .any(|route| route_matches(route, method, path))
+    }
+}
+
+/// What to create; checked by [`NewApiKey::validate`].
+#[derive(Debug, Clone, Default)]
+pub struct NewApiKey {
+    pub label: String,
+    pub routes: Vec<String>,
+    pub rate_limit_per_minute: Option<u32>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum InvalidApiKey {
+    #[error("label is required and must be at most {MAX_LABEL_CHARS} characters")]
+    Label,
+    #[error("routes must list 1 to {MAX_ROUTES} entries like \"POST /webhook\"; invalid: {0}")]
+    Routes(String),
+    #[error("rateLimitPerMinute must be greater than zero")]
+    RateLimit,
+    #[error("expiresAt must be in the future")]
+    Expiry,
+}
+
+impl NewApiKey {
+    pub fn validate(&self) -> Result<(), InvalidApiKey> {
+        let label = self.label.trim();
+        if label.is_empty() || label.chars().count() > MAX_LABEL_CHARS {
+            return Err(InvalidApiKey::Label);
+        }
+        if self.routes.is_empty() || self.routes.len() > MAX_ROUTES {
+            return Err(InvalidApiKey::Routes(String::new()));
+        }
+        if let Some(route) = self
+            .routes
+            .iter()
+            .find(|route| parse_route(route).is_none())
+        {
+            return Err(InvalidApiKey::Routes(route.clone()));
+        }
+        if self.rate_limit_per_minute == Some(0) {
+            return Err(InvalidApiKey::RateLimit);
+        }
+        if self.expires_at.is_some_and(|expires| expires <= Utc::now()) {
+            return Err(InvalidApiKey::Expiry);
+        }
+        Ok(())
+    }
+}
+
+/// Why a request carrying an API key was refused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Denied {
+    Unknown,
+    Expired,
+    RouteNotAllowed,
+    RateLimited,
+}
+
+/// The key a request was accepted with.
+#[derive(Debug, Clone)]
+pub struct Caller {
+    pub id: String,
+    pub label: String,
+    pub rate_limited: bool,
+}
+
+fn parse_route(route: &str) -> Option<(&str, glob::Pattern)> {
+    let (method, path) = route.trim().split_once(' ')?;
+    let path = path.trim();
+    if !METHODS.contains(&method) || !path.starts_with('/') {
+        return None;
+    }
+    Some((method, glob::Pattern::new(path).ok()?))
+}
+
+fn route_matches(route: &str, method: &str, path: &str) -> bool {
+    let Some((allowed_method, pattern)) = parse_route(route) else {
+        return false;
+    };
+    let options = glob::MatchOptions {
+        case_sensitive: true,
+        require_literal_separator: true,
+        require_literal_leading_dot: false,
+    };
+    (allowed_method == "*" || allowed_method.eq_ignore_ascii_case(method))
+        && pattern.matches_with(path, options)
+}
+
+fn hash_key(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+/// The bearer token from `Authorization`, if it is an API key.
+pub fn bearer_key(headers: &axum::http::HeaderMap) -> Option<&str> {
+    headers
+        .get(axum::http::header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim)
+        .filter(|token| token.starts_with(KEY_PREFIX))
+}
+
+pub fn store_path(workspace_dir: &Path) -> PathBuf {
+    workspace_dir.join("state").join("api_keys.json")
+}
+
+/// API keys of one workspace, cached in memory and written through to
+/// `state/api_keys.json`.
+pub struct ApiKeyStore {
+    path: PathBuf,
+    keys: Mutex<Vec<ApiKey>>,
+    recent: Mutex<HashMap<String, Vec<Instant>>>,
+}
+
+impl ApiKeyStore {
+    fn load(path: PathBuf) -> Self {
+        let keys = match std::fs::read_to_string(&path) {
+            Ok(raw) => serde_json::from_str(&raw).unwrap_or_else(|err| {
+                tracing::warn!("Ignoring unreadable {}: {err}", path.display());
+                Vec::new()
+            }),
+            Err(_) => Vec::new(),
+        };
+        Self {
+            path,
+            keys: Mutex::new(keys),
+            recent: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn save(&self, keys: &[ApiKey]) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        let json = serde_json::to_vec_pretty(keys)?;
+        atomic_write(&self.path, json)
+            .with_context(|| format!("Failed to write {}", self.path.display()))
+    }
+
+    pub fn list(&self) -> Vec<ApiKey> {
+        self.keys.lock().clone()
+    }
+
+    /// Store a validated key; returns it with its plaintext, which is not
+    /// kept anywhere.
+    pub fn create(&self, new: NewApiKey) -> Result<(ApiKey, String)> {
+        let plaintext = format!("{KEY_PREFIX}{}", hex::encode(rand::random::<[u8; 32]>()));
+        let key = ApiKey {
+            id: format!("key_{}", hex::encode(rand::random::<[u8; 6]>())),
+            label: new.label.trim().to_string(),
+            routes: new
+                .routes
+                .iter()
+                .map(|route| route.trim().to_string())
+                .collect(),
+            rate_limit_per_minute: new.rate_limit_per_minute,
+            expires_at: new.expires_at,
+            created_at: Utc::now(),
+            last_used_at: None,
+            hash: hash_key(&plaintext),
+        };
+        let mut keys = self.keys.lock();
+        keys.push(key.clone());
+        if let Err(err) = self.save(&keys) {
+            keys.pop();
+            return Err(err);
+        }
+        Ok((key, plaintext))
+    }
+
+    /// Remove a key; `false` when there is no such key.
+    pub fn revoke(&self, id: &str) -> Result<bool> {
+        let mut keys = self.keys.lock();
+        let Some(index) = keys.iter().position(|key| key.id == id) else {
+            return Ok(false);
+        };
+        let removed = keys.remove(index);
+        if let Err(err) = self.save(&keys) {
+            keys.insert(index, removed);
+            return Err(err);
+        }
+        self.recent.lock().remove(id);
+        Ok(true)
+    }
+
+    /// The unexpired key with this plaintext.
+    pub fn authenticate(&self, key: &str) -> Option<Caller> {
+        let hash = hash_key(key);
+        let now = Utc::now();
+        self.keys
+            .lock()
+            .iter()
+            .find(|stored| crate::security::pairing::constant_time_eq(&stored.hash, &hash))
+            .filter(|stored| !stored.is_expired(now))
+            .map(|stored| Caller {
+                id: stored.id.clone(),
+                label: stored.label.clone(),
+                rate_limited: stored.rate_limit_per_minute.is_some(),
+            })
+    }
+
+    /// Check `key` against the route and its rate limit, and note the use.
+    pub fn authorize(&self, key: &str, method: &str, path: &str) -> Result<Caller, Denied> {
+        let hash = hash_key(key);
+        let now = Utc::now();
+        let mut keys = self.keys.lock();
+        let Some(stored) = keys
+            .iter_mut()
+            .find(|stored| crate::security::pairing::constant_time_eq(&stored.hash, &hash))
+        else {
+            return Err(Denied::Unknown);
+        };
+        if stored.is_expired(now) {
+            return Err(Denied::Expired);
+        }
+        if !stored.allows(method, path) {
+            return Err(Denied::RouteNotAllowed);
+        }
+        if let Some(limit) = stored.rate_limit_per_minute {
+            let instant = Instant::now();
+            let mut recent = self.recent.lock();
+            let hits = recent.entry(stored.id.clone()).or_default();
+            hits.retain(|hit| instant.duration_since(*hit) < RATE_WINDOW);
+            if hits.len() >= limit as usize {
+                return Err(Denied::RateLimited);
+            }
+            hits.push(instant);
+        }
+        let persist = stored
+            .last_used_at
+            .is_none_or(|last| now - last >= chrono::Duration::seconds(LAST_USED_WRITE_SECS));
+        stored.last_used_at = Some(now);
+        let caller = Caller {
+            id: stored.id.clone(),
+            label: stored.label.clone(),
+            rate_limited: stored.rate_limit_per_minute.is_some(),
+        };
+        if persist {
+            if let Err(err) = self.save(&keys) {
+                tracing::warn!("API key last-used update failed: {err:#}");
+            }
+        }
+        Ok(caller)
+    }
+}
+
+/// The shared store for `workspace_dir`, loaded on first use.
+pub fn store(workspace_dir: &Path) -> Arc<ApiKeyStore> {
+    static STORES: OnceLock<Mutex<HashMap<PathBuf, Arc<ApiKeyStore>>>> = OnceLock::new();
+    let path = store_path(workspace_dir);
+    STORES
+        .get_or_init(Mutex::default)
+        .lock()
+        .entry(path.clone())
+        .or_insert_with(|| Arc::new(ApiKeyStore::load(path)))
+        .clone()
+}
+
+/// Append an API key use or refusal to the security audit log.
+pub fn audit(
+    config: &crate::config::Config,
+    caller: Option<&Caller>,
+    route: &str,
+    denied: Option<Denied>,
+) {
+    let Some(zeroclaw_dir) = config.config_path.parent().map(Path::to_path_buf) else {
+        return;
+    };
+    let event_type = if denied.is_some() {
+        AuditEventType::AuthFailure
+    } else {
+        AuditEventType::AuthSuccess
+    };
+    let mut event = AuditEvent::new(event_type)
+        .with_actor(
+            "api_key".to_string(),
+            caller.map(|caller| caller.id.clone()),
+            caller.map(|caller| caller.label.clone()),
+        )
+        .with_action(
+            route.to_string(),
+            "low".to_string(),
+            false,
+            denied.is_none(),
+        );
+    if let Some(denied) = denied {
+        event.result = Some(crate::security::audit::ExecutionResult {
+            success: false,
+            exit_code: None,
+            duration_ms: None,
+            error: Some(format!("{denied:?}")),
+        });
+    }
+    let audit_config = config.security.audit.clone();
+    tokio::task::spawn_blocking(move || {
+        let logged = AuditLogger::new(audit_config, zeroclaw_dir).and_then(|log| log.log(&event));
+        if let Err(err) = logged {
+            tracing::debug!("API key audit write failed: {err:#}");
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_key(routes: &[&str]) -> NewApiKey {
+        NewApiKey {
+            label: "Shortcuts".into(),
+            routes: routes.iter().map(|route| route.to_string()).collect(),
+            ..NewApiKey::default()
+        }
+    }
+
+    #[test]
+    fn routes_match_method_and_path_globs() {
+        assert!(route_matches("POST /webhook", "POST", "/webhook"));
+        assert!(!route_matches("POST /webhook", "GET", "/webhook"));
+        assert!(route_matches(
+            "* /api/library/*",
+            "GET",
+            "/api/library/text"
+        ));
+        assert!(!route_matches(
+            "* /api/library/*",
+            "POST",
+            "/api/library/trash/restore"
+        ));
+        assert!(route_matches(
+            "POST /api/library/**",
+            "POST",
+            "/api/library/trash/restore"
+        ));
+        assert!(!route_matches("POST webhook", "POST", "/webhook"));
+
+        assert_eq!(
+            new_key(&["FETCH /webhook"]).validate(),
+            Err(InvalidApiKey::Routes("FETCH /webhook".into()))
+        );
+        assert_eq!(
+            new_key(&[]).validate(),
+            Err(InvalidApiKey::Routes(String::new()))
+        );
+        assert!(new_key(&["POST /webhook"]).validate().is_ok());
+    }
+
+    #[test]
+    fn keys_are_stored_hashed_and_scoped() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = ApiKeyStore::load(store_path(tmp.path()));
+        let (key, plaintext) = store
+            .create(NewApiKey {
+                rate_limit_per_minute: Some(2),
+                ..new_key(&["POST /webhook"])
+            })
+            .unwrap();
+        assert!(plaintext.starts_with(KEY_PREFIX));
+        let saved = std::fs::read_to_string(store_path(tmp.path())).unwrap();
+        assert!(!saved.contains(&plaintext));
+        assert!(key.to_json().get("hash").is_none());
+
+        assert_eq!(
+            store.authorize(&plaintext, "GET", "/api/feed").unwrap_err(),
+            Denied::RouteNotAllowed
+        );
+        assert_eq!(
+            store.authorize(&plaintext, "POST", "/webhook").unwrap().id,
+            key.id
+        );
+        assert!(store.authorize(&plaintext, "POST", "/webhook").is_ok());
+        assert_eq!(
+            store.authorize(&plaintext, "POST", "/webhook").unwrap_err(),
+            Denied::RateLimited
+        );
+        assert_eq!(
+            store.authorize("sck_nope", "POST", "/webhook").unwrap_err(),
+            Denied::Unknown
+        );
+
+        let reloaded = ApiKeyStore::load(store_path(tmp.path()));
+        assert!(reloaded.list()[0].last_used_at.is_some());
+        assert!(reloaded.authenticate(&plaintext).is_some());
+        assert!(reloaded.revoke(&key.id).unwrap());
+        assert!(!reloaded.revoke(&key.id).unwrap());
+        assert!(reloaded.authenticate(&plaintext).is_none());
+    }
+
+    #[test]
+    fn expired_keys_are_refused() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = ApiKeyStore::load(store_path(tmp.path()));
+        let (key, plaintext) = store.create(new_key(&["POST /webhook"])).unwrap();
+        store.keys.lock()[0].expires_at = Some(Utc::now() - chrono::Duration::minutes(1));
+        assert!(store.authenticate(&plaintext).is_none());
+        assert_eq!(
+            store.authorize(&plaintext, "POST", "/webhook").unwrap_err(),
+            Denied::Expired
+        );
+        assert_eq!(key.to_json()["expired"], false);
+    }
+}
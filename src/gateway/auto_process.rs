@@ -0,0 +1,302 @@
+//! Auto-processing of fresh media uploads.
+//!
+//! Each `[[gateway.auto_process]]` rule names a tool to run on every upload
+//! of a given kind. A run is a background job (see [`crate::jobs`]) that calls
+//! the tool with `{ "path", "asset_id" }` and walks the asset's `media_assets`
+//! status from `uploaded` through `processing` to `processed` or `error`.
+//! Runs of the same rule are serialized, so a burst of uploads queues up
+//! instead of starting several heavy renders at once. When
+//! `gateway.auto_process_thread` is set, job completion is posted there.
+
+use super::local_store;
+use crate::channels::context::{with_channel_execution_context, ChannelExecutionContext};
+use crate::config::{AutoProcessRule, Config};
+use crate::jobs::{JobManager, JobOutcome};
+use crate::memory::Memory;
+use crate::security::SecurityPolicy;
+use crate::tools::ToolProfile;
+use anyhow::{bail, Context, Result};
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use tokio::sync::Semaphore;
+
+pub const STATUS_PROCESSING: &str = "processing";
+pub const STATUS_PROCESSED: &str = "processed";
+pub const STATUS_ERROR: &str = "error";
+
+/// Rules whose `match_kind` equals the upload's kind (ASCII case-insensitive).
+pub fn matching_rules<'a>(
+    rules: &'a [AutoProcessRule],
+    kind: &'a str,
+) -> impl Iterator<Item = &'a AutoProcessRule> + 'a {
+    rules.iter().filter(move |rule| {
+        !rule.tool.trim().is_empty() && rule.match_kind.trim().eq_ignore_ascii_case(kind.trim())
+    })
+}
+
+/// Enqueue one job per rule matching `kind` and return the job ids.
+pub async fn trigger(
+    jobs: &Arc<JobManager>,
+    config: &Config,
+    mem: Arc<dyn Memory>,
+    kind: &str,
+    rel_path: &str,
+    asset_id: Option<&str>,
+) -> Vec<String> {
+    let thread = config
+        .gateway
+        .auto_process_thread
+        .as_deref()
+        .map(str::trim)
+        .filter(|thread| !thread.is_empty());
+    let mut job_ids = Vec::new();
+    for rule in matching_rules(&config.gateway.auto_process, kind) {
+        let args = serde_json::json!({ "path": rel_path, "asset_id": asset_id });
+        let work = run_serialized(
+            rule_slot(rule),
+            config.workspace_dir.clone(),
+            rel_path.to_string(),
+            Box::pin(execute_tool(
+                config.clone(),
+                Arc::clone(&mem),
+                rule.tool.clone(),
+                args,
+            )),
+        );
+        let job_id = match thread {
+            // The job manager reads its completion target from the channel
+            // context active when the job is enqueued.
+            Some(thread) => {
+                let ctx = ChannelExecutionContext::new("pocketbase", thread, None);
+                with_channel_execution_context(ctx, async { jobs.enqueue(&rule.tool, work) }).await
+            }
+            None => jobs.enqueue(&rule.tool, work),
+        };
+        tracing::info!(tool = %rule.tool, path = rel_path, job_id, "Auto-process job queued");
+        job_ids.push(job_id);
+    }
+    job_ids
+}
+
+/// One permit per rule, shared by every upload that matches it.
+fn rule_slot(rule: &AutoProcessRule) -> Arc<Semaphore> {
+    static SLOTS: OnceLock<Mutex<HashMap<String, Arc<Semaphore>>>> = OnceLock::new();
+    let key = format!(
+        "{}:{}",
+        rule.match_kind.trim().to_ascii_lowercase(),
+        rule.tool.trim()
+    );
+    Arc::clone(
+        SLOTS
+            .get_or_init(|| Mutex::new(HashMap::new()))
+            .lock()
+            .entry(key)
+            .or_insert_with(|| Arc::new(Semaphore::new(1))),
+    )
+}
+
+/// Wait for the rule's slot, then run `work` while tracking the asset status.
+async fn run_serialized<F>(
+    slot: Arc<Semaphore>,
+    workspace_dir: PathBuf,
+    rel_path: String,
+    work: F,
+) -> Result<JobOutcome>
+where
+    F: Future<Output = Result<JobOutcome>>,
+{
+    let _permit = slot
+        .acquire_owned()
+        .await
+        .context("Auto-process queue closed")?;
+    set_status(&workspace_dir, &rel_path, STATUS_PROCESSING, "");
+    let result = work.await;
+    match &result {
+        Ok(_) => set_status(&workspace_dir, &rel_path, STATUS_PROCESSED, ""),
+        Err(err) => set_status(&workspace_dir, &rel_path, STATUS_ERROR, &err.to_string()),
+    }
+    result
+}
+
+/// Update the asset's status and its error text (empty clears it).
+fn set_status(workspace_dir: &Path, rel_path: &str, status: &str, error: &str) {
+    let result = local_store::set_library_item_status(workspace_dir, rel_path, status)
+        .and_then(|_| local_store::set_media_asset_error(workspace_dir, rel_path, error));
+    if let Err(err) = result {
+        tracing::warn!("Auto-process status update for {rel_path} failed: {err:#}");
+    }
+}
+
+async fn execute_tool(
+    config: Config,
+    mem: Arc<dyn Memory>,
+    tool_name: String,
+    args: serde_json::Value,
+) -> Result<JobOutcome> {
+    let runtime: Arc<dyn crate::runtime::RuntimeAdapter> =
+        Arc::from(crate::runtime::create_runtime(&config.runtime)?);
+    let security = Arc::new(SecurityPolicy::from_config(
+        &config.autonomy,
+        &config.workspace_dir,
+    ));
+    let (composio_key, composio_entity_id) = if config.composio.enabled {
+        (
+            config.composio.api_key.as_deref(),
+            Some(config.composio.entity_id.as_str()),
+        )
+    } else {
+        (None, None)
+    };
+    // Rules come from config.toml, not from a paired client, so they may name
+    // any tool the agent itself could run.
+    let tools = crate::tools::all_tools_with_runtime_and_profile(
+        Arc::new(config.clone()),
+        &security,
+        runtime,
+        ToolProfile::Full,
+        mem,
+        composio_key,
+        composio_entity_id,
+        &config.browser,
+        &config.http_request,
+        &config.web_fetch,
+        &config.workspace_dir,
+        &config.agents,
+        config.api_key.as_deref(),
+        &config,
+    );
+    let Some(tool) = tools.iter().find(|tool| tool.name() == tool_name) else {
+        bail!("Unknown auto-process tool `{tool_name}`");
+    };
+    let result = tool.execute(args).await?;
+    if !result.success {
+        bail!(
+            "{}",
+            result
+                .error
+                .filter(|err| !err.trim().is_empty())
+                .unwrap_or(result.output)
+        );
+    }
+    Ok(JobOutcome {
+        output: result.output,
+        artifacts: Vec::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    fn rule(kind: &str, tool: &str) -> AutoProcessRule {
+        AutoProcessRule {
+            match_kind: kind.into(),
+            tool: tool.into(),
+        }
+    }
+
+    fn upload(workspace: &Path, rel_path: &str) {
+        local_store::initialize(workspace).unwrap();
+        local_store::create_media_asset_metadata(
+            workspace,
+            &local_store::MediaAssetInput {
+                title: String::new(),
+                entry_id: String::new(),
+                asset_type: "audio".into(),
+                mime_type: "audio/mpeg".into(),
+                source: "test".into(),
+                status: "uploaded".into(),
+                workspace_path: rel_path.into(),
+                size_bytes: 3,
+                created_at_client: None,
+                probe: None,
+                tags_csv: String::new(),
+            },
+        )
+        .unwrap();
+    }
+
+    fn status(workspace: &Path, rel_path: &str) -> String {
+        local_store::library_item_status(workspace, rel_path)
+            .unwrap()
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn matching_rules_compare_kind_case_insensitively() {
+        let rules = vec![
+            rule("audio", "audio_to_video"),
+            rule("image", "image_describe"),
+            rule("AUDIO", "transcribe"),
+            rule("audio", " "),
+        ];
+        let tools: Vec<&str> = matching_rules(&rules, "Audio")
+            .map(|rule| rule.tool.as_str())
+            .collect();
+        assert_eq!(tools, vec!["audio_to_video", "transcribe"]);
+    }
+
+    #[tokio::test]
+    async fn serialized_runs_move_status_and_never_overlap() {
+        let tmp = tempfile::tempdir().unwrap();
+        let ws = tmp.path().to_path_buf();
+        upload(&ws, "journals/media/audio/a.mp3");
+        upload(&ws, "journals/media/audio/b.mp3");
+        let slot = Arc::new(Semaphore::new(1));
+        let running = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+
+        let run = |path: &'static str, fail: bool| {
+            let (running, peak) = (Arc::clone(&running), Arc::clone(&peak));
+            let ws_check = ws.clone();
+            run_serialized(Arc::clone(&slot), ws.clone(), path.into(), async move {
+                assert_eq!(status(&ws_check, path), STATUS_PROCESSING);
+                let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                running.fetch_sub(1, Ordering::SeqCst);
+                if fail {
+                    bail!("render failed");
+                }
+                Ok(JobOutcome::default())
+            })
+        };
+        let (a, b) = tokio::join!(
+            run("journals/media/audio/a.mp3", false),
+            run("journals/media/audio/b.mp3", true)
+        );
+
+        assert!(a.is_ok());
+        assert!(b.is_err());
+        assert_eq!(peak.load(Ordering::SeqCst), 1);
+        assert_eq!(status(&ws, "journals/media/audio/a.mp3"), STATUS_PROCESSED);
+        assert_eq!(status(&ws, "journals/media/audio/b.mp3"), STATUS_ERROR);
+        let states =
+            local_store::library_record_states(&ws, &["journals/media/audio/b.mp3".to_string()])
+                .unwrap();
+        assert_eq!(states["journals/media/audio/b.mp3"].error, "render failed");
+    }
+
+    #[tokio::test]
+    async fn unknown_tool_fails_the_run() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.workspace_dir = tmp.path().to_path_buf();
+        let mem_cfg = crate::config::MemoryConfig {
+            backend: "markdown".into(),
+            ..crate::config::MemoryConfig::default()
+        };
+        let mem: Arc<dyn Memory> =
+            Arc::from(crate::memory::create_memory(&mem_cfg, tmp.path(), None).unwrap());
+
+        let err = execute_tool(config, mem, "no_such_tool".into(), serde_json::json!({}))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("no_such_tool"));
+    }
+}
@@ -0,0 +1,946 @@
+//! Gateway startup, split into the stages `run_gateway` runs in order.
+//!
+//! [`GatewayBuilder::new`] checks the bind address, `bind` opens the TCP
+//! or Unix socket listener, `build_state` creates the local store, provider, memory, and
+//! auth state, `persist_migrated_tokens` saves paired tokens salted on load,
+//! `announce` prints the banner, `start_sidecars` and
+//! `start_tunnel` launch background work, `build_router` assembles the
+//! routes, and `serve` runs until the listener closes.
+
+use super::{
+    build_router, ensure_workflow_bot_creation_skill, ensure_workspace_journal_drop_folders,
+    hash_webhook_secret, library_watch, local_store, normalize_max_keys, persist_pairing_tokens,
+    start_journal_inbox_maintenance, start_memory_retention, start_outbound_retry,
+    start_post_draft_expiry, start_runtime_alerts, start_status_heartbeat, start_trash_maintenance,
+    startup_json_line, workspace_git, AppState, GatewayListener, GatewayRateLimiter,
+    IdempotencyStore, IDEMPOTENCY_MAX_KEYS_DEFAULT, PAIRING_CODE_MARKER,
+    RATE_LIMIT_MAX_KEYS_DEFAULT,
+};
+use crate::channels::pocketbase_schema::SchemaReport;
+use crate::config::{Config, GatewayConfig};
+use crate::cost::guard::BudgetGuard;
+use crate::hooks::HookRunner;
+use crate::memory::{self, Memory};
+use crate::observability::alerts::AlertDispatcher;
+use crate::providers::{self, Provider};
+use crate::security::pairing::{is_public_bind, PairingGuard, SecretString};
+use crate::security::trusted_proxies::TrustedProxies;
+use crate::security::AutonomyLevel;
+use anyhow::{Context, Result};
+use axum::Router;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+pub struct GatewayBuilder {
+    host: String,
+    port: u16,
+    config: Config,
+}
+
+/// Everything `build_state` creates, handed to the later stages.
+pub struct GatewayState {
+    pub app: AppState,
+    pub local_store: local_store::BootstrapReport,
+    hooks: Option<Arc<HookRunner>>,
+    alerts: Option<AlertDispatcher>,
+    heartbeat_thread: Option<String>,
+}
+
+/// Background tasks started by `start_sidecars`. In read-only mode only
+/// `runtime_alerts` may run; everything else writes to the workspace,
+/// PocketBase, or a channel.
+pub struct SidecarHandles {
+    pub journal_inbox: Option<JoinHandle<()>>,
+    pub trash: Option<JoinHandle<()>>,
+    pub post_draft_expiry: Option<JoinHandle<()>>,
+    pub memory_retention: Option<JoinHandle<()>>,
+    pub pocketbase_schema: Option<JoinHandle<SchemaReport>>,
+    pub runtime_alerts: Option<JoinHandle<()>>,
+    pub status_heartbeat: Option<JoinHandle<()>>,
+    pub library_watch: Option<JoinHandle<()>>,
+    pub workspace_git: Option<JoinHandle<()>>,
+    pub outbound_retry: Option<JoinHandle<()>>,
+}
+
+impl SidecarHandles {
+    pub fn abort(&self) {
+        if let Some(handle) = &self.pocketbase_schema {
+            handle.abort();
+        }
+        for handle in [
+            &self.journal_inbox,
+            &self.trash,
+            &self.post_draft_expiry,
+            &self.outbound_retry,
+            &self.memory_retention,
+            &self.runtime_alerts,
+            &self.status_heartbeat,
+            &self.library_watch,
+            &self.workspace_git,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            handle.abort();
+        }
+    }
+}
+
+/// The public URL slot that `/health`, `/api/gateway-info`, and the startup
+/// JSON line report. The gateway has no tunnel runtime of its own yet, so the
+/// slot stays empty until something sets it.
+#[derive(Clone)]
+pub struct TunnelHandle {
+    url: Arc<Mutex<Option<String>>>,
+}
+
+impl TunnelHandle {
+    pub fn url(&self) -> Option<String> {
+        self.url.lock().clone()
+    }
+
+    pub fn set_url(&self, url: Option<String>) {
+        *self.url.lock() = url;
+    }
+}
+
+impl GatewayBuilder {
+    /// Refuses a public bind address unless `gateway.allow_public_bind` is set.
+    pub fn new(host: &str, port: u16, config: Config) -> Result<Self> {
+        // Ensure the rustls CryptoProvider is installed. When the gateway is run
+        // as a library call (e.g. from Tauri) rather than via main(), the provider
+        // may not have been set up yet. Without this, any HTTPS request (RSS feeds,
+        // Bluesky API, Nostr relays, embedding providers) will panic.
+        let _ = rustls::crypto::ring::default_provider().install_default();
+
+        // ── Security: refuse public bind without explicit opt-in ──
+        // A Unix socket replaces the TCP listener, so `host` is not bound.
+        if config.gateway.uds_path.is_none()
+            && is_public_bind(host)
+            && !config.gateway.allow_public_bind
+        {
+            anyhow::bail!(
+                "🛑 Refusing to bind to {host} — gateway would be exposed to the internet.\n\
+                 Fix: use --host 127.0.0.1 (default) or set\n\
+                 [gateway] allow_public_bind = true in config.toml (NOT recommended)."
+            );
+        }
+        // ── Refuse a channel section whose webhooks this build cannot verify ──
+        if let Some((section, fix)) = unverifiable_channel(&config) {
+            anyhow::bail!(
+                "🛑 [channels_config.{section}] is set, but this channel's signature verification is not available in this build.\n\
+                 Fix: {fix}"
+            );
+        }
+        if let Some(warning) = whatsapp_config_warning(&config) {
+            tracing::warn!("{warning}");
+        }
+        // Read-only mode reaches the tools through the autonomy level, so
+        // every `SecurityPolicy` built from this config refuses to act.
+        let mut config = config;
+        if config.gateway.read_only {
+            config.autonomy.level = AutonomyLevel::ReadOnly;
+        }
+        Ok(Self {
+            host: host.to_string(),
+            port,
+            config,
+        })
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// With `startup_json`, stdout carries only the JSON startup line and the
+    /// human banner moves to stderr.
+    fn emit(&self, line: &str) {
+        if self.config.gateway.startup_json {
+            eprintln!("{line}");
+        } else {
+            println!("{line}");
+        }
+    }
+
+    /// Listen on `gateway.uds_path` when set, otherwise on `host:port`.
+    pub async fn bind(&self) -> Result<GatewayListener> {
+        if let Some(path) = &self.config.gateway.uds_path {
+            return GatewayListener::bind_unix(Path::new(path), self.config.gateway.uds_mode);
+        }
+        let addr: SocketAddr = format!("{}:{}", self.host, self.port).parse()?;
+        GatewayListener::bind_tcp(addr).await
+    }
+
+    /// Prepare the workspace and create the shared handler state.
+    pub fn build_state(&self) -> Result<GatewayState> {
+        let config = &self.config;
+        let config_state = Arc::new(Mutex::new(config.clone()));
+
+        if config
+            .memory
+            .embedding_provider
+            .trim()
+            .eq_ignore_ascii_case("builtin")
+        {
+            let provider = config.memory.embedding_provider.clone();
+            let model = config.memory.embedding_model.clone();
+            tokio::spawn(async move {
+                if let Err(err) =
+                    memory::embeddings::prewarm_builtin_embedding_assets(&provider, &model).await
+                {
+                    tracing::warn!(
+                        error = %err,
+                        "Failed to prewarm local builtin embedding assets"
+                    );
+                }
+            });
+        }
+
+        crate::workspace::set_external_roots(
+            &config.workspace_dir,
+            config
+                .workspace
+                .external_roots
+                .iter()
+                .map(|root| (root.path().to_path_buf(), root.writable())),
+        );
+        if !config.gateway.read_only {
+            match crate::workspace::repair_skeleton(&config.workspace_dir) {
+                Ok(created) if !created.is_empty() => {
+                    tracing::info!("Restored missing workspace entries: {}", created.join(", "));
+                }
+                Ok(_) => {}
+                Err(err) => tracing::warn!("Failed to repair workspace skeleton: {err}"),
+            }
+            if let Err(err) = ensure_workflow_bot_creation_skill(&config.workspace_dir) {
+                tracing::warn!("Failed to ensure workflow bot creation skill: {err}");
+            }
+            if let Err(err) = ensure_workspace_journal_drop_folders(&config.workspace_dir) {
+                tracing::warn!("Failed to ensure workspace journal inbox folders: {err}");
+            }
+            crate::workspace::remove_orphaned_temp_files(&config.workspace_dir);
+        }
+
+        // ── Hooks ──────────────────────────────────────────────────────
+        let hooks = config.hooks.enabled.then(|| Arc::new(HookRunner::new()));
+
+        let local_bootstrap = local_store::initialize(&config.workspace_dir)
+            .context("Failed to initialize local gateway store")?;
+        for line in migration_lines(&local_bootstrap) {
+            self.emit(&line);
+        }
+
+        let provider = providers::create_resilient_provider_with_options(
+            config
+                .default_provider
+                .as_deref()
+                .unwrap_or(providers::DEFAULT_PROVIDER),
+            config.api_key.as_deref(),
+            config.api_url.as_deref(),
+            &config.reliability,
+            &providers::ProviderRuntimeOptions {
+                auth_profile_override: None,
+                provider_api_url: config.api_url.clone(),
+                zeroclaw_dir: config.config_path.parent().map(std::path::PathBuf::from),
+                secrets_encrypt: config.secrets.encrypt,
+                reasoning_enabled: config.runtime.reasoning_enabled,
+            },
+        )?;
+        // Inside the cache, so answers served from disk cost nothing.
+        let provider: Arc<dyn Provider> = Arc::from(BudgetGuard::wrap(
+            provider,
+            &config.cost,
+            &config.workspace_dir,
+        )?);
+        let model = config
+            .default_model
+            .clone()
+            .unwrap_or_else(|| providers::DEFAULT_MODEL.into());
+        let mem: Arc<dyn Memory> = Arc::from(memory::create_memory_with_storage(
+            &config.memory,
+            Some(&config.storage.provider.config),
+            &config.workspace_dir,
+            config.api_key.as_deref(),
+        )?);
+        // Extract webhook secret for authentication
+        let webhook_secret_hash: Option<SecretString> =
+            config.channels_config.webhook.as_ref().and_then(|webhook| {
+                webhook.secret.as_ref().and_then(|raw_secret| {
+                    let trimmed_secret = raw_secret.trim();
+                    (!trimmed_secret.is_empty())
+                        .then(|| SecretString::new(hash_webhook_secret(trimmed_secret)))
+                })
+            });
+
+        // ── Pairing guard ──────────────────────────────────────
+        // With `uds_trusted`, every client is a socket peer the file
+        // permissions already admitted, so pairing is satisfied up front.
+        let pairing = Arc::new(PairingGuard::new(
+            config.gateway.require_pairing && !config.gateway.uds_satisfies_pairing(),
+            &config.gateway.paired_tokens,
+        ));
+        let rate_limit_max_keys = normalize_max_keys(
+            config.gateway.rate_limit_max_keys,
+            RATE_LIMIT_MAX_KEYS_DEFAULT,
+        );
+        let rate_limiter = Arc::new(GatewayRateLimiter::new(
+            config.gateway.pair_rate_limit_per_minute,
+            config.gateway.webhook_rate_limit_per_minute,
+            rate_limit_max_keys,
+        ));
+        let idempotency_max_keys = normalize_max_keys(
+            config.gateway.idempotency_max_keys,
+            IDEMPOTENCY_MAX_KEYS_DEFAULT,
+        );
+        let idempotency_store = Arc::new(IdempotencyStore::new(
+            Duration::from_secs(config.gateway.idempotency_ttl_secs.max(1)),
+            idempotency_max_keys,
+        ));
+
+        // The activity timeline rides alongside whatever backend is configured;
+        // `/api/activity` finds it with `find_observer`.
+        let alerts = AlertDispatcher::spawn(&config.observability);
+        let mut observers: Vec<Box<dyn crate::observability::Observer>> = vec![
+            crate::observability::create_observer(&config.observability),
+            Box::new(crate::observability::ActivityObserver::new(
+                config.observability.debug_payloads,
+            )),
+        ];
+        let heartbeat_thread = config
+            .observability
+            .heartbeat_thread
+            .as_deref()
+            .map(str::trim)
+            .filter(|thread| !thread.is_empty())
+            .map(str::to_string);
+        if heartbeat_thread.is_some() {
+            observers.push(Box::new(
+                crate::observability::stats::RuntimeStatsObserver::new(),
+            ));
+        }
+        if let Some(alerts) = &alerts {
+            observers.push(Box::new(crate::observability::alerts::AlertObserver::new(
+                alerts.clone(),
+                config.observability.alert_llm_failure_threshold,
+            )));
+        }
+        let observer: Arc<dyn crate::observability::Observer> =
+            Arc::new(crate::observability::MultiObserver::new(observers));
+        memory::dedupe::set_dedupe_observer(Arc::clone(&observer));
+
+        // Repeated deterministic prompts are answered from disk when
+        // `[provider] cache_ttl_secs` is set.
+        let provider: Arc<dyn Provider> = if config.provider.cache_ttl_secs > 0 {
+            Arc::new(
+                providers::cache::CachedProvider::open(
+                    provider,
+                    &config.workspace_dir,
+                    &config.provider,
+                )?
+                .with_observer(Arc::clone(&observer)),
+            )
+        } else {
+            provider
+        };
+
+        let app = AppState {
+            config: config_state,
+            provider,
+            model,
+            temperature: config.default_temperature,
+            mem,
+            auto_save: config.memory.auto_save,
+            webhook_secret_hash,
+            pairing,
+            trusted_proxies: TrustedProxies::from_config(
+                config.gateway.trust_forwarded_headers,
+                &config.gateway.trusted_proxies,
+            ),
+            rate_limiter,
+            idempotency_store,
+            observer,
+            pb_chat_base_url: None,
+            pb_chat_collection: "chat_messages".to_string(),
+            pb_chat_token: None,
+            journal_transcription_jobs: Arc::new(Mutex::new(HashMap::new())),
+            jobs: crate::jobs::JobManager::for_workspace(&config.workspace_dir),
+            openrouter_oauth: Arc::new(Mutex::new(None)),
+            model_catalog: Arc::new(Mutex::new(None)),
+            chat_runs: Arc::new(Mutex::new(HashMap::new())),
+            tunnel_url: Arc::new(Mutex::new(None)),
+        };
+
+        Ok(GatewayState {
+            app,
+            local_store: local_bootstrap,
+            hooks,
+            alerts,
+            heartbeat_thread,
+        })
+    }
+
+    /// Save the paired tokens `PairingGuard::new` salted on load, so plaintext
+    /// and unsalted entries leave config.toml on the first start.
+    pub async fn persist_migrated_tokens(&self, gateway: &GatewayState) {
+        let pairing = &gateway.app.pairing;
+        if !pairing.migrated_on_load() {
+            return;
+        }
+        match persist_pairing_tokens(gateway.app.config.clone(), pairing).await {
+            Ok(()) => tracing::info!("🔐 Salted the paired tokens stored in config.toml"),
+            Err(err) => tracing::warn!("🔐 Failed to save the salted paired tokens: {err:#}"),
+        }
+    }
+
+    /// Print the banner, mark the gateway healthy, and fire the start hook.
+    pub async fn announce(&self, port: u16, gateway: &GatewayState) {
+        let listen_url = match &self.config.gateway.uds_path {
+            Some(path) => format!("unix:{path}"),
+            None => format!("http://{}:{port}", self.host),
+        };
+        for line in banner_lines(
+            &listen_url,
+            &gateway.local_store.db_path,
+            &self.config.workspace_dir,
+            &gateway.app.pairing,
+            &self.config.gateway,
+        ) {
+            self.emit(&line);
+        }
+
+        crate::health::mark_component_ok("gateway");
+
+        // Fire gateway start hook
+        if let Some(ref hooks) = gateway.hooks {
+            hooks.fire_gateway_start(&self.host, port).await;
+        }
+    }
+
+    /// Start maintenance loops, the PocketBase schema bootstrap, the reply
+    /// retry loop, and the optional alert and heartbeat loops.
+    pub fn start_sidecars(&self, gateway: &mut GatewayState) -> SidecarHandles {
+        let state = &gateway.app;
+        // Email and Bluesky never listen; registering them lets job
+        // completion notices addressed to `email` or `bluesky` find a sender.
+        if let Some(email) = self.config.channels_config.email.as_ref() {
+            if let Ok(channel) = crate::channels::EmailChannel::from_config(email) {
+                crate::channels::register_live_channel(Arc::new(channel));
+            }
+        }
+        if let Some(client) = crate::bluesky::BlueskyClient::from_env(&self.config.workspace_dir) {
+            let security = Arc::new(crate::security::SecurityPolicy::from_config(
+                &self.config.autonomy,
+                &self.config.workspace_dir,
+            ));
+            crate::channels::register_live_channel(Arc::new(
+                crate::channels::bluesky::BlueskyChannel::new(Arc::new(client), security),
+            ));
+        }
+        let writable = !self.config.gateway.read_only;
+        SidecarHandles {
+            journal_inbox: writable.then(|| start_journal_inbox_maintenance(state.clone())),
+            trash: writable.then(|| start_trash_maintenance(state.clone())),
+            post_draft_expiry: writable.then(|| start_post_draft_expiry(state.clone())),
+            memory_retention: writable
+                .then(|| start_memory_retention(state.clone()))
+                .flatten(),
+            // Create or extend the PocketBase chat collection in the background so a
+            // slow or absent PocketBase does not hold up startup.
+            pocketbase_schema: writable
+                .then(|| tokio::spawn(crate::channels::pocketbase_schema::bootstrap_from_env())),
+            runtime_alerts: gateway
+                .alerts
+                .take()
+                .map(|alerts| start_runtime_alerts(state.clone(), alerts)),
+            status_heartbeat: gateway.heartbeat_thread.take().filter(|_| writable).map(
+                |thread_id| {
+                    start_status_heartbeat(
+                        state.clone(),
+                        thread_id,
+                        self.config.observability.heartbeat_interval_hours,
+                    )
+                },
+            ),
+            library_watch: (writable && self.config.gateway.library_watch).then(|| {
+                library_watch::spawn(
+                    self.config.workspace_dir.clone(),
+                    Duration::from_secs(self.config.gateway.library_rescan_interval_secs),
+                )
+            }),
+            workspace_git: (writable && self.config.workspace.git_autocommit).then(|| {
+                workspace_git::spawn(
+                    self.config.workspace_dir.clone(),
+                    self.config.workspace.git_max_file_bytes,
+                )
+            }),
+            outbound_retry: writable.then(|| start_outbound_retry(state.clone())),
+        }
+    }
+
+    pub fn start_tunnel(&self, gateway: &GatewayState) -> TunnelHandle {
+        TunnelHandle {
+            url: Arc::clone(&gateway.app.tunnel_url),
+        }
+    }
+
+    pub fn build_router(&self, gateway: &GatewayState) -> Router {
+        build_router(gateway.app.clone(), &self.config)
+    }
+
+    /// Print the startup JSON line when requested, then serve until the
+    /// listener closes.
+    pub async fn serve(
+        &self,
+        listener: GatewayListener,
+        app: Router,
+        gateway: &GatewayState,
+    ) -> Result<()> {
+        if self.config.gateway.startup_json {
+            let port = listener.port()?;
+            println!("{}", startup_json_line(&self.host, port, &gateway.app));
+        }
+
+        listener.serve(app).await
+    }
+}
+
+/// Lines printed after a legacy local store was migrated; empty otherwise.
+/// The first configured channel whose webhook signatures this build cannot
+/// check, with the fix. Stub verifiers answer `Unsupported` whatever they are
+/// given, so probing them with empty input tells a stub from the real thing.
+fn unverifiable_channel(config: &Config) -> Option<(&'static str, &'static str)> {
+    use crate::channels::linq::verify_linq_signature;
+    use crate::channels::nextcloud_talk::verify_nextcloud_talk_signature;
+    use crate::channels::VerifyResult;
+
+    let channels = &config.channels_config;
+    if channels.nextcloud_talk.is_some()
+        && verify_nextcloud_talk_signature("", "", "", "") == VerifyResult::Unsupported
+    {
+        return Some((
+            "nextcloud_talk",
+            "rebuild with --features channel-nextcloud, or remove the section.",
+        ));
+    }
+    let linq_signed = channels
+        .linq
+        .as_ref()
+        .is_some_and(|linq| linq.signing_secret.is_some());
+    if linq_signed && verify_linq_signature("", "", "", "") == VerifyResult::Unsupported {
+        return Some((
+            "linq",
+            "Linq webhooks are not supported by this fork; remove signing_secret or the section.",
+        ));
+    }
+    None
+}
+
+/// Why `[channels_config.whatsapp]` will not be served, if it will not.
+/// A warning rather than an error: the section is also read by the
+/// channel runtime, which may not need the gateway routes.
+fn whatsapp_config_warning(config: &Config) -> Option<&'static str> {
+    let whatsapp = config.channels_config.whatsapp.as_ref()?;
+    if !cfg!(feature = "channel-whatsapp") {
+        return Some(
+            "⚠️ [channels_config.whatsapp] is set, but this build has no WhatsApp support; \
+             /whatsapp will answer 404. Rebuild with --features channel-whatsapp.",
+        );
+    }
+    (!whatsapp.is_cloud_config()).then_some(
+        "⚠️ [channels_config.whatsapp] needs access_token, phone_number_id, and verify_token \
+         for the Cloud API; /whatsapp will answer 404.",
+    )
+}
+
+fn migration_lines(report: &local_store::BootstrapReport) -> Vec<String> {
+    if !report.migrated_from_legacy {
+        return Vec::new();
+    }
+    vec![
+        format!(
+            "  💾 Local store migration complete from {}",
+            report
+                .legacy_source
+                .as_ref()
+                .map(|p| p.display().to_string())
+                .unwrap_or_else(|| "unknown source".to_string())
+        ),
+        format!(
+            "     chat_messages={} drafts={} post_history={} journal_entries={} media_assets={} artifacts={}",
+            report.migrated_chat_messages,
+            report.migrated_drafts,
+            report.migrated_post_history,
+            report.migrated_journal_entries,
+            report.migrated_media_assets,
+            report.migrated_artifacts
+        ),
+    ]
+}
+
+/// `listen_url` is `http://host:port` or `unix:/path/to/gateway.sock`.
+fn banner_lines(
+    listen_url: &str,
+    db_path: &Path,
+    workspace_dir: &Path,
+    pairing: &PairingGuard,
+    gateway: &GatewayConfig,
+) -> Vec<String> {
+    let mut lines = vec![format!("🦀 SlowClaw Gateway listening on {listen_url}")];
+    // A browser cannot open a Unix socket, so there is no UI address to show.
+    if gateway.serve_ui && gateway.uds_path.is_none() {
+        let route_prefix = gateway.route_prefix();
+        lines.push(format!("  🌐 Web UI: {listen_url}{route_prefix}/"));
+    }
+    lines.extend([
+        format!("  💾 Local store: {}", db_path.display()),
+        format!("  📁 Workspace: {}", workspace_dir.display()),
+        "  POST /pair      — pair a new client (X-Pairing-Code header)".to_string(),
+        "  POST /pair/new-code — mint a fresh one-time pairing code (requires bearer)".to_string(),
+        "  POST /webhook   — {\"message\": \"your prompt\"}".to_string(),
+        "  GET  /health    — health check".to_string(),
+        "  GET  /metrics   — Prometheus metrics".to_string(),
+    ]);
+    if let Some(code) = pairing.pairing_code() {
+        lines.extend([
+            String::new(),
+            "  🔐 PAIRING REQUIRED — use this one-time code:".to_string(),
+            "     ┌──────────────┐".to_string(),
+            format!("     │  {code}  │"),
+            "     └──────────────┘".to_string(),
+            format!("     Send: POST /pair with header X-Pairing-Code: {code}"),
+            format!("{PAIRING_CODE_MARKER} {code}"),
+        ]);
+    } else if pairing.require_pairing() {
+        lines.push("  🔒 Pairing: ACTIVE (bearer token required)".to_string());
+    } else if gateway.uds_satisfies_pairing() {
+        lines.push("  🔒 Pairing: socket peers trusted (uds_trusted)".to_string());
+    } else {
+        lines.push("  ⚠️  Pairing: DISABLED (all requests accepted)".to_string());
+    }
+    if gateway.read_only {
+        lines.push("  👀 READ-ONLY: writes are refused and nothing is posted".to_string());
+    }
+    lines.push("  Press Ctrl+C to stop.\n".to_string());
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gateway::test_support::TestGateway;
+    use std::path::PathBuf;
+
+    #[test]
+    fn banner_matches_the_established_output() {
+        let pairing = PairingGuard::new(true, &[]);
+        let code = pairing.pairing_code().unwrap();
+        let lines = banner_lines(
+            "http://127.0.0.1:42617",
+            &PathBuf::from("/ws/.slowclaw/local.db"),
+            &PathBuf::from("/ws"),
+            &pairing,
+            &GatewayConfig::default(),
+        );
+        let expected = format!(
+            "🦀 SlowClaw Gateway listening on http://127.0.0.1:42617
+  🌐 Web UI: http://127.0.0.1:42617/
+  💾 Local store: /ws/.slowclaw/local.db
+  📁 Workspace: /ws
+  POST /pair      — pair a new client (X-Pairing-Code header)
+  POST /pair/new-code — mint a fresh one-time pairing code (requires bearer)
+  POST /webhook   — {{\"message\": \"your prompt\"}}
+  GET  /health    — health check
+  GET  /metrics   — Prometheus metrics
+
+  🔐 PAIRING REQUIRED — use this one-time code:
+     ┌──────────────┐
+     │  {code}  │
+     └──────────────┘
+     Send: POST /pair with header X-Pairing-Code: {code}
+PAIRING-CODE: {code}
+  Press Ctrl+C to stop.
+"
+        );
+        assert_eq!(lines.join("\n"), expected);
+    }
+
+    #[test]
+    fn banner_reports_pairing_mode_without_a_code() {
+        let paired = PairingGuard::new(true, &["zc_token".to_string()]);
+        let lines = banner_lines(
+            "h:1",
+            Path::new("db"),
+            Path::new("ws"),
+            &paired,
+            &GatewayConfig::default(),
+        );
+        assert!(lines.contains(&"  🔒 Pairing: ACTIVE (bearer token required)".to_string()));
+
+        let open = PairingGuard::new(false, &[]);
+        let lines = banner_lines(
+            "h:1",
+            Path::new("db"),
+            Path::new("ws"),
+            &open,
+            &GatewayConfig::default(),
+        );
+        assert!(lines.contains(&"  ⚠️  Pairing: DISABLED (all requests accepted)".to_string()));
+        assert_eq!(lines.last().unwrap(), "  Press Ctrl+C to stop.\n");
+    }
+
+    #[test]
+    fn banner_leaves_out_the_web_ui_when_it_is_not_served() {
+        let paired = PairingGuard::new(true, &["zc_token".to_string()]);
+        let gateway = GatewayConfig {
+            serve_ui: false,
+            ..GatewayConfig::default()
+        };
+        let lines = banner_lines("h:1", Path::new("db"), Path::new("ws"), &paired, &gateway);
+        assert!(!lines.iter().any(|line| line.contains("Web UI")));
+        assert!(lines.iter().any(|line| line.contains("GET  /health")));
+    }
+
+    #[test]
+    fn read_only_mode_is_announced_and_reaches_the_autonomy_level() {
+        let paired = PairingGuard::new(true, &["zc_token".to_string()]);
+        let gateway = GatewayConfig {
+            read_only: true,
+            ..GatewayConfig::default()
+        };
+        let lines = banner_lines("h:1", Path::new("db"), Path::new("ws"), &paired, &gateway);
+        assert!(lines.iter().any(|line| line.contains("READ-ONLY")));
+
+        let mut config = Config::default();
+        config.gateway.read_only = true;
+        let builder = GatewayBuilder::new("127.0.0.1", 0, config).unwrap();
+        assert_eq!(builder.config().autonomy.level, AutonomyLevel::ReadOnly);
+        let policy = crate::security::SecurityPolicy::from_config(
+            &builder.config().autonomy,
+            &builder.config().workspace_dir,
+        );
+        assert!(!policy.can_act());
+    }
+
+    #[test]
+    fn public_bind_is_refused_without_opt_in() {
+        let err = GatewayBuilder::new("0.0.0.0", 0, Config::default())
+            .err()
+            .expect("public bind refused");
+        assert!(err
+            .to_string()
+            .starts_with("🛑 Refusing to bind to 0.0.0.0"));
+
+        let mut config = Config::default();
+        config.gateway.allow_public_bind = true;
+        assert!(GatewayBuilder::new("0.0.0.0", 0, config).is_ok());
+    }
+
+    #[cfg(not(feature = "channel-nextcloud"))]
+    #[test]
+    fn nextcloud_config_is_refused_without_the_feature() {
+        let mut config = Config::default();
+        config.channels_config.nextcloud_talk = Some(crate::config::schema::NextcloudTalkConfig {
+            base_url: "https://cloud.example.com".into(),
+            app_token: "app-token".into(),
+            webhook_secret: None,
+            allowed_users: vec!["*".into()],
+        });
+        let err = GatewayBuilder::new("127.0.0.1", 0, config)
+            .err()
+            .expect("nextcloud config refused");
+        assert!(err.to_string().contains("channel-nextcloud"));
+        assert!(err
+            .to_string()
+            .contains("signature verification is not available in this build"));
+    }
+
+    #[test]
+    fn signed_linq_config_is_refused_at_startup() {
+        let mut config = Config::default();
+        config.channels_config.linq = Some(crate::config::schema::LinqConfig {
+            api_token: "token".into(),
+            from_phone: "+15551234567".into(),
+            signing_secret: None,
+            allowed_senders: vec!["*".into()],
+        });
+        assert!(GatewayBuilder::new("127.0.0.1", 0, config.clone()).is_ok());
+
+        if let Some(linq) = config.channels_config.linq.as_mut() {
+            linq.signing_secret = Some("whsec".into());
+        }
+        let err = GatewayBuilder::new("127.0.0.1", 0, config)
+            .err()
+            .expect("signed linq config refused");
+        assert!(err.to_string().starts_with(
+            "🛑 [channels_config.linq] is set, but this channel's signature verification"
+        ));
+    }
+
+    #[test]
+    fn whatsapp_config_warns_when_it_cannot_be_served() {
+        let mut config = Config::default();
+        assert_eq!(whatsapp_config_warning(&config), None);
+        config.channels_config.whatsapp = Some(crate::config::schema::WhatsAppConfig {
+            access_token: Some("token".into()),
+            phone_number_id: Some("106540352242922".into()),
+            verify_token: Some("verify".into()),
+            app_secret: Some("secret".into()),
+            session_path: None,
+            pair_phone: None,
+            pair_code: None,
+            allowed_numbers: vec!["*".into()],
+            reply_template: None,
+            reply_template_language: None,
+            api_url: None,
+        });
+        let warning = whatsapp_config_warning(&config);
+        if cfg!(feature = "channel-whatsapp") {
+            assert_eq!(warning, None);
+        } else {
+            assert!(warning.unwrap().contains("channel-whatsapp"));
+        }
+
+        if let Some(whatsapp) = config.channels_config.whatsapp.as_mut() {
+            whatsapp.verify_token = None;
+        }
+        assert!(whatsapp_config_warning(&config).is_some());
+        assert!(GatewayBuilder::new("127.0.0.1", 0, config).is_ok());
+    }
+
+    #[tokio::test]
+    async fn state_built_by_the_builder_serves_requests() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut config = Config {
+            workspace_dir: tmp.path().join("workspace"),
+            config_path: tmp.path().join("config.toml"),
+            ..Config::default()
+        };
+        config.memory.backend = "none".into();
+        config.memory.embedding_provider = "none".into();
+        config.gateway.require_pairing = false;
+        std::fs::create_dir_all(&config.workspace_dir).unwrap();
+
+        let builder = GatewayBuilder::new("127.0.0.1", 0, config).unwrap();
+        let mut gateway = builder.build_state().unwrap();
+        assert!(gateway.local_store.db_path.starts_with(tmp.path()));
+        assert!(!gateway.app.pairing.require_pairing());
+
+        let tunnel = builder.start_tunnel(&gateway);
+        assert_eq!(tunnel.url(), None);
+        tunnel.set_url(Some("https://example.trycloudflare.com".into()));
+        assert_eq!(
+            gateway.app.tunnel_url.lock().as_deref(),
+            Some("https://example.trycloudflare.com")
+        );
+
+        let sidecars = builder.start_sidecars(&mut gateway);
+        assert!(sidecars.runtime_alerts.is_none());
+        assert!(sidecars.status_heartbeat.is_none());
+        sidecars.abort();
+
+        let server = TestGateway::spawn(gateway.app.clone(), builder.config()).await;
+        let health: serde_json::Value = reqwest::get(server.url("/health"))
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(health["status"], "ok");
+        assert_eq!(health["tunnel_url"], "https://example.trycloudflare.com");
+    }
+
+    #[tokio::test]
+    async fn plaintext_and_legacy_tokens_leave_config_on_first_start() {
+        use sha2::{Digest, Sha256};
+
+        let tmp = tempfile::tempdir().unwrap();
+        let mut config = Config {
+            workspace_dir: tmp.path().join("workspace"),
+            config_path: tmp.path().join("config.toml"),
+            ..Config::default()
+        };
+        config.memory.backend = "none".into();
+        config.memory.embedding_provider = "none".into();
+        let legacy = format!("{:x}", Sha256::digest(b"zc_legacy"));
+        config.gateway.paired_tokens = vec!["zc_plaintext".into(), legacy.clone()];
+        std::fs::create_dir_all(&config.workspace_dir).unwrap();
+        config.save().await.unwrap();
+
+        let builder = GatewayBuilder::new("127.0.0.1", 0, config.clone()).unwrap();
+        let gateway = builder.build_state().unwrap();
+        builder.persist_migrated_tokens(&gateway).await;
+
+        let saved = std::fs::read_to_string(&config.config_path).unwrap();
+        let reloaded: Config = toml::from_str(&saved).unwrap();
+        let stored = &reloaded.gateway.paired_tokens;
+        assert_eq!(stored.len(), 2);
+        for token in stored {
+            let parts: Vec<&str> = token.split('$').collect();
+            assert_eq!(parts.len(), 3, "{token}");
+            assert_eq!(parts[0], "sha256", "{token}");
+            assert_eq!(parts[2].len(), 64, "{token}");
+        }
+        assert!(!saved.contains("zc_plaintext"));
+        assert!(!saved.contains(&legacy));
+
+        let pairing = PairingGuard::new(true, stored);
+        assert!(!pairing.migrated_on_load());
+        assert!(pairing.is_authenticated("zc_plaintext"));
+        assert!(pairing.is_authenticated("zc_legacy"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn trusted_socket_serves_without_pairing_or_a_tcp_port() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let tmp = tempfile::tempdir().unwrap();
+        let socket = tmp.path().join("gateway.sock");
+        let mut config = Config {
+            workspace_dir: tmp.path().join("workspace"),
+            config_path: tmp.path().join("config.toml"),
+            ..Config::default()
+        };
+        config.memory.backend = "none".into();
+        config.memory.embedding_provider = "none".into();
+        config.gateway.uds_path = Some(socket.display().to_string());
+        config.gateway.uds_trusted = true;
+        std::fs::create_dir_all(&config.workspace_dir).unwrap();
+
+        // The host is never bound, so a public one is not refused.
+        let builder = GatewayBuilder::new("0.0.0.0", 0, config).unwrap();
+        let gateway = builder.build_state().unwrap();
+        assert!(!gateway.app.pairing.require_pairing());
+
+        let lines = banner_lines(
+            "unix:/run/gateway.sock",
+            Path::new("db"),
+            Path::new("ws"),
+            &gateway.app.pairing,
+            &builder.config().gateway,
+        );
+        assert_eq!(
+            lines[0],
+            "🦀 SlowClaw Gateway listening on unix:/run/gateway.sock"
+        );
+        assert!(!lines.iter().any(|line| line.contains("Web UI")));
+        assert!(lines.contains(&"  🔒 Pairing: socket peers trusted (uds_trusted)".to_string()));
+
+        let listener = builder.bind().await.unwrap();
+        let server = tokio::spawn(listener.serve(builder.build_router(&gateway)));
+        let mut stream = tokio::net::UnixStream::connect(&socket).await.unwrap();
+        stream
+            .write_all(b"GET /api/feed HTTP/1.1\r\nHost: gateway\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        server.abort();
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+    }
+}
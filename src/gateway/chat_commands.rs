@@ -0,0 +1,395 @@
+//! Slash commands the gateway chat answers itself instead of running the agent.
+//!
+//! Each [`ChatCommand`] has a name, aliases, and a handler that parses its
+//! argument and returns the reply. `/help` lists the registry. A message that
+//! looks like a slash command but matches nothing gets a hint instead of
+//! reaching the model; `/home/me/notes.md ...` style paths are not commands.
+//! The plain-text forms `approve <id>`, `deny <id>` and "show my pending
+//! drafts" map onto their commands.
+
+use super::{
+    cancel_chat_runs, chat_thread_model_allowed, chat_thread_model_error,
+    chat_thread_temperature_allowed, chat_thread_temperature_error, local_store, AppState,
+};
+use crate::channels::ChannelExecutionContext;
+use crate::config::Config;
+use crate::post_drafts;
+use anyhow::Result;
+use std::fmt::Write as _;
+
+pub const NOTHING_TO_CANCEL_REPLY: &str = "Nothing is running in this thread.";
+const PENDING_DRAFTS_LIMIT: usize = 20;
+/// `/model default` or `/temp default` drops the thread's override.
+const SETTING_RESET: &str = "default";
+
+/// What a command handler can reach.
+pub struct ChatCommandContext<'a> {
+    pub state: &'a AppState,
+    pub config: &'a Config,
+    pub thread_id: &'a str,
+}
+
+pub struct ChatCommand {
+    /// Without the leading `/`; matched case-insensitively, as are aliases.
+    pub name: &'static str,
+    pub aliases: &'static [&'static str],
+    /// Argument synopsis shown by `/help`, e.g. `[name|default]`.
+    pub args: &'static str,
+    pub summary: &'static str,
+    /// Returns the reply, or `None` when the reply is posted elsewhere (a
+    /// cancelled run's worker confirms the cancellation itself).
+    pub run: fn(&ChatCommandContext<'_>, &str) -> Result<Option<String>>,
+}
+
+impl std::fmt::Debug for ChatCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ChatCommand")
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ChatCommand {
+    fn matches(&self, name: &str) -> bool {
+        self.name.eq_ignore_ascii_case(name)
+            || self
+                .aliases
+                .iter()
+                .any(|alias| alias.eq_ignore_ascii_case(name))
+    }
+
+    fn usage(&self) -> String {
+        if self.args.is_empty() {
+            format!("/{}", self.name)
+        } else {
+            format!("/{} {}", self.name, self.args)
+        }
+    }
+}
+
+pub const COMMANDS: &[ChatCommand] = &[
+    ChatCommand {
+        name: "help",
+        aliases: &["commands"],
+        args: "",
+        summary: "list chat commands",
+        run: run_help,
+    },
+    ChatCommand {
+        name: "cancel",
+        aliases: &["stop"],
+        args: "",
+        summary: "stop the request running in this thread",
+        run: run_cancel,
+    },
+    ChatCommand {
+        name: "model",
+        aliases: &[],
+        args: "[name|default]",
+        summary: "show or set this thread's model",
+        run: run_model,
+    },
+    ChatCommand {
+        name: "temp",
+        aliases: &["temperature"],
+        args: "[value|default]",
+        summary: "show or set this thread's temperature",
+        run: run_temperature,
+    },
+    ChatCommand {
+        name: "drafts",
+        aliases: &[],
+        args: "",
+        summary: "list drafts waiting for approval",
+        run: run_drafts,
+    },
+    ChatCommand {
+        name: "approve",
+        aliases: &[],
+        args: "<id>",
+        summary: "let a parked tool call run",
+        run: run_approve,
+    },
+    ChatCommand {
+        name: "deny",
+        aliases: &[],
+        args: "<id>",
+        summary: "refuse a parked tool call",
+        run: run_deny,
+    },
+];
+
+/// A chat message the registry claims.
+#[derive(Debug, Clone, Copy)]
+pub enum ChatCommandMatch<'a> {
+    Known(&'static ChatCommand, &'a str),
+    Unknown(&'a str),
+}
+
+pub fn find(name: &str) -> Option<&'static ChatCommand> {
+    COMMANDS.iter().find(|command| command.matches(name))
+}
+
+/// The command `content` invokes, if any. `None` sends it to the agent.
+pub fn match_command(content: &str) -> Option<ChatCommandMatch<'_>> {
+    let content = content.trim();
+    if let Some(rest) = content.strip_prefix('/') {
+        let (name, args) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+        let is_word = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !is_word {
+            return None;
+        }
+        return Some(match find(name) {
+            Some(command) => ChatCommandMatch::Known(command, args.trim()),
+            None => ChatCommandMatch::Unknown(name),
+        });
+    }
+    if let Some((approve, id)) = crate::approval::chat::parse_chat_command(content) {
+        let name = if approve { "approve" } else { "deny" };
+        return find(name).map(|command| ChatCommandMatch::Known(command, id));
+    }
+    if post_drafts::is_pending_drafts_request(content) {
+        return find("drafts").map(|command| ChatCommandMatch::Known(command, ""));
+    }
+    None
+}
+
+pub fn unknown_command_reply(name: &str) -> String {
+    format!("Unknown command /{name}. Send /help to see the available commands.")
+}
+
+fn usage_reply(name: &str) -> String {
+    let usage = find(name).map_or_else(|| format!("/{name}"), ChatCommand::usage);
+    format!("Usage: {usage}")
+}
+
+pub fn help_reply() -> String {
+    let mut out = String::from("Chat commands:");
+    for command in COMMANDS {
+        let _ = write!(out, "\n{} — {}", command.usage(), command.summary);
+        if !command.aliases.is_empty() {
+            let aliases: Vec<String> = command.aliases.iter().map(|a| format!("/{a}")).collect();
+            let _ = write!(out, " (also {})", aliases.join(", "));
+        }
+    }
+    out
+}
+
+/// A `/model` or `/temp` argument.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SettingArg<T> {
+    Show,
+    Reset,
+    Set(T),
+}
+
+pub fn parse_model_args(args: &str) -> SettingArg<&str> {
+    match args.trim() {
+        "" => SettingArg::Show,
+        reset if reset.eq_ignore_ascii_case(SETTING_RESET) => SettingArg::Reset,
+        model => SettingArg::Set(model),
+    }
+}
+
+/// `None` when the argument is not a number.
+pub fn parse_temperature_args(args: &str) -> Option<SettingArg<f64>> {
+    match parse_model_args(args) {
+        SettingArg::Show => Some(SettingArg::Show),
+        SettingArg::Reset => Some(SettingArg::Reset),
+        SettingArg::Set(raw) => raw.parse::<f64>().ok().map(SettingArg::Set),
+    }
+}
+
+/// The approval id of `/approve <id>` or `/deny <id>`.
+pub fn parse_approval_args(args: &str) -> Option<&str> {
+    let mut words = args.split_whitespace();
+    let id = words.next()?;
+    (words.next().is_none() && id.starts_with("ap_")).then_some(id)
+}
+
+fn run_help(_ctx: &ChatCommandContext<'_>, _args: &str) -> Result<Option<String>> {
+    Ok(Some(help_reply()))
+}
+
+fn run_cancel(ctx: &ChatCommandContext<'_>, args: &str) -> Result<Option<String>> {
+    if !args.is_empty() {
+        return Ok(Some(usage_reply("cancel")));
+    }
+    if cancel_chat_runs(ctx.state, Some(ctx.thread_id), None).is_empty() {
+        return Ok(Some(NOTHING_TO_CANCEL_REPLY.to_string()));
+    }
+    Ok(None)
+}
+
+fn run_model(ctx: &ChatCommandContext<'_>, args: &str) -> Result<Option<String>> {
+    let workspace_dir = &ctx.config.workspace_dir;
+    let mut settings = local_store::get_chat_thread_settings(workspace_dir, ctx.thread_id)?;
+    let default_model = ctx.config.default_model.as_deref().unwrap_or("not set");
+    let reply = match parse_model_args(args) {
+        SettingArg::Show => match settings.model.as_deref() {
+            Some(model) => format!("This thread uses {model}."),
+            None => format!("This thread uses the default model ({default_model})."),
+        },
+        SettingArg::Reset => {
+            settings.model = None;
+            local_store::set_chat_thread_settings(workspace_dir, ctx.thread_id, &settings)?;
+            format!("Model reset to the default ({default_model}).")
+        }
+        SettingArg::Set(model) if chat_thread_model_allowed(ctx.config, model) => {
+            settings.model = Some(model.to_string());
+            local_store::set_chat_thread_settings(workspace_dir, ctx.thread_id, &settings)?;
+            format!("Model set to {model} for this thread.")
+        }
+        SettingArg::Set(model) => chat_thread_model_error(ctx.config, model),
+    };
+    Ok(Some(reply))
+}
+
+fn run_temperature(ctx: &ChatCommandContext<'_>, args: &str) -> Result<Option<String>> {
+    let workspace_dir = &ctx.config.workspace_dir;
+    let mut settings = local_store::get_chat_thread_settings(workspace_dir, ctx.thread_id)?;
+    let default_temperature = ctx.config.default_temperature;
+    let reply = match parse_temperature_args(args) {
+        Some(SettingArg::Show) => match settings.temperature {
+            Some(temperature) => format!("This thread uses temperature {temperature}."),
+            None => format!("This thread uses the default temperature ({default_temperature})."),
+        },
+        Some(SettingArg::Reset) => {
+            settings.temperature = None;
+            local_store::set_chat_thread_settings(workspace_dir, ctx.thread_id, &settings)?;
+            format!("Temperature reset to the default ({default_temperature}).")
+        }
+        Some(SettingArg::Set(temperature))
+            if chat_thread_temperature_allowed(ctx.config, temperature) =>
+        {
+            settings.temperature = Some(temperature);
+            local_store::set_chat_thread_settings(workspace_dir, ctx.thread_id, &settings)?;
+            format!("Temperature set to {temperature} for this thread.")
+        }
+        Some(SettingArg::Set(_)) | None => chat_thread_temperature_error(ctx.config),
+    };
+    Ok(Some(reply))
+}
+
+fn run_drafts(ctx: &ChatCommandContext<'_>, _args: &str) -> Result<Option<String>> {
+    post_drafts::pending_summary(&ctx.config.workspace_dir, PENDING_DRAFTS_LIMIT).map(Some)
+}
+
+fn run_approval(ctx: &ChatCommandContext<'_>, args: &str, verb: &str) -> Result<Option<String>> {
+    let Some(id) = parse_approval_args(args) else {
+        return Ok(Some(usage_reply(verb)));
+    };
+    // Without pairing anyone who can reach the gateway could post here.
+    if !ctx.state.pairing.require_pairing() {
+        return Ok(Some(
+            "Approving tool calls from the gateway chat needs gateway pairing.".to_string(),
+        ));
+    }
+    let from = ChannelExecutionContext::new(
+        "local",
+        ctx.thread_id.to_string(),
+        Some(ctx.thread_id.to_string()),
+    );
+    let reply = crate::approval::chat::handle_chat_command(
+        &ctx.config.workspace_dir,
+        &format!("{verb} {id}"),
+        &from,
+    );
+    Ok(reply)
+}
+
+fn run_approve(ctx: &ChatCommandContext<'_>, args: &str) -> Result<Option<String>> {
+    run_approval(ctx, args, "approve")
+}
+
+fn run_deny(ctx: &ChatCommandContext<'_>, args: &str) -> Result<Option<String>> {
+    run_approval(ctx, args, "deny")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn known(content: &str) -> Option<(&'static str, &str)> {
+        match match_command(content)? {
+            ChatCommandMatch::Known(command, args) => Some((command.name, args)),
+            ChatCommandMatch::Unknown(_) => None,
+        }
+    }
+
+    #[test]
+    fn command_names_and_aliases_are_unique() {
+        let mut seen = HashSet::new();
+        for command in COMMANDS {
+            for name in std::iter::once(&command.name).chain(command.aliases) {
+                assert!(seen.insert(name.to_ascii_lowercase()), "duplicate /{name}");
+            }
+        }
+    }
+
+    #[test]
+    fn registry_matches_names_aliases_and_plain_text_forms() {
+        assert_eq!(known("/cancel"), Some(("cancel", "")));
+        assert_eq!(known(" /STOP "), Some(("cancel", "")));
+        assert_eq!(known("/temperature  0.4"), Some(("temp", "0.4")));
+        assert_eq!(known("/model cheap-model"), Some(("model", "cheap-model")));
+        assert_eq!(known("approve ap_12"), Some(("approve", "ap_12")));
+        assert_eq!(known("/deny ap_12"), Some(("deny", "ap_12")));
+        assert_eq!(known("show my pending drafts"), Some(("drafts", "")));
+
+        assert!(matches!(
+            match_command("/remind me later"),
+            Some(ChatCommandMatch::Unknown("remind"))
+        ));
+        for message in [
+            "hello",
+            "/",
+            "/ model",
+            "/home/me/notes.md summarize",
+            "approve it",
+        ] {
+            assert!(match_command(message).is_none(), "{message:?}");
+        }
+    }
+
+    #[test]
+    fn help_lists_every_command_with_its_usage() {
+        let help = help_reply();
+        for command in COMMANDS {
+            assert!(help.contains(&command.usage()), "{help}");
+        }
+        assert!(help.contains(
+            "/temp [value|default] — show or set this thread's temperature (also /temperature)"
+        ));
+        assert_eq!(
+            unknown_command_reply("remind"),
+            "Unknown command /remind. Send /help to see the available commands."
+        );
+    }
+
+    #[test]
+    fn setting_arguments_parse_show_reset_and_values() {
+        assert_eq!(parse_model_args(""), SettingArg::Show);
+        assert_eq!(parse_model_args(" Default "), SettingArg::Reset);
+        assert_eq!(
+            parse_model_args("openai/gpt-4o-mini"),
+            SettingArg::Set("openai/gpt-4o-mini")
+        );
+        assert_eq!(parse_temperature_args(""), Some(SettingArg::Show));
+        assert_eq!(parse_temperature_args("default"), Some(SettingArg::Reset));
+        assert_eq!(parse_temperature_args("0.7"), Some(SettingArg::Set(0.7)));
+        assert_eq!(parse_temperature_args("warm"), None);
+    }
+
+    #[test]
+    fn approval_arguments_need_a_single_approval_id() {
+        assert_eq!(parse_approval_args("ap_1234"), Some("ap_1234"));
+        assert_eq!(parse_approval_args(""), None);
+        assert_eq!(parse_approval_args("1234"), None);
+        assert_eq!(parse_approval_args("ap_1 ap_2"), None);
+    }
+}
@@ -0,0 +1,444 @@
+//! Thin HTTP client for a running gateway, used by `slowclaw gw`.
+//!
+//! The base URL comes from `--url`, `ZEROCLAW_GATEWAY_URL`, or the configured
+//! `gateway.host`/`gateway.port`. The bearer token comes from `--token`,
+//! `ZEROCLAW_GATEWAY_TOKEN`, or the token `gw pair` saved (encrypted like
+//! other secrets) next to `config.toml`.
+
+use crate::config::Config;
+use crate::security::SecretStore;
+use anyhow::Context;
+use futures_util::StreamExt;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+
+pub const GATEWAY_URL_ENV: &str = "ZEROCLAW_GATEWAY_URL";
+pub const GATEWAY_TOKEN_ENV: &str = "ZEROCLAW_GATEWAY_TOKEN";
+const SAVED_TOKEN_FILE: &str = "gateway_client_token";
+const CHAT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, thiserror::Error)]
+pub enum GatewayClientError {
+    #[error("cannot reach gateway at {url}: {source}")]
+    Connect {
+        url: String,
+        #[source]
+        source: reqwest::Error,
+    },
+    #[error("gateway rejected the request ({status}): {message}. Pair with `slowclaw gw pair <code>` or pass --token")]
+    Unauthorized { status: u16, message: String },
+    #[error("gateway returned {status}: {message}")]
+    Http { status: u16, message: String },
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+impl GatewayClientError {
+    /// Process exit code for `slowclaw gw`: 3 unreachable, 4 auth, 5 other
+    /// 4xx, 6 5xx, 1 anything else. Clap keeps 2 for usage errors.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::Connect { .. } => 3,
+            Self::Unauthorized { .. } => 4,
+            Self::Http { status, .. } if *status < 500 => 5,
+            Self::Http { .. } => 6,
+            Self::Other(_) => 1,
+        }
+    }
+}
+
+pub type ClientResult<T> = std::result::Result<T, GatewayClientError>;
+
+fn saved_token_path(config: &Config) -> Option<PathBuf> {
+    config
+        .config_path
+        .parent()
+        .map(|dir| dir.join(SAVED_TOKEN_FILE))
+}
+
+fn secret_store(config: &Config) -> Option<SecretStore> {
+    config
+        .config_path
+        .parent()
+        .map(|dir| SecretStore::new(dir, config.secrets.encrypt))
+}
+
+/// Token stored by a previous `gw pair`, if any.
+pub fn load_saved_token(config: &Config) -> Option<String> {
+    let raw = std::fs::read_to_string(saved_token_path(config)?).ok()?;
+    let token = secret_store(config)?.decrypt(raw.trim()).ok()?;
+    let token = token.trim();
+    (!token.is_empty()).then(|| token.to_string())
+}
+
+/// Save a bearer token for later `gw` calls.
+pub fn save_token(config: &Config, token: &str) -> anyhow::Result<PathBuf> {
+    let path = saved_token_path(config).context("config path has no parent directory")?;
+    let store = secret_store(config).context("config path has no parent directory")?;
+    std::fs::write(&path, store.encrypt(token.trim())?)
+        .with_context(|| format!("failed to write {}", path.display()))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let _ = std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600));
+    }
+    Ok(path)
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+pub struct GatewayClient {
+    http: reqwest::Client,
+    base_url: String,
+    token: Option<String>,
+}
+
+impl GatewayClient {
+    pub fn new(base_url: &str, token: Option<String>) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            base_url: base_url.trim().trim_end_matches('/').to_string(),
+            token: non_empty(token),
+        }
+    }
+
+    /// Client for the gateway described by flags, environment, and config,
+    /// in that order.
+    pub fn from_config(config: &Config, url: Option<String>, token: Option<String>) -> Self {
+        let base_url = non_empty(url)
+            .or_else(|| non_empty(std::env::var(GATEWAY_URL_ENV).ok()))
+            .unwrap_or_else(|| {
+                let host = match config.gateway.host.trim() {
+                    "0.0.0.0" | "" => "127.0.0.1",
+                    "::" => "[::1]",
+                    host => host,
+                };
+                format!("http://{host}:{}", config.gateway.port)
+            });
+        let token = non_empty(token)
+            .or_else(|| non_empty(std::env::var(GATEWAY_TOKEN_ENV).ok()))
+            .or_else(|| load_saved_token(config));
+        Self::new(&base_url, token)
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{path}", self.base_url)
+    }
+
+    async fn send(&self, req: reqwest::RequestBuilder) -> ClientResult<reqwest::Response> {
+        let req = match &self.token {
+            Some(token) => req.bearer_auth(token),
+            None => req,
+        };
+        let response = req
+            .send()
+            .await
+            .map_err(|source| GatewayClientError::Connect {
+                url: self.base_url.clone(),
+                source,
+            })?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+        let raw = response.text().await.unwrap_or_default();
+        let message = serde_json::from_str::<Value>(&raw)
+            .ok()
+            .and_then(|body| {
+                ["error", "message"]
+                    .iter()
+                    .find_map(|key| body.get(key)?.as_str().map(str::to_string))
+            })
+            .unwrap_or_else(|| raw.trim().to_string());
+        Err(
+            if status == reqwest::StatusCode::UNAUTHORIZED
+                || status == reqwest::StatusCode::FORBIDDEN
+            {
+                GatewayClientError::Unauthorized {
+                    status: status.as_u16(),
+                    message,
+                }
+            } else {
+                GatewayClientError::Http {
+                    status: status.as_u16(),
+                    message,
+                }
+            },
+        )
+    }
+
+    async fn send_json(&self, req: reqwest::RequestBuilder) -> ClientResult<Value> {
+        let response = self.send(req).await?;
+        Ok(response
+            .json()
+            .await
+            .context("gateway sent a response that is not JSON")?)
+    }
+
+    /// `POST /pair`; returns the bearer token the gateway issued.
+    pub async fn pair(&self, code: &str) -> ClientResult<String> {
+        let body = self
+            .send_json(
+                self.http
+                    .post(self.url("/pair"))
+                    .header("X-Pairing-Code", code.trim()),
+            )
+            .await?;
+        body.get("token")
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .ok_or_else(|| anyhow::anyhow!("gateway response has no token").into())
+    }
+
+    /// `POST /webhook`; returns the agent reply body.
+    pub async fn webhook(&self, message: &str, session_id: Option<&str>) -> ClientResult<Value> {
+        let mut body = serde_json::json!({ "message": message });
+        if let Some(session_id) = session_id {
+            body["session_id"] = Value::String(session_id.to_string());
+        }
+        self.send_json(self.http.post(self.url("/webhook")).json(&body))
+            .await
+    }
+
+    /// `POST /api/chat/messages`; returns the stored user message.
+    pub async fn chat_send(&self, thread_id: &str, content: &str) -> ClientResult<Value> {
+        self.send_json(
+            self.http
+                .post(self.url("/api/chat/messages"))
+                .json(&serde_json::json!({ "threadId": thread_id, "content": content })),
+        )
+        .await
+    }
+
+    /// Poll the thread until the reply to `message_id` is done or failed.
+    /// Returns `None` when `timeout` passes first.
+    pub async fn chat_wait_reply(
+        &self,
+        thread_id: &str,
+        message_id: &str,
+        timeout: Duration,
+    ) -> ClientResult<Option<Value>> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            let listing = self
+                .send_json(
+                    self.http
+                        .get(self.url("/api/chat/messages"))
+                        .query(&[("threadId", thread_id)]),
+                )
+                .await?;
+            let reply = listing
+                .get("items")
+                .and_then(Value::as_array)
+                .and_then(|items| {
+                    items.iter().find(|item| {
+                        item.get("replyToId").and_then(Value::as_str) == Some(message_id)
+                            && matches!(
+                                item.get("status").and_then(Value::as_str),
+                                Some("done" | "error")
+                            )
+                    })
+                })
+                .cloned();
+            if reply.is_some() || tokio::time::Instant::now() >= deadline {
+                return Ok(reply);
+            }
+            tokio::time::sleep(CHAT_POLL_INTERVAL).await;
+        }
+    }
+
+    /// `POST /api/media/upload`, streaming the file from disk.
+    pub async fn upload(&self, file: &Path, kind: Option<&str>) -> ClientResult<Value> {
+        let filename = file
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("upload")
+            .to_string();
+        let handle = tokio::fs::File::open(file)
+            .await
+            .with_context(|| format!("failed to open {}", file.display()))?;
+        let len = handle.metadata().await.map(|meta| meta.len()).ok();
+        let content_type = mime_guess::from_path(file)
+            .first_or_octet_stream()
+            .to_string();
+
+        let mut query = vec![("filename", filename), ("source", "cli".to_string())];
+        if let Some(kind) = kind {
+            query.push(("kind", kind.to_string()));
+        }
+        let mut req = self
+            .http
+            .post(self.url("/api/media/upload"))
+            .query(&query)
+            .header(reqwest::header::CONTENT_TYPE, content_type)
+            .body(reqwest::Body::from(handle));
+        if let Some(len) = len {
+            req = req.header(reqwest::header::CONTENT_LENGTH, len);
+        }
+        self.send_json(req).await
+    }
+
+    /// `GET /api/library/items`.
+    pub async fn library_items(&self, scope: &str, limit: Option<usize>) -> ClientResult<Value> {
+        let mut query = vec![("scope", scope.to_string())];
+        if let Some(limit) = limit {
+            query.push(("limit", limit.to_string()));
+        }
+        self.send_json(self.http.get(self.url("/api/library/items")).query(&query))
+            .await
+    }
+
+    /// `GET /api/media/<path>`, streamed into `out`. Returns the bytes written.
+    pub async fn download_media(&self, rel_path: &str, out: &Path) -> ClientResult<u64> {
+        let encoded = rel_path
+            .trim_start_matches('/')
+            .split('/')
+            .map(|segment| urlencoding::encode(segment).into_owned())
+            .collect::<Vec<_>>()
+            .join("/");
+        let response = self
+            .send(self.http.get(self.url(&format!("/api/media/{encoded}"))))
+            .await?;
+        let mut file = tokio::fs::File::create(out)
+            .await
+            .with_context(|| format!("failed to create {}", out.display()))?;
+        let mut written = 0u64;
+        let mut stream = response.bytes_stream();
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.context("download interrupted")?;
+            file.write_all(&chunk)
+                .await
+                .with_context(|| format!("failed to write {}", out.display()))?;
+            written += chunk.len() as u64;
+        }
+        file.flush().await.context("failed to flush download")?;
+        Ok(written)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{header, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn pair_sends_code_and_returns_token() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/pair"))
+            .and(header("X-Pairing-Code", "123456"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({ "paired": true, "token": "zc_abc" })),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = GatewayClient::new(&server.uri(), None);
+        assert_eq!(client.pair(" 123456 ").await.unwrap(), "zc_abc");
+    }
+
+    #[tokio::test]
+    async fn errors_map_to_exit_codes() {
+        let server = MockServer::start().await;
+        Mock::given(path("/api/library/items"))
+            .respond_with(
+                ResponseTemplate::new(401)
+                    .set_body_json(serde_json::json!({ "error": "Unauthorized" })),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(path("/webhook"))
+            .respond_with(ResponseTemplate::new(500).set_body_string("boom"))
+            .mount(&server)
+            .await;
+
+        let client = GatewayClient::new(&server.uri(), Some("bad".into()));
+        let err = client.library_items("all", None).await.unwrap_err();
+        assert_eq!(err.exit_code(), 4);
+        assert!(err.to_string().contains("Unauthorized"));
+
+        let err = client.webhook("hi", None).await.unwrap_err();
+        assert_eq!(err.exit_code(), 6);
+        assert!(err.to_string().contains("boom"));
+
+        let unreachable = GatewayClient::new("http://127.0.0.1:1", None);
+        assert_eq!(
+            unreachable
+                .webhook("hi", None)
+                .await
+                .unwrap_err()
+                .exit_code(),
+            3
+        );
+    }
+
+    #[tokio::test]
+    async fn chat_wait_reply_finds_the_finished_reply() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/chat/messages"))
+            .and(query_param("threadId", "t1"))
+            .and(header("Authorization", "Bearer tok"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "items": [
+                    { "id": "m1", "role": "user", "status": "done" },
+                    { "id": "m2", "role": "assistant", "status": "done", "replyToId": "m1", "content": "hello" },
+                ]
+            })))
+            .mount(&server)
+            .await;
+
+        let client = GatewayClient::new(&server.uri(), Some("tok".into()));
+        let reply = client
+            .chat_wait_reply("t1", "m1", Duration::from_secs(1))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(reply["content"], "hello");
+    }
+
+    #[tokio::test]
+    async fn download_streams_media_to_disk() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/media/journals/media/audio/a%20b.m4a"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(vec![7u8; 4096]))
+            .mount(&server)
+            .await;
+
+        let tmp = tempfile::tempdir().unwrap();
+        let out = tmp.path().join("a.m4a");
+        let client = GatewayClient::new(&server.uri(), Some("tok".into()));
+        let written = client
+            .download_media("journals/media/audio/a b.m4a", &out)
+            .await
+            .unwrap();
+        assert_eq!(written, 4096);
+        assert_eq!(std::fs::read(out).unwrap().len(), 4096);
+    }
+
+    #[test]
+    fn saved_token_round_trips() {
+        let tmp = tempfile::tempdir().unwrap();
+        let config = Config {
+            config_path: tmp.path().join("config.toml"),
+            ..Config::default()
+        };
+        assert!(load_saved_token(&config).is_none());
+        save_token(&config, " zc_saved ").unwrap();
+        assert_eq!(load_saved_token(&config).as_deref(), Some("zc_saved"));
+    }
+}
@@ -0,0 +1,212 @@
+//! File names derived from client-supplied titles and upload names.
+//!
+//! Names keep their letters in any script, are NFC-normalized so the same
+//! title typed on different platforms maps to the same bytes, and avoid what
+//! Windows and common filesystems refuse: separators, control characters,
+//! reserved device names, trailing dots or spaces, and names over 255 bytes.
+
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use unicode_normalization::UnicodeNormalization;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Byte budget for a sanitized name. Leaves room under the common 255-byte
+/// limit for the `HHMMSS_` prefix, a collision suffix, and an extension.
+pub const MAX_NAME_BYTES: usize = 200;
+
+/// Extensions longer than this are treated as part of the stem when a name
+/// has to be shortened.
+const MAX_EXTENSION_BYTES: usize = 16;
+
+const WINDOWS_RESERVED: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Bidirectional overrides and isolates can make `gpj.exe` render as
+/// `exe.jpg`; they never belong in a stored name.
+fn is_bidi_control(ch: char) -> bool {
+    matches!(ch, '\u{200e}' | '\u{200f}' | '\u{202a}'..='\u{202e}' | '\u{2066}'..='\u{2069}')
+}
+
+fn keep_char(ch: char) -> bool {
+    if ch.is_ascii() {
+        return ch.is_alphanumeric() || matches!(ch, '.' | '_' | '-');
+    }
+    // Non-ASCII letters and digits, plus marks (Devanagari virama and the
+    // like) and emoji, which `is_alphanumeric` alone would drop.
+    ch.is_alphanumeric() || !(ch.is_whitespace() || ch.is_control() || is_bidi_control(ch))
+}
+
+fn is_windows_reserved(name: &str) -> bool {
+    let stem = name.split('.').next().unwrap_or(name).trim_end();
+    WINDOWS_RESERVED
+        .iter()
+        .any(|reserved| stem.eq_ignore_ascii_case(reserved))
+}
+
+/// Cut `text` to at most `max_bytes` without splitting a grapheme.
+fn truncate_to_bytes(text: &str, max_bytes: usize) -> &str {
+    let mut end = 0;
+    for (offset, grapheme) in text.grapheme_indices(true) {
+        if offset + grapheme.len() > max_bytes {
+            break;
+        }
+        end = offset + grapheme.len();
+    }
+    &text[..end]
+}
+
+/// Shorten `name` to `max_bytes`, keeping a short extension intact.
+fn cap_bytes(name: &str, max_bytes: usize) -> String {
+    if name.len() <= max_bytes {
+        return name.to_string();
+    }
+    match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() && ext.len() < MAX_EXTENSION_BYTES => {
+            let stem = truncate_to_bytes(stem, max_bytes - ext.len() - 1);
+            format!("{stem}.{ext}")
+        }
+        _ => truncate_to_bytes(name, max_bytes).to_string(),
+    }
+}
+
+/// Leading dots would hide the file; trailing dots and spaces are dropped by
+/// Windows, so two names differing only there would collide.
+fn trim_name(name: &str) -> &str {
+    name.trim_start_matches(['.', '_'])
+        .trim_end_matches(['.', ' ', '_'])
+}
+
+/// Sanitize `name` for storage, or return `fallback` when nothing usable is
+/// left. ASCII punctuation and whitespace become `_`, as before; other
+/// scripts, marks, and emoji are kept.
+pub fn safe_file_name(name: &str, fallback: &str) -> String {
+    let replaced: String = name
+        .nfc()
+        .map(|ch| if keep_char(ch) { ch } else { '_' })
+        .collect();
+    let capped = cap_bytes(trim_name(&replaced), MAX_NAME_BYTES);
+    let trimmed = trim_name(&capped);
+    if trimmed.is_empty() {
+        return fallback.to_string();
+    }
+    if is_windows_reserved(trimmed) {
+        return format!("_{trimmed}");
+    }
+    trimmed.to_string()
+}
+
+/// `file_name`, or `stem-<hash>.ext` when `dir` already holds that name.
+/// The hash is derived from the name and attempt number, so it is short and
+/// stable, and the loop only spins on repeated collisions.
+pub fn unique_file_name(dir: &Path, file_name: &str) -> String {
+    if !dir.join(file_name).exists() {
+        return file_name.to_string();
+    }
+    let (stem, ext) = match file_name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => (stem, Some(ext)),
+        _ => (file_name, None),
+    };
+    for attempt in 1u32.. {
+        let digest = Sha256::digest(format!("{file_name}\0{attempt}").as_bytes());
+        let suffix = &hex::encode(digest)[..8];
+        let candidate = match ext {
+            Some(ext) => format!("{stem}-{suffix}.{ext}"),
+            None => format!("{stem}-{suffix}"),
+        };
+        if !dir.join(&candidate).exists() {
+            return candidate;
+        }
+    }
+    unreachable!("u32 attempts exhausted")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_non_latin_scripts() {
+        assert_eq!(safe_file_name("Grüße aus Köln", "x"), "Grüße_aus_Köln");
+        assert_eq!(safe_file_name("नमस्ते दुनिया", "x"), "नमस्ते_दुनिया");
+        assert_eq!(safe_file_name("שלום עולם.md", "x"), "שלום_עולם.md");
+        assert_eq!(safe_file_name("مرحبا", "x"), "مرحبا");
+        assert_ne!(
+            safe_file_name("Grüße", "x"),
+            safe_file_name("नमस्ते", "x"),
+            "different titles must not collapse to the same name"
+        );
+    }
+
+    #[test]
+    fn normalizes_to_nfc() {
+        let decomposed = "Cafe\u{301}";
+        assert_eq!(safe_file_name(decomposed, "x"), "Caf\u{e9}");
+    }
+
+    #[test]
+    fn keeps_emoji_sequences() {
+        assert_eq!(safe_file_name("trip 🏔️.jpg", "x"), "trip_🏔️.jpg");
+        assert_eq!(safe_file_name("👩‍👩‍👧", "x"), "👩‍👩‍👧");
+    }
+
+    #[test]
+    fn strips_separators_controls_and_bidi_overrides() {
+        assert_eq!(safe_file_name("../../etc/passwd", "x"), "etc_passwd");
+        assert_eq!(safe_file_name("a\\b:c*d?.txt", "x"), "a_b_c_d_.txt");
+        assert_eq!(safe_file_name("bad\u{0}\u{7}\nname", "x"), "bad___name");
+        assert_eq!(safe_file_name("evil\u{202e}gpj.exe", "x"), "evil_gpj.exe");
+        assert_eq!(safe_file_name("///", "fallback.bin"), "fallback.bin");
+        assert_eq!(safe_file_name("..", "fallback.bin"), "fallback.bin");
+    }
+
+    #[test]
+    fn escapes_windows_reserved_names() {
+        assert_eq!(safe_file_name("CON", "x"), "_CON");
+        assert_eq!(safe_file_name("aux.md", "x"), "_aux.md");
+        assert_eq!(safe_file_name("lpt9.tar.gz", "x"), "_lpt9.tar.gz");
+        assert_eq!(safe_file_name("console.md", "x"), "console.md");
+        assert_eq!(safe_file_name("COM10", "x"), "COM10");
+    }
+
+    #[test]
+    fn trims_trailing_dots_and_spaces() {
+        assert_eq!(safe_file_name("notes. . .", "x"), "notes");
+        assert_eq!(safe_file_name("draft...", "x"), "draft");
+        assert_eq!(safe_file_name("name.md ", "x"), "name.md");
+    }
+
+    #[test]
+    fn caps_bytes_on_grapheme_boundaries_and_keeps_extension() {
+        let long = format!("{}.m4a", "ह".repeat(200));
+        let safe = safe_file_name(&long, "x");
+        assert!(safe.len() <= MAX_NAME_BYTES, "{} bytes", safe.len());
+        assert!(safe.ends_with(".m4a"));
+        assert!(safe.trim_end_matches(".m4a").chars().all(|ch| ch == 'ह'));
+
+        let emoji = "👩‍👩‍👧".repeat(40);
+        let safe = safe_file_name(&emoji, "x");
+        assert!(safe.len() <= MAX_NAME_BYTES);
+        assert_eq!(safe.len() % "👩‍👩‍👧".len(), 0, "split a grapheme");
+    }
+
+    #[test]
+    fn unique_name_appends_a_short_hash_on_collision() {
+        let tmp = tempfile::tempdir().unwrap();
+        assert_eq!(unique_file_name(tmp.path(), "note.md"), "note.md");
+
+        std::fs::write(tmp.path().join("note.md"), "a").unwrap();
+        let second = unique_file_name(tmp.path(), "note.md");
+        assert_ne!(second, "note.md");
+        assert!(second.starts_with("note-") && second.ends_with(".md"));
+        assert_eq!(second.len(), "note-12345678.md".len());
+
+        std::fs::write(tmp.path().join(&second), "b").unwrap();
+        let third = unique_file_name(tmp.path(), "note.md");
+        assert!(third != second && third != "note.md");
+
+        std::fs::write(tmp.path().join("README"), "c").unwrap();
+        assert!(unique_file_name(tmp.path(), "README").starts_with("README-"));
+    }
+}
@@ -0,0 +1,450 @@
+//! Daily journal digest: summarize recent entries and transcripts, save the
+//! summary as a journal note, and optionally post it to a chat thread or mail it.
+//!
+//! Driven from `slowclaw journal digest`, so it can be scheduled from the
+//! system crontab or any other scheduler.
+
+use crate::channels::{EmailChannel, OutboundDispatcher, PocketBaseChannel, SendMessage};
+use crate::config::Config;
+use crate::providers;
+use crate::workspace::atomic_write_async;
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+const JOURNAL_TEXT_DIR: &str = "journals/text";
+pub const DIGEST_DIR: &str = "journals/text/digests";
+pub const DEFAULT_MAX_INPUT_CHARS: usize = 48_000;
+const DIGEST_TEMPERATURE: f64 = 0.3;
+
+const DIGEST_SYSTEM_PROMPT: &str = "You write short daily digests of a person's private journal. \
+Summarize the entries below in Markdown: a two or three sentence overview, then bullet points \
+for notable events, moods, and open todos. Write in second person, stay faithful to the \
+entries, and do not invent details.";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DigestEntry {
+    pub path: String,
+    pub modified_at: DateTime<Utc>,
+    pub text: String,
+}
+
+/// Entries selected for the prompt, oldest first, plus how many older
+/// entries were dropped to stay inside the input budget.
+#[derive(Debug, Default)]
+pub struct DigestInput {
+    pub entries: Vec<DigestEntry>,
+    pub dropped: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DigestTarget {
+    PocketBase { thread_id: String },
+    Email { to: String },
+}
+
+#[derive(Debug, Default)]
+pub struct DigestOutcome {
+    pub path: Option<String>,
+    pub entries: usize,
+    pub dropped: usize,
+    pub delivered: bool,
+}
+
+/// Parse a window such as `24h`, `90m`, `7d`, or `3600s`.
+pub fn parse_since(raw: &str) -> Result<chrono::Duration> {
+    let raw = raw.trim();
+    let split = raw.find(|c: char| !c.is_ascii_digit()).unwrap_or(raw.len());
+    let (digits, unit) = raw.split_at(split);
+    let amount: i64 = digits
+        .parse()
+        .with_context(|| format!("Invalid --since value '{raw}' (expected e.g. 24h, 7d)"))?;
+    let duration = match unit {
+        "s" => chrono::Duration::try_seconds(amount),
+        "m" => chrono::Duration::try_minutes(amount),
+        "h" | "" => chrono::Duration::try_hours(amount),
+        "d" => chrono::Duration::try_days(amount),
+        _ => bail!("Invalid --since unit '{unit}' (use s, m, h, or d)"),
+    }
+    .with_context(|| format!("--since value '{raw}' is too large"))?;
+    if amount <= 0 {
+        bail!("--since must be greater than zero");
+    }
+    Ok(duration)
+}
+
+/// Parse a `--deliver` target: `pocketbase:<threadId>` or `email:<address>`.
+pub fn parse_delivery(raw: &str) -> Result<DigestTarget> {
+    match raw.trim().split_once(':') {
+        Some(("pocketbase", thread_id)) if !thread_id.trim().is_empty() => {
+            Ok(DigestTarget::PocketBase {
+                thread_id: thread_id.trim().to_string(),
+            })
+        }
+        Some(("email", to)) if !to.trim().is_empty() => Ok(DigestTarget::Email {
+            to: to.trim().to_string(),
+        }),
+        _ => bail!(
+            "Unsupported --deliver target '{raw}' (expected pocketbase:<threadId> or email:<address>)"
+        ),
+    }
+}
+
+fn is_digest_source(rel_path: &str) -> bool {
+    let lower = rel_path.to_ascii_lowercase();
+    lower.starts_with("journals/text/")
+        && !lower.starts_with("journals/text/digests/")
+        && !lower.ends_with(".caption.txt")
+        && (lower.ends_with(".md") || lower.ends_with(".txt"))
+}
+
+fn walk(workspace_dir: &Path, dir: &Path, since: DateTime<Utc>, out: &mut Vec<DigestEntry>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            walk(workspace_dir, &path, since, out);
+            continue;
+        }
+        let Ok(rel) = path.strip_prefix(workspace_dir) else {
+            continue;
+        };
+        let rel = rel.to_string_lossy().replace('\\', "/");
+        if !file_type.is_file() || !is_digest_source(&rel) {
+            continue;
+        }
+        let Some(modified_at) = entry
+            .metadata()
+            .ok()
+            .and_then(|meta| meta.modified().ok())
+            .map(DateTime::<Utc>::from)
+        else {
+            continue;
+        };
+        if modified_at < since {
+            continue;
+        }
+        let Ok(text) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        let text = text.trim();
+        if !text.is_empty() {
+            out.push(DigestEntry {
+                path: rel,
+                modified_at,
+                text: text.to_string(),
+            });
+        }
+    }
+}
+
+/// Text entries and transcripts under `journals/text/` modified at or after
+/// `since`, oldest first. Earlier digests and caption sidecars are skipped.
+pub fn collect_entries(workspace_dir: &Path, since: DateTime<Utc>) -> Vec<DigestEntry> {
+    let mut out = Vec::new();
+    walk(
+        workspace_dir,
+        &workspace_dir.join(JOURNAL_TEXT_DIR),
+        since,
+        &mut out,
+    );
+    out.sort_by(|a, b| {
+        a.modified_at
+            .cmp(&b.modified_at)
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    out
+}
+
+/// Drop the oldest entries until the rest fit in `max_chars`. A lone entry
+/// that is still too long is cut down rather than dropped.
+pub fn fit_to_budget(mut entries: Vec<DigestEntry>, max_chars: usize) -> DigestInput {
+    let mut total: usize = entries.iter().map(|e| e.text.chars().count()).sum();
+    let mut dropped = 0;
+    while total > max_chars && entries.len() > 1 {
+        total -= entries.remove(0).text.chars().count();
+        dropped += 1;
+    }
+    if let Some(only) = entries.first_mut().filter(|_| total > max_chars) {
+        only.text = crate::util::truncate_with_ellipsis(&only.text, max_chars);
+    }
+    DigestInput { entries, dropped }
+}
+
+pub fn build_prompt(input: &DigestInput) -> String {
+    let mut prompt = String::new();
+    if input.dropped > 0 {
+        let _ = write!(
+            prompt,
+            "({} older entries were omitted to fit the input limit.)\n\n",
+            input.dropped
+        );
+    }
+    for entry in &input.entries {
+        let _ = write!(
+            prompt,
+            "## {} ({})\n{}\n\n",
+            entry.path,
+            entry.modified_at.format("%Y-%m-%d %H:%M UTC"),
+            entry.text
+        );
+    }
+    prompt
+}
+
+fn digest_rel_path(now: DateTime<Utc>) -> String {
+    format!(
+        "{DIGEST_DIR}/{}/{}_journal_digest.md",
+        now.format("%Y/%m/%d"),
+        now.format("%H%M%S")
+    )
+}
+
+async fn summarize(config: &Config, prompt: &str) -> Result<String> {
+    let provider_name = config
+        .default_provider
+        .as_deref()
+        .unwrap_or(providers::DEFAULT_PROVIDER);
+    let model = config
+        .default_model
+        .clone()
+        .unwrap_or_else(|| providers::DEFAULT_MODEL.into());
+    let provider = providers::create_resilient_provider_with_options(
+        provider_name,
+        config.api_key.as_deref(),
+        config.api_url.as_deref(),
+        &config.reliability,
+        &providers::ProviderRuntimeOptions {
+            auth_profile_override: None,
+            provider_api_url: config.api_url.clone(),
+            zeroclaw_dir: config.config_path.parent().map(PathBuf::from),
+            secrets_encrypt: config.secrets.encrypt,
+            reasoning_enabled: config.runtime.reasoning_enabled,
+        },
+    )?;
+    let summary = provider
+        .chat_with_system(
+            Some(DIGEST_SYSTEM_PROMPT),
+            prompt,
+            &model,
+            DIGEST_TEMPERATURE,
+        )
+        .await?;
+    let summary = summary.trim();
+    if summary.is_empty() {
+        bail!("Provider returned an empty digest");
+    }
+    Ok(summary.to_string())
+}
+
+async fn deliver(config: &Config, target: &DigestTarget, title: &str, body: &str) -> Result<()> {
+    let workspace_dir = &config.workspace_dir;
+    match target {
+        DigestTarget::PocketBase { thread_id } => {
+            let channel = PocketBaseChannel::from_env_defaults()?.with_outbox(workspace_dir);
+            channel.flush_outbox().await;
+            OutboundDispatcher::new(workspace_dir)
+                .send(&channel, &SendMessage::new(body, thread_id.clone()))
+                .await
+                .map(|_| ())
+        }
+        DigestTarget::Email { to } => {
+            let Some(email) = config.channels_config.email.as_ref() else {
+                bail!("--deliver email needs [channels_config.email]");
+            };
+            let channel = EmailChannel::from_config(email)?;
+            OutboundDispatcher::new(workspace_dir)
+                .send(
+                    &channel,
+                    &SendMessage::with_subject(body, to.clone(), title),
+                )
+                .await
+                .map(|_| ())
+        }
+    }
+}
+
+/// Summarize entries from the last `window`, save the digest, and deliver it.
+/// An empty window writes nothing and skips delivery.
+pub async fn run_digest(
+    config: &Config,
+    window: chrono::Duration,
+    max_input_chars: usize,
+    target: Option<&DigestTarget>,
+) -> Result<DigestOutcome> {
+    let now = Utc::now();
+    let since = now
+        .checked_sub_signed(window)
+        .context("Digest window reaches back past the earliest representable date")?;
+    let workspace_dir = config.workspace_dir.clone();
+    let entries =
+        tokio::task::spawn_blocking(move || collect_entries(&workspace_dir, since)).await?;
+    if entries.is_empty() {
+        return Ok(DigestOutcome::default());
+    }
+    let input = fit_to_budget(entries, max_input_chars.max(1));
+    let summary = summarize(config, &build_prompt(&input)).await?;
+
+    let title = format!("Journal digest — {}", now.format("%Y-%m-%d"));
+    let body = format!("# {title}\n\n{summary}\n");
+    let rel_path = digest_rel_path(now);
+    let abs_path = config.workspace_dir.join(&rel_path);
+    if let Some(parent) = abs_path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    atomic_write_async(abs_path, body.clone().into_bytes())
+        .await
+        .context("Failed to write journal digest")?;
+    super::search_index::journal_file_changed(&config.workspace_dir, &rel_path).await;
+
+    let delivered = match target {
+        Some(target) => {
+            deliver(config, target, &title, &body)
+                .await
+                .context("Digest saved but delivery failed")?;
+            true
+        }
+        None => false,
+    };
+    Ok(DigestOutcome {
+        path: Some(rel_path),
+        entries: input.entries.len(),
+        dropped: input.dropped,
+        delivered,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(workspace: &Path, rel: &str, body: &str) {
+        let path = workspace.join(rel);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, body).unwrap();
+    }
+
+    fn entry(path: &str, minutes_ago: i64, text: &str) -> DigestEntry {
+        DigestEntry {
+            path: path.into(),
+            modified_at: Utc::now() - chrono::Duration::minutes(minutes_ago),
+            text: text.into(),
+        }
+    }
+
+    #[test]
+    fn parse_since_accepts_units() {
+        assert_eq!(parse_since("24h").unwrap(), chrono::Duration::hours(24));
+        assert_eq!(parse_since("7d").unwrap(), chrono::Duration::days(7));
+        assert_eq!(parse_since("90m").unwrap(), chrono::Duration::minutes(90));
+        assert!(parse_since("0h").is_err());
+        assert!(parse_since("1w").is_err());
+        assert!(parse_since("soon").is_err());
+        assert!(parse_since("99999999999999d").is_err());
+    }
+
+    #[test]
+    fn parse_delivery_accepts_pocketbase_threads_and_email() {
+        assert_eq!(
+            parse_delivery("pocketbase:daily").unwrap(),
+            DigestTarget::PocketBase {
+                thread_id: "daily".into()
+            }
+        );
+        assert_eq!(
+            parse_delivery("email: me@example.com").unwrap(),
+            DigestTarget::Email {
+                to: "me@example.com".into()
+            }
+        );
+        assert!(parse_delivery("pocketbase:").is_err());
+        assert!(parse_delivery("email:").is_err());
+        assert!(parse_delivery("slack:general").is_err());
+    }
+
+    #[test]
+    fn collect_includes_entries_and_transcripts_but_skips_digests_and_sidecars() {
+        let tmp = tempfile::tempdir().unwrap();
+        write(
+            tmp.path(),
+            "journals/text/2026/03/01/080000_walk.md",
+            "walk",
+        );
+        write(
+            tmp.path(),
+            "journals/text/transcriptions/audio/2026/03/01/voice.txt",
+            "voice note",
+        );
+        write(tmp.path(), "journals/text/2026/03/01/blank.md", "   ");
+        write(tmp.path(), "journals/text/2026/03/01/a.md.tags.json", "{}");
+        write(tmp.path(), "journals/media/image/p.jpg.caption.txt", "cap");
+        write(
+            tmp.path(),
+            "journals/text/digests/2026/03/01/d.md",
+            "old digest",
+        );
+
+        let paths: Vec<String> =
+            collect_entries(tmp.path(), Utc::now() - chrono::Duration::hours(1))
+                .into_iter()
+                .map(|e| e.path)
+                .collect();
+        assert_eq!(paths.len(), 2);
+        assert!(paths.contains(&"journals/text/2026/03/01/080000_walk.md".to_string()));
+        assert!(
+            paths.contains(&"journals/text/transcriptions/audio/2026/03/01/voice.txt".to_string())
+        );
+
+        assert!(collect_entries(tmp.path(), Utc::now() + chrono::Duration::hours(1)).is_empty());
+    }
+
+    #[test]
+    fn fit_to_budget_drops_oldest_first() {
+        let entries = vec![
+            entry("old.md", 60, "aaaaaaaaaa"),
+            entry("mid.md", 30, "bbbbbbbbbb"),
+            entry("new.md", 5, "cccccccccc"),
+        ];
+        let input = fit_to_budget(entries, 25);
+        assert_eq!(input.dropped, 1);
+        let kept: Vec<_> = input.entries.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(kept, vec!["mid.md", "new.md"]);
+        assert!(build_prompt(&input).starts_with("(1 older entries were omitted"));
+    }
+
+    #[test]
+    fn fit_to_budget_truncates_single_oversized_entry() {
+        let input = fit_to_budget(vec![entry("long.md", 1, &"x".repeat(100))], 10);
+        assert_eq!(input.dropped, 0);
+        assert_eq!(input.entries.len(), 1);
+        assert!(input.entries[0].text.chars().count() <= 13);
+    }
+
+    #[tokio::test]
+    async fn empty_window_skips_summary_and_delivery() {
+        let tmp = tempfile::tempdir().unwrap();
+        let config = Config {
+            workspace_dir: tmp.path().to_path_buf(),
+            ..Config::default()
+        };
+        let target = DigestTarget::PocketBase {
+            thread_id: "daily".into(),
+        };
+        let outcome = run_digest(&config, chrono::Duration::hours(24), 1_000, Some(&target))
+            .await
+            .unwrap();
+        assert_eq!(outcome.entries, 0);
+        assert!(outcome.path.is_none());
+        assert!(!outcome.delivered);
+        assert!(!tmp.path().join(DIGEST_DIR).exists());
+
+        let forever = chrono::Duration::try_days(100_000_000_000).unwrap();
+        assert!(run_digest(&config, forever, 1_000, None).await.is_err());
+    }
+}
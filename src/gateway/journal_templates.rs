@@ -0,0 +1,165 @@
+//! Markdown templates for journal notes.
+//!
+//! Templates live in `workspace/templates/*.md` and may reference `{{date}}`,
+//! `{{title}}`, and `{{content}}`. Substitution is a single pass over the
+//! template, so placeholder-looking text inside the values is left alone.
+
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+pub const TEMPLATES_DIR: &str = "templates";
+const TEMPLATE_EXT: &str = "md";
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalTemplate {
+    pub name: String,
+    pub path: String,
+    pub content: String,
+}
+
+pub struct TemplateVars<'a> {
+    pub date: &'a str,
+    pub title: &'a str,
+    pub content: &'a str,
+}
+
+pub fn templates_dir(workspace_dir: &Path) -> PathBuf {
+    workspace_dir.join(TEMPLATES_DIR)
+}
+
+/// All `*.md` templates in the workspace, sorted by name.
+pub fn list_templates(workspace_dir: &Path) -> Vec<JournalTemplate> {
+    let Ok(entries) = std::fs::read_dir(templates_dir(workspace_dir)) else {
+        return Vec::new();
+    };
+    let mut out: Vec<JournalTemplate> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            if !path.is_file() || path.extension().and_then(|e| e.to_str()) != Some(TEMPLATE_EXT) {
+                return None;
+            }
+            let name = path.file_stem()?.to_str()?.to_string();
+            let content = std::fs::read_to_string(&path).ok()?;
+            Some(JournalTemplate {
+                path: format!("{TEMPLATES_DIR}/{name}.{TEMPLATE_EXT}"),
+                name,
+                content,
+            })
+        })
+        .collect();
+    out.sort_by(|a, b| a.name.cmp(&b.name));
+    out
+}
+
+/// Look up a template by name (with or without the `.md` extension).
+pub fn find_template(workspace_dir: &Path, name: &str) -> Option<JournalTemplate> {
+    let name = name.trim();
+    let name = name
+        .strip_suffix(&format!(".{TEMPLATE_EXT}"))
+        .unwrap_or(name);
+    list_templates(workspace_dir)
+        .into_iter()
+        .find(|template| template.name == name)
+}
+
+/// Fill placeholders in `template`. Unknown `{{...}}` sequences are kept
+/// verbatim; when the template has no `{{content}}`, the content is appended.
+pub fn render_template(template: &str, vars: &TemplateVars<'_>) -> String {
+    let mut out = String::with_capacity(template.len() + vars.content.len());
+    let mut used_content = false;
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let value = after.find("}}").and_then(|end| {
+            let value = match after[..end].trim() {
+                "date" => vars.date,
+                "title" => vars.title,
+                "content" => {
+                    used_content = true;
+                    vars.content
+                }
+                _ => return None,
+            };
+            Some((value, end))
+        });
+        match value {
+            Some((value, end)) => {
+                out.push_str(value);
+                rest = &after[end + 2..];
+            }
+            None => {
+                out.push_str("{{");
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+
+    if !used_content && !vars.content.is_empty() {
+        if !out.is_empty() && !out.ends_with('\n') {
+            out.push('\n');
+        }
+        out.push_str(vars.content);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(content: &str) -> TemplateVars<'_> {
+        TemplateVars {
+            date: "2026-03-01",
+            title: "Sunday",
+            content,
+        }
+    }
+
+    #[test]
+    fn render_substitutes_known_placeholders() {
+        let rendered = render_template(
+            "# {{title}} ({{ date }})\n\n{{content}}\n",
+            &vars("went for a walk"),
+        );
+        assert_eq!(rendered, "# Sunday (2026-03-01)\n\nwent for a walk\n");
+    }
+
+    #[test]
+    fn render_leaves_literal_braces_in_content_and_unknown_placeholders() {
+        let rendered = render_template(
+            "{{mood}} {{title}}: {{content}} {{",
+            &vars("code uses {{title}} and {{ unclosed"),
+        );
+        assert_eq!(
+            rendered,
+            "{{mood}} Sunday: code uses {{title}} and {{ unclosed {{"
+        );
+    }
+
+    #[test]
+    fn render_appends_content_when_template_has_no_slot() {
+        assert_eq!(render_template("## Mood\n", &vars("calm")), "## Mood\ncalm");
+        assert_eq!(render_template("## Mood", &vars("")), "## Mood");
+    }
+
+    #[test]
+    fn list_and_find_only_markdown_templates() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = templates_dir(tmp.path());
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("daily.md"), "# {{date}}").unwrap();
+        std::fs::write(dir.join("notes.txt"), "ignored").unwrap();
+
+        let names: Vec<_> = list_templates(tmp.path())
+            .into_iter()
+            .map(|t| t.name)
+            .collect();
+        assert_eq!(names, vec!["daily"]);
+        assert!(find_template(tmp.path(), "daily.md").is_some());
+        assert!(find_template(tmp.path(), "../daily").is_none());
+    }
+}
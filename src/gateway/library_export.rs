@@ -0,0 +1,344 @@
+//! Zip bundles of library items for sharing.
+//!
+//! A single item exports with its recognized sidecars (transcripts, captions,
+//! probe metadata, tags); processed pipeline folders under
+//! `journals/processed/` export as a whole subtree. Every bundle carries a
+//! generated `manifest.json`.
+
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use zip::write::SimpleFileOptions;
+use zip::CompressionMethod;
+
+pub const EXPORTABLE_DIR_PREFIX: &str = "journals/processed/";
+pub const EXPORTS_DIR: &str = "state/exports";
+/// Bundles up to this size are built and streamed inline.
+pub const MAX_INLINE_EXPORT_BYTES: u64 = 512 * 1024 * 1024;
+/// Hard cap; larger bundles are refused outright.
+pub const MAX_EXPORT_BYTES: u64 = 4 * 1024 * 1024 * 1024;
+pub const MANIFEST_NAME: &str = "manifest.json";
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportFile {
+    /// Entry name inside the zip.
+    pub name: String,
+    /// Workspace-relative source path.
+    pub path: String,
+    pub bytes: u64,
+    pub role: &'static str,
+    #[serde(skip)]
+    pub abs_path: PathBuf,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportPlan {
+    pub source: String,
+    pub kind: &'static str,
+    pub files: Vec<ExportFile>,
+}
+
+impl ExportPlan {
+    pub fn total_bytes(&self) -> u64 {
+        self.files.iter().map(|f| f.bytes).sum()
+    }
+
+    /// Download name derived from the exported item or folder.
+    pub fn archive_name(&self) -> String {
+        let stem = Path::new(&self.source)
+            .file_name()
+            .and_then(|s| s.to_str())
+            .unwrap_or("export");
+        let safe: String = stem
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        format!("{safe}.zip")
+    }
+}
+
+pub fn is_exportable_directory(rel_path: &str) -> bool {
+    rel_path.starts_with(EXPORTABLE_DIR_PREFIX)
+        && rel_path.len() > EXPORTABLE_DIR_PREFIX.len()
+        && !rel_path.split('/').any(|part| part == "..")
+}
+
+fn file_len(path: &Path) -> u64 {
+    std::fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+}
+
+fn entry_name(rel_path: &str) -> String {
+    Path::new(rel_path)
+        .file_name()
+        .and_then(|s| s.to_str())
+        .unwrap_or(rel_path)
+        .to_string()
+}
+
+/// Plan a single-item bundle. `related` holds existing workspace-relative
+/// sidecar paths with their roles; entries are stored flat by file name.
+pub fn plan_item_export(
+    workspace_dir: &Path,
+    rel_path: &str,
+    abs_path: &Path,
+    related: &[(String, &'static str)],
+) -> ExportPlan {
+    let mut files = vec![ExportFile {
+        name: entry_name(rel_path),
+        path: rel_path.to_string(),
+        bytes: file_len(abs_path),
+        role: "primary",
+        abs_path: abs_path.to_path_buf(),
+    }];
+    for (rel, role) in related {
+        let abs = workspace_dir.join(rel);
+        let mut name = entry_name(rel);
+        if files.iter().any(|f| f.name == name) {
+            name = format!("{role}/{name}");
+        }
+        files.push(ExportFile {
+            name,
+            path: rel.clone(),
+            bytes: file_len(&abs),
+            role,
+            abs_path: abs,
+        });
+    }
+    ExportPlan {
+        source: rel_path.to_string(),
+        kind: "item",
+        files,
+    }
+}
+
+fn walk_dir(
+    dir: &Path,
+    root_resolved: &Path,
+    workspace_resolved: &Path,
+    prefix: &str,
+    out: &mut Vec<ExportFile>,
+) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        // Symlinks are never followed, so nothing outside the folder leaks in.
+        if file_type.is_symlink() {
+            continue;
+        }
+        let Some(name) = path.file_name().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        let entry_name = format!("{prefix}/{name}");
+        if file_type.is_dir() {
+            walk_dir(&path, root_resolved, workspace_resolved, &entry_name, out);
+        } else if file_type.is_file() {
+            let Ok(resolved) = path.canonicalize() else {
+                continue;
+            };
+            if !resolved.starts_with(root_resolved) {
+                continue;
+            }
+            let rel = resolved
+                .strip_prefix(workspace_resolved)
+                .map(|p| p.to_string_lossy().replace('\\', "/"))
+                .unwrap_or_default();
+            out.push(ExportFile {
+                name: entry_name,
+                path: rel,
+                bytes: file_len(&resolved),
+                role: "file",
+                abs_path: resolved,
+            });
+        }
+    }
+}
+
+/// Plan a bundle of a processed pipeline folder. Entries keep their paths
+/// relative to the folder, nested under the folder's own name.
+pub fn plan_directory_export(workspace_dir: &Path, rel_path: &str) -> Result<ExportPlan> {
+    let rel_path = rel_path.trim_end_matches('/');
+    if !is_exportable_directory(&format!("{rel_path}/")) {
+        bail!("Only folders under {EXPORTABLE_DIR_PREFIX} can be exported");
+    }
+    let workspace_resolved = workspace_dir
+        .canonicalize()
+        .unwrap_or_else(|_| workspace_dir.to_path_buf());
+    let root = workspace_dir
+        .join(rel_path)
+        .canonicalize()
+        .context("Export folder not found")?;
+    let processed_root = workspace_resolved.join(EXPORTABLE_DIR_PREFIX.trim_end_matches('/'));
+    if !root.starts_with(&processed_root) || root == processed_root || !root.is_dir() {
+        bail!("Export folder must be inside {EXPORTABLE_DIR_PREFIX}");
+    }
+    let prefix = root
+        .file_name()
+        .and_then(|s| s.to_str())
+        .unwrap_or("export")
+        .to_string();
+    let mut files = Vec::new();
+    walk_dir(&root, &root, &workspace_resolved, &prefix, &mut files);
+    files.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(ExportPlan {
+        source: rel_path.to_string(),
+        kind: "directory",
+        files,
+    })
+}
+
+fn is_precompressed(name: &str) -> bool {
+    let lower = name.to_ascii_lowercase();
+    [
+        ".mp4", ".mov", ".webm", ".mkv", ".m4a", ".mp3", ".ogg", ".opus", ".aac", ".jpg", ".jpeg",
+        ".png", ".webp", ".gif", ".zip",
+    ]
+    .iter()
+    .any(|ext| lower.ends_with(ext))
+}
+
+/// Write the bundle (files plus `manifest.json`) as a zip archive at `out_path`.
+pub fn write_zip(plan: &ExportPlan, out_path: &Path) -> Result<()> {
+    if let Some(parent) = out_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let file = std::fs::File::create(out_path).context("Failed to create export archive")?;
+    let mut zip = zip::ZipWriter::new(std::io::BufWriter::new(file));
+    for entry in &plan.files {
+        let method = if is_precompressed(&entry.name) {
+            CompressionMethod::Stored
+        } else {
+            CompressionMethod::Deflated
+        };
+        let options = SimpleFileOptions::default()
+            .compression_method(method)
+            .large_file(entry.bytes >= u64::from(u32::MAX));
+        zip.start_file(entry.name.as_str(), options)?;
+        let mut source = std::fs::File::open(&entry.abs_path)
+            .with_context(|| format!("Failed to read {}", entry.path))?;
+        std::io::copy(&mut source, &mut zip)?;
+    }
+    let manifest = serde_json::json!({
+        "source": plan.source,
+        "kind": plan.kind,
+        "exportedAt": chrono::Utc::now().to_rfc3339(),
+        "totalBytes": plan.total_bytes(),
+        "files": plan.files,
+    });
+    zip.start_file(
+        MANIFEST_NAME,
+        SimpleFileOptions::default().compression_method(CompressionMethod::Deflated),
+    )?;
+    zip.write_all(serde_json::to_string_pretty(&manifest)?.as_bytes())?;
+    zip.finish()?.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(workspace: &Path, rel: &str, body: &str) {
+        let path = workspace.join(rel);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, body).unwrap();
+    }
+
+    fn zip_entries(path: &Path) -> Vec<String> {
+        let archive = zip::ZipArchive::new(std::fs::File::open(path).unwrap()).unwrap();
+        let mut names: Vec<String> = archive.file_names().map(str::to_string).collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn item_bundle_contains_primary_sidecars_and_manifest() {
+        let tmp = tempfile::tempdir().unwrap();
+        let media = "journals/media/video/2026/03/01/clip.mp4";
+        write(tmp.path(), media, "video");
+        write(
+            tmp.path(),
+            "journals/text/transcriptions/video/2026/03/01/clip.srt",
+            "1",
+        );
+        write(tmp.path(), &format!("{media}.tags.json"), "{}");
+
+        let related = vec![
+            (
+                "journals/text/transcriptions/video/2026/03/01/clip.srt".to_string(),
+                "transcript",
+            ),
+            (format!("{media}.tags.json"), "tags"),
+        ];
+        let plan = plan_item_export(tmp.path(), media, &tmp.path().join(media), &related);
+        assert_eq!(plan.archive_name(), "clip.mp4.zip");
+        let out = tmp.path().join("out.zip");
+        write_zip(&plan, &out).unwrap();
+        assert_eq!(
+            zip_entries(&out),
+            vec![
+                "clip.mp4",
+                "clip.mp4.tags.json",
+                "clip.srt",
+                "manifest.json"
+            ]
+        );
+
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(&out).unwrap()).unwrap();
+        let manifest: serde_json::Value =
+            serde_json::from_reader(archive.by_name(MANIFEST_NAME).unwrap()).unwrap();
+        assert_eq!(manifest["source"], media);
+        assert_eq!(manifest["files"][1]["role"], "transcript");
+    }
+
+    #[test]
+    fn directory_bundle_stays_inside_requested_subtree() {
+        let tmp = tempfile::tempdir().unwrap();
+        write(tmp.path(), "journals/processed/clip-1/video.mp4", "v");
+        write(tmp.path(), "journals/processed/clip-1/plan/plan.json", "{}");
+        write(tmp.path(), "journals/processed/clip-2/other.mp4", "x");
+        write(tmp.path(), "journals/text/secret.md", "private");
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(
+            tmp.path().join("journals/text/secret.md"),
+            tmp.path().join("journals/processed/clip-1/leak.md"),
+        )
+        .unwrap();
+
+        let plan = plan_directory_export(tmp.path(), "journals/processed/clip-1").unwrap();
+        let out = tmp.path().join("dir.zip");
+        write_zip(&plan, &out).unwrap();
+        assert_eq!(
+            zip_entries(&out),
+            vec!["clip-1/plan/plan.json", "clip-1/video.mp4", "manifest.json"]
+        );
+        assert!(plan
+            .files
+            .iter()
+            .all(|f| f.path.starts_with("journals/processed/clip-1/")));
+    }
+
+    #[test]
+    fn directory_export_is_limited_to_processed_folders() {
+        let tmp = tempfile::tempdir().unwrap();
+        write(tmp.path(), "journals/text/a.md", "a");
+        write(tmp.path(), "journals/processed/x/a.md", "a");
+        assert!(plan_directory_export(tmp.path(), "journals/text").is_err());
+        assert!(plan_directory_export(tmp.path(), "journals/processed").is_err());
+        assert!(plan_directory_export(tmp.path(), "journals/processed/../text").is_err());
+        assert!(plan_directory_export(tmp.path(), "journals/processed/x").is_ok());
+    }
+}
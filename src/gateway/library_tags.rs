@@ -0,0 +1,137 @@
+//! Tags for journal entries and library media.
+//!
+//! The workspace sidecar `<file>.tags.json` is the source of truth; the local
+//! store keeps a `tags_csv` copy for metadata consumers.
+
+use crate::workspace::atomic_write;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+pub const TAGS_SIDECAR_SUFFIX: &str = ".tags.json";
+pub const MAX_TAGS_PER_ITEM: usize = 32;
+pub const MAX_TAG_CHARS: usize = 48;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TagsSidecar {
+    tags: Vec<String>,
+    updated_at: String,
+}
+
+/// Trim, lowercase, strip a leading `#`, drop empties and overlong tags,
+/// dedupe (keeping first occurrence), and cap the count.
+pub fn normalize_tags<I, S>(raw: I) -> Vec<String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let mut out: Vec<String> = Vec::new();
+    for tag in raw {
+        let tag = tag
+            .as_ref()
+            .trim()
+            .trim_start_matches('#')
+            .trim()
+            .to_lowercase();
+        if tag.is_empty() || tag.chars().count() > MAX_TAG_CHARS || out.contains(&tag) {
+            continue;
+        }
+        out.push(tag);
+        if out.len() == MAX_TAGS_PER_ITEM {
+            break;
+        }
+    }
+    out
+}
+
+/// Parse a comma-separated tag list (query string or header form).
+pub fn parse_tag_list(raw: &str) -> Vec<String> {
+    normalize_tags(raw.split(','))
+}
+
+fn sidecar_path(item_path: &Path) -> PathBuf {
+    let mut path = item_path.as_os_str().to_os_string();
+    path.push(TAGS_SIDECAR_SUFFIX);
+    PathBuf::from(path)
+}
+
+pub fn read_tags(item_path: &Path) -> Vec<String> {
+    std::fs::read_to_string(sidecar_path(item_path))
+        .ok()
+        .and_then(|raw| serde_json::from_str::<TagsSidecar>(&raw).ok())
+        .map(|sidecar| normalize_tags(sidecar.tags))
+        .unwrap_or_default()
+}
+
+/// Persist `tags` next to the item; an empty list removes the sidecar.
+pub fn write_tags(item_path: &Path, tags: &[String]) -> Result<()> {
+    let path = sidecar_path(item_path);
+    if tags.is_empty() {
+        if path.exists() {
+            std::fs::remove_file(&path).context("Failed to remove tags sidecar")?;
+        }
+        return Ok(());
+    }
+    let sidecar = TagsSidecar {
+        tags: tags.to_vec(),
+        updated_at: chrono::Utc::now().to_rfc3339(),
+    };
+    atomic_write(&path, serde_json::to_string_pretty(&sidecar)?)
+        .context("Failed to write tags sidecar")
+}
+
+/// Apply additions then removals to the item's current tags and persist the result.
+pub fn update_tags(item_path: &Path, add: &[String], remove: &[String]) -> Result<Vec<String>> {
+    let remove = normalize_tags(remove);
+    let tags: Vec<String> =
+        normalize_tags(read_tags(item_path).into_iter().chain(normalize_tags(add)))
+            .into_iter()
+            .filter(|tag| !remove.contains(tag))
+            .collect();
+    write_tags(item_path, &tags)?;
+    Ok(tags)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_trims_lowercases_and_dedupes() {
+        assert_eq!(
+            normalize_tags([" Travel", "#travel", "", "Visa ", "visa"]),
+            vec!["travel".to_string(), "visa".to_string()]
+        );
+        assert!(normalize_tags(["x".repeat(MAX_TAG_CHARS + 1)]).is_empty());
+    }
+
+    #[test]
+    fn normalize_caps_tag_count() {
+        let many: Vec<String> = (0..100).map(|i| format!("tag{i}")).collect();
+        assert_eq!(normalize_tags(&many).len(), MAX_TAGS_PER_ITEM);
+    }
+
+    #[test]
+    fn parse_tag_list_splits_on_commas() {
+        assert_eq!(parse_tag_list("mood, Family,,mood"), vec!["mood", "family"]);
+    }
+
+    #[test]
+    fn update_tags_adds_removes_and_clears_sidecar() {
+        let tmp = tempfile::tempdir().unwrap();
+        let item = tmp.path().join("photo.jpg");
+        std::fs::write(&item, b"jpg").unwrap();
+
+        let tags = update_tags(&item, &["Beach".into(), "summer".into()], &[]).unwrap();
+        assert_eq!(tags, vec!["beach", "summer"]);
+        assert_eq!(read_tags(&item), tags);
+
+        let tags = update_tags(&item, &[], &["BEACH".into()]).unwrap();
+        assert_eq!(tags, vec!["summer"]);
+
+        update_tags(&item, &[], &["summer".into()]).unwrap();
+        assert!(!sidecar_path(&item).exists());
+        assert!(read_tags(&item).is_empty());
+    }
+}
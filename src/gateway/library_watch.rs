@@ -0,0 +1,574 @@
+//! Library watcher: keeps `journal_entries` and `media_assets` in step with
+//! files that appear in `journals/` and `posts/` without going through the
+//! gateway (an editor, a cron script, the audio_to_video pipeline).
+//!
+//! Filesystem events are debounced and each touched path is reconciled
+//! against the local store. A full rescan runs at startup, on a timer, and
+//! whenever the watcher reports an overflow or error, so a dropped event is
+//! repaired eventually. The watcher only writes the store under `state/`,
+//! which it does not watch, and a reconcile that finds nothing new writes
+//! nothing, so its own work never feeds back into more events.
+
+use super::local_store::{self, LibraryMetadata, LIBRARY_WATCH_SOURCE};
+use super::{library_tags, search_index, workspace_relative_display_path};
+use super::{JOURNAL_AUDIO_INBOX_DIR, JOURNAL_TEXT_INBOX_DIR};
+use crate::util::truncate_with_ellipsis;
+use anyhow::{Context, Result};
+use notify::{EventKind, RecursiveMode, Watcher};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time::{Instant, Interval, MissedTickBehavior};
+
+pub const WATCH_ROOTS: [&str; 2] = ["journals", "posts"];
+
+/// Quiet period after the last event before a batch is reconciled.
+const DEBOUNCE: Duration = Duration::from_millis(750);
+/// Longest a steady stream of events can hold a batch back.
+const MAX_DEBOUNCE: Duration = Duration::from_secs(5);
+const PREVIEW_CHARS: usize = 240;
+/// Transcripts belong to their media item; the inbox maintenance task moves
+/// drop-folder files and records their metadata itself.
+const SKIPPED_DIRS: [&str; 3] = [
+    JOURNAL_TEXT_INBOX_DIR,
+    JOURNAL_AUDIO_INBOX_DIR,
+    "journals/text/transcriptions",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ItemKind {
+    Text,
+    Media {
+        kind: &'static str,
+        mime: &'static str,
+    },
+}
+
+/// What the watcher tracks at `rel_path`, or `None` for everything else
+/// (sidecars, hidden files such as `.versions/` and temp files, unknown
+/// extensions, and the folders other tasks own).
+fn item_kind(rel_path: &str) -> Option<ItemKind> {
+    if rel_path.split('/').any(|part| part.starts_with('.')) {
+        return None;
+    }
+    let lower = rel_path.to_ascii_lowercase();
+    let in_dir = |dir: &str| {
+        lower
+            .strip_prefix(dir)
+            .is_some_and(|rest| rest.starts_with('/'))
+    };
+    if SKIPPED_DIRS.iter().any(|dir| in_dir(dir)) {
+        return None;
+    }
+    if lower.starts_with("posts/")
+        && (lower.contains("/artifacts/") || lower.contains("/pipeline/"))
+    {
+        return None;
+    }
+    let media = |kind, mime| Some(ItemKind::Media { kind, mime });
+    match Path::new(&lower).extension()?.to_str()? {
+        "md" | "txt" => Some(ItemKind::Text),
+        "mp3" => media("audio", "audio/mpeg"),
+        "wav" => media("audio", "audio/wav"),
+        "m4a" => media("audio", "audio/mp4"),
+        "aac" => media("audio", "audio/aac"),
+        "flac" => media("audio", "audio/flac"),
+        "mp4" => media("video", "video/mp4"),
+        "mov" => media("video", "video/quicktime"),
+        "webm" => media("video", "video/webm"),
+        "mkv" => media("video", "video/x-matroska"),
+        "jpg" | "jpeg" => media("image", "image/jpeg"),
+        "png" => media("image", "image/png"),
+        "webp" => media("image", "image/webp"),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncOutcome {
+    Created,
+    Updated,
+    Removed,
+    Unchanged,
+    Ignored,
+}
+
+fn modified_rfc3339(meta: &std::fs::Metadata) -> Option<String> {
+    let modified = meta.modified().ok()?;
+    Some(chrono::DateTime::<chrono::Utc>::from(modified).to_rfc3339())
+}
+
+/// Bring the metadata for `rel_path` in line with the file on disk: create a
+/// row for a new file, refresh the text or size of a changed one, and drop
+/// the row of a file that is gone.
+pub fn sync_path(workspace_dir: &Path, rel_path: &str) -> Result<SyncOutcome> {
+    let Some(kind) = item_kind(rel_path) else {
+        return Ok(SyncOutcome::Ignored);
+    };
+    let abs_path = workspace_dir.join(rel_path);
+    let meta = match std::fs::symlink_metadata(&abs_path) {
+        Ok(meta) if meta.is_file() => meta,
+        Ok(_) => return Ok(SyncOutcome::Ignored),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            let removed = local_store::delete_library_metadata(workspace_dir, rel_path)?;
+            if kind == ItemKind::Text {
+                search_index::note_journal_file_changed(workspace_dir, rel_path);
+            }
+            return Ok(if removed > 0 {
+                SyncOutcome::Removed
+            } else {
+                SyncOutcome::Unchanged
+            });
+        }
+        Err(err) => {
+            return Err(err).with_context(|| format!("Failed to stat {}", abs_path.display()))
+        }
+    };
+    let existing = local_store::library_metadata(workspace_dir, rel_path)?;
+    let title = Path::new(rel_path)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let tags_csv = library_tags::read_tags(&abs_path).join(",");
+
+    match kind {
+        ItemKind::Text => {
+            let body = match std::fs::read_to_string(&abs_path) {
+                Ok(body) => body.trim().to_string(),
+                // Not UTF-8 text; leave it to the library listing.
+                Err(err) if err.kind() == std::io::ErrorKind::InvalidData => {
+                    return Ok(SyncOutcome::Ignored)
+                }
+                Err(err) => {
+                    return Err(err)
+                        .with_context(|| format!("Failed to read {}", abs_path.display()))
+                }
+            };
+            let preview = truncate_with_ellipsis(&body, PREVIEW_CHARS);
+            let outcome = match existing {
+                Some(LibraryMetadata::Journal { text_body }) if text_body.trim() == body => {
+                    return Ok(SyncOutcome::Unchanged)
+                }
+                Some(LibraryMetadata::Journal { .. }) => {
+                    local_store::update_journal_entry_text(
+                        workspace_dir,
+                        rel_path,
+                        &preview,
+                        &body,
+                    )?;
+                    SyncOutcome::Updated
+                }
+                Some(LibraryMetadata::Media { .. }) => return Ok(SyncOutcome::Unchanged),
+                // Editors often create an empty file before the first save.
+                None if body.is_empty() => return Ok(SyncOutcome::Ignored),
+                None => {
+                    local_store::create_journal_entry_metadata(
+                        workspace_dir,
+                        &local_store::JournalEntryInput {
+                            title,
+                            entry_type: "text".to_string(),
+                            source: LIBRARY_WATCH_SOURCE.to_string(),
+                            status: "raw".to_string(),
+                            workspace_path: rel_path.to_string(),
+                            preview_text: preview,
+                            text_body: body,
+                            tags_csv,
+                            created_at_client: modified_rfc3339(&meta),
+                        },
+                    )?;
+                    SyncOutcome::Created
+                }
+            };
+            search_index::note_journal_file_changed(workspace_dir, rel_path);
+            Ok(outcome)
+        }
+        ItemKind::Media { kind, mime } => {
+            let size_bytes = i64::try_from(meta.len()).unwrap_or(i64::MAX);
+            match existing {
+                Some(LibraryMetadata::Media { size_bytes: known }) if known == size_bytes => {
+                    Ok(SyncOutcome::Unchanged)
+                }
+                Some(LibraryMetadata::Media { .. }) => {
+                    local_store::update_media_asset_size(workspace_dir, rel_path, size_bytes)?;
+                    Ok(SyncOutcome::Updated)
+                }
+                Some(LibraryMetadata::Journal { .. }) => Ok(SyncOutcome::Unchanged),
+                None => {
+                    let file_name = Path::new(rel_path)
+                        .file_name()
+                        .map(|name| name.to_string_lossy().into_owned())
+                        .unwrap_or(title);
+                    local_store::create_media_asset_metadata(
+                        workspace_dir,
+                        &local_store::MediaAssetInput {
+                            title: file_name,
+                            entry_id: String::new(),
+                            asset_type: kind.to_string(),
+                            mime_type: mime.to_string(),
+                            source: LIBRARY_WATCH_SOURCE.to_string(),
+                            status: "uploaded".to_string(),
+                            workspace_path: rel_path.to_string(),
+                            size_bytes,
+                            created_at_client: modified_rfc3339(&meta),
+                            probe: crate::media_probe::read_cached_probe(&abs_path),
+                            tags_csv,
+                        },
+                    )?;
+                    Ok(SyncOutcome::Created)
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SyncReport {
+    pub created: usize,
+    pub updated: usize,
+    pub removed: usize,
+}
+
+impl SyncReport {
+    fn record(&mut self, outcome: SyncOutcome) {
+        match outcome {
+            SyncOutcome::Created => self.created += 1,
+            SyncOutcome::Updated => self.updated += 1,
+            SyncOutcome::Removed => self.removed += 1,
+            SyncOutcome::Unchanged | SyncOutcome::Ignored => {}
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+fn sync_logged(workspace_dir: &Path, rel_path: &str, report: &mut SyncReport) {
+    match sync_path(workspace_dir, rel_path) {
+        Ok(outcome) => report.record(outcome),
+        Err(err) => tracing::warn!(path = %rel_path, "Library watch sync failed: {err:#}"),
+    }
+}
+
+fn collect_files(workspace_dir: &Path, dir: &Path, out: &mut Vec<String>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        let path = entry.path();
+        if file_type.is_dir() {
+            collect_files(workspace_dir, &path, out);
+        } else if file_type.is_file() {
+            if let Ok(rel) = path.strip_prefix(workspace_dir) {
+                out.push(workspace_relative_display_path(rel));
+            }
+        }
+    }
+}
+
+/// Reconcile every file under the watched roots, then drop metadata whose
+/// file no longer exists.
+pub fn rescan(workspace_dir: &Path) -> Result<SyncReport> {
+    let mut report = SyncReport::default();
+    for root in WATCH_ROOTS {
+        let mut files = Vec::new();
+        collect_files(workspace_dir, &workspace_dir.join(root), &mut files);
+        for rel_path in &files {
+            sync_logged(workspace_dir, rel_path, &mut report);
+        }
+        let known: HashSet<String> = files.into_iter().collect();
+        for rel_path in
+            local_store::list_library_metadata_paths(workspace_dir, &format!("{root}/"))?
+        {
+            if !known.contains(&rel_path) && !workspace_dir.join(&rel_path).exists() {
+                sync_logged(workspace_dir, &rel_path, &mut report);
+            }
+        }
+    }
+    Ok(report)
+}
+
+/// Paths touched since the last reconcile, or a request for a full rescan.
+#[derive(Debug, Default)]
+struct Batch {
+    paths: HashSet<String>,
+    rescan: bool,
+}
+
+impl Batch {
+    fn add(&mut self, workspace_dirs: &[PathBuf], path: &Path) {
+        let Some(rel) = workspace_dirs
+            .iter()
+            .find_map(|dir| path.strip_prefix(dir).ok())
+        else {
+            return;
+        };
+        // A directory moved in or out carries files no event will name.
+        let has_extension = rel.extension().is_some();
+        if path.is_dir() || (!has_extension && !path.exists()) {
+            self.rescan = true;
+        } else {
+            self.paths.insert(workspace_relative_display_path(rel));
+        }
+    }
+
+    fn apply(self, workspace_dir: &Path) -> Result<SyncReport> {
+        if self.rescan {
+            return rescan(workspace_dir);
+        }
+        let mut report = SyncReport::default();
+        for rel_path in &self.paths {
+            sync_logged(workspace_dir, rel_path, &mut report);
+        }
+        Ok(report)
+    }
+}
+
+enum Signal {
+    Changed(PathBuf),
+    Rescan,
+}
+
+async fn apply_batch(workspace_dir: &Path, batch: Batch) {
+    let rescan = batch.rescan;
+    if rescan {
+        super::workspace_git::note_change(workspace_dir, WATCH_ROOTS, "Sync external edits");
+    } else {
+        super::workspace_git::note_change(workspace_dir, &batch.paths, "Sync external edits");
+    }
+    let dir = workspace_dir.to_path_buf();
+    match tokio::task::spawn_blocking(move || batch.apply(&dir)).await {
+        Ok(Ok(report)) if !report.is_empty() => tracing::info!(
+            rescan,
+            created = report.created,
+            updated = report.updated,
+            removed = report.removed,
+            "Library watch synced metadata"
+        ),
+        Ok(Ok(_)) => {}
+        Ok(Err(err)) => tracing::warn!("Library watch rescan failed: {err:#}"),
+        Err(err) => tracing::warn!("Library watch task failed: {err}"),
+    }
+    super::media_feed::invalidate(workspace_dir);
+}
+
+async fn next_tick(timer: &mut Option<Interval>) {
+    match timer {
+        Some(timer) => {
+            timer.tick().await;
+        }
+        None => std::future::pending().await,
+    }
+}
+
+async fn run(workspace_dir: PathBuf, rescan_interval: Duration) -> Result<()> {
+    for root in WATCH_ROOTS {
+        std::fs::create_dir_all(workspace_dir.join(root))
+            .with_context(|| format!("Failed to create {root}/"))?;
+    }
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        let signals: Vec<Signal> = match res {
+            Ok(event) if event.need_rescan() => vec![Signal::Rescan],
+            Ok(event) if matches!(event.kind, EventKind::Access(_)) => Vec::new(),
+            Ok(event) => event.paths.into_iter().map(Signal::Changed).collect(),
+            Err(err) => {
+                tracing::debug!("Library watcher error, rescanning: {err}");
+                vec![Signal::Rescan]
+            }
+        };
+        for signal in signals {
+            let _ = tx.send(signal);
+        }
+    })
+    .context("Failed to start the library watcher")?;
+    for root in WATCH_ROOTS {
+        watcher
+            .watch(&workspace_dir.join(root), RecursiveMode::Recursive)
+            .with_context(|| format!("Failed to watch {root}/"))?;
+    }
+    // Events may name the canonical path (e.g. `/private/var` on macOS).
+    let mut workspace_dirs = vec![workspace_dir.clone()];
+    if let Ok(canonical) = workspace_dir.canonicalize() {
+        if canonical != workspace_dir {
+            workspace_dirs.push(canonical);
+        }
+    }
+
+    apply_batch(
+        &workspace_dir,
+        Batch {
+            rescan: true,
+            ..Batch::default()
+        },
+    )
+    .await;
+    let mut rescan_timer = (!rescan_interval.is_zero()).then(|| {
+        let mut timer = tokio::time::interval_at(Instant::now() + rescan_interval, rescan_interval);
+        timer.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        timer
+    });
+
+    let add = |batch: &mut Batch, signal: Signal| match signal {
+        Signal::Changed(path) => batch.add(&workspace_dirs, &path),
+        Signal::Rescan => batch.rescan = true,
+    };
+    loop {
+        let mut batch = Batch::default();
+        tokio::select! {
+            signal = rx.recv() => match signal {
+                Some(signal) => add(&mut batch, signal),
+                None => break,
+            },
+            () = next_tick(&mut rescan_timer) => batch.rescan = true,
+        }
+        let deadline = Instant::now() + MAX_DEBOUNCE;
+        loop {
+            let wait = DEBOUNCE.min(deadline.saturating_duration_since(Instant::now()));
+            match tokio::time::timeout(wait, rx.recv()).await {
+                Ok(Some(signal)) => add(&mut batch, signal),
+                Ok(None) | Err(_) => break,
+            }
+        }
+        apply_batch(&workspace_dir, batch).await;
+    }
+    drop(watcher);
+    Ok(())
+}
+
+/// Start the watcher. `rescan_interval` of zero rescans only at startup and
+/// after a watcher overflow.
+pub fn spawn(workspace_dir: PathBuf, rescan_interval: Duration) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        if let Err(err) = run(workspace_dir, rescan_interval).await {
+            tracing::warn!("Library watcher stopped: {err:#}");
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn workspace() -> tempfile::TempDir {
+        let tmp = tempfile::tempdir().unwrap();
+        local_store::initialize(tmp.path()).unwrap();
+        std::fs::create_dir_all(tmp.path().join("journals/text")).unwrap();
+        std::fs::create_dir_all(tmp.path().join("posts")).unwrap();
+        tmp
+    }
+
+    #[test]
+    fn tracks_text_and_media_but_not_sidecars_or_owned_folders() {
+        assert_eq!(item_kind("journals/text/note.md"), Some(ItemKind::Text));
+        assert_eq!(
+            item_kind("posts/clip.MP4"),
+            Some(ItemKind::Media {
+                kind: "video",
+                mime: "video/mp4"
+            })
+        );
+        for ignored in [
+            "journals/text/note.md.tags.json",
+            "journals/text/.note.md.1-0.slowclaw-tmp",
+            ".versions/journals/text/note.md/1.md",
+            "journals/.trash/a/note.md",
+            "journals/text/inbox/drop.md",
+            "journals/media/audio/inbox/memo.m4a",
+            "journals/text/transcriptions/memo.txt",
+            "posts/x/artifacts/frame.png",
+            "journals/media/file.pdf",
+        ] {
+            assert_eq!(item_kind(ignored), None, "{ignored}");
+        }
+    }
+
+    #[test]
+    fn text_files_are_created_updated_and_removed() {
+        let tmp = workspace();
+        let ws = tmp.path();
+        let rel = "journals/text/from-editor.md";
+
+        std::fs::write(ws.join(rel), "").unwrap();
+        assert_eq!(sync_path(ws, rel).unwrap(), SyncOutcome::Ignored);
+
+        std::fs::write(ws.join(rel), "# Trip\n\nPacked the tent.\n").unwrap();
+        assert_eq!(sync_path(ws, rel).unwrap(), SyncOutcome::Created);
+        assert_eq!(sync_path(ws, rel).unwrap(), SyncOutcome::Unchanged);
+        assert_eq!(
+            local_store::library_metadata(ws, rel).unwrap(),
+            Some(LibraryMetadata::Journal {
+                text_body: "# Trip\n\nPacked the tent.".into()
+            })
+        );
+
+        std::fs::write(ws.join(rel), "# Trip\n\nForgot the stove.\n").unwrap();
+        assert_eq!(sync_path(ws, rel).unwrap(), SyncOutcome::Updated);
+
+        std::fs::remove_file(ws.join(rel)).unwrap();
+        assert_eq!(sync_path(ws, rel).unwrap(), SyncOutcome::Removed);
+        assert_eq!(local_store::library_metadata(ws, rel).unwrap(), None);
+    }
+
+    #[test]
+    fn media_size_changes_update_the_row() {
+        let tmp = workspace();
+        let ws = tmp.path();
+        let rel = "posts/render.mp4";
+        std::fs::write(ws.join(rel), vec![0u8; 10]).unwrap();
+        assert_eq!(sync_path(ws, rel).unwrap(), SyncOutcome::Created);
+        std::fs::write(ws.join(rel), vec![0u8; 20]).unwrap();
+        assert_eq!(sync_path(ws, rel).unwrap(), SyncOutcome::Updated);
+        assert_eq!(
+            local_store::library_metadata(ws, rel).unwrap(),
+            Some(LibraryMetadata::Media { size_bytes: 20 })
+        );
+    }
+
+    #[test]
+    fn rescan_picks_up_new_files_and_forgets_missing_ones() {
+        let tmp = workspace();
+        let ws = tmp.path();
+        std::fs::write(ws.join("journals/text/a.md"), "alpha").unwrap();
+        std::fs::write(ws.join("posts/b.png"), b"png").unwrap();
+        let report = rescan(ws).unwrap();
+        assert_eq!(
+            report,
+            SyncReport {
+                created: 2,
+                ..SyncReport::default()
+            }
+        );
+        assert!(rescan(ws).unwrap().is_empty(), "a second rescan is a no-op");
+
+        std::fs::remove_file(ws.join("posts/b.png")).unwrap();
+        let report = rescan(ws).unwrap();
+        assert_eq!(report.removed, 1);
+        assert_eq!(
+            local_store::library_metadata(ws, "posts/b.png").unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn batch_collects_relative_paths_and_escalates_directory_moves() {
+        let tmp = workspace();
+        let ws = tmp.path().to_path_buf();
+        let dirs = [ws.clone()];
+
+        let mut batch = Batch::default();
+        batch.add(&dirs, &ws.join("journals/text/a.md"));
+        batch.add(&dirs, &ws.join("journals/text/a.md"));
+        batch.add(&dirs, Path::new("/elsewhere/b.md"));
+        assert_eq!(batch.paths.len(), 1);
+        assert!(!batch.rescan);
+
+        batch.add(&dirs, &ws.join("journals/text"));
+        assert!(batch.rescan);
+    }
+}
@@ -0,0 +1,180 @@
+//! The socket the gateway serves on: TCP at `host:port`, or a Unix domain
+//! socket when `[gateway] uds_path` is set.
+//!
+//! Handlers see the client as a [`GatewayPeer`]. Socket clients have no IP
+//! address, so rate limiting keys them by user id when the OS reports one.
+
+use anyhow::Result;
+use axum::extract::connect_info::Connected;
+use axum::serve::IncomingStream;
+use axum::Router;
+use std::net::SocketAddr;
+use std::path::Path;
+use tokio::net::TcpListener;
+
+/// The client on the other end of a gateway connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GatewayPeer {
+    Tcp(SocketAddr),
+    /// A Unix domain socket client, with its user id when available.
+    Unix {
+        uid: Option<u32>,
+    },
+}
+
+impl Connected<IncomingStream<'_, TcpListener>> for GatewayPeer {
+    fn connect_info(stream: IncomingStream<'_, TcpListener>) -> Self {
+        Self::Tcp(*stream.remote_addr())
+    }
+}
+
+#[cfg(unix)]
+impl Connected<IncomingStream<'_, tokio::net::UnixListener>> for GatewayPeer {
+    fn connect_info(stream: IncomingStream<'_, tokio::net::UnixListener>) -> Self {
+        Self::Unix {
+            uid: stream.io().peer_cred().ok().map(|cred| cred.uid()),
+        }
+    }
+}
+
+pub enum GatewayListener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(tokio::net::UnixListener),
+}
+
+impl GatewayListener {
+    pub async fn bind_tcp(addr: SocketAddr) -> Result<Self> {
+        Ok(Self::Tcp(TcpListener::bind(addr).await?))
+    }
+
+    /// Bind the socket at `path` and give the file `mode`. A socket file
+    /// nobody is listening on is a leftover from an earlier run and is
+    /// replaced; a live socket or any other kind of file is an error.
+    #[cfg(unix)]
+    pub fn bind_unix(path: &Path, mode: u32) -> Result<Self> {
+        use anyhow::Context;
+        use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+
+        if let Ok(metadata) = std::fs::symlink_metadata(path) {
+            if !metadata.file_type().is_socket() {
+                anyhow::bail!(
+                    "🛑 {} exists and is not a socket; refusing to replace it",
+                    path.display()
+                );
+            }
+            if std::os::unix::net::UnixStream::connect(path).is_ok() {
+                anyhow::bail!(
+                    "🛑 Another process is already listening on {}",
+                    path.display()
+                );
+            }
+            std::fs::remove_file(path)
+                .with_context(|| format!("Failed to remove stale socket {}", path.display()))?;
+        }
+        let listener = tokio::net::UnixListener::bind(path)
+            .with_context(|| format!("Failed to bind Unix socket {}", path.display()))?;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))
+            .with_context(|| format!("Failed to set permissions on {}", path.display()))?;
+        Ok(Self::Unix(listener))
+    }
+
+    #[cfg(not(unix))]
+    pub fn bind_unix(path: &Path, _mode: u32) -> Result<Self> {
+        anyhow::bail!(
+            "🛑 gateway.uds_path is set to {}, but Unix domain sockets are not supported on this platform",
+            path.display()
+        )
+    }
+
+    /// The bound TCP port, or 0 for a Unix socket.
+    pub fn port(&self) -> Result<u16> {
+        match self {
+            Self::Tcp(listener) => Ok(listener.local_addr()?.port()),
+            #[cfg(unix)]
+            Self::Unix(_) => Ok(0),
+        }
+    }
+
+    /// Serve `app` until the listener closes.
+    pub async fn serve(self, app: Router) -> Result<()> {
+        let service = app.into_make_service_with_connect_info::<GatewayPeer>();
+        match self {
+            Self::Tcp(listener) => axum::serve(listener, service).await?,
+            #[cfg(unix)]
+            Self::Unix(listener) => axum::serve(listener, service).await?,
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    async fn get_over_socket(path: &Path, uri: &str) -> String {
+        let mut stream = tokio::net::UnixStream::connect(path).await.unwrap();
+        stream
+            .write_all(
+                format!("GET {uri} HTTP/1.1\r\nHost: gateway\r\nConnection: close\r\n\r\n")
+                    .as_bytes(),
+            )
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    #[tokio::test]
+    async fn socket_is_owner_only_and_reports_the_peer_uid() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("gateway.sock");
+        let listener = GatewayListener::bind_unix(&path, 0o600).unwrap();
+        assert_eq!(listener.port().unwrap(), 0);
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        let app = Router::new().route(
+            "/peer",
+            axum::routing::get(
+                |axum::extract::ConnectInfo(peer): axum::extract::ConnectInfo<GatewayPeer>| async move {
+                    format!("{peer:?}")
+                },
+            ),
+        );
+        let server = tokio::spawn(listener.serve(app));
+        let response = get_over_socket(&path, "/peer").await;
+        server.abort();
+
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+        assert!(response.contains("Unix { uid: Some("), "{response}");
+    }
+
+    #[tokio::test]
+    async fn stale_sockets_are_replaced_but_live_ones_and_files_are_not() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("gateway.sock");
+        drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+        assert!(path.exists(), "an unlinked socket file stays behind");
+        let live = GatewayListener::bind_unix(&path, 0o660).unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o660);
+
+        let err = GatewayListener::bind_unix(&path, 0o600)
+            .err()
+            .expect("live socket refused");
+        assert!(err.to_string().contains("already listening"), "{err}");
+        drop(live);
+
+        let file = tmp.path().join("notes.txt");
+        std::fs::write(&file, "keep me").unwrap();
+        let err = GatewayListener::bind_unix(&file, 0o600)
+            .err()
+            .expect("regular file refused");
+        assert!(err.to_string().contains("not a socket"), "{err}");
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "keep me");
+    }
+}
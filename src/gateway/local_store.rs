@@ -1,8 +1,11 @@
 use anyhow::{Context, Result};
 use chrono::Utc;
+use parking_lot::Mutex;
 use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
-use std::time::Duration;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
 use uuid::Uuid;
 
 #[derive(Debug, Clone, Default)]
@@ -27,6 +30,26 @@ pub struct DraftUpsert {
     pub updated_at_client: Option<String>,
 }
 
+/// Post proposed by the agent; stored in `drafts` with status `pending`.
+#[derive(Debug, Clone)]
+pub struct PostDraftInput {
+    pub text: String,
+    pub channel: String,
+    pub media: Vec<String>,
+}
+
+/// Tool call parked until someone approves it from chat or the API.
+#[derive(Debug, Clone)]
+pub struct ToolApprovalInput {
+    pub tool_name: String,
+    pub arguments: serde_json::Value,
+    pub summary: String,
+    pub origin_channel: String,
+    pub origin_thread: String,
+    /// RFC 3339 time after which the call is denied.
+    pub expires_at: String,
+}
+
 #[derive(Debug, Clone)]
 pub struct PostHistoryInput {
     pub provider: String,
@@ -64,6 +87,8 @@ pub struct MediaAssetInput {
     pub workspace_path: String,
     pub size_bytes: i64,
     pub created_at_client: Option<String>,
+    pub probe: Option<crate::media_probe::MediaProbe>,
+    pub tags_csv: String,
 }
 
 #[derive(Debug, Clone)]
@@ -376,10 +401,10 @@ pub fn list_chat_messages(workspace_dir: &Path, thread_id: &str, limit: usize) -
     let conn = open_conn(&db_path(workspace_dir))?;
     let lim = i64::try_from(limit.max(1)).unwrap_or(200);
     let mut stmt = conn.prepare(
-        "SELECT id, thread_id, role, content, status, source, reply_to_id, error, created_at_client, created, updated
+        "SELECT id, thread_id, role, content, status, source, reply_to_id, error, created_at_client, created, updated, trace, part_index
          FROM chat_messages
          WHERE thread_id = ?1
-         ORDER BY COALESCE(NULLIF(created_at_client, ''), created) ASC, id ASC
+         ORDER BY COALESCE(NULLIF(created_at_client, ''), created) ASC, part_index ASC, id ASC
          LIMIT ?2",
     )?;
     let rows = stmt.query_map(params![thread_id, lim], |row| {
@@ -395,6 +420,8 @@ pub fn list_chat_messages(workspace_dir: &Path, thread_id: &str, limit: usize) -
             "createdAtClient": row.get::<_, String>(8)?,
             "created": row.get::<_, String>(9)?,
             "updated": row.get::<_, String>(10)?,
+            "trace": serde_json::from_str::<serde_json::Value>(&row.get::<_, String>(11)?).ok(),
+            "partIndex": Some(row.get::<_, i64>(12)?).filter(|index| *index > 0),
         }))
     })?;
 
@@ -452,6 +479,57 @@ pub fn create_chat_message(
     }))
 }
 
+/// Store an assistant reply split into `parts` (see `channels::traits::chunk_message`).
+/// All parts share one timestamp and `reply_to_id`; when there is more than
+/// one, each carries its 1-based `partIndex`. Returns the created records.
+pub fn create_chat_reply_parts(
+    workspace_dir: &Path,
+    thread_id: &str,
+    parts: &[String],
+    source: &str,
+    reply_to_id: Option<&str>,
+) -> Result<Vec<serde_json::Value>> {
+    let mut conn = open_conn(&db_path(workspace_dir))?;
+    let now = Utc::now().to_rfc3339();
+    let reply_to = reply_to_id.unwrap_or("").trim();
+    let multipart = parts.len() > 1;
+    let tx = conn.transaction()?;
+    let mut records = Vec::with_capacity(parts.len());
+    for (index, content) in parts.iter().enumerate() {
+        let id = format!("lc_{}", Uuid::new_v4().simple());
+        let part_index = if multipart { index + 1 } else { 0 };
+        tx.execute(
+            "INSERT INTO chat_messages (
+                id, thread_id, role, content, status, source, reply_to_id, error,
+                created_at_client, processed_at, created, updated, part_index
+             ) VALUES (?1, ?2, 'assistant', ?3, 'done', ?4, ?5, '', ?6, '', ?6, ?6, ?7)",
+            params![
+                id,
+                thread_id.trim(),
+                content,
+                source.trim(),
+                reply_to,
+                now,
+                i64::try_from(part_index).unwrap_or(0)
+            ],
+        )
+        .context("Failed to insert chat reply part")?;
+        records.push(serde_json::json!({
+            "id": id,
+            "threadId": thread_id.trim(),
+            "role": "assistant",
+            "content": content,
+            "status": "done",
+            "source": non_empty_opt(source.trim().to_string()),
+            "replyToId": non_empty_opt(reply_to.to_string()),
+            "createdAtClient": now,
+            "partIndex": multipart.then_some(part_index),
+        }));
+    }
+    tx.commit()?;
+    Ok(records)
+}
+
 pub fn patch_chat_status(
     workspace_dir: &Path,
     record_id: &str,
@@ -470,6 +548,127 @@ pub fn patch_chat_status(
     Ok(())
 }
 
+/// Attach a tool-call trace (JSON) to a chat message.
+pub fn set_chat_message_trace(
+    workspace_dir: &Path,
+    record_id: &str,
+    trace: &serde_json::Value,
+) -> Result<()> {
+    let conn = open_conn(&db_path(workspace_dir))?;
+    conn.execute(
+        "UPDATE chat_messages SET trace = ?2, updated = ?3 WHERE id = ?1",
+        params![record_id, trace.to_string(), Utc::now().to_rfc3339()],
+    )
+    .with_context(|| format!("Failed to store chat trace for {}", record_id))?;
+    Ok(())
+}
+
+/// Agent settings for one chat thread. Unset fields fall back to the config.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChatThreadSettings {
+    pub model: Option<String>,
+    pub temperature: Option<f64>,
+    /// Appended to the system prompt for runs in the thread.
+    pub system_suffix: Option<String>,
+}
+
+impl ChatThreadSettings {
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "model": self.model,
+            "temperature": self.temperature,
+            "systemSuffix": self.system_suffix,
+        })
+    }
+}
+
+pub fn get_chat_thread_settings(
+    workspace_dir: &Path,
+    thread_id: &str,
+) -> Result<ChatThreadSettings> {
+    let conn = open_conn(&db_path(workspace_dir))?;
+    let settings = conn
+        .query_row(
+            "SELECT model, temperature, system_suffix FROM chat_threads WHERE thread_id = ?1",
+            params![thread_id.trim()],
+            |row| {
+                Ok(ChatThreadSettings {
+                    model: non_empty_opt(row.get::<_, String>(0)?),
+                    temperature: row.get::<_, Option<f64>>(1)?,
+                    system_suffix: non_empty_opt(row.get::<_, String>(2)?),
+                })
+            },
+        )
+        .optional()
+        .with_context(|| format!("Failed to load chat thread settings for {}", thread_id))?;
+    Ok(settings.unwrap_or_default())
+}
+
+pub fn set_chat_thread_settings(
+    workspace_dir: &Path,
+    thread_id: &str,
+    settings: &ChatThreadSettings,
+) -> Result<()> {
+    let conn = open_conn(&db_path(workspace_dir))?;
+    conn.execute(
+        "INSERT INTO chat_threads (thread_id, model, temperature, system_suffix, updated)
+         VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT(thread_id) DO UPDATE SET
+            model = excluded.model,
+            temperature = excluded.temperature,
+            system_suffix = excluded.system_suffix,
+            updated = excluded.updated",
+        params![
+            thread_id.trim(),
+            settings.model.as_deref().unwrap_or("").trim(),
+            settings.temperature,
+            settings.system_suffix.as_deref().unwrap_or("").trim(),
+            Utc::now().to_rfc3339()
+        ],
+    )
+    .with_context(|| format!("Failed to save chat thread settings for {}", thread_id))?;
+    Ok(())
+}
+
+/// Threads with messages or saved settings, most recently active first.
+pub fn list_chat_threads(workspace_dir: &Path, limit: usize) -> Result<Vec<serde_json::Value>> {
+    let conn = open_conn(&db_path(workspace_dir))?;
+    let lim = i64::try_from(limit.max(1)).unwrap_or(200);
+    let mut stmt = conn.prepare(
+        "SELECT activity.thread_id, MAX(activity.last_at), SUM(activity.message_count),
+                COALESCE(s.model, ''), s.temperature, COALESCE(s.system_suffix, '')
+         FROM (
+            SELECT thread_id, MAX(created) AS last_at, COUNT(*) AS message_count
+            FROM chat_messages GROUP BY thread_id
+            UNION ALL
+            SELECT thread_id, updated, 0 FROM chat_threads
+         ) AS activity
+         LEFT JOIN chat_threads s ON s.thread_id = activity.thread_id
+         GROUP BY activity.thread_id
+         ORDER BY MAX(activity.last_at) DESC
+         LIMIT ?1",
+    )?;
+    let rows = stmt.query_map(params![lim], |row| {
+        let settings = ChatThreadSettings {
+            model: non_empty_opt(row.get::<_, String>(3)?),
+            temperature: row.get::<_, Option<f64>>(4)?,
+            system_suffix: non_empty_opt(row.get::<_, String>(5)?),
+        };
+        Ok(serde_json::json!({
+            "threadId": row.get::<_, String>(0)?,
+            "lastActivity": row.get::<_, String>(1)?,
+            "messageCount": row.get::<_, i64>(2)?,
+            "settings": settings.to_json(),
+        }))
+    })?;
+
+    let mut out = Vec::new();
+    for row in rows {
+        out.push(row?);
+    }
+    Ok(out)
+}
+
 pub fn upsert_draft(workspace_dir: &Path, draft: &DraftUpsert) -> Result<serde_json::Value> {
     let conn = open_conn(&db_path(workspace_dir))?;
     let now = Utc::now().to_rfc3339();
@@ -516,26 +715,46 @@ pub fn upsert_draft(workspace_dir: &Path, draft: &DraftUpsert) -> Result<serde_j
     }))
 }
 
-pub fn list_drafts(workspace_dir: &Path, limit: usize) -> Result<Vec<serde_json::Value>> {
+const DRAFT_COLUMNS: &str = "id, text, video_name, created_at_client, updated_at_client, created, updated,
+     status, channel, media_json, result_uri, error, decided_at";
+
+fn draft_row_json(row: &rusqlite::Row<'_>) -> rusqlite::Result<serde_json::Value> {
+    let media: Vec<String> =
+        serde_json::from_str(&row.get::<_, String>(9)?).unwrap_or_default();
+    Ok(serde_json::json!({
+        "id": row.get::<_, String>(0)?,
+        "text": row.get::<_, String>(1)?,
+        "videoName": row.get::<_, String>(2)?,
+        "createdAtClient": row.get::<_, String>(3)?,
+        "updatedAtClient": row.get::<_, String>(4)?,
+        "created": row.get::<_, String>(5)?,
+        "updated": row.get::<_, String>(6)?,
+        "status": non_empty_opt(row.get::<_, String>(7)?),
+        "channel": non_empty_opt(row.get::<_, String>(8)?),
+        "media": media,
+        "resultUri": non_empty_opt(row.get::<_, String>(10)?),
+        "error": non_empty_opt(row.get::<_, String>(11)?),
+        "decidedAt": non_empty_opt(row.get::<_, String>(12)?),
+    }))
+}
+
+/// Newest drafts first. `status` narrows the list to one approval state;
+/// composer drafts saved by the UI have no status.
+pub fn list_drafts(
+    workspace_dir: &Path,
+    limit: usize,
+    status: Option<&str>,
+) -> Result<Vec<serde_json::Value>> {
     let conn = open_conn(&db_path(workspace_dir))?;
     let lim = i64::try_from(limit.max(1)).unwrap_or(20);
-    let mut stmt = conn.prepare(
-        "SELECT id, text, video_name, created_at_client, updated_at_client, created, updated
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {DRAFT_COLUMNS}
          FROM drafts
+         WHERE ?2 IS NULL OR status = ?2
          ORDER BY COALESCE(NULLIF(created_at_client, ''), created) DESC, id DESC
-         LIMIT ?1",
-    )?;
-    let rows = stmt.query_map(params![lim], |row| {
-        Ok(serde_json::json!({
-            "id": row.get::<_, String>(0)?,
-            "text": row.get::<_, String>(1)?,
-            "videoName": row.get::<_, String>(2)?,
-            "createdAtClient": row.get::<_, String>(3)?,
-            "updatedAtClient": row.get::<_, String>(4)?,
-            "created": row.get::<_, String>(5)?,
-            "updated": row.get::<_, String>(6)?,
-        }))
-    })?;
+         LIMIT ?1"
+    ))?;
+    let rows = stmt.query_map(params![lim, status], draft_row_json)?;
 
     let mut out = Vec::new();
     for row in rows {
@@ -544,6 +763,222 @@ pub fn list_drafts(workspace_dir: &Path, limit: usize) -> Result<Vec<serde_json:
     Ok(out)
 }
 
+pub fn get_draft(workspace_dir: &Path, draft_id: &str) -> Result<Option<serde_json::Value>> {
+    let conn = open_conn(&db_path(workspace_dir))?;
+    conn.query_row(
+        &format!("SELECT {DRAFT_COLUMNS} FROM drafts WHERE id = ?1"),
+        params![draft_id],
+        draft_row_json,
+    )
+    .optional()
+    .with_context(|| format!("Failed to load draft {draft_id}"))
+}
+
+pub fn create_post_draft(workspace_dir: &Path, draft: &PostDraftInput) -> Result<serde_json::Value> {
+    let conn = open_conn(&db_path(workspace_dir))?;
+    let id = format!("lc_{}", Uuid::new_v4().simple());
+    let now = Utc::now().to_rfc3339();
+    conn.execute(
+        "INSERT INTO drafts (
+            id, text, created_at_client, updated_at_client, created, updated, status, channel, media_json
+         ) VALUES (?1, ?2, ?3, ?3, ?3, ?3, 'pending', ?4, ?5)",
+        params![
+            id,
+            draft.text,
+            now,
+            draft.channel.trim(),
+            serde_json::to_string(&draft.media)?,
+        ],
+    )
+    .context("Failed to create post draft")?;
+    get_draft(workspace_dir, &id)?.context("Post draft vanished after insert")
+}
+
+/// Move a draft into `to` if its current status is one of `from`. Returns
+/// `false` when the draft is missing or in another state, so concurrent
+/// approvals cannot both win.
+pub fn transition_draft(
+    workspace_dir: &Path,
+    draft_id: &str,
+    from: &[&str],
+    to: &str,
+    result_uri: Option<&str>,
+    error: Option<&str>,
+) -> Result<bool> {
+    let conn = open_conn(&db_path(workspace_dir))?;
+    let now = Utc::now().to_rfc3339();
+    let rows = conn
+        .execute(
+            "UPDATE drafts
+             SET status = ?3, result_uri = COALESCE(?4, result_uri), error = ?5,
+                 decided_at = ?6, updated = ?6
+             WHERE id = ?1 AND status IN (SELECT value FROM json_each(?2))",
+            params![
+                draft_id,
+                serde_json::to_string(from)?,
+                to,
+                result_uri,
+                error.unwrap_or(""),
+                now,
+            ],
+        )
+        .with_context(|| format!("Failed to update draft {draft_id}"))?;
+    Ok(rows > 0)
+}
+
+/// Reject pending drafts created before `cutoff` (RFC 3339). Returns the ids
+/// that were expired.
+pub fn expire_pending_drafts(workspace_dir: &Path, cutoff: &str) -> Result<Vec<String>> {
+    let conn = open_conn(&db_path(workspace_dir))?;
+    let mut stmt =
+        conn.prepare("SELECT id FROM drafts WHERE status = 'pending' AND created < ?1")?;
+    let ids = stmt
+        .query_map(params![cutoff], |row| row.get::<_, String>(0))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    drop(stmt);
+    let mut expired = Vec::with_capacity(ids.len());
+    for id in ids {
+        if transition_draft(workspace_dir, &id, &["pending"], "rejected", None, Some("expired"))? {
+            expired.push(id);
+        }
+    }
+    Ok(expired)
+}
+
+/// Mark drafts still `sending` as `failed` with `error`. Returns their ids.
+pub fn fail_sending_drafts(workspace_dir: &Path, error: &str) -> Result<Vec<String>> {
+    let conn = open_conn(&db_path(workspace_dir))?;
+    let mut stmt = conn.prepare("SELECT id FROM drafts WHERE status = 'sending'")?;
+    let ids = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    drop(stmt);
+    let mut failed = Vec::with_capacity(ids.len());
+    for id in ids {
+        if transition_draft(
+            workspace_dir,
+            &id,
+            &["sending"],
+            "failed",
+            None,
+            Some(error),
+        )? {
+            failed.push(id);
+        }
+    }
+    Ok(failed)
+}
+
+const TOOL_APPROVAL_COLUMNS: &str = "id, tool_name, arguments_json, summary, status, origin_channel,
+     origin_thread, decided_by, expires_at, decided_at, created, updated";
+
+fn tool_approval_row_json(row: &rusqlite::Row<'_>) -> rusqlite::Result<serde_json::Value> {
+    let arguments: serde_json::Value =
+        serde_json::from_str(&row.get::<_, String>(2)?).unwrap_or(serde_json::Value::Null);
+    Ok(serde_json::json!({
+        "id": row.get::<_, String>(0)?,
+        "toolName": row.get::<_, String>(1)?,
+        "arguments": arguments,
+        "summary": row.get::<_, String>(3)?,
+        "status": row.get::<_, String>(4)?,
+        "originChannel": non_empty_opt(row.get::<_, String>(5)?),
+        "originThread": non_empty_opt(row.get::<_, String>(6)?),
+        "decidedBy": non_empty_opt(row.get::<_, String>(7)?),
+        "expiresAt": row.get::<_, String>(8)?,
+        "decidedAt": non_empty_opt(row.get::<_, String>(9)?),
+        "created": row.get::<_, String>(10)?,
+        "updated": row.get::<_, String>(11)?,
+    }))
+}
+
+/// Store a parked tool call with status `pending`.
+pub fn create_tool_approval(workspace_dir: &Path, approval: &ToolApprovalInput) -> Result<serde_json::Value> {
+    let conn = open_conn(&db_path(workspace_dir))?;
+    let id = format!("ap_{}", &Uuid::new_v4().simple().to_string()[..12]);
+    let now = Utc::now().to_rfc3339();
+    conn.execute(
+        "INSERT INTO tool_approvals (
+            id, tool_name, arguments_json, summary, status, origin_channel, origin_thread,
+            expires_at, created, updated
+         ) VALUES (?1, ?2, ?3, ?4, 'pending', ?5, ?6, ?7, ?8, ?8)",
+        params![
+            id,
+            approval.tool_name,
+            approval.arguments.to_string(),
+            approval.summary,
+            approval.origin_channel,
+            approval.origin_thread,
+            approval.expires_at,
+            now,
+        ],
+    )
+    .context("Failed to create tool approval")?;
+    get_tool_approval(workspace_dir, &id)?.context("Tool approval vanished after insert")
+}
+
+pub fn get_tool_approval(workspace_dir: &Path, approval_id: &str) -> Result<Option<serde_json::Value>> {
+    let conn = open_conn(&db_path(workspace_dir))?;
+    conn.query_row(
+        &format!("SELECT {TOOL_APPROVAL_COLUMNS} FROM tool_approvals WHERE id = ?1"),
+        params![approval_id],
+        tool_approval_row_json,
+    )
+    .optional()
+    .with_context(|| format!("Failed to load tool approval {approval_id}"))
+}
+
+/// Newest approvals first, optionally narrowed to one status.
+pub fn list_tool_approvals(
+    workspace_dir: &Path,
+    limit: usize,
+    status: Option<&str>,
+) -> Result<Vec<serde_json::Value>> {
+    let conn = open_conn(&db_path(workspace_dir))?;
+    let lim = i64::try_from(limit.max(1)).unwrap_or(20);
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {TOOL_APPROVAL_COLUMNS}
+         FROM tool_approvals
+         WHERE ?2 IS NULL OR status = ?2
+         ORDER BY created DESC, id DESC
+         LIMIT ?1"
+    ))?;
+    let rows = stmt.query_map(params![lim, status], tool_approval_row_json)?;
+    rows.collect::<rusqlite::Result<Vec<_>>>()
+        .context("Failed to list tool approvals")
+}
+
+/// Move a pending approval to `to`. Returns `false` when it is missing or
+/// already decided, so only the first decision wins.
+pub fn decide_tool_approval(
+    workspace_dir: &Path,
+    approval_id: &str,
+    to: &str,
+    decided_by: &str,
+) -> Result<bool> {
+    let conn = open_conn(&db_path(workspace_dir))?;
+    let now = Utc::now().to_rfc3339();
+    let rows = conn
+        .execute(
+            "UPDATE tool_approvals
+             SET status = ?2, decided_by = ?3, decided_at = ?4, updated = ?4
+             WHERE id = ?1 AND status = 'pending'",
+            params![approval_id, to, decided_by, now],
+        )
+        .with_context(|| format!("Failed to update tool approval {approval_id}"))?;
+    Ok(rows > 0)
+}
+
+/// Ids of pending approvals whose `expires_at` is before `now` (RFC 3339).
+pub fn overdue_tool_approvals(workspace_dir: &Path, now: &str) -> Result<Vec<String>> {
+    let conn = open_conn(&db_path(workspace_dir))?;
+    let mut stmt = conn
+        .prepare("SELECT id FROM tool_approvals WHERE status = 'pending' AND expires_at < ?1")?;
+    let ids = stmt
+        .query_map(params![now], |row| row.get::<_, String>(0))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(ids)
+}
+
 pub fn create_post_history(workspace_dir: &Path, item: &PostHistoryInput) -> Result<serde_json::Value> {
     let conn = open_conn(&db_path(workspace_dir))?;
     let id = format!("lc_{}", Uuid::new_v4().simple());
@@ -1051,6 +1486,291 @@ pub fn rename_media_asset_path(
                 old_path, new_path
             )
         })?;
+    invalidate_library_record_states(workspace_dir);
+    Ok(rows)
+}
+
+pub fn update_media_asset_preview_text(
+    workspace_dir: &Path,
+    workspace_path: &str,
+    preview_text: &str,
+) -> Result<usize> {
+    let conn = open_conn(&db_path(workspace_dir))?;
+    let rows = conn
+        .execute(
+            "UPDATE media_assets
+             SET preview_text = ?2
+             WHERE workspace_path = ?1",
+            params![workspace_path.trim(), preview_text.trim()],
+        )
+        .with_context(|| format!("Failed to update media preview for {}", workspace_path))?;
+    Ok(rows)
+}
+
+/// Current metadata status for a library path, checking journal entries first.
+pub fn library_item_status(workspace_dir: &Path, workspace_path: &str) -> Result<Option<String>> {
+    let conn = open_conn(&db_path(workspace_dir))?;
+    for table in ["journal_entries", "media_assets"] {
+        let status: Option<String> = conn
+            .query_row(
+                &format!("SELECT status FROM {table} WHERE workspace_path = ?1 LIMIT 1"),
+                params![workspace_path.trim()],
+                |row| row.get(0),
+            )
+            .optional()
+            .with_context(|| format!("Failed to read status for {}", workspace_path))?;
+        if status.is_some() {
+            return Ok(status);
+        }
+    }
+    Ok(None)
+}
+
+pub fn set_library_item_status(
+    workspace_dir: &Path,
+    workspace_path: &str,
+    status: &str,
+) -> Result<usize> {
+    let conn = open_conn(&db_path(workspace_dir))?;
+    let mut rows = 0;
+    for table in ["journal_entries", "media_assets"] {
+        rows += conn
+            .execute(
+                &format!("UPDATE {table} SET status = ?2 WHERE workspace_path = ?1"),
+                params![workspace_path.trim(), status],
+            )
+            .with_context(|| format!("Failed to update status for {}", workspace_path))?;
+    }
+    invalidate_library_record_states(workspace_dir);
+    Ok(rows)
+}
+
+/// How long [`library_record_states`] answers from memory before reading the
+/// store again. Writes through this module drop the cache immediately.
+const LIBRARY_RECORD_STATE_TTL: Duration = Duration::from_secs(30);
+
+/// Processing state of a library file, from whichever metadata row tracks it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LibraryRecordState {
+    pub status: String,
+    /// Journal entry the file belongs to; a journal entry's own id.
+    pub entry_id: String,
+    pub error: String,
+}
+
+struct LibraryRecordStateCache {
+    loaded_at: Instant,
+    /// `None` remembers paths that have no metadata row.
+    states: HashMap<String, Option<LibraryRecordState>>,
+}
+
+fn library_record_state_cache() -> &'static Mutex<HashMap<PathBuf, LibraryRecordStateCache>> {
+    static CACHE: OnceLock<Mutex<HashMap<PathBuf, LibraryRecordStateCache>>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn invalidate_library_record_states(workspace_dir: &Path) {
+    library_record_state_cache().lock().remove(workspace_dir);
+}
+
+/// Metadata state for each of `paths` that has a journal entry or media
+/// asset row. Paths not cached yet are read with a single query.
+pub fn library_record_states(
+    workspace_dir: &Path,
+    paths: &[String],
+) -> Result<HashMap<String, LibraryRecordState>> {
+    let missing: Vec<&str> = {
+        let mut cache = library_record_state_cache().lock();
+        if cache
+            .get(workspace_dir)
+            .is_some_and(|entry| entry.loaded_at.elapsed() > LIBRARY_RECORD_STATE_TTL)
+        {
+            cache.remove(workspace_dir);
+        }
+        let cached = cache.get(workspace_dir);
+        paths
+            .iter()
+            .map(String::as_str)
+            .filter(|path| cached.map_or(true, |entry| !entry.states.contains_key(*path)))
+            .collect()
+    };
+
+    if !missing.is_empty() {
+        let conn = open_conn(&db_path(workspace_dir))?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT workspace_path, status, id, '' FROM journal_entries
+                 WHERE workspace_path IN (SELECT value FROM json_each(?1))
+                 UNION ALL
+                 SELECT workspace_path, status, entry_id, error FROM media_assets
+                 WHERE workspace_path IN (SELECT value FROM json_each(?1))",
+            )
+            .context("Failed to prepare library record state query")?;
+        let rows = stmt
+            .query_map(params![serde_json::to_string(&missing)?], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    LibraryRecordState {
+                        status: row.get(1)?,
+                        entry_id: row.get(2)?,
+                        error: row.get(3)?,
+                    },
+                ))
+            })
+            .context("Failed to read library record states")?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .context("Failed to read library record states")?;
+
+        let mut cache = library_record_state_cache().lock();
+        let entry = cache
+            .entry(workspace_dir.to_path_buf())
+            .or_insert_with(|| LibraryRecordStateCache {
+                loaded_at: Instant::now(),
+                states: HashMap::new(),
+            });
+        for path in &missing {
+            entry.states.entry((*path).to_string()).or_insert(None);
+        }
+        for (path, state) in rows {
+            // Journal entries come first and win over a media row for the same path.
+            let slot = entry.states.entry(path).or_insert(None);
+            if slot.is_none() {
+                *slot = Some(state);
+            }
+        }
+    }
+
+    let cache = library_record_state_cache().lock();
+    let Some(entry) = cache.get(workspace_dir) else {
+        return Ok(HashMap::new());
+    };
+    Ok(paths
+        .iter()
+        .filter_map(|path| {
+            entry
+                .states
+                .get(path)
+                .cloned()
+                .flatten()
+                .map(|state| (path.clone(), state))
+        })
+        .collect())
+}
+
+/// Title and processing status of a library file, for the feed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LibraryItemDetails {
+    pub title: String,
+    pub status: String,
+}
+
+/// Title and status for each of `paths` that has a journal entry or media
+/// asset row; a journal entry wins over a media row for the same path.
+pub fn library_item_details(
+    workspace_dir: &Path,
+    paths: &[String],
+) -> Result<HashMap<String, LibraryItemDetails>> {
+    let conn = open_conn(&db_path(workspace_dir))?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT workspace_path, title, status FROM media_assets
+             WHERE workspace_path IN (SELECT value FROM json_each(?1))
+             UNION ALL
+             SELECT workspace_path, title, status FROM journal_entries
+             WHERE workspace_path IN (SELECT value FROM json_each(?1))",
+        )
+        .context("Failed to prepare library item details query")?;
+    let rows = stmt
+        .query_map(params![serde_json::to_string(paths)?], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                LibraryItemDetails {
+                    title: row.get(1)?,
+                    status: row.get(2)?,
+                },
+            ))
+        })
+        .context("Failed to read library item details")?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("Failed to read library item details")?;
+    Ok(rows.into_iter().collect())
+}
+
+/// The latest successful post of a library file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PublishedPost {
+    pub provider: String,
+    pub uri: String,
+    pub published_at: String,
+}
+
+/// Latest successful `post_history` row for each of `source_paths` that
+/// was posted.
+pub fn latest_posts_by_source(
+    workspace_dir: &Path,
+    source_paths: &[String],
+) -> Result<HashMap<String, PublishedPost>> {
+    let conn = open_conn(&db_path(workspace_dir))?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT source_path, provider, uri, COALESCE(NULLIF(created_at_client, ''), created)
+             FROM post_history
+             WHERE status = 'success' AND source_path IN (SELECT value FROM json_each(?1))
+             ORDER BY COALESCE(NULLIF(created_at_client, ''), created) ASC, id ASC",
+        )
+        .context("Failed to prepare published post query")?;
+    let rows = stmt
+        .query_map(params![serde_json::to_string(source_paths)?], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                PublishedPost {
+                    provider: row.get(1)?,
+                    uri: row.get(2)?,
+                    published_at: row.get(3)?,
+                },
+            ))
+        })
+        .context("Failed to read published posts")?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("Failed to read published posts")?;
+    // Rows are oldest first, so the newest post per source is inserted last.
+    Ok(rows.into_iter().collect())
+}
+
+/// Record why processing of a media asset failed; empty clears it.
+pub fn set_media_asset_error(
+    workspace_dir: &Path,
+    workspace_path: &str,
+    error: &str,
+) -> Result<usize> {
+    let conn = open_conn(&db_path(workspace_dir))?;
+    let rows = conn
+        .execute(
+            "UPDATE media_assets SET error = ?2 WHERE workspace_path = ?1",
+            params![workspace_path.trim(), error.trim()],
+        )
+        .with_context(|| format!("Failed to update media error for {}", workspace_path))?;
+    invalidate_library_record_states(workspace_dir);
+    Ok(rows)
+}
+
+/// Mirror an item's tags onto whichever metadata row tracks its path.
+pub fn update_library_item_tags(
+    workspace_dir: &Path,
+    workspace_path: &str,
+    tags_csv: &str,
+) -> Result<usize> {
+    let conn = open_conn(&db_path(workspace_dir))?;
+    let path = workspace_path.trim();
+    let mut rows = 0;
+    for table in ["journal_entries", "media_assets"] {
+        rows += conn
+            .execute(
+                &format!("UPDATE {table} SET tags_csv = ?2 WHERE workspace_path = ?1"),
+                params![path, tags_csv],
+            )
+            .with_context(|| format!("Failed to update tags for {}", path))?;
+    }
     Ok(rows)
 }
 
@@ -1173,6 +1893,17 @@ fn journal_preview_and_body_from_workspace(
     Some((preview, trimmed))
 }
 
+/// A handler creating metadata for a path supersedes whatever the library
+/// watcher recorded for it first.
+fn remove_watch_created_row(conn: &Connection, table: &str, workspace_path: &str) -> Result<()> {
+    conn.execute(
+        &format!("DELETE FROM {table} WHERE workspace_path = ?1 AND source = ?2"),
+        params![workspace_path.trim(), LIBRARY_WATCH_SOURCE],
+    )
+    .with_context(|| format!("Failed to replace watcher metadata for {}", workspace_path))?;
+    Ok(())
+}
+
 pub fn create_journal_entry_metadata(
     workspace_dir: &Path,
     item: &JournalEntryInput,
@@ -1187,6 +1918,7 @@ pub fn create_journal_entry_metadata(
         .filter(|v| !v.is_empty())
         .map(ToOwned::to_owned)
         .unwrap_or_else(|| created.clone());
+    remove_watch_created_row(&conn, "journal_entries", &item.workspace_path)?;
     conn.execute(
         "INSERT INTO journal_entries (
             id, title, entry_type, source, status, workspace_path, preview_text, text_body,
@@ -1207,6 +1939,7 @@ pub fn create_journal_entry_metadata(
         ],
     )
     .context("Failed to insert journal metadata")?;
+    invalidate_library_record_states(workspace_dir);
     Ok(serde_json::json!({
         "id": id,
         "title": item.title,
@@ -1235,11 +1968,14 @@ pub fn create_media_asset_metadata(
         .filter(|v| !v.is_empty())
         .map(ToOwned::to_owned)
         .unwrap_or_else(|| created.clone());
+    let probe = item.probe.clone().unwrap_or_default();
+    remove_watch_created_row(&conn, "media_assets", &item.workspace_path)?;
     conn.execute(
         "INSERT INTO media_assets (
             id, title, entry_id, asset_type, mime_type, source, status, workspace_path,
-            size_bytes, created_at_client, created
-         ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            size_bytes, created_at_client, created, duration_ms, sample_rate, width, height,
+            codec, tags_csv
+         ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)",
         params![
             id,
             item.title,
@@ -1251,10 +1987,17 @@ pub fn create_media_asset_metadata(
             item.workspace_path,
             item.size_bytes,
             created_at_client,
-            created
+            created,
+            probe.duration_ms.map(|v| v as i64),
+            probe.sample_rate,
+            probe.width,
+            probe.height,
+            probe.codec.clone().unwrap_or_default(),
+            item.tags_csv
         ],
     )
     .context("Failed to insert media metadata")?;
+    invalidate_library_record_states(workspace_dir);
     Ok(serde_json::json!({
         "id": id,
         "title": item.title,
@@ -1266,9 +2009,123 @@ pub fn create_media_asset_metadata(
         "workspacePath": item.workspace_path,
         "sizeBytes": item.size_bytes,  // integer
         "createdAtClient": created_at_client,
+        "durationMs": probe.duration_ms,
+        "sampleRate": probe.sample_rate,
+        "width": probe.width,
+        "height": probe.height,
+        "codec": probe.codec,
+        "tagsCsv": item.tags_csv,
     }))
 }
 
+/// `source` of metadata rows the library watcher creates for files that
+/// appeared on disk. A later upload or journal save for the same path
+/// replaces such a row instead of duplicating it.
+pub const LIBRARY_WATCH_SOURCE: &str = "workspace-watch";
+
+/// What the library watcher compares against a file on disk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LibraryMetadata {
+    Journal { text_body: String },
+    Media { size_bytes: i64 },
+}
+
+/// Metadata tracking `workspace_path`, checking journal entries first.
+pub fn library_metadata(
+    workspace_dir: &Path,
+    workspace_path: &str,
+) -> Result<Option<LibraryMetadata>> {
+    let conn = open_conn(&db_path(workspace_dir))?;
+    let path = workspace_path.trim();
+    let journal = conn
+        .query_row(
+            "SELECT text_body FROM journal_entries WHERE workspace_path = ?1 LIMIT 1",
+            params![path],
+            |row| row.get(0),
+        )
+        .optional()
+        .with_context(|| format!("Failed to read journal metadata for {path}"))?
+        .map(|text_body| LibraryMetadata::Journal { text_body });
+    if journal.is_some() {
+        return Ok(journal);
+    }
+    conn.query_row(
+        "SELECT size_bytes FROM media_assets WHERE workspace_path = ?1 LIMIT 1",
+        params![path],
+        |row| row.get(0),
+    )
+    .optional()
+    .map(|size| size.map(|size_bytes| LibraryMetadata::Media { size_bytes }))
+    .with_context(|| format!("Failed to read media metadata for {path}"))
+}
+
+pub fn update_journal_entry_text(
+    workspace_dir: &Path,
+    workspace_path: &str,
+    preview_text: &str,
+    text_body: &str,
+) -> Result<usize> {
+    let conn = open_conn(&db_path(workspace_dir))?;
+    conn.execute(
+        "UPDATE journal_entries
+         SET preview_text = ?2, text_body = ?3
+         WHERE workspace_path = ?1",
+        params![workspace_path.trim(), preview_text, text_body],
+    )
+    .with_context(|| format!("Failed to update journal text for {}", workspace_path))
+}
+
+pub fn update_media_asset_size(
+    workspace_dir: &Path,
+    workspace_path: &str,
+    size_bytes: i64,
+) -> Result<usize> {
+    let conn = open_conn(&db_path(workspace_dir))?;
+    conn.execute(
+        "UPDATE media_assets SET size_bytes = ?2 WHERE workspace_path = ?1",
+        params![workspace_path.trim(), size_bytes],
+    )
+    .with_context(|| format!("Failed to update media size for {}", workspace_path))
+}
+
+/// Drop the metadata of a file that is gone. Trashed items keep theirs so a
+/// restore from `.trash/` brings them back intact.
+pub fn delete_library_metadata(workspace_dir: &Path, workspace_path: &str) -> Result<usize> {
+    let conn = open_conn(&db_path(workspace_dir))?;
+    let mut rows = 0;
+    for table in ["journal_entries", "media_assets"] {
+        rows += conn
+            .execute(
+                &format!("DELETE FROM {table} WHERE workspace_path = ?1 AND status != 'trashed'"),
+                params![workspace_path.trim()],
+            )
+            .with_context(|| format!("Failed to delete metadata for {}", workspace_path))?;
+    }
+    invalidate_library_record_states(workspace_dir);
+    Ok(rows)
+}
+
+/// Paths of every journal entry and media asset under `prefix` that is not
+/// in the trash.
+pub fn list_library_metadata_paths(workspace_dir: &Path, prefix: &str) -> Result<Vec<String>> {
+    let conn = open_conn(&db_path(workspace_dir))?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT workspace_path FROM journal_entries
+             WHERE status != 'trashed' AND substr(workspace_path, 1, length(?1)) = ?1
+             UNION
+             SELECT workspace_path FROM media_assets
+             WHERE status != 'trashed' AND substr(workspace_path, 1, length(?1)) = ?1",
+        )
+        .context("Failed to prepare library metadata path query")?;
+    let paths = stmt
+        .query_map(params![prefix], |row| row.get(0))
+        .context("Failed to list library metadata paths")?
+        .collect::<rusqlite::Result<Vec<String>>>()
+        .context("Failed to read library metadata paths")?;
+    Ok(paths)
+}
+
 pub fn list_feed_interests(workspace_dir: &Path) -> Result<Vec<FeedInterestRecord>> {
     let conn = open_conn(&db_path(workspace_dir))?;
     let mut stmt = conn.prepare(
@@ -2336,6 +3193,14 @@ fn init_schema(conn: &Connection) -> Result<()> {
         CREATE INDEX IF NOT EXISTS idx_chat_messages_thread_created
             ON chat_messages(thread_id, created_at_client, created);
 
+        CREATE TABLE IF NOT EXISTS chat_threads (
+            thread_id TEXT PRIMARY KEY,
+            model TEXT NOT NULL DEFAULT '',
+            temperature REAL,
+            system_suffix TEXT NOT NULL DEFAULT '',
+            updated TEXT NOT NULL
+        );
+
         CREATE TABLE IF NOT EXISTS drafts (
             id TEXT PRIMARY KEY,
             text TEXT NOT NULL DEFAULT '',
@@ -2348,6 +3213,23 @@ fn init_schema(conn: &Connection) -> Result<()> {
         CREATE INDEX IF NOT EXISTS idx_drafts_created
             ON drafts(created_at_client, created);
 
+        CREATE TABLE IF NOT EXISTS tool_approvals (
+            id TEXT PRIMARY KEY,
+            tool_name TEXT NOT NULL,
+            arguments_json TEXT NOT NULL DEFAULT '{}',
+            summary TEXT NOT NULL DEFAULT '',
+            status TEXT NOT NULL DEFAULT 'pending',
+            origin_channel TEXT NOT NULL DEFAULT '',
+            origin_thread TEXT NOT NULL DEFAULT '',
+            decided_by TEXT NOT NULL DEFAULT '',
+            expires_at TEXT NOT NULL,
+            decided_at TEXT NOT NULL DEFAULT '',
+            created TEXT NOT NULL,
+            updated TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_tool_approvals_status
+            ON tool_approvals(status, expires_at);
+
         CREATE TABLE IF NOT EXISTS post_history (
             id TEXT PRIMARY KEY,
             provider TEXT NOT NULL DEFAULT 'bluesky',
@@ -2658,6 +3540,24 @@ fn init_schema(conn: &Connection) -> Result<()> {
         "triage_keywords_json",
         "TEXT NOT NULL DEFAULT ''",
     )?;
+    ensure_column(
+        conn,
+        "media_assets",
+        "preview_text",
+        "TEXT NOT NULL DEFAULT ''",
+    )?;
+    ensure_column(conn, "media_assets", "duration_ms", "INTEGER")?;
+    ensure_column(conn, "media_assets", "sample_rate", "INTEGER")?;
+    ensure_column(conn, "media_assets", "width", "INTEGER")?;
+    ensure_column(conn, "media_assets", "height", "INTEGER")?;
+    ensure_column(conn, "media_assets", "codec", "TEXT NOT NULL DEFAULT ''")?;
+    ensure_column(conn, "media_assets", "tags_csv", "TEXT NOT NULL DEFAULT ''")?;
+    ensure_column(conn, "media_assets", "error", "TEXT NOT NULL DEFAULT ''")?;
+    ensure_column(conn, "chat_messages", "trace", "TEXT NOT NULL DEFAULT ''")?;
+    ensure_column(conn, "chat_messages", "part_index", "INTEGER NOT NULL DEFAULT 0")?;
+    for column in ["status", "channel", "media_json", "result_uri", "error", "decided_at"] {
+        ensure_column(conn, "drafts", column, "TEXT NOT NULL DEFAULT ''")?;
+    }
 
     Ok(())
 }
@@ -2787,6 +3687,28 @@ mod tests {
         assert!(empty.is_empty());
     }
 
+    #[test]
+    fn chat_reply_parts_share_reply_and_list_in_order() {
+        let tmp = test_workspace();
+        initialize(tmp.path()).unwrap();
+
+        let parts: Vec<String> = (1..=3).map(|i| format!("part {i}")).collect();
+        let records =
+            create_chat_reply_parts(tmp.path(), "thread-1", &parts, "slowclaw", Some("lc_user"))
+                .unwrap();
+        assert_eq!(records.len(), 3);
+
+        let msgs = list_chat_messages(tmp.path(), "thread-1", 100).unwrap();
+        let order: Vec<_> = msgs.iter().map(|m| m["content"].clone()).collect();
+        assert_eq!(order, vec!["part 1", "part 2", "part 3"]);
+        assert!(msgs.iter().all(|m| m["replyToId"] == "lc_user"));
+        assert_eq!(msgs[2]["partIndex"], 3);
+
+        create_chat_reply_parts(tmp.path(), "thread-2", &parts[..1], "slowclaw", None).unwrap();
+        let single = list_chat_messages(tmp.path(), "thread-2", 100).unwrap();
+        assert!(single[0]["partIndex"].is_null());
+    }
+
     #[test]
     fn patch_chat_status_updates_fields() {
         let tmp = test_workspace();
@@ -2811,6 +3733,75 @@ mod tests {
         assert_eq!(msgs[0]["status"], "done");
     }
 
+    #[test]
+    fn chat_message_trace_roundtrips() {
+        let tmp = test_workspace();
+        initialize(tmp.path()).unwrap();
+
+        let msg = create_chat_message(
+            tmp.path(),
+            "thread-1",
+            "assistant",
+            "done",
+            "done",
+            "slowclaw",
+            None,
+            None,
+        )
+        .unwrap();
+        let msgs = list_chat_messages(tmp.path(), "thread-1", 100).unwrap();
+        assert!(msgs[0]["trace"].is_null());
+
+        let trace =
+            serde_json::json!({"tool_calls": [{"tool": "audio_to_video", "success": true}]});
+        set_chat_message_trace(tmp.path(), msg["id"].as_str().unwrap(), &trace).unwrap();
+        let msgs = list_chat_messages(tmp.path(), "thread-1", 100).unwrap();
+        assert_eq!(msgs[0]["trace"], trace);
+    }
+
+    #[test]
+    fn chat_thread_settings_roundtrip_and_show_in_thread_list() {
+        let tmp = test_workspace();
+        initialize(tmp.path()).unwrap();
+
+        assert_eq!(
+            get_chat_thread_settings(tmp.path(), "quick").unwrap(),
+            ChatThreadSettings::default()
+        );
+        create_chat_message(tmp.path(), "quick", "user", "hi", "done", "", None, None).unwrap();
+        create_chat_message(tmp.path(), "quick", "user", "again", "done", "", None, None).unwrap();
+        let settings = ChatThreadSettings {
+            model: Some("cheap-model".into()),
+            temperature: Some(0.2),
+            system_suffix: None,
+        };
+        set_chat_thread_settings(tmp.path(), "quick", &settings).unwrap();
+        set_chat_thread_settings(
+            tmp.path(),
+            "writing",
+            &ChatThreadSettings {
+                system_suffix: Some("Write in long form.".into()),
+                ..ChatThreadSettings::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            get_chat_thread_settings(tmp.path(), "quick").unwrap(),
+            settings
+        );
+
+        let threads = list_chat_threads(tmp.path(), 10).unwrap();
+        assert_eq!(threads.len(), 2);
+        let quick = threads.iter().find(|t| t["threadId"] == "quick").unwrap();
+        assert_eq!(quick["messageCount"], 2);
+        assert_eq!(quick["settings"]["model"], "cheap-model");
+        assert_eq!(quick["settings"]["temperature"], 0.2);
+        let writing = threads.iter().find(|t| t["threadId"] == "writing").unwrap();
+        assert_eq!(writing["messageCount"], 0);
+        assert!(writing["settings"]["model"].is_null());
+        assert_eq!(writing["settings"]["systemSuffix"], "Write in long form.");
+    }
+
     #[test]
     fn draft_upsert_and_list() {
         let tmp = test_workspace();
@@ -2830,7 +3821,7 @@ mod tests {
         assert!(!d1["id"].as_str().unwrap().is_empty());
         assert_eq!(d1["text"], "draft one");
 
-        let drafts = list_drafts(tmp.path(), 100).unwrap();
+        let drafts = list_drafts(tmp.path(), 100, None).unwrap();
         assert_eq!(drafts.len(), 1);
 
         // Update existing draft
@@ -2847,11 +3838,48 @@ mod tests {
         )
         .unwrap();
 
-        let drafts = list_drafts(tmp.path(), 100).unwrap();
+        let drafts = list_drafts(tmp.path(), 100, None).unwrap();
         assert_eq!(drafts.len(), 1);
         assert_eq!(drafts[0]["text"], "updated draft");
     }
 
+    #[test]
+    fn post_draft_transitions_are_exclusive_and_filterable() {
+        let tmp = test_workspace();
+        initialize(tmp.path()).unwrap();
+        upsert_draft(
+            tmp.path(),
+            &DraftUpsert {
+                id: None,
+                text: "composer draft".into(),
+                video_name: String::new(),
+                created_at_client: None,
+                updated_at_client: None,
+            },
+        )
+        .unwrap();
+        let draft = create_post_draft(
+            tmp.path(),
+            &PostDraftInput {
+                text: "agent draft".into(),
+                channel: "bluesky".into(),
+                media: vec!["journals/media/image/a.jpg".into()],
+            },
+        )
+        .unwrap();
+        let id = draft["id"].as_str().unwrap();
+        assert_eq!(draft["status"], "pending");
+        assert_eq!(draft["media"][0], "journals/media/image/a.jpg");
+
+        let pending = list_drafts(tmp.path(), 10, Some("pending")).unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(list_drafts(tmp.path(), 10, None).unwrap().len(), 2);
+
+        assert!(transition_draft(tmp.path(), id, &["pending"], "sending", None, None).unwrap());
+        assert!(!transition_draft(tmp.path(), id, &["pending"], "sending", None, None).unwrap());
+        assert!(expire_pending_drafts(tmp.path(), "9999").unwrap().is_empty());
+    }
+
     #[test]
     fn post_history_roundtrip() {
         let tmp = test_workspace();
@@ -3358,6 +4386,8 @@ mod tests {
                 workspace_path: "journals/media/recording.mp4".into(),
                 size_bytes: 1_048_576,
                 created_at_client: None,
+                probe: None,
+                tags_csv: String::new(),
             },
         )
         .unwrap();
@@ -3365,6 +4395,125 @@ mod tests {
         assert!(asset["sizeBytes"].is_i64());
     }
 
+    #[test]
+    fn media_asset_preview_text_updates_by_workspace_path() {
+        let tmp = test_workspace();
+        initialize(tmp.path()).unwrap();
+
+        create_media_asset_metadata(
+            tmp.path(),
+            &MediaAssetInput {
+                title: "photo.jpg".into(),
+                entry_id: String::new(),
+                asset_type: "image".into(),
+                mime_type: "image/jpeg".into(),
+                source: "mobile-ui".into(),
+                status: "uploaded".into(),
+                workspace_path: "journals/media/image/photo.jpg".into(),
+                size_bytes: 2048,
+                created_at_client: None,
+                probe: None,
+                tags_csv: String::new(),
+            },
+        )
+        .unwrap();
+
+        let rows = update_media_asset_preview_text(
+            tmp.path(),
+            "journals/media/image/photo.jpg",
+            "A dog on a beach",
+        )
+        .unwrap();
+        assert_eq!(rows, 1);
+        let missing =
+            update_media_asset_preview_text(tmp.path(), "journals/media/image/none.jpg", "x")
+                .unwrap();
+        assert_eq!(missing, 0);
+    }
+
+    #[test]
+    fn handler_metadata_replaces_watcher_rows_and_trash_survives_deletes() {
+        let tmp = test_workspace();
+        initialize(tmp.path()).unwrap();
+        let entry = |source: &str, path: &str| JournalEntryInput {
+            title: "note".into(),
+            entry_type: "text".into(),
+            source: source.into(),
+            status: "raw".into(),
+            workspace_path: path.into(),
+            preview_text: "body".into(),
+            text_body: "body".into(),
+            tags_csv: String::new(),
+            created_at_client: None,
+        };
+
+        let path = "journals/text/note.md";
+        create_journal_entry_metadata(tmp.path(), &entry(LIBRARY_WATCH_SOURCE, path)).unwrap();
+        create_journal_entry_metadata(tmp.path(), &entry("mobile-ui", path)).unwrap();
+        let conn = open_conn(&db_path(tmp.path())).unwrap();
+        let sources: Vec<String> = conn
+            .prepare("SELECT source FROM journal_entries WHERE workspace_path = ?1")
+            .unwrap()
+            .query_map(params![path], |row| row.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(sources, vec!["mobile-ui".to_string()]);
+        assert_eq!(
+            library_metadata(tmp.path(), path).unwrap(),
+            Some(LibraryMetadata::Journal {
+                text_body: "body".into()
+            })
+        );
+
+        let trashed = "journals/text/trashed.md";
+        create_journal_entry_metadata(tmp.path(), &entry("mobile-ui", trashed)).unwrap();
+        set_library_item_status(tmp.path(), trashed, "trashed").unwrap();
+        assert_eq!(
+            list_library_metadata_paths(tmp.path(), "journals/").unwrap(),
+            vec![path.to_string()]
+        );
+        assert_eq!(delete_library_metadata(tmp.path(), trashed).unwrap(), 0);
+        assert_eq!(delete_library_metadata(tmp.path(), path).unwrap(), 1);
+        assert_eq!(library_metadata(tmp.path(), path).unwrap(), None);
+    }
+
+    #[test]
+    fn library_record_states_batch_lookup_and_invalidate_on_write() {
+        let tmp = test_workspace();
+        initialize(tmp.path()).unwrap();
+        let audio = "journals/media/audio/memo.mp3";
+        create_media_asset_metadata(
+            tmp.path(),
+            &MediaAssetInput {
+                title: "memo".into(),
+                entry_id: "entry-1".into(),
+                asset_type: "audio".into(),
+                mime_type: "audio/mpeg".into(),
+                source: "mobile".into(),
+                status: "uploaded".into(),
+                workspace_path: audio.into(),
+                size_bytes: 10,
+                created_at_client: None,
+                probe: None,
+                tags_csv: String::new(),
+            },
+        )
+        .unwrap();
+        let paths = vec![audio.to_string(), "journals/text/none.md".to_string()];
+
+        let states = library_record_states(tmp.path(), &paths).unwrap();
+        assert_eq!(states.len(), 1);
+        assert_eq!(states[audio].status, "uploaded");
+        assert_eq!(states[audio].entry_id, "entry-1");
+
+        set_library_item_status(tmp.path(), audio, "error").unwrap();
+        set_media_asset_error(tmp.path(), audio, "render failed").unwrap();
+        let states = library_record_states(tmp.path(), &paths).unwrap();
+        assert_eq!(states[audio].status, "error");
+        assert_eq!(states[audio].error, "render failed");
+    }
+
     #[test]
     fn normalize_role_maps_correctly() {
         assert_eq!(normalize_role("user"), "user");
@@ -0,0 +1,361 @@
+//! Server-side Markdown rendering for library text.
+//!
+//! The output is meant to be dropped straight into the mobile UI, so it is
+//! sanitized here: raw HTML in the source (`<script>`, `<iframe>`, inline
+//! `onerror=` handlers, ...) is escaped into text, and link or image URLs
+//! with any scheme other than http, https, or mailto are blanked. Relative
+//! image paths that stay inside the workspace become `/api/media/...` URLs
+//! under the gateway's route prefix.
+
+use pulldown_cmark::{CowStr, Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fmt::Write as _;
+
+const SAFE_SCHEMES: &[&str] = &["http", "https", "mailto"];
+
+/// One heading of the document, in order, for a table of contents.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct OutlineHeading {
+    pub level: u8,
+    pub text: String,
+    /// The `id` attribute given to the rendered heading.
+    pub anchor: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct MarkdownSummary {
+    pub word_count: usize,
+    pub outline: Vec<OutlineHeading>,
+}
+
+fn options() -> Options {
+    Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS
+}
+
+/// Word count and heading outline of `markdown`, ignoring markup.
+pub fn summarize(markdown: &str) -> MarkdownSummary {
+    let mut summary = MarkdownSummary::default();
+    let mut anchors = HashSet::new();
+    let mut heading: Option<(u8, String)> = None;
+    for event in Parser::new_ext(markdown, options()) {
+        match event {
+            Event::Start(Tag::Heading { level, .. }) => {
+                heading = Some((heading_level(level), String::new()));
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                if let Some((level, text)) = heading.take() {
+                    let text = text.trim().to_string();
+                    let anchor = unique_anchor(&text, &mut anchors);
+                    summary.outline.push(OutlineHeading {
+                        level,
+                        text,
+                        anchor,
+                    });
+                }
+            }
+            Event::Text(text) | Event::Code(text) => {
+                summary.word_count += text
+                    .split_whitespace()
+                    .filter(|word| word.chars().any(char::is_alphanumeric))
+                    .count();
+                if let Some((_, heading_text)) = heading.as_mut() {
+                    heading_text.push_str(&text);
+                }
+            }
+            _ => {}
+        }
+    }
+    summary
+}
+
+/// Sanitized HTML for `markdown`, stored at `note_rel_path` in the
+/// workspace. Headings carry the anchors [`summarize`] reports.
+pub fn render_html(markdown: &str, note_rel_path: &str, route_prefix: &str) -> String {
+    let outline = summarize(markdown).outline;
+    let mut anchors = outline.into_iter().map(|heading| heading.anchor);
+    let events = Parser::new_ext(markdown, options()).map(|event| match event {
+        Event::Html(raw) => Event::Html(CowStr::from(super::html_escape(&raw))),
+        Event::InlineHtml(raw) => Event::InlineHtml(CowStr::from(super::html_escape(&raw))),
+        Event::Start(Tag::Heading {
+            level,
+            classes,
+            attrs,
+            ..
+        }) => Event::Start(Tag::Heading {
+            level,
+            id: anchors.next().map(CowStr::from),
+            classes,
+            attrs,
+        }),
+        Event::Start(Tag::Link {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) => Event::Start(Tag::Link {
+            link_type,
+            dest_url: safe_url(dest_url),
+            title,
+            id,
+        }),
+        Event::Start(Tag::Image {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) => Event::Start(Tag::Image {
+            link_type,
+            dest_url: image_url(dest_url, note_rel_path, route_prefix),
+            title,
+            id,
+        }),
+        other => other,
+    });
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, events);
+    html
+}
+
+/// `markdown` with the markup removed, for plain-text consumers. Blocks
+/// stay separated by blank lines and list items keep a `- ` or `1. ` lead.
+pub fn to_plain_text(markdown: &str) -> String {
+    let mut out = String::new();
+    let mut lists: Vec<Option<u64>> = Vec::new();
+    for event in Parser::new_ext(markdown, options()) {
+        match event {
+            Event::Text(text) | Event::Code(text) | Event::Html(text) | Event::InlineHtml(text) => {
+                out.push_str(&text);
+            }
+            Event::SoftBreak | Event::HardBreak => out.push('\n'),
+            Event::Start(Tag::List(start)) => lists.push(start),
+            Event::End(TagEnd::List(_)) => {
+                lists.pop();
+                if lists.is_empty() {
+                    block_break(&mut out);
+                }
+            }
+            Event::Start(Tag::Item) => {
+                if !out.is_empty() && !out.ends_with('\n') {
+                    out.push('\n');
+                }
+                out.push_str(&"  ".repeat(lists.len().saturating_sub(1)));
+                match lists.last_mut() {
+                    Some(Some(number)) => {
+                        let _ = write!(out, "{number}. ");
+                        *number += 1;
+                    }
+                    _ => out.push_str("- "),
+                }
+            }
+            Event::TaskListMarker(done) => out.push_str(if done { "[x] " } else { "[ ] " }),
+            Event::End(TagEnd::TableCell) => out.push('\t'),
+            Event::End(TagEnd::TableHead | TagEnd::TableRow) => {
+                out.truncate(out.trim_end_matches('\t').len());
+                out.push('\n');
+            }
+            Event::End(
+                TagEnd::Paragraph
+                | TagEnd::Heading(_)
+                | TagEnd::CodeBlock
+                | TagEnd::BlockQuote(_)
+                | TagEnd::Table,
+            )
+            | Event::Rule
+                if lists.is_empty() =>
+            {
+                block_break(&mut out);
+            }
+            _ => {}
+        }
+    }
+    out.trim_end().to_string()
+}
+
+fn block_break(out: &mut String) {
+    out.truncate(out.trim_end().len());
+    if !out.is_empty() {
+        out.push_str("\n\n");
+    }
+}
+
+fn heading_level(level: HeadingLevel) -> u8 {
+    match level {
+        HeadingLevel::H1 => 1,
+        HeadingLevel::H2 => 2,
+        HeadingLevel::H3 => 3,
+        HeadingLevel::H4 => 4,
+        HeadingLevel::H5 => 5,
+        HeadingLevel::H6 => 6,
+    }
+}
+
+fn unique_anchor(text: &str, taken: &mut HashSet<String>) -> String {
+    let mut slug = String::new();
+    for ch in text.chars().flat_map(char::to_lowercase) {
+        if ch.is_alphanumeric() {
+            slug.push(ch);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug = match slug.trim_end_matches('-') {
+        "" => "section".to_string(),
+        trimmed => trimmed.to_string(),
+    };
+    let mut anchor = slug.clone();
+    let mut n = 2;
+    while !taken.insert(anchor.clone()) {
+        anchor = format!("{slug}-{n}");
+        n += 1;
+    }
+    anchor
+}
+
+/// The URL's scheme, if it has one. Browsers ignore ASCII whitespace and
+/// control characters inside a scheme, so those are dropped first.
+fn url_scheme(url: &str) -> Option<String> {
+    let cleaned: String = url
+        .chars()
+        .filter(|c| !c.is_ascii_whitespace() && !c.is_ascii_control())
+        .collect();
+    let (scheme, _) = cleaned.split_once(':')?;
+    let is_scheme = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    // A colon after a path or query separator does not start a scheme.
+    is_scheme.then(|| scheme.to_ascii_lowercase())
+}
+
+fn safe_url(url: CowStr<'_>) -> CowStr<'_> {
+    match url_scheme(&url) {
+        Some(scheme) if !SAFE_SCHEMES.contains(&scheme.as_str()) => CowStr::from(""),
+        _ => url,
+    }
+}
+
+/// Whether browsers would resolve `url` against another host (`//host/...`,
+/// and `/\host/...` since backslashes count as slashes there).
+fn is_protocol_relative(url: &str) -> bool {
+    let mut chars = url
+        .trim_start()
+        .chars()
+        .map(|c| if c == '\\' { '/' } else { c });
+    chars.next() == Some('/') && chars.next() == Some('/')
+}
+
+fn image_url<'a>(url: CowStr<'a>, note_rel_path: &str, route_prefix: &str) -> CowStr<'a> {
+    if is_protocol_relative(&url) {
+        return CowStr::from("");
+    }
+    if url_scheme(&url).is_some() || url.starts_with('/') || url.starts_with('#') {
+        return safe_url(url);
+    }
+    let base = note_rel_path.rsplit_once('/').map_or("", |(dir, _)| dir);
+    match resolve_relative(base, &url) {
+        Some(rel) => CowStr::from(super::media_url(route_prefix, &rel)),
+        None => CowStr::from(""),
+    }
+}
+
+/// Join `relative` onto the workspace directory `base`, or `None` when the
+/// result would climb out of the workspace.
+fn resolve_relative(base: &str, relative: &str) -> Option<String> {
+    let mut parts: Vec<&str> = base.split('/').filter(|part| !part.is_empty()).collect();
+    for part in relative.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop()?;
+            }
+            part => parts.push(part),
+        }
+    }
+    (!parts.is_empty()).then(|| parts.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_text_drops_markup_but_keeps_structure() {
+        let source = "# Today\n\nSome **bold** and `code` with a [link](https://example.com).\n\n\
+                      - one\n- [x] two\n  1. nested\n\n> quoted\n\n---\n\n```\nlet x = 1;\n```\n";
+        assert_eq!(
+            to_plain_text(source),
+            "Today\n\nSome bold and code with a link.\n\n- one\n- [x] two\n  1. nested\n\n\
+             quoted\n\nlet x = 1;"
+        );
+        assert_eq!(to_plain_text("plain words"), "plain words");
+    }
+
+    #[test]
+    fn script_iframe_and_handlers_are_neutralized() {
+        let source = "# Notes\n\n<script>alert('x')</script>\n\n\
+                      Hi <img src=x onerror=\"alert(1)\"> there\n\n\
+                      <iframe src=\"https://evil.example\"></iframe>\n\n\
+                      [click](javascript:alert(1)) [also](JaVaScRiPt:alert(2)) \
+                      <javascript:alert(3)>\n\n\
+                      ![pixel](data:text/html;base64,PHNjcmlwdD4=)";
+        let html = render_html(source, "journals/text/note.md", "");
+        let lower = html.to_ascii_lowercase();
+
+        assert!(!html.contains("<script"), "{html}");
+        assert!(!html.contains("<iframe"), "{html}");
+        assert!(!html.contains("<img src=\"x\""), "{html}");
+        assert!(html.contains("&lt;script&gt;"), "{html}");
+        assert!(!lower.contains("href=\"javascript"), "{html}");
+        assert!(!lower.contains("onerror=\""), "{html}");
+        assert!(!html.contains("data:text/html"), "{html}");
+        assert!(html.contains("<a href=\"\">click</a>"), "{html}");
+    }
+
+    #[test]
+    fn relative_images_resolve_to_media_urls_inside_the_workspace() {
+        let source = "![a](../media/photo.png) ![b](./c.jpg) \
+                      ![out](../../../../secret.png) ![web](https://example.com/x.png) \
+                      [site](https://example.com)";
+        let html = render_html(source, "journals/text/2026/note.md", "");
+
+        assert!(
+            html.contains("src=\"/api/media/journals/text/media/photo.png\""),
+            "{html}"
+        );
+        assert!(
+            html.contains("src=\"/api/media/journals/text/2026/c.jpg\""),
+            "{html}"
+        );
+        assert!(html.contains("src=\"\" alt=\"out\""), "{html}");
+        let remote = render_html(
+            "![p](//evil.example/x.png) ![q](/\\evil.example/y.png)",
+            "journals/text/note.md",
+            "",
+        );
+        assert!(!remote.contains("evil.example"), "{remote}");
+        assert!(html.contains("src=\"https://example.com/x.png\""), "{html}");
+        assert!(html.contains("href=\"https://example.com\""), "{html}");
+
+        let prefixed = render_html(source, "journals/text/2026/note.md", "/slowclaw");
+        assert!(
+            prefixed.contains("src=\"/slowclaw/api/media/journals/text/2026/c.jpg\""),
+            "{prefixed}"
+        );
+    }
+
+    #[test]
+    fn summary_counts_words_and_outlines_headings_with_unique_anchors() {
+        let source = "# Daily note\n\nOne two `three`.\n\n## 09:30\n\nfour **five**\n\n## 09:30\n";
+        let summary = summarize(source);
+
+        assert_eq!(summary.word_count, 9);
+        let anchors: Vec<&str> = summary.outline.iter().map(|h| h.anchor.as_str()).collect();
+        assert_eq!(anchors, ["daily-note", "09-30", "09-30-2"]);
+        assert_eq!(summary.outline[1].level, 2);
+        assert_eq!(summary.outline[1].text, "09:30");
+
+        let html = render_html(source, "journals/text/daily.md", "");
+        assert!(html.contains("<h2 id=\"09-30-2\">09:30</h2>"), "{html}");
+    }
+}
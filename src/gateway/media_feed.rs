@@ -0,0 +1,394 @@
+//! The mobile feed: processed journal media and posts as one newest-first
+//! list.
+//!
+//! Walking `journals/processed/` and `posts/` is the slow part, so the file
+//! list is cached per workspace until the library watcher reports a change
+//! (or the cache ages out). Titles, processing status, and where a file was
+//! posted come from the local store on every request, so they are never
+//! staler than the store itself.
+
+use super::{local_store, media_url, workspace_relative_display_path};
+use crate::util::truncate_with_ellipsis;
+use anyhow::Result;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+pub const PROCESSED_DIR: &str = "journals/processed";
+pub const POSTS_DIR: &str = "posts";
+const CAPTION_SUFFIX: &str = ".caption.txt";
+const CAPTION_CHARS: usize = 240;
+/// Backstop for changes the watcher cannot see (it may be disabled).
+const CACHE_TTL: Duration = Duration::from_secs(300);
+/// Posts still waiting for review, and pipeline working files.
+const SKIPPED_POST_DIRS: [&str; 3] = ["/drafts/", "/pipeline/", "/artifacts/"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FeedFile {
+    path: String,
+    origin: &'static str,
+    kind: &'static str,
+    modified_at: i64,
+    size_bytes: u64,
+    caption: Option<String>,
+}
+
+/// Position after the last item of a page: newest first, then by path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeedCursor {
+    modified_at: i64,
+    path: String,
+}
+
+impl FeedCursor {
+    /// Parse the `nextCursor` of a previous page.
+    pub fn parse(raw: &str) -> Option<Self> {
+        let (modified_at, path) = raw.trim().split_once(':')?;
+        Some(Self {
+            modified_at: modified_at.parse().ok()?,
+            path: path.to_string(),
+        })
+    }
+
+    fn of(file: &FeedFile) -> Self {
+        Self {
+            modified_at: file.modified_at,
+            path: file.path.clone(),
+        }
+    }
+
+    fn encode(&self) -> String {
+        format!("{}:{}", self.modified_at, self.path)
+    }
+
+    fn is_before(&self, file: &FeedFile) -> bool {
+        (std::cmp::Reverse(self.modified_at), self.path.as_str())
+            < (std::cmp::Reverse(file.modified_at), file.path.as_str())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeedPage {
+    pub items: Vec<serde_json::Value>,
+    pub next_cursor: Option<String>,
+}
+
+/// Scanned feed files per workspace, with the time they were scanned.
+type FeedFileCache = HashMap<PathBuf, (Instant, Arc<Vec<FeedFile>>)>;
+
+fn cache() -> &'static Mutex<FeedFileCache> {
+    static CACHE: OnceLock<Mutex<FeedFileCache>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Forget the cached file list after something under the feed roots changed.
+pub fn invalidate(workspace_dir: &Path) {
+    cache().lock().remove(workspace_dir);
+}
+
+fn feed_files(workspace_dir: &Path) -> Arc<Vec<FeedFile>> {
+    if let Some((loaded_at, files)) = cache().lock().get(workspace_dir) {
+        if loaded_at.elapsed() < CACHE_TTL {
+            return Arc::clone(files);
+        }
+    }
+    let files = Arc::new(scan(workspace_dir));
+    cache().lock().insert(
+        workspace_dir.to_path_buf(),
+        (Instant::now(), Arc::clone(&files)),
+    );
+    files
+}
+
+fn media_kind(rel_lower: &str) -> Option<&'static str> {
+    match Path::new(rel_lower).extension()?.to_str()? {
+        "mp3" | "wav" | "m4a" | "aac" | "flac" => Some("audio"),
+        "mp4" | "mov" | "webm" | "mkv" => Some("video"),
+        "jpg" | "jpeg" | "png" | "webp" => Some("image"),
+        _ => None,
+    }
+}
+
+/// Kind of the feed item at `rel_path`: media from both roots, plus Markdown
+/// and text posts. Sidecars, hidden files, drafts, and pipeline output are
+/// not items.
+fn item_kind(rel_path: &str) -> Option<(&'static str, &'static str)> {
+    if rel_path.split('/').any(|part| part.starts_with('.')) {
+        return None;
+    }
+    let lower = rel_path.to_ascii_lowercase();
+    if lower.ends_with(CAPTION_SUFFIX) {
+        return None;
+    }
+    if lower.starts_with(&format!("{PROCESSED_DIR}/")) {
+        return media_kind(&lower).map(|kind| ("processed", kind));
+    }
+    let in_posts = format!("/{lower}");
+    if SKIPPED_POST_DIRS.iter().any(|dir| in_posts.contains(dir)) {
+        return None;
+    }
+    let kind = media_kind(&lower)
+        .or_else(|| (lower.ends_with(".md") || lower.ends_with(".txt")).then_some("text"))?;
+    Some(("post", kind))
+}
+
+fn caption(abs_path: &Path, kind: &str) -> Option<String> {
+    let text = if kind == "text" {
+        std::fs::read_to_string(abs_path).ok()?
+    } else {
+        let mut sidecar = abs_path.as_os_str().to_owned();
+        sidecar.push(CAPTION_SUFFIX);
+        std::fs::read_to_string(sidecar).ok()?
+    };
+    let text = text.trim();
+    (!text.is_empty()).then(|| truncate_with_ellipsis(text, CAPTION_CHARS))
+}
+
+fn collect(workspace_dir: &Path, dir: &Path, out: &mut Vec<FeedFile>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(meta) = entry.metadata() else {
+            continue;
+        };
+        if meta.is_dir() {
+            collect(workspace_dir, &path, out);
+            continue;
+        }
+        let Ok(rel) = path.strip_prefix(workspace_dir) else {
+            continue;
+        };
+        let rel = workspace_relative_display_path(rel);
+        let Some((origin, kind)) = meta.is_file().then(|| item_kind(&rel)).flatten() else {
+            continue;
+        };
+        let modified_at = meta
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |age| i64::try_from(age.as_secs()).unwrap_or(0));
+        out.push(FeedFile {
+            caption: caption(&path, kind),
+            path: rel,
+            origin,
+            kind,
+            modified_at,
+            size_bytes: meta.len(),
+        });
+    }
+}
+
+fn scan(workspace_dir: &Path) -> Vec<FeedFile> {
+    let mut files = Vec::new();
+    for root in [PROCESSED_DIR, POSTS_DIR] {
+        collect(workspace_dir, &workspace_dir.join(root), &mut files);
+    }
+    files.sort_by(|a, b| {
+        b.modified_at
+            .cmp(&a.modified_at)
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    files
+}
+
+fn title_from_path(rel_path: &str) -> String {
+    Path::new(rel_path)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("untitled")
+        .replace(['_', '-'], " ")
+}
+
+/// One page of at most `limit` items after `cursor`, with media URLs under
+/// `route_prefix`. External post URIs are included only when
+/// `bluesky_enabled`, since nothing else can open them.
+pub fn page(
+    workspace_dir: &Path,
+    route_prefix: &str,
+    cursor: Option<&FeedCursor>,
+    limit: usize,
+    bluesky_enabled: bool,
+) -> Result<FeedPage> {
+    let files = feed_files(workspace_dir);
+    let mut window: Vec<&FeedFile> = files
+        .iter()
+        .filter(|file| cursor.map_or(true, |cursor| cursor.is_before(file)))
+        .take(limit + 1)
+        .collect();
+    let has_more = window.len() > limit;
+    window.truncate(limit);
+    let next_cursor = has_more
+        .then(|| window.last().map(|file| FeedCursor::of(file).encode()))
+        .flatten();
+
+    let paths: Vec<String> = window.iter().map(|file| file.path.clone()).collect();
+    let details = local_store::library_item_details(workspace_dir, &paths).unwrap_or_else(|err| {
+        tracing::debug!("Feed metadata unavailable: {err:#}");
+        HashMap::new()
+    });
+    let posts = local_store::latest_posts_by_source(workspace_dir, &paths).unwrap_or_else(|err| {
+        tracing::debug!("Feed post history unavailable: {err:#}");
+        HashMap::new()
+    });
+
+    let items = window
+        .into_iter()
+        .map(|file| {
+            let detail = details.get(&file.path);
+            let post = posts.get(&file.path);
+            let title = detail
+                .map(|detail| detail.title.trim())
+                .filter(|title| !title.is_empty())
+                .map_or_else(|| title_from_path(&file.path), str::to_string);
+            let external_uri = post
+                .filter(|post| bluesky_enabled && post.provider == "bluesky")
+                .map(|post| post.uri.trim())
+                .filter(|uri| !uri.is_empty());
+            serde_json::json!({
+                "id": file.path,
+                "path": file.path,
+                "origin": file.origin,
+                "kind": file.kind,
+                "title": title,
+                "status": detail.map(|detail| detail.status.clone()),
+                "caption": file.caption,
+                "mediaUrl": (file.kind != "text").then(|| media_url(route_prefix, &file.path)),
+                "modifiedAt": file.modified_at,
+                "sizeBytes": file.size_bytes,
+                "publishedAt": post.map(|post| post.published_at.clone()),
+                "targetChannel": post.map(|post| post.provider.clone()),
+                "externalUri": external_uri,
+            })
+        })
+        .collect();
+    Ok(FeedPage { items, next_cursor })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{self, File, FileTimes};
+    use std::time::SystemTime;
+
+    fn write(root: &Path, rel: &str, body: &str, age_secs: u64) {
+        let path = root.join(rel);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, body).unwrap();
+        let modified = SystemTime::now() - Duration::from_secs(age_secs);
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_times(FileTimes::new().set_modified(modified))
+            .unwrap();
+    }
+
+    fn paths(page: &FeedPage) -> Vec<&str> {
+        page.items
+            .iter()
+            .map(|item| item["path"].as_str().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn merges_processed_media_and_posts_newest_first_across_pages() {
+        let tmp = tempfile::tempdir().unwrap();
+        let ws = tmp.path();
+        local_store::initialize(ws).unwrap();
+        write(ws, "journals/processed/clip/video.mp4", "v", 10);
+        write(
+            ws,
+            "journals/processed/clip/video.mp4.caption.txt",
+            " At the beach ",
+            10,
+        );
+        write(ws, "journals/processed/clip/plan.md", "not media", 5);
+        write(ws, "posts/week.md", "# Week\n\nQuiet one.", 20);
+        write(ws, "posts/drafts/pending.md", "draft", 1);
+        write(ws, "posts/articles/pipeline/state.md", "pipeline", 1);
+        write(ws, "journals/text/note.md", "journal, not feed", 1);
+
+        let first = page(ws, "", None, 1, false).unwrap();
+        assert_eq!(paths(&first), ["journals/processed/clip/video.mp4"]);
+        let item = &first.items[0];
+        assert_eq!(item["origin"], "processed");
+        assert_eq!(item["caption"], "At the beach");
+        assert_eq!(
+            item["mediaUrl"],
+            "/api/media/journals/processed/clip/video.mp4"
+        );
+        assert_eq!(item["title"], "video");
+
+        let cursor = FeedCursor::parse(first.next_cursor.as_deref().unwrap()).unwrap();
+        let second = page(ws, "", Some(&cursor), 5, false).unwrap();
+        assert_eq!(paths(&second), ["posts/week.md"]);
+        assert_eq!(second.items[0]["caption"], "# Week\n\nQuiet one.");
+        assert!(second.items[0]["mediaUrl"].is_null());
+        assert_eq!(second.next_cursor, None);
+        assert_eq!(FeedCursor::parse("not-a-cursor"), None);
+    }
+
+    #[test]
+    fn metadata_and_published_posts_are_joined_and_watch_changes_invalidate() {
+        let tmp = tempfile::tempdir().unwrap();
+        let ws = tmp.path();
+        local_store::initialize(ws).unwrap();
+        write(ws, "posts/launch.md", "We shipped.", 10);
+        local_store::create_journal_entry_metadata(
+            ws,
+            &local_store::JournalEntryInput {
+                title: "Launch day".into(),
+                entry_type: "text".into(),
+                source: "workflow".into(),
+                status: "processed".into(),
+                workspace_path: "posts/launch.md".into(),
+                preview_text: String::new(),
+                text_body: "We shipped.".into(),
+                tags_csv: String::new(),
+                created_at_client: None,
+            },
+        )
+        .unwrap();
+        local_store::create_post_history(
+            ws,
+            &local_store::PostHistoryInput {
+                provider: "bluesky".into(),
+                text: "We shipped.".into(),
+                video_name: String::new(),
+                source_path: "posts/launch.md".into(),
+                uri: "at://did:plc:abc/app.bsky.feed.post/1".into(),
+                cid: "cid1".into(),
+                status: "success".into(),
+                error: String::new(),
+                created_at_client: Some("2026-10-01T10:00:00Z".into()),
+            },
+        )
+        .unwrap();
+
+        let listed = page(ws, "", None, 10, true).unwrap();
+        let item = &listed.items[0];
+        assert_eq!(item["title"], "Launch day");
+        assert_eq!(item["status"], "processed");
+        assert_eq!(item["publishedAt"], "2026-10-01T10:00:00Z");
+        assert_eq!(item["targetChannel"], "bluesky");
+        assert_eq!(item["externalUri"], "at://did:plc:abc/app.bsky.feed.post/1");
+        let without_bluesky = page(ws, "", None, 10, false).unwrap();
+        assert!(without_bluesky.items[0]["externalUri"].is_null());
+
+        // The cached file list hides a new file until the watcher invalidates.
+        write(ws, "posts/later.md", "Next.", 0);
+        assert_eq!(
+            paths(&page(ws, "", None, 10, true).unwrap()),
+            ["posts/launch.md"]
+        );
+        invalidate(ws);
+        assert_eq!(
+            paths(&page(ws, "", None, 10, true).unwrap()),
+            ["posts/later.md", "posts/launch.md"]
+        );
+    }
+}
@@ -0,0 +1,184 @@
+//! Magic-number sniffing for media uploads.
+//!
+//! Clients label uploads with a `kind` and `Content-Type` that are sometimes
+//! wrong (a voice memo posted as `video/mp4`, a screenshot as
+//! `application/octet-stream`). The first bytes of common audio, video, and
+//! image containers decide where the file is stored instead. Anything not
+//! recognised here keeps the declared classification.
+
+/// Bytes to buffer from the upload stream before sniffing.
+pub const SNIFF_LEN: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sniffed {
+    /// `audio`, `video`, or `image`.
+    pub kind: &'static str,
+    pub mime: &'static str,
+}
+
+const fn sniffed(kind: &'static str, mime: &'static str) -> Option<Sniffed> {
+    Some(Sniffed { kind, mime })
+}
+
+/// Containers that carry audio and video alike; the declared kind picks
+/// between them and video wins otherwise.
+fn av_container(declared_kind: &str, audio: &'static str, video: &'static str) -> Option<Sniffed> {
+    if declared_kind.eq_ignore_ascii_case("audio") {
+        sniffed("audio", audio)
+    } else {
+        sniffed("video", video)
+    }
+}
+
+/// Classify `head` (the start of the file) by magic number, or `None` when
+/// it is not a recognised media container.
+pub fn sniff(head: &[u8], declared_kind: &str) -> Option<Sniffed> {
+    let at = |offset: usize, magic: &[u8]| head.get(offset..offset + magic.len()) == Some(magic);
+
+    if at(0, b"\x89PNG\r\n\x1a\n") {
+        return sniffed("image", "image/png");
+    }
+    if at(0, b"\xff\xd8\xff") {
+        return sniffed("image", "image/jpeg");
+    }
+    if at(0, b"GIF87a") || at(0, b"GIF89a") {
+        return sniffed("image", "image/gif");
+    }
+    if at(0, b"RIFF") {
+        if at(8, b"WEBP") {
+            return sniffed("image", "image/webp");
+        }
+        if at(8, b"WAVE") {
+            return sniffed("audio", "audio/wav");
+        }
+        if at(8, b"AVI ") {
+            return sniffed("video", "video/x-msvideo");
+        }
+        return None;
+    }
+    if at(4, b"ftyp") {
+        let brand = head.get(8..12)?;
+        return match brand {
+            b"M4A " | b"M4B " | b"M4P " => sniffed("audio", "audio/mp4"),
+            b"qt  " => sniffed("video", "video/quicktime"),
+            b"heic" | b"heix" | b"mif1" | b"msf1" => sniffed("image", "image/heic"),
+            b"avif" => sniffed("image", "image/avif"),
+            _ if brand.starts_with(b"3gp") => {
+                av_container(declared_kind, "audio/3gpp", "video/3gpp")
+            }
+            _ => av_container(declared_kind, "audio/mp4", "video/mp4"),
+        };
+    }
+    if at(0, b"\x1a\x45\xdf\xa3") {
+        let webm = head.windows(4).any(|window| window == b"webm");
+        return if webm {
+            av_container(declared_kind, "audio/webm", "video/webm")
+        } else {
+            av_container(declared_kind, "audio/x-matroska", "video/x-matroska")
+        };
+    }
+    if at(0, b"OggS") {
+        return if declared_kind.eq_ignore_ascii_case("video") {
+            sniffed("video", "video/ogg")
+        } else {
+            sniffed("audio", "audio/ogg")
+        };
+    }
+    if at(0, b"fLaC") {
+        return sniffed("audio", "audio/flac");
+    }
+    if at(0, b"ID3") {
+        return sniffed("audio", "audio/mpeg");
+    }
+    if let [0xff, second, ..] = head {
+        // ADTS AAC: sync word plus layer 00.
+        if second & 0xf6 == 0xf0 {
+            return sniffed("audio", "audio/aac");
+        }
+        // MPEG audio frame: sync word plus a non-reserved layer.
+        if second & 0xe0 == 0xe0 && second & 0x06 != 0 {
+            return sniffed("audio", "audio/mpeg");
+        }
+    }
+    None
+}
+
+/// Whether the client's `kind` or media `Content-Type` disagrees with what
+/// was sniffed. A generic `file` kind or non-media content type is not a
+/// claim, so correcting it is never a mismatch.
+pub fn contradicts(declared_kind: &str, declared_content_type: &str, sniffed: Sniffed) -> bool {
+    let is_media_kind = |kind: &str| matches!(kind, "audio" | "video" | "image");
+    let declared_kind = declared_kind.trim().to_ascii_lowercase();
+    if is_media_kind(&declared_kind) && declared_kind != sniffed.kind {
+        return true;
+    }
+    let family = declared_content_type
+        .split('/')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    is_media_kind(&family) && family != sniffed.kind
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const M4A: &[u8] = b"\x00\x00\x00\x20ftypM4A \x00\x00\x00\x00M4A mp42isom";
+    const MP4: &[u8] = b"\x00\x00\x00\x18ftypmp42\x00\x00\x00\x00mp42isom";
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\x00\x00\x00\x0dIHDR";
+
+    #[test]
+    fn sniffs_common_containers() {
+        assert_eq!(sniff(M4A, "video"), sniffed("audio", "audio/mp4"));
+        assert_eq!(sniff(MP4, "video"), sniffed("video", "video/mp4"));
+        assert_eq!(sniff(PNG, "file"), sniffed("image", "image/png"));
+        assert_eq!(
+            sniff(b"\xff\xd8\xff\xe0\x00\x10JFIF", ""),
+            sniffed("image", "image/jpeg")
+        );
+        assert_eq!(
+            sniff(b"RIFF\x24\x00\x00\x00WAVEfmt ", ""),
+            sniffed("audio", "audio/wav")
+        );
+        assert_eq!(sniff(b"ID3\x04\x00", ""), sniffed("audio", "audio/mpeg"));
+        assert_eq!(
+            sniff(b"\xff\xfb\x90\x64", ""),
+            sniffed("audio", "audio/mpeg")
+        );
+        assert_eq!(
+            sniff(b"\xff\xf1\x50\x80", ""),
+            sniffed("audio", "audio/aac")
+        );
+    }
+
+    #[test]
+    fn ambiguous_containers_follow_the_declared_kind() {
+        assert_eq!(sniff(MP4, "audio"), sniffed("audio", "audio/mp4"));
+        let webm = b"\x1a\x45\xdf\xa3\x9f\x42\x86\x81\x01\x42\x82\x84webm";
+        assert_eq!(sniff(webm, "audio"), sniffed("audio", "audio/webm"));
+        assert_eq!(sniff(webm, "file"), sniffed("video", "video/webm"));
+        assert_eq!(sniff(b"OggS\x00\x02", ""), sniffed("audio", "audio/ogg"));
+    }
+
+    #[test]
+    fn text_and_truncated_input_are_not_sniffed() {
+        assert_eq!(sniff(b"# Journal\n\nhello", "file"), None);
+        assert_eq!(sniff(b"%PDF-1.7", "file"), None);
+        assert_eq!(sniff(b"\x00\x00\x00\x20ftyp", "audio"), None);
+        assert_eq!(sniff(b"RIFF\x00\x00", "audio"), None);
+        assert_eq!(sniff(b"", "audio"), None);
+    }
+
+    #[test]
+    fn mismatch_needs_a_media_claim() {
+        let audio = sniff(M4A, "video").unwrap();
+        assert!(contradicts("video", "video/mp4", audio));
+        assert!(contradicts("", "video/mp4", audio));
+        assert!(contradicts("audio", "image/png", audio));
+        assert!(!contradicts("audio", "audio/x-m4a", audio));
+        assert!(!contradicts("audio", "application/octet-stream", audio));
+        assert!(!contradicts("file", "application/octet-stream", audio));
+    }
+}
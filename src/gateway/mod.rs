@@ -7,22 +7,55 @@
 //! - Request timeouts (30s) to prevent slow-loris attacks
 //! - Header sanitization (handled by axum/hyper)
 
+pub mod api_keys;
 pub mod article_synthesizer;
+pub mod auto_process;
+pub mod builder;
+pub mod chat_commands;
+pub mod client;
+pub mod search_index;
 pub mod static_files;
+pub mod storage;
+pub mod text_versions;
+#[cfg(test)]
+pub(crate) mod test_support;
+pub mod thumbnails;
+pub mod trash;
 pub mod local_store;
 pub mod feed_web_sources;
+pub mod file_names;
+pub mod journal_digest;
+pub mod journal_templates;
+pub mod library_export;
+pub mod library_tags;
+pub mod library_watch;
+pub mod listener;
+pub mod markdown_render;
+pub mod media_feed;
+pub mod media_sniff;
+pub mod workspace_git;
 pub mod workspace_synthesizer;
 
 use crate::auth::AuthService;
+pub use builder::GatewayBuilder;
+pub use listener::{GatewayListener, GatewayPeer};
+use crate::channels::traits::{chunk_message, compose_prompt, ChannelAttachment, ChannelMessage};
 use crate::config::{Config, TranscriptionConfig};
+use crate::cost::guard::{budget_exceeded, with_budget_override, BudgetExceeded};
 use crate::gateway::feed_web_sources::DEFAULT_FEED_WEB_SOURCES;
 use crate::media::{command_media_backend, MediaToolCapabilities};
-use crate::memory::{self, Memory, MemoryCategory};
+use crate::memory::{self, Memory, MemoryCategory, MemoryEntry};
 use crate::memory::vector::{bytes_to_vec, cosine_similarity, vec_to_bytes};
+use crate::post_drafts;
 use crate::providers::{self, ChatMessage, Provider};
-use crate::security::pairing::{constant_time_eq, is_public_bind, PairingGuard};
+use crate::security::pairing::{PairingGuard, SecretString};
+use crate::security::trusted_proxies::TrustedProxies;
 use crate::tools::web_search_tool::WebSearchTool;
 use crate::util::truncate_with_ellipsis;
+use crate::workspace::{
+    atomic_write_async, ensure_writable, resolve_contained_path, Location, ResolveError,
+    ResolveMode, WriteError,
+};
 use anyhow::{Context, Result};
 use base64::{engine::general_purpose::STANDARD as BASE64_STANDARD, Engine as _};
 use chrono::{Datelike, Utc};
@@ -33,14 +66,15 @@ use axum::{
         sse::{Event, KeepAlive, Sse},
         IntoResponse, Json,
     },
-    routing::{delete, get, patch, post},
+    routing::{delete, get, patch, post, put},
     Router,
 };
 use http_body_util::BodyExt as _;
 use parking_lot::Mutex;
 use regex::Regex;
-use std::collections::{BTreeSet, HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::convert::Infallible;
+use std::fmt::Write as _;
 use std::net::{IpAddr, SocketAddr};
 use std::path::{Path as StdPath, PathBuf};
 use std::sync::{Arc, OnceLock};
@@ -56,10 +90,12 @@ use tower_http::services::ServeFile;
 use tower_http::timeout::TimeoutLayer;
 use uuid::Uuid;
 
-/// Maximum request body size (64KB) — prevents memory exhaustion
+/// Default `[gateway] max_body_bytes` (64KB) — prevents memory exhaustion
 pub const MAX_BODY_SIZE: usize = 65_536;
-/// Large media uploads for journal audio/video (1 GiB).
+/// Default `[gateway] max_media_upload_bytes` for journal audio/video (1 GiB).
 pub const MAX_MEDIA_UPLOAD_BODY_SIZE: usize = 1_073_741_824;
+/// Library and journal text saves (1 MiB) unless `[gateway] max_text_bytes` says otherwise.
+pub const MAX_TEXT_BODY_SIZE: usize = 1_048_576;
 /// Request timeout (30s) — prevents slow-loris attacks
 pub const REQUEST_TIMEOUT_SECS: u64 = 30;
 /// Workflow template creation timeout (5 min) to allow agent skill authoring.
@@ -82,11 +118,118 @@ const WORKSPACE_SYNTHESIZER_WORKFLOW_KEY: &str = "workspace_synthesizer";
 const WORKSPACE_SYNTH_JOURNAL_SAVE_COOLDOWN_SECS: i64 = 60;
 const WORKSPACE_SYNTH_ERROR_RETRY_DELAY_SECS: i64 = 60 * 60;
 const JOURNAL_INBOX_MAINTENANCE_INTERVAL_SECS: u64 = 60;
+const TRASH_PURGE_INTERVAL_SECS: u64 = 3_600;
+const MEMORY_RETENTION_INTERVAL_SECS: u64 = 21_600;
+const MEMORY_RETENTION_LOG_SAMPLE: usize = 5;
+const RUNTIME_ALERT_INTERVAL_SECS: u64 = 60;
+const STATUS_HEARTBEAT_TOP_ERRORS: usize = 3;
+const OUTBOUND_RETRY_INTERVAL_SECS: u64 = 30;
+/// Longest `system` override accepted by `/webhook`.
+const WEBHOOK_MAX_SYSTEM_CHARS: usize = 32_000;
+/// Largest `max_history` a `/webhook` request may ask for.
+const WEBHOOK_MAX_HISTORY_TURNS: usize = 50;
+/// Prior messages replayed for a webhook session when `max_history` is unset.
+const WEBHOOK_DEFAULT_HISTORY_TURNS: usize = 20;
+/// Character budget for replayed webhook session history.
+const WEBHOOK_HISTORY_MAX_CHARS: usize = 24_000;
+const WEBHOOK_MAX_SESSION_ID_CHARS: usize = 64;
+const WEBHOOK_REPLY_KEY_PREFIX: &str = "webhook_reply_";
+/// Most messages accepted by one `/webhook/batch` call.
+const WEBHOOK_BATCH_MAX_ITEMS: usize = 20;
+/// Batch messages sent to the provider at the same time.
+const WEBHOOK_BATCH_CONCURRENCY: usize = 4;
 const LEGACY_AUDIO_INSIGHT_CLIPS_GOAL: &str =
     "Use my journal notes and available audio/video transcripts to identify practical insights and turn them into concise feed-ready posts, with each post saved as a separate file in posts/.";
 
-fn webhook_memory_key() -> String {
-    format!("webhook_msg_{}", Uuid::new_v4())
+const WEBHOOK_MESSAGE_KEY_PREFIX: &str = "webhook_msg_";
+const CHAT_MESSAGE_KEY_PREFIX: &str = "chat_";
+const CHAT_REPLY_KEY_PREFIX: &str = "chat_reply_";
+
+/// Memory keys for one user message and the reply to it. Both keys carry the
+/// same turn id, after the session or thread id when there is one, so the two
+/// halves of an exchange can be matched up on recall.
+#[derive(Clone, Debug)]
+struct ConversationTurnMemory {
+    user_key: String,
+    reply_key: String,
+    /// Passed to `Memory::store` for backends that scope by session.
+    session: Option<String>,
+}
+
+impl ConversationTurnMemory {
+    fn new(
+        user_prefix: &str,
+        reply_prefix: &str,
+        scope: Option<&str>,
+        session: Option<String>,
+    ) -> Self {
+        let turn = match scope {
+            Some(scope) => format!("{scope}_{}", Uuid::new_v4()),
+            None => Uuid::new_v4().to_string(),
+        };
+        Self {
+            user_key: format!("{user_prefix}{turn}"),
+            reply_key: format!("{reply_prefix}{turn}"),
+            session,
+        }
+    }
+
+    /// `session_id` is the sanitized webhook session, if the request named one.
+    fn webhook(session_id: Option<&str>) -> Self {
+        Self::new(
+            WEBHOOK_MESSAGE_KEY_PREFIX,
+            WEBHOOK_REPLY_KEY_PREFIX,
+            session_id,
+            session_id.map(webhook_memory_session),
+        )
+    }
+
+    fn chat(thread_id: &str) -> Self {
+        Self::new(
+            CHAT_MESSAGE_KEY_PREFIX,
+            CHAT_REPLY_KEY_PREFIX,
+            Some(thread_id),
+            Some(format!("chat:{thread_id}")),
+        )
+    }
+
+    async fn store_user(&self, mem: &dyn Memory, content: &str) {
+        self.store(mem, &self.user_key, content).await;
+    }
+
+    async fn store_reply(&self, mem: &dyn Memory, content: &str) {
+        self.store(mem, &self.reply_key, content).await;
+    }
+
+    async fn store(&self, mem: &dyn Memory, key: &str, content: &str) {
+        if let Err(err) = mem
+            .store(
+                key,
+                content,
+                MemoryCategory::Conversation,
+                self.session.as_deref(),
+            )
+            .await
+        {
+            tracing::warn!("Conversation memory save for {key} failed: {err:#}");
+        }
+    }
+}
+
+/// Keep only `[A-Za-z0-9_.:-]`, capped at `WEBHOOK_MAX_SESSION_ID_CHARS`.
+fn sanitize_webhook_session_id(raw: &str) -> Option<String> {
+    let sanitized: String = raw
+        .trim()
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | ':' | '-'))
+        .take(WEBHOOK_MAX_SESSION_ID_CHARS)
+        .collect();
+    (!sanitized.is_empty()).then_some(sanitized)
+}
+
+/// Memory session id for a webhook conversation, namespaced away from channel sessions.
+fn webhook_memory_session(session_id: &str) -> String {
+    format!("webhook:{session_id}")
 }
 
 fn hash_webhook_secret(value: &str) -> String {
@@ -177,7 +320,13 @@ impl SlidingWindowRateLimiter {
     }
 
     fn allow(&self, key: &str) -> bool {
-        if self.limit_per_window == 0 {
+        self.allow_n(key, 1)
+    }
+
+    /// Record `n` requests at once; all-or-nothing, so a batch that would
+    /// overflow the window is rejected without consuming any budget.
+    fn allow_n(&self, key: &str, n: usize) -> bool {
+        if self.limit_per_window == 0 || n == 0 {
             return true;
         }
 
@@ -212,11 +361,11 @@ impl SlidingWindowRateLimiter {
         let entry = requests.entry(key.to_owned()).or_default();
         entry.retain(|instant| *instant > cutoff);
 
-        if entry.len() >= self.limit_per_window as usize {
+        if entry.len() + n > self.limit_per_window as usize {
             return false;
         }
 
-        entry.push(now);
+        entry.extend(std::iter::repeat_n(now, n));
         true
     }
 }
@@ -243,6 +392,11 @@ impl GatewayRateLimiter {
     fn allow_webhook(&self, key: &str) -> bool {
         self.webhook.allow(key)
     }
+
+    /// Count a batch of `n` webhook messages against the webhook limit.
+    fn allow_webhook_n(&self, key: &str, n: usize) -> bool {
+        self.webhook.allow_n(key, n)
+    }
 }
 
 #[derive(Debug)]
@@ -305,28 +459,45 @@ fn parse_client_ip(value: &str) -> Option<IpAddr> {
     value.parse::<IpAddr>().ok()
 }
 
-fn forwarded_client_ip(headers: &HeaderMap) -> Option<IpAddr> {
-    if let Some(xff) = headers.get("X-Forwarded-For").and_then(|v| v.to_str().ok()) {
-        for candidate in xff.split(',') {
-            if let Some(ip) = parse_client_ip(candidate) {
-                return Some(ip);
-            }
+/// The client address reported by a trusted proxy. `X-Forwarded-For` is
+/// walked from the right, skipping hops that are themselves trusted proxies,
+/// so the first untrusted hop wins and anything a client prepended to the
+/// chain is ignored. If every hop is trusted, the leftmost one is used.
+fn forwarded_client_ip(headers: &HeaderMap, trusted: &TrustedProxies) -> Option<IpAddr> {
+    let hops: Vec<&str> = headers
+        .get_all("X-Forwarded-For")
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|xff| xff.split(','))
+        .collect();
+    let mut client = None;
+    for hop in hops.into_iter().rev() {
+        let Some(ip) = parse_client_ip(hop) else {
+            break;
+        };
+        client = Some(ip);
+        if !trusted.trusts(ip) {
+            break;
         }
     }
 
-    headers
-        .get("X-Real-IP")
-        .and_then(|v| v.to_str().ok())
-        .and_then(parse_client_ip)
+    client.or_else(|| {
+        headers
+            .get("X-Real-IP")
+            .and_then(|v| v.to_str().ok())
+            .and_then(parse_client_ip)
+    })
 }
 
+/// Rate-limit key for a request: the peer address, or the forwarded client
+/// address when the peer is one of `[gateway] trusted_proxies`.
 fn client_key_from_request(
     peer_addr: Option<SocketAddr>,
     headers: &HeaderMap,
-    trust_forwarded_headers: bool,
+    trusted: &TrustedProxies,
 ) -> String {
-    if trust_forwarded_headers {
-        if let Some(ip) = forwarded_client_ip(headers) {
+    if peer_addr.is_some_and(|addr| trusted.trusts(addr.ip())) {
+        if let Some(ip) = forwarded_client_ip(headers, trusted) {
             return ip.to_string();
         }
     }
@@ -336,6 +507,16 @@ fn client_key_from_request(
         .unwrap_or_else(|| "unknown".to_string())
 }
 
+/// Rate-limit key for `peer`. Unix socket clients have no address, so they
+/// are keyed by user id when the OS reports one and share `uds` otherwise.
+fn peer_rate_key(peer: GatewayPeer, headers: &HeaderMap, trusted: &TrustedProxies) -> String {
+    match peer {
+        GatewayPeer::Tcp(addr) => client_key_from_request(Some(addr), headers, trusted),
+        GatewayPeer::Unix { uid: Some(uid) } => format!("uds:uid:{uid}"),
+        GatewayPeer::Unix { uid: None } => "uds".to_string(),
+    }
+}
+
 fn normalize_max_keys(configured: usize, fallback: usize) -> usize {
     if configured == 0 {
         fallback.max(1)
@@ -393,6 +574,7 @@ fn desktop_cors_layer(config: &Config) -> CorsLayer {
             header::IF_NONE_MATCH,
             header::HeaderName::from_static("idempotency-key"),
             header::HeaderName::from_static("last-event-id"),
+            header::HeaderName::from_static("x-budget-override"),
             header::HeaderName::from_static("x-pairing-code"),
             header::HeaderName::from_static("x-webhook-secret"),
         ])
@@ -408,19 +590,29 @@ pub struct AppState {
     pub mem: Arc<dyn Memory>,
     pub auto_save: bool,
     /// SHA-256 hash of `X-Webhook-Secret` (hex-encoded), never plaintext.
-    pub webhook_secret_hash: Option<Arc<str>>,
+    pub webhook_secret_hash: Option<SecretString>,
     pub pairing: Arc<PairingGuard>,
-    pub trust_forwarded_headers: bool,
+    /// Peers whose `X-Forwarded-For` / `X-Real-IP` are believed.
+    pub trusted_proxies: TrustedProxies,
     pub rate_limiter: Arc<GatewayRateLimiter>,
     pub idempotency_store: Arc<IdempotencyStore>,
     /// Observability backend for metrics scraping
     pub observer: Arc<dyn crate::observability::Observer>,
     pub pb_chat_base_url: Option<String>,
     pub pb_chat_collection: String,
-    pub pb_chat_token: Option<String>,
+    pub pb_chat_token: Option<SecretString>,
     journal_transcription_jobs: Arc<Mutex<HashMap<String, JournalTranscriptionJob>>>,
+    /// Background tool jobs, shared with tools through `JobManager::for_workspace`.
+    jobs: Arc<crate::jobs::JobManager>,
     /// In-flight OpenRouter OAuth PKCE session (one at a time).
     openrouter_oauth: Arc<Mutex<Option<OpenRouterOAuthSession>>>,
+    /// Last provider model catalog served by `/api/models`.
+    model_catalog: Arc<Mutex<Option<ModelCatalogCacheEntry>>>,
+    /// In-flight chat worker runs keyed by user message record id.
+    chat_runs: Arc<Mutex<HashMap<String, InFlightChatRun>>>,
+    /// Public URL of the active tunnel. Replaced whenever the tunnel
+    /// restarts, so readers must not cache it.
+    tunnel_url: Arc<Mutex<Option<String>>>,
 }
 
 #[derive(Clone, Debug)]
@@ -454,6 +646,18 @@ struct TranscriptionModelCacheEntry {
     cached_at: Instant,
 }
 
+#[derive(Clone, Debug)]
+struct InFlightChatRun {
+    thread_id: String,
+    token: tokio_util::sync::CancellationToken,
+}
+
+#[derive(Clone, Debug)]
+struct ModelCatalogCacheEntry {
+    models: Vec<crate::providers::ModelInfo>,
+    cached_at: Instant,
+}
+
 static TRANSCRIPTION_MODEL_CACHE: OnceLock<Mutex<Option<TranscriptionModelCacheEntry>>> =
     OnceLock::new();
 
@@ -471,199 +675,145 @@ impl JournalTranscriptionJob {
 }
 
 /// Run the HTTP gateway using axum with proper HTTP/1.1 compliance.
-#[allow(clippy::too_many_lines)]
 pub async fn run_gateway(host: &str, port: u16, config: Config) -> Result<()> {
-    // Ensure the rustls CryptoProvider is installed. When the gateway is run
-    // as a library call (e.g. from Tauri) rather than via main(), the provider
-    // may not have been set up yet. Without this, any HTTPS request (RSS feeds,
-    // Bluesky API, Nostr relays, embedding providers) will panic.
-    let _ = rustls::crypto::ring::default_provider().install_default();
-
-    // ── Security: refuse public bind without explicit opt-in ──
-    if is_public_bind(host) && !config.gateway.allow_public_bind {
-        anyhow::bail!(
-            "🛑 Refusing to bind to {host} — gateway would be exposed to the internet.\n\
-             Fix: use --host 127.0.0.1 (default) or set\n\
-             [gateway] allow_public_bind = true in config.toml (NOT recommended)."
-        );
-    }
-    let config_state = Arc::new(Mutex::new(config.clone()));
-
-    if config.memory.embedding_provider.trim().eq_ignore_ascii_case("builtin") {
-        let provider = config.memory.embedding_provider.clone();
-        let model = config.memory.embedding_model.clone();
-        tokio::spawn(async move {
-            if let Err(err) =
-                memory::embeddings::prewarm_builtin_embedding_assets(&provider, &model).await
-            {
-                tracing::warn!(
-                    error = %err,
-                    "Failed to prewarm local builtin embedding assets"
-                );
-            }
-        });
-    }
+    let builder = GatewayBuilder::new(host, port, config)?;
+    let listener = builder.bind().await?;
+    let actual_port = listener.port()?;
 
-    if let Err(err) = ensure_workflow_bot_creation_skill(&config.workspace_dir) {
-        tracing::warn!("Failed to ensure workflow bot creation skill: {err}");
-    }
-    if let Err(err) = ensure_workspace_journal_drop_folders(&config.workspace_dir) {
-        tracing::warn!("Failed to ensure workspace journal inbox folders: {err}");
-    }
+    let mut gateway = builder.build_state()?;
+    builder.persist_migrated_tokens(&gateway).await;
+    builder.announce(actual_port, &gateway).await;
+    let _sidecars = builder.start_sidecars(&mut gateway);
+    let _tunnel = builder.start_tunnel(&gateway);
+    let app = builder.build_router(&gateway);
 
-    // ── Hooks ──────────────────────────────────────────────────────
-    let hooks: Option<std::sync::Arc<crate::hooks::HookRunner>> = if config.hooks.enabled {
-        Some(std::sync::Arc::new(crate::hooks::HookRunner::new()))
-    } else {
-        None
-    };
+    builder.serve(listener, app, &gateway).await
+}
 
-    let addr: SocketAddr = format!("{host}:{port}").parse()?;
-    let listener = tokio::net::TcpListener::bind(addr).await?;
-    let actual_port = listener.local_addr()?.port();
-    let display_addr = format!("{host}:{actual_port}");
+/// Body-size and timeout limits applied by [`build_router`].
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct RouterLimits {
+    pub body_limit: usize,
+    pub media_body_limit: usize,
+    pub text_body_limit: usize,
+    pub request_timeout: Duration,
+    pub workflow_template_timeout: Duration,
+    pub media_timeout: Duration,
+}
 
-    let local_bootstrap = local_store::initialize(&config.workspace_dir)
-        .context("Failed to initialize local gateway store")?;
-    if local_bootstrap.migrated_from_legacy {
-        println!(
-            "  💾 Local store migration complete from {}",
-            local_bootstrap
-                .legacy_source
-                .as_ref()
-                .map(|p| p.display().to_string())
-                .unwrap_or_else(|| "unknown source".to_string())
-        );
-        println!(
-            "     chat_messages={} drafts={} post_history={} journal_entries={} media_assets={} artifacts={}",
-            local_bootstrap.migrated_chat_messages,
-            local_bootstrap.migrated_drafts,
-            local_bootstrap.migrated_post_history,
-            local_bootstrap.migrated_journal_entries,
-            local_bootstrap.migrated_media_assets,
-            local_bootstrap.migrated_artifacts
-        );
+impl Default for RouterLimits {
+    fn default() -> Self {
+        Self {
+            body_limit: MAX_BODY_SIZE,
+            media_body_limit: MAX_MEDIA_UPLOAD_BODY_SIZE,
+            text_body_limit: MAX_TEXT_BODY_SIZE,
+            request_timeout: Duration::from_secs(REQUEST_TIMEOUT_SECS),
+            workflow_template_timeout: Duration::from_secs(WORKFLOW_TEMPLATE_TIMEOUT_SECS),
+            media_timeout: Duration::from_secs(MEDIA_UPLOAD_TIMEOUT_SECS),
+        }
     }
+}
 
-    let provider: Arc<dyn Provider> = Arc::from(providers::create_resilient_provider_with_options(
-        config.default_provider.as_deref().unwrap_or("openrouter"),
-        config.api_key.as_deref(),
-        config.api_url.as_deref(),
-        &config.reliability,
-        &providers::ProviderRuntimeOptions {
-            auth_profile_override: None,
-            provider_api_url: config.api_url.clone(),
-            zeroclaw_dir: config.config_path.parent().map(std::path::PathBuf::from),
-            secrets_encrypt: config.secrets.encrypt,
-            reasoning_enabled: config.runtime.reasoning_enabled,
-        },
-    )?);
-    let model = config
-        .default_model
-        .clone()
-        .unwrap_or_else(|| "anthropic/claude-sonnet-4".into());
-    let temperature = config.default_temperature;
-    let mem: Arc<dyn Memory> = Arc::from(memory::create_memory_with_storage(
-        &config.memory,
-        Some(&config.storage.provider.config),
-        &config.workspace_dir,
-        config.api_key.as_deref(),
-    )?);
-    // Extract webhook secret for authentication
-    let webhook_secret_hash: Option<Arc<str>> =
-        config.channels_config.webhook.as_ref().and_then(|webhook| {
-            webhook.secret.as_ref().and_then(|raw_secret| {
-                let trimmed_secret = raw_secret.trim();
-                (!trimmed_secret.is_empty())
-                    .then(|| Arc::<str>::from(hash_webhook_secret(trimmed_secret)))
-            })
-        });
-
-    // ── Pairing guard ──────────────────────────────────────
-    let pairing = Arc::new(PairingGuard::new(
-        config.gateway.require_pairing,
-        &config.gateway.paired_tokens,
-    ));
-    let rate_limit_max_keys = normalize_max_keys(
-        config.gateway.rate_limit_max_keys,
-        RATE_LIMIT_MAX_KEYS_DEFAULT,
-    );
-    let rate_limiter = Arc::new(GatewayRateLimiter::new(
-        config.gateway.pair_rate_limit_per_minute,
-        config.gateway.webhook_rate_limit_per_minute,
-        rate_limit_max_keys,
-    ));
-    let idempotency_max_keys = normalize_max_keys(
-        config.gateway.idempotency_max_keys,
-        IDEMPOTENCY_MAX_KEYS_DEFAULT,
-    );
-    let idempotency_store = Arc::new(IdempotencyStore::new(
-        Duration::from_secs(config.gateway.idempotency_ttl_secs.max(1)),
-        idempotency_max_keys,
-    ));
-
-    println!("🦀 SlowClaw Gateway listening on http://{display_addr}");
-    println!("  🌐 Web UI: http://{display_addr}/");
-    println!(
-        "  💾 Local store: {}",
-        local_bootstrap.db_path.display()
-    );
-    println!("  📁 Workspace: {}", config.workspace_dir.display());
-    println!("  POST /pair      — pair a new client (X-Pairing-Code header)");
-    println!("  POST /pair/new-code — mint a fresh one-time pairing code (requires bearer)");
-    println!("  POST /webhook   — {{\"message\": \"your prompt\"}}");
-    println!("  GET  /health    — health check");
-    println!("  GET  /metrics   — Prometheus metrics");
-    if let Some(code) = pairing.pairing_code() {
-        println!();
-        println!("  🔐 PAIRING REQUIRED — use this one-time code:");
-        println!("     ┌──────────────┐");
-        println!("     │  {code}  │");
-        println!("     └──────────────┘");
-        println!("     Send: POST /pair with header X-Pairing-Code: {code}");
-    } else if pairing.require_pairing() {
-        println!("  🔒 Pairing: ACTIVE (bearer token required)");
-    } else {
-        println!("  ⚠️  Pairing: DISABLED (all requests accepted)");
+impl RouterLimits {
+    /// Defaults with the limits that `[gateway]` makes configurable.
+    pub(crate) fn for_config(config: &Config) -> Self {
+        Self {
+            body_limit: config.gateway.max_body_bytes,
+            media_body_limit: config.gateway.max_media_upload_bytes,
+            text_body_limit: config.gateway.max_text_bytes,
+            ..Self::default()
+        }
     }
-    println!("  Press Ctrl+C to stop.\n");
+}
 
-    crate::health::mark_component_ok("gateway");
+/// Body limit and timeout of one route group, echoed in the JSON envelope
+/// that [`limit_rejections_as_json`] gives their rejections.
+#[derive(Debug, Clone, Copy)]
+struct RejectionLimits {
+    body_limit: usize,
+    timeout: Duration,
+}
 
-    // Fire gateway start hook
-    if let Some(ref hooks) = hooks {
-        hooks.fire_gateway_start(host, actual_port).await;
+impl RejectionLimits {
+    fn new(body_limit: usize, timeout: Duration) -> Self {
+        Self {
+            body_limit,
+            timeout,
+        }
     }
+}
 
-    let observer: Arc<dyn crate::observability::Observer> =
-        crate::observability::create_observer(&config.observability).into();
+/// `RequestBodyLimitLayer` and `TimeoutLayer` answer with an empty 413/408,
+/// which breaks clients that always parse the body. Rewrap those (and axum's
+/// plain-text body-limit rejection) in the usual error envelope, leaving
+/// handler-made JSON errors alone.
+async fn limit_rejections_as_json(
+    State(limits): State<RejectionLimits>,
+    request: Request,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    let request_id = request
+        .headers()
+        .get("x-request-id")
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+    let response = next.run(request).await;
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json"));
+    if is_json {
+        return response;
+    }
+    let status = response.status();
+    let (code, message, extra) = match status {
+        StatusCode::PAYLOAD_TOO_LARGE => (
+            "PAYLOAD_TOO_LARGE",
+            format!(
+                "Request body exceeds the {} byte limit for this endpoint.",
+                limits.body_limit
+            ),
+            serde_json::json!({
+                "limit_bytes": limits.body_limit,
+                "request_id": request_id,
+            }),
+        ),
+        StatusCode::REQUEST_TIMEOUT => (
+            "REQUEST_TIMEOUT",
+            format!(
+                "Request did not complete within {}s.",
+                limits.timeout.as_secs()
+            ),
+            serde_json::json!({
+                "timeout_secs": limits.timeout.as_secs(),
+                "request_id": request_id,
+            }),
+        ),
+        _ => return response,
+    };
+    frontend_error_response_with_meta(status, code, message, extra).into_response()
+}
 
-    let state = AppState {
-        config: config_state,
-        provider,
-        model,
-        temperature,
-        mem,
-        auto_save: config.memory.auto_save,
-        webhook_secret_hash,
-        pairing,
-        trust_forwarded_headers: config.gateway.trust_forwarded_headers,
-        rate_limiter,
-        idempotency_store,
-        observer,
-        pb_chat_base_url: None,
-        pb_chat_collection: "chat_messages".to_string(),
-        pb_chat_token: None,
-        journal_transcription_jobs: Arc::new(Mutex::new(HashMap::new())),
-        openrouter_oauth: Arc::new(Mutex::new(None)),
-    };
-
-    start_journal_inbox_maintenance(state.clone());
+/// The full gateway router: every route, body limit, timeout, static UI
+/// fallback, and CORS layer that `run_gateway` serves, nested under
+/// `[gateway] base_path` when one is set.
+pub fn build_router(state: AppState, config: &Config) -> Router {
+    build_router_with_limits(state, config, RouterLimits::for_config(config))
+}
 
+pub(crate) fn build_router_with_limits(
+    state: AppState,
+    config: &Config,
+    limits: RouterLimits,
+) -> Router {
+    let key_auth_state = state.clone();
     // Core API/UI router (small request bodies)
     let core_router = Router::new()
         .route("/health", get(handle_health))
         .route("/metrics", get(handle_metrics))
+        .route("/api/gateway-info", get(handle_gateway_info))
         .route("/pair", post(handle_pair))
         .route("/pair/new-code", post(handle_pair_new_code))
         .route(
@@ -672,7 +822,15 @@ pub async fn run_gateway(host: &str, port: u16, config: Config) -> Result<()> {
         )
         .route("/api/media/capabilities", get(handle_media_capabilities))
         .route("/webhook", post(handle_webhook))
+        .route("/webhook/batch", post(handle_webhook_batch))
+        .route("/telegram", post(handle_telegram_webhook))
         .route("/api/chat/messages", get(handle_chat_list).post(handle_chat_send))
+        .route("/api/chat/cancel", post(handle_chat_cancel))
+        .route("/api/chat/threads", get(handle_chat_threads))
+        .route(
+            "/api/chat/threads/{id}/settings",
+            put(handle_chat_thread_settings_update),
+        )
         .route("/api/chat/stream", get(handle_chat_stream))
         .route("/api/chat/result/stream", get(handle_chat_result_stream))
         .route(
@@ -724,7 +882,32 @@ pub async fn run_gateway(host: &str, port: u16, config: Config) -> Result<()> {
             patch(handle_workspace_todo_update),
         )
         .route("/api/workspace/events", get(handle_workspace_events_list))
+        .route("/api/jobs", get(handle_jobs_list))
+        .route("/api/activity", get(handle_activity))
+        .route("/api/jobs/{job_id}", get(handle_job_get))
+        .route("/api/security/budget", get(handle_security_budget))
+        .route("/api/usage", get(handle_usage))
+        .route("/api/provider/status", get(handle_provider_status))
+        .route("/api/provider/reset", post(handle_provider_reset))
+        .route(
+            "/api/keys",
+            get(handle_api_keys_list).post(handle_api_key_create),
+        )
+        .route("/api/keys/{key_id}", delete(handle_api_key_revoke))
+        .route("/api/models", get(handle_models))
+        .route(
+            "/api/memory",
+            get(handle_memory_list).delete(handle_memory_delete_session),
+        )
+        .route("/api/memory/stats", get(handle_memory_stats))
+        .route("/api/memory/export", get(handle_memory_export))
+        .route("/api/memory/{key}", delete(handle_memory_forget))
         .route("/api/drafts", get(handle_drafts_list).post(handle_drafts_upsert))
+        .route("/api/drafts/{id}/approve", post(handle_draft_approve))
+        .route("/api/drafts/{id}/reject", post(handle_draft_reject))
+        .route("/api/approvals", get(handle_approvals_list))
+        .route("/api/approvals/{id}/approve", post(handle_approval_approve))
+        .route("/api/approvals/{id}/deny", post(handle_approval_deny))
         .route(
             "/api/post-history",
             get(handle_post_history_list).post(handle_post_history_create),
@@ -742,10 +925,14 @@ pub async fn run_gateway(host: &str, port: u16, config: Config) -> Result<()> {
             get(handle_openrouter_oauth_status),
         )
         .with_state(state.clone())
-        .layer(RequestBodyLimitLayer::new(MAX_BODY_SIZE))
+        .layer(RequestBodyLimitLayer::new(limits.body_limit))
         .layer(TimeoutLayer::with_status_code(
             StatusCode::REQUEST_TIMEOUT,
-            Duration::from_secs(REQUEST_TIMEOUT_SECS),
+            limits.request_timeout,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            RejectionLimits::new(limits.body_limit, limits.request_timeout),
+            limit_rejections_as_json,
         ));
 
     // Content-agent creation can take longer because it invokes the agent to author skills.
@@ -759,16 +946,43 @@ pub async fn run_gateway(host: &str, port: u16, config: Config) -> Result<()> {
             post(handle_feed_workflow_template_create),
         )
         .with_state(state.clone())
-        .layer(RequestBodyLimitLayer::new(MAX_BODY_SIZE))
+        .layer(RequestBodyLimitLayer::new(limits.body_limit))
+        .layer(TimeoutLayer::with_status_code(
+            StatusCode::REQUEST_TIMEOUT,
+            limits.workflow_template_timeout,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            RejectionLimits::new(limits.body_limit, limits.workflow_template_timeout),
+            limit_rejections_as_json,
+        ));
+
+    // Journal/library text writes (bounded by `[gateway] max_text_bytes`)
+    let text_router = Router::new()
+        .route("/api/journal/text", post(handle_journal_text))
+        .route("/api/library/save-text", post(handle_library_save_text))
+        .route("/api/library/versions", get(handle_library_versions))
+        .route(
+            "/api/library/versions/restore",
+            post(handle_library_version_restore),
+        )
+        .route("/api/library/history", get(handle_library_history))
+        .route("/api/library/revert", post(handle_library_revert))
+        .with_state(state.clone())
+        .layer(RequestBodyLimitLayer::new(limits.text_body_limit))
         .layer(TimeoutLayer::with_status_code(
             StatusCode::REQUEST_TIMEOUT,
-            Duration::from_secs(WORKFLOW_TEMPLATE_TIMEOUT_SECS),
+            limits.request_timeout,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            RejectionLimits::new(limits.text_body_limit, limits.request_timeout),
+            limit_rejections_as_json,
         ));
 
     // Journal/media endpoints (large uploads + file streaming)
     let media_router = Router::new()
         .route("/api/media/upload", post(handle_media_upload))
-        .route("/api/journal/text", post(handle_journal_text))
+        .route("/api/storage", get(handle_storage))
+        .route("/api/journal/templates", get(handle_journal_templates))
         .route("/api/journal/transcribe", post(handle_journal_transcribe))
         .route(
             "/api/journal/transcribe/status",
@@ -779,37 +993,243 @@ pub async fn run_gateway(host: &str, port: u16, config: Config) -> Result<()> {
             get(handle_journal_transcribe_stream),
         )
         .route("/api/library/items", get(handle_library_items))
+        .route("/api/feed", get(handle_feed))
+        .route("/api/search", get(handle_search))
         .route("/api/library/text", get(handle_library_text))
-        .route("/api/library/save-text", post(handle_library_save_text))
         .route("/api/library/delete", post(handle_library_delete))
+        .route("/api/library/export", get(handle_library_export))
+        .route("/api/library/trash", get(handle_library_trash))
+        .route("/api/library/trash/restore", post(handle_library_trash_restore))
+        .route("/api/library/tags", post(handle_library_tags))
+        .route("/api/memory/import", post(handle_memory_import))
+        .route("/api/media/thumb/{*path}", get(handle_media_thumb))
         .route("/api/media/{*path}", get(handle_media_stream))
         .with_state(state.clone())
-        .layer(RequestBodyLimitLayer::new(MAX_MEDIA_UPLOAD_BODY_SIZE))
+        .layer(RequestBodyLimitLayer::new(limits.media_body_limit))
         .layer(TimeoutLayer::with_status_code(
             StatusCode::REQUEST_TIMEOUT,
-            Duration::from_secs(MEDIA_UPLOAD_TIMEOUT_SECS),
+            limits.media_timeout,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            RejectionLimits::new(limits.media_body_limit, limits.media_timeout),
+            limit_rejections_as_json,
         ));
 
-    let app = Router::new()
+    // Nextcloud Talk signs its webhooks itself, so the route skips pairing.
+    #[cfg(feature = "channel-nextcloud")]
+    let core_router = core_router.merge(
+        Router::new()
+            .route("/nextcloud-talk", post(handle_nextcloud_talk_webhook))
+            .with_state(state.clone())
+            .layer(RequestBodyLimitLayer::new(limits.body_limit))
+            .layer(TimeoutLayer::with_status_code(
+                StatusCode::REQUEST_TIMEOUT,
+                limits.request_timeout,
+            ))
+            .layer(axum::middleware::from_fn_with_state(
+                RejectionLimits::new(limits.body_limit, limits.request_timeout),
+                limit_rejections_as_json,
+            )),
+    );
+
+    // Meta signs WhatsApp webhooks with the app secret, so the route skips
+    // pairing. Without the feature the path answers 404 rather than 200.
+    #[cfg(feature = "channel-whatsapp")]
+    let core_router = core_router.merge(
+        Router::new()
+            .route(
+                "/whatsapp",
+                get(handle_whatsapp_verify).post(handle_whatsapp_webhook),
+            )
+            .with_state(state.clone())
+            .layer(RequestBodyLimitLayer::new(limits.body_limit))
+            .layer(TimeoutLayer::with_status_code(
+                StatusCode::REQUEST_TIMEOUT,
+                limits.request_timeout,
+            ))
+            .layer(axum::middleware::from_fn_with_state(
+                RejectionLimits::new(limits.body_limit, limits.request_timeout),
+                limit_rejections_as_json,
+            )),
+    );
+    #[cfg(not(feature = "channel-whatsapp"))]
+    let core_router = core_router.route("/whatsapp", axum::routing::any(handle_unknown_route));
+
+    let router = Router::new()
         .merge(core_router)
         .merge(workflow_template_router)
-        .merge(media_router)
-        .route("/_app/{*path}", get(static_files::handle_static))
-        .fallback(get(static_files::handle_spa_fallback))
-        .layer(desktop_cors_layer(&config));
-
-    // Run the server
-    axum::serve(
-        listener,
-        app.into_make_service_with_connect_info::<SocketAddr>(),
+        .merge(text_router)
+        .merge(media_router);
+    let route_prefix = config.gateway.route_prefix();
+    let router = if config.gateway.serve_ui {
+        router.merge(static_files::router(&route_prefix))
+    } else {
+        router.fallback(handle_unknown_route)
+    };
+    let router = router.layer(axum::middleware::from_fn_with_state(
+        key_auth_state,
+        authorize_api_key_requests,
+    ));
+    let router = if config.gateway.read_only {
+        router.layer(axum::middleware::from_fn(reject_writes_in_read_only))
+    } else {
+        router
+    };
+    let router = router.layer(desktop_cors_layer(config));
+    if route_prefix.is_empty() {
+        return router;
+    }
+    // Behind a path-prefix proxy, unprefixed paths must not half-work.
+    Router::new()
+        .nest(&route_prefix, router)
+        .fallback(handle_unknown_route)
+}
+
+/// Fallback when `[gateway] serve_ui` is off, so an API-only gateway does
+/// not answer unknown paths with the web UI shell, and for paths outside
+/// `[gateway] base_path`.
+async fn handle_unknown_route() -> axum::response::Response {
+    frontend_error_response(StatusCode::NOT_FOUND, "ROUTE_NOT_FOUND", "No such route")
+        .into_response()
+}
+
+/// Refuses every request that could change state while `[gateway] read_only`
+/// is set. Matching on the method rather than a route list keeps new write
+/// endpoints covered; pairing stays open so a demo client can still connect.
+async fn reject_writes_in_read_only(
+    request: Request,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    let safe_method = matches!(
+        *request.method(),
+        Method::GET | Method::HEAD | Method::OPTIONS
+    );
+    let pairing_route = matches!(request.uri().path(), "/pair" | "/pair/new-code");
+    if safe_method || pairing_route {
+        return next.run(request).await;
+    }
+    frontend_error_response(
+        StatusCode::FORBIDDEN,
+        "READ_ONLY_MODE",
+        "The gateway is in read-only mode; nothing can be changed",
     )
-    .await?;
+    .into_response()
+}
 
-    Ok(())
+/// Requests authenticated with an API key may only reach the routes the key
+/// lists, within its own rate limit. Each use or refusal is audited. Paired
+/// device tokens pass through untouched.
+async fn authorize_api_key_requests(
+    State(state): State<AppState>,
+    request: Request,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    let Some(key) = api_keys::bearer_key(request.headers()) else {
+        return next.run(request).await;
+    };
+    let route = format!("{} {}", request.method(), request.uri().path());
+    let workspace_dir = state.config.lock().workspace_dir.clone();
+    let store = api_keys::store(&workspace_dir);
+    match store.authorize(key, request.method().as_str(), request.uri().path()) {
+        Ok(caller) => {
+            api_keys::audit(&state.config.lock(), Some(&caller), &route, None);
+            next.run(request).await
+        }
+        Err(denied) => {
+            let caller = store.authenticate(key);
+            api_keys::audit(&state.config.lock(), caller.as_ref(), &route, Some(denied));
+            tracing::warn!(route = %route, "API key rejected: {denied:?}");
+            let (status, code, message) = match denied {
+                api_keys::Denied::Unknown => (
+                    StatusCode::UNAUTHORIZED,
+                    "API_KEY_INVALID",
+                    "Unknown or revoked API key",
+                ),
+                api_keys::Denied::Expired => (
+                    StatusCode::UNAUTHORIZED,
+                    "API_KEY_EXPIRED",
+                    "API key has expired",
+                ),
+                api_keys::Denied::RouteNotAllowed => (
+                    StatusCode::FORBIDDEN,
+                    "API_KEY_ROUTE_NOT_ALLOWED",
+                    "This API key may not call this route",
+                ),
+                api_keys::Denied::RateLimited => (
+                    StatusCode::TOO_MANY_REQUESTS,
+                    "API_KEY_RATE_LIMITED",
+                    "Too many requests for this API key. Please retry later.",
+                ),
+            };
+            frontend_error_response(status, code, message).into_response()
+        }
+    }
 }
 
 // ══════════════════════════════════════════════════════════════════════════════
 // AXUM HANDLERS
+/// Prefix of the plain banner line that carries the one-time pairing code.
+pub const PAIRING_CODE_MARKER: &str = "PAIRING-CODE:";
+
+/// Pull the one-time pairing code out of one line of gateway output. The
+/// `PAIRING-CODE:` line wins, then the `X-Pairing-Code:` hint, and only then
+/// a line holding nothing but six digits inside box-drawing decoration.
+pub fn extract_pairing_code(line: &str) -> Option<String> {
+    fn six_digits(candidate: &str) -> Option<String> {
+        let candidate = candidate.trim();
+        (candidate.len() == 6 && candidate.bytes().all(|b| b.is_ascii_digit()))
+            .then(|| candidate.to_string())
+    }
+    fn after<'a>(line: &'a str, prefix: &str) -> Option<&'a str> {
+        let start = line.find(prefix)? + prefix.len();
+        line[start..].split_whitespace().next()
+    }
+
+    if let Some(code) = after(line, PAIRING_CODE_MARKER).and_then(six_digits) {
+        return Some(code);
+    }
+    if let Some(code) = after(line, "X-Pairing-Code:").and_then(six_digits) {
+        return Some(code);
+    }
+    six_digits(line.trim().trim_matches(|c: char| {
+        c.is_whitespace() || c == '|' || ('\u{2500}'..='\u{257F}').contains(&c)
+    }))
+}
+
+/// The one stdout line printed under `gateway.startup_json`, so supervisors
+/// can read the bound port and pairing code without scraping the banner.
+fn startup_json_line(host: &str, port: u16, state: &AppState) -> serde_json::Value {
+    // A Unix socket listener has no URL; clients connect to `socket`.
+    let (url, socket) = {
+        let config = state.config.lock();
+        match &config.gateway.uds_path {
+            Some(path) => (None, Some(path.clone())),
+            None => {
+                let url = format!("http://{host}:{port}{}", config.gateway.route_prefix());
+                (Some(url), None)
+            }
+        }
+    };
+    serde_json::json!({
+        "event": "gateway_listening",
+        "host": host,
+        "port": port,
+        "url": url,
+        "socket": socket,
+        "tunnel_url": public_tunnel_url(state),
+        "pairing_required": state.pairing.require_pairing(),
+        "pairing_code": state.pairing.pairing_code(),
+        "pocketbase_url": crate::channels::PocketBaseChannel::from_env_defaults()
+            .ok()
+            .map(|channel| channel.base_url().to_string()),
+        "read_only": state.config.lock().gateway.read_only,
+        "chat_bridge": {
+            "enabled": state.pb_chat_base_url.is_some(),
+            "collection": state.pb_chat_collection,
+        },
+    })
+}
+
 // ══════════════════════════════════════════════════════════════════════════════
 
 /// GET /health — always public (no secrets leaked)
@@ -819,24 +1239,70 @@ async fn handle_health(State(state): State<AppState>) -> impl IntoResponse {
         "paired": state.pairing.is_paired(),
         "require_pairing": state.pairing.require_pairing(),
         "runtime": crate::health::snapshot_json(),
+        "storage": storage_snapshot(&state).await,
+        "pocketbase_schema": crate::channels::pocketbase_schema::last_report(),
+        "tunnel_url": public_tunnel_url(&state),
+        "read_only": state.config.lock().gateway.read_only,
+        "budget": budget_snapshot(&state),
     });
     Json(body)
 }
 
+/// `[cost]` spend against its limits, or null while cost tracking is off.
+fn budget_snapshot(state: &AppState) -> serde_json::Value {
+    let (cost, workspace_dir) = {
+        let config = state.config.lock();
+        (config.cost.clone(), config.workspace_dir.clone())
+    };
+    match crate::cost::guard::budget_status(&cost, &workspace_dir) {
+        Ok(status) => serde_json::json!(status),
+        Err(err) => {
+            tracing::warn!("Budget status unavailable: {err:#}");
+            serde_json::Value::Null
+        }
+    }
+}
+
+/// The active tunnel's public URL with `[gateway] base_path` appended, since
+/// that is where the routes live (and what the pairing QR hands out).
+fn public_tunnel_url(state: &AppState) -> Option<String> {
+    let route_prefix = state.config.lock().gateway.route_prefix();
+    state
+        .tunnel_url
+        .lock()
+        .as_deref()
+        .map(|url| format!("{}{route_prefix}", url.trim_end_matches('/')))
+}
+
+/// GET /api/gateway-info — how remote clients should reach this gateway.
+/// `tunnel_url` is null unless a tunnel is currently up.
+async fn handle_gateway_info(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> axum::response::Response {
+    if let Some(err) = pairing_auth_error(&state, &headers, "Gateway info") {
+        return err.into_response();
+    }
+    let body = serde_json::json!({
+        "tunnel_url": public_tunnel_url(&state),
+        "base_path": state.config.lock().gateway.route_prefix(),
+        "require_pairing": state.pairing.require_pairing(),
+    });
+    (StatusCode::OK, Json(body)).into_response()
+}
+
 /// Prometheus content type for text exposition format.
 const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
 
 /// GET /metrics — Prometheus text exposition format
 async fn handle_metrics(State(state): State<AppState>) -> impl IntoResponse {
-    let body = if let Some(prom) = state
-        .observer
-        .as_ref()
-        .as_any()
-        .downcast_ref::<crate::observability::PrometheusObserver>()
+    let body = if let Some(prom) = crate::observability::find_observer::<
+        crate::observability::PrometheusObserver,
+    >(state.observer.as_ref())
     {
         prom.encode()
     } else {
-        String::from("# Prometheus backend not enabled. Set [observability] backend = \"prometheus\" in config.\n")
+        String::from("# Prometheus backend not enabled. Set [observability] backend = \"prometheus\" (or add it to backends) in config.\n")
     };
 
     (
@@ -1135,7 +1601,9 @@ async fn handle_openrouter_oauth_start(
 
     let config = state.config.lock().clone();
     let port = config.gateway.port;
-    let callback_url = format!("http://localhost:{port}/api/auth/openrouter/callback");
+    let route_prefix = config.gateway.route_prefix();
+    let callback_url =
+        format!("http://localhost:{port}{route_prefix}/api/auth/openrouter/callback");
 
     let pkce = crate::auth::oauth_common::generate_pkce_state();
     let auth_url = crate::auth::openrouter_oauth::build_authorize_url(&pkce, &callback_url);
@@ -1179,7 +1647,7 @@ async fn handle_openrouter_oauth_callback(
         }
     };
 
-    let client = reqwest::Client::new();
+    let client = crate::http_client::shared();
     match crate::auth::openrouter_oauth::exchange_code_for_key(&client, &code, &session.pkce).await
     {
         Ok(api_key) => {
@@ -1286,11 +1754,10 @@ fn html_escape(s: &str) -> String {
 #[axum::debug_handler]
 async fn handle_pair(
     State(state): State<AppState>,
-    ConnectInfo(peer_addr): ConnectInfo<SocketAddr>,
+    ConnectInfo(peer): ConnectInfo<GatewayPeer>,
     headers: HeaderMap,
 ) -> impl IntoResponse {
-    let rate_key =
-        client_key_from_request(Some(peer_addr), &headers, state.trust_forwarded_headers);
+    let rate_key = peer_rate_key(peer, &headers, &state.trusted_proxies);
     if !state.rate_limiter.allow_pair(&rate_key) {
         tracing::warn!("/pair rate limit exceeded");
         return frontend_error_response_with_retry_after(
@@ -1309,6 +1776,15 @@ async fn handle_pair(
     match state.pairing.try_pair(code, &rate_key).await {
         Ok(Some(token)) => {
             tracing::info!("🔐 New client paired successfully");
+            if state.config.lock().gateway.read_only {
+                let body = serde_json::json!({
+                    "paired": true,
+                    "persisted": false,
+                    "token": token,
+                    "message": "Paired for this process only; read-only mode leaves config.toml untouched.",
+                });
+                return (StatusCode::OK, Json(body));
+            }
             if let Err(err) = persist_pairing_tokens(state.config.clone(), &state.pairing).await {
                 tracing::error!("🔐 Pairing succeeded but token persistence failed: {err:#}");
                 let body = serde_json::json!({
@@ -1355,7 +1831,7 @@ async fn handle_pair_new_code(
     State(state): State<AppState>,
     headers: HeaderMap,
 ) -> impl IntoResponse {
-    if let Some(err) = pairing_auth_error(&state, &headers, "Pair new code") {
+    if let Some(err) = paired_device_auth_error(&state, &headers, "Pair new code") {
         return err;
     }
     if !state.pairing.require_pairing() {
@@ -1449,42 +1925,259 @@ async fn persist_pairing_tokens(config: Arc<Mutex<Config>>, pairing: &PairingGua
     Ok(())
 }
 
-/// Simple chat for webhook endpoint (no tools, for backward compatibility and testing).
-async fn run_gateway_chat_simple(state: &AppState, message: &str) -> anyhow::Result<String> {
-    let user_messages = vec![ChatMessage::user(message)];
-
-    // Keep webhook/gateway prompts aligned with channel behavior by injecting
-    // workspace-aware system context before model invocation.
-    let system_prompt = {
-        let config_guard = state.config.lock();
-        crate::channels::build_system_prompt(
-            &config_guard.workspace_dir,
-            &state.model,
-            &[], // tools - empty for simple chat
-            &[], // skills
-            Some(&config_guard.identity),
-            None, // bootstrap_max_chars - use default
-        )
-    };
+/// Per-request `/webhook` overrides, already checked against the gateway config.
+#[derive(Debug, Clone, Default)]
+struct WebhookChatOverrides {
+    system: Option<String>,
+    model: Option<String>,
+    temperature: Option<f64>,
+    /// Sanitized `session_id`; enables history replay and reply storage.
+    session_id: Option<String>,
+    max_history: usize,
+}
 
-    let mut messages = Vec::with_capacity(1 + user_messages.len());
-    messages.push(ChatMessage::system(system_prompt));
-    messages.extend(user_messages);
+impl WebhookChatOverrides {
+    fn model<'a>(&'a self, state: &'a AppState) -> &'a str {
+        self.model.as_deref().unwrap_or(&state.model)
+    }
+}
 
-    let multimodal_config = state.config.lock().multimodal.clone();
-    let prepared =
-        crate::multimodal::prepare_messages_for_provider(&messages, &multimodal_config).await?;
+/// A rejected webhook override: the offending field and why.
+#[derive(Debug, PartialEq)]
+struct WebhookOverrideError {
+    field: &'static str,
+    message: String,
+}
+
+impl WebhookOverrideError {
+    fn new(field: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            field,
+            message: message.into(),
+        }
+    }
+}
+
+fn validate_webhook_overrides(
+    body: &WebhookBody,
+    default_model: &str,
+    gateway: &crate::config::GatewayConfig,
+) -> Result<WebhookChatOverrides, WebhookOverrideError> {
+    let system = match body.system.as_deref() {
+        None => None,
+        Some(system) if system.trim().is_empty() => {
+            return Err(WebhookOverrideError::new("system", "system must not be empty"));
+        }
+        Some(system) if system.chars().count() > WEBHOOK_MAX_SYSTEM_CHARS => {
+            return Err(WebhookOverrideError::new(
+                "system",
+                format!("system must be at most {WEBHOOK_MAX_SYSTEM_CHARS} characters"),
+            ));
+        }
+        Some(system) => Some(system.to_string()),
+    };
+
+    let model = match body.model.as_deref().map(str::trim) {
+        None => None,
+        Some("") => return Err(WebhookOverrideError::new("model", "model must not be empty")),
+        Some(model) if model == default_model => None,
+        Some(model) if gateway.allowed_models.iter().any(|allowed| allowed == model) => {
+            Some(model.to_string())
+        }
+        Some(model) => {
+            return Err(WebhookOverrideError::new(
+                "model",
+                format!("model `{model}` is not listed in gateway.allowed_models"),
+            ));
+        }
+    };
+
+    let temperature = match body.temperature {
+        None => None,
+        Some(t)
+            if t.is_finite()
+                && t >= gateway.webhook_temperature_min
+                && t <= gateway.webhook_temperature_max =>
+        {
+            Some(t)
+        }
+        Some(_) => {
+            return Err(WebhookOverrideError::new(
+                "temperature",
+                format!(
+                    "temperature must be between {} and {}",
+                    gateway.webhook_temperature_min, gateway.webhook_temperature_max
+                ),
+            ));
+        }
+    };
+
+    if body
+        .max_history
+        .is_some_and(|turns| turns > WEBHOOK_MAX_HISTORY_TURNS)
+    {
+        return Err(WebhookOverrideError::new(
+            "max_history",
+            format!("max_history must be at most {WEBHOOK_MAX_HISTORY_TURNS}"),
+        ));
+    }
+
+    let session_id = match body.session_id.as_deref() {
+        None => None,
+        Some(raw) => Some(sanitize_webhook_session_id(raw).ok_or_else(|| {
+            WebhookOverrideError::new(
+                "session_id",
+                "session_id must contain letters, digits, `_`, `.`, `:`, or `-`",
+            )
+        })?),
+    };
+    if body.reset && session_id.is_none() {
+        return Err(WebhookOverrideError::new(
+            "reset",
+            "reset requires a session_id",
+        ));
+    }
+
+    Ok(WebhookChatOverrides {
+        system,
+        model,
+        temperature,
+        session_id,
+        max_history: body.max_history.unwrap_or(WEBHOOK_DEFAULT_HISTORY_TURNS),
+    })
+}
+
+/// Recent turns of a webhook session, oldest first, bounded by `max_turns`
+/// and `WEBHOOK_HISTORY_MAX_CHARS`.
+async fn load_webhook_session_history(
+    mem: &dyn Memory,
+    session_id: &str,
+    max_turns: usize,
+) -> Vec<ChatMessage> {
+    if max_turns == 0 {
+        return Vec::new();
+    }
+    let session = webhook_memory_session(session_id);
+    let mut entries = match mem
+        .list_all(Some(&MemoryCategory::Conversation), Some(&session))
+        .await
+    {
+        Ok(entries) => entries,
+        Err(err) => {
+            tracing::warn!("Webhook session history unavailable: {err:#}");
+            return Vec::new();
+        }
+    };
+    entries.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+
+    let mut budget = WEBHOOK_HISTORY_MAX_CHARS;
+    let mut history = Vec::new();
+    for entry in entries.into_iter().take(max_turns) {
+        let chars = entry.content.chars().count();
+        if chars > budget {
+            break;
+        }
+        budget -= chars;
+        history.push(if entry.key.starts_with(WEBHOOK_REPLY_KEY_PREFIX) {
+            ChatMessage::assistant(entry.content)
+        } else {
+            ChatMessage::user(entry.content)
+        });
+    }
+    history.reverse();
+    history
+}
+
+/// Forget every stored turn of a webhook session. Returns how many were removed.
+async fn clear_webhook_session(mem: &dyn Memory, session_id: &str) -> usize {
+    let session = webhook_memory_session(session_id);
+    let Ok(entries) = mem.list_all(None, Some(&session)).await else {
+        return 0;
+    };
+    let mut removed = 0;
+    for entry in entries {
+        if matches!(mem.forget(&entry.key).await, Ok(true)) {
+            removed += 1;
+        }
+    }
+    removed
+}
+
+/// Simple chat for webhook endpoint (no tools, for backward compatibility and testing).
+async fn run_gateway_chat_simple(
+    state: &AppState,
+    history: &[ChatMessage],
+    message: &str,
+    overrides: &WebhookChatOverrides,
+) -> anyhow::Result<String> {
+    let mut user_messages = history.to_vec();
+    user_messages.push(ChatMessage::user(message));
+    let model = overrides.model(state);
+
+    // Keep webhook/gateway prompts aligned with channel behavior by injecting
+    // workspace-aware system context before model invocation.
+    let system_prompt = match overrides.system.clone() {
+        Some(system) => system,
+        None => {
+            let config_guard = state.config.lock();
+            crate::channels::build_system_prompt(
+                &config_guard.workspace_dir,
+                model,
+                &[], // tools - empty for simple chat
+                &[], // skills
+                Some(&config_guard.identity),
+                None, // bootstrap_max_chars - use default
+            )
+        }
+    };
+
+    let mut messages = Vec::with_capacity(1 + user_messages.len());
+    messages.push(ChatMessage::system(system_prompt));
+    messages.extend(user_messages);
+
+    let multimodal_config = state.config.lock().multimodal.clone();
+    let prepared =
+        crate::multimodal::prepare_messages_for_provider(&messages, &multimodal_config).await?;
 
     state
         .provider
-        .chat_with_history(&prepared.messages, &state.model, state.temperature)
+        .chat_with_history(
+            &prepared.messages,
+            model,
+            overrides.temperature.unwrap_or(state.temperature),
+        )
         .await
 }
 
 /// Full-featured chat with tools for channel handlers (WhatsApp, Linq, Nextcloud Talk).
-async fn run_gateway_chat_with_tools(state: &AppState, message: &str) -> anyhow::Result<String> {
+async fn run_gateway_chat_with_tools(
+    state: &AppState,
+    message: &ChannelMessage,
+) -> anyhow::Result<String> {
     let config = state.config.lock().clone();
-    crate::agent::process_message(config, message).await
+    let prompt = message.prompt_content(
+        &config.workspace_dir,
+        config.multimodal.allow_remote_fetch,
+    );
+    crate::agent::process_message(config, &prompt).await
+}
+
+/// Message text plus rendered attachments, ready for the agent.
+fn gateway_prompt_with_attachments(
+    state: &AppState,
+    content: &str,
+    attachments: &[ChannelAttachment],
+) -> String {
+    if attachments.is_empty() {
+        return content.to_string();
+    }
+    let config = state.config.lock();
+    compose_prompt(
+        content,
+        attachments,
+        &config.workspace_dir,
+        config.multimodal.allow_remote_fetch,
+    )
 }
 
 fn gateway_ui_tool_profile() -> crate::tools::ToolProfile {
@@ -1503,10 +2196,64 @@ async fn run_gateway_ui_chat_with_tools(
     .await
 }
 
+async fn run_gateway_ui_chat_cancellable(
+    config: Config,
+    message: &str,
+    cancellation_token: tokio_util::sync::CancellationToken,
+) -> anyhow::Result<String> {
+    Box::pin(crate::agent::process_message_with_cancellation(
+        config,
+        message,
+        gateway_ui_tool_profile(),
+        Some(cancellation_token),
+    ))
+    .await
+}
+
+async fn run_gateway_ui_chat_with_tools_traced(
+    config: Config,
+    message: &str,
+    cancellation_token: tokio_util::sync::CancellationToken,
+) -> anyhow::Result<(String, crate::agent::AgentTrace)> {
+    Box::pin(crate::agent::process_message_traced(
+        config,
+        message,
+        gateway_ui_tool_profile(),
+        Some(cancellation_token),
+    ))
+    .await
+}
+
 /// Webhook request body
-#[derive(serde::Deserialize)]
+#[derive(Default, serde::Deserialize)]
 pub struct WebhookBody {
     pub message: String,
+    /// Replaces the workspace system prompt for this request.
+    pub system: Option<String>,
+    /// Must fall within `gateway.webhook_temperature_min..=webhook_temperature_max`.
+    pub temperature: Option<f64>,
+    /// Must be the default model or listed in `gateway.allowed_models`.
+    pub model: Option<String>,
+    /// Upper bound on prior session messages sent with the message.
+    pub max_history: Option<usize>,
+    /// Continue a conversation: prior turns stored under this id are replayed.
+    pub session_id: Option<String>,
+    /// Clear the session's stored turns before handling the message.
+    #[serde(default)]
+    pub reset: bool,
+    /// Return the tool invocations made for this reply under `trace`.
+    #[serde(default)]
+    pub include_trace: bool,
+    /// Images or files sent with the message; images reach the model when
+    /// multimodal input allows it, others are described in text.
+    #[serde(default)]
+    pub attachments: Vec<ChannelAttachment>,
+    /// Skip the provider response cache (`[provider] cache_ttl_secs`).
+    #[serde(default)]
+    pub no_cache: bool,
+    /// With a plain-text response, remove the reply's Markdown formatting.
+    #[serde(default)]
+    pub markdown_strip: bool,
 }
 
 #[derive(serde::Deserialize)]
@@ -1521,6 +2268,52 @@ struct ChatSendBody {
     #[serde(rename = "threadId")]
     thread_id: String,
     content: String,
+    /// Store the agent's tool-call trace on the assistant reply.
+    #[serde(default, rename = "includeTrace")]
+    include_trace: bool,
+    #[serde(default)]
+    attachments: Vec<ChannelAttachment>,
+}
+
+#[derive(serde::Deserialize)]
+struct ChatThreadListQuery {
+    limit: Option<usize>,
+}
+
+/// Replaces a thread's settings; fields left out use the config defaults.
+#[derive(serde::Deserialize)]
+struct ChatThreadSettingsBody {
+    model: Option<String>,
+    temperature: Option<f64>,
+    #[serde(rename = "systemSuffix")]
+    system_suffix: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+struct ChatCancelBody {
+    #[serde(default, rename = "threadId")]
+    thread_id: Option<String>,
+    /// User message record whose run should stop; all runs in the thread otherwise.
+    #[serde(default, rename = "messageId")]
+    message_id: Option<String>,
+}
+
+/// Assistant replies longer than this are stored as numbered parts.
+const CHAT_REPLY_MAX_CHARS: usize = 4_000;
+const CHAT_CANCELLED_REPLY: &str = "Stopped. The request was cancelled.";
+/// `wait=done` timeout when the request sets none, in seconds.
+const CHAT_SEND_WAIT_DEFAULT_SECS: u64 = 20;
+/// Longest `wait=done` may hold a request, kept under the route's
+/// `REQUEST_TIMEOUT_SECS` so the caller gets a 202 instead of a 408.
+const CHAT_SEND_WAIT_MAX_SECS: u64 = REQUEST_TIMEOUT_SECS - 5;
+const CHAT_SEND_WAIT_POLL: Duration = Duration::from_millis(250);
+
+#[derive(Default, serde::Deserialize)]
+struct ChatSendQuery {
+    /// `done` holds the response until the worker finishes or the timeout passes.
+    wait: Option<String>,
+    /// Seconds to wait, capped at `CHAT_SEND_WAIT_MAX_SECS`.
+    timeout: Option<u64>,
 }
 
 #[derive(serde::Deserialize)]
@@ -1534,6 +2327,13 @@ struct ChatResultStreamQuery {
 #[derive(serde::Deserialize)]
 struct DraftListQuery {
     limit: Option<usize>,
+    status: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+struct ApprovalListQuery {
+    limit: Option<usize>,
+    status: Option<String>,
 }
 
 #[derive(serde::Deserialize)]
@@ -2774,7 +3574,7 @@ async fn enqueue_pending_journal_inbox_audio_transcriptions(state: &AppState) ->
     queued
 }
 
-fn start_journal_inbox_maintenance(state: AppState) {
+fn start_journal_inbox_maintenance(state: AppState) -> tokio::task::JoinHandle<()> {
     tokio::spawn(async move {
         let mut interval =
             tokio::time::interval(Duration::from_secs(JOURNAL_INBOX_MAINTENANCE_INTERVAL_SECS));
@@ -2803,105 +3603,484 @@ fn start_journal_inbox_maintenance(state: AppState) {
                 tracing::warn!("Failed to queue workspace synth from inbox maintenance: {err}");
             }
         }
-    });
+    })
 }
 
-fn schedule_workspace_synth_after_journal_save_cooldown(
-    state: AppState,
-    cooldown_until: String,
-) {
+fn start_trash_maintenance(state: AppState) -> tokio::task::JoinHandle<()> {
     tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(TRASH_PURGE_INTERVAL_SECS));
         loop {
-            let wait = workspace_synth_cooldown_wait_duration(&cooldown_until);
-            if !wait.is_zero() {
-                tokio::time::sleep(wait).await;
+            interval.tick().await;
+            let (workspace_dir, retention_days) = {
+                let config = state.config.lock();
+                (
+                    config.workspace_dir.clone(),
+                    config.gateway.trash_retention_days,
+                )
+            };
+            let purged = tokio::task::spawn_blocking(move || {
+                trash::purge_expired(&workspace_dir, retention_days)
+            })
+            .await
+            .unwrap_or(0);
+            if purged > 0 {
+                tracing::info!(purged, "Purged expired library trash entries");
             }
+        }
+    })
+}
 
-            let workspace_dir = state.config.lock().workspace_dir.clone();
-            let status = workspace_synthesizer::load_status(&workspace_dir);
-            if status.journal_save_cooldown_until.trim() != cooldown_until {
-                return;
+/// Reject agent-proposed post drafts left pending longer than
+/// `gateway.post_draft_expiry_hours`. Drafts a previous run left `sending`
+/// are marked `failed` first.
+fn start_post_draft_expiry(state: AppState) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let workspace_dir = state.config.lock().workspace_dir.clone();
+        match tokio::task::spawn_blocking(move || post_drafts::recover_interrupted(&workspace_dir))
+            .await
+        {
+            Ok(Ok(count)) if count > 0 => {
+                tracing::warn!(count, "Marked interrupted post drafts as failed");
             }
-
-            if matches!(status.status.as_str(), "pending" | "processing") {
-                tokio::time::sleep(Duration::from_secs(2)).await;
+            Ok(Err(err)) => tracing::warn!("Post draft recovery failed: {err}"),
+            _ => {}
+        }
+        let mut interval = tokio::time::interval(Duration::from_secs(TRASH_PURGE_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+            let (workspace_dir, expiry_hours) = {
+                let config = state.config.lock();
+                (
+                    config.workspace_dir.clone(),
+                    config.gateway.post_draft_expiry_hours,
+                )
+            };
+            if expiry_hours == 0 {
                 continue;
             }
-
-            let store = match load_or_seed_feed_workflow_settings_store(&workspace_dir) {
-                Ok(store) => store,
-                Err(err) => {
-                    tracing::warn!(
-                        "Failed to load workflow settings while dispatching journal-save cooldown: {err}"
-                    );
-                    clear_workspace_synth_journal_save_cooldown(
-                        &workspace_dir,
-                        Some(&cooldown_until),
-                    );
-                    return;
+            let max_age = chrono::Duration::hours(i64::try_from(expiry_hours).unwrap_or(i64::MAX));
+            let expired = tokio::task::spawn_blocking(move || {
+                post_drafts::expire(&workspace_dir, max_age)
+            })
+            .await;
+            match expired {
+                Ok(Ok(count)) if count > 0 => {
+                    tracing::info!(count, "Expired pending post drafts");
                 }
-            };
-            let Some(record) = store.workflows.get(WORKSPACE_SYNTHESIZER_WORKFLOW_KEY) else {
-                clear_workspace_synth_journal_save_cooldown(&workspace_dir, Some(&cooldown_until));
-                return;
-            };
-            if !record.enabled {
-                clear_workspace_synth_journal_save_cooldown(&workspace_dir, Some(&cooldown_until));
-                return;
+                Ok(Err(err)) => tracing::warn!("Post draft expiry failed: {err}"),
+                _ => {}
             }
+        }
+    })
+}
 
-            let selection = match select_workspace_synth_sources(&workspace_dir, &[], false) {
-                Ok(selection) => selection,
-                Err(err) => {
-                    tracing::warn!(
-                        "Failed to select workspace synth sources after journal-save cooldown: {err}"
-                    );
-                    clear_workspace_synth_journal_save_cooldown(
-                        &workspace_dir,
-                        Some(&cooldown_until),
-                    );
-                    return;
-                }
-            };
-            let latest_source_updated_at = selection
-                .pending
-                .iter()
-                .map(|item| item.modified_at)
-                .max()
-                .unwrap_or(0);
-            if latest_source_updated_at <= 0 || selection.selected.is_empty() {
-                clear_workspace_synth_journal_save_cooldown(&workspace_dir, Some(&cooldown_until));
-                return;
+/// Periodically enforce `[memory]` retention through the memory backend.
+///
+/// The first pass runs at startup. With `retention_dry_run = true` each pass
+/// only logs what would be purged. Skipped when neither
+/// `conversation_ttl_days` nor `max_entries_per_category` is set.
+fn start_memory_retention(state: AppState) -> Option<tokio::task::JoinHandle<()>> {
+    if !memory_retention_settings(&state).0.is_enabled() {
+        return None;
+    }
+    Some(tokio::spawn(async move {
+        let mut interval =
+            tokio::time::interval(Duration::from_secs(MEMORY_RETENTION_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+            let (policy, dry_run) = memory_retention_settings(&state);
+            if !policy.is_enabled() {
+                continue;
             }
-
-            match queue_workspace_synthesizer_run(
-                state.clone(),
-                "journal-save",
-                latest_source_updated_at,
-                selection.pending.len(),
-                selection.selected_word_count,
-                selection.selected,
-            ) {
-                Ok(_) => {
-                    clear_workspace_synth_journal_save_cooldown(
-                        &workspace_dir,
-                        Some(&cooldown_until),
-                    );
-                    return;
-                }
-                Err(err) => {
-                    tracing::warn!(
-                        "Failed to queue workspace synthesis after journal-save cooldown: {err}"
+            if dry_run {
+                log_memory_retention_plan(&state, &policy).await;
+                continue;
+            }
+            match memory::retention::run(state.mem.as_ref(), &policy, Utc::now()).await {
+                Ok(report) if report.total() > 0 => {
+                    state.observer.record_event(
+                        &crate::observability::ObserverEvent::MemoryCompaction {
+                            expired: report.expired,
+                            evicted: report.evicted,
+                        },
                     );
-                    clear_workspace_synth_journal_save_cooldown(
-                        &workspace_dir,
-                        Some(&cooldown_until),
+                    tracing::info!(
+                        expired = report.expired,
+                        evicted = report.evicted,
+                        "Memory retention pass removed entries"
                     );
-                    return;
                 }
+                Ok(_) => {}
+                Err(e) => tracing::warn!("Memory retention pass failed: {e:#}"),
             }
         }
-    });
+    }))
+}
+
+/// Poll health, storage, and PocketBase reachability and feed the results
+/// into the alert webhook. Only state changes produce notifications.
+fn start_runtime_alerts(
+    state: AppState,
+    alerts: crate::observability::alerts::AlertDispatcher,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(RUNTIME_ALERT_INTERVAL_SECS));
+        let mut pocketbase = PocketBaseWatch::default();
+        loop {
+            interval.tick().await;
+            check_component_alerts(&alerts);
+            check_storage_alerts(&state, &alerts).await;
+            check_pocketbase_alert(&state, &alerts, &mut pocketbase).await;
+        }
+    })
+}
+
+fn check_component_alerts(alerts: &crate::observability::alerts::AlertDispatcher) {
+    for (name, component) in crate::health::snapshot().components {
+        let failed = component.status == "error";
+        let summary = if failed {
+            format!(
+                "Component {name} failed: {}",
+                providers::sanitize_api_error(component.last_error.as_deref().unwrap_or("unknown"))
+            )
+        } else {
+            format!("Component {name} recovered")
+        };
+        alerts.report(&format!("component:{name}"), failed, &summary);
+    }
+}
+
+async fn check_storage_alerts(
+    state: &AppState,
+    alerts: &crate::observability::alerts::AlertDispatcher,
+) {
+    let usage = storage_snapshot(state).await;
+    let over_quota = usage.media_quota_bytes > 0 && usage.media_bytes >= usage.media_quota_bytes;
+    alerts.report(
+        "disk_quota",
+        over_quota,
+        &format!(
+            "Media usage {} of {} byte quota",
+            usage.media_bytes, usage.media_quota_bytes
+        ),
+    );
+    let low_disk = usage
+        .available_bytes
+        .is_some_and(|available| available < usage.min_free_bytes);
+    alerts.report(
+        "disk_space",
+        low_disk,
+        &format!(
+            "Free disk space {} bytes (floor {})",
+            usage.available_bytes.unwrap_or(0),
+            usage.min_free_bytes
+        ),
+    );
+}
+
+/// How long PocketBase has been unreachable. Nothing fires until it has
+/// answered at least once, so installs without PocketBase never alert.
+#[derive(Debug, Default)]
+struct PocketBaseWatch {
+    seen_healthy: bool,
+    down_since: Option<Instant>,
+}
+
+impl PocketBaseWatch {
+    /// Record one probe; returns whether the outage has lasted `threshold`.
+    fn observe(&mut self, healthy: bool, now: Instant, threshold: Duration) -> bool {
+        if healthy {
+            self.seen_healthy = true;
+            self.down_since = None;
+            return false;
+        }
+        if !self.seen_healthy {
+            return false;
+        }
+        let since = *self.down_since.get_or_insert(now);
+        now.duration_since(since) >= threshold
+    }
+}
+
+async fn check_pocketbase_alert(
+    state: &AppState,
+    alerts: &crate::observability::alerts::AlertDispatcher,
+    watch: &mut PocketBaseWatch,
+) {
+    let minutes = state
+        .config
+        .lock()
+        .observability
+        .alert_pocketbase_down_minutes;
+    if minutes == 0 {
+        return;
+    }
+    let healthy = match crate::channels::PocketBaseChannel::from_env_defaults() {
+        Ok(channel) => crate::channels::Channel::health_check(&channel).await,
+        Err(_) => return,
+    };
+    let down = watch.observe(
+        healthy,
+        Instant::now(),
+        Duration::from_secs(minutes.saturating_mul(60)),
+    );
+    let summary = if down {
+        format!("PocketBase unreachable for at least {minutes} minutes")
+    } else {
+        "PocketBase is reachable again".to_string()
+    };
+    alerts.report("pocketbase_unreachable", down, &summary);
+}
+
+/// Post a periodic "still alive" summary into a PocketBase chat thread.
+fn start_status_heartbeat(
+    state: AppState,
+    thread_id: String,
+    interval_hours: u64,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let period = Duration::from_secs(interval_hours.max(1).saturating_mul(3_600));
+        let mut interval = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+        loop {
+            interval.tick().await;
+            let Some(stats) = crate::observability::find_observer::<
+                crate::observability::stats::RuntimeStatsObserver,
+            >(state.observer.as_ref()) else {
+                return;
+            };
+            let summary = format_status_heartbeat(&stats.snapshot(STATUS_HEARTBEAT_TOP_ERRORS));
+            let workspace_dir = state.config.lock().workspace_dir.clone();
+            let channel = match crate::channels::PocketBaseChannel::from_env_defaults() {
+                Ok(channel) => channel
+                    .with_outbox(&workspace_dir)
+                    .with_observer(state.observer.clone()),
+                Err(err) => {
+                    tracing::warn!("Status heartbeat skipped: {err}");
+                    continue;
+                }
+            };
+            if !crate::channels::Channel::health_check(&channel).await {
+                tracing::warn!("Status heartbeat skipped: PocketBase is unreachable");
+                continue;
+            }
+            channel.flush_outbox().await;
+            let message = crate::channels::SendMessage::new(summary, thread_id.clone());
+            let dispatcher = crate::channels::OutboundDispatcher::new(&workspace_dir)
+                .with_observer(Arc::clone(&state.observer));
+            if let Err(err) = dispatcher.send(&channel, &message).await {
+                tracing::warn!("Status heartbeat delivery failed: {err}");
+            }
+        }
+    })
+}
+
+/// Resend replies that an [`crate::channels::OutboundDispatcher`] queued after
+/// a failed send. Queues live on disk, so replies from before a restart are
+/// picked up on the first pass.
+fn start_outbound_retry(state: AppState) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(OUTBOUND_RETRY_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+            let workspace_dir = state.config.lock().workspace_dir.clone();
+            let dispatcher = crate::channels::OutboundDispatcher::new(&workspace_dir)
+                .with_observer(Arc::clone(&state.observer));
+            let mut channels: Vec<Box<dyn crate::channels::Channel>> = Vec::new();
+            if let Ok(channel) = crate::channels::PocketBaseChannel::from_env_defaults() {
+                channels.push(Box::new(channel));
+            }
+            if let Some(telegram) = state.config.lock().channels_config.telegram.as_ref() {
+                channels.push(Box::new(crate::channels::TelegramChannel::from_config(
+                    telegram,
+                )));
+            }
+            if let Some(email) = state.config.lock().channels_config.email.as_ref() {
+                if let Ok(channel) = crate::channels::EmailChannel::from_config(email) {
+                    channels.push(Box::new(channel));
+                }
+            }
+            #[cfg(feature = "channel-nextcloud")]
+            if let Some((channel, _)) = nextcloud_talk_channel(&state.config.lock()) {
+                channels.push(Box::new(channel));
+            }
+            #[cfg(feature = "channel-whatsapp")]
+            if let Some((channel, _)) = whatsapp_channel(&state.config.lock()) {
+                channels.push(Box::new(channel));
+            }
+            for channel in &channels {
+                if dispatcher.depth(channel.name()) == 0 {
+                    continue;
+                }
+                let report = dispatcher.retry_due(channel.as_ref()).await;
+                if report.dead > 0 {
+                    tracing::warn!(
+                        channel = channel.name(),
+                        dead = report.dead,
+                        "Queued replies expired undelivered"
+                    );
+                }
+            }
+        }
+    })
+}
+
+fn format_status_heartbeat(stats: &crate::observability::stats::RuntimeStats) -> String {
+    let uptime = stats.uptime_secs;
+    let mut lines = vec![
+        format!(
+            "Status: still alive (gateway up {}d {}h {}m)",
+            uptime / 86_400,
+            uptime % 86_400 / 3_600,
+            uptime % 3_600 / 60
+        ),
+        format!(
+            "- Webhook requests: {}",
+            stats.inbound_messages.get("webhook").copied().unwrap_or(0)
+        ),
+        format!(
+            "- Chat messages: {}",
+            stats.inbound_messages.get("chat").copied().unwrap_or(0)
+        ),
+        format!(
+            "- LLM calls: {} ({} failed)",
+            stats.llm_calls, stats.llm_failures
+        ),
+        format!(
+            "- Tool runs: {} ({} failed)",
+            stats.tool_runs, stats.tool_failures
+        ),
+        format!("- Errors: {}", stats.errors),
+    ];
+    if !stats.top_errors.is_empty() {
+        lines.push("Top errors:".to_string());
+        lines.extend(
+            stats
+                .top_errors
+                .iter()
+                .map(|(message, count)| format!("- {count}x {message}")),
+        );
+    }
+    lines.join("\n")
+}
+
+fn memory_retention_settings(state: &AppState) -> (memory::retention::RetentionPolicy, bool) {
+    let config = state.config.lock();
+    (
+        memory::retention::RetentionPolicy::from_config(&config.memory),
+        config.memory.retention_dry_run,
+    )
+}
+
+async fn log_memory_retention_plan(state: &AppState, policy: &memory::retention::RetentionPolicy) {
+    match memory::retention::plan(state.mem.as_ref(), policy, Utc::now()).await {
+        Ok(plan) => tracing::info!(
+            ttl_days = policy.conversation_ttl_days,
+            max_per_category = policy.max_entries_per_category,
+            would_expire = plan.expired.len(),
+            would_evict = plan.evicted.len(),
+            sample = ?plan.sample_keys(MEMORY_RETENTION_LOG_SAMPLE),
+            "Memory retention dry run"
+        ),
+        Err(e) => tracing::warn!("Memory retention dry run failed: {e:#}"),
+    }
+}
+
+fn schedule_workspace_synth_after_journal_save_cooldown(
+    state: AppState,
+    cooldown_until: String,
+) {
+    tokio::spawn(async move {
+        loop {
+            let wait = workspace_synth_cooldown_wait_duration(&cooldown_until);
+            if !wait.is_zero() {
+                tokio::time::sleep(wait).await;
+            }
+
+            let workspace_dir = state.config.lock().workspace_dir.clone();
+            let status = workspace_synthesizer::load_status(&workspace_dir);
+            if status.journal_save_cooldown_until.trim() != cooldown_until {
+                return;
+            }
+
+            if matches!(status.status.as_str(), "pending" | "processing") {
+                tokio::time::sleep(Duration::from_secs(2)).await;
+                continue;
+            }
+
+            let store = match load_or_seed_feed_workflow_settings_store(&workspace_dir) {
+                Ok(store) => store,
+                Err(err) => {
+                    tracing::warn!(
+                        "Failed to load workflow settings while dispatching journal-save cooldown: {err}"
+                    );
+                    clear_workspace_synth_journal_save_cooldown(
+                        &workspace_dir,
+                        Some(&cooldown_until),
+                    );
+                    return;
+                }
+            };
+            let Some(record) = store.workflows.get(WORKSPACE_SYNTHESIZER_WORKFLOW_KEY) else {
+                clear_workspace_synth_journal_save_cooldown(&workspace_dir, Some(&cooldown_until));
+                return;
+            };
+            if !record.enabled {
+                clear_workspace_synth_journal_save_cooldown(&workspace_dir, Some(&cooldown_until));
+                return;
+            }
+
+            let selection = match select_workspace_synth_sources(&workspace_dir, &[], false) {
+                Ok(selection) => selection,
+                Err(err) => {
+                    tracing::warn!(
+                        "Failed to select workspace synth sources after journal-save cooldown: {err}"
+                    );
+                    clear_workspace_synth_journal_save_cooldown(
+                        &workspace_dir,
+                        Some(&cooldown_until),
+                    );
+                    return;
+                }
+            };
+            let latest_source_updated_at = selection
+                .pending
+                .iter()
+                .map(|item| item.modified_at)
+                .max()
+                .unwrap_or(0);
+            if latest_source_updated_at <= 0 || selection.selected.is_empty() {
+                clear_workspace_synth_journal_save_cooldown(&workspace_dir, Some(&cooldown_until));
+                return;
+            }
+
+            match queue_workspace_synthesizer_run(
+                state.clone(),
+                "journal-save",
+                latest_source_updated_at,
+                selection.pending.len(),
+                selection.selected_word_count,
+                selection.selected,
+            ) {
+                Ok(_) => {
+                    clear_workspace_synth_journal_save_cooldown(
+                        &workspace_dir,
+                        Some(&cooldown_until),
+                    );
+                    return;
+                }
+                Err(err) => {
+                    tracing::warn!(
+                        "Failed to queue workspace synthesis after journal-save cooldown: {err}"
+                    );
+                    clear_workspace_synth_journal_save_cooldown(
+                        &workspace_dir,
+                        Some(&cooldown_until),
+                    );
+                    return;
+                }
+            }
+        }
+    });
 }
 
 fn queue_eligible_content_agents_for_trigger(
@@ -6293,6 +7472,42 @@ fn chat_result_payload(
     }))
 }
 
+/// Poll a sent message until it is `done` or `error`, answering with its
+/// result payload, or with 202 and the latest payload once `timeout` passes.
+async fn wait_for_chat_result(
+    workspace_dir: &StdPath,
+    thread_id: &str,
+    message_id: &str,
+    timeout: Duration,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let deadline = tokio::time::Instant::now() + timeout;
+    loop {
+        let payload = match chat_result_payload(workspace_dir, thread_id, message_id) {
+            Ok(payload) => payload,
+            Err(err) => {
+                return frontend_internal_error(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "chat send wait",
+                    "Failed to load chat result.",
+                    err,
+                );
+            }
+        };
+        let finished = matches!(
+            payload.get("status").and_then(serde_json::Value::as_str),
+            Some("done" | "error")
+        );
+        if finished {
+            return (StatusCode::OK, Json(payload));
+        }
+        let now = tokio::time::Instant::now();
+        if now >= deadline {
+            return (StatusCode::ACCEPTED, Json(payload));
+        }
+        tokio::time::sleep(CHAT_SEND_WAIT_POLL.min(deadline - now)).await;
+    }
+}
+
 async fn handle_chat_list(
     State(state): State<AppState>,
     headers: HeaderMap,
@@ -6322,28 +7537,139 @@ async fn handle_chat_list(
     }
 }
 
-async fn handle_chat_stream(
+/// GET /api/chat/threads — chat threads with their active settings
+async fn handle_chat_threads(
     State(state): State<AppState>,
     headers: HeaderMap,
-    Query(query): Query<ChatListQuery>,
-) -> axum::response::Response {
-    if let Some(err) = pairing_auth_error(&state, &headers, "Chat stream") {
-        return err.into_response();
+    Query(query): Query<ChatThreadListQuery>,
+) -> impl IntoResponse {
+    if let Some(err) = pairing_auth_error(&state, &headers, "Chat API") {
+        return err;
     }
 
-    let thread_id = query
-        .thread_id
-        .as_deref()
-        .map(str::trim)
-        .filter(|value| !value.is_empty())
-        .unwrap_or("default")
-        .to_string();
-    let limit = query.limit.unwrap_or(200).clamp(1, 500);
-    let workspace_dir = state.config.lock().workspace_dir.clone();
-    let (tx, rx) = mpsc::channel(8);
+    let config = state.config.lock().clone();
+    let limit = query.limit.unwrap_or(100).clamp(1, 500);
+    match local_store::list_chat_threads(&config.workspace_dir, limit) {
+        Ok(items) => (
+            StatusCode::OK,
+            Json(serde_json::json!({
+                "items": items,
+                "defaults": {
+                    "model": config.default_model,
+                    "temperature": config.default_temperature,
+                },
+            })),
+        ),
+        Err(err) => frontend_internal_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "chat thread list",
+            "Failed to load chat threads.",
+            err,
+        ),
+    }
+}
 
-    tokio::spawn(async move {
-        let mut last_snapshot = String::new();
+/// PUT /api/chat/threads/{id}/settings — replace a thread's model,
+/// temperature and system prompt suffix
+async fn handle_chat_thread_settings_update(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(thread_id): AxumPath<String>,
+    Json(body): Json<ChatThreadSettingsBody>,
+) -> impl IntoResponse {
+    if let Some(err) = pairing_auth_error(&state, &headers, "Chat API") {
+        return err;
+    }
+    let thread_id = thread_id.trim();
+    if thread_id.is_empty() {
+        return frontend_error_response(
+            StatusCode::BAD_REQUEST,
+            "CHAT_THREAD_ID_REQUIRED",
+            "thread id is required",
+        );
+    }
+
+    let config = state.config.lock().clone();
+    let settings = local_store::ChatThreadSettings {
+        model: body
+            .model
+            .map(|m| m.trim().to_string())
+            .filter(|m| !m.is_empty()),
+        temperature: body.temperature,
+        system_suffix: body
+            .system_suffix
+            .map(|suffix| suffix.trim().to_string())
+            .filter(|suffix| !suffix.is_empty()),
+    };
+    if let Some(model) = settings.model.as_deref() {
+        if !chat_thread_model_allowed(&config, model) {
+            return frontend_error_response(
+                StatusCode::BAD_REQUEST,
+                "CHAT_THREAD_MODEL_NOT_ALLOWED",
+                chat_thread_model_error(&config, model),
+            );
+        }
+    }
+    if let Some(temperature) = settings.temperature {
+        if !chat_thread_temperature_allowed(&config, temperature) {
+            return frontend_error_response(
+                StatusCode::BAD_REQUEST,
+                "CHAT_THREAD_TEMPERATURE_INVALID",
+                chat_thread_temperature_error(&config),
+            );
+        }
+    }
+    if settings
+        .system_suffix
+        .as_deref()
+        .is_some_and(|suffix| suffix.chars().count() > WEBHOOK_MAX_SYSTEM_CHARS)
+    {
+        return frontend_error_response(
+            StatusCode::BAD_REQUEST,
+            "CHAT_THREAD_SUFFIX_TOO_LONG",
+            format!("systemSuffix must be at most {WEBHOOK_MAX_SYSTEM_CHARS} characters"),
+        );
+    }
+
+    match local_store::set_chat_thread_settings(&config.workspace_dir, thread_id, &settings) {
+        Ok(()) => (
+            StatusCode::OK,
+            Json(serde_json::json!({
+                "threadId": thread_id,
+                "settings": settings.to_json(),
+            })),
+        ),
+        Err(err) => frontend_internal_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "chat thread settings update",
+            "Failed to save the thread settings.",
+            err,
+        ),
+    }
+}
+
+async fn handle_chat_stream(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<ChatListQuery>,
+) -> axum::response::Response {
+    if let Some(err) = pairing_auth_error(&state, &headers, "Chat stream") {
+        return err.into_response();
+    }
+
+    let thread_id = query
+        .thread_id
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .unwrap_or("default")
+        .to_string();
+    let limit = query.limit.unwrap_or(200).clamp(1, 500);
+    let workspace_dir = state.config.lock().workspace_dir.clone();
+    let (tx, rx) = mpsc::channel(8);
+
+    tokio::spawn(async move {
+        let mut last_snapshot = String::new();
         loop {
             match chat_messages_payload(&workspace_dir, &thread_id, limit) {
                 Ok(payload) => {
@@ -6433,14 +7759,194 @@ async fn handle_chat_result_stream(
     frontend_event_stream(rx)
 }
 
+/// Trigger cancellation for in-flight runs matching a record id or thread.
+/// Returns the cancelled user message ids.
+fn cancel_chat_runs(
+    state: &AppState,
+    thread_id: Option<&str>,
+    message_id: Option<&str>,
+) -> Vec<String> {
+    let runs = state.chat_runs.lock();
+    let mut cancelled: Vec<String> = runs
+        .iter()
+        .filter(|(id, run)| match message_id {
+            Some(message_id) => id.as_str() == message_id,
+            None => thread_id.is_some_and(|thread_id| run.thread_id == thread_id),
+        })
+        .filter(|(_, run)| !run.token.is_cancelled())
+        .map(|(id, run)| {
+            run.token.cancel();
+            id.clone()
+        })
+        .collect();
+    cancelled.sort();
+    cancelled
+}
+
+/// POST /api/chat/cancel — stop in-flight agent runs for a message or thread
+async fn handle_chat_cancel(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(body): Json<ChatCancelBody>,
+) -> impl IntoResponse {
+    if let Some(err) = pairing_auth_error(&state, &headers, "Chat API") {
+        return err;
+    }
+    let thread_id = body
+        .thread_id
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty());
+    let message_id = body
+        .message_id
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty());
+    if thread_id.is_none() && message_id.is_none() {
+        return frontend_error_response(
+            StatusCode::BAD_REQUEST,
+            "CHAT_CANCEL_INVALID_REQUEST",
+            "threadId or messageId is required",
+        );
+    }
+    let cancelled = cancel_chat_runs(&state, thread_id, message_id);
+    if !cancelled.is_empty() {
+        tracing::info!(count = cancelled.len(), "Chat runs cancelled via gateway");
+    }
+    (
+        StatusCode::OK,
+        Json(serde_json::json!({ "ok": true, "cancelled": cancelled })),
+    )
+}
+
+/// A thread may use the default model or one listed in `gateway.allowed_models`.
+fn chat_thread_model_allowed(config: &Config, model: &str) -> bool {
+    config.default_model.as_deref() == Some(model)
+        || config
+            .gateway
+            .allowed_models
+            .iter()
+            .any(|allowed| allowed == model)
+}
+
+/// Thread temperatures share the webhook override bounds.
+fn chat_thread_temperature_allowed(config: &Config, temperature: f64) -> bool {
+    temperature.is_finite()
+        && temperature >= config.gateway.webhook_temperature_min
+        && temperature <= config.gateway.webhook_temperature_max
+}
+
+fn chat_thread_model_error(config: &Config, model: &str) -> String {
+    if config.gateway.allowed_models.is_empty() {
+        format!("`{model}` is not an allowed model. Add it to gateway.allowed_models to use it in a thread.")
+    } else {
+        format!(
+            "`{model}` is not an allowed model. Choose one of: {}.",
+            config.gateway.allowed_models.join(", ")
+        )
+    }
+}
+
+fn chat_thread_temperature_error(config: &Config) -> String {
+    format!(
+        "Temperature must be a number between {} and {}.",
+        config.gateway.webhook_temperature_min, config.gateway.webhook_temperature_max
+    )
+}
+
+/// Point `config` at the thread's model and temperature. Settings that no
+/// longer validate (the allow-list or bounds changed) are ignored.
+fn apply_chat_thread_settings(config: &mut Config, settings: &local_store::ChatThreadSettings) {
+    if let Some(model) = settings.model.as_deref() {
+        if chat_thread_model_allowed(config, model) {
+            config.default_model = Some(model.to_string());
+        } else {
+            tracing::warn!(
+                model,
+                "Ignoring chat thread model not in gateway.allowed_models"
+            );
+        }
+    }
+    if let Some(temperature) = settings.temperature {
+        if chat_thread_temperature_allowed(config, temperature) {
+            config.default_temperature = temperature;
+        } else {
+            tracing::warn!(temperature, "Ignoring out-of-range chat thread temperature");
+        }
+    }
+}
+
+/// Record a chat command and its reply without running the agent.
+fn handle_chat_command(
+    state: &AppState,
+    thread_id: &str,
+    content: &str,
+    command: chat_commands::ChatCommandMatch<'_>,
+) -> anyhow::Result<serde_json::Value> {
+    let config = state.config.lock().clone();
+    let record = local_store::create_chat_message(
+        &config.workspace_dir,
+        thread_id,
+        "user",
+        content,
+        "done",
+        "gateway-ui",
+        None,
+        None,
+    )?;
+    let reply = match command {
+        chat_commands::ChatCommandMatch::Known(command, args) => {
+            let ctx = chat_commands::ChatCommandContext {
+                state,
+                config: &config,
+                thread_id,
+            };
+            (command.run)(&ctx, args)?
+        }
+        chat_commands::ChatCommandMatch::Unknown(name) => {
+            Some(chat_commands::unknown_command_reply(name))
+        }
+    };
+    if let Some(reply) = reply {
+        local_store::create_chat_message(
+            &config.workspace_dir,
+            thread_id,
+            "assistant",
+            &reply,
+            "done",
+            "slowclaw",
+            record.get("id").and_then(serde_json::Value::as_str),
+            None,
+        )?;
+    }
+    Ok(record)
+}
+
 async fn handle_chat_send(
     State(state): State<AppState>,
     headers: HeaderMap,
+    Query(query): Query<ChatSendQuery>,
     Json(body): Json<ChatSendBody>,
 ) -> impl IntoResponse {
     if let Some(err) = pairing_auth_error(&state, &headers, "Chat API") {
         return err;
     }
+    let wait_timeout = match query.wait.as_deref().map(str::trim) {
+        None | Some("") => None,
+        Some(wait) if wait.eq_ignore_ascii_case("done") => Some(Duration::from_secs(
+            query
+                .timeout
+                .unwrap_or(CHAT_SEND_WAIT_DEFAULT_SECS)
+                .min(CHAT_SEND_WAIT_MAX_SECS),
+        )),
+        Some(_) => {
+            return frontend_error_response(
+                StatusCode::BAD_REQUEST,
+                "CHAT_MESSAGE_INVALID_WAIT",
+                "wait must be `done` when set",
+            );
+        }
+    };
 
     let thread_id = body.thread_id.trim();
     let content = body.content.trim();
@@ -6453,6 +7959,17 @@ async fn handle_chat_send(
     }
 
     let workspace_dir = state.config.lock().workspace_dir.clone();
+    if let Some(command) = chat_commands::match_command(content) {
+        return match handle_chat_command(&state, thread_id, content, command) {
+            Ok(record) => (StatusCode::OK, Json(record)),
+            Err(err) => frontend_internal_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "chat command",
+                "Failed to run the chat command.",
+                err,
+            ),
+        };
+    }
     match local_store::create_chat_message(
         &workspace_dir,
         thread_id,
@@ -6464,14 +7981,19 @@ async fn handle_chat_send(
         None,
     ) {
         Ok(record) => {
+            state
+                .observer
+                .record_event(&crate::observability::ObserverEvent::ChannelMessage {
+                    channel: "chat".to_string(),
+                    direction: "inbound".to_string(),
+                });
+            let turn_memory = ConversationTurnMemory::chat(thread_id);
             if state.auto_save {
-                let key = format!("chat_{}_{}", thread_id, Uuid::new_v4());
                 let mem = state.mem.clone();
                 let content_copy = content.to_string();
+                let turn_memory = turn_memory.clone();
                 tokio::spawn(async move {
-                    let _ = mem
-                        .store(&key, &content_copy, MemoryCategory::Conversation, None)
-                        .await;
+                    turn_memory.store_user(mem.as_ref(), &content_copy).await;
                 });
             }
 
@@ -6481,7 +8003,17 @@ async fn handle_chat_send(
                 .unwrap_or("")
                 .to_string();
             let thread_id_owned = thread_id.to_string();
-            let content_owned = content.to_string();
+            let content_owned = gateway_prompt_with_attachments(&state, content, &body.attachments);
+            let include_trace = body.include_trace;
+            let budget_override = budget_override_requested(&state, &headers);
+            let cancellation_token = tokio_util::sync::CancellationToken::new();
+            state.chat_runs.lock().insert(
+                user_id.clone(),
+                InFlightChatRun {
+                    thread_id: thread_id_owned.clone(),
+                    token: cancellation_token.clone(),
+                },
+            );
             let state_for_worker = state.clone();
             let workspace_for_worker = workspace_dir.clone();
             tokio::spawn(async move {
@@ -6496,31 +8028,73 @@ async fn handle_chat_send(
                     thread_id_owned.clone(),
                     Some(thread_id_owned.clone()),
                 );
-                let config = state_for_worker.config.lock().clone();
-                let result = crate::channels::with_channel_execution_context(
-                    channel_ctx,
-                    run_gateway_ui_chat_with_tools(config, &content_owned),
-                )
-                .await;
+                let mut config = state_for_worker.config.lock().clone();
+                let settings =
+                    local_store::get_chat_thread_settings(&workspace_for_worker, &thread_id_owned)
+                        .unwrap_or_else(|err| {
+                            tracing::warn!("Chat worker failed to load thread settings: {err}");
+                            local_store::ChatThreadSettings::default()
+                        });
+                apply_chat_thread_settings(&mut config, &settings);
+                let run = crate::channels::with_channel_execution_context(channel_ctx, async {
+                    if include_trace {
+                        Box::pin(run_gateway_ui_chat_with_tools_traced(
+                            config,
+                            &content_owned,
+                            cancellation_token,
+                        ))
+                        .await
+                        .map(|(reply, trace)| (reply, Some(trace)))
+                    } else {
+                        Box::pin(run_gateway_ui_chat_cancellable(
+                            config,
+                            &content_owned,
+                            cancellation_token,
+                        ))
+                        .await
+                        .map(|reply| (reply, None))
+                    }
+                });
+                let run = crate::agent::with_system_prompt_suffix(
+                    settings.system_suffix.clone().unwrap_or_default(),
+                    run,
+                );
+                let result = Box::pin(with_budget_override(budget_override, run)).await;
+                state_for_worker.chat_runs.lock().remove(&user_id);
 
                 match result {
-                    Ok(reply) => {
+                    Ok((reply, trace)) => {
                         let reply_text = if reply.trim().is_empty() {
                             "(empty response)"
                         } else {
                             reply.trim()
                         };
-                        if let Err(err) = local_store::create_chat_message(
+                        match local_store::create_chat_reply_parts(
                             &workspace_for_worker,
                             &thread_id_owned,
-                            "assistant",
-                            reply_text,
-                            "done",
+                            &chunk_message(reply_text, CHAT_REPLY_MAX_CHARS),
                             "slowclaw",
                             Some(&user_id),
-                            None,
                         ) {
-                            tracing::warn!("Chat worker failed to save assistant reply: {err}");
+                            Ok(records) => {
+                                let reply_id = records
+                                    .last()
+                                    .and_then(|record| record.get("id"))
+                                    .and_then(serde_json::Value::as_str)
+                                    .unwrap_or("");
+                                if let Some(trace) = trace {
+                                    if let Err(err) = local_store::set_chat_message_trace(
+                                        &workspace_for_worker,
+                                        reply_id,
+                                        &serde_json::json!(trace),
+                                    ) {
+                                        tracing::warn!("Chat worker failed to save trace: {err}");
+                                    }
+                                }
+                            }
+                            Err(err) => {
+                                tracing::warn!("Chat worker failed to save assistant reply: {err}");
+                            }
                         }
                         if let Err(err) =
                             local_store::patch_chat_status(&workspace_for_worker, &user_id, "done", None)
@@ -6528,13 +8102,57 @@ async fn handle_chat_send(
                             tracing::warn!("Chat worker failed to mark done: {err}");
                         }
                         if state_for_worker.auto_save {
-                            let key = format!("chat_{}_{}", thread_id_owned, Uuid::new_v4());
-                            let _ = state_for_worker
-                                .mem
-                                .store(&key, reply_text, MemoryCategory::Conversation, None)
+                            turn_memory
+                                .store_reply(state_for_worker.mem.as_ref(), reply_text)
                                 .await;
                         }
                     }
+                    Err(err) if crate::agent::is_tool_loop_cancelled(&err) => {
+                        tracing::info!("Chat run {user_id} cancelled");
+                        if let Err(err) = local_store::create_chat_message(
+                            &workspace_for_worker,
+                            &thread_id_owned,
+                            "assistant",
+                            CHAT_CANCELLED_REPLY,
+                            "done",
+                            "slowclaw",
+                            Some(&user_id),
+                            None,
+                        ) {
+                            tracing::warn!("Chat worker failed to save cancel reply: {err}");
+                        }
+                        if let Err(err) = local_store::patch_chat_status(
+                            &workspace_for_worker,
+                            &user_id,
+                            "cancelled",
+                            None,
+                        ) {
+                            tracing::warn!("Chat worker failed to mark cancelled: {err}");
+                        }
+                    }
+                    Err(err) if budget_exceeded(&err).is_some() => {
+                        tracing::warn!("Chat run {user_id} refused: {err}");
+                        if let Err(err) = local_store::create_chat_message(
+                            &workspace_for_worker,
+                            &thread_id_owned,
+                            "assistant",
+                            &err.to_string(),
+                            "done",
+                            "slowclaw",
+                            Some(&user_id),
+                            None,
+                        ) {
+                            tracing::warn!("Chat worker failed to save budget reply: {err}");
+                        }
+                        if let Err(err) = local_store::patch_chat_status(
+                            &workspace_for_worker,
+                            &user_id,
+                            "done",
+                            None,
+                        ) {
+                            tracing::warn!("Chat worker failed to mark done: {err}");
+                        }
+                    }
                     Err(err) => {
                         let err_text = frontend_background_error(
                             "chat message worker",
@@ -6563,7 +8181,16 @@ async fn handle_chat_send(
                 }
             });
 
-            (StatusCode::OK, Json(record))
+            match wait_timeout {
+                Some(timeout) => {
+                    let message_id = record
+                        .get("id")
+                        .and_then(serde_json::Value::as_str)
+                        .unwrap_or("");
+                    wait_for_chat_result(&workspace_dir, thread_id, message_id, timeout).await
+                }
+                None => (StatusCode::OK, Json(record)),
+            }
         }
         Err(err) => frontend_internal_error(
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -7275,5595 +8902,10817 @@ async fn handle_workspace_todos_list(
     }
 }
 
-async fn handle_workspace_todo_update(
+#[derive(serde::Deserialize)]
+struct JobsListQuery {
+    limit: Option<usize>,
+}
+
+/// GET /api/jobs — background tool jobs, newest first
+async fn handle_jobs_list(
     State(state): State<AppState>,
     headers: HeaderMap,
-    AxumPath(todo_id): AxumPath<String>,
-    Json(body): Json<WorkspaceTodoUpdateBody>,
+    Query(query): Query<JobsListQuery>,
 ) -> impl IntoResponse {
-    if let Some(err) = pairing_auth_error(&state, &headers, "Workspace todo update") {
+    if let Some(err) = pairing_auth_error(&state, &headers, "Jobs list") {
         return err;
     }
 
-    let status = body.status.unwrap_or_default().trim().to_ascii_lowercase();
-    if status != "open" && status != "done" {
-        return frontend_error_response(
-            StatusCode::BAD_REQUEST,
-            "WORKSPACE_TODO_STATUS_INVALID",
-            "status must be `open` or `done`",
-        );
-    }
-    let workspace_dir = state.config.lock().workspace_dir.clone();
-    let update = local_store::WorkspaceTodoStatusUpdate {
-        id: todo_id,
-        status_override: status,
-    };
-    match local_store::update_workspace_todo_status(&workspace_dir, &update) {
-        Ok(item) => (StatusCode::OK, Json(serde_json::json!({ "item": item }))),
-        Err(err) => frontend_internal_error(
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "workspace todo update",
-            "Failed to update the todo status.",
-            err,
-        ),
-    }
+    let limit = query.limit.unwrap_or(50).clamp(1, 500);
+    (
+        StatusCode::OK,
+        Json(serde_json::json!({ "items": state.jobs.list(limit) })),
+    )
 }
 
-async fn handle_workspace_events_list(
+/// GET /api/jobs/{id} — status, output tail, and artifacts of one job
+async fn handle_job_get(
     State(state): State<AppState>,
     headers: HeaderMap,
-    Query(query): Query<WorkspaceListQuery>,
+    AxumPath(job_id): AxumPath<String>,
 ) -> impl IntoResponse {
-    if let Some(err) = pairing_auth_error(&state, &headers, "Workspace events") {
+    if let Some(err) = pairing_auth_error(&state, &headers, "Job status") {
         return err;
     }
 
-    let workspace_dir = state.config.lock().workspace_dir.clone();
-    let limit = query.limit.unwrap_or(100);
-    match local_store::list_workspace_events(&workspace_dir, limit) {
-        Ok(items) => (StatusCode::OK, Json(serde_json::json!({ "items": items }))),
-        Err(err) => frontend_internal_error(
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "workspace event list",
-            "Failed to load workspace events.",
-            err,
-        ),
+    match state.jobs.get(job_id.trim()) {
+        Some(job) => (StatusCode::OK, Json(serde_json::json!(job))),
+        None => frontend_error_response(StatusCode::NOT_FOUND, "JOB_NOT_FOUND", "Job not found"),
     }
 }
 
-async fn handle_feed_personalized(
+/// GET /api/security/budget — hourly tool action budget left for the agent
+async fn handle_security_budget(
     State(state): State<AppState>,
     headers: HeaderMap,
-    Json(body): Json<PersonalizedFeedRequest>,
 ) -> impl IntoResponse {
-    if let Some(err) = pairing_auth_error(&state, &headers, "Personalized feed") {
+    if let Some(err) = pairing_auth_error(&state, &headers, "Security budget") {
         return err;
     }
 
-    let limit = body.limit.unwrap_or(30).clamp(1, BLUESKY_TIMELINE_LIMIT_MAX);
-    let config_snapshot = state.config.lock().clone();
-    let bluesky_auth = match (
-        body.service_url
-            .as_deref()
-            .map(str::trim)
-            .filter(|value| !value.is_empty()),
-        body.access_jwt
-            .as_deref()
-            .map(str::trim)
-            .filter(|value| !value.is_empty()),
-        ) {
-        (Some(service_url), Some(access_jwt)) => Some(crate::feed::BlueskyAuth {
-            service_url: service_url.to_string(),
-            access_jwt: access_jwt.to_string(),
+    let config = state.config.lock().clone();
+    let policy =
+        crate::security::SecurityPolicy::from_config(&config.autonomy, &config.workspace_dir);
+    (StatusCode::OK, Json(serde_json::json!(policy.action_budget())))
+}
+
+/// GET /api/usage — provider spend today and this month against the `[cost]`
+/// limits; `budget` is null while cost tracking is off
+async fn handle_usage(State(state): State<AppState>, headers: HeaderMap) -> impl IntoResponse {
+    if let Some(err) = pairing_auth_error(&state, &headers, "Usage") {
+        return err;
+    }
+
+    let enabled = state.config.lock().cost.enabled;
+    let body = serde_json::json!({
+        "enabled": enabled,
+        "budget": budget_snapshot(&state),
+    });
+    (StatusCode::OK, Json(body))
+}
+
+#[derive(serde::Deserialize)]
+struct ActivityQuery {
+    since: Option<String>,
+    kind: Option<String>,
+    limit: Option<usize>,
+}
+
+/// GET /api/activity — recent agent activity (LLM calls, tools, errors), newest first
+async fn handle_activity(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<ActivityQuery>,
+) -> impl IntoResponse {
+    if let Some(err) = pairing_auth_error(&state, &headers, "Activity") {
+        return err;
+    }
+
+    let since = match query
+        .since
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+    {
+        Some(raw) => match chrono::DateTime::parse_from_rfc3339(raw) {
+            Ok(ts) => Some(ts.with_timezone(&chrono::Utc)),
+            Err(_) => {
+                return frontend_error_response(
+                    StatusCode::BAD_REQUEST,
+                    "INVALID_SINCE",
+                    "since must be an RFC 3339 timestamp",
+                )
+            }
+        },
+        None => None,
+    };
+    let kind = match query
+        .kind
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+    {
+        Some(raw) => match crate::observability::activity::ActivityKind::parse(raw) {
+            Some(kind) => Some(kind),
+            None => {
+                return frontend_error_response(
+                    StatusCode::BAD_REQUEST,
+                    "INVALID_KIND",
+                    "kind must be one of agent, llm, tool, channel, memory, error",
+                )
+            }
+        },
+        None => None,
+    };
+
+    let limit = query
+        .limit
+        .unwrap_or(crate::observability::activity::ACTIVITY_CAPACITY)
+        .clamp(1, crate::observability::activity::ACTIVITY_CAPACITY);
+    let items = crate::observability::find_observer::<crate::observability::ActivityObserver>(
+        state.observer.as_ref(),
+    )
+    .map(|activity| activity.recent(since, kind, limit))
+    .unwrap_or_default();
+    (StatusCode::OK, Json(serde_json::json!({ "items": items })))
+}
+
+/// GET /api/provider/status — active backend, error counts, and circuit state
+async fn handle_provider_status(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> axum::response::Response {
+    if let Some(err) = pairing_auth_error(&state, &headers, "Provider status") {
+        return err.into_response();
+    }
+    let body = match state.provider.introspection() {
+        Some(introspection) => serde_json::json!({
+            "supported": true,
+            "model": state.model,
+            "status": introspection.status(),
+        }),
+        None => serde_json::json!({
+            "supported": false,
+            "model": state.model,
+            "status": null,
         }),
-        _ => None,
     };
-    match crate::feed::load_world_feed(&config_snapshot, bluesky_auth, limit, body.force).await {
-        Ok(response) => (
-            StatusCode::OK,
-            Json(serde_json::to_value(response).unwrap_or_else(|_| serde_json::json!({}))),
+    (StatusCode::OK, Json(body)).into_response()
+}
+
+/// POST /api/provider/reset — close every circuit so the primary is retried
+async fn handle_provider_reset(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> axum::response::Response {
+    if let Some(err) = paired_device_auth_error(&state, &headers, "Provider reset") {
+        return err.into_response();
+    }
+    let Some(introspection) = state.provider.introspection() else {
+        return frontend_error_response(
+            StatusCode::CONFLICT,
+            "PROVIDER_RESET_UNSUPPORTED",
+            "The active provider has no circuit breaker to reset.",
+        )
+        .into_response();
+    };
+    introspection.reset_circuits();
+    tracing::info!("Provider circuits reset via gateway");
+    (
+        StatusCode::OK,
+        Json(serde_json::json!({ "ok": true, "status": introspection.status() })),
+    )
+        .into_response()
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ApiKeyCreateBody {
+    label: String,
+    routes: Vec<String>,
+    rate_limit_per_minute: Option<u32>,
+    expires_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Managing keys needs a paired device token; an API key cannot mint or
+/// revoke keys, whatever its routes say.
+fn api_key_admin_error(
+    state: &AppState,
+    headers: &HeaderMap,
+    scope: &str,
+) -> Option<axum::response::Response> {
+    paired_device_auth_error(state, headers, scope).map(IntoResponse::into_response)
+}
+
+/// GET /api/keys — API keys without their secrets, with last use
+async fn handle_api_keys_list(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> axum::response::Response {
+    if let Some(err) = api_key_admin_error(&state, &headers, "API keys") {
+        return err;
+    }
+    let workspace_dir = state.config.lock().workspace_dir.clone();
+    let items: Vec<serde_json::Value> = api_keys::store(&workspace_dir)
+        .list()
+        .iter()
+        .map(api_keys::ApiKey::to_json)
+        .collect();
+    (StatusCode::OK, Json(serde_json::json!({ "items": items }))).into_response()
+}
+
+/// POST /api/keys — create a key; its plaintext is only in this response
+async fn handle_api_key_create(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(body): Json<ApiKeyCreateBody>,
+) -> axum::response::Response {
+    if let Some(err) = api_key_admin_error(&state, &headers, "API key create") {
+        return err;
+    }
+    let new = api_keys::NewApiKey {
+        label: body.label,
+        routes: body.routes,
+        rate_limit_per_minute: body.rate_limit_per_minute,
+        expires_at: body.expires_at,
+    };
+    if let Err(err) = new.validate() {
+        return frontend_error_response(
+            StatusCode::BAD_REQUEST,
+            "API_KEY_INVALID_REQUEST",
+            err.to_string(),
+        )
+        .into_response();
+    }
+    let workspace_dir = state.config.lock().workspace_dir.clone();
+    match api_keys::store(&workspace_dir).create(new) {
+        Ok((key, plaintext)) => {
+            tracing::info!(id = %key.id, label = %key.label, "API key created");
+            (
+                StatusCode::CREATED,
+                Json(serde_json::json!({ "key": plaintext, "item": key.to_json() })),
+            )
+                .into_response()
+        }
+        Err(err) => frontend_internal_error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "api key create",
+            "Failed to save the API key.",
+            err,
         ),
-        Err(err) => frontend_internal_error(
+    }
+}
+
+/// DELETE /api/keys/{id} — revoke a key
+async fn handle_api_key_revoke(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(key_id): AxumPath<String>,
+) -> axum::response::Response {
+    if let Some(err) = api_key_admin_error(&state, &headers, "API key revoke") {
+        return err;
+    }
+    let workspace_dir = state.config.lock().workspace_dir.clone();
+    match api_keys::store(&workspace_dir).revoke(&key_id) {
+        Ok(true) => {
+            tracing::info!(id = %key_id, "API key revoked");
+            (
+                StatusCode::OK,
+                Json(serde_json::json!({ "ok": true, "id": key_id })),
+            )
+                .into_response()
+        }
+        Ok(false) => frontend_error_response(
+            StatusCode::NOT_FOUND,
+            "API_KEY_NOT_FOUND",
+            "API key not found",
+        )
+        .into_response(),
+        Err(err) => frontend_internal_error_response(
             StatusCode::INTERNAL_SERVER_ERROR,
-            "feed personalized",
-            "Failed to load the personalized world feed.",
+            "api key revoke",
+            "Failed to revoke the API key.",
             err,
         ),
     }
 }
 
-async fn handle_world_feed_interests_list(
+const MEMORY_LIST_DEFAULT_LIMIT: usize = 100;
+const MEMORY_LIST_MAX_LIMIT: usize = 500;
+const MEMORY_PREVIEW_CHARS: usize = 200;
+
+#[derive(Default, serde::Deserialize)]
+struct MemoryListQuery {
+    category: Option<String>,
+    /// Keyword search via `recall`; lists newest entries when absent.
+    q: Option<String>,
+    session: Option<String>,
+    limit: Option<usize>,
+}
+
+#[derive(Default, serde::Deserialize)]
+struct MemorySessionQuery {
+    session: Option<String>,
+}
+
+fn non_empty_query_value(value: Option<&str>) -> Option<&str> {
+    value.map(str::trim).filter(|v| !v.is_empty())
+}
+
+fn memory_entry_summary(entry: &MemoryEntry) -> serde_json::Value {
+    serde_json::json!({
+        "key": entry.key,
+        "preview": crate::util::truncate_with_ellipsis(&entry.content, MEMORY_PREVIEW_CHARS),
+        "category": entry.category.to_string(),
+        "sessionId": entry.session_id,
+        "timestamp": entry.timestamp,
+        "score": entry.score,
+    })
+}
+
+/// GET /api/memory — list or search memory entries
+async fn handle_memory_list(
     State(state): State<AppState>,
     headers: HeaderMap,
+    Query(query): Query<MemoryListQuery>,
 ) -> impl IntoResponse {
-    if let Some(err) = pairing_auth_error(&state, &headers, "World feed interests") {
+    if let Some(err) = pairing_auth_error(&state, &headers, "Memory API") {
         return err;
     }
-    let config_snapshot = state.config.lock().clone();
-    match crate::feed::list_world_feed_interest_diagnostics(&config_snapshot) {
-        Ok(response) => (
-            StatusCode::OK,
-            Json(serde_json::to_value(response).unwrap_or_else(|_| serde_json::json!({}))),
-        ),
+    let category =
+        non_empty_query_value(query.category.as_deref()).map(crate::memory::cli::parse_category);
+    let session = non_empty_query_value(query.session.as_deref());
+    let limit = query
+        .limit
+        .unwrap_or(MEMORY_LIST_DEFAULT_LIMIT)
+        .clamp(1, MEMORY_LIST_MAX_LIMIT);
+
+    let result = match non_empty_query_value(query.q.as_deref()) {
+        Some(q) => state.mem.recall(q, limit, session).await.map(|entries| {
+            entries
+                .into_iter()
+                .filter(|entry| category.as_ref().is_none_or(|c| &entry.category == c))
+                .collect::<Vec<_>>()
+        }),
+        None => state
+            .mem
+            .list(category.as_ref(), session)
+            .await
+            .map(|mut entries| {
+                entries.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+                entries
+            }),
+    };
+    match result {
+        Ok(entries) => {
+            let total = entries.len();
+            let items: Vec<serde_json::Value> = entries
+                .iter()
+                .take(limit)
+                .map(memory_entry_summary)
+                .collect();
+            (
+                StatusCode::OK,
+                Json(serde_json::json!({ "items": items, "total": total })),
+            )
+        }
         Err(err) => frontend_internal_error(
             StatusCode::INTERNAL_SERVER_ERROR,
-            "world feed interests",
-            "Failed to load world-feed interests.",
+            "memory list",
+            "Failed to read memory.",
             err,
         ),
     }
 }
 
-async fn handle_world_feed_interest_create(
+/// GET /api/memory/stats — entry counts per category
+async fn handle_memory_stats(
     State(state): State<AppState>,
     headers: HeaderMap,
-    Json(body): Json<WorldFeedInterestCreateRequest>,
 ) -> impl IntoResponse {
-    if let Some(err) = pairing_auth_error(&state, &headers, "World feed interest create") {
+    if let Some(err) = pairing_auth_error(&state, &headers, "Memory API") {
         return err;
     }
-    let label = body
-        .label
-        .as_deref()
-        .map(str::trim)
-        .filter(|value| !value.is_empty())
-        .unwrap_or("Open protocols, developer tools, startups, AI products");
-    let config_snapshot = state.config.lock().clone();
-    match crate::feed::create_dummy_world_feed_interest(&config_snapshot, label).await {
-        Ok(item) => (
-            StatusCode::OK,
-            Json(serde_json::json!({
-                "created": true,
-                "item": item,
-            })),
-        ),
+    match state.mem.list_all(None, None).await {
+        Ok(entries) => {
+            let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+            for entry in &entries {
+                *counts.entry(entry.category.to_string()).or_default() += 1;
+            }
+            let categories: Vec<serde_json::Value> = counts
+                .into_iter()
+                .map(
+                    |(category, count)| serde_json::json!({ "category": category, "count": count }),
+                )
+                .collect();
+            (
+                StatusCode::OK,
+                Json(serde_json::json!({
+                    "backend": state.mem.name(),
+                    "total": entries.len(),
+                    "categories": categories,
+                })),
+            )
+        }
         Err(err) => frontend_internal_error(
             StatusCode::INTERNAL_SERVER_ERROR,
-            "world feed interest create",
-            "Failed to create diagnostic world-feed interest.",
+            "memory stats",
+            "Failed to read memory.",
             err,
         ),
     }
 }
 
-async fn handle_world_feed_interest_delete(
+/// DELETE /api/memory/{key} — forget one entry
+async fn handle_memory_forget(
     State(state): State<AppState>,
     headers: HeaderMap,
-    AxumPath(interest_id): AxumPath<String>,
+    AxumPath(key): AxumPath<String>,
 ) -> impl IntoResponse {
-    if let Some(err) = pairing_auth_error(&state, &headers, "World feed interest delete") {
+    if let Some(err) = paired_device_auth_error(&state, &headers, "Memory API") {
         return err;
     }
-    let trimmed_interest_id = interest_id.trim();
-    if trimmed_interest_id.is_empty() {
-        return frontend_error_response(
-            StatusCode::BAD_REQUEST,
-            "WORLD_FEED_INTEREST_ID_REQUIRED",
-            "interest id is required",
-        );
-    }
-    let config_snapshot = state.config.lock().clone();
-    match crate::feed::delete_world_feed_interest(&config_snapshot, trimmed_interest_id) {
+    match state.mem.forget(&key).await {
         Ok(true) => (
             StatusCode::OK,
-            Json(serde_json::json!({
-                "deleted": true,
-                "interestId": trimmed_interest_id,
-            })),
+            Json(serde_json::json!({ "ok": true, "key": key })),
         ),
         Ok(false) => frontend_error_response(
             StatusCode::NOT_FOUND,
-            "WORLD_FEED_INTEREST_NOT_FOUND",
-            "world-feed interest not found",
+            "MEMORY_NOT_FOUND",
+            "Memory entry not found",
         ),
         Err(err) => frontend_internal_error(
-            StatusCode::BAD_REQUEST,
-            "world feed interest delete",
-            "Failed to delete world-feed interest.",
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "memory forget",
+            "Failed to delete the memory entry.",
             err,
         ),
     }
 }
 
-async fn handle_world_feed_interest_update(
+/// DELETE /api/memory?session=... — forget every entry stored for a session
+async fn handle_memory_delete_session(
     State(state): State<AppState>,
     headers: HeaderMap,
-    AxumPath(interest_id): AxumPath<String>,
-    Json(body): Json<WorldFeedInterestUpdateRequest>,
+    Query(query): Query<MemorySessionQuery>,
 ) -> impl IntoResponse {
-    if let Some(err) = pairing_auth_error(&state, &headers, "World feed interest update") {
+    if let Some(err) = paired_device_auth_error(&state, &headers, "Memory API") {
         return err;
     }
-    let trimmed_interest_id = interest_id.trim();
-    if trimmed_interest_id.is_empty() {
+    let Some(session) = non_empty_query_value(query.session.as_deref()) else {
         return frontend_error_response(
             StatusCode::BAD_REQUEST,
-            "WORLD_FEED_INTEREST_ID_REQUIRED",
-            "interest id is required",
+            "MEMORY_SESSION_REQUIRED",
+            "session is required for bulk deletion",
         );
+    };
+    let entries = match state.mem.list_all(None, Some(session)).await {
+        Ok(entries) => entries,
+        Err(err) => {
+            return frontend_internal_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "memory session delete",
+                "Failed to read memory.",
+                err,
+            )
+        }
+    };
+    let mut deleted = 0usize;
+    for entry in &entries {
+        match state.mem.forget(&entry.key).await {
+            Ok(true) => deleted += 1,
+            Ok(false) => {}
+            Err(err) => tracing::warn!("Memory forget failed for {}: {err}", entry.key),
+        }
     }
-    let config_snapshot = state.config.lock().clone();
-    match crate::feed::update_world_feed_interest(
-        &config_snapshot,
-        trimmed_interest_id,
-        body.label.as_deref(),
-        body.keywords_override,
+    tracing::info!(session, deleted, "Memory session cleared via gateway");
+    (
+        StatusCode::OK,
+        Json(serde_json::json!({ "ok": true, "session": session, "deleted": deleted })),
     )
-    .await
-    {
-        Ok(Some(item)) => (
-            StatusCode::OK,
-            Json(serde_json::json!({
-                "updated": true,
-                "item": item,
-            })),
-        ),
-        Ok(None) => frontend_error_response(
-            StatusCode::NOT_FOUND,
-            "WORLD_FEED_INTEREST_NOT_FOUND",
-            "world-feed interest not found",
-        ),
-        Err(err) => frontend_internal_error(
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "world feed interest update",
-            "Failed to update world-feed interest.",
-            err,
-        ),
-    }
 }
 
-async fn handle_feed_workflow_template_create(
+/// GET /api/memory/export — every entry as JSONL, see `memory::transfer`
+async fn handle_memory_export(
     State(state): State<AppState>,
     headers: HeaderMap,
-    Json(body): Json<FeedContentAgentCreateBody>,
-) -> impl IntoResponse {
-    if let Some(err) = pairing_auth_error(&state, &headers, "Feed workflow template create") {
-        return err;
+) -> axum::response::Response {
+    if let Some(err) = paired_device_auth_error(&state, &headers, "Memory API") {
+        return err.into_response();
     }
-
-    let name = body
-        .name
-        .as_deref()
-        .map(str::trim)
-        .filter(|value| !value.is_empty())
-        .map(ToOwned::to_owned);
-    let Some(name) = name else {
-        return frontend_error_response(
-            StatusCode::BAD_REQUEST,
-            "WORKFLOW_TEMPLATE_NAME_REQUIRED",
-            "name is required",
-        );
+    let mut body = Vec::new();
+    let count = match memory::transfer::export_jsonl(state.mem.as_ref(), &mut body).await {
+        Ok(count) => count,
+        Err(err) => {
+            return frontend_internal_error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "memory export",
+                "Failed to export memory.",
+                err,
+            )
+        }
     };
+    tracing::info!(count, "Memory exported via gateway");
+    axum::response::Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "application/x-ndjson")
+        .header(
+            header::CONTENT_DISPOSITION,
+            "attachment; filename=\"memories.jsonl\"",
+        )
+        .body(axum::body::Body::from(body))
+        .unwrap_or_else(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())
+}
 
-    let goal = normalize_goal_text(body.goal.clone().or(body.prompt.clone()));
-    let Some(goal) = goal else {
-        return frontend_error_response(
-            StatusCode::BAD_REQUEST,
-            "WORKFLOW_TEMPLATE_GOAL_REQUIRED",
-            "goal is required",
-        );
-    };
-    let media_capabilities = local_media_capabilities(&state.config.lock().clone());
-    if goal_requests_media_output(&goal)
-        && !(media_capabilities.transcribe_media && media_capabilities.compose_simple_clip)
-    {
-        return frontend_error_response(
-            StatusCode::BAD_REQUEST,
-            "WORKFLOW_TEMPLATE_MEDIA_CAPABILITY_REQUIRED",
-            required_media_capability_reason(media_capabilities),
-        );
+#[derive(Default, serde::Deserialize)]
+struct MemoryImportQuery {
+    /// Replace entries whose key already exists.
+    #[serde(default)]
+    overwrite: bool,
+}
+
+/// POST /api/memory/import — stream a JSONL export into the memory backend.
+///
+/// The body is consumed frame by frame and imported line by line, so large
+/// exports never have to be buffered whole.
+async fn handle_memory_import(
+    State(state): State<AppState>,
+    Query(query): Query<MemoryImportQuery>,
+    req: Request,
+) -> axum::response::Response {
+    if let Some(err) = paired_device_auth_error(&state, req.headers(), "Memory API") {
+        return err.into_response();
     }
-    let workflow_name = name.clone();
-    let workflow_key = sanitize_workflow_key(&workflow_name);
 
-    let workflow_bot = body
-        .bot_name
-        .as_deref()
-        .map(str::trim)
-        .filter(|value| !value.is_empty())
-        .map(ToOwned::to_owned)
-        .unwrap_or_else(|| name.clone());
+    let mut importer = memory::transfer::JsonlImporter::new(state.mem.as_ref(), query.overwrite);
+    let mut pending: Vec<u8> = Vec::new();
+    let mut body = req.into_body();
+    while let Some(frame_result) = body.frame().await {
+        let frame = match frame_result {
+            Ok(frame) => frame,
+            Err(err) => {
+                return frontend_internal_error_response(
+                    StatusCode::BAD_REQUEST,
+                    "memory import stream",
+                    "The import stream could not be read.",
+                    err,
+                );
+            }
+        };
+        let Some(data) = frame.data_ref() else {
+            continue;
+        };
+        pending.extend_from_slice(data);
+        let mut start = 0;
+        while let Some(offset) = pending[start..].iter().position(|byte| *byte == b'\n') {
+            let end = start + offset;
+            import_memory_line(&mut importer, &pending[start..end]).await;
+            start = end + 1;
+        }
+        pending.drain(..start);
+    }
+    if !pending.is_empty() {
+        import_memory_line(&mut importer, &pending).await;
+    }
 
-    let mut settings = FeedWorkflowSettings {
-        mode: FeedWorkflowMode::DateRange,
-        days: default_feed_workflow_days(),
-        random_count: default_feed_workflow_random_count(),
-        schedule_enabled: false,
-        schedule_cron: default_workflow_schedule_cron(),
-        schedule_tz: None,
-        goal: Some(goal.clone()),
-        prompt: Some(goal.clone()),
+    let summary = importer.finish();
+    tracing::info!(
+        inserted = summary.inserted,
+        skipped = summary.skipped,
+        failed = summary.failed,
+        "Memory imported via gateway"
+    );
+    (
+        StatusCode::OK,
+        Json(serde_json::json!({
+            "ok": true,
+            "inserted": summary.inserted,
+            "skipped": summary.skipped,
+            "failed": summary.failed,
+            "errors": summary.errors,
+        })),
+    )
+        .into_response()
+}
+
+async fn import_memory_line(importer: &mut memory::transfer::JsonlImporter<'_>, line: &[u8]) {
+    match std::str::from_utf8(line) {
+        Ok(text) => importer.import_line(text.trim_end_matches('\r')).await,
+        Err(_) => importer.reject_line("line is not valid UTF-8"),
+    }
+}
+
+#[derive(Default, serde::Deserialize)]
+struct ModelsQuery {
+    /// Bypass the cached catalog.
+    #[serde(default)]
+    refresh: bool,
+}
+
+/// Restrict a catalog to `allowed_models` (when set) and make sure the
+/// default model is always offered.
+fn filter_model_catalog(
+    mut models: Vec<crate::providers::ModelInfo>,
+    default_model: &str,
+    allowed_models: &[String],
+) -> Vec<crate::providers::ModelInfo> {
+    if !allowed_models.is_empty() {
+        models.retain(|m| m.id == default_model || allowed_models.iter().any(|a| a == &m.id));
+    }
+    if !models.iter().any(|m| m.id == default_model) {
+        models.insert(0, crate::providers::ModelInfo::id_only(default_model));
+    }
+    models
+}
+
+/// GET /api/models — model catalog of the active provider, cached for
+/// `gateway.models_cache_ttl_secs`
+async fn handle_models(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<ModelsQuery>,
+) -> axum::response::Response {
+    if let Some(err) = pairing_auth_error(&state, &headers, "Model listing") {
+        return err.into_response();
+    }
+    let (ttl_secs, allowed_models) = {
+        let config = state.config.lock();
+        (
+            config.gateway.models_cache_ttl_secs,
+            config.gateway.allowed_models.clone(),
+        )
     };
-    settings = normalize_workflow_settings(settings);
 
-    let workspace_dir = state.config.lock().workspace_dir.clone();
-    let store = match load_or_seed_feed_workflow_settings_store(&workspace_dir) {
-        Ok(store) => store,
-        Err(err) => {
-            return frontend_internal_error(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "feed workflow template store load",
-                "Failed to load content agent settings.",
-                err,
-            );
-        }
+    let cached = if query.refresh || ttl_secs == 0 {
+        None
+    } else {
+        state
+            .model_catalog
+            .lock()
+            .as_ref()
+            .filter(|entry| entry.cached_at.elapsed() < Duration::from_secs(ttl_secs))
+            .map(|entry| entry.models.clone())
     };
-    if workflow_definition_by_key(&store, &workflow_key).is_some() {
-        return frontend_error_response(
-            StatusCode::CONFLICT,
-            "WORKFLOW_TEMPLATE_KEY_CONFLICT",
-            "workflow key already exists",
-        );
-    }
 
-    let output_dir_rel = format!("posts/{workflow_key}");
-    let skill_rel = format!("skills/{workflow_key}/SKILL.md");
-    let skill_abs = workspace_dir.join(&skill_rel);
+    let mut error = None;
+    let from_cache = cached.is_some();
+    let models = match cached {
+        Some(models) => models,
+        None => match state.provider.list_models(&state.model).await {
+            Ok(models) => {
+                *state.model_catalog.lock() = Some(ModelCatalogCacheEntry {
+                    models: models.clone(),
+                    cached_at: Instant::now(),
+                });
+                models
+            }
+            Err(err) => {
+                let sanitized = providers::sanitize_api_error(&err.to_string());
+                tracing::warn!("Model listing failed: {sanitized}");
+                error = Some(sanitized);
+                Vec::new()
+            }
+        },
+    };
 
-    let skill_body = render_template_skill_markdown(&workflow_name, &goal, &output_dir_rel);
+    let items = filter_model_catalog(models, &state.model, &allowed_models);
+    (
+        StatusCode::OK,
+        Json(serde_json::json!({
+            "items": items,
+            "defaultModel": state.model,
+            "cached": from_cache,
+            "error": error,
+        })),
+    )
+        .into_response()
+}
 
-    if let Some(parent) = skill_abs.parent() {
-        if let Err(err) = std::fs::create_dir_all(parent) {
-            return frontend_internal_error(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "feed workflow template skill dir create",
-                "Failed to create the content agent skill directory.",
-                err,
-            );
-        }
+async fn handle_workspace_todo_update(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(todo_id): AxumPath<String>,
+    Json(body): Json<WorkspaceTodoUpdateBody>,
+) -> impl IntoResponse {
+    if let Some(err) = pairing_auth_error(&state, &headers, "Workspace todo update") {
+        return err;
     }
-    if let Err(err) = std::fs::create_dir_all(workspace_dir.join(&output_dir_rel)) {
-        return frontend_internal_error(
+
+    let status = body.status.unwrap_or_default().trim().to_ascii_lowercase();
+    if status != "open" && status != "done" {
+        return frontend_error_response(
+            StatusCode::BAD_REQUEST,
+            "WORKSPACE_TODO_STATUS_INVALID",
+            "status must be `open` or `done`",
+        );
+    }
+    let workspace_dir = state.config.lock().workspace_dir.clone();
+    let update = local_store::WorkspaceTodoStatusUpdate {
+        id: todo_id,
+        status_override: status,
+    };
+    match local_store::update_workspace_todo_status(&workspace_dir, &update) {
+        Ok(item) => (StatusCode::OK, Json(serde_json::json!({ "item": item }))),
+        Err(err) => frontend_internal_error(
             StatusCode::INTERNAL_SERVER_ERROR,
-            "feed workflow template output dir create",
-            "Failed to create the content agent output directory.",
+            "workspace todo update",
+            "Failed to update the todo status.",
             err,
-        );
+        ),
     }
-    if let Err(err) = std::fs::write(&skill_abs, skill_body) {
-        return frontend_internal_error(
+}
+
+async fn handle_workspace_events_list(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<WorkspaceListQuery>,
+) -> impl IntoResponse {
+    if let Some(err) = pairing_auth_error(&state, &headers, "Workspace events") {
+        return err;
+    }
+
+    let workspace_dir = state.config.lock().workspace_dir.clone();
+    let limit = query.limit.unwrap_or(100);
+    match local_store::list_workspace_events(&workspace_dir, limit) {
+        Ok(items) => (StatusCode::OK, Json(serde_json::json!({ "items": items }))),
+        Err(err) => frontend_internal_error(
             StatusCode::INTERNAL_SERVER_ERROR,
-            "feed workflow template skill write",
-            "Failed to write the content agent skill.",
+            "workspace event list",
+            "Failed to load workspace events.",
             err,
-        );
+        ),
     }
+}
 
-    let creation_skill_markdown = match ensure_workflow_bot_creation_skill(&workspace_dir) {
-        Ok(markdown) => markdown,
-        Err(err) => {
-            return frontend_internal_error(
-                StatusCode::INTERNAL_SERVER_ERROR,
+async fn handle_feed_personalized(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(body): Json<PersonalizedFeedRequest>,
+) -> impl IntoResponse {
+    if let Some(err) = pairing_auth_error(&state, &headers, "Personalized feed") {
+        return err;
+    }
+
+    let limit = body.limit.unwrap_or(30).clamp(1, BLUESKY_TIMELINE_LIMIT_MAX);
+    let config_snapshot = state.config.lock().clone();
+    let bluesky_auth = match (
+        body.service_url
+            .as_deref()
+            .map(str::trim)
+            .filter(|value| !value.is_empty()),
+        body.access_jwt
+            .as_deref()
+            .map(str::trim)
+            .filter(|value| !value.is_empty()),
+        ) {
+        (Some(service_url), Some(access_jwt)) => Some(crate::feed::BlueskyAuth {
+            service_url: service_url.to_string(),
+            access_jwt: access_jwt.to_string(),
+        }),
+        _ => None,
+    };
+    match crate::feed::load_world_feed(&config_snapshot, bluesky_auth, limit, body.force).await {
+        Ok(response) => (
+            StatusCode::OK,
+            Json(serde_json::to_value(response).unwrap_or_else(|_| serde_json::json!({}))),
+        ),
+        Err(err) => frontend_internal_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "feed personalized",
+            "Failed to load the personalized world feed.",
+            err,
+        ),
+    }
+}
+
+async fn handle_world_feed_interests_list(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    if let Some(err) = pairing_auth_error(&state, &headers, "World feed interests") {
+        return err;
+    }
+    let config_snapshot = state.config.lock().clone();
+    match crate::feed::list_world_feed_interest_diagnostics(&config_snapshot) {
+        Ok(response) => (
+            StatusCode::OK,
+            Json(serde_json::to_value(response).unwrap_or_else(|_| serde_json::json!({}))),
+        ),
+        Err(err) => frontend_internal_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "world feed interests",
+            "Failed to load world-feed interests.",
+            err,
+        ),
+    }
+}
+
+async fn handle_world_feed_interest_create(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(body): Json<WorldFeedInterestCreateRequest>,
+) -> impl IntoResponse {
+    if let Some(err) = pairing_auth_error(&state, &headers, "World feed interest create") {
+        return err;
+    }
+    let label = body
+        .label
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .unwrap_or("Open protocols, developer tools, startups, AI products");
+    let config_snapshot = state.config.lock().clone();
+    match crate::feed::create_dummy_world_feed_interest(&config_snapshot, label).await {
+        Ok(item) => (
+            StatusCode::OK,
+            Json(serde_json::json!({
+                "created": true,
+                "item": item,
+            })),
+        ),
+        Err(err) => frontend_internal_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "world feed interest create",
+            "Failed to create diagnostic world-feed interest.",
+            err,
+        ),
+    }
+}
+
+async fn handle_world_feed_interest_delete(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(interest_id): AxumPath<String>,
+) -> impl IntoResponse {
+    if let Some(err) = pairing_auth_error(&state, &headers, "World feed interest delete") {
+        return err;
+    }
+    let trimmed_interest_id = interest_id.trim();
+    if trimmed_interest_id.is_empty() {
+        return frontend_error_response(
+            StatusCode::BAD_REQUEST,
+            "WORLD_FEED_INTEREST_ID_REQUIRED",
+            "interest id is required",
+        );
+    }
+    let config_snapshot = state.config.lock().clone();
+    match crate::feed::delete_world_feed_interest(&config_snapshot, trimmed_interest_id) {
+        Ok(true) => (
+            StatusCode::OK,
+            Json(serde_json::json!({
+                "deleted": true,
+                "interestId": trimmed_interest_id,
+            })),
+        ),
+        Ok(false) => frontend_error_response(
+            StatusCode::NOT_FOUND,
+            "WORLD_FEED_INTEREST_NOT_FOUND",
+            "world-feed interest not found",
+        ),
+        Err(err) => frontend_internal_error(
+            StatusCode::BAD_REQUEST,
+            "world feed interest delete",
+            "Failed to delete world-feed interest.",
+            err,
+        ),
+    }
+}
+
+async fn handle_world_feed_interest_update(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(interest_id): AxumPath<String>,
+    Json(body): Json<WorldFeedInterestUpdateRequest>,
+) -> impl IntoResponse {
+    if let Some(err) = pairing_auth_error(&state, &headers, "World feed interest update") {
+        return err;
+    }
+    let trimmed_interest_id = interest_id.trim();
+    if trimmed_interest_id.is_empty() {
+        return frontend_error_response(
+            StatusCode::BAD_REQUEST,
+            "WORLD_FEED_INTEREST_ID_REQUIRED",
+            "interest id is required",
+        );
+    }
+    let config_snapshot = state.config.lock().clone();
+    match crate::feed::update_world_feed_interest(
+        &config_snapshot,
+        trimmed_interest_id,
+        body.label.as_deref(),
+        body.keywords_override,
+    )
+    .await
+    {
+        Ok(Some(item)) => (
+            StatusCode::OK,
+            Json(serde_json::json!({
+                "updated": true,
+                "item": item,
+            })),
+        ),
+        Ok(None) => frontend_error_response(
+            StatusCode::NOT_FOUND,
+            "WORLD_FEED_INTEREST_NOT_FOUND",
+            "world-feed interest not found",
+        ),
+        Err(err) => frontend_internal_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "world feed interest update",
+            "Failed to update world-feed interest.",
+            err,
+        ),
+    }
+}
+
+async fn handle_feed_workflow_template_create(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(body): Json<FeedContentAgentCreateBody>,
+) -> impl IntoResponse {
+    if let Some(err) = pairing_auth_error(&state, &headers, "Feed workflow template create") {
+        return err;
+    }
+
+    let name = body
+        .name
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(ToOwned::to_owned);
+    let Some(name) = name else {
+        return frontend_error_response(
+            StatusCode::BAD_REQUEST,
+            "WORKFLOW_TEMPLATE_NAME_REQUIRED",
+            "name is required",
+        );
+    };
+
+    let goal = normalize_goal_text(body.goal.clone().or(body.prompt.clone()));
+    let Some(goal) = goal else {
+        return frontend_error_response(
+            StatusCode::BAD_REQUEST,
+            "WORKFLOW_TEMPLATE_GOAL_REQUIRED",
+            "goal is required",
+        );
+    };
+    let media_capabilities = local_media_capabilities(&state.config.lock().clone());
+    if goal_requests_media_output(&goal)
+        && !(media_capabilities.transcribe_media && media_capabilities.compose_simple_clip)
+    {
+        return frontend_error_response(
+            StatusCode::BAD_REQUEST,
+            "WORKFLOW_TEMPLATE_MEDIA_CAPABILITY_REQUIRED",
+            required_media_capability_reason(media_capabilities),
+        );
+    }
+    let workflow_name = name.clone();
+    let workflow_key = sanitize_workflow_key(&workflow_name);
+
+    let workflow_bot = body
+        .bot_name
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(ToOwned::to_owned)
+        .unwrap_or_else(|| name.clone());
+
+    let mut settings = FeedWorkflowSettings {
+        mode: FeedWorkflowMode::DateRange,
+        days: default_feed_workflow_days(),
+        random_count: default_feed_workflow_random_count(),
+        schedule_enabled: false,
+        schedule_cron: default_workflow_schedule_cron(),
+        schedule_tz: None,
+        goal: Some(goal.clone()),
+        prompt: Some(goal.clone()),
+    };
+    settings = normalize_workflow_settings(settings);
+
+    let workspace_dir = state.config.lock().workspace_dir.clone();
+    let store = match load_or_seed_feed_workflow_settings_store(&workspace_dir) {
+        Ok(store) => store,
+        Err(err) => {
+            return frontend_internal_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "feed workflow template store load",
+                "Failed to load content agent settings.",
+                err,
+            );
+        }
+    };
+    if workflow_definition_by_key(&store, &workflow_key).is_some() {
+        return frontend_error_response(
+            StatusCode::CONFLICT,
+            "WORKFLOW_TEMPLATE_KEY_CONFLICT",
+            "workflow key already exists",
+        );
+    }
+
+    let output_dir_rel = format!("posts/{workflow_key}");
+    let skill_rel = format!("skills/{workflow_key}/SKILL.md");
+    let skill_abs = workspace_dir.join(&skill_rel);
+
+    let skill_body = render_template_skill_markdown(&workflow_name, &goal, &output_dir_rel);
+
+    if let Some(parent) = skill_abs.parent() {
+        if let Err(err) = std::fs::create_dir_all(parent) {
+            return frontend_internal_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "feed workflow template skill dir create",
+                "Failed to create the content agent skill directory.",
+                err,
+            );
+        }
+    }
+    if let Err(err) = std::fs::create_dir_all(workspace_dir.join(&output_dir_rel)) {
+        return frontend_internal_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "feed workflow template output dir create",
+            "Failed to create the content agent output directory.",
+            err,
+        );
+    }
+    if let Err(err) = std::fs::write(&skill_abs, skill_body) {
+        return frontend_internal_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "feed workflow template skill write",
+            "Failed to write the content agent skill.",
+            err,
+        );
+    }
+
+    let creation_skill_markdown = match ensure_workflow_bot_creation_skill(&workspace_dir) {
+        Ok(markdown) => markdown,
+        Err(err) => {
+            return frontend_internal_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
                 "feed workflow template creation skill load",
                 "Failed to load the workflow creation skill.",
                 err,
             );
         }
-    };
-    let creation_prompt = render_content_agent_authoring_prompt(
-        &workflow_name,
-        &workflow_key,
-        &workflow_bot,
-        &skill_rel,
-        &output_dir_rel,
-        &goal,
-        &creation_skill_markdown,
-        &std::fs::read_to_string(&skill_abs).unwrap_or_default(),
-        &local_media_capabilities(&state.config.lock().clone()).summary(),
-    );
+    };
+    let creation_prompt = render_content_agent_authoring_prompt(
+        &workflow_name,
+        &workflow_key,
+        &workflow_bot,
+        &skill_rel,
+        &output_dir_rel,
+        &goal,
+        &creation_skill_markdown,
+        &std::fs::read_to_string(&skill_abs).unwrap_or_default(),
+        &local_media_capabilities(&state.config.lock().clone()).summary(),
+    );
+
+    let creation_thread_id = format!("workflow:create:{workflow_key}");
+    let creation_user_content =
+        format!("[create] goal={goal}; key={workflow_key}; skill={skill_rel}; output={output_dir_rel}");
+    let creation_user_record = match local_store::create_chat_message(
+        &workspace_dir,
+        &creation_thread_id,
+        "user",
+        &creation_user_content,
+        "pending",
+        "workflow-template-create",
+        None,
+        None,
+    ) {
+        Ok(record) => record,
+        Err(err) => {
+            return frontend_internal_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "feed workflow template request persist",
+                "Failed to queue content agent creation.",
+                err,
+            );
+        }
+    };
+    let creation_user_id = creation_user_record
+        .get("id")
+        .and_then(serde_json::Value::as_str)
+        .unwrap_or("")
+        .to_string();
+
+    let enabled = body.enabled.unwrap_or(true);
+    let run_now = enabled && body.run_now.unwrap_or(true);
+    let output_prefix = format!("{output_dir_rel}/");
+
+    let state_for_worker = state.clone();
+    let workspace_for_worker = workspace_dir.clone();
+    let thread_id_for_worker = creation_thread_id.clone();
+    let user_id_for_worker = creation_user_id.clone();
+    let workflow_key_for_worker = workflow_key.clone();
+    let workflow_bot_for_worker = workflow_bot.clone();
+    let skill_rel_for_worker = skill_rel.clone();
+    let output_dir_for_worker = output_dir_rel.clone();
+    let output_prefix_for_worker = output_prefix.clone();
+    let settings_for_worker = settings.clone();
+    let creation_prompt_for_worker = creation_prompt.clone();
+    let goal_for_worker = goal.clone();
+
+    tokio::spawn(async move {
+        let persist_error = |message: &str| {
+            let _ = local_store::create_chat_message(
+                &workspace_for_worker,
+                &thread_id_for_worker,
+                "assistant",
+                "",
+                "error",
+                "workflow-template-create",
+                Some(&user_id_for_worker),
+                Some(message),
+            );
+            if let Err(update_err) = local_store::patch_chat_status(
+                &workspace_for_worker,
+                &user_id_for_worker,
+                "error",
+                Some(message),
+            ) {
+                tracing::warn!(
+                    "Failed to persist workflow-template-create error status: {update_err}"
+                );
+            }
+        };
+
+        if let Err(err) = local_store::patch_chat_status(
+            &workspace_for_worker,
+            &user_id_for_worker,
+            "processing",
+            None,
+        ) {
+            tracing::warn!("Failed to mark workflow-template-create as processing: {err}");
+        }
+
+        let creation_result = tokio::time::timeout(
+            Duration::from_secs(CONTENT_AGENT_TIMEOUT_SECS),
+            run_local_agent_prompt_in_thread(
+                &state_for_worker,
+                &thread_id_for_worker,
+                &creation_prompt_for_worker,
+            ),
+        )
+        .await;
+        let creation_reply = match creation_result {
+            Ok(Ok(reply)) => reply,
+            Ok(Err(err)) => {
+                let err_text = frontend_background_error(
+                    "feed workflow template create agent",
+                    "Content agent creation failed.",
+                    &err,
+                );
+                persist_error(&err_text);
+                return;
+            }
+            Err(_) => {
+                let err_text = format!(
+                    "workflow bot creation agent timed out after {}s",
+                    CONTENT_AGENT_TIMEOUT_SECS
+                );
+                persist_error(&err_text);
+                return;
+            }
+        };
+
+        let skill_abs_for_worker = workspace_for_worker.join(&skill_rel_for_worker);
+        if let Err(err) = validate_content_agent_skill_contract(&skill_abs_for_worker) {
+            let err_text = frontend_background_error(
+                "feed workflow template skill validation",
+                "The generated content agent skill failed validation.",
+                &err,
+            );
+            persist_error(&err_text);
+            return;
+        }
+        let final_skill = match std::fs::read_to_string(&skill_abs_for_worker) {
+            Ok(raw) => raw,
+            Err(err) => {
+                let err_text = frontend_background_error(
+                    "feed workflow template skill read",
+                    "Failed to read the generated content agent skill.",
+                    &err,
+                );
+                persist_error(&err_text);
+                return;
+            }
+        };
+        if final_skill.trim().is_empty() {
+            persist_error("content agent creation produced an empty skill file");
+            return;
+        }
+
+        let mut worker_store = match load_or_seed_feed_workflow_settings_store(&workspace_for_worker) {
+            Ok(store) => store,
+            Err(err) => {
+                let err_text = frontend_background_error(
+                    "feed workflow template worker store load",
+                    "Failed to load content agent settings.",
+                    &err,
+                );
+                persist_error(&err_text);
+                return;
+            }
+        };
+        if workflow_definition_by_key(&worker_store, &workflow_key_for_worker).is_some() {
+            persist_error("workflow key already exists");
+            return;
+        }
+
+        let mut workflow_record = FeedContentAgentRecord {
+            workflow_key: workflow_key_for_worker.clone(),
+            workflow_bot: workflow_bot_for_worker.clone(),
+            skill_path: skill_rel_for_worker.clone(),
+            output_prefix: output_prefix_for_worker.clone(),
+            enabled,
+            editable_files: vec![skill_rel_for_worker.clone()],
+            goal: Some(goal_for_worker.clone()),
+            last_triggered_at: None,
+            last_run_at: None,
+            last_triggered_source_updated_at: None,
+            built_in_skill_fingerprint: None,
+            visible_in_ui: true,
+            settings: settings_for_worker.clone(),
+        };
+        workflow_record = normalize_workflow_record(&workflow_key_for_worker, workflow_record);
+        let workflow_def = feed_workflow_definition_from_record(&workflow_record);
+
+        worker_store
+            .workflows
+            .insert(workflow_key_for_worker.clone(), workflow_record);
+        if let Err(err) = save_feed_workflow_settings_store(&workspace_for_worker, &worker_store) {
+            let err_text = frontend_background_error(
+                "feed workflow template worker store persist",
+                "Failed to save content agent settings.",
+                &err,
+            );
+            persist_error(&err_text);
+            return;
+        }
+
+        let run_thread_id = if run_now {
+            match queue_workflow_run(
+                state_for_worker.clone(),
+                workflow_def,
+                "workflow-template-create",
+            ) {
+                Ok(thread_id) => Some(thread_id),
+                Err(err) => {
+                    tracing::warn!("Failed to queue workflow run after template creation: {err}");
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        let mut summary = format!(
+            "Content agent `{}` ({}) created.\n\nSkill: `{}`\nOutput: `{}`\n",
+            workflow_bot_for_worker,
+            workflow_key_for_worker,
+            skill_rel_for_worker,
+            output_dir_for_worker
+        );
+        if let Some(thread_id) = &run_thread_id {
+            summary.push_str(&format!("\nInitial run queued: `{thread_id}`\n"));
+        } else if run_now {
+            summary.push_str("\nInitial run requested, but queueing failed.\n");
+        } else {
+            summary.push_str("\nInitial run not requested.\n");
+        }
+        let reply_preview = truncate_with_ellipsis(creation_reply.trim(), 1200);
+        if !reply_preview.is_empty() {
+            summary.push_str("\nAgent summary:\n");
+            summary.push_str(&reply_preview);
+        }
+
+        if let Err(err) = local_store::create_chat_message(
+            &workspace_for_worker,
+            &thread_id_for_worker,
+            "assistant",
+            &summary,
+            "done",
+            "workflow-template-create",
+            Some(&user_id_for_worker),
+            None,
+        ) {
+            tracing::warn!("Failed to persist workflow-template-create success message: {err}");
+        }
+        if let Err(err) =
+            local_store::patch_chat_status(&workspace_for_worker, &user_id_for_worker, "done", None)
+        {
+            tracing::warn!("Failed to mark workflow-template-create as done: {err}");
+        }
+    });
+
+    (
+        StatusCode::OK,
+        Json(serde_json::json!({
+            "created": false,
+            "queued": true,
+            "threadId": creation_thread_id,
+            "messageId": creation_user_id,
+            "workflowKey": workflow_key,
+            "workflowBot": workflow_bot,
+            "skillPath": skill_rel,
+            "outputDir": output_dir_rel,
+            "outputPrefix": output_prefix,
+            "runQueued": false,
+            "runThreadId": serde_json::Value::Null,
+            "creationSummary": "Content agent creation queued.",
+        })),
+    )
+}
+
+async fn handle_feed_workflow_comment(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(body): Json<FeedContentAgentCommentBody>,
+) -> impl IntoResponse {
+    if let Some(err) = pairing_auth_error(&state, &headers, "Feed workflow comment") {
+        return err;
+    }
+
+    let requested_path = body.path.trim().trim_start_matches('/').to_string();
+    let comment = body.comment.trim();
+    if requested_path.is_empty() || comment.is_empty() {
+        return frontend_error_response(
+            StatusCode::BAD_REQUEST,
+            "WORKFLOW_COMMENT_INVALID_REQUEST",
+            "path and comment are required",
+        );
+    }
+    if comment.chars().count() > 1500 {
+        return frontend_error_response(
+            StatusCode::BAD_REQUEST,
+            "WORKFLOW_COMMENT_TOO_LONG",
+            "comment is too long (max 1500 characters)",
+        );
+    }
+    if !requested_path.starts_with("posts/") {
+        return frontend_error_response(
+            StatusCode::BAD_REQUEST,
+            "WORKFLOW_COMMENT_PATH_UNSUPPORTED",
+            "workflow comments are only supported for posts/* feed items",
+        );
+    }
+
+    let workspace_dir = state.config.lock().workspace_dir.clone();
+    let store = load_or_seed_feed_workflow_settings_store(&workspace_dir).unwrap_or_default();
+    let synth_skill_store = workspace_synthesizer::load_or_seed_skill_store(&workspace_dir)
+        .unwrap_or_default();
+    let workflow = workflow_for_feed_path(&store, &requested_path).or_else(|| {
+        workspace_synthesizer::skill_for_feed_path(&synth_skill_store, &requested_path)
+            .map(|skill| workspace_synth_skill_as_workflow_definition(&skill))
+    });
+    let Some(workflow) = workflow else {
+        let mut supported_prefixes: Vec<String> = workflow_definitions(&store)
+            .into_iter()
+            .map(|item| item.output_prefix)
+            .collect();
+        supported_prefixes.extend(
+            workspace_synthesizer::skill_definitions(&synth_skill_store)
+                .into_iter()
+                .map(|item| item.output_prefix),
+        );
+        let err = serde_json::json!({
+            "supportedPrefixes": supported_prefixes,
+        });
+        return frontend_error_response_with_meta(
+            StatusCode::BAD_REQUEST,
+            "WORKFLOW_COMMENT_TARGET_UNMAPPED",
+            "No editable workflow is mapped to this feed path yet",
+            err,
+        );
+    };
+
+    let Ok(resolved_target) = resolve_workspace_text_path(&workspace_dir, &requested_path) else {
+        return frontend_error_response(
+            StatusCode::BAD_REQUEST,
+            "WORKFLOW_COMMENT_TARGET_INVALID",
+            "invalid feed item path",
+        );
+    };
+    if !resolved_target.exists() || !resolved_target.is_file() {
+        return frontend_error_response(
+            StatusCode::NOT_FOUND,
+            "WORKFLOW_COMMENT_TARGET_NOT_FOUND",
+            "feed item file not found",
+        );
+    }
+
+    let quickfix_result =
+        maybe_apply_workflow_comment_quickfix(&workspace_dir, &workflow, &requested_path, comment);
+    match quickfix_result {
+        Ok(Some(quickfix_message)) => {
+            let _ = crate::feed::mark_world_feed_dirty(&workspace_dir);
+            let thread_id = format!("workflow:{}", workflow.key);
+            let user_content = format!("[{requested_path}] {comment}");
+            let user_record = match local_store::create_chat_message(
+                &workspace_dir,
+                &thread_id,
+                "user",
+                &user_content,
+                "done",
+                "feed-comment",
+                None,
+                None,
+            ) {
+                Ok(record) => record,
+                Err(err) => {
+                    return frontend_internal_error(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        "feed workflow quickfix persist",
+                        "Failed to save the workflow quickfix request.",
+                        err,
+                    );
+                }
+            };
+            let user_id = user_record
+                .get("id")
+                .and_then(serde_json::Value::as_str)
+                .unwrap_or("")
+                .to_string();
+            if let Err(err) = local_store::create_chat_message(
+                &workspace_dir,
+                &thread_id,
+                "assistant",
+                &quickfix_message,
+                "done",
+                "workflow-quickfix",
+                Some(&user_id),
+                None,
+            ) {
+                tracing::warn!("Failed to persist workflow quickfix assistant message: {err}");
+            }
+
+            return (
+                StatusCode::OK,
+                Json(serde_json::json!({
+                    "queued": false,
+                    "threadId": thread_id,
+                    "workflowKey": workflow.key,
+                    "workflowBot": workflow.bot_name,
+                    "editableFiles": workflow.editable_files,
+                    "messageId": user_id,
+                    "message": quickfix_message,
+                    "quickfixApplied": true,
+                })),
+            );
+        }
+        Ok(None) => {}
+        Err(err) => {
+            tracing::warn!(
+                "workflow quickfix check failed for {} on {}: {err}",
+                workflow.key,
+                requested_path
+            );
+        }
+    }
+
+    let thread_id = format!("workflow:{}", workflow.key);
+    let user_content = format!("[{requested_path}] {comment}");
+    let user_record = match local_store::create_chat_message(
+        &workspace_dir,
+        &thread_id,
+        "user",
+        &user_content,
+        "pending",
+        "feed-comment",
+        None,
+        None,
+    ) {
+        Ok(record) => record,
+        Err(err) => {
+            return frontend_internal_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "feed workflow comment persist",
+                "Failed to queue the workflow comment.",
+                err,
+            );
+        }
+    };
+
+    let user_id = user_record
+        .get("id")
+        .and_then(serde_json::Value::as_str)
+        .unwrap_or("")
+        .to_string();
+    let prompt = workflow_comment_prompt(&workflow, &requested_path, comment);
+    let thread_id_for_worker = thread_id.clone();
+    let workspace_for_worker = workspace_dir.clone();
+    let state_for_worker = state.clone();
+    let user_id_for_worker = user_id.clone();
+
+    tokio::spawn(async move {
+        if let Err(err) =
+            local_store::patch_chat_status(
+                &workspace_for_worker,
+                &user_id_for_worker,
+                "processing",
+                None,
+            )
+        {
+            tracing::warn!("Failed to update feed-comment status to processing: {err}");
+        }
+
+        let channel_ctx = crate::channels::ChannelExecutionContext::new(
+            "local",
+            thread_id_for_worker.clone(),
+            Some(thread_id_for_worker.clone()),
+        );
+        let config = state_for_worker.config.lock().clone();
+        let result = crate::channels::with_channel_execution_context(
+            channel_ctx,
+            run_gateway_ui_chat_with_tools(config, &prompt),
+        )
+        .await;
+
+        match result {
+            Ok(reply) => {
+                let _ = crate::feed::mark_world_feed_dirty(&workspace_for_worker);
+                let reply_text = if reply.trim().is_empty() {
+                    "Workflow update applied."
+                } else {
+                    reply.trim()
+                };
+                if let Err(err) = local_store::create_chat_message(
+                    &workspace_for_worker,
+                    &thread_id_for_worker,
+                    "assistant",
+                    reply_text,
+                    "done",
+                    "workflow-modifier",
+                    Some(&user_id_for_worker),
+                    None,
+                ) {
+                    tracing::warn!("Failed to persist workflow-modifier reply: {err}");
+                }
+                if let Err(err) = local_store::patch_chat_status(
+                    &workspace_for_worker,
+                    &user_id_for_worker,
+                    "done",
+                    None,
+                ) {
+                    tracing::warn!("Failed to mark feed-comment as done: {err}");
+                }
+            }
+            Err(err) => {
+                let err_text = frontend_background_error(
+                    "feed workflow comment worker",
+                    "Comment processing failed.",
+                    &err,
+                );
+                let _ = local_store::create_chat_message(
+                    &workspace_for_worker,
+                    &thread_id_for_worker,
+                    "assistant",
+                    "",
+                    "error",
+                    "workflow-modifier",
+                    Some(&user_id_for_worker),
+                    Some(&err_text),
+                );
+                if let Err(update_err) = local_store::patch_chat_status(
+                    &workspace_for_worker,
+                    &user_id_for_worker,
+                    "error",
+                    Some(&err_text),
+                ) {
+                    tracing::warn!(
+                        "Failed to mark feed-comment as error after workflow-modifier failure: {update_err}"
+                    );
+                }
+            }
+        }
+    });
+
+    (
+        StatusCode::OK,
+        Json(serde_json::json!({
+            "queued": true,
+            "threadId": thread_id,
+            "workflowKey": workflow.key,
+            "workflowBot": workflow.bot_name,
+            "editableFiles": workflow.editable_files,
+            "messageId": user_id,
+            "message": format!("Queued update for {}", workflow.bot_name),
+        })),
+    )
+}
+
+async fn handle_drafts_list(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<DraftListQuery>,
+) -> impl IntoResponse {
+    if let Some(err) = pairing_auth_error(&state, &headers, "Drafts API") {
+        return err;
+    }
+    let workspace_dir = state.config.lock().workspace_dir.clone();
+    let limit = query.limit.unwrap_or(20).clamp(1, 200);
+    let status = query
+        .status
+        .as_deref()
+        .map(str::trim)
+        .filter(|status| !status.is_empty());
+    match local_store::list_drafts(&workspace_dir, limit, status) {
+        Ok(items) => (StatusCode::OK, Json(serde_json::json!({ "items": items }))),
+        Err(err) => frontend_internal_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "draft list",
+            "Failed to load drafts.",
+            err,
+        ),
+    }
+}
+
+async fn handle_drafts_upsert(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(body): Json<DraftUpsertBody>,
+) -> impl IntoResponse {
+    if let Some(err) = pairing_auth_error(&state, &headers, "Drafts API") {
+        return err;
+    }
+    let workspace_dir = state.config.lock().workspace_dir.clone();
+    let payload = local_store::DraftUpsert {
+        id: body.id,
+        text: body.text.unwrap_or_default(),
+        video_name: body.video_name.unwrap_or_default(),
+        created_at_client: body.created_at_client,
+        updated_at_client: body.updated_at_client,
+    };
+    match local_store::upsert_draft(&workspace_dir, &payload) {
+        Ok(record) => (StatusCode::OK, Json(record)),
+        Err(err) => frontend_internal_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "draft upsert",
+            "Failed to save the draft.",
+            err,
+        ),
+    }
+}
+
+fn draft_decision_response(
+    decision: anyhow::Result<post_drafts::Decision>,
+    scope: &str,
+) -> (StatusCode, Json<serde_json::Value>) {
+    match decision {
+        Ok(post_drafts::Decision::Missing) => frontend_error_response(
+            StatusCode::NOT_FOUND,
+            "DRAFT_NOT_FOUND",
+            "Draft not found",
+        ),
+        Ok(post_drafts::Decision::Conflict(status)) => frontend_error_response(
+            StatusCode::CONFLICT,
+            "DRAFT_NOT_PENDING",
+            format!("Draft is {status}, not pending"),
+        ),
+        Ok(post_drafts::Decision::Done(draft)) => {
+            if draft.get("status").and_then(serde_json::Value::as_str) == Some("failed") {
+                let error = draft
+                    .get("error")
+                    .and_then(serde_json::Value::as_str)
+                    .unwrap_or("Post failed")
+                    .to_string();
+                return frontend_error_response_with_meta(
+                    StatusCode::BAD_GATEWAY,
+                    "DRAFT_SEND_FAILED",
+                    error,
+                    serde_json::json!({ "draft": draft }),
+                );
+            }
+            (StatusCode::OK, Json(draft))
+        }
+        Err(err) => frontend_internal_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            scope,
+            "Failed to update the draft.",
+            err,
+        ),
+    }
+}
+
+/// Publish a pending post draft through its channel. Any paired token may
+/// approve; the gateway has no narrower scopes.
+async fn handle_draft_approve(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(id): AxumPath<String>,
+) -> impl IntoResponse {
+    if let Some(err) = paired_device_auth_error(&state, &headers, "Drafts API") {
+        return err;
+    }
+    let workspace_dir = state.config.lock().workspace_dir.clone();
+    let bluesky = crate::bluesky::BlueskyClient::from_env(&workspace_dir);
+    let decision = post_drafts::approve(&workspace_dir, id.trim(), bluesky.as_ref()).await;
+    draft_decision_response(decision, "draft approve")
+}
+
+async fn handle_draft_reject(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(id): AxumPath<String>,
+) -> impl IntoResponse {
+    if let Some(err) = paired_device_auth_error(&state, &headers, "Drafts API") {
+        return err;
+    }
+    let workspace_dir = state.config.lock().workspace_dir.clone();
+    let decision = tokio::task::spawn_blocking(move || {
+        post_drafts::reject(&workspace_dir, id.trim())
+    })
+    .await
+    .unwrap_or_else(|err| Err(anyhow::anyhow!(err)));
+    draft_decision_response(decision, "draft reject")
+}
+
+async fn handle_approvals_list(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<ApprovalListQuery>,
+) -> impl IntoResponse {
+    if let Some(err) = pairing_auth_error(&state, &headers, "Approvals API") {
+        return err;
+    }
+    let workspace_dir = state.config.lock().workspace_dir.clone();
+    let limit = query.limit.unwrap_or(20).clamp(1, 200);
+    let status = query
+        .status
+        .as_deref()
+        .map(str::trim)
+        .filter(|status| !status.is_empty());
+    match crate::approval::chat::list(&workspace_dir, limit, status) {
+        Ok(items) => (StatusCode::OK, Json(serde_json::json!({ "items": items }))),
+        Err(err) => frontend_internal_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "approval list",
+            "Failed to load approvals.",
+            err,
+        ),
+    }
+}
+
+/// Deciding a parked tool call needs a paired bearer token even when pairing
+/// is optional for the rest of the API. Paired tokens carry full scope.
+fn approval_auth_error(
+    state: &AppState,
+    headers: &HeaderMap,
+) -> Option<(StatusCode, Json<serde_json::Value>)> {
+    if !state.pairing.require_pairing() {
+        return Some(frontend_error_response(
+            StatusCode::FORBIDDEN,
+            "APPROVALS_REQUIRE_PAIRING",
+            "Tool approvals over the API need gateway pairing; reply `approve <id>` in chat instead",
+        ));
+    }
+    paired_device_auth_error(state, headers, "Approvals API")
+}
+
+async fn approval_decision_response(
+    state: &AppState,
+    id: &str,
+    approve: bool,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let workspace_dir = state.config.lock().workspace_dir.clone();
+    let id = id.trim().to_string();
+    let decision = tokio::task::spawn_blocking(move || {
+        crate::approval::chat::decide(&workspace_dir, &id, approve, "api")
+    })
+    .await
+    .unwrap_or_else(|err| Err(anyhow::anyhow!(err)));
+    match decision {
+        Ok(crate::approval::chat::Decision::Missing) => frontend_error_response(
+            StatusCode::NOT_FOUND,
+            "APPROVAL_NOT_FOUND",
+            "Approval not found",
+        ),
+        Ok(crate::approval::chat::Decision::Conflict(status)) => frontend_error_response(
+            StatusCode::CONFLICT,
+            "APPROVAL_NOT_PENDING",
+            format!("Approval is {status}, not pending"),
+        ),
+        Ok(crate::approval::chat::Decision::Done(record)) => (StatusCode::OK, Json(record)),
+        Err(err) => frontend_internal_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "approval decision",
+            "Failed to update the approval.",
+            err,
+        ),
+    }
+}
+
+/// Let a parked tool call run; the waiting agent turn resumes.
+async fn handle_approval_approve(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(id): AxumPath<String>,
+) -> impl IntoResponse {
+    if let Some(err) = approval_auth_error(&state, &headers) {
+        return err;
+    }
+    approval_decision_response(&state, &id, true).await
+}
+
+async fn handle_approval_deny(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(id): AxumPath<String>,
+) -> impl IntoResponse {
+    if let Some(err) = approval_auth_error(&state, &headers) {
+        return err;
+    }
+    approval_decision_response(&state, &id, false).await
+}
+
+async fn handle_post_history_list(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<PostHistoryListQuery>,
+) -> impl IntoResponse {
+    if let Some(err) = pairing_auth_error(&state, &headers, "Post history API") {
+        return err;
+    }
+    let workspace_dir = state.config.lock().workspace_dir.clone();
+    let limit = query.limit.unwrap_or(50).clamp(1, 500);
+    match local_store::list_post_history(&workspace_dir, limit) {
+        Ok(items) => (StatusCode::OK, Json(serde_json::json!({ "items": items }))),
+        Err(err) => frontend_internal_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "post history list",
+            "Failed to load post history.",
+            err,
+        ),
+    }
+}
+
+async fn handle_post_history_create(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(body): Json<PostHistoryCreateBody>,
+) -> impl IntoResponse {
+    if let Some(err) = pairing_auth_error(&state, &headers, "Post history API") {
+        return err;
+    }
+    let workspace_dir = state.config.lock().workspace_dir.clone();
+    let payload = local_store::PostHistoryInput {
+        provider: body.provider.unwrap_or_else(|| "bluesky".to_string()),
+        text: body.text.unwrap_or_default(),
+        video_name: body.video_name.unwrap_or_default(),
+        source_path: body.source_path.unwrap_or_default(),
+        uri: body.uri.unwrap_or_default(),
+        cid: body.cid.unwrap_or_default(),
+        status: body.status.unwrap_or_else(|| "success".to_string()),
+        error: body.error.unwrap_or_default(),
+        created_at_client: body.created_at_client,
+    };
+    match local_store::create_post_history(&workspace_dir, &payload) {
+        Ok(record) => (StatusCode::OK, Json(record)),
+        Err(err) => frontend_internal_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "post history create",
+            "Failed to save post history.",
+            err,
+        ),
+    }
+}
+
+/// An unexpired API key. Which routes it may call is checked earlier, by
+/// `authorize_api_key_requests`, for every request.
+fn api_key_authenticated(state: &AppState, token: &str) -> bool {
+    if !token.starts_with(api_keys::KEY_PREFIX) {
+        return false;
+    }
+    let workspace_dir = state.config.lock().workspace_dir.clone();
+    api_keys::store(&workspace_dir)
+        .authenticate(token)
+        .is_some()
+}
+
+/// Like [`pairing_auth_error`], but only a paired device token will do.
+/// For endpoints that need full scope (pairing codes, API keys, approvals,
+/// memory export and wipes, provider reset, draft decisions), so an API key
+/// is refused whatever its routes say.
+fn paired_device_auth_error(
+    state: &AppState,
+    headers: &HeaderMap,
+    scope: &str,
+) -> Option<(StatusCode, Json<serde_json::Value>)> {
+    if let Some(err) = pairing_auth_error(state, headers, scope) {
+        return Some(err);
+    }
+    api_keys::bearer_key(headers).map(|_| {
+        tracing::warn!("{scope}: rejected — API keys cannot call this endpoint");
+        frontend_error_response(
+            StatusCode::FORBIDDEN,
+            "API_KEY_FORBIDDEN",
+            format!("{scope} needs a paired device token, not an API key"),
+        )
+    })
+}
+
+fn pairing_auth_error(
+    state: &AppState,
+    headers: &HeaderMap,
+    scope: &str,
+) -> Option<(StatusCode, Json<serde_json::Value>)> {
+    if !state.pairing.require_pairing() {
+        return None;
+    }
+    let auth = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("");
+    let token = auth.strip_prefix("Bearer ").unwrap_or("");
+    if state.pairing.is_authenticated(token) || api_key_authenticated(state, token) {
+        return None;
+    }
+    tracing::warn!("{scope}: rejected — not paired / invalid bearer token");
+    Some(frontend_error_response(
+        StatusCode::UNAUTHORIZED,
+        "PAIRING_REQUIRED",
+        "Unauthorized — pair first via POST /pair, then send Authorization: Bearer <token>",
+    ))
+}
+
+#[derive(Default, serde::Deserialize)]
+struct MediaUploadQuery {
+    kind: Option<String>,
+    filename: Option<String>,
+    title: Option<String>,
+    source: Option<String>,
+    entry_id: Option<String>,
+    /// `describe=1` queues an `image_describe` job for image uploads.
+    describe: Option<String>,
+    /// Comma-separated tags; the `X-Tags` header is accepted as well.
+    tags: Option<String>,
+    /// `strict=1` rejects uploads whose content contradicts the declared
+    /// kind or content type instead of reclassifying them.
+    strict: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+struct JournalTextBody {
+    title: Option<String>,
+    content: String,
+    source: Option<String>,
+    tags: Option<Vec<String>>,
+    template: Option<String>,
+    #[serde(default)]
+    mode: JournalTextMode,
+}
+
+/// `create` writes a new note per request; `append_daily` adds a timestamped
+/// section to today's `daily.md`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum JournalTextMode {
+    #[default]
+    Create,
+    AppendDaily,
+}
+
+#[derive(serde::Deserialize)]
+struct LibraryItemsQuery {
+    scope: Option<String>,
+    limit: Option<usize>,
+    tag: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+struct LibraryTextQuery {
+    path: String,
+    /// `html` adds sanitized rendered Markdown to the response.
+    render: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+struct SaveTextBody {
+    path: String,
+    content: String,
+    /// `revision` from the read this edit started from. When it no longer
+    /// matches the file, the save is refused with 409 instead of clobbering
+    /// an edit made elsewhere. Omitted means last write wins.
+    #[serde(default)]
+    base_revision: Option<String>,
+    /// Save over a conflicting edit (the overwritten text is still versioned).
+    #[serde(default)]
+    force: bool,
+}
+
+#[derive(serde::Deserialize)]
+struct DeleteLibraryBody {
+    path: String,
+}
+
+#[derive(serde::Deserialize)]
+struct VersionRestoreBody {
+    path: String,
+    id: String,
+}
+
+#[derive(serde::Deserialize)]
+struct LibraryHistoryQuery {
+    path: String,
+    limit: Option<usize>,
+}
+
+#[derive(serde::Deserialize)]
+struct LibraryRevertBody {
+    path: String,
+    commit: String,
+}
+
+#[derive(serde::Deserialize)]
+struct LibraryTagsBody {
+    path: String,
+    #[serde(default)]
+    add: Vec<String>,
+    #[serde(default)]
+    remove: Vec<String>,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JournalTranscribeBody {
+    media_path: String,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RuntimeConfigUpdateBody {
+    default_provider: String,
+    default_model: String,
+    transcription_enabled: bool,
+    transcription_model: Option<String>,
+    api_key: Option<String>,
+}
+
+fn available_local_transcription_models() -> Vec<String> {
+    let (cache_key, models) = compute_available_transcription_models();
+    let cache = TRANSCRIPTION_MODEL_CACHE.get_or_init(|| Mutex::new(None));
+    let mut guard = cache.lock();
+    if let Some(entry) = guard.as_ref() {
+        let fresh = entry.cached_at.elapsed() < Duration::from_secs(TRANSCRIPTION_MODEL_CACHE_TTL_SECS);
+        if fresh && entry.cache_key == cache_key {
+            return entry.models.clone();
+        }
+    }
+    let models_vec: Vec<String> = models.into_iter().collect();
+    *guard = Some(TranscriptionModelCacheEntry {
+        cache_key,
+        models: models_vec.clone(),
+        cached_at: Instant::now(),
+    });
+    models_vec
+}
+
+fn compute_available_transcription_models() -> (String, BTreeSet<String>) {
+    let roots = transcription_cache_roots();
+    let mut signature_parts: Vec<String> = Vec::new();
+    let mut models = BTreeSet::new();
+
+    for root in roots {
+        let root_display = root.display().to_string();
+        if !root.exists() || !root.is_dir() {
+            signature_parts.push(format!("{root_display}:missing"));
+            continue;
+        }
+        let read = match std::fs::read_dir(&root) {
+            Ok(entries) => entries,
+            Err(_) => {
+                signature_parts.push(format!("{root_display}:unreadable"));
+                continue;
+            }
+        };
+
+        for entry in read.flatten() {
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            if !file_type.is_dir() {
+                continue;
+            }
+            let dir_name = entry.file_name().to_string_lossy().to_string();
+            let lower = dir_name.to_ascii_lowercase();
+            if !(lower.starts_with("models--") && lower.contains("faster-whisper")) {
+                continue;
+            }
+            let modified = entry
+                .metadata()
+                .ok()
+                .and_then(|m| m.modified().ok())
+                .and_then(|ts| ts.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |dur| dur.as_secs());
+            signature_parts.push(format!("{root_display}:{dir_name}:{modified}"));
+
+            if lower.contains("faster-whisper-large-v3-turbo") {
+                models.insert("whisper-large-v3-turbo".to_string());
+                models.insert("large-v3".to_string());
+            }
+            if lower.contains("faster-whisper-large-v3") {
+                models.insert("large-v3".to_string());
+            }
+            if lower.contains("faster-whisper-large-v2") {
+                models.insert("large-v2".to_string());
+            }
+            if lower.contains("faster-whisper-large-v1") {
+                models.insert("large-v1".to_string());
+            }
+            if lower.contains("faster-whisper-large") {
+                models.insert("large".to_string());
+            }
+            if lower.contains("faster-whisper-medium") {
+                models.insert("medium".to_string());
+            }
+            if lower.contains("faster-whisper-small") {
+                models.insert("small".to_string());
+            }
+            if lower.contains("faster-whisper-base") {
+                models.insert("base".to_string());
+            }
+            if lower.contains("faster-whisper-tiny") {
+                models.insert("tiny".to_string());
+            }
+        }
+    }
+
+    signature_parts.sort();
+    let cache_key = signature_parts.join("|");
+    (cache_key, models)
+}
+
+fn transcription_cache_roots() -> Vec<PathBuf> {
+    let mut roots: Vec<PathBuf> = Vec::new();
+    if let Some(home) = std::env::var("HOME")
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+    {
+        roots.push(PathBuf::from(home).join(".cache/huggingface/hub"));
+    }
+    if let Some(hf_home) = std::env::var("HF_HOME")
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+    {
+        roots.push(PathBuf::from(hf_home).join("hub"));
+    }
+    roots
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JournalTranscribeStatusQuery {
+    media_path: String,
+}
+
+async fn storage_snapshot(state: &AppState) -> storage::StorageSnapshot {
+    let (workspace_dir, quota, min_free) = {
+        let config = state.config.lock();
+        (
+            config.workspace_dir.clone(),
+            config.gateway.media_quota_bytes,
+            config.gateway.min_free_disk_bytes,
+        )
+    };
+    let fallback_dir = workspace_dir.clone();
+    tokio::task::spawn_blocking(move || storage::snapshot(&workspace_dir, quota, min_free))
+        .await
+        .unwrap_or_else(|_| storage::StorageSnapshot {
+            media_bytes: 0,
+            media_quota_bytes: quota,
+            available_bytes: storage::available_disk_bytes(&fallback_dir),
+            min_free_bytes: min_free,
+        })
+}
+
+fn storage_limit_response(
+    limit: storage::StorageLimit,
+    usage: &storage::StorageSnapshot,
+) -> axum::response::Response {
+    frontend_error_response_with_meta(
+        StatusCode::INSUFFICIENT_STORAGE,
+        limit.code(),
+        limit.message(),
+        serde_json::json!({ "storage": usage }),
+    )
+    .into_response()
+}
+
+/// Response for a failed [`atomic_write_async`]. The previous file is intact
+/// either way; a full disk is `507` like the upload guard, and an unwritable
+/// workspace gets its own code so the UI can say so.
+fn write_error_response(
+    err: WriteError,
+    context: &str,
+    user_message: &str,
+) -> axum::response::Response {
+    match &err {
+        WriteError::StorageFull { .. } => {
+            tracing::warn!(context, error = %err, "Workspace write failed");
+            frontend_error_response(
+                StatusCode::INSUFFICIENT_STORAGE,
+                storage::StorageLimit::DiskSpace.code(),
+                "Not enough free disk space to save.",
+            )
+            .into_response()
+        }
+        WriteError::PermissionDenied { .. } => {
+            tracing::warn!(context, error = %err, "Workspace write failed");
+            frontend_error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "WORKSPACE_NOT_WRITABLE",
+                "The workspace folder is not writable.",
+            )
+            .into_response()
+        }
+        WriteError::Io { .. } => frontend_internal_error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            context,
+            user_message,
+            err,
+        ),
+    }
+}
+
+/// GET /api/storage — media usage, quota, and free disk space for the UI
+async fn handle_storage(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> axum::response::Response {
+    if let Some(err) = pairing_auth_error(&state, &headers, "Storage") {
+        return err.into_response();
+    }
+    let usage = storage_snapshot(&state).await;
+    let body = serde_json::json!({
+        "storage": usage,
+        "uploadAllowanceBytes": usage.upload_allowance().map(|(bytes, _)| bytes),
+    });
+    (StatusCode::OK, Json(body)).into_response()
+}
+
+async fn handle_media_upload(
+    State(state): State<AppState>,
+    Query(query): Query<MediaUploadQuery>,
+    req: Request,
+) -> axum::response::Response {
+    if let Some(err) = pairing_auth_error(&state, req.headers(), "Media upload") {
+        return err.into_response();
+    }
+
+    let headers = req.headers().clone();
+    let declared_content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("application/octet-stream")
+        .to_string();
+
+    let declared_kind = query
+        .kind
+        .as_deref()
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .unwrap_or_else(|| infer_media_kind_from_content_type(&declared_content_type));
+    let strict = query
+        .strict
+        .as_deref()
+        .map(str::trim)
+        .is_some_and(|v| v == "1" || v.eq_ignore_ascii_case("true"));
+    let source = query
+        .source
+        .as_deref()
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .unwrap_or("mobile");
+    let title = query
+        .title
+        .as_deref()
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(ToOwned::to_owned);
+    let original_name = query
+        .filename
+        .clone()
+        .or_else(|| {
+            headers
+                .get("X-File-Name")
+                .and_then(|v| v.to_str().ok())
+                .map(ToOwned::to_owned)
+        })
+        .unwrap_or_else(|| format!("upload-{}", Uuid::new_v4()));
+
+    let tags = query
+        .tags
+        .clone()
+        .or_else(|| {
+            headers
+                .get("X-Tags")
+                .and_then(|v| v.to_str().ok())
+                .map(ToOwned::to_owned)
+        })
+        .map(|raw| library_tags::parse_tag_list(&raw))
+        .unwrap_or_default();
+
+    let workspace_dir = state.config.lock().workspace_dir.clone();
+    let storage_usage = storage_snapshot(&state).await;
+    let declared_len = headers
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<u64>().ok());
+    if let Some(limit) = declared_len.and_then(|len| storage_usage.check_upload(len)) {
+        return storage_limit_response(limit, &storage_usage);
+    }
+    let upload_allowance = storage_usage.upload_allowance();
+
+    // Buffer the start of the stream so the stored kind comes from the
+    // content, not the label.
+    let mut body = req.into_body();
+    let mut head: Vec<u8> = Vec::new();
+    let mut body_done = false;
+    while head.len() < media_sniff::SNIFF_LEN {
+        match body.frame().await {
+            Some(Ok(frame)) => {
+                if let Some(data) = frame.data_ref() {
+                    head.extend_from_slice(data);
+                }
+            }
+            Some(Err(err)) => {
+                return frontend_internal_error_response(
+                    StatusCode::BAD_REQUEST,
+                    "media upload stream",
+                    "The upload stream could not be read.",
+                    err,
+                );
+            }
+            None => {
+                body_done = true;
+                break;
+            }
+        }
+    }
+    let sniffed = media_sniff::sniff(&head, declared_kind);
+    if let Some(sniffed) = sniffed {
+        if strict && media_sniff::contradicts(declared_kind, &declared_content_type, sniffed) {
+            return frontend_error_response_with_meta(
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                "MEDIA_KIND_MISMATCH",
+                format!(
+                    "Upload content is {} ({}), not the declared {declared_kind}.",
+                    sniffed.kind, sniffed.mime
+                ),
+                serde_json::json!({
+                    "declaredKind": declared_kind,
+                    "declaredContentType": declared_content_type,
+                    "sniffedKind": sniffed.kind,
+                    "sniffedContentType": sniffed.mime,
+                }),
+            )
+            .into_response();
+        }
+    }
+    let kind = sniffed.map_or(declared_kind, |s| s.kind);
+    let content_type = sniffed.map_or_else(
+        || declared_content_type.clone(),
+        |s| s.mime.to_string(),
+    );
+    let kind_corrected = !kind.eq_ignore_ascii_case(declared_kind);
+    if kind_corrected {
+        tracing::info!(
+            declared_kind,
+            sniffed_kind = kind,
+            content_type = %content_type,
+            "Media upload reclassified from its content"
+        );
+    }
+
+    let rel_path = media_storage_rel_path(&workspace_dir, kind, &original_name);
+    let abs_path = workspace_dir.join(&rel_path);
+    if let Some(parent) = abs_path.parent() {
+        if let Err(err) = tokio::fs::create_dir_all(parent).await {
+            return frontend_internal_error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "media upload dir create",
+                "Failed to prepare media storage for the upload.",
+                err,
+            );
+        }
+    }
+
+    let mut file = match tokio::fs::File::create(&abs_path).await {
+        Ok(f) => f,
+        Err(err) => {
+            return frontend_internal_error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "media upload file create",
+                "Failed to create the uploaded media file.",
+                err,
+            );
+        }
+    };
+
+    let mut bytes_written: u64 = 0;
+    let mut pending = Some(axum::body::Bytes::from(head));
+    loop {
+        let data = match pending.take() {
+            Some(data) => data,
+            None if body_done => break,
+            None => match body.frame().await {
+                None => break,
+                Some(Ok(frame)) => match frame.into_data() {
+                    Ok(data) => data,
+                    // Trailers carry no payload.
+                    Err(_) => continue,
+                },
+                Some(Err(err)) => {
+                    let _ = tokio::fs::remove_file(&abs_path).await;
+                    return frontend_internal_error_response(
+                        StatusCode::BAD_REQUEST,
+                        "media upload stream",
+                        "The upload stream could not be read.",
+                        err,
+                    );
+                }
+            },
+        };
+        let next_len = bytes_written.saturating_add(data.len() as u64);
+        if let Some((allowed, limit)) = upload_allowance.filter(|(a, _)| next_len > *a) {
+            drop(file);
+            let _ = tokio::fs::remove_file(&abs_path).await;
+            tracing::warn!(
+                path = %rel_path,
+                allowed,
+                "Media upload cut off at storage limit"
+            );
+            return storage_limit_response(limit, &storage_usage);
+        }
+        if let Err(err) = file.write_all(&data).await {
+            let _ = tokio::fs::remove_file(&abs_path).await;
+            return frontend_internal_error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "media upload file write",
+                "Failed while writing the uploaded media file.",
+                err,
+            );
+        }
+        bytes_written = next_len;
+    }
+    let _ = file.flush().await;
+    storage::note_media_bytes_added(&workspace_dir, bytes_written);
+
+    if let Err(err) = library_tags::write_tags(&abs_path, &tags) {
+        tracing::warn!("Media tags write failed: {err:#}");
+    }
+
+    let probe = if kind.eq_ignore_ascii_case("audio") || kind.eq_ignore_ascii_case("video") {
+        let probe_config = state.config.lock().media_probe.clone();
+        crate::media_probe::probe_and_cache(&probe_config, &abs_path).await
+    } else {
+        None
+    };
+
+    let pb_record = match upsert_media_asset_metadata(
+        &state,
+        &rel_path,
+        &content_type,
+        kind,
+        title.as_deref(),
+        source,
+        bytes_written,
+        query.entry_id.as_deref(),
+        probe.clone(),
+        &tags,
+    )
+    .await
+    {
+        Ok(record) => Some(record),
+        Err(e) => {
+            tracing::warn!("Media metadata write failed: {e}");
+            None
+        }
+    };
+
+    let transcription = if kind.eq_ignore_ascii_case("audio") {
+        enqueue_journal_transcription(&state, rel_path.clone()).await
+    } else {
+        None
+    };
+
+    let auto_process_jobs = {
+        let config = state.config.lock().clone();
+        let asset_id = pb_record
+            .as_ref()
+            .and_then(|record| record.get("id"))
+            .and_then(|id| id.as_str());
+        auto_process::trigger(
+            &state.jobs,
+            &config,
+            Arc::clone(&state.mem),
+            kind,
+            &rel_path,
+            asset_id,
+        )
+        .await
+    };
+
+    let describe_requested = query
+        .describe
+        .as_deref()
+        .map(str::trim)
+        .is_some_and(|v| v == "1" || v.eq_ignore_ascii_case("true"));
+    let describe_job_id = if describe_requested && kind.eq_ignore_ascii_case("image") {
+        let config = state.config.lock().clone();
+        let image_path = rel_path.clone();
+        Some(state.jobs.enqueue("image_describe", async move {
+            let description =
+                crate::tools::image_describe::describe_workspace_image(&config, &image_path)
+                    .await?;
+            Ok(crate::jobs::JobOutcome {
+                output: description.caption,
+                artifacts: vec![description.caption_path],
+            })
+        }))
+    } else {
+        None
+    };
+
+    let body = serde_json::json!({
+        "ok": true,
+        "kind": kind,
+        "kindCorrected": kind_corrected,
+        "declaredKind": declared_kind,
+        "contentType": content_type,
+        "bytes": bytes_written,
+        "path": rel_path,
+        "title": title,
+        "metadata": pb_record,
+        "transcription": transcription,
+        "describeJobId": describe_job_id,
+        "autoProcessJobIds": auto_process_jobs,
+        "probe": probe,
+        "tags": tags,
+    });
+    (StatusCode::OK, Json(body)).into_response()
+}
+
+async fn handle_journal_text(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(body): Json<JournalTextBody>,
+) -> axum::response::Response {
+    if let Some(err) = pairing_auth_error(&state, &headers, "Journal text") {
+        return err.into_response();
+    }
+    let template_name = body
+        .template
+        .as_deref()
+        .map(str::trim)
+        .filter(|v| !v.is_empty());
+    let content = body.content.trim();
+    let title = body
+        .title
+        .as_deref()
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .unwrap_or("Journal entry");
+    let source = body
+        .source
+        .as_deref()
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .unwrap_or("mobile");
+    let workspace_dir = state.config.lock().workspace_dir.clone();
+    let rendered;
+    let content = match template_name {
+        Some(name) => {
+            let Some(template) = journal_templates::find_template(&workspace_dir, name) else {
+                let available: Vec<String> = journal_templates::list_templates(&workspace_dir)
+                    .into_iter()
+                    .map(|t| t.name)
+                    .collect();
+                return frontend_error_response_with_meta(
+                    StatusCode::BAD_REQUEST,
+                    "JOURNAL_TEMPLATE_NOT_FOUND",
+                    format!(
+                        "Unknown template '{name}'. Available: {}",
+                        if available.is_empty() {
+                            "none".to_string()
+                        } else {
+                            available.join(", ")
+                        }
+                    ),
+                    serde_json::json!({ "available": available }),
+                )
+                .into_response();
+            };
+            let date = chrono::Utc::now().format("%Y-%m-%d").to_string();
+            rendered = journal_templates::render_template(
+                &template.content,
+                &journal_templates::TemplateVars {
+                    date: &date,
+                    title,
+                    content,
+                },
+            );
+            rendered.trim()
+        }
+        None => content,
+    };
+    if content.is_empty() {
+        return frontend_error_response(
+            StatusCode::BAD_REQUEST,
+            "JOURNAL_CONTENT_REQUIRED",
+            "content is required",
+        )
+        .into_response();
+    }
+    if body.mode == JournalTextMode::AppendDaily {
+        let tags = library_tags::normalize_tags(body.tags.iter().flatten());
+        return append_daily_journal_text(&workspace_dir, content, source, &tags).await;
+    }
+    let rel_path = text_journal_rel_path(&workspace_dir, title);
+    let abs_path = workspace_dir.join(&rel_path);
+    if let Some(parent) = abs_path.parent() {
+        if let Err(err) = tokio::fs::create_dir_all(parent).await {
+            return frontend_internal_error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "journal text dir create",
+                "Failed to prepare journal storage.",
+                err,
+            );
+        }
+    }
+    let file_body = format!("{content}\n");
+    if let Err(err) = atomic_write_async(abs_path.clone(), file_body.into_bytes()).await {
+        return write_error_response(err, "journal text save", "Failed to save the journal note.");
+    }
+    search_index::journal_file_changed(&workspace_dir, &rel_path).await;
+    workspace_git::note_change(&workspace_dir, [&rel_path], format!("Add {rel_path}"));
+    let tags = library_tags::normalize_tags(body.tags.iter().flatten());
+    if let Err(err) = library_tags::write_tags(&abs_path, &tags) {
+        tracing::warn!("Journal tags write failed: {err:#}");
+    }
+
+    let pb_record = match create_journal_entry_metadata(
+        &state,
+        &rel_path,
+        title,
+        content,
+        source,
+        Some(&tags),
+    )
+    .await
+    {
+        Ok(record) => Some(record),
+        Err(e) => {
+            tracing::warn!("Journal metadata write failed: {e}");
+            None
+        }
+    };
+
+    let resp = serde_json::json!({
+        "ok": true,
+        "path": rel_path,
+        "title": title,
+        "metadata": pb_record,
+        "tags": tags,
+    });
+    (StatusCode::OK, Json(resp)).into_response()
+}
+
+/// Serializes appends to one daily note, so two devices writing at once
+/// produce two whole sections instead of interleaved lines.
+fn daily_note_lock(abs_path: &StdPath) -> Arc<tokio::sync::Mutex<()>> {
+    static LOCKS: OnceLock<Mutex<HashMap<PathBuf, Arc<tokio::sync::Mutex<()>>>>> = OnceLock::new();
+    Arc::clone(
+        LOCKS
+            .get_or_init(|| Mutex::new(HashMap::new()))
+            .lock()
+            .entry(abs_path.to_path_buf())
+            .or_default(),
+    )
+}
+
+/// Append `content` under a `## HH:MM` heading to today's
+/// `journals/text/YYYY/MM/DD/daily.md`, creating the note on first use, and
+/// keep its single journal entry in step with the newest section as preview.
+async fn append_daily_journal_text(
+    workspace_dir: &StdPath,
+    content: &str,
+    source: &str,
+    tags: &[String],
+) -> axum::response::Response {
+    let now = chrono::Utc::now();
+    let date = now.format("%Y-%m-%d").to_string();
+    let title = format!("Daily note {date}");
+    let rel_path = format!("{JOURNAL_TEXT_DIR}/{}/daily.md", now.format("%Y/%m/%d"));
+    let abs_path = workspace_dir.join(&rel_path);
+    if let Some(parent) = abs_path.parent() {
+        if let Err(err) = tokio::fs::create_dir_all(parent).await {
+            return frontend_internal_error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "journal text dir create",
+                "Failed to prepare journal storage.",
+                err,
+            );
+        }
+    }
 
-    let creation_thread_id = format!("workflow:create:{workflow_key}");
-    let creation_user_content =
-        format!("[create] goal={goal}; key={workflow_key}; skill={skill_rel}; output={output_dir_rel}");
-    let creation_user_record = match local_store::create_chat_message(
-        &workspace_dir,
-        &creation_thread_id,
-        "user",
-        &creation_user_content,
-        "pending",
-        "workflow-template-create",
-        None,
-        None,
-    ) {
-        Ok(record) => record,
+    let lock = daily_note_lock(&abs_path);
+    let _guard = lock.lock().await;
+    let mut note = match tokio::fs::read_to_string(&abs_path).await {
+        Ok(existing) => existing,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => format!("# {title}\n"),
         Err(err) => {
-            return frontend_internal_error(
+            return frontend_internal_error_response(
                 StatusCode::INTERNAL_SERVER_ERROR,
-                "feed workflow template request persist",
-                "Failed to queue content agent creation.",
+                "daily note read",
+                "Failed to read today's daily note.",
                 err,
             );
         }
     };
-    let creation_user_id = creation_user_record
-        .get("id")
-        .and_then(serde_json::Value::as_str)
-        .unwrap_or("")
-        .to_string();
+    if !note.ends_with('\n') {
+        note.push('\n');
+    }
+    let heading = now.format("%H:%M").to_string();
+    let _ = write!(note, "\n## {heading}\n\n{content}\n");
+    if let Err(err) = atomic_write_async(abs_path.clone(), note.clone().into_bytes()).await {
+        return write_error_response(err, "daily note append", "Failed to save the journal note.");
+    }
+    search_index::journal_file_changed(workspace_dir, &rel_path).await;
+    workspace_git::note_change(workspace_dir, [&rel_path], format!("Append to {rel_path}"));
+    let tags = match library_tags::update_tags(&abs_path, tags, &[]) {
+        Ok(tags) => tags,
+        Err(err) => {
+            tracing::warn!("Journal tags write failed: {err:#}");
+            library_tags::read_tags(&abs_path)
+        }
+    };
 
-    let enabled = body.enabled.unwrap_or(true);
-    let run_now = enabled && body.run_now.unwrap_or(true);
-    let output_prefix = format!("{output_dir_rel}/");
+    let preview = truncate_with_ellipsis(&format!("{heading} {content}"), 240);
+    let updated = local_store::update_journal_entry_text(workspace_dir, &rel_path, &preview, &note);
+    let metadata = match updated {
+        Ok(0) => {
+            let entry = local_store::JournalEntryInput {
+                title: title.clone(),
+                entry_type: "text".to_string(),
+                source: source.to_string(),
+                status: "raw".to_string(),
+                workspace_path: rel_path.clone(),
+                preview_text: preview,
+                text_body: note,
+                tags_csv: tags.join(","),
+                created_at_client: Some(now.to_rfc3339()),
+            };
+            match local_store::create_journal_entry_metadata(workspace_dir, &entry) {
+                Ok(record) => Some(record),
+                Err(e) => {
+                    tracing::warn!("Journal metadata write failed: {e}");
+                    None
+                }
+            }
+        }
+        Ok(_) => {
+            if let Err(err) =
+                local_store::update_library_item_tags(workspace_dir, &rel_path, &tags.join(","))
+            {
+                tracing::warn!("Journal tags metadata update failed: {err:#}");
+            }
+            Some(serde_json::json!({
+                "workspacePath": rel_path,
+                "previewText": preview,
+            }))
+        }
+        Err(e) => {
+            tracing::warn!("Journal metadata update failed: {e}");
+            None
+        }
+    };
 
-    let state_for_worker = state.clone();
-    let workspace_for_worker = workspace_dir.clone();
-    let thread_id_for_worker = creation_thread_id.clone();
-    let user_id_for_worker = creation_user_id.clone();
-    let workflow_key_for_worker = workflow_key.clone();
-    let workflow_bot_for_worker = workflow_bot.clone();
-    let skill_rel_for_worker = skill_rel.clone();
-    let output_dir_for_worker = output_dir_rel.clone();
-    let output_prefix_for_worker = output_prefix.clone();
-    let settings_for_worker = settings.clone();
-    let creation_prompt_for_worker = creation_prompt.clone();
-    let goal_for_worker = goal.clone();
+    let resp = serde_json::json!({
+        "ok": true,
+        "path": rel_path,
+        "title": title,
+        "mode": "append_daily",
+        "metadata": metadata,
+        "tags": tags,
+    });
+    (StatusCode::OK, Json(resp)).into_response()
+}
 
-    tokio::spawn(async move {
-        let persist_error = |message: &str| {
-            let _ = local_store::create_chat_message(
-                &workspace_for_worker,
-                &thread_id_for_worker,
-                "assistant",
-                "",
-                "error",
-                "workflow-template-create",
-                Some(&user_id_for_worker),
-                Some(message),
-            );
-            if let Err(update_err) = local_store::patch_chat_status(
-                &workspace_for_worker,
-                &user_id_for_worker,
-                "error",
-                Some(message),
-            ) {
-                tracing::warn!(
-                    "Failed to persist workflow-template-create error status: {update_err}"
+async fn handle_journal_templates(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> axum::response::Response {
+    if let Some(err) = pairing_auth_error(&state, &headers, "Journal templates") {
+        return err.into_response();
+    }
+    let workspace_dir = state.config.lock().workspace_dir.clone();
+    let items = journal_templates::list_templates(&workspace_dir);
+    (StatusCode::OK, Json(serde_json::json!({ "items": items }))).into_response()
+}
+
+async fn handle_media_stream(
+    State(state): State<AppState>,
+    AxumPath(path): AxumPath<String>,
+    req: Request,
+) -> axum::response::Response {
+    if let Some(err) = pairing_auth_error(&state, req.headers(), "Media stream") {
+        return err.into_response();
+    }
+    let workspace_dir = state.config.lock().workspace_dir.clone();
+    let abs_path = match resolve_workspace_media_path(&workspace_dir, &path) {
+        Ok(abs_path) => abs_path,
+        Err(err) => return media_path_error_response(err).into_response(),
+    };
+
+    match ServeFile::new(abs_path).oneshot(req).await {
+        Ok(resp) => resp.into_response(),
+        Err(err) => frontend_internal_error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "media stream",
+            "Failed to stream the media file.",
+            err,
+        ),
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct MediaThumbQuery {
+    w: Option<u32>,
+}
+
+async fn handle_media_thumb(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumPath(path): AxumPath<String>,
+    Query(query): Query<MediaThumbQuery>,
+) -> axum::response::Response {
+    if let Some(err) = pairing_auth_error(&state, &headers, "Media thumbnail") {
+        return err.into_response();
+    }
+    let workspace_dir = state.config.lock().workspace_dir.clone();
+    let abs_path = match resolve_workspace_media_path(&workspace_dir, &path) {
+        Ok(abs_path) => abs_path,
+        Err(err) => return media_path_error_response(err).into_response(),
+    };
+    let Some(kind) = thumbnails::ThumbSource::from_path(&abs_path) else {
+        return frontend_error_response(
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            "THUMB_UNSUPPORTED_TYPE",
+            "Thumbnails are only available for images and videos",
+        )
+        .into_response();
+    };
+
+    let width = thumbnails::clamp_thumb_width(query.w);
+    let rel_path = normalize_workspace_relative_path(&path);
+    match thumbnails::thumbnail_jpeg(&workspace_dir, &rel_path, &abs_path, kind, width).await {
+        Ok(jpeg) => (
+            StatusCode::OK,
+            [
+                (header::CONTENT_TYPE, "image/jpeg"),
+                (header::CACHE_CONTROL, "private, max-age=86400"),
+            ],
+            jpeg,
+        )
+            .into_response(),
+        Err(err) => frontend_internal_error_response(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "media thumbnail",
+            "Failed to generate a thumbnail for this media file.",
+            err,
+        ),
+    }
+}
+
+async fn handle_library_items(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<LibraryItemsQuery>,
+) -> axum::response::Response {
+    if let Some(err) = pairing_auth_error(&state, &headers, "Library list") {
+        return err.into_response();
+    }
+    let (workspace_dir, route_prefix) = {
+        let config = state.config.lock();
+        (config.workspace_dir.clone(), config.gateway.route_prefix())
+    };
+    let scope = query.scope.as_deref().unwrap_or("all");
+    let limit = query.limit.unwrap_or(200).clamp(1, 1000);
+    let tag = query.tag.as_deref();
+    match list_workspace_library_items(&workspace_dir, scope, limit, tag, &route_prefix) {
+        Ok(items) => (StatusCode::OK, Json(serde_json::json!({ "items": items }))).into_response(),
+        Err(err) => frontend_internal_error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "library item list",
+            "Failed to list library items.",
+            err,
+        ),
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct FeedQuery {
+    cursor: Option<String>,
+    limit: Option<usize>,
+}
+
+async fn handle_feed(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<FeedQuery>,
+) -> axum::response::Response {
+    if let Some(err) = pairing_auth_error(&state, &headers, "Feed") {
+        return err.into_response();
+    }
+    let cursor = match query.cursor.as_deref().filter(|raw| !raw.trim().is_empty()) {
+        None => None,
+        Some(raw) => match media_feed::FeedCursor::parse(raw) {
+            Some(cursor) => Some(cursor),
+            None => {
+                return frontend_error_response(
+                    StatusCode::BAD_REQUEST,
+                    "FEED_CURSOR_INVALID",
+                    "cursor must be a nextCursor value from a previous page",
+                )
+                .into_response()
+            }
+        },
+    };
+    let (workspace_dir, route_prefix) = {
+        let config = state.config.lock();
+        (config.workspace_dir.clone(), config.gateway.route_prefix())
+    };
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let bluesky_enabled = crate::security::credentials::load_bluesky_credentials().is_some();
+    let page = media_feed::page(
+        &workspace_dir,
+        &route_prefix,
+        cursor.as_ref(),
+        limit,
+        bluesky_enabled,
+    );
+    match page {
+        Ok(page) => (
+            StatusCode::OK,
+            Json(serde_json::json!({
+                "items": page.items,
+                "nextCursor": page.next_cursor,
+            })),
+        )
+            .into_response(),
+        Err(err) => frontend_internal_error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "feed",
+            "Failed to load the feed.",
+            err,
+        ),
+    }
+}
+
+const SEARCH_MIN_QUERY_CHARS: usize = 2;
+const SEARCH_DEFAULT_LIMIT: usize = 20;
+const SEARCH_MAX_LIMIT: usize = 100;
+const SEARCH_MAX_OFFSET: usize = 1_000;
+const SEARCH_CHAT_SNIPPET_CHARS: usize = 160;
+
+#[derive(serde::Deserialize)]
+struct SearchQuery {
+    q: Option<String>,
+    scope: Option<String>,
+    limit: Option<usize>,
+    offset: Option<usize>,
+}
+
+/// Scale scores from one source into 0..=1 so journal BM25 and memory recall
+/// scores can be merged into a single ranking.
+fn normalize_search_scores(items: &mut [(f64, serde_json::Value)]) {
+    let max = items
+        .iter()
+        .map(|(score, _)| *score)
+        .fold(f64::MIN, f64::max);
+    if max <= 0.0 {
+        return;
+    }
+    for (score, _) in items.iter_mut() {
+        *score /= max;
+    }
+}
+
+async fn handle_search(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<SearchQuery>,
+) -> axum::response::Response {
+    if let Some(err) = pairing_auth_error(&state, &headers, "Search") {
+        return err.into_response();
+    }
+    let q = query.q.as_deref().unwrap_or("").trim().to_string();
+    if q.chars().count() < SEARCH_MIN_QUERY_CHARS {
+        return frontend_error_response(
+            StatusCode::BAD_REQUEST,
+            "SEARCH_QUERY_TOO_SHORT",
+            "Search query must be at least 2 characters",
+        )
+        .into_response();
+    }
+    let scope = query.scope.as_deref().unwrap_or("all").trim().to_ascii_lowercase();
+    let (search_journal, search_chat) = match scope.as_str() {
+        "journal" => (true, false),
+        "chat" => (false, true),
+        "all" => (true, true),
+        _ => {
+            return frontend_error_response(
+                StatusCode::BAD_REQUEST,
+                "SEARCH_SCOPE_INVALID",
+                "scope must be one of journal, chat, all",
+            )
+            .into_response();
+        }
+    };
+    let limit = query
+        .limit
+        .unwrap_or(SEARCH_DEFAULT_LIMIT)
+        .clamp(1, SEARCH_MAX_LIMIT);
+    let offset = query.offset.unwrap_or(0).min(SEARCH_MAX_OFFSET);
+    // Each source returns enough rows to fill this page after merging, plus
+    // one to detect whether another page exists.
+    let fetch = offset + limit + 1;
+
+    let mut journal_items: Vec<(f64, serde_json::Value)> = Vec::new();
+    if search_journal {
+        let workspace_dir = state.config.lock().workspace_dir.clone();
+        let journal_query = q.clone();
+        let hits = tokio::task::spawn_blocking(move || {
+            search_index::search_journal(&workspace_dir, &journal_query, fetch, 0)
+        })
+        .await
+        .map_err(anyhow::Error::from)
+        .and_then(|result| result);
+        match hits {
+            Ok(hits) => {
+                journal_items = hits
+                    .into_iter()
+                    .map(|hit| {
+                        (
+                            hit.score,
+                            serde_json::json!({
+                                "source": "journal",
+                                "path": hit.path,
+                                "title": hit.title,
+                                "snippet": hit.snippet,
+                                "modifiedAt": hit.modified_at,
+                            }),
+                        )
+                    })
+                    .collect();
+            }
+            Err(err) => {
+                return frontend_internal_error_response(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "journal search",
+                    "Failed to search journal entries.",
+                    err,
                 );
             }
-        };
-
-        if let Err(err) = local_store::patch_chat_status(
-            &workspace_for_worker,
-            &user_id_for_worker,
-            "processing",
-            None,
-        ) {
-            tracing::warn!("Failed to mark workflow-template-create as processing: {err}");
         }
+    }
 
-        let creation_result = tokio::time::timeout(
-            Duration::from_secs(CONTENT_AGENT_TIMEOUT_SECS),
-            run_local_agent_prompt_in_thread(
-                &state_for_worker,
-                &thread_id_for_worker,
-                &creation_prompt_for_worker,
-            ),
-        )
-        .await;
-        let creation_reply = match creation_result {
-            Ok(Ok(reply)) => reply,
-            Ok(Err(err)) => {
-                let err_text = frontend_background_error(
-                    "feed workflow template create agent",
-                    "Content agent creation failed.",
-                    &err,
-                );
-                persist_error(&err_text);
-                return;
+    let mut chat_items: Vec<(f64, serde_json::Value)> = Vec::new();
+    if search_chat {
+        match state.mem.recall(&q, fetch, None).await {
+            Ok(entries) => {
+                chat_items = entries
+                    .into_iter()
+                    .enumerate()
+                    .map(|(rank, entry)| {
+                        let modified_at = chrono::DateTime::parse_from_rfc3339(&entry.timestamp)
+                            .map(|ts| ts.timestamp())
+                            .unwrap_or(0);
+                        (
+                            entry.score.unwrap_or(1.0 / (rank as f64 + 1.0)),
+                            serde_json::json!({
+                                "source": "chat",
+                                "threadId": entry.session_id,
+                                "key": entry.key,
+                                "snippet": search_index::highlight_snippet(
+                                    &entry.content,
+                                    &q,
+                                    SEARCH_CHAT_SNIPPET_CHARS,
+                                ),
+                                "modifiedAt": modified_at,
+                            }),
+                        )
+                    })
+                    .collect();
             }
-            Err(_) => {
-                let err_text = format!(
-                    "workflow bot creation agent timed out after {}s",
-                    CONTENT_AGENT_TIMEOUT_SECS
+            Err(err) => {
+                return frontend_internal_error_response(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "chat memory search",
+                    "Failed to search chat memory.",
+                    err,
                 );
-                persist_error(&err_text);
-                return;
             }
-        };
+        }
+    }
 
-        let skill_abs_for_worker = workspace_for_worker.join(&skill_rel_for_worker);
-        if let Err(err) = validate_content_agent_skill_contract(&skill_abs_for_worker) {
-            let err_text = frontend_background_error(
-                "feed workflow template skill validation",
-                "The generated content agent skill failed validation.",
-                &err,
-            );
-            persist_error(&err_text);
-            return;
+    normalize_search_scores(&mut journal_items);
+    normalize_search_scores(&mut chat_items);
+    let mut merged: Vec<(f64, serde_json::Value)> =
+        journal_items.into_iter().chain(chat_items).collect();
+    merged.sort_by(|a, b| b.0.total_cmp(&a.0));
+    let has_more = merged.len() > offset + limit;
+    let items: Vec<serde_json::Value> = merged
+        .into_iter()
+        .skip(offset)
+        .take(limit)
+        .map(|(score, mut item)| {
+            item["score"] = serde_json::json!(score);
+            item
+        })
+        .collect();
+
+    (
+        StatusCode::OK,
+        Json(serde_json::json!({
+            "items": items,
+            "query": q,
+            "scope": scope,
+            "offset": offset,
+            "limit": limit,
+            "hasMore": has_more,
+        })),
+    )
+        .into_response()
+}
+
+async fn handle_library_text(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<LibraryTextQuery>,
+) -> axum::response::Response {
+    if let Some(err) = pairing_auth_error(&state, &headers, "Library text") {
+        return err.into_response();
+    }
+    let (workspace_dir, route_prefix) = {
+        let config = state.config.lock();
+        (config.workspace_dir.clone(), config.gateway.route_prefix())
+    };
+    let Ok(path) = resolve_workspace_text_path(&workspace_dir, &query.path) else {
+        return frontend_error_response(
+            StatusCode::BAD_REQUEST,
+            "LIBRARY_TEXT_PATH_INVALID",
+            "Invalid text path",
+        )
+        .into_response();
+    };
+    let render_html = match query.render.as_deref().map(str::trim) {
+        None | Some("") => false,
+        Some(mode) if mode.eq_ignore_ascii_case("html") => true,
+        Some(_) => {
+            return frontend_error_response(
+                StatusCode::BAD_REQUEST,
+                "LIBRARY_TEXT_RENDER_INVALID",
+                "render must be html when given",
+            )
+            .into_response();
         }
-        let final_skill = match std::fs::read_to_string(&skill_abs_for_worker) {
-            Ok(raw) => raw,
-            Err(err) => {
-                let err_text = frontend_background_error(
-                    "feed workflow template skill read",
-                    "Failed to read the generated content agent skill.",
-                    &err,
-                );
-                persist_error(&err_text);
-                return;
+    };
+    match tokio::fs::read_to_string(&path).await {
+        Ok(content) => {
+            let rel = path
+                .strip_prefix(&workspace_dir)
+                .ok()
+                .map(workspace_relative_display_path)
+                .unwrap_or_else(|| normalize_workspace_relative_path(&query.path));
+            let revision = text_versions::revision(content.as_bytes());
+            let summary = markdown_render::summarize(&content);
+            let mut body = serde_json::json!({
+                "path": rel,
+                "content": content,
+                "revision": revision,
+                "wordCount": summary.word_count,
+                "outline": summary.outline,
+            });
+            if render_html {
+                body["html"] = markdown_render::render_html(&content, &rel, &route_prefix).into();
             }
-        };
-        if final_skill.trim().is_empty() {
-            persist_error("content agent creation produced an empty skill file");
-            return;
+            (StatusCode::OK, Json(body)).into_response()
         }
+        Err(err) => frontend_internal_error_response(
+            StatusCode::NOT_FOUND,
+            "library text read",
+            "Failed to read the requested text file.",
+            err,
+        ),
+    }
+}
 
-        let mut worker_store = match load_or_seed_feed_workflow_settings_store(&workspace_for_worker) {
-            Ok(store) => store,
+/// Serializes library text saves so a revision check and the write it guards
+/// cannot interleave with another save.
+fn library_text_write_lock() -> &'static tokio::sync::Mutex<()> {
+    static LOCK: OnceLock<tokio::sync::Mutex<()>> = OnceLock::new();
+    LOCK.get_or_init(|| tokio::sync::Mutex::new(()))
+}
+
+fn maybe_mark_world_feed_dirty_for_path(workspace_dir: &StdPath, rel_path: &str) {
+    if normalize_workspace_relative_path(rel_path).starts_with("posts/") {
+        let _ = crate::feed::mark_world_feed_dirty(workspace_dir);
+    }
+}
+
+async fn handle_library_save_text(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(body): Json<SaveTextBody>,
+) -> axum::response::Response {
+    if let Some(err) = pairing_auth_error(&state, &headers, "Library save") {
+        return err.into_response();
+    }
+    let workspace_dir = state.config.lock().workspace_dir.clone();
+    let Ok(path) = resolve_workspace_text_path(&workspace_dir, &body.path) else {
+        return frontend_error_response(
+            StatusCode::BAD_REQUEST,
+            "LIBRARY_TEXT_PATH_INVALID",
+            "Invalid text path",
+        )
+        .into_response();
+    };
+    let rel = path
+        .strip_prefix(&workspace_dir)
+        .ok()
+        .map(workspace_relative_display_path)
+        .unwrap_or_else(|| normalize_workspace_relative_path(&body.path));
+    let _write_guard = library_text_write_lock().lock().await;
+    if let Some(base_revision) = body.base_revision.as_deref().filter(|_| !body.force) {
+        let current = match tokio::fs::read(&path).await {
+            Ok(current) => Some(current),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
             Err(err) => {
-                let err_text = frontend_background_error(
-                    "feed workflow template worker store load",
-                    "Failed to load content agent settings.",
-                    &err,
+                return frontend_internal_error_response(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "library text read",
+                    "Failed to read the text file.",
+                    err,
                 );
-                persist_error(&err_text);
-                return;
             }
         };
-        if workflow_definition_by_key(&worker_store, &workflow_key_for_worker).is_some() {
-            persist_error("workflow key already exists");
-            return;
+        // A file that does not exist yet matches an empty base revision.
+        let current_revision = current.as_deref().map(text_versions::revision);
+        if current_revision.as_deref().unwrap_or_default() != base_revision.trim() {
+            return frontend_error_response_with_meta(
+                StatusCode::CONFLICT,
+                "LIBRARY_TEXT_CONFLICT",
+                "This file was changed elsewhere since you opened it.",
+                serde_json::json!({
+                    "path": rel,
+                    "revision": current_revision,
+                    "content": current.map(|bytes| String::from_utf8_lossy(&bytes).into_owned()),
+                }),
+            )
+            .into_response();
         }
-
-        let mut workflow_record = FeedContentAgentRecord {
-            workflow_key: workflow_key_for_worker.clone(),
-            workflow_bot: workflow_bot_for_worker.clone(),
-            skill_path: skill_rel_for_worker.clone(),
-            output_prefix: output_prefix_for_worker.clone(),
-            enabled,
-            editable_files: vec![skill_rel_for_worker.clone()],
-            goal: Some(goal_for_worker.clone()),
-            last_triggered_at: None,
-            last_run_at: None,
-            last_triggered_source_updated_at: None,
-            built_in_skill_fingerprint: None,
-            visible_in_ui: true,
-            settings: settings_for_worker.clone(),
-        };
-        workflow_record = normalize_workflow_record(&workflow_key_for_worker, workflow_record);
-        let workflow_def = feed_workflow_definition_from_record(&workflow_record);
-
-        worker_store
-            .workflows
-            .insert(workflow_key_for_worker.clone(), workflow_record);
-        if let Err(err) = save_feed_workflow_settings_store(&workspace_for_worker, &worker_store) {
-            let err_text = frontend_background_error(
-                "feed workflow template worker store persist",
-                "Failed to save content agent settings.",
-                &err,
+    }
+    // Never overwrite without a copy of what was there.
+    let keep = state.config.lock().gateway.text_versions_keep;
+    let version = match text_versions::snapshot_before_write(
+        &workspace_dir,
+        &rel,
+        body.content.as_bytes(),
+        keep,
+    ) {
+        Ok(version) => version,
+        Err(err) => {
+            return frontend_internal_error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "library text version",
+                "Failed to keep the previous version; nothing was saved.",
+                err,
+            );
+        }
+    };
+    if let Some(parent) = path.parent() {
+        if let Err(err) = tokio::fs::create_dir_all(parent).await {
+            return frontend_internal_error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "library text dir create",
+                "Failed to prepare the destination folder.",
+                err,
             );
-            persist_error(&err_text);
-            return;
         }
+    }
+    let revision = text_versions::revision(body.content.as_bytes());
+    if let Err(err) = atomic_write_async(path, body.content.into_bytes()).await {
+        return write_error_response(err, "library text save", "Failed to save the text file.");
+    }
+    search_index::journal_file_changed(&workspace_dir, &rel).await;
+    workspace_git::note_change(&workspace_dir, [&rel], format!("Save {rel}"));
+    maybe_mark_world_feed_dirty_for_path(&workspace_dir, &rel);
+    (
+        StatusCode::OK,
+        Json(serde_json::json!({
+            "ok": true,
+            "path": rel,
+            "revision": revision,
+            "version": version,
+        })),
+    )
+        .into_response()
+}
 
-        let run_thread_id = if run_now {
-            match queue_workflow_run(
-                state_for_worker.clone(),
-                workflow_def,
-                "workflow-template-create",
-            ) {
-                Ok(thread_id) => Some(thread_id),
-                Err(err) => {
-                    tracing::warn!("Failed to queue workflow run after template creation: {err}");
-                    None
-                }
-            }
-        } else {
-            None
-        };
+/// GET /api/library/versions?path= — earlier contents of a text file, newest first
+async fn handle_library_versions(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<LibraryTextQuery>,
+) -> axum::response::Response {
+    if let Some(err) = pairing_auth_error(&state, &headers, "Library versions") {
+        return err.into_response();
+    }
+    let workspace_dir = state.config.lock().workspace_dir.clone();
+    let path = match resolve_workspace_text_path(&workspace_dir, &query.path) {
+        Ok(path) => path,
+        Err(err) => return library_path_error_response(err).into_response(),
+    };
+    let rel = path
+        .strip_prefix(&workspace_dir)
+        .ok()
+        .map(workspace_relative_display_path)
+        .unwrap_or_else(|| normalize_workspace_relative_path(&query.path));
+    match text_versions::list_versions(&workspace_dir, &rel) {
+        Ok(versions) => (
+            StatusCode::OK,
+            Json(serde_json::json!({ "path": rel, "versions": versions })),
+        )
+            .into_response(),
+        Err(err) => frontend_internal_error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "library versions",
+            "Failed to list previous versions.",
+            err,
+        ),
+    }
+}
 
-        let mut summary = format!(
-            "Content agent `{}` ({}) created.\n\nSkill: `{}`\nOutput: `{}`\n",
-            workflow_bot_for_worker,
-            workflow_key_for_worker,
-            skill_rel_for_worker,
-            output_dir_for_worker
-        );
-        if let Some(thread_id) = &run_thread_id {
-            summary.push_str(&format!("\nInitial run queued: `{thread_id}`\n"));
-        } else if run_now {
-            summary.push_str("\nInitial run requested, but queueing failed.\n");
-        } else {
-            summary.push_str("\nInitial run not requested.\n");
-        }
-        let reply_preview = truncate_with_ellipsis(creation_reply.trim(), 1200);
-        if !reply_preview.is_empty() {
-            summary.push_str("\nAgent summary:\n");
-            summary.push_str(&reply_preview);
+/// POST /api/library/versions/restore — put an earlier version back. The
+/// content being replaced is versioned first, so a restore can be undone.
+async fn handle_library_version_restore(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(body): Json<VersionRestoreBody>,
+) -> axum::response::Response {
+    if let Some(err) = pairing_auth_error(&state, &headers, "Library version restore") {
+        return err.into_response();
+    }
+    let (workspace_dir, keep) = {
+        let config = state.config.lock();
+        (
+            config.workspace_dir.clone(),
+            config.gateway.text_versions_keep,
+        )
+    };
+    let path = match resolve_workspace_text_path(&workspace_dir, &body.path) {
+        Ok(path) => path,
+        Err(err) => return library_path_error_response(err).into_response(),
+    };
+    let rel = path
+        .strip_prefix(&workspace_dir)
+        .ok()
+        .map(workspace_relative_display_path)
+        .unwrap_or_else(|| normalize_workspace_relative_path(&body.path));
+    let content = match text_versions::read_version(&workspace_dir, &rel, &body.id) {
+        Ok(content) => content,
+        Err(err) => {
+            return match err.downcast_ref::<text_versions::VersionError>() {
+                Some(text_versions::VersionError::NotFound(_)) => frontend_error_response(
+                    StatusCode::NOT_FOUND,
+                    "LIBRARY_VERSION_NOT_FOUND",
+                    "Version not found",
+                )
+                .into_response(),
+                _ => frontend_internal_error_response(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "library version read",
+                    "Failed to read the version.",
+                    err,
+                ),
+            };
         }
-
-        if let Err(err) = local_store::create_chat_message(
-            &workspace_for_worker,
-            &thread_id_for_worker,
-            "assistant",
-            &summary,
-            "done",
-            "workflow-template-create",
-            Some(&user_id_for_worker),
-            None,
-        ) {
-            tracing::warn!("Failed to persist workflow-template-create success message: {err}");
+    };
+    let _write_guard = library_text_write_lock().lock().await;
+    let revision = text_versions::revision(&content);
+    let version = match text_versions::snapshot_before_write(&workspace_dir, &rel, &content, keep) {
+        Ok(version) => version,
+        Err(err) => {
+            return frontend_internal_error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "library text version",
+                "Failed to keep the current version; nothing was restored.",
+                err,
+            );
         }
-        if let Err(err) =
-            local_store::patch_chat_status(&workspace_for_worker, &user_id_for_worker, "done", None)
-        {
-            tracing::warn!("Failed to mark workflow-template-create as done: {err}");
+    };
+    if let Some(parent) = path.parent() {
+        if let Err(err) = tokio::fs::create_dir_all(parent).await {
+            return frontend_internal_error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "library text dir create",
+                "Failed to prepare the destination folder.",
+                err,
+            );
         }
-    });
-
+    }
+    if let Err(err) = atomic_write_async(path, content).await {
+        return write_error_response(
+            err,
+            "library version restore",
+            "Failed to restore the version.",
+        );
+    }
+    search_index::journal_file_changed(&workspace_dir, &rel).await;
+    workspace_git::note_change(
+        &workspace_dir,
+        [&rel],
+        format!("Restore a version of {rel}"),
+    );
+    maybe_mark_world_feed_dirty_for_path(&workspace_dir, &rel);
     (
         StatusCode::OK,
         Json(serde_json::json!({
-            "created": false,
-            "queued": true,
-            "threadId": creation_thread_id,
-            "messageId": creation_user_id,
-            "workflowKey": workflow_key,
-            "workflowBot": workflow_bot,
-            "skillPath": skill_rel,
-            "outputDir": output_dir_rel,
-            "outputPrefix": output_prefix,
-            "runQueued": false,
-            "runThreadId": serde_json::Value::Null,
-            "creationSummary": "Content agent creation queued.",
+            "ok": true,
+            "path": rel,
+            "restored": body.id.trim(),
+            "revision": revision,
+            "version": version,
         })),
     )
+        .into_response()
 }
 
-async fn handle_feed_workflow_comment(
+fn workspace_git_disabled_response() -> axum::response::Response {
+    frontend_error_response(
+        StatusCode::NOT_FOUND,
+        "WORKSPACE_GIT_DISABLED",
+        "Workspace history is off; set [workspace] git_autocommit = true",
+    )
+    .into_response()
+}
+
+/// GET /api/library/history?path= — commits that changed a file, newest first
+async fn handle_library_history(
     State(state): State<AppState>,
     headers: HeaderMap,
-    Json(body): Json<FeedContentAgentCommentBody>,
-) -> impl IntoResponse {
-    if let Some(err) = pairing_auth_error(&state, &headers, "Feed workflow comment") {
-        return err;
+    Query(query): Query<LibraryHistoryQuery>,
+) -> axum::response::Response {
+    if let Some(err) = pairing_auth_error(&state, &headers, "Library history") {
+        return err.into_response();
+    }
+    let (workspace_dir, enabled) = {
+        let config = state.config.lock();
+        (
+            config.workspace_dir.clone(),
+            config.workspace.git_autocommit,
+        )
+    };
+    if !enabled {
+        return workspace_git_disabled_response();
+    }
+    let path = match resolve_workspace_text_path(&workspace_dir, &query.path) {
+        Ok(path) => path,
+        Err(err) => return library_path_error_response(err).into_response(),
+    };
+    let rel = path
+        .strip_prefix(&workspace_dir)
+        .ok()
+        .map(workspace_relative_display_path)
+        .unwrap_or_else(|| normalize_workspace_relative_path(&query.path));
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let history_rel = rel.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        workspace_git::history(&workspace_dir, &history_rel, limit)
+    })
+    .await
+    .map_err(anyhow::Error::from)
+    .and_then(|result| result);
+    match result {
+        Ok(commits) => (
+            StatusCode::OK,
+            Json(serde_json::json!({ "path": rel, "commits": commits })),
+        )
+            .into_response(),
+        Err(err) => frontend_internal_error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "library history",
+            "Failed to read the file history.",
+            err,
+        ),
     }
+}
 
-    let requested_path = body.path.trim().trim_start_matches('/').to_string();
-    let comment = body.comment.trim();
-    if requested_path.is_empty() || comment.is_empty() {
-        return frontend_error_response(
-            StatusCode::BAD_REQUEST,
-            "WORKFLOW_COMMENT_INVALID_REQUEST",
-            "path and comment are required",
-        );
+/// POST /api/library/revert — write a file back to its content at a commit.
+/// The current content is versioned first, like a version restore.
+async fn handle_library_revert(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(body): Json<LibraryRevertBody>,
+) -> axum::response::Response {
+    if let Some(err) = pairing_auth_error(&state, &headers, "Library revert") {
+        return err.into_response();
     }
-    if comment.chars().count() > 1500 {
-        return frontend_error_response(
-            StatusCode::BAD_REQUEST,
-            "WORKFLOW_COMMENT_TOO_LONG",
-            "comment is too long (max 1500 characters)",
-        );
+    let (workspace_dir, keep, enabled) = {
+        let config = state.config.lock();
+        (
+            config.workspace_dir.clone(),
+            config.gateway.text_versions_keep,
+            config.workspace.git_autocommit,
+        )
+    };
+    if !enabled {
+        return workspace_git_disabled_response();
     }
-    if !requested_path.starts_with("posts/") {
+    let path = match resolve_workspace_text_path(&workspace_dir, &body.path) {
+        Ok(path) => path,
+        Err(err) => return library_path_error_response(err).into_response(),
+    };
+    let rel = path
+        .strip_prefix(&workspace_dir)
+        .ok()
+        .map(workspace_relative_display_path)
+        .unwrap_or_else(|| normalize_workspace_relative_path(&body.path));
+    let (dir, file_rel, commit) = (workspace_dir.clone(), rel.clone(), body.commit.clone());
+    let read =
+        tokio::task::spawn_blocking(move || workspace_git::file_at(&dir, &file_rel, &commit))
+            .await
+            .map_err(anyhow::Error::from)
+            .and_then(|result| result);
+    let (content, commit_id) = match read {
+        Ok(found) => found,
+        Err(err) => {
+            return match err.downcast_ref::<workspace_git::HistoryError>() {
+                Some(workspace_git::HistoryError::CommitNotFound(_)) => frontend_error_response(
+                    StatusCode::NOT_FOUND,
+                    "LIBRARY_COMMIT_NOT_FOUND",
+                    "Commit not found",
+                )
+                .into_response(),
+                Some(workspace_git::HistoryError::NotInCommit { .. }) => frontend_error_response(
+                    StatusCode::NOT_FOUND,
+                    "LIBRARY_PATH_NOT_IN_COMMIT",
+                    "The file does not exist in that commit",
+                )
+                .into_response(),
+                None => frontend_internal_error_response(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "library revert read",
+                    "Failed to read the file from history.",
+                    err,
+                ),
+            };
+        }
+    };
+    let _write_guard = library_text_write_lock().lock().await;
+    let revision = text_versions::revision(&content);
+    let version = match text_versions::snapshot_before_write(&workspace_dir, &rel, &content, keep) {
+        Ok(version) => version,
+        Err(err) => {
+            return frontend_internal_error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "library text version",
+                "Failed to keep the current version; nothing was reverted.",
+                err,
+            );
+        }
+    };
+    if let Some(parent) = path.parent() {
+        if let Err(err) = tokio::fs::create_dir_all(parent).await {
+            return frontend_internal_error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "library text dir create",
+                "Failed to prepare the destination folder.",
+                err,
+            );
+        }
+    }
+    if let Err(err) = atomic_write_async(path, content).await {
+        return write_error_response(err, "library revert", "Failed to revert the file.");
+    }
+    search_index::journal_file_changed(&workspace_dir, &rel).await;
+    maybe_mark_world_feed_dirty_for_path(&workspace_dir, &rel);
+    let short: String = commit_id.chars().take(7).collect();
+    workspace_git::note_change(&workspace_dir, [&rel], format!("Revert {rel} to {short}"));
+    (
+        StatusCode::OK,
+        Json(serde_json::json!({
+            "ok": true,
+            "path": rel,
+            "commit": commit_id,
+            "revision": revision,
+            "version": version,
+        })),
+    )
+        .into_response()
+}
+
+async fn handle_library_delete(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(body): Json<DeleteLibraryBody>,
+) -> axum::response::Response {
+    if let Some(err) = pairing_auth_error(&state, &headers, "Library delete") {
+        return err.into_response();
+    }
+    let requested = normalize_workspace_relative_path(&body.path);
+    if requested.is_empty() {
         return frontend_error_response(
             StatusCode::BAD_REQUEST,
-            "WORKFLOW_COMMENT_PATH_UNSUPPORTED",
-            "workflow comments are only supported for posts/* feed items",
-        );
+            "LIBRARY_PATH_REQUIRED",
+            "path is required",
+        )
+        .into_response();
     }
 
     let workspace_dir = state.config.lock().workspace_dir.clone();
-    let store = load_or_seed_feed_workflow_settings_store(&workspace_dir).unwrap_or_default();
-    let synth_skill_store = workspace_synthesizer::load_or_seed_skill_store(&workspace_dir)
-        .unwrap_or_default();
-    let workflow = workflow_for_feed_path(&store, &requested_path).or_else(|| {
-        workspace_synthesizer::skill_for_feed_path(&synth_skill_store, &requested_path)
-            .map(|skill| workspace_synth_skill_as_workflow_definition(&skill))
-    });
-    let Some(workflow) = workflow else {
-        let mut supported_prefixes: Vec<String> = workflow_definitions(&store)
-            .into_iter()
-            .map(|item| item.output_prefix)
-            .collect();
-        supported_prefixes.extend(
-            workspace_synthesizer::skill_definitions(&synth_skill_store)
-                .into_iter()
-                .map(|item| item.output_prefix),
-        );
-        let err = serde_json::json!({
-            "supportedPrefixes": supported_prefixes,
-        });
-        return frontend_error_response_with_meta(
-            StatusCode::BAD_REQUEST,
-            "WORKFLOW_COMMENT_TARGET_UNMAPPED",
-            "No editable workflow is mapped to this feed path yet",
-            err,
-        );
+    let lower = requested.to_ascii_lowercase();
+    let target_path = if lower.starts_with("journals/media/") {
+        resolve_workspace_media_path(&workspace_dir, &requested)
+    } else {
+        resolve_workspace_text_path(&workspace_dir, &requested)
     };
-
-    let Some(resolved_target) = resolve_workspace_text_path(&workspace_dir, &requested_path) else {
-        return frontend_error_response(
-            StatusCode::BAD_REQUEST,
-            "WORKFLOW_COMMENT_TARGET_INVALID",
-            "invalid feed item path",
-        );
+    // Text paths resolve as creatable, so a missing text file still needs
+    // the explicit file check below.
+    let target_path =
+        target_path.and_then(|path| ensure_writable(&workspace_dir, &path).map(|()| path));
+    match target_path {
+        Ok(abs_path) if abs_path.is_file() => {}
+        Ok(_) => return library_path_error_response(ResolveError::NotFound).into_response(),
+        Err(err) => return library_path_error_response(err).into_response(),
+    }
+
+    // Related transcripts and sidecars travel into the same trash entry so a
+    // restore brings the whole item back.
+    let paths: Vec<String> = std::iter::once(requested.clone())
+        .chain(
+            library_item_related_paths(&workspace_dir, &requested)
+                .into_iter()
+                .map(|(rel, _)| rel),
+        )
+        .collect();
+    let previous_status: HashMap<String, String> = paths
+        .iter()
+        .filter_map(|rel| {
+            local_store::library_item_status(&workspace_dir, rel)
+                .ok()
+                .flatten()
+                .map(|status| (rel.clone(), status))
+        })
+        .collect();
+    let entry = match trash::move_to_trash(&workspace_dir, &paths, previous_status) {
+        Ok(entry) => entry,
+        Err(err) => {
+            return frontend_internal_error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "library delete",
+                "Failed to move the file to trash.",
+                err,
+            );
+        }
     };
-    if !resolved_target.exists() || !resolved_target.is_file() {
-        return frontend_error_response(
-            StatusCode::NOT_FOUND,
-            "WORKFLOW_COMMENT_TARGET_NOT_FOUND",
-            "feed item file not found",
-        );
+    storage::invalidate_media_usage(&workspace_dir);
+
+    for rel in &entry.paths {
+        if let Err(err) = local_store::set_library_item_status(&workspace_dir, rel, "trashed") {
+            tracing::warn!(path = %rel, "Failed to flag trashed metadata: {err:#}");
+        }
+        search_index::journal_file_changed(&workspace_dir, rel).await;
     }
+    workspace_git::note_change(&workspace_dir, &entry.paths, format!("Trash {requested}"));
+    maybe_mark_world_feed_dirty_for_path(&workspace_dir, &requested);
 
-    let quickfix_result =
-        maybe_apply_workflow_comment_quickfix(&workspace_dir, &workflow, &requested_path, comment);
-    match quickfix_result {
-        Ok(Some(quickfix_message)) => {
-            let _ = crate::feed::mark_world_feed_dirty(&workspace_dir);
-            let thread_id = format!("workflow:{}", workflow.key);
-            let user_content = format!("[{requested_path}] {comment}");
-            let user_record = match local_store::create_chat_message(
-                &workspace_dir,
-                &thread_id,
-                "user",
-                &user_content,
-                "done",
-                "feed-comment",
-                None,
-                None,
-            ) {
-                Ok(record) => record,
-                Err(err) => {
-                    return frontend_internal_error(
+    let removed_related: Vec<&String> = entry.paths.iter().skip(1).collect();
+    let body = serde_json::json!({
+        "ok": true,
+        "path": requested,
+        "removedRelated": removed_related,
+        "trashId": entry.id,
+    });
+    (StatusCode::OK, Json(body)).into_response()
+}
+
+#[derive(serde::Deserialize)]
+struct TrashRestoreBody {
+    id: String,
+    path: Option<String>,
+}
+
+async fn handle_library_trash(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> axum::response::Response {
+    if let Some(err) = pairing_auth_error(&state, &headers, "Library trash") {
+        return err.into_response();
+    }
+    let (workspace_dir, retention_days) = {
+        let config = state.config.lock();
+        (
+            config.workspace_dir.clone(),
+            config.gateway.trash_retention_days,
+        )
+    };
+    let items = trash::list_trash(&workspace_dir);
+    (
+        StatusCode::OK,
+        Json(serde_json::json!({ "items": items, "retentionDays": retention_days })),
+    )
+        .into_response()
+}
+
+async fn handle_library_trash_restore(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(body): Json<TrashRestoreBody>,
+) -> axum::response::Response {
+    if let Some(err) = pairing_auth_error(&state, &headers, "Library trash restore") {
+        return err.into_response();
+    }
+    let workspace_dir = state.config.lock().workspace_dir.clone();
+    let only_path = body
+        .path
+        .as_deref()
+        .map(normalize_workspace_relative_path)
+        .filter(|p| !p.is_empty());
+    let (entry, restored) =
+        match trash::restore_from_trash(&workspace_dir, &body.id, only_path.as_deref()) {
+            Ok(result) => result,
+            Err(err) => {
+                return match err.downcast_ref::<trash::TrashError>() {
+                    Some(trash::TrashError::NotFound(_)) => frontend_error_response(
+                        StatusCode::NOT_FOUND,
+                        "TRASH_ENTRY_NOT_FOUND",
+                        "Trash entry not found",
+                    )
+                    .into_response(),
+                    Some(trash::TrashError::Conflict(path)) => frontend_error_response(
+                        StatusCode::CONFLICT,
+                        "TRASH_RESTORE_CONFLICT",
+                        format!("A file already exists at {path}"),
+                    )
+                    .into_response(),
+                    None => frontend_internal_error_response(
                         StatusCode::INTERNAL_SERVER_ERROR,
-                        "feed workflow quickfix persist",
-                        "Failed to save the workflow quickfix request.",
+                        "library trash restore",
+                        "Failed to restore from trash.",
                         err,
-                    );
-                }
-            };
-            let user_id = user_record
-                .get("id")
-                .and_then(serde_json::Value::as_str)
-                .unwrap_or("")
-                .to_string();
-            if let Err(err) = local_store::create_chat_message(
-                &workspace_dir,
-                &thread_id,
-                "assistant",
-                &quickfix_message,
-                "done",
-                "workflow-quickfix",
-                Some(&user_id),
-                None,
-            ) {
-                tracing::warn!("Failed to persist workflow quickfix assistant message: {err}");
+                    ),
+                };
             }
+        };
+    storage::invalidate_media_usage(&workspace_dir);
 
-            return (
-                StatusCode::OK,
-                Json(serde_json::json!({
-                    "queued": false,
-                    "threadId": thread_id,
-                    "workflowKey": workflow.key,
-                    "workflowBot": workflow.bot_name,
-                    "editableFiles": workflow.editable_files,
-                    "messageId": user_id,
-                    "message": quickfix_message,
-                    "quickfixApplied": true,
-                })),
-            );
-        }
-        Ok(None) => {}
-        Err(err) => {
-            tracing::warn!(
-                "workflow quickfix check failed for {} on {}: {err}",
-                workflow.key,
-                requested_path
-            );
+    for rel in &restored {
+        if let Some(status) = entry.previous_status.get(rel) {
+            if let Err(err) = local_store::set_library_item_status(&workspace_dir, rel, status) {
+                tracing::warn!(path = %rel, "Failed to restore metadata status: {err:#}");
+            }
         }
+        search_index::journal_file_changed(&workspace_dir, rel).await;
+        maybe_mark_world_feed_dirty_for_path(&workspace_dir, rel);
+    }
+    if let Some(first) = restored.first() {
+        workspace_git::note_change(
+            &workspace_dir,
+            &restored,
+            format!("Restore {first} from trash"),
+        );
     }
 
-    let thread_id = format!("workflow:{}", workflow.key);
-    let user_content = format!("[{requested_path}] {comment}");
-    let user_record = match local_store::create_chat_message(
-        &workspace_dir,
-        &thread_id,
-        "user",
-        &user_content,
-        "pending",
-        "feed-comment",
-        None,
-        None,
-    ) {
-        Ok(record) => record,
+    (
+        StatusCode::OK,
+        Json(serde_json::json!({ "ok": true, "id": entry.id, "restored": restored })),
+    )
+        .into_response()
+}
+
+#[derive(Default, serde::Deserialize)]
+struct LibraryExportQuery {
+    path: Option<String>,
+    /// Download the bundle produced by an earlier async export job.
+    job: Option<String>,
+}
+
+/// Removes a finished export archive once its response body is dropped.
+struct ExportArchiveGuard(PathBuf);
+
+impl Drop for ExportArchiveGuard {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+async fn export_archive_response(archive: PathBuf, download_name: &str) -> axum::response::Response {
+    use tokio::io::AsyncReadExt as _;
+
+    let guard = ExportArchiveGuard(archive);
+    let file = match tokio::fs::File::open(&guard.0).await {
+        Ok(file) => file,
         Err(err) => {
-            return frontend_internal_error(
+            return frontend_internal_error_response(
                 StatusCode::INTERNAL_SERVER_ERROR,
-                "feed workflow comment persist",
-                "Failed to queue the workflow comment.",
+                "library export",
+                "Failed to read the export archive.",
                 err,
             );
         }
     };
+    let len = file.metadata().await.map(|m| m.len()).unwrap_or(0);
+    let stream = futures_util::stream::unfold((file, guard), |(mut file, guard)| async move {
+        let mut buf = vec![0u8; 64 * 1024];
+        match file.read(&mut buf).await {
+            Ok(0) => None,
+            Ok(n) => {
+                buf.truncate(n);
+                Some((Ok::<_, std::io::Error>(axum::body::Bytes::from(buf)), (file, guard)))
+            }
+            Err(err) => Some((Err(err), (file, guard))),
+        }
+    });
+    axum::response::Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "application/zip")
+        .header(header::CONTENT_LENGTH, len)
+        .header(
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{download_name}\""),
+        )
+        .body(axum::body::Body::from_stream(stream))
+        .unwrap_or_else(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())
+}
 
-    let user_id = user_record
-        .get("id")
-        .and_then(serde_json::Value::as_str)
-        .unwrap_or("")
-        .to_string();
-    let prompt = workflow_comment_prompt(&workflow, &requested_path, comment);
-    let thread_id_for_worker = thread_id.clone();
-    let workspace_for_worker = workspace_dir.clone();
-    let state_for_worker = state.clone();
-    let user_id_for_worker = user_id.clone();
+/// GET /api/library/export — zip a library item with its sidecars, or a
+/// processed pipeline folder. Oversized bundles are built by a background job
+/// and fetched later with `?job=<id>`.
+async fn handle_library_export(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<LibraryExportQuery>,
+) -> axum::response::Response {
+    if let Some(err) = pairing_auth_error(&state, &headers, "Library export") {
+        return err.into_response();
+    }
+    let workspace_dir = state.config.lock().workspace_dir.clone();
 
-    tokio::spawn(async move {
-        if let Err(err) =
-            local_store::patch_chat_status(
-                &workspace_for_worker,
-                &user_id_for_worker,
-                "processing",
-                None,
+    if let Some(job_id) = query.job.as_deref().map(str::trim).filter(|id| !id.is_empty()) {
+        let Some(job) = state
+            .jobs
+            .get(job_id)
+            .filter(|job| job.kind == "library_export")
+        else {
+            return frontend_error_response(StatusCode::NOT_FOUND, "JOB_NOT_FOUND", "Job not found")
+                .into_response();
+        };
+        if job.status != crate::jobs::JobStatus::Succeeded {
+            return frontend_error_response_with_meta(
+                StatusCode::CONFLICT,
+                "EXPORT_NOT_READY",
+                "The export job has not finished.",
+                serde_json::json!({ "status": job.status }),
             )
-        {
-            tracing::warn!("Failed to update feed-comment status to processing: {err}");
+            .into_response();
         }
+        let archive = job
+            .artifacts
+            .first()
+            .filter(|rel| rel.starts_with(library_export::EXPORTS_DIR))
+            .map(|rel| workspace_dir.join(rel))
+            .filter(|abs| abs.is_file());
+        let Some(archive) = archive else {
+            return frontend_error_response(
+                StatusCode::GONE,
+                "EXPORT_EXPIRED",
+                "The export archive was already downloaded.",
+            )
+            .into_response();
+        };
+        let download_name = job
+            .output_tail
+            .lines()
+            .next()
+            .filter(|name| !name.is_empty())
+            .unwrap_or("export.zip")
+            .to_string();
+        return export_archive_response(archive, &download_name).await;
+    }
 
-        let channel_ctx = crate::channels::ChannelExecutionContext::new(
-            "local",
-            thread_id_for_worker.clone(),
-            Some(thread_id_for_worker.clone()),
-        );
-        let config = state_for_worker.config.lock().clone();
-        let result = crate::channels::with_channel_execution_context(
-            channel_ctx,
-            run_gateway_ui_chat_with_tools(config, &prompt),
+    let requested = normalize_workspace_relative_path(query.path.as_deref().unwrap_or_default());
+    if requested.is_empty() {
+        return frontend_error_response(
+            StatusCode::BAD_REQUEST,
+            "LIBRARY_PATH_REQUIRED",
+            "path is required",
         )
-        .await;
+        .into_response();
+    }
 
-        match result {
-            Ok(reply) => {
-                let _ = crate::feed::mark_world_feed_dirty(&workspace_for_worker);
-                let reply_text = if reply.trim().is_empty() {
-                    "Workflow update applied."
-                } else {
-                    reply.trim()
-                };
-                if let Err(err) = local_store::create_chat_message(
-                    &workspace_for_worker,
-                    &thread_id_for_worker,
-                    "assistant",
-                    reply_text,
-                    "done",
-                    "workflow-modifier",
-                    Some(&user_id_for_worker),
-                    None,
-                ) {
-                    tracing::warn!("Failed to persist workflow-modifier reply: {err}");
-                }
-                if let Err(err) = local_store::patch_chat_status(
-                    &workspace_for_worker,
-                    &user_id_for_worker,
-                    "done",
-                    None,
-                ) {
-                    tracing::warn!("Failed to mark feed-comment as done: {err}");
-                }
+    let planned = if library_export::is_exportable_directory(&format!("{requested}/"))
+        && workspace_dir.join(&requested).is_dir()
+    {
+        library_export::plan_directory_export(&workspace_dir, &requested).map_err(|err| {
+            frontend_error_response(
+                StatusCode::BAD_REQUEST,
+                "LIBRARY_PATH_INVALID",
+                err.to_string(),
+            )
+        })
+    } else {
+        let target_path = if requested.to_ascii_lowercase().starts_with("journals/media/") {
+            resolve_workspace_media_path(&workspace_dir, &requested)
+        } else {
+            resolve_workspace_text_path(&workspace_dir, &requested)
+        };
+        match target_path {
+            Err(err) => Err(library_path_error_response(err)),
+            Ok(abs) if !abs.is_file() => {
+                Err(library_path_error_response(ResolveError::NotFound))
             }
-            Err(err) => {
-                let err_text = frontend_background_error(
-                    "feed workflow comment worker",
-                    "Comment processing failed.",
-                    &err,
-                );
-                let _ = local_store::create_chat_message(
-                    &workspace_for_worker,
-                    &thread_id_for_worker,
-                    "assistant",
-                    "",
-                    "error",
-                    "workflow-modifier",
-                    Some(&user_id_for_worker),
-                    Some(&err_text),
-                );
-                if let Err(update_err) = local_store::patch_chat_status(
-                    &workspace_for_worker,
-                    &user_id_for_worker,
-                    "error",
-                    Some(&err_text),
-                ) {
-                    tracing::warn!(
-                        "Failed to mark feed-comment as error after workflow-modifier failure: {update_err}"
-                    );
-                }
+            Ok(abs) => {
+                let related = library_item_related_paths(&workspace_dir, &requested);
+                Ok(library_export::plan_item_export(
+                    &workspace_dir,
+                    &requested,
+                    &abs,
+                    &related,
+                ))
             }
         }
-    });
+    };
+    let plan = match planned {
+        Ok(plan) => plan,
+        Err(err) => return err.into_response(),
+    };
 
-    (
-        StatusCode::OK,
-        Json(serde_json::json!({
-            "queued": true,
-            "threadId": thread_id,
-            "workflowKey": workflow.key,
-            "workflowBot": workflow.bot_name,
-            "editableFiles": workflow.editable_files,
-            "messageId": user_id,
-            "message": format!("Queued update for {}", workflow.bot_name),
-        })),
-    )
-}
+    let total_bytes = plan.total_bytes();
+    if total_bytes > library_export::MAX_EXPORT_BYTES {
+        return frontend_error_response_with_meta(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            "EXPORT_TOO_LARGE",
+            "This export is too large to bundle.",
+            serde_json::json!({
+                "bytes": total_bytes,
+                "maxBytes": library_export::MAX_EXPORT_BYTES,
+            }),
+        )
+        .into_response();
+    }
 
-async fn handle_drafts_list(
-    State(state): State<AppState>,
-    headers: HeaderMap,
-    Query(query): Query<DraftListQuery>,
-) -> impl IntoResponse {
-    if let Some(err) = pairing_auth_error(&state, &headers, "Drafts API") {
-        return err;
+    let archive_rel = format!("{}/{}.zip", library_export::EXPORTS_DIR, Uuid::new_v4());
+    let archive = workspace_dir.join(&archive_rel);
+    let download_name = plan.archive_name();
+
+    if total_bytes > library_export::MAX_INLINE_EXPORT_BYTES {
+        let job_archive = archive.clone();
+        let job_name = download_name.clone();
+        let job_id = state.jobs.enqueue("library_export", async move {
+            tokio::task::spawn_blocking(move || library_export::write_zip(&plan, &job_archive))
+                .await??;
+            Ok(crate::jobs::JobOutcome {
+                output: job_name,
+                artifacts: vec![archive_rel],
+            })
+        });
+        return (
+            StatusCode::ACCEPTED,
+            Json(serde_json::json!({
+                "ok": true,
+                "jobId": job_id,
+                "bytes": total_bytes,
+            })),
+        )
+            .into_response();
     }
-    let workspace_dir = state.config.lock().workspace_dir.clone();
-    let limit = query.limit.unwrap_or(20).clamp(1, 200);
-    match local_store::list_drafts(&workspace_dir, limit) {
-        Ok(items) => (StatusCode::OK, Json(serde_json::json!({ "items": items }))),
-        Err(err) => frontend_internal_error(
+
+    let build_archive = archive.clone();
+    let written =
+        tokio::task::spawn_blocking(move || library_export::write_zip(&plan, &build_archive))
+            .await
+            .map_err(anyhow::Error::from)
+            .and_then(|result| result);
+    if let Err(err) = written {
+        let _ = std::fs::remove_file(&archive);
+        return frontend_internal_error_response(
             StatusCode::INTERNAL_SERVER_ERROR,
-            "draft list",
-            "Failed to load drafts.",
+            "library export",
+            "Failed to build the export archive.",
             err,
-        ),
+        );
     }
+    export_archive_response(archive, &download_name).await
 }
 
-async fn handle_drafts_upsert(
+async fn handle_library_tags(
     State(state): State<AppState>,
     headers: HeaderMap,
-    Json(body): Json<DraftUpsertBody>,
-) -> impl IntoResponse {
-    if let Some(err) = pairing_auth_error(&state, &headers, "Drafts API") {
-        return err;
+    Json(body): Json<LibraryTagsBody>,
+) -> axum::response::Response {
+    if let Some(err) = pairing_auth_error(&state, &headers, "Library tags") {
+        return err.into_response();
+    }
+    let requested = normalize_workspace_relative_path(&body.path);
+    if requested.is_empty() {
+        return frontend_error_response(
+            StatusCode::BAD_REQUEST,
+            "LIBRARY_PATH_REQUIRED",
+            "path is required",
+        )
+        .into_response();
     }
+
     let workspace_dir = state.config.lock().workspace_dir.clone();
-    let payload = local_store::DraftUpsert {
-        id: body.id,
-        text: body.text.unwrap_or_default(),
-        video_name: body.video_name.unwrap_or_default(),
-        created_at_client: body.created_at_client,
-        updated_at_client: body.updated_at_client,
+    let target_path = if requested.to_ascii_lowercase().starts_with("journals/media/") {
+        resolve_workspace_media_path(&workspace_dir, &requested)
+    } else {
+        resolve_workspace_text_path(&workspace_dir, &requested)
+    }
+    .and_then(|path| ensure_writable(&workspace_dir, &path).map(|()| path));
+    let abs_path = match target_path {
+        Ok(abs_path) if abs_path.is_file() => abs_path,
+        Ok(_) => return library_path_error_response(ResolveError::NotFound).into_response(),
+        Err(err) => return library_path_error_response(err).into_response(),
     };
-    match local_store::upsert_draft(&workspace_dir, &payload) {
-        Ok(record) => (StatusCode::OK, Json(record)),
-        Err(err) => frontend_internal_error(
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "draft upsert",
-            "Failed to save the draft.",
-            err,
-        ),
+
+    let tags = match library_tags::update_tags(&abs_path, &body.add, &body.remove) {
+        Ok(tags) => tags,
+        Err(err) => {
+            return frontend_internal_error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "library tags update",
+                "Failed to update tags.",
+                err,
+            );
+        }
+    };
+    if let Err(err) =
+        local_store::update_library_item_tags(&workspace_dir, &requested, &tags.join(","))
+    {
+        tracing::warn!("Library tags metadata update failed: {err:#}");
     }
+
+    (
+        StatusCode::OK,
+        Json(serde_json::json!({ "ok": true, "path": requested, "tags": tags })),
+    )
+        .into_response()
 }
 
-async fn handle_post_history_list(
+async fn handle_journal_transcribe(
     State(state): State<AppState>,
     headers: HeaderMap,
-    Query(query): Query<PostHistoryListQuery>,
-) -> impl IntoResponse {
-    if let Some(err) = pairing_auth_error(&state, &headers, "Post history API") {
-        return err;
+    Json(body): Json<JournalTranscribeBody>,
+) -> axum::response::Response {
+    if let Some(err) = pairing_auth_error(&state, &headers, "Journal transcribe") {
+        return err.into_response();
     }
-    let workspace_dir = state.config.lock().workspace_dir.clone();
-    let limit = query.limit.unwrap_or(50).clamp(1, 500);
-    match local_store::list_post_history(&workspace_dir, limit) {
-        Ok(items) => (StatusCode::OK, Json(serde_json::json!({ "items": items }))),
-        Err(err) => frontend_internal_error(
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "post history list",
-            "Failed to load post history.",
-            err,
-        ),
+
+    let requested = normalize_workspace_relative_path(&body.media_path);
+    if requested.is_empty() {
+        return frontend_error_response(
+            StatusCode::BAD_REQUEST,
+            "TRANSCRIPTION_MEDIA_PATH_REQUIRED",
+            "mediaPath is required",
+        )
+        .into_response();
     }
-}
 
-async fn handle_post_history_create(
-    State(state): State<AppState>,
-    headers: HeaderMap,
-    Json(body): Json<PostHistoryCreateBody>,
-) -> impl IntoResponse {
-    if let Some(err) = pairing_auth_error(&state, &headers, "Post history API") {
-        return err;
+    let config_snapshot = state.config.lock().clone();
+    if !config_snapshot.transcription.enabled {
+        return frontend_error_response(
+            StatusCode::BAD_REQUEST,
+            "TRANSCRIPTION_DISABLED",
+            "Transcription is disabled. Enable [transcription] enabled = true in config.",
+        )
+        .into_response();
+    }
+    let media_capabilities = local_media_capabilities(&config_snapshot);
+    if !media_capabilities.transcribe_media {
+        return frontend_error_response(
+            StatusCode::BAD_REQUEST,
+            "TRANSCRIPTION_UNAVAILABLE",
+            "Local transcription is unavailable on this device. Check local media capabilities in settings.",
+        )
+        .into_response();
+    }
+
+    let workspace_dir = config_snapshot.workspace_dir.clone();
+    let abs_media_path = match resolve_workspace_media_path(&workspace_dir, &requested) {
+        Ok(abs_media_path) => abs_media_path,
+        Err(ResolveError::Escapes) => {
+            return frontend_error_response(
+                StatusCode::BAD_REQUEST,
+                "TRANSCRIPTION_MEDIA_PATH_INVALID",
+                "Invalid media path",
+            )
+            .into_response();
+        }
+        Err(_) => {
+            return frontend_error_response(
+                StatusCode::NOT_FOUND,
+                "TRANSCRIPTION_MEDIA_FILE_NOT_FOUND",
+                "Media file not found",
+            )
+            .into_response();
+        }
+    };
+
+    let Some(transcript_rel_path) = transcript_rel_path_for_media(&requested) else {
+        return frontend_error_response(
+            StatusCode::BAD_REQUEST,
+            "TRANSCRIPTION_PATH_UNAVAILABLE",
+            "Could not derive transcript path",
+        )
+        .into_response();
+    };
+    let transcript_abs_path = workspace_dir.join(&transcript_rel_path);
+    let transcript_json_path = transcript_json_rel_path(&transcript_rel_path);
+    let transcript_srt_path = transcript_srt_rel_path(&transcript_rel_path);
+
+    if transcript_abs_path.exists() && transcript_abs_path.is_file() {
+        let transcript_text = tokio::fs::read_to_string(&transcript_abs_path)
+            .await
+            .unwrap_or_default();
+        if !transcript_text.trim().is_empty() {
+            let body = serde_json::json!({
+                "ok": true,
+                "mediaPath": requested,
+                "path": transcript_rel_path,
+                "jsonPath": transcript_json_path,
+                "srtPath": transcript_srt_path,
+                "text": transcript_text,
+                "status": "done",
+            });
+            return (StatusCode::OK, Json(body)).into_response();
+        }
     }
+
+    let enqueue_result = enqueue_transcription_job(
+        state.clone(),
+        requested.clone(),
+        abs_media_path,
+        transcript_rel_path.clone(),
+        transcript_abs_path,
+        config_snapshot.transcription.clone(),
+    );
+
+    let body = serde_json::json!({
+        "ok": true,
+        "mediaPath": requested,
+        "path": transcript_rel_path,
+        "jsonPath": transcript_json_path,
+        "srtPath": transcript_srt_path,
+        "status": enqueue_result.status,
+        "error": enqueue_result.error,
+        "updatedAt": enqueue_result.updated_at,
+    });
+    (StatusCode::OK, Json(body)).into_response()
+}
+
+async fn journal_transcribe_status_payload(
+    state: &AppState,
+    requested: &str,
+) -> Result<serde_json::Value, (StatusCode, Json<serde_json::Value>)> {
     let workspace_dir = state.config.lock().workspace_dir.clone();
-    let payload = local_store::PostHistoryInput {
-        provider: body.provider.unwrap_or_else(|| "bluesky".to_string()),
-        text: body.text.unwrap_or_default(),
-        video_name: body.video_name.unwrap_or_default(),
-        source_path: body.source_path.unwrap_or_default(),
-        uri: body.uri.unwrap_or_default(),
-        cid: body.cid.unwrap_or_default(),
-        status: body.status.unwrap_or_else(|| "success".to_string()),
-        error: body.error.unwrap_or_default(),
-        created_at_client: body.created_at_client,
+    let Some(transcript_rel_path) = transcript_rel_path_for_media(requested) else {
+        return Err(frontend_error_response(
+            StatusCode::BAD_REQUEST,
+            "TRANSCRIPTION_PATH_UNAVAILABLE",
+            "Could not derive transcript path",
+        ));
     };
-    match local_store::create_post_history(&workspace_dir, &payload) {
-        Ok(record) => (StatusCode::OK, Json(record)),
-        Err(err) => frontend_internal_error(
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "post history create",
-            "Failed to save post history.",
-            err,
-        ),
+    let transcript_abs_path = workspace_dir.join(&transcript_rel_path);
+    let transcript_json_path = transcript_json_rel_path(&transcript_rel_path);
+    let transcript_srt_path = transcript_srt_rel_path(&transcript_rel_path);
+
+    if transcript_abs_path.exists() && transcript_abs_path.is_file() {
+        let transcript_text = tokio::fs::read_to_string(&transcript_abs_path)
+            .await
+            .unwrap_or_default();
+        if !transcript_text.trim().is_empty() {
+            return Ok(serde_json::json!({
+                "ok": true,
+                "mediaPath": requested,
+                "path": transcript_rel_path,
+                "jsonPath": transcript_json_path,
+                "srtPath": transcript_srt_path,
+                "text": transcript_text,
+                "status": "done",
+            }));
+        }
     }
-}
 
-fn pairing_auth_error(
-    state: &AppState,
-    headers: &HeaderMap,
-    scope: &str,
-) -> Option<(StatusCode, Json<serde_json::Value>)> {
-    if !state.pairing.require_pairing() {
-        return None;
-    }
-    let auth = headers
-        .get(header::AUTHORIZATION)
-        .and_then(|v| v.to_str().ok())
-        .unwrap_or("");
-    let token = auth.strip_prefix("Bearer ").unwrap_or("");
-    if state.pairing.is_authenticated(token) {
-        return None;
+    let jobs = state.journal_transcription_jobs.lock();
+    if let Some(job) = jobs.get(requested) {
+        let resolved_transcript_path = job
+            .transcript_path
+            .clone()
+            .unwrap_or_else(|| transcript_rel_path.clone());
+        return Ok(serde_json::json!({
+            "ok": true,
+            "mediaPath": requested,
+            "path": resolved_transcript_path,
+            "jsonPath": transcript_json_rel_path(&resolved_transcript_path),
+            "srtPath": transcript_srt_rel_path(&resolved_transcript_path),
+            "status": job.status,
+            "error": job.error,
+            "updatedAt": job.updated_at,
+        }));
     }
-    tracing::warn!("{scope}: rejected — not paired / invalid bearer token");
-    Some(frontend_error_response(
-        StatusCode::UNAUTHORIZED,
-        "PAIRING_REQUIRED",
-        "Unauthorized — pair first via POST /pair, then send Authorization: Bearer <token>",
-    ))
-}
-
-#[derive(serde::Deserialize)]
-struct MediaUploadQuery {
-    kind: Option<String>,
-    filename: Option<String>,
-    title: Option<String>,
-    source: Option<String>,
-    entry_id: Option<String>,
-}
-
-#[derive(serde::Deserialize)]
-struct JournalTextBody {
-    title: Option<String>,
-    content: String,
-    source: Option<String>,
-    tags: Option<Vec<String>>,
-}
 
-#[derive(serde::Deserialize)]
-struct LibraryItemsQuery {
-    scope: Option<String>,
-    limit: Option<usize>,
+    Ok(serde_json::json!({
+        "ok": true,
+        "mediaPath": requested,
+        "path": transcript_rel_path,
+        "jsonPath": transcript_json_path,
+        "srtPath": transcript_srt_path,
+        "status": "idle",
+    }))
 }
 
-#[derive(serde::Deserialize)]
-struct LibraryTextQuery {
-    path: String,
-}
+async fn handle_journal_transcribe_status(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<JournalTranscribeStatusQuery>,
+) -> axum::response::Response {
+    if let Some(err) = pairing_auth_error(&state, &headers, "Journal transcribe status") {
+        return err.into_response();
+    }
 
-#[derive(serde::Deserialize)]
-struct SaveTextBody {
-    path: String,
-    content: String,
-}
+    let requested = normalize_workspace_relative_path(&query.media_path);
+    if requested.is_empty() {
+        return frontend_error_response(
+            StatusCode::BAD_REQUEST,
+            "TRANSCRIPTION_MEDIA_PATH_REQUIRED",
+            "mediaPath is required",
+        )
+        .into_response();
+    }
 
-#[derive(serde::Deserialize)]
-struct DeleteLibraryBody {
-    path: String,
+    match journal_transcribe_status_payload(&state, &requested).await {
+        Ok(body) => (StatusCode::OK, Json(body)).into_response(),
+        Err(err) => err.into_response(),
+    }
 }
 
-#[derive(serde::Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct JournalTranscribeBody {
-    media_path: String,
-}
+async fn handle_journal_transcribe_stream(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<JournalTranscribeStatusQuery>,
+) -> axum::response::Response {
+    if let Some(err) = pairing_auth_error(&state, &headers, "Journal transcribe stream") {
+        return err.into_response();
+    }
 
-#[derive(serde::Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct RuntimeConfigUpdateBody {
-    default_provider: String,
-    default_model: String,
-    transcription_enabled: bool,
-    transcription_model: Option<String>,
-    api_key: Option<String>,
-}
+    let requested = normalize_workspace_relative_path(&query.media_path);
+    if requested.is_empty() {
+        return frontend_error_response(
+            StatusCode::BAD_REQUEST,
+            "TRANSCRIPTION_MEDIA_PATH_REQUIRED",
+            "mediaPath is required",
+        )
+        .into_response();
+    }
 
-fn available_local_transcription_models() -> Vec<String> {
-    let (cache_key, models) = compute_available_transcription_models();
-    let cache = TRANSCRIPTION_MODEL_CACHE.get_or_init(|| Mutex::new(None));
-    let mut guard = cache.lock();
-    if let Some(entry) = guard.as_ref() {
-        let fresh = entry.cached_at.elapsed() < Duration::from_secs(TRANSCRIPTION_MODEL_CACHE_TTL_SECS);
-        if fresh && entry.cache_key == cache_key {
-            return entry.models.clone();
-        }
+    if let Err(err) = journal_transcribe_status_payload(&state, &requested).await {
+        return err.into_response();
     }
-    let models_vec: Vec<String> = models.into_iter().collect();
-    *guard = Some(TranscriptionModelCacheEntry {
-        cache_key,
-        models: models_vec.clone(),
-        cached_at: Instant::now(),
-    });
-    models_vec
-}
 
-fn compute_available_transcription_models() -> (String, BTreeSet<String>) {
-    let roots = transcription_cache_roots();
-    let mut signature_parts: Vec<String> = Vec::new();
-    let mut models = BTreeSet::new();
+    let state_for_stream = state.clone();
+    let (tx, rx) = mpsc::channel(8);
+    tokio::spawn(async move {
+        let mut last_snapshot = String::new();
+        loop {
+            match journal_transcribe_status_payload(&state_for_stream, &requested).await {
+                Ok(payload) => {
+                    let fingerprint = serde_json::to_string(&payload).unwrap_or_default();
+                    if fingerprint != last_snapshot {
+                        last_snapshot = fingerprint;
+                        if tx.send(sse_json_event("transcription_status", &payload)).await.is_err() {
+                            break;
+                        }
+                    }
+                    let status = payload
+                        .get("status")
+                        .and_then(serde_json::Value::as_str)
+                        .unwrap_or("idle");
+                    if matches!(status, "done" | "error" | "idle") {
+                        break;
+                    }
+                }
+                Err((_, Json(body))) => {
+                    let payload = serde_json::json!({
+                        "error": body.get("error").and_then(serde_json::Value::as_str).unwrap_or("Failed to load transcription status."),
+                        "code": body.get("code").and_then(serde_json::Value::as_str).unwrap_or("TRANSCRIPTION_STREAM_FAILED"),
+                    });
+                    let _ = tx.send(sse_json_event("error", &payload)).await;
+                    break;
+                }
+            }
 
-    for root in roots {
-        let root_display = root.display().to_string();
-        if !root.exists() || !root.is_dir() {
-            signature_parts.push(format!("{root_display}:missing"));
-            continue;
+            tokio::time::sleep(Duration::from_millis(1500)).await;
         }
-        let read = match std::fs::read_dir(&root) {
-            Ok(entries) => entries,
-            Err(_) => {
-                signature_parts.push(format!("{root_display}:unreadable"));
-                continue;
-            }
-        };
+    });
 
-        for entry in read.flatten() {
-            let Ok(file_type) = entry.file_type() else {
-                continue;
-            };
-            if !file_type.is_dir() {
-                continue;
-            }
-            let dir_name = entry.file_name().to_string_lossy().to_string();
-            let lower = dir_name.to_ascii_lowercase();
-            if !(lower.starts_with("models--") && lower.contains("faster-whisper")) {
-                continue;
-            }
-            let modified = entry
-                .metadata()
-                .ok()
-                .and_then(|m| m.modified().ok())
-                .and_then(|ts| ts.duration_since(UNIX_EPOCH).ok())
-                .map_or(0, |dur| dur.as_secs());
-            signature_parts.push(format!("{root_display}:{dir_name}:{modified}"));
+    frontend_event_stream(rx)
+}
 
-            if lower.contains("faster-whisper-large-v3-turbo") {
-                models.insert("whisper-large-v3-turbo".to_string());
-                models.insert("large-v3".to_string());
-            }
-            if lower.contains("faster-whisper-large-v3") {
-                models.insert("large-v3".to_string());
-            }
-            if lower.contains("faster-whisper-large-v2") {
-                models.insert("large-v2".to_string());
-            }
-            if lower.contains("faster-whisper-large-v1") {
-                models.insert("large-v1".to_string());
-            }
-            if lower.contains("faster-whisper-large") {
-                models.insert("large".to_string());
-            }
-            if lower.contains("faster-whisper-medium") {
-                models.insert("medium".to_string());
-            }
-            if lower.contains("faster-whisper-small") {
-                models.insert("small".to_string());
-            }
-            if lower.contains("faster-whisper-base") {
-                models.insert("base".to_string());
-            }
-            if lower.contains("faster-whisper-tiny") {
-                models.insert("tiny".to_string());
-            }
+fn transcript_rel_path_for_media(media_rel_path: &str) -> Option<String> {
+    let normalized = media_rel_path.trim_start_matches('/');
+    let relative = normalized.strip_prefix("journals/media/")?;
+    let media_rel = StdPath::new(relative);
+    let stem = media_rel.file_stem()?.to_str()?.trim();
+    if stem.is_empty() {
+        return None;
+    }
+
+    let mut out = PathBuf::from("journals/text/transcriptions");
+    if let Some(parent) = media_rel.parent() {
+        if !parent.as_os_str().is_empty() {
+            out.push(parent);
         }
     }
+    out.push(format!("{stem}.txt"));
+    Some(out.to_string_lossy().replace('\\', "/"))
+}
 
-    signature_parts.sort();
-    let cache_key = signature_parts.join("|");
-    (cache_key, models)
+fn transcript_json_rel_path(transcript_rel_path: &str) -> String {
+    match transcript_rel_path.rsplit_once('.') {
+        Some((base, _)) => format!("{base}.json"),
+        None => format!("{transcript_rel_path}.json"),
+    }
 }
 
-fn transcription_cache_roots() -> Vec<PathBuf> {
-    let mut roots: Vec<PathBuf> = Vec::new();
-    if let Some(home) = std::env::var("HOME")
-        .ok()
-        .map(|value| value.trim().to_string())
-        .filter(|value| !value.is_empty())
-    {
-        roots.push(PathBuf::from(home).join(".cache/huggingface/hub"));
+fn transcript_srt_rel_path(transcript_rel_path: &str) -> String {
+    match transcript_rel_path.rsplit_once('.') {
+        Some((base, _)) => format!("{base}.srt"),
+        None => format!("{transcript_rel_path}.srt"),
     }
-    if let Some(hf_home) = std::env::var("HF_HOME")
-        .ok()
-        .map(|value| value.trim().to_string())
-        .filter(|value| !value.is_empty())
-    {
-        roots.push(PathBuf::from(hf_home).join("hub"));
+}
+
+fn legacy_transcript_rel_path_for_media(media_rel_path: &str) -> Option<String> {
+    let stem = StdPath::new(media_rel_path).file_stem()?.to_str()?.trim();
+    if stem.is_empty() {
+        return None;
     }
-    roots
+    Some(format!("journals/text/transcript/{stem}.txt"))
 }
 
-#[derive(serde::Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct JournalTranscribeStatusQuery {
-    media_path: String,
+/// Existing transcripts and sidecars that belong to a library item, paired
+/// with their role (`transcript`, `caption`, `tags`, `meta`).
+fn library_item_related_paths(
+    workspace_dir: &StdPath,
+    requested: &str,
+) -> Vec<(String, &'static str)> {
+    let mut related = Vec::new();
+    if requested.to_ascii_lowercase().starts_with("journals/media/") {
+        let mut transcript_candidates = Vec::new();
+        if let Some(transcript_rel) = transcript_rel_path_for_media(requested) {
+            transcript_candidates.push(transcript_json_rel_path(&transcript_rel));
+            transcript_candidates.push(transcript_srt_rel_path(&transcript_rel));
+            transcript_candidates.insert(0, transcript_rel);
+        }
+        transcript_candidates.extend(legacy_transcript_rel_path_for_media(requested));
+        for transcript_rel in transcript_candidates {
+            if resolve_workspace_text_path(workspace_dir, &transcript_rel)
+                .is_ok_and(|abs| abs.is_file())
+            {
+                related.push((transcript_rel, "transcript"));
+            }
+        }
+    }
+    for (suffix, role) in [
+        (".caption.txt", "caption"),
+        (library_tags::TAGS_SIDECAR_SUFFIX, "tags"),
+        (crate::media_probe::META_SIDECAR_SUFFIX, "meta"),
+    ] {
+        let sidecar_rel = format!("{requested}{suffix}");
+        if workspace_dir.join(&sidecar_rel).is_file() {
+            related.push((sidecar_rel, role));
+        }
+    }
+    related
 }
 
-async fn handle_media_upload(
-    State(state): State<AppState>,
-    Query(query): Query<MediaUploadQuery>,
-    req: Request,
-) -> axum::response::Response {
-    if let Some(err) = pairing_auth_error(&state, req.headers(), "Media upload") {
-        return err.into_response();
+fn source_file_created_or_modified_at(path: &StdPath) -> Option<chrono::DateTime<Utc>> {
+    let metadata = path.metadata().ok()?;
+    let timestamp = metadata.created().or_else(|_| metadata.modified()).ok()?;
+    Some(timestamp.into())
+}
+
+fn rewrite_transcript_json_sidecar_paths(
+    json_abs_path: &StdPath,
+    media_abs_path: &StdPath,
+    transcript_abs_path: &StdPath,
+) -> Result<()> {
+    let raw = std::fs::read_to_string(json_abs_path)
+        .with_context(|| format!("failed to read {}", json_abs_path.display()))?;
+    let mut payload: serde_json::Value = serde_json::from_str(&raw)
+        .with_context(|| format!("invalid JSON in {}", json_abs_path.display()))?;
+    let Some(object) = payload.as_object_mut() else {
+        anyhow::bail!(
+            "transcript json sidecar must be a JSON object: {}",
+            json_abs_path.display()
+        );
+    };
+    object.insert(
+        "source".to_string(),
+        serde_json::Value::String(media_abs_path.to_string_lossy().into_owned()),
+    );
+    object.insert(
+        "transcriptPath".to_string(),
+        serde_json::Value::String(transcript_abs_path.to_string_lossy().into_owned()),
+    );
+    let serialized = serde_json::to_string_pretty(&payload)
+        .with_context(|| format!("failed to serialize {}", json_abs_path.display()))?;
+    std::fs::write(json_abs_path, format!("{serialized}\n"))
+        .with_context(|| format!("failed to write {}", json_abs_path.display()))
+}
+
+fn relocate_inbox_audio_after_transcription(
+    workspace_dir: &StdPath,
+    media_rel_path: &str,
+    transcript_rel_path: &str,
+) -> Result<(String, String)> {
+    let media_rel = normalize_workspace_relative_path(media_rel_path);
+    let transcript_rel = normalize_workspace_relative_path(transcript_rel_path);
+    if !media_rel.starts_with(&format!("{JOURNAL_AUDIO_INBOX_DIR}/")) {
+        return Ok((media_rel, transcript_rel));
     }
 
-    let headers = req.headers().clone();
-    let content_type = headers
-        .get(header::CONTENT_TYPE)
-        .and_then(|v| v.to_str().ok())
-        .unwrap_or("application/octet-stream")
-        .to_string();
+    let Ok(media_abs) = resolve_workspace_media_path(workspace_dir, &media_rel)
+        .and_then(|path| ensure_writable(workspace_dir, &path).map(|()| path))
+    else {
+        return Ok((media_rel, transcript_rel));
+    };
+    let observed_at = source_file_created_or_modified_at(&media_abs).unwrap_or_else(Utc::now);
+    let original_name = media_abs
+        .file_name()
+        .and_then(|value| value.to_str())
+        .unwrap_or("audio-note.m4a");
 
-    let kind = query
-        .kind
-        .as_deref()
-        .map(str::trim)
-        .filter(|v| !v.is_empty())
-        .unwrap_or_else(|| infer_media_kind_from_content_type(&content_type));
-    let source = query
-        .source
-        .as_deref()
-        .map(str::trim)
-        .filter(|v| !v.is_empty())
-        .unwrap_or("mobile");
-    let title = query
-        .title
-        .as_deref()
-        .map(str::trim)
-        .filter(|v| !v.is_empty())
-        .map(ToOwned::to_owned);
-    let original_name = query
-        .filename
-        .clone()
-        .or_else(|| {
-            headers
-                .get("X-File-Name")
-                .and_then(|v| v.to_str().ok())
-                .map(ToOwned::to_owned)
-        })
-        .unwrap_or_else(|| format!("upload-{}", Uuid::new_v4()));
+    let dir_rel = format!(
+        "{}/audio/{:04}/{:02}/{:02}",
+        JOURNAL_MEDIA_DIR,
+        observed_at.year(),
+        observed_at.month(),
+        observed_at.day()
+    );
+    let file_name = format!(
+        "{}_{}",
+        observed_at.format("%H%M%S"),
+        file_names::safe_file_name(original_name, "audio-note.m4a")
+    );
+    let mut candidate_rel = format!("{dir_rel}/{file_name}");
+    let mut candidate_abs = workspace_dir.join(&candidate_rel);
+    if candidate_abs.exists() && candidate_abs != media_abs {
+        let file_name = file_names::unique_file_name(&workspace_dir.join(&dir_rel), &file_name);
+        candidate_rel = format!("{dir_rel}/{file_name}");
+        candidate_abs = workspace_dir.join(&candidate_rel);
+    }
 
-    let workspace_dir = state.config.lock().workspace_dir.clone();
-    let rel_path = media_storage_rel_path(kind, &original_name);
-    let abs_path = workspace_dir.join(&rel_path);
-    if let Some(parent) = abs_path.parent() {
-        if let Err(err) = tokio::fs::create_dir_all(parent).await {
-            return frontend_internal_error_response(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "media upload dir create",
-                "Failed to prepare media storage for the upload.",
-                err,
+    if candidate_abs == media_abs {
+        return Ok((media_rel, transcript_rel));
+    }
+
+    if let Some(parent) = candidate_abs.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    std::fs::rename(&media_abs, &candidate_abs).with_context(|| {
+        format!(
+            "failed to relocate inbox audio {} -> {}",
+            media_abs.display(),
+            candidate_abs.display()
+        )
+    })?;
+
+    let Some(new_transcript_rel) = transcript_rel_path_for_media(&candidate_rel) else {
+        return Ok((candidate_rel, transcript_rel));
+    };
+    let new_transcript_abs = workspace_dir.join(&new_transcript_rel);
+    let new_transcript_json_rel = transcript_json_rel_path(&new_transcript_rel);
+    let transcript_pairs = [
+        (transcript_rel.clone(), new_transcript_rel.clone()),
+        (
+            transcript_json_rel_path(&transcript_rel),
+            new_transcript_json_rel.clone(),
+        ),
+        (
+            transcript_srt_rel_path(&transcript_rel),
+            transcript_srt_rel_path(&new_transcript_rel),
+        ),
+    ];
+    for (old_rel, new_rel) in transcript_pairs {
+        if old_rel == new_rel {
+            continue;
+        }
+        let old_abs = workspace_dir.join(&old_rel);
+        if !old_abs.exists() {
+            continue;
+        }
+        let new_abs = workspace_dir.join(&new_rel);
+        if let Some(parent) = new_abs.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create {}", parent.display()))?;
+        }
+        std::fs::rename(&old_abs, &new_abs).with_context(|| {
+            format!(
+                "failed to relocate transcript sidecar {} -> {}",
+                old_abs.display(),
+                new_abs.display()
+            )
+        })?;
+        if let Err(err) =
+            local_store::rename_workspace_synth_source_path(workspace_dir, &old_rel, &new_rel)
+        {
+            tracing::warn!(
+                old = old_rel,
+                new = %new_rel,
+                err = %err,
+                "transcript source path rename failed"
             );
         }
+        if new_rel == new_transcript_json_rel {
+            if let Err(err) = rewrite_transcript_json_sidecar_paths(
+                &new_abs,
+                &candidate_abs,
+                &new_transcript_abs,
+            ) {
+                tracing::warn!(
+                    path = %new_rel,
+                    err = %err,
+                    "failed to rewrite relocated transcript json metadata"
+                );
+            }
+        }
+    }
+
+    match local_store::rename_media_asset_path(workspace_dir, &media_rel, &candidate_rel) {
+        Ok(0) => tracing::warn!(
+            old = media_rel,
+            new = %candidate_rel,
+            "audio relocation: no media asset rows matched"
+        ),
+        Err(err) => tracing::warn!(
+            old = media_rel,
+            new = %candidate_rel,
+            err = %err,
+            "audio relocation failed to update media metadata"
+        ),
+        _ => {}
+    }
+
+    Ok((candidate_rel, new_transcript_rel))
+}
+
+fn enqueue_transcription_job(
+    state: AppState,
+    media_rel_path: String,
+    media_abs_path: PathBuf,
+    transcript_rel_path: String,
+    transcript_abs_path: PathBuf,
+    transcription_config: TranscriptionConfig,
+) -> JournalTranscriptionJob {
+    {
+        let mut jobs = state.journal_transcription_jobs.lock();
+        if let Some(existing) = jobs.get(&media_rel_path).cloned() {
+            if existing.status == "queued" || existing.status == "running" {
+                return existing;
+            }
+        }
+        jobs.insert(media_rel_path.clone(), JournalTranscriptionJob::queued());
     }
 
-    let mut file = match tokio::fs::File::create(&abs_path).await {
-        Ok(f) => f,
-        Err(err) => {
-            return frontend_internal_error_response(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "media upload file create",
-                "Failed to create the uploaded media file.",
-                err,
+    let queued_path = transcript_rel_path.clone();
+    let task_transcript_rel_path = transcript_rel_path.clone();
+    let state_for_task = state.clone();
+    tokio::spawn(async move {
+        {
+            let mut jobs = state_for_task.journal_transcription_jobs.lock();
+            jobs.insert(
+                media_rel_path.clone(),
+                JournalTranscriptionJob {
+                    status: "running".to_string(),
+                    transcript_path: Some(task_transcript_rel_path.clone()),
+                    error: None,
+                    updated_at: chrono::Utc::now().to_rfc3339(),
+                },
             );
         }
-    };
 
-    let mut body = req.into_body();
-    let mut bytes_written: u64 = 0;
-    while let Some(frame_result) = body.frame().await {
-        let frame = match frame_result {
-            Ok(frame) => frame,
-            Err(err) => {
-                let _ = tokio::fs::remove_file(&abs_path).await;
-                return frontend_internal_error_response(
-                    StatusCode::BAD_REQUEST,
-                    "media upload stream",
-                    "The upload stream could not be read.",
-                    err,
-                );
+        let (final_media_rel_path, final_state) = match run_local_faster_whisper(
+            &state_for_task,
+            &media_abs_path,
+            &transcript_abs_path,
+            &transcription_config,
+        )
+        .await
+        {
+            Ok(_) => {
+                let workspace_dir = state_for_task.config.lock().workspace_dir.clone();
+                let (final_media_rel, final_transcript_rel) =
+                    match relocate_inbox_audio_after_transcription(
+                        &workspace_dir,
+                        &media_rel_path,
+                        &task_transcript_rel_path,
+                    ) {
+                        Ok(paths) => paths,
+                        Err(err) => {
+                            tracing::warn!(
+                                media_path = %media_rel_path,
+                                transcript_path = %task_transcript_rel_path,
+                                err = %err,
+                                "Failed to relocate processed inbox audio; keeping original paths"
+                            );
+                            (media_rel_path.clone(), task_transcript_rel_path.clone())
+                        }
+                    };
+                search_index::journal_file_changed(&workspace_dir, &final_transcript_rel).await;
+                (
+                    final_media_rel,
+                    JournalTranscriptionJob {
+                        status: "done".to_string(),
+                        transcript_path: Some(final_transcript_rel),
+                        error: None,
+                        updated_at: chrono::Utc::now().to_rfc3339(),
+                    },
+                )
             }
+            Err(error) => (
+                media_rel_path.clone(),
+                JournalTranscriptionJob {
+                    status: "error".to_string(),
+                    transcript_path: Some(task_transcript_rel_path.clone()),
+                    error: Some(frontend_background_error(
+                        "journal transcription",
+                        "Transcription failed.",
+                        &error,
+                    )),
+                    updated_at: chrono::Utc::now().to_rfc3339(),
+                },
+            ),
         };
-        if let Some(data) = frame.data_ref() {
-            if let Err(err) = file.write_all(data).await {
-                let _ = tokio::fs::remove_file(&abs_path).await;
-                return frontend_internal_error_response(
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    "media upload file write",
-                    "Failed while writing the uploaded media file.",
-                    err,
-                );
-            }
-            bytes_written = bytes_written.saturating_add(data.len() as u64);
-        }
-    }
-    let _ = file.flush().await;
 
-    let pb_record = match upsert_media_asset_metadata(
-        &state,
-        &rel_path,
-        &content_type,
-        kind,
-        title.as_deref(),
-        source,
-        bytes_written,
-        query.entry_id.as_deref(),
-    )
-    .await
-    {
-        Ok(record) => Some(record),
-        Err(e) => {
-            tracing::warn!("Media metadata write failed: {e}");
-            None
+        {
+            let mut jobs = state_for_task.journal_transcription_jobs.lock();
+            jobs.insert(media_rel_path.clone(), final_state.clone());
+            if final_media_rel_path != media_rel_path {
+                jobs.insert(final_media_rel_path.clone(), final_state.clone());
+            }
         }
-    };
-
-    let transcription = if kind.eq_ignore_ascii_case("audio") {
-        enqueue_journal_transcription(&state, rel_path.clone()).await
-    } else {
-        None
-    };
 
-    let body = serde_json::json!({
-        "ok": true,
-        "kind": kind,
-        "contentType": content_type,
-        "bytes": bytes_written,
-        "path": rel_path,
-        "title": title,
-        "metadata": pb_record,
-        "transcription": transcription,
     });
-    (StatusCode::OK, Json(body)).into_response()
+
+    JournalTranscriptionJob {
+        status: "queued".to_string(),
+        transcript_path: Some(queued_path),
+        error: None,
+        updated_at: chrono::Utc::now().to_rfc3339(),
+    }
 }
 
-async fn handle_journal_text(
-    State(state): State<AppState>,
-    headers: HeaderMap,
-    Json(body): Json<JournalTextBody>,
-) -> axum::response::Response {
-    if let Some(err) = pairing_auth_error(&state, &headers, "Journal text") {
-        return err.into_response();
+async fn enqueue_journal_transcription(
+    state: &AppState,
+    media_rel_path: String,
+) -> Option<serde_json::Value> {
+    let workspace_dir = state.config.lock().workspace_dir.clone();
+    let Ok(abs_media_path) = resolve_workspace_media_path(&workspace_dir, &media_rel_path) else {
+        return None;
+    };
+    let Some(transcript_rel_path) = transcript_rel_path_for_media(&media_rel_path) else {
+        return None;
+    };
+    let transcript_json_path = transcript_json_rel_path(&transcript_rel_path);
+    let transcript_srt_path = transcript_srt_rel_path(&transcript_rel_path);
+    let transcript_abs_path = workspace_dir.join(&transcript_rel_path);
+    if transcript_abs_path.exists() && transcript_abs_path.is_file() {
+        let existing = tokio::fs::read_to_string(&transcript_abs_path)
+            .await
+            .unwrap_or_default();
+        if !existing.trim().is_empty() {
+            return Some(serde_json::json!({
+                "status": "done",
+                "path": transcript_rel_path,
+                "jsonPath": transcript_json_path,
+                "srtPath": transcript_srt_path,
+            }));
+        }
     }
-    let content = body.content.trim();
-    if content.is_empty() {
-        return frontend_error_response(
-            StatusCode::BAD_REQUEST,
-            "JOURNAL_CONTENT_REQUIRED",
-            "content is required",
-        )
-        .into_response();
+    let cfg = state.config.lock().transcription.clone();
+    if !cfg.enabled {
+        return Some(serde_json::json!({
+            "status": "disabled",
+            "path": transcript_rel_path,
+            "jsonPath": transcript_json_path,
+            "srtPath": transcript_srt_path,
+        }));
     }
+    let media_capabilities = local_media_capabilities(&state.config.lock().clone());
+    if !media_capabilities.transcribe_media {
+        return Some(serde_json::json!({
+            "status": "unsupported",
+            "path": transcript_rel_path,
+            "jsonPath": transcript_json_path,
+            "srtPath": transcript_srt_path,
+            "error": "Local transcription is unavailable on this device.",
+        }));
+    }
+    let job = enqueue_transcription_job(
+        state.clone(),
+        media_rel_path,
+        abs_media_path,
+        transcript_rel_path.clone(),
+        transcript_abs_path,
+        cfg,
+    );
+    Some(serde_json::json!({
+        "status": job.status,
+        "path": transcript_rel_path,
+        "jsonPath": transcript_json_path,
+        "srtPath": transcript_srt_path,
+        "error": job.error,
+        "updatedAt": job.updated_at,
+    }))
+}
+
+async fn run_local_faster_whisper(
+    state: &AppState,
+    media_abs_path: &StdPath,
+    transcript_abs_path: &StdPath,
+    transcription_config: &TranscriptionConfig,
+) -> Result<()> {
+    let workspace_dir = state.config.lock().workspace_dir.clone();
+    ensure_workspace_content_agent_helper_scripts(&workspace_dir).await?;
+
+    let script_path = workspace_dir.join("scripts/transcribe_audio_journal.py");
+    let mut cmd = Command::new(transcription_config.python_bin.trim());
+    cmd.arg(&script_path)
+        .arg("--input")
+        .arg(media_abs_path)
+        .arg("--output")
+        .arg(transcript_abs_path)
+        .arg("--model")
+        .arg(transcription_config.model.trim())
+        .arg("--device")
+        .arg(transcription_config.device.trim())
+        .arg("--compute-type")
+        .arg(transcription_config.compute_type.trim())
+        .arg("--beam-size")
+        .arg(transcription_config.beam_size.max(1).to_string())
+        .current_dir(&workspace_dir)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .kill_on_drop(true);
 
-    let title = body
-        .title
-        .as_deref()
-        .map(str::trim)
-        .filter(|v| !v.is_empty())
-        .unwrap_or("Journal entry");
-    let source = body
-        .source
+    if let Some(language) = transcription_config
+        .language
         .as_deref()
         .map(str::trim)
-        .filter(|v| !v.is_empty())
-        .unwrap_or("mobile");
-    let workspace_dir = state.config.lock().workspace_dir.clone();
-    let rel_path = text_journal_rel_path(title);
-    let abs_path = workspace_dir.join(&rel_path);
-    if let Some(parent) = abs_path.parent() {
-        if let Err(err) = tokio::fs::create_dir_all(parent).await {
-            return frontend_internal_error_response(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "journal text dir create",
-                "Failed to prepare journal storage.",
-                err,
-            );
-        }
+        .filter(|value: &&str| !value.is_empty())
+    {
+        cmd.arg("--language").arg(language);
     }
-    let file_body = format!("{content}\n");
-    if let Err(err) = tokio::fs::write(&abs_path, file_body).await {
-        return frontend_internal_error_response(
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "journal text save",
-            "Failed to save the journal note.",
-            err,
+
+    let output = tokio::time::timeout(Duration::from_secs(3_600), cmd.output())
+        .await
+        .context("local transcription timed out")?
+        .context("failed to execute local transcriber script")?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+        anyhow::bail!(
+            "transcriber script failed ({}): {}",
+            output.status,
+            truncate_with_ellipsis(
+                &(if stderr.trim().is_empty() { stdout } else { stderr }),
+                300
+            )
         );
     }
 
-    let pb_record = match create_journal_entry_metadata(
-        &state,
-        &rel_path,
-        title,
-        content,
-        source,
-        body.tags.as_deref(),
-    )
-    .await
-    {
-        Ok(record) => Some(record),
-        Err(e) => {
-            tracing::warn!("Journal metadata write failed: {e}");
-            None
-        }
-    };
+    Ok(())
+}
 
-    let resp = serde_json::json!({
-        "ok": true,
-        "path": rel_path,
-        "title": title,
-        "metadata": pb_record,
-    });
-    (StatusCode::OK, Json(resp)).into_response()
+fn write_workspace_helper_script(
+    workspace_dir: &StdPath,
+    filename: &str,
+    body: &str,
+) -> Result<()> {
+    let scripts_dir = workspace_dir.join("scripts");
+    std::fs::create_dir_all(&scripts_dir)?;
+    std::fs::write(scripts_dir.join(filename), body)?;
+    Ok(())
 }
 
-async fn handle_media_stream(
-    State(state): State<AppState>,
-    AxumPath(path): AxumPath<String>,
-    req: Request,
-) -> axum::response::Response {
-    if let Some(err) = pairing_auth_error(&state, req.headers(), "Media stream") {
-        return err.into_response();
-    }
-    let workspace_dir = state.config.lock().workspace_dir.clone();
-    let Some(abs_path) = resolve_workspace_media_path(&workspace_dir, &path) else {
-        return frontend_error_response(
-            StatusCode::BAD_REQUEST,
-            "MEDIA_PATH_INVALID",
-            "Invalid media path",
-        )
-        .into_response();
-    };
-    if !abs_path.exists() || !abs_path.is_file() {
-        return frontend_error_response(
-            StatusCode::NOT_FOUND,
-            "MEDIA_FILE_NOT_FOUND",
-            "Media file not found",
-        )
-        .into_response();
-    }
+fn ensure_workspace_content_agent_helper_scripts_sync(workspace_dir: &StdPath) -> Result<()> {
+    write_workspace_helper_script(
+        workspace_dir,
+        "transcribe_audio_journal.py",
+        include_str!("../../scripts/transcribe_audio_journal.py"),
+    )?;
+    write_workspace_helper_script(
+        workspace_dir,
+        "render_audio_insight_clip.py",
+        include_str!("../../scripts/render_audio_insight_clip.py"),
+    )?;
+    Ok(())
+}
 
-    match ServeFile::new(abs_path).oneshot(req).await {
-        Ok(resp) => resp.into_response(),
-        Err(err) => frontend_internal_error_response(
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "media stream",
-            "Failed to stream the media file.",
-            err,
-        ),
-    }
+async fn ensure_workspace_content_agent_helper_scripts(workspace_dir: &StdPath) -> Result<()> {
+    let scripts_dir = workspace_dir.join("scripts");
+    tokio::fs::create_dir_all(&scripts_dir).await?;
+    tokio::fs::write(
+        scripts_dir.join("transcribe_audio_journal.py"),
+        include_str!("../../scripts/transcribe_audio_journal.py"),
+    )
+    .await?;
+    tokio::fs::write(
+        scripts_dir.join("render_audio_insight_clip.py"),
+        include_str!("../../scripts/render_audio_insight_clip.py"),
+    )
+    .await?;
+    Ok(())
 }
 
-async fn handle_library_items(
-    State(state): State<AppState>,
-    headers: HeaderMap,
-    Query(query): Query<LibraryItemsQuery>,
-) -> axum::response::Response {
-    if let Some(err) = pairing_auth_error(&state, &headers, "Library list") {
-        return err.into_response();
-    }
-    let workspace_dir = state.config.lock().workspace_dir.clone();
-    let scope = query.scope.as_deref().unwrap_or("all");
-    let limit = query.limit.unwrap_or(200).clamp(1, 1000);
-    match list_workspace_library_items(&workspace_dir, scope, limit) {
-        Ok(items) => (StatusCode::OK, Json(serde_json::json!({ "items": items }))).into_response(),
-        Err(err) => frontend_internal_error_response(
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "library item list",
-            "Failed to list library items.",
-            err,
-        ),
+fn infer_media_kind_from_content_type(content_type: &str) -> &'static str {
+    let lower = content_type.to_ascii_lowercase();
+    if lower.starts_with("audio/") {
+        "audio"
+    } else if lower.starts_with("video/") {
+        "video"
+    } else if lower.starts_with("image/") {
+        "image"
+    } else {
+        "file"
     }
 }
 
-async fn handle_library_text(
-    State(state): State<AppState>,
-    headers: HeaderMap,
-    Query(query): Query<LibraryTextQuery>,
-) -> axum::response::Response {
-    if let Some(err) = pairing_auth_error(&state, &headers, "Library text") {
-        return err.into_response();
-    }
-    let workspace_dir = state.config.lock().workspace_dir.clone();
-    let Some(path) = resolve_workspace_text_path(&workspace_dir, &query.path) else {
-        return frontend_error_response(
-            StatusCode::BAD_REQUEST,
-            "LIBRARY_TEXT_PATH_INVALID",
-            "Invalid text path",
-        )
-        .into_response();
+fn media_storage_rel_path(workspace_dir: &StdPath, kind: &str, original_name: &str) -> String {
+    let now = chrono::Utc::now();
+    let kind = kind.trim().to_ascii_lowercase();
+    let kind_dir = match kind.as_str() {
+        "audio" => "audio",
+        "video" => "video",
+        "image" => "image",
+        _ => "files",
     };
-    match tokio::fs::read_to_string(&path).await {
-        Ok(content) => {
-            let rel = path
-                .strip_prefix(&workspace_dir)
-                .ok()
-                .map(workspace_relative_display_path)
-                .unwrap_or_else(|| normalize_workspace_relative_path(&query.path));
-            (StatusCode::OK, Json(serde_json::json!({"path": rel, "content": content}))).into_response()
-        }
-        Err(err) => frontend_internal_error_response(
-            StatusCode::NOT_FOUND,
-            "library text read",
-            "Failed to read the requested text file.",
-            err,
-        ),
-    }
+    let dir_rel = format!(
+        "{}/{}/{:04}/{:02}/{:02}",
+        JOURNAL_MEDIA_DIR,
+        kind_dir,
+        now.year(),
+        now.month(),
+        now.day()
+    );
+    let safe_name = file_names::safe_file_name(original_name, "upload.bin");
+    let file_name = format!("{}_{}", now.format("%H%M%S"), safe_name);
+    let file_name = file_names::unique_file_name(&workspace_dir.join(&dir_rel), &file_name);
+    format!("{dir_rel}/{file_name}")
 }
 
-fn maybe_mark_world_feed_dirty_for_path(workspace_dir: &StdPath, rel_path: &str) {
-    if normalize_workspace_relative_path(rel_path).starts_with("posts/") {
-        let _ = crate::feed::mark_world_feed_dirty(workspace_dir);
-    }
+fn text_journal_rel_path(workspace_dir: &StdPath, title: &str) -> String {
+    let now = chrono::Utc::now();
+    let dir_rel = format!(
+        "{}/{:04}/{:02}/{:02}",
+        JOURNAL_TEXT_DIR,
+        now.year(),
+        now.month(),
+        now.day()
+    );
+    let stem = file_names::safe_file_name(title, "journal");
+    let file_name = format!("{}_{}.md", now.format("%H%M%S"), stem);
+    let file_name = file_names::unique_file_name(&workspace_dir.join(&dir_rel), &file_name);
+    format!("{dir_rel}/{file_name}")
 }
 
-async fn handle_library_save_text(
-    State(state): State<AppState>,
-    headers: HeaderMap,
-    Json(body): Json<SaveTextBody>,
-) -> axum::response::Response {
-    if let Some(err) = pairing_auth_error(&state, &headers, "Library save") {
-        return err.into_response();
-    }
-    let workspace_dir = state.config.lock().workspace_dir.clone();
-    let Some(path) = resolve_workspace_text_path(&workspace_dir, &body.path) else {
-        return frontend_error_response(
-            StatusCode::BAD_REQUEST,
-            "LIBRARY_TEXT_PATH_INVALID",
-            "Invalid text path",
-        )
-        .into_response();
+fn normalize_workspace_relative_path(requested: &str) -> String {
+    requested
+        .trim()
+        .trim_start_matches('/')
+        .replace('\\', "/")
+}
+
+/// URL that `/api/media/*` serves a workspace file at, under the gateway's
+/// route prefix (see `GatewayConfig::route_prefix`).
+pub(crate) fn media_url(route_prefix: &str, rel_path: &str) -> String {
+    format!("{route_prefix}/api/media/{rel_path}")
+}
+
+fn workspace_relative_display_path(path: &StdPath) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+/// Existing media file under `journals/`.
+fn resolve_workspace_media_path(
+    workspace_dir: &StdPath,
+    requested: &str,
+) -> Result<PathBuf, ResolveError> {
+    resolve_workspace_path_under(
+        workspace_dir,
+        requested,
+        ResolveMode::ExistingFile,
+        &["journals"],
+    )
+}
+
+const LIBRARY_TEXT_ROOTS: &[&str] = &[
+    "journals",
+    "memory",
+    "state",
+    "posts",
+    "outputs",
+    "artifacts",
+];
+
+/// Existing or creatable text file under one of the library's top-level
+/// folders.
+fn resolve_workspace_text_path(
+    workspace_dir: &StdPath,
+    requested: &str,
+) -> Result<PathBuf, ResolveError> {
+    resolve_workspace_path_under(
+        workspace_dir,
+        requested,
+        ResolveMode::CreatableFile,
+        LIBRARY_TEXT_ROOTS,
+    )
+}
+
+/// Anything outside `roots` is [`ResolveError::Escapes`], whether or not it
+/// exists, so missing files only become 404s where the caller may look.
+fn resolve_workspace_path_under(
+    workspace_dir: &StdPath,
+    requested: &str,
+    mode: ResolveMode,
+    roots: &[&str],
+) -> Result<PathBuf, ResolveError> {
+    let in_roots = |path: &StdPath| {
+        path.strip_prefix(workspace_dir)
+            .ok()
+            .and_then(|rel| rel.components().next())
+            .is_some_and(|first| roots.iter().any(|root| first.as_os_str() == *root))
     };
-    if let Some(parent) = path.parent() {
-        if let Err(err) = tokio::fs::create_dir_all(parent).await {
-            return frontend_internal_error_response(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "library text dir create",
-                "Failed to prepare the destination folder.",
-                err,
+    match resolve_contained_path(workspace_dir, requested, mode) {
+        Ok(resolved) if in_roots(&resolved) => Ok(resolved),
+        Err(ResolveError::Escapes) => Err(ResolveError::Escapes),
+        Err(err) if in_roots(&workspace_dir.join(normalize_workspace_relative_path(requested))) => {
+            Err(err)
+        }
+        _ => {
+            tracing::debug!(
+                requested = %requested,
+                roots = ?roots,
+                "resolve_workspace_path_under: path outside the allowed folders"
             );
+            Err(ResolveError::Escapes)
         }
     }
-    if let Err(err) = tokio::fs::write(&path, &body.content).await {
-        return frontend_internal_error_response(
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "library text save",
-            "Failed to save the text file.",
-            err,
-        );
-    }
-    let rel = path
-        .strip_prefix(&workspace_dir)
-        .ok()
-        .map(workspace_relative_display_path)
-        .unwrap_or_else(|| normalize_workspace_relative_path(&body.path));
-    maybe_mark_world_feed_dirty_for_path(&workspace_dir, &rel);
-    (StatusCode::OK, Json(serde_json::json!({"ok": true, "path": rel}))).into_response()
 }
 
-async fn handle_library_delete(
-    State(state): State<AppState>,
-    headers: HeaderMap,
-    Json(body): Json<DeleteLibraryBody>,
-) -> axum::response::Response {
-    if let Some(err) = pairing_auth_error(&state, &headers, "Library delete") {
-        return err.into_response();
-    }
-    let requested = normalize_workspace_relative_path(&body.path);
-    if requested.is_empty() {
-        return frontend_error_response(
+/// Map a refused media path to 400 when it escapes, 403 when it is in a
+/// read-only external root, and 404 when it is just not there.
+fn media_path_error_response(err: ResolveError) -> (StatusCode, Json<serde_json::Value>) {
+    match err {
+        ResolveError::Escapes => frontend_error_response(
             StatusCode::BAD_REQUEST,
-            "LIBRARY_PATH_REQUIRED",
-            "path is required",
-        )
-        .into_response();
+            "MEDIA_PATH_INVALID",
+            "Invalid media path",
+        ),
+        ResolveError::ReadOnly => frontend_error_response(
+            StatusCode::FORBIDDEN,
+            "MEDIA_PATH_READ_ONLY",
+            "Media path is in a read-only external folder",
+        ),
+        ResolveError::NotFound | ResolveError::NotAFile | ResolveError::NotADirectory => {
+            frontend_error_response(
+                StatusCode::NOT_FOUND,
+                "MEDIA_FILE_NOT_FOUND",
+                "Media file not found",
+            )
+        }
     }
+}
 
-    let workspace_dir = state.config.lock().workspace_dir.clone();
-    let lower = requested.to_ascii_lowercase();
-    let target_path = if lower.starts_with("journals/media/") {
-        resolve_workspace_media_path(&workspace_dir, &requested)
-    } else {
-        resolve_workspace_text_path(&workspace_dir, &requested)
-    };
-    let Some(abs_path) = target_path else {
-        return frontend_error_response(
+/// Library counterpart of [`media_path_error_response`].
+fn library_path_error_response(err: ResolveError) -> (StatusCode, Json<serde_json::Value>) {
+    match err {
+        ResolveError::Escapes => frontend_error_response(
             StatusCode::BAD_REQUEST,
             "LIBRARY_PATH_INVALID",
             "Invalid path",
-        )
-        .into_response();
-    };
-    if !abs_path.exists() || !abs_path.is_file() {
-        return frontend_error_response(
-            StatusCode::NOT_FOUND,
-            "LIBRARY_FILE_NOT_FOUND",
-            "File not found",
-        )
-        .into_response();
-    }
-
-    if let Err(err) = tokio::fs::remove_file(&abs_path).await {
-        return frontend_internal_error_response(
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "library delete",
-            "Failed to delete the file.",
-            err,
-        );
-    }
-
-    let mut removed_related: Vec<String> = Vec::new();
-    if lower.starts_with("journals/media/") {
-        let transcript_candidates = [
-            transcript_rel_path_for_media(&requested),
-            legacy_transcript_rel_path_for_media(&requested),
-        ];
-        for transcript_rel in transcript_candidates.into_iter().flatten() {
-            if let Some(transcript_abs) =
-                resolve_workspace_text_path(&workspace_dir, &transcript_rel)
-            {
-                if transcript_abs.exists()
-                    && transcript_abs.is_file()
-                    && tokio::fs::remove_file(&transcript_abs).await.is_ok()
-                {
-                    removed_related.push(transcript_rel);
-                }
-            }
-        }
-        let legacy_caption_rel = format!("{requested}.caption.txt");
-        if let Some(legacy_caption_abs) =
-            resolve_workspace_text_path(&workspace_dir, &legacy_caption_rel)
-        {
-            if legacy_caption_abs.exists() && legacy_caption_abs.is_file() {
-                if tokio::fs::remove_file(&legacy_caption_abs).await.is_ok() {
-                    removed_related.push(legacy_caption_rel);
-                }
-            }
+        ),
+        ResolveError::ReadOnly => frontend_error_response(
+            StatusCode::FORBIDDEN,
+            "LIBRARY_PATH_READ_ONLY",
+            "Path is in a read-only external folder",
+        ),
+        ResolveError::NotFound | ResolveError::NotAFile | ResolveError::NotADirectory => {
+            frontend_error_response(
+                StatusCode::NOT_FOUND,
+                "LIBRARY_FILE_NOT_FOUND",
+                "File not found",
+            )
         }
     }
-
-    maybe_mark_world_feed_dirty_for_path(&workspace_dir, &requested);
-
-    let body = serde_json::json!({
-        "ok": true,
-        "path": requested,
-        "removedRelated": removed_related,
-    });
-    (StatusCode::OK, Json(body)).into_response()
 }
 
-async fn handle_journal_transcribe(
-    State(state): State<AppState>,
-    headers: HeaderMap,
-    Json(body): Json<JournalTranscribeBody>,
-) -> axum::response::Response {
-    if let Some(err) = pairing_auth_error(&state, &headers, "Journal transcribe") {
-        return err.into_response();
-    }
-
-    let requested = normalize_workspace_relative_path(&body.media_path);
-    if requested.is_empty() {
-        return frontend_error_response(
-            StatusCode::BAD_REQUEST,
-            "TRANSCRIPTION_MEDIA_PATH_REQUIRED",
-            "mediaPath is required",
-        )
-        .into_response();
-    }
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum LibraryScope {
+    Journal,
+    Feed,
+    All,
+    /// Only items reached through symlinks into `[workspace] external_roots`.
+    External,
+}
 
-    let config_snapshot = state.config.lock().clone();
-    if !config_snapshot.transcription.enabled {
-        return frontend_error_response(
-            StatusCode::BAD_REQUEST,
-            "TRANSCRIPTION_DISABLED",
-            "Transcription is disabled. Enable [transcription] enabled = true in config.",
-        )
-        .into_response();
-    }
-    let media_capabilities = local_media_capabilities(&config_snapshot);
-    if !media_capabilities.transcribe_media {
-        return frontend_error_response(
-            StatusCode::BAD_REQUEST,
-            "TRANSCRIPTION_UNAVAILABLE",
-            "Local transcription is unavailable on this device. Check local media capabilities in settings.",
-        )
-        .into_response();
-    }
+/// Deepest directory below the workspace the library walk descends into, so
+/// a symlink loop inside an external root cannot recurse forever.
+const LIBRARY_MAX_DEPTH: usize = 16;
 
-    let workspace_dir = config_snapshot.workspace_dir.clone();
-    let Some(abs_media_path) = resolve_workspace_media_path(&workspace_dir, &requested) else {
-        return frontend_error_response(
-            StatusCode::BAD_REQUEST,
-            "TRANSCRIPTION_MEDIA_PATH_INVALID",
-            "Invalid media path",
-        )
-        .into_response();
-    };
-    if !abs_media_path.exists() || !abs_media_path.is_file() {
-        return frontend_error_response(
-            StatusCode::NOT_FOUND,
-            "TRANSCRIPTION_MEDIA_FILE_NOT_FOUND",
-            "Media file not found",
-        )
-        .into_response();
-    }
+fn list_workspace_library_items(
+    workspace_dir: &StdPath,
+    scope: &str,
+    limit: usize,
+    tag: Option<&str>,
+    route_prefix: &str,
+) -> Result<Vec<serde_json::Value>> {
+    let tag_filter = tag
+        .map(library_tags::parse_tag_list)
+        .and_then(|tags| tags.into_iter().next());
 
-    let Some(transcript_rel_path) = transcript_rel_path_for_media(&requested) else {
-        return frontend_error_response(
-            StatusCode::BAD_REQUEST,
-            "TRANSCRIPTION_PATH_UNAVAILABLE",
-            "Could not derive transcript path",
-        )
-        .into_response();
+    let mut roots: Vec<PathBuf> = Vec::new();
+    let normalized = scope.trim().to_ascii_lowercase();
+    let requested_scope = match normalized.as_str() {
+        "journal" => {
+            roots.push(workspace_dir.join("journals"));
+            LibraryScope::Journal
+        }
+        "feed" => {
+            roots.push(workspace_dir.join("posts"));
+            LibraryScope::Feed
+        }
+        "external" => {
+            if !crate::workspace::has_external_roots(workspace_dir) {
+                return Ok(Vec::new());
+            }
+            roots.push(workspace_dir.join("journals"));
+            roots.push(workspace_dir.join("posts"));
+            LibraryScope::External
+        }
+        _ => {
+            roots.push(workspace_dir.join("journals"));
+            roots.push(workspace_dir.join("posts"));
+            LibraryScope::All
+        }
     };
-    let transcript_abs_path = workspace_dir.join(&transcript_rel_path);
-    let transcript_json_path = transcript_json_rel_path(&transcript_rel_path);
-    let transcript_srt_path = transcript_srt_rel_path(&transcript_rel_path);
 
-    if transcript_abs_path.exists() && transcript_abs_path.is_file() {
-        let transcript_text = tokio::fs::read_to_string(&transcript_abs_path)
-            .await
-            .unwrap_or_default();
-        if !transcript_text.trim().is_empty() {
-            let body = serde_json::json!({
-                "ok": true,
-                "mediaPath": requested,
-                "path": transcript_rel_path,
-                "jsonPath": transcript_json_path,
-                "srtPath": transcript_srt_path,
-                "text": transcript_text,
-                "status": "done",
-            });
-            return (StatusCode::OK, Json(body)).into_response();
+    let mut items: Vec<serde_json::Value> = Vec::new();
+    let synth_state_map: HashMap<String, local_store::WorkspaceSynthSourceRecord> =
+        local_store::list_workspace_synth_sources(workspace_dir)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|item| (item.source_path.clone(), item))
+            .collect();
+    for root in roots {
+        if !root.exists() {
+            continue;
+        }
+        collect_library_items_recursive(
+            workspace_dir,
+            &root,
+            Location::Workspace,
+            &mut items,
+            limit,
+            requested_scope,
+            &synth_state_map,
+            tag_filter.as_deref(),
+            route_prefix,
+        )?;
+        if items.len() >= limit {
+            break;
         }
     }
 
-    let enqueue_result = enqueue_transcription_job(
-        state.clone(),
-        requested.clone(),
-        abs_media_path,
-        transcript_rel_path.clone(),
-        transcript_abs_path,
-        config_snapshot.transcription.clone(),
-    );
-
-    let body = serde_json::json!({
-        "ok": true,
-        "mediaPath": requested,
-        "path": transcript_rel_path,
-        "jsonPath": transcript_json_path,
-        "srtPath": transcript_srt_path,
-        "status": enqueue_result.status,
-        "error": enqueue_result.error,
-        "updatedAt": enqueue_result.updated_at,
+    items.sort_by(|a, b| {
+        let a_ts = a.get("modifiedAt").and_then(serde_json::Value::as_i64).unwrap_or(0);
+        let b_ts = b.get("modifiedAt").and_then(serde_json::Value::as_i64).unwrap_or(0);
+        b_ts.cmp(&a_ts)
     });
-    (StatusCode::OK, Json(body)).into_response()
+    items.truncate(limit);
+    attach_library_record_states(workspace_dir, &mut items);
+    Ok(items)
+}
+
+/// Add processing fields from the metadata store to listed items. Items the
+/// store does not track, or a store that cannot be read, keep the plain
+/// filesystem fields.
+fn attach_library_record_states(workspace_dir: &StdPath, items: &mut [serde_json::Value]) {
+    let paths: Vec<String> = items
+        .iter()
+        .filter_map(|item| {
+            item.get("path")
+                .and_then(|p| p.as_str())
+                .map(str::to_string)
+        })
+        .collect();
+    let states = match local_store::library_record_states(workspace_dir, &paths) {
+        Ok(states) => states,
+        Err(err) => {
+            tracing::debug!("Library metadata unavailable for listing: {err:#}");
+            HashMap::new()
+        }
+    };
+    for item in items.iter_mut() {
+        let Some(path) = item
+            .get("path")
+            .and_then(|p| p.as_str())
+            .map(str::to_string)
+        else {
+            continue;
+        };
+        let is_media = matches!(
+            item.get("kind").and_then(|k| k.as_str()),
+            Some("audio" | "video")
+        );
+        let transcript_path = is_media
+            .then(|| transcript_rel_path_for_media(&path))
+            .flatten()
+            .filter(|rel| workspace_dir.join(rel).is_file());
+        let Some(fields) = item.as_object_mut() else {
+            continue;
+        };
+        if let Some(transcript_path) = transcript_path {
+            fields.insert("transcriptPath".into(), transcript_path.into());
+        }
+        if let Some(state) = states.get(&path) {
+            fields.insert("status".into(), state.status.clone().into());
+            fields.insert(
+                "entryId".into(),
+                non_empty_string(state.entry_id.clone()).into(),
+            );
+            fields.insert("error".into(), non_empty_string(state.error.clone()).into());
+        }
+    }
 }
 
-async fn journal_transcribe_status_payload(
-    state: &AppState,
-    requested: &str,
-) -> Result<serde_json::Value, (StatusCode, Json<serde_json::Value>)> {
-    let workspace_dir = state.config.lock().workspace_dir.clone();
-    let Some(transcript_rel_path) = transcript_rel_path_for_media(requested) else {
-        return Err(frontend_error_response(
-            StatusCode::BAD_REQUEST,
-            "TRANSCRIPTION_PATH_UNAVAILABLE",
-            "Could not derive transcript path",
-        ));
+fn collect_library_items_recursive(
+    workspace_dir: &StdPath,
+    dir: &StdPath,
+    location: Location,
+    out: &mut Vec<serde_json::Value>,
+    limit: usize,
+    requested_scope: LibraryScope,
+    synth_state_map: &HashMap<String, local_store::WorkspaceSynthSourceRecord>,
+    tag_filter: Option<&str>,
+    route_prefix: &str,
+) -> Result<()> {
+    if out.len() >= limit {
+        return Ok(());
+    }
+    let depth = dir
+        .strip_prefix(workspace_dir)
+        .map_or(0, |rel| rel.components().count());
+    if depth > LIBRARY_MAX_DEPTH {
+        return Ok(());
+    }
+    let entries = match std::fs::read_dir(dir) {
+        Ok(e) => e,
+        Err(_) => return Ok(()),
     };
-    let transcript_abs_path = workspace_dir.join(&transcript_rel_path);
-    let transcript_json_path = transcript_json_rel_path(&transcript_rel_path);
-    let transcript_srt_path = transcript_srt_rel_path(&transcript_rel_path);
+    for entry in entries {
+        if out.len() >= limit {
+            break;
+        }
+        let entry = match entry {
+            Ok(e) => e,
+            Err(_) => continue,
+        };
+        let path = entry.path();
+        let meta = match entry.metadata() {
+            Ok(m) => m,
+            Err(_) => continue,
+        };
+        // Symlinks are followed only by the external scope, and only into a
+        // configured external root; anything else they point at is skipped.
+        let (meta, location) = if meta.is_symlink() {
+            match crate::workspace::locate(workspace_dir, &path) {
+                Some(target @ Location::External { .. })
+                    if requested_scope == LibraryScope::External =>
+                {
+                    match std::fs::metadata(&path) {
+                        Ok(m) => (m, target),
+                        Err(_) => continue,
+                    }
+                }
+                _ => continue,
+            }
+        } else {
+            (meta, location)
+        };
+        if meta.is_dir() {
+            collect_library_items_recursive(
+                workspace_dir,
+                &path,
+                location,
+                out,
+                limit,
+                requested_scope,
+                synth_state_map,
+                tag_filter,
+                route_prefix,
+            )?;
+            continue;
+        }
+        if !meta.is_file() {
+            continue;
+        }
+        let writable = match location {
+            Location::Workspace if requested_scope == LibraryScope::External => continue,
+            Location::Workspace => true,
+            Location::External { writable } => writable,
+        };
+        let ext = path
+            .extension()
+            .and_then(|s| s.to_str())
+            .unwrap_or("")
+            .to_ascii_lowercase();
+        let kind = match ext.as_str() {
+            "md" | "txt" | "json" | "srt" => "text",
+            "mp3" | "wav" | "m4a" | "aac" | "flac" => "audio",
+            "mp4" | "mov" | "webm" | "mkv" => "video",
+            "jpg" | "jpeg" | "png" | "webp" => "image",
+            _ => {
+                // Hide unknown binaries for a cleaner mobile UI.
+                continue;
+            }
+        };
+        let rel = match path.strip_prefix(workspace_dir) {
+            Ok(p) => workspace_relative_display_path(p),
+            Err(_) => continue,
+        };
+        let rel_lower = rel.to_ascii_lowercase();
 
-    if transcript_abs_path.exists() && transcript_abs_path.is_file() {
-        let transcript_text = tokio::fs::read_to_string(&transcript_abs_path)
-            .await
-            .unwrap_or_default();
-        if !transcript_text.trim().is_empty() {
-            return Ok(serde_json::json!({
-                "ok": true,
-                "mediaPath": requested,
-                "path": transcript_rel_path,
-                "jsonPath": transcript_json_path,
-                "srtPath": transcript_srt_path,
-                "text": transcript_text,
-                "status": "done",
-            }));
+        let scope_value = if rel.starts_with("posts/") {
+            "feed"
+        } else {
+            "journal"
+        };
+
+        let is_feed_item = scope_value == "feed";
+        match requested_scope {
+            LibraryScope::Feed if !is_feed_item => continue,
+            LibraryScope::Journal if is_feed_item => continue,
+            _ => {}
+        }
+        if is_feed_item {
+            if rel_lower.contains("/artifacts/")
+                || rel_lower.contains("/pipeline/")
+                || rel_lower.ends_with(".srt")
+                || rel_lower.ends_with(".json")
+                || rel_lower.ends_with(".caption.txt")
+            {
+                continue;
+            }
+        } else if let Some(suffix) = [
+            ".caption.txt",
+            crate::media_probe::META_SIDECAR_SUFFIX,
+            library_tags::TAGS_SIDECAR_SUFFIX,
+        ]
+        .into_iter()
+        .find(|suffix| rel_lower.ends_with(suffix))
+        {
+            // Caption, probe, and tag sidecars surface as fields on their item instead.
+            let sidecar = path.to_string_lossy();
+            let media_path = &sidecar[..sidecar.len() - suffix.len()];
+            if StdPath::new(media_path).is_file() {
+                continue;
+            }
         }
-    }
 
-    let jobs = state.journal_transcription_jobs.lock();
-    if let Some(job) = jobs.get(requested) {
-        let resolved_transcript_path = job
-            .transcript_path
-            .clone()
-            .unwrap_or_else(|| transcript_rel_path.clone());
-        return Ok(serde_json::json!({
-            "ok": true,
-            "mediaPath": requested,
-            "path": resolved_transcript_path,
-            "jsonPath": transcript_json_rel_path(&resolved_transcript_path),
-            "srtPath": transcript_srt_rel_path(&resolved_transcript_path),
-            "status": job.status,
-            "error": job.error,
-            "updatedAt": job.updated_at,
+        let tags = library_tags::read_tags(&path);
+        if tag_filter.is_some_and(|wanted| !tags.iter().any(|tag| tag == wanted)) {
+            continue;
+        }
+
+        let modified_at = meta
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| i64::try_from(d.as_secs()).unwrap_or(0))
+            .unwrap_or(0);
+        let title = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("untitled")
+            .replace(['_', '-'], " ");
+        let text_content = if kind == "text" {
+            std::fs::read_to_string(&path).ok()
+        } else {
+            None
+        };
+        let preview = if kind == "image" {
+            let mut caption_path = path.clone().into_os_string();
+            caption_path.push(".caption.txt");
+            std::fs::read_to_string(caption_path)
+                .ok()
+                .map(|s| truncate_with_ellipsis(s.trim(), 240))
+                .unwrap_or_default()
+        } else {
+            text_content
+                .as_deref()
+                .map(|s| truncate_with_ellipsis(s, 240))
+                .unwrap_or_default()
+        };
+        let (workspace_synth_processed, workspace_synth_pending, workspace_synth_last_processed_at) =
+            if kind == "text" && rel.starts_with("journals/text/") {
+                let current_hash = text_content
+                    .as_deref()
+                    .map(|content| content_hash_16(content.trim()))
+                    .unwrap_or_default();
+                let state = synth_state_map.get(&rel);
+                let processed = state
+                    .map(|item| !current_hash.is_empty() && item.last_processed_hash == current_hash)
+                    .unwrap_or(false);
+                (
+                    processed,
+                    !processed,
+                    state.and_then(|item| non_empty_string(item.last_processed_at.clone())),
+                )
+            } else {
+                (false, false, None)
+            };
+        let probe = if kind == "audio" || kind == "video" {
+            crate::media_probe::read_cached_probe(&path).unwrap_or_default()
+        } else {
+            crate::media_probe::MediaProbe::default()
+        };
+        out.push(serde_json::json!({
+            "id": rel.clone(),
+            "path": rel.clone(),
+            "title": title,
+            "kind": kind,
+            "sizeBytes": meta.len(),
+            "modifiedAt": modified_at,
+            "previewText": preview,
+            "mediaUrl": if kind == "audio" || kind == "video" || kind == "image" {
+                serde_json::Value::String(media_url(route_prefix, &rel))
+            } else {
+                serde_json::Value::Null
+            },
+            "editableText": kind == "text" && writable,
+            "external": location != Location::Workspace,
+            "scope": scope_value,
+            "workspaceSynthProcessed": workspace_synth_processed,
+            "workspaceSynthPending": workspace_synth_pending,
+            "workspaceSynthLastProcessedAt": workspace_synth_last_processed_at,
+            "durationMs": probe.duration_ms,
+            "sampleRate": probe.sample_rate,
+            "width": probe.width,
+            "height": probe.height,
+            "codec": probe.codec,
+            "tags": tags,
         }));
     }
-
-    Ok(serde_json::json!({
-        "ok": true,
-        "mediaPath": requested,
-        "path": transcript_rel_path,
-        "jsonPath": transcript_json_path,
-        "srtPath": transcript_srt_path,
-        "status": "idle",
-    }))
+    Ok(())
 }
 
-async fn handle_journal_transcribe_status(
-    State(state): State<AppState>,
-    headers: HeaderMap,
-    Query(query): Query<JournalTranscribeStatusQuery>,
-) -> axum::response::Response {
-    if let Some(err) = pairing_auth_error(&state, &headers, "Journal transcribe status") {
-        return err.into_response();
-    }
-
-    let requested = normalize_workspace_relative_path(&query.media_path);
-    if requested.is_empty() {
-        return frontend_error_response(
-            StatusCode::BAD_REQUEST,
-            "TRANSCRIPTION_MEDIA_PATH_REQUIRED",
-            "mediaPath is required",
-        )
-        .into_response();
-    }
+const INTEREST_MERGE_THRESHOLD: f32 = 0.75;
+const INTEREST_SPAWN_THRESHOLD: f32 = 0.35;
+const FEED_MATCH_THRESHOLD: f32 = 0.65;
+const INTEREST_DECAY_RATE: f64 = 0.95;
+const INTEREST_EMA_NEW_WEIGHT: f32 = 0.2;
+const BLUESKY_TIMELINE_LIMIT_MAX: usize = 100;
+const BLUESKY_DISCOVER_FEED_URI: &str =
+    "at://did:plc:qh3lfd7q24h3fn3pejqr25ct/app.bsky.feed.generator/whats-hot";
+const BLUESKY_FEED_GENERATOR_DISCOVERY_PAGE_LIMIT: usize = 2;
+const BLUESKY_FEED_GENERATOR_DISCOVERY_PAGE_SIZE: usize = 25;
+const BLUESKY_FEED_GENERATOR_MATCH_LIMIT: usize = 4;
+const BLUESKY_FEED_SOURCE_MATCH_THRESHOLD: f32 = 0.55;
+const BLUESKY_PERSONALIZED_PAGE_LIMIT_PER_SOURCE: usize = 10;
+const BLUESKY_PERSONALIZED_MATCH_LIMIT: usize = 10;
+const WORKSPACE_SYNTH_BATCH_WORD_LIMIT: usize = 500;
+const BLUESKY_PERSONALIZED_PAGE_SIZE: usize = 30;
+const FEED_WEB_SOURCE_KIND: &str = "hn-popular-blogs-2025";
+const FEED_WEB_PREVIEW_CACHE_TTL_SECS: i64 = 24 * 60 * 60;
+const FEED_WEB_INTEREST_QUERY_COUNT: usize = 3;
+const FEED_WEB_DOMAIN_BATCH_SIZE: usize = 5;
+const FEED_WEB_DOMAIN_BATCHES_PER_INTEREST: usize = 2;
+const FEED_WEB_RESULT_LIMIT_PER_QUERY: usize = 5;
+const CONTENT_SOURCE_REFRESH_TTL_SECS: i64 = 30 * 60;
+const CONTENT_SOURCE_REFRESH_BATCH_SIZE: usize = 6;
+const CONTENT_ITEM_EMBEDDING_BACKFILL_BATCH_SIZE: usize = 16;
+const CONTENT_SOURCE_ITEM_LIMIT: usize = 12;
+const CONTENT_RANK_CANDIDATE_LIMIT: usize = 160;
+const CONTENT_TEXT_MAX_CHARS: usize = 2400;
+const CONTENT_FETCH_TIMEOUT_SECS: u64 = 8;
 
-    match journal_transcribe_status_payload(&state, &requested).await {
-        Ok(body) => (StatusCode::OK, Json(body)).into_response(),
-        Err(err) => err.into_response(),
-    }
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct WebFeedPreview {
+    url: String,
+    title: String,
+    description: String,
+    image_url: Option<String>,
+    domain: String,
+    provider: String,
+    provider_snippet: Option<String>,
+    discovered_at: String,
 }
 
-async fn handle_journal_transcribe_stream(
-    State(state): State<AppState>,
-    headers: HeaderMap,
-    Query(query): Query<JournalTranscribeStatusQuery>,
-) -> axum::response::Response {
-    if let Some(err) = pairing_auth_error(&state, &headers, "Journal transcribe stream") {
-        return err.into_response();
-    }
-
-    let requested = normalize_workspace_relative_path(&query.media_path);
-    if requested.is_empty() {
-        return frontend_error_response(
-            StatusCode::BAD_REQUEST,
-            "TRANSCRIPTION_MEDIA_PATH_REQUIRED",
-            "mediaPath is required",
-        )
-        .into_response();
-    }
-
-    if let Err(err) = journal_transcribe_status_payload(&state, &requested).await {
-        return err.into_response();
-    }
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PersonalizedFeedRequest {
+    service_url: Option<String>,
+    access_jwt: Option<String>,
+    limit: Option<usize>,
+    #[serde(default)]
+    force: bool,
+}
 
-    let state_for_stream = state.clone();
-    let (tx, rx) = mpsc::channel(8);
-    tokio::spawn(async move {
-        let mut last_snapshot = String::new();
-        loop {
-            match journal_transcribe_status_payload(&state_for_stream, &requested).await {
-                Ok(payload) => {
-                    let fingerprint = serde_json::to_string(&payload).unwrap_or_default();
-                    if fingerprint != last_snapshot {
-                        last_snapshot = fingerprint;
-                        if tx.send(sse_json_event("transcription_status", &payload)).await.is_err() {
-                            break;
-                        }
-                    }
-                    let status = payload
-                        .get("status")
-                        .and_then(serde_json::Value::as_str)
-                        .unwrap_or("idle");
-                    if matches!(status, "done" | "error" | "idle") {
-                        break;
-                    }
-                }
-                Err((_, Json(body))) => {
-                    let payload = serde_json::json!({
-                        "error": body.get("error").and_then(serde_json::Value::as_str).unwrap_or("Failed to load transcription status."),
-                        "code": body.get("code").and_then(serde_json::Value::as_str).unwrap_or("TRANSCRIPTION_STREAM_FAILED"),
-                    });
-                    let _ = tx.send(sse_json_event("error", &payload)).await;
-                    break;
-                }
-            }
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WorldFeedInterestCreateRequest {
+    label: Option<String>,
+}
 
-            tokio::time::sleep(Duration::from_millis(1500)).await;
-        }
-    });
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WorldFeedInterestUpdateRequest {
+    label: Option<String>,
+    keywords_override: Option<Vec<String>>,
+}
 
-    frontend_event_stream(rx)
+#[derive(Debug, Clone, serde::Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct InterestProfileStats {
+    interest_count: usize,
+    source_count: usize,
+    refreshed_sources: usize,
+    merged_count: usize,
+    spawned_count: usize,
+    ignored_count: usize,
 }
 
-fn transcript_rel_path_for_media(media_rel_path: &str) -> Option<String> {
-    let normalized = media_rel_path.trim_start_matches('/');
-    let relative = normalized.strip_prefix("journals/media/")?;
-    let media_rel = StdPath::new(relative);
-    let stem = media_rel.file_stem()?.to_str()?.trim();
-    if stem.is_empty() {
-        return None;
-    }
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BlueskyFeedSourceContext {
+    label: String,
+    description: Option<String>,
+    matched_interest_label: Option<String>,
+    matched_interest_score: Option<f32>,
+}
 
-    let mut out = PathBuf::from("journals/text/transcriptions");
-    if let Some(parent) = media_rel.parent() {
-        if !parent.as_os_str().is_empty() {
-            out.push(parent);
-        }
-    }
-    out.push(format!("{stem}.txt"));
-    Some(out.to_string_lossy().replace('\\', "/"))
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PersonalizedBlueskyItem {
+    source_type: String,
+    feed_item: serde_json::Value,
+    web_preview: Option<WebFeedPreview>,
+    feed_source: Option<BlueskyFeedSourceContext>,
+    score: Option<f32>,
+    matched_interest_label: Option<String>,
+    matched_interest_score: Option<f32>,
+    passed_threshold: bool,
 }
 
-fn transcript_json_rel_path(transcript_rel_path: &str) -> String {
-    match transcript_rel_path.rsplit_once('.') {
-        Some((base, _)) => format!("{base}.json"),
-        None => format!("{transcript_rel_path}.json"),
-    }
+#[derive(Debug, Clone)]
+struct ActiveInterest {
+    record: local_store::FeedInterestRecord,
+    embedding: Vec<f32>,
 }
 
-fn transcript_srt_rel_path(transcript_rel_path: &str) -> String {
-    match transcript_rel_path.rsplit_once('.') {
-        Some((base, _)) => format!("{base}.srt"),
-        None => format!("{transcript_rel_path}.srt"),
-    }
+#[derive(Debug, Clone)]
+struct CandidateFeedPost {
+    feed_item: serde_json::Value,
+    text: String,
+    feed_source: Option<BlueskyFeedSourceContext>,
 }
 
-fn legacy_transcript_rel_path_for_media(media_rel_path: &str) -> Option<String> {
-    let stem = StdPath::new(media_rel_path).file_stem()?.to_str()?.trim();
-    if stem.is_empty() {
-        return None;
-    }
-    Some(format!("journals/text/transcript/{stem}.txt"))
+#[derive(Debug, Clone)]
+struct CandidateFeedGenerator {
+    uri: String,
+    display_name: String,
+    description: String,
+    creator_handle: String,
+    creator_display_name: String,
 }
 
-fn source_file_created_or_modified_at(path: &StdPath) -> Option<chrono::DateTime<Utc>> {
-    let metadata = path.metadata().ok()?;
-    let timestamp = metadata.created().or_else(|_| metadata.modified()).ok()?;
-    Some(timestamp.into())
+#[derive(Debug, Clone)]
+struct CandidateWebResult {
+    url: String,
+    title: String,
+    description: String,
+    domain: String,
+    provider: String,
+    search_query: String,
 }
 
-fn rewrite_transcript_json_sidecar_paths(
-    json_abs_path: &StdPath,
-    media_abs_path: &StdPath,
-    transcript_abs_path: &StdPath,
-) -> Result<()> {
-    let raw = std::fs::read_to_string(json_abs_path)
-        .with_context(|| format!("failed to read {}", json_abs_path.display()))?;
-    let mut payload: serde_json::Value = serde_json::from_str(&raw)
-        .with_context(|| format!("invalid JSON in {}", json_abs_path.display()))?;
-    let Some(object) = payload.as_object_mut() else {
-        anyhow::bail!(
-            "transcript json sidecar must be a JSON object: {}",
-            json_abs_path.display()
-        );
-    };
-    object.insert(
-        "source".to_string(),
-        serde_json::Value::String(media_abs_path.to_string_lossy().into_owned()),
-    );
-    object.insert(
-        "transcriptPath".to_string(),
-        serde_json::Value::String(transcript_abs_path.to_string_lossy().into_owned()),
-    );
-    let serialized = serde_json::to_string_pretty(&payload)
-        .with_context(|| format!("failed to serialize {}", json_abs_path.display()))?;
-    std::fs::write(json_abs_path, format!("{serialized}\n"))
-        .with_context(|| format!("failed to write {}", json_abs_path.display()))
+#[derive(Debug, Clone)]
+struct ParsedFeedEntry {
+    external_id: String,
+    canonical_url: String,
+    title: String,
+    author: String,
+    summary: String,
+    content_text: String,
+    published_at: String,
 }
 
-fn relocate_inbox_audio_after_transcription(
-    workspace_dir: &StdPath,
-    media_rel_path: &str,
-    transcript_rel_path: &str,
-) -> Result<(String, String)> {
-    let media_rel = normalize_workspace_relative_path(media_rel_path);
-    let transcript_rel = normalize_workspace_relative_path(transcript_rel_path);
-    if !media_rel.starts_with(&format!("{JOURNAL_AUDIO_INBOX_DIR}/")) {
-        return Ok((media_rel, transcript_rel));
-    }
+#[derive(Debug, Clone)]
+enum BlueskyCandidateSourceEndpoint {
+    HomeTimeline,
+    FeedGenerator { uri: String },
+}
 
-    let Some(media_abs) = resolve_workspace_media_path(workspace_dir, &media_rel) else {
-        return Ok((media_rel, transcript_rel));
-    };
-    let observed_at = source_file_created_or_modified_at(&media_abs).unwrap_or_else(Utc::now);
-    let original_name = media_abs
-        .file_name()
-        .and_then(|value| value.to_str())
-        .unwrap_or("audio-note.m4a");
+#[derive(Debug, Clone)]
+struct BlueskyCandidateSource {
+    endpoint: BlueskyCandidateSourceEndpoint,
+    label: String,
+    feed_source: Option<BlueskyFeedSourceContext>,
+}
 
-    let safe_name = safe_file_name(original_name);
-    let mut candidate_rel = format!(
-        "{}/audio/{:04}/{:02}/{:02}/{}_{}",
-        JOURNAL_MEDIA_DIR,
-        observed_at.year(),
-        observed_at.month(),
-        observed_at.day(),
-        observed_at.format("%H%M%S"),
-        safe_name
-    );
-    let mut candidate_abs = workspace_dir.join(&candidate_rel);
-    if candidate_abs.exists() && candidate_abs != media_abs {
-        let suffix = &Uuid::new_v4().simple().to_string()[..8];
-        let file_stem = StdPath::new(&safe_name)
-            .file_stem()
-            .and_then(|value| value.to_str())
-            .unwrap_or("audio-note");
-        let extension = StdPath::new(&safe_name)
-            .extension()
-            .and_then(|value| value.to_str())
-            .unwrap_or("m4a");
-        candidate_rel = format!(
-            "{}/audio/{:04}/{:02}/{:02}/{}_{}-{}.{}",
-            JOURNAL_MEDIA_DIR,
-            observed_at.year(),
-            observed_at.month(),
-            observed_at.day(),
-            observed_at.format("%H%M%S"),
-            file_stem,
-            suffix,
-            extension
-        );
-        candidate_abs = workspace_dir.join(&candidate_rel);
+impl BlueskyCandidateSource {
+    fn home_timeline() -> Self {
+        Self {
+            endpoint: BlueskyCandidateSourceEndpoint::HomeTimeline,
+            label: "home".to_string(),
+            feed_source: Some(BlueskyFeedSourceContext {
+                label: "Home timeline".to_string(),
+                description: None,
+                matched_interest_label: None,
+                matched_interest_score: None,
+            }),
+        }
     }
 
-    if candidate_abs == media_abs {
-        return Ok((media_rel, transcript_rel));
+    fn feed_generator(
+        uri: impl Into<String>,
+        label: impl Into<String>,
+        feed_source: Option<BlueskyFeedSourceContext>,
+    ) -> Self {
+        Self {
+            endpoint: BlueskyCandidateSourceEndpoint::FeedGenerator { uri: uri.into() },
+            label: label.into(),
+            feed_source,
+        }
     }
 
-    if let Some(parent) = candidate_abs.parent() {
-        std::fs::create_dir_all(parent)
-            .with_context(|| format!("failed to create {}", parent.display()))?;
+    fn request_label(&self) -> &str {
+        self.label.as_str()
     }
-    std::fs::rename(&media_abs, &candidate_abs).with_context(|| {
-        format!(
-            "failed to relocate inbox audio {} -> {}",
-            media_abs.display(),
-            candidate_abs.display()
-        )
-    })?;
 
-    let Some(new_transcript_rel) = transcript_rel_path_for_media(&candidate_rel) else {
-        return Ok((candidate_rel, transcript_rel));
-    };
-    let new_transcript_abs = workspace_dir.join(&new_transcript_rel);
-    let new_transcript_json_rel = transcript_json_rel_path(&new_transcript_rel);
-    let transcript_pairs = [
-        (transcript_rel.clone(), new_transcript_rel.clone()),
-        (
-            transcript_json_rel_path(&transcript_rel),
-            new_transcript_json_rel.clone(),
-        ),
-        (
-            transcript_srt_rel_path(&transcript_rel),
-            transcript_srt_rel_path(&new_transcript_rel),
-        ),
-    ];
-    for (old_rel, new_rel) in transcript_pairs {
-        if old_rel == new_rel {
-            continue;
-        }
-        let old_abs = workspace_dir.join(&old_rel);
-        if !old_abs.exists() {
-            continue;
-        }
-        let new_abs = workspace_dir.join(&new_rel);
-        if let Some(parent) = new_abs.parent() {
-            std::fs::create_dir_all(parent)
-                .with_context(|| format!("failed to create {}", parent.display()))?;
-        }
-        std::fs::rename(&old_abs, &new_abs).with_context(|| {
-            format!(
-                "failed to relocate transcript sidecar {} -> {}",
-                old_abs.display(),
-                new_abs.display()
-            )
-        })?;
-        if let Err(err) =
-            local_store::rename_workspace_synth_source_path(workspace_dir, &old_rel, &new_rel)
-        {
-            tracing::warn!(
-                old = old_rel,
-                new = %new_rel,
-                err = %err,
-                "transcript source path rename failed"
-            );
-        }
-        if new_rel == new_transcript_json_rel {
-            if let Err(err) = rewrite_transcript_json_sidecar_paths(
-                &new_abs,
-                &candidate_abs,
-                &new_transcript_abs,
-            ) {
-                tracing::warn!(
-                    path = %new_rel,
-                    err = %err,
-                    "failed to rewrite relocated transcript json metadata"
-                );
-            }
+    fn endpoint_key(&self) -> String {
+        match &self.endpoint {
+            BlueskyCandidateSourceEndpoint::HomeTimeline => "home".to_string(),
+            BlueskyCandidateSourceEndpoint::FeedGenerator { uri } => format!("feed:{uri}"),
         }
     }
+}
 
-    match local_store::rename_media_asset_path(workspace_dir, &media_rel, &candidate_rel) {
-        Ok(0) => tracing::warn!(
-            old = media_rel,
-            new = %candidate_rel,
-            "audio relocation: no media asset rows matched"
-        ),
-        Err(err) => tracing::warn!(
-            old = media_rel,
-            new = %candidate_rel,
-            err = %err,
-            "audio relocation failed to update media metadata"
-        ),
-        _ => {}
-    }
+#[derive(Debug, Clone, Default)]
+struct RebuildInterestProfileResult {
+    status: &'static str,
+    stats: InterestProfileStats,
+    interests: Vec<ActiveInterest>,
+}
 
-    Ok((candidate_rel, new_transcript_rel))
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WorkspaceSyncFile {
+    path: String,
+    modified_at: i64,
+    content_base64: String,
 }
 
-fn enqueue_transcription_job(
-    state: AppState,
-    media_rel_path: String,
-    media_abs_path: PathBuf,
-    transcript_rel_path: String,
-    transcript_abs_path: PathBuf,
-    transcription_config: TranscriptionConfig,
-) -> JournalTranscriptionJob {
-    {
-        let mut jobs = state.journal_transcription_jobs.lock();
-        if let Some(existing) = jobs.get(&media_rel_path).cloned() {
-            if existing.status == "queued" || existing.status == "running" {
-                return existing;
-            }
-        }
-        jobs.insert(media_rel_path.clone(), JournalTranscriptionJob::queued());
-    }
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LocalStoreSyncBlob {
+    modified_at: i64,
+    content_base64: String,
+}
 
-    let queued_path = transcript_rel_path.clone();
-    let task_transcript_rel_path = transcript_rel_path.clone();
-    let state_for_task = state.clone();
-    tokio::spawn(async move {
-        {
-            let mut jobs = state_for_task.journal_transcription_jobs.lock();
-            jobs.insert(
-                media_rel_path.clone(),
-                JournalTranscriptionJob {
-                    status: "running".to_string(),
-                    transcript_path: Some(task_transcript_rel_path.clone()),
-                    error: None,
-                    updated_at: chrono::Utc::now().to_rfc3339(),
-                },
-            );
-        }
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WorkspaceSyncSnapshot {
+    exported_at: i64,
+    files: Vec<WorkspaceSyncFile>,
+    local_store: Option<LocalStoreSyncBlob>,
+}
 
-        let (final_media_rel_path, final_state) = match run_local_faster_whisper(
-            &state_for_task,
-            &media_abs_path,
-            &transcript_abs_path,
-            &transcription_config,
+fn content_hash_16(text: &str) -> String {
+    use sha2::{Digest, Sha256};
+
+    let hash = Sha256::digest(text.as_bytes());
+    format!(
+        "{:016x}",
+        u64::from_be_bytes(
+            hash[..8]
+                .try_into()
+                .expect("SHA-256 always produces at least 8 bytes")
         )
-        .await
-        {
-            Ok(_) => {
-                let workspace_dir = state_for_task.config.lock().workspace_dir.clone();
-                let (final_media_rel, final_transcript_rel) =
-                    match relocate_inbox_audio_after_transcription(
-                        &workspace_dir,
-                        &media_rel_path,
-                        &task_transcript_rel_path,
-                    ) {
-                        Ok(paths) => paths,
-                        Err(err) => {
-                            tracing::warn!(
-                                media_path = %media_rel_path,
-                                transcript_path = %task_transcript_rel_path,
-                                err = %err,
-                                "Failed to relocate processed inbox audio; keeping original paths"
-                            );
-                            (media_rel_path.clone(), task_transcript_rel_path.clone())
-                        }
-                    };
-                (
-                    final_media_rel,
-                    JournalTranscriptionJob {
-                        status: "done".to_string(),
-                        transcript_path: Some(final_transcript_rel),
-                        error: None,
-                        updated_at: chrono::Utc::now().to_rfc3339(),
-                    },
-                )
-            }
-            Err(error) => (
-                media_rel_path.clone(),
-                JournalTranscriptionJob {
-                    status: "error".to_string(),
-                    transcript_path: Some(task_transcript_rel_path.clone()),
-                    error: Some(frontend_background_error(
-                        "journal transcription",
-                        "Transcription failed.",
-                        &error,
-                    )),
-                    updated_at: chrono::Utc::now().to_rfc3339(),
-                },
-            ),
-        };
+    )
+}
+
+#[derive(Debug, Clone, Default)]
+struct WorkspaceSynthSourceCandidate {
+    source_path: String,
+    content_hash: String,
+    word_count: usize,
+    modified_at: i64,
+}
 
-        {
-            let mut jobs = state_for_task.journal_transcription_jobs.lock();
-            jobs.insert(media_rel_path.clone(), final_state.clone());
-            if final_media_rel_path != media_rel_path {
-                jobs.insert(final_media_rel_path.clone(), final_state.clone());
-            }
-        }
+#[derive(Debug, Clone, Default)]
+struct WorkspaceSynthPendingSelection {
+    pending: Vec<WorkspaceSynthSourceCandidate>,
+    selected: Vec<WorkspaceSynthSourceCandidate>,
+    selected_word_count: usize,
+}
 
-    });
+fn count_words(text: &str) -> usize {
+    text.split_whitespace().count()
+}
 
-    JournalTranscriptionJob {
-        status: "queued".to_string(),
-        transcript_path: Some(queued_path),
-        error: None,
-        updated_at: chrono::Utc::now().to_rfc3339(),
+fn collect_workspace_synth_source_candidates(
+    workspace_dir: &StdPath,
+) -> Vec<WorkspaceSynthSourceCandidate> {
+    let root = workspace_dir.join(JOURNAL_TEXT_DIR);
+    if !root.exists() || !root.is_dir() {
+        return Vec::new();
     }
-}
 
-async fn enqueue_journal_transcription(
-    state: &AppState,
-    media_rel_path: String,
-) -> Option<serde_json::Value> {
-    let workspace_dir = state.config.lock().workspace_dir.clone();
-    let Some(abs_media_path) = resolve_workspace_media_path(&workspace_dir, &media_rel_path) else {
-        return None;
-    };
-    let Some(transcript_rel_path) = transcript_rel_path_for_media(&media_rel_path) else {
-        return None;
-    };
-    let transcript_json_path = transcript_json_rel_path(&transcript_rel_path);
-    let transcript_srt_path = transcript_srt_rel_path(&transcript_rel_path);
-    let transcript_abs_path = workspace_dir.join(&transcript_rel_path);
-    if transcript_abs_path.exists() && transcript_abs_path.is_file() {
-        let existing = tokio::fs::read_to_string(&transcript_abs_path)
-            .await
-            .unwrap_or_default();
-        if !existing.trim().is_empty() {
-            return Some(serde_json::json!({
-                "status": "done",
-                "path": transcript_rel_path,
-                "jsonPath": transcript_json_path,
-                "srtPath": transcript_srt_path,
-            }));
+    let mut items = Vec::new();
+    let mut stack = vec![root];
+    while let Some(dir) = stack.pop() {
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            if file_type.is_dir() {
+                stack.push(path);
+                continue;
+            }
+            if !file_type.is_file() || !is_content_agent_source_file(&path) {
+                continue;
+            }
+            let Ok(content) = std::fs::read_to_string(&path) else {
+                continue;
+            };
+            let trimmed = content.trim();
+            if trimmed.is_empty() {
+                continue;
+            }
+            let Ok(rel) = path.strip_prefix(workspace_dir) else {
+                continue;
+            };
+            items.push(WorkspaceSynthSourceCandidate {
+                source_path: rel.to_string_lossy().replace('\\', "/"),
+                content_hash: content_hash_16(trimmed),
+                word_count: count_words(trimmed),
+                modified_at: source_file_modified_at_secs(&path),
+            });
         }
     }
-    let cfg = state.config.lock().transcription.clone();
-    if !cfg.enabled {
-        return Some(serde_json::json!({
-            "status": "disabled",
-            "path": transcript_rel_path,
-            "jsonPath": transcript_json_path,
-            "srtPath": transcript_srt_path,
-        }));
-    }
-    let media_capabilities = local_media_capabilities(&state.config.lock().clone());
-    if !media_capabilities.transcribe_media {
-        return Some(serde_json::json!({
-            "status": "unsupported",
-            "path": transcript_rel_path,
-            "jsonPath": transcript_json_path,
-            "srtPath": transcript_srt_path,
-            "error": "Local transcription is unavailable on this device.",
-        }));
-    }
-    let job = enqueue_transcription_job(
-        state.clone(),
-        media_rel_path,
-        abs_media_path,
-        transcript_rel_path.clone(),
-        transcript_abs_path,
-        cfg,
-    );
-    Some(serde_json::json!({
-        "status": job.status,
-        "path": transcript_rel_path,
-        "jsonPath": transcript_json_path,
-        "srtPath": transcript_srt_path,
-        "error": job.error,
-        "updatedAt": job.updated_at,
-    }))
+    items.sort_by(|a, b| b.modified_at.cmp(&a.modified_at));
+    items
 }
 
-async fn run_local_faster_whisper(
-    state: &AppState,
-    media_abs_path: &StdPath,
-    transcript_abs_path: &StdPath,
-    transcription_config: &TranscriptionConfig,
-) -> Result<()> {
-    let workspace_dir = state.config.lock().workspace_dir.clone();
-    ensure_workspace_content_agent_helper_scripts(&workspace_dir).await?;
-
-    let script_path = workspace_dir.join("scripts/transcribe_audio_journal.py");
-    let mut cmd = Command::new(transcription_config.python_bin.trim());
-    cmd.arg(&script_path)
-        .arg("--input")
-        .arg(media_abs_path)
-        .arg("--output")
-        .arg(transcript_abs_path)
-        .arg("--model")
-        .arg(transcription_config.model.trim())
-        .arg("--device")
-        .arg(transcription_config.device.trim())
-        .arg("--compute-type")
-        .arg(transcription_config.compute_type.trim())
-        .arg("--beam-size")
-        .arg(transcription_config.beam_size.max(1).to_string())
-        .current_dir(&workspace_dir)
-        .stdin(std::process::Stdio::null())
-        .stdout(std::process::Stdio::piped())
-        .stderr(std::process::Stdio::piped())
-        .kill_on_drop(true);
-
-    if let Some(language) = transcription_config
-        .language
-        .as_deref()
-        .map(str::trim)
-        .filter(|value: &&str| !value.is_empty())
-    {
-        cmd.arg("--language").arg(language);
+fn select_workspace_synth_sources(
+    workspace_dir: &StdPath,
+    forced_paths: &[String],
+    force_reprocess: bool,
+) -> Result<WorkspaceSynthPendingSelection> {
+    let candidates = collect_workspace_synth_source_candidates(workspace_dir);
+    if candidates.is_empty() {
+        return Ok(WorkspaceSynthPendingSelection::default());
     }
 
-    let output = tokio::time::timeout(Duration::from_secs(3_600), cmd.output())
-        .await
-        .context("local transcription timed out")?
-        .context("failed to execute local transcriber script")?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
-        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-        anyhow::bail!(
-            "transcriber script failed ({}): {}",
-            output.status,
-            truncate_with_ellipsis(
-                &(if stderr.trim().is_empty() { stdout } else { stderr }),
-                300
-            )
-        );
-    }
+    let state_map: HashMap<String, local_store::WorkspaceSynthSourceRecord> =
+        local_store::list_workspace_synth_sources(workspace_dir)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|item| (item.source_path.clone(), item))
+            .collect();
+    let forced: HashSet<String> = forced_paths
+        .iter()
+        .map(|value| value.trim().trim_start_matches('/').replace('\\', "/"))
+        .filter(|value| !value.is_empty())
+        .collect();
 
-    Ok(())
-}
+    let pending: Vec<WorkspaceSynthSourceCandidate> = candidates
+        .iter()
+        .filter(|item| {
+            if forced.contains(&item.source_path) {
+                return true;
+            }
+            state_map
+                .get(&item.source_path)
+                .map(|state| state.last_processed_hash != item.content_hash)
+                .unwrap_or(true)
+        })
+        .cloned()
+        .collect();
 
-fn write_workspace_helper_script(
-    workspace_dir: &StdPath,
-    filename: &str,
-    body: &str,
-) -> Result<()> {
-    let scripts_dir = workspace_dir.join("scripts");
-    std::fs::create_dir_all(&scripts_dir)?;
-    std::fs::write(scripts_dir.join(filename), body)?;
-    Ok(())
-}
+    if pending.is_empty() {
+        return Ok(WorkspaceSynthPendingSelection::default());
+    }
 
-fn ensure_workspace_content_agent_helper_scripts_sync(workspace_dir: &StdPath) -> Result<()> {
-    write_workspace_helper_script(
-        workspace_dir,
-        "transcribe_audio_journal.py",
-        include_str!("../../scripts/transcribe_audio_journal.py"),
-    )?;
-    write_workspace_helper_script(
-        workspace_dir,
-        "render_audio_insight_clip.py",
-        include_str!("../../scripts/render_audio_insight_clip.py"),
-    )?;
-    Ok(())
-}
+    if !forced.is_empty() {
+        let selected: Vec<WorkspaceSynthSourceCandidate> = candidates
+            .into_iter()
+            .filter(|item| {
+                forced.contains(&item.source_path)
+                    && (force_reprocess
+                        || state_map
+                            .get(&item.source_path)
+                            .map(|state| state.last_processed_hash != item.content_hash)
+                            .unwrap_or(true))
+            })
+            .collect();
+        let selected_word_count = selected.iter().map(|item| item.word_count).sum();
+        return Ok(WorkspaceSynthPendingSelection {
+            pending,
+            selected,
+            selected_word_count,
+        });
+    }
 
-async fn ensure_workspace_content_agent_helper_scripts(workspace_dir: &StdPath) -> Result<()> {
-    let scripts_dir = workspace_dir.join("scripts");
-    tokio::fs::create_dir_all(&scripts_dir).await?;
-    tokio::fs::write(
-        scripts_dir.join("transcribe_audio_journal.py"),
-        include_str!("../../scripts/transcribe_audio_journal.py"),
-    )
-    .await?;
-    tokio::fs::write(
-        scripts_dir.join("render_audio_insight_clip.py"),
-        include_str!("../../scripts/render_audio_insight_clip.py"),
-    )
-    .await?;
-    Ok(())
+    let mut selected = Vec::new();
+    let mut selected_word_count = 0usize;
+    for item in &pending {
+        if !selected.is_empty() && selected_word_count + item.word_count > WORKSPACE_SYNTH_BATCH_WORD_LIMIT {
+            break;
+        }
+        selected_word_count += item.word_count;
+        selected.push(item.clone());
+    }
+    if selected.is_empty() && !pending.is_empty() {
+        selected.push(pending[0].clone());
+        selected_word_count = pending[0].word_count;
+    }
+
+    Ok(WorkspaceSynthPendingSelection {
+        pending,
+        selected,
+        selected_word_count,
+    })
 }
 
-fn infer_media_kind_from_content_type(content_type: &str) -> &'static str {
-    let lower = content_type.to_ascii_lowercase();
-    if lower.starts_with("audio/") {
-        "audio"
-    } else if lower.starts_with("video/") {
-        "video"
-    } else if lower.starts_with("image/") {
-        "image"
-    } else {
-        "file"
+fn derive_interest_label(default_title: &str, content: &str) -> String {
+    let normalized_title = default_title.trim();
+    if !normalized_title.is_empty() && !normalized_title.eq_ignore_ascii_case("untitled") {
+        return truncate_with_ellipsis(normalized_title, 80);
     }
-}
 
-fn safe_file_name(name: &str) -> String {
-    let mut out = String::with_capacity(name.len().min(128));
-    for ch in name.chars() {
-        if ch.is_ascii_alphanumeric() || matches!(ch, '.' | '_' | '-') {
-            out.push(ch);
-        } else {
-            out.push('_');
+    for line in content.lines() {
+        let trimmed = line.trim().trim_start_matches('#').trim();
+        if !trimmed.is_empty() {
+            return truncate_with_ellipsis(trimmed, 80);
         }
     }
-    let trimmed = out.trim_matches('_');
+
+    "Workspace interest".to_string()
+}
+
+fn non_empty_string(value: String) -> Option<String> {
+    let trimmed = value.trim();
     if trimmed.is_empty() {
-        "upload.bin".to_string()
+        None
     } else {
-        trimmed.chars().take(128).collect()
+        Some(trimmed.to_string())
     }
 }
 
-fn media_storage_rel_path(kind: &str, original_name: &str) -> String {
-    let now = chrono::Utc::now();
-    let kind = kind.trim().to_ascii_lowercase();
-    let kind_dir = match kind.as_str() {
-        "audio" => "audio",
-        "video" => "video",
-        "image" => "image",
-        _ => "files",
-    };
-    let safe_name = safe_file_name(original_name);
-    format!(
-        "{}/{}/{:04}/{:02}/{:02}/{}_{}",
-        JOURNAL_MEDIA_DIR,
-        kind_dir,
-        now.year(),
-        now.month(),
-        now.day(),
-        now.format("%H%M%S"),
-        safe_name
-    )
+fn ema_merge_vectors(current: &[f32], previous: &[f32]) -> Vec<f32> {
+    current
+        .iter()
+        .zip(previous.iter())
+        .map(|(new_value, previous_value)| {
+            INTEREST_EMA_NEW_WEIGHT * *new_value + (1.0 - INTEREST_EMA_NEW_WEIGHT) * *previous_value
+        })
+        .collect()
 }
 
-fn text_journal_rel_path(title: &str) -> String {
-    let now = chrono::Utc::now();
-    let safe = safe_file_name(title).trim_end_matches('.').to_string();
-    let stem = if safe.is_empty() { "journal" } else { &safe };
-    format!(
-        "{}/{:04}/{:02}/{:02}/{}_{}.md",
-        JOURNAL_TEXT_DIR,
-        now.year(),
-        now.month(),
-        now.day(),
-        now.format("%H%M%S"),
-        stem
-    )
+fn sort_personalized_items(items: &mut [PersonalizedBlueskyItem]) {
+    items.sort_by(|left, right| {
+        let score_order = right
+            .score
+            .unwrap_or(0.0)
+            .partial_cmp(&left.score.unwrap_or(0.0))
+            .unwrap_or(std::cmp::Ordering::Equal);
+        if score_order != std::cmp::Ordering::Equal {
+            return score_order;
+        }
+        let right_ts = item_sort_timestamp(right);
+        let left_ts = item_sort_timestamp(left);
+        right_ts.cmp(left_ts)
+    });
 }
 
-fn normalize_workspace_relative_path(requested: &str) -> String {
-    requested
-        .trim()
-        .trim_start_matches('/')
-        .replace('\\', "/")
-}
+fn item_sort_timestamp(item: &PersonalizedBlueskyItem) -> &str {
+    if let Some(discovered_at) = item
+        .web_preview
+        .as_ref()
+        .map(|preview| preview.discovered_at.as_str())
+        .filter(|value| !value.is_empty())
+    {
+        return discovered_at;
+    }
 
-fn workspace_relative_display_path(path: &StdPath) -> String {
-    path.to_string_lossy().replace('\\', "/")
+    item.feed_item
+        .get("post")
+        .and_then(|post| post.get("indexedAt"))
+        .and_then(serde_json::Value::as_str)
+        .unwrap_or("")
 }
 
-fn resolve_workspace_media_path(workspace_dir: &StdPath, requested: &str) -> Option<PathBuf> {
-    let trimmed = normalize_workspace_relative_path(requested);
-    if trimmed.is_empty() {
-        return None;
-    }
-    let candidate = workspace_dir.join(&trimmed);
-    let resolved = candidate.canonicalize().ok()?;
-    // Canonicalize workspace_dir too so both sides use the same symlink resolution.
-    let workspace_resolved = workspace_dir.canonicalize().unwrap_or_else(|_| workspace_dir.to_path_buf());
-    if !resolved.starts_with(&workspace_resolved) {
-        tracing::debug!(
-            requested = %requested,
-            resolved = %resolved.display(),
-            workspace = %workspace_resolved.display(),
-            "resolve_workspace_media_path: resolved path not under workspace"
-        );
-        return None;
-    }
-    let journals_dir = workspace_resolved.join("journals");
-    if !resolved.starts_with(journals_dir) {
-        tracing::debug!(
-            requested = %requested,
-            "resolve_workspace_media_path: resolved path not under journals/"
-        );
-        return None;
+fn extract_bluesky_post_text(feed_item: &serde_json::Value) -> String {
+    let post = feed_item.get("post").unwrap_or(feed_item);
+    let text = post
+        .get("record")
+        .and_then(|record| record.get("text"))
+        .and_then(serde_json::Value::as_str)
+        .unwrap_or("")
+        .trim();
+    if !text.is_empty() {
+        return text.to_string();
     }
-    Some(resolved)
-}
 
-fn resolve_workspace_text_path(workspace_dir: &StdPath, requested: &str) -> Option<PathBuf> {
-    let trimmed = normalize_workspace_relative_path(requested);
-    if trimmed.is_empty() {
-        return None;
-    }
-    let candidate = workspace_dir.join(&trimmed);
-    let parent = candidate.parent()?.to_path_buf();
-    let parent_resolved = parent.canonicalize().unwrap_or(parent);
-    // Canonicalize workspace_dir too so both sides use the same symlink resolution.
-    let workspace_resolved = workspace_dir.canonicalize().unwrap_or_else(|_| workspace_dir.to_path_buf());
-    if !parent_resolved.starts_with(&workspace_resolved) {
-        tracing::debug!(
-            requested = %requested,
-            parent = %parent_resolved.display(),
-            workspace = %workspace_resolved.display(),
-            "resolve_workspace_text_path: parent not under workspace"
-        );
-        return None;
-    }
-    let allowed = ["journals", "memory", "state", "posts", "outputs", "artifacts"];
-    let rel_parent = parent_resolved.strip_prefix(&workspace_resolved).ok()?;
-    let first = rel_parent.components().next()?.as_os_str().to_string_lossy();
-    if !allowed.iter().any(|a| *a == first) {
-        tracing::debug!(
-            requested = %requested,
-            first_component = %first,
-            "resolve_workspace_text_path: first component not in allowed list"
-        );
-        return None;
-    }
-    Some(candidate)
+    post.get("embed")
+        .and_then(|embed| embed.get("external"))
+        .and_then(|external| external.get("title"))
+        .and_then(serde_json::Value::as_str)
+        .unwrap_or("")
+        .trim()
+        .to_string()
 }
 
-#[derive(Copy, Clone, Eq, PartialEq)]
-enum LibraryScope {
-    Journal,
-    Feed,
-    All,
+fn default_bluesky_candidate_sources() -> Vec<BlueskyCandidateSource> {
+    vec![
+        BlueskyCandidateSource::home_timeline(),
+        BlueskyCandidateSource::feed_generator(
+            BLUESKY_DISCOVER_FEED_URI,
+            "discover".to_string(),
+            Some(BlueskyFeedSourceContext {
+                label: "Discover".to_string(),
+                description: None,
+                matched_interest_label: None,
+                matched_interest_score: None,
+            }),
+        ),
+    ]
 }
 
-fn list_workspace_library_items(
-    workspace_dir: &StdPath,
-    scope: &str,
+fn build_bluesky_feed_endpoint(
+    service_url: &str,
+    source: &BlueskyCandidateSource,
+    cursor: Option<&str>,
     limit: usize,
-) -> Result<Vec<serde_json::Value>> {
-
-    let mut roots: Vec<PathBuf> = Vec::new();
-    let normalized = scope.trim().to_ascii_lowercase();
-    let requested_scope = match normalized.as_str() {
-        "journal" => {
-            roots.push(workspace_dir.join("journals"));
-            LibraryScope::Journal
-        }
-        "feed" => {
-            roots.push(workspace_dir.join("posts"));
-            LibraryScope::Feed
-        }
-        _ => {
-            roots.push(workspace_dir.join("journals"));
-            roots.push(workspace_dir.join("posts"));
-            LibraryScope::All
-        }
+) -> String {
+    let trimmed_service = service_url.trim().trim_end_matches('/');
+    let normalized_limit = limit.clamp(1, BLUESKY_TIMELINE_LIMIT_MAX);
+    let mut url = match &source.endpoint {
+        BlueskyCandidateSourceEndpoint::HomeTimeline => format!(
+            "{trimmed_service}/xrpc/app.bsky.feed.getTimeline?limit={normalized_limit}"
+        ),
+        BlueskyCandidateSourceEndpoint::FeedGenerator { uri } => format!(
+            "{trimmed_service}/xrpc/app.bsky.feed.getFeed?feed={}&limit={normalized_limit}",
+            urlencoding::encode(uri)
+        ),
     };
 
-    let mut items: Vec<serde_json::Value> = Vec::new();
-    let synth_state_map: HashMap<String, local_store::WorkspaceSynthSourceRecord> =
-        local_store::list_workspace_synth_sources(workspace_dir)
-            .unwrap_or_default()
-            .into_iter()
-            .map(|item| (item.source_path.clone(), item))
-            .collect();
-    for root in roots {
-        if !root.exists() {
-            continue;
-        }
-        collect_library_items_recursive(
-            workspace_dir,
-            &root,
-            &mut items,
-            limit,
-            requested_scope,
-            &synth_state_map,
-        )?;
-        if items.len() >= limit {
-            break;
-        }
+    if let Some(next_cursor) = cursor.map(str::trim).filter(|value| !value.is_empty()) {
+        url.push_str("&cursor=");
+        url.push_str(urlencoding::encode(next_cursor).as_ref());
+    }
+
+    url
+}
+
+fn build_bluesky_feed_generator_discovery_endpoint(
+    service_url: &str,
+    cursor: Option<&str>,
+    limit: usize,
+) -> String {
+    let trimmed_service = service_url.trim().trim_end_matches('/');
+    let normalized_limit = limit.clamp(1, BLUESKY_TIMELINE_LIMIT_MAX);
+    let mut url = format!(
+        "{trimmed_service}/xrpc/app.bsky.unspecced.getPopularFeedGenerators?limit={normalized_limit}"
+    );
+
+    if let Some(next_cursor) = cursor.map(str::trim).filter(|value| !value.is_empty()) {
+        url.push_str("&cursor=");
+        url.push_str(urlencoding::encode(next_cursor).as_ref());
     }
 
-    items.sort_by(|a, b| {
-        let a_ts = a.get("modifiedAt").and_then(serde_json::Value::as_i64).unwrap_or(0);
-        let b_ts = b.get("modifiedAt").and_then(serde_json::Value::as_i64).unwrap_or(0);
-        b_ts.cmp(&a_ts)
-    });
-    items.truncate(limit);
-    Ok(items)
+    url
 }
 
-fn collect_library_items_recursive(
-    workspace_dir: &StdPath,
-    dir: &StdPath,
-    out: &mut Vec<serde_json::Value>,
-    limit: usize,
-    requested_scope: LibraryScope,
-    synth_state_map: &HashMap<String, local_store::WorkspaceSynthSourceRecord>,
-) -> Result<()> {
-    if out.len() >= limit {
-        return Ok(());
-    }
-    let entries = match std::fs::read_dir(dir) {
-        Ok(e) => e,
-        Err(_) => return Ok(()),
-    };
-    for entry in entries {
-        if out.len() >= limit {
-            break;
-        }
-        let entry = match entry {
-            Ok(e) => e,
-            Err(_) => continue,
-        };
-        let path = entry.path();
-        let meta = match entry.metadata() {
-            Ok(m) => m,
-            Err(_) => continue,
-        };
-        if meta.is_dir() {
-            collect_library_items_recursive(
-                workspace_dir,
-                &path,
-                out,
-                limit,
-                requested_scope,
-                synth_state_map,
-            )?;
-            continue;
-        }
-        if !meta.is_file() {
-            continue;
-        }
-        let ext = path
-            .extension()
-            .and_then(|s| s.to_str())
-            .unwrap_or("")
-            .to_ascii_lowercase();
-        let kind = match ext.as_str() {
-            "md" | "txt" | "json" | "srt" => "text",
-            "mp3" | "wav" | "m4a" | "aac" | "flac" => "audio",
-            "mp4" | "mov" | "webm" | "mkv" => "video",
-            "jpg" | "jpeg" | "png" | "webp" => "image",
-            _ => {
-                // Hide unknown binaries for a cleaner mobile UI.
-                continue;
-            }
-        };
-        let rel = match path.strip_prefix(workspace_dir) {
-            Ok(p) => workspace_relative_display_path(p),
-            Err(_) => continue,
-        };
-        let rel_lower = rel.to_ascii_lowercase();
+fn bluesky_feed_generator_label(candidate: &CandidateFeedGenerator) -> String {
+    non_empty_string(candidate.display_name.clone())
+        .or_else(|| non_empty_string(candidate.creator_display_name.clone()))
+        .or_else(|| non_empty_string(candidate.creator_handle.clone()))
+        .unwrap_or_else(|| candidate.uri.clone())
+}
 
-        let scope_value = if rel.starts_with("posts/") {
-            "feed"
-        } else {
-            "journal"
-        };
+fn bluesky_feed_generator_search_text(candidate: &CandidateFeedGenerator) -> String {
+    [
+        candidate.display_name.trim(),
+        candidate.description.trim(),
+        candidate.creator_display_name.trim(),
+        candidate.creator_handle.trim(),
+    ]
+    .into_iter()
+    .filter(|value| !value.is_empty())
+    .collect::<Vec<_>>()
+    .join("\n")
+}
 
-        let is_feed_item = scope_value == "feed";
-        match requested_scope {
-            LibraryScope::Feed if !is_feed_item => continue,
-            LibraryScope::Journal if is_feed_item => continue,
-            _ => {}
+fn append_unique_bluesky_sources(
+    target: &mut Vec<BlueskyCandidateSource>,
+    extra: Vec<BlueskyCandidateSource>,
+) {
+    let mut seen: HashSet<String> = target.iter().map(BlueskyCandidateSource::endpoint_key).collect();
+    for source in extra {
+        if seen.insert(source.endpoint_key()) {
+            target.push(source);
         }
-        if is_feed_item {
-            if rel_lower.contains("/artifacts/")
-                || rel_lower.contains("/pipeline/")
-                || rel_lower.ends_with(".srt")
-                || rel_lower.ends_with(".json")
-                || rel_lower.ends_with(".caption.txt")
-            {
+    }
+}
+
+fn bluesky_candidate_dedup_key(feed_item: &serde_json::Value) -> Option<String> {
+    let post = feed_item.get("post").unwrap_or(feed_item);
+    post.get("uri")
+        .and_then(serde_json::Value::as_str)
+        .map(ToOwned::to_owned)
+        .or_else(|| {
+            post.get("cid")
+                .and_then(serde_json::Value::as_str)
+                .map(ToOwned::to_owned)
+        })
+}
+
+fn dedupe_candidate_posts(
+    candidates: Vec<CandidateFeedPost>,
+    seen: &mut BTreeSet<String>,
+) -> Vec<CandidateFeedPost> {
+    let mut out = Vec::new();
+    for candidate in candidates {
+        if let Some(key) = bluesky_candidate_dedup_key(&candidate.feed_item) {
+            if !seen.insert(key) {
                 continue;
             }
         }
-
-        let modified_at = meta
-            .modified()
-            .ok()
-            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
-            .map(|d| i64::try_from(d.as_secs()).unwrap_or(0))
-            .unwrap_or(0);
-        let title = path
-            .file_stem()
-            .and_then(|s| s.to_str())
-            .unwrap_or("untitled")
-            .replace(['_', '-'], " ");
-        let text_content = if kind == "text" {
-            std::fs::read_to_string(&path).ok()
-        } else {
-            None
-        };
-        let preview = text_content
-            .as_deref()
-            .map(|s| truncate_with_ellipsis(s, 240))
-            .unwrap_or_default();
-        let (workspace_synth_processed, workspace_synth_pending, workspace_synth_last_processed_at) =
-            if kind == "text" && rel.starts_with("journals/text/") {
-                let current_hash = text_content
-                    .as_deref()
-                    .map(|content| content_hash_16(content.trim()))
-                    .unwrap_or_default();
-                let state = synth_state_map.get(&rel);
-                let processed = state
-                    .map(|item| !current_hash.is_empty() && item.last_processed_hash == current_hash)
-                    .unwrap_or(false);
-                (
-                    processed,
-                    !processed,
-                    state.and_then(|item| non_empty_string(item.last_processed_at.clone())),
-                )
-            } else {
-                (false, false, None)
-            };
-        out.push(serde_json::json!({
-            "id": rel.clone(),
-            "path": rel.clone(),
-            "title": title,
-            "kind": kind,
-            "sizeBytes": meta.len(),
-            "modifiedAt": modified_at,
-            "previewText": preview,
-            "mediaUrl": if kind == "audio" || kind == "video" || kind == "image" {
-                serde_json::Value::String(format!("/api/media/{rel}"))
-            } else {
-                serde_json::Value::Null
-            },
-            "editableText": kind == "text",
-            "scope": scope_value,
-            "workspaceSynthProcessed": workspace_synth_processed,
-            "workspaceSynthPending": workspace_synth_pending,
-            "workspaceSynthLastProcessedAt": workspace_synth_last_processed_at,
-        }));
+        out.push(candidate);
     }
-    Ok(())
+    out
 }
 
-const INTEREST_MERGE_THRESHOLD: f32 = 0.75;
-const INTEREST_SPAWN_THRESHOLD: f32 = 0.35;
-const FEED_MATCH_THRESHOLD: f32 = 0.65;
-const INTEREST_DECAY_RATE: f64 = 0.95;
-const INTEREST_EMA_NEW_WEIGHT: f32 = 0.2;
-const BLUESKY_TIMELINE_LIMIT_MAX: usize = 100;
-const BLUESKY_DISCOVER_FEED_URI: &str =
-    "at://did:plc:qh3lfd7q24h3fn3pejqr25ct/app.bsky.feed.generator/whats-hot";
-const BLUESKY_FEED_GENERATOR_DISCOVERY_PAGE_LIMIT: usize = 2;
-const BLUESKY_FEED_GENERATOR_DISCOVERY_PAGE_SIZE: usize = 25;
-const BLUESKY_FEED_GENERATOR_MATCH_LIMIT: usize = 4;
-const BLUESKY_FEED_SOURCE_MATCH_THRESHOLD: f32 = 0.55;
-const BLUESKY_PERSONALIZED_PAGE_LIMIT_PER_SOURCE: usize = 10;
-const BLUESKY_PERSONALIZED_MATCH_LIMIT: usize = 10;
-const WORKSPACE_SYNTH_BATCH_WORD_LIMIT: usize = 500;
-const BLUESKY_PERSONALIZED_PAGE_SIZE: usize = 30;
-const FEED_WEB_SOURCE_KIND: &str = "hn-popular-blogs-2025";
-const FEED_WEB_PREVIEW_CACHE_TTL_SECS: i64 = 24 * 60 * 60;
-const FEED_WEB_INTEREST_QUERY_COUNT: usize = 3;
-const FEED_WEB_DOMAIN_BATCH_SIZE: usize = 5;
-const FEED_WEB_DOMAIN_BATCHES_PER_INTEREST: usize = 2;
-const FEED_WEB_RESULT_LIMIT_PER_QUERY: usize = 5;
-const CONTENT_SOURCE_REFRESH_TTL_SECS: i64 = 30 * 60;
-const CONTENT_SOURCE_REFRESH_BATCH_SIZE: usize = 6;
-const CONTENT_ITEM_EMBEDDING_BACKFILL_BATCH_SIZE: usize = 16;
-const CONTENT_SOURCE_ITEM_LIMIT: usize = 12;
-const CONTENT_RANK_CANDIDATE_LIMIT: usize = 160;
-const CONTENT_TEXT_MAX_CHARS: usize = 2400;
-const CONTENT_FETCH_TIMEOUT_SECS: u64 = 8;
-
-#[derive(Debug, Clone, serde::Serialize)]
-#[serde(rename_all = "camelCase")]
-struct WebFeedPreview {
-    url: String,
-    title: String,
-    description: String,
-    image_url: Option<String>,
-    domain: String,
-    provider: String,
-    provider_snippet: Option<String>,
-    discovered_at: String,
+fn build_raw_personalized_items(
+    candidates: Vec<CandidateFeedPost>,
+    limit: usize,
+) -> Vec<PersonalizedBlueskyItem> {
+    candidates
+        .into_iter()
+        .take(limit)
+        .map(|candidate| PersonalizedBlueskyItem {
+            source_type: "bluesky".to_string(),
+            feed_item: candidate.feed_item,
+            web_preview: None,
+            feed_source: candidate.feed_source,
+            score: None,
+            matched_interest_label: None,
+            matched_interest_score: None,
+            passed_threshold: false,
+        })
+        .collect()
 }
 
-#[derive(Debug, Clone, serde::Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct PersonalizedFeedRequest {
-    service_url: Option<String>,
-    access_jwt: Option<String>,
-    limit: Option<usize>,
-    #[serde(default)]
-    force: bool,
-}
+async fn fetch_bluesky_feed_generator_page(
+    service_url: &str,
+    access_jwt: &str,
+    cursor: Option<&str>,
+    limit: usize,
+) -> Result<(Vec<CandidateFeedGenerator>, Option<String>)> {
+    let url = build_bluesky_feed_generator_discovery_endpoint(service_url, cursor, limit);
+    let response = crate::http_client::shared()
+        .get(url)
+        .bearer_auth(access_jwt.trim())
+        .send()
+        .await
+        .context("Failed to fetch Bluesky feed generators")?;
 
-#[derive(Debug, Clone, serde::Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct WorldFeedInterestCreateRequest {
-    label: Option<String>,
-}
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        anyhow::bail!("Bluesky feed generator request failed ({status}): {body}");
+    }
 
-#[derive(Debug, Clone, serde::Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct WorldFeedInterestUpdateRequest {
-    label: Option<String>,
-    keywords_override: Option<Vec<String>>,
-}
+    let json: serde_json::Value = response
+        .json()
+        .await
+        .context("Failed to decode Bluesky feed generator response")?;
+    let feeds = json
+        .get("feeds")
+        .and_then(serde_json::Value::as_array)
+        .cloned()
+        .unwrap_or_default();
+    let next_cursor = json
+        .get("cursor")
+        .and_then(serde_json::Value::as_str)
+        .map(ToOwned::to_owned);
 
-#[derive(Debug, Clone, serde::Serialize, Default)]
-#[serde(rename_all = "camelCase")]
-struct InterestProfileStats {
-    interest_count: usize,
-    source_count: usize,
-    refreshed_sources: usize,
-    merged_count: usize,
-    spawned_count: usize,
-    ignored_count: usize,
-}
+    let generators = feeds
+        .into_iter()
+        .filter_map(|item| {
+            let uri = item
+                .get("uri")
+                .and_then(serde_json::Value::as_str)
+                .map(str::trim)
+                .filter(|value| !value.is_empty())?
+                .to_string();
+            Some(CandidateFeedGenerator {
+                uri,
+                display_name: item
+                    .get("displayName")
+                    .and_then(serde_json::Value::as_str)
+                    .unwrap_or("")
+                    .trim()
+                    .to_string(),
+                description: item
+                    .get("description")
+                    .and_then(serde_json::Value::as_str)
+                    .unwrap_or("")
+                    .trim()
+                    .to_string(),
+                creator_handle: item
+                    .get("creator")
+                    .and_then(|creator| creator.get("handle"))
+                    .and_then(serde_json::Value::as_str)
+                    .unwrap_or("")
+                    .trim()
+                    .to_string(),
+                creator_display_name: item
+                    .get("creator")
+                    .and_then(|creator| creator.get("displayName"))
+                    .and_then(serde_json::Value::as_str)
+                    .unwrap_or("")
+                    .trim()
+                    .to_string(),
+            })
+        })
+        .collect();
 
-#[derive(Debug, Clone, serde::Serialize)]
-#[serde(rename_all = "camelCase")]
-struct BlueskyFeedSourceContext {
-    label: String,
-    description: Option<String>,
-    matched_interest_label: Option<String>,
-    matched_interest_score: Option<f32>,
+    Ok((generators, next_cursor))
 }
 
-#[derive(Debug, Clone, serde::Serialize)]
-#[serde(rename_all = "camelCase")]
-struct PersonalizedBlueskyItem {
-    source_type: String,
-    feed_item: serde_json::Value,
-    web_preview: Option<WebFeedPreview>,
-    feed_source: Option<BlueskyFeedSourceContext>,
-    score: Option<f32>,
-    matched_interest_label: Option<String>,
-    matched_interest_score: Option<f32>,
-    passed_threshold: bool,
-}
+async fn collect_ranked_bluesky_feed_sources(
+    service_url: &str,
+    access_jwt: &str,
+    embedder: Arc<dyn memory::embeddings::EmbeddingProvider>,
+    interests: &[ActiveInterest],
+) -> Result<Vec<BlueskyCandidateSource>> {
+    let mut generators = Vec::new();
+    let mut seen_uris = HashSet::new();
+    let mut cursor: Option<String> = None;
 
-#[derive(Debug, Clone)]
-struct ActiveInterest {
-    record: local_store::FeedInterestRecord,
-    embedding: Vec<f32>,
-}
+    for _ in 0..BLUESKY_FEED_GENERATOR_DISCOVERY_PAGE_LIMIT {
+        let (page, next_cursor) = fetch_bluesky_feed_generator_page(
+            service_url,
+            access_jwt,
+            cursor.as_deref(),
+            BLUESKY_FEED_GENERATOR_DISCOVERY_PAGE_SIZE,
+        )
+        .await?;
+        if page.is_empty() {
+            break;
+        }
 
-#[derive(Debug, Clone)]
-struct CandidateFeedPost {
-    feed_item: serde_json::Value,
-    text: String,
-    feed_source: Option<BlueskyFeedSourceContext>,
-}
+        for generator in page {
+            if seen_uris.insert(generator.uri.clone()) {
+                generators.push(generator);
+            }
+        }
 
-#[derive(Debug, Clone)]
-struct CandidateFeedGenerator {
-    uri: String,
-    display_name: String,
-    description: String,
-    creator_handle: String,
-    creator_display_name: String,
-}
+        let Some(next_cursor) = next_cursor.filter(|value| !value.trim().is_empty()) else {
+            break;
+        };
+        cursor = Some(next_cursor);
+    }
 
-#[derive(Debug, Clone)]
-struct CandidateWebResult {
-    url: String,
-    title: String,
-    description: String,
-    domain: String,
-    provider: String,
-    search_query: String,
-}
+    let mut ranked = Vec::new();
+    for generator in generators {
+        let search_text = bluesky_feed_generator_search_text(&generator);
+        if search_text.trim().is_empty() {
+            continue;
+        }
 
-#[derive(Debug, Clone)]
-struct ParsedFeedEntry {
-    external_id: String,
-    canonical_url: String,
-    title: String,
-    author: String,
-    summary: String,
-    content_text: String,
-    published_at: String,
-}
+        let embedding = embedder
+            .embed_one(&search_text)
+            .await
+            .with_context(|| format!("Failed to embed Bluesky feed {}", generator.uri))?;
+        let (best_weighted, best_similarity, best_label) =
+            best_interest_match(&embedding, interests);
+        if best_weighted < BLUESKY_FEED_SOURCE_MATCH_THRESHOLD {
+            continue;
+        }
+        let label = bluesky_feed_generator_label(&generator);
+        let description = non_empty_string(generator.description.clone());
 
-#[derive(Debug, Clone)]
-enum BlueskyCandidateSourceEndpoint {
-    HomeTimeline,
-    FeedGenerator { uri: String },
-}
+        ranked.push((
+            best_weighted,
+            BlueskyCandidateSource::feed_generator(
+                generator.uri,
+                label.clone(),
+                Some(BlueskyFeedSourceContext {
+                    label,
+                    description,
+                    matched_interest_label: best_label,
+                    matched_interest_score: if best_similarity > 0.0 {
+                        Some(best_similarity)
+                    } else {
+                        None
+                    },
+                }),
+            ),
+        ));
+    }
 
-#[derive(Debug, Clone)]
-struct BlueskyCandidateSource {
-    endpoint: BlueskyCandidateSourceEndpoint,
-    label: String,
-    feed_source: Option<BlueskyFeedSourceContext>,
+    ranked.sort_by(|left, right| {
+        right
+            .0
+            .partial_cmp(&left.0)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    Ok(ranked
+        .into_iter()
+        .take(BLUESKY_FEED_GENERATOR_MATCH_LIMIT)
+        .map(|(_, source)| source)
+        .collect())
 }
 
-impl BlueskyCandidateSource {
-    fn home_timeline() -> Self {
-        Self {
-            endpoint: BlueskyCandidateSourceEndpoint::HomeTimeline,
-            label: "home".to_string(),
-            feed_source: Some(BlueskyFeedSourceContext {
-                label: "Home timeline".to_string(),
-                description: None,
-                matched_interest_label: None,
-                matched_interest_score: None,
-            }),
-        }
+async fn fetch_bluesky_candidate_page(
+    service_url: &str,
+    access_jwt: &str,
+    source: &BlueskyCandidateSource,
+    cursor: Option<&str>,
+    limit: usize,
+) -> Result<(Vec<CandidateFeedPost>, Option<String>)> {
+    let url = build_bluesky_feed_endpoint(service_url, source, cursor, limit);
+    let response = crate::http_client::shared()
+        .get(url)
+        .bearer_auth(access_jwt.trim())
+        .send()
+        .await
+        .with_context(|| format!("Failed to fetch Bluesky {} feed", source.request_label()))?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        anyhow::bail!(
+            "Bluesky {} feed request failed ({status}): {body}",
+            source.request_label()
+        );
     }
 
-    fn feed_generator(
-        uri: impl Into<String>,
-        label: impl Into<String>,
-        feed_source: Option<BlueskyFeedSourceContext>,
-    ) -> Self {
-        Self {
-            endpoint: BlueskyCandidateSourceEndpoint::FeedGenerator { uri: uri.into() },
-            label: label.into(),
-            feed_source,
-        }
-    }
+    let json: serde_json::Value = response
+        .json()
+        .await
+        .with_context(|| {
+            format!(
+                "Failed to decode Bluesky {} feed response",
+                source.request_label()
+            )
+        })?;
+    let feed = json
+        .get("feed")
+        .and_then(serde_json::Value::as_array)
+        .cloned()
+        .unwrap_or_default();
+    let next_cursor = json
+        .get("cursor")
+        .and_then(serde_json::Value::as_str)
+        .map(ToOwned::to_owned);
 
-    fn request_label(&self) -> &str {
-        self.label.as_str()
-    }
+    Ok((
+        feed.into_iter()
+            .map(|feed_item| CandidateFeedPost {
+                text: extract_bluesky_post_text(&feed_item),
+                feed_item,
+                feed_source: source.feed_source.clone(),
+            })
+            .collect(),
+        next_cursor,
+    ))
+}
 
-    fn endpoint_key(&self) -> String {
-        match &self.endpoint {
-            BlueskyCandidateSourceEndpoint::HomeTimeline => "home".to_string(),
-            BlueskyCandidateSourceEndpoint::FeedGenerator { uri } => format!("feed:{uri}"),
+async fn fetch_bluesky_fallback_candidates(
+    service_url: &str,
+    access_jwt: &str,
+    limit: usize,
+) -> Result<Vec<CandidateFeedPost>> {
+    let mut seen = BTreeSet::new();
+    let mut all_candidates = Vec::new();
+    for source in default_bluesky_candidate_sources() {
+        let (page, _) = fetch_bluesky_candidate_page(
+            service_url,
+            access_jwt,
+            &source,
+            None,
+            limit.min(BLUESKY_PERSONALIZED_PAGE_SIZE),
+        )
+        .await?;
+        let unique_page = dedupe_candidate_posts(page, &mut seen);
+        all_candidates.extend(unique_page);
+        if all_candidates.len() >= limit {
+            break;
         }
     }
+    Ok(all_candidates)
 }
 
-#[derive(Debug, Clone, Default)]
-struct RebuildInterestProfileResult {
-    status: &'static str,
-    stats: InterestProfileStats,
-    interests: Vec<ActiveInterest>,
+fn normalize_feed_web_domain(raw: &str) -> String {
+    raw.trim()
+        .trim_start_matches("www.")
+        .trim_end_matches('.')
+        .to_ascii_lowercase()
 }
 
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct WorkspaceSyncFile {
-    path: String,
-    modified_at: i64,
-    content_base64: String,
+fn resolve_feed_web_domain(url: &str) -> Option<String> {
+    let parsed = reqwest::Url::parse(url).ok()?;
+    let host = parsed.host_str()?;
+    Some(normalize_feed_web_domain(host))
 }
 
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct LocalStoreSyncBlob {
-    modified_at: i64,
-    content_base64: String,
+fn is_allowed_feed_web_domain(host: &str, allowed: &BTreeSet<String>) -> bool {
+    let normalized = normalize_feed_web_domain(host);
+    allowed.iter().any(|domain| {
+        normalized == *domain || normalized.ends_with(&format!(".{domain}"))
+    })
 }
 
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct WorkspaceSyncSnapshot {
-    exported_at: i64,
-    files: Vec<WorkspaceSyncFile>,
-    local_store: Option<LocalStoreSyncBlob>,
+fn seed_default_feed_web_sources(workspace_dir: &StdPath) -> Result<()> {
+    for source in DEFAULT_FEED_WEB_SOURCES {
+        let _ = local_store::upsert_feed_web_source(
+            workspace_dir,
+            &local_store::FeedWebSourceUpsert {
+                domain: source.domain.to_string(),
+                title: source.title.to_string(),
+                html_url: source.html_url.to_string(),
+                xml_url: source.xml_url.to_string(),
+                description: String::new(),
+                topics_csv: String::new(),
+                metadata_embedding: Vec::new(),
+                enabled: true,
+                source_kind: FEED_WEB_SOURCE_KIND.to_string(),
+            },
+        )?;
+    }
+    Ok(())
 }
 
-fn content_hash_16(text: &str) -> String {
-    use sha2::{Digest, Sha256};
+fn build_feed_web_queries(
+    interests: &[ActiveInterest],
+    sources: &[local_store::FeedWebSourceRecord],
+) -> Vec<String> {
+    let top_interests: Vec<&ActiveInterest> = interests
+        .iter()
+        .filter(|interest| !interest.record.label.trim().is_empty())
+        .collect();
+    let mut top_interests = top_interests;
+    top_interests.sort_by(|left, right| {
+        right
+            .record
+            .health_score
+            .partial_cmp(&left.record.health_score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    top_interests.truncate(FEED_WEB_INTEREST_QUERY_COUNT);
 
-    let hash = Sha256::digest(text.as_bytes());
-    format!(
-        "{:016x}",
-        u64::from_be_bytes(
-            hash[..8]
-                .try_into()
-                .expect("SHA-256 always produces at least 8 bytes")
-        )
-    )
-}
+    let domains: Vec<String> = sources
+        .iter()
+        .map(|source| normalize_feed_web_domain(&source.domain))
+        .filter(|domain| !domain.is_empty())
+        .collect();
+    if domains.is_empty() {
+        return Vec::new();
+    }
 
-#[derive(Debug, Clone, Default)]
-struct WorkspaceSynthSourceCandidate {
-    source_path: String,
-    content_hash: String,
-    word_count: usize,
-    modified_at: i64,
+    let batches: Vec<&[String]> = domains.chunks(FEED_WEB_DOMAIN_BATCH_SIZE).collect();
+    let mut queries = Vec::new();
+    for (interest_index, interest) in top_interests.iter().enumerate() {
+        for batch_offset in 0..FEED_WEB_DOMAIN_BATCHES_PER_INTEREST {
+            let batch_index = (interest_index * FEED_WEB_DOMAIN_BATCHES_PER_INTEREST + batch_offset)
+                % batches.len();
+            let batch = batches[batch_index];
+            let site_filters = batch
+                .iter()
+                .map(|domain| format!("site:{domain}"))
+                .collect::<Vec<_>>()
+                .join(" OR ");
+            queries.push(format!("{} ({site_filters})", interest.record.label));
+        }
+    }
+    queries
 }
 
-#[derive(Debug, Clone, Default)]
-struct WorkspaceSynthPendingSelection {
-    pending: Vec<WorkspaceSynthSourceCandidate>,
-    selected: Vec<WorkspaceSynthSourceCandidate>,
-    selected_word_count: usize,
-}
+fn build_feed_web_search_tool(config: &Config) -> Option<WebSearchTool> {
+    if !config.web_search.enabled {
+        return None;
+    }
 
-fn count_words(text: &str) -> usize {
-    text.split_whitespace().count()
+    Some(WebSearchTool::new(
+        config.web_search.provider.clone(),
+        config.web_search.brave_api_key.clone(),
+        FEED_WEB_RESULT_LIMIT_PER_QUERY.min(config.web_search.max_results),
+        config.web_search.timeout_secs,
+    ))
 }
 
-fn collect_workspace_synth_source_candidates(
-    workspace_dir: &StdPath,
-) -> Vec<WorkspaceSynthSourceCandidate> {
-    let root = workspace_dir.join(JOURNAL_TEXT_DIR);
-    if !root.exists() || !root.is_dir() {
-        return Vec::new();
-    }
+async fn collect_web_search_candidates(
+    config: &Config,
+    interests: &[ActiveInterest],
+) -> Result<Vec<CandidateWebResult>> {
+    let Some(tool) = build_feed_web_search_tool(config) else {
+        return Ok(Vec::new());
+    };
 
-    let mut items = Vec::new();
-    let mut stack = vec![root];
-    while let Some(dir) = stack.pop() {
-        let entries = match std::fs::read_dir(&dir) {
-            Ok(entries) => entries,
-            Err(_) => continue,
-        };
-        for entry in entries.flatten() {
-            let path = entry.path();
-            let Ok(file_type) = entry.file_type() else {
-                continue;
-            };
-            if file_type.is_dir() {
-                stack.push(path);
-                continue;
-            }
-            if !file_type.is_file() || !is_content_agent_source_file(&path) {
+    let workspace_dir = &config.workspace_dir;
+    seed_default_feed_web_sources(workspace_dir)?;
+    let sources = local_store::list_feed_web_sources(workspace_dir)?;
+    if sources.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let allowed_domains: BTreeSet<String> = sources
+        .iter()
+        .map(|source| normalize_feed_web_domain(&source.domain))
+        .collect();
+    let mut candidates = Vec::new();
+    let mut seen_urls = BTreeSet::new();
+
+    for query in build_feed_web_queries(interests, &sources) {
+        let results = match tool.search_structured(&query).await {
+            Ok(results) => results,
+            Err(err) => {
+                tracing::debug!(query, error = %err, "Feed web search query failed");
                 continue;
             }
-            let Ok(content) = std::fs::read_to_string(&path) else {
+        };
+        for result in results {
+            let Some(domain) = resolve_feed_web_domain(&result.url) else {
                 continue;
             };
-            let trimmed = content.trim();
-            if trimmed.is_empty() {
+            if !is_allowed_feed_web_domain(&domain, &allowed_domains) {
                 continue;
             }
-            let Ok(rel) = path.strip_prefix(workspace_dir) else {
+            if !seen_urls.insert(result.url.clone()) {
                 continue;
-            };
-            items.push(WorkspaceSynthSourceCandidate {
-                source_path: rel.to_string_lossy().replace('\\', "/"),
-                content_hash: content_hash_16(trimmed),
-                word_count: count_words(trimmed),
-                modified_at: source_file_modified_at_secs(&path),
+            }
+            candidates.push(CandidateWebResult {
+                url: result.url,
+                title: result.title,
+                description: result.description,
+                domain,
+                provider: result.provider,
+                search_query: query.clone(),
             });
         }
     }
-    items.sort_by(|a, b| b.modified_at.cmp(&a.modified_at));
-    items
+
+    Ok(candidates)
 }
 
-fn select_workspace_synth_sources(
-    workspace_dir: &StdPath,
-    forced_paths: &[String],
-    force_reprocess: bool,
-) -> Result<WorkspaceSynthPendingSelection> {
-    let candidates = collect_workspace_synth_source_candidates(workspace_dir);
-    if candidates.is_empty() {
-        return Ok(WorkspaceSynthPendingSelection::default());
+fn web_preview_cache_is_fresh(updated_at: &str) -> bool {
+    chrono::DateTime::parse_from_rfc3339(updated_at)
+        .ok()
+        .map(|value| Utc::now().signed_duration_since(value.with_timezone(&Utc)).num_seconds())
+        .map(|age| age >= 0 && age <= FEED_WEB_PREVIEW_CACHE_TTL_SECS)
+        .unwrap_or(false)
+}
+
+fn first_meta_capture(html: &str, patterns: &[&str]) -> Option<String> {
+    for pattern in patterns {
+        let regex = Regex::new(pattern).ok()?;
+        if let Some(capture) = regex.captures(html) {
+            if let Some(value) = capture.get(1) {
+                let trimmed = html_unescape_basic(value.as_str().trim());
+                if !trimmed.is_empty() {
+                    return Some(trimmed);
+                }
+            }
+        }
     }
+    None
+}
 
-    let state_map: HashMap<String, local_store::WorkspaceSynthSourceRecord> =
-        local_store::list_workspace_synth_sources(workspace_dir)
-            .unwrap_or_default()
-            .into_iter()
-            .map(|item| (item.source_path.clone(), item))
-            .collect();
-    let forced: HashSet<String> = forced_paths
-        .iter()
-        .map(|value| value.trim().trim_start_matches('/').replace('\\', "/"))
-        .filter(|value| !value.is_empty())
-        .collect();
+fn html_unescape_basic(raw: &str) -> String {
+    raw.replace("&amp;", "&")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&nbsp;", " ")
+}
 
-    let pending: Vec<WorkspaceSynthSourceCandidate> = candidates
-        .iter()
-        .filter(|item| {
-            if forced.contains(&item.source_path) {
-                return true;
-            }
-            state_map
-                .get(&item.source_path)
-                .map(|state| state.last_processed_hash != item.content_hash)
-                .unwrap_or(true)
-        })
-        .cloned()
-        .collect();
+fn xml_block_regex(tag: &str) -> Regex {
+    Regex::new(&format!(r"(?is)<{tag}\b[^>]*>(.*?)</{tag}>", tag = regex::escape(tag)))
+        .expect("valid XML block regex")
+}
 
-    if pending.is_empty() {
-        return Ok(WorkspaceSynthPendingSelection::default());
-    }
+fn xml_tag_regex(tag: &str) -> Regex {
+    Regex::new(&format!(r"(?is)<{tag}\b[^>]*>(.*?)</{tag}>", tag = regex::escape(tag)))
+        .expect("valid XML tag regex")
+}
 
-    if !forced.is_empty() {
-        let selected: Vec<WorkspaceSynthSourceCandidate> = candidates
-            .into_iter()
-            .filter(|item| {
-                forced.contains(&item.source_path)
-                    && (force_reprocess
-                        || state_map
-                            .get(&item.source_path)
-                            .map(|state| state.last_processed_hash != item.content_hash)
-                            .unwrap_or(true))
-            })
-            .collect();
-        let selected_word_count = selected.iter().map(|item| item.word_count).sum();
-        return Ok(WorkspaceSynthPendingSelection {
-            pending,
-            selected,
-            selected_word_count,
-        });
-    }
+fn xml_link_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r#"(?is)<link\b([^>]*)>"#).expect("valid XML link regex"))
+}
 
-    let mut selected = Vec::new();
-    let mut selected_word_count = 0usize;
-    for item in &pending {
-        if !selected.is_empty() && selected_word_count + item.word_count > WORKSPACE_SYNTH_BATCH_WORD_LIMIT {
-            break;
-        }
-        selected_word_count += item.word_count;
-        selected.push(item.clone());
-    }
-    if selected.is_empty() && !pending.is_empty() {
-        selected.push(pending[0].clone());
-        selected_word_count = pending[0].word_count;
-    }
+fn xml_href_attr_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| {
+        Regex::new(r#"(?is)\bhref\s*=\s*["']([^"']+)["']"#).expect("valid href regex")
+    })
+}
 
-    Ok(WorkspaceSynthPendingSelection {
-        pending,
-        selected,
-        selected_word_count,
+fn xml_rel_attr_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| {
+        Regex::new(r#"(?is)\brel\s*=\s*["']([^"']+)["']"#).expect("valid rel regex")
     })
 }
 
-fn derive_interest_label(default_title: &str, content: &str) -> String {
-    let normalized_title = default_title.trim();
-    if !normalized_title.is_empty() && !normalized_title.eq_ignore_ascii_case("untitled") {
-        return truncate_with_ellipsis(normalized_title, 80);
-    }
+fn html_tag_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r"(?is)<[^>]+>").expect("valid HTML tag regex"))
+}
 
-    for line in content.lines() {
-        let trimmed = line.trim().trim_start_matches('#').trim();
-        if !trimmed.is_empty() {
-            return truncate_with_ellipsis(trimmed, 80);
-        }
-    }
+fn html_break_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r"(?is)<br\s*/?>").expect("valid break regex"))
+}
 
-    "Workspace interest".to_string()
+fn html_paragraph_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r"(?is)</p\s*>").expect("valid paragraph regex"))
 }
 
-fn non_empty_string(value: String) -> Option<String> {
-    let trimmed = value.trim();
-    if trimmed.is_empty() {
-        None
-    } else {
-        Some(trimmed.to_string())
-    }
+fn collapse_whitespace(raw: &str) -> String {
+    raw.split_whitespace().collect::<Vec<_>>().join(" ")
 }
 
-fn ema_merge_vectors(current: &[f32], previous: &[f32]) -> Vec<f32> {
-    current
-        .iter()
-        .zip(previous.iter())
-        .map(|(new_value, previous_value)| {
-            INTEREST_EMA_NEW_WEIGHT * *new_value + (1.0 - INTEREST_EMA_NEW_WEIGHT) * *previous_value
-        })
-        .collect()
+fn sanitize_feed_text(raw: &str) -> String {
+    let without_breaks = html_break_regex().replace_all(raw, "\n");
+    let with_paragraphs = html_paragraph_regex().replace_all(&without_breaks, "\n");
+    let without_tags = html_tag_regex().replace_all(&with_paragraphs, " ");
+    let without_cdata = without_tags
+        .replace("<![CDATA[", "")
+        .replace("]]>", "")
+        .replace("&apos;", "'");
+    collapse_whitespace(&html_unescape_basic(&without_cdata))
 }
 
-fn sort_personalized_items(items: &mut [PersonalizedBlueskyItem]) {
-    items.sort_by(|left, right| {
-        let score_order = right
-            .score
-            .unwrap_or(0.0)
-            .partial_cmp(&left.score.unwrap_or(0.0))
-            .unwrap_or(std::cmp::Ordering::Equal);
-        if score_order != std::cmp::Ordering::Equal {
-            return score_order;
+fn extract_xml_tag_text(fragment: &str, tags: &[&str]) -> Option<String> {
+    for tag in tags {
+        let regex = xml_tag_regex(tag);
+        if let Some(capture) = regex.captures(fragment) {
+            if let Some(value) = capture.get(1) {
+                let sanitized = sanitize_feed_text(value.as_str());
+                if !sanitized.is_empty() {
+                    return Some(sanitized);
+                }
+            }
+        }
+    }
+    None
+}
+
+fn extract_atom_link(fragment: &str, base_url: &str) -> Option<String> {
+    let mut fallback: Option<String> = None;
+    for capture in xml_link_regex().captures_iter(fragment) {
+        let attrs = capture.get(1).map(|value| value.as_str()).unwrap_or("");
+        let href = xml_href_attr_regex()
+            .captures(attrs)
+            .and_then(|value| value.get(1))
+            .map(|value| value.as_str().trim().to_string());
+        let Some(href) = href.filter(|value| !value.is_empty()) else {
+            continue;
+        };
+        let rel = xml_rel_attr_regex()
+            .captures(attrs)
+            .and_then(|value| value.get(1))
+            .map(|value| value.as_str().trim().to_ascii_lowercase());
+        if rel.as_deref() != Some("self") {
+            return Some(absolutize_feed_url(base_url, &href));
+        }
+        if fallback.is_none() {
+            fallback = Some(absolutize_feed_url(base_url, &href));
         }
-        let right_ts = item_sort_timestamp(right);
-        let left_ts = item_sort_timestamp(left);
-        right_ts.cmp(left_ts)
-    });
-}
-
-fn item_sort_timestamp(item: &PersonalizedBlueskyItem) -> &str {
-    if let Some(discovered_at) = item
-        .web_preview
-        .as_ref()
-        .map(|preview| preview.discovered_at.as_str())
-        .filter(|value| !value.is_empty())
-    {
-        return discovered_at;
     }
-
-    item.feed_item
-        .get("post")
-        .and_then(|post| post.get("indexedAt"))
-        .and_then(serde_json::Value::as_str)
-        .unwrap_or("")
+    fallback
 }
 
-fn extract_bluesky_post_text(feed_item: &serde_json::Value) -> String {
-    let post = feed_item.get("post").unwrap_or(feed_item);
-    let text = post
-        .get("record")
-        .and_then(|record| record.get("text"))
-        .and_then(serde_json::Value::as_str)
-        .unwrap_or("")
-        .trim();
-    if !text.is_empty() {
-        return text.to_string();
+fn absolutize_feed_url(base_url: &str, raw_url: &str) -> String {
+    let trimmed = raw_url.trim();
+    if trimmed.is_empty() {
+        return String::new();
     }
-
-    post.get("embed")
-        .and_then(|embed| embed.get("external"))
-        .and_then(|external| external.get("title"))
-        .and_then(serde_json::Value::as_str)
-        .unwrap_or("")
-        .trim()
-        .to_string()
+    if let Ok(parsed) = reqwest::Url::parse(trimmed) {
+        return parsed.to_string();
+    }
+    reqwest::Url::parse(base_url)
+        .and_then(|base| base.join(trimmed))
+        .map(|url| url.to_string())
+        .unwrap_or_else(|_| trimmed.to_string())
 }
 
-fn default_bluesky_candidate_sources() -> Vec<BlueskyCandidateSource> {
-    vec![
-        BlueskyCandidateSource::home_timeline(),
-        BlueskyCandidateSource::feed_generator(
-            BLUESKY_DISCOVER_FEED_URI,
-            "discover".to_string(),
-            Some(BlueskyFeedSourceContext {
-                label: "Discover".to_string(),
-                description: None,
-                matched_interest_label: None,
-                matched_interest_score: None,
-            }),
-        ),
-    ]
+fn normalize_feed_timestamp(raw: &str) -> String {
+    let trimmed = raw.trim();
+    if trimmed.is_empty() {
+        return String::new();
+    }
+    if let Ok(parsed) = chrono::DateTime::parse_from_rfc3339(trimmed) {
+        return parsed.with_timezone(&Utc).to_rfc3339();
+    }
+    if let Ok(parsed) = chrono::DateTime::parse_from_rfc2822(trimmed) {
+        return parsed.with_timezone(&Utc).to_rfc3339();
+    }
+    trimmed.to_string()
 }
 
-fn build_bluesky_feed_endpoint(
-    service_url: &str,
-    source: &BlueskyCandidateSource,
-    cursor: Option<&str>,
-    limit: usize,
-) -> String {
-    let trimmed_service = service_url.trim().trim_end_matches('/');
-    let normalized_limit = limit.clamp(1, BLUESKY_TIMELINE_LIMIT_MAX);
-    let mut url = match &source.endpoint {
-        BlueskyCandidateSourceEndpoint::HomeTimeline => format!(
-            "{trimmed_service}/xrpc/app.bsky.feed.getTimeline?limit={normalized_limit}"
-        ),
-        BlueskyCandidateSourceEndpoint::FeedGenerator { uri } => format!(
-            "{trimmed_service}/xrpc/app.bsky.feed.getFeed?feed={}&limit={normalized_limit}",
-            urlencoding::encode(uri)
-        ),
-    };
-
-    if let Some(next_cursor) = cursor.map(str::trim).filter(|value| !value.is_empty()) {
-        url.push_str("&cursor=");
-        url.push_str(urlencoding::encode(next_cursor).as_ref());
+fn parse_rss_feed_entries(xml: &str, base_url: &str) -> Vec<ParsedFeedEntry> {
+    let mut items = Vec::new();
+    for capture in xml_block_regex("item").captures_iter(xml) {
+        let fragment = capture.get(1).map(|value| value.as_str()).unwrap_or("");
+        let title = extract_xml_tag_text(fragment, &["title"]).unwrap_or_default();
+        let canonical_url = extract_xml_tag_text(fragment, &["link"])
+            .map(|value| absolutize_feed_url(base_url, &value))
+            .filter(|value| !value.is_empty())
+            .or_else(|| {
+                extract_xml_tag_text(fragment, &["guid"])
+                    .map(|value| absolutize_feed_url(base_url, &value))
+                    .filter(|value| !value.is_empty())
+            })
+            .unwrap_or_else(|| base_url.to_string());
+        let summary = extract_xml_tag_text(fragment, &["description"]).unwrap_or_default();
+        let content_text =
+            extract_xml_tag_text(fragment, &["content:encoded", "content", "description"])
+                .unwrap_or_else(|| summary.clone());
+        let author = extract_xml_tag_text(fragment, &["author", "dc:creator"]).unwrap_or_default();
+        let published_at = extract_xml_tag_text(fragment, &["pubDate", "published", "updated"])
+            .map(|value| normalize_feed_timestamp(&value))
+            .unwrap_or_default();
+        let external_id = extract_xml_tag_text(fragment, &["guid"])
+            .or_else(|| non_empty_string(canonical_url.clone()))
+            .unwrap_or_default();
+        if title.is_empty() && content_text.is_empty() {
+            continue;
+        }
+        items.push(ParsedFeedEntry {
+            external_id,
+            canonical_url,
+            title,
+            author,
+            summary,
+            content_text,
+            published_at,
+        });
     }
-
-    url
+    items
 }
 
-fn build_bluesky_feed_generator_discovery_endpoint(
-    service_url: &str,
-    cursor: Option<&str>,
-    limit: usize,
-) -> String {
-    let trimmed_service = service_url.trim().trim_end_matches('/');
-    let normalized_limit = limit.clamp(1, BLUESKY_TIMELINE_LIMIT_MAX);
-    let mut url = format!(
-        "{trimmed_service}/xrpc/app.bsky.unspecced.getPopularFeedGenerators?limit={normalized_limit}"
-    );
-
-    if let Some(next_cursor) = cursor.map(str::trim).filter(|value| !value.is_empty()) {
-        url.push_str("&cursor=");
-        url.push_str(urlencoding::encode(next_cursor).as_ref());
+fn parse_atom_feed_entries(xml: &str, base_url: &str) -> Vec<ParsedFeedEntry> {
+    let mut items = Vec::new();
+    for capture in xml_block_regex("entry").captures_iter(xml) {
+        let fragment = capture.get(1).map(|value| value.as_str()).unwrap_or("");
+        let title = extract_xml_tag_text(fragment, &["title"]).unwrap_or_default();
+        let canonical_url = extract_atom_link(fragment, base_url).unwrap_or_else(|| base_url.to_string());
+        let summary = extract_xml_tag_text(fragment, &["summary"]).unwrap_or_default();
+        let content_text = extract_xml_tag_text(fragment, &["content", "summary"])
+            .unwrap_or_else(|| summary.clone());
+        let author = xml_block_regex("author")
+            .captures(fragment)
+            .and_then(|value| value.get(1))
+            .and_then(|value| extract_xml_tag_text(value.as_str(), &["name"]))
+            .or_else(|| extract_xml_tag_text(fragment, &["author", "name"]))
+            .unwrap_or_default();
+        let published_at = extract_xml_tag_text(fragment, &["published", "updated"])
+            .map(|value| normalize_feed_timestamp(&value))
+            .unwrap_or_default();
+        let external_id = extract_xml_tag_text(fragment, &["id"])
+            .or_else(|| non_empty_string(canonical_url.clone()))
+            .unwrap_or_default();
+        if title.is_empty() && content_text.is_empty() {
+            continue;
+        }
+        items.push(ParsedFeedEntry {
+            external_id,
+            canonical_url,
+            title,
+            author,
+            summary,
+            content_text,
+            published_at,
+        });
     }
-
-    url
+    items
 }
 
-fn bluesky_feed_generator_label(candidate: &CandidateFeedGenerator) -> String {
-    non_empty_string(candidate.display_name.clone())
-        .or_else(|| non_empty_string(candidate.creator_display_name.clone()))
-        .or_else(|| non_empty_string(candidate.creator_handle.clone()))
-        .unwrap_or_else(|| candidate.uri.clone())
+fn parse_feed_entries(xml: &str, base_url: &str) -> Vec<ParsedFeedEntry> {
+    let trimmed = xml.trim();
+    if trimmed.is_empty() {
+        return Vec::new();
+    }
+    let mut items = if trimmed.contains("<feed") {
+        parse_atom_feed_entries(trimmed, base_url)
+    } else {
+        parse_rss_feed_entries(trimmed, base_url)
+    };
+    items.retain(|item| !item.canonical_url.trim().is_empty());
+    items
 }
 
-fn bluesky_feed_generator_search_text(candidate: &CandidateFeedGenerator) -> String {
-    [
-        candidate.display_name.trim(),
-        candidate.description.trim(),
-        candidate.creator_display_name.trim(),
-        candidate.creator_handle.trim(),
-    ]
-    .into_iter()
-    .filter(|value| !value.is_empty())
-    .collect::<Vec<_>>()
-    .join("\n")
+fn content_source_is_stale(last_fetch_at: &str) -> bool {
+    chrono::DateTime::parse_from_rfc3339(last_fetch_at.trim())
+        .ok()
+        .map(|value| Utc::now().signed_duration_since(value.with_timezone(&Utc)).num_seconds())
+        .map(|age| age < 0 || age > CONTENT_SOURCE_REFRESH_TTL_SECS)
+        .unwrap_or(true)
 }
 
-fn append_unique_bluesky_sources(
-    target: &mut Vec<BlueskyCandidateSource>,
-    extra: Vec<BlueskyCandidateSource>,
-) {
-    let mut seen: HashSet<String> = target.iter().map(BlueskyCandidateSource::endpoint_key).collect();
-    for source in extra {
-        if seen.insert(source.endpoint_key()) {
-            target.push(source);
-        }
-    }
+fn content_item_embedding_text(entry: &ParsedFeedEntry) -> String {
+    let combined = format!(
+        "{}\n{}\n{}",
+        entry.title.trim(),
+        entry.summary.trim(),
+        entry.content_text.trim()
+    );
+    truncate_with_ellipsis(combined.trim(), CONTENT_TEXT_MAX_CHARS)
 }
 
-fn bluesky_candidate_dedup_key(feed_item: &serde_json::Value) -> Option<String> {
-    let post = feed_item.get("post").unwrap_or(feed_item);
-    post.get("uri")
-        .and_then(serde_json::Value::as_str)
-        .map(ToOwned::to_owned)
-        .or_else(|| {
-            post.get("cid")
-                .and_then(serde_json::Value::as_str)
-                .map(ToOwned::to_owned)
-        })
+fn build_content_item_id(source_key: &str, canonical_url: &str, external_id: &str) -> String {
+    format!(
+        "content_{}",
+        content_hash_16(&format!("{source_key}\n{canonical_url}\n{external_id}"))
+    )
 }
 
-fn dedupe_candidate_posts(
-    candidates: Vec<CandidateFeedPost>,
-    seen: &mut BTreeSet<String>,
-) -> Vec<CandidateFeedPost> {
-    let mut out = Vec::new();
-    for candidate in candidates {
-        if let Some(key) = bluesky_candidate_dedup_key(&candidate.feed_item) {
-            if !seen.insert(key) {
-                continue;
-            }
+fn sync_content_sources_from_feed_web_sources(workspace_dir: &StdPath) -> Result<()> {
+    seed_default_feed_web_sources(workspace_dir)?;
+    for source in local_store::list_feed_web_sources(workspace_dir)? {
+        let source_key = source.xml_url.trim();
+        if source_key.is_empty() {
+            continue;
         }
-        out.push(candidate);
+        let _ = local_store::upsert_content_source(
+            workspace_dir,
+            &local_store::ContentSourceUpsert {
+                source_key: source_key.to_string(),
+                domain: source.domain.clone(),
+                title: source.title.clone(),
+                html_url: source.html_url.clone(),
+                xml_url: source.xml_url.clone(),
+                source_kind: source.source_kind.clone(),
+                enabled: source.enabled,
+            },
+        )?;
     }
-    out
+    Ok(())
 }
 
-fn build_raw_personalized_items(
-    candidates: Vec<CandidateFeedPost>,
-    limit: usize,
-) -> Vec<PersonalizedBlueskyItem> {
-    candidates
-        .into_iter()
-        .take(limit)
-        .map(|candidate| PersonalizedBlueskyItem {
-            source_type: "bluesky".to_string(),
-            feed_item: candidate.feed_item,
-            web_preview: None,
-            feed_source: candidate.feed_source,
-            score: None,
-            matched_interest_label: None,
-            matched_interest_score: None,
-            passed_threshold: false,
-        })
-        .collect()
+struct RemoteFeedFetchResult {
+    entries: Vec<ParsedFeedEntry>,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    not_modified: bool,
 }
 
-async fn fetch_bluesky_feed_generator_page(
-    service_url: &str,
-    access_jwt: &str,
-    cursor: Option<&str>,
-    limit: usize,
-) -> Result<(Vec<CandidateFeedGenerator>, Option<String>)> {
-    let url = build_bluesky_feed_generator_discovery_endpoint(service_url, cursor, limit);
-    let response = reqwest::Client::new()
-        .get(url)
-        .bearer_auth(access_jwt.trim())
+async fn fetch_remote_feed(source: &local_store::ContentSourceRecord) -> Result<RemoteFeedFetchResult> {
+    let mut request = crate::http_client::shared()
+        .get(source.xml_url.trim())
+        .timeout(Duration::from_secs(CONTENT_FETCH_TIMEOUT_SECS));
+    if !source.etag.trim().is_empty() {
+        request = request.header(reqwest::header::IF_NONE_MATCH, source.etag.trim());
+    }
+    if !source.last_modified.trim().is_empty() {
+        request = request.header(
+            reqwest::header::IF_MODIFIED_SINCE,
+            source.last_modified.trim(),
+        );
+    }
+
+    let response = request
         .send()
         .await
-        .context("Failed to fetch Bluesky feed generators")?;
+        .with_context(|| format!("Failed to fetch content source {}", source.xml_url))?;
+    let etag = response
+        .headers()
+        .get(reqwest::header::ETAG)
+        .and_then(|value| value.to_str().ok())
+        .map(ToOwned::to_owned);
+    let last_modified = response
+        .headers()
+        .get(reqwest::header::LAST_MODIFIED)
+        .and_then(|value| value.to_str().ok())
+        .map(ToOwned::to_owned);
+
+    if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+        return Ok(RemoteFeedFetchResult {
+            entries: Vec::new(),
+            etag,
+            last_modified,
+            not_modified: true,
+        });
+    }
 
     if !response.status().is_success() {
         let status = response.status();
         let body = response.text().await.unwrap_or_default();
-        anyhow::bail!("Bluesky feed generator request failed ({status}): {body}");
+        anyhow::bail!("Feed fetch failed for {} ({status}): {body}", source.xml_url);
+    }
+
+    let body = response.bytes().await?;
+    let xml = String::from_utf8_lossy(&body);
+    Ok(RemoteFeedFetchResult {
+        entries: parse_feed_entries(&xml, &source.html_url),
+        etag,
+        last_modified,
+        not_modified: false,
+    })
+}
+
+async fn refresh_cached_content_sources(
+    workspace_dir: &StdPath,
+    embedder: Option<Arc<dyn memory::embeddings::EmbeddingProvider>>,
+) -> Result<()> {
+    sync_content_sources_from_feed_web_sources(workspace_dir)?;
+    let sources = local_store::list_content_sources(workspace_dir, 128)?;
+    let stale_sources: Vec<local_store::ContentSourceRecord> = sources
+        .into_iter()
+        .filter(|source| content_source_is_stale(&source.last_fetch_at))
+        .take(CONTENT_SOURCE_REFRESH_BATCH_SIZE)
+        .collect();
+
+    for source in stale_sources {
+        let fetched_at = Utc::now().to_rfc3339();
+        match fetch_remote_feed(&source).await {
+            Ok(result) => {
+                if !result.not_modified {
+                    for entry in result.entries.into_iter().take(CONTENT_SOURCE_ITEM_LIMIT) {
+                        let embedding_text = content_item_embedding_text(&entry);
+                        if embedding_text.trim().is_empty() {
+                            continue;
+                        }
+                        let embedding = if let Some(embedder) = embedder.as_ref() {
+                            match embedder.embed_one(&embedding_text).await {
+                                Ok(value) => vec_to_bytes(&value),
+                                Err(err) => {
+                                    tracing::debug!(
+                                        source = %source.xml_url,
+                                        url = %entry.canonical_url,
+                                        error = %err,
+                                        "Failed to embed feed content item"
+                                    );
+                                    Vec::new()
+                                }
+                            }
+                        } else {
+                            Vec::new()
+                        };
+                        let canonical_url = if entry.canonical_url.trim().is_empty() {
+                            source.html_url.clone()
+                        } else {
+                            entry.canonical_url.clone()
+                        };
+                        let id = build_content_item_id(
+                            &source.source_key,
+                            &canonical_url,
+                            &entry.external_id,
+                        );
+                        let content_hash = content_hash_16(&embedding_text);
+                        let _ = local_store::upsert_content_item(
+                            workspace_dir,
+                            &local_store::ContentItemUpsert {
+                                id,
+                                source_key: source.source_key.clone(),
+                                source_title: source.title.clone(),
+                                source_kind: source.source_kind.clone(),
+                                domain: source.domain.clone(),
+                                canonical_url,
+                                external_id: entry.external_id.clone(),
+                                title: entry.title.clone(),
+                                author: entry.author.clone(),
+                                summary: truncate_with_ellipsis(entry.summary.trim(), 280),
+                                content_text: embedding_text,
+                                content_hash,
+                                embedding,
+                                published_at: entry.published_at.clone(),
+                                discovered_at: fetched_at.clone(),
+                            },
+                        )?;
+                    }
+                }
+                local_store::update_content_source_fetch(
+                    workspace_dir,
+                    &source.source_key,
+                    &fetched_at,
+                    result.etag.as_deref(),
+                    result.last_modified.as_deref(),
+                    None,
+                    true,
+                )?;
+            }
+            Err(err) => {
+                tracing::debug!(
+                    source = %source.xml_url,
+                    error = %err,
+                    "Failed to refresh content source"
+                );
+                local_store::update_content_source_fetch(
+                    workspace_dir,
+                    &source.source_key,
+                    &fetched_at,
+                    None,
+                    None,
+                    Some(&err.to_string()),
+                    false,
+                )?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn backfill_cached_content_item_embeddings(
+    workspace_dir: &StdPath,
+    embedder: Arc<dyn memory::embeddings::EmbeddingProvider>,
+) -> Result<usize> {
+    let missing_items = local_store::list_content_items_missing_embeddings(
+        workspace_dir,
+        CONTENT_ITEM_EMBEDDING_BACKFILL_BATCH_SIZE,
+    )?;
+    if missing_items.is_empty() {
+        return Ok(0);
+    }
+
+    let mut updated_count = 0usize;
+    for item in missing_items {
+        let content = item.content_text.trim();
+        if content.is_empty() {
+            continue;
+        }
+        match embedder.embed_one(content).await {
+            Ok(embedding) if !embedding.is_empty() => {
+                local_store::update_content_item_embedding(
+                    workspace_dir,
+                    &item.id,
+                    &vec_to_bytes(&embedding),
+                )?;
+                updated_count += 1;
+            }
+            Ok(_) => {
+                tracing::debug!(
+                    item_id = %item.id,
+                    url = %item.canonical_url,
+                    "Feed embedder returned an empty vector for cached content item"
+                );
+            }
+            Err(err) => {
+                tracing::debug!(
+                    item_id = %item.id,
+                    url = %item.canonical_url,
+                    error = %err,
+                    "Failed to backfill cached content item embedding"
+                );
+            }
+        }
     }
 
-    let json: serde_json::Value = response
-        .json()
-        .await
-        .context("Failed to decode Bluesky feed generator response")?;
-    let feeds = json
-        .get("feeds")
-        .and_then(serde_json::Value::as_array)
-        .cloned()
-        .unwrap_or_default();
-    let next_cursor = json
-        .get("cursor")
-        .and_then(serde_json::Value::as_str)
-        .map(ToOwned::to_owned);
-
-    let generators = feeds
-        .into_iter()
-        .filter_map(|item| {
-            let uri = item
-                .get("uri")
-                .and_then(serde_json::Value::as_str)
-                .map(str::trim)
-                .filter(|value| !value.is_empty())?
-                .to_string();
-            Some(CandidateFeedGenerator {
-                uri,
-                display_name: item
-                    .get("displayName")
-                    .and_then(serde_json::Value::as_str)
-                    .unwrap_or("")
-                    .trim()
-                    .to_string(),
-                description: item
-                    .get("description")
-                    .and_then(serde_json::Value::as_str)
-                    .unwrap_or("")
-                    .trim()
-                    .to_string(),
-                creator_handle: item
-                    .get("creator")
-                    .and_then(|creator| creator.get("handle"))
-                    .and_then(serde_json::Value::as_str)
-                    .unwrap_or("")
-                    .trim()
-                    .to_string(),
-                creator_display_name: item
-                    .get("creator")
-                    .and_then(|creator| creator.get("displayName"))
-                    .and_then(serde_json::Value::as_str)
-                    .unwrap_or("")
-                    .trim()
-                    .to_string(),
-            })
-        })
-        .collect();
+    Ok(updated_count)
+}
 
-    Ok((generators, next_cursor))
+fn cached_content_backfill_inflight() -> &'static Mutex<HashSet<PathBuf>> {
+    static INFLIGHT: OnceLock<Mutex<HashSet<PathBuf>>> = OnceLock::new();
+    INFLIGHT.get_or_init(|| Mutex::new(HashSet::new()))
 }
 
-async fn collect_ranked_bluesky_feed_sources(
-    service_url: &str,
-    access_jwt: &str,
+fn spawn_cached_content_item_embedding_backfill(
+    workspace_dir: PathBuf,
     embedder: Arc<dyn memory::embeddings::EmbeddingProvider>,
-    interests: &[ActiveInterest],
-) -> Result<Vec<BlueskyCandidateSource>> {
-    let mut generators = Vec::new();
-    let mut seen_uris = HashSet::new();
-    let mut cursor: Option<String> = None;
-
-    for _ in 0..BLUESKY_FEED_GENERATOR_DISCOVERY_PAGE_LIMIT {
-        let (page, next_cursor) = fetch_bluesky_feed_generator_page(
-            service_url,
-            access_jwt,
-            cursor.as_deref(),
-            BLUESKY_FEED_GENERATOR_DISCOVERY_PAGE_SIZE,
-        )
-        .await?;
-        if page.is_empty() {
-            break;
+) {
+    {
+        let mut inflight = cached_content_backfill_inflight().lock();
+        if !inflight.insert(workspace_dir.clone()) {
+            return;
         }
+    }
 
-        for generator in page {
-            if seen_uris.insert(generator.uri.clone()) {
-                generators.push(generator);
-            }
+    tokio::spawn(async move {
+        let result = backfill_cached_content_item_embeddings(&workspace_dir, embedder).await;
+        if let Err(err) = result {
+            tracing::warn!("Failed to backfill cached content item embeddings: {err}");
         }
+        cached_content_backfill_inflight()
+            .lock()
+            .remove(&workspace_dir);
+    });
+}
 
-        let Some(next_cursor) = next_cursor.filter(|value| !value.trim().is_empty()) else {
-            break;
-        };
-        cursor = Some(next_cursor);
+fn content_preview_timestamp(item: &local_store::ContentItemRecord) -> String {
+    non_empty_string(item.published_at.clone())
+        .or_else(|| non_empty_string(item.discovered_at.clone()))
+        .or_else(|| non_empty_string(item.updated_at.clone()))
+        .unwrap_or_default()
+}
+
+fn build_content_preview(item: &local_store::ContentItemRecord) -> WebFeedPreview {
+    let description = if !item.summary.trim().is_empty() {
+        item.summary.trim().to_string()
+    } else {
+        truncate_with_ellipsis(item.content_text.trim(), 220)
+    };
+    WebFeedPreview {
+        url: item.canonical_url.clone(),
+        title: if item.title.trim().is_empty() {
+            item.canonical_url.clone()
+        } else {
+            item.title.clone()
+        },
+        description,
+        image_url: None,
+        domain: item.domain.clone(),
+        provider: "RSS/Atom".to_string(),
+        provider_snippet: non_empty_string(item.source_title.clone()),
+        discovered_at: content_preview_timestamp(item),
     }
+}
+
+fn build_recent_content_items(
+    workspace_dir: &StdPath,
+    limit: usize,
+) -> Result<Vec<PersonalizedBlueskyItem>> {
+    let items = local_store::list_recent_content_items(workspace_dir, limit)?;
+    Ok(items
+        .into_iter()
+        .filter(|item| !item.canonical_url.trim().is_empty())
+        .map(|item| {
+            let preview = build_content_preview(&item);
+            PersonalizedBlueskyItem {
+                source_type: "web".to_string(),
+                feed_item: serde_json::json!({
+                    "url": item.canonical_url,
+                    "title": item.title,
+                    "description": item.summary,
+                    "domain": item.domain,
+                    "author": item.author,
+                    "sourceTitle": item.source_title,
+                    "publishedAt": item.published_at,
+                }),
+                web_preview: Some(preview),
+                feed_source: None,
+                score: None,
+                matched_interest_label: None,
+                matched_interest_score: None,
+                passed_threshold: false,
+            }
+        })
+        .collect())
+}
 
+fn rank_cached_content_items(
+    workspace_dir: &StdPath,
+    interests: &[ActiveInterest],
+    limit: usize,
+) -> Result<Vec<PersonalizedBlueskyItem>> {
     let mut ranked = Vec::new();
-    for generator in generators {
-        let search_text = bluesky_feed_generator_search_text(&generator);
-        if search_text.trim().is_empty() {
+    for item in local_store::list_recent_content_items(workspace_dir, CONTENT_RANK_CANDIDATE_LIMIT)? {
+        let embedding = bytes_to_vec(&item.embedding);
+        if embedding.is_empty() {
             continue;
         }
-
-        let embedding = embedder
-            .embed_one(&search_text)
-            .await
-            .with_context(|| format!("Failed to embed Bluesky feed {}", generator.uri))?;
         let (best_weighted, best_similarity, best_label) =
             best_interest_match(&embedding, interests);
-        if best_weighted < BLUESKY_FEED_SOURCE_MATCH_THRESHOLD {
+        if best_weighted < FEED_MATCH_THRESHOLD {
             continue;
         }
-        let label = bluesky_feed_generator_label(&generator);
-        let description = non_empty_string(generator.description.clone());
-
-        ranked.push((
-            best_weighted,
-            BlueskyCandidateSource::feed_generator(
-                generator.uri,
-                label.clone(),
-                Some(BlueskyFeedSourceContext {
-                    label,
-                    description,
-                    matched_interest_label: best_label,
-                    matched_interest_score: if best_similarity > 0.0 {
-                        Some(best_similarity)
-                    } else {
-                        None
-                    },
-                }),
-            ),
-        ));
+        let preview = build_content_preview(&item);
+        ranked.push(PersonalizedBlueskyItem {
+            source_type: "web".to_string(),
+            feed_item: serde_json::json!({
+                "url": item.canonical_url,
+                "title": item.title,
+                "description": item.summary,
+                "domain": item.domain,
+                "author": item.author,
+                "sourceTitle": item.source_title,
+                "publishedAt": item.published_at,
+            }),
+            web_preview: Some(preview),
+            feed_source: None,
+            score: Some(best_weighted),
+            matched_interest_label: best_label,
+            matched_interest_score: if best_similarity > 0.0 {
+                Some(best_similarity)
+            } else {
+                None
+            },
+            passed_threshold: true,
+        });
     }
-
-    ranked.sort_by(|left, right| {
-        right
-            .0
-            .partial_cmp(&left.0)
-            .unwrap_or(std::cmp::Ordering::Equal)
-    });
-
-    Ok(ranked
-        .into_iter()
-        .take(BLUESKY_FEED_GENERATOR_MATCH_LIMIT)
-        .map(|(_, source)| source)
-        .collect())
+    sort_personalized_items(&mut ranked);
+    ranked.truncate(limit);
+    Ok(ranked)
 }
 
-async fn fetch_bluesky_candidate_page(
-    service_url: &str,
-    access_jwt: &str,
-    source: &BlueskyCandidateSource,
-    cursor: Option<&str>,
+fn append_feed_items_up_to_limit(
+    target: &mut Vec<PersonalizedBlueskyItem>,
+    mut extra: Vec<PersonalizedBlueskyItem>,
     limit: usize,
-) -> Result<(Vec<CandidateFeedPost>, Option<String>)> {
-    let url = build_bluesky_feed_endpoint(service_url, source, cursor, limit);
-    let response = reqwest::Client::new()
+) {
+    if target.len() >= limit {
+        return;
+    }
+    let remaining = limit - target.len();
+    extra.truncate(remaining);
+    target.extend(extra);
+}
+
+async fn fetch_web_preview_html(url: &str) -> Result<String> {
+    let response = crate::http_client::shared()
         .get(url)
-        .bearer_auth(access_jwt.trim())
+        .timeout(Duration::from_secs(12))
+        .header(reqwest::header::USER_AGENT, "SlowClawFeedPreview/1.0")
         .send()
         .await
-        .with_context(|| format!("Failed to fetch Bluesky {} feed", source.request_label()))?;
+        .with_context(|| format!("Failed to fetch preview URL {url}"))?;
 
     if !response.status().is_success() {
-        let status = response.status();
-        let body = response.text().await.unwrap_or_default();
-        anyhow::bail!(
-            "Bluesky {} feed request failed ({status}): {body}",
-            source.request_label()
-        );
+        anyhow::bail!("Preview fetch failed for {url} ({})", response.status());
     }
 
-    let json: serde_json::Value = response
-        .json()
+    response
+        .text()
         .await
-        .with_context(|| {
-            format!(
-                "Failed to decode Bluesky {} feed response",
-                source.request_label()
-            )
-        })?;
-    let feed = json
-        .get("feed")
-        .and_then(serde_json::Value::as_array)
-        .cloned()
-        .unwrap_or_default();
-    let next_cursor = json
-        .get("cursor")
-        .and_then(serde_json::Value::as_str)
-        .map(ToOwned::to_owned);
-
-    Ok((
-        feed.into_iter()
-            .map(|feed_item| CandidateFeedPost {
-                text: extract_bluesky_post_text(&feed_item),
-                feed_item,
-                feed_source: source.feed_source.clone(),
-            })
-            .collect(),
-        next_cursor,
-    ))
+        .with_context(|| format!("Failed to read preview body {url}"))
 }
 
-async fn fetch_bluesky_fallback_candidates(
-    service_url: &str,
-    access_jwt: &str,
-    limit: usize,
-) -> Result<Vec<CandidateFeedPost>> {
-    let mut seen = BTreeSet::new();
-    let mut all_candidates = Vec::new();
-    for source in default_bluesky_candidate_sources() {
-        let (page, _) = fetch_bluesky_candidate_page(
-            service_url,
-            access_jwt,
-            &source,
-            None,
-            limit.min(BLUESKY_PERSONALIZED_PAGE_SIZE),
-        )
-        .await?;
-        let unique_page = dedupe_candidate_posts(page, &mut seen);
-        all_candidates.extend(unique_page);
-        if all_candidates.len() >= limit {
-            break;
-        }
+fn preview_from_html(candidate: &CandidateWebResult, html: &str) -> WebFeedPreview {
+    let title = first_meta_capture(
+        html,
+        &[
+            r#"(?is)<meta[^>]+property=["']og:title["'][^>]+content=["']([^"']+)["']"#,
+            r#"(?is)<meta[^>]+name=["']twitter:title["'][^>]+content=["']([^"']+)["']"#,
+            r#"(?is)<title[^>]*>\s*([^<]+?)\s*</title>"#,
+        ],
+    )
+    .unwrap_or_else(|| candidate.title.clone());
+    let description = first_meta_capture(
+        html,
+        &[
+            r#"(?is)<meta[^>]+property=["']og:description["'][^>]+content=["']([^"']+)["']"#,
+            r#"(?is)<meta[^>]+name=["']description["'][^>]+content=["']([^"']+)["']"#,
+            r#"(?is)<meta[^>]+name=["']twitter:description["'][^>]+content=["']([^"']+)["']"#,
+        ],
+    )
+    .unwrap_or_else(|| candidate.description.clone());
+    let image_url = first_meta_capture(
+        html,
+        &[
+            r#"(?is)<meta[^>]+property=["']og:image["'][^>]+content=["']([^"']+)["']"#,
+            r#"(?is)<meta[^>]+name=["']twitter:image["'][^>]+content=["']([^"']+)["']"#,
+        ],
+    );
+
+    WebFeedPreview {
+        url: candidate.url.clone(),
+        title,
+        description,
+        image_url,
+        domain: candidate.domain.clone(),
+        provider: candidate.provider.clone(),
+        provider_snippet: (!candidate.description.trim().is_empty())
+            .then(|| candidate.description.clone()),
+        discovered_at: Utc::now().to_rfc3339(),
     }
-    Ok(all_candidates)
 }
 
-fn normalize_feed_web_domain(raw: &str) -> String {
-    raw.trim()
-        .trim_start_matches("www.")
-        .trim_end_matches('.')
-        .to_ascii_lowercase()
-}
+async fn resolve_web_preview(
+    workspace_dir: &StdPath,
+    candidate: &CandidateWebResult,
+) -> WebFeedPreview {
+    if let Ok(Some(cached)) = local_store::get_feed_web_cache(workspace_dir, &candidate.url) {
+        if web_preview_cache_is_fresh(&cached.updated_at) {
+            return WebFeedPreview {
+                url: cached.url,
+                title: if cached.title.trim().is_empty() {
+                    candidate.title.clone()
+                } else {
+                    cached.title
+                },
+                description: if cached.description.trim().is_empty() {
+                    candidate.description.clone()
+                } else {
+                    cached.description
+                },
+                image_url: non_empty_string(cached.image_url),
+                domain: if cached.domain.trim().is_empty() {
+                    candidate.domain.clone()
+                } else {
+                    cached.domain
+                },
+                provider: if cached.provider.trim().is_empty() {
+                    candidate.provider.clone()
+                } else {
+                    cached.provider
+                },
+                provider_snippet: non_empty_string(cached.snippet),
+                discovered_at: if cached.fetched_at.trim().is_empty() {
+                    Utc::now().to_rfc3339()
+                } else {
+                    cached.fetched_at
+                },
+            };
+        }
+    }
 
-fn resolve_feed_web_domain(url: &str) -> Option<String> {
-    let parsed = reqwest::Url::parse(url).ok()?;
-    let host = parsed.host_str()?;
-    Some(normalize_feed_web_domain(host))
-}
+    let preview = match fetch_web_preview_html(&candidate.url).await {
+        Ok(html) => preview_from_html(candidate, &html),
+        Err(err) => {
+            tracing::debug!(url = %candidate.url, error = %err, "Feed preview fetch failed");
+            WebFeedPreview {
+                url: candidate.url.clone(),
+                title: candidate.title.clone(),
+                description: candidate.description.clone(),
+                image_url: None,
+                domain: candidate.domain.clone(),
+                provider: candidate.provider.clone(),
+                provider_snippet: (!candidate.description.trim().is_empty())
+                    .then(|| candidate.description.clone()),
+                discovered_at: Utc::now().to_rfc3339(),
+            }
+        }
+    };
 
-fn is_allowed_feed_web_domain(host: &str, allowed: &BTreeSet<String>) -> bool {
-    let normalized = normalize_feed_web_domain(host);
-    allowed.iter().any(|domain| {
-        normalized == *domain || normalized.ends_with(&format!(".{domain}"))
-    })
-}
+    let _ = local_store::upsert_feed_web_cache(
+        workspace_dir,
+        &local_store::FeedWebCacheUpsert {
+            url: preview.url.clone(),
+            domain: preview.domain.clone(),
+            title: preview.title.clone(),
+            description: preview.description.clone(),
+            image_url: preview.image_url.clone().unwrap_or_default(),
+            provider: preview.provider.clone(),
+            snippet: preview.provider_snippet.clone().unwrap_or_default(),
+            search_query: candidate.search_query.clone(),
+            fetched_at: preview.discovered_at.clone(),
+        },
+    );
 
-fn seed_default_feed_web_sources(workspace_dir: &StdPath) -> Result<()> {
-    for source in DEFAULT_FEED_WEB_SOURCES {
-        let _ = local_store::upsert_feed_web_source(
-            workspace_dir,
-            &local_store::FeedWebSourceUpsert {
-                domain: source.domain.to_string(),
-                title: source.title.to_string(),
-                html_url: source.html_url.to_string(),
-                xml_url: source.xml_url.to_string(),
-                description: String::new(),
-                topics_csv: String::new(),
-                metadata_embedding: Vec::new(),
-                enabled: true,
-                source_kind: FEED_WEB_SOURCE_KIND.to_string(),
-            },
-        )?;
-    }
-    Ok(())
+    preview
 }
 
-fn build_feed_web_queries(
-    interests: &[ActiveInterest],
-    sources: &[local_store::FeedWebSourceRecord],
-) -> Vec<String> {
-    let top_interests: Vec<&ActiveInterest> = interests
-        .iter()
-        .filter(|interest| !interest.record.label.trim().is_empty())
-        .collect();
-    let mut top_interests = top_interests;
-    top_interests.sort_by(|left, right| {
-        right
-            .record
-            .health_score
-            .partial_cmp(&left.record.health_score)
-            .unwrap_or(std::cmp::Ordering::Equal)
-    });
-    top_interests.truncate(FEED_WEB_INTEREST_QUERY_COUNT);
-
-    let domains: Vec<String> = sources
-        .iter()
-        .map(|source| normalize_feed_web_domain(&source.domain))
-        .filter(|domain| !domain.is_empty())
-        .collect();
-    if domains.is_empty() {
-        return Vec::new();
+async fn select_feed_embedder(
+    config: &Config,
+) -> Result<Option<Arc<dyn memory::embeddings::EmbeddingProvider>>> {
+    let configured = memory::create_embedder_from_config(config);
+    if configured.dimensions() == 0 {
+        return Ok(None);
     }
-
-    let batches: Vec<&[String]> = domains.chunks(FEED_WEB_DOMAIN_BATCH_SIZE).collect();
-    let mut queries = Vec::new();
-    for (interest_index, interest) in top_interests.iter().enumerate() {
-        for batch_offset in 0..FEED_WEB_DOMAIN_BATCHES_PER_INTEREST {
-            let batch_index = (interest_index * FEED_WEB_DOMAIN_BATCHES_PER_INTEREST + batch_offset)
-                % batches.len();
-            let batch = batches[batch_index];
-            let site_filters = batch
-                .iter()
-                .map(|domain| format!("site:{domain}"))
-                .collect::<Vec<_>>()
-                .join(" OR ");
-            queries.push(format!("{} ({site_filters})", interest.record.label));
+
+    match configured.embed_one("feed profile probe").await {
+        Ok(embedding) if !embedding.is_empty() => Ok(Some(configured)),
+        Ok(_) => {
+            tracing::debug!(
+                provider = config.memory.embedding_provider.trim(),
+                model = config.memory.embedding_model.trim(),
+                dimensions = configured.dimensions(),
+                "Configured feed embedder returned an empty probe vector"
+            );
+            Ok(None)
+        }
+        Err(err) => {
+            tracing::debug!(
+                provider = config.memory.embedding_provider.trim(),
+                model = config.memory.embedding_model.trim(),
+                dimensions = configured.dimensions(),
+                error = %err,
+                "Configured feed embedder probe failed"
+            );
+            Ok(None)
         }
     }
-    queries
 }
 
-fn build_feed_web_search_tool(config: &Config) -> Option<WebSearchTool> {
-    if !config.web_search.enabled {
-        return None;
+async fn resolve_feed_embedder(
+    config: &Config,
+) -> Result<(
+    Option<Arc<dyn memory::embeddings::EmbeddingProvider>>,
+    Option<String>,
+)> {
+    if config.memory.embedding_provider.trim().eq_ignore_ascii_case("none") {
+        return Ok((
+            None,
+            Some(
+                "Personalized feed embeddings are disabled in [memory]. Showing recent cached content and raw Bluesky items when available.".to_string(),
+            ),
+        ));
     }
 
-    Some(WebSearchTool::new(
-        config.web_search.provider.clone(),
-        config.web_search.brave_api_key.clone(),
-        FEED_WEB_RESULT_LIMIT_PER_QUERY.min(config.web_search.max_results),
-        config.web_search.timeout_secs,
+    if let Some(embedder) = select_feed_embedder(config).await? {
+        return Ok((Some(embedder), None));
+    }
+
+    Ok((
+        None,
+        Some(
+            "Configured embedding provider is unavailable. Showing recent cached content and raw Bluesky items when available.".to_string(),
+        ),
     ))
 }
 
-async fn collect_web_search_candidates(
+async fn rebuild_interest_profile(
     config: &Config,
-    interests: &[ActiveInterest],
-) -> Result<Vec<CandidateWebResult>> {
-    let Some(tool) = build_feed_web_search_tool(config) else {
-        return Ok(Vec::new());
-    };
+    embedder: Arc<dyn memory::embeddings::EmbeddingProvider>,
+) -> Result<RebuildInterestProfileResult> {
+    if embedder.dimensions() == 0 {
+        return Ok(RebuildInterestProfileResult {
+            status: "embeddingUnavailable",
+            ..RebuildInterestProfileResult::default()
+        });
+    }
 
     let workspace_dir = &config.workspace_dir;
-    seed_default_feed_web_sources(workspace_dir)?;
-    let sources = local_store::list_feed_web_sources(workspace_dir)?;
-    if sources.is_empty() {
-        return Ok(Vec::new());
-    }
+    let _ = local_store::decay_feed_interests(workspace_dir, INTEREST_DECAY_RATE)?;
+    let mut active_interests: Vec<ActiveInterest> = local_store::list_feed_interests(workspace_dir)?
+        .into_iter()
+        .map(|record| ActiveInterest {
+            embedding: bytes_to_vec(&record.embedding),
+            record,
+        })
+        .filter(|interest| !interest.embedding.is_empty())
+        .collect();
 
-    let allowed_domains: BTreeSet<String> = sources
-        .iter()
-        .map(|source| normalize_feed_web_domain(&source.domain))
+    let items = list_workspace_library_items(workspace_dir, "feed", 2_000, None, "")?;
+    let text_items: Vec<serde_json::Value> = items
+        .into_iter()
+        .filter(|item| item.get("kind").and_then(serde_json::Value::as_str) == Some("text"))
         .collect();
-    let mut candidates = Vec::new();
-    let mut seen_urls = BTreeSet::new();
 
-    for query in build_feed_web_queries(interests, &sources) {
-        let results = match tool.search_structured(&query).await {
-            Ok(results) => results,
-            Err(err) => {
-                tracing::debug!(query, error = %err, "Feed web search query failed");
-                continue;
-            }
+    let mut stats = InterestProfileStats {
+        source_count: text_items.len(),
+        ..InterestProfileStats::default()
+    };
+
+    for item in text_items {
+        let Some(path) = item.get("path").and_then(serde_json::Value::as_str) else {
+            continue;
         };
-        for result in results {
-            let Some(domain) = resolve_feed_web_domain(&result.url) else {
-                continue;
-            };
-            if !is_allowed_feed_web_domain(&domain, &allowed_domains) {
+        let abs_path = workspace_dir.join(path);
+        let content = match tokio::fs::read_to_string(&abs_path).await {
+            Ok(content) => content,
+            Err(_) => continue,
+        };
+        let trimmed = content.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        let content_hash = content_hash_16(trimmed);
+        if let Some(previous) = local_store::get_feed_interest_source(workspace_dir, path)? {
+            if previous.content_hash == content_hash {
                 continue;
             }
-            if !seen_urls.insert(result.url.clone()) {
-                continue;
+        }
+
+        let label = derive_interest_label(
+            item.get("title")
+                .and_then(serde_json::Value::as_str)
+                .unwrap_or("Workspace interest"),
+            trimmed,
+        );
+        let embedding = embedder
+            .embed_one(trimmed)
+            .await
+            .with_context(|| format!("Failed to embed feed source {path}"))?;
+
+        let mut best_match: Option<(usize, f32)> = None;
+        for (index, interest) in active_interests.iter().enumerate() {
+            let similarity = cosine_similarity(&embedding, &interest.embedding);
+            if best_match
+                .as_ref()
+                .map(|(_, current_best)| similarity > *current_best)
+                .unwrap_or(true)
+            {
+                best_match = Some((index, similarity));
             }
-            candidates.push(CandidateWebResult {
-                url: result.url,
-                title: result.title,
-                description: result.description,
-                domain,
-                provider: result.provider,
-                search_query: query.clone(),
-            });
         }
-    }
 
-    Ok(candidates)
-}
+        let now = Utc::now().to_rfc3339();
+        let mapped_interest_id = if let Some((index, similarity)) = best_match {
+            if similarity >= INTEREST_MERGE_THRESHOLD {
+                let current = active_interests[index].clone();
+                let merged_embedding = ema_merge_vectors(&embedding, &current.embedding);
+                let next_label = if current.record.label.trim().is_empty() {
+                    label.clone()
+                } else {
+                    current.record.label.clone()
+                };
+                let updated = local_store::upsert_feed_interest(
+                    workspace_dir,
+                    &local_store::FeedInterestUpsert {
+                        id: Some(current.record.id.clone()),
+                        label: next_label,
+                        source_path: path.to_string(),
+                        embedding: vec_to_bytes(&merged_embedding),
+                        health_score: 1.0,
+                        last_seen_at: now.clone(),
+                    },
+                )?;
+                active_interests[index] = ActiveInterest {
+                    embedding: merged_embedding,
+                    record: updated.clone(),
+                };
+                stats.refreshed_sources += 1;
+                stats.merged_count += 1;
+                Some(updated.id)
+            } else if similarity >= INTEREST_SPAWN_THRESHOLD {
+                let created = local_store::upsert_feed_interest(
+                    workspace_dir,
+                    &local_store::FeedInterestUpsert {
+                        id: None,
+                        label: label.clone(),
+                        source_path: path.to_string(),
+                        embedding: vec_to_bytes(&embedding),
+                        health_score: 1.0,
+                        last_seen_at: now.clone(),
+                    },
+                )?;
+                active_interests.push(ActiveInterest {
+                    embedding,
+                    record: created.clone(),
+                });
+                stats.refreshed_sources += 1;
+                stats.spawned_count += 1;
+                Some(created.id)
+            } else {
+                stats.ignored_count += 1;
+                None
+            }
+        } else {
+            let created = local_store::upsert_feed_interest(
+                workspace_dir,
+                &local_store::FeedInterestUpsert {
+                    id: None,
+                    label: label.clone(),
+                    source_path: path.to_string(),
+                    embedding: vec_to_bytes(&embedding),
+                    health_score: 1.0,
+                    last_seen_at: now.clone(),
+                },
+            )?;
+            active_interests.push(ActiveInterest {
+                embedding,
+                record: created.clone(),
+            });
+            stats.refreshed_sources += 1;
+            stats.spawned_count += 1;
+            Some(created.id)
+        };
 
-fn web_preview_cache_is_fresh(updated_at: &str) -> bool {
-    chrono::DateTime::parse_from_rfc3339(updated_at)
-        .ok()
-        .map(|value| Utc::now().signed_duration_since(value.with_timezone(&Utc)).num_seconds())
-        .map(|age| age >= 0 && age <= FEED_WEB_PREVIEW_CACHE_TTL_SECS)
-        .unwrap_or(false)
+        local_store::upsert_feed_interest_source(
+            workspace_dir,
+            &local_store::FeedInterestSourceRecord {
+                source_path: path.to_string(),
+                content_hash,
+                profile_input_hash: String::new(),
+                interest_id: mapped_interest_id,
+                title: label,
+                triage_keywords_json: String::new(),
+                updated_at: now,
+            },
+        )?;
+    }
+
+    stats.interest_count = active_interests.len();
+    Ok(RebuildInterestProfileResult {
+        status: if active_interests.is_empty() {
+            "noInterests"
+        } else {
+            "ready"
+        },
+        stats,
+        interests: active_interests,
+    })
 }
 
-fn first_meta_capture(html: &str, patterns: &[&str]) -> Option<String> {
-    for pattern in patterns {
-        let regex = Regex::new(pattern).ok()?;
-        if let Some(capture) = regex.captures(html) {
-            if let Some(value) = capture.get(1) {
-                let trimmed = html_unescape_basic(value.as_str().trim());
-                if !trimmed.is_empty() {
-                    return Some(trimmed);
-                }
-            }
+fn best_interest_match(
+    embedding: &[f32],
+    interests: &[ActiveInterest],
+) -> (f32, f32, Option<String>) {
+    let mut best_weighted = 0.0_f32;
+    let mut best_similarity = 0.0_f32;
+    let mut best_label: Option<String> = None;
+    for interest in interests {
+        let similarity = cosine_similarity(embedding, &interest.embedding);
+        let weighted = similarity * interest.record.health_score as f32;
+        if weighted > best_weighted {
+            best_weighted = weighted;
+            best_similarity = similarity;
+            best_label = Some(interest.record.label.clone());
         }
     }
-    None
+    (best_weighted, best_similarity, best_label)
 }
 
-fn html_unescape_basic(raw: &str) -> String {
-    raw.replace("&amp;", "&")
-        .replace("&quot;", "\"")
-        .replace("&#39;", "'")
-        .replace("&lt;", "<")
-        .replace("&gt;", ">")
-        .replace("&nbsp;", " ")
-}
+async fn rank_bluesky_candidates(
+    embedder: Arc<dyn memory::embeddings::EmbeddingProvider>,
+    interests: &[ActiveInterest],
+    candidates: Vec<CandidateFeedPost>,
+) -> Result<Vec<PersonalizedBlueskyItem>> {
+    let mut ranked = Vec::new();
+    for candidate in candidates {
+        let feed_source = candidate.feed_source.clone();
+        let trimmed = candidate.text.trim();
+        if trimmed.is_empty() {
+            ranked.push(PersonalizedBlueskyItem {
+                source_type: "bluesky".to_string(),
+                feed_item: candidate.feed_item,
+                web_preview: None,
+                feed_source,
+                score: None,
+                matched_interest_label: None,
+                matched_interest_score: None,
+                passed_threshold: false,
+            });
+            continue;
+        }
 
-fn xml_block_regex(tag: &str) -> Regex {
-    Regex::new(&format!(r"(?is)<{tag}\b[^>]*>(.*?)</{tag}>", tag = regex::escape(tag)))
-        .expect("valid XML block regex")
-}
+        let embedding = embedder
+            .embed_one(trimmed)
+            .await
+            .context("Failed to embed Bluesky candidate post")?;
 
-fn xml_tag_regex(tag: &str) -> Regex {
-    Regex::new(&format!(r"(?is)<{tag}\b[^>]*>(.*?)</{tag}>", tag = regex::escape(tag)))
-        .expect("valid XML tag regex")
-}
+        let (best_weighted, best_similarity, best_label) =
+            best_interest_match(&embedding, interests);
 
-fn xml_link_regex() -> &'static Regex {
-    static REGEX: OnceLock<Regex> = OnceLock::new();
-    REGEX.get_or_init(|| Regex::new(r#"(?is)<link\b([^>]*)>"#).expect("valid XML link regex"))
-}
+        ranked.push(PersonalizedBlueskyItem {
+            source_type: "bluesky".to_string(),
+            feed_item: candidate.feed_item,
+            web_preview: None,
+            feed_source,
+            score: Some(best_weighted),
+            matched_interest_label: best_label,
+            matched_interest_score: if best_similarity > 0.0 {
+                Some(best_similarity)
+            } else {
+                None
+            },
+            passed_threshold: best_weighted >= FEED_MATCH_THRESHOLD,
+        });
+    }
 
-fn xml_href_attr_regex() -> &'static Regex {
-    static REGEX: OnceLock<Regex> = OnceLock::new();
-    REGEX.get_or_init(|| {
-        Regex::new(r#"(?is)\bhref\s*=\s*["']([^"']+)["']"#).expect("valid href regex")
-    })
+    let mut filtered: Vec<PersonalizedBlueskyItem> =
+        ranked.into_iter().filter(|item| item.passed_threshold).collect();
+    sort_personalized_items(&mut filtered);
+    Ok(filtered)
 }
 
-fn xml_rel_attr_regex() -> &'static Regex {
-    static REGEX: OnceLock<Regex> = OnceLock::new();
-    REGEX.get_or_init(|| {
-        Regex::new(r#"(?is)\brel\s*=\s*["']([^"']+)["']"#).expect("valid rel regex")
-    })
-}
+async fn rank_web_candidates(
+    workspace_dir: &StdPath,
+    embedder: Arc<dyn memory::embeddings::EmbeddingProvider>,
+    interests: &[ActiveInterest],
+    candidates: Vec<CandidateWebResult>,
+    limit: usize,
+) -> Result<Vec<PersonalizedBlueskyItem>> {
+    let mut ranked = Vec::new();
+    for candidate in candidates {
+        let combined = format!("{}\n{}", candidate.title.trim(), candidate.description.trim());
+        let trimmed = combined.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
 
-fn html_tag_regex() -> &'static Regex {
-    static REGEX: OnceLock<Regex> = OnceLock::new();
-    REGEX.get_or_init(|| Regex::new(r"(?is)<[^>]+>").expect("valid HTML tag regex"))
-}
+        let embedding = embedder
+            .embed_one(trimmed)
+            .await
+            .with_context(|| format!("Failed to embed web candidate {}", candidate.url))?;
+        let (best_weighted, best_similarity, best_label) =
+            best_interest_match(&embedding, interests);
+        if best_weighted < FEED_MATCH_THRESHOLD {
+            continue;
+        }
 
-fn html_break_regex() -> &'static Regex {
-    static REGEX: OnceLock<Regex> = OnceLock::new();
-    REGEX.get_or_init(|| Regex::new(r"(?is)<br\s*/?>").expect("valid break regex"))
-}
+        let preview = resolve_web_preview(workspace_dir, &candidate).await;
+        ranked.push(PersonalizedBlueskyItem {
+            source_type: "web".to_string(),
+            feed_item: serde_json::json!({
+                "url": candidate.url,
+                "title": candidate.title,
+                "description": candidate.description,
+                "domain": candidate.domain,
+            }),
+            web_preview: Some(preview),
+            feed_source: None,
+            score: Some(best_weighted),
+            matched_interest_label: best_label,
+            matched_interest_score: if best_similarity > 0.0 {
+                Some(best_similarity)
+            } else {
+                None
+            },
+            passed_threshold: true,
+        });
+    }
 
-fn html_paragraph_regex() -> &'static Regex {
-    static REGEX: OnceLock<Regex> = OnceLock::new();
-    REGEX.get_or_init(|| Regex::new(r"(?is)</p\s*>").expect("valid paragraph regex"))
+    sort_personalized_items(&mut ranked);
+    ranked.truncate(limit);
+    Ok(ranked)
 }
 
-fn collapse_whitespace(raw: &str) -> String {
-    raw.split_whitespace().collect::<Vec<_>>().join(" ")
-}
+async fn collect_ranked_bluesky_matches(
+    service_url: &str,
+    access_jwt: &str,
+    embedder: Arc<dyn memory::embeddings::EmbeddingProvider>,
+    interests: &[ActiveInterest],
+    limit: usize,
+) -> Result<(Vec<PersonalizedBlueskyItem>, Vec<CandidateFeedPost>)> {
+    let mut matched = Vec::new();
+    let mut raw_candidates = Vec::new();
+    let mut seen = BTreeSet::new();
+    let target_matches = limit.min(BLUESKY_PERSONALIZED_MATCH_LIMIT);
+    let mut candidate_sources = match collect_ranked_bluesky_feed_sources(
+        service_url,
+        access_jwt,
+        embedder.clone(),
+        interests,
+    )
+    .await
+    {
+        Ok(sources) => sources,
+        Err(err) => {
+            tracing::warn!("Failed to rank Bluesky feed generators: {err}");
+            Vec::new()
+        }
+    };
+    append_unique_bluesky_sources(&mut candidate_sources, default_bluesky_candidate_sources());
 
-fn sanitize_feed_text(raw: &str) -> String {
-    let without_breaks = html_break_regex().replace_all(raw, "\n");
-    let with_paragraphs = html_paragraph_regex().replace_all(&without_breaks, "\n");
-    let without_tags = html_tag_regex().replace_all(&with_paragraphs, " ");
-    let without_cdata = without_tags
-        .replace("<![CDATA[", "")
-        .replace("]]>", "")
-        .replace("&apos;", "'");
-    collapse_whitespace(&html_unescape_basic(&without_cdata))
-}
+    for source in candidate_sources {
+        let mut cursor: Option<String> = None;
+        for _ in 0..BLUESKY_PERSONALIZED_PAGE_LIMIT_PER_SOURCE {
+            let (page, next_cursor) = fetch_bluesky_candidate_page(
+                service_url,
+                access_jwt,
+                &source,
+                cursor.as_deref(),
+                BLUESKY_PERSONALIZED_PAGE_SIZE,
+            )
+            .await?;
+            if page.is_empty() {
+                break;
+            }
 
-fn extract_xml_tag_text(fragment: &str, tags: &[&str]) -> Option<String> {
-    for tag in tags {
-        let regex = xml_tag_regex(tag);
-        if let Some(capture) = regex.captures(fragment) {
-            if let Some(value) = capture.get(1) {
-                let sanitized = sanitize_feed_text(value.as_str());
-                if !sanitized.is_empty() {
-                    return Some(sanitized);
+            let unique_page = dedupe_candidate_posts(page, &mut seen);
+            if !unique_page.is_empty() {
+                raw_candidates.extend(unique_page.clone());
+                let mut ranked_page =
+                    rank_bluesky_candidates(embedder.clone(), interests, unique_page).await?;
+                matched.append(&mut ranked_page);
+                if matched.len() >= target_matches {
+                    sort_personalized_items(&mut matched);
+                    matched.truncate(target_matches);
+                    return Ok((matched, raw_candidates));
                 }
             }
+
+            let Some(next_cursor) = next_cursor.filter(|value| !value.trim().is_empty()) else {
+                break;
+            };
+            cursor = Some(next_cursor);
         }
     }
-    None
+
+    sort_personalized_items(&mut matched);
+    matched.truncate(target_matches);
+    Ok((matched, raw_candidates))
+}
+
+fn modified_unix_secs(path: &StdPath) -> i64 {
+    path.metadata()
+        .ok()
+        .and_then(|meta| meta.modified().ok())
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| i64::try_from(duration.as_secs()).unwrap_or(0))
+        .unwrap_or(0)
+}
+
+fn sync_file_allowed(path: &str) -> bool {
+    let normalized = path.trim().trim_start_matches('/').replace('\\', "/");
+    if normalized.is_empty() || normalized.contains("..") {
+        return false;
+    }
+    if normalized == "feed_workflow_settings.json" {
+        return true;
+    }
+    SYNC_ALLOWED_ROOTS
+        .iter()
+        .any(|root| normalized == *root || normalized.starts_with(&format!("{root}/")))
 }
 
-fn extract_atom_link(fragment: &str, base_url: &str) -> Option<String> {
-    let mut fallback: Option<String> = None;
-    for capture in xml_link_regex().captures_iter(fragment) {
-        let attrs = capture.get(1).map(|value| value.as_str()).unwrap_or("");
-        let href = xml_href_attr_regex()
-            .captures(attrs)
-            .and_then(|value| value.get(1))
-            .map(|value| value.as_str().trim().to_string());
-        let Some(href) = href.filter(|value| !value.is_empty()) else {
-            continue;
+fn collect_sync_files_recursive(
+    workspace_dir: &StdPath,
+    dir: &StdPath,
+    out: &mut Vec<WorkspaceSyncFile>,
+) -> Result<()> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return Ok(()),
+    };
+
+    for entry in entries {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(_) => continue,
         };
-        let rel = xml_rel_attr_regex()
-            .captures(attrs)
-            .and_then(|value| value.get(1))
-            .map(|value| value.as_str().trim().to_ascii_lowercase());
-        if rel.as_deref() != Some("self") {
-            return Some(absolutize_feed_url(base_url, &href));
+        let path = entry.path();
+        let metadata = match entry.metadata() {
+            Ok(metadata) => metadata,
+            Err(_) => continue,
+        };
+        if metadata.is_dir() {
+            collect_sync_files_recursive(workspace_dir, &path, out)?;
+            continue;
         }
-        if fallback.is_none() {
-            fallback = Some(absolutize_feed_url(base_url, &href));
+        if !metadata.is_file() {
+            continue;
+        }
+        let rel = match path.strip_prefix(workspace_dir) {
+            Ok(rel) => rel.to_string_lossy().replace('\\', "/"),
+            Err(_) => continue,
+        };
+        if !sync_file_allowed(&rel) {
+            continue;
         }
+        let bytes = match std::fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(_) => continue,
+        };
+        out.push(WorkspaceSyncFile {
+            path: rel,
+            modified_at: modified_unix_secs(&path),
+            content_base64: BASE64_STANDARD.encode(bytes),
+        });
     }
-    fallback
+    Ok(())
 }
 
-fn absolutize_feed_url(base_url: &str, raw_url: &str) -> String {
-    let trimmed = raw_url.trim();
-    if trimmed.is_empty() {
-        return String::new();
+fn export_local_store_blob(workspace_dir: &StdPath) -> Result<Option<LocalStoreSyncBlob>> {
+    let db_path = local_store::db_path(workspace_dir);
+    if !db_path.exists() {
+        return Ok(None);
     }
-    if let Ok(parsed) = reqwest::Url::parse(trimmed) {
-        return parsed.to_string();
+
+    {
+        let conn = rusqlite::Connection::open(&db_path)
+            .with_context(|| format!("Failed to open sync export DB {}", db_path.display()))?;
+        let _ = conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE);");
     }
-    reqwest::Url::parse(base_url)
-        .and_then(|base| base.join(trimmed))
-        .map(|url| url.to_string())
-        .unwrap_or_else(|_| trimmed.to_string())
+
+    let bytes = std::fs::read(&db_path)
+        .with_context(|| format!("Failed to read local store DB {}", db_path.display()))?;
+    Ok(Some(LocalStoreSyncBlob {
+        modified_at: modified_unix_secs(&db_path),
+        content_base64: BASE64_STANDARD.encode(bytes),
+    }))
 }
 
-fn normalize_feed_timestamp(raw: &str) -> String {
-    let trimmed = raw.trim();
-    if trimmed.is_empty() {
-        return String::new();
-    }
-    if let Ok(parsed) = chrono::DateTime::parse_from_rfc3339(trimmed) {
-        return parsed.with_timezone(&Utc).to_rfc3339();
+fn export_workspace_sync_snapshot(workspace_dir: &StdPath) -> Result<WorkspaceSyncSnapshot> {
+    let mut files = Vec::new();
+    for root in SYNC_ALLOWED_ROOTS {
+        collect_sync_files_recursive(workspace_dir, &workspace_dir.join(root), &mut files)?;
     }
-    if let Ok(parsed) = chrono::DateTime::parse_from_rfc2822(trimmed) {
-        return parsed.with_timezone(&Utc).to_rfc3339();
+    let workflow_settings = workspace_dir.join("feed_workflow_settings.json");
+    if workflow_settings.exists() && workflow_settings.is_file() {
+        let bytes = std::fs::read(&workflow_settings)
+            .with_context(|| format!("Failed to read {}", workflow_settings.display()))?;
+        files.push(WorkspaceSyncFile {
+            path: "feed_workflow_settings.json".to_string(),
+            modified_at: modified_unix_secs(&workflow_settings),
+            content_base64: BASE64_STANDARD.encode(bytes),
+        });
     }
-    trimmed.to_string()
+    files.sort_by(|left, right| left.path.cmp(&right.path));
+    Ok(WorkspaceSyncSnapshot {
+        exported_at: Utc::now().timestamp(),
+        files,
+        local_store: export_local_store_blob(workspace_dir)?,
+    })
 }
 
-fn parse_rss_feed_entries(xml: &str, base_url: &str) -> Vec<ParsedFeedEntry> {
-    let mut items = Vec::new();
-    for capture in xml_block_regex("item").captures_iter(xml) {
-        let fragment = capture.get(1).map(|value| value.as_str()).unwrap_or("");
-        let title = extract_xml_tag_text(fragment, &["title"]).unwrap_or_default();
-        let canonical_url = extract_xml_tag_text(fragment, &["link"])
-            .map(|value| absolutize_feed_url(base_url, &value))
-            .filter(|value| !value.is_empty())
-            .or_else(|| {
-                extract_xml_tag_text(fragment, &["guid"])
-                    .map(|value| absolutize_feed_url(base_url, &value))
-                    .filter(|value| !value.is_empty())
-            })
-            .unwrap_or_else(|| base_url.to_string());
-        let summary = extract_xml_tag_text(fragment, &["description"]).unwrap_or_default();
-        let content_text =
-            extract_xml_tag_text(fragment, &["content:encoded", "content", "description"])
-                .unwrap_or_else(|| summary.clone());
-        let author = extract_xml_tag_text(fragment, &["author", "dc:creator"]).unwrap_or_default();
-        let published_at = extract_xml_tag_text(fragment, &["pubDate", "published", "updated"])
-            .map(|value| normalize_feed_timestamp(&value))
-            .unwrap_or_default();
-        let external_id = extract_xml_tag_text(fragment, &["guid"])
-            .or_else(|| non_empty_string(canonical_url.clone()))
-            .unwrap_or_default();
-        if title.is_empty() && content_text.is_empty() {
+fn import_workspace_sync_snapshot(
+    workspace_dir: &StdPath,
+    snapshot: &WorkspaceSyncSnapshot,
+) -> Result<(usize, bool)> {
+    let mut imported_files = 0_usize;
+    for file in &snapshot.files {
+        if !sync_file_allowed(&file.path) {
             continue;
         }
-        items.push(ParsedFeedEntry {
-            external_id,
-            canonical_url,
-            title,
-            author,
-            summary,
-            content_text,
-            published_at,
-        });
+        let abs_path = workspace_dir.join(&file.path);
+        let current_modified = modified_unix_secs(&abs_path);
+        if abs_path.exists() && current_modified > file.modified_at {
+            continue;
+        }
+        let bytes = BASE64_STANDARD
+            .decode(file.content_base64.as_bytes())
+            .with_context(|| format!("Failed to decode sync file {}", file.path))?;
+        if let Some(parent) = abs_path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        std::fs::write(&abs_path, bytes)
+            .with_context(|| format!("Failed to write synced file {}", abs_path.display()))?;
+        imported_files += 1;
     }
-    items
+
+    let mut imported_db = false;
+    if let Some(local_store_blob) = &snapshot.local_store {
+        let db_path = local_store::db_path(workspace_dir);
+        let local_modified = modified_unix_secs(&db_path);
+        if !db_path.exists() || local_modified <= local_store_blob.modified_at {
+            let bytes = BASE64_STANDARD
+                .decode(local_store_blob.content_base64.as_bytes())
+                .context("Failed to decode synced local store")?;
+            if let Some(parent) = db_path.parent() {
+                std::fs::create_dir_all(parent)
+                    .with_context(|| format!("Failed to create {}", parent.display()))?;
+            }
+            let temp_path = db_path.with_extension("db.sync.tmp");
+            std::fs::write(&temp_path, bytes)
+                .with_context(|| format!("Failed to write temp DB {}", temp_path.display()))?;
+            std::fs::rename(&temp_path, &db_path)
+                .with_context(|| format!("Failed to replace DB {}", db_path.display()))?;
+            let wal_path = db_path.with_extension("db-wal");
+            if wal_path.exists() {
+                let _ = std::fs::remove_file(&wal_path);
+            }
+            let shm_path = db_path.with_extension("db-shm");
+            if shm_path.exists() {
+                let _ = std::fs::remove_file(&shm_path);
+            }
+            imported_db = true;
+        }
+    }
+
+    Ok((imported_files, imported_db))
+}
+
+async fn create_journal_entry_metadata(
+    state: &AppState,
+    rel_path: &str,
+    title: &str,
+    content: &str,
+    source: &str,
+    tags: Option<&[String]>,
+) -> Result<serde_json::Value> {
+    let preview = truncate_with_ellipsis(content, 240);
+    let workspace_dir = state.config.lock().workspace_dir.clone();
+    local_store::create_journal_entry_metadata(
+        &workspace_dir,
+        &local_store::JournalEntryInput {
+            title: title.to_string(),
+            entry_type: "text".to_string(),
+            source: source.to_string(),
+            status: "raw".to_string(),
+            workspace_path: rel_path.to_string(),
+            preview_text: preview,
+            text_body: content.to_string(),
+            tags_csv: tags.map(|t| t.join(",")).unwrap_or_default(),
+            created_at_client: Some(chrono::Utc::now().to_rfc3339()),
+        },
+    )
+}
+
+async fn upsert_media_asset_metadata(
+    state: &AppState,
+    rel_path: &str,
+    content_type: &str,
+    kind: &str,
+    title: Option<&str>,
+    source: &str,
+    bytes: u64,
+    entry_id: Option<&str>,
+    probe: Option<crate::media_probe::MediaProbe>,
+    tags: &[String],
+) -> Result<serde_json::Value> {
+    let workspace_dir = state.config.lock().workspace_dir.clone();
+    local_store::create_media_asset_metadata(
+        &workspace_dir,
+        &local_store::MediaAssetInput {
+            title: title.unwrap_or("").to_string(),
+            entry_id: entry_id.unwrap_or("").to_string(),
+            asset_type: kind.to_string(),
+            mime_type: content_type.to_string(),
+            source: source.to_string(),
+            status: "uploaded".to_string(),
+            workspace_path: rel_path.to_string(),
+            size_bytes: bytes as i64,
+            created_at_client: Some(chrono::Utc::now().to_rfc3339()),
+            probe,
+            tags_csv: tags.join(","),
+        },
+    )
 }
 
-fn parse_atom_feed_entries(xml: &str, base_url: &str) -> Vec<ParsedFeedEntry> {
-    let mut items = Vec::new();
-    for capture in xml_block_regex("entry").captures_iter(xml) {
-        let fragment = capture.get(1).map(|value| value.as_str()).unwrap_or("");
-        let title = extract_xml_tag_text(fragment, &["title"]).unwrap_or_default();
-        let canonical_url = extract_atom_link(fragment, base_url).unwrap_or_else(|| base_url.to_string());
-        let summary = extract_xml_tag_text(fragment, &["summary"]).unwrap_or_default();
-        let content_text = extract_xml_tag_text(fragment, &["content", "summary"])
-            .unwrap_or_else(|| summary.clone());
-        let author = xml_block_regex("author")
-            .captures(fragment)
-            .and_then(|value| value.get(1))
-            .and_then(|value| extract_xml_tag_text(value.as_str(), &["name"]))
-            .or_else(|| extract_xml_tag_text(fragment, &["author", "name"]))
-            .unwrap_or_default();
-        let published_at = extract_xml_tag_text(fragment, &["published", "updated"])
-            .map(|value| normalize_feed_timestamp(&value))
-            .unwrap_or_default();
-        let external_id = extract_xml_tag_text(fragment, &["id"])
-            .or_else(|| non_empty_string(canonical_url.clone()))
-            .unwrap_or_default();
-        if title.is_empty() && content_text.is_empty() {
-            continue;
+/// Rejects a webhook call that lacks a paired bearer token or the optional
+/// `X-Webhook-Secret`.
+fn webhook_auth_error(
+    state: &AppState,
+    headers: &HeaderMap,
+) -> Option<(StatusCode, Json<serde_json::Value>)> {
+    // ── Bearer token auth (pairing) ──
+    if state.pairing.require_pairing() {
+        let auth = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("");
+        let token = auth.strip_prefix("Bearer ").unwrap_or("");
+        if !state.pairing.is_authenticated(token) && !api_key_authenticated(state, token) {
+            tracing::warn!("Webhook: rejected — not paired / invalid bearer token");
+            let err = serde_json::json!({
+                "error": "Unauthorized — pair first via POST /pair, then send Authorization: Bearer <token>"
+            });
+            return Some((StatusCode::UNAUTHORIZED, Json(err)));
         }
-        items.push(ParsedFeedEntry {
-            external_id,
-            canonical_url,
-            title,
-            author,
-            summary,
-            content_text,
-            published_at,
-        });
     }
-    items
-}
 
-fn parse_feed_entries(xml: &str, base_url: &str) -> Vec<ParsedFeedEntry> {
-    let trimmed = xml.trim();
-    if trimmed.is_empty() {
-        return Vec::new();
+    // ── Webhook secret auth (optional, additional layer) ──
+    if let Some(ref secret_hash) = state.webhook_secret_hash {
+        let header_hash = headers
+            .get("X-Webhook-Secret")
+            .and_then(|v| v.to_str().ok())
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(hash_webhook_secret);
+        match header_hash {
+            Some(val) if secret_hash.matches(&val) => {}
+            _ => {
+                tracing::warn!("Webhook: rejected request — invalid or missing X-Webhook-Secret");
+                let err = serde_json::json!({"error": "Unauthorized — invalid or missing X-Webhook-Secret header"});
+                return Some((StatusCode::UNAUTHORIZED, Json(err)));
+            }
+        }
     }
-    let mut items = if trimmed.contains("<feed") {
-        parse_atom_feed_entries(trimmed, base_url)
-    } else {
-        parse_rss_feed_entries(trimmed, base_url)
-    };
-    items.retain(|item| !item.canonical_url.trim().is_empty());
-    items
+    None
 }
 
-fn content_source_is_stale(last_fetch_at: &str) -> bool {
-    chrono::DateTime::parse_from_rfc3339(last_fetch_at.trim())
-        .ok()
-        .map(|value| Utc::now().signed_duration_since(value.with_timezone(&Utc)).num_seconds())
-        .map(|age| age < 0 || age > CONTENT_SOURCE_REFRESH_TTL_SECS)
-        .unwrap_or(true)
+fn webhook_rate_limited_response() -> (StatusCode, Json<serde_json::Value>) {
+    let err = serde_json::json!({
+        "error": "Too many webhook requests. Please retry later.",
+        "retry_after": RATE_LIMIT_WINDOW_SECS,
+    });
+    (StatusCode::TOO_MANY_REQUESTS, Json(err))
 }
 
-fn content_item_embedding_text(entry: &ParsedFeedEntry) -> String {
-    let combined = format!(
-        "{}\n{}\n{}",
-        entry.title.trim(),
-        entry.summary.trim(),
-        entry.content_text.trim()
-    );
-    truncate_with_ellipsis(combined.trim(), CONTENT_TEXT_MAX_CHARS)
+/// Why a single webhook message could not be answered.
+enum WebhookMessageError {
+    Invalid(WebhookOverrideError),
+    /// Provider failure, already passed through `sanitize_api_error`.
+    Provider(String),
+    /// Refused by the `[cost]` budget before reaching the provider.
+    Budget(BudgetExceeded),
 }
 
-fn build_content_item_id(source_key: &str, canonical_url: &str, external_id: &str) -> String {
-    format!(
-        "content_{}",
-        content_hash_16(&format!("{source_key}\n{canonical_url}\n{external_id}"))
-    )
+/// Whether this request may run past a spent `[cost]` budget: it sends
+/// `X-Budget-Override: true`, `allow_override` is on, and it carries a paired
+/// bearer token. Paired tokens carry full scope; without pairing there is no
+/// token to check, so the override is never honored.
+fn budget_override_requested(state: &AppState, headers: &HeaderMap) -> bool {
+    let requested = headers
+        .get("X-Budget-Override")
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|value| {
+            let value = value.trim();
+            value == "1" || value.eq_ignore_ascii_case("true")
+        });
+    if !requested || !state.config.lock().cost.allow_override {
+        return false;
+    }
+    if !state.pairing.require_pairing() {
+        tracing::warn!("Budget override ignored: it needs gateway pairing");
+        return false;
+    }
+    let auth = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("");
+    let token = auth.strip_prefix("Bearer ").unwrap_or("");
+    let allowed = state.pairing.is_authenticated(token);
+    if allowed {
+        tracing::warn!("Budget override granted for this request");
+    }
+    allowed
 }
 
-fn sync_content_sources_from_feed_web_sources(workspace_dir: &StdPath) -> Result<()> {
-    seed_default_feed_web_sources(workspace_dir)?;
-    for source in local_store::list_feed_web_sources(workspace_dir)? {
-        let source_key = source.xml_url.trim();
-        if source_key.is_empty() {
-            continue;
+/// Validate, run, and record one webhook message. Shared by `/webhook` and
+/// `/webhook/batch`; auth, rate limiting, and idempotency stay with the caller.
+async fn process_webhook_message(
+    state: &AppState,
+    webhook_body: &WebhookBody,
+) -> Result<serde_json::Value, WebhookMessageError> {
+    let overrides = {
+        let config = state.config.lock();
+        validate_webhook_overrides(webhook_body, &state.model, &config.gateway)
+    }
+    .map_err(WebhookMessageError::Invalid)?;
+
+    let message = &gateway_prompt_with_attachments(
+        state,
+        &webhook_body.message,
+        &webhook_body.attachments,
+    );
+    let turn_memory = ConversationTurnMemory::webhook(overrides.session_id.as_deref());
+
+    if webhook_body.reset {
+        if let Some(session_id) = overrides.session_id.as_deref() {
+            let cleared = clear_webhook_session(state.mem.as_ref(), session_id).await;
+            if message.trim().is_empty() {
+                return Ok(serde_json::json!({
+                    "status": "reset",
+                    "session_id": session_id,
+                    "cleared": cleared,
+                }));
+            }
         }
-        let _ = local_store::upsert_content_source(
-            workspace_dir,
-            &local_store::ContentSourceUpsert {
-                source_key: source_key.to_string(),
-                domain: source.domain.clone(),
-                title: source.title.clone(),
-                html_url: source.html_url.clone(),
-                xml_url: source.xml_url.clone(),
-                source_kind: source.source_kind.clone(),
-                enabled: source.enabled,
-            },
-        )?;
     }
-    Ok(())
-}
 
-struct RemoteFeedFetchResult {
-    entries: Vec<ParsedFeedEntry>,
-    etag: Option<String>,
-    last_modified: Option<String>,
-    not_modified: bool,
-}
+    let history = match overrides.session_id.as_deref() {
+        Some(session_id) => {
+            load_webhook_session_history(state.mem.as_ref(), session_id, overrides.max_history)
+                .await
+        }
+        None => Vec::new(),
+    };
 
-async fn fetch_remote_feed(source: &local_store::ContentSourceRecord) -> Result<RemoteFeedFetchResult> {
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(CONTENT_FETCH_TIMEOUT_SECS))
-        .build()?;
-    let mut request = client.get(source.xml_url.trim());
-    if !source.etag.trim().is_empty() {
-        request = request.header(reqwest::header::IF_NONE_MATCH, source.etag.trim());
-    }
-    if !source.last_modified.trim().is_empty() {
-        request = request.header(
-            reqwest::header::IF_MODIFIED_SINCE,
-            source.last_modified.trim(),
-        );
+    // Sessions always persist their turns; plain calls only when auto_save is on.
+    let persist_turns = state.auto_save || turn_memory.session.is_some();
+    if persist_turns {
+        turn_memory.store_user(state.mem.as_ref(), message).await;
     }
 
-    let response = request
-        .send()
-        .await
-        .with_context(|| format!("Failed to fetch content source {}", source.xml_url))?;
-    let etag = response
-        .headers()
-        .get(reqwest::header::ETAG)
-        .and_then(|value| value.to_str().ok())
-        .map(ToOwned::to_owned);
-    let last_modified = response
-        .headers()
-        .get(reqwest::header::LAST_MODIFIED)
-        .and_then(|value| value.to_str().ok())
-        .map(ToOwned::to_owned);
+    let provider_label = state
+        .config
+        .lock()
+        .default_provider
+        .clone()
+        .unwrap_or_else(|| "unknown".to_string());
+    let model_label = overrides.model(state).to_string();
+    let started_at = Instant::now();
 
-    if response.status() == reqwest::StatusCode::NOT_MODIFIED {
-        return Ok(RemoteFeedFetchResult {
-            entries: Vec::new(),
-            etag,
-            last_modified,
-            not_modified: true,
+    state
+        .observer
+        .record_event(&crate::observability::ObserverEvent::ChannelMessage {
+            channel: "webhook".to_string(),
+            direction: "inbound".to_string(),
         });
-    }
+    state
+        .observer
+        .record_event(&crate::observability::ObserverEvent::AgentStart {
+            provider: provider_label.clone(),
+            model: model_label.clone(),
+        });
+    state
+        .observer
+        .record_event(&crate::observability::ObserverEvent::LlmRequest {
+            provider: provider_label.clone(),
+            model: model_label.clone(),
+            messages_count: history.len() + 1,
+        });
+
+    match run_gateway_chat_simple(state, &history, message, &overrides).await {
+        Ok(response) => {
+            let duration = started_at.elapsed();
+            state
+                .observer
+                .record_event(&crate::observability::ObserverEvent::LlmResponse {
+                    provider: provider_label.clone(),
+                    model: model_label.clone(),
+                    duration,
+                    success: true,
+                    error_message: None,
+                    input_tokens: None,
+                    output_tokens: None,
+                });
+            state.observer.record_metric(
+                &crate::observability::traits::ObserverMetric::RequestLatency(duration),
+            );
+            state
+                .observer
+                .record_event(&crate::observability::ObserverEvent::AgentEnd {
+                    provider: provider_label,
+                    model: model_label.clone(),
+                    duration,
+                    tokens_used: None,
+                    cost_usd: None,
+                });
+
+            if persist_turns {
+                turn_memory.store_reply(state.mem.as_ref(), &response).await;
+            }
+
+            let mut body = serde_json::json!({"response": response, "model": model_label});
+            if let Some(session_id) = overrides.session_id.as_deref() {
+                body["session_id"] = serde_json::json!(session_id);
+            }
+            if webhook_body.include_trace {
+                // Webhook replies come from a plain chat call, so no tools run.
+                body["trace"] = serde_json::json!(crate::agent::AgentTrace::default());
+            }
+            Ok(body)
+        }
+        Err(e) => {
+            let duration = started_at.elapsed();
+            let sanitized = providers::sanitize_api_error(&e.to_string());
+
+            state
+                .observer
+                .record_event(&crate::observability::ObserverEvent::LlmResponse {
+                    provider: provider_label.clone(),
+                    model: model_label.clone(),
+                    duration,
+                    success: false,
+                    error_message: Some(sanitized.clone()),
+                    input_tokens: None,
+                    output_tokens: None,
+                });
+            state.observer.record_metric(
+                &crate::observability::traits::ObserverMetric::RequestLatency(duration),
+            );
+            state
+                .observer
+                .record_event(&crate::observability::ObserverEvent::Error {
+                    component: "gateway".to_string(),
+                    message: format!("provider={provider_label}: {sanitized}"),
+                });
+            state
+                .observer
+                .record_event(&crate::observability::ObserverEvent::AgentEnd {
+                    provider: provider_label,
+                    model: model_label,
+                    duration,
+                    tokens_used: None,
+                    cost_usd: None,
+                });
 
-    if !response.status().is_success() {
-        let status = response.status();
-        let body = response.text().await.unwrap_or_default();
-        anyhow::bail!("Feed fetch failed for {} ({status}): {body}", source.xml_url);
+            if let Some(exceeded) = budget_exceeded(&e) {
+                tracing::warn!("Webhook refused: {exceeded}");
+                return Err(WebhookMessageError::Budget(exceeded.clone()));
+            }
+            tracing::error!("Webhook provider error: {}", sanitized);
+            Err(WebhookMessageError::Provider(sanitized))
+        }
     }
+}
 
-    let body = response.bytes().await?;
-    let xml = String::from_utf8_lossy(&body);
-    Ok(RemoteFeedFetchResult {
-        entries: parse_feed_entries(&xml, &source.html_url),
-        etag,
-        last_modified,
-        not_modified: false,
-    })
+#[derive(Default, serde::Deserialize)]
+struct WebhookQuery {
+    /// `json` (default) or `text`; overrides the `Accept` header.
+    format: Option<String>,
 }
 
-async fn refresh_cached_content_sources(
-    workspace_dir: &StdPath,
-    embedder: Option<Arc<dyn memory::embeddings::EmbeddingProvider>>,
-) -> Result<()> {
-    sync_content_sources_from_feed_web_sources(workspace_dir)?;
-    let sources = local_store::list_content_sources(workspace_dir, 128)?;
-    let stale_sources: Vec<local_store::ContentSourceRecord> = sources
-        .into_iter()
-        .filter(|source| content_source_is_stale(&source.last_fetch_at))
-        .take(CONTENT_SOURCE_REFRESH_BATCH_SIZE)
-        .collect();
+/// How `/webhook` answers: the JSON envelope, or just the reply text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WebhookFormat {
+    Json,
+    Text,
+}
 
-    for source in stale_sources {
-        let fetched_at = Utc::now().to_rfc3339();
-        match fetch_remote_feed(&source).await {
-            Ok(result) => {
-                if !result.not_modified {
-                    for entry in result.entries.into_iter().take(CONTENT_SOURCE_ITEM_LIMIT) {
-                        let embedding_text = content_item_embedding_text(&entry);
-                        if embedding_text.trim().is_empty() {
-                            continue;
-                        }
-                        let embedding = if let Some(embedder) = embedder.as_ref() {
-                            match embedder.embed_one(&embedding_text).await {
-                                Ok(value) => vec_to_bytes(&value),
-                                Err(err) => {
-                                    tracing::debug!(
-                                        source = %source.xml_url,
-                                        url = %entry.canonical_url,
-                                        error = %err,
-                                        "Failed to embed feed content item"
-                                    );
-                                    Vec::new()
-                                }
-                            }
-                        } else {
-                            Vec::new()
-                        };
-                        let canonical_url = if entry.canonical_url.trim().is_empty() {
-                            source.html_url.clone()
-                        } else {
-                            entry.canonical_url.clone()
-                        };
-                        let id = build_content_item_id(
-                            &source.source_key,
-                            &canonical_url,
-                            &entry.external_id,
-                        );
-                        let content_hash = content_hash_16(&embedding_text);
-                        let _ = local_store::upsert_content_item(
-                            workspace_dir,
-                            &local_store::ContentItemUpsert {
-                                id,
-                                source_key: source.source_key.clone(),
-                                source_title: source.title.clone(),
-                                source_kind: source.source_kind.clone(),
-                                domain: source.domain.clone(),
-                                canonical_url,
-                                external_id: entry.external_id.clone(),
-                                title: entry.title.clone(),
-                                author: entry.author.clone(),
-                                summary: truncate_with_ellipsis(entry.summary.trim(), 280),
-                                content_text: embedding_text,
-                                content_hash,
-                                embedding,
-                                published_at: entry.published_at.clone(),
-                                discovered_at: fetched_at.clone(),
-                            },
-                        )?;
-                    }
-                }
-                local_store::update_content_source_fetch(
-                    workspace_dir,
-                    &source.source_key,
-                    &fetched_at,
-                    result.etag.as_deref(),
-                    result.last_modified.as_deref(),
-                    None,
-                    true,
-                )?;
-            }
-            Err(err) => {
-                tracing::debug!(
-                    source = %source.xml_url,
-                    error = %err,
-                    "Failed to refresh content source"
-                );
-                local_store::update_content_source_fetch(
-                    workspace_dir,
-                    &source.source_key,
-                    &fetched_at,
-                    None,
-                    None,
-                    Some(&err.to_string()),
-                    false,
-                )?;
-            }
+/// `?format=` wins; otherwise `text/plain` must outrank JSON in `Accept`
+/// (`*/*` counts for JSON, which stays the default). `None` for an unknown
+/// `format` value.
+fn webhook_format(query: &WebhookQuery, headers: &HeaderMap) -> Option<WebhookFormat> {
+    match query.format.as_deref().map(str::trim) {
+        Some(format) if format.eq_ignore_ascii_case("text") => return Some(WebhookFormat::Text),
+        Some(format) if format.eq_ignore_ascii_case("json") => return Some(WebhookFormat::Json),
+        Some(_) => return None,
+        None => {}
+    }
+    let accept = headers
+        .get(header::ACCEPT)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("");
+    let (mut text_q, mut json_q) = (0.0_f32, 0.0_f32);
+    for range in accept.split(',') {
+        let mut parts = range.split(';').map(str::trim);
+        let media = parts.next().unwrap_or("").to_ascii_lowercase();
+        let q = parts
+            .find_map(|param| param.strip_prefix("q="))
+            .and_then(|q| q.parse::<f32>().ok())
+            .unwrap_or(1.0);
+        match media.as_str() {
+            "text/plain" | "text/*" => text_q = text_q.max(q),
+            "application/json" | "application/*" | "*/*" => json_q = json_q.max(q),
+            _ => {}
         }
     }
+    Some(if text_q > 0.0 && text_q > json_q {
+        WebhookFormat::Text
+    } else {
+        WebhookFormat::Json
+    })
+}
 
-    Ok(())
+/// Send a `/webhook` result in the negotiated format. As text, a success is
+/// the bare reply with the model in `X-Model`, and anything else is its
+/// `error` (or `message`) on one line.
+fn webhook_response(
+    format: WebhookFormat,
+    status: StatusCode,
+    body: serde_json::Value,
+    markdown_strip: bool,
+) -> axum::response::Response {
+    if format == WebhookFormat::Json {
+        return (status, Json(body)).into_response();
+    }
+    let text_response = |status: StatusCode, text: String| {
+        (
+            status,
+            [(header::CONTENT_TYPE, "text/plain; charset=utf-8")],
+            text,
+        )
+            .into_response()
+    };
+    let Some(reply) = body["response"].as_str().filter(|_| status.is_success()) else {
+        let line = body["error"]
+            .as_str()
+            .or_else(|| body["message"].as_str())
+            .unwrap_or_else(|| status.canonical_reason().unwrap_or("error"));
+        return text_response(status, format!("{}\n", line.replace('\n', " ")));
+    };
+    let reply = if markdown_strip {
+        markdown_render::to_plain_text(reply)
+    } else {
+        reply.to_string()
+    };
+    let mut response = text_response(status, reply);
+    if let Some(model) = body["model"]
+        .as_str()
+        .and_then(|model| HeaderValue::from_str(model).ok())
+    {
+        response.headers_mut().insert("X-Model", model);
+    }
+    response
 }
 
-async fn backfill_cached_content_item_embeddings(
-    workspace_dir: &StdPath,
-    embedder: Arc<dyn memory::embeddings::EmbeddingProvider>,
-) -> Result<usize> {
-    let missing_items = local_store::list_content_items_missing_embeddings(
-        workspace_dir,
-        CONTENT_ITEM_EMBEDDING_BACKFILL_BATCH_SIZE,
-    )?;
-    if missing_items.is_empty() {
-        return Ok(0);
+/// POST /webhook — main webhook endpoint
+async fn handle_webhook(
+    State(state): State<AppState>,
+    ConnectInfo(peer): ConnectInfo<GatewayPeer>,
+    Query(query): Query<WebhookQuery>,
+    headers: HeaderMap,
+    body: Result<Json<WebhookBody>, axum::extract::rejection::JsonRejection>,
+) -> axum::response::Response {
+    let Some(format) = webhook_format(&query, &headers) else {
+        let err = serde_json::json!({"error": "format must be json or text"});
+        return (StatusCode::BAD_REQUEST, Json(err)).into_response();
+    };
+    // A key with its own rate limit was already counted by
+    // `authorize_api_key_requests`; that limit replaces the per-peer one.
+    let key_rate_limited = api_keys::bearer_key(&headers).is_some_and(|key| {
+        let workspace_dir = state.config.lock().workspace_dir.clone();
+        api_keys::store(&workspace_dir)
+            .authenticate(key)
+            .is_some_and(|caller| caller.rate_limited)
+    });
+    let rate_key = peer_rate_key(peer, &headers, &state.trusted_proxies);
+    if !key_rate_limited && !state.rate_limiter.allow_webhook(&rate_key) {
+        tracing::warn!("/webhook rate limit exceeded");
+        let (status, Json(err)) = webhook_rate_limited_response();
+        return webhook_response(format, status, err, false);
     }
 
-    let mut updated_count = 0usize;
-    for item in missing_items {
-        let content = item.content_text.trim();
-        if content.is_empty() {
-            continue;
+    if let Some((status, Json(err))) = webhook_auth_error(&state, &headers) {
+        return webhook_response(format, status, err, false);
+    }
+
+    // ── Parse body ──
+    let Json(webhook_body) = match body {
+        Ok(b) => b,
+        Err(e) => {
+            tracing::warn!("Webhook JSON parse error: {e}");
+            let err = serde_json::json!({
+                "error": "Invalid JSON body. Expected: {\"message\": \"...\"}"
+            });
+            return webhook_response(format, StatusCode::BAD_REQUEST, err, false);
         }
-        match embedder.embed_one(content).await {
-            Ok(embedding) if !embedding.is_empty() => {
-                local_store::update_content_item_embedding(
-                    workspace_dir,
-                    &item.id,
-                    &vec_to_bytes(&embedding),
-                )?;
-                updated_count += 1;
-            }
-            Ok(_) => {
-                tracing::debug!(
-                    item_id = %item.id,
-                    url = %item.canonical_url,
-                    "Feed embedder returned an empty vector for cached content item"
-                );
-            }
-            Err(err) => {
-                tracing::debug!(
-                    item_id = %item.id,
-                    url = %item.canonical_url,
-                    error = %err,
-                    "Failed to backfill cached content item embedding"
-                );
-            }
+    };
+    let markdown_strip = webhook_body.markdown_strip;
+
+    // ── Idempotency (optional) ──
+    if let Some(idempotency_key) = headers
+        .get("X-Idempotency-Key")
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+    {
+        if !state.idempotency_store.record_if_new(idempotency_key) {
+            tracing::info!("Webhook duplicate ignored (idempotency key: {idempotency_key})");
+            let body = serde_json::json!({
+                "status": "duplicate",
+                "idempotent": true,
+                "message": "Request already processed for this idempotency key"
+            });
+            return webhook_response(format, StatusCode::OK, body, false);
         }
     }
 
-    Ok(updated_count)
+    let (result, cache_outcome) = providers::cache::with_cache_scope(
+        webhook_body.no_cache,
+        with_budget_override(
+            budget_override_requested(&state, &headers),
+            process_webhook_message(&state, &webhook_body),
+        ),
+    )
+    .await;
+    let (status, body) = match result {
+        Ok(body) => (StatusCode::OK, body),
+        Err(WebhookMessageError::Invalid(violation)) => {
+            let err = serde_json::json!({
+                "error": violation.message,
+                "field": violation.field,
+            });
+            (StatusCode::BAD_REQUEST, err)
+        }
+        Err(WebhookMessageError::Provider(_)) => {
+            let err = serde_json::json!({"error": "LLM request failed"});
+            (StatusCode::INTERNAL_SERVER_ERROR, err)
+        }
+        Err(WebhookMessageError::Budget(exceeded)) => {
+            let err = serde_json::json!({
+                "error": exceeded.to_string(),
+                "code": "BUDGET_EXCEEDED",
+                "resets_at": exceeded.resets_at,
+            });
+            (StatusCode::TOO_MANY_REQUESTS, err)
+        }
+    };
+    let mut response = webhook_response(format, status, body, markdown_strip);
+    // Debug aid: only reported while runtime tracing is on.
+    let tracing_on = crate::observability::runtime_trace::storage_mode_from_config(
+        &state.config.lock().observability,
+    ) != crate::observability::runtime_trace::RuntimeTraceStorageMode::None;
+    if let Some(outcome) = cache_outcome.filter(|_| tracing_on) {
+        response
+            .headers_mut()
+            .insert("X-LlmCache", HeaderValue::from_static(outcome.as_str()));
+    }
+    response
 }
 
-fn cached_content_backfill_inflight() -> &'static Mutex<HashSet<PathBuf>> {
-    static INFLIGHT: OnceLock<Mutex<HashSet<PathBuf>>> = OnceLock::new();
-    INFLIGHT.get_or_init(|| Mutex::new(HashSet::new()))
-}
+/// POST /telegram — updates for a bot in webhook mode. Requires
+/// `channels_config.telegram.webhook_secret`, echoed by Telegram in
+/// `X-Telegram-Bot-Api-Secret-Token`. Messages are answered in the background
+/// so Telegram gets its 200 before the agent runs.
+async fn handle_telegram_webhook(
+    State(state): State<AppState>,
+    ConnectInfo(peer): ConnectInfo<GatewayPeer>,
+    headers: HeaderMap,
+    body: axum::body::Bytes,
+) -> impl IntoResponse {
+    use crate::channels::telegram::{verify_webhook_secret, SECRET_TOKEN_HEADER};
 
-fn spawn_cached_content_item_embedding_backfill(
-    workspace_dir: PathBuf,
-    embedder: Arc<dyn memory::embeddings::EmbeddingProvider>,
-) {
-    {
-        let mut inflight = cached_content_backfill_inflight().lock();
-        if !inflight.insert(workspace_dir.clone()) {
-            return;
+    let rate_key = peer_rate_key(peer, &headers, &state.trusted_proxies);
+    if !state.rate_limiter.allow_webhook(&rate_key) {
+        tracing::warn!("/telegram rate limit exceeded");
+        return webhook_rate_limited_response();
+    }
+
+    let config = state.config.lock().clone();
+    let Some(telegram) = config
+        .channels_config
+        .telegram
+        .clone()
+        .filter(|telegram| telegram.webhook_secret.is_some())
+    else {
+        let err = serde_json::json!({"error": "Telegram webhook mode is not configured"});
+        return (StatusCode::NOT_FOUND, Json(err));
+    };
+    let secret = SecretString::new(
+        telegram
+            .webhook_secret
+            .as_deref()
+            .unwrap_or_default()
+            .trim(),
+    );
+    let header_secret = headers
+        .get(SECRET_TOKEN_HEADER)
+        .and_then(|v| v.to_str().ok());
+    if !verify_webhook_secret(&secret, header_secret) {
+        tracing::warn!("Telegram: rejected webhook with a missing or wrong secret token");
+        let err = serde_json::json!({"error": "Invalid Telegram secret token"});
+        return (StatusCode::UNAUTHORIZED, Json(err));
+    }
+    let update: serde_json::Value = match serde_json::from_slice(&body) {
+        Ok(update) => update,
+        Err(e) => {
+            tracing::warn!("Telegram update JSON parse error: {e}");
+            let err = serde_json::json!({"error": "Invalid JSON body"});
+            return (StatusCode::BAD_REQUEST, Json(err));
         }
+    };
+
+    let channel = crate::channels::TelegramChannel::from_config(&telegram);
+    if telegram.mention_only {
+        channel.load_bot_username().await;
     }
+    let message = channel.parse_update(&update);
+    let accepted = message.is_some();
+    if let Some(message) = message {
+        crate::channels::register_live_channel(Arc::new(channel.clone()));
+        tokio::spawn(async move {
+            crate::channels::telegram::answer(config, &channel, message).await;
+        });
+    }
+    (
+        StatusCode::OK,
+        Json(serde_json::json!({"ok": true, "accepted": accepted})),
+    )
+}
 
-    tokio::spawn(async move {
-        let result = backfill_cached_content_item_embeddings(&workspace_dir, embedder).await;
-        if let Err(err) = result {
-            tracing::warn!("Failed to backfill cached content item embeddings: {err}");
-        }
-        cached_content_backfill_inflight()
-            .lock()
-            .remove(&workspace_dir);
+/// A channel webhook whose signature this build cannot check. The builder
+/// refuses such configs at startup; this covers a config reloaded since.
+#[cfg(any(feature = "channel-nextcloud", feature = "channel-whatsapp"))]
+fn unsupported_signature_response(channel: &str) -> (StatusCode, Json<serde_json::Value>) {
+    tracing::error!("{channel}: signature verification is not available in this build");
+    let err = serde_json::json!({
+        "error": format!("{channel} signature verification is not available in this build")
     });
+    (StatusCode::NOT_IMPLEMENTED, Json(err))
 }
 
-fn content_preview_timestamp(item: &local_store::ContentItemRecord) -> String {
-    non_empty_string(item.published_at.clone())
-        .or_else(|| non_empty_string(item.discovered_at.clone()))
-        .or_else(|| non_empty_string(item.updated_at.clone()))
-        .unwrap_or_default()
+/// The configured Nextcloud Talk channel and its webhook secret;
+/// `ZEROCLAW_NEXTCLOUD_TALK_WEBHOOK_SECRET` overrides the config value.
+#[cfg(feature = "channel-nextcloud")]
+fn nextcloud_talk_channel(
+    config: &Config,
+) -> Option<(crate::channels::NextcloudTalkChannel, Option<SecretString>)> {
+    let talk = config.channels_config.nextcloud_talk.as_ref()?;
+    let secret = std::env::var("ZEROCLAW_NEXTCLOUD_TALK_WEBHOOK_SECRET")
+        .ok()
+        .or_else(|| talk.webhook_secret.clone())
+        .map(|secret| SecretString::new(secret.trim()))
+        .filter(|secret| !secret.is_empty());
+    let channel = crate::channels::NextcloudTalkChannel::new(
+        talk.base_url.clone(),
+        talk.app_token.clone(),
+        talk.allowed_users.clone(),
+    )
+    .with_webhook_secret(secret.clone());
+    Some((channel, secret))
 }
 
-fn build_content_preview(item: &local_store::ContentItemRecord) -> WebFeedPreview {
-    let description = if !item.summary.trim().is_empty() {
-        item.summary.trim().to_string()
-    } else {
-        truncate_with_ellipsis(item.content_text.trim(), 220)
+/// POST /nextcloud-talk — Talk bot webhook. Checks the signature, then
+/// answers each message in the background and replies in the same room, so
+/// Talk gets its 200 before the model runs.
+#[cfg(feature = "channel-nextcloud")]
+async fn handle_nextcloud_talk_webhook(
+    State(state): State<AppState>,
+    ConnectInfo(peer): ConnectInfo<GatewayPeer>,
+    headers: HeaderMap,
+    body: axum::body::Bytes,
+) -> impl IntoResponse {
+    use crate::channels::nextcloud_talk::{
+        verify_nextcloud_talk_signature, RANDOM_HEADER, SIGNATURE_HEADER,
     };
-    WebFeedPreview {
-        url: item.canonical_url.clone(),
-        title: if item.title.trim().is_empty() {
-            item.canonical_url.clone()
-        } else {
-            item.title.clone()
-        },
-        description,
-        image_url: None,
-        domain: item.domain.clone(),
-        provider: "RSS/Atom".to_string(),
-        provider_snippet: non_empty_string(item.source_title.clone()),
-        discovered_at: content_preview_timestamp(item),
-    }
-}
+    use crate::channels::VerifyResult;
 
-fn build_recent_content_items(
-    workspace_dir: &StdPath,
-    limit: usize,
-) -> Result<Vec<PersonalizedBlueskyItem>> {
-    let items = local_store::list_recent_content_items(workspace_dir, limit)?;
-    Ok(items
-        .into_iter()
-        .filter(|item| !item.canonical_url.trim().is_empty())
-        .map(|item| {
-            let preview = build_content_preview(&item);
-            PersonalizedBlueskyItem {
-                source_type: "web".to_string(),
-                feed_item: serde_json::json!({
-                    "url": item.canonical_url,
-                    "title": item.title,
-                    "description": item.summary,
-                    "domain": item.domain,
-                    "author": item.author,
-                    "sourceTitle": item.source_title,
-                    "publishedAt": item.published_at,
-                }),
-                web_preview: Some(preview),
-                feed_source: None,
-                score: None,
-                matched_interest_label: None,
-                matched_interest_score: None,
-                passed_threshold: false,
-            }
-        })
-        .collect())
-}
+    let rate_key = peer_rate_key(peer, &headers, &state.trusted_proxies);
+    if !state.rate_limiter.allow_webhook(&rate_key) {
+        tracing::warn!("/nextcloud-talk rate limit exceeded");
+        return webhook_rate_limited_response();
+    }
 
-fn rank_cached_content_items(
-    workspace_dir: &StdPath,
-    interests: &[ActiveInterest],
-    limit: usize,
-) -> Result<Vec<PersonalizedBlueskyItem>> {
-    let mut ranked = Vec::new();
-    for item in local_store::list_recent_content_items(workspace_dir, CONTENT_RANK_CANDIDATE_LIMIT)? {
-        let embedding = bytes_to_vec(&item.embedding);
-        if embedding.is_empty() {
-            continue;
+    let Some((channel, secret)) = nextcloud_talk_channel(&state.config.lock()) else {
+        let err = serde_json::json!({"error": "Nextcloud Talk is not configured"});
+        return (StatusCode::NOT_FOUND, Json(err));
+    };
+    let Some(secret) = secret else {
+        tracing::warn!("Nextcloud Talk: rejected webhook, no webhook_secret is configured");
+        let err = serde_json::json!({"error": "Nextcloud Talk webhook secret is not configured"});
+        return (StatusCode::UNAUTHORIZED, Json(err));
+    };
+    let header_value = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default()
+    };
+    let Ok(raw) = std::str::from_utf8(&body) else {
+        let err = serde_json::json!({"error": "Body must be UTF-8 JSON"});
+        return (StatusCode::BAD_REQUEST, Json(err));
+    };
+    match verify_nextcloud_talk_signature(
+        secret.expose(),
+        header_value(RANDOM_HEADER),
+        raw,
+        header_value(SIGNATURE_HEADER),
+    ) {
+        VerifyResult::Valid => {}
+        VerifyResult::Invalid => {
+            tracing::warn!("Nextcloud Talk: rejected webhook with an invalid signature");
+            let err = serde_json::json!({"error": "Invalid Nextcloud Talk signature"});
+            return (StatusCode::UNAUTHORIZED, Json(err));
         }
-        let (best_weighted, best_similarity, best_label) =
-            best_interest_match(&embedding, interests);
-        if best_weighted < FEED_MATCH_THRESHOLD {
-            continue;
+        VerifyResult::Unsupported => return unsupported_signature_response("Nextcloud Talk"),
+    }
+    let payload: serde_json::Value = match serde_json::from_str(raw) {
+        Ok(payload) => payload,
+        Err(e) => {
+            tracing::warn!("Nextcloud Talk JSON parse error: {e}");
+            let err = serde_json::json!({"error": "Invalid JSON body"});
+            return (StatusCode::BAD_REQUEST, Json(err));
         }
-        let preview = build_content_preview(&item);
-        ranked.push(PersonalizedBlueskyItem {
-            source_type: "web".to_string(),
-            feed_item: serde_json::json!({
-                "url": item.canonical_url,
-                "title": item.title,
-                "description": item.summary,
-                "domain": item.domain,
-                "author": item.author,
-                "sourceTitle": item.source_title,
-                "publishedAt": item.published_at,
-            }),
-            web_preview: Some(preview),
-            feed_source: None,
-            score: Some(best_weighted),
-            matched_interest_label: best_label,
-            matched_interest_score: if best_similarity > 0.0 {
-                Some(best_similarity)
-            } else {
-                None
-            },
-            passed_threshold: true,
-        });
+    };
+
+    let messages = channel.parse_webhook_payload(&payload);
+    let accepted = messages.len();
+    for message in messages {
+        tokio::spawn(answer_nextcloud_talk_message(
+            state.clone(),
+            channel.clone(),
+            message,
+        ));
     }
-    sort_personalized_items(&mut ranked);
-    ranked.truncate(limit);
-    Ok(ranked)
+    (
+        StatusCode::OK,
+        Json(serde_json::json!({"status": "ok", "accepted": accepted})),
+    )
 }
 
-fn append_feed_items_up_to_limit(
-    target: &mut Vec<PersonalizedBlueskyItem>,
-    mut extra: Vec<PersonalizedBlueskyItem>,
-    limit: usize,
+/// Answer one Talk message as a webhook turn keyed by room, so each room
+/// keeps its own history, and post the reply back to the room.
+#[cfg(feature = "channel-nextcloud")]
+async fn answer_nextcloud_talk_message(
+    state: AppState,
+    channel: crate::channels::NextcloudTalkChannel,
+    message: crate::channels::traits::ChannelMessage,
 ) {
-    if target.len() >= limit {
+    let body = WebhookBody {
+        message: message.content.clone(),
+        session_id: Some(format!("nextcloud-talk:{}", message.reply_target)),
+        ..WebhookBody::default()
+    };
+    let reply = match process_webhook_message(&state, &body).await {
+        Ok(result) => result["response"].as_str().unwrap_or_default().to_string(),
+        Err(WebhookMessageError::Invalid(violation)) => {
+            tracing::warn!("Nextcloud Talk message rejected: {}", violation.message);
+            return;
+        }
+        Err(WebhookMessageError::Provider(_)) => return,
+        Err(WebhookMessageError::Budget(exceeded)) => exceeded.to_string(),
+    };
+    if reply.trim().is_empty() {
         return;
     }
-    let remaining = limit - target.len();
-    extra.truncate(remaining);
-    target.extend(extra);
+    let mut outgoing = crate::channels::SendMessage::new(reply, message.reply_target.clone());
+    outgoing.thread_ts = message.thread_ts.clone();
+    let workspace_dir = state.config.lock().workspace_dir.clone();
+    let dispatcher = crate::channels::OutboundDispatcher::new(&workspace_dir)
+        .with_observer(Arc::clone(&state.observer));
+    if let Err(err) = dispatcher.send(&channel, &outgoing).await {
+        tracing::warn!("Nextcloud Talk reply failed: {err:#}");
+    }
 }
 
-async fn fetch_web_preview_html(url: &str) -> Result<String> {
-    let response = reqwest::Client::builder()
-        .timeout(Duration::from_secs(12))
-        .user_agent("SlowClawFeedPreview/1.0")
-        .build()?
-        .get(url)
-        .send()
-        .await
-        .with_context(|| format!("Failed to fetch preview URL {url}"))?;
+/// The configured WhatsApp Cloud API channel and its app secret;
+/// `ZEROCLAW_WHATSAPP_APP_SECRET` overrides the config value.
+#[cfg(feature = "channel-whatsapp")]
+fn whatsapp_channel(
+    config: &Config,
+) -> Option<(crate::channels::WhatsAppChannel, Option<SecretString>)> {
+    let whatsapp = config.channels_config.whatsapp.as_ref()?;
+    let channel = crate::channels::WhatsAppChannel::from_config(whatsapp)?;
+    let secret = std::env::var("ZEROCLAW_WHATSAPP_APP_SECRET")
+        .ok()
+        .or_else(|| whatsapp.app_secret.clone())
+        .map(|secret| SecretString::new(secret.trim()))
+        .filter(|secret| !secret.is_empty());
+    Some((channel, secret))
+}
+
+/// Meta's subscription handshake parameters.
+#[cfg(feature = "channel-whatsapp")]
+#[derive(Debug, serde::Deserialize)]
+struct WhatsAppVerifyQuery {
+    #[serde(rename = "hub.mode", default)]
+    mode: String,
+    #[serde(rename = "hub.verify_token", default)]
+    verify_token: String,
+    #[serde(rename = "hub.challenge", default)]
+    challenge: String,
+}
+
+/// GET /whatsapp — Meta's webhook verification: echo `hub.challenge` when
+/// the verify token matches.
+#[cfg(feature = "channel-whatsapp")]
+async fn handle_whatsapp_verify(
+    State(state): State<AppState>,
+    Query(query): Query<WhatsAppVerifyQuery>,
+) -> axum::response::Response {
+    let Some((channel, _)) = whatsapp_channel(&state.config.lock()) else {
+        let err = serde_json::json!({"error": "WhatsApp is not configured"});
+        return (StatusCode::NOT_FOUND, Json(err)).into_response();
+    };
+    if !channel.verify_subscription(&query.mode, &query.verify_token) {
+        tracing::warn!("WhatsApp: rejected webhook verification with a bad verify token");
+        return (StatusCode::FORBIDDEN, "Forbidden").into_response();
+    }
+    (StatusCode::OK, query.challenge).into_response()
+}
 
-    if !response.status().is_success() {
-        anyhow::bail!("Preview fetch failed for {url} ({})", response.status());
+/// POST /whatsapp — Cloud API webhook. Checks the app-secret signature, then
+/// answers each message in the background, so Meta gets its 200 before the
+/// model runs.
+#[cfg(feature = "channel-whatsapp")]
+async fn handle_whatsapp_webhook(
+    State(state): State<AppState>,
+    ConnectInfo(peer): ConnectInfo<GatewayPeer>,
+    headers: HeaderMap,
+    body: axum::body::Bytes,
+) -> impl IntoResponse {
+    use crate::channels::whatsapp::{verify_whatsapp_signature, SIGNATURE_HEADER};
+    use crate::channels::VerifyResult;
+
+    let rate_key = peer_rate_key(peer, &headers, &state.trusted_proxies);
+    if !state.rate_limiter.allow_webhook(&rate_key) {
+        tracing::warn!("/whatsapp rate limit exceeded");
+        return webhook_rate_limited_response();
     }
 
-    response
-        .text()
-        .await
-        .with_context(|| format!("Failed to read preview body {url}"))
-}
+    let Some((channel, secret)) = whatsapp_channel(&state.config.lock()) else {
+        let err = serde_json::json!({"error": "WhatsApp is not configured"});
+        return (StatusCode::NOT_FOUND, Json(err));
+    };
+    let Some(secret) = secret else {
+        tracing::warn!("WhatsApp: rejected webhook, no app_secret is configured");
+        let err = serde_json::json!({"error": "WhatsApp app secret is not configured"});
+        return (StatusCode::UNAUTHORIZED, Json(err));
+    };
+    let signature = headers
+        .get(SIGNATURE_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    match verify_whatsapp_signature(secret.expose(), &body, signature) {
+        VerifyResult::Valid => {}
+        VerifyResult::Invalid => {
+            tracing::warn!("WhatsApp: rejected webhook with an invalid signature");
+            let err = serde_json::json!({"error": "Invalid WhatsApp signature"});
+            return (StatusCode::UNAUTHORIZED, Json(err));
+        }
+        VerifyResult::Unsupported => return unsupported_signature_response("WhatsApp"),
+    }
+    let payload: serde_json::Value = match serde_json::from_slice(&body) {
+        Ok(payload) => payload,
+        Err(e) => {
+            tracing::warn!("WhatsApp JSON parse error: {e}");
+            let err = serde_json::json!({"error": "Invalid JSON body"});
+            return (StatusCode::BAD_REQUEST, Json(err));
+        }
+    };
 
-fn preview_from_html(candidate: &CandidateWebResult, html: &str) -> WebFeedPreview {
-    let title = first_meta_capture(
-        html,
-        &[
-            r#"(?is)<meta[^>]+property=["']og:title["'][^>]+content=["']([^"']+)["']"#,
-            r#"(?is)<meta[^>]+name=["']twitter:title["'][^>]+content=["']([^"']+)["']"#,
-            r#"(?is)<title[^>]*>\s*([^<]+?)\s*</title>"#,
-        ],
-    )
-    .unwrap_or_else(|| candidate.title.clone());
-    let description = first_meta_capture(
-        html,
-        &[
-            r#"(?is)<meta[^>]+property=["']og:description["'][^>]+content=["']([^"']+)["']"#,
-            r#"(?is)<meta[^>]+name=["']description["'][^>]+content=["']([^"']+)["']"#,
-            r#"(?is)<meta[^>]+name=["']twitter:description["'][^>]+content=["']([^"']+)["']"#,
-        ],
+    let messages = channel.parse_webhook_payload(&payload);
+    let accepted = messages.len();
+    for message in messages {
+        tokio::spawn(answer_whatsapp_message(
+            state.clone(),
+            channel.clone(),
+            message,
+        ));
+    }
+    (
+        StatusCode::OK,
+        Json(serde_json::json!({"status": "ok", "accepted": accepted})),
     )
-    .unwrap_or_else(|| candidate.description.clone());
-    let image_url = first_meta_capture(
-        html,
-        &[
-            r#"(?is)<meta[^>]+property=["']og:image["'][^>]+content=["']([^"']+)["']"#,
-            r#"(?is)<meta[^>]+name=["']twitter:image["'][^>]+content=["']([^"']+)["']"#,
-        ],
+}
+
+/// Download a WhatsApp media object into the journal media folders, within
+/// the storage limits. Returns the workspace-relative path.
+#[cfg(feature = "channel-whatsapp")]
+async fn save_whatsapp_media(
+    state: &AppState,
+    channel: &crate::channels::WhatsAppChannel,
+    media: &crate::channels::whatsapp::PendingMedia,
+) -> Result<String> {
+    let (bytes, mime) = channel.download_media(&media.media_id).await?;
+    let usage = storage_snapshot(state).await;
+    if let Some(limit) = usage.check_upload(bytes.len() as u64) {
+        anyhow::bail!("{}", limit.message());
+    }
+    let extension = mime
+        .as_deref()
+        .or(media.mime.as_deref())
+        .and_then(|mime| mime.split(';').next())
+        .and_then(|mime| mime_guess::get_mime_extensions_str(mime.trim()))
+        .and_then(|extensions| extensions.first())
+        .copied()
+        .unwrap_or("bin");
+    let workspace_dir = state.config.lock().workspace_dir.clone();
+    let rel_path = media_storage_rel_path(
+        &workspace_dir,
+        media.kind.as_str(),
+        &format!("whatsapp_{}.{extension}", media.media_id),
     );
+    let abs_path = workspace_dir.join(&rel_path);
+    if let Some(parent) = abs_path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let len = bytes.len() as u64;
+    atomic_write_async(abs_path, bytes).await?;
+    storage::note_media_bytes_added(&workspace_dir, len);
+    Ok(rel_path)
+}
 
-    WebFeedPreview {
-        url: candidate.url.clone(),
-        title,
-        description,
-        image_url,
-        domain: candidate.domain.clone(),
-        provider: candidate.provider.clone(),
-        provider_snippet: (!candidate.description.trim().is_empty())
-            .then(|| candidate.description.clone()),
-        discovered_at: Utc::now().to_rfc3339(),
+/// Answer one WhatsApp message as a webhook turn keyed by sender, after
+/// saving its media into the workspace, and reply in the same chat quoting
+/// the message.
+#[cfg(feature = "channel-whatsapp")]
+async fn answer_whatsapp_message(
+    state: AppState,
+    channel: crate::channels::WhatsAppChannel,
+    message: ChannelMessage,
+) {
+    use crate::channels::traits::AttachmentSource;
+
+    let pending = channel.pending_media(&message);
+    let mut attachments = Vec::with_capacity(message.attachments.len());
+    for mut attachment in message.attachments.clone() {
+        let media = match &attachment.source {
+            AttachmentSource::Url(url) => pending
+                .iter()
+                .find(|media| url.ends_with(&format!("/{}", media.media_id))),
+            AttachmentSource::Path(_) => None,
+        };
+        if let Some(media) = media {
+            // A Graph URL is useless to the agent without the token, so a
+            // failed download drops the attachment.
+            match save_whatsapp_media(&state, &channel, media).await {
+                Ok(rel_path) => attachment.source = AttachmentSource::Path(rel_path),
+                Err(err) => {
+                    tracing::warn!(
+                        media_id = %media.media_id,
+                        "WhatsApp media download failed: {err:#}"
+                    );
+                    continue;
+                }
+            }
+        }
+        attachments.push(attachment);
+    }
+    let body = WebhookBody {
+        message: message.content.clone(),
+        session_id: Some(format!("whatsapp:{}", message.reply_target)),
+        attachments,
+        ..WebhookBody::default()
+    };
+    let reply = match process_webhook_message(&state, &body).await {
+        Ok(result) => result["response"].as_str().unwrap_or_default().to_string(),
+        Err(WebhookMessageError::Invalid(violation)) => {
+            tracing::warn!("WhatsApp message rejected: {}", violation.message);
+            return;
+        }
+        Err(WebhookMessageError::Provider(_)) => return,
+        Err(WebhookMessageError::Budget(exceeded)) => exceeded.to_string(),
+    };
+    if reply.trim().is_empty() {
+        return;
+    }
+    let mut outgoing = crate::channels::SendMessage::new(reply, message.reply_target.clone());
+    outgoing.thread_ts = message.thread_ts.clone();
+    let workspace_dir = state.config.lock().workspace_dir.clone();
+    let dispatcher = crate::channels::OutboundDispatcher::new(&workspace_dir)
+        .with_observer(Arc::clone(&state.observer));
+    if let Err(err) = dispatcher.send(&channel, &outgoing).await {
+        tracing::warn!("WhatsApp reply failed: {err:#}");
     }
 }
 
-async fn resolve_web_preview(
-    workspace_dir: &StdPath,
-    candidate: &CandidateWebResult,
-) -> WebFeedPreview {
-    if let Ok(Some(cached)) = local_store::get_feed_web_cache(workspace_dir, &candidate.url) {
-        if web_preview_cache_is_fresh(&cached.updated_at) {
-            return WebFeedPreview {
-                url: cached.url,
-                title: if cached.title.trim().is_empty() {
-                    candidate.title.clone()
-                } else {
-                    cached.title
-                },
-                description: if cached.description.trim().is_empty() {
-                    candidate.description.clone()
-                } else {
-                    cached.description
-                },
-                image_url: non_empty_string(cached.image_url),
-                domain: if cached.domain.trim().is_empty() {
-                    candidate.domain.clone()
-                } else {
-                    cached.domain
-                },
-                provider: if cached.provider.trim().is_empty() {
-                    candidate.provider.clone()
-                } else {
-                    cached.provider
-                },
-                provider_snippet: non_empty_string(cached.snippet),
-                discovered_at: if cached.fetched_at.trim().is_empty() {
-                    Utc::now().to_rfc3339()
-                } else {
-                    cached.fetched_at
-                },
-            };
-        }
+/// One entry of a `/webhook/batch` request.
+#[derive(Default, serde::Deserialize)]
+pub struct WebhookBatchItem {
+    /// Per-item idempotency key; a repeated id is reported as a duplicate.
+    pub id: Option<String>,
+    #[serde(flatten)]
+    pub body: WebhookBody,
+}
+
+/// POST /webhook/batch — answer up to `WEBHOOK_BATCH_MAX_ITEMS` messages in
+/// one call. Results keep request order; each carries its own `ok` flag.
+async fn handle_webhook_batch(
+    State(state): State<AppState>,
+    ConnectInfo(peer): ConnectInfo<GatewayPeer>,
+    headers: HeaderMap,
+    body: Result<Json<Vec<WebhookBatchItem>>, axum::extract::rejection::JsonRejection>,
+) -> impl IntoResponse {
+    let rate_key = peer_rate_key(peer, &headers, &state.trusted_proxies);
+    let cost = body.as_ref().map_or(1, |Json(items)| items.len().max(1));
+    if !state.rate_limiter.allow_webhook_n(&rate_key, cost) {
+        tracing::warn!("/webhook/batch rate limit exceeded ({cost} items)");
+        return webhook_rate_limited_response();
     }
 
-    let preview = match fetch_web_preview_html(&candidate.url).await {
-        Ok(html) => preview_from_html(candidate, &html),
-        Err(err) => {
-            tracing::debug!(url = %candidate.url, error = %err, "Feed preview fetch failed");
-            WebFeedPreview {
-                url: candidate.url.clone(),
-                title: candidate.title.clone(),
-                description: candidate.description.clone(),
-                image_url: None,
-                domain: candidate.domain.clone(),
-                provider: candidate.provider.clone(),
-                provider_snippet: (!candidate.description.trim().is_empty())
-                    .then(|| candidate.description.clone()),
-                discovered_at: Utc::now().to_rfc3339(),
-            }
+    if let Some(err) = webhook_auth_error(&state, &headers) {
+        return err;
+    }
+
+    let Json(items) = match body {
+        Ok(b) => b,
+        Err(e) => {
+            tracing::warn!("Webhook batch JSON parse error: {e}");
+            let err = serde_json::json!({
+                "error": "Invalid JSON body. Expected: [{\"id\": \"...\", \"message\": \"...\"}]"
+            });
+            return (StatusCode::BAD_REQUEST, Json(err));
         }
     };
+    if items.is_empty() || items.len() > WEBHOOK_BATCH_MAX_ITEMS {
+        let err = serde_json::json!({
+            "error": format!("A batch must contain between 1 and {WEBHOOK_BATCH_MAX_ITEMS} items"),
+        });
+        return (StatusCode::BAD_REQUEST, Json(err));
+    }
 
-    let _ = local_store::upsert_feed_web_cache(
-        workspace_dir,
-        &local_store::FeedWebCacheUpsert {
-            url: preview.url.clone(),
-            domain: preview.domain.clone(),
-            title: preview.title.clone(),
-            description: preview.description.clone(),
-            image_url: preview.image_url.clone().unwrap_or_default(),
-            provider: preview.provider.clone(),
-            snippet: preview.provider_snippet.clone().unwrap_or_default(),
-            search_query: candidate.search_query.clone(),
-            fetched_at: preview.discovered_at.clone(),
-        },
-    );
+    let budget_override = budget_override_requested(&state, &headers);
+    let permits = Arc::new(tokio::sync::Semaphore::new(WEBHOOK_BATCH_CONCURRENCY));
+    let handles: Vec<_> = items
+        .into_iter()
+        .map(|item| {
+            let id = item
+                .id
+                .as_deref()
+                .map(str::trim)
+                .filter(|id| !id.is_empty())
+                .map(str::to_string);
+            // Checked before spawning so repeated ids resolve in request order.
+            let duplicate = id
+                .as_deref()
+                .is_some_and(|id| !state.idempotency_store.record_if_new(id));
+            let state = state.clone();
+            let permits = Arc::clone(&permits);
+            tokio::spawn(async move {
+                if duplicate {
+                    return serde_json::json!({
+                        "id": id,
+                        "ok": true,
+                        "status": "duplicate",
+                        "idempotent": true,
+                    });
+                }
+                let _permit = permits.acquire_owned().await;
+                let (result, _) = providers::cache::with_cache_scope(
+                    item.body.no_cache,
+                    with_budget_override(
+                        budget_override,
+                        process_webhook_message(&state, &item.body),
+                    ),
+                )
+                .await;
+                let mut result = match result {
+                    Ok(body) => body,
+                    Err(WebhookMessageError::Invalid(violation)) => serde_json::json!({
+                        "ok": false,
+                        "error": violation.message,
+                        "field": violation.field,
+                    }),
+                    Err(WebhookMessageError::Provider(sanitized)) => serde_json::json!({
+                        "ok": false,
+                        "error": sanitized,
+                    }),
+                    Err(WebhookMessageError::Budget(exceeded)) => serde_json::json!({
+                        "ok": false,
+                        "error": exceeded.to_string(),
+                        "code": "BUDGET_EXCEEDED",
+                        "resets_at": exceeded.resets_at,
+                    }),
+                };
+                if result.get("ok").is_none() {
+                    result["ok"] = serde_json::json!(true);
+                }
+                result["id"] = serde_json::json!(id);
+                result
+            })
+        })
+        .collect();
 
-    preview
+    let mut results = Vec::with_capacity(handles.len());
+    for handle in handles {
+        results.push(handle.await.unwrap_or_else(|err| {
+            serde_json::json!({ "ok": false, "error": format!("Batch item aborted: {err}") })
+        }));
+    }
+    let failed = results.iter().filter(|r| r["ok"] == false).count();
+    let body = serde_json::json!({
+        "results": results,
+        "succeeded": results.len() - failed,
+        "failed": failed,
+    });
+    (StatusCode::OK, Json(body))
 }
 
-async fn select_feed_embedder(
-    config: &Config,
-) -> Result<Option<Arc<dyn memory::embeddings::EmbeddingProvider>>> {
-    let configured = memory::create_embedder_from_config(config);
-    if configured.dimensions() == 0 {
-        return Ok(None);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::{Memory, MemoryCategory, MemoryEntry};
+    use crate::providers::Provider;
+    use async_trait::async_trait;
+    use axum::http::HeaderValue;
+    use axum::response::IntoResponse;
+    use http_body_util::BodyExt;
+    use parking_lot::Mutex;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio_util::sync::CancellationToken;
+
+    /// Generate a random hex secret at runtime to avoid hard-coded cryptographic values.
+    fn generate_test_secret() -> String {
+        let bytes: [u8; 32] = rand::random();
+        hex::encode(bytes)
     }
 
-    match configured.embed_one("feed profile probe").await {
-        Ok(embedding) if !embedding.is_empty() => Ok(Some(configured)),
-        Ok(_) => {
-            tracing::debug!(
-                provider = config.memory.embedding_provider.trim(),
-                model = config.memory.embedding_model.trim(),
-                dimensions = configured.dimensions(),
-                "Configured feed embedder returned an empty probe vector"
-            );
-            Ok(None)
+    fn test_app_state_with_config(config: Config) -> AppState {
+        AppState {
+            config: Arc::new(Mutex::new(config)),
+            provider: Arc::new(MockProvider::default()),
+            model: "test-model".into(),
+            temperature: 0.0,
+            mem: Arc::new(MockMemory),
+            auto_save: false,
+            webhook_secret_hash: None,
+            pairing: Arc::new(PairingGuard::new(false, &[])),
+            trusted_proxies: TrustedProxies::default(),
+            rate_limiter: Arc::new(GatewayRateLimiter::new(100, 100, 100)),
+            idempotency_store: Arc::new(IdempotencyStore::new(Duration::from_secs(300), 1000)),
+            pb_chat_base_url: None,
+            pb_chat_collection: "chat_messages".into(),
+            pb_chat_token: None,
+            observer: Arc::new(crate::observability::NoopObserver),
+            journal_transcription_jobs: Arc::new(Mutex::new(HashMap::new())),
+            jobs: Arc::new(crate::jobs::JobManager::in_memory()),
+            openrouter_oauth: Arc::new(Mutex::new(None)),
+            model_catalog: Arc::new(Mutex::new(None)),
+            chat_runs: Arc::new(Mutex::new(HashMap::new())),
+            tunnel_url: Arc::new(Mutex::new(None)),
         }
-        Err(err) => {
-            tracing::debug!(
-                provider = config.memory.embedding_provider.trim(),
-                model = config.memory.embedding_model.trim(),
-                dimensions = configured.dimensions(),
-                error = %err,
-                "Configured feed embedder probe failed"
-            );
-            Ok(None)
+    }
+
+    #[test]
+    fn extract_pairing_code_prefers_marker_then_header_then_banner() {
+        assert_eq!(
+            extract_pairing_code("PAIRING-CODE: 482913").as_deref(),
+            Some("482913")
+        );
+        assert_eq!(
+            extract_pairing_code("     Send: POST /pair with header X-Pairing-Code: 071245")
+                .as_deref(),
+            Some("071245")
+        );
+        assert_eq!(
+            extract_pairing_code("     │  930114  │").as_deref(),
+            Some("930114")
+        );
+        assert_eq!(
+            extract_pairing_code("  | 930114 |").as_deref(),
+            Some("930114")
+        );
+        assert_eq!(extract_pairing_code("930114").as_deref(), Some("930114"));
+    }
+
+    #[test]
+    fn extract_pairing_code_ignores_lookalike_digits() {
+        for line in [
+            "🦀 SlowClaw Gateway listening on http://127.0.0.1:424242",
+            "  🌐 Web UI: http://192.168.100.200:424242/",
+            "2026-10-15T12:34:56.123456Z  INFO gateway started",
+            "12:34:56",
+            "request 550e8400-e29b-41d4-a716-446655440000 finished",
+            "     │  request 123456 done  │",
+            "     ┌──────────────┐",
+            "PAIRING-CODE: 1234567",
+            "X-Pairing-Code: abc123",
+            "1234567",
+        ] {
+            assert_eq!(extract_pairing_code(line), None, "{line}");
         }
     }
-}
 
-async fn resolve_feed_embedder(
-    config: &Config,
-) -> Result<(
-    Option<Arc<dyn memory::embeddings::EmbeddingProvider>>,
-    Option<String>,
-)> {
-    if config.memory.embedding_provider.trim().eq_ignore_ascii_case("none") {
-        return Ok((
-            None,
-            Some(
-                "Personalized feed embeddings are disabled in [memory]. Showing recent cached content and raw Bluesky items when available.".to_string(),
-            ),
+    #[test]
+    fn startup_json_line_reports_bound_port_and_pairing_code() {
+        let mut state = test_app_state_with_config(Config::default());
+        state.pairing = Arc::new(PairingGuard::new(true, &[]));
+        let code = state.pairing.pairing_code().expect("fresh guard has a code");
+
+        let line = startup_json_line("127.0.0.1", 51234, &state);
+        let parsed: serde_json::Value = serde_json::from_str(&line.to_string()).unwrap();
+        assert_eq!(parsed["event"], "gateway_listening");
+        assert_eq!(parsed["port"], 51234);
+        assert_eq!(parsed["url"], "http://127.0.0.1:51234");
+        assert!(parsed["socket"].is_null());
+        assert_eq!(parsed["pairing_required"], true);
+        assert_eq!(parsed["pairing_code"], code);
+        assert!(parsed["tunnel_url"].is_null());
+        assert_eq!(parsed["chat_bridge"]["enabled"], false);
+        assert!(!line.to_string().contains('\n'));
+
+        state.config.lock().gateway.uds_path = Some("/run/slowclaw/gateway.sock".into());
+        let line = startup_json_line("127.0.0.1", 0, &state);
+        assert!(line["url"].is_null());
+        assert_eq!(line["socket"], "/run/slowclaw/gateway.sock");
+    }
+
+    #[test]
+    fn gateway_ui_profile_omits_shell_and_git_tools() {
+        let temp = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.workspace_dir = temp.path().to_path_buf();
+        let security = Arc::new(crate::security::SecurityPolicy::from_config(
+            &config.autonomy,
+            &config.workspace_dir,
         ));
+        let mem_cfg = crate::config::MemoryConfig {
+            backend: "markdown".into(),
+            ..crate::config::MemoryConfig::default()
+        };
+        let mem: Arc<dyn crate::memory::Memory> =
+            Arc::from(crate::memory::create_memory(&mem_cfg, temp.path(), None).unwrap());
+        let tools = crate::tools::all_tools_with_runtime_and_profile(
+            Arc::new(config.clone()),
+            &security,
+            Arc::new(crate::runtime::NativeRuntime::new()),
+            gateway_ui_tool_profile(),
+            mem,
+            None,
+            None,
+            &crate::config::BrowserConfig::default(),
+            &crate::config::HttpRequestConfig::default(),
+            &crate::config::WebFetchConfig::default(),
+            temp.path(),
+            &HashMap::new(),
+            None,
+            &config,
+        );
+        let names: Vec<&str> = tools.iter().map(|tool| tool.name()).collect();
+
+        for removed in ["shell", "git_operations"] {
+            assert!(
+                !names.contains(&removed),
+                "UI tool profile should not expose {removed}"
+            );
+            assert!(
+                tools.iter().find(|tool| tool.name() == removed).is_none(),
+                "UI tool profile should treat {removed} as unavailable"
+            );
+        }
+    }
+
+    #[test]
+    fn security_body_limit_is_64kb() {
+        assert_eq!(MAX_BODY_SIZE, 65_536);
+    }
+
+    #[test]
+    fn security_timeout_is_30_seconds() {
+        assert_eq!(REQUEST_TIMEOUT_SECS, 30);
+    }
+
+    #[test]
+    fn webhook_body_requires_message_field() {
+        let valid = r#"{"message": "hello"}"#;
+        let parsed: Result<WebhookBody, _> = serde_json::from_str(valid);
+        assert!(parsed.is_ok());
+        assert_eq!(parsed.unwrap().message, "hello");
+
+        let missing = r#"{"other": "field"}"#;
+        let parsed: Result<WebhookBody, _> = serde_json::from_str(missing);
+        assert!(parsed.is_err());
     }
 
-    if let Some(embedder) = select_feed_embedder(config).await? {
-        return Ok((Some(embedder), None));
+    #[test]
+    fn app_state_is_clone() {
+        fn assert_clone<T: Clone>() {}
+        assert_clone::<AppState>();
     }
 
-    Ok((
-        None,
-        Some(
-            "Configured embedding provider is unavailable. Showing recent cached content and raw Bluesky items when available.".to_string(),
-        ),
-    ))
-}
+    #[tokio::test]
+    async fn metrics_endpoint_returns_hint_when_prometheus_is_disabled() {
+        let state = AppState {
+            config: Arc::new(Mutex::new(Config::default())),
+            provider: Arc::new(MockProvider::default()),
+            model: "test-model".into(),
+            temperature: 0.0,
+            mem: Arc::new(MockMemory),
+            auto_save: false,
+            webhook_secret_hash: None,
+            pairing: Arc::new(PairingGuard::new(false, &[])),
+            trusted_proxies: TrustedProxies::default(),
+            rate_limiter: Arc::new(GatewayRateLimiter::new(100, 100, 100)),
+            idempotency_store: Arc::new(IdempotencyStore::new(Duration::from_secs(300), 1000)),
+            pb_chat_base_url: None,
+            pb_chat_collection: "chat_messages".into(),
+            pb_chat_token: None,
+            observer: Arc::new(crate::observability::NoopObserver),
+            journal_transcription_jobs: Arc::new(Mutex::new(HashMap::new())),
+            jobs: Arc::new(crate::jobs::JobManager::in_memory()),
+            openrouter_oauth: Arc::new(Mutex::new(None)),
+            model_catalog: Arc::new(Mutex::new(None)),
+            chat_runs: Arc::new(Mutex::new(HashMap::new())),
+            tunnel_url: Arc::new(Mutex::new(None)),
+        };
 
-async fn rebuild_interest_profile(
-    config: &Config,
-    embedder: Arc<dyn memory::embeddings::EmbeddingProvider>,
-) -> Result<RebuildInterestProfileResult> {
-    if embedder.dimensions() == 0 {
-        return Ok(RebuildInterestProfileResult {
-            status: "embeddingUnavailable",
-            ..RebuildInterestProfileResult::default()
-        });
+        let response = handle_metrics(State(state)).await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response
+                .headers()
+                .get(header::CONTENT_TYPE)
+                .and_then(|value| value.to_str().ok()),
+            Some(PROMETHEUS_CONTENT_TYPE)
+        );
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let text = String::from_utf8(body.to_vec()).unwrap();
+        assert!(text.contains("Prometheus backend not enabled"));
     }
 
-    let workspace_dir = &config.workspace_dir;
-    let _ = local_store::decay_feed_interests(workspace_dir, INTEREST_DECAY_RATE)?;
-    let mut active_interests: Vec<ActiveInterest> = local_store::list_feed_interests(workspace_dir)?
-        .into_iter()
-        .map(|record| ActiveInterest {
-            embedding: bytes_to_vec(&record.embedding),
-            record,
-        })
-        .filter(|interest| !interest.embedding.is_empty())
-        .collect();
+    #[tokio::test]
+    async fn metrics_endpoint_renders_prometheus_output() {
+        let prom = Arc::new(crate::observability::PrometheusObserver::new());
+        crate::observability::Observer::record_event(
+            prom.as_ref(),
+            &crate::observability::ObserverEvent::HeartbeatTick,
+        );
 
-    let items = list_workspace_library_items(workspace_dir, "feed", 2_000)?;
-    let text_items: Vec<serde_json::Value> = items
-        .into_iter()
-        .filter(|item| item.get("kind").and_then(serde_json::Value::as_str) == Some("text"))
-        .collect();
+        let observer: Arc<dyn crate::observability::Observer> = prom;
+        let state = AppState {
+            config: Arc::new(Mutex::new(Config::default())),
+            provider: Arc::new(MockProvider::default()),
+            model: "test-model".into(),
+            temperature: 0.0,
+            mem: Arc::new(MockMemory),
+            auto_save: false,
+            webhook_secret_hash: None,
+            pairing: Arc::new(PairingGuard::new(false, &[])),
+            trusted_proxies: TrustedProxies::default(),
+            rate_limiter: Arc::new(GatewayRateLimiter::new(100, 100, 100)),
+            idempotency_store: Arc::new(IdempotencyStore::new(Duration::from_secs(300), 1000)),
+            pb_chat_base_url: None,
+            pb_chat_collection: "chat_messages".into(),
+            pb_chat_token: None,
+            observer,
+            journal_transcription_jobs: Arc::new(Mutex::new(HashMap::new())),
+            jobs: Arc::new(crate::jobs::JobManager::in_memory()),
+            openrouter_oauth: Arc::new(Mutex::new(None)),
+            model_catalog: Arc::new(Mutex::new(None)),
+            chat_runs: Arc::new(Mutex::new(HashMap::new())),
+            tunnel_url: Arc::new(Mutex::new(None)),
+        };
 
-    let mut stats = InterestProfileStats {
-        source_count: text_items.len(),
-        ..InterestProfileStats::default()
-    };
+        let response = handle_metrics(State(state)).await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
 
-    for item in text_items {
-        let Some(path) = item.get("path").and_then(serde_json::Value::as_str) else {
-            continue;
-        };
-        let abs_path = workspace_dir.join(path);
-        let content = match tokio::fs::read_to_string(&abs_path).await {
-            Ok(content) => content,
-            Err(_) => continue,
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let text = String::from_utf8(body.to_vec()).unwrap();
+        assert!(text.contains("zeroclaw_heartbeat_ticks_total 1"));
+    }
+
+    #[tokio::test]
+    async fn metrics_endpoint_finds_prometheus_inside_multi_observer() {
+        let observer = crate::observability::create_observer(&crate::config::ObservabilityConfig {
+            backends: vec!["log".into(), "prometheus".into()],
+            ..crate::config::ObservabilityConfig::default()
+        });
+        observer.record_event(&crate::observability::ObserverEvent::HeartbeatTick);
+        let state = AppState {
+            observer: Arc::from(observer),
+            ..test_app_state_with_config(Config::default())
         };
-        let trimmed = content.trim();
-        if trimmed.is_empty() {
-            continue;
-        }
-        let content_hash = content_hash_16(trimmed);
-        if let Some(previous) = local_store::get_feed_interest_source(workspace_dir, path)? {
-            if previous.content_hash == content_hash {
-                continue;
-            }
-        }
 
-        let label = derive_interest_label(
-            item.get("title")
-                .and_then(serde_json::Value::as_str)
-                .unwrap_or("Workspace interest"),
-            trimmed,
-        );
-        let embedding = embedder
-            .embed_one(trimmed)
-            .await
-            .with_context(|| format!("Failed to embed feed source {path}"))?;
+        let response = handle_metrics(State(state)).await.into_response();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let text = String::from_utf8(body.to_vec()).unwrap();
+        assert!(text.contains("zeroclaw_heartbeat_ticks_total 1"));
+    }
+    #[tokio::test]
+    async fn activity_endpoint_filters_and_orders_recent_events() {
+        let observer: Arc<dyn crate::observability::Observer> =
+            Arc::new(crate::observability::MultiObserver::new(vec![
+                Box::new(crate::observability::NoopObserver),
+                Box::new(crate::observability::ActivityObserver::new(false)),
+            ]));
+        observer.record_event(&crate::observability::ObserverEvent::ToolCall {
+            tool: "shell".into(),
+            duration: Duration::from_millis(5),
+            success: true,
+        });
+        observer.record_event(&crate::observability::ObserverEvent::Error {
+            component: "provider".into(),
+            message: "timeout".into(),
+        });
+        observer.record_event(&crate::observability::ObserverEvent::Error {
+            component: "gateway".into(),
+            message: "x".repeat(500),
+        });
+        let state = AppState {
+            observer,
+            ..test_app_state_with_config(Config::default())
+        };
 
-        let mut best_match: Option<(usize, f32)> = None;
-        for (index, interest) in active_interests.iter().enumerate() {
-            let similarity = cosine_similarity(&embedding, &interest.embedding);
-            if best_match
-                .as_ref()
-                .map(|(_, current_best)| similarity > *current_best)
-                .unwrap_or(true)
-            {
-                best_match = Some((index, similarity));
-            }
-        }
+        let query = |since: Option<&str>, kind: Option<&str>| ActivityQuery {
+            since: since.map(str::to_string),
+            kind: kind.map(str::to_string),
+            limit: None,
+        };
+        let response = handle_activity(
+            State(state.clone()),
+            HeaderMap::new(),
+            Query(query(None, Some("error"))),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let parsed: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let items = parsed["items"].as_array().unwrap();
+        assert_eq!(items.len(), 2);
+        assert!(items[0]["summary"]
+            .as_str()
+            .unwrap()
+            .starts_with("gateway: xxx"));
+        assert!(items[0]["summary"].as_str().unwrap().len() < 200);
+        assert!(items[0].get("detail").is_none());
+        assert_eq!(items[1]["summary"], "provider: timeout");
+
+        let future = (chrono::Utc::now() + chrono::Duration::minutes(1)).to_rfc3339();
+        let response = handle_activity(
+            State(state.clone()),
+            HeaderMap::new(),
+            Query(query(Some(&future), None)),
+        )
+        .await
+        .into_response();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let parsed: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert!(parsed["items"].as_array().unwrap().is_empty());
 
-        let now = Utc::now().to_rfc3339();
-        let mapped_interest_id = if let Some((index, similarity)) = best_match {
-            if similarity >= INTEREST_MERGE_THRESHOLD {
-                let current = active_interests[index].clone();
-                let merged_embedding = ema_merge_vectors(&embedding, &current.embedding);
-                let next_label = if current.record.label.trim().is_empty() {
-                    label.clone()
-                } else {
-                    current.record.label.clone()
-                };
-                let updated = local_store::upsert_feed_interest(
-                    workspace_dir,
-                    &local_store::FeedInterestUpsert {
-                        id: Some(current.record.id.clone()),
-                        label: next_label,
-                        source_path: path.to_string(),
-                        embedding: vec_to_bytes(&merged_embedding),
-                        health_score: 1.0,
-                        last_seen_at: now.clone(),
-                    },
-                )?;
-                active_interests[index] = ActiveInterest {
-                    embedding: merged_embedding,
-                    record: updated.clone(),
-                };
-                stats.refreshed_sources += 1;
-                stats.merged_count += 1;
-                Some(updated.id)
-            } else if similarity >= INTEREST_SPAWN_THRESHOLD {
-                let created = local_store::upsert_feed_interest(
-                    workspace_dir,
-                    &local_store::FeedInterestUpsert {
-                        id: None,
-                        label: label.clone(),
-                        source_path: path.to_string(),
-                        embedding: vec_to_bytes(&embedding),
-                        health_score: 1.0,
-                        last_seen_at: now.clone(),
-                    },
-                )?;
-                active_interests.push(ActiveInterest {
-                    embedding,
-                    record: created.clone(),
-                });
-                stats.refreshed_sources += 1;
-                stats.spawned_count += 1;
-                Some(created.id)
-            } else {
-                stats.ignored_count += 1;
-                None
-            }
-        } else {
-            let created = local_store::upsert_feed_interest(
-                workspace_dir,
-                &local_store::FeedInterestUpsert {
-                    id: None,
-                    label: label.clone(),
-                    source_path: path.to_string(),
-                    embedding: vec_to_bytes(&embedding),
-                    health_score: 1.0,
-                    last_seen_at: now.clone(),
-                },
-            )?;
-            active_interests.push(ActiveInterest {
-                embedding,
-                record: created.clone(),
-            });
-            stats.refreshed_sources += 1;
-            stats.spawned_count += 1;
-            Some(created.id)
+        let response = handle_activity(
+            State(state),
+            HeaderMap::new(),
+            Query(query(None, Some("bogus"))),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn status_heartbeat_summarizes_counts_and_top_errors() {
+        let stats = crate::observability::stats::RuntimeStats {
+            uptime_secs: 90_061,
+            inbound_messages: HashMap::from([("webhook".to_string(), 4), ("chat".to_string(), 7)]),
+            llm_calls: 11,
+            llm_failures: 1,
+            tool_runs: 5,
+            tool_failures: 0,
+            errors: 2,
+            top_errors: vec![("provider: timeout".to_string(), 2)],
         };
+        assert_eq!(
+            format_status_heartbeat(&stats),
+            "Status: still alive (gateway up 1d 1h 1m)\n\
+             - Webhook requests: 4\n\
+             - Chat messages: 7\n\
+             - LLM calls: 11 (1 failed)\n\
+             - Tool runs: 5 (0 failed)\n\
+             - Errors: 2\n\
+             Top errors:\n\
+             - 2x provider: timeout"
+        );
+    }
+    #[test]
+    fn pocketbase_watch_fires_only_after_a_sustained_outage() {
+        let threshold = Duration::from_secs(600);
+        let start = Instant::now();
+        let mut watch = PocketBaseWatch::default();
+        assert!(!watch.observe(false, start, threshold), "never seen healthy");
+        assert!(!watch.observe(false, start + threshold, threshold));
 
-        local_store::upsert_feed_interest_source(
-            workspace_dir,
-            &local_store::FeedInterestSourceRecord {
-                source_path: path.to_string(),
-                content_hash,
-                profile_input_hash: String::new(),
-                interest_id: mapped_interest_id,
-                title: label,
-                triage_keywords_json: String::new(),
-                updated_at: now,
-            },
-        )?;
+        assert!(!watch.observe(true, start, threshold));
+        assert!(!watch.observe(false, start + Duration::from_secs(60), threshold));
+        assert!(watch.observe(false, start + Duration::from_secs(660), threshold));
+        assert!(!watch.observe(true, start + Duration::from_secs(700), threshold));
+        assert!(!watch.observe(false, start + Duration::from_secs(760), threshold));
     }
 
-    stats.interest_count = active_interests.len();
-    Ok(RebuildInterestProfileResult {
-        status: if active_interests.is_empty() {
-            "noInterests"
-        } else {
-            "ready"
-        },
-        stats,
-        interests: active_interests,
-    })
-}
+    #[tokio::test]
+    async fn jobs_endpoints_list_and_report_missing_jobs() {
+        let state = test_app_state_with_config(Config::default());
+        let job_id = state.jobs.enqueue("test_job", async {
+            Ok(crate::jobs::JobOutcome::default())
+        });
 
-fn best_interest_match(
-    embedding: &[f32],
-    interests: &[ActiveInterest],
-) -> (f32, f32, Option<String>) {
-    let mut best_weighted = 0.0_f32;
-    let mut best_similarity = 0.0_f32;
-    let mut best_label: Option<String> = None;
-    for interest in interests {
-        let similarity = cosine_similarity(embedding, &interest.embedding);
-        let weighted = similarity * interest.record.health_score as f32;
-        if weighted > best_weighted {
-            best_weighted = weighted;
-            best_similarity = similarity;
-            best_label = Some(interest.record.label.clone());
-        }
+        let response = handle_jobs_list(
+            State(state.clone()),
+            HeaderMap::new(),
+            Query(JobsListQuery { limit: None }),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(payload["items"][0]["id"], job_id);
+        assert_eq!(payload["items"][0]["kind"], "test_job");
+
+        let response = handle_job_get(
+            State(state),
+            HeaderMap::new(),
+            AxumPath("missing".to_string()),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
     }
-    (best_weighted, best_similarity, best_label)
-}
 
-async fn rank_bluesky_candidates(
-    embedder: Arc<dyn memory::embeddings::EmbeddingProvider>,
-    interests: &[ActiveInterest],
-    candidates: Vec<CandidateFeedPost>,
-) -> Result<Vec<PersonalizedBlueskyItem>> {
-    let mut ranked = Vec::new();
-    for candidate in candidates {
-        let feed_source = candidate.feed_source.clone();
-        let trimmed = candidate.text.trim();
-        if trimmed.is_empty() {
-            ranked.push(PersonalizedBlueskyItem {
-                source_type: "bluesky".to_string(),
-                feed_item: candidate.feed_item,
-                web_preview: None,
-                feed_source,
-                score: None,
-                matched_interest_label: None,
-                matched_interest_score: None,
-                passed_threshold: false,
-            });
-            continue;
-        }
+    #[tokio::test]
+    async fn security_budget_reports_persisted_weighted_actions() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut config = Config {
+            workspace_dir: tmp.path().to_path_buf(),
+            ..Config::default()
+        };
+        config.autonomy.max_actions_per_hour = 10;
+        config
+            .autonomy
+            .tool_action_weights
+            .insert("render_text_card_video".into(), 4);
+        let policy =
+            crate::security::SecurityPolicy::from_config(&config.autonomy, &config.workspace_dir);
+        assert!(policy.record_tool_action("render_text_card_video"));
+        let state = test_app_state_with_config(config);
 
-        let embedding = embedder
-            .embed_one(trimmed)
+        let response = handle_security_budget(State(state), HeaderMap::new())
             .await
-            .context("Failed to embed Bluesky candidate post")?;
+            .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(payload["limit"], 10);
+        assert_eq!(payload["used"], 4);
+        assert_eq!(payload["remaining"], 6);
+        assert_eq!(payload["toolWeights"]["render_text_card_video"], 4);
+    }
 
-        let (best_weighted, best_similarity, best_label) =
-            best_interest_match(&embedding, interests);
+    #[tokio::test]
+    async fn approval_decisions_need_a_paired_token_and_settle_once() {
+        let tmp = tempfile::tempdir().unwrap();
+        let config = Config {
+            workspace_dir: tmp.path().to_path_buf(),
+            ..Config::default()
+        };
+        let request = crate::approval::ApprovalRequest {
+            tool_name: "bluesky_post".into(),
+            arguments: serde_json::json!({ "text": "hi" }),
+        };
+        let parked = crate::approval::chat::park(
+            tmp.path(),
+            &request,
+            None,
+            Duration::from_secs(60),
+        )
+        .unwrap();
+        let id = parked["id"].as_str().unwrap().to_string();
 
-        ranked.push(PersonalizedBlueskyItem {
-            source_type: "bluesky".to_string(),
-            feed_item: candidate.feed_item,
-            web_preview: None,
-            feed_source,
-            score: Some(best_weighted),
-            matched_interest_label: best_label,
-            matched_interest_score: if best_similarity > 0.0 {
-                Some(best_similarity)
-            } else {
-                None
-            },
-            passed_threshold: best_weighted >= FEED_MATCH_THRESHOLD,
-        });
+        let mut state = test_app_state_with_config(config);
+        let response = handle_approval_approve(
+            State(state.clone()),
+            HeaderMap::new(),
+            AxumPath(id.clone()),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        state.pairing = Arc::new(PairingGuard::new(true, &["zc_approver".into()]));
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_static("Bearer zc_approver"),
+        );
+        let response = handle_approval_approve(
+            State(state.clone()),
+            headers.clone(),
+            AxumPath(id.clone()),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(payload["status"], "approved");
+        assert_eq!(payload["decidedBy"], "api");
+
+        let response = handle_approval_deny(State(state), headers, AxumPath(id))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
     }
 
-    let mut filtered: Vec<PersonalizedBlueskyItem> =
-        ranked.into_iter().filter(|item| item.passed_threshold).collect();
-    sort_personalized_items(&mut filtered);
-    Ok(filtered)
-}
+    #[tokio::test]
+    async fn media_thumb_rejects_unsupported_types() {
+        let tmp = tempfile::tempdir().unwrap();
+        let audio_dir = tmp.path().join("journals/media/audio");
+        std::fs::create_dir_all(&audio_dir).unwrap();
+        std::fs::write(audio_dir.join("voice.m4a"), b"not audio").unwrap();
+        let config = Config {
+            workspace_dir: tmp.path().to_path_buf(),
+            ..Config::default()
+        };
+        let state = test_app_state_with_config(config);
 
-async fn rank_web_candidates(
-    workspace_dir: &StdPath,
-    embedder: Arc<dyn memory::embeddings::EmbeddingProvider>,
-    interests: &[ActiveInterest],
-    candidates: Vec<CandidateWebResult>,
-    limit: usize,
-) -> Result<Vec<PersonalizedBlueskyItem>> {
-    let mut ranked = Vec::new();
-    for candidate in candidates {
-        let combined = format!("{}\n{}", candidate.title.trim(), candidate.description.trim());
-        let trimmed = combined.trim();
-        if trimmed.is_empty() {
-            continue;
-        }
+        let response = handle_media_thumb(
+            State(state.clone()),
+            HeaderMap::new(),
+            AxumPath("journals/media/audio/voice.m4a".to_string()),
+            Query(MediaThumbQuery { w: None }),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
 
-        let embedding = embedder
-            .embed_one(trimmed)
-            .await
-            .with_context(|| format!("Failed to embed web candidate {}", candidate.url))?;
-        let (best_weighted, best_similarity, best_label) =
-            best_interest_match(&embedding, interests);
-        if best_weighted < FEED_MATCH_THRESHOLD {
-            continue;
-        }
+        let response = handle_media_thumb(
+            State(state),
+            HeaderMap::new(),
+            AxumPath("journals/media/image/missing.png".to_string()),
+            Query(MediaThumbQuery { w: Some(320) }),
+        )
+        .await;
+        assert_ne!(response.status(), StatusCode::OK);
+    }
 
-        let preview = resolve_web_preview(workspace_dir, &candidate).await;
-        ranked.push(PersonalizedBlueskyItem {
-            source_type: "web".to_string(),
-            feed_item: serde_json::json!({
-                "url": candidate.url,
-                "title": candidate.title,
-                "description": candidate.description,
-                "domain": candidate.domain,
+    #[tokio::test]
+    async fn search_rejects_short_queries_and_finds_journal_text() {
+        let tmp = tempfile::tempdir().unwrap();
+        let rel = "journals/text/2026-03-01/visa.md";
+        std::fs::create_dir_all(tmp.path().join("journals/text/2026-03-01")).unwrap();
+        std::fs::write(tmp.path().join(rel), "Visa appointment moved to Friday").unwrap();
+        search_index::index_journal_file(tmp.path(), rel).unwrap();
+        let config = Config {
+            workspace_dir: tmp.path().to_path_buf(),
+            ..Config::default()
+        };
+        let state = test_app_state_with_config(config);
+
+        let response = handle_search(
+            State(state.clone()),
+            HeaderMap::new(),
+            Query(SearchQuery {
+                q: Some("v".into()),
+                scope: None,
+                limit: None,
+                offset: None,
             }),
-            web_preview: Some(preview),
-            feed_source: None,
-            score: Some(best_weighted),
-            matched_interest_label: best_label,
-            matched_interest_score: if best_similarity > 0.0 {
-                Some(best_similarity)
-            } else {
-                None
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = handle_search(
+            State(state),
+            HeaderMap::new(),
+            Query(SearchQuery {
+                q: Some("visa".into()),
+                scope: Some("journal".into()),
+                limit: Some(5),
+                offset: None,
+            }),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(payload["items"][0]["path"], rel);
+        assert_eq!(payload["items"][0]["source"], "journal");
+        assert_eq!(payload["hasMore"], false);
+    }
+
+    #[tokio::test]
+    async fn library_tags_update_and_filter_items() {
+        let tmp = tempfile::tempdir().unwrap();
+        let text_dir = tmp.path().join("journals/text/2026-03-01");
+        std::fs::create_dir_all(&text_dir).unwrap();
+        std::fs::write(text_dir.join("trip.md"), "Packed for the trip").unwrap();
+        std::fs::write(text_dir.join("work.md"), "Quarterly planning").unwrap();
+        let config = Config {
+            workspace_dir: tmp.path().to_path_buf(),
+            ..Config::default()
+        };
+        let state = test_app_state_with_config(config);
+
+        let response = handle_library_tags(
+            State(state.clone()),
+            HeaderMap::new(),
+            Json(LibraryTagsBody {
+                path: "journals/text/2026-03-01/trip.md".into(),
+                add: vec![" Travel ".into(), "#family".into(), "travel".into()],
+                remove: vec![],
+            }),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(payload["tags"], serde_json::json!(["travel", "family"]));
+
+        let items =
+            list_workspace_library_items(tmp.path(), "journal", 20, Some("Travel"), "").unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0]["path"], "journals/text/2026-03-01/trip.md");
+        assert_eq!(items[0]["tags"], serde_json::json!(["travel", "family"]));
+
+        let all = list_workspace_library_items(tmp.path(), "journal", 20, None, "").unwrap();
+        assert_eq!(all.len(), 2, "tag sidecars must not be listed as items");
+    }
+
+    #[test]
+    fn library_items_carry_metadata_status_and_transcript() {
+        let tmp = tempfile::tempdir().unwrap();
+        let audio = "journals/media/audio/2026/03/01/memo.mp3";
+        let plain = "journals/media/audio/2026/03/01/other.mp3";
+        for rel in [audio, plain] {
+            std::fs::create_dir_all(tmp.path().join(rel).parent().unwrap()).unwrap();
+            std::fs::write(tmp.path().join(rel), b"ID3").unwrap();
+        }
+        let transcript = "journals/text/transcriptions/audio/2026/03/01/memo.txt";
+        std::fs::create_dir_all(tmp.path().join(transcript).parent().unwrap()).unwrap();
+        std::fs::write(tmp.path().join(transcript), "hello").unwrap();
+
+        // No metadata store yet: the listing is the plain filesystem view.
+        let items = list_workspace_library_items(tmp.path(), "journal", 20, None, "").unwrap();
+        let memo = items.iter().find(|item| item["path"] == audio).unwrap();
+        assert!(memo.get("status").is_none());
+        assert_eq!(memo["transcriptPath"], transcript);
+
+        local_store::initialize(tmp.path()).unwrap();
+        local_store::create_media_asset_metadata(
+            tmp.path(),
+            &local_store::MediaAssetInput {
+                title: "memo".into(),
+                entry_id: String::new(),
+                asset_type: "audio".into(),
+                mime_type: "audio/mpeg".into(),
+                source: "mobile".into(),
+                status: "uploaded".into(),
+                workspace_path: audio.into(),
+                size_bytes: 3,
+                created_at_client: None,
+                probe: None,
+                tags_csv: String::new(),
             },
-            passed_threshold: true,
-        });
+        )
+        .unwrap();
+        local_store::set_library_item_status(tmp.path(), audio, "error").unwrap();
+        local_store::set_media_asset_error(tmp.path(), audio, "ffmpeg exited 1").unwrap();
+
+        let items = list_workspace_library_items(tmp.path(), "journal", 20, None, "").unwrap();
+        let memo = items.iter().find(|item| item["path"] == audio).unwrap();
+        assert_eq!(memo["status"], "error");
+        assert_eq!(memo["error"], "ffmpeg exited 1");
+        assert!(memo["entryId"].is_null());
+        let other = items.iter().find(|item| item["path"] == plain).unwrap();
+        assert!(other.get("status").is_none());
+        assert!(other.get("transcriptPath").is_none());
     }
 
-    sort_personalized_items(&mut ranked);
-    ranked.truncate(limit);
-    Ok(ranked)
-}
+    #[tokio::test]
+    async fn library_delete_moves_files_to_trash_and_restore_brings_them_back() {
+        let tmp = tempfile::tempdir().unwrap();
+        let rel = "journals/text/2026-03-01/note.md";
+        std::fs::create_dir_all(tmp.path().join("journals/text/2026-03-01")).unwrap();
+        std::fs::write(tmp.path().join(rel), "keep me").unwrap();
+        library_tags::write_tags(&tmp.path().join(rel), &["keep".to_string()]).unwrap();
+        let config = Config {
+            workspace_dir: tmp.path().to_path_buf(),
+            ..Config::default()
+        };
+        let state = test_app_state_with_config(config);
+
+        let response = handle_library_delete(
+            State(state.clone()),
+            HeaderMap::new(),
+            Json(DeleteLibraryBody { path: rel.into() }),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let trash_id = payload["trashId"].as_str().unwrap().to_string();
+        assert_eq!(
+            payload["removedRelated"],
+            serde_json::json!([format!("{rel}.tags.json")])
+        );
+        assert!(!tmp.path().join(rel).exists());
+
+        let response = handle_library_trash(State(state.clone()), HeaderMap::new()).await;
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(payload["items"][0]["id"], trash_id.as_str());
+        assert_eq!(payload["retentionDays"], 30);
+
+        let response = handle_library_trash_restore(
+            State(state.clone()),
+            HeaderMap::new(),
+            Json(TrashRestoreBody {
+                id: "missing".into(),
+                path: None,
+            }),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
 
-async fn collect_ranked_bluesky_matches(
-    service_url: &str,
-    access_jwt: &str,
-    embedder: Arc<dyn memory::embeddings::EmbeddingProvider>,
-    interests: &[ActiveInterest],
-    limit: usize,
-) -> Result<(Vec<PersonalizedBlueskyItem>, Vec<CandidateFeedPost>)> {
-    let mut matched = Vec::new();
-    let mut raw_candidates = Vec::new();
-    let mut seen = BTreeSet::new();
-    let target_matches = limit.min(BLUESKY_PERSONALIZED_MATCH_LIMIT);
-    let mut candidate_sources = match collect_ranked_bluesky_feed_sources(
-        service_url,
-        access_jwt,
-        embedder.clone(),
-        interests,
-    )
-    .await
-    {
-        Ok(sources) => sources,
-        Err(err) => {
-            tracing::warn!("Failed to rank Bluesky feed generators: {err}");
-            Vec::new()
-        }
-    };
-    append_unique_bluesky_sources(&mut candidate_sources, default_bluesky_candidate_sources());
+        let response = handle_library_trash_restore(
+            State(state),
+            HeaderMap::new(),
+            Json(TrashRestoreBody {
+                id: trash_id,
+                path: None,
+            }),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            std::fs::read_to_string(tmp.path().join(rel)).unwrap(),
+            "keep me"
+        );
+        assert_eq!(library_tags::read_tags(&tmp.path().join(rel)), vec!["keep"]);
+    }
 
-    for source in candidate_sources {
-        let mut cursor: Option<String> = None;
-        for _ in 0..BLUESKY_PERSONALIZED_PAGE_LIMIT_PER_SOURCE {
-            let (page, next_cursor) = fetch_bluesky_candidate_page(
-                service_url,
-                access_jwt,
-                &source,
-                cursor.as_deref(),
-                BLUESKY_PERSONALIZED_PAGE_SIZE,
-            )
-            .await?;
-            if page.is_empty() {
-                break;
-            }
+    #[tokio::test]
+    async fn library_export_streams_zip_with_sidecars_and_cleans_up() {
+        let tmp = tempfile::tempdir().unwrap();
+        let rel = "journals/media/audio/2026/03/01/memo.m4a";
+        std::fs::create_dir_all(tmp.path().join("journals/media/audio/2026/03/01")).unwrap();
+        std::fs::create_dir_all(tmp.path().join("journals/text/transcriptions/audio/2026/03/01"))
+            .unwrap();
+        std::fs::write(tmp.path().join(rel), "audio").unwrap();
+        std::fs::write(
+            tmp.path()
+                .join("journals/text/transcriptions/audio/2026/03/01/memo.txt"),
+            "hello",
+        )
+        .unwrap();
+        std::fs::write(tmp.path().join("journals/media/audio/2026/03/01/other.m4a"), "x").unwrap();
+        library_tags::write_tags(&tmp.path().join(rel), &["walk".to_string()]).unwrap();
+        let config = Config {
+            workspace_dir: tmp.path().to_path_buf(),
+            ..Config::default()
+        };
+        let state = test_app_state_with_config(config);
 
-            let unique_page = dedupe_candidate_posts(page, &mut seen);
-            if !unique_page.is_empty() {
-                raw_candidates.extend(unique_page.clone());
-                let mut ranked_page =
-                    rank_bluesky_candidates(embedder.clone(), interests, unique_page).await?;
-                matched.append(&mut ranked_page);
-                if matched.len() >= target_matches {
-                    sort_personalized_items(&mut matched);
-                    matched.truncate(target_matches);
-                    return Ok((matched, raw_candidates));
-                }
-            }
+        let response = handle_library_export(
+            State(state.clone()),
+            HeaderMap::new(),
+            Query(LibraryExportQuery {
+                path: Some("../etc/passwd".into()),
+                job: None,
+            }),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
 
-            let Some(next_cursor) = next_cursor.filter(|value| !value.trim().is_empty()) else {
-                break;
-            };
-            cursor = Some(next_cursor);
-        }
+        let response = handle_library_export(
+            State(state),
+            HeaderMap::new(),
+            Query(LibraryExportQuery {
+                path: Some(rel.into()),
+                job: None,
+            }),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::CONTENT_DISPOSITION],
+            "attachment; filename=\"memo.m4a.zip\""
+        );
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let archive = zip::ZipArchive::new(std::io::Cursor::new(body.to_vec())).unwrap();
+        let mut names: Vec<&str> = archive.file_names().collect();
+        names.sort_unstable();
+        assert_eq!(
+            names,
+            vec!["manifest.json", "memo.m4a", "memo.m4a.tags.json", "memo.txt"]
+        );
+        let exports = tmp.path().join(library_export::EXPORTS_DIR);
+        assert_eq!(std::fs::read_dir(exports).unwrap().count(), 0);
     }
 
-    sort_personalized_items(&mut matched);
-    matched.truncate(target_matches);
-    Ok((matched, raw_candidates))
-}
+    #[test]
+    fn storage_paths_keep_unicode_titles_distinct() {
+        let tmp = tempfile::tempdir().unwrap();
+        let german = text_journal_rel_path(tmp.path(), "Grüße aus Köln");
+        let hindi = text_journal_rel_path(tmp.path(), "नमस्ते दुनिया");
+        assert!(german.ends_with("_Grüße_aus_Köln.md"), "{german}");
+        assert!(hindi.ends_with("_नमस्ते_दुनिया.md"), "{hindi}");
 
-fn modified_unix_secs(path: &StdPath) -> i64 {
-    path.metadata()
-        .ok()
-        .and_then(|meta| meta.modified().ok())
-        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
-        .map(|duration| i64::try_from(duration.as_secs()).unwrap_or(0))
-        .unwrap_or(0)
-}
+        // The same title within the same second gets a suffix, not an overwrite.
+        let abs = tmp.path().join(&german);
+        std::fs::create_dir_all(abs.parent().unwrap()).unwrap();
+        std::fs::write(&abs, "first").unwrap();
+        assert_ne!(text_journal_rel_path(tmp.path(), "Grüße aus Köln"), german);
 
-fn sync_file_allowed(path: &str) -> bool {
-    let normalized = path.trim().trim_start_matches('/').replace('\\', "/");
-    if normalized.is_empty() || normalized.contains("..") {
-        return false;
+        let media = media_storage_rel_path(tmp.path(), "audio", "Sprachnotiz 🎙️.m4a");
+        assert!(media.starts_with("journals/media/audio/"), "{media}");
+        assert!(media.ends_with("_Sprachnotiz_🎙️.m4a"), "{media}");
+        assert!(text_journal_rel_path(tmp.path(), "...").ends_with("_journal.md"));
     }
-    if normalized == "feed_workflow_settings.json" {
-        return true;
+
+    #[test]
+    fn resolve_errors_map_to_400_for_escapes_403_for_read_only_and_404_otherwise() {
+        for (err, status) in [
+            (ResolveError::Escapes, StatusCode::BAD_REQUEST),
+            (ResolveError::NotFound, StatusCode::NOT_FOUND),
+            (ResolveError::NotAFile, StatusCode::NOT_FOUND),
+            (ResolveError::NotADirectory, StatusCode::NOT_FOUND),
+            (ResolveError::ReadOnly, StatusCode::FORBIDDEN),
+        ] {
+            assert_eq!(media_path_error_response(err).0, status, "{err:?}");
+            assert_eq!(library_path_error_response(err).0, status, "{err:?}");
+        }
+    }
+
+    fn path_error_test_state(tmp: &tempfile::TempDir) -> AppState {
+        std::fs::create_dir_all(tmp.path().join("ws/journals/media/audio")).unwrap();
+        std::fs::create_dir_all(tmp.path().join("ws/journals/text")).unwrap();
+        std::fs::write(tmp.path().join("outside.m4a"), "secret").unwrap();
+        test_app_state_with_config(Config {
+            workspace_dir: tmp.path().join("ws"),
+            ..Config::default()
+        })
     }
-    SYNC_ALLOWED_ROOTS
-        .iter()
-        .any(|root| normalized == *root || normalized.starts_with(&format!("{root}/")))
-}
 
-fn collect_sync_files_recursive(
-    workspace_dir: &StdPath,
-    dir: &StdPath,
-    out: &mut Vec<WorkspaceSyncFile>,
-) -> Result<()> {
-    let entries = match std::fs::read_dir(dir) {
-        Ok(entries) => entries,
-        Err(_) => return Ok(()),
-    };
+    async fn error_code(response: axum::response::Response) -> (StatusCode, String) {
+        let status = response.status();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        (
+            status,
+            payload["code"].as_str().unwrap_or_default().to_string(),
+        )
+    }
 
-    for entry in entries {
-        let entry = match entry {
-            Ok(entry) => entry,
-            Err(_) => continue,
-        };
-        let path = entry.path();
-        let metadata = match entry.metadata() {
-            Ok(metadata) => metadata,
-            Err(_) => continue,
-        };
-        if metadata.is_dir() {
-            collect_sync_files_recursive(workspace_dir, &path, out)?;
-            continue;
-        }
-        if !metadata.is_file() {
-            continue;
-        }
-        let rel = match path.strip_prefix(workspace_dir) {
-            Ok(rel) => rel.to_string_lossy().replace('\\', "/"),
-            Err(_) => continue,
+    #[tokio::test]
+    async fn media_stream_distinguishes_missing_from_escaping_paths() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = path_error_test_state(&tmp);
+        let stream = |path: &str| {
+            handle_media_stream(
+                State(state.clone()),
+                AxumPath(path.to_string()),
+                Request::builder().body(axum::body::Body::empty()).unwrap(),
+            )
         };
-        if !sync_file_allowed(&rel) {
-            continue;
+
+        let not_found = (StatusCode::NOT_FOUND, "MEDIA_FILE_NOT_FOUND".to_string());
+        let invalid = (StatusCode::BAD_REQUEST, "MEDIA_PATH_INVALID".to_string());
+        // NotFound (even without the parent dir) and NotAFile.
+        for path in [
+            "journals/media/audio/missing.m4a",
+            "journals/media/video/2026/missing.mp4",
+            "journals/media/audio",
+        ] {
+            assert_eq!(error_code(stream(path).await).await, not_found, "{path}");
+        }
+        // Escapes: traversal, or outside journals/.
+        for path in [
+            "../outside.m4a",
+            "journals/../../outside.m4a",
+            "state/x.m4a",
+        ] {
+            assert_eq!(error_code(stream(path).await).await, invalid, "{path}");
+        }
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(
+                tmp.path().join("outside.m4a"),
+                tmp.path().join("ws/journals/media/audio/leak.m4a"),
+            )
+            .unwrap();
+            assert_eq!(
+                error_code(stream("journals/media/audio/leak.m4a").await).await,
+                invalid
+            );
         }
-        let bytes = match std::fs::read(&path) {
-            Ok(bytes) => bytes,
-            Err(_) => continue,
-        };
-        out.push(WorkspaceSyncFile {
-            path: rel,
-            modified_at: modified_unix_secs(&path),
-            content_base64: BASE64_STANDARD.encode(bytes),
-        });
     }
-    Ok(())
-}
 
-fn export_local_store_blob(workspace_dir: &StdPath) -> Result<Option<LocalStoreSyncBlob>> {
-    let db_path = local_store::db_path(workspace_dir);
-    if !db_path.exists() {
-        return Ok(None);
-    }
+    #[tokio::test]
+    async fn library_save_text_rejects_stale_base_revision() {
+        async fn save(
+            state: &AppState,
+            content: &str,
+            base_revision: Option<&str>,
+            force: bool,
+        ) -> (StatusCode, serde_json::Value) {
+            let response = handle_library_save_text(
+                State(state.clone()),
+                HeaderMap::new(),
+                Json(SaveTextBody {
+                    path: "journals/text/shared.md".into(),
+                    content: content.into(),
+                    base_revision: base_revision.map(str::to_string),
+                    force,
+                }),
+            )
+            .await;
+            let status = response.status();
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            (status, serde_json::from_slice(&body).unwrap())
+        }
+
+        let tmp = tempfile::tempdir().unwrap();
+        let state = path_error_test_state(&tmp);
+        let file = tmp.path().join("ws/journals/text/shared.md");
+
+        // Creating with an empty base revision, then editing from it.
+        let (status, created) = save(&state, "phone draft", Some(""), false).await;
+        assert_eq!(status, StatusCode::OK, "{created}");
+        let base = created["revision"].as_str().unwrap().to_string();
+        let (status, laptop) = save(&state, "laptop edit", Some(&base), false).await;
+        assert_eq!(status, StatusCode::OK, "{laptop}");
+
+        // The phone still holds the first revision.
+        let (status, conflict) = save(&state, "phone edit", Some(&base), false).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(conflict["code"], "LIBRARY_TEXT_CONFLICT");
+        assert_eq!(conflict["content"], "laptop edit");
+        assert_eq!(conflict["revision"], laptop["revision"]);
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "laptop edit");
+
+        // Forcing overwrites but still versions the laptop's text.
+        let (status, forced) = save(&state, "phone edit", Some(&base), true).await;
+        assert_eq!(status, StatusCode::OK, "{forced}");
+        let version = forced["version"]["id"].as_str().unwrap();
+        let workspace = tmp.path().join("ws");
+        assert_eq!(
+            text_versions::read_version(&workspace, "journals/text/shared.md", version).unwrap(),
+            b"laptop edit"
+        );
 
-    {
-        let conn = rusqlite::Connection::open(&db_path)
-            .with_context(|| format!("Failed to open sync export DB {}", db_path.display()))?;
-        let _ = conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE);");
+        // No base revision keeps last-write-wins.
+        let (status, _) = save(&state, "legacy client", None, false).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "legacy client");
     }
 
-    let bytes = std::fs::read(&db_path)
-        .with_context(|| format!("Failed to read local store DB {}", db_path.display()))?;
-    Ok(Some(LocalStoreSyncBlob {
-        modified_at: modified_unix_secs(&db_path),
-        content_base64: BASE64_STANDARD.encode(bytes),
-    }))
-}
+    #[tokio::test]
+    async fn library_delete_and_export_distinguish_missing_from_escaping_paths() {
+        let tmp = tempfile::tempdir().unwrap();
+        let state = path_error_test_state(&tmp);
+
+        let not_found = (StatusCode::NOT_FOUND, "LIBRARY_FILE_NOT_FOUND");
+        let invalid = (StatusCode::BAD_REQUEST, "LIBRARY_PATH_INVALID");
+        let cases = [
+            ("journals/media/audio/missing.m4a", not_found),
+            ("journals/text/missing.md", not_found),
+            ("journals/text", not_found),
+            ("journals/media/../../../outside.m4a", invalid),
+            ("secrets/keys.md", invalid),
+        ];
+        for (path, (status, code)) in cases {
+            let response = handle_library_delete(
+                State(state.clone()),
+                HeaderMap::new(),
+                Json(DeleteLibraryBody { path: path.into() }),
+            )
+            .await;
+            assert_eq!(
+                error_code(response).await,
+                (status, code.to_string()),
+                "delete {path}"
+            );
 
-fn export_workspace_sync_snapshot(workspace_dir: &StdPath) -> Result<WorkspaceSyncSnapshot> {
-    let mut files = Vec::new();
-    for root in SYNC_ALLOWED_ROOTS {
-        collect_sync_files_recursive(workspace_dir, &workspace_dir.join(root), &mut files)?;
-    }
-    let workflow_settings = workspace_dir.join("feed_workflow_settings.json");
-    if workflow_settings.exists() && workflow_settings.is_file() {
-        let bytes = std::fs::read(&workflow_settings)
-            .with_context(|| format!("Failed to read {}", workflow_settings.display()))?;
-        files.push(WorkspaceSyncFile {
-            path: "feed_workflow_settings.json".to_string(),
-            modified_at: modified_unix_secs(&workflow_settings),
-            content_base64: BASE64_STANDARD.encode(bytes),
-        });
+            let response = handle_library_export(
+                State(state.clone()),
+                HeaderMap::new(),
+                Query(LibraryExportQuery {
+                    path: Some(path.into()),
+                    job: None,
+                }),
+            )
+            .await;
+            assert_eq!(
+                error_code(response).await,
+                (status, code.to_string()),
+                "export {path}"
+            );
+        }
     }
-    files.sort_by(|left, right| left.path.cmp(&right.path));
-    Ok(WorkspaceSyncSnapshot {
-        exported_at: Utc::now().timestamp(),
-        files,
-        local_store: export_local_store_blob(workspace_dir)?,
-    })
-}
 
-fn import_workspace_sync_snapshot(
-    workspace_dir: &StdPath,
-    snapshot: &WorkspaceSyncSnapshot,
-) -> Result<(usize, bool)> {
-    let mut imported_files = 0_usize;
-    for file in &snapshot.files {
-        if !sync_file_allowed(&file.path) {
-            continue;
-        }
-        let abs_path = workspace_dir.join(&file.path);
-        let current_modified = modified_unix_secs(&abs_path);
-        if abs_path.exists() && current_modified > file.modified_at {
-            continue;
-        }
-        let bytes = BASE64_STANDARD
-            .decode(file.content_base64.as_bytes())
-            .with_context(|| format!("Failed to decode sync file {}", file.path))?;
-        if let Some(parent) = abs_path.parent() {
-            std::fs::create_dir_all(parent)
-                .with_context(|| format!("Failed to create {}", parent.display()))?;
-        }
-        std::fs::write(&abs_path, bytes)
-            .with_context(|| format!("Failed to write synced file {}", abs_path.display()))?;
-        imported_files += 1;
+    #[tokio::test]
+    async fn gateway_info_reports_null_tunnel_until_one_is_up() {
+        let state = test_app_state_with_config(Config::default());
+        let response = handle_gateway_info(State(state.clone()), HeaderMap::new()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert!(payload["tunnel_url"].is_null());
+        assert_eq!(payload["require_pairing"], false);
+
+        *state.tunnel_url.lock() = Some("https://slowclaw.example.trycloudflare.com".into());
+        let response = handle_gateway_info(State(state.clone()), HeaderMap::new()).await;
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            payload["tunnel_url"],
+            "https://slowclaw.example.trycloudflare.com"
+        );
+
+        let health = handle_health(State(state.clone())).await.into_response();
+        let body = health.into_body().collect().await.unwrap().to_bytes();
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            payload["tunnel_url"],
+            "https://slowclaw.example.trycloudflare.com"
+        );
+
+        state.config.lock().gateway.base_path = "slowclaw/".into();
+        let response = handle_gateway_info(State(state), HeaderMap::new()).await;
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            payload["tunnel_url"],
+            "https://slowclaw.example.trycloudflare.com/slowclaw"
+        );
+        assert_eq!(payload["base_path"], "/slowclaw");
     }
 
-    let mut imported_db = false;
-    if let Some(local_store_blob) = &snapshot.local_store {
-        let db_path = local_store::db_path(workspace_dir);
-        let local_modified = modified_unix_secs(&db_path);
-        if !db_path.exists() || local_modified <= local_store_blob.modified_at {
-            let bytes = BASE64_STANDARD
-                .decode(local_store_blob.content_base64.as_bytes())
-                .context("Failed to decode synced local store")?;
-            if let Some(parent) = db_path.parent() {
-                std::fs::create_dir_all(parent)
-                    .with_context(|| format!("Failed to create {}", parent.display()))?;
-            }
-            let temp_path = db_path.with_extension("db.sync.tmp");
-            std::fs::write(&temp_path, bytes)
-                .with_context(|| format!("Failed to write temp DB {}", temp_path.display()))?;
-            std::fs::rename(&temp_path, &db_path)
-                .with_context(|| format!("Failed to replace DB {}", db_path.display()))?;
-            let wal_path = db_path.with_extension("db-wal");
-            if wal_path.exists() {
-                let _ = std::fs::remove_file(&wal_path);
-            }
-            let shm_path = db_path.with_extension("db-shm");
-            if shm_path.exists() {
-                let _ = std::fs::remove_file(&shm_path);
+    #[tokio::test]
+    async fn provider_status_reports_circuits_and_reset_reopens_primary() {
+        let plain = test_app_state_with_config(Config::default());
+        let response = handle_provider_status(State(plain.clone()), HeaderMap::new()).await;
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(payload["supported"], false);
+        let response = handle_provider_reset(State(plain), HeaderMap::new()).await;
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+
+        let reliable = crate::providers::reliable::ReliableProvider::new(
+            vec![("primary".into(), Box::new(MockProvider::default()))],
+            0,
+            1,
+        );
+        let state = AppState {
+            provider: Arc::new(reliable),
+            ..test_app_state_with_config(Config::default())
+        };
+        state.provider.simple_chat("hi", "test-model", 0.0).await.unwrap();
+
+        let response = handle_provider_status(State(state.clone()), HeaderMap::new()).await;
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(payload["supported"], true);
+        assert_eq!(payload["status"]["active"], "primary");
+        assert_eq!(payload["status"]["backends"][0]["successes"], 1);
+        assert_eq!(payload["status"]["backends"][0]["circuit"], "closed");
+
+        let response = handle_provider_reset(State(state), HeaderMap::new()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    struct CatalogProvider {
+        fail: bool,
+        list_calls: std::sync::atomic::AtomicUsize,
+    }
+
+    #[async_trait]
+    impl Provider for CatalogProvider {
+        async fn chat_with_system(
+            &self,
+            _system_prompt: Option<&str>,
+            _message: &str,
+            _model: &str,
+            _temperature: f64,
+        ) -> anyhow::Result<String> {
+            Ok("ok".into())
+        }
+
+        async fn list_models(
+            &self,
+            _default_model: &str,
+        ) -> anyhow::Result<Vec<crate::providers::ModelInfo>> {
+            self.list_calls
+                .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            if self.fail {
+                anyhow::bail!("OpenRouter API error (503): upstream unavailable");
             }
-            imported_db = true;
+            Ok(vec![
+                crate::providers::ModelInfo {
+                    context_length: Some(128_000),
+                    pricing: Some(crate::providers::ModelPricing {
+                        prompt: Some("0.00000015".into()),
+                        completion: Some("0.0000006".into()),
+                    }),
+                    ..crate::providers::ModelInfo::id_only("openai/gpt-4o-mini")
+                },
+                crate::providers::ModelInfo::id_only("anthropic/claude-sonnet-4"),
+            ])
         }
     }
 
-    Ok((imported_files, imported_db))
-}
-
-async fn create_journal_entry_metadata(
-    state: &AppState,
-    rel_path: &str,
-    title: &str,
-    content: &str,
-    source: &str,
-    tags: Option<&[String]>,
-) -> Result<serde_json::Value> {
-    let preview = truncate_with_ellipsis(content, 240);
-    let workspace_dir = state.config.lock().workspace_dir.clone();
-    local_store::create_journal_entry_metadata(
-        &workspace_dir,
-        &local_store::JournalEntryInput {
-            title: title.to_string(),
-            entry_type: "text".to_string(),
-            source: source.to_string(),
-            status: "raw".to_string(),
-            workspace_path: rel_path.to_string(),
-            preview_text: preview,
-            text_body: content.to_string(),
-            tags_csv: tags.map(|t| t.join(",")).unwrap_or_default(),
-            created_at_client: Some(chrono::Utc::now().to_rfc3339()),
-        },
-    )
-}
+    async fn models_payload(state: &AppState, refresh: bool) -> serde_json::Value {
+        let response = handle_models(
+            State(state.clone()),
+            HeaderMap::new(),
+            Query(ModelsQuery { refresh }),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        serde_json::from_slice(&body).unwrap()
+    }
 
-async fn upsert_media_asset_metadata(
-    state: &AppState,
-    rel_path: &str,
-    content_type: &str,
-    kind: &str,
-    title: Option<&str>,
-    source: &str,
-    bytes: u64,
-    entry_id: Option<&str>,
-) -> Result<serde_json::Value> {
-    let workspace_dir = state.config.lock().workspace_dir.clone();
-    local_store::create_media_asset_metadata(
-        &workspace_dir,
-        &local_store::MediaAssetInput {
-            title: title.unwrap_or("").to_string(),
-            entry_id: entry_id.unwrap_or("").to_string(),
-            asset_type: kind.to_string(),
-            mime_type: content_type.to_string(),
-            source: source.to_string(),
-            status: "uploaded".to_string(),
-            workspace_path: rel_path.to_string(),
-            size_bytes: bytes as i64,
-            created_at_client: Some(chrono::Utc::now().to_rfc3339()),
-        },
-    )
-}
+    #[tokio::test]
+    async fn models_endpoint_caches_filters_and_falls_back_to_default_model() {
+        let plain = test_app_state_with_config(Config::default());
+        let payload = models_payload(&plain, false).await;
+        assert_eq!(
+            payload["items"],
+            serde_json::json!([{
+                "id": "test-model", "name": null, "contextLength": null, "pricing": null
+            }])
+        );
 
-/// POST /webhook — main webhook endpoint
-async fn handle_webhook(
-    State(state): State<AppState>,
-    ConnectInfo(peer_addr): ConnectInfo<SocketAddr>,
-    headers: HeaderMap,
-    body: Result<Json<WebhookBody>, axum::extract::rejection::JsonRejection>,
-) -> impl IntoResponse {
-    let rate_key =
-        client_key_from_request(Some(peer_addr), &headers, state.trust_forwarded_headers);
-    if !state.rate_limiter.allow_webhook(&rate_key) {
-        tracing::warn!("/webhook rate limit exceeded");
-        let err = serde_json::json!({
-            "error": "Too many webhook requests. Please retry later.",
-            "retry_after": RATE_LIMIT_WINDOW_SECS,
+        let provider = Arc::new(CatalogProvider {
+            fail: false,
+            list_calls: std::sync::atomic::AtomicUsize::new(0),
         });
-        return (StatusCode::TOO_MANY_REQUESTS, Json(err));
+        let mut config = Config::default();
+        config.gateway.allowed_models = vec!["openai/gpt-4o-mini".into()];
+        let state = AppState {
+            provider: provider.clone(),
+            ..test_app_state_with_config(config)
+        };
+        let payload = models_payload(&state, false).await;
+        let ids: Vec<&str> = payload["items"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|m| m["id"].as_str().unwrap())
+            .collect();
+        assert_eq!(ids, vec!["test-model", "openai/gpt-4o-mini"]);
+        assert_eq!(payload["items"][1]["contextLength"], 128_000);
+        assert_eq!(payload["items"][1]["pricing"]["completion"], "0.0000006");
+        assert_eq!(payload["cached"], false);
+
+        assert_eq!(models_payload(&state, false).await["cached"], true);
+        assert_eq!(
+            provider
+                .list_calls
+                .load(std::sync::atomic::Ordering::SeqCst),
+            1
+        );
+        assert_eq!(models_payload(&state, true).await["cached"], false);
+        assert_eq!(
+            provider
+                .list_calls
+                .load(std::sync::atomic::Ordering::SeqCst),
+            2
+        );
+
+        let failing = AppState {
+            provider: Arc::new(CatalogProvider {
+                fail: true,
+                list_calls: std::sync::atomic::AtomicUsize::new(0),
+            }),
+            ..test_app_state_with_config(Config::default())
+        };
+        let payload = models_payload(&failing, false).await;
+        assert_eq!(payload["items"][0]["id"], "test-model");
+        assert!(payload["error"].as_str().unwrap().contains("503"));
+        assert!(failing.model_catalog.lock().is_none());
+    }
+
+    fn register_chat_run(state: &AppState, message_id: &str, thread_id: &str) -> CancellationToken {
+        let token = CancellationToken::new();
+        state.chat_runs.lock().insert(
+            message_id.to_string(),
+            InFlightChatRun {
+                thread_id: thread_id.to_string(),
+                token: token.clone(),
+            },
+        );
+        token
     }
 
-    // ── Bearer token auth (pairing) ──
-    if state.pairing.require_pairing() {
-        let auth = headers
-            .get(header::AUTHORIZATION)
-            .and_then(|v| v.to_str().ok())
-            .unwrap_or("");
-        let token = auth.strip_prefix("Bearer ").unwrap_or("");
-        if !state.pairing.is_authenticated(token) {
-            tracing::warn!("Webhook: rejected — not paired / invalid bearer token");
-            let err = serde_json::json!({
-                "error": "Unauthorized — pair first via POST /pair, then send Authorization: Bearer <token>"
-            });
-            return (StatusCode::UNAUTHORIZED, Json(err));
-        }
+    #[tokio::test]
+    async fn chat_cancel_triggers_tokens_by_thread_or_message() {
+        let state = test_app_state_with_config(Config::default());
+        let first = register_chat_run(&state, "lc_1", "thread-a");
+        let second = register_chat_run(&state, "lc_2", "thread-a");
+        let other = register_chat_run(&state, "lc_3", "thread-b");
+
+        let cancel = |thread_id: Option<&str>, message_id: Option<&str>| {
+            handle_chat_cancel(
+                State(state.clone()),
+                HeaderMap::new(),
+                Json(ChatCancelBody {
+                    thread_id: thread_id.map(str::to_string),
+                    message_id: message_id.map(str::to_string),
+                }),
+            )
+        };
+
+        let response = cancel(None, None).await.into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = cancel(None, Some("lc_2")).await.into_response();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(payload["cancelled"], serde_json::json!(["lc_2"]));
+        assert!(second.is_cancelled());
+        assert!(!first.is_cancelled());
+
+        let response = cancel(Some("thread-a"), None).await.into_response();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(payload["cancelled"], serde_json::json!(["lc_1"]));
+        assert!(first.is_cancelled());
+        assert!(!other.is_cancelled());
     }
 
-    // ── Webhook secret auth (optional, additional layer) ──
-    if let Some(ref secret_hash) = state.webhook_secret_hash {
-        let header_hash = headers
-            .get("X-Webhook-Secret")
-            .and_then(|v| v.to_str().ok())
-            .map(str::trim)
-            .filter(|value| !value.is_empty())
-            .map(hash_webhook_secret);
-        match header_hash {
-            Some(val) if constant_time_eq(&val, secret_hash.as_ref()) => {}
-            _ => {
-                tracing::warn!("Webhook: rejected request — invalid or missing X-Webhook-Secret");
-                let err = serde_json::json!({"error": "Unauthorized — invalid or missing X-Webhook-Secret header"});
-                return (StatusCode::UNAUTHORIZED, Json(err));
-            }
-        }
+    #[tokio::test]
+    async fn chat_send_wait_returns_reply_or_accepted_on_timeout() {
+        let tmp = tempfile::tempdir().unwrap();
+        local_store::initialize(tmp.path()).unwrap();
+        let create = |role: &str, status: &str, reply_to_id: Option<&str>| {
+            local_store::create_chat_message(
+                tmp.path(),
+                "thread-a",
+                role,
+                "hello",
+                status,
+                "gateway-ui",
+                reply_to_id,
+                None,
+            )
+            .unwrap()["id"]
+                .as_str()
+                .unwrap()
+                .to_string()
+        };
+
+        let pending = create("user", "pending", None);
+        let (status, Json(payload)) =
+            wait_for_chat_result(tmp.path(), "thread-a", &pending, Duration::ZERO).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        assert_eq!(payload["messageId"], pending);
+        assert_eq!(payload["status"], "pending");
+
+        let answered = create("user", "processing", None);
+        create("assistant", "done", Some(&answered));
+        let (status, Json(payload)) =
+            wait_for_chat_result(tmp.path(), "thread-a", &answered, Duration::from_secs(5)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(payload["status"], "done");
+        assert_eq!(payload["reply"]["replyToId"], answered);
     }
 
-    // ── Parse body ──
-    let Json(webhook_body) = match body {
-        Ok(b) => b,
-        Err(e) => {
-            tracing::warn!("Webhook JSON parse error: {e}");
-            let err = serde_json::json!({
-                "error": "Invalid JSON body. Expected: {\"message\": \"...\"}"
-            });
-            return (StatusCode::BAD_REQUEST, Json(err));
-        }
-    };
+    #[tokio::test]
+    async fn chat_send_rejects_unknown_wait_mode() {
+        let tmp = tempfile::tempdir().unwrap();
+        let config = Config {
+            workspace_dir: tmp.path().to_path_buf(),
+            ..Config::default()
+        };
+        let response = handle_chat_send(
+            State(test_app_state_with_config(config)),
+            HeaderMap::new(),
+            Query(ChatSendQuery {
+                wait: Some("processing".into()),
+                timeout: None,
+            }),
+            Json(ChatSendBody {
+                thread_id: "thread-a".into(),
+                content: "hi".into(),
+                include_trace: false,
+                attachments: Vec::new(),
+            }),
+        )
+        .await
+        .into_response();
+        assert_eq!(
+            error_code(response).await,
+            (
+                StatusCode::BAD_REQUEST,
+                "CHAT_MESSAGE_INVALID_WAIT".to_string()
+            )
+        );
+    }
 
-    // ── Idempotency (optional) ──
-    if let Some(idempotency_key) = headers
-        .get("X-Idempotency-Key")
-        .and_then(|v| v.to_str().ok())
-        .map(str::trim)
-        .filter(|value| !value.is_empty())
-    {
-        if !state.idempotency_store.record_if_new(idempotency_key) {
-            tracing::info!("Webhook duplicate ignored (idempotency key: {idempotency_key})");
-            let body = serde_json::json!({
-                "status": "duplicate",
-                "idempotent": true,
-                "message": "Request already processed for this idempotency key"
-            });
-            return (StatusCode::OK, Json(body));
-        }
+    #[tokio::test]
+    async fn chat_cancel_command_stops_thread_or_reports_nothing_running() {
+        let tmp = tempfile::tempdir().unwrap();
+        local_store::initialize(tmp.path()).unwrap();
+        let config = Config {
+            workspace_dir: tmp.path().to_path_buf(),
+            ..Config::default()
+        };
+        let state = test_app_state_with_config(config);
+        let running = register_chat_run(&state, "lc_running", "thread-a");
+
+        let send = |thread_id: &str| {
+            handle_chat_send(
+                State(state.clone()),
+                HeaderMap::new(),
+                Query(ChatSendQuery::default()),
+                Json(ChatSendBody {
+                    thread_id: thread_id.to_string(),
+                    content: "/cancel".into(),
+                    include_trace: false,
+                    attachments: Vec::new(),
+                }),
+            )
+        };
+
+        let response = send("thread-a").await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(running.is_cancelled());
+        let messages = local_store::list_chat_messages(tmp.path(), "thread-a", 10).unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0]["status"], "done");
+
+        let response = send("thread-b").await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let messages = local_store::list_chat_messages(tmp.path(), "thread-b", 10).unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(
+            messages[1]["content"],
+            chat_commands::NOTHING_TO_CANCEL_REPLY
+        );
     }
 
-    let message = &webhook_body.message;
+    #[tokio::test]
+    async fn chat_settings_commands_update_the_thread_and_confirm() {
+        let tmp = tempfile::tempdir().unwrap();
+        local_store::initialize(tmp.path()).unwrap();
+        let mut config = Config {
+            workspace_dir: tmp.path().to_path_buf(),
+            default_model: Some("big-model".into()),
+            ..Config::default()
+        };
+        config.gateway.allowed_models = vec!["cheap-model".into()];
+        let state = test_app_state_with_config(config.clone());
+
+        let send = |content: &str| {
+            handle_chat_send(
+                State(state.clone()),
+                HeaderMap::new(),
+                Query(ChatSendQuery::default()),
+                Json(ChatSendBody {
+                    thread_id: "quick".into(),
+                    content: content.to_string(),
+                    include_trace: false,
+                    attachments: Vec::new(),
+                }),
+            )
+        };
+        let last_reply = || {
+            let messages = local_store::list_chat_messages(tmp.path(), "quick", 50).unwrap();
+            messages.last().unwrap()["content"]
+                .as_str()
+                .unwrap()
+                .to_string()
+        };
 
-    if state.auto_save {
-        let key = webhook_memory_key();
-        let _ = state
-            .mem
-            .store(&key, message, MemoryCategory::Conversation, None)
-            .await;
+        for (command, reply) in [
+            (
+                "/model other-model",
+                "`other-model` is not an allowed model. Choose one of: cheap-model.",
+            ),
+            (
+                "/model cheap-model",
+                "Model set to cheap-model for this thread.",
+            ),
+            ("/temp 9", "Temperature must be a number between 0 and 2."),
+            ("/TEMP 0.2", "Temperature set to 0.2 for this thread."),
+            ("/model", "This thread uses cheap-model."),
+            (
+                "/remind me at 5",
+                "Unknown command /remind. Send /help to see the available commands.",
+            ),
+        ] {
+            let response = send(command).await.into_response();
+            assert_eq!(response.status(), StatusCode::OK, "{command}");
+            assert_eq!(last_reply(), reply, "{command}");
+        }
+        let settings = local_store::get_chat_thread_settings(tmp.path(), "quick").unwrap();
+        assert_eq!(settings.model.as_deref(), Some("cheap-model"));
+        assert_eq!(settings.temperature, Some(0.2));
+
+        let mut run_config = config.clone();
+        apply_chat_thread_settings(&mut run_config, &settings);
+        assert_eq!(run_config.default_model.as_deref(), Some("cheap-model"));
+        assert!((run_config.default_temperature - 0.2).abs() < f64::EPSILON);
+
+        send("/model default").await;
+        assert_eq!(last_reply(), "Model reset to the default (big-model).");
+        let response = handle_chat_threads(
+            State(state.clone()),
+            HeaderMap::new(),
+            Query(ChatThreadListQuery { limit: None }),
+        )
+        .await
+        .into_response();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(payload["defaults"]["model"], "big-model");
+        assert_eq!(payload["items"][0]["threadId"], "quick");
+        assert!(payload["items"][0]["settings"]["model"].is_null());
+        assert_eq!(payload["items"][0]["settings"]["temperature"], 0.2);
+
+        // A model dropped from the allow-list is no longer applied.
+        config.gateway.allowed_models.clear();
+        let mut run_config = config.clone();
+        apply_chat_thread_settings(
+            &mut run_config,
+            &local_store::ChatThreadSettings {
+                model: Some("cheap-model".into()),
+                ..local_store::ChatThreadSettings::default()
+            },
+        );
+        assert_eq!(run_config.default_model.as_deref(), Some("big-model"));
     }
 
-    let provider_label = state
-        .config
-        .lock()
-        .default_provider
-        .clone()
-        .unwrap_or_else(|| "unknown".to_string());
-    let model_label = state.model.clone();
-    let started_at = Instant::now();
+    #[tokio::test]
+    async fn chat_thread_settings_update_validates_the_model_and_suffix() {
+        let tmp = tempfile::tempdir().unwrap();
+        local_store::initialize(tmp.path()).unwrap();
+        let mut config = Config {
+            workspace_dir: tmp.path().to_path_buf(),
+            ..Config::default()
+        };
+        config.gateway.allowed_models = vec!["cheap-model".into()];
+        let state = test_app_state_with_config(config);
+        let update = |model: &str, suffix: &str| {
+            handle_chat_thread_settings_update(
+                State(state.clone()),
+                HeaderMap::new(),
+                AxumPath("writing".to_string()),
+                Json(ChatThreadSettingsBody {
+                    model: Some(model.to_string()),
+                    temperature: None,
+                    system_suffix: Some(suffix.to_string()),
+                }),
+            )
+        };
 
-    state
-        .observer
-        .record_event(&crate::observability::ObserverEvent::AgentStart {
-            provider: provider_label.clone(),
-            model: model_label.clone(),
-        });
-    state
-        .observer
-        .record_event(&crate::observability::ObserverEvent::LlmRequest {
-            provider: provider_label.clone(),
-            model: model_label.clone(),
-            messages_count: 1,
-        });
+        let response = update("big-model", "").await.into_response();
+        assert_eq!(
+            error_code(response).await,
+            (
+                StatusCode::BAD_REQUEST,
+                "CHAT_THREAD_MODEL_NOT_ALLOWED".to_string()
+            )
+        );
+        let too_long = "x".repeat(WEBHOOK_MAX_SYSTEM_CHARS + 1);
+        let response = update("", &too_long).await.into_response();
+        assert_eq!(
+            error_code(response).await,
+            (
+                StatusCode::BAD_REQUEST,
+                "CHAT_THREAD_SUFFIX_TOO_LONG".to_string()
+            )
+        );
 
-    match run_gateway_chat_simple(&state, message).await {
-        Ok(response) => {
-            let duration = started_at.elapsed();
-            state
-                .observer
-                .record_event(&crate::observability::ObserverEvent::LlmResponse {
-                    provider: provider_label.clone(),
-                    model: model_label.clone(),
-                    duration,
-                    success: true,
-                    error_message: None,
-                    input_tokens: None,
-                    output_tokens: None,
-                });
-            state.observer.record_metric(
-                &crate::observability::traits::ObserverMetric::RequestLatency(duration),
-            );
-            state
-                .observer
-                .record_event(&crate::observability::ObserverEvent::AgentEnd {
-                    provider: provider_label,
-                    model: model_label,
-                    duration,
-                    tokens_used: None,
-                    cost_usd: None,
-                });
+        let response = update("cheap-model", " Write in long form. ")
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let settings = local_store::get_chat_thread_settings(tmp.path(), "writing").unwrap();
+        assert_eq!(settings.model.as_deref(), Some("cheap-model"));
+        assert_eq!(
+            settings.system_suffix.as_deref(),
+            Some("Write in long form.")
+        );
+    }
 
-            let body = serde_json::json!({"response": response, "model": state.model});
-            (StatusCode::OK, Json(body))
+    #[tokio::test]
+    async fn memory_endpoints_list_search_stats_and_forget() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mem = Arc::new(crate::memory::SqliteMemory::new(tmp.path()).unwrap());
+        mem.store(
+            "pref_editor",
+            "Prefers vim keybindings",
+            MemoryCategory::Core,
+            None,
+        )
+        .await
+        .unwrap();
+        for (key, content) in [
+            ("chat_a1", "Booked the dentist"),
+            ("chat_a2", "Dentist moved"),
+        ] {
+            mem.store(key, content, MemoryCategory::Conversation, Some("thread-a"))
+                .await
+                .unwrap();
         }
-        Err(e) => {
-            let duration = started_at.elapsed();
-            let sanitized = providers::sanitize_api_error(&e.to_string());
+        mem.store(
+            "chat_b1",
+            "Unrelated",
+            MemoryCategory::Conversation,
+            Some("thread-b"),
+        )
+        .await
+        .unwrap();
+        let state = AppState {
+            mem,
+            ..test_app_state_with_config(Config::default())
+        };
+        let json_of = |response: axum::response::Response| async move {
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+        };
+        let list = |query: MemoryListQuery| {
+            handle_memory_list(State(state.clone()), HeaderMap::new(), Query(query))
+        };
 
-            state
-                .observer
-                .record_event(&crate::observability::ObserverEvent::LlmResponse {
-                    provider: provider_label.clone(),
-                    model: model_label.clone(),
-                    duration,
-                    success: false,
-                    error_message: Some(sanitized.clone()),
-                    input_tokens: None,
-                    output_tokens: None,
-                });
-            state.observer.record_metric(
-                &crate::observability::traits::ObserverMetric::RequestLatency(duration),
-            );
-            state
-                .observer
-                .record_event(&crate::observability::ObserverEvent::Error {
-                    component: "gateway".to_string(),
-                    message: sanitized.clone(),
-                });
-            state
-                .observer
-                .record_event(&crate::observability::ObserverEvent::AgentEnd {
-                    provider: provider_label,
-                    model: model_label,
-                    duration,
-                    tokens_used: None,
-                    cost_usd: None,
-                });
+        let payload = json_of(
+            list(MemoryListQuery {
+                category: Some("conversation".into()),
+                session: Some("thread-a".into()),
+                ..MemoryListQuery::default()
+            })
+            .await
+            .into_response(),
+        )
+        .await;
+        assert_eq!(payload["total"], 2);
+        assert_eq!(payload["items"][0]["sessionId"], "thread-a");
+        assert_eq!(payload["items"][0]["category"], "conversation");
+
+        let payload = json_of(
+            list(MemoryListQuery {
+                q: Some("vim".into()),
+                ..MemoryListQuery::default()
+            })
+            .await
+            .into_response(),
+        )
+        .await;
+        assert_eq!(payload["items"][0]["key"], "pref_editor");
 
-            tracing::error!("Webhook provider error: {}", sanitized);
-            let err = serde_json::json!({"error": "LLM request failed"});
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(err))
-        }
-    }
-}
+        let payload = json_of(
+            handle_memory_stats(State(state.clone()), HeaderMap::new())
+                .await
+                .into_response(),
+        )
+        .await;
+        assert_eq!(payload["total"], 4);
+        assert_eq!(
+            payload["categories"],
+            serde_json::json!([
+                { "category": "conversation", "count": 3 },
+                { "category": "core", "count": 1 }
+            ])
+        );
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::memory::{Memory, MemoryCategory, MemoryEntry};
-    use crate::providers::Provider;
-    use async_trait::async_trait;
-    use axum::http::HeaderValue;
-    use axum::response::IntoResponse;
-    use http_body_util::BodyExt;
-    use parking_lot::Mutex;
-    use std::sync::atomic::{AtomicUsize, Ordering};
+        let forget = |key: &str| {
+            handle_memory_forget(
+                State(state.clone()),
+                HeaderMap::new(),
+                AxumPath(key.to_string()),
+            )
+        };
+        assert_eq!(
+            forget("pref_editor").await.into_response().status(),
+            StatusCode::OK
+        );
+        assert_eq!(
+            forget("pref_editor").await.into_response().status(),
+            StatusCode::NOT_FOUND
+        );
 
-    /// Generate a random hex secret at runtime to avoid hard-coded cryptographic values.
-    fn generate_test_secret() -> String {
-        let bytes: [u8; 32] = rand::random();
-        hex::encode(bytes)
+        let delete_session = |session: Option<&str>| {
+            handle_memory_delete_session(
+                State(state.clone()),
+                HeaderMap::new(),
+                Query(MemorySessionQuery {
+                    session: session.map(str::to_string),
+                }),
+            )
+        };
+        assert_eq!(
+            delete_session(None).await.into_response().status(),
+            StatusCode::BAD_REQUEST
+        );
+        let payload = json_of(delete_session(Some("thread-a")).await.into_response()).await;
+        assert_eq!(payload["deleted"], 2);
+        assert_eq!(state.mem.count().await.unwrap(), 1);
     }
 
-    fn test_app_state_with_config(config: Config) -> AppState {
-        AppState {
-            config: Arc::new(Mutex::new(config)),
-            provider: Arc::new(MockProvider::default()),
-            model: "test-model".into(),
-            temperature: 0.0,
-            mem: Arc::new(MockMemory),
-            auto_save: false,
-            webhook_secret_hash: None,
-            pairing: Arc::new(PairingGuard::new(false, &[])),
-            trust_forwarded_headers: false,
-            rate_limiter: Arc::new(GatewayRateLimiter::new(100, 100, 100)),
-            idempotency_store: Arc::new(IdempotencyStore::new(Duration::from_secs(300), 1000)),
-            pb_chat_base_url: None,
-            pb_chat_collection: "chat_messages".into(),
-            pb_chat_token: None,
-            observer: Arc::new(crate::observability::NoopObserver),
-            journal_transcription_jobs: Arc::new(Mutex::new(HashMap::new())),
-            openrouter_oauth: Arc::new(Mutex::new(None)),
+    #[tokio::test]
+    async fn memory_stats_and_session_delete_see_past_the_list_cap() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mem = Arc::new(crate::memory::SqliteMemory::new(tmp.path()).unwrap());
+        for i in 0..1002 {
+            mem.store(
+                &format!("chat_{i}"),
+                "turn",
+                MemoryCategory::Conversation,
+                Some("long"),
+            )
+            .await
+            .unwrap();
         }
+        mem.store("pref", "keep", MemoryCategory::Core, None)
+            .await
+            .unwrap();
+        let state = AppState {
+            mem,
+            ..test_app_state_with_config(Config::default())
+        };
+        let json_of = |response: axum::response::Response| async move {
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+        };
+
+        let stats = json_of(
+            handle_memory_stats(State(state.clone()), HeaderMap::new())
+                .await
+                .into_response(),
+        )
+        .await;
+        assert_eq!(stats["total"], 1003);
+
+        let deleted = json_of(
+            handle_memory_delete_session(
+                State(state.clone()),
+                HeaderMap::new(),
+                Query(MemorySessionQuery {
+                    session: Some("long".into()),
+                }),
+            )
+            .await
+            .into_response(),
+        )
+        .await;
+        assert_eq!(deleted["deleted"], 1002);
+        assert_eq!(state.mem.count().await.unwrap(), 1);
     }
 
-    #[test]
-    fn gateway_ui_profile_omits_shell_and_git_tools() {
-        let temp = tempfile::tempdir().unwrap();
-        let mut config = Config::default();
-        config.workspace_dir = temp.path().to_path_buf();
-        let security = Arc::new(crate::security::SecurityPolicy::from_config(
-            &config.autonomy,
-            &config.workspace_dir,
-        ));
-        let mem_cfg = crate::config::MemoryConfig {
-            backend: "markdown".into(),
-            ..crate::config::MemoryConfig::default()
+    #[tokio::test]
+    async fn memory_export_then_streamed_import_skips_existing_keys() {
+        let src_dir = tempfile::tempdir().unwrap();
+        let src = Arc::new(crate::memory::SqliteMemory::new(src_dir.path()).unwrap());
+        src.store("pref_editor", "Prefers vim", MemoryCategory::Core, None)
+            .await
+            .unwrap();
+        src.store(
+            "chat_a1",
+            "Booked the dentist",
+            MemoryCategory::Conversation,
+            Some("thread-a"),
+        )
+        .await
+        .unwrap();
+        let source = AppState {
+            mem: src,
+            ..test_app_state_with_config(Config::default())
         };
-        let mem: Arc<dyn crate::memory::Memory> =
-            Arc::from(crate::memory::create_memory(&mem_cfg, temp.path(), None).unwrap());
-        let tools = crate::tools::all_tools_with_runtime_and_profile(
-            Arc::new(config.clone()),
-            &security,
-            Arc::new(crate::runtime::NativeRuntime::new()),
-            gateway_ui_tool_profile(),
-            mem,
-            None,
-            None,
-            &crate::config::BrowserConfig::default(),
-            &crate::config::HttpRequestConfig::default(),
-            &crate::config::WebFetchConfig::default(),
-            temp.path(),
-            &HashMap::new(),
-            None,
-            &config,
+
+        let response = handle_memory_export(State(source), HeaderMap::new()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/x-ndjson"
+        );
+        let exported = response.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(std::str::from_utf8(&exported).unwrap().lines().count(), 2);
+
+        let dst_dir = tempfile::tempdir().unwrap();
+        let dst = Arc::new(crate::memory::SqliteMemory::new(dst_dir.path()).unwrap());
+        dst.store("pref_editor", "Prefers emacs", MemoryCategory::Core, None)
+            .await
+            .unwrap();
+        let target = AppState {
+            mem: dst,
+            ..test_app_state_with_config(Config::default())
+        };
+        // Split the upload mid-line so lines span body frames.
+        let mut upload = exported.to_vec();
+        upload.extend_from_slice(b"{broken");
+        let chunks: Vec<Result<axum::body::Bytes, std::io::Error>> = upload
+            .chunks(7)
+            .map(|chunk| Ok(axum::body::Bytes::copy_from_slice(chunk)))
+            .collect();
+        let request = Request::builder()
+            .method("POST")
+            .body(axum::body::Body::from_stream(futures_util::stream::iter(
+                chunks,
+            )))
+            .unwrap();
+
+        let response = handle_memory_import(
+            State(target.clone()),
+            Query(MemoryImportQuery::default()),
+            request,
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(payload["inserted"], 1);
+        assert_eq!(payload["skipped"], 1);
+        assert_eq!(payload["failed"], 1);
+        assert_eq!(
+            target
+                .mem
+                .get("pref_editor")
+                .await
+                .unwrap()
+                .unwrap()
+                .content,
+            "Prefers emacs"
         );
-        let names: Vec<&str> = tools.iter().map(|tool| tool.name()).collect();
-
-        for removed in ["shell", "git_operations"] {
-            assert!(
-                !names.contains(&removed),
-                "UI tool profile should not expose {removed}"
-            );
-            assert!(
-                tools.iter().find(|tool| tool.name() == removed).is_none(),
-                "UI tool profile should treat {removed} as unavailable"
-            );
-        }
+        let chat = target.mem.get("chat_a1").await.unwrap().unwrap();
+        assert_eq!(chat.session_id.as_deref(), Some("thread-a"));
     }
+    #[tokio::test]
+    async fn journal_text_applies_template_and_lists_available_on_miss() {
+        let tmp = tempfile::tempdir().unwrap();
+        let templates = tmp.path().join(journal_templates::TEMPLATES_DIR);
+        std::fs::create_dir_all(&templates).unwrap();
+        std::fs::write(
+            templates.join("daily.md"),
+            "# {{title}}\n\n## Highlights\n{{content}}\n",
+        )
+        .unwrap();
+        let config = Config {
+            workspace_dir: tmp.path().to_path_buf(),
+            ..Config::default()
+        };
+        let state = test_app_state_with_config(config);
 
-    #[test]
-    fn security_body_limit_is_64kb() {
-        assert_eq!(MAX_BODY_SIZE, 65_536);
-    }
+        let response = handle_journal_text(
+            State(state.clone()),
+            HeaderMap::new(),
+            Json(JournalTextBody {
+                title: Some("Sunday".into()),
+                content: "beach {{date}}".into(),
+                source: None,
+                tags: None,
+                template: Some("daily".into()),
+                mode: JournalTextMode::Create,
+            }),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let saved = std::fs::read_to_string(tmp.path().join(payload["path"].as_str().unwrap()))
+            .unwrap();
+        assert_eq!(saved, "# Sunday\n\n## Highlights\nbeach {{date}}\n");
 
-    #[test]
-    fn security_timeout_is_30_seconds() {
-        assert_eq!(REQUEST_TIMEOUT_SECS, 30);
+        let response = handle_journal_text(
+            State(state.clone()),
+            HeaderMap::new(),
+            Json(JournalTextBody {
+                title: None,
+                content: "x".into(),
+                source: None,
+                tags: None,
+                template: Some("weekly".into()),
+                mode: JournalTextMode::Create,
+            }),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(payload["available"], serde_json::json!(["daily"]));
+
+        let response = handle_journal_templates(State(state), HeaderMap::new()).await;
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(payload["items"][0]["path"], "templates/daily.md");
     }
 
-    #[test]
-    fn webhook_body_requires_message_field() {
-        let valid = r#"{"message": "hello"}"#;
-        let parsed: Result<WebhookBody, _> = serde_json::from_str(valid);
-        assert!(parsed.is_ok());
-        assert_eq!(parsed.unwrap().message, "hello");
+    #[tokio::test]
+    async fn journal_text_append_daily_grows_one_note_and_one_entry() {
+        let tmp = tempfile::tempdir().unwrap();
+        let config = Config {
+            workspace_dir: tmp.path().to_path_buf(),
+            ..Config::default()
+        };
+        local_store::initialize(tmp.path()).unwrap();
+        let state = test_app_state_with_config(config);
+        let append = |content: &str| {
+            handle_journal_text(
+                State(state.clone()),
+                HeaderMap::new(),
+                Json(JournalTextBody {
+                    title: None,
+                    content: content.into(),
+                    source: None,
+                    tags: Some(vec![content.into()]),
+                    template: None,
+                    mode: JournalTextMode::AppendDaily,
+                }),
+            )
+        };
 
-        let missing = r#"{"other": "field"}"#;
-        let parsed: Result<WebhookBody, _> = serde_json::from_str(missing);
-        assert!(parsed.is_err());
+        let (first, second) = tokio::join!(append("first thought"), append("second thought"));
+        assert_eq!(first.status(), StatusCode::OK);
+        assert_eq!(second.status(), StatusCode::OK);
+        let response = append("third thought").await;
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let rel_path = payload["path"].as_str().unwrap();
+        assert!(rel_path.starts_with("journals/text/") && rel_path.ends_with("/daily.md"));
+        let preview = payload["metadata"]["previewText"].as_str().unwrap();
+        assert!(preview.ends_with(" third thought"), "{preview}");
+
+        let note = std::fs::read_to_string(tmp.path().join(rel_path)).unwrap();
+        assert!(note.starts_with("# Daily note "), "{note}");
+        assert_eq!(note.matches("\n## ").count(), 3, "{note}");
+        for thought in ["first thought", "second thought", "third thought"] {
+            let section = format!("\n\n{thought}\n");
+            assert_eq!(note.matches(&section).count(), 1, "{note}");
+        }
+        assert!(note.ends_with("\n\nthird thought\n"));
+        assert_eq!(payload["tags"].as_array().unwrap().len(), 3);
+
+        let paths = local_store::list_library_metadata_paths(tmp.path(), "journals/text/").unwrap();
+        assert_eq!(paths, vec![rel_path.to_string()]);
+        match local_store::library_metadata(tmp.path(), rel_path).unwrap() {
+            Some(local_store::LibraryMetadata::Journal { text_body }) => {
+                assert_eq!(text_body, note);
+            }
+            other => panic!("expected journal metadata, got {other:?}"),
+        }
     }
 
-    #[test]
-    fn app_state_is_clone() {
-        fn assert_clone<T: Clone>() {}
-        assert_clone::<AppState>();
+    #[tokio::test]
+    async fn library_text_renders_sanitized_html_with_an_outline() {
+        let tmp = tempfile::tempdir().unwrap();
+        let note = tmp.path().join("journals/text/note.md");
+        std::fs::create_dir_all(note.parent().unwrap()).unwrap();
+        std::fs::write(
+            &note,
+            "# Trip\n\n<script>fetch('/pair')</script>\n\n## Photos\n\n![beach](../media/beach.jpg)\n",
+        )
+        .unwrap();
+        let config = Config {
+            workspace_dir: tmp.path().to_path_buf(),
+            ..Config::default()
+        };
+        let state = test_app_state_with_config(config);
+        let read = |render: Option<&str>| {
+            handle_library_text(
+                State(state.clone()),
+                HeaderMap::new(),
+                Query(LibraryTextQuery {
+                    path: "journals/text/note.md".into(),
+                    render: render.map(str::to_string),
+                }),
+            )
+        };
+
+        let response = read(Some("html")).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert!(payload["content"].as_str().unwrap().contains("<script>"));
+        let html = payload["html"].as_str().unwrap();
+        assert!(!html.contains("<script"), "{html}");
+        assert!(html.contains("/api/media/journals/media/beach.jpg"), "{html}");
+        assert_eq!(payload["wordCount"], 3);
+        assert_eq!(payload["outline"][1]["anchor"], "photos");
+
+        let response = read(None).await;
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert!(payload.get("html").is_none());
+        assert_eq!(payload["outline"][0]["text"], "Trip");
+
+        let response = read(Some("pdf")).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
     }
 
     #[tokio::test]
-    async fn metrics_endpoint_returns_hint_when_prometheus_is_disabled() {
-        let state = AppState {
-            config: Arc::new(Mutex::new(Config::default())),
-            provider: Arc::new(MockProvider::default()),
-            model: "test-model".into(),
-            temperature: 0.0,
-            mem: Arc::new(MockMemory),
-            auto_save: false,
-            webhook_secret_hash: None,
-            pairing: Arc::new(PairingGuard::new(false, &[])),
-            trust_forwarded_headers: false,
-            rate_limiter: Arc::new(GatewayRateLimiter::new(100, 100, 100)),
-            idempotency_store: Arc::new(IdempotencyStore::new(Duration::from_secs(300), 1000)),
-            pb_chat_base_url: None,
-            pb_chat_collection: "chat_messages".into(),
-            pb_chat_token: None,
-            observer: Arc::new(crate::observability::NoopObserver),
-            journal_transcription_jobs: Arc::new(Mutex::new(HashMap::new())),
-            openrouter_oauth: Arc::new(Mutex::new(None)),
+    async fn feed_pages_with_next_cursor_and_rejects_a_garbled_one() {
+        let tmp = tempfile::tempdir().unwrap();
+        for name in ["one.md", "two.md"] {
+            let path = tmp.path().join("posts").join(name);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, name).unwrap();
+        }
+        let config = Config {
+            workspace_dir: tmp.path().to_path_buf(),
+            ..Config::default()
+        };
+        let state = test_app_state_with_config(config);
+        let read = |cursor: Option<String>| {
+            handle_feed(
+                State(state.clone()),
+                HeaderMap::new(),
+                Query(FeedQuery {
+                    cursor,
+                    limit: Some(1),
+                }),
+            )
         };
 
-        let response = handle_metrics(State(state)).await.into_response();
+        let response = read(None).await;
         assert_eq!(response.status(), StatusCode::OK);
-        assert_eq!(
-            response
-                .headers()
-                .get(header::CONTENT_TYPE)
-                .and_then(|value| value.to_str().ok()),
-            Some(PROMETHEUS_CONTENT_TYPE)
-        );
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let first: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(first["items"].as_array().unwrap().len(), 1);
+        let cursor = first["nextCursor"].as_str().unwrap().to_string();
 
+        let response = read(Some(cursor)).await;
         let body = response.into_body().collect().await.unwrap().to_bytes();
-        let text = String::from_utf8(body.to_vec()).unwrap();
-        assert!(text.contains("Prometheus backend not enabled"));
+        let second: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_ne!(second["items"][0]["path"], first["items"][0]["path"]);
+        assert!(second["nextCursor"].is_null());
+
+        let response = read(Some("yesterday".into())).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(payload["code"], "FEED_CURSOR_INVALID");
     }
 
     #[tokio::test]
-    async fn metrics_endpoint_renders_prometheus_output() {
-        let prom = Arc::new(crate::observability::PrometheusObserver::new());
-        crate::observability::Observer::record_event(
-            prom.as_ref(),
-            &crate::observability::ObserverEvent::HeartbeatTick,
-        );
-
-        let observer: Arc<dyn crate::observability::Observer> = prom;
-        let state = AppState {
-            config: Arc::new(Mutex::new(Config::default())),
-            provider: Arc::new(MockProvider::default()),
-            model: "test-model".into(),
-            temperature: 0.0,
-            mem: Arc::new(MockMemory),
-            auto_save: false,
-            webhook_secret_hash: None,
-            pairing: Arc::new(PairingGuard::new(false, &[])),
-            trust_forwarded_headers: false,
-            rate_limiter: Arc::new(GatewayRateLimiter::new(100, 100, 100)),
-            idempotency_store: Arc::new(IdempotencyStore::new(Duration::from_secs(300), 1000)),
-            pb_chat_base_url: None,
-            pb_chat_collection: "chat_messages".into(),
-            pb_chat_token: None,
-            observer,
-            journal_transcription_jobs: Arc::new(Mutex::new(HashMap::new())),
-            openrouter_oauth: Arc::new(Mutex::new(None)),
+    async fn media_upload_is_rejected_and_cleaned_up_past_quota() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut config = Config {
+            workspace_dir: tmp.path().to_path_buf(),
+            ..Config::default()
+        };
+        config.gateway.media_quota_bytes = 100;
+        config.gateway.min_free_disk_bytes = 0;
+        let state = test_app_state_with_config(config);
+        let upload = |bytes: usize, declared: bool| {
+            let mut builder = Request::builder()
+                .method("POST")
+                .header(header::CONTENT_TYPE, "audio/mp4");
+            if declared {
+                builder = builder.header(header::CONTENT_LENGTH, bytes.to_string());
+            }
+            builder
+                .body(axum::body::Body::from(vec![0u8; bytes]))
+                .unwrap()
+        };
+        let query = |name: &str| MediaUploadQuery {
+            kind: Some("image".into()),
+            filename: Some(name.into()),
+            ..MediaUploadQuery::default()
         };
 
-        let response = handle_metrics(State(state)).await.into_response();
+        let response =
+            handle_media_upload(State(state.clone()), Query(query("a.jpg")), upload(60, true)).await;
         assert_eq!(response.status(), StatusCode::OK);
 
+        let response =
+            handle_media_upload(State(state.clone()), Query(query("b.jpg")), upload(60, true)).await;
+        assert_eq!(response.status(), StatusCode::INSUFFICIENT_STORAGE);
         let body = response.into_body().collect().await.unwrap().to_bytes();
-        let text = String::from_utf8(body.to_vec()).unwrap();
-        assert!(text.contains("zeroclaw_heartbeat_ticks_total 1"));
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(payload["code"], "MEDIA_QUOTA_EXCEEDED");
+        assert_eq!(payload["storage"]["mediaBytes"], 60);
+        assert_eq!(payload["storage"]["mediaQuotaBytes"], 100);
+
+        // Without a Content-Length the stream is cut off at the boundary.
+        let response =
+            handle_media_upload(State(state.clone()), Query(query("c.jpg")), upload(60, false)).await;
+        assert_eq!(response.status(), StatusCode::INSUFFICIENT_STORAGE);
+        storage::invalidate_media_usage(tmp.path());
+        assert_eq!(storage::media_usage_bytes(tmp.path()), 60);
+
+        let response = handle_storage(State(state), HeaderMap::new()).await;
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(payload["uploadAllowanceBytes"], 40);
     }
 
     #[test]
@@ -12874,6 +19723,16 @@ mod tests {
         assert!(!limiter.allow_pair("127.0.0.1"));
     }
 
+    #[test]
+    fn rate_limiter_allow_n_is_all_or_nothing() {
+        let limiter = GatewayRateLimiter::new(10, 5, 100);
+        assert!(limiter.allow_webhook_n("batch", 3));
+        assert!(!limiter.allow_webhook_n("batch", 3));
+        assert!(limiter.allow_webhook_n("batch", 2));
+        assert!(!limiter.allow_webhook("batch"));
+        assert!(limiter.allow_webhook_n("batch", 0));
+    }
+
     #[test]
     fn rate_limiter_sweep_removes_stale_entries() {
         let limiter = SlidingWindowRateLimiter::new(10, Duration::from_secs(60), 100);
@@ -13085,10 +19944,24 @@ mod tests {
             HeaderValue::from_static("198.51.100.10, 203.0.113.11"),
         );
 
-        let key = client_key_from_request(Some(peer), &headers, false);
+        let key = client_key_from_request(Some(peer), &headers, &TrustedProxies::default());
         assert_eq!(key, "10.0.0.5");
     }
 
+    #[test]
+    fn socket_peers_are_keyed_by_uid_and_ignore_forwarded_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Forwarded-For", HeaderValue::from_static("198.51.100.10"));
+        let trusted = TrustedProxies::from_config(true, &[]);
+
+        let key = peer_rate_key(GatewayPeer::Unix { uid: Some(1000) }, &headers, &trusted);
+        assert_eq!(key, "uds:uid:1000");
+        let key = peer_rate_key(GatewayPeer::Unix { uid: None }, &headers, &trusted);
+        assert_eq!(key, "uds");
+        let tcp = GatewayPeer::Tcp(SocketAddr::from(([10, 0, 0, 5], 42617)));
+        assert_eq!(peer_rate_key(tcp, &headers, &trusted), "198.51.100.10");
+    }
+
     #[test]
     fn client_key_uses_forwarded_ip_only_in_trusted_proxy_mode() {
         let peer = SocketAddr::from(([10, 0, 0, 5], 42617));
@@ -13098,8 +19971,68 @@ mod tests {
             HeaderValue::from_static("198.51.100.10, 203.0.113.11"),
         );
 
-        let key = client_key_from_request(Some(peer), &headers, true);
+        let legacy = TrustedProxies::from_config(true, &[]);
+        let key = client_key_from_request(Some(peer), &headers, &legacy);
+        assert_eq!(key, "198.51.100.10");
+    }
+
+    fn trusted(ranges: &[&str]) -> TrustedProxies {
+        let ranges: Vec<String> = ranges.iter().map(ToString::to_string).collect();
+        TrustedProxies::from_config(false, &ranges)
+    }
+
+    #[test]
+    fn client_key_ignores_forwarded_headers_from_an_untrusted_peer() {
+        let proxies = trusted(&["10.0.0.2/32", "fd00::/8"]);
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Forwarded-For", HeaderValue::from_static("198.51.100.10"));
+        headers.insert("X-Real-IP", HeaderValue::from_static("198.51.100.11"));
+
+        // A LAN client spoofing its address keeps its own bucket.
+        let spoofer = SocketAddr::from(([10, 0, 0, 9], 50000));
+        let key = client_key_from_request(Some(spoofer), &headers, &proxies);
+        assert_eq!(key, "10.0.0.9");
+
+        let proxy = SocketAddr::from(([10, 0, 0, 2], 50000));
+        let key = client_key_from_request(Some(proxy), &headers, &proxies);
         assert_eq!(key, "198.51.100.10");
+
+        let v6_proxy: SocketAddr = "[fd00::2]:50000".parse().unwrap();
+        let key = client_key_from_request(Some(v6_proxy), &headers, &proxies);
+        assert_eq!(key, "198.51.100.10");
+    }
+
+    #[test]
+    fn client_key_takes_the_rightmost_untrusted_hop_of_a_multi_hop_chain() {
+        let proxies = trusted(&["10.0.0.0/8"]);
+        let proxy = SocketAddr::from(([10, 0, 0, 2], 50000));
+        let mut headers = HeaderMap::new();
+        // The client prepended a fake hop; the CDN edge and inner proxy are
+        // appended by infrastructure.
+        headers.insert(
+            "X-Forwarded-For",
+            HeaderValue::from_static("1.2.3.4, 203.0.113.7, 10.1.0.3"),
+        );
+        let key = client_key_from_request(Some(proxy), &headers, &proxies);
+        assert_eq!(key, "203.0.113.7");
+
+        // A chain made only of trusted hops falls back to the leftmost one.
+        headers.insert(
+            "X-Forwarded-For",
+            HeaderValue::from_static("10.4.0.1, 10.1.0.3"),
+        );
+        let key = client_key_from_request(Some(proxy), &headers, &proxies);
+        assert_eq!(key, "10.4.0.1");
+
+        // Several header lines form one chain, in order.
+        let mut headers = HeaderMap::new();
+        headers.append("X-Forwarded-For", HeaderValue::from_static("203.0.113.7"));
+        headers.append(
+            "X-Forwarded-For",
+            HeaderValue::from_static("198.51.100.4, 10.1.0.3"),
+        );
+        let key = client_key_from_request(Some(proxy), &headers, &proxies);
+        assert_eq!(key, "198.51.100.4");
     }
 
     #[test]
@@ -13108,7 +20041,8 @@ mod tests {
         let mut headers = HeaderMap::new();
         headers.insert("X-Forwarded-For", HeaderValue::from_static("garbage-value"));
 
-        let key = client_key_from_request(Some(peer), &headers, true);
+        let legacy = TrustedProxies::from_config(true, &[]);
+        let key = client_key_from_request(Some(peer), &headers, &legacy);
         assert_eq!(key, "10.0.0.5");
     }
 
@@ -13130,59 +20064,217 @@ mod tests {
         let config_path = temp.path().join("config.toml");
         let workspace_path = temp.path().join("workspace");
 
-        let mut config = Config::default();
-        config.config_path = config_path.clone();
-        config.workspace_dir = workspace_path;
-        config.save().await.unwrap();
+        let mut config = Config::default();
+        config.config_path = config_path.clone();
+        config.workspace_dir = workspace_path;
+        config.save().await.unwrap();
+
+        let guard = PairingGuard::new(true, &[]);
+        let code = guard.pairing_code().unwrap();
+        let token = guard.try_pair(&code, "test_client").await.unwrap().unwrap();
+        assert!(guard.is_authenticated(&token));
+
+        let shared_config = Arc::new(Mutex::new(config));
+        persist_pairing_tokens(shared_config.clone(), &guard)
+            .await
+            .unwrap();
+
+        let saved = tokio::fs::read_to_string(config_path).await.unwrap();
+        let parsed: Config = toml::from_str(&saved).unwrap();
+        assert_eq!(parsed.gateway.paired_tokens.len(), 1);
+        let persisted = &parsed.gateway.paired_tokens[0];
+        assert!(persisted.starts_with("sha256$"));
+        assert!(!persisted.contains(&token));
+
+        let in_memory = shared_config.lock();
+        assert_eq!(in_memory.gateway.paired_tokens.len(), 1);
+        assert_eq!(&in_memory.gateway.paired_tokens[0], persisted);
+    }
+
+    #[test]
+    fn conversation_turn_keys_pair_user_and_reply() {
+        let first = ConversationTurnMemory::webhook(None);
+        let second = ConversationTurnMemory::webhook(None);
+        assert!(first.user_key.starts_with("webhook_msg_"));
+        assert_ne!(first.user_key, second.user_key);
+        assert_eq!(
+            first.reply_key.strip_prefix(WEBHOOK_REPLY_KEY_PREFIX),
+            first.user_key.strip_prefix(WEBHOOK_MESSAGE_KEY_PREFIX)
+        );
+        assert!(first.session.is_none());
+
+        let session = ConversationTurnMemory::webhook(Some("chat1"));
+        assert!(session.user_key.starts_with("webhook_msg_chat1_"));
+        assert_eq!(session.session.as_deref(), Some("webhook:chat1"));
+
+        let chat = ConversationTurnMemory::chat("thread-a");
+        assert!(chat.user_key.starts_with("chat_thread-a_"));
+        assert!(chat.reply_key.starts_with("chat_reply_thread-a_"));
+        assert_eq!(chat.session.as_deref(), Some("chat:thread-a"));
+    }
+
+    #[derive(Default)]
+    struct MockMemory;
+
+    #[async_trait]
+    impl Memory for MockMemory {
+        fn name(&self) -> &str {
+            "mock"
+        }
+
+        async fn store(
+            &self,
+            _key: &str,
+            _content: &str,
+            _category: MemoryCategory,
+            _session_id: Option<&str>,
+        ) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        async fn recall(
+            &self,
+            _query: &str,
+            _limit: usize,
+            _session_id: Option<&str>,
+        ) -> anyhow::Result<Vec<MemoryEntry>> {
+            Ok(Vec::new())
+        }
+
+        async fn get(&self, _key: &str) -> anyhow::Result<Option<MemoryEntry>> {
+            Ok(None)
+        }
+
+        async fn list(
+            &self,
+            _category: Option<&MemoryCategory>,
+            _session_id: Option<&str>,
+        ) -> anyhow::Result<Vec<MemoryEntry>> {
+            Ok(Vec::new())
+        }
+
+        async fn forget(&self, _key: &str) -> anyhow::Result<bool> {
+            Ok(false)
+        }
 
-        let guard = PairingGuard::new(true, &[]);
-        let code = guard.pairing_code().unwrap();
-        let token = guard.try_pair(&code, "test_client").await.unwrap().unwrap();
-        assert!(guard.is_authenticated(&token));
+        async fn count(&self) -> anyhow::Result<usize> {
+            Ok(0)
+        }
 
-        let shared_config = Arc::new(Mutex::new(config));
-        persist_pairing_tokens(shared_config.clone(), &guard)
-            .await
-            .unwrap();
+        async fn health_check(&self) -> bool {
+            true
+        }
+    }
 
-        let saved = tokio::fs::read_to_string(config_path).await.unwrap();
-        let parsed: Config = toml::from_str(&saved).unwrap();
-        assert_eq!(parsed.gateway.paired_tokens.len(), 1);
-        let persisted = &parsed.gateway.paired_tokens[0];
-        assert_eq!(persisted.len(), 64);
-        assert!(persisted.chars().all(|c| c.is_ascii_hexdigit()));
+    #[derive(Default)]
+    struct MockProvider {
+        calls: AtomicUsize,
+        /// `(system prompt, model, temperature)` of the latest call.
+        last_call: Mutex<Option<(Option<String>, String, f64)>>,
+        /// `(role, content)` of the non-system messages in the latest history call.
+        last_history: Mutex<Vec<(String, String)>>,
+    }
 
-        let in_memory = shared_config.lock();
-        assert_eq!(in_memory.gateway.paired_tokens.len(), 1);
-        assert_eq!(&in_memory.gateway.paired_tokens[0], persisted);
+    #[async_trait]
+    impl Provider for MockProvider {
+        async fn chat_with_history(
+            &self,
+            messages: &[ChatMessage],
+            model: &str,
+            temperature: f64,
+        ) -> anyhow::Result<String> {
+            *self.last_history.lock() = messages
+                .iter()
+                .filter(|m| m.role != "system")
+                .map(|m| (m.role.clone(), m.content.clone()))
+                .collect();
+            let system = messages
+                .iter()
+                .find(|m| m.role == "system")
+                .map(|m| m.content.as_str());
+            let last_user = messages
+                .iter()
+                .rfind(|m| m.role == "user")
+                .map_or("", |m| m.content.as_str());
+            self.chat_with_system(system, last_user, model, temperature)
+                .await
+        }
+
+        async fn chat_with_system(
+            &self,
+            system_prompt: Option<&str>,
+            _message: &str,
+            model: &str,
+            temperature: f64,
+        ) -> anyhow::Result<String> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            *self.last_call.lock() = Some((
+                system_prompt.map(str::to_string),
+                model.to_string(),
+                temperature,
+            ));
+            Ok("ok".into())
+        }
     }
 
-    #[test]
-    fn webhook_memory_key_is_unique() {
-        let key1 = webhook_memory_key();
-        let key2 = webhook_memory_key();
+    // Lets a test keep a handle on the mock it wraps in another provider.
+    #[async_trait]
+    impl Provider for Arc<MockProvider> {
+        async fn chat_with_history(
+            &self,
+            messages: &[ChatMessage],
+            model: &str,
+            temperature: f64,
+        ) -> anyhow::Result<String> {
+            self.as_ref()
+                .chat_with_history(messages, model, temperature)
+                .await
+        }
 
-        assert!(key1.starts_with("webhook_msg_"));
-        assert!(key2.starts_with("webhook_msg_"));
-        assert_ne!(key1, key2);
+        async fn chat_with_system(
+            &self,
+            system_prompt: Option<&str>,
+            message: &str,
+            model: &str,
+            temperature: f64,
+        ) -> anyhow::Result<String> {
+            self.as_ref()
+                .chat_with_system(system_prompt, message, model, temperature)
+                .await
+        }
     }
 
     #[derive(Default)]
-    struct MockMemory;
+    struct TrackingMemory {
+        keys: Mutex<Vec<String>>,
+        entries: Mutex<Vec<MemoryEntry>>,
+    }
 
     #[async_trait]
-    impl Memory for MockMemory {
+    impl Memory for TrackingMemory {
         fn name(&self) -> &str {
-            "mock"
+            "tracking"
         }
 
         async fn store(
             &self,
-            _key: &str,
-            _content: &str,
-            _category: MemoryCategory,
-            _session_id: Option<&str>,
+            key: &str,
+            content: &str,
+            category: MemoryCategory,
+            session_id: Option<&str>,
         ) -> anyhow::Result<()> {
+            self.keys.lock().push(key.to_string());
+            let mut entries = self.entries.lock();
+            let sequence = entries.len();
+            entries.push(MemoryEntry {
+                id: key.to_string(),
+                key: key.to_string(),
+                content: content.to_string(),
+                category,
+                timestamp: format!("2026-03-01T00:00:{sequence:02}Z"),
+                session_id: session_id.map(str::to_string),
+                score: None,
+            });
             Ok(())
         }
 
@@ -13202,109 +20294,536 @@ mod tests {
         async fn list(
             &self,
             _category: Option<&MemoryCategory>,
-            _session_id: Option<&str>,
+            session_id: Option<&str>,
         ) -> anyhow::Result<Vec<MemoryEntry>> {
-            Ok(Vec::new())
+            Ok(self
+                .entries
+                .lock()
+                .iter()
+                .filter(|entry| session_id.is_none() || entry.session_id.as_deref() == session_id)
+                .cloned()
+                .collect())
         }
 
-        async fn forget(&self, _key: &str) -> anyhow::Result<bool> {
-            Ok(false)
+        async fn forget(&self, key: &str) -> anyhow::Result<bool> {
+            let mut entries = self.entries.lock();
+            let before = entries.len();
+            entries.retain(|entry| entry.key != key);
+            Ok(entries.len() < before)
+        }
+
+        async fn count(&self) -> anyhow::Result<usize> {
+            let size = self.keys.lock().len();
+            Ok(size)
+        }
+
+        async fn health_check(&self) -> bool {
+            true
+        }
+    }
+
+    fn test_connect_info() -> ConnectInfo<GatewayPeer> {
+        ConnectInfo(GatewayPeer::Tcp(SocketAddr::from(([127, 0, 0, 1], 30_300))))
+    }
+
+    #[tokio::test]
+    async fn webhook_idempotency_skips_duplicate_provider_calls() {
+        let provider_impl = Arc::new(MockProvider::default());
+        let provider: Arc<dyn Provider> = provider_impl.clone();
+        let memory: Arc<dyn Memory> = Arc::new(MockMemory);
+
+        let state = AppState {
+            config: Arc::new(Mutex::new(Config::default())),
+            provider,
+            model: "test-model".into(),
+            temperature: 0.0,
+            mem: memory,
+            auto_save: false,
+            webhook_secret_hash: None,
+            pairing: Arc::new(PairingGuard::new(false, &[])),
+            trusted_proxies: TrustedProxies::default(),
+            rate_limiter: Arc::new(GatewayRateLimiter::new(100, 100, 100)),
+            idempotency_store: Arc::new(IdempotencyStore::new(Duration::from_secs(300), 1000)),
+            pb_chat_base_url: None,
+            pb_chat_collection: "chat_messages".into(),
+            pb_chat_token: None,
+            observer: Arc::new(crate::observability::NoopObserver),
+            journal_transcription_jobs: Arc::new(Mutex::new(HashMap::new())),
+            jobs: Arc::new(crate::jobs::JobManager::in_memory()),
+            openrouter_oauth: Arc::new(Mutex::new(None)),
+            model_catalog: Arc::new(Mutex::new(None)),
+            chat_runs: Arc::new(Mutex::new(HashMap::new())),
+            tunnel_url: Arc::new(Mutex::new(None)),
+        };
+
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Idempotency-Key", HeaderValue::from_static("abc-123"));
+
+        let body = Ok(Json(WebhookBody {
+            message: "hello".into(),
+            ..WebhookBody::default()
+        }));
+        let first = handle_webhook(
+            State(state.clone()),
+            test_connect_info(),
+            Query(WebhookQuery::default()),
+            headers.clone(),
+            body,
+        )
+        .await
+        .into_response();
+        assert_eq!(first.status(), StatusCode::OK);
+
+        let body = Ok(Json(WebhookBody {
+            message: "hello".into(),
+            ..WebhookBody::default()
+        }));
+        let second = handle_webhook(
+            State(state),
+            test_connect_info(),
+            Query(WebhookQuery::default()),
+            headers,
+            body,
+        )
+        .await
+        .into_response();
+        assert_eq!(second.status(), StatusCode::OK);
+
+        let payload = second.into_body().collect().await.unwrap().to_bytes();
+        let parsed: serde_json::Value = serde_json::from_slice(&payload).unwrap();
+        assert_eq!(parsed["status"], "duplicate");
+        assert_eq!(parsed["idempotent"], true);
+        assert_eq!(provider_impl.calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn webhook_format_prefers_the_query_then_an_explicit_accept() {
+        let query = |format: Option<&str>| WebhookQuery {
+            format: format.map(str::to_string),
+        };
+        let accept = |value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(header::ACCEPT, HeaderValue::from_str(value).unwrap());
+            headers
+        };
+        use WebhookFormat::{Json, Text};
+        let cases = [
+            (None, "", Some(Json)),
+            (None, "*/*", Some(Json)),
+            (None, "text/plain", Some(Text)),
+            (None, "text/plain, */*;q=0.8", Some(Text)),
+            (None, "text/plain;q=0.5, application/json", Some(Json)),
+            (None, "application/json, text/plain;q=0", Some(Json)),
+            (Some("text"), "application/json", Some(Text)),
+            (Some("JSON"), "text/plain", Some(Json)),
+            (Some("xml"), "", None),
+        ];
+        for (format, accept_value, expected) in cases {
+            assert_eq!(
+                webhook_format(&query(format), &accept(accept_value)),
+                expected,
+                "{format:?} / {accept_value}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn webhook_text_response_is_the_bare_reply_or_one_error_line() {
+        async fn text(response: axum::response::Response) -> String {
+            let bytes = response.into_body().collect().await.unwrap().to_bytes();
+            String::from_utf8(bytes.to_vec()).unwrap()
+        }
+        let body = serde_json::json!({ "response": "**Hi** there\n\n- one", "model": "m-1" });
+        let response = webhook_response(WebhookFormat::Text, StatusCode::OK, body.clone(), false);
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "text/plain; charset=utf-8"
+        );
+        assert_eq!(response.headers()["X-Model"], "m-1");
+        assert_eq!(text(response).await, "**Hi** there\n\n- one");
+        let stripped = webhook_response(WebhookFormat::Text, StatusCode::OK, body, true);
+        assert_eq!(text(stripped).await, "Hi there\n\n- one");
+
+        let err = serde_json::json!({ "error": "LLM request\nfailed" });
+        let failed = webhook_response(
+            WebhookFormat::Text,
+            StatusCode::INTERNAL_SERVER_ERROR,
+            err.clone(),
+            true,
+        );
+        assert_eq!(failed.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(text(failed).await, "LLM request failed\n");
+        let json = webhook_response(WebhookFormat::Json, StatusCode::BAD_GATEWAY, err, false);
+        assert_eq!(json.headers()[header::CONTENT_TYPE], "application/json");
+    }
+
+    #[tokio::test]
+    async fn webhook_negotiates_text_only_when_asked() {
+        let state = test_app_state_with_config(Config::default());
+        let send = |query: WebhookQuery, accept: Option<&'static str>, body: WebhookBody| {
+            let mut headers = HeaderMap::new();
+            if let Some(accept) = accept {
+                headers.insert(header::ACCEPT, HeaderValue::from_static(accept));
+            }
+            handle_webhook(
+                State(state.clone()),
+                test_connect_info(),
+                Query(query),
+                headers,
+                Ok(Json(body)),
+            )
+        };
+        let hello = || WebhookBody {
+            message: "hello".into(),
+            ..WebhookBody::default()
+        };
+        let too_hot = || WebhookBody {
+            temperature: Some(9.0),
+            ..hello()
+        };
+        let text = WebhookQuery {
+            format: Some("text".into()),
+        };
+
+        let response = send(WebhookQuery::default(), None, hello()).await;
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+        let response = send(WebhookQuery::default(), Some("text/plain"), hello()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["X-Model"], "test-model");
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(&bytes[..], b"ok");
+        let response = send(text, None, too_hot()).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert!(response.headers()[header::CONTENT_TYPE]
+            .to_str()
+            .unwrap()
+            .starts_with("text/plain"));
+        let response = send(WebhookQuery::default(), None, too_hot()).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+        let response = send(
+            WebhookQuery {
+                format: Some("xml".into()),
+            },
+            Some("text/plain"),
+            hello(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+    }
+
+    #[cfg(feature = "observability-otel")]
+    #[tokio::test]
+    async fn webhook_exports_llm_span_as_child_of_agent_span() {
+        use crate::observability::Observer;
+        use opentelemetry_proto::tonic::collector::trace::v1::ExportTraceServiceRequest;
+        use prost::Message;
+        use std::io::{BufRead, BufReader, Read, Write};
+
+        // Minimal OTLP/HTTP receiver: forwards the headers and body of each trace export.
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        let (tx, rx) = std::sync::mpsc::channel::<(String, Vec<u8>)>();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { break };
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut head = String::new();
+                let mut content_length = 0;
+                loop {
+                    let mut line = String::new();
+                    if reader.read_line(&mut line).unwrap_or(0) == 0 || line.trim().is_empty() {
+                        break;
+                    }
+                    if let Some((name, value)) = line.split_once(':') {
+                        if name.eq_ignore_ascii_case("content-length") {
+                            content_length = value.trim().parse().unwrap_or(0);
+                        }
+                    }
+                    head.push_str(&line.to_ascii_lowercase());
+                }
+                let mut body = vec![0; content_length];
+                let _ = reader.read_exact(&mut body);
+                let _ = stream.write_all(
+                    b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\nconnection: close\r\n\r\n",
+                );
+                if head.contains("/v1/traces") {
+                    let _ = tx.send((head, body));
+                }
+            }
+        });
+
+        let observer = Arc::new(
+            crate::observability::OtelObserver::from_config(&crate::config::ObservabilityConfig {
+                backend: "otlp".into(),
+                otel_endpoint: Some(endpoint),
+                otel_headers: HashMap::from([(
+                    "x-collector-token".to_string(),
+                    "secret".to_string(),
+                )]),
+                ..crate::config::ObservabilityConfig::default()
+            })
+            .unwrap(),
+        );
+        let state = AppState {
+            provider: Arc::new(MockProvider::default()),
+            observer: observer.clone(),
+            ..test_app_state_with_config(Config::default())
+        };
+
+        let body = Ok(Json(WebhookBody {
+            message: "hello".into(),
+            ..WebhookBody::default()
+        }));
+        let response = handle_webhook(
+            State(state),
+            test_connect_info(),
+            Query(WebhookQuery::default()),
+            HeaderMap::new(),
+            body,
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        observer.flush();
+
+        let mut spans = Vec::new();
+        while let Ok((head, body)) = rx.recv_timeout(Duration::from_secs(10)) {
+            assert!(head.contains("x-collector-token: secret"));
+            let request = ExportTraceServiceRequest::decode(body.as_slice()).unwrap();
+            spans.extend(
+                request
+                    .resource_spans
+                    .into_iter()
+                    .flat_map(|resource| resource.scope_spans)
+                    .flat_map(|scope| scope.spans),
+            );
+            if spans.iter().any(|span| span.name == "agent.invocation") {
+                break;
+            }
         }
 
-        async fn count(&self) -> anyhow::Result<usize> {
-            Ok(0)
-        }
+        let agent = spans
+            .iter()
+            .find(|span| span.name == "agent.invocation")
+            .expect("agent span exported");
+        let llm = spans
+            .iter()
+            .find(|span| span.name == "llm.call")
+            .expect("llm span exported");
+        assert!(agent.parent_span_id.is_empty());
+        assert_eq!(llm.trace_id, agent.trace_id);
+        assert_eq!(llm.parent_span_id, agent.span_id);
+    }
+    #[tokio::test]
+    async fn webhook_autosave_stores_distinct_keys_per_request() {
+        let provider_impl = Arc::new(MockProvider::default());
+        let provider: Arc<dyn Provider> = provider_impl.clone();
+
+        let tracking_impl = Arc::new(TrackingMemory::default());
+        let memory: Arc<dyn Memory> = tracking_impl.clone();
+
+        let state = AppState {
+            config: Arc::new(Mutex::new(Config::default())),
+            provider,
+            model: "test-model".into(),
+            temperature: 0.0,
+            mem: memory,
+            auto_save: true,
+            webhook_secret_hash: None,
+            pairing: Arc::new(PairingGuard::new(false, &[])),
+            trusted_proxies: TrustedProxies::default(),
+            rate_limiter: Arc::new(GatewayRateLimiter::new(100, 100, 100)),
+            idempotency_store: Arc::new(IdempotencyStore::new(Duration::from_secs(300), 1000)),
+            pb_chat_base_url: None,
+            pb_chat_collection: "chat_messages".into(),
+            pb_chat_token: None,
+            observer: Arc::new(crate::observability::NoopObserver),
+            journal_transcription_jobs: Arc::new(Mutex::new(HashMap::new())),
+            jobs: Arc::new(crate::jobs::JobManager::in_memory()),
+            openrouter_oauth: Arc::new(Mutex::new(None)),
+            model_catalog: Arc::new(Mutex::new(None)),
+            chat_runs: Arc::new(Mutex::new(HashMap::new())),
+            tunnel_url: Arc::new(Mutex::new(None)),
+        };
+
+        let headers = HeaderMap::new();
 
-        async fn health_check(&self) -> bool {
-            true
-        }
-    }
+        let body1 = Ok(Json(WebhookBody {
+            message: "hello one".into(),
+            ..WebhookBody::default()
+        }));
+        let first = handle_webhook(
+            State(state.clone()),
+            test_connect_info(),
+            Query(WebhookQuery::default()),
+            headers.clone(),
+            body1,
+        )
+        .await
+        .into_response();
+        assert_eq!(first.status(), StatusCode::OK);
 
-    #[derive(Default)]
-    struct MockProvider {
-        calls: AtomicUsize,
-    }
+        let body2 = Ok(Json(WebhookBody {
+            message: "hello two".into(),
+            ..WebhookBody::default()
+        }));
+        let second = handle_webhook(
+            State(state),
+            test_connect_info(),
+            Query(WebhookQuery::default()),
+            headers,
+            body2,
+        )
+        .await
+        .into_response();
+        assert_eq!(second.status(), StatusCode::OK);
 
-    #[async_trait]
-    impl Provider for MockProvider {
-        async fn chat_with_system(
-            &self,
-            _system_prompt: Option<&str>,
-            _message: &str,
-            _model: &str,
-            _temperature: f64,
-        ) -> anyhow::Result<String> {
-            self.calls.fetch_add(1, Ordering::SeqCst);
-            Ok("ok".into())
-        }
+        let keys = tracking_impl.keys.lock().clone();
+        assert_eq!(keys.len(), 4);
+        assert_ne!(keys[0], keys[2]);
+        assert!(keys[0].starts_with("webhook_msg_"));
+        assert!(keys[1].starts_with(WEBHOOK_REPLY_KEY_PREFIX));
+        assert!(keys[2].starts_with("webhook_msg_"));
+        assert!(keys[3].starts_with(WEBHOOK_REPLY_KEY_PREFIX));
+        assert_eq!(
+            keys[1].strip_prefix(WEBHOOK_REPLY_KEY_PREFIX),
+            keys[0].strip_prefix("webhook_msg_"),
+            "reply key must share the request's turn id"
+        );
+        let contents: Vec<String> = tracking_impl
+            .entries
+            .lock()
+            .iter()
+            .map(|entry| entry.content.clone())
+            .collect();
+        assert_eq!(contents, vec!["hello one", "ok", "hello two", "ok"]);
+        assert_eq!(provider_impl.calls.load(Ordering::SeqCst), 2);
     }
 
-    #[derive(Default)]
-    struct TrackingMemory {
-        keys: Mutex<Vec<String>>,
+    #[test]
+    fn webhook_secret_hash_is_deterministic_and_nonempty() {
+        let secret_a = generate_test_secret();
+        let secret_b = generate_test_secret();
+        let one = hash_webhook_secret(&secret_a);
+        let two = hash_webhook_secret(&secret_a);
+        let other = hash_webhook_secret(&secret_b);
+
+        assert_eq!(one, two);
+        assert_ne!(one, other);
+        assert_eq!(one.len(), 64);
     }
 
-    #[async_trait]
-    impl Memory for TrackingMemory {
-        fn name(&self) -> &str {
-            "tracking"
-        }
+    #[tokio::test]
+    async fn webhook_secret_hash_rejects_missing_header() {
+        let provider_impl = Arc::new(MockProvider::default());
+        let provider: Arc<dyn Provider> = provider_impl.clone();
+        let memory: Arc<dyn Memory> = Arc::new(MockMemory);
+        let secret = generate_test_secret();
 
-        async fn store(
-            &self,
-            key: &str,
-            _content: &str,
-            _category: MemoryCategory,
-            _session_id: Option<&str>,
-        ) -> anyhow::Result<()> {
-            self.keys.lock().push(key.to_string());
-            Ok(())
-        }
+        let state = AppState {
+            config: Arc::new(Mutex::new(Config::default())),
+            provider,
+            model: "test-model".into(),
+            temperature: 0.0,
+            mem: memory,
+            auto_save: false,
+            webhook_secret_hash: Some(SecretString::new(hash_webhook_secret(&secret))),
+            pairing: Arc::new(PairingGuard::new(false, &[])),
+            trusted_proxies: TrustedProxies::default(),
+            rate_limiter: Arc::new(GatewayRateLimiter::new(100, 100, 100)),
+            idempotency_store: Arc::new(IdempotencyStore::new(Duration::from_secs(300), 1000)),
+            pb_chat_base_url: None,
+            pb_chat_collection: "chat_messages".into(),
+            pb_chat_token: None,
+            observer: Arc::new(crate::observability::NoopObserver),
+            journal_transcription_jobs: Arc::new(Mutex::new(HashMap::new())),
+            jobs: Arc::new(crate::jobs::JobManager::in_memory()),
+            openrouter_oauth: Arc::new(Mutex::new(None)),
+            model_catalog: Arc::new(Mutex::new(None)),
+            chat_runs: Arc::new(Mutex::new(HashMap::new())),
+            tunnel_url: Arc::new(Mutex::new(None)),
+        };
 
-        async fn recall(
-            &self,
-            _query: &str,
-            _limit: usize,
-            _session_id: Option<&str>,
-        ) -> anyhow::Result<Vec<MemoryEntry>> {
-            Ok(Vec::new())
-        }
+        let response = handle_webhook(
+            State(state),
+            test_connect_info(),
+            Query(WebhookQuery::default()),
+            HeaderMap::new(),
+            Ok(Json(WebhookBody {
+                message: "hello".into(),
+                ..WebhookBody::default()
+            })),
+        )
+        .await
+        .into_response();
 
-        async fn get(&self, _key: &str) -> anyhow::Result<Option<MemoryEntry>> {
-            Ok(None)
-        }
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(provider_impl.calls.load(Ordering::SeqCst), 0);
+    }
 
-        async fn list(
-            &self,
-            _category: Option<&MemoryCategory>,
-            _session_id: Option<&str>,
-        ) -> anyhow::Result<Vec<MemoryEntry>> {
-            Ok(Vec::new())
-        }
+    #[tokio::test]
+    async fn webhook_secret_hash_rejects_invalid_header() {
+        let provider_impl = Arc::new(MockProvider::default());
+        let provider: Arc<dyn Provider> = provider_impl.clone();
+        let memory: Arc<dyn Memory> = Arc::new(MockMemory);
+        let valid_secret = generate_test_secret();
+        let wrong_secret = generate_test_secret();
 
-        async fn forget(&self, _key: &str) -> anyhow::Result<bool> {
-            Ok(false)
-        }
+        let state = AppState {
+            config: Arc::new(Mutex::new(Config::default())),
+            provider,
+            model: "test-model".into(),
+            temperature: 0.0,
+            mem: memory,
+            auto_save: false,
+            webhook_secret_hash: Some(SecretString::new(hash_webhook_secret(&valid_secret))),
+            pairing: Arc::new(PairingGuard::new(false, &[])),
+            trusted_proxies: TrustedProxies::default(),
+            rate_limiter: Arc::new(GatewayRateLimiter::new(100, 100, 100)),
+            idempotency_store: Arc::new(IdempotencyStore::new(Duration::from_secs(300), 1000)),
+            pb_chat_base_url: None,
+            pb_chat_collection: "chat_messages".into(),
+            pb_chat_token: None,
+            observer: Arc::new(crate::observability::NoopObserver),
+            journal_transcription_jobs: Arc::new(Mutex::new(HashMap::new())),
+            jobs: Arc::new(crate::jobs::JobManager::in_memory()),
+            openrouter_oauth: Arc::new(Mutex::new(None)),
+            model_catalog: Arc::new(Mutex::new(None)),
+            chat_runs: Arc::new(Mutex::new(HashMap::new())),
+            tunnel_url: Arc::new(Mutex::new(None)),
+        };
 
-        async fn count(&self) -> anyhow::Result<usize> {
-            let size = self.keys.lock().len();
-            Ok(size)
-        }
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "X-Webhook-Secret",
+            HeaderValue::from_str(&wrong_secret).unwrap(),
+        );
 
-        async fn health_check(&self) -> bool {
-            true
-        }
-    }
+        let response = handle_webhook(
+            State(state),
+            test_connect_info(),
+            Query(WebhookQuery::default()),
+            headers,
+            Ok(Json(WebhookBody {
+                message: "hello".into(),
+                ..WebhookBody::default()
+            })),
+        )
+        .await
+        .into_response();
 
-    fn test_connect_info() -> ConnectInfo<SocketAddr> {
-        ConnectInfo(SocketAddr::from(([127, 0, 0, 1], 30_300)))
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(provider_impl.calls.load(Ordering::SeqCst), 0);
     }
 
     #[tokio::test]
-    async fn webhook_idempotency_skips_duplicate_provider_calls() {
+    async fn webhook_secret_hash_accepts_valid_header() {
         let provider_impl = Arc::new(MockProvider::default());
         let provider: Arc<dyn Provider> = provider_impl.clone();
         let memory: Arc<dyn Memory> = Arc::new(MockMemory);
+        let secret = generate_test_secret();
 
         let state = AppState {
             config: Arc::new(Mutex::new(Config::default())),
@@ -13313,9 +20832,9 @@ mod tests {
             temperature: 0.0,
             mem: memory,
             auto_save: false,
-            webhook_secret_hash: None,
+            webhook_secret_hash: Some(SecretString::new(hash_webhook_secret(&secret))),
             pairing: Arc::new(PairingGuard::new(false, &[])),
-            trust_forwarded_headers: false,
+            trusted_proxies: TrustedProxies::default(),
             rate_limiter: Arc::new(GatewayRateLimiter::new(100, 100, 100)),
             idempotency_store: Arc::new(IdempotencyStore::new(Duration::from_secs(300), 1000)),
             pb_chat_base_url: None,
@@ -13323,246 +20842,748 @@ mod tests {
             pb_chat_token: None,
             observer: Arc::new(crate::observability::NoopObserver),
             journal_transcription_jobs: Arc::new(Mutex::new(HashMap::new())),
+            jobs: Arc::new(crate::jobs::JobManager::in_memory()),
             openrouter_oauth: Arc::new(Mutex::new(None)),
+            model_catalog: Arc::new(Mutex::new(None)),
+            chat_runs: Arc::new(Mutex::new(HashMap::new())),
+            tunnel_url: Arc::new(Mutex::new(None)),
+        };
+
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Webhook-Secret", HeaderValue::from_str(&secret).unwrap());
+
+        let response = handle_webhook(
+            State(state),
+            test_connect_info(),
+            Query(WebhookQuery::default()),
+            headers,
+            Ok(Json(WebhookBody {
+                message: "hello".into(),
+                ..WebhookBody::default()
+            })),
+        )
+        .await
+        .into_response();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(provider_impl.calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn telegram_webhook_requires_webhook_mode_and_the_secret_token() {
+        const UPDATE: &str = include_str!("../../tests/fixtures/telegram/private_message.json");
+        let call = |state: AppState, secret: Option<&'static str>| async move {
+            let mut headers = HeaderMap::new();
+            if let Some(secret) = secret {
+                headers.insert(
+                    "X-Telegram-Bot-Api-Secret-Token",
+                    HeaderValue::from_static(secret),
+                );
+            }
+            handle_telegram_webhook(
+                State(state),
+                test_connect_info(),
+                headers,
+                axum::body::Bytes::from_static(UPDATE.as_bytes()),
+            )
+            .await
+            .into_response()
+        };
+
+        let polling = test_app_state_with_config(Config::default());
+        assert_eq!(
+            call(polling, Some("s3cret")).await.status(),
+            StatusCode::NOT_FOUND
+        );
+
+        let mut config = Config::default();
+        config.channels_config.telegram = Some(crate::config::TelegramConfig {
+            bot_token: "123:ABC".into(),
+            // Nobody is allowed, so the accepted update never reaches the agent.
+            allowed_users: vec![],
+            stream_mode: crate::config::StreamMode::default(),
+            draft_update_interval_ms: 1000,
+            interrupt_on_new_message: false,
+            mention_only: false,
+            allowed_chats: vec![],
+            webhook_secret: Some("s3cret".into()),
+        });
+        let state = test_app_state_with_config(config);
+        assert_eq!(
+            call(state.clone(), None).await.status(),
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            call(state.clone(), Some("wrong")).await.status(),
+            StatusCode::UNAUTHORIZED
+        );
+        let response = call(state, Some("s3cret")).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(payload["accepted"], false);
+    }
+
+    #[cfg(feature = "channel-nextcloud")]
+    #[tokio::test]
+    async fn nextcloud_talk_webhook_verifies_and_replies_in_the_room() {
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        const MESSAGE: &str = include_str!("../../tests/fixtures/nextcloud_talk/message.json");
+        const RANDOM: &str = "f3a1c6d2e8b94f0a7c5d1e2b3a4f6c8d9e0a1b2c3d4e5f60718293a4b5c6d7e8";
+        const SIGNATURE: &str = "ddbacbcf7a93da40181cfed250b6641e077cbcfcb5c6449b69bd78ead3c56d3a";
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/ocs/v2.php/apps/spreed/api/v1/bot/n3xtc10ud/message"))
+            .and(body_partial_json(
+                serde_json::json!({"message": "ok", "replyTo": 1567}),
+            ))
+            .respond_with(ResponseTemplate::new(201))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let tmp = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.workspace_dir = tmp.path().to_path_buf();
+        config.channels_config.nextcloud_talk = Some(crate::config::schema::NextcloudTalkConfig {
+            base_url: server.uri(),
+            app_token: "app-token".into(),
+            webhook_secret: Some("slowclaw-talk-secret".into()),
+            allowed_users: vec!["ada".into()],
+        });
+        let state = test_app_state_with_config(config);
+        let signed = |signature: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert("X-Nextcloud-Talk-Random", HeaderValue::from_static(RANDOM));
+            headers.insert(
+                "X-Nextcloud-Talk-Signature",
+                HeaderValue::from_str(signature).unwrap(),
+            );
+            headers
         };
 
-        let mut headers = HeaderMap::new();
-        headers.insert("X-Idempotency-Key", HeaderValue::from_static("abc-123"));
+        let rejected = handle_nextcloud_talk_webhook(
+            State(state.clone()),
+            test_connect_info(),
+            signed(&"0".repeat(64)),
+            axum::body::Bytes::from_static(MESSAGE.as_bytes()),
+        )
+        .await
+        .into_response();
+        assert_eq!(rejected.status(), StatusCode::UNAUTHORIZED);
+
+        let accepted = handle_nextcloud_talk_webhook(
+            State(state),
+            test_connect_info(),
+            signed(SIGNATURE),
+            axum::body::Bytes::from_static(MESSAGE.as_bytes()),
+        )
+        .await
+        .into_response();
+        assert_eq!(accepted.status(), StatusCode::OK);
+        let body = accepted.into_body().collect().await.unwrap().to_bytes();
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(payload["accepted"], 1);
+
+        // The reply is posted from a background task.
+        for _ in 0..100 {
+            if !server
+                .received_requests()
+                .await
+                .unwrap_or_default()
+                .is_empty()
+            {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        server.verify().await;
+    }
+
+    #[cfg(feature = "channel-whatsapp")]
+    #[tokio::test]
+    async fn whatsapp_webhook_verifies_and_replies_to_the_sender() {
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        const MESSAGE: &str = include_str!("../../tests/fixtures/whatsapp/text_message.json");
+        const SIGNATURE: &str =
+            "sha256=954fa835f9f0d0c14defd7f8f5500b2ab94a86b94f52f893f96f6e730299fc21";
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/106540352242922/messages"))
+            .and(body_partial_json(serde_json::json!({
+                "to": "16505551234",
+                "text": {"body": "ok"},
+                "context": {
+                    "message_id": "wamid.HBgLMTY1MDU1NTEyMzQVAgASGBQzQTRBNjU5OUFFRTAzODEwMTQ0RgA="
+                }
+            })))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let tmp = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.workspace_dir = tmp.path().to_path_buf();
+        config.channels_config.whatsapp = Some(crate::config::schema::WhatsAppConfig {
+            access_token: Some("access-token".into()),
+            phone_number_id: Some("106540352242922".into()),
+            verify_token: Some("slowclaw-verify".into()),
+            app_secret: Some("slowclaw-whatsapp-secret".into()),
+            session_path: None,
+            pair_phone: None,
+            pair_code: None,
+            allowed_numbers: vec!["+1 650 555 1234".into()],
+            reply_template: None,
+            reply_template_language: None,
+            api_url: Some(server.uri()),
+        });
+        let state = test_app_state_with_config(config);
 
-        let body = Ok(Json(WebhookBody {
-            message: "hello".into(),
-        }));
-        let first = handle_webhook(
+        let verify = |token: &str| WhatsAppVerifyQuery {
+            mode: "subscribe".into(),
+            verify_token: token.into(),
+            challenge: "1158201444".into(),
+        };
+        let refused = handle_whatsapp_verify(State(state.clone()), Query(verify("wrong"))).await;
+        assert_eq!(refused.status(), StatusCode::FORBIDDEN);
+        let verified =
+            handle_whatsapp_verify(State(state.clone()), Query(verify("slowclaw-verify"))).await;
+        assert_eq!(verified.status(), StatusCode::OK);
+        let body = verified.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(&body[..], b"1158201444");
+
+        let signed = |signature: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(
+                "X-Hub-Signature-256",
+                HeaderValue::from_str(signature).unwrap(),
+            );
+            headers
+        };
+        let rejected = handle_whatsapp_webhook(
             State(state.clone()),
             test_connect_info(),
-            headers.clone(),
-            body,
+            signed(&format!("sha256={}", "0".repeat(64))),
+            axum::body::Bytes::from_static(MESSAGE.as_bytes()),
         )
         .await
         .into_response();
-        assert_eq!(first.status(), StatusCode::OK);
+        assert_eq!(rejected.status(), StatusCode::UNAUTHORIZED);
 
-        let body = Ok(Json(WebhookBody {
-            message: "hello".into(),
-        }));
-        let second = handle_webhook(State(state), test_connect_info(), headers, body)
-            .await
-            .into_response();
-        assert_eq!(second.status(), StatusCode::OK);
+        let accepted = handle_whatsapp_webhook(
+            State(state),
+            test_connect_info(),
+            signed(SIGNATURE),
+            axum::body::Bytes::from_static(MESSAGE.as_bytes()),
+        )
+        .await
+        .into_response();
+        assert_eq!(accepted.status(), StatusCode::OK);
+        let body = accepted.into_body().collect().await.unwrap().to_bytes();
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(payload["accepted"], 1);
 
-        let payload = second.into_body().collect().await.unwrap().to_bytes();
-        let parsed: serde_json::Value = serde_json::from_slice(&payload).unwrap();
-        assert_eq!(parsed["status"], "duplicate");
-        assert_eq!(parsed["idempotent"], true);
-        assert_eq!(provider_impl.calls.load(Ordering::SeqCst), 1);
+        // The reply is sent from a background task.
+        for _ in 0..100 {
+            if !server
+                .received_requests()
+                .await
+                .unwrap_or_default()
+                .is_empty()
+            {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        server.verify().await;
     }
 
     #[tokio::test]
-    async fn webhook_autosave_stores_distinct_keys_per_request() {
+    async fn webhook_reports_llm_cache_hits_while_tracing() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.workspace_dir = tmp.path().to_path_buf();
+        config.observability.runtime_trace_mode = "rolling".into();
+        config.provider.cache_ttl_secs = 600;
         let provider_impl = Arc::new(MockProvider::default());
-        let provider: Arc<dyn Provider> = provider_impl.clone();
+        let cached = crate::providers::cache::CachedProvider::open(
+            provider_impl.clone(),
+            tmp.path(),
+            &config.provider,
+        )
+        .unwrap();
+        let state = AppState {
+            provider: Arc::new(cached),
+            ..test_app_state_with_config(config)
+        };
+        let call = |no_cache: bool| {
+            let body: WebhookBody = serde_json::from_value(serde_json::json!({
+                "message": "summarize today",
+                "temperature": 0.0,
+                "no_cache": no_cache,
+            }))
+            .unwrap();
+            handle_webhook(
+                State(state.clone()),
+                test_connect_info(),
+                Query(WebhookQuery::default()),
+                HeaderMap::new(),
+                Ok(Json(body)),
+            )
+        };
+        let cache_header = |response: &axum::response::Response| {
+            response
+                .headers()
+                .get("X-LlmCache")
+                .map(|v| v.to_str().unwrap().to_string())
+        };
 
-        let tracking_impl = Arc::new(TrackingMemory::default());
-        let memory: Arc<dyn Memory> = tracking_impl.clone();
+        let first = call(false).await;
+        assert_eq!(first.status(), StatusCode::OK);
+        assert_eq!(cache_header(&first).as_deref(), Some("miss"));
+        let second = call(false).await;
+        assert_eq!(cache_header(&second).as_deref(), Some("hit"));
+        let bypassed = call(true).await;
+        assert_eq!(bypassed.status(), StatusCode::OK);
+        assert_eq!(cache_header(&bypassed), None);
+        assert_eq!(provider_impl.calls.load(Ordering::SeqCst), 2);
+    }
 
+    #[tokio::test]
+    async fn webhook_stops_at_the_daily_budget_unless_a_paired_caller_overrides() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.workspace_dir = tmp.path().to_path_buf();
+        config.cost.enabled = true;
+        config.cost.daily_limit_usd = 1.0;
+        config.cost.allow_override = true;
+        crate::cost::CostTracker::for_workspace(config.cost.clone(), tmp.path())
+            .unwrap()
+            .record_usage(crate::cost::TokenUsage::new(
+                "test-model",
+                2_000_000,
+                0,
+                1.0,
+                0.0,
+            ))
+            .unwrap();
+        let provider_impl = Arc::new(MockProvider::default());
+        let guarded = crate::cost::guard::BudgetGuard::wrap(
+            Box::new(provider_impl.clone()),
+            &config.cost,
+            tmp.path(),
+        )
+        .unwrap();
         let state = AppState {
-            config: Arc::new(Mutex::new(Config::default())),
-            provider,
-            model: "test-model".into(),
-            temperature: 0.0,
-            mem: memory,
-            auto_save: true,
-            webhook_secret_hash: None,
-            pairing: Arc::new(PairingGuard::new(false, &[])),
-            trust_forwarded_headers: false,
-            rate_limiter: Arc::new(GatewayRateLimiter::new(100, 100, 100)),
-            idempotency_store: Arc::new(IdempotencyStore::new(Duration::from_secs(300), 1000)),
-            pb_chat_base_url: None,
-            pb_chat_collection: "chat_messages".into(),
-            pb_chat_token: None,
-            observer: Arc::new(crate::observability::NoopObserver),
-            journal_transcription_jobs: Arc::new(Mutex::new(HashMap::new())),
-            openrouter_oauth: Arc::new(Mutex::new(None)),
+            provider: Arc::from(guarded),
+            pairing: Arc::new(PairingGuard::new(true, &["zc_admin".into()])),
+            ..test_app_state_with_config(config)
+        };
+        let call = |headers: HeaderMap| {
+            let body = WebhookBody {
+                message: "hello".into(),
+                ..WebhookBody::default()
+            };
+            handle_webhook(
+                State(state.clone()),
+                test_connect_info(),
+                Query(WebhookQuery::default()),
+                headers,
+                Ok(Json(body)),
+            )
         };
+        let mut paired = HeaderMap::new();
+        paired.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_static("Bearer zc_admin"),
+        );
 
-        let headers = HeaderMap::new();
+        let refused = call(paired.clone()).await;
+        assert_eq!(refused.status(), StatusCode::TOO_MANY_REQUESTS);
+        let body = refused.into_body().collect().await.unwrap().to_bytes();
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(payload["code"], "BUDGET_EXCEEDED");
+        assert!(payload["error"]
+            .as_str()
+            .unwrap()
+            .starts_with("Daily budget exceeded ($2.00 of $1.00 spent), resets at "));
+        assert_eq!(provider_impl.calls.load(Ordering::SeqCst), 0);
 
-        let body1 = Ok(Json(WebhookBody {
-            message: "hello one".into(),
-        }));
-        let first = handle_webhook(
-            State(state.clone()),
-            test_connect_info(),
-            headers.clone(),
-            body1,
-        )
-        .await
-        .into_response();
-        assert_eq!(first.status(), StatusCode::OK);
+        let mut overriding = paired.clone();
+        overriding.insert("X-Budget-Override", HeaderValue::from_static("true"));
+        assert_eq!(call(overriding).await.status(), StatusCode::OK);
+        assert_eq!(provider_impl.calls.load(Ordering::SeqCst), 1);
 
-        let body2 = Ok(Json(WebhookBody {
-            message: "hello two".into(),
-        }));
-        let second = handle_webhook(State(state), test_connect_info(), headers, body2)
+        let health = handle_health(State(state.clone())).await.into_response();
+        let body = health.into_body().collect().await.unwrap().to_bytes();
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(payload["budget"]["exceeded"], "day");
+        assert_eq!(payload["budget"]["daily"]["limit_usd"], 1.0);
+
+        let usage = handle_usage(State(state.clone()), paired)
             .await
             .into_response();
-        assert_eq!(second.status(), StatusCode::OK);
-
-        let keys = tracking_impl.keys.lock().clone();
-        assert_eq!(keys.len(), 2);
-        assert_ne!(keys[0], keys[1]);
-        assert!(keys[0].starts_with("webhook_msg_"));
-        assert!(keys[1].starts_with("webhook_msg_"));
-        assert_eq!(provider_impl.calls.load(Ordering::SeqCst), 2);
+        let body = usage.into_body().collect().await.unwrap().to_bytes();
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(payload["enabled"], true);
+        assert!(payload["budget"]["daily"]["spent_usd"].as_f64().unwrap() > 2.0);
+        let unpaired = handle_usage(State(state), HeaderMap::new())
+            .await
+            .into_response();
+        assert_eq!(unpaired.status(), StatusCode::UNAUTHORIZED);
     }
 
     #[test]
-    fn webhook_secret_hash_is_deterministic_and_nonempty() {
-        let secret_a = generate_test_secret();
-        let secret_b = generate_test_secret();
-        let one = hash_webhook_secret(&secret_a);
-        let two = hash_webhook_secret(&secret_a);
-        let other = hash_webhook_secret(&secret_b);
+    fn budget_override_needs_the_config_flag_and_a_paired_token() {
+        let mut config = Config::default();
+        config.cost.allow_override = true;
+        let mut state = test_app_state_with_config(config);
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Budget-Override", HeaderValue::from_static("1"));
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_static("Bearer zc_admin"),
+        );
+        // Pairing is off, so there is no full-scope token to trust.
+        assert!(!budget_override_requested(&state, &headers));
 
-        assert_eq!(one, two);
-        assert_ne!(one, other);
-        assert_eq!(one.len(), 64);
+        state.pairing = Arc::new(PairingGuard::new(true, &["zc_admin".into()]));
+        assert!(budget_override_requested(&state, &headers));
+        state.config.lock().cost.allow_override = false;
+        assert!(!budget_override_requested(&state, &headers));
     }
 
     #[tokio::test]
-    async fn webhook_secret_hash_rejects_missing_header() {
+    async fn webhook_attachments_are_rendered_into_the_prompt() {
         let provider_impl = Arc::new(MockProvider::default());
-        let provider: Arc<dyn Provider> = provider_impl.clone();
-        let memory: Arc<dyn Memory> = Arc::new(MockMemory);
-        let secret = generate_test_secret();
-
         let state = AppState {
-            config: Arc::new(Mutex::new(Config::default())),
-            provider,
-            model: "test-model".into(),
-            temperature: 0.0,
-            mem: memory,
-            auto_save: false,
-            webhook_secret_hash: Some(Arc::from(hash_webhook_secret(&secret))),
-            pairing: Arc::new(PairingGuard::new(false, &[])),
-            trust_forwarded_headers: false,
-            rate_limiter: Arc::new(GatewayRateLimiter::new(100, 100, 100)),
-            idempotency_store: Arc::new(IdempotencyStore::new(Duration::from_secs(300), 1000)),
-            pb_chat_base_url: None,
-            pb_chat_collection: "chat_messages".into(),
-            pb_chat_token: None,
-            observer: Arc::new(crate::observability::NoopObserver),
-            journal_transcription_jobs: Arc::new(Mutex::new(HashMap::new())),
-            openrouter_oauth: Arc::new(Mutex::new(None)),
+            provider: provider_impl.clone(),
+            ..test_app_state_with_config(Config::default())
         };
+        let body: WebhookBody = serde_json::from_value(serde_json::json!({
+            "message": "what is this?",
+            "attachments": [
+                { "kind": "audio", "url": "https://cdn.example/note.ogg", "mime": "audio/ogg" }
+            ]
+        }))
+        .unwrap();
 
         let response = handle_webhook(
             State(state),
             test_connect_info(),
+            Query(WebhookQuery::default()),
             HeaderMap::new(),
-            Ok(Json(WebhookBody {
-                message: "hello".into(),
-            })),
+            Ok(Json(body)),
         )
         .await
         .into_response();
 
-        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
-        assert_eq!(provider_impl.calls.load(Ordering::SeqCst), 0);
+        assert_eq!(response.status(), StatusCode::OK);
+        let history = provider_impl.last_history.lock().clone();
+        assert_eq!(
+            history.last().map(|(_, content)| content.as_str()),
+            Some(
+                "what is this?\n[Attachment: audio (audio/ogg) at https://cdn.example/note.ogg, not loaded]"
+            )
+        );
     }
 
     #[tokio::test]
-    async fn webhook_secret_hash_rejects_invalid_header() {
+    async fn webhook_overrides_are_validated_and_applied() {
         let provider_impl = Arc::new(MockProvider::default());
-        let provider: Arc<dyn Provider> = provider_impl.clone();
-        let memory: Arc<dyn Memory> = Arc::new(MockMemory);
-        let valid_secret = generate_test_secret();
-        let wrong_secret = generate_test_secret();
+        let mut config = Config::default();
+        config.gateway.allowed_models = vec!["cheap-model".into()];
+        config.gateway.webhook_temperature_max = 1.0;
+        let state = AppState {
+            provider: provider_impl.clone(),
+            ..test_app_state_with_config(config)
+        };
+
+        let send = |body: WebhookBody| {
+            handle_webhook(
+                State(state.clone()),
+                test_connect_info(),
+                Query(WebhookQuery::default()),
+                HeaderMap::new(),
+                Ok(Json(body)),
+            )
+        };
+        let read = |response: axum::response::Response| async move {
+            let status = response.status();
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            (
+                status,
+                serde_json::from_slice::<serde_json::Value>(&body).unwrap(),
+            )
+        };
+
+        let (status, payload) = read(
+            send(WebhookBody {
+                message: "hi".into(),
+                model: Some("expensive-model".into()),
+                ..WebhookBody::default()
+            })
+            .await
+            .into_response(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(payload["field"], "model");
+        assert!(payload["error"]
+            .as_str()
+            .unwrap()
+            .contains("gateway.allowed_models"));
+
+        let (status, payload) = read(
+            send(WebhookBody {
+                message: "hi".into(),
+                temperature: Some(1.5),
+                ..WebhookBody::default()
+            })
+            .await
+            .into_response(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(payload["field"], "temperature");
+        assert_eq!(provider_impl.calls.load(Ordering::SeqCst), 0);
+
+        let (status, payload) = read(
+            send(WebhookBody {
+                message: "hi".into(),
+                system: Some("Answer in one word.".into()),
+                model: Some("cheap-model".into()),
+                temperature: Some(0.25),
+                ..WebhookBody::default()
+            })
+            .await
+            .into_response(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(payload["model"], "cheap-model");
+        let (system, model, temperature) = provider_impl.last_call.lock().clone().unwrap();
+        assert_eq!(system.as_deref(), Some("Answer in one word."));
+        assert_eq!(model, "cheap-model");
+        assert!((temperature - 0.25).abs() < f64::EPSILON);
+
+        let (status, payload) = read(
+            send(WebhookBody {
+                message: "hi".into(),
+                ..WebhookBody::default()
+            })
+            .await
+            .into_response(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(payload["model"], "test-model");
+        assert!(payload.get("trace").is_none());
+        let (system, model, temperature) = provider_impl.last_call.lock().clone().unwrap();
+        assert_ne!(system.as_deref(), Some("Answer in one word."));
+        assert_eq!(model, "test-model");
+        assert!(temperature.abs() < f64::EPSILON);
+
+        let (status, payload) = read(
+            send(WebhookBody {
+                message: "hi".into(),
+                include_trace: true,
+                ..WebhookBody::default()
+            })
+            .await
+            .into_response(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(payload["trace"], serde_json::json!({ "tool_calls": [] }));
+    }
 
+    #[tokio::test]
+    async fn webhook_session_replays_history_and_stores_both_turns() {
+        let provider_impl = Arc::new(MockProvider::default());
+        let tracking_impl = Arc::new(TrackingMemory::default());
         let state = AppState {
-            config: Arc::new(Mutex::new(Config::default())),
-            provider,
-            model: "test-model".into(),
-            temperature: 0.0,
-            mem: memory,
-            auto_save: false,
-            webhook_secret_hash: Some(Arc::from(hash_webhook_secret(&valid_secret))),
-            pairing: Arc::new(PairingGuard::new(false, &[])),
-            trust_forwarded_headers: false,
-            rate_limiter: Arc::new(GatewayRateLimiter::new(100, 100, 100)),
-            idempotency_store: Arc::new(IdempotencyStore::new(Duration::from_secs(300), 1000)),
-            pb_chat_base_url: None,
-            pb_chat_collection: "chat_messages".into(),
-            pb_chat_token: None,
-            observer: Arc::new(crate::observability::NoopObserver),
-            journal_transcription_jobs: Arc::new(Mutex::new(HashMap::new())),
-            openrouter_oauth: Arc::new(Mutex::new(None)),
+            provider: provider_impl.clone(),
+            mem: tracking_impl.clone(),
+            ..test_app_state_with_config(Config::default())
+        };
+        let send = |message: &str, session_id: &str, reset: bool| {
+            handle_webhook(
+                State(state.clone()),
+                test_connect_info(),
+                Query(WebhookQuery::default()),
+                HeaderMap::new(),
+                Ok(Json(WebhookBody {
+                    message: message.into(),
+                    session_id: Some(session_id.into()),
+                    reset,
+                    ..WebhookBody::default()
+                })),
+            )
         };
 
-        let mut headers = HeaderMap::new();
-        headers.insert(
-            "X-Webhook-Secret",
-            HeaderValue::from_str(&wrong_secret).unwrap(),
+        let first = send("my name is Ada", " chat/1 ", false).await.into_response();
+        assert_eq!(first.status(), StatusCode::OK);
+        let body = first.into_body().collect().await.unwrap().to_bytes();
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(payload["session_id"], "chat1");
+
+        let stored: Vec<(String, Option<String>)> = tracking_impl
+            .entries
+            .lock()
+            .iter()
+            .map(|e| (e.key.clone(), e.session_id.clone()))
+            .collect();
+        assert_eq!(stored.len(), 2);
+        assert!(stored[0].0.starts_with("webhook_msg_"));
+        assert!(stored[1].0.starts_with(WEBHOOK_REPLY_KEY_PREFIX));
+        assert!(stored
+            .iter()
+            .all(|(_, session)| session.as_deref() == Some("webhook:chat1")));
+
+        send("what is my name?", "chat1", false).await;
+        assert_eq!(
+            provider_impl.last_history.lock().clone(),
+            vec![
+                ("user".to_string(), "my name is Ada".to_string()),
+                ("assistant".to_string(), "ok".to_string()),
+                ("user".to_string(), "what is my name?".to_string()),
+            ]
         );
 
-        let response = handle_webhook(
-            State(state),
-            test_connect_info(),
-            headers,
-            Ok(Json(WebhookBody {
-                message: "hello".into(),
-            })),
-        )
-        .await
-        .into_response();
+        let reset = send("", "chat1", true).await.into_response();
+        let body = reset.into_body().collect().await.unwrap().to_bytes();
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(payload["status"], "reset");
+        assert_eq!(payload["cleared"], 4);
+        assert!(tracking_impl.entries.lock().is_empty());
+        assert_eq!(provider_impl.calls.load(Ordering::SeqCst), 2);
+
+        let invalid = send("hi", "///", false).await.into_response();
+        assert_eq!(invalid.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn webhook_session_history_respects_turn_and_char_limits() {
+        let mem = TrackingMemory::default();
+        let session = webhook_memory_session("s");
+        for (key, content) in [
+            ("webhook_msg_1", "x".repeat(WEBHOOK_HISTORY_MAX_CHARS)),
+            ("webhook_reply_1", "first reply".to_string()),
+            ("webhook_msg_2", "second".to_string()),
+            ("webhook_reply_2", "second reply".to_string()),
+        ] {
+            mem.store(key, &content, MemoryCategory::Conversation, Some(&session))
+                .await
+                .unwrap();
+        }
+
+        let history = load_webhook_session_history(&mem, "s", 2).await;
+        let contents: Vec<&str> = history.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, vec!["second", "second reply"]);
+
+        let history = load_webhook_session_history(&mem, "s", 10).await;
+        assert_eq!(history.len(), 3);
+        assert_eq!(history[0].role, "assistant");
+        assert!(load_webhook_session_history(&mem, "s", 0).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn webhook_session_reset_reaches_past_the_list_cap() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mem = crate::memory::SqliteMemory::new(tmp.path()).unwrap();
+        let session = webhook_memory_session("long");
+        for i in 0..1003 {
+            mem.store(
+                &format!("webhook_msg_{i}"),
+                "turn",
+                MemoryCategory::Conversation,
+                Some(&session),
+            )
+            .await
+            .unwrap();
+        }
+        mem.store("other", "keep", MemoryCategory::Core, None)
+            .await
+            .unwrap();
 
-        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
-        assert_eq!(provider_impl.calls.load(Ordering::SeqCst), 0);
+        assert_eq!(clear_webhook_session(&mem, "long").await, 1003);
+        assert_eq!(mem.count().await.unwrap(), 1);
     }
 
     #[tokio::test]
-    async fn webhook_secret_hash_accepts_valid_header() {
+    async fn webhook_batch_preserves_order_and_reports_per_item_results() {
         let provider_impl = Arc::new(MockProvider::default());
-        let provider: Arc<dyn Provider> = provider_impl.clone();
-        let memory: Arc<dyn Memory> = Arc::new(MockMemory);
-        let secret = generate_test_secret();
-
         let state = AppState {
-            config: Arc::new(Mutex::new(Config::default())),
-            provider,
-            model: "test-model".into(),
-            temperature: 0.0,
-            mem: memory,
-            auto_save: false,
-            webhook_secret_hash: Some(Arc::from(hash_webhook_secret(&secret))),
-            pairing: Arc::new(PairingGuard::new(false, &[])),
-            trust_forwarded_headers: false,
-            rate_limiter: Arc::new(GatewayRateLimiter::new(100, 100, 100)),
-            idempotency_store: Arc::new(IdempotencyStore::new(Duration::from_secs(300), 1000)),
-            pb_chat_base_url: None,
-            pb_chat_collection: "chat_messages".into(),
-            pb_chat_token: None,
-            observer: Arc::new(crate::observability::NoopObserver),
-            journal_transcription_jobs: Arc::new(Mutex::new(HashMap::new())),
-            openrouter_oauth: Arc::new(Mutex::new(None)),
+            provider: provider_impl.clone(),
+            rate_limiter: Arc::new(GatewayRateLimiter::new(100, 4, 100)),
+            ..test_app_state_with_config(Config::default())
+        };
+        let item = |id: &str, model: Option<&str>| WebhookBatchItem {
+            id: Some(id.into()),
+            body: WebhookBody {
+                message: format!("prompt {id}"),
+                model: model.map(str::to_string),
+                ..WebhookBody::default()
+            },
+        };
+        let send = |items: Vec<WebhookBatchItem>| {
+            handle_webhook_batch(
+                State(state.clone()),
+                test_connect_info(),
+                HeaderMap::new(),
+                Ok(Json(items)),
+            )
         };
 
-        let mut headers = HeaderMap::new();
-        headers.insert("X-Webhook-Secret", HeaderValue::from_str(&secret).unwrap());
+        let response = send(vec![
+            item("a", None),
+            item("b", Some("not-allowed")),
+            item("a", None),
+        ])
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let results = payload["results"].as_array().unwrap();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0]["id"], "a");
+        assert_eq!(results[0]["ok"], true);
+        assert_eq!(results[0]["response"], "ok");
+        assert_eq!(results[1]["id"], "b");
+        assert_eq!(results[1]["ok"], false);
+        assert_eq!(results[1]["field"], "model");
+        assert_eq!(results[2]["status"], "duplicate");
+        assert_eq!(payload["failed"], 1);
+        assert_eq!(provider_impl.calls.load(Ordering::SeqCst), 1);
 
-        let response = handle_webhook(
-            State(state),
+        // Three of four per-minute slots are spent, so a two-item batch is refused.
+        let response = send(vec![item("c", None), item("d", None)])
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+
+        let oversized: Vec<_> = (0..=WEBHOOK_BATCH_MAX_ITEMS)
+            .map(|i| item(&format!("x{i}"), None))
+            .collect();
+        let response = handle_webhook_batch(
+            State(AppState {
+                rate_limiter: Arc::new(GatewayRateLimiter::new(100, 100, 100)),
+                ..state.clone()
+            }),
             test_connect_info(),
-            headers,
-            Ok(Json(WebhookBody {
-                message: "hello".into(),
-            })),
+            HeaderMap::new(),
+            Ok(Json(oversized)),
         )
         .await
         .into_response();
-
-        assert_eq!(response.status(), StatusCode::OK);
-        assert_eq!(provider_impl.calls.load(Ordering::SeqCst), 1);
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
     }
 
     // ══════════════════════════════════════════════════════════
@@ -13770,7 +21791,7 @@ mod tests {
             auto_save: false,
             webhook_secret_hash: None,
             pairing: Arc::new(PairingGuard::new(true, &[])),
-            trust_forwarded_headers: false,
+            trusted_proxies: TrustedProxies::default(),
             rate_limiter: Arc::new(GatewayRateLimiter::new(100, 100, 100)),
             idempotency_store: Arc::new(IdempotencyStore::new(Duration::from_secs(300), 1000)),
             pb_chat_base_url: None,
@@ -13778,7 +21799,11 @@ mod tests {
             pb_chat_token: None,
             observer: Arc::new(crate::observability::NoopObserver),
             journal_transcription_jobs: Arc::new(Mutex::new(HashMap::new())),
+            jobs: Arc::new(crate::jobs::JobManager::in_memory()),
             openrouter_oauth: Arc::new(Mutex::new(None)),
+            model_catalog: Arc::new(Mutex::new(None)),
+            chat_runs: Arc::new(Mutex::new(HashMap::new())),
+            tunnel_url: Arc::new(Mutex::new(None)),
         };
 
         let Some((status, Json(payload))) = pairing_auth_error(&state, &HeaderMap::new(), "test") else {
@@ -13803,7 +21828,7 @@ mod tests {
         std::fs::write(posts_dir.join("workflow_post.md"), "# post\n").unwrap();
         std::fs::write(legacy_feed_dir.join("legacy_clip.md"), "# old\n").unwrap();
 
-        let items = list_workspace_library_items(workspace, "feed", 20).unwrap();
+        let items = list_workspace_library_items(workspace, "feed", 20, None, "").unwrap();
         assert!(!items.is_empty());
 
         let paths: Vec<String> = items
@@ -13816,6 +21841,42 @@ mod tests {
         assert!(!paths.iter().any(|path| path.starts_with("journals/processed/")));
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn list_library_external_scope_follows_symlinks_into_allowed_roots_only() {
+        let temp = tempfile::tempdir().unwrap();
+        let workspace = temp.path().join("workspace");
+        let nas = temp.path().join("nas");
+        let private = temp.path().join("private");
+        for dir in [&workspace.join("journals/text"), &nas, &private] {
+            std::fs::create_dir_all(dir).unwrap();
+        }
+        std::fs::write(workspace.join("journals/text/note.md"), "# local\n").unwrap();
+        std::fs::write(nas.join("field.md"), "# nas\n").unwrap();
+        std::fs::write(private.join("secret.md"), "# private\n").unwrap();
+        std::os::unix::fs::symlink(&nas, workspace.join("journals/nas")).unwrap();
+        std::os::unix::fs::symlink(&private, workspace.join("journals/private")).unwrap();
+        crate::workspace::set_external_roots(&workspace, [(nas.clone(), false)]);
+
+        let paths = |scope: &str| -> Vec<String> {
+            list_workspace_library_items(&workspace, scope, 20, None, "")
+                .unwrap()
+                .iter()
+                .filter_map(|item| item["path"].as_str().map(str::to_string))
+                .collect()
+        };
+        assert_eq!(paths("external"), ["journals/nas/field.md"]);
+        assert_eq!(paths("all"), ["journals/text/note.md"]);
+
+        let items = list_workspace_library_items(&workspace, "external", 20, None, "").unwrap();
+        assert_eq!(items[0]["external"], true);
+        assert_eq!(items[0]["editableText"], false);
+        assert_eq!(
+            ensure_writable(&workspace, &workspace.join("journals/nas/field.md")),
+            Err(ResolveError::ReadOnly)
+        );
+    }
+
     #[test]
     fn list_library_all_scope_keeps_journal_and_feed_labels() {
         let temp = tempfile::tempdir().unwrap();
@@ -13826,7 +21887,7 @@ mod tests {
         std::fs::write(workspace.join("posts/feed_note.md"), "# feed\n").unwrap();
         std::fs::write(workspace.join("journals/text/note.md"), "# journal\n").unwrap();
 
-        let items = list_workspace_library_items(workspace, "all", 20).unwrap();
+        let items = list_workspace_library_items(workspace, "all", 20, None, "").unwrap();
         assert!(items.len() >= 2);
 
         let mut has_feed = false;
@@ -13869,7 +21930,7 @@ mod tests {
             auto_save: false,
             webhook_secret_hash: None,
             pairing: Arc::new(PairingGuard::new(false, &[])),
-            trust_forwarded_headers: false,
+            trusted_proxies: TrustedProxies::default(),
             rate_limiter: Arc::new(GatewayRateLimiter::new(100, 100, 100)),
             idempotency_store: Arc::new(IdempotencyStore::new(Duration::from_secs(300), 1000)),
             pb_chat_base_url: None,
@@ -13877,7 +21938,11 @@ mod tests {
             pb_chat_token: None,
             observer: Arc::new(crate::observability::NoopObserver),
             journal_transcription_jobs: Arc::new(Mutex::new(HashMap::new())),
+            jobs: Arc::new(crate::jobs::JobManager::in_memory()),
             openrouter_oauth: Arc::new(Mutex::new(None)),
+            model_catalog: Arc::new(Mutex::new(None)),
+            chat_runs: Arc::new(Mutex::new(HashMap::new())),
+            tunnel_url: Arc::new(Mutex::new(None)),
         };
 
         let response = handle_feed_workflow_template_create(
@@ -13917,7 +21982,7 @@ mod tests {
             auto_save: false,
             webhook_secret_hash: None,
             pairing: Arc::new(PairingGuard::new(false, &[])),
-            trust_forwarded_headers: false,
+            trusted_proxies: TrustedProxies::default(),
             rate_limiter: Arc::new(GatewayRateLimiter::new(100, 100, 100)),
             idempotency_store: Arc::new(IdempotencyStore::new(Duration::from_secs(300), 1000)),
             pb_chat_base_url: None,
@@ -13925,7 +21990,11 @@ mod tests {
             pb_chat_token: None,
             observer: Arc::new(crate::observability::NoopObserver),
             journal_transcription_jobs: Arc::new(Mutex::new(HashMap::new())),
+            jobs: Arc::new(crate::jobs::JobManager::in_memory()),
             openrouter_oauth: Arc::new(Mutex::new(None)),
+            model_catalog: Arc::new(Mutex::new(None)),
+            chat_runs: Arc::new(Mutex::new(HashMap::new())),
+            tunnel_url: Arc::new(Mutex::new(None)),
         };
 
         let response = handle_feed_workflow_template_create(
@@ -14836,4 +22905,757 @@ mod tests {
         );
     }
 
+
+    fn router_test_config(tmp: &tempfile::TempDir) -> Config {
+        let mut config = Config {
+            workspace_dir: tmp.path().join("workspace"),
+            config_path: tmp.path().join("config.toml"),
+            ..Config::default()
+        };
+        config.gateway.min_free_disk_bytes = 0;
+        std::fs::create_dir_all(&config.workspace_dir).unwrap();
+        config
+    }
+
+    #[tokio::test]
+    async fn router_rejects_oversized_webhook_body_with_413() {
+        let tmp = tempfile::tempdir().unwrap();
+        let config = router_test_config(&tmp);
+        let mut state = test_app_state_with_config(config.clone());
+        let token = test_support::with_paired_token(&mut state);
+        let gateway = test_support::TestGateway::spawn(state, &config).await;
+
+        let message = "x".repeat(MAX_BODY_SIZE + 1);
+        let response = reqwest::Client::new()
+            .post(gateway.url("/webhook"))
+            .bearer_auth(token)
+            .json(&serde_json::json!({ "message": message }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::PAYLOAD_TOO_LARGE);
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["code"], "PAYLOAD_TOO_LARGE");
+        assert_eq!(body["limit_bytes"], MAX_BODY_SIZE);
+        assert!(body["request_id"].as_str().is_some_and(|id| !id.is_empty()));
+    }
+
+    #[cfg(not(feature = "channel-whatsapp"))]
+    #[tokio::test]
+    async fn router_whatsapp_routes_answer_404_without_the_feature() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut config = router_test_config(&tmp);
+        config.channels_config.whatsapp = Some(crate::config::schema::WhatsAppConfig {
+            access_token: Some("access-token".into()),
+            phone_number_id: Some("106540352242922".into()),
+            verify_token: Some("slowclaw-verify".into()),
+            app_secret: Some("slowclaw-whatsapp-secret".into()),
+            session_path: None,
+            pair_phone: None,
+            pair_code: None,
+            allowed_numbers: vec!["*".into()],
+            reply_template: None,
+            reply_template_language: None,
+            api_url: None,
+        });
+        let state = test_app_state_with_config(config.clone());
+        let gateway = test_support::TestGateway::spawn(state, &config).await;
+        let client = reqwest::Client::new();
+
+        let verify = client
+            .get(gateway.url(
+                "/whatsapp?hub.mode=subscribe&hub.verify_token=slowclaw-verify&hub.challenge=1",
+            ))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(verify.status(), reqwest::StatusCode::NOT_FOUND);
+        let webhook = client
+            .post(gateway.url("/whatsapp"))
+            .body(include_str!(
+                "../../tests/fixtures/whatsapp/text_message.json"
+            ))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(webhook.status(), reqwest::StatusCode::NOT_FOUND);
+        let body: serde_json::Value = webhook.json().await.unwrap();
+        assert_eq!(body["code"], "ROUTE_NOT_FOUND");
+    }
+
+    #[tokio::test]
+    async fn router_body_limits_apply_at_the_configured_byte() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut config = router_test_config(&tmp);
+        config.gateway.max_body_bytes = 1024;
+        config.gateway.max_media_upload_bytes = 4096;
+        let mut state = test_app_state_with_config(config.clone());
+        test_support::with_paired_token(&mut state);
+        let gateway = test_support::TestGateway::spawn(state, &config).await;
+        let client = reqwest::Client::new();
+
+        // Unauthenticated requests within the limit reach the handler and get
+        // 401; one byte more is refused before it.
+        for (path, limit) in [("/webhook", 1024), ("/api/media/upload", 4096)] {
+            for (size, expected) in [
+                (limit, reqwest::StatusCode::UNAUTHORIZED),
+                (limit + 1, reqwest::StatusCode::PAYLOAD_TOO_LARGE),
+            ] {
+                let response = client
+                    .post(gateway.url(path))
+                    .header("X-Request-Id", "req-42")
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(vec![b' '; size])
+                    .send()
+                    .await
+                    .unwrap();
+                assert_eq!(response.status(), expected, "{path} with {size} bytes");
+                if expected == reqwest::StatusCode::PAYLOAD_TOO_LARGE {
+                    let body: serde_json::Value = response.json().await.unwrap();
+                    assert_eq!(body["limit_bytes"], limit, "{path}");
+                    assert_eq!(body["request_id"], "req-42", "{path}");
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn router_times_out_slow_request_bodies_with_408() {
+        let tmp = tempfile::tempdir().unwrap();
+        let config = router_test_config(&tmp);
+        let mut state = test_app_state_with_config(config.clone());
+        let token = test_support::with_paired_token(&mut state);
+        let limits = RouterLimits {
+            request_timeout: Duration::from_millis(200),
+            ..RouterLimits::default()
+        };
+        let gateway = test_support::TestGateway::spawn_with_limits(state, &config, limits).await;
+
+        use futures_util::StreamExt;
+
+        // Send the start of the JSON body and then stall forever.
+        let stalled = futures_util::stream::once(async {
+            Ok::<_, std::io::Error>(b"{\"message\":".to_vec())
+        })
+        .chain(futures_util::stream::pending());
+        let response = tokio::time::timeout(
+            Duration::from_secs(10),
+            reqwest::Client::new()
+                .post(gateway.url("/webhook"))
+                .bearer_auth(token)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(reqwest::Body::wrap_stream(stalled))
+                .send(),
+        )
+        .await
+        .expect("gateway answers before the client gives up")
+        .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::REQUEST_TIMEOUT);
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["code"], "REQUEST_TIMEOUT");
+    }
+
+    #[tokio::test]
+    async fn router_pairing_flow_issues_a_working_token() {
+        let tmp = tempfile::tempdir().unwrap();
+        let config = router_test_config(&tmp);
+        let mut state = test_app_state_with_config(config.clone());
+        state.pairing = Arc::new(PairingGuard::new(true, &[]));
+        let gateway = test_support::TestGateway::spawn(state, &config).await;
+        let client = reqwest::Client::new();
+        let webhook = |token: Option<&str>| {
+            let mut req = client
+                .post(gateway.url("/webhook"))
+                .json(&serde_json::json!({ "message": "hello" }));
+            if let Some(token) = token {
+                req = req.bearer_auth(token);
+            }
+            req.send()
+        };
+
+        let response = webhook(None).await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+        let code = gateway.state.pairing.pairing_code().unwrap();
+        let token = gateway.pair().await;
+        let response = webhook(Some(&token)).await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        let response = webhook(Some("zc_not_a_token")).await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+        // The one-time code is spent.
+        let response = client
+            .post(gateway.url("/pair"))
+            .header("X-Pairing-Code", code)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn router_moves_every_route_under_the_base_path() {
+        let tmp = tempfile::tempdir().unwrap();
+        let media = tmp.path().join("workspace/journals/media/clip.mp4");
+        std::fs::create_dir_all(media.parent().unwrap()).unwrap();
+        std::fs::write(&media, b"video").unwrap();
+        let client = reqwest::Client::new();
+        for (base_path, prefix) in [("", ""), ("/slowclaw/", "/slowclaw")] {
+            let mut config = router_test_config(&tmp);
+            config.gateway.base_path = base_path.into();
+            let mut state = test_app_state_with_config(config.clone());
+            let token = test_support::with_paired_token(&mut state);
+            let gateway = test_support::TestGateway::spawn(state, &config).await;
+
+            let health = client
+                .get(gateway.url(&format!("{prefix}/health")))
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(health.status(), reqwest::StatusCode::OK, "{base_path:?}");
+            let listed: serde_json::Value = client
+                .get(gateway.url(&format!("{prefix}/api/library/items?scope=journal")))
+                .bearer_auth(&token)
+                .send()
+                .await
+                .unwrap()
+                .json()
+                .await
+                .unwrap();
+            let media_url = listed["items"][0]["mediaUrl"].as_str().unwrap();
+            assert_eq!(
+                media_url,
+                format!("{prefix}/api/media/journals/media/clip.mp4")
+            );
+            let streamed = client
+                .get(gateway.url(media_url))
+                .bearer_auth(&token)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(streamed.status(), reqwest::StatusCode::OK, "{media_url}");
+            if prefix.is_empty() {
+                continue;
+            }
+
+            let unprefixed = client.get(gateway.url("/health")).send().await.unwrap();
+            assert_eq!(unprefixed.status(), reqwest::StatusCode::NOT_FOUND);
+            let body: serde_json::Value = unprefixed.json().await.unwrap();
+            assert_eq!(body["code"], "ROUTE_NOT_FOUND");
+            let shell = client
+                .get(gateway.url("/slowclaw/journal"))
+                .send()
+                .await
+                .unwrap();
+            if cfg!(feature = "embed-ui") {
+                let html = shell.text().await.unwrap();
+                assert!(html.contains("<base href=\"/slowclaw/\" />"), "{html}");
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn router_serves_the_spa_only_when_the_ui_is_enabled() {
+        let tmp = tempfile::tempdir().unwrap();
+        let client = reqwest::Client::new();
+        for serve_ui in [true, false] {
+            let mut config = router_test_config(&tmp);
+            config.gateway.serve_ui = serve_ui;
+            let state = test_app_state_with_config(config.clone());
+            let gateway = test_support::TestGateway::spawn(state, &config).await;
+
+            let root = client.get(gateway.url("/")).send().await.unwrap();
+            if serve_ui {
+                // Without embed-ui the SPA fallback answers with a text notice.
+                if cfg!(feature = "embed-ui") {
+                    assert_eq!(root.status(), reqwest::StatusCode::OK);
+                    let content_type = &root.headers()[reqwest::header::CONTENT_TYPE];
+                    assert!(content_type.to_str().unwrap().starts_with("text/html"));
+                }
+                assert!(!root.text().await.unwrap().contains("ROUTE_NOT_FOUND"));
+                continue;
+            }
+            assert_eq!(root.status(), reqwest::StatusCode::NOT_FOUND);
+            let body: serde_json::Value = root.json().await.unwrap();
+            assert_eq!(body["code"], "ROUTE_NOT_FOUND");
+            let asset = client
+                .get(gateway.url("/_app/index.html"))
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(asset.status(), reqwest::StatusCode::NOT_FOUND);
+            let health = client.get(gateway.url("/health")).send().await.unwrap();
+            assert_eq!(health.status(), reqwest::StatusCode::OK);
+        }
+    }
+
+    #[tokio::test]
+    async fn router_read_only_mode_refuses_every_mutating_route() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut config = router_test_config(&tmp);
+        config.gateway.read_only = true;
+        let mut state = test_app_state_with_config(config.clone());
+        let token = test_support::with_paired_token(&mut state);
+        let gateway = test_support::TestGateway::spawn(state, &config).await;
+        let client = reqwest::Client::new();
+
+        let mutating = [
+            (Method::POST, "/api/config/runtime"),
+            (Method::POST, "/webhook"),
+            (Method::POST, "/webhook/batch"),
+            (Method::POST, "/telegram"),
+            (Method::POST, "/api/chat/messages"),
+            (Method::POST, "/api/chat/cancel"),
+            (Method::PUT, "/api/chat/threads/t_1/settings"),
+            (Method::POST, "/api/feed/workflow-comment"),
+            (Method::POST, "/api/feed/bluesky/personalized"),
+            (Method::POST, "/api/feed/personalized"),
+            (Method::POST, "/api/sync/import"),
+            (Method::POST, "/api/feed/workflow-run"),
+            (Method::POST, "/api/feed/workflow-auto-run"),
+            (Method::PATCH, "/api/workspace/synthesizer/skills"),
+            (Method::POST, "/api/workspace/synthesizer/run"),
+            (Method::POST, "/api/workspace/synthesizer/auto-run"),
+            (Method::POST, "/api/workspace/world-feed/interests"),
+            (Method::DELETE, "/api/workspace/world-feed/interests/wi_1"),
+            (Method::PATCH, "/api/workspace/world-feed/interests/wi_1"),
+            (Method::PATCH, "/api/workspace/todos/todo_1"),
+            (Method::POST, "/api/provider/reset"),
+            (Method::DELETE, "/api/memory"),
+            (Method::DELETE, "/api/memory/some_key"),
+            (Method::POST, "/api/drafts"),
+            (Method::POST, "/api/drafts/d_1/approve"),
+            (Method::POST, "/api/drafts/d_1/reject"),
+            (Method::POST, "/api/approvals/ap_1/approve"),
+            (Method::POST, "/api/approvals/ap_1/deny"),
+            (Method::POST, "/api/post-history"),
+            (Method::POST, "/api/auth/openrouter/start"),
+            (Method::POST, "/api/feed/workflow-settings"),
+            (Method::POST, "/api/feed/workflow-template"),
+            (Method::POST, "/api/journal/text"),
+            (Method::POST, "/api/library/save-text"),
+            (Method::POST, "/api/library/versions/restore"),
+            (Method::POST, "/api/library/revert"),
+            (Method::POST, "/api/keys"),
+            (Method::DELETE, "/api/keys/key_1"),
+            (Method::POST, "/api/media/upload"),
+            (Method::POST, "/api/journal/transcribe"),
+            (Method::POST, "/api/library/delete"),
+            (Method::POST, "/api/library/trash/restore"),
+            (Method::POST, "/api/library/tags"),
+            (Method::POST, "/api/memory/import"),
+        ];
+        for (method, path) in mutating {
+            let response = client
+                .request(method.clone(), gateway.url(path))
+                .bearer_auth(&token)
+                .json(&serde_json::json!({ "message": "hello" }))
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(
+                response.status(),
+                reqwest::StatusCode::FORBIDDEN,
+                "{method} {path}"
+            );
+            let body: serde_json::Value = response.json().await.unwrap();
+            assert_eq!(body["code"], "READ_ONLY_MODE", "{method} {path}");
+        }
+
+        let health: serde_json::Value = client
+            .get(gateway.url("/health"))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(health["status"], "ok");
+        assert_eq!(health["read_only"], true);
+        let response = client
+            .get(gateway.url("/api/gateway-info"))
+            .bearer_auth(&token)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn router_media_upload_streams_back_byte_for_byte() {
+        let tmp = tempfile::tempdir().unwrap();
+        let config = router_test_config(&tmp);
+        let mut state = test_app_state_with_config(config.clone());
+        let token = test_support::with_paired_token(&mut state);
+        let gateway = test_support::TestGateway::spawn(state, &config).await;
+        let client = reqwest::Client::new();
+        // Larger than the core body limit, so the media router's limit applies.
+        let payload: Vec<u8> = (0..MAX_BODY_SIZE * 2)
+            .map(|i| u8::try_from(i % 251).unwrap())
+            .collect();
+
+        let response = client
+            .post(gateway.url("/api/media/upload"))
+            .query(&[("filename", "photo.png"), ("kind", "image")])
+            .bearer_auth(&token)
+            .header(header::CONTENT_TYPE, "image/png")
+            .body(payload.clone())
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        let uploaded: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(uploaded["bytes"], payload.len());
+        let rel_path = uploaded["path"].as_str().unwrap().to_string();
+
+        let response = client
+            .get(gateway.url(&format!("/api/media/{rel_path}")))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+        let response = client
+            .get(gateway.url(&format!("/api/media/{rel_path}")))
+            .bearer_auth(&token)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        assert_eq!(response.bytes().await.unwrap().as_ref(), payload.as_slice());
+    }
+
+    #[tokio::test]
+    async fn router_library_save_text_is_capped_and_versioned() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut config = router_test_config(&tmp);
+        config.gateway.max_text_bytes = 4096;
+        config.gateway.text_versions_keep = 2;
+        let mut state = test_app_state_with_config(config.clone());
+        let token = test_support::with_paired_token(&mut state);
+        let gateway = test_support::TestGateway::spawn(state, &config).await;
+        let client = reqwest::Client::new();
+        let path = "journals/text/note.md";
+        let save = |content: String| {
+            client
+                .post(gateway.url("/api/library/save-text"))
+                .bearer_auth(&token)
+                .json(&serde_json::json!({ "path": path, "content": content }))
+                .send()
+        };
+
+        let response = save("x".repeat(5000)).await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::PAYLOAD_TOO_LARGE);
+        assert!(!config.workspace_dir.join(path).exists());
+
+        for content in ["first", "second", "third", "fourth"] {
+            let response = save(content.to_string()).await.unwrap();
+            assert_eq!(response.status(), reqwest::StatusCode::OK);
+        }
+        let listed: serde_json::Value = client
+            .get(gateway.url("/api/library/versions"))
+            .query(&[("path", path)])
+            .bearer_auth(&token)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        let versions = listed["versions"].as_array().unwrap();
+        assert_eq!(versions.len(), 2, "{listed}");
+        let newest = versions[0]["id"].as_str().unwrap().to_string();
+
+        let restore = |id: &str| {
+            client
+                .post(gateway.url("/api/library/versions/restore"))
+                .bearer_auth(&token)
+                .json(&serde_json::json!({ "path": path, "id": id }))
+                .send()
+        };
+        let response = restore(&newest).await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        let restored: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(restored["restored"], newest.as_str());
+        assert!(restored["version"]["id"].is_string(), "{restored}");
+        assert_eq!(
+            std::fs::read_to_string(config.workspace_dir.join(path)).unwrap(),
+            "third"
+        );
+
+        let response = restore("20000101T000000000Z").await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+        let response = client
+            .get(gateway.url("/api/library/versions"))
+            .query(&[("path", "../outside.md")])
+            .bearer_auth(&token)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn api_keys_reach_only_their_routes_until_revoked() {
+        let tmp = tempfile::tempdir().unwrap();
+        let config = router_test_config(&tmp);
+        let mut state = test_app_state_with_config(config.clone());
+        let token = test_support::with_paired_token(&mut state);
+        let gateway = test_support::TestGateway::spawn(state, &config).await;
+        let client = reqwest::Client::new();
+
+        let response = client
+            .post(gateway.url("/api/keys"))
+            .bearer_auth(&token)
+            .json(&serde_json::json!({ "label": "Shortcuts", "routes": ["webhook"] }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+        let created: serde_json::Value = client
+            .post(gateway.url("/api/keys"))
+            .bearer_auth(&token)
+            .json(&serde_json::json!({
+                "label": "Home Assistant",
+                "routes": [
+                    "POST /webhook",
+                    "* /api/keys",
+                    "POST /api/approvals/**",
+                    "POST /api/provider/reset",
+                    "* /api/memory",
+                    "* /api/memory/*",
+                    "POST /api/drafts/**",
+                ],
+            }))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        let key = created["key"].as_str().unwrap().to_string();
+        let id = created["item"]["id"].as_str().unwrap().to_string();
+        assert!(key.starts_with(api_keys::KEY_PREFIX), "{created}");
+
+        let response = client
+            .post(gateway.url("/webhook"))
+            .bearer_auth(&key)
+            .json(&serde_json::json!({ "message": "hello" }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        for (path, status, code) in [
+            ("/api/feed", 403, "API_KEY_ROUTE_NOT_ALLOWED"),
+            ("/api/keys", 403, "API_KEY_FORBIDDEN"),
+        ] {
+            let response = client
+                .get(gateway.url(path))
+                .bearer_auth(&key)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.status().as_u16(), status, "{path}");
+            let body: serde_json::Value = response.json().await.unwrap();
+            assert_eq!(body["code"], code, "{path}");
+        }
+        for (method, path) in [
+            (reqwest::Method::POST, "/api/approvals/ap_1/approve"),
+            (reqwest::Method::POST, "/api/provider/reset"),
+            (reqwest::Method::DELETE, "/api/memory/some-key"),
+            (reqwest::Method::DELETE, "/api/memory?session=s1"),
+            (reqwest::Method::GET, "/api/memory/export"),
+            (reqwest::Method::POST, "/api/memory/import"),
+            (reqwest::Method::POST, "/api/drafts/d_1/approve"),
+            (reqwest::Method::POST, "/api/drafts/d_1/reject"),
+        ] {
+            let response = client
+                .request(method, gateway.url(path))
+                .bearer_auth(&key)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN, "{path}");
+            let body: serde_json::Value = response.json().await.unwrap();
+            assert_eq!(body["code"], "API_KEY_FORBIDDEN", "{path}");
+        }
+
+        let listed: serde_json::Value = client
+            .get(gateway.url("/api/keys"))
+            .bearer_auth(&token)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        let item = listed["items"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|item| item["id"] == id.as_str())
+            .unwrap();
+        assert!(item["lastUsedAt"].is_string(), "{listed}");
+        assert!(!listed.to_string().contains(&key));
+
+        let response = client
+            .delete(gateway.url(&format!("/api/keys/{id}")))
+            .bearer_auth(&token)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        let response = client
+            .post(gateway.url("/webhook"))
+            .bearer_auth(&key)
+            .json(&serde_json::json!({ "message": "hello" }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["code"], "API_KEY_INVALID");
+
+        let audit_log = tmp.path().join("audit.log");
+        for _ in 0..50 {
+            if std::fs::read_to_string(&audit_log).is_ok_and(|log| log.lines().count() >= 4) {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        let log = std::fs::read_to_string(&audit_log).unwrap();
+        assert!(log.contains(&id) && log.contains("POST /webhook"), "{log}");
+    }
+
+    #[tokio::test]
+    async fn media_upload_classifies_by_sniffed_content() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut config = router_test_config(&tmp);
+        config.transcription.enabled = false;
+        local_store::initialize(&config.workspace_dir).unwrap();
+        let mut state = test_app_state_with_config(config.clone());
+        let token = test_support::with_paired_token(&mut state);
+        let gateway = test_support::TestGateway::spawn(state, &config).await;
+        let client = reqwest::Client::new();
+        let fixture = |head: &[u8]| {
+            let mut bytes = head.to_vec();
+            bytes.resize(256, 0);
+            bytes
+        };
+        let m4a = fixture(b"\x00\x00\x00\x20ftypM4A \x00\x00\x00\x00M4A mp42isom");
+        let mp4 = fixture(b"\x00\x00\x00\x18ftypmp42\x00\x00\x00\x00mp42isom");
+        let png = fixture(b"\x89PNG\r\n\x1a\n\x00\x00\x00\x0dIHDR");
+        let upload = |name: &str, kind: &str, content_type: &str, body: Vec<u8>, strict: bool| {
+            let mut query = vec![("filename", name.to_string()), ("kind", kind.to_string())];
+            if strict {
+                query.push(("strict", "1".to_string()));
+            }
+            client
+                .post(gateway.url("/api/media/upload"))
+                .query(&query)
+                .bearer_auth(&token)
+                .header(header::CONTENT_TYPE, content_type)
+                .body(body)
+                .send()
+        };
+
+        // (declared kind, declared type, body) -> (stored kind, stored type)
+        let cases = [
+            ("video", "video/mp4", m4a.clone(), "audio", "audio/mp4"),
+            ("video", "video/mp4", mp4.clone(), "video", "video/mp4"),
+            ("audio", "audio/x-m4a", m4a.clone(), "audio", "audio/mp4"),
+            (
+                "file",
+                "application/octet-stream",
+                png,
+                "image",
+                "image/png",
+            ),
+            (
+                "file",
+                "text/plain",
+                b"plain notes".to_vec(),
+                "file",
+                "text/plain",
+            ),
+        ];
+        for (index, (kind, content_type, body, want_kind, want_type)) in
+            cases.into_iter().enumerate()
+        {
+            let name = format!("upload-{index}");
+            let response = upload(&name, kind, content_type, body, false)
+                .await
+                .unwrap();
+            assert_eq!(response.status(), reqwest::StatusCode::OK, "{name}");
+            let uploaded: serde_json::Value = response.json().await.unwrap();
+            assert_eq!(uploaded["kind"], want_kind, "{name}");
+            assert_eq!(uploaded["contentType"], want_type, "{name}");
+            assert_eq!(uploaded["kindCorrected"], kind != want_kind, "{name}");
+            assert_eq!(uploaded["metadata"]["mimeType"], want_type, "{name}");
+            let dir = match want_kind {
+                "file" => "files",
+                other => other,
+            };
+            let path = uploaded["path"].as_str().unwrap();
+            assert!(
+                path.starts_with(&format!("journals/media/{dir}/")),
+                "{path}"
+            );
+        }
+
+        // Strict mode refuses the mismatch and stores nothing.
+        let response = upload("strict.mp4", "video", "video/mp4", m4a, true)
+            .await
+            .unwrap();
+        assert_eq!(
+            response.status(),
+            reqwest::StatusCode::UNSUPPORTED_MEDIA_TYPE
+        );
+        let rejected: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(rejected["code"], "MEDIA_KIND_MISMATCH");
+        assert_eq!(rejected["sniffedKind"], "audio");
+        let stored = format!(
+            "{}/journals/media/**/*strict.mp4",
+            config.workspace_dir.display()
+        );
+        assert_eq!(glob::glob(&stored).unwrap().count(), 0);
+
+        // A matching label passes strict mode.
+        let response = upload("clip.mp4", "video", "video/mp4", mp4, true)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn router_falls_back_to_the_spa_for_unknown_get_paths() {
+        let tmp = tempfile::tempdir().unwrap();
+        let config = router_test_config(&tmp);
+        let state = test_app_state_with_config(config.clone());
+        let gateway = test_support::TestGateway::spawn(state, &config).await;
+        let client = reqwest::Client::new();
+
+        let index = client.get(gateway.url("/")).send().await.unwrap();
+        let index_status = index.status();
+        let index_body = index.bytes().await.unwrap();
+        let deep = client
+            .get(gateway.url("/library/journal/2026"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(deep.status(), index_status);
+        assert_eq!(deep.bytes().await.unwrap(), index_body);
+
+        // API routes are not shadowed, and the fallback only answers GET.
+        let health = client.get(gateway.url("/health")).send().await.unwrap();
+        assert_eq!(health.status(), reqwest::StatusCode::OK);
+        let health: serde_json::Value = health.json().await.unwrap();
+        assert_eq!(health["status"], "ok");
+        let post = client
+            .post(gateway.url("/library/journal/2026"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(post.status(), reqwest::StatusCode::METHOD_NOT_ALLOWED);
+    }
 }
@@ -0,0 +1,439 @@
+//! Full-text index over journal text files (`state/search/journal.db`).
+//!
+//! Handlers that write journal text call [`index_journal_file`] so the index
+//! stays current without rescanning; [`sync_journal_index`] reconciles edits
+//! made outside the gateway (SSH, sync tools) and runs lazily from search.
+
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+const SEARCH_DB_FILE: &str = "journal.db";
+const SYNC_INTERVAL_SECS: i64 = 300;
+const SNIPPET_TOKENS: i64 = 16;
+pub const MATCH_OPEN: &str = "<mark>";
+pub const MATCH_CLOSE: &str = "</mark>";
+// Private-use sentinels FTS5 wraps matches in, swapped for the markers once
+// the surrounding journal text has been HTML-escaped.
+const SNIPPET_OPEN: &str = "\u{E000}";
+const SNIPPET_CLOSE: &str = "\u{E001}";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct JournalSearchHit {
+    pub path: String,
+    pub title: String,
+    pub snippet: String,
+    pub modified_at: i64,
+    pub score: f64,
+}
+
+fn search_dir(workspace_dir: &Path) -> PathBuf {
+    workspace_dir.join("state").join("search")
+}
+
+fn open_index(workspace_dir: &Path) -> Result<Connection> {
+    let dir = search_dir(workspace_dir);
+    std::fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    let conn = Connection::open(dir.join(SEARCH_DB_FILE)).context("Failed to open search index")?;
+    conn.execute_batch(
+        "PRAGMA journal_mode = WAL;
+        CREATE TABLE IF NOT EXISTS journal_docs (
+            path TEXT PRIMARY KEY,
+            size_bytes INTEGER NOT NULL,
+            modified_at INTEGER NOT NULL
+        );
+        CREATE VIRTUAL TABLE IF NOT EXISTS journal_docs_fts USING fts5(
+            path UNINDEXED, title, body, tokenize = 'porter unicode61'
+        );
+        CREATE TABLE IF NOT EXISTS index_state (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            last_sync_at INTEGER NOT NULL
+        );
+        INSERT OR IGNORE INTO index_state (id, last_sync_at) VALUES (1, 0);",
+    )
+    .context("Failed to initialize search index schema")?;
+    Ok(conn)
+}
+
+/// Journal files worth indexing: Markdown and plain text under `journals/`.
+pub fn is_indexable_journal_path(rel_path: &str) -> bool {
+    let lower = rel_path.to_ascii_lowercase();
+    lower.starts_with("journals/") && (lower.ends_with(".md") || lower.ends_with(".txt"))
+}
+
+fn file_stamp(path: &Path) -> Option<(i64, i64)> {
+    let meta = std::fs::metadata(path).ok()?;
+    let modified = meta
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| i64::try_from(d.as_secs()).unwrap_or(0))
+        .unwrap_or(0);
+    Some((i64::try_from(meta.len()).unwrap_or(i64::MAX), modified))
+}
+
+fn derive_title(rel_path: &str, body: &str) -> String {
+    body.lines()
+        .map(str::trim)
+        .find_map(|line| line.strip_prefix("# "))
+        .map(|heading| heading.trim().to_string())
+        .unwrap_or_else(|| {
+            Path::new(rel_path)
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or("untitled")
+                .replace(['_', '-'], " ")
+        })
+}
+
+fn upsert_doc(conn: &Connection, workspace_dir: &Path, rel_path: &str) -> Result<()> {
+    let abs = workspace_dir.join(rel_path);
+    let Some((size, modified)) = file_stamp(&abs) else {
+        return remove_doc(conn, rel_path);
+    };
+    let body = std::fs::read_to_string(&abs).unwrap_or_default();
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(
+        "DELETE FROM journal_docs_fts WHERE path = ?1",
+        params![rel_path],
+    )?;
+    tx.execute(
+        "INSERT INTO journal_docs_fts (path, title, body) VALUES (?1, ?2, ?3)",
+        params![rel_path, derive_title(rel_path, &body), body],
+    )?;
+    tx.execute(
+        "INSERT INTO journal_docs (path, size_bytes, modified_at) VALUES (?1, ?2, ?3)
+         ON CONFLICT(path) DO UPDATE SET size_bytes = excluded.size_bytes,
+             modified_at = excluded.modified_at",
+        params![rel_path, size, modified],
+    )?;
+    tx.commit()?;
+    Ok(())
+}
+
+fn remove_doc(conn: &Connection, rel_path: &str) -> Result<()> {
+    conn.execute(
+        "DELETE FROM journal_docs_fts WHERE path = ?1",
+        params![rel_path],
+    )?;
+    conn.execute(
+        "DELETE FROM journal_docs WHERE path = ?1",
+        params![rel_path],
+    )?;
+    Ok(())
+}
+
+/// Reindex (or drop, if it no longer exists) a single journal file.
+pub fn index_journal_file(workspace_dir: &Path, rel_path: &str) -> Result<()> {
+    if !is_indexable_journal_path(rel_path) {
+        return Ok(());
+    }
+    let conn = open_index(workspace_dir)?;
+    upsert_doc(&conn, workspace_dir, rel_path)
+}
+
+/// Best-effort wrapper for write paths that must not fail on index errors.
+pub fn note_journal_file_changed(workspace_dir: &Path, rel_path: &str) {
+    if let Err(err) = index_journal_file(workspace_dir, rel_path) {
+        tracing::warn!(path = %rel_path, "Search index update failed: {err:#}");
+    }
+}
+
+/// [`note_journal_file_changed`] for async handlers, run on the blocking pool.
+pub async fn journal_file_changed(workspace_dir: &Path, rel_path: &str) {
+    if !is_indexable_journal_path(rel_path) {
+        return;
+    }
+    let workspace_dir = workspace_dir.to_path_buf();
+    let rel_path = rel_path.to_string();
+    if let Err(err) = tokio::task::spawn_blocking(move || {
+        note_journal_file_changed(&workspace_dir, &rel_path);
+    })
+    .await
+    {
+        tracing::warn!("Search index update task failed: {err}");
+    }
+}
+
+fn collect_journal_files(workspace_dir: &Path, dir: &Path, out: &mut Vec<String>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            collect_journal_files(workspace_dir, &path, out);
+        } else if file_type.is_file() {
+            if let Ok(rel) = path.strip_prefix(workspace_dir) {
+                let rel = rel.to_string_lossy().replace('\\', "/");
+                if is_indexable_journal_path(&rel) {
+                    out.push(rel);
+                }
+            }
+        }
+    }
+}
+
+/// Bring the index in line with the filesystem, touching only changed files.
+pub fn sync_journal_index(workspace_dir: &Path) -> Result<usize> {
+    let conn = open_index(workspace_dir)?;
+    let mut files = Vec::new();
+    collect_journal_files(workspace_dir, &workspace_dir.join("journals"), &mut files);
+
+    let mut changed = 0;
+    for rel in &files {
+        let Some((size, modified)) = file_stamp(&workspace_dir.join(rel)) else {
+            continue;
+        };
+        let known: Option<(i64, i64)> = conn
+            .query_row(
+                "SELECT size_bytes, modified_at FROM journal_docs WHERE path = ?1",
+                params![rel],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        if known != Some((size, modified)) {
+            upsert_doc(&conn, workspace_dir, rel)?;
+            changed += 1;
+        }
+    }
+
+    let indexed: Vec<String> = conn
+        .prepare("SELECT path FROM journal_docs")?
+        .query_map([], |row| row.get(0))?
+        .collect::<rusqlite::Result<_>>()?;
+    let present: HashSet<&String> = files.iter().collect();
+    for rel in indexed {
+        if !present.contains(&rel) {
+            remove_doc(&conn, &rel)?;
+            changed += 1;
+        }
+    }
+    Ok(changed)
+}
+
+/// Sync at most once per [`SYNC_INTERVAL_SECS`] per workspace. The claim is a
+/// conditional update in the index itself, so concurrent searches run one sync.
+fn maybe_sync(workspace_dir: &Path) {
+    let now = chrono::Utc::now().timestamp();
+    let claimed = open_index(workspace_dir).and_then(|conn| {
+        conn.execute(
+            "UPDATE index_state SET last_sync_at = ?1 WHERE id = 1 AND last_sync_at <= ?2",
+            params![now, now - SYNC_INTERVAL_SECS],
+        )
+        .context("Failed to claim search index sync")
+    });
+    match claimed {
+        Ok(1) => {}
+        Ok(_) => return,
+        Err(err) => {
+            tracing::warn!("Search index sync failed: {err:#}");
+            return;
+        }
+    }
+    if let Err(err) = sync_journal_index(workspace_dir) {
+        tracing::warn!("Search index sync failed: {err:#}");
+    }
+}
+
+/// Turn free text into an FTS5 query: every term must match, the last one as a prefix.
+pub fn fts_query(raw: &str) -> Option<String> {
+    let terms: Vec<String> = raw
+        .split_whitespace()
+        .map(|term| term.replace('"', ""))
+        .filter(|term| !term.is_empty())
+        .collect();
+    let (last, rest) = terms.split_last()?;
+    let mut query: Vec<String> = rest.iter().map(|t| format!("\"{t}\"")).collect();
+    query.push(format!("\"{last}\"*"));
+    Some(query.join(" "))
+}
+
+/// HTML-escape a snippet, then turn the FTS sentinels into `<mark>` markers.
+fn mark_snippet(raw: &str) -> String {
+    super::html_escape(raw)
+        .replace(SNIPPET_OPEN, MATCH_OPEN)
+        .replace(SNIPPET_CLOSE, MATCH_CLOSE)
+}
+
+/// Ranked journal matches with HTML-escaped, `<mark>`-highlighted snippets.
+pub fn search_journal(
+    workspace_dir: &Path,
+    query: &str,
+    limit: usize,
+    offset: usize,
+) -> Result<Vec<JournalSearchHit>> {
+    let Some(match_expr) = fts_query(query) else {
+        return Ok(Vec::new());
+    };
+    maybe_sync(workspace_dir);
+    let conn = open_index(workspace_dir)?;
+    let mut stmt = conn.prepare(
+        "SELECT f.path, f.title,
+                snippet(journal_docs_fts, 2, ?2, ?3, '…', ?4),
+                d.modified_at, bm25(journal_docs_fts) AS score
+         FROM journal_docs_fts f
+         JOIN journal_docs d ON d.path = f.path
+         WHERE journal_docs_fts MATCH ?1
+         ORDER BY score
+         LIMIT ?5 OFFSET ?6",
+    )?;
+    let rows = stmt.query_map(
+        params![
+            match_expr,
+            SNIPPET_OPEN,
+            SNIPPET_CLOSE,
+            SNIPPET_TOKENS,
+            i64::try_from(limit).unwrap_or(i64::MAX),
+            i64::try_from(offset).unwrap_or(0)
+        ],
+        |row| {
+            let score: f64 = row.get(4)?;
+            Ok(JournalSearchHit {
+                path: row.get(0)?,
+                title: row.get(1)?,
+                snippet: mark_snippet(&row.get::<_, String>(2)?),
+                modified_at: row.get(3)?,
+                // BM25 is negative with lower = better; flip so higher ranks first.
+                score: -score,
+            })
+        },
+    )?;
+    rows.collect::<rusqlite::Result<_>>()
+        .context("Failed to read search results")
+}
+
+/// Highlight the first case-insensitive occurrence of any query term in `text`,
+/// trimming to a window around it. Used for sources without an FTS index; the
+/// text is HTML-escaped like [`search_journal`] snippets.
+pub fn highlight_snippet(text: &str, query: &str, max_chars: usize) -> String {
+    let lower = text.to_lowercase();
+    let hit = query
+        .split_whitespace()
+        .filter_map(|term| {
+            let term = term.to_lowercase();
+            lower.find(&term).map(|pos| (pos, term.len()))
+        })
+        .min_by_key(|(pos, _)| *pos);
+    let Some((pos, len)) = hit.filter(|_| lower.len() == text.len()) else {
+        return super::html_escape(&crate::util::truncate_with_ellipsis(text, max_chars));
+    };
+    let start = text[..pos]
+        .char_indices()
+        .rev()
+        .nth(max_chars / 3)
+        .map_or(0, |(i, _)| i);
+    let end = text[pos + len..]
+        .char_indices()
+        .nth(max_chars / 2)
+        .map_or(text.len(), |(i, _)| pos + len + i);
+    format!(
+        "{}{}{MATCH_OPEN}{}{MATCH_CLOSE}{}{}",
+        if start > 0 { "…" } else { "" },
+        super::html_escape(&text[start..pos]),
+        super::html_escape(&text[pos..pos + len]),
+        super::html_escape(&text[pos + len..end]),
+        if end < text.len() { "…" } else { "" },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(workspace: &Path, rel: &str, body: &str) {
+        let path = workspace.join(rel);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, body).unwrap();
+    }
+
+    #[test]
+    fn fts_query_quotes_terms_and_prefixes_last() {
+        assert_eq!(
+            fts_query("visa appoint").as_deref(),
+            Some("\"visa\" \"appoint\"*")
+        );
+        assert_eq!(fts_query("  \" ").as_deref(), None);
+    }
+
+    #[test]
+    fn indexed_files_are_searchable_and_removed_when_deleted() {
+        let tmp = tempfile::tempdir().unwrap();
+        let rel = "journals/text/2026-03-01/visa.md";
+        write(
+            tmp.path(),
+            rel,
+            "# Visa\n\nBooked the visa appointment for Tuesday morning.",
+        );
+        index_journal_file(tmp.path(), rel).unwrap();
+
+        let hits = search_journal(tmp.path(), "visa appoint", 10, 0).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].path, rel);
+        assert_eq!(hits[0].title, "Visa");
+        assert!(hits[0].snippet.contains("<mark>appointment</mark>"));
+
+        std::fs::remove_file(tmp.path().join(rel)).unwrap();
+        index_journal_file(tmp.path(), rel).unwrap();
+        assert!(search_journal(tmp.path(), "visa", 10, 0)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn sync_picks_up_external_edits_and_skips_non_journal_files() {
+        let tmp = tempfile::tempdir().unwrap();
+        write(tmp.path(), "journals/text/a.md", "alpha note");
+        write(tmp.path(), "journals/text/b.txt", "beta note");
+        write(tmp.path(), "posts/feed.md", "alpha in the feed");
+        assert_eq!(sync_journal_index(tmp.path()).unwrap(), 2);
+        assert_eq!(sync_journal_index(tmp.path()).unwrap(), 0);
+
+        std::fs::remove_file(tmp.path().join("journals/text/b.txt")).unwrap();
+        assert_eq!(sync_journal_index(tmp.path()).unwrap(), 1);
+        let hits = search_journal(tmp.path(), "note", 10, 0).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].path, "journals/text/a.md");
+    }
+
+    #[test]
+    fn highlight_snippet_marks_first_match() {
+        let snippet = highlight_snippet("Remember the Visa appointment", "visa", 80);
+        assert_eq!(snippet, "Remember the <mark>Visa</mark> appointment");
+        assert_eq!(
+            highlight_snippet("no match here", "zzz", 80),
+            "no match here"
+        );
+    }
+
+    #[test]
+    fn snippets_escape_journal_html_but_keep_match_markers() {
+        let tmp = tempfile::tempdir().unwrap();
+        let rel = "journals/text/2026-03-02/xss.md";
+        write(
+            tmp.path(),
+            rel,
+            "Pasted <img src=x onerror=alert(1)> into the visa form & saved",
+        );
+        index_journal_file(tmp.path(), rel).unwrap();
+
+        let hits = search_journal(tmp.path(), "visa", 10, 0).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert!(!hits[0].snippet.contains("<img"));
+        assert!(hits[0]
+            .snippet
+            .contains("&lt;img src=x onerror=alert(1)&gt;"));
+        assert!(hits[0].snippet.contains("<mark>visa</mark>"));
+        assert!(hits[0].snippet.contains("&amp;"));
+
+        assert_eq!(
+            highlight_snippet("<b>Visa</b> day", "visa", 80),
+            "&lt;b&gt;<mark>Visa</mark>&lt;/b&gt; day"
+        );
+        assert_eq!(highlight_snippet("a <b> c", "zzz", 80), "a &lt;b&gt; c");
+    }
+}
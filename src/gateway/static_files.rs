@@ -1,55 +1,496 @@
-//! Static file serving for the embedded web dashboard.
+//! Static file serving for the web dashboard.
 //!
-//! Uses `rust-embed` to bundle the `web/dist/` directory into the binary at compile time.
+//! With the `embed-ui` feature (on by default), `rust-embed` bundles the
+//! `web/dist/` directory into the binary at compile time. Setting
+//! `ZEROCLAW_UI_DIR` serves that directory from disk instead, so frontend
+//! rebuilds show up without rebuilding the gateway.
+//! Every response carries an `ETag` so revalidation costs a 304, and a
+//! precompressed `.br` / `.gz` sibling is served instead of the original when
+//! the build produced one and the client accepts that encoding.
 
 use axum::{
-    http::{header, StatusCode, Uri},
-    response::IntoResponse,
+    extract::State,
+    http::{header, HeaderMap, HeaderValue, StatusCode, Uri},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
 };
-use rust_embed::Embed;
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, OnceLock};
 
-#[derive(Embed)]
+#[cfg(feature = "embed-ui")]
+#[derive(rust_embed::Embed)]
 #[folder = "web/dist/"]
 struct WebAssets;
 
+/// Directory that overrides the embedded web UI.
+pub const UI_DIR_ENV: &str = "ZEROCLAW_UI_DIR";
+
+/// Precompressed variants in order of preference: `(content-coding, suffix)`.
+const PRECOMPRESSED: &[(&str, &str)] = &[("br", "br"), ("gzip", "gz")];
+
+const CACHE_IMMUTABLE: &str = "public, max-age=31536000, immutable";
+const CACHE_REVALIDATE: &str = "no-cache";
+
+/// One servable file with its strong validator.
+#[derive(Clone)]
+struct Asset {
+    data: Cow<'static, [u8]>,
+    etag: String,
+}
+
+fn etag_for(hash: &[u8]) -> String {
+    format!("\"{}\"", hex::encode(&hash[..16]))
+}
+
+/// Where web UI files are read from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum UiSource {
+    /// `ZEROCLAW_UI_DIR`, read on every request.
+    Disk(PathBuf),
+    /// Files compiled into the binary.
+    #[cfg(feature = "embed-ui")]
+    Embedded,
+    /// Neither an override nor an embedded build.
+    Missing,
+}
+
+impl UiSource {
+    /// An existing override directory wins over the embedded files.
+    fn resolve(ui_dir: Option<PathBuf>) -> Self {
+        match ui_dir {
+            Some(dir) if dir.is_dir() => return Self::Disk(dir),
+            Some(dir) => tracing::warn!(
+                "{UI_DIR_ENV}={} is not a directory; ignoring it",
+                dir.display()
+            ),
+            None => {}
+        }
+        #[cfg(feature = "embed-ui")]
+        {
+            Self::Embedded
+        }
+        #[cfg(not(feature = "embed-ui"))]
+        {
+            Self::Missing
+        }
+    }
+
+    fn from_env() -> &'static Self {
+        static SOURCE: OnceLock<UiSource> = OnceLock::new();
+        SOURCE.get_or_init(|| {
+            Self::resolve(
+                std::env::var_os(UI_DIR_ENV)
+                    .filter(|value| !value.is_empty())
+                    .map(PathBuf::from),
+            )
+        })
+    }
+
+    fn get(&self, path: &str) -> Option<Asset> {
+        match self {
+            Self::Disk(dir) => {
+                let relative = Path::new(path);
+                if !relative
+                    .components()
+                    .all(|component| matches!(component, Component::Normal(_)))
+                {
+                    return None;
+                }
+                let data = std::fs::read(dir.join(relative)).ok()?;
+                Some(Asset {
+                    etag: etag_for(&Sha256::digest(&data)),
+                    data: Cow::Owned(data),
+                })
+            }
+            #[cfg(feature = "embed-ui")]
+            Self::Embedded => WebAssets::get(path).map(|file| Asset {
+                etag: etag_for(&file.metadata.sha256_hash()),
+                data: file.data,
+            }),
+            Self::Missing => None,
+        }
+    }
+
+    fn not_found(&self) -> Response {
+        let body = if *self == Self::Missing {
+            "Web UI not available: build with the embed-ui feature or set ZEROCLAW_UI_DIR"
+        } else {
+            "Not found"
+        };
+        (StatusCode::NOT_FOUND, body).into_response()
+    }
+}
+
+/// The web UI routes: files under `/_app/*`, and `index.html` for every other
+/// path. With a `route_prefix` (see `GatewayConfig::route_prefix`) the shell
+/// links its assets under that prefix.
+pub fn router(route_prefix: &str) -> Router {
+    Router::new()
+        .route("/_app/{*path}", get(handle_static))
+        .fallback(get(handle_spa_fallback))
+        .with_state(Arc::<str>::from(route_prefix))
+}
+
 /// Serve static files from `/_app/*` path
-pub async fn handle_static(uri: Uri) -> impl IntoResponse {
+pub async fn handle_static(uri: Uri, headers: HeaderMap) -> Response {
     let path = uri.path().strip_prefix("/_app/").unwrap_or(uri.path());
+    // Hashed filenames under `assets/` never change content.
+    let cache = if path.contains("assets/") {
+        CACHE_IMMUTABLE
+    } else {
+        CACHE_REVALIDATE
+    };
 
-    serve_embedded_file(path)
+    let source = UiSource::from_env();
+    serve_asset(path, &headers, cache, |path| source.get(path))
+        .unwrap_or_else(|| source.not_found())
 }
 
 /// SPA fallback: serve index.html for any non-API, non-static GET request
-pub async fn handle_spa_fallback() -> impl IntoResponse {
-    serve_embedded_file("index.html")
-}
-
-fn serve_embedded_file(path: &str) -> impl IntoResponse {
-    match WebAssets::get(path) {
-        Some(content) => {
-            let mime = mime_guess::from_path(path)
-                .first_or_octet_stream()
-                .to_string();
-
-            (
-                StatusCode::OK,
-                [
-                    (header::CONTENT_TYPE, mime),
-                    (
-                        header::CACHE_CONTROL,
-                        if path.contains("assets/") {
-                            // Hashed filenames — immutable cache
-                            "public, max-age=31536000, immutable".to_string()
-                        } else {
-                            // index.html etc — no cache
-                            "no-cache".to_string()
-                        },
-                    ),
-                ],
-                content.data.to_vec(),
-            )
-                .into_response()
+pub async fn handle_spa_fallback(
+    State(route_prefix): State<Arc<str>>,
+    headers: HeaderMap,
+) -> Response {
+    let source = UiSource::from_env();
+    if route_prefix.is_empty() {
+        return serve_asset("index.html", &headers, CACHE_REVALIDATE, |path| {
+            source.get(path)
+        })
+        .unwrap_or_else(|| source.not_found());
+    }
+    // Precompressed variants still carry the unprefixed links, so only the
+    // rewritten plain file is served.
+    let index = source
+        .get("index.html")
+        .map(|asset| rebase_index(&asset.data, &route_prefix));
+    serve_asset("index.html", &headers, CACHE_REVALIDATE, |path| {
+        index.clone().filter(|_| path == "index.html")
+    })
+    .unwrap_or_else(|| source.not_found())
+}
+
+/// `index.html` with a `<base href>` for `route_prefix` and its absolute
+/// `/_app/` asset links moved under the prefix.
+fn rebase_index(html: &[u8], route_prefix: &str) -> Asset {
+    let data = String::from_utf8_lossy(html)
+        .replace("=\"/_app/", &format!("=\"{route_prefix}/_app/"))
+        .replacen(
+            "<head>",
+            &format!("<head>\n    <base href=\"{route_prefix}/\" />"),
+            1,
+        )
+        .into_bytes();
+    Asset {
+        etag: etag_for(&Sha256::digest(&data)),
+        data: Cow::Owned(data),
+    }
+}
+
+fn serve_asset(
+    path: &str,
+    headers: &HeaderMap,
+    cache_control: &'static str,
+    lookup: impl Fn(&str) -> Option<Asset>,
+) -> Option<Response> {
+    let accept_encoding = headers
+        .get(header::ACCEPT_ENCODING)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("");
+    let precompressed = PRECOMPRESSED
+        .iter()
+        .filter(|(coding, _)| accepts_encoding(accept_encoding, coding))
+        .find_map(|(coding, suffix)| {
+            lookup(&format!("{path}.{suffix}")).map(|asset| (asset, Some(*coding)))
+        });
+    let (asset, coding) = precompressed.or_else(|| lookup(path).map(|asset| (asset, None)))?;
+
+    let mut response_headers = HeaderMap::new();
+    response_headers.insert(
+        header::CACHE_CONTROL,
+        HeaderValue::from_static(cache_control),
+    );
+    response_headers.insert(header::VARY, HeaderValue::from_static("Accept-Encoding"));
+    if let Ok(etag) = HeaderValue::from_str(&asset.etag) {
+        response_headers.insert(header::ETAG, etag);
+    }
+
+    let if_none_match = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok());
+    if if_none_match.is_some_and(|value| etag_matches(value, &asset.etag)) {
+        return Some((StatusCode::NOT_MODIFIED, response_headers).into_response());
+    }
+
+    if let Ok(mime) = HeaderValue::from_str(&content_type(path)) {
+        response_headers.insert(header::CONTENT_TYPE, mime);
+    }
+    if let Some(coding) = coding {
+        response_headers.insert(header::CONTENT_ENCODING, HeaderValue::from_static(coding));
+    }
+    Some((StatusCode::OK, response_headers, asset.data.into_owned()).into_response())
+}
+
+fn content_type(path: &str) -> String {
+    let extension = path.rsplit_once('.').map_or("", |(_, ext)| ext);
+    match extension.to_ascii_lowercase().as_str() {
+        "wasm" => "application/wasm".to_string(),
+        "webmanifest" => "application/manifest+json".to_string(),
+        "map" => "application/json".to_string(),
+        _ => mime_guess::from_path(path)
+            .first_or_octet_stream()
+            .to_string(),
+    }
+}
+
+/// Whether an `Accept-Encoding` value allows `coding`. Codings listed with
+/// `q=0` are refused; `*` stands in for anything not listed.
+fn accepts_encoding(accept_encoding: &str, coding: &str) -> bool {
+    let mut wildcard = None;
+    for entry in accept_encoding.split(',') {
+        let mut parts = entry.split(';');
+        let name = parts.next().unwrap_or("").trim();
+        let allowed = parts
+            .find_map(|param| param.trim().strip_prefix("q="))
+            .and_then(|q| q.trim().parse::<f32>().ok())
+            .is_none_or(|q| q > 0.0);
+        if name.eq_ignore_ascii_case(coding) {
+            return allowed;
+        }
+        if name == "*" {
+            wildcard = Some(allowed);
         }
-        None => (StatusCode::NOT_FOUND, "Not found").into_response(),
+    }
+    wildcard.unwrap_or(false)
+}
+
+/// `If-None-Match` comparison. Weak validators match too, as RFC 9110
+/// requires for this header.
+fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    if_none_match.split(',').map(str::trim).any(|candidate| {
+        candidate == "*" || candidate.strip_prefix("W/").unwrap_or(candidate) == etag
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn lookup_from(files: &[(&'static str, &'static [u8])]) -> impl Fn(&str) -> Option<Asset> {
+        let files: HashMap<&'static str, &'static [u8]> = files.iter().copied().collect();
+        move |path| {
+            files.get(path).map(|data| Asset {
+                data: Cow::Borrowed(*data),
+                etag: format!("\"{path}\""),
+            })
+        }
+    }
+
+    fn request(accept_encoding: Option<&str>, if_none_match: Option<&str>) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        if let Some(value) = accept_encoding {
+            headers.insert(
+                header::ACCEPT_ENCODING,
+                HeaderValue::from_str(value).unwrap(),
+            );
+        }
+        if let Some(value) = if_none_match {
+            headers.insert(header::IF_NONE_MATCH, HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn precompressed_variant_follows_accept_encoding() {
+        let lookup = lookup_from(&[
+            ("assets/app.js", b"plain"),
+            ("assets/app.js.br", b"brotli"),
+            ("assets/app.js.gz", b"gzip"),
+        ]);
+
+        let response = serve_asset(
+            "assets/app.js",
+            &request(Some("gzip, deflate, br"), None),
+            CACHE_IMMUTABLE,
+            &lookup,
+        )
+        .unwrap();
+        assert_eq!(response.headers()[header::CONTENT_ENCODING], "br");
+        assert!(response.headers()[header::CONTENT_TYPE]
+            .to_str()
+            .unwrap()
+            .contains("javascript"));
+        assert_eq!(response.headers()[header::VARY], "Accept-Encoding");
+        assert_eq!(response.headers()[header::CACHE_CONTROL], CACHE_IMMUTABLE);
+
+        let response = serve_asset(
+            "assets/app.js",
+            &request(Some("gzip, br;q=0"), None),
+            CACHE_IMMUTABLE,
+            &lookup,
+        )
+        .unwrap();
+        assert_eq!(response.headers()[header::CONTENT_ENCODING], "gzip");
+
+        let response = serve_asset(
+            "assets/app.js",
+            &request(None, None),
+            CACHE_IMMUTABLE,
+            &lookup,
+        )
+        .unwrap();
+        assert!(response.headers().get(header::CONTENT_ENCODING).is_none());
+        assert_eq!(response.headers()[header::ETAG], "\"assets/app.js\"");
+    }
+
+    #[test]
+    fn missing_precompressed_variant_falls_back_to_original() {
+        let lookup = lookup_from(&[("index.html", b"<html>")]);
+        let response = serve_asset(
+            "index.html",
+            &request(Some("br"), None),
+            CACHE_REVALIDATE,
+            &lookup,
+        )
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers().get(header::CONTENT_ENCODING).is_none());
+        assert_eq!(response.headers()[header::CACHE_CONTROL], CACHE_REVALIDATE);
+
+        assert!(serve_asset(
+            "missing.js",
+            &request(None, None),
+            CACHE_REVALIDATE,
+            &lookup
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn matching_etag_returns_not_modified() {
+        let lookup = lookup_from(&[("index.html", b"<html>"), ("index.html.gz", b"gz")]);
+
+        let response = serve_asset(
+            "index.html",
+            &request(None, Some("\"other\", W/\"index.html\"")),
+            CACHE_REVALIDATE,
+            &lookup,
+        )
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.headers()[header::ETAG], "\"index.html\"");
+
+        // The gzip variant is a different representation with its own tag.
+        let response = serve_asset(
+            "index.html",
+            &request(Some("gzip"), Some("\"index.html\"")),
+            CACHE_REVALIDATE,
+            &lookup,
+        )
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::ETAG], "\"index.html.gz\"");
+    }
+
+    #[test]
+    fn accept_encoding_honours_q_values_and_wildcard() {
+        assert!(accepts_encoding("gzip, br", "br"));
+        assert!(!accepts_encoding("gzip;q=1.0, br;q=0", "br"));
+        assert!(accepts_encoding("*", "gzip"));
+        assert!(!accepts_encoding("*;q=0", "gzip"));
+        assert!(!accepts_encoding("identity", "gzip"));
+        assert!(!accepts_encoding("", "br"));
+    }
+
+    #[test]
+    fn content_type_covers_wasm_manifest_and_source_maps() {
+        assert_eq!(content_type("assets/app.wasm"), "application/wasm");
+        assert_eq!(
+            content_type("manifest.webmanifest"),
+            "application/manifest+json"
+        );
+        assert_eq!(content_type("assets/app.js.map"), "application/json");
+        assert_eq!(content_type("index.html"), "text/html");
+    }
+
+    #[test]
+    fn ui_dir_override_takes_precedence_over_embedded_files() {
+        let tmp = tempfile::tempdir().unwrap();
+        std::fs::write(tmp.path().join("index.html"), "<html>disk</html>").unwrap();
+
+        let source = UiSource::resolve(Some(tmp.path().to_path_buf()));
+        assert_eq!(source, UiSource::Disk(tmp.path().to_path_buf()));
+        let asset = source.get("index.html").unwrap();
+        assert_eq!(&*asset.data, b"<html>disk</html>");
+        assert!(source.get("../secret").is_none());
+        assert!(source.get("/etc/passwd").is_none());
+
+        // A missing override directory falls back as if it were unset.
+        let fallback = UiSource::resolve(Some(tmp.path().join("absent")));
+        assert_eq!(fallback, UiSource::resolve(None));
+        #[cfg(feature = "embed-ui")]
+        assert_eq!(fallback, UiSource::Embedded);
+        #[cfg(not(feature = "embed-ui"))]
+        assert_eq!(fallback, UiSource::Missing);
+    }
+
+    #[test]
+    fn spa_fallback_serves_index_the_same_way_from_disk() {
+        let tmp = tempfile::tempdir().unwrap();
+        std::fs::write(tmp.path().join("index.html"), "<html>disk</html>").unwrap();
+        let source = UiSource::Disk(tmp.path().to_path_buf());
+
+        let response = serve_asset(
+            "index.html",
+            &request(None, None),
+            CACHE_REVALIDATE,
+            |path| source.get(path),
+        )
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CACHE_CONTROL], CACHE_REVALIDATE);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "text/html");
+        let etag = response.headers()[header::ETAG]
+            .to_str()
+            .unwrap()
+            .to_string();
+
+        let response = serve_asset(
+            "index.html",
+            &request(None, Some(&etag)),
+            CACHE_REVALIDATE,
+            |path| source.get(path),
+        )
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    }
+
+    #[test]
+    fn index_is_rebased_under_the_route_prefix() {
+        let html = b"<html><head>\n<script src=\"/_app/assets/app.js\"></script>\n\
+                     <link href=\"/_app/assets/app.css\"></head></html>";
+        let rebased = rebase_index(html, "/slowclaw");
+        let text = String::from_utf8(rebased.data.into_owned()).unwrap();
+
+        assert!(text.contains("<base href=\"/slowclaw/\" />"), "{text}");
+        assert!(
+            text.contains("src=\"/slowclaw/_app/assets/app.js\""),
+            "{text}"
+        );
+        assert!(
+            text.contains("href=\"/slowclaw/_app/assets/app.css\""),
+            "{text}"
+        );
+        assert!(!text.contains("\"/_app/"), "{text}");
+        assert_ne!(rebased.etag, rebase_index(html, "/other").etag);
+    }
+
+    #[test]
+    fn missing_ui_explains_how_to_get_one() {
+        let response = UiSource::Missing.not_found();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
     }
 }
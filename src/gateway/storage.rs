@@ -0,0 +1,233 @@
+//! Media quota and free-disk-space guard for uploads.
+//!
+//! `journals/media` usage is computed by a directory walk at most every
+//! `USAGE_REFRESH_SECS` and nudged forward as uploads land, so the upload
+//! path never walks the tree per request.
+
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+const MEDIA_DIR: &str = "journals/media";
+const USAGE_REFRESH_SECS: i64 = 300;
+
+#[derive(Clone, Copy)]
+struct UsageCache {
+    media_bytes: u64,
+    computed_at: i64,
+}
+
+static MEDIA_USAGE: OnceLock<Mutex<HashMap<PathBuf, UsageCache>>> = OnceLock::new();
+
+fn usage_cache() -> &'static Mutex<HashMap<PathBuf, UsageCache>> {
+    MEDIA_USAGE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageSnapshot {
+    pub media_bytes: u64,
+    /// Zero means no quota.
+    pub media_quota_bytes: u64,
+    /// `None` when the platform cannot report free space.
+    pub available_bytes: Option<u64>,
+    pub min_free_bytes: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageLimit {
+    Quota,
+    DiskSpace,
+}
+
+impl StorageLimit {
+    pub fn code(self) -> &'static str {
+        match self {
+            Self::Quota => "MEDIA_QUOTA_EXCEEDED",
+            Self::DiskSpace => "INSUFFICIENT_DISK_SPACE",
+        }
+    }
+
+    pub fn message(self) -> &'static str {
+        match self {
+            Self::Quota => "This upload would exceed the workspace media quota.",
+            Self::DiskSpace => "Not enough free disk space for this upload.",
+        }
+    }
+}
+
+impl StorageSnapshot {
+    /// Bytes a new upload may still write, and the limit that caps it.
+    /// `None` when neither a quota nor a free-space floor applies.
+    pub fn upload_allowance(&self) -> Option<(u64, StorageLimit)> {
+        let quota = (self.media_quota_bytes > 0).then(|| {
+            (
+                self.media_quota_bytes.saturating_sub(self.media_bytes),
+                StorageLimit::Quota,
+            )
+        });
+        let disk = self.available_bytes.map(|available| {
+            (
+                available.saturating_sub(self.min_free_bytes),
+                StorageLimit::DiskSpace,
+            )
+        });
+        match (quota, disk) {
+            (Some(q), Some(d)) => Some(if d.0 < q.0 { d } else { q }),
+            (q, d) => q.or(d),
+        }
+    }
+
+    /// The limit an upload of `bytes` would break, if any.
+    pub fn check_upload(&self, bytes: u64) -> Option<StorageLimit> {
+        self.upload_allowance()
+            .filter(|(allowed, _)| bytes > *allowed)
+            .map(|(_, limit)| limit)
+    }
+}
+
+fn dir_size(dir: &Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.file_type() {
+            Ok(t) if t.is_dir() => dir_size(&entry.path()),
+            Ok(t) if t.is_file() => entry.metadata().map(|m| m.len()).unwrap_or(0),
+            _ => 0,
+        })
+        .sum()
+}
+
+/// Current `journals/media` usage, walking the tree only when the cached
+/// figure is missing or older than the refresh interval.
+pub fn media_usage_bytes(workspace_dir: &Path) -> u64 {
+    let now = chrono::Utc::now().timestamp();
+    if let Some(cache) = usage_cache().lock().get(workspace_dir) {
+        if now - cache.computed_at < USAGE_REFRESH_SECS {
+            return cache.media_bytes;
+        }
+    }
+    let media_bytes = dir_size(&workspace_dir.join(MEDIA_DIR));
+    usage_cache().lock().insert(
+        workspace_dir.to_path_buf(),
+        UsageCache {
+            media_bytes,
+            computed_at: now,
+        },
+    );
+    media_bytes
+}
+
+/// Account for a completed upload without waiting for the next walk.
+pub fn note_media_bytes_added(workspace_dir: &Path, bytes: u64) {
+    if let Some(cache) = usage_cache().lock().get_mut(workspace_dir) {
+        cache.media_bytes = cache.media_bytes.saturating_add(bytes);
+    }
+}
+
+/// Force the next usage read to walk the tree (e.g. after deletes).
+pub fn invalidate_media_usage(workspace_dir: &Path) {
+    usage_cache().lock().remove(workspace_dir);
+}
+
+#[cfg(unix)]
+pub fn available_disk_bytes(path: &Path) -> Option<u64> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = std::ffi::CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    // SAFETY: `c_path` is a valid NUL-terminated string and `stat` is a
+    // properly sized, writable statvfs struct.
+    let rc = unsafe { libc::statvfs(c_path.as_ptr(), std::ptr::addr_of_mut!(stat)) };
+    if rc != 0 {
+        return None;
+    }
+    #[allow(clippy::useless_conversion)]
+    Some(u64::from(stat.f_bavail).saturating_mul(u64::from(stat.f_frsize)))
+}
+
+#[cfg(not(unix))]
+pub fn available_disk_bytes(_path: &Path) -> Option<u64> {
+    None
+}
+
+pub fn snapshot(
+    workspace_dir: &Path,
+    media_quota_bytes: u64,
+    min_free_bytes: u64,
+) -> StorageSnapshot {
+    StorageSnapshot {
+        media_bytes: media_usage_bytes(workspace_dir),
+        media_quota_bytes,
+        available_bytes: available_disk_bytes(workspace_dir),
+        min_free_bytes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snap(media: u64, quota: u64, available: Option<u64>, floor: u64) -> StorageSnapshot {
+        StorageSnapshot {
+            media_bytes: media,
+            media_quota_bytes: quota,
+            available_bytes: available,
+            min_free_bytes: floor,
+        }
+    }
+
+    #[test]
+    fn allowance_takes_the_tighter_limit() {
+        assert_eq!(snap(10, 0, None, 0).upload_allowance(), None);
+        assert_eq!(
+            snap(60, 100, Some(1_000), 100).upload_allowance(),
+            Some((40, StorageLimit::Quota))
+        );
+        assert_eq!(
+            snap(60, 100, Some(120), 100).upload_allowance(),
+            Some((20, StorageLimit::DiskSpace))
+        );
+        assert_eq!(
+            snap(0, 0, Some(50), 100).upload_allowance(),
+            Some((0, StorageLimit::DiskSpace))
+        );
+    }
+
+    #[test]
+    fn check_upload_reports_the_breaking_limit() {
+        let s = snap(60, 100, Some(10_000), 0);
+        assert_eq!(s.check_upload(40), None);
+        assert_eq!(s.check_upload(41), Some(StorageLimit::Quota));
+        assert_eq!(snap(0, 0, None, 0).check_upload(u64::MAX), None);
+    }
+
+    #[test]
+    fn usage_is_cached_and_nudged_by_uploads() {
+        let tmp = tempfile::tempdir().unwrap();
+        let media = tmp.path().join(MEDIA_DIR).join("audio");
+        std::fs::create_dir_all(&media).unwrap();
+        std::fs::write(media.join("a.m4a"), vec![0u8; 100]).unwrap();
+
+        invalidate_media_usage(tmp.path());
+        assert_eq!(media_usage_bytes(tmp.path()), 100);
+        std::fs::write(media.join("b.m4a"), vec![0u8; 50]).unwrap();
+        assert_eq!(media_usage_bytes(tmp.path()), 100);
+        note_media_bytes_added(tmp.path(), 50);
+        assert_eq!(media_usage_bytes(tmp.path()), 150);
+        invalidate_media_usage(tmp.path());
+        assert_eq!(media_usage_bytes(tmp.path()), 150);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn available_disk_bytes_reports_space_for_existing_paths() {
+        let tmp = tempfile::tempdir().unwrap();
+        assert!(available_disk_bytes(tmp.path()).is_some());
+        assert!(available_disk_bytes(&tmp.path().join("missing/dir")).is_none());
+    }
+}
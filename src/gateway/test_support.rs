@@ -0,0 +1,75 @@
+//! Serves the real gateway router on an ephemeral port so tests exercise
+//! routing, body limits, timeouts, and auth together.
+
+use super::{build_router_with_limits, AppState, GatewayPeer, RouterLimits};
+use crate::config::Config;
+use crate::security::PairingGuard;
+use std::sync::Arc;
+use tokio::task::JoinHandle;
+
+pub(crate) struct TestGateway {
+    pub base_url: String,
+    pub state: AppState,
+    server: JoinHandle<()>,
+}
+
+impl TestGateway {
+    pub async fn spawn(state: AppState, config: &Config) -> Self {
+        Self::spawn_with_limits(state, config, RouterLimits::for_config(config)).await
+    }
+
+    pub async fn spawn_with_limits(state: AppState, config: &Config, limits: RouterLimits) -> Self {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind ephemeral port");
+        let addr = listener.local_addr().expect("local addr");
+        let app = build_router_with_limits(state.clone(), config, limits);
+        let server = tokio::spawn(async move {
+            let _ = axum::serve(
+                listener,
+                app.into_make_service_with_connect_info::<GatewayPeer>(),
+            )
+            .await;
+        });
+        Self {
+            base_url: format!("http://{addr}"),
+            state,
+            server,
+        }
+    }
+
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{path}", self.base_url)
+    }
+
+    /// Pair through `POST /pair` with the guard's current one-time code.
+    pub async fn pair(&self) -> String {
+        let code = self
+            .state
+            .pairing
+            .pairing_code()
+            .expect("pairing is required and unused");
+        let response = reqwest::Client::new()
+            .post(self.url("/pair"))
+            .header("X-Pairing-Code", code)
+            .send()
+            .await
+            .expect("pair request");
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        let body: serde_json::Value = response.json().await.expect("pair body");
+        body["token"].as_str().expect("token").to_string()
+    }
+}
+
+impl Drop for TestGateway {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+/// Require pairing on `state` with one token already paired, and return it.
+pub(crate) fn with_paired_token(state: &mut AppState) -> String {
+    let token = "zc_test_token".to_string();
+    state.pairing = Arc::new(PairingGuard::new(true, std::slice::from_ref(&token)));
+    token
+}
@@ -0,0 +1,268 @@
+//! Version history for library text edits.
+//!
+//! Before a save overwrites a text file, its previous content is copied to
+//! `.versions/<relative path>/<timestamp>.md`, keeping the newest `keep`
+//! copies per file. Every path goes through workspace containment, so a
+//! symlink planted under `.versions/` cannot redirect reads or writes.
+
+use crate::workspace::{atomic_write, resolve_contained_path, ResolveError, ResolveMode};
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+pub const VERSIONS_DIR: &str = ".versions";
+const VERSION_EXT: &str = ".md";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextVersion {
+    pub id: String,
+    pub bytes: u64,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum VersionError {
+    #[error("version not found: {0}")]
+    NotFound(String),
+    #[error("version path escapes the workspace: {0}")]
+    Escapes(String),
+}
+
+/// Ids are UTC timestamps (`20260301T120000123Z`, with `-0001` on a clash),
+/// so they sort chronologically and never contain a separator.
+fn is_valid_id(id: &str) -> bool {
+    !id.is_empty() && id.chars().all(|ch| ch.is_ascii_alphanumeric() || ch == '-')
+}
+
+/// A fresh id that sorts after `newest`, even within the same millisecond or
+/// after the clock steps back.
+fn next_id(newest: Option<&str>) -> String {
+    let now = chrono::Utc::now().format("%Y%m%dT%H%M%S%3fZ").to_string();
+    match newest {
+        Some(newest) if newest >= now.as_str() => {
+            let (stem, count) = match newest.rsplit_once('-') {
+                Some((stem, count)) => (stem, count.parse::<u32>().unwrap_or(0)),
+                None => (newest, 0),
+            };
+            format!("{stem}-{:04}", count + 1)
+        }
+        _ => now,
+    }
+}
+
+/// Opaque token for a file's content. Reads return it and saves may send it
+/// back as `base_revision` to detect an edit made elsewhere in between.
+pub fn revision(content: &[u8]) -> String {
+    hex::encode(&Sha256::digest(content)[..12])
+}
+
+fn resolve(workspace_dir: &Path, rel: &str, mode: ResolveMode) -> Result<PathBuf> {
+    match resolve_contained_path(workspace_dir, rel, mode) {
+        Ok(path) => Ok(path),
+        Err(ResolveError::Escapes) => bail!(VersionError::Escapes(rel.to_string())),
+        Err(_) => bail!(VersionError::NotFound(rel.to_string())),
+    }
+}
+
+fn version_rel(rel_path: &str, id: &str) -> String {
+    format!("{VERSIONS_DIR}/{rel_path}/{id}{VERSION_EXT}")
+}
+
+/// Versions of `rel_path`, newest first. A file that was never overwritten
+/// has none.
+pub fn list_versions(workspace_dir: &Path, rel_path: &str) -> Result<Vec<TextVersion>> {
+    let dir_rel = format!("{VERSIONS_DIR}/{rel_path}");
+    let dir = match resolve_contained_path(workspace_dir, &dir_rel, ResolveMode::Dir) {
+        Ok(dir) => dir,
+        Err(ResolveError::Escapes) => bail!(VersionError::Escapes(dir_rel)),
+        Err(_) => return Ok(Vec::new()),
+    };
+    let mut versions: Vec<TextVersion> = std::fs::read_dir(&dir)
+        .with_context(|| format!("Failed to read {}", dir.display()))?
+        .flatten()
+        .filter(|entry| entry.file_type().is_ok_and(|kind| kind.is_file()))
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let id = name.strip_suffix(VERSION_EXT)?.to_string();
+            let bytes = entry.metadata().ok()?.len();
+            is_valid_id(&id).then_some(TextVersion { id, bytes })
+        })
+        .collect();
+    versions.sort_by(|a, b| b.id.cmp(&a.id));
+    Ok(versions)
+}
+
+fn prune(workspace_dir: &Path, rel_path: &str, keep: usize) -> Result<()> {
+    for stale in list_versions(workspace_dir, rel_path)?
+        .into_iter()
+        .skip(keep)
+    {
+        let path = resolve(
+            workspace_dir,
+            &version_rel(rel_path, &stale.id),
+            ResolveMode::ExistingFile,
+        )?;
+        std::fs::remove_file(&path)
+            .with_context(|| format!("Failed to prune {}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// Copy the current content of `rel_path` into its history unless it is
+/// missing or already equal to `next_content`. `keep == 0` disables history.
+pub fn snapshot_before_write(
+    workspace_dir: &Path,
+    rel_path: &str,
+    next_content: &[u8],
+    keep: usize,
+) -> Result<Option<TextVersion>> {
+    if keep == 0 {
+        return Ok(None);
+    }
+    let current_path = workspace_dir.join(rel_path);
+    if !current_path.is_file() {
+        return Ok(None);
+    }
+    let current = std::fs::read(&current_path)
+        .with_context(|| format!("Failed to read {}", current_path.display()))?;
+    if current == next_content {
+        return Ok(None);
+    }
+
+    let newest = list_versions(workspace_dir, rel_path)?.into_iter().next();
+    let id = next_id(newest.as_ref().map(|v| v.id.as_str()));
+    let path = resolve(
+        workspace_dir,
+        &version_rel(rel_path, &id),
+        ResolveMode::CreatableFile,
+    )?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    atomic_write(&path, &current).with_context(|| format!("Failed to write {}", path.display()))?;
+    prune(workspace_dir, rel_path, keep)?;
+    Ok(Some(TextVersion {
+        id,
+        bytes: current.len() as u64,
+    }))
+}
+
+/// Content of one stored version.
+pub fn read_version(workspace_dir: &Path, rel_path: &str, id: &str) -> Result<Vec<u8>> {
+    let id = id.trim();
+    if !is_valid_id(id) {
+        bail!(VersionError::NotFound(id.to_string()));
+    }
+    let path = resolve(
+        workspace_dir,
+        &version_rel(rel_path, id),
+        ResolveMode::ExistingFile,
+    )?;
+    std::fs::read(&path).with_context(|| format!("Failed to read {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REL: &str = "journals/text/note.md";
+
+    fn save(workspace: &Path, content: &str, keep: usize) -> Option<TextVersion> {
+        let version = snapshot_before_write(workspace, REL, content.as_bytes(), keep).unwrap();
+        let path = workspace.join(REL);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+        version
+    }
+
+    #[test]
+    fn revision_tracks_content() {
+        assert_eq!(revision(b"draft"), revision(b"draft"));
+        assert_ne!(revision(b"draft"), revision(b"draft "));
+        assert_eq!(revision(b"").len(), 24);
+    }
+
+    #[test]
+    fn snapshots_previous_content_and_skips_noops() {
+        let tmp = tempfile::tempdir().unwrap();
+        assert_eq!(save(tmp.path(), "first", 5), None, "nothing to keep yet");
+        assert_eq!(save(tmp.path(), "first", 5), None, "unchanged content");
+
+        let version = save(tmp.path(), "", 5).expect("overwrite is versioned");
+        assert_eq!(version.bytes, 5);
+        assert_eq!(
+            read_version(tmp.path(), REL, &version.id).unwrap(),
+            b"first"
+        );
+        assert!(tmp
+            .path()
+            .join(VERSIONS_DIR)
+            .join(REL)
+            .join(format!("{}.md", version.id))
+            .is_file());
+        assert_eq!(list_versions(tmp.path(), REL).unwrap(), vec![version]);
+    }
+
+    #[test]
+    fn keeps_only_the_newest_versions() {
+        let tmp = tempfile::tempdir().unwrap();
+        save(tmp.path(), "v0", 3);
+        for i in 1..=6 {
+            save(tmp.path(), &format!("v{i}"), 3);
+        }
+        let versions = list_versions(tmp.path(), REL).unwrap();
+        assert_eq!(versions.len(), 3);
+        let contents: Vec<Vec<u8>> = versions
+            .iter()
+            .map(|v| read_version(tmp.path(), REL, &v.id).unwrap())
+            .collect();
+        assert_eq!(
+            contents,
+            vec![b"v5".to_vec(), b"v4".to_vec(), b"v3".to_vec()]
+        );
+
+        assert_eq!(save(tmp.path(), "v7", 0), None, "keep = 0 disables history");
+    }
+
+    #[test]
+    fn rejects_bad_ids_and_missing_versions() {
+        let tmp = tempfile::tempdir().unwrap();
+        save(tmp.path(), "a", 5);
+        save(tmp.path(), "b", 5);
+        for id in ["", "../../../secret", "x/y", "missing"] {
+            let err = read_version(tmp.path(), REL, id).unwrap_err();
+            assert!(
+                matches!(
+                    err.downcast_ref::<VersionError>(),
+                    Some(VersionError::NotFound(_))
+                ),
+                "{id}: {err}"
+            );
+        }
+        assert!(list_versions(tmp.path(), "journals/text/other.md")
+            .unwrap()
+            .is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinked_versions_dir_cannot_escape() {
+        let tmp = tempfile::tempdir().unwrap();
+        let workspace = tmp.path().join("ws");
+        let outside = tmp.path().join("outside");
+        std::fs::create_dir_all(&workspace).unwrap();
+        std::fs::create_dir_all(&outside).unwrap();
+        std::os::unix::fs::symlink(&outside, workspace.join(VERSIONS_DIR)).unwrap();
+
+        save(&workspace, "a", 5);
+        let err = snapshot_before_write(&workspace, REL, b"b", 5).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<VersionError>(),
+            Some(VersionError::Escapes(_))
+        ));
+        assert!(list_versions(&workspace, REL).is_err());
+        assert_eq!(std::fs::read_dir(&outside).unwrap().count(), 0);
+    }
+}
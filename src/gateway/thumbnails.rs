@@ -0,0 +1,299 @@
+//! JPEG thumbnails for library images and videos.
+//!
+//! Thumbnails are cached under `state/thumbs/` keyed by a content hash of the
+//! source plus the requested width, so cache hits are served straight from
+//! disk. Cache misses share a small permit pool so a burst of grid loads
+//! cannot fan out into one decoder or ffmpeg process per request.
+
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::time::Duration;
+use tokio::process::Command;
+use tokio::sync::Semaphore;
+
+pub const DEFAULT_THUMB_WIDTH: u32 = 320;
+pub const MIN_THUMB_WIDTH: u32 = 32;
+pub const MAX_THUMB_WIDTH: u32 = 1024;
+const THUMB_JPEG_QUALITY: u8 = 80;
+const THUMB_RENDER_CONCURRENCY: usize = 2;
+const VIDEO_FRAME_TIMEOUT_SECS: u64 = 20;
+/// Bytes hashed from each end of the source; together with the file length
+/// this identifies the content without reading multi-GB videos per request.
+const HASH_SAMPLE_BYTES: u64 = 1024 * 1024;
+const ORPHAN_SWEEP_INTERVAL_SECS: i64 = 600;
+/// Each cached thumbnail has a `<key>.src` companion naming its source so
+/// orphaned entries can be evicted once the media file is gone.
+const SOURCE_MARKER_EXT: &str = "src";
+
+static RENDER_PERMITS: Semaphore = Semaphore::const_new(THUMB_RENDER_CONCURRENCY);
+static LAST_ORPHAN_SWEEP: AtomicI64 = AtomicI64::new(0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThumbSource {
+    Image,
+    Video,
+}
+
+impl ThumbSource {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path
+            .extension()
+            .and_then(|s| s.to_str())
+            .unwrap_or("")
+            .to_ascii_lowercase();
+        match ext.as_str() {
+            "jpg" | "jpeg" | "png" | "webp" => Some(Self::Image),
+            "mp4" | "mov" | "webm" | "mkv" => Some(Self::Video),
+            _ => None,
+        }
+    }
+}
+
+pub fn thumbs_dir(workspace_dir: &Path) -> PathBuf {
+    workspace_dir.join("state").join("thumbs")
+}
+
+pub fn clamp_thumb_width(requested: Option<u32>) -> u32 {
+    requested
+        .unwrap_or(DEFAULT_THUMB_WIDTH)
+        .clamp(MIN_THUMB_WIDTH, MAX_THUMB_WIDTH)
+}
+
+fn content_key(source: &Path, width: u32) -> Result<String> {
+    let mut file = std::fs::File::open(source).context("failed to open thumbnail source")?;
+    let len = file.metadata()?.len();
+    let mut hasher = Sha256::new();
+    hasher.update(len.to_le_bytes());
+
+    let mut buf = Vec::new();
+    (&mut file).take(HASH_SAMPLE_BYTES).read_to_end(&mut buf)?;
+    hasher.update(&buf);
+    if len > HASH_SAMPLE_BYTES * 2 {
+        buf.clear();
+        file.seek(SeekFrom::Start(len - HASH_SAMPLE_BYTES))?;
+        file.take(HASH_SAMPLE_BYTES).read_to_end(&mut buf)?;
+        hasher.update(&buf);
+    }
+    let digest = hex::encode(hasher.finalize());
+    Ok(format!("{}-w{width}", &digest[..32]))
+}
+
+fn render_image_thumb(source: &Path, width: u32) -> Result<Vec<u8>> {
+    let decoded = image::ImageReader::open(source)?
+        .with_guessed_format()?
+        .decode()
+        .context("failed to decode image")?;
+    let rgb = decoded.thumbnail(width, width).into_rgb8();
+    let mut out = Vec::new();
+    image::codecs::jpeg::JpegEncoder::new_with_quality(&mut out, THUMB_JPEG_QUALITY)
+        .encode_image(&rgb)
+        .context("failed to encode thumbnail")?;
+    Ok(out)
+}
+
+async fn grab_video_frame(source: &Path, width: u32, seek_secs: &str) -> Result<Vec<u8>> {
+    let scale = format!("scale='min({width},iw)':-2");
+    let output = tokio::time::timeout(
+        Duration::from_secs(VIDEO_FRAME_TIMEOUT_SECS),
+        Command::new("ffmpeg")
+            .args(["-v", "error", "-ss", seek_secs, "-i"])
+            .arg(source)
+            .args(["-frames:v", "1", "-vf", &scale, "-f", "image2pipe"])
+            .args(["-vcodec", "mjpeg", "-q:v", "5", "-"])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .output(),
+    )
+    .await
+    .context("ffmpeg frame grab timed out")?
+    .context("failed to execute ffmpeg")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "ffmpeg frame grab failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(output.stdout)
+}
+
+async fn render_video_thumb(source: &Path, width: u32) -> Result<Vec<u8>> {
+    // One second in skips black intro frames; very short clips fall back to the first frame.
+    let frame = grab_video_frame(source, width, "1").await?;
+    if !frame.is_empty() {
+        return Ok(frame);
+    }
+    let frame = grab_video_frame(source, width, "0").await?;
+    if frame.is_empty() {
+        anyhow::bail!("ffmpeg produced no frame");
+    }
+    Ok(frame)
+}
+
+fn write_cache_entry(dir: &Path, key: &str, rel_source: &str, jpeg: &[u8]) -> Result<()> {
+    std::fs::create_dir_all(dir)?;
+    let tmp = dir.join(format!("{key}.jpg.tmp"));
+    std::fs::write(&tmp, jpeg)?;
+    std::fs::rename(&tmp, dir.join(format!("{key}.jpg")))?;
+    std::fs::write(dir.join(format!("{key}.{SOURCE_MARKER_EXT}")), rel_source)?;
+    Ok(())
+}
+
+/// Remove cached thumbnails whose source file no longer exists.
+pub fn evict_orphaned_thumbs(workspace_dir: &Path) -> usize {
+    let dir = thumbs_dir(workspace_dir);
+    let Ok(entries) = std::fs::read_dir(&dir) else {
+        return 0;
+    };
+    let mut removed = 0;
+    for entry in entries.flatten() {
+        let marker = entry.path();
+        if marker.extension().and_then(|e| e.to_str()) != Some(SOURCE_MARKER_EXT) {
+            continue;
+        }
+        let Ok(rel_source) = std::fs::read_to_string(&marker) else {
+            continue;
+        };
+        if workspace_dir.join(rel_source.trim()).is_file() {
+            continue;
+        }
+        let _ = std::fs::remove_file(marker.with_extension("jpg"));
+        let _ = std::fs::remove_file(&marker);
+        removed += 1;
+    }
+    removed
+}
+
+fn maybe_sweep_orphans(workspace_dir: &Path) {
+    let now = chrono::Utc::now().timestamp();
+    let last = LAST_ORPHAN_SWEEP.load(Ordering::Relaxed);
+    if now - last < ORPHAN_SWEEP_INTERVAL_SECS
+        || LAST_ORPHAN_SWEEP
+            .compare_exchange(last, now, Ordering::Relaxed, Ordering::Relaxed)
+            .is_err()
+    {
+        return;
+    }
+    let workspace_dir = workspace_dir.to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        let removed = evict_orphaned_thumbs(&workspace_dir);
+        if removed > 0 {
+            tracing::debug!("Evicted {removed} orphaned thumbnails");
+        }
+    });
+}
+
+/// Return JPEG bytes for `source` (already resolved inside the workspace),
+/// rendering and caching on a miss.
+pub async fn thumbnail_jpeg(
+    workspace_dir: &Path,
+    rel_source: &str,
+    source: &Path,
+    kind: ThumbSource,
+    width: u32,
+) -> Result<Vec<u8>> {
+    let dir = thumbs_dir(workspace_dir);
+    let key = {
+        let source = source.to_path_buf();
+        tokio::task::spawn_blocking(move || content_key(&source, width)).await??
+    };
+    let cached = dir.join(format!("{key}.jpg"));
+    if let Ok(bytes) = tokio::fs::read(&cached).await {
+        return Ok(bytes);
+    }
+
+    let _permit = RENDER_PERMITS
+        .acquire()
+        .await
+        .context("thumbnail renderer unavailable")?;
+    // Another request may have rendered the same thumbnail while we waited.
+    if let Ok(bytes) = tokio::fs::read(&cached).await {
+        return Ok(bytes);
+    }
+    let jpeg = match kind {
+        ThumbSource::Image => {
+            let source = source.to_path_buf();
+            tokio::task::spawn_blocking(move || render_image_thumb(&source, width)).await??
+        }
+        ThumbSource::Video => render_video_thumb(source, width).await?,
+    };
+    if let Err(err) = write_cache_entry(&dir, &key, rel_source, &jpeg) {
+        tracing::warn!("Thumbnail cache write failed: {err:#}");
+    }
+    maybe_sweep_orphans(workspace_dir);
+    Ok(jpeg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_png(path: &Path, width: u32, height: u32) {
+        image::RgbImage::from_pixel(width, height, image::Rgb([200, 40, 40]))
+            .save(path)
+            .unwrap();
+    }
+
+    #[test]
+    fn thumb_source_classifies_extensions() {
+        assert_eq!(
+            ThumbSource::from_path(Path::new("a/b.JPG")),
+            Some(ThumbSource::Image)
+        );
+        assert_eq!(
+            ThumbSource::from_path(Path::new("clip.mov")),
+            Some(ThumbSource::Video)
+        );
+        assert_eq!(ThumbSource::from_path(Path::new("voice.m4a")), None);
+    }
+
+    #[test]
+    fn thumb_width_is_clamped() {
+        assert_eq!(clamp_thumb_width(None), DEFAULT_THUMB_WIDTH);
+        assert_eq!(clamp_thumb_width(Some(1)), MIN_THUMB_WIDTH);
+        assert_eq!(clamp_thumb_width(Some(10_000)), MAX_THUMB_WIDTH);
+    }
+
+    #[tokio::test]
+    async fn image_thumbnail_is_cached_and_evicted_with_source() {
+        let tmp = tempfile::tempdir().unwrap();
+        let rel = "journals/media/image/photo.png";
+        let source = tmp.path().join(rel);
+        std::fs::create_dir_all(source.parent().unwrap()).unwrap();
+        write_png(&source, 800, 400);
+
+        let jpeg = thumbnail_jpeg(tmp.path(), rel, &source, ThumbSource::Image, 320)
+            .await
+            .unwrap();
+        let decoded = image::load_from_memory(&jpeg).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (320, 160));
+
+        let cached: Vec<_> = std::fs::read_dir(thumbs_dir(tmp.path()))
+            .unwrap()
+            .flatten()
+            .filter(|e| e.path().extension().and_then(|x| x.to_str()) == Some("jpg"))
+            .collect();
+        assert_eq!(cached.len(), 1);
+
+        assert_eq!(evict_orphaned_thumbs(tmp.path()), 0);
+        std::fs::remove_file(&source).unwrap();
+        assert_eq!(evict_orphaned_thumbs(tmp.path()), 1);
+        assert!(!cached[0].path().exists());
+    }
+
+    #[test]
+    fn content_key_changes_with_content_and_width() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("a.png");
+        std::fs::write(&path, b"one").unwrap();
+        let first = content_key(&path, 320).unwrap();
+        assert_ne!(first, content_key(&path, 640).unwrap());
+        std::fs::write(&path, b"two").unwrap();
+        assert_ne!(first, content_key(&path, 320).unwrap());
+    }
+}
@@ -0,0 +1,295 @@
+//! Soft-delete for workspace library files.
+//!
+//! Deleting moves files into `.trash/<id>/<original relative path>` with a
+//! `manifest.json` describing the batch, so a delete can be listed, restored,
+//! or purged once it ages past the configured retention.
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+pub const TRASH_DIR: &str = ".trash";
+const MANIFEST_FILE: &str = "manifest.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashEntry {
+    pub id: String,
+    pub trashed_at: String,
+    pub paths: Vec<String>,
+    /// Metadata status of each path before it was trashed, for restore.
+    #[serde(default)]
+    pub previous_status: HashMap<String, String>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum TrashError {
+    #[error("trash entry not found: {0}")]
+    NotFound(String),
+    #[error("cannot restore over existing file: {0}")]
+    Conflict(String),
+}
+
+pub fn trash_root(workspace_dir: &Path) -> PathBuf {
+    workspace_dir.join(TRASH_DIR)
+}
+
+fn is_plain_relative(rel_path: &str) -> bool {
+    let path = Path::new(rel_path);
+    !rel_path.is_empty() && path.components().all(|c| matches!(c, Component::Normal(_)))
+}
+
+/// Resolve a trash entry directory, refusing ids that could escape `.trash/`.
+fn entry_dir(workspace_dir: &Path, id: &str) -> Result<PathBuf> {
+    let id = id.trim();
+    if !is_plain_relative(id) || Path::new(id).components().count() != 1 {
+        bail!(TrashError::NotFound(id.to_string()));
+    }
+    let dir = trash_root(workspace_dir).join(id);
+    if !dir.join(MANIFEST_FILE).is_file() {
+        bail!(TrashError::NotFound(id.to_string()));
+    }
+    Ok(dir)
+}
+
+fn read_manifest(dir: &Path) -> Result<TrashEntry> {
+    let raw = std::fs::read_to_string(dir.join(MANIFEST_FILE))
+        .context("Failed to read trash manifest")?;
+    serde_json::from_str(&raw).context("Failed to parse trash manifest")
+}
+
+fn write_manifest(dir: &Path, entry: &TrashEntry) -> Result<()> {
+    std::fs::write(
+        dir.join(MANIFEST_FILE),
+        serde_json::to_string_pretty(entry)?,
+    )
+    .context("Failed to write trash manifest")
+}
+
+fn new_entry_id() -> String {
+    let now = chrono::Utc::now().format("%Y%m%dT%H%M%SZ");
+    let suffix = uuid::Uuid::new_v4().simple().to_string();
+    format!("{now}-{}", &suffix[..8])
+}
+
+/// Move workspace-relative files into a new trash entry. Paths that do not
+/// exist are skipped; the first path must exist or nothing is trashed.
+pub fn move_to_trash(
+    workspace_dir: &Path,
+    rel_paths: &[String],
+    previous_status: impl IntoIterator<Item = (String, String)>,
+) -> Result<TrashEntry> {
+    let workspace_resolved = workspace_dir
+        .canonicalize()
+        .unwrap_or_else(|_| workspace_dir.to_path_buf());
+    let trash_resolved = workspace_resolved.join(TRASH_DIR);
+    let mut sources = Vec::new();
+    for rel in rel_paths {
+        if !is_plain_relative(rel) {
+            bail!("Invalid path for trash: {rel}");
+        }
+        let abs = workspace_dir.join(rel);
+        let Ok(resolved) = abs.canonicalize() else {
+            continue;
+        };
+        if !resolved.starts_with(&workspace_resolved) || resolved.starts_with(&trash_resolved) {
+            bail!("Path is outside the trashable workspace: {rel}");
+        }
+        if resolved.is_file() {
+            sources.push((rel.clone(), abs));
+        }
+    }
+    match rel_paths.first() {
+        Some(first) if sources.first().is_some_and(|(rel, _)| rel == first) => {}
+        _ => bail!("Nothing to trash"),
+    }
+
+    let id = new_entry_id();
+    let dir = trash_root(workspace_dir).join(&id);
+    std::fs::create_dir_all(&dir).context("Failed to create trash entry")?;
+    let mut moved = Vec::new();
+    for (rel, abs) in sources {
+        let dest = dir.join(&rel);
+        if let Some(parent) = dest.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::rename(&abs, &dest).with_context(|| format!("Failed to move {rel} to trash"))?;
+        moved.push(rel);
+    }
+    let entry = TrashEntry {
+        id,
+        trashed_at: chrono::Utc::now().to_rfc3339(),
+        paths: moved,
+        previous_status: previous_status.into_iter().collect(),
+    };
+    write_manifest(&dir, &entry)?;
+    Ok(entry)
+}
+
+/// All trash entries, newest first.
+pub fn list_trash(workspace_dir: &Path) -> Vec<TrashEntry> {
+    let Ok(entries) = std::fs::read_dir(trash_root(workspace_dir)) else {
+        return Vec::new();
+    };
+    let mut out: Vec<TrashEntry> = entries
+        .flatten()
+        .filter_map(|entry| read_manifest(&entry.path()).ok())
+        .collect();
+    out.sort_by(|a, b| b.trashed_at.cmp(&a.trashed_at));
+    out
+}
+
+/// Restore every file in an entry, or just `only_path` when given. Returns
+/// the restored paths; the entry is removed once it is empty.
+pub fn restore_from_trash(
+    workspace_dir: &Path,
+    id: &str,
+    only_path: Option<&str>,
+) -> Result<(TrashEntry, Vec<String>)> {
+    let dir = entry_dir(workspace_dir, id)?;
+    let mut entry = read_manifest(&dir)?;
+    let selected: Vec<String> = match only_path {
+        Some(path) => {
+            if !entry.paths.iter().any(|p| p == path) {
+                bail!(TrashError::NotFound(format!("{id}/{path}")));
+            }
+            vec![path.to_string()]
+        }
+        None => entry.paths.clone(),
+    };
+    for rel in &selected {
+        if workspace_dir.join(rel).exists() {
+            bail!(TrashError::Conflict(rel.clone()));
+        }
+    }
+    for rel in &selected {
+        let dest = workspace_dir.join(rel);
+        if let Some(parent) = dest.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::rename(dir.join(rel), &dest)
+            .with_context(|| format!("Failed to restore {rel} from trash"))?;
+    }
+    entry.paths.retain(|p| !selected.contains(p));
+    if entry.paths.is_empty() {
+        std::fs::remove_dir_all(&dir).context("Failed to remove emptied trash entry")?;
+    } else {
+        write_manifest(&dir, &entry)?;
+    }
+    Ok((entry, selected))
+}
+
+/// Permanently delete entries older than `retention_days`. Zero disables purging.
+pub fn purge_expired(workspace_dir: &Path, retention_days: u32) -> usize {
+    if retention_days == 0 {
+        return 0;
+    }
+    let cutoff = chrono::Utc::now() - chrono::Duration::days(i64::from(retention_days));
+    let mut purged = 0;
+    for entry in list_trash(workspace_dir) {
+        let expired =
+            chrono::DateTime::parse_from_rfc3339(&entry.trashed_at).is_ok_and(|ts| ts < cutoff);
+        if !expired {
+            continue;
+        }
+        match std::fs::remove_dir_all(trash_root(workspace_dir).join(&entry.id)) {
+            Ok(()) => purged += 1,
+            Err(err) => tracing::warn!(id = %entry.id, "Failed to purge trash entry: {err}"),
+        }
+    }
+    purged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(workspace: &Path, rel: &str) {
+        let path = workspace.join(rel);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, rel).unwrap();
+    }
+
+    #[test]
+    fn trash_and_restore_preserves_relative_paths() {
+        let tmp = tempfile::tempdir().unwrap();
+        write(tmp.path(), "journals/text/a.md");
+        write(tmp.path(), "journals/text/a.md.tags.json");
+
+        let entry = move_to_trash(
+            tmp.path(),
+            &[
+                "journals/text/a.md".into(),
+                "journals/text/a.md.tags.json".into(),
+                "journals/text/missing.txt".into(),
+            ],
+            HashMap::new(),
+        )
+        .unwrap();
+        assert_eq!(entry.paths.len(), 2);
+        assert!(!tmp.path().join("journals/text/a.md").exists());
+        assert!(trash_root(tmp.path())
+            .join(&entry.id)
+            .join("journals/text/a.md")
+            .is_file());
+        assert_eq!(list_trash(tmp.path()).len(), 1);
+
+        let (_, restored) = restore_from_trash(tmp.path(), &entry.id, None).unwrap();
+        assert_eq!(restored.len(), 2);
+        assert!(tmp.path().join("journals/text/a.md").is_file());
+        assert!(list_trash(tmp.path()).is_empty());
+    }
+
+    #[test]
+    fn restore_refuses_to_overwrite_and_rejects_escaping_ids() {
+        let tmp = tempfile::tempdir().unwrap();
+        write(tmp.path(), "journals/text/a.md");
+        let entry =
+            move_to_trash(tmp.path(), &["journals/text/a.md".into()], HashMap::new()).unwrap();
+        write(tmp.path(), "journals/text/a.md");
+
+        let err = restore_from_trash(tmp.path(), &entry.id, None).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<TrashError>(),
+            Some(TrashError::Conflict(_))
+        ));
+        let err = restore_from_trash(tmp.path(), "../journals", None).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<TrashError>(),
+            Some(TrashError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn move_to_trash_rejects_traversal_and_trash_paths() {
+        let tmp = tempfile::tempdir().unwrap();
+        assert!(move_to_trash(tmp.path(), &["../outside.md".into()], HashMap::new()).is_err());
+        write(tmp.path(), ".trash/old/manifest.json");
+        assert!(move_to_trash(
+            tmp.path(),
+            &[".trash/old/manifest.json".into()],
+            HashMap::new()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn purge_removes_only_expired_entries() {
+        let tmp = tempfile::tempdir().unwrap();
+        write(tmp.path(), "journals/text/old.md");
+        write(tmp.path(), "journals/text/new.md");
+        let old =
+            move_to_trash(tmp.path(), &["journals/text/old.md".into()], HashMap::new()).unwrap();
+        move_to_trash(tmp.path(), &["journals/text/new.md".into()], HashMap::new()).unwrap();
+
+        let old_dir = trash_root(tmp.path()).join(&old.id);
+        let mut manifest = read_manifest(&old_dir).unwrap();
+        manifest.trashed_at = (chrono::Utc::now() - chrono::Duration::days(45)).to_rfc3339();
+        write_manifest(&old_dir, &manifest).unwrap();
+
+        assert_eq!(purge_expired(tmp.path(), 0), 0);
+        assert_eq!(purge_expired(tmp.path(), 30), 1);
+        assert_eq!(list_trash(tmp.path()).len(), 1);
+    }
+}
@@ -0,0 +1,492 @@
+//! Local git history for the workspace (`[workspace] git_autocommit`).
+//!
+//! At startup the workspace becomes a git repository if it is not one yet,
+//! with a `.gitignore` for PocketBase data, `state/`, trash, version copies,
+//! and temp files, and its current files are committed. Gateway saves, trash
+//! moves, and the library watcher call [`note_change`]; a background task
+//! commits the queued paths once they have been quiet for a moment, so a
+//! burst of saves becomes one commit. Files over `git_max_file_bytes` are
+//! never staged.
+//!
+//! Only the local repository is used: nothing here adds, fetches from, or
+//! pushes to a remote. Git failures, corrupt objects included, are logged and
+//! dropped, so the workspace keeps working without history.
+
+use crate::workspace::atomic_write;
+use anyhow::{Context, Result};
+use git2::{ErrorCode, IndexAddOption, Oid, Repository, Signature, Sort};
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+const GITIGNORE: &str = "\
+# Written by slowclaw for [workspace] git_autocommit; edit as needed.
+pb_data/
+state/
+.trash/
+.versions/
+*.db
+*.db-shm
+*.db-wal
+*.sqlite
+*.slowclaw-tmp
+";
+const INITIAL_MESSAGE: &str = "Start workspace history";
+/// Quiet period after the last change before a commit.
+const DEBOUNCE: Duration = Duration::from_secs(3);
+/// Longest a steady stream of changes can hold a commit back.
+const MAX_DEBOUNCE: Duration = Duration::from_secs(30);
+const FALLBACK_NAME: &str = "slowclaw";
+const FALLBACK_EMAIL: &str = "slowclaw@localhost";
+
+#[derive(Debug, thiserror::Error)]
+pub enum HistoryError {
+    #[error("commit not found: {0}")]
+    CommitNotFound(String),
+    #[error("{path} is not in commit {commit}")]
+    NotInCommit { path: String, commit: String },
+}
+
+/// One commit that changed a file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileCommit {
+    pub id: String,
+    pub short_id: String,
+    pub message: String,
+    pub time: Option<String>,
+    /// `added`, `modified`, or `deleted`.
+    pub change: &'static str,
+}
+
+struct Change {
+    paths: Vec<String>,
+    message: String,
+}
+
+/// Paths and messages waiting for the next commit.
+#[derive(Debug, Default)]
+struct Batch {
+    paths: BTreeSet<String>,
+    messages: Vec<String>,
+}
+
+impl Batch {
+    fn add(&mut self, change: Change) {
+        self.paths.extend(change.paths);
+        if !self.messages.contains(&change.message) {
+            self.messages.push(change.message);
+        }
+    }
+
+    fn message(&self) -> String {
+        match self.messages.as_slice() {
+            [] => "Update workspace".to_string(),
+            [only] => only.clone(),
+            [first, rest @ ..] => {
+                let mut out = format!("{first} and {} more\n", rest.len());
+                for message in &self.messages {
+                    out.push_str("\n- ");
+                    out.push_str(message);
+                }
+                out
+            }
+        }
+    }
+}
+
+type Queues = Mutex<HashMap<PathBuf, mpsc::UnboundedSender<Change>>>;
+
+fn queues() -> &'static Queues {
+    static QUEUES: OnceLock<Queues> = OnceLock::new();
+    QUEUES.get_or_init(Queues::default)
+}
+
+fn queue_key(workspace_dir: &Path) -> PathBuf {
+    workspace_dir
+        .canonicalize()
+        .unwrap_or_else(|_| workspace_dir.to_path_buf())
+}
+
+/// Queue workspace-relative `paths` (files or directories) for the next
+/// commit. Does nothing unless [`spawn`] is running for the workspace.
+pub fn note_change<I>(workspace_dir: &Path, paths: I, message: impl Into<String>)
+where
+    I: IntoIterator,
+    I::Item: AsRef<str>,
+{
+    let queues = queues().lock();
+    if queues.is_empty() {
+        return;
+    }
+    let Some(tx) = queues.get(&queue_key(workspace_dir)) else {
+        return;
+    };
+    let paths: Vec<String> = paths
+        .into_iter()
+        .map(|path| path.as_ref().trim_matches('/').to_string())
+        .filter(|path| !path.is_empty())
+        .collect();
+    if !paths.is_empty() {
+        let _ = tx.send(Change {
+            paths,
+            message: message.into(),
+        });
+    }
+}
+
+fn too_large(path: &Path, max_file_bytes: u64) -> bool {
+    std::fs::metadata(path).is_ok_and(|meta| meta.len() > max_file_bytes)
+}
+
+fn signature(repo: &Repository) -> Result<Signature<'static>> {
+    repo.signature()
+        .or_else(|_| Signature::now(FALLBACK_NAME, FALLBACK_EMAIL))
+        .context("Failed to build a commit signature")
+}
+
+/// Stage `paths` (new, changed, and deleted files under each) and commit
+/// them. Returns `None` when nothing changed.
+fn commit_paths(
+    workspace_dir: &Path,
+    paths: &BTreeSet<String>,
+    message: &str,
+    max_file_bytes: u64,
+) -> Result<Option<Oid>> {
+    let repo =
+        Repository::open(workspace_dir).context("Failed to open the workspace repository")?;
+    let mut index = repo.index()?;
+    let mut skip_large =
+        |path: &Path, _spec: &[u8]| i32::from(too_large(&workspace_dir.join(path), max_file_bytes));
+    index.add_all(
+        paths.iter(),
+        IndexAddOption::DEFAULT,
+        Some(&mut skip_large as &mut git2::IndexMatchedPath),
+    )?;
+    index.update_all(
+        paths.iter(),
+        Some(&mut skip_large as &mut git2::IndexMatchedPath),
+    )?;
+    index.write()?;
+    let tree_id = index.write_tree()?;
+
+    let parent = match repo.head() {
+        Ok(head) => Some(head.peel_to_commit()?),
+        Err(err) if matches!(err.code(), ErrorCode::UnbornBranch | ErrorCode::NotFound) => None,
+        Err(err) => return Err(err).context("Failed to read HEAD"),
+    };
+    if parent
+        .as_ref()
+        .is_some_and(|parent| parent.tree_id() == tree_id)
+    {
+        return Ok(None);
+    }
+    let tree = repo.find_tree(tree_id)?;
+    let signature = signature(&repo)?;
+    let parents: Vec<&git2::Commit<'_>> = parent.iter().collect();
+    let id = repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        message,
+        &tree,
+        &parents,
+    )?;
+    Ok(Some(id))
+}
+
+/// Make the workspace a repository with the default `.gitignore` and an
+/// initial commit, unless it already is one. Returns whether it was created.
+pub fn prepare(workspace_dir: &Path, max_file_bytes: u64) -> Result<bool> {
+    match Repository::open(workspace_dir) {
+        Ok(_) => return Ok(false),
+        Err(err) if err.code() == ErrorCode::NotFound => {}
+        Err(err) => return Err(err).context("Failed to open the workspace repository"),
+    }
+    Repository::init(workspace_dir).context("Failed to initialize the workspace repository")?;
+    let gitignore = workspace_dir.join(".gitignore");
+    if !gitignore.exists() {
+        atomic_write(&gitignore, GITIGNORE)
+            .with_context(|| format!("Failed to write {}", gitignore.display()))?;
+    }
+    let everything = BTreeSet::from(["*".to_string()]);
+    commit_paths(workspace_dir, &everything, INITIAL_MESSAGE, max_file_bytes)?;
+    Ok(true)
+}
+
+async fn commit_batch(workspace_dir: &Path, batch: Batch, max_file_bytes: u64) {
+    let dir = workspace_dir.to_path_buf();
+    let message = batch.message();
+    let result = tokio::task::spawn_blocking(move || {
+        commit_paths(&dir, &batch.paths, &message, max_file_bytes)
+    })
+    .await;
+    match result {
+        Ok(Ok(Some(id))) => tracing::debug!(commit = %id, "Workspace changes committed"),
+        Ok(Ok(None)) => {}
+        Ok(Err(err)) => tracing::debug!("Workspace commit skipped: {err:#}"),
+        Err(err) => tracing::debug!("Workspace commit task failed: {err}"),
+    }
+}
+
+async fn run(workspace_dir: PathBuf, max_file_bytes: u64, mut rx: mpsc::UnboundedReceiver<Change>) {
+    let dir = workspace_dir.clone();
+    match tokio::task::spawn_blocking(move || prepare(&dir, max_file_bytes)).await {
+        Ok(Ok(true)) => tracing::info!("Workspace history started in {}", workspace_dir.display()),
+        Ok(Ok(false)) => {}
+        Ok(Err(err)) => {
+            tracing::warn!("Workspace history disabled: {err:#}");
+            queues().lock().remove(&queue_key(&workspace_dir));
+            return;
+        }
+        Err(err) => {
+            tracing::warn!("Workspace history disabled: {err}");
+            queues().lock().remove(&queue_key(&workspace_dir));
+            return;
+        }
+    }
+
+    while let Some(change) = rx.recv().await {
+        let mut batch = Batch::default();
+        batch.add(change);
+        let deadline = Instant::now() + MAX_DEBOUNCE;
+        loop {
+            let wait = DEBOUNCE.min(deadline.saturating_duration_since(Instant::now()));
+            match tokio::time::timeout(wait, rx.recv()).await {
+                Ok(Some(change)) => batch.add(change),
+                Ok(None) | Err(_) => break,
+            }
+        }
+        commit_batch(&workspace_dir, batch, max_file_bytes).await;
+    }
+}
+
+/// Start committing noted changes for `workspace_dir`.
+pub fn spawn(workspace_dir: PathBuf, max_file_bytes: u64) -> JoinHandle<()> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    queues().lock().insert(queue_key(&workspace_dir), tx);
+    tokio::spawn(run(workspace_dir, max_file_bytes, rx))
+}
+
+fn entry_id(tree: &git2::Tree<'_>, path: &Path) -> Option<Oid> {
+    tree.get_path(path).ok().map(|entry| entry.id())
+}
+
+/// Commits that changed `rel_path`, newest first. A corrupt object ends the
+/// walk; the commits read before it are still returned.
+pub fn history(workspace_dir: &Path, rel_path: &str, limit: usize) -> Result<Vec<FileCommit>> {
+    let repo =
+        Repository::open(workspace_dir).context("Failed to open the workspace repository")?;
+    let mut walk = repo.revwalk()?;
+    walk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME)?;
+    if walk.push_head().is_err() {
+        return Ok(Vec::new());
+    }
+    let path = Path::new(rel_path);
+    let mut out = Vec::new();
+    for id in walk {
+        let Ok(commit) = id.and_then(|id| repo.find_commit(id)) else {
+            break;
+        };
+        let Ok(tree) = commit.tree() else {
+            break;
+        };
+        let after = entry_id(&tree, path);
+        let before = commit
+            .parent(0)
+            .ok()
+            .and_then(|parent| parent.tree().ok())
+            .and_then(|tree| entry_id(&tree, path));
+        let change = match (before, after) {
+            (None, Some(_)) => "added",
+            (Some(_), None) => "deleted",
+            (Some(before), Some(after)) if before != after => "modified",
+            _ => continue,
+        };
+        let id = commit.id().to_string();
+        out.push(FileCommit {
+            short_id: id.chars().take(7).collect(),
+            id,
+            message: commit.summary().unwrap_or_default().to_string(),
+            time: chrono::DateTime::<chrono::Utc>::from_timestamp(commit.time().seconds(), 0)
+                .map(|time| time.to_rfc3339()),
+            change,
+        });
+        if out.len() >= limit.max(1) {
+            break;
+        }
+    }
+    Ok(out)
+}
+
+/// The content of `rel_path` in `commit` (a full or abbreviated id), with
+/// the commit's full id.
+pub fn file_at(workspace_dir: &Path, rel_path: &str, commit: &str) -> Result<(Vec<u8>, String)> {
+    let commit = commit.trim();
+    if commit.len() < 4 || commit.len() > 40 || !commit.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(HistoryError::CommitNotFound(commit.to_string()).into());
+    }
+    let repo =
+        Repository::open(workspace_dir).context("Failed to open the workspace repository")?;
+    let found = repo
+        .revparse_single(commit)
+        .and_then(|object| object.peel_to_commit())
+        .map_err(|_| HistoryError::CommitNotFound(commit.to_string()))?;
+    let tree = found.tree()?;
+    let Some(id) = entry_id(&tree, Path::new(rel_path)) else {
+        return Err(HistoryError::NotInCommit {
+            path: rel_path.to_string(),
+            commit: commit.to_string(),
+        }
+        .into());
+    };
+    let blob = repo
+        .find_blob(id)
+        .with_context(|| format!("Failed to read {rel_path} from {commit}"))?;
+    Ok((blob.content().to_vec(), found.id().to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAX: u64 = 1024;
+
+    fn paths(items: &[&str]) -> BTreeSet<String> {
+        items.iter().map(|item| item.to_string()).collect()
+    }
+
+    fn workspace() -> tempfile::TempDir {
+        let tmp = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(tmp.path().join("journals/text")).unwrap();
+        std::fs::create_dir_all(tmp.path().join("state")).unwrap();
+        tmp
+    }
+
+    fn indexed_files(workspace_dir: &Path) -> Vec<String> {
+        let repo = Repository::open(workspace_dir).unwrap();
+        let index = repo.index().unwrap();
+        index
+            .iter()
+            .map(|entry| String::from_utf8(entry.path).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn prepare_commits_current_files_but_not_ignored_or_large_ones() {
+        let tmp = workspace();
+        let ws = tmp.path();
+        std::fs::write(ws.join("journals/text/note.md"), "first\n").unwrap();
+        std::fs::write(ws.join("state/local_data.db"), "sqlite").unwrap();
+        std::fs::write(ws.join("journals/big.mp4"), vec![0u8; 2048]).unwrap();
+
+        assert!(prepare(ws, MAX).unwrap());
+        assert!(!prepare(ws, MAX).unwrap());
+        assert_eq!(
+            indexed_files(ws),
+            vec![
+                ".gitignore".to_string(),
+                "journals/text/note.md".to_string()
+            ]
+        );
+        let log = history(ws, "journals/text/note.md", 10).unwrap();
+        assert_eq!(log.len(), 1);
+        assert_eq!(log[0].change, "added");
+        assert_eq!(log[0].message, INITIAL_MESSAGE);
+    }
+
+    #[test]
+    fn changes_commit_once_and_old_content_stays_readable() {
+        let tmp = workspace();
+        let ws = tmp.path();
+        let rel = "journals/text/note.md";
+        std::fs::write(ws.join(rel), "first\n").unwrap();
+        prepare(ws, MAX).unwrap();
+        let first = history(ws, rel, 10).unwrap()[0].id.clone();
+
+        std::fs::write(ws.join(rel), "second\n").unwrap();
+        let id = commit_paths(ws, &paths(&[rel]), "Save note", MAX).unwrap();
+        assert!(id.is_some());
+        assert_eq!(
+            commit_paths(ws, &paths(&[rel]), "Save note", MAX).unwrap(),
+            None
+        );
+
+        std::fs::remove_file(ws.join(rel)).unwrap();
+        commit_paths(ws, &paths(&["journals"]), "Trash note", MAX).unwrap();
+
+        let log = history(ws, rel, 10).unwrap();
+        let changes: Vec<_> = log.iter().map(|c| (c.change, c.message.as_str())).collect();
+        assert_eq!(
+            changes,
+            vec![
+                ("deleted", "Trash note"),
+                ("modified", "Save note"),
+                ("added", INITIAL_MESSAGE)
+            ]
+        );
+        assert_eq!(history(ws, rel, 1).unwrap().len(), 1);
+
+        let (content, full_id) = file_at(ws, rel, &first[..8]).unwrap();
+        assert_eq!(content, b"first\n");
+        assert_eq!(full_id, first);
+        let err = file_at(ws, rel, "HEAD~1").unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<HistoryError>(),
+            Some(HistoryError::CommitNotFound(_))
+        ));
+        let err = file_at(ws, "journals/text/other.md", &first).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<HistoryError>(),
+            Some(HistoryError::NotInCommit { .. })
+        ));
+    }
+
+    #[test]
+    fn unreadable_objects_end_history_without_failing() {
+        let tmp = workspace();
+        let ws = tmp.path();
+        let rel = "journals/text/note.md";
+        std::fs::write(ws.join(rel), "first\n").unwrap();
+        prepare(ws, MAX).unwrap();
+        std::fs::remove_dir_all(ws.join(".git/objects")).unwrap();
+        std::fs::create_dir_all(ws.join(".git/objects")).unwrap();
+
+        assert!(history(ws, rel, 10).unwrap().is_empty());
+        std::fs::write(ws.join(rel), "second\n").unwrap();
+        assert!(commit_paths(ws, &paths(&[rel]), "Save note", MAX).is_err());
+    }
+
+    #[test]
+    fn batches_merge_paths_and_describe_every_change() {
+        let mut batch = Batch::default();
+        for (path, message) in [
+            ("journals/a.md", "Save journals/a.md"),
+            ("journals/a.md", "Save journals/a.md"),
+            ("journals/b.md", "Trash journals/b.md"),
+        ] {
+            batch.add(Change {
+                paths: vec![path.to_string()],
+                message: message.to_string(),
+            });
+        }
+        assert_eq!(batch.paths.len(), 2);
+        assert_eq!(
+            batch.message(),
+            "Save journals/a.md and 1 more\n\n- Save journals/a.md\n- Trash journals/b.md"
+        );
+    }
+
+    #[test]
+    fn notes_are_dropped_when_history_is_off() {
+        let tmp = workspace();
+        note_change(tmp.path(), ["journals/a.md"], "Save journals/a.md");
+        assert!(!queues().lock().contains_key(&queue_key(tmp.path())));
+    }
+}
@@ -5024,6 +5024,8 @@ Do something useful.
                 workspace_path: media_rel.to_string(),
                 size_bytes: 5,
                 created_at_client: None,
+                probe: None,
+                tags_csv: String::new(),
             },
         )
         .unwrap();
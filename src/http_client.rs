@@ -0,0 +1,45 @@
+//! Process-wide HTTP client for PocketBase, feed, and gateway-internal calls.
+//!
+//! `reqwest::Client` keeps a connection pool, so building one per request
+//! throws away keep-alive connections and repeats the TLS handshake on every
+//! poll. Call sites clone the shared client instead (a cheap `Arc` clone) and
+//! set per-request timeouts with `RequestBuilder::timeout` where they need a
+//! tighter bound than the default.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// `User-Agent` sent with every request from the shared client.
+pub const USER_AGENT: &str = concat!("slowclaw/", env!("CARGO_PKG_VERSION"));
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+
+static SHARED: OnceLock<reqwest::Client> = OnceLock::new();
+static BUILT: AtomicUsize = AtomicUsize::new(0);
+
+/// The shared client, built on first use.
+pub fn shared() -> reqwest::Client {
+    SHARED
+        .get_or_init(|| {
+            BUILT.fetch_add(1, Ordering::Relaxed);
+            reqwest::Client::builder()
+                .user_agent(USER_AGENT)
+                .timeout(REQUEST_TIMEOUT)
+                .connect_timeout(CONNECT_TIMEOUT)
+                .pool_idle_timeout(POOL_IDLE_TIMEOUT)
+                .build()
+                .unwrap_or_else(|error| {
+                    tracing::warn!("Failed to build shared HTTP client: {error}");
+                    reqwest::Client::new()
+                })
+        })
+        .clone()
+}
+
+/// How many times the shared client has been built; stays at 1 for the
+/// lifetime of the process.
+pub fn clients_built() -> usize {
+    BUILT.load(Ordering::Relaxed)
+}
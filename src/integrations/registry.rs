@@ -678,7 +678,7 @@ pub fn all_integrations() -> Vec<IntegrationEntry> {
         },
         IntegrationEntry {
             name: "Email",
-            description: "IMAP/SMTP email channel",
+            description: "SMTP digest delivery",
             category: IntegrationCategory::Social,
             status_fn: |c| {
                 if c.channels_config.email.is_some() {
@@ -804,6 +804,8 @@ mod tests {
             draft_update_interval_ms: 1000,
             interrupt_on_new_message: false,
             mention_only: false,
+            allowed_chats: vec![],
+            webhook_secret: None,
         });
         let entries = all_integrations();
         let tg = entries.iter().find(|e| e.name == "Telegram").unwrap();
@@ -0,0 +1,402 @@
+//! Background job queue for long-running tool work.
+//!
+//! Tools that may run for minutes (video renders, workspace scripts) can
+//! enqueue their work here instead of blocking the agent turn. The tool call
+//! returns a job id immediately; job state is persisted to
+//! `<workspace>/state/jobs/<id>.json` and exposed by the gateway under
+//! `/api/jobs`. When a job was enqueued from a PocketBase thread or a live
+//! channel chat (e.g. Telegram), a completion message is posted back there.
+
+use crate::channels::context::{
+    current_channel_execution_context, default_cron_delivery_for_current_channel,
+    with_channel_execution_context, ChannelExecutionContext,
+};
+use crate::channels::outbound::send_to_origin;
+use crate::workspace::atomic_write;
+use anyhow::{Context, Result};
+use chrono::Utc;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use uuid::Uuid;
+
+/// Maximum number of bytes of job output retained in the persisted record.
+const JOB_OUTPUT_TAIL_BYTES: usize = 4096;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+}
+
+impl JobStatus {
+    pub fn is_finished(self) -> bool {
+        matches!(self, Self::Succeeded | Self::Failed)
+    }
+}
+
+/// Channel the job was enqueued from, used for completion notices.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobOrigin {
+    pub channel: String,
+    pub recipient: String,
+    pub thread_ts: Option<String>,
+}
+
+impl From<ChannelExecutionContext> for JobOrigin {
+    fn from(ctx: ChannelExecutionContext) -> Self {
+        Self {
+            channel: ctx.channel,
+            recipient: ctx.recipient,
+            thread_ts: ctx.thread_ts,
+        }
+    }
+}
+
+impl From<&JobOrigin> for ChannelExecutionContext {
+    fn from(origin: &JobOrigin) -> Self {
+        Self::new(
+            origin.channel.clone(),
+            origin.recipient.clone(),
+            origin.thread_ts.clone(),
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobRecord {
+    pub id: String,
+    pub kind: String,
+    pub status: JobStatus,
+    pub created_at: String,
+    pub updated_at: String,
+    #[serde(default)]
+    pub output_tail: String,
+    #[serde(default)]
+    pub artifacts: Vec<String>,
+    #[serde(default)]
+    pub error: Option<String>,
+    #[serde(default)]
+    pub origin: Option<JobOrigin>,
+}
+
+/// Result a job future resolves to on success.
+#[derive(Debug, Clone, Default)]
+pub struct JobOutcome {
+    pub output: String,
+    pub artifacts: Vec<String>,
+}
+
+fn output_tail(output: &str) -> String {
+    if output.len() <= JOB_OUTPUT_TAIL_BYTES {
+        return output.to_string();
+    }
+    let mut idx = output.len() - JOB_OUTPUT_TAIL_BYTES;
+    while !output.is_char_boundary(idx) {
+        idx += 1;
+    }
+    output[idx..].to_string()
+}
+
+pub struct JobManager {
+    /// `None` keeps jobs in memory only (tests).
+    jobs_dir: Option<PathBuf>,
+    jobs: Mutex<HashMap<String, JobRecord>>,
+}
+
+static JOB_MANAGERS: OnceLock<Mutex<HashMap<PathBuf, Arc<JobManager>>>> = OnceLock::new();
+
+impl JobManager {
+    /// Shared manager for a workspace. The gateway and tools resolve the same
+    /// instance so jobs enqueued from a tool are visible to `/api/jobs`.
+    pub fn for_workspace(workspace_dir: &Path) -> Arc<Self> {
+        let key = workspace_dir
+            .canonicalize()
+            .unwrap_or_else(|_| workspace_dir.to_path_buf());
+        let registry = JOB_MANAGERS.get_or_init(|| Mutex::new(HashMap::new()));
+        registry
+            .lock()
+            .entry(key)
+            .or_insert_with(|| Arc::new(Self::load(workspace_dir.join("state").join("jobs"))))
+            .clone()
+    }
+
+    /// Non-persistent manager, used by tests.
+    pub fn in_memory() -> Self {
+        Self {
+            jobs_dir: None,
+            jobs: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Load persisted jobs. Jobs that were still queued or running when the
+    /// process stopped can never complete, so they are marked failed.
+    fn load(jobs_dir: PathBuf) -> Self {
+        let manager = Self {
+            jobs_dir: Some(jobs_dir.clone()),
+            jobs: Mutex::new(HashMap::new()),
+        };
+        let Ok(entries) = std::fs::read_dir(&jobs_dir) else {
+            return manager;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue;
+            }
+            let Some(mut record) = std::fs::read_to_string(&path)
+                .ok()
+                .and_then(|raw| serde_json::from_str::<JobRecord>(&raw).ok())
+            else {
+                tracing::warn!("Skipping unreadable job record {}", path.display());
+                continue;
+            };
+            if !record.status.is_finished() {
+                record.status = JobStatus::Failed;
+                record.error = Some("Interrupted by restart".to_string());
+                record.updated_at = Utc::now().to_rfc3339();
+                if let Err(err) = manager.persist(&record) {
+                    tracing::warn!("Failed to persist interrupted job {}: {err}", record.id);
+                }
+            }
+            manager.jobs.lock().insert(record.id.clone(), record);
+        }
+        manager
+    }
+
+    fn persist(&self, record: &JobRecord) -> Result<()> {
+        let Some(dir) = self.jobs_dir.as_ref() else {
+            return Ok(());
+        };
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create jobs dir {}", dir.display()))?;
+        let path = dir.join(format!("{}.json", record.id));
+        atomic_write(&path, serde_json::to_vec_pretty(record)?)
+            .with_context(|| format!("Failed to write job record {}", path.display()))
+    }
+
+    fn update(&self, id: &str, apply: impl FnOnce(&mut JobRecord)) -> Option<JobRecord> {
+        let record = {
+            let mut jobs = self.jobs.lock();
+            let record = jobs.get_mut(id)?;
+            apply(record);
+            record.updated_at = Utc::now().to_rfc3339();
+            record.clone()
+        };
+        if let Err(err) = self.persist(&record) {
+            tracing::warn!("Failed to persist job {id}: {err}");
+        }
+        Some(record)
+    }
+
+    /// Enqueue `work` on the Tokio runtime and return the new job id. The
+    /// caller's channel context is captured so completion can be reported
+    /// back to the originating chat thread, and `work` runs inside it so
+    /// subprocesses it spawns still see the origin.
+    pub fn enqueue<F>(self: &Arc<Self>, kind: &str, work: F) -> String
+    where
+        F: Future<Output = Result<JobOutcome>> + Send + 'static,
+    {
+        let now = Utc::now().to_rfc3339();
+        let record = JobRecord {
+            id: Uuid::new_v4().to_string(),
+            kind: kind.to_string(),
+            status: JobStatus::Queued,
+            created_at: now.clone(),
+            updated_at: now,
+            output_tail: String::new(),
+            artifacts: Vec::new(),
+            error: None,
+            origin: default_cron_delivery_for_current_channel().map(JobOrigin::from),
+        };
+        let id = record.id.clone();
+        if let Err(err) = self.persist(&record) {
+            tracing::warn!("Failed to persist job {id}: {err}");
+        }
+        self.jobs.lock().insert(id.clone(), record);
+
+        let manager = Arc::clone(self);
+        let job_id = id.clone();
+        let context = current_channel_execution_context();
+        tokio::spawn(async move {
+            manager.update(&job_id, |r| r.status = JobStatus::Running);
+            let result = match context {
+                Some(ctx) => with_channel_execution_context(ctx, work).await,
+                None => work.await,
+            };
+            let finished = manager.update(&job_id, |r| match result {
+                Ok(outcome) => {
+                    r.status = JobStatus::Succeeded;
+                    r.output_tail = output_tail(&outcome.output);
+                    r.artifacts = outcome.artifacts;
+                }
+                Err(err) => {
+                    r.status = JobStatus::Failed;
+                    r.error = Some(err.to_string());
+                }
+            });
+            if let Some(record) = finished {
+                notify_job_completion(&record, manager.workspace_dir()).await;
+            }
+        });
+        id
+    }
+
+    /// Workspace the jobs are persisted under (`<workspace>/state/jobs`).
+    fn workspace_dir(&self) -> Option<&Path> {
+        self.jobs_dir.as_deref()?.parent()?.parent()
+    }
+
+    pub fn get(&self, id: &str) -> Option<JobRecord> {
+        self.jobs.lock().get(id).cloned()
+    }
+
+    /// Jobs ordered newest first.
+    pub fn list(&self, limit: usize) -> Vec<JobRecord> {
+        let mut items: Vec<JobRecord> = self.jobs.lock().values().cloned().collect();
+        items.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        items.truncate(limit);
+        items
+    }
+}
+
+fn completion_message(record: &JobRecord) -> String {
+    match record.status {
+        JobStatus::Succeeded if record.artifacts.is_empty() => {
+            format!("Background job `{}` ({}) finished.", record.kind, record.id)
+        }
+        JobStatus::Succeeded => format!(
+            "Background job `{}` ({}) finished. Artifacts: {}",
+            record.kind,
+            record.id,
+            record.artifacts.join(", ")
+        ),
+        _ => format!(
+            "Background job `{}` ({}) failed: {}",
+            record.kind,
+            record.id,
+            record.error.as_deref().unwrap_or("unknown error")
+        ),
+    }
+}
+
+/// Post the completion notice to the originating chat. With a workspace, a
+/// failed post is queued for retry instead of being dropped.
+async fn notify_job_completion(record: &JobRecord, workspace_dir: Option<&Path>) {
+    let Some(origin) = record.origin.as_ref() else {
+        return;
+    };
+    let origin = ChannelExecutionContext::from(origin);
+    if let Err(err) = send_to_origin(&origin, completion_message(record), workspace_dir).await {
+        tracing::warn!("Job {} completion notice failed: {err}", record.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    async fn wait_finished(manager: &JobManager, id: &str) -> JobRecord {
+        for _ in 0..100 {
+            if let Some(record) = manager.get(id).filter(|r| r.status.is_finished()) {
+                return record;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("job {id} did not finish");
+    }
+
+    #[tokio::test]
+    async fn enqueue_records_success_and_artifacts() {
+        let manager = Arc::new(JobManager::in_memory());
+        let id = manager.enqueue("render", async {
+            Ok(JobOutcome {
+                output: "done".into(),
+                artifacts: vec!["posts/clip.mp4".into()],
+            })
+        });
+        let record = wait_finished(&manager, &id).await;
+        assert_eq!(record.status, JobStatus::Succeeded);
+        assert_eq!(record.output_tail, "done");
+        assert_eq!(record.artifacts, vec!["posts/clip.mp4".to_string()]);
+        assert_eq!(manager.list(10).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn enqueued_work_runs_inside_the_callers_channel_context() {
+        let manager = Arc::new(JobManager::in_memory());
+        let id = crate::channels::context::with_channel_execution_context(
+            ChannelExecutionContext::new("telegram", "42", None),
+            async {
+                manager.enqueue("render", async {
+                    tokio::task::yield_now().await;
+                    let ctx = current_channel_execution_context()
+                        .ok_or_else(|| anyhow::anyhow!("context lost"))?;
+                    Ok(JobOutcome {
+                        output: format!("{}:{}", ctx.channel, ctx.recipient),
+                        artifacts: Vec::new(),
+                    })
+                })
+            },
+        )
+        .await;
+        let record = wait_finished(&manager, &id).await;
+        assert_eq!(record.status, JobStatus::Succeeded, "{:?}", record.error);
+        assert_eq!(record.output_tail, "telegram:42");
+    }
+
+    #[tokio::test]
+    async fn enqueue_records_failure() {
+        let manager = Arc::new(JobManager::in_memory());
+        let id = manager.enqueue("render", async { anyhow::bail!("ffmpeg exploded") });
+        let record = wait_finished(&manager, &id).await;
+        assert_eq!(record.status, JobStatus::Failed);
+        assert_eq!(record.error.as_deref(), Some("ffmpeg exploded"));
+    }
+
+    #[tokio::test]
+    async fn persisted_jobs_reload_and_unfinished_are_failed() {
+        let tmp = TempDir::new().unwrap();
+        let jobs_dir = tmp.path().join("state").join("jobs");
+        let manager = Arc::new(JobManager::load(jobs_dir.clone()));
+        let id = manager.enqueue("script", async { Ok(JobOutcome::default()) });
+        wait_finished(&manager, &id).await;
+
+        let stale = JobRecord {
+            id: "stale".into(),
+            kind: "script".into(),
+            status: JobStatus::Running,
+            created_at: Utc::now().to_rfc3339(),
+            updated_at: Utc::now().to_rfc3339(),
+            output_tail: String::new(),
+            artifacts: Vec::new(),
+            error: None,
+            origin: None,
+        };
+        manager.persist(&stale).unwrap();
+
+        let reloaded = JobManager::load(jobs_dir);
+        assert_eq!(reloaded.get(&id).unwrap().status, JobStatus::Succeeded);
+        let stale = reloaded.get("stale").unwrap();
+        assert_eq!(stale.status, JobStatus::Failed);
+        assert!(stale.error.unwrap().contains("Interrupted"));
+    }
+
+    #[test]
+    fn output_tail_keeps_utf8_boundary() {
+        let long = "é".repeat(JOB_OUTPUT_TAIL_BYTES);
+        let tail = output_tail(&long);
+        assert!(tail.len() <= JOB_OUTPUT_TAIL_BYTES);
+        assert!(tail.chars().all(|c| c == 'é'));
+    }
+}
@@ -41,6 +41,7 @@ use serde::{Deserialize, Serialize};
 pub mod agent;
 pub(crate) mod approval;
 pub(crate) mod auth;
+pub mod bluesky;
 pub mod channels;
 pub mod config;
 pub(crate) mod cost;
@@ -51,14 +52,18 @@ pub mod gateway;
 pub(crate) mod health;
 pub(crate) mod heartbeat;
 pub mod hooks;
+pub mod http_client;
 pub(crate) mod identity;
 pub(crate) mod integrations;
+pub(crate) mod jobs;
 pub mod media;
+pub(crate) mod media_probe;
 pub mod memory;
 pub(crate) mod migration;
 pub(crate) mod multimodal;
 pub mod observability;
 pub(crate) mod onboard;
+pub(crate) mod post_drafts;
 pub mod providers;
 pub mod runtime;
 pub(crate) mod security;
@@ -67,8 +72,10 @@ pub(crate) mod skills;
 pub mod tools;
 pub(crate) mod util;
 pub(crate) mod workflow_assets;
+pub(crate) mod workspace;
 
 pub use config::Config;
+pub use security::credentials;
 
 pub async fn has_openai_codex_auth(profile_override: Option<&str>) -> anyhow::Result<bool> {
     let config = Config::load_or_init().await?;
@@ -135,7 +142,14 @@ pub enum ChannelCommands {
     /// Start all configured channels (handled in main.rs for async)
     Start,
     /// Run health checks for configured channels (handled in main.rs for async)
-    Doctor,
+    Doctor {
+        /// Exit with an error when any check fails
+        #[arg(long)]
+        strict: bool,
+        /// Print the results as JSON
+        #[arg(long)]
+        json: bool,
+    },
     /// Add a new channel configuration
     #[command(long_about = "\
 Add a new channel configuration.
@@ -250,6 +264,21 @@ pub enum MemoryCommands {
         #[arg(long)]
         yes: bool,
     },
+    /// Export every memory entry as JSONL (one entry per line)
+    Export {
+        /// Output file path
+        #[arg(long)]
+        out: std::path::PathBuf,
+    },
+    /// Import memory entries from a JSONL export into the configured backend
+    Import {
+        /// JSONL file produced by `memory export`
+        #[arg(long = "in")]
+        input: std::path::PathBuf,
+        /// Replace entries whose key already exists instead of skipping them
+        #[arg(long)]
+        overwrite: bool,
+    },
 }
 
 /// Integration subcommands
@@ -51,6 +51,7 @@ fn parse_temperature(s: &str) -> std::result::Result<f64, String> {
 mod agent;
 mod approval;
 mod auth;
+mod bluesky;
 mod channels;
 mod config;
 mod cost;
@@ -61,14 +62,18 @@ mod gateway;
 mod health;
 mod heartbeat;
 mod hooks;
+mod http_client;
 mod identity;
 mod integrations;
+mod jobs;
 mod media;
+mod media_probe;
 mod memory;
 mod migration;
 mod multimodal;
 mod observability;
 mod onboard;
+mod post_drafts;
 mod providers;
 mod runtime;
 mod security;
@@ -78,6 +83,7 @@ mod skills;
 mod tools;
 mod util;
 mod workflow_assets;
+mod workspace;
 
 use config::Config;
 
@@ -199,7 +205,8 @@ Examples:
   slowclaw gateway                  # use config defaults
   slowclaw gateway -p 8080          # listen on port 8080
   slowclaw gateway --host 0.0.0.0   # bind to all interfaces
-  slowclaw gateway -p 0             # random available port")]
+  slowclaw gateway -p 0             # random available port
+  slowclaw gateway --uds /run/slowclaw/gateway.sock  # Unix socket only")]
     Gateway {
         /// Port to listen on (use 0 for random available port); defaults to config gateway.port
         #[arg(short, long)]
@@ -208,6 +215,26 @@ Examples:
         /// Host to bind to; defaults to config gateway.host
         #[arg(long)]
         host: Option<String>,
+
+        /// Print one JSON line with the bound address and pairing state once
+        /// listening; human output goes to stderr (env: ZEROCLAW_GATEWAY_STARTUP_JSON)
+        #[arg(long)]
+        startup_json: bool,
+
+        /// Serve the UI without writing anything: mutating endpoints return
+        /// 403 and tools refuse writes (same as `[gateway] read_only = true`)
+        #[arg(long)]
+        read_only: bool,
+
+        /// Serve only the API: no web UI, and unknown paths get a JSON 404
+        /// (same as `[gateway] serve_ui = false`)
+        #[arg(long)]
+        no_ui: bool,
+
+        /// Listen on this Unix domain socket instead of host:port
+        /// (same as `[gateway] uds_path`)
+        #[arg(long, value_name = "PATH")]
+        uds: Option<String>,
     },
 
     /// Start long-running autonomous runtime (gateway + heartbeat)
@@ -224,7 +251,8 @@ service (systemd/launchd) for auto-start on boot.
 Examples:
   slowclaw daemon                   # use config defaults
   slowclaw daemon -p 9090           # gateway on port 9090
-  slowclaw daemon --host 127.0.0.1  # localhost only")]
+  slowclaw daemon --host 127.0.0.1  # localhost only
+  slowclaw daemon --no-ui           # API only, no web UI")]
     Daemon {
         /// Port to listen on (use 0 for random available port); defaults to config gateway.port
         #[arg(short, long)]
@@ -233,6 +261,16 @@ Examples:
         /// Host to bind to; defaults to config gateway.host
         #[arg(long)]
         host: Option<String>,
+
+        /// Serve only the API: no web UI, and unknown paths get a JSON 404
+        /// (same as `[gateway] serve_ui = false`)
+        #[arg(long)]
+        no_ui: bool,
+
+        /// Listen on this Unix domain socket instead of host:port
+        /// (same as `[gateway] uds_path`)
+        #[arg(long, value_name = "PATH")]
+        uds: Option<String>,
     },
 
     /// Manage OS service lifecycle (launchd/systemd user service)
@@ -245,8 +283,26 @@ Examples:
         service_command: ServiceCommands,
     },
 
-    /// Run diagnostics for daemon/scheduler/channel freshness
+    /// Check the whole setup in one pass without starting any server
+    #[command(long_about = "\
+Check the whole setup in one pass without starting any server.
+
+Covers config parsing and validation, the workspace skeleton, the \
+PocketBase binary and its version, whether the gateway and PocketBase \
+ports are free, the default provider's credential (one cheap API call), \
+OS keyring access, ffmpeg/ffprobe/python for the media tools, and \
+daemon freshness. Every warning or error comes with a fix-it hint.
+
+Exits non-zero when any check fails.
+
+Examples:
+  slowclaw doctor
+  slowclaw doctor --json")]
     Doctor {
+        /// Print the results as JSON
+        #[arg(long)]
+        json: bool,
+
         #[command(subcommand)]
         doctor_command: Option<DoctorCommands>,
     },
@@ -260,6 +316,38 @@ Examples:
         pair_command: PairCommands,
     },
 
+    /// Call a running gateway's HTTP API from the terminal
+    #[command(long_about = "\
+Call a running gateway's HTTP API from the terminal.
+
+The gateway URL comes from --url, ZEROCLAW_GATEWAY_URL, or the configured \
+gateway host and port. The bearer token comes from --token, \
+ZEROCLAW_GATEWAY_TOKEN, or the token saved by `gw pair`.
+
+Exit codes: 0 success, 2 usage error, 3 gateway unreachable, 4 token \
+rejected, 5 other client error, 6 gateway error, 1 anything else.
+
+Examples:
+  slowclaw gw pair 123456
+  slowclaw gw chat \"summarize today\" --thread t1
+  slowclaw gw webhook \"ping\" --json
+  slowclaw gw upload memo.m4a --kind audio
+  slowclaw gw library ls --scope journal
+  slowclaw gw download journals/media/audio/memo.m4a")]
+    Gw {
+        /// Gateway base URL
+        #[arg(long, global = true)]
+        url: Option<String>,
+        /// Bearer token
+        #[arg(long, global = true)]
+        token: Option<String>,
+        /// Print the raw JSON response instead of a summary
+        #[arg(long, global = true)]
+        json: bool,
+        #[command(subcommand)]
+        gw_command: GwCommands,
+    },
+
     /// Engage, inspect, and resume emergency-stop states.
     ///
     /// Examples:
@@ -359,6 +447,61 @@ Examples:
         memory_command: MemoryCommands,
     },
 
+    /// Journal maintenance (daily digest)
+    #[command(long_about = "\
+Journal maintenance commands.
+
+`digest` summarizes text entries and transcripts from a recent window, \
+saves the summary under journals/text/digests/, and can post it to a \
+PocketBase chat thread or mail it via [channels_config.email]. Schedule it \
+from cron for a morning digest.
+
+Examples:
+  slowclaw journal digest
+  slowclaw journal digest --since 24h --deliver pocketbase:<threadId>
+  slowclaw journal digest --deliver email:me@example.com")]
+    Journal {
+        #[command(subcommand)]
+        journal_command: JournalCommands,
+    },
+
+    /// PocketBase maintenance (collection bootstrap)
+    #[command(long_about = "\
+PocketBase maintenance commands.
+
+`init` creates the chat collection the gateway expects when it is missing, \
+adds missing fields to an existing one, and reports fields whose type \
+differs. Requires a superuser token in ZEROCLAW_POCKETBASE_ADMIN_TOKEN, \
+or superuser credentials in ZEROCLAW_POCKETBASE_IDENTITY and \
+ZEROCLAW_POCKETBASE_PASSWORD; the gateway runs the same check on startup.
+
+Examples:
+  slowclaw pocketbase init")]
+    Pocketbase {
+        #[command(subcommand)]
+        pocketbase_command: PocketbaseCommands,
+    },
+
+    /// Check or restore the workspace skeleton
+    #[command(long_about = "\
+Check or restore the workspace skeleton.
+
+`verify` lists core files and directories (IDENTITY.md, MEMORY.md, \
+sessions/, journal inboxes, ...) that are missing from the workspace \
+or have the wrong kind, and exits non-zero on drift. `repair` creates \
+the missing ones with the default contents onboarding writes and never \
+touches files that already exist. The gateway runs the same repair on \
+startup unless it is read-only.
+
+Examples:
+  slowclaw workspace verify
+  slowclaw workspace verify --json
+  slowclaw workspace repair")]
+    Workspace {
+        #[command(subcommand)]
+        workspace_command: WorkspaceCommands,
+    },
+
     /// Manage configuration
     #[command(long_about = "\
 Manage SlowClaw configuration.
@@ -411,6 +554,61 @@ enum PairCommands {
     },
 }
 
+#[derive(Subcommand, Debug)]
+enum GwCommands {
+    /// Exchange a one-time pairing code for a token and save it
+    Pair { code: String },
+    /// Send a chat message and wait for the agent's reply
+    Chat {
+        message: String,
+        /// Chat thread id
+        #[arg(long, default_value = "cli")]
+        thread: String,
+        /// Seconds to wait for the reply (0 returns right after sending)
+        #[arg(long, default_value_t = 120)]
+        wait: u64,
+    },
+    /// Send a message to /webhook and print the reply
+    Webhook {
+        message: String,
+        /// Continue a webhook session
+        #[arg(long)]
+        session: Option<String>,
+    },
+    /// Upload a media file into the journal
+    Upload {
+        file: std::path::PathBuf,
+        /// Media kind (audio, video, image); inferred from the file type when omitted
+        #[arg(long)]
+        kind: Option<String>,
+    },
+    /// Browse the library
+    Library {
+        #[command(subcommand)]
+        library_command: GwLibraryCommands,
+    },
+    /// Download a media file from the workspace
+    Download {
+        /// Workspace-relative media path
+        path: String,
+        /// Output file (default: the file name in the current directory)
+        #[arg(long)]
+        out: Option<std::path::PathBuf>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum GwLibraryCommands {
+    /// List library items
+    Ls {
+        /// Scope: all, journal, feed, ...
+        #[arg(long, default_value = "all")]
+        scope: String,
+        #[arg(long)]
+        limit: Option<usize>,
+    },
+}
+
 #[derive(Subcommand, Debug)]
 enum EstopSubcommands {
     /// Print current estop status.
@@ -515,6 +713,44 @@ enum AuthCommands {
     Status,
 }
 
+#[derive(Subcommand, Debug)]
+enum JournalCommands {
+    /// Summarize recent journal entries into a digest note
+    Digest {
+        /// Window to summarize (e.g. 24h, 90m, 7d)
+        #[arg(long, default_value = "24h")]
+        since: String,
+        /// Delivery target for the digest (pocketbase:<threadId> or email:<address>)
+        #[arg(long)]
+        deliver: Option<String>,
+        /// Maximum characters of journal text sent to the provider; oldest entries are dropped first
+        #[arg(long, default_value_t = gateway::journal_digest::DEFAULT_MAX_INPUT_CHARS)]
+        max_input_chars: usize,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum PocketbaseCommands {
+    /// Create or extend the PocketBase collections the gateway uses
+    Init,
+}
+
+#[derive(Subcommand, Debug)]
+enum WorkspaceCommands {
+    /// Report skeleton entries that are missing or have the wrong kind
+    Verify {
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Create missing skeleton entries without overwriting existing files
+    Repair {
+        /// Print what was created as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
 #[derive(Subcommand, Debug)]
 enum ModelCommands {
     /// Refresh and cache provider models
@@ -603,6 +839,19 @@ enum MemoryCommands {
         #[arg(long)]
         yes: bool,
     },
+    /// Export every memory entry as JSONL (one entry per line)
+    Export {
+        #[arg(long)]
+        out: std::path::PathBuf,
+    },
+    /// Import memory entries from a JSONL export into the configured backend
+    Import {
+        #[arg(long = "in")]
+        input: std::path::PathBuf,
+        /// Replace entries whose key already exists instead of skipping them
+        #[arg(long)]
+        overwrite: bool,
+    },
 }
 
 #[tokio::main]
@@ -632,14 +881,27 @@ async fn main() -> Result<()> {
         return Ok(());
     }
 
-    // Initialize logging - respects RUST_LOG env var, defaults to INFO
-    let subscriber = fmt::Subscriber::builder()
-        .with_env_filter(
-            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")),
+    // Initialize logging - respects RUST_LOG env var, defaults to INFO.
+    // A gateway started with --startup-json keeps stdout for its JSON line.
+    let logs_to_stderr = matches!(
+        &cli.command,
+        Commands::Gateway {
+            startup_json: true,
+            ..
+        }
+    ) || std::env::var("ZEROCLAW_GATEWAY_STARTUP_JSON")
+        .is_ok_and(|val| val == "1" || val.eq_ignore_ascii_case("true"));
+    let subscriber = fmt::Subscriber::builder().with_env_filter(
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")),
+    );
+    if logs_to_stderr {
+        tracing::subscriber::set_global_default(
+            subscriber.with_writer(std::io::stderr).finish(),
         )
-        .finish();
-
-    tracing::subscriber::set_global_default(subscriber).expect("setting default subscriber failed");
+    } else {
+        tracing::subscriber::set_global_default(subscriber.finish())
+    }
+    .expect("setting default subscriber failed");
 
     // Onboard runs quick setup by default, or the interactive wizard with --interactive.
     // The onboard wizard uses reqwest::blocking internally, which creates its own
@@ -696,7 +958,16 @@ async fn main() -> Result<()> {
     }
 
     // All other commands need config loaded first
-    let mut config = Config::load_or_init().await?;
+    let mut config = match Config::load_or_init().await {
+        Ok(config) => config,
+        Err(err) => match &cli.command {
+            Commands::Doctor {
+                json,
+                doctor_command: None,
+            } => return doctor::run_config_failure(&err, *json),
+            _ => return Err(err),
+        },
+    };
     config.apply_env_overrides();
     observability::runtime_trace::init_from_config(&config.observability, &config.workspace_dir);
     if config.security.otp.enabled {
@@ -733,10 +1004,31 @@ async fn main() -> Result<()> {
         .await
         .map(|_| ()),
 
-        Commands::Gateway { port, host } => {
+        Commands::Gateway {
+            port,
+            host,
+            startup_json,
+            read_only,
+            no_ui,
+            uds,
+        } => {
             let port = port.unwrap_or(config.gateway.port);
             let host = host.unwrap_or_else(|| config.gateway.host.clone());
-            if port == 0 {
+            if startup_json {
+                config.gateway.startup_json = true;
+            }
+            if read_only {
+                config.gateway.read_only = true;
+            }
+            if no_ui {
+                config.gateway.serve_ui = false;
+            }
+            if let Some(path) = uds {
+                config.gateway.uds_path = Some(path);
+            }
+            if let Some(path) = &config.gateway.uds_path {
+                info!("🚀 Starting SlowClaw Gateway on unix:{path}");
+            } else if port == 0 {
                 info!("🚀 Starting SlowClaw Gateway on {host} (random port)");
             } else {
                 info!("🚀 Starting SlowClaw Gateway on {host}:{port}");
@@ -744,10 +1036,23 @@ async fn main() -> Result<()> {
             gateway::run_gateway(&host, port, config).await
         }
 
-        Commands::Daemon { port, host } => {
+        Commands::Daemon {
+            port,
+            host,
+            no_ui,
+            uds,
+        } => {
             let port = port.unwrap_or(config.gateway.port);
             let host = host.unwrap_or_else(|| config.gateway.host.clone());
-            if port == 0 {
+            if no_ui {
+                config.gateway.serve_ui = false;
+            }
+            if let Some(path) = uds {
+                config.gateway.uds_path = Some(path);
+            }
+            if let Some(path) = &config.gateway.uds_path {
+                info!("🧠 Starting SlowClaw Daemon with the gateway on unix:{path}");
+            } else if port == 0 {
                 info!("🧠 Starting SlowClaw Daemon on {host} (random port)");
             } else {
                 info!("🧠 Starting SlowClaw Daemon on {host}:{port}");
@@ -840,6 +1145,20 @@ async fn main() -> Result<()> {
 
         Commands::Pair { pair_command } => handle_pair_command(pair_command, &config).await,
 
+        Commands::Gw {
+            url,
+            token,
+            json,
+            gw_command,
+        } => {
+            let client = gateway::client::GatewayClient::from_config(&config, url, token);
+            if let Err(err) = handle_gw_command(gw_command, &client, &config, json).await {
+                eprintln!("Error: {err}");
+                std::process::exit(err.exit_code());
+            }
+            Ok(())
+        }
+
         Commands::Estop {
             estop_command,
             level,
@@ -910,7 +1229,10 @@ async fn main() -> Result<()> {
             service::handle_command(&service_command, &config, init_system)
         }
 
-        Commands::Doctor { doctor_command } => match doctor_command {
+        Commands::Doctor {
+            json,
+            doctor_command,
+        } => match doctor_command {
             Some(DoctorCommands::Models {
                 provider,
                 use_cache,
@@ -927,12 +1249,14 @@ async fn main() -> Result<()> {
                 contains.as_deref(),
                 limit,
             ),
-            None => doctor::run(&config),
+            None => doctor::run(&config, json).await,
         },
 
         Commands::Channel { channel_command } => match channel_command {
             ChannelCommands::Start => channels::start_channels(config).await,
-            ChannelCommands::Doctor => channels::doctor_channels(config).await,
+            ChannelCommands::Doctor { strict, json } => {
+                Box::pin(channels::doctor_channels(config, strict, json)).await
+            }
             other => channels::handle_command(other, &config).await,
         },
 
@@ -952,6 +1276,18 @@ async fn main() -> Result<()> {
 
         Commands::Auth { auth_command } => handle_auth_command(auth_command, &config).await,
 
+        Commands::Journal { journal_command } => {
+            handle_journal_command(journal_command, &config).await
+        }
+
+        Commands::Pocketbase { pocketbase_command } => {
+            handle_pocketbase_command(pocketbase_command).await
+        }
+
+        Commands::Workspace { workspace_command } => {
+            handle_workspace_command(workspace_command, &config)
+        }
+
         Commands::Config { config_command } => match config_command {
             ConfigCommands::Schema => {
                 let schema = schemars::schema_for!(config::Config);
@@ -965,6 +1301,131 @@ async fn main() -> Result<()> {
     }
 }
 
+async fn handle_journal_command(journal_command: JournalCommands, config: &Config) -> Result<()> {
+    use gateway::journal_digest;
+
+    match journal_command {
+        JournalCommands::Digest {
+            since,
+            deliver,
+            max_input_chars,
+        } => {
+            let window = journal_digest::parse_since(&since)?;
+            let target = deliver
+                .as_deref()
+                .map(journal_digest::parse_delivery)
+                .transpose()?;
+            let outcome =
+                journal_digest::run_digest(config, window, max_input_chars, target.as_ref())
+                    .await?;
+            let Some(path) = outcome.path else {
+                println!("No journal entries in the last {since}; skipping digest.");
+                return Ok(());
+            };
+            println!(
+                "📝 Journal digest written to {path} ({} entries{})",
+                outcome.entries,
+                if outcome.dropped > 0 {
+                    format!(", {} older omitted", outcome.dropped)
+                } else {
+                    String::new()
+                }
+            );
+            if outcome.delivered {
+                println!("   Delivered to {}", deliver.unwrap_or_default());
+            }
+            Ok(())
+        }
+    }
+}
+
+async fn handle_pocketbase_command(pocketbase_command: PocketbaseCommands) -> Result<()> {
+    use channels::pocketbase_schema::{self, SchemaStatus};
+
+    match pocketbase_command {
+        PocketbaseCommands::Init => {
+            let report = pocketbase_schema::bootstrap_from_env().await;
+            for name in &report.created {
+                println!("✅ Created collection {name}");
+            }
+            for field in &report.added_fields {
+                println!("✅ Added field {field}");
+            }
+            for problem in &report.problems {
+                println!("⚠️  {problem}");
+            }
+            match report.status {
+                SchemaStatus::Skipped => bail!(
+                    "Set {} to a PocketBase superuser token and retry",
+                    pocketbase_schema::ADMIN_TOKEN_ENV
+                ),
+                SchemaStatus::Ok => {
+                    println!("PocketBase schema is up to date.");
+                    Ok(())
+                }
+                SchemaStatus::Drift => {
+                    bail!("PocketBase schema has fields that need manual repair")
+                }
+                SchemaStatus::Error => bail!("PocketBase schema bootstrap failed"),
+            }
+        }
+    }
+}
+
+fn handle_workspace_command(workspace_command: WorkspaceCommands, config: &Config) -> Result<()> {
+    let ws = &config.workspace_dir;
+    match workspace_command {
+        WorkspaceCommands::Verify { json } => {
+            let report = workspace::verify_skeleton(ws);
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                for rel in &report.missing {
+                    println!("❌ Missing {rel}");
+                }
+                for rel in &report.wrong_kind {
+                    println!("⚠️  {rel} exists but is the wrong kind of entry");
+                }
+            }
+            if !report.is_clean() {
+                bail!("Workspace skeleton drifted; run `slowclaw workspace repair`");
+            }
+            if !json {
+                println!("Workspace skeleton at {} is complete.", ws.display());
+            }
+            Ok(())
+        }
+        WorkspaceCommands::Repair { json } => {
+            let created = workspace::repair_skeleton(ws)
+                .with_context(|| format!("Failed to repair workspace at {}", ws.display()))?;
+            let report = workspace::verify_skeleton(ws);
+            if json {
+                println!(
+                    "{}",
+                    serde_json::json!({ "created": created, "wrongKind": report.wrong_kind })
+                );
+            } else {
+                for rel in &created {
+                    println!("✅ Created {rel}");
+                }
+                for rel in &report.wrong_kind {
+                    println!("⚠️  {rel} exists but is the wrong kind of entry");
+                }
+            }
+            if !report.is_clean() {
+                bail!("Workspace entries need manual repair");
+            }
+            if !json && created.is_empty() {
+                println!(
+                    "Workspace skeleton at {} is already complete.",
+                    ws.display()
+                );
+            }
+            Ok(())
+        }
+    }
+}
+
 async fn handle_pair_command(pair_command: PairCommands, config: &Config) -> Result<()> {
     match pair_command {
         PairCommands::NewCode { gateway_url, token } => {
@@ -986,7 +1447,7 @@ async fn handle_pair_command(pair_command: PairCommands, config: &Config) -> Res
                 })?;
 
             let url = format!("{}/pair/new-code", base_url.trim_end_matches('/'));
-            let client = reqwest::Client::new();
+            let client = http_client::shared();
             let response = client
                 .post(&url)
                 .bearer_auth(&token)
@@ -1019,6 +1480,123 @@ async fn handle_pair_command(pair_command: PairCommands, config: &Config) -> Res
     }
 }
 
+async fn handle_gw_command(
+    gw_command: GwCommands,
+    client: &gateway::client::GatewayClient,
+    config: &Config,
+    json: bool,
+) -> gateway::client::ClientResult<()> {
+    let print_json = |value: &serde_json::Value| {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(value).unwrap_or_else(|_| value.to_string())
+        );
+    };
+    let text = |value: &serde_json::Value, key: &str| {
+        value
+            .get(key)
+            .and_then(serde_json::Value::as_str)
+            .unwrap_or_default()
+            .to_string()
+    };
+
+    match gw_command {
+        GwCommands::Pair { code } => {
+            let token = client.pair(&code).await?;
+            let path = gateway::client::save_token(config, &token)?;
+            if json {
+                print_json(&serde_json::json!({ "paired": true, "saved_to": path }));
+            } else {
+                println!("✅ Paired with {}", client.base_url());
+                println!("   Token saved to {}", path.display());
+            }
+        }
+        GwCommands::Chat {
+            message,
+            thread,
+            wait,
+        } => {
+            let record = client.chat_send(&thread, &message).await?;
+            let id = text(&record, "id");
+            let reply = if wait == 0 || id.is_empty() {
+                None
+            } else {
+                client
+                    .chat_wait_reply(&thread, &id, std::time::Duration::from_secs(wait))
+                    .await?
+            };
+            if json {
+                print_json(&serde_json::json!({ "message": record, "reply": reply }));
+            } else if let Some(reply) = &reply {
+                if text(reply, "status") == "error" {
+                    println!("⚠️  {}", text(reply, "error"));
+                } else {
+                    println!("{}", text(reply, "content"));
+                }
+            } else if wait > 0 {
+                println!("Sent {id} to thread {thread}; no reply within {wait}s.");
+            } else {
+                println!("Sent {id} to thread {thread}.");
+            }
+        }
+        GwCommands::Webhook { message, session } => {
+            let body = client.webhook(&message, session.as_deref()).await?;
+            if json {
+                print_json(&body);
+            } else {
+                println!("{}", text(&body, "response"));
+            }
+        }
+        GwCommands::Upload { file, kind } => {
+            let body = client.upload(&file, kind.as_deref()).await?;
+            if json {
+                print_json(&body);
+            } else {
+                println!(
+                    "✅ Uploaded {} ({} bytes, {})",
+                    text(&body, "path"),
+                    body.get("bytes").and_then(serde_json::Value::as_u64).unwrap_or(0),
+                    text(&body, "kind")
+                );
+            }
+        }
+        GwCommands::Library {
+            library_command: GwLibraryCommands::Ls { scope, limit },
+        } => {
+            let body = client.library_items(&scope, limit).await?;
+            if json {
+                print_json(&body);
+            } else {
+                let items = body
+                    .get("items")
+                    .and_then(serde_json::Value::as_array)
+                    .cloned()
+                    .unwrap_or_default();
+                for item in &items {
+                    println!("{:6}  {}", text(item, "kind"), text(item, "path"));
+                }
+                println!("{} item(s)", items.len());
+            }
+        }
+        GwCommands::Download { path, out } => {
+            let out = out.unwrap_or_else(|| {
+                std::path::PathBuf::from(
+                    std::path::Path::new(&path)
+                        .file_name()
+                        .unwrap_or_else(|| std::ffi::OsStr::new("download")),
+                )
+            });
+            let written = client.download_media(&path, &out).await?;
+            if json {
+                print_json(&serde_json::json!({ "path": out, "bytes": written }));
+            } else {
+                println!("✅ Saved {} ({written} bytes)", out.display());
+            }
+        }
+    }
+    Ok(())
+}
+
 fn handle_estop_command(
     config: &Config,
     estop_command: Option<EstopSubcommands>,
@@ -1,3 +1,4 @@
+use crate::channels::context::channel_context_env;
 use crate::config::TranscriptionConfig;
 use crate::util::truncate_with_ellipsis;
 use anyhow::{Context, Result};
@@ -209,6 +210,7 @@ impl CommandContentMediaBackend {
             Command::new(program)
                 .args(args)
                 .current_dir(&self.workspace_dir)
+                .envs(channel_context_env())
                 .stdin(Stdio::null())
                 .stdout(Stdio::piped())
                 .stderr(Stdio::piped())
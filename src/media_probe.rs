@@ -0,0 +1,252 @@
+//! ffprobe-backed metadata extraction for uploaded audio/video.
+//!
+//! Probing is best-effort: when ffprobe is missing, disabled, slow, or fails,
+//! callers get `None` and carry on without the extra fields. Results are
+//! cached next to the media file as `<file>.meta.json` so library listings
+//! never need to spawn ffprobe.
+
+use crate::config::MediaProbeConfig;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::{Duration, UNIX_EPOCH};
+use tokio::process::Command;
+
+pub const META_SIDECAR_SUFFIX: &str = ".meta.json";
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MediaProbe {
+    pub duration_ms: Option<u64>,
+    pub sample_rate: Option<u32>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub codec: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MetaSidecar {
+    source_size_bytes: u64,
+    source_modified_at: u64,
+    #[serde(flatten)]
+    probe: MediaProbe,
+}
+
+#[derive(Deserialize)]
+struct FfprobeOutput {
+    #[serde(default)]
+    streams: Vec<FfprobeStream>,
+    format: Option<FfprobeFormat>,
+}
+
+#[derive(Deserialize)]
+struct FfprobeStream {
+    codec_type: Option<String>,
+    codec_name: Option<String>,
+    sample_rate: Option<String>,
+    width: Option<u32>,
+    height: Option<u32>,
+    duration: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct FfprobeFormat {
+    duration: Option<String>,
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn seconds_to_ms(raw: Option<&str>) -> Option<u64> {
+    let seconds: f64 = raw?.trim().parse().ok()?;
+    (seconds.is_finite() && seconds >= 0.0).then(|| (seconds * 1000.0).round() as u64)
+}
+
+/// Reduce `ffprobe -print_format json -show_format -show_streams` output to
+/// the fields the library UI cares about. Video streams win for codec.
+pub fn parse_ffprobe_json(raw: &str) -> Result<MediaProbe> {
+    let parsed: FfprobeOutput =
+        serde_json::from_str(raw).context("failed to parse ffprobe output")?;
+    let stream_of = |kind: &str| {
+        parsed
+            .streams
+            .iter()
+            .find(|s| s.codec_type.as_deref() == Some(kind))
+    };
+    let video = stream_of("video");
+    let audio = stream_of("audio");
+    let duration_ms = seconds_to_ms(parsed.format.as_ref().and_then(|f| f.duration.as_deref()))
+        .or_else(|| seconds_to_ms(video.or(audio).and_then(|s| s.duration.as_deref())));
+
+    Ok(MediaProbe {
+        duration_ms,
+        sample_rate: audio
+            .and_then(|s| s.sample_rate.as_deref())
+            .and_then(|rate| rate.trim().parse().ok()),
+        width: video.and_then(|s| s.width),
+        height: video.and_then(|s| s.height),
+        codec: video
+            .or(audio)
+            .and_then(|s| s.codec_name.clone())
+            .filter(|c| !c.trim().is_empty()),
+    })
+}
+
+fn sidecar_path(media_path: &Path) -> PathBuf {
+    let mut path = media_path.as_os_str().to_os_string();
+    path.push(META_SIDECAR_SUFFIX);
+    PathBuf::from(path)
+}
+
+fn file_fingerprint(media_path: &Path) -> Option<(u64, u64)> {
+    let meta = std::fs::metadata(media_path).ok()?;
+    let modified = meta
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    Some((meta.len(), modified))
+}
+
+/// Read a cached probe, ignoring sidecars written for an older version of the file.
+pub fn read_cached_probe(media_path: &Path) -> Option<MediaProbe> {
+    let raw = std::fs::read_to_string(sidecar_path(media_path)).ok()?;
+    let sidecar: MetaSidecar = serde_json::from_str(&raw).ok()?;
+    let (size, modified) = file_fingerprint(media_path)?;
+    (sidecar.source_size_bytes == size && sidecar.source_modified_at == modified)
+        .then_some(sidecar.probe)
+}
+
+fn write_cached_probe(media_path: &Path, probe: &MediaProbe) -> Result<()> {
+    let (source_size_bytes, source_modified_at) =
+        file_fingerprint(media_path).context("media file disappeared before caching probe")?;
+    let sidecar = MetaSidecar {
+        source_size_bytes,
+        source_modified_at,
+        probe: probe.clone(),
+    };
+    std::fs::write(
+        sidecar_path(media_path),
+        serde_json::to_string_pretty(&sidecar)?,
+    )
+    .context("failed to write media meta sidecar")
+}
+
+async fn run_ffprobe(config: &MediaProbeConfig, media_path: &Path) -> Result<MediaProbe> {
+    let output = tokio::time::timeout(
+        Duration::from_secs(config.timeout_secs.max(1)),
+        Command::new(config.ffprobe_path.trim())
+            .args(["-v", "error", "-print_format", "json"])
+            .args(["-show_format", "-show_streams"])
+            .arg(media_path)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .output(),
+    )
+    .await
+    .context("ffprobe timed out")?
+    .context("failed to execute ffprobe")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "ffprobe failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    parse_ffprobe_json(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Probe `media_path` and cache the result in its `.meta.json` sidecar.
+/// Returns `None` (after logging) whenever probing is unavailable or fails.
+pub async fn probe_and_cache(config: &MediaProbeConfig, media_path: &Path) -> Option<MediaProbe> {
+    if !config.enabled {
+        return None;
+    }
+    if let Some(cached) = read_cached_probe(media_path) {
+        return Some(cached);
+    }
+    if which::which(config.ffprobe_path.trim()).is_err() {
+        tracing::debug!(
+            "ffprobe not found at '{}'; skipping media probe",
+            config.ffprobe_path
+        );
+        return None;
+    }
+    match run_ffprobe(config, media_path).await {
+        Ok(probe) => {
+            if let Err(err) = write_cached_probe(media_path, &probe) {
+                tracing::warn!("Media probe cache write failed: {err:#}");
+            }
+            Some(probe)
+        }
+        Err(err) => {
+            tracing::warn!("Media probe failed for {}: {err:#}", media_path.display());
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VIDEO_JSON: &str = r#"{
+        "streams": [
+            {"codec_type": "video", "codec_name": "h264", "width": 1920, "height": 1080, "duration": "12.480000"},
+            {"codec_type": "audio", "codec_name": "aac", "sample_rate": "48000"}
+        ],
+        "format": {"duration": "12.512000"}
+    }"#;
+
+    #[test]
+    fn parses_video_with_audio_track() {
+        let probe = parse_ffprobe_json(VIDEO_JSON).unwrap();
+        assert_eq!(probe.duration_ms, Some(12_512));
+        assert_eq!(probe.sample_rate, Some(48_000));
+        assert_eq!((probe.width, probe.height), (Some(1920), Some(1080)));
+        assert_eq!(probe.codec.as_deref(), Some("h264"));
+    }
+
+    #[test]
+    fn parses_audio_only_and_falls_back_to_stream_duration() {
+        let probe = parse_ffprobe_json(
+            r#"{"streams": [{"codec_type": "audio", "codec_name": "opus", "sample_rate": "16000", "duration": "3.5"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(probe.duration_ms, Some(3_500));
+        assert_eq!(probe.sample_rate, Some(16_000));
+        assert_eq!(probe.width, None);
+        assert_eq!(probe.codec.as_deref(), Some("opus"));
+    }
+
+    #[test]
+    fn cached_probe_is_invalidated_when_file_changes() {
+        let tmp = tempfile::tempdir().unwrap();
+        let media = tmp.path().join("clip.m4a");
+        std::fs::write(&media, b"abc").unwrap();
+        let probe = MediaProbe {
+            duration_ms: Some(1_000),
+            ..MediaProbe::default()
+        };
+        write_cached_probe(&media, &probe).unwrap();
+        assert_eq!(read_cached_probe(&media), Some(probe));
+
+        std::fs::write(&media, b"abcdef").unwrap();
+        assert_eq!(read_cached_probe(&media), None);
+    }
+
+    #[tokio::test]
+    async fn missing_ffprobe_degrades_to_none() {
+        let tmp = tempfile::tempdir().unwrap();
+        let media = tmp.path().join("clip.mp4");
+        std::fs::write(&media, b"not really a video").unwrap();
+        let config = MediaProbeConfig {
+            ffprobe_path: "definitely-not-ffprobe-binary".into(),
+            ..MediaProbeConfig::default()
+        };
+        assert_eq!(probe_and_cache(&config, &media).await, None);
+    }
+}
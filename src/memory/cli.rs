@@ -1,13 +1,12 @@
 use super::traits::{Memory, MemoryCategory};
 use super::{
-    classify_memory_backend, create_memory_for_migration, effective_memory_backend_name,
-    MemoryBackendKind,
+    classify_memory_backend, create_memory_for_migration, create_memory_with_storage,
+    effective_memory_backend_name, MemoryBackendKind,
 };
 use crate::config::Config;
-#[cfg(feature = "memory-postgres")]
-use anyhow::Context;
-use anyhow::{bail, Result};
+use anyhow::{bail, Context, Result};
 use console::style;
+use std::path::Path;
 
 /// Handle `zeroclaw memory <subcommand>` CLI commands.
 pub async fn handle_command(command: crate::MemoryCommands, config: &Config) -> Result<()> {
@@ -23,12 +22,16 @@ pub async fn handle_command(command: crate::MemoryCommands, config: &Config) ->
         crate::MemoryCommands::Clear { key, category, yes } => {
             handle_clear(config, key, category, yes).await
         }
+        crate::MemoryCommands::Export { out } => handle_export(config, &out).await,
+        crate::MemoryCommands::Import { input, overwrite } => {
+            handle_import(config, &input, overwrite).await
+        }
     }
 }
 
 /// Create a lightweight memory backend for CLI management operations.
 ///
-/// CLI commands (list/get/stats/clear) never use vector search, so we skip
+/// CLI commands (list/get/stats/clear/export) never use vector search, so we skip
 /// embedding provider initialisation for local backends by using the
 /// migration factory.  Postgres still needs its full connection config.
 fn create_cli_memory(config: &Config) -> Result<Box<dyn Memory>> {
@@ -297,7 +300,54 @@ async fn handle_clear_key(mem: &dyn Memory, key: &str, yes: bool) -> Result<()>
     Ok(())
 }
 
-fn parse_category(s: &str) -> MemoryCategory {
+async fn handle_export(config: &Config, out: &Path) -> Result<()> {
+    let mem = create_cli_memory(config)?;
+    let file = std::fs::File::create(out)
+        .with_context(|| format!("failed to create {}", out.display()))?;
+    let mut writer = std::io::BufWriter::new(file);
+    let count = super::transfer::export_jsonl(&*mem, &mut writer).await?;
+
+    println!(
+        "{} Exported {count} entries from {} to {}",
+        style("✓").green().bold(),
+        mem.name(),
+        out.display(),
+    );
+    Ok(())
+}
+
+/// Import into the full runtime backend (with embeddings), not the CLI one.
+async fn handle_import(config: &Config, input: &Path, overwrite: bool) -> Result<()> {
+    let mem = create_memory_with_storage(
+        &config.memory,
+        Some(&config.storage.provider.config),
+        &config.workspace_dir,
+        config.api_key.as_deref(),
+    )?;
+    let file = tokio::fs::File::open(input)
+        .await
+        .with_context(|| format!("failed to open {}", input.display()))?;
+    let summary =
+        super::transfer::import_jsonl(&*mem, tokio::io::BufReader::new(file), overwrite).await?;
+
+    println!(
+        "{} Imported into {}: {} inserted, {} skipped, {} failed",
+        style("✓").green().bold(),
+        mem.name(),
+        summary.inserted,
+        summary.skipped,
+        summary.failed,
+    );
+    for error in &summary.errors {
+        println!("    {error}");
+    }
+    if summary.failed > summary.errors.len() {
+        println!("    ... and {} more", summary.failed - summary.errors.len());
+    }
+    Ok(())
+}
+
+pub(crate) fn parse_category(s: &str) -> MemoryCategory {
     match s.trim().to_ascii_lowercase().as_str() {
         "core" => MemoryCategory::Core,
         "daily" => MemoryCategory::Daily,
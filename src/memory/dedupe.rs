@@ -0,0 +1,244 @@
+//! Store-time deduplication for autosaved conversation memories.
+//!
+//! [`DedupeMemory`] wraps any backend and skips storing a Conversation entry
+//! when an entry with the same normalized content was stored within the
+//! configured window. Candidates come from the backend's own `recall`, so the
+//! check stays cheap on large stores.
+
+use super::traits::{Memory, MemoryCategory, MemoryEntry};
+use crate::config::MemoryDedupeMode;
+use crate::observability::{Observer, ObserverEvent};
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use parking_lot::RwLock;
+use std::sync::{Arc, OnceLock};
+
+/// Recall candidates inspected per store.
+const DEDUPE_CANDIDATES: usize = 5;
+
+static DEDUPE_OBSERVER: OnceLock<RwLock<Option<Arc<dyn Observer>>>> = OnceLock::new();
+
+/// Route skipped-duplicate events to `observer`.
+///
+/// Memory backends are built before the runtime observer exists, so long-running
+/// entry points register it here once it is available.
+pub fn set_dedupe_observer(observer: Arc<dyn Observer>) {
+    *DEDUPE_OBSERVER.get_or_init(|| RwLock::new(None)).write() = Some(observer);
+}
+
+fn record_skipped(category: &MemoryCategory) {
+    let observer = DEDUPE_OBSERVER
+        .get()
+        .and_then(|slot| slot.read().as_ref().map(Arc::clone));
+    if let Some(observer) = observer {
+        observer.record_event(&ObserverEvent::MemoryDedupeSkipped {
+            category: category.to_string(),
+        });
+    }
+}
+
+/// Trim, lowercase, and collapse internal whitespace.
+pub fn normalize_content(content: &str) -> String {
+    content
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+pub struct DedupeMemory {
+    inner: Box<dyn Memory>,
+    mode: MemoryDedupeMode,
+    window: Duration,
+}
+
+impl DedupeMemory {
+    pub fn new(inner: Box<dyn Memory>, mode: MemoryDedupeMode, window_minutes: u32) -> Self {
+        Self {
+            inner,
+            mode,
+            window: Duration::minutes(i64::from(window_minutes)),
+        }
+    }
+
+    async fn find_duplicate(&self, content: &str) -> anyhow::Result<Option<MemoryEntry>> {
+        let normalized = normalize_content(content);
+        if normalized.is_empty() {
+            return Ok(None);
+        }
+        let cutoff = Utc::now() - self.window;
+        let candidates = self.inner.recall(content, DEDUPE_CANDIDATES, None).await?;
+        Ok(candidates.into_iter().find(|entry| {
+            entry.category == MemoryCategory::Conversation
+                && match self.mode {
+                    MemoryDedupeMode::Off => false,
+                    MemoryDedupeMode::Exact => normalize_content(&entry.content) == normalized,
+                }
+                && DateTime::parse_from_rfc3339(&entry.timestamp)
+                    .is_ok_and(|ts| ts.with_timezone(&Utc) >= cutoff)
+        }))
+    }
+}
+
+#[async_trait]
+impl Memory for DedupeMemory {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    async fn store(
+        &self,
+        key: &str,
+        content: &str,
+        category: MemoryCategory,
+        session_id: Option<&str>,
+    ) -> anyhow::Result<()> {
+        if category == MemoryCategory::Conversation && self.mode != MemoryDedupeMode::Off {
+            match self.find_duplicate(content).await {
+                Ok(Some(existing)) if existing.key != key => {
+                    tracing::debug!(key, existing = %existing.key, "Skipping duplicate memory entry");
+                    record_skipped(&category);
+                    return Ok(());
+                }
+                Ok(_) => {}
+                Err(e) => tracing::warn!("memory dedupe check failed, storing anyway: {e}"),
+            }
+        }
+        self.inner.store(key, content, category, session_id).await
+    }
+
+    async fn recall(
+        &self,
+        query: &str,
+        limit: usize,
+        session_id: Option<&str>,
+    ) -> anyhow::Result<Vec<MemoryEntry>> {
+        self.inner.recall(query, limit, session_id).await
+    }
+
+    async fn get(&self, key: &str) -> anyhow::Result<Option<MemoryEntry>> {
+        self.inner.get(key).await
+    }
+
+    async fn list(
+        &self,
+        category: Option<&MemoryCategory>,
+        session_id: Option<&str>,
+    ) -> anyhow::Result<Vec<MemoryEntry>> {
+        self.inner.list(category, session_id).await
+    }
+
+    async fn list_all(
+        &self,
+        category: Option<&MemoryCategory>,
+        session_id: Option<&str>,
+    ) -> anyhow::Result<Vec<MemoryEntry>> {
+        self.inner.list_all(category, session_id).await
+    }
+
+    async fn forget(&self, key: &str) -> anyhow::Result<bool> {
+        self.inner.forget(key).await
+    }
+
+    async fn count(&self) -> anyhow::Result<usize> {
+        self.inner.count().await
+    }
+
+    async fn health_check(&self) -> bool {
+        self.inner.health_check().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::SqliteMemory;
+    use tempfile::TempDir;
+
+    fn dedupe_sqlite(tmp: &TempDir, window_minutes: u32) -> DedupeMemory {
+        DedupeMemory::new(
+            Box::new(SqliteMemory::new(tmp.path()).unwrap()),
+            MemoryDedupeMode::Exact,
+            window_minutes,
+        )
+    }
+
+    #[test]
+    fn normalize_content_trims_lowercases_and_collapses_whitespace() {
+        assert_eq!(
+            normalize_content("  Remind me\tto   DRINK\nwater "),
+            "remind me to drink water"
+        );
+    }
+
+    #[tokio::test]
+    async fn skips_near_identical_conversation_entries_within_window() {
+        let tmp = TempDir::new().unwrap();
+        let mem = dedupe_sqlite(&tmp, 60);
+
+        mem.store(
+            "msg_1",
+            "Remind me to drink water",
+            MemoryCategory::Conversation,
+            None,
+        )
+        .await
+        .unwrap();
+        mem.store(
+            "msg_2",
+            "  remind me to   DRINK water",
+            MemoryCategory::Conversation,
+            Some("other-session"),
+        )
+        .await
+        .unwrap();
+        mem.store(
+            "msg_3",
+            "Remind me to stretch",
+            MemoryCategory::Conversation,
+            None,
+        )
+        .await
+        .unwrap();
+        // Only conversation entries are deduplicated.
+        mem.store(
+            "fact",
+            "Remind me to drink water",
+            MemoryCategory::Core,
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert!(mem.get("msg_2").await.unwrap().is_none());
+        assert!(mem.get("msg_3").await.unwrap().is_some());
+        assert_eq!(mem.count().await.unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn stores_duplicates_outside_window_or_when_off() {
+        let tmp = TempDir::new().unwrap();
+        let mem = dedupe_sqlite(&tmp, 0);
+        mem.store("msg_1", "drink water", MemoryCategory::Conversation, None)
+            .await
+            .unwrap();
+        mem.store("msg_2", "drink water", MemoryCategory::Conversation, None)
+            .await
+            .unwrap();
+        assert_eq!(mem.count().await.unwrap(), 2);
+
+        let tmp = TempDir::new().unwrap();
+        let mem = DedupeMemory::new(
+            Box::new(SqliteMemory::new(tmp.path()).unwrap()),
+            MemoryDedupeMode::Off,
+            60,
+        );
+        mem.store("msg_1", "drink water", MemoryCategory::Conversation, None)
+            .await
+            .unwrap();
+        mem.store("msg_2", "drink water", MemoryCategory::Conversation, None)
+            .await
+            .unwrap();
+        assert_eq!(mem.count().await.unwrap(), 2);
+    }
+}
@@ -371,6 +371,14 @@ impl Memory for LucidMemory {
         self.local.list(category, session_id).await
     }
 
+    async fn list_all(
+        &self,
+        category: Option<&MemoryCategory>,
+        session_id: Option<&str>,
+    ) -> anyhow::Result<Vec<MemoryEntry>> {
+        self.local.list_all(category, session_id).await
+    }
+
     async fn forget(&self, key: &str) -> anyhow::Result<bool> {
         self.local.forget(key).await
     }
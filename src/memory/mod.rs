@@ -1,6 +1,7 @@
 pub mod backend;
 pub mod chunker;
 pub mod cli;
+pub mod dedupe;
 pub mod embeddings;
 pub mod hygiene;
 pub mod lucid;
@@ -10,9 +11,11 @@ pub mod none;
 pub mod postgres;
 pub mod qdrant;
 pub mod response_cache;
+pub mod retention;
 pub mod snapshot;
 pub mod sqlite;
 pub mod traits;
+pub mod transfer;
 pub mod vector;
 
 #[allow(unused_imports)]
@@ -32,7 +35,9 @@ pub use traits::Memory;
 #[allow(unused_imports)]
 pub use traits::{MemoryCategory, MemoryEntry};
 
-use crate::config::{Config, EmbeddingRouteConfig, MemoryConfig, StorageProviderConfig};
+use crate::config::{
+    Config, EmbeddingRouteConfig, MemoryConfig, MemoryDedupeMode, StorageProviderConfig,
+};
 use anyhow::Context;
 use std::path::Path;
 use std::sync::Arc;
@@ -354,12 +359,15 @@ pub fn create_memory_with_storage_and_routes(
             url,
             collection
         );
-        return Ok(Box::new(QdrantMemory::new_lazy(
-            &url,
-            &collection,
-            qdrant_api_key,
-            embedder,
-        )));
+        return Ok(with_dedupe(
+            config,
+            Box::new(QdrantMemory::new_lazy(
+                &url,
+                &collection,
+                qdrant_api_key,
+                embedder,
+            )),
+        ));
     }
 
     create_memory_with_builders(
@@ -369,6 +377,19 @@ pub fn create_memory_with_storage_and_routes(
         || build_postgres_memory(storage_provider),
         "",
     )
+    .map(|memory| with_dedupe(config, memory))
+}
+
+/// Wrap `memory` in the store-time dedupe layer when `[memory] dedupe_mode` is set.
+fn with_dedupe(config: &MemoryConfig, memory: Box<dyn Memory>) -> Box<dyn Memory> {
+    if config.dedupe_mode == MemoryDedupeMode::Off {
+        return memory;
+    }
+    Box::new(dedupe::DedupeMemory::new(
+        memory,
+        config.dedupe_mode,
+        config.dedupe_window_minutes,
+    ))
 }
 
 pub fn create_memory_for_migration(
@@ -495,6 +516,25 @@ mod tests {
         assert_eq!(mem.name(), "markdown");
     }
 
+    #[tokio::test]
+    async fn factory_applies_dedupe_layer_when_enabled() {
+        let tmp = TempDir::new().unwrap();
+        let cfg = MemoryConfig {
+            backend: "sqlite".into(),
+            embedding_provider: "none".into(),
+            dedupe_mode: MemoryDedupeMode::Exact,
+            ..MemoryConfig::default()
+        };
+        let mem = create_memory(&cfg, tmp.path(), None).unwrap();
+        assert_eq!(mem.name(), "sqlite");
+        for key in ["msg_1", "msg_2"] {
+            mem.store(key, "Drink water", MemoryCategory::Conversation, None)
+                .await
+                .unwrap();
+        }
+        assert_eq!(mem.count().await.unwrap(), 1);
+    }
+
     #[test]
     fn migration_factory_lucid() {
         let tmp = TempDir::new().unwrap();
@@ -8,6 +8,9 @@ use std::sync::Arc;
 use tokio::sync::OnceCell;
 use uuid::Uuid;
 
+/// Points per scroll request; `list` returns at most one page.
+const SCROLL_PAGE_SIZE: usize = 1000;
+
 /// Qdrant vector database memory backend.
 ///
 /// Uses Qdrant's REST API for vector storage and semantic search.
@@ -169,6 +172,90 @@ impl QdrantMemory {
         }
     }
 
+    /// Scroll through points matching the filters, one page of
+    /// `SCROLL_PAGE_SIZE` unless `all` asks for every page.
+    async fn scroll(
+        &self,
+        category: Option<&MemoryCategory>,
+        session_id: Option<&str>,
+        all: bool,
+    ) -> Result<Vec<MemoryEntry>> {
+        self.ensure_initialized().await?;
+
+        // Build filter conditions
+        let mut must_conditions = Vec::new();
+
+        if let Some(cat) = category {
+            must_conditions.push(serde_json::json!({
+                "key": "category",
+                "match": { "value": Self::category_to_str(cat) }
+            }));
+        }
+
+        if let Some(sid) = session_id {
+            must_conditions.push(serde_json::json!({
+                "key": "session_id",
+                "match": { "value": sid }
+            }));
+        }
+
+        let mut scroll_body = serde_json::json!({
+            "limit": SCROLL_PAGE_SIZE,
+            "with_payload": true
+        });
+
+        if !must_conditions.is_empty() {
+            scroll_body["filter"] = serde_json::json!({ "must": must_conditions });
+        }
+
+        let mut entries = Vec::new();
+        loop {
+            let resp = self
+                .request(
+                    reqwest::Method::POST,
+                    &format!("/collections/{}/points/scroll", self.collection),
+                )
+                .json(&scroll_body)
+                .send()
+                .await
+                .context("failed to scroll Qdrant")?;
+
+            if !resp.status().is_success() {
+                let status = resp.status();
+                let text = resp.text().await.unwrap_or_default();
+                anyhow::bail!("Qdrant scroll failed ({status}): {text}");
+            }
+
+            let result: QdrantScrollResult = resp.json().await?;
+
+            entries.extend(result.result.points.into_iter().filter_map(|point| {
+                let payload = point.payload?;
+                let id = match &point.id {
+                    serde_json::Value::String(s) => s.clone(),
+                    serde_json::Value::Number(n) => n.to_string(),
+                    _ => return None,
+                };
+
+                Some(MemoryEntry {
+                    id,
+                    key: payload.key,
+                    content: payload.content,
+                    category: Self::parse_category(&payload.category),
+                    timestamp: payload.timestamp,
+                    session_id: payload.session_id,
+                    score: None,
+                })
+            }));
+
+            match result.result.next_page_offset {
+                Some(offset) if all && !offset.is_null() => scroll_body["offset"] = offset,
+                _ => break,
+            }
+        }
+
+        Ok(entries)
+    }
+
     fn parse_category(value: &str) -> MemoryCategory {
         match value {
             "core" => MemoryCategory::Core,
@@ -212,6 +299,8 @@ struct QdrantScrollResult {
 #[derive(Debug, Deserialize)]
 struct QdrantScrollPoints {
     points: Vec<QdrantPoint>,
+    #[serde(default)]
+    next_page_offset: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -430,77 +519,15 @@ impl Memory for QdrantMemory {
         category: Option<&MemoryCategory>,
         session_id: Option<&str>,
     ) -> Result<Vec<MemoryEntry>> {
-        self.ensure_initialized().await?;
-
-        // Build filter conditions
-        let mut must_conditions = Vec::new();
-
-        if let Some(cat) = category {
-            must_conditions.push(serde_json::json!({
-                "key": "category",
-                "match": { "value": Self::category_to_str(cat) }
-            }));
-        }
-
-        if let Some(sid) = session_id {
-            must_conditions.push(serde_json::json!({
-                "key": "session_id",
-                "match": { "value": sid }
-            }));
-        }
-
-        let mut scroll_body = serde_json::json!({
-            "limit": 1000,
-            "with_payload": true
-        });
-
-        if !must_conditions.is_empty() {
-            scroll_body["filter"] = serde_json::json!({ "must": must_conditions });
-        }
-
-        let resp = self
-            .request(
-                reqwest::Method::POST,
-                &format!("/collections/{}/points/scroll", self.collection),
-            )
-            .json(&scroll_body)
-            .send()
-            .await
-            .context("failed to scroll Qdrant")?;
-
-        if !resp.status().is_success() {
-            let status = resp.status();
-            let text = resp.text().await.unwrap_or_default();
-            anyhow::bail!("Qdrant scroll failed ({status}): {text}");
-        }
-
-        let result: QdrantScrollResult = resp.json().await?;
-
-        let entries = result
-            .result
-            .points
-            .into_iter()
-            .filter_map(|point| {
-                let payload = point.payload?;
-                let id = match &point.id {
-                    serde_json::Value::String(s) => s.clone(),
-                    serde_json::Value::Number(n) => n.to_string(),
-                    _ => return None,
-                };
-
-                Some(MemoryEntry {
-                    id,
-                    key: payload.key,
-                    content: payload.content,
-                    category: Self::parse_category(&payload.category),
-                    timestamp: payload.timestamp,
-                    session_id: payload.session_id,
-                    score: None,
-                })
-            })
-            .collect();
+        self.scroll(category, session_id, false).await
+    }
 
-        Ok(entries)
+    async fn list_all(
+        &self,
+        category: Option<&MemoryCategory>,
+        session_id: Option<&str>,
+    ) -> Result<Vec<MemoryEntry>> {
+        self.scroll(category, session_id, true).await
     }
 
     async fn forget(&self, key: &str) -> Result<bool> {
@@ -0,0 +1,322 @@
+//! Memory retention: TTL expiry for conversation entries plus per-category caps.
+//!
+//! Unlike [`super::hygiene`], which prunes the sqlite file directly, retention goes
+//! through the [`Memory`] trait so every backend applies its own locking. Each
+//! deletion re-reads the entry first, so a key that was re-stored after planning
+//! is left alone.
+
+use super::traits::{Memory, MemoryCategory, MemoryEntry};
+use crate::config::MemoryConfig;
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashMap;
+
+/// Retention limits read from `[memory]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    /// Conversation entries older than this many days expire. `0` = never.
+    pub conversation_ttl_days: u32,
+    /// Keep at most this many entries per category (oldest evicted first). `0` = unlimited.
+    pub max_entries_per_category: usize,
+}
+
+impl RetentionPolicy {
+    pub fn from_config(config: &MemoryConfig) -> Self {
+        Self {
+            conversation_ttl_days: config.conversation_ttl_days,
+            max_entries_per_category: config.max_entries_per_category,
+        }
+    }
+
+    /// Retention is skipped entirely when neither limit is set.
+    pub fn is_enabled(&self) -> bool {
+        self.conversation_ttl_days > 0 || self.max_entries_per_category > 0
+    }
+}
+
+/// Entries a retention pass would remove, split by reason.
+#[derive(Debug, Clone, Default)]
+pub struct RetentionPlan {
+    pub expired: Vec<MemoryEntry>,
+    pub evicted: Vec<MemoryEntry>,
+}
+
+impl RetentionPlan {
+    pub fn is_empty(&self) -> bool {
+        self.expired.is_empty() && self.evicted.is_empty()
+    }
+
+    /// First `limit` keys in the plan, for log previews.
+    pub fn sample_keys(&self, limit: usize) -> Vec<&str> {
+        self.expired
+            .iter()
+            .chain(&self.evicted)
+            .take(limit)
+            .map(|entry| entry.key.as_str())
+            .collect()
+    }
+}
+
+/// Entries actually removed by [`run`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RetentionReport {
+    pub expired: u64,
+    pub evicted: u64,
+}
+
+impl RetentionReport {
+    pub fn total(&self) -> u64 {
+        self.expired + self.evicted
+    }
+}
+
+type DatedEntry = (Option<DateTime<Utc>>, MemoryEntry);
+
+fn entry_time(entry: &MemoryEntry) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(&entry.timestamp)
+        .ok()
+        .map(|ts| ts.with_timezone(&Utc))
+}
+
+/// Decide which entries to remove. Entries with unparseable timestamps never
+/// expire and are evicted last.
+pub fn plan_entries(
+    entries: Vec<MemoryEntry>,
+    policy: &RetentionPolicy,
+    now: DateTime<Utc>,
+) -> RetentionPlan {
+    let mut plan = RetentionPlan::default();
+    if !policy.is_enabled() {
+        return plan;
+    }
+
+    let cutoff = (policy.conversation_ttl_days > 0)
+        .then(|| now - Duration::days(i64::from(policy.conversation_ttl_days)));
+    let mut by_category: HashMap<String, Vec<DatedEntry>> = HashMap::new();
+    for entry in entries {
+        let time = entry_time(&entry);
+        if entry.category == MemoryCategory::Conversation
+            && time.zip(cutoff).is_some_and(|(ts, cutoff)| ts < cutoff)
+        {
+            plan.expired.push(entry);
+            continue;
+        }
+        by_category
+            .entry(entry.category.to_string())
+            .or_default()
+            .push((time, entry));
+    }
+
+    if policy.max_entries_per_category > 0 {
+        let mut categories: Vec<_> = by_category.into_iter().collect();
+        categories.sort_by(|a, b| a.0.cmp(&b.0));
+        for (_, mut kept) in categories {
+            if kept.len() <= policy.max_entries_per_category {
+                continue;
+            }
+            // `None` sorts first; flip it so undated entries are evicted last.
+            kept.sort_by_key(|(time, _)| (time.is_none(), *time));
+            let overflow = kept.len() - policy.max_entries_per_category;
+            plan.evicted
+                .extend(kept.into_iter().take(overflow).map(|(_, entry)| entry));
+        }
+    }
+
+    plan.expired.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+    plan
+}
+
+/// Compute the retention plan for `memory` without deleting anything.
+pub async fn plan(
+    memory: &dyn Memory,
+    policy: &RetentionPolicy,
+    now: DateTime<Utc>,
+) -> Result<RetentionPlan> {
+    if !policy.is_enabled() {
+        return Ok(RetentionPlan::default());
+    }
+    let entries = memory.list_all(None, None).await?;
+    Ok(plan_entries(entries, policy, now))
+}
+
+/// Delete the entries in `plan`, skipping any that changed since it was computed.
+pub async fn apply(memory: &dyn Memory, plan: &RetentionPlan) -> Result<RetentionReport> {
+    let mut report = RetentionReport::default();
+    for entry in &plan.expired {
+        if forget_if_unchanged(memory, entry).await? {
+            report.expired += 1;
+        }
+    }
+    for entry in &plan.evicted {
+        if forget_if_unchanged(memory, entry).await? {
+            report.evicted += 1;
+        }
+    }
+    Ok(report)
+}
+
+/// Plan and apply one retention pass.
+pub async fn run(
+    memory: &dyn Memory,
+    policy: &RetentionPolicy,
+    now: DateTime<Utc>,
+) -> Result<RetentionReport> {
+    let plan = plan(memory, policy, now).await?;
+    apply(memory, &plan).await
+}
+
+async fn forget_if_unchanged(memory: &dyn Memory, planned: &MemoryEntry) -> Result<bool> {
+    let Some(current) = memory.get(&planned.key).await? else {
+        return Ok(false);
+    };
+    if current.timestamp != planned.timestamp
+        || current.content != planned.content
+        || current.category != planned.category
+    {
+        return Ok(false);
+    }
+    memory.forget(&planned.key).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::SqliteMemory;
+    use tempfile::TempDir;
+
+    fn entry(
+        key: &str,
+        category: MemoryCategory,
+        age_days: i64,
+        now: DateTime<Utc>,
+    ) -> MemoryEntry {
+        MemoryEntry {
+            id: key.to_string(),
+            key: key.to_string(),
+            content: format!("content for {key}"),
+            category,
+            timestamp: (now - Duration::days(age_days)).to_rfc3339(),
+            session_id: None,
+            score: None,
+        }
+    }
+
+    fn keys(entries: &[MemoryEntry]) -> Vec<&str> {
+        entries.iter().map(|entry| entry.key.as_str()).collect()
+    }
+
+    #[test]
+    fn plan_expires_old_conversation_and_evicts_oldest_overflow() {
+        let now = Utc::now();
+        let policy = RetentionPolicy {
+            conversation_ttl_days: 30,
+            max_entries_per_category: 2,
+        };
+        let plan = plan_entries(
+            vec![
+                entry("conv_old", MemoryCategory::Conversation, 45, now),
+                entry("conv_new", MemoryCategory::Conversation, 1, now),
+                entry("core_old", MemoryCategory::Core, 90, now),
+                entry("core_mid", MemoryCategory::Core, 10, now),
+                entry("core_new", MemoryCategory::Core, 2, now),
+                MemoryEntry {
+                    timestamp: "not-a-date".into(),
+                    ..entry("core_undated", MemoryCategory::Core, 0, now)
+                },
+            ],
+            &policy,
+            now,
+        );
+
+        assert_eq!(keys(&plan.expired), vec!["conv_old"]);
+        assert_eq!(keys(&plan.evicted), vec!["core_old", "core_mid"]);
+    }
+
+    #[test]
+    fn category_cap_applies_without_a_ttl() {
+        let now = Utc::now();
+        let entries = || {
+            vec![
+                entry("a", MemoryCategory::Conversation, 400, now),
+                entry("b", MemoryCategory::Conversation, 300, now),
+            ]
+        };
+        let policy = RetentionPolicy {
+            conversation_ttl_days: 0,
+            max_entries_per_category: 1,
+        };
+        let plan = plan_entries(entries(), &policy, now);
+        assert!(plan.expired.is_empty());
+        assert_eq!(keys(&plan.evicted), vec!["a"]);
+
+        let disabled = RetentionPolicy {
+            conversation_ttl_days: 0,
+            max_entries_per_category: 0,
+        };
+        assert!(plan_entries(entries(), &disabled, now).is_empty());
+    }
+
+    #[tokio::test]
+    async fn plan_covers_entries_past_the_list_cap() {
+        let tmp = TempDir::new().unwrap();
+        let mem = SqliteMemory::new(tmp.path()).unwrap();
+        for i in 0..1005 {
+            mem.store(
+                &format!("conv_{i}"),
+                "x",
+                MemoryCategory::Conversation,
+                None,
+            )
+            .await
+            .unwrap();
+        }
+        let policy = RetentionPolicy {
+            conversation_ttl_days: 30,
+            max_entries_per_category: 0,
+        };
+
+        let later = Utc::now() + Duration::days(365);
+        let report = run(&mem, &policy, later).await.unwrap();
+        assert_eq!(report.expired, 1005);
+        assert_eq!(mem.count().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn apply_skips_entries_restored_after_planning() {
+        let tmp = TempDir::new().unwrap();
+        let mem = SqliteMemory::new(tmp.path()).unwrap();
+        for key in ["first", "second", "third"] {
+            mem.store(key, key, MemoryCategory::Conversation, None)
+                .await
+                .unwrap();
+        }
+        let policy = RetentionPolicy {
+            conversation_ttl_days: 30,
+            max_entries_per_category: 1,
+        };
+
+        let preview = plan(&mem, &policy, Utc::now()).await.unwrap();
+        assert_eq!(keys(&preview.evicted), vec!["first", "second"]);
+        assert_eq!(mem.count().await.unwrap(), 3, "planning must not delete");
+
+        mem.store("first", "updated", MemoryCategory::Conversation, None)
+            .await
+            .unwrap();
+        let report = apply(&mem, &preview).await.unwrap();
+        assert_eq!(report.evicted, 1);
+        assert!(mem.get("first").await.unwrap().is_some());
+        assert!(mem.get("second").await.unwrap().is_none());
+
+        // Everything left is past the TTL a year from now.
+        let later = Utc::now() + Duration::days(365);
+        let report = run(&mem, &policy, later).await.unwrap();
+        assert_eq!(
+            report,
+            RetentionReport {
+                expired: 2,
+                evicted: 0
+            }
+        );
+        assert_eq!(mem.count().await.unwrap(), 0);
+    }
+}
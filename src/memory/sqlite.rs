@@ -748,6 +748,38 @@ impl Memory for SqliteMemory {
         .await?
     }
 
+    async fn list_all(
+        &self,
+        category: Option<&MemoryCategory>,
+        session_id: Option<&str>,
+    ) -> anyhow::Result<Vec<MemoryEntry>> {
+        let conn = self.conn.clone();
+        let category = category.map(Self::category_to_str);
+        let sid = session_id.map(String::from);
+
+        tokio::task::spawn_blocking(move || -> anyhow::Result<Vec<MemoryEntry>> {
+            let conn = conn.lock();
+            let mut stmt = conn.prepare(
+                "SELECT id, key, content, category, created_at, session_id FROM memories
+                 WHERE (?1 IS NULL OR category = ?1) AND (?2 IS NULL OR session_id = ?2)
+                 ORDER BY updated_at DESC",
+            )?;
+            let rows = stmt.query_map(params![category, sid], |row| {
+                Ok(MemoryEntry {
+                    id: row.get(0)?,
+                    key: row.get(1)?,
+                    content: row.get(2)?,
+                    category: Self::str_to_category(&row.get::<_, String>(3)?),
+                    timestamp: row.get(4)?,
+                    session_id: row.get(5)?,
+                    score: None,
+                })
+            })?;
+            Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+        })
+        .await?
+    }
+
     async fn forget(&self, key: &str) -> anyhow::Result<bool> {
         let conn = self.conn.clone();
         let key = key.to_string();
@@ -1663,6 +1695,35 @@ mod tests {
         assert_eq!(project.len(), 2);
     }
 
+    #[tokio::test]
+    async fn list_all_is_not_capped() {
+        let (_tmp, mem) = temp_sqlite();
+        for i in 0..1005 {
+            let session = (i % 2 == 0).then_some("even");
+            mem.store(&format!("k{i}"), "x", MemoryCategory::Daily, session)
+                .await
+                .unwrap();
+        }
+        mem.store("late", "x", MemoryCategory::Core, Some("even"))
+            .await
+            .unwrap();
+
+        assert_eq!(mem.list(None, None).await.unwrap().len(), 1000);
+        assert_eq!(mem.list_all(None, None).await.unwrap().len(), 1006);
+        assert_eq!(
+            mem.list_all(Some(&MemoryCategory::Daily), None)
+                .await
+                .unwrap()
+                .len(),
+            1005
+        );
+        let even = mem.list_all(None, Some("even")).await.unwrap();
+        assert_eq!(even.len(), 504);
+        assert!(even
+            .iter()
+            .all(|entry| entry.session_id.as_deref() == Some("even")));
+    }
+
     #[tokio::test]
     async fn list_empty_db() {
         let (_tmp, mem) = temp_sqlite();
@@ -84,6 +84,16 @@ pub trait Memory: Send + Sync {
         session_id: Option<&str>,
     ) -> anyhow::Result<Vec<MemoryEntry>>;
 
+    /// Like [`Memory::list`], but without the backend's result cap, for
+    /// exports, retention and bulk deletes that must see every entry
+    async fn list_all(
+        &self,
+        category: Option<&MemoryCategory>,
+        session_id: Option<&str>,
+    ) -> anyhow::Result<Vec<MemoryEntry>> {
+        self.list(category, session_id).await
+    }
+
     /// Remove a memory by key
     async fn forget(&self, key: &str) -> anyhow::Result<bool>;
 
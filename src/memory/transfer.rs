@@ -0,0 +1,240 @@
+//! Portable JSONL export/import for moving memories between backends.
+//!
+//! Each line is one [`MemoryRecord`]. Import goes through [`Memory::store`], so
+//! the target backend assigns fresh timestamps and embeddings; the exported
+//! `timestamp` is kept in the file for reference.
+
+use super::cli::parse_category;
+use super::traits::{Memory, MemoryEntry};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt};
+
+/// Maximum number of per-line error messages kept in an [`ImportSummary`].
+const IMPORT_ERROR_SAMPLE: usize = 20;
+
+/// One exported memory entry.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemoryRecord {
+    pub key: String,
+    pub content: String,
+    pub category: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<String>,
+}
+
+impl From<&MemoryEntry> for MemoryRecord {
+    fn from(entry: &MemoryEntry) -> Self {
+        Self {
+            key: entry.key.clone(),
+            content: entry.content.clone(),
+            category: entry.category.to_string(),
+            session_id: entry.session_id.clone(),
+            timestamp: Some(entry.timestamp.clone()).filter(|ts| !ts.is_empty()),
+        }
+    }
+}
+
+/// Outcome of an import run.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ImportSummary {
+    pub inserted: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    /// First few failures as `line N: reason`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<String>,
+}
+
+/// Write every entry in `memory` as one JSON line. Returns the entry count.
+pub async fn export_jsonl<W: Write>(memory: &dyn Memory, out: &mut W) -> Result<usize> {
+    let entries = memory.list_all(None, None).await?;
+    for entry in &entries {
+        serde_json::to_writer(&mut *out, &MemoryRecord::from(entry))?;
+        out.write_all(b"\n")?;
+    }
+    out.flush()?;
+    Ok(entries.len())
+}
+
+/// Line-at-a-time importer; callers feed lines as they arrive so the whole
+/// file never has to be held in memory.
+pub struct JsonlImporter<'a> {
+    memory: &'a dyn Memory,
+    overwrite: bool,
+    line_no: usize,
+    summary: ImportSummary,
+}
+
+impl<'a> JsonlImporter<'a> {
+    /// Existing keys are skipped unless `overwrite` is set.
+    pub fn new(memory: &'a dyn Memory, overwrite: bool) -> Self {
+        Self {
+            memory,
+            overwrite,
+            line_no: 0,
+            summary: ImportSummary::default(),
+        }
+    }
+
+    /// Import one line. Blank lines are ignored; bad lines count as failed.
+    pub async fn import_line(&mut self, line: &str) {
+        self.line_no += 1;
+        if line.trim().is_empty() {
+            return;
+        }
+        match self.store_line(line).await {
+            Ok(true) => self.summary.inserted += 1,
+            Ok(false) => self.summary.skipped += 1,
+            Err(err) => self.record_failure(&format!("{err:#}")),
+        }
+    }
+
+    /// Count a line that could not be decoded before reaching the importer.
+    pub fn reject_line(&mut self, reason: &str) {
+        self.line_no += 1;
+        self.record_failure(reason);
+    }
+
+    pub fn finish(self) -> ImportSummary {
+        self.summary
+    }
+
+    async fn store_line(&self, line: &str) -> Result<bool> {
+        let record: MemoryRecord = serde_json::from_str(line)?;
+        let key = record.key.trim();
+        if key.is_empty() {
+            anyhow::bail!("missing key");
+        }
+        if !self.overwrite && self.memory.get(key).await?.is_some() {
+            return Ok(false);
+        }
+        self.memory
+            .store(
+                key,
+                &record.content,
+                parse_category(&record.category),
+                record.session_id.as_deref(),
+            )
+            .await?;
+        Ok(true)
+    }
+
+    fn record_failure(&mut self, reason: &str) {
+        self.summary.failed += 1;
+        if self.summary.errors.len() < IMPORT_ERROR_SAMPLE {
+            self.summary
+                .errors
+                .push(format!("line {}: {reason}", self.line_no));
+        }
+    }
+}
+
+/// Stream JSONL records from `reader` into `memory`.
+pub async fn import_jsonl<R: AsyncBufRead + Unpin>(
+    memory: &dyn Memory,
+    reader: R,
+    overwrite: bool,
+) -> Result<ImportSummary> {
+    let mut importer = JsonlImporter::new(memory, overwrite);
+    let mut lines = reader.lines();
+    while let Some(line) = lines.next_line().await? {
+        importer.import_line(&line).await;
+    }
+    Ok(importer.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::{MemoryCategory, SqliteMemory};
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn export_then_import_roundtrips_into_another_backend() {
+        let src_dir = TempDir::new().unwrap();
+        let src = SqliteMemory::new(src_dir.path()).unwrap();
+        src.store("pref", "likes rust", MemoryCategory::Core, None)
+            .await
+            .unwrap();
+        src.store("chat_1", "hello", MemoryCategory::Conversation, Some("s1"))
+            .await
+            .unwrap();
+        src.store(
+            "note",
+            "custom",
+            MemoryCategory::Custom("notes".into()),
+            None,
+        )
+        .await
+        .unwrap();
+
+        let mut buf = Vec::new();
+        assert_eq!(export_jsonl(&src, &mut buf).await.unwrap(), 3);
+
+        let dst_dir = TempDir::new().unwrap();
+        let dst = SqliteMemory::new(dst_dir.path()).unwrap();
+        dst.store("pref", "old value", MemoryCategory::Core, None)
+            .await
+            .unwrap();
+
+        let summary = import_jsonl(&dst, buf.as_slice(), false).await.unwrap();
+        assert_eq!(
+            (summary.inserted, summary.skipped, summary.failed),
+            (2, 1, 0)
+        );
+        assert_eq!(dst.get("pref").await.unwrap().unwrap().content, "old value");
+        let chat = dst.get("chat_1").await.unwrap().unwrap();
+        assert_eq!(chat.category, MemoryCategory::Conversation);
+        assert_eq!(chat.session_id.as_deref(), Some("s1"));
+        assert_eq!(
+            dst.get("note").await.unwrap().unwrap().category,
+            MemoryCategory::Custom("notes".into())
+        );
+
+        let summary = import_jsonl(&dst, buf.as_slice(), true).await.unwrap();
+        assert_eq!((summary.inserted, summary.skipped), (3, 0));
+        assert_eq!(
+            dst.get("pref").await.unwrap().unwrap().content,
+            "likes rust"
+        );
+    }
+
+    #[tokio::test]
+    async fn export_includes_entries_past_the_list_cap() {
+        let tmp = TempDir::new().unwrap();
+        let mem = SqliteMemory::new(tmp.path()).unwrap();
+        for i in 0..1005 {
+            mem.store(&format!("k{i}"), "x", MemoryCategory::Daily, None)
+                .await
+                .unwrap();
+        }
+
+        let mut buf = Vec::new();
+        assert_eq!(export_jsonl(&mem, &mut buf).await.unwrap(), 1005);
+        assert_eq!(String::from_utf8(buf).unwrap().lines().count(), 1005);
+    }
+
+    #[tokio::test]
+    async fn import_counts_bad_lines_and_keeps_going() {
+        let tmp = TempDir::new().unwrap();
+        let mem = SqliteMemory::new(tmp.path()).unwrap();
+        let input = "not json\n\n{\"key\":\"\",\"content\":\"x\",\"category\":\"core\"}\n\
+                     {\"key\":\"ok\",\"content\":\"fine\",\"category\":\"daily\"}\n";
+
+        let summary = import_jsonl(&mem, input.as_bytes(), false).await.unwrap();
+        assert_eq!(
+            (summary.inserted, summary.skipped, summary.failed),
+            (1, 0, 2)
+        );
+        assert!(summary.errors[0].starts_with("line 1:"));
+        assert_eq!(summary.errors[1], "line 3: missing key");
+        assert_eq!(
+            mem.get("ok").await.unwrap().unwrap().category,
+            MemoryCategory::Daily
+        );
+    }
+}
@@ -0,0 +1,290 @@
+use super::traits::{Observer, ObserverEvent, ObserverMetric};
+use crate::util::truncate_with_ellipsis;
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::any::Any;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// Events kept in the activity timeline.
+pub const ACTIVITY_CAPACITY: usize = 500;
+
+/// Longest summary shown in a timeline row.
+const SUMMARY_MAX_CHARS: usize = 160;
+
+/// Longest `detail` payload kept when `debug_payloads` is enabled.
+const DETAIL_MAX_CHARS: usize = 4_000;
+
+/// Coarse grouping used to filter the timeline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActivityKind {
+    Agent,
+    Llm,
+    Tool,
+    Channel,
+    Memory,
+    Error,
+}
+
+impl ActivityKind {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "agent" => Some(Self::Agent),
+            "llm" => Some(Self::Llm),
+            "tool" => Some(Self::Tool),
+            "channel" => Some(Self::Channel),
+            "memory" => Some(Self::Memory),
+            "error" => Some(Self::Error),
+            _ => None,
+        }
+    }
+}
+
+/// One sanitized timeline row.
+#[derive(Debug, Clone, Serialize)]
+pub struct ActivityEntry {
+    pub timestamp: DateTime<Utc>,
+    pub kind: ActivityKind,
+    pub summary: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub success: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+    /// Untruncated error text; only present when `debug_payloads` is on.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+/// In-memory ring buffer of recent agent activity for the desktop UI.
+///
+/// Only lifecycle facts are kept: provider/model/tool names, outcomes, and
+/// durations. Free-form text (error messages) is cut to a short summary; the
+/// full text is kept only when `debug_payloads` is enabled.
+pub struct ActivityObserver {
+    entries: Mutex<VecDeque<ActivityEntry>>,
+    capacity: usize,
+    debug_payloads: bool,
+}
+
+impl ActivityObserver {
+    pub fn new(debug_payloads: bool) -> Self {
+        Self::with_capacity(ACTIVITY_CAPACITY, debug_payloads)
+    }
+
+    pub fn with_capacity(capacity: usize, debug_payloads: bool) -> Self {
+        Self {
+            entries: Mutex::new(VecDeque::with_capacity(capacity.min(ACTIVITY_CAPACITY))),
+            capacity: capacity.max(1),
+            debug_payloads,
+        }
+    }
+
+    /// Entries newer than `since`, optionally of one kind, newest first.
+    pub fn recent(
+        &self,
+        since: Option<DateTime<Utc>>,
+        kind: Option<ActivityKind>,
+        limit: usize,
+    ) -> Vec<ActivityEntry> {
+        self.entries
+            .lock()
+            .iter()
+            .rev()
+            .filter(|entry| since.is_none_or(|since| entry.timestamp > since))
+            .filter(|entry| kind.is_none_or(|kind| entry.kind == kind))
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+
+    fn push(
+        &self,
+        kind: ActivityKind,
+        summary: String,
+        success: Option<bool>,
+        duration: Option<&Duration>,
+        detail: Option<&str>,
+    ) {
+        let entry = ActivityEntry {
+            timestamp: Utc::now(),
+            kind,
+            summary: truncate_with_ellipsis(&summary, SUMMARY_MAX_CHARS),
+            success,
+            duration_ms: duration.map(|d| u64::try_from(d.as_millis()).unwrap_or(u64::MAX)),
+            detail: detail
+                .filter(|_| self.debug_payloads)
+                .map(|text| truncate_with_ellipsis(text, DETAIL_MAX_CHARS)),
+        };
+        let mut entries = self.entries.lock();
+        if entries.len() >= self.capacity {
+            entries.pop_front();
+        }
+        entries.push_back(entry);
+    }
+}
+
+impl Observer for ActivityObserver {
+    fn record_event(&self, event: &ObserverEvent) {
+        match event {
+            ObserverEvent::AgentEnd {
+                provider,
+                model,
+                duration,
+                ..
+            } => self.push(
+                ActivityKind::Agent,
+                format!("Agent run finished ({provider}/{model})"),
+                None,
+                Some(duration),
+                None,
+            ),
+            ObserverEvent::LlmResponse {
+                provider,
+                model,
+                duration,
+                success,
+                error_message,
+                ..
+            } => {
+                let summary = match error_message {
+                    Some(err) if !success => format!("{provider}/{model} failed: {err}"),
+                    _ => format!("{provider}/{model} replied"),
+                };
+                self.push(
+                    ActivityKind::Llm,
+                    summary,
+                    Some(*success),
+                    Some(duration),
+                    error_message.as_deref(),
+                );
+            }
+            ObserverEvent::ToolCall {
+                tool,
+                duration,
+                success,
+            } => self.push(
+                ActivityKind::Tool,
+                format!(
+                    "Tool {tool} {}",
+                    if *success { "succeeded" } else { "failed" }
+                ),
+                Some(*success),
+                Some(duration),
+                None,
+            ),
+            ObserverEvent::ToolCallBlocked { tool } => self.push(
+                ActivityKind::Tool,
+                format!("Tool {tool} blocked by policy"),
+                Some(false),
+                None,
+                None,
+            ),
+            ObserverEvent::ChannelMessage { channel, direction } => self.push(
+                ActivityKind::Channel,
+                format!("{channel} message ({direction})"),
+                None,
+                None,
+                None,
+            ),
+            ObserverEvent::MemoryCompaction { expired, evicted } => self.push(
+                ActivityKind::Memory,
+                format!("Memory retention removed {expired} expired and {evicted} evicted entries"),
+                None,
+                None,
+                None,
+            ),
+            ObserverEvent::Error { component, message } => self.push(
+                ActivityKind::Error,
+                format!("{component}: {message}"),
+                Some(false),
+                None,
+                Some(message),
+            ),
+            ObserverEvent::AgentStart { .. }
+            | ObserverEvent::LlmRequest { .. }
+            | ObserverEvent::ToolCallStart { .. }
+            | ObserverEvent::TurnComplete
+            | ObserverEvent::HeartbeatTick
+            | ObserverEvent::LlmCacheLookup { .. }
+            | ObserverEvent::MemoryDedupeSkipped { .. }
+            | ObserverEvent::PocketBaseWriteRetry { .. } => {}
+        }
+    }
+
+    fn record_metric(&self, _metric: &ObserverMetric) {}
+
+    fn name(&self) -> &str {
+        "activity"
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error_event(message: &str) -> ObserverEvent {
+        ObserverEvent::Error {
+            component: "gateway".into(),
+            message: message.into(),
+        }
+    }
+
+    #[test]
+    fn keeps_only_the_newest_entries() {
+        let obs = ActivityObserver::with_capacity(3, false);
+        for i in 0..5 {
+            obs.record_event(&error_event(&format!("boom {i}")));
+        }
+        let summaries: Vec<_> = obs
+            .recent(None, None, 10)
+            .into_iter()
+            .map(|entry| entry.summary)
+            .collect();
+        assert_eq!(
+            summaries,
+            vec!["gateway: boom 4", "gateway: boom 3", "gateway: boom 2"]
+        );
+    }
+
+    #[test]
+    fn filters_by_kind_and_skips_noise() {
+        let obs = ActivityObserver::new(false);
+        obs.record_event(&ObserverEvent::HeartbeatTick);
+        obs.record_event(&ObserverEvent::ToolCall {
+            tool: "shell".into(),
+            duration: Duration::from_millis(12),
+            success: true,
+        });
+        obs.record_event(&error_event("boom"));
+
+        let tools = obs.recent(None, Some(ActivityKind::Tool), 10);
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0].summary, "Tool shell succeeded");
+        assert_eq!(tools[0].duration_ms, Some(12));
+        assert_eq!(obs.recent(None, None, 10).len(), 2);
+    }
+
+    #[test]
+    fn truncates_text_and_gates_detail_on_debug_payloads() {
+        let long = "x".repeat(1_000);
+
+        let obs = ActivityObserver::new(false);
+        obs.record_event(&error_event(&long));
+        let entry = &obs.recent(None, None, 1)[0];
+        assert!(entry.summary.chars().count() <= SUMMARY_MAX_CHARS + 3);
+        assert!(entry.detail.is_none());
+
+        let obs = ActivityObserver::new(true);
+        obs.record_event(&error_event(&long));
+        assert_eq!(
+            obs.recent(None, None, 1)[0].detail.as_deref(),
+            Some(long.as_str())
+        );
+    }
+}
@@ -0,0 +1,368 @@
+//! Outbound webhook alerts for critical runtime conditions.
+//!
+//! Each condition has a stable key (`llm_failures`, `component:<name>`,
+//! `pocketbase_unreachable`, `disk_quota`). [`AlertTracker`] only emits a
+//! notification when a key changes between open and resolved, so a condition
+//! that persists across poll cycles is reported once. Notifications are
+//! delivered by a background task with retries; a notification that still
+//! fails is dropped with a log line.
+
+use super::traits::{Observer, ObserverEvent, ObserverMetric};
+use crate::config::ObservabilityConfig;
+use crate::util::truncate_with_ellipsis;
+use hmac::{Hmac, Mac};
+use parking_lot::Mutex;
+use serde::Serialize;
+use sha2::Sha256;
+use std::any::Any;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// Header carrying `sha256=<hex HMAC of the body>` when a secret is configured.
+pub const ALERT_SIGNATURE_HEADER: &str = "X-Slowclaw-Signature";
+
+/// Delivery attempts per notification before it is dropped.
+const DELIVERY_ATTEMPTS: u32 = 4;
+
+/// Backoff before the second attempt; doubles after each failure.
+const DELIVERY_BACKOFF: Duration = Duration::from_secs(2);
+
+/// Notifications waiting for delivery; further alerts are dropped when full.
+const DELIVERY_QUEUE: usize = 64;
+
+const SUMMARY_MAX_CHARS: usize = 300;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertStatus {
+    Open,
+    Resolved,
+}
+
+/// JSON body posted to `alert_webhook_url`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AlertNotification {
+    pub key: String,
+    pub status: AlertStatus,
+    pub summary: String,
+    pub timestamp: String,
+}
+
+/// Open/resolved state per alert key.
+#[derive(Default)]
+pub struct AlertTracker {
+    open: Mutex<HashSet<String>>,
+}
+
+impl AlertTracker {
+    /// Record whether `key` is currently firing. Returns a notification only
+    /// when this changes the key's state.
+    pub fn update(&self, key: &str, firing: bool, summary: &str) -> Option<AlertNotification> {
+        let mut open = self.open.lock();
+        let status = match (firing, open.contains(key)) {
+            (true, false) => {
+                open.insert(key.to_string());
+                AlertStatus::Open
+            }
+            (false, true) => {
+                open.remove(key);
+                AlertStatus::Resolved
+            }
+            _ => return None,
+        };
+        Some(AlertNotification {
+            key: key.to_string(),
+            status,
+            summary: truncate_with_ellipsis(summary, SUMMARY_MAX_CHARS),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        })
+    }
+}
+
+/// Shared handle that turns condition checks into queued notifications.
+#[derive(Clone)]
+pub struct AlertDispatcher {
+    tracker: Arc<AlertTracker>,
+    tx: mpsc::Sender<AlertNotification>,
+}
+
+impl AlertDispatcher {
+    /// Start the delivery task for `config.alert_webhook_url`. Returns `None`
+    /// when no URL is configured. Must be called inside a Tokio runtime.
+    pub fn spawn(config: &ObservabilityConfig) -> Option<Self> {
+        let url = config
+            .alert_webhook_url
+            .as_deref()
+            .map(str::trim)
+            .filter(|url| !url.is_empty())?
+            .to_string();
+        let secret = config
+            .alert_webhook_secret
+            .clone()
+            .filter(|secret| !secret.is_empty());
+        let (dispatcher, mut rx) = Self::channel();
+        tokio::spawn(async move {
+            let client = crate::config::build_runtime_proxy_client_with_timeouts(
+                "observability.alerts",
+                15,
+                10,
+            );
+            while let Some(notification) = rx.recv().await {
+                deliver_with_retry(
+                    &client,
+                    &url,
+                    secret.as_deref(),
+                    &notification,
+                    DELIVERY_BACKOFF,
+                )
+                .await;
+            }
+        });
+        Some(dispatcher)
+    }
+
+    fn channel() -> (Self, mpsc::Receiver<AlertNotification>) {
+        let (tx, rx) = mpsc::channel(DELIVERY_QUEUE);
+        (
+            Self {
+                tracker: Arc::new(AlertTracker::default()),
+                tx,
+            },
+            rx,
+        )
+    }
+
+    /// Update `key` and queue a notification if it opened or resolved.
+    pub fn report(&self, key: &str, firing: bool, summary: &str) {
+        let Some(notification) = self.tracker.update(key, firing, summary) else {
+            return;
+        };
+        tracing::info!(
+            key,
+            status = ?notification.status,
+            "runtime alert state changed"
+        );
+        if let Err(err) = self.tx.try_send(notification) {
+            tracing::warn!("Dropping runtime alert '{key}': {err}");
+        }
+    }
+}
+
+/// `sha256=<hex>` signature of `body` for [`ALERT_SIGNATURE_HEADER`].
+pub fn sign_body(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC can take key of any size");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+async fn deliver_with_retry(
+    client: &reqwest::Client,
+    url: &str,
+    secret: Option<&str>,
+    notification: &AlertNotification,
+    backoff: Duration,
+) -> bool {
+    let Ok(body) = serde_json::to_vec(notification) else {
+        return false;
+    };
+    let mut delay = backoff;
+    for attempt in 1..=DELIVERY_ATTEMPTS {
+        let mut request = client
+            .post(url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body.clone());
+        if let Some(secret) = secret {
+            request = request.header(ALERT_SIGNATURE_HEADER, sign_body(secret, &body));
+        }
+        let outcome = match request.send().await {
+            Ok(response) if response.status().is_success() => return true,
+            Ok(response) => format!("HTTP {}", response.status()),
+            Err(err) => err.to_string(),
+        };
+        if attempt == DELIVERY_ATTEMPTS {
+            tracing::warn!(
+                key = %notification.key,
+                "Giving up on alert webhook after {attempt} attempts: {outcome}"
+            );
+            break;
+        }
+        tracing::debug!(
+            key = %notification.key,
+            "Alert webhook attempt {attempt} failed: {outcome}"
+        );
+        tokio::time::sleep(delay).await;
+        delay *= 2;
+    }
+    false
+}
+
+/// Opens `llm_failures` after N consecutive failed provider calls and
+/// resolves it on the next success.
+pub struct AlertObserver {
+    dispatcher: AlertDispatcher,
+    threshold: u32,
+    consecutive_llm_failures: AtomicU32,
+}
+
+impl AlertObserver {
+    pub fn new(dispatcher: AlertDispatcher, llm_failure_threshold: u32) -> Self {
+        Self {
+            dispatcher,
+            threshold: llm_failure_threshold.max(1),
+            consecutive_llm_failures: AtomicU32::new(0),
+        }
+    }
+}
+
+impl Observer for AlertObserver {
+    fn record_event(&self, event: &ObserverEvent) {
+        let ObserverEvent::LlmResponse {
+            provider,
+            success,
+            error_message,
+            ..
+        } = event
+        else {
+            return;
+        };
+        if *success {
+            self.consecutive_llm_failures.store(0, Ordering::Relaxed);
+            self.dispatcher.report(
+                "llm_failures",
+                false,
+                &format!("LLM calls to {provider} are succeeding again"),
+            );
+            return;
+        }
+        let failures = self
+            .consecutive_llm_failures
+            .fetch_add(1, Ordering::Relaxed)
+            + 1;
+        if failures >= self.threshold {
+            let reason = error_message.as_deref().unwrap_or("unknown error");
+            self.dispatcher.report(
+                "llm_failures",
+                true,
+                &format!(
+                    "{failures} consecutive LLM failures from {provider}: {}",
+                    crate::providers::sanitize_api_error(reason)
+                ),
+            );
+        }
+    }
+
+    fn record_metric(&self, _metric: &ObserverMetric) {}
+
+    fn name(&self) -> &str {
+        "alerts"
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    fn llm_response(success: bool) -> ObserverEvent {
+        ObserverEvent::LlmResponse {
+            provider: "openrouter".into(),
+            model: "m".into(),
+            duration: Duration::from_millis(1),
+            success,
+            error_message: (!success).then(|| "503 Service Unavailable".into()),
+            input_tokens: None,
+            output_tokens: None,
+        }
+    }
+
+    #[test]
+    fn tracker_only_reports_state_changes() {
+        let tracker = AlertTracker::default();
+        assert!(tracker.update("disk_quota", false, "ok").is_none());
+
+        let opened = tracker
+            .update("disk_quota", true, "quota exceeded")
+            .unwrap();
+        assert_eq!(opened.status, AlertStatus::Open);
+        assert!(tracker
+            .update("disk_quota", true, "still exceeded")
+            .is_none());
+
+        let resolved = tracker
+            .update("disk_quota", false, "back under quota")
+            .unwrap();
+        assert_eq!(resolved.status, AlertStatus::Resolved);
+        assert!(tracker.update("disk_quota", false, "ok").is_none());
+    }
+
+    #[test]
+    fn observer_opens_after_consecutive_failures_and_resolves_on_success() {
+        let (dispatcher, mut rx) = AlertDispatcher::channel();
+        let observer = AlertObserver::new(dispatcher, 3);
+
+        observer.record_event(&llm_response(false));
+        observer.record_event(&llm_response(false));
+        assert!(rx.try_recv().is_err());
+
+        observer.record_event(&llm_response(false));
+        observer.record_event(&llm_response(false));
+        let opened = rx.try_recv().unwrap();
+        assert_eq!(opened.status, AlertStatus::Open);
+        assert!(opened.summary.starts_with("3 consecutive LLM failures"));
+        assert!(rx.try_recv().is_err());
+
+        observer.record_event(&llm_response(true));
+        assert_eq!(rx.try_recv().unwrap().status, AlertStatus::Resolved);
+    }
+
+    #[test]
+    fn sign_body_matches_known_hmac() {
+        assert_eq!(
+            sign_body("key", b"The quick brown fox jumps over the lazy dog"),
+            "sha256=f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8"
+        );
+    }
+
+    #[tokio::test]
+    async fn delivery_retries_until_the_webhook_accepts() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/alerts", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let mut signatures = Vec::new();
+            for status in ["500 Internal Server Error", "200 OK"] {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut buf = vec![0; 8192];
+                let n = stream.read(&mut buf).await.unwrap();
+                let request = String::from_utf8_lossy(&buf[..n]).to_ascii_lowercase();
+                signatures.push(request.contains("x-slowclaw-signature: sha256="));
+                let response =
+                    format!("HTTP/1.1 {status}\r\ncontent-length: 0\r\nconnection: close\r\n\r\n");
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+            signatures
+        });
+
+        let notification = AlertTracker::default()
+            .update("llm_failures", true, "provider down")
+            .unwrap();
+        let delivered = deliver_with_retry(
+            &reqwest::Client::new(),
+            &url,
+            Some("secret"),
+            &notification,
+            Duration::from_millis(10),
+        )
+        .await;
+
+        assert!(delivered);
+        assert_eq!(server.await.unwrap(), vec![true, true]);
+    }
+}
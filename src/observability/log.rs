@@ -38,6 +38,9 @@ impl Observer for LogObserver {
                 let ms = u64::try_from(duration.as_millis()).unwrap_or(u64::MAX);
                 info!(tool = %tool, duration_ms = ms, success = success, "tool.call");
             }
+            ObserverEvent::ToolCallBlocked { tool } => {
+                info!(tool = %tool, "tool.blocked");
+            }
             ObserverEvent::TurnComplete => {
                 info!("turn.complete");
             }
@@ -47,6 +50,18 @@ impl Observer for LogObserver {
             ObserverEvent::HeartbeatTick => {
                 info!("heartbeat.tick");
             }
+            ObserverEvent::LlmCacheLookup { model, hit } => {
+                info!(model = %model, hit = hit, "llm.cache_lookup");
+            }
+            ObserverEvent::MemoryCompaction { expired, evicted } => {
+                info!(expired = expired, evicted = evicted, "memory.compaction");
+            }
+            ObserverEvent::MemoryDedupeSkipped { category } => {
+                info!(category = %category, "memory.dedupe_skipped");
+            }
+            ObserverEvent::PocketBaseWriteRetry { operation } => {
+                info!(operation = %operation, "pocketbase.write_retry");
+            }
             ObserverEvent::Error { component, message } => {
                 info!(component = %component, error = %message, "error");
             }
@@ -101,6 +116,12 @@ impl Observer for LogObserver {
             ObserverMetric::QueueDepth(d) => {
                 info!(depth = d, "metric.queue_depth");
             }
+            ObserverMetric::PocketBaseOutboxDepth(d) => {
+                info!(depth = d, "metric.pocketbase_outbox_depth");
+            }
+            ObserverMetric::OutboundQueueDepth { channel, depth } => {
+                info!(channel = %channel, depth, "metric.outbound_queue_depth");
+            }
         }
     }
 
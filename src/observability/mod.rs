@@ -1,3 +1,5 @@
+pub mod activity;
+pub mod alerts;
 pub mod log;
 pub mod multi;
 pub mod noop;
@@ -5,13 +7,15 @@ pub mod noop;
 pub mod otel;
 pub mod prometheus;
 pub mod runtime_trace;
+pub mod stats;
 pub mod traits;
 pub mod verbose;
 
+pub use activity::ActivityObserver;
 #[allow(unused_imports)]
 pub use self::log::LogObserver;
 #[allow(unused_imports)]
-pub use self::multi::MultiObserver;
+pub use self::multi::{find_observer, MultiObserver};
 pub use noop::NoopObserver;
 #[cfg(feature = "observability-otel")]
 pub use otel::OtelObserver;
@@ -23,16 +27,42 @@ pub use verbose::VerboseObserver;
 use crate::config::ObservabilityConfig;
 
 /// Factory: create the right observer from config
+///
+/// `backends` takes precedence over the singular `backend`; several backends
+/// are combined into a [`MultiObserver`].
 pub fn create_observer(config: &ObservabilityConfig) -> Box<dyn Observer> {
-    match config.backend.as_str() {
+    let names: Vec<&str> = if config.backends.is_empty() {
+        vec![config.backend.as_str()]
+    } else {
+        config.backends.iter().map(String::as_str).collect()
+    };
+
+    let mut observers: Vec<Box<dyn Observer>> = Vec::new();
+    let mut seen = Vec::new();
+    for name in names {
+        let observer = create_backend(name.trim(), config);
+        if observer.name() == "noop" || seen.contains(&observer.name().to_string()) {
+            continue;
+        }
+        seen.push(observer.name().to_string());
+        observers.push(observer);
+    }
+
+    match observers.len() {
+        0 => Box::new(NoopObserver),
+        1 => observers.remove(0),
+        _ => Box::new(MultiObserver::new(observers)),
+    }
+}
+
+#[cfg_attr(not(feature = "observability-otel"), allow(unused_variables))]
+fn create_backend(backend: &str, config: &ObservabilityConfig) -> Box<dyn Observer> {
+    match backend {
         "log" => Box::new(LogObserver::new()),
         "prometheus" => Box::new(PrometheusObserver::new()),
         "otel" | "opentelemetry" | "otlp" => {
             #[cfg(feature = "observability-otel")]
-            match OtelObserver::new(
-                config.otel_endpoint.as_deref(),
-                config.otel_service_name.as_deref(),
-            ) {
+            match OtelObserver::from_config(config) {
                 Ok(obs) => {
                     tracing::info!(
                         endpoint = config
@@ -60,7 +90,7 @@ pub fn create_observer(config: &ObservabilityConfig) -> Box<dyn Observer> {
         _ => {
             tracing::warn!(
                 "Unknown observability backend '{}', falling back to noop",
-                config.backend
+                backend
             );
             Box::new(NoopObserver)
         }
@@ -155,6 +185,31 @@ mod tests {
         assert_eq!(create_observer(&cfg).name(), expected);
     }
 
+    #[test]
+    fn factory_backends_list_returns_multi() {
+        let cfg = ObservabilityConfig {
+            backend: "none".into(),
+            backends: vec!["prometheus".into(), "log".into(), "prometheus".into()],
+            ..ObservabilityConfig::default()
+        };
+        let observer = create_observer(&cfg);
+        assert_eq!(observer.name(), "multi");
+        let multi = find_observer::<MultiObserver>(observer.as_ref()).unwrap();
+        let names: Vec<&str> = multi.observers().iter().map(|obs| obs.name()).collect();
+        assert_eq!(names, vec!["prometheus", "log"]);
+        assert!(find_observer::<PrometheusObserver>(observer.as_ref()).is_some());
+    }
+
+    #[test]
+    fn factory_backends_list_skips_noop_entries() {
+        let cfg = ObservabilityConfig {
+            backend: "log".into(),
+            backends: vec!["none".into(), "prometheus".into()],
+            ..ObservabilityConfig::default()
+        };
+        assert_eq!(create_observer(&cfg).name(), "prometheus");
+    }
+
     #[test]
     fn factory_unknown_falls_back_to_noop() {
         let cfg = ObservabilityConfig {
@@ -1,7 +1,12 @@
 use super::traits::{Observer, ObserverEvent, ObserverMetric};
 use std::any::Any;
+use std::panic::{catch_unwind, AssertUnwindSafe};
 
 /// Combine multiple observers — fan-out events to all backends
+///
+/// A child that panics is logged and skipped; the remaining children still
+/// receive the call. `as_any` returns the composite itself, so look up a
+/// specific backend with [`find_observer`] rather than a direct downcast.
 pub struct MultiObserver {
     observers: Vec<Box<dyn Observer>>,
 }
@@ -10,25 +15,45 @@ impl MultiObserver {
     pub fn new(observers: Vec<Box<dyn Observer>>) -> Self {
         Self { observers }
     }
+
+    /// Child observers, in fan-out order.
+    pub fn observers(&self) -> &[Box<dyn Observer>] {
+        &self.observers
+    }
+
+    fn for_each(&self, action: &str, f: impl Fn(&dyn Observer)) {
+        for obs in &self.observers {
+            if catch_unwind(AssertUnwindSafe(|| f(obs.as_ref()))).is_err() {
+                tracing::warn!(observer = obs.name(), "observer panicked during {action}");
+            }
+        }
+    }
+}
+
+/// Find an observer of type `T`, either `observer` itself or any child of a
+/// (possibly nested) [`MultiObserver`].
+pub fn find_observer<T: Observer + 'static>(observer: &dyn Observer) -> Option<&T> {
+    let any = observer.as_any();
+    if let Some(found) = any.downcast_ref::<T>() {
+        return Some(found);
+    }
+    any.downcast_ref::<MultiObserver>()?
+        .observers
+        .iter()
+        .find_map(|child| find_observer::<T>(child.as_ref()))
 }
 
 impl Observer for MultiObserver {
     fn record_event(&self, event: &ObserverEvent) {
-        for obs in &self.observers {
-            obs.record_event(event);
-        }
+        self.for_each("record_event", |obs| obs.record_event(event));
     }
 
     fn record_metric(&self, metric: &ObserverMetric) {
-        for obs in &self.observers {
-            obs.record_metric(metric);
-        }
+        self.for_each("record_metric", |obs| obs.record_metric(metric));
     }
 
     fn flush(&self) {
-        for obs in &self.observers {
-            obs.flush();
-        }
+        self.for_each("flush", |obs| obs.flush());
     }
 
     fn name(&self) -> &str {
@@ -160,4 +185,55 @@ mod tests {
         assert_eq!(fc1.load(Ordering::SeqCst), 1);
         assert_eq!(fc2.load(Ordering::SeqCst), 1);
     }
+
+    struct PanickingObserver;
+
+    impl Observer for PanickingObserver {
+        fn record_event(&self, _event: &ObserverEvent) {
+            panic!("broken sink");
+        }
+        fn record_metric(&self, _metric: &ObserverMetric) {
+            panic!("broken sink");
+        }
+        fn name(&self) -> &str {
+            "panicking"
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+    }
+
+    #[test]
+    fn multi_isolates_panicking_child() {
+        let ec = Arc::new(AtomicUsize::new(0));
+        let mc = Arc::new(AtomicUsize::new(0));
+        let fc = Arc::new(AtomicUsize::new(0));
+
+        let m = MultiObserver::new(vec![
+            Box::new(PanickingObserver),
+            Box::new(CountingObserver::new(ec.clone(), mc.clone(), fc.clone())),
+        ]);
+
+        m.record_event(&ObserverEvent::HeartbeatTick);
+        m.record_metric(&ObserverMetric::TokensUsed(1));
+        assert_eq!(ec.load(Ordering::SeqCst), 1);
+        assert_eq!(mc.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn find_observer_searches_nested_children() {
+        let counters = || Arc::new(AtomicUsize::new(0));
+        let inner = MultiObserver::new(vec![Box::new(CountingObserver::new(
+            counters(),
+            counters(),
+            counters(),
+        ))]);
+        let m = MultiObserver::new(vec![Box::new(PanickingObserver), Box::new(inner)]);
+
+        assert!(find_observer::<CountingObserver>(&m).is_some());
+        assert!(find_observer::<PanickingObserver>(&m).is_some());
+        assert!(find_observer::<MultiObserver>(&m).is_some());
+        assert!(find_observer::<CountingObserver>(&PanickingObserver).is_none());
+    }
 }
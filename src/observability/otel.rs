@@ -1,17 +1,52 @@
 use super::traits::{Observer, ObserverEvent, ObserverMetric};
+use crate::config::ObservabilityConfig;
 use opentelemetry::metrics::{Counter, Gauge, Histogram};
-use opentelemetry::trace::{Span, SpanKind, Status, Tracer};
-use opentelemetry::{global, KeyValue};
-use opentelemetry_otlp::WithExportConfig;
+use opentelemetry::trace::{
+    Span, SpanBuilder, SpanKind, Status, TraceContextExt, Tracer, TracerProvider,
+};
+use opentelemetry::{global, Context, KeyValue};
+use opentelemetry_otlp::{WithExportConfig, WithHttpConfig};
 use opentelemetry_sdk::metrics::SdkMeterProvider;
-use opentelemetry_sdk::trace::SdkTracerProvider;
+use opentelemetry_sdk::trace::{
+    BatchConfigBuilder, BatchSpanProcessor, SdkTracer, SdkTracerProvider,
+};
+use parking_lot::Mutex;
 use std::any::Any;
+use std::collections::HashMap;
 use std::time::SystemTime;
 
+/// Spans buffered for export when no queue size is configured.
+const DEFAULT_QUEUE_SIZE: usize = 2_048;
+
+/// Upper bound on tracked invocations; guards against leaks when an
+/// `AgentStart` never sees its matching `AgentEnd`.
+const MAX_OPEN_INVOCATIONS: usize = 1_024;
+
+/// Identifies the caller an `agent.invocation` span belongs to, so events
+/// recorded later by the same task or thread become its children.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum InvocationKey {
+    Task(tokio::task::Id),
+    Thread(std::thread::ThreadId),
+}
+
+impl InvocationKey {
+    fn current() -> Self {
+        tokio::task::try_id().map_or_else(|| Self::Thread(std::thread::current().id()), Self::Task)
+    }
+}
+
 /// OpenTelemetry-backed observer — exports traces and metrics via OTLP.
+///
+/// `agent.invocation` spans stay open between `AgentStart` and `AgentEnd`;
+/// LLM, tool, and error spans recorded in between are parented to them.
+/// Spans are exported in batches from a bounded queue, so an unreachable
+/// collector drops spans instead of blocking callers.
 pub struct OtelObserver {
     tracer_provider: SdkTracerProvider,
     meter_provider: SdkMeterProvider,
+    tracer: SdkTracer,
+    open_invocations: Mutex<HashMap<InvocationKey, Vec<Context>>>,
 
     // Metrics instruments
     agent_starts: Counter<u64>,
@@ -35,6 +70,26 @@ impl OtelObserver {
     /// Uses HTTP/protobuf transport (port 4318 by default).
     /// Falls back to `http://localhost:4318` if no endpoint is provided.
     pub fn new(endpoint: Option<&str>, service_name: Option<&str>) -> Result<Self, String> {
+        Self::build(endpoint, service_name, &HashMap::new(), DEFAULT_QUEUE_SIZE)
+    }
+
+    /// Create an OTel observer from the `[observability]` section, including
+    /// export headers and the span queue size.
+    pub fn from_config(config: &ObservabilityConfig) -> Result<Self, String> {
+        Self::build(
+            config.otel_endpoint.as_deref(),
+            config.otel_service_name.as_deref(),
+            &config.otel_headers,
+            config.otel_queue_size,
+        )
+    }
+
+    fn build(
+        endpoint: Option<&str>,
+        service_name: Option<&str>,
+        headers: &HashMap<String, String>,
+        queue_size: usize,
+    ) -> Result<Self, String> {
         let base_endpoint = endpoint.unwrap_or("http://localhost:4318");
         let traces_endpoint = format!("{}/v1/traces", base_endpoint.trim_end_matches('/'));
         let metrics_endpoint = format!("{}/v1/metrics", base_endpoint.trim_end_matches('/'));
@@ -44,11 +99,22 @@ impl OtelObserver {
         let span_exporter = opentelemetry_otlp::SpanExporter::builder()
             .with_http()
             .with_endpoint(&traces_endpoint)
+            .with_headers(headers.clone())
             .build()
             .map_err(|e| format!("Failed to create OTLP span exporter: {e}"))?;
 
+        // The batch processor drops new spans once the queue is full, so a
+        // down collector never blocks the caller.
+        let span_processor = BatchSpanProcessor::builder(span_exporter)
+            .with_batch_config(
+                BatchConfigBuilder::default()
+                    .with_max_queue_size(queue_size.max(1))
+                    .build(),
+            )
+            .build();
+
         let tracer_provider = SdkTracerProvider::builder()
-            .with_batch_exporter(span_exporter)
+            .with_span_processor(span_processor)
             .with_resource(
                 opentelemetry_sdk::Resource::builder()
                     .with_service_name(service_name.to_string())
@@ -57,11 +123,13 @@ impl OtelObserver {
             .build();
 
         global::set_tracer_provider(tracer_provider.clone());
+        let tracer = tracer_provider.tracer("zeroclaw");
 
         // ── Metric exporter ─────────────────────────────────────
         let metric_exporter = opentelemetry_otlp::MetricExporter::builder()
             .with_http()
             .with_endpoint(&metrics_endpoint)
+            .with_headers(headers.clone())
             .build()
             .map_err(|e| format!("Failed to create OTLP metric exporter: {e}"))?;
 
@@ -155,6 +223,8 @@ impl OtelObserver {
         Ok(Self {
             tracer_provider,
             meter_provider: meter_provider_clone,
+            tracer,
+            open_invocations: Mutex::new(HashMap::new()),
             agent_starts,
             agent_duration,
             llm_calls,
@@ -170,11 +240,47 @@ impl OtelObserver {
             queue_depth,
         })
     }
+
+    /// Context of the innermost open invocation for the current caller.
+    fn current_invocation(&self) -> Option<Context> {
+        self.open_invocations
+            .lock()
+            .get(&InvocationKey::current())
+            .and_then(|stack| stack.last().cloned())
+    }
+
+    /// Parent for a child span: the open invocation, else the ambient context.
+    fn parent_context(&self) -> Context {
+        self.current_invocation().unwrap_or_else(Context::current)
+    }
+
+    fn push_invocation(&self, cx: Context) {
+        let mut open = self.open_invocations.lock();
+        if open.len() >= MAX_OPEN_INVOCATIONS {
+            tracing::warn!(
+                "OTel observer dropped {} unfinished agent spans",
+                open.len()
+            );
+            open.clear();
+        }
+        open.entry(InvocationKey::current()).or_default().push(cx);
+    }
+
+    fn pop_invocation(&self) -> Option<Context> {
+        let key = InvocationKey::current();
+        let mut open = self.open_invocations.lock();
+        let stack = open.get_mut(&key)?;
+        let cx = stack.pop();
+        if stack.is_empty() {
+            open.remove(&key);
+        }
+        cx
+    }
 }
 
 impl Observer for OtelObserver {
     fn record_event(&self, event: &ObserverEvent) {
-        let tracer = global::tracer("zeroclaw");
+        let tracer = &self.tracer;
 
         match event {
             ObserverEvent::AgentStart { provider, model } => {
@@ -185,10 +291,41 @@ impl Observer for OtelObserver {
                         KeyValue::new("model", model.clone()),
                     ],
                 );
+
+                let parent = self.parent_context();
+                let span = tracer.build_with_context(
+                    SpanBuilder::from_name("agent.invocation")
+                        .with_kind(SpanKind::Internal)
+                        .with_attributes(vec![
+                            KeyValue::new("provider", provider.clone()),
+                            KeyValue::new("model", model.clone()),
+                        ]),
+                    &parent,
+                );
+                self.push_invocation(parent.with_span(span));
             }
-            ObserverEvent::LlmRequest { .. }
-            | ObserverEvent::ToolCallStart { .. }
-            | ObserverEvent::TurnComplete => {}
+            ObserverEvent::LlmRequest {
+                provider,
+                model,
+                messages_count,
+            } => {
+                if let Some(cx) = self.current_invocation() {
+                    cx.span().add_event(
+                        "llm.request",
+                        vec![
+                            KeyValue::new("provider", provider.clone()),
+                            KeyValue::new("model", model.clone()),
+                            KeyValue::new("messages_count", *messages_count as i64),
+                        ],
+                    );
+                }
+            }
+            ObserverEvent::ToolCallStart { .. }
+            | ObserverEvent::TurnComplete
+            | ObserverEvent::LlmCacheLookup { .. }
+            | ObserverEvent::MemoryCompaction { .. }
+            | ObserverEvent::MemoryDedupeSkipped { .. }
+            | ObserverEvent::PocketBaseWriteRetry { .. } => {}
             ObserverEvent::LlmResponse {
                 provider,
                 model,
@@ -211,8 +348,8 @@ impl Observer for OtelObserver {
                 let start_time = SystemTime::now()
                     .checked_sub(*duration)
                     .unwrap_or(SystemTime::now());
-                let mut span = tracer.build(
-                    opentelemetry::trace::SpanBuilder::from_name("llm.call")
+                let mut span = tracer.build_with_context(
+                    SpanBuilder::from_name("llm.call")
                         .with_kind(SpanKind::Internal)
                         .with_start_time(start_time)
                         .with_attributes(vec![
@@ -221,6 +358,7 @@ impl Observer for OtelObserver {
                             KeyValue::new("success", *success),
                             KeyValue::new("duration_s", secs),
                         ]),
+                    &self.parent_context(),
                 );
                 if *success {
                     span.set_status(Status::Ok);
@@ -241,24 +379,32 @@ impl Observer for OtelObserver {
                     .checked_sub(*duration)
                     .unwrap_or(SystemTime::now());
 
-                // Create a completed span with correct timing
-                let mut span = tracer.build(
-                    opentelemetry::trace::SpanBuilder::from_name("agent.invocation")
-                        .with_kind(SpanKind::Internal)
-                        .with_start_time(start_time)
-                        .with_attributes(vec![
-                            KeyValue::new("provider", provider.clone()),
-                            KeyValue::new("model", model.clone()),
-                            KeyValue::new("duration_s", secs),
-                        ]),
-                );
+                let mut attributes = vec![KeyValue::new("duration_s", secs)];
                 if let Some(t) = tokens_used {
-                    span.set_attribute(KeyValue::new("tokens_used", *t as i64));
+                    attributes.push(KeyValue::new("tokens_used", *t as i64));
                 }
                 if let Some(c) = cost_usd {
-                    span.set_attribute(KeyValue::new("cost_usd", *c));
+                    attributes.push(KeyValue::new("cost_usd", *c));
+                }
+
+                if let Some(cx) = self.pop_invocation() {
+                    let span = cx.span();
+                    span.set_attributes(attributes);
+                    span.end();
+                } else {
+                    // No matching AgentStart on this task: create a completed
+                    // span with correct timing instead.
+                    attributes.push(KeyValue::new("provider", provider.clone()));
+                    attributes.push(KeyValue::new("model", model.clone()));
+                    let mut span = tracer.build_with_context(
+                        SpanBuilder::from_name("agent.invocation")
+                            .with_kind(SpanKind::Internal)
+                            .with_start_time(start_time)
+                            .with_attributes(attributes),
+                        &Context::current(),
+                    );
+                    span.end();
                 }
-                span.end();
 
                 self.agent_duration.record(
                     secs,
@@ -286,8 +432,8 @@ impl Observer for OtelObserver {
                     Status::error("")
                 };
 
-                let mut span = tracer.build(
-                    opentelemetry::trace::SpanBuilder::from_name("tool.call")
+                let mut span = tracer.build_with_context(
+                    SpanBuilder::from_name("tool.call")
                         .with_kind(SpanKind::Internal)
                         .with_start_time(start_time)
                         .with_attributes(vec![
@@ -295,6 +441,7 @@ impl Observer for OtelObserver {
                             KeyValue::new("tool.success", *success),
                             KeyValue::new("duration_s", secs),
                         ]),
+                    &self.parent_context(),
                 );
                 span.set_status(status);
                 span.end();
@@ -307,6 +454,21 @@ impl Observer for OtelObserver {
                 self.tool_duration
                     .record(secs, &[KeyValue::new("tool", tool.clone())]);
             }
+            ObserverEvent::ToolCallBlocked { tool } => {
+                if let Some(cx) = self.current_invocation() {
+                    cx.span().add_event(
+                        "tool.blocked",
+                        vec![KeyValue::new("tool.name", tool.clone())],
+                    );
+                }
+                self.tool_calls.add(
+                    1,
+                    &[
+                        KeyValue::new("tool", tool.clone()),
+                        KeyValue::new("success", "blocked"),
+                    ],
+                );
+            }
             ObserverEvent::ChannelMessage { channel, direction } => {
                 self.channel_messages.add(
                     1,
@@ -321,16 +483,20 @@ impl Observer for OtelObserver {
             }
             ObserverEvent::Error { component, message } => {
                 // Create an error span for visibility in trace backends
-                let mut span = tracer.build(
-                    opentelemetry::trace::SpanBuilder::from_name("error")
+                let mut span = tracer.build_with_context(
+                    SpanBuilder::from_name("error")
                         .with_kind(SpanKind::Internal)
                         .with_attributes(vec![
                             KeyValue::new("component", component.clone()),
                             KeyValue::new("error.message", message.clone()),
                         ]),
+                    &self.parent_context(),
                 );
                 span.set_status(Status::error(message.clone()));
                 span.end();
+                if let Some(cx) = self.current_invocation() {
+                    cx.span().set_status(Status::error(message.clone()));
+                }
 
                 self.errors
                     .add(1, &[KeyValue::new("component", component.clone())]);
@@ -352,6 +518,8 @@ impl Observer for OtelObserver {
             ObserverMetric::QueueDepth(d) => {
                 self.queue_depth.record(*d as u64, &[]);
             }
+            ObserverMetric::PocketBaseOutboxDepth(_)
+            | ObserverMetric::OutboundQueueDepth { .. } => {}
         }
     }
 
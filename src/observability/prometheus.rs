@@ -10,12 +10,16 @@ pub struct PrometheusObserver {
     // Counters
     agent_starts: IntCounterVec,
     llm_requests: IntCounterVec,
+    llm_cache_lookups: IntCounterVec,
     tokens_input_total: IntCounterVec,
     tokens_output_total: IntCounterVec,
     tool_calls: IntCounterVec,
     channel_messages: IntCounterVec,
     heartbeat_ticks: prometheus::IntCounter,
     errors: IntCounterVec,
+    memory_compacted: IntCounterVec,
+    memory_dedupe_skipped: IntCounterVec,
+    pocketbase_write_retries: IntCounterVec,
 
     // Histograms
     agent_duration: HistogramVec,
@@ -26,6 +30,8 @@ pub struct PrometheusObserver {
     tokens_used: prometheus::IntGauge,
     active_sessions: GaugeVec,
     queue_depth: GaugeVec,
+    pocketbase_outbox_depth: prometheus::IntGauge,
+    outbound_queue_depth: prometheus::IntGaugeVec,
 }
 
 impl PrometheusObserver {
@@ -81,6 +87,42 @@ impl PrometheusObserver {
         )
         .expect("valid metric");
 
+        let memory_compacted = IntCounterVec::new(
+            prometheus::Opts::new(
+                "zeroclaw_memory_compacted_total",
+                "Total memory entries removed by retention, by reason",
+            ),
+            &["reason"],
+        )
+        .expect("valid metric");
+
+        let llm_cache_lookups = IntCounterVec::new(
+            prometheus::Opts::new(
+                "zeroclaw_llm_cache_lookups_total",
+                "Total provider response cache lookups, by result (hit, miss)",
+            ),
+            &["result"],
+        )
+        .expect("valid metric");
+
+        let memory_dedupe_skipped = IntCounterVec::new(
+            prometheus::Opts::new(
+                "zeroclaw_memory_dedupe_skipped_total",
+                "Total duplicate memory entries skipped at store time, by category",
+            ),
+            &["category"],
+        )
+        .expect("valid metric");
+
+        let pocketbase_write_retries = IntCounterVec::new(
+            prometheus::Opts::new(
+                "zeroclaw_pocketbase_write_retries_total",
+                "Total PocketBase writes retried after a transient failure, by operation",
+            ),
+            &["operation"],
+        )
+        .expect("valid metric");
+
         let agent_duration = HistogramVec::new(
             HistogramOpts::new(
                 "zeroclaw_agent_duration_seconds",
@@ -128,6 +170,21 @@ impl PrometheusObserver {
         )
         .expect("valid metric");
 
+        let pocketbase_outbox_depth = prometheus::IntGauge::new(
+            "zeroclaw_pocketbase_outbox_depth",
+            "Reply records waiting in the PocketBase outbox",
+        )
+        .expect("valid metric");
+
+        let outbound_queue_depth = prometheus::IntGaugeVec::new(
+            prometheus::Opts::new(
+                "zeroclaw_outbound_queue_depth",
+                "Replies waiting in a channel's outbound retry queue",
+            ),
+            &["channel"],
+        )
+        .expect("valid metric");
+
         // Register all metrics
         registry.register(Box::new(agent_starts.clone())).ok();
         registry.register(Box::new(llm_requests.clone())).ok();
@@ -139,29 +196,49 @@ impl PrometheusObserver {
         registry.register(Box::new(channel_messages.clone())).ok();
         registry.register(Box::new(heartbeat_ticks.clone())).ok();
         registry.register(Box::new(errors.clone())).ok();
+        registry.register(Box::new(llm_cache_lookups.clone())).ok();
+        registry.register(Box::new(memory_compacted.clone())).ok();
+        registry
+            .register(Box::new(memory_dedupe_skipped.clone()))
+            .ok();
+        registry
+            .register(Box::new(pocketbase_write_retries.clone()))
+            .ok();
         registry.register(Box::new(agent_duration.clone())).ok();
         registry.register(Box::new(tool_duration.clone())).ok();
         registry.register(Box::new(request_latency.clone())).ok();
         registry.register(Box::new(tokens_used.clone())).ok();
         registry.register(Box::new(active_sessions.clone())).ok();
         registry.register(Box::new(queue_depth.clone())).ok();
+        registry
+            .register(Box::new(pocketbase_outbox_depth.clone()))
+            .ok();
+        registry
+            .register(Box::new(outbound_queue_depth.clone()))
+            .ok();
 
         Self {
             registry,
             agent_starts,
             llm_requests,
+            llm_cache_lookups,
             tokens_input_total,
             tokens_output_total,
             tool_calls,
             channel_messages,
             heartbeat_ticks,
             errors,
+            memory_compacted,
+            memory_dedupe_skipped,
+            pocketbase_write_retries,
             agent_duration,
             tool_duration,
             request_latency,
             tokens_used,
             active_sessions,
             queue_depth,
+            pocketbase_outbox_depth,
+            outbound_queue_depth,
         }
     }
 
@@ -237,6 +314,11 @@ impl Observer for PrometheusObserver {
                     .with_label_values(&[tool.as_str()])
                     .observe(duration.as_secs_f64());
             }
+            ObserverEvent::ToolCallBlocked { tool } => {
+                self.tool_calls
+                    .with_label_values(&[tool.as_str(), "blocked"])
+                    .inc();
+            }
             ObserverEvent::ChannelMessage { channel, direction } => {
                 self.channel_messages
                     .with_label_values(&[channel, direction])
@@ -245,6 +327,28 @@ impl Observer for PrometheusObserver {
             ObserverEvent::HeartbeatTick => {
                 self.heartbeat_ticks.inc();
             }
+            ObserverEvent::LlmCacheLookup { model: _, hit } => {
+                let result = if *hit { "hit" } else { "miss" };
+                self.llm_cache_lookups.with_label_values(&[result]).inc();
+            }
+            ObserverEvent::MemoryCompaction { expired, evicted } => {
+                self.memory_compacted
+                    .with_label_values(&["expired"])
+                    .inc_by(*expired);
+                self.memory_compacted
+                    .with_label_values(&["evicted"])
+                    .inc_by(*evicted);
+            }
+            ObserverEvent::MemoryDedupeSkipped { category } => {
+                self.memory_dedupe_skipped
+                    .with_label_values(&[category])
+                    .inc();
+            }
+            ObserverEvent::PocketBaseWriteRetry { operation } => {
+                self.pocketbase_write_retries
+                    .with_label_values(&[operation])
+                    .inc();
+            }
             ObserverEvent::Error {
                 component,
                 message: _,
@@ -272,6 +376,15 @@ impl Observer for PrometheusObserver {
                     .with_label_values(&[] as &[&str])
                     .set(*d as f64);
             }
+            ObserverMetric::PocketBaseOutboxDepth(d) => {
+                self.pocketbase_outbox_depth
+                    .set(i64::try_from(*d).unwrap_or(i64::MAX));
+            }
+            ObserverMetric::OutboundQueueDepth { channel, depth } => {
+                self.outbound_queue_depth
+                    .with_label_values(&[channel.as_str()])
+                    .set(i64::try_from(*depth).unwrap_or(i64::MAX));
+            }
         }
     }
 
@@ -380,6 +493,21 @@ mod tests {
         assert!(output.contains("zeroclaw_heartbeat_ticks_total 3"));
     }
 
+    #[test]
+    fn llm_cache_lookups_count_hits_and_misses() {
+        let obs = PrometheusObserver::new();
+        for hit in [true, true, false] {
+            obs.record_event(&ObserverEvent::LlmCacheLookup {
+                model: "claude-sonnet".into(),
+                hit,
+            });
+        }
+
+        let output = obs.encode();
+        assert!(output.contains(r#"zeroclaw_llm_cache_lookups_total{result="hit"} 2"#));
+        assert!(output.contains(r#"zeroclaw_llm_cache_lookups_total{result="miss"} 1"#));
+    }
+
     #[test]
     fn tool_calls_track_success_and_failure_separately() {
         let obs = PrometheusObserver::new();
@@ -405,6 +533,18 @@ mod tests {
         assert!(output.contains(r#"zeroclaw_tool_calls_total{success="false",tool="shell"} 1"#));
     }
 
+    #[test]
+    fn blocked_tool_calls_get_their_own_outcome() {
+        let obs = PrometheusObserver::new();
+        obs.record_event(&ObserverEvent::ToolCallBlocked {
+            tool: "shell".into(),
+        });
+
+        let output = obs.encode();
+        assert!(output.contains(r#"zeroclaw_tool_calls_total{success="blocked",tool="shell"} 1"#));
+        assert!(!output.contains(r#"zeroclaw_tool_duration_seconds_count{tool="shell"}"#));
+    }
+
     #[test]
     fn errors_track_by_component() {
         let obs = PrometheusObserver::new();
@@ -321,8 +321,18 @@ mod tests {
     fn test_observability_config() -> ObservabilityConfig {
         ObservabilityConfig {
             backend: "none".to_string(),
+            backends: Vec::new(),
             otel_endpoint: None,
             otel_service_name: None,
+            otel_headers: std::collections::HashMap::new(),
+            otel_queue_size: 2_048,
+            alert_webhook_url: None,
+            alert_webhook_secret: None,
+            alert_llm_failure_threshold: 3,
+            alert_pocketbase_down_minutes: 10,
+            heartbeat_thread: None,
+            heartbeat_interval_hours: 24,
+            debug_payloads: false,
             runtime_trace_mode: "rolling".to_string(),
             runtime_trace_path: "state/runtime-trace.jsonl".to_string(),
             runtime_trace_max_entries: 3,
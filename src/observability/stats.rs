@@ -0,0 +1,170 @@
+use super::traits::{Observer, ObserverEvent, ObserverMetric};
+use crate::util::truncate_with_ellipsis;
+use parking_lot::Mutex;
+use std::any::Any;
+use std::collections::HashMap;
+use std::time::Instant;
+
+/// Distinct error messages tracked; rarer ones are folded into "other".
+const MAX_TRACKED_ERRORS: usize = 50;
+
+/// Longest error message kept per entry.
+const ERROR_MAX_CHARS: usize = 120;
+
+/// Point-in-time copy of [`RuntimeStatsObserver`] counters.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RuntimeStats {
+    pub uptime_secs: u64,
+    /// Inbound messages per channel (`webhook`, `chat`, ...).
+    pub inbound_messages: HashMap<String, u64>,
+    pub llm_calls: u64,
+    pub llm_failures: u64,
+    pub tool_runs: u64,
+    pub tool_failures: u64,
+    pub errors: u64,
+    /// Most frequent error messages, most frequent first.
+    pub top_errors: Vec<(String, u64)>,
+}
+
+#[derive(Default)]
+struct Counters {
+    inbound_messages: HashMap<String, u64>,
+    llm_calls: u64,
+    llm_failures: u64,
+    tool_runs: u64,
+    tool_failures: u64,
+    errors: u64,
+    error_messages: HashMap<String, u64>,
+}
+
+/// Cumulative counters since startup, used for the status heartbeat.
+pub struct RuntimeStatsObserver {
+    started_at: Instant,
+    counters: Mutex<Counters>,
+}
+
+impl Default for RuntimeStatsObserver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RuntimeStatsObserver {
+    pub fn new() -> Self {
+        Self {
+            started_at: Instant::now(),
+            counters: Mutex::new(Counters::default()),
+        }
+    }
+
+    /// Current counters with the `top` most frequent error messages.
+    pub fn snapshot(&self, top: usize) -> RuntimeStats {
+        let counters = self.counters.lock();
+        let mut top_errors: Vec<(String, u64)> = counters
+            .error_messages
+            .iter()
+            .map(|(message, count)| (message.clone(), *count))
+            .collect();
+        top_errors.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        top_errors.truncate(top);
+        RuntimeStats {
+            uptime_secs: self.started_at.elapsed().as_secs(),
+            inbound_messages: counters.inbound_messages.clone(),
+            llm_calls: counters.llm_calls,
+            llm_failures: counters.llm_failures,
+            tool_runs: counters.tool_runs,
+            tool_failures: counters.tool_failures,
+            errors: counters.errors,
+            top_errors,
+        }
+    }
+}
+
+impl Observer for RuntimeStatsObserver {
+    fn record_event(&self, event: &ObserverEvent) {
+        let mut counters = self.counters.lock();
+        match event {
+            ObserverEvent::ChannelMessage { channel, direction } if direction == "inbound" => {
+                *counters
+                    .inbound_messages
+                    .entry(channel.clone())
+                    .or_default() += 1;
+            }
+            ObserverEvent::LlmResponse { success, .. } => {
+                counters.llm_calls += 1;
+                if !success {
+                    counters.llm_failures += 1;
+                }
+            }
+            ObserverEvent::ToolCall { success, .. } => {
+                counters.tool_runs += 1;
+                if !success {
+                    counters.tool_failures += 1;
+                }
+            }
+            ObserverEvent::Error { component, message } => {
+                counters.errors += 1;
+                let message = truncate_with_ellipsis(
+                    &format!(
+                        "{component}: {}",
+                        crate::providers::sanitize_api_error(message)
+                    ),
+                    ERROR_MAX_CHARS,
+                );
+                let key = if counters.error_messages.contains_key(&message)
+                    || counters.error_messages.len() < MAX_TRACKED_ERRORS
+                {
+                    message
+                } else {
+                    "other".to_string()
+                };
+                *counters.error_messages.entry(key).or_default() += 1;
+            }
+            _ => {}
+        }
+    }
+
+    fn record_metric(&self, _metric: &ObserverMetric) {}
+
+    fn name(&self) -> &str {
+        "runtime-stats"
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn counts_messages_calls_and_top_errors() {
+        let obs = RuntimeStatsObserver::new();
+        for direction in ["inbound", "inbound", "outbound"] {
+            obs.record_event(&ObserverEvent::ChannelMessage {
+                channel: "webhook".into(),
+                direction: direction.into(),
+            });
+        }
+        obs.record_event(&ObserverEvent::ToolCall {
+            tool: "shell".into(),
+            duration: Duration::from_millis(1),
+            success: false,
+        });
+        for message in ["timeout", "timeout", "bad gateway"] {
+            obs.record_event(&ObserverEvent::Error {
+                component: "provider".into(),
+                message: message.into(),
+            });
+        }
+
+        let stats = obs.snapshot(1);
+        assert_eq!(stats.inbound_messages.get("webhook"), Some(&2));
+        assert_eq!((stats.tool_runs, stats.tool_failures), (1, 1));
+        assert_eq!(stats.errors, 3);
+        assert_eq!(stats.top_errors, vec![("provider: timeout".to_string(), 2)]);
+    }
+}
@@ -47,6 +47,9 @@ pub enum ObserverEvent {
         duration: Duration,
         success: bool,
     },
+    /// A tool call was refused by the security policy or an approval
+    /// prompt, so the tool did no work.
+    ToolCallBlocked { tool: String },
     /// The agent produced a final answer for the current user message.
     TurnComplete,
     /// A message was sent or received through a channel.
@@ -58,6 +61,23 @@ pub enum ObserverEvent {
     },
     /// Periodic heartbeat tick from the runtime keep-alive loop.
     HeartbeatTick,
+    /// A gateway provider call was looked up in the response cache
+    /// (`[provider] cache_ttl_secs`).
+    LlmCacheLookup { model: String, hit: bool },
+    /// A memory retention pass removed entries from the memory backend.
+    MemoryCompaction {
+        /// Conversation entries deleted because they outlived the TTL.
+        expired: u64,
+        /// Entries evicted (oldest first) because a category exceeded its cap.
+        evicted: u64,
+    },
+    /// A conversation memory entry was not stored because it duplicated a recent one.
+    MemoryDedupeSkipped { category: String },
+    /// A PocketBase write failed transiently and is being retried.
+    PocketBaseWriteRetry {
+        /// `"create"` or `"patch"`.
+        operation: String,
+    },
     /// An error occurred in a named component.
     Error {
         /// Subsystem where the error originated (e.g., `"provider"`, `"gateway"`).
@@ -81,6 +101,10 @@ pub enum ObserverMetric {
     ActiveSessions(u64),
     /// Current depth of the inbound message queue.
     QueueDepth(u64),
+    /// Reply records waiting in the PocketBase outbox for redelivery.
+    PocketBaseOutboxDepth(u64),
+    /// Replies waiting in a channel's outbound retry queue.
+    OutboundQueueDepth { channel: String, depth: u64 },
 }
 
 /// Core observability trait for recording agent runtime telemetry.
@@ -44,6 +44,9 @@ impl Observer for VerboseObserver {
                 let ms = u64::try_from(duration.as_millis()).unwrap_or(u64::MAX);
                 eprintln!("< Tool {tool} (success={success}, duration_ms={ms})");
             }
+            ObserverEvent::ToolCallBlocked { tool } => {
+                eprintln!("< Tool {tool} (blocked)");
+            }
             ObserverEvent::TurnComplete => {
                 eprintln!("< Complete");
             }
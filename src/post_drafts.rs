@@ -0,0 +1,411 @@
+//! Agent-proposed social posts that wait for a human decision.
+//!
+//! The `post_draft` tool stores proposals in the local store `drafts` table
+//! with status `pending` and mirrors each one to `posts/drafts/<id>.json`.
+//! Approving a draft sends it through its channel and records the outcome
+//! (`posted` with the post URI, or `failed` with the error) on the draft;
+//! rejecting or expiring it sets `rejected`. A failed draft may be approved
+//! again. A draft left `sending` by a restart mid-approval is marked `failed`
+//! at startup, since whether the post went out is unknown.
+
+use crate::bluesky::{resolve_workspace_image, BlueskyClient, PostImage, PostRequest};
+use crate::gateway::local_store::{self, PostDraftInput, PostHistoryInput};
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::fmt::Write as _;
+use std::path::Path;
+
+pub const DRAFTS_DIR: &str = "posts/drafts";
+
+/// Channels a draft can target.
+pub const SUPPORTED_CHANNELS: &[&str] = &["bluesky"];
+
+const SUMMARY_PREVIEW_CHARS: usize = 80;
+
+/// Result of an approve or reject request.
+#[derive(Debug)]
+pub enum Decision {
+    Missing,
+    /// The draft is not awaiting a decision; carries its current status.
+    Conflict(String),
+    Done(Value),
+}
+
+fn status_of(draft: &Value) -> &str {
+    draft.get("status").and_then(Value::as_str).unwrap_or("")
+}
+
+fn write_mirror(workspace_dir: &Path, draft: &Value) {
+    let Some(id) = draft.get("id").and_then(Value::as_str) else {
+        return;
+    };
+    let dir = workspace_dir.join(DRAFTS_DIR);
+    let result = std::fs::create_dir_all(&dir).and_then(|()| {
+        let body = serde_json::to_vec_pretty(draft).unwrap_or_default();
+        std::fs::write(dir.join(format!("{id}.json")), body)
+    });
+    if let Err(err) = result {
+        tracing::warn!("Failed to mirror post draft {id}: {err}");
+    }
+}
+
+fn reload(workspace_dir: &Path, draft_id: &str) -> Result<Value> {
+    let draft = local_store::get_draft(workspace_dir, draft_id)?
+        .with_context(|| format!("Draft {draft_id} disappeared"))?;
+    write_mirror(workspace_dir, &draft);
+    Ok(draft)
+}
+
+/// Build the post a draft would publish, resolving its media references.
+fn post_request(workspace_dir: &Path, text: &str, media: &[String]) -> Result<PostRequest> {
+    let images = media
+        .iter()
+        .map(|rel_path| {
+            Ok(PostImage {
+                path: resolve_workspace_image(workspace_dir, rel_path)?,
+                alt: String::new(),
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let request = PostRequest {
+        text: text.to_string(),
+        images,
+        link: None,
+    };
+    request.validate()?;
+    Ok(request)
+}
+
+/// Validate and store a new pending draft.
+pub fn create(
+    workspace_dir: &Path,
+    text: &str,
+    channel: &str,
+    media: Vec<String>,
+) -> Result<Value> {
+    let channel = channel.trim().to_ascii_lowercase();
+    if !SUPPORTED_CHANNELS.contains(&channel.as_str()) {
+        anyhow::bail!(
+            "Unsupported draft channel '{channel}' (expected one of: {})",
+            SUPPORTED_CHANNELS.join(", ")
+        );
+    }
+    post_request(workspace_dir, text, &media)?;
+    local_store::initialize(workspace_dir)?;
+    let draft = local_store::create_post_draft(
+        workspace_dir,
+        &PostDraftInput {
+            text: text.to_string(),
+            channel,
+            media,
+        },
+    )?;
+    write_mirror(workspace_dir, &draft);
+    Ok(draft)
+}
+
+pub fn reject(workspace_dir: &Path, draft_id: &str) -> Result<Decision> {
+    let Some(draft) = local_store::get_draft(workspace_dir, draft_id)? else {
+        return Ok(Decision::Missing);
+    };
+    if !local_store::transition_draft(
+        workspace_dir,
+        draft_id,
+        &["pending", "failed"],
+        "rejected",
+        None,
+        None,
+    )? {
+        return Ok(Decision::Conflict(status_of(&draft).to_string()));
+    }
+    Ok(Decision::Done(reload(workspace_dir, draft_id)?))
+}
+
+/// Publish a pending (or previously failed) draft. `bluesky` is the client
+/// used for `bluesky` drafts; `None` records a configuration failure.
+pub async fn approve(
+    workspace_dir: &Path,
+    draft_id: &str,
+    bluesky: Option<&BlueskyClient>,
+) -> Result<Decision> {
+    let Some(draft) = local_store::get_draft(workspace_dir, draft_id)? else {
+        return Ok(Decision::Missing);
+    };
+    if !local_store::transition_draft(
+        workspace_dir,
+        draft_id,
+        &["pending", "failed"],
+        "sending",
+        None,
+        None,
+    )? {
+        return Ok(Decision::Conflict(status_of(&draft).to_string()));
+    }
+
+    let text = draft.get("text").and_then(Value::as_str).unwrap_or("");
+    let media: Vec<String> = serde_json::from_value(draft["media"].clone()).unwrap_or_default();
+    let sent = async {
+        let client = bluesky.context(
+            "Bluesky credentials are not configured \
+             (set ZEROCLAW_BLUESKY_HANDLE and ZEROCLAW_BLUESKY_APP_PASSWORD)",
+        )?;
+        let request = post_request(workspace_dir, text, &media)?;
+        client.post(&request).await
+    }
+    .await;
+
+    match sent {
+        Ok(posted) => {
+            local_store::transition_draft(
+                workspace_dir,
+                draft_id,
+                &["sending"],
+                "posted",
+                Some(&posted.uri),
+                None,
+            )?;
+            let history = PostHistoryInput {
+                provider: "bluesky".into(),
+                text: text.to_string(),
+                video_name: String::new(),
+                source_path: media.first().cloned().unwrap_or_default(),
+                uri: posted.uri,
+                cid: posted.cid,
+                status: "success".into(),
+                error: String::new(),
+                created_at_client: None,
+            };
+            if let Err(err) = local_store::create_post_history(workspace_dir, &history) {
+                tracing::warn!("Failed to record post history for draft {draft_id}: {err}");
+            }
+        }
+        Err(err) => {
+            let message = crate::providers::sanitize_api_error(&err.to_string());
+            tracing::warn!("Approved draft {draft_id} failed to send: {message}");
+            local_store::transition_draft(
+                workspace_dir,
+                draft_id,
+                &["sending"],
+                "failed",
+                None,
+                Some(&message),
+            )?;
+        }
+    }
+    Ok(Decision::Done(reload(workspace_dir, draft_id)?))
+}
+
+/// Mark drafts whose send was cut off by a restart as `failed`, so they can
+/// be approved again or rejected. Returns how many were recovered.
+pub fn recover_interrupted(workspace_dir: &Path) -> Result<usize> {
+    let recovered = local_store::fail_sending_drafts(
+        workspace_dir,
+        "Interrupted before the post was confirmed; check the account before approving again",
+    )?;
+    for id in &recovered {
+        if let Ok(Some(draft)) = local_store::get_draft(workspace_dir, id) {
+            write_mirror(workspace_dir, &draft);
+        }
+    }
+    Ok(recovered.len())
+}
+
+/// Reject pending drafts older than `max_age`. Returns how many expired.
+pub fn expire(workspace_dir: &Path, max_age: chrono::Duration) -> Result<usize> {
+    let cutoff = (chrono::Utc::now() - max_age).to_rfc3339();
+    let expired = local_store::expire_pending_drafts(workspace_dir, &cutoff)?;
+    for id in &expired {
+        if let Ok(Some(draft)) = local_store::get_draft(workspace_dir, id) {
+            write_mirror(workspace_dir, &draft);
+        }
+    }
+    Ok(expired.len())
+}
+
+/// Whether a chat message asks to see pending drafts ("show my pending
+/// drafts", "/drafts").
+pub fn is_pending_drafts_request(message: &str) -> bool {
+    let normalized: String = message
+        .trim()
+        .to_ascii_lowercase()
+        .chars()
+        .filter(|c| c.is_alphanumeric() || c.is_whitespace() || *c == '/')
+        .collect();
+    let normalized = normalized.split_whitespace().collect::<Vec<_>>().join(" ");
+    normalized == "/drafts"
+        || (normalized.contains("pending drafts") && normalized.split(' ').count() <= 8)
+}
+
+/// Chat reply listing pending drafts, newest first.
+pub fn pending_summary(workspace_dir: &Path, limit: usize) -> Result<String> {
+    let drafts = local_store::list_drafts(workspace_dir, limit, Some("pending"))?;
+    if drafts.is_empty() {
+        return Ok("No drafts are waiting for approval.".to_string());
+    }
+    let mut out = format!("Pending drafts ({}):", drafts.len());
+    for draft in &drafts {
+        let text = draft.get("text").and_then(Value::as_str).unwrap_or("");
+        let _ = write!(
+            out,
+            "\n- {} [{}] {}",
+            draft.get("id").and_then(Value::as_str).unwrap_or(""),
+            draft.get("channel").and_then(Value::as_str).unwrap_or("?"),
+            crate::util::truncate_with_ellipsis(text.trim(), SUMMARY_PREVIEW_CHARS),
+        );
+        let media = draft["media"].as_array().map_or(0, Vec::len);
+        if media > 0 {
+            let _ = write!(out, " (+{media} media)");
+        }
+    }
+    out.push_str("\nApprove or reject them from the Drafts screen.");
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bluesky::BlueskyCredentials;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn workspace() -> tempfile::TempDir {
+        let tmp = tempfile::tempdir().unwrap();
+        local_store::initialize(tmp.path()).unwrap();
+        tmp
+    }
+
+    #[test]
+    fn recognizes_pending_drafts_requests() {
+        assert!(is_pending_drafts_request("Show my pending drafts?"));
+        assert!(is_pending_drafts_request("/drafts"));
+        assert!(!is_pending_drafts_request(
+            "write three drafts about pending tasks"
+        ));
+    }
+
+    #[test]
+    fn create_rejects_unknown_channel_and_mirrors_to_workspace() {
+        let tmp = workspace();
+        assert!(create(tmp.path(), "hi", "myspace", Vec::new()).is_err());
+
+        let draft = create(tmp.path(), "hello from the agent", "Bluesky", Vec::new()).unwrap();
+        let id = draft["id"].as_str().unwrap();
+        let mirror = tmp.path().join(DRAFTS_DIR).join(format!("{id}.json"));
+        let mirrored: Value = serde_json::from_slice(&std::fs::read(mirror).unwrap()).unwrap();
+        assert_eq!(mirrored["channel"], "bluesky");
+        assert!(pending_summary(tmp.path(), 10)
+            .unwrap()
+            .contains("hello from the agent"));
+    }
+
+    #[tokio::test]
+    async fn approve_posts_once_and_records_the_uri() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/xrpc/com.atproto.server.createSession"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "accessJwt": "a", "refreshJwt": "r", "did": "did:plc:me", "handle": "me.test"
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/xrpc/com.atproto.repo.createRecord"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "uri": "at://did:plc:me/app.bsky.feed.post/1", "cid": "c1"
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let client = BlueskyClient::new(
+            BlueskyCredentials {
+                service_url: server.uri(),
+                handle: "me.test".into(),
+                app_password: "pw".into(),
+            },
+            None,
+        );
+
+        let tmp = workspace();
+        let id = create(tmp.path(), "ship it", "bluesky", Vec::new()).unwrap()["id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        let Decision::Done(draft) = approve(tmp.path(), &id, Some(&client)).await.unwrap() else {
+            panic!("expected approval");
+        };
+        assert_eq!(draft["status"], "posted");
+        assert_eq!(draft["resultUri"], "at://did:plc:me/app.bsky.feed.post/1");
+        assert_eq!(
+            local_store::list_post_history(tmp.path(), 5).unwrap().len(),
+            1
+        );
+
+        assert!(matches!(
+            approve(tmp.path(), &id, Some(&client)).await.unwrap(),
+            Decision::Conflict(status) if status == "posted"
+        ));
+    }
+
+    #[tokio::test]
+    async fn approve_without_credentials_marks_failed_and_allows_reject() {
+        let tmp = workspace();
+        let id = create(tmp.path(), "later", "bluesky", Vec::new()).unwrap()["id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        let Decision::Done(draft) = approve(tmp.path(), &id, None).await.unwrap() else {
+            panic!("expected a recorded failure");
+        };
+        assert_eq!(draft["status"], "failed");
+        assert!(draft["error"].as_str().unwrap().contains("not configured"));
+
+        let Decision::Done(draft) = reject(tmp.path(), &id).unwrap() else {
+            panic!("expected rejection");
+        };
+        assert_eq!(draft["status"], "rejected");
+        assert!(matches!(
+            reject(tmp.path(), "missing").unwrap(),
+            Decision::Missing
+        ));
+    }
+
+    #[test]
+    fn interrupted_sends_become_failed_and_can_be_rejected() {
+        let tmp = workspace();
+        let id = create(tmp.path(), "mid-flight", "bluesky", Vec::new()).unwrap()["id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        assert!(local_store::transition_draft(
+            tmp.path(),
+            &id,
+            &["pending"],
+            "sending",
+            None,
+            None
+        )
+        .unwrap());
+
+        assert_eq!(recover_interrupted(tmp.path()).unwrap(), 1);
+        let draft = local_store::get_draft(tmp.path(), &id).unwrap().unwrap();
+        assert_eq!(draft["status"], "failed");
+        assert!(draft["error"].as_str().unwrap().contains("Interrupted"));
+        assert!(matches!(
+            reject(tmp.path(), &id).unwrap(),
+            Decision::Done(draft) if draft["status"] == "rejected"
+        ));
+    }
+
+    #[test]
+    fn expire_rejects_stale_pending_drafts() {
+        let tmp = workspace();
+        create(tmp.path(), "old news", "bluesky", Vec::new()).unwrap();
+        assert_eq!(expire(tmp.path(), chrono::Duration::hours(1)).unwrap(), 0);
+        assert_eq!(
+            expire(tmp.path(), chrono::Duration::seconds(-1)).unwrap(),
+            1
+        );
+        let rejected = local_store::list_drafts(tmp.path(), 10, Some("rejected")).unwrap();
+        assert_eq!(rejected[0]["error"], "expired");
+    }
+}
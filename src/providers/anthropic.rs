@@ -569,8 +569,9 @@ impl Provider for AnthropicProvider {
                 .header("anthropic-version", "2023-06-01");
             request = self.apply_auth(request, credential);
             // Send a minimal request; the goal is TLS + HTTP/2 setup, not a valid response.
-            // Anthropic has no lightweight GET endpoint, so we accept any non-network error.
-            let _ = request.send().await?;
+            // Anthropic has no lightweight GET endpoint, so only a refused key is an error.
+            let response = request.send().await?;
+            super::check_warmup_response("Anthropic", response).await?;
         }
         Ok(())
     }
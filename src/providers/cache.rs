@@ -0,0 +1,475 @@
+//! Response cache for repeated low-temperature prompts.
+//!
+//! With `[provider] cache_ttl_secs` set, the gateway wraps its provider in a
+//! [`CachedProvider`]. Plain chat calls at or below `cache_max_temperature`
+//! are keyed on a hash of (model, temperature, full message list) and served
+//! from `state/llm_cache/responses.db` until the TTL runs out. Tool-calling
+//! and streaming calls always reach the provider.
+//!
+//! A caller can skip the cache for one request, and learn whether its calls
+//! were answered from it, by running them inside [`with_cache_scope`].
+
+use super::traits::{
+    ChatMessage, ChatRequest, ChatResponse, ModelInfo, Provider, ProviderCapabilities,
+    ProviderIntrospection, StreamChunk, StreamOptions, StreamResult, ToolsPayload,
+};
+use crate::config::schema::ProviderConfig;
+use crate::observability::{Observer, ObserverEvent};
+use crate::tools::ToolSpec;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use futures_util::stream;
+use parking_lot::Mutex;
+use rusqlite::{params, Connection, OptionalExtension};
+use sha2::{Digest, Sha256};
+use std::future::Future;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Cache directory, relative to the workspace.
+pub const CACHE_DIR: &str = "state/llm_cache";
+
+/// Whether a request's provider calls were answered from the cache.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheOutcome {
+    Hit,
+    Miss,
+}
+
+impl CacheOutcome {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Hit => "hit",
+            Self::Miss => "miss",
+        }
+    }
+}
+
+struct CacheScope {
+    bypass: bool,
+    outcome: Mutex<Option<CacheOutcome>>,
+}
+
+tokio::task_local! {
+    static CACHE_SCOPE: CacheScope;
+}
+
+/// Run `fut`, skipping the cache when `bypass` is set, and report how its
+/// provider calls fared: `Hit` only when every lookup hit, `None` when
+/// nothing was looked up (cache off, temperature too high, or bypassed).
+pub async fn with_cache_scope<F: Future>(
+    bypass: bool,
+    fut: F,
+) -> (F::Output, Option<CacheOutcome>) {
+    let scope = CacheScope {
+        bypass,
+        outcome: Mutex::new(None),
+    };
+    CACHE_SCOPE
+        .scope(scope, async {
+            let output = fut.await;
+            let outcome = CACHE_SCOPE.with(|scope| *scope.outcome.lock());
+            (output, outcome)
+        })
+        .await
+}
+
+fn bypassed() -> bool {
+    CACHE_SCOPE.try_with(|scope| scope.bypass).unwrap_or(false)
+}
+
+fn note_outcome(outcome: CacheOutcome) {
+    let _ = CACHE_SCOPE.try_with(|scope| {
+        let mut current = scope.outcome.lock();
+        if *current != Some(CacheOutcome::Miss) {
+            *current = Some(outcome);
+        }
+    });
+}
+
+/// Stable key for one call. `kind` separates the entry points, which send
+/// different requests for the same messages.
+fn cache_key(kind: &str, model: &str, temperature: f64, messages: &[ChatMessage]) -> String {
+    let mut hasher = Sha256::new();
+    let payload = serde_json::json!({
+        "kind": kind,
+        "model": model,
+        "temperature": temperature.to_bits(),
+        "messages": messages,
+    });
+    hasher.update(payload.to_string().as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+fn now_secs() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+/// A [`Provider`] that answers repeated deterministic prompts from disk.
+pub struct CachedProvider {
+    inner: Arc<dyn Provider>,
+    db: Mutex<Connection>,
+    ttl: Duration,
+    max_entries: usize,
+    max_temperature: f64,
+    observer: Option<Arc<dyn Observer>>,
+}
+
+impl CachedProvider {
+    /// Wrap `inner` with the cache under `workspace_dir`, sized by the
+    /// `[provider]` cache settings.
+    pub fn open(
+        inner: Arc<dyn Provider>,
+        workspace_dir: &Path,
+        config: &ProviderConfig,
+    ) -> Result<Self> {
+        let dir = workspace_dir.join(CACHE_DIR);
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+        let db = Connection::open(dir.join("responses.db"))
+            .context("Failed to open the provider response cache")?;
+        db.execute_batch(
+            "CREATE TABLE IF NOT EXISTS responses (
+                key TEXT PRIMARY KEY,
+                response TEXT NOT NULL,
+                created_at INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS responses_created_at ON responses (created_at);",
+        )
+        .context("Failed to prepare the provider response cache")?;
+        Ok(Self {
+            inner,
+            db: Mutex::new(db),
+            ttl: Duration::from_secs(config.cache_ttl_secs),
+            max_entries: config.cache_max_entries.max(1),
+            max_temperature: config.cache_max_temperature,
+            observer: None,
+        })
+    }
+
+    pub fn with_observer(mut self, observer: Arc<dyn Observer>) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    fn oldest_valid(&self) -> i64 {
+        now_secs().saturating_sub(i64::try_from(self.ttl.as_secs()).unwrap_or(i64::MAX))
+    }
+
+    fn lookup(&self, key: &str) -> Option<String> {
+        let result = self
+            .db
+            .lock()
+            .query_row(
+                "SELECT response FROM responses WHERE key = ?1 AND created_at > ?2",
+                params![key, self.oldest_valid()],
+                |row| row.get(0),
+            )
+            .optional();
+        result.unwrap_or_else(|err| {
+            tracing::warn!("Provider cache lookup failed: {err}");
+            None
+        })
+    }
+
+    /// Store a response, then drop expired entries and the oldest beyond
+    /// `max_entries`.
+    fn store(&self, key: &str, response: &str) {
+        let db = self.db.lock();
+        let result = db
+            .execute(
+                "INSERT OR REPLACE INTO responses (key, response, created_at) VALUES (?1, ?2, ?3)",
+                params![key, response, now_secs()],
+            )
+            .and_then(|_| {
+                db.execute(
+                    "DELETE FROM responses WHERE created_at <= ?1 OR key IN (
+                        SELECT key FROM responses ORDER BY created_at DESC, rowid DESC
+                        LIMIT -1 OFFSET ?2
+                    )",
+                    params![
+                        self.oldest_valid(),
+                        i64::try_from(self.max_entries).unwrap_or(i64::MAX)
+                    ],
+                )
+            });
+        if let Err(err) = result {
+            tracing::warn!("Provider cache write failed: {err}");
+        }
+    }
+
+    fn record_lookup(&self, model: &str, outcome: CacheOutcome) {
+        note_outcome(outcome);
+        if let Some(observer) = &self.observer {
+            observer.record_event(&ObserverEvent::LlmCacheLookup {
+                model: model.to_string(),
+                hit: outcome == CacheOutcome::Hit,
+            });
+        }
+    }
+
+    /// Answer from the cache when the call qualifies, otherwise (or on a
+    /// miss) run `call` and remember a non-empty answer.
+    async fn cached<F>(
+        &self,
+        key: impl FnOnce() -> String,
+        model: &str,
+        temperature: f64,
+        call: F,
+    ) -> Result<String>
+    where
+        F: Future<Output = Result<String>>,
+    {
+        if temperature > self.max_temperature || bypassed() {
+            return call.await;
+        }
+        let key = key();
+        if let Some(response) = self.lookup(&key) {
+            self.record_lookup(model, CacheOutcome::Hit);
+            return Ok(response);
+        }
+        self.record_lookup(model, CacheOutcome::Miss);
+        let response = call.await?;
+        if !response.trim().is_empty() {
+            self.store(&key, &response);
+        }
+        Ok(response)
+    }
+}
+
+#[async_trait]
+impl Provider for CachedProvider {
+    fn capabilities(&self) -> ProviderCapabilities {
+        self.inner.capabilities()
+    }
+
+    fn convert_tools(&self, tools: &[ToolSpec]) -> ToolsPayload {
+        self.inner.convert_tools(tools)
+    }
+
+    async fn chat_with_system(
+        &self,
+        system_prompt: Option<&str>,
+        message: &str,
+        model: &str,
+        temperature: f64,
+    ) -> Result<String> {
+        let key = || {
+            let mut messages: Vec<ChatMessage> =
+                system_prompt.map(ChatMessage::system).into_iter().collect();
+            messages.push(ChatMessage::user(message));
+            cache_key("system", model, temperature, &messages)
+        };
+        self.cached(
+            key,
+            model,
+            temperature,
+            self.inner
+                .chat_with_system(system_prompt, message, model, temperature),
+        )
+        .await
+    }
+
+    async fn chat_with_history(
+        &self,
+        messages: &[ChatMessage],
+        model: &str,
+        temperature: f64,
+    ) -> Result<String> {
+        self.cached(
+            || cache_key("history", model, temperature, messages),
+            model,
+            temperature,
+            self.inner.chat_with_history(messages, model, temperature),
+        )
+        .await
+    }
+
+    async fn chat(
+        &self,
+        request: ChatRequest<'_>,
+        model: &str,
+        temperature: f64,
+    ) -> Result<ChatResponse> {
+        self.inner.chat(request, model, temperature).await
+    }
+
+    fn supports_native_tools(&self) -> bool {
+        self.inner.supports_native_tools()
+    }
+
+    fn supports_vision(&self) -> bool {
+        self.inner.supports_vision()
+    }
+
+    async fn warmup(&self) -> Result<()> {
+        self.inner.warmup().await
+    }
+
+    async fn list_models(&self, default_model: &str) -> Result<Vec<ModelInfo>> {
+        self.inner.list_models(default_model).await
+    }
+
+    fn introspection(&self) -> Option<&dyn ProviderIntrospection> {
+        self.inner.introspection()
+    }
+
+    async fn chat_with_tools(
+        &self,
+        messages: &[ChatMessage],
+        tools: &[serde_json::Value],
+        model: &str,
+        temperature: f64,
+    ) -> Result<ChatResponse> {
+        self.inner
+            .chat_with_tools(messages, tools, model, temperature)
+            .await
+    }
+
+    fn supports_streaming(&self) -> bool {
+        self.inner.supports_streaming()
+    }
+
+    fn stream_chat_with_system(
+        &self,
+        system_prompt: Option<&str>,
+        message: &str,
+        model: &str,
+        temperature: f64,
+        options: StreamOptions,
+    ) -> stream::BoxStream<'static, StreamResult<StreamChunk>> {
+        self.inner
+            .stream_chat_with_system(system_prompt, message, model, temperature, options)
+    }
+
+    fn stream_chat_with_history(
+        &self,
+        messages: &[ChatMessage],
+        model: &str,
+        temperature: f64,
+        options: StreamOptions,
+    ) -> stream::BoxStream<'static, StreamResult<StreamChunk>> {
+        self.inner
+            .stream_chat_with_history(messages, model, temperature, options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[derive(Default)]
+    struct CountingProvider {
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl Provider for CountingProvider {
+        async fn chat_with_system(
+            &self,
+            _system_prompt: Option<&str>,
+            message: &str,
+            _model: &str,
+            _temperature: f64,
+        ) -> Result<String> {
+            let n = self.calls.fetch_add(1, Ordering::SeqCst) + 1;
+            Ok(format!("answer {n} to {message}"))
+        }
+    }
+
+    fn cached(
+        workspace: &Path,
+        ttl_secs: u64,
+        max_entries: usize,
+    ) -> (CachedProvider, Arc<CountingProvider>) {
+        let inner = Arc::new(CountingProvider::default());
+        let config = ProviderConfig {
+            cache_ttl_secs: ttl_secs,
+            cache_max_entries: max_entries,
+            ..ProviderConfig::default()
+        };
+        let provider = CachedProvider::open(inner.clone(), workspace, &config).unwrap();
+        (provider, inner)
+    }
+
+    fn history(text: &str) -> Vec<ChatMessage> {
+        vec![ChatMessage::system("Summarize."), ChatMessage::user(text)]
+    }
+
+    #[tokio::test]
+    async fn identical_deterministic_prompts_are_answered_once() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (provider, inner) = cached(tmp.path(), 3600, 100);
+
+        let (first, outcome) =
+            with_cache_scope(false, provider.chat_with_history(&history("day"), "m", 0.0)).await;
+        assert_eq!(first.unwrap(), "answer 1 to day");
+        assert_eq!(outcome, Some(CacheOutcome::Miss));
+
+        let (second, outcome) =
+            with_cache_scope(false, provider.chat_with_history(&history("day"), "m", 0.0)).await;
+        assert_eq!(second.unwrap(), "answer 1 to day");
+        assert_eq!(outcome, Some(CacheOutcome::Hit));
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 1);
+
+        // Any change to the key reaches the provider.
+        provider
+            .chat_with_history(&history("night"), "m", 0.0)
+            .await
+            .unwrap();
+        provider
+            .chat_with_history(&history("day"), "other-model", 0.0)
+            .await
+            .unwrap();
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 3);
+
+        assert!(tmp.path().join(CACHE_DIR).join("responses.db").exists());
+    }
+
+    #[tokio::test]
+    async fn warm_temperatures_and_bypassed_requests_skip_the_cache() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (provider, inner) = cached(tmp.path(), 3600, 100);
+
+        for _ in 0..2 {
+            let (_, outcome) =
+                with_cache_scope(false, provider.chat_with_history(&history("a"), "m", 0.7)).await;
+            assert_eq!(outcome, None);
+        }
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 2);
+
+        provider.simple_chat("b", "m", 0.0).await.unwrap();
+        let (response, outcome) = with_cache_scope(true, provider.simple_chat("b", "m", 0.0)).await;
+        assert_eq!(response.unwrap(), "answer 4 to b");
+        assert_eq!(outcome, None);
+    }
+
+    #[tokio::test]
+    async fn expired_and_overflowing_entries_are_dropped() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (provider, inner) = cached(tmp.path(), 3600, 2);
+        for text in ["one", "two", "three"] {
+            provider.simple_chat(text, "m", 0.0).await.unwrap();
+        }
+        let rows: i64 = provider
+            .db
+            .lock()
+            .query_row("SELECT COUNT(*) FROM responses", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(rows, 2);
+        provider.simple_chat("three", "m", 0.0).await.unwrap();
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 3);
+        provider.simple_chat("one", "m", 0.0).await.unwrap();
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 4);
+
+        provider
+            .db
+            .lock()
+            .execute("UPDATE responses SET created_at = created_at - 7200", [])
+            .unwrap();
+        provider.simple_chat("three", "m", 0.0).await.unwrap();
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 5);
+    }
+}
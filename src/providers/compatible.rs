@@ -1690,12 +1690,13 @@ impl Provider for OpenAiCompatibleProvider {
         if let Some(credential) = self.credential.as_ref() {
             // Hit the chat completions URL with a GET to establish the connection pool.
             // The server will likely return 405 Method Not Allowed, which is fine -
-            // the goal is TLS handshake and HTTP/2 negotiation.
+            // the goal is TLS handshake and HTTP/2 negotiation. A refused key is not.
             let url = self.chat_completions_url();
-            let _ = self
+            let response = self
                 .apply_auth_header(self.http_client().get(&url), credential)
                 .send()
                 .await?;
+            super::check_warmup_response(&self.name, response).await?;
         }
         Ok(())
     }
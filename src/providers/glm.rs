@@ -261,12 +261,13 @@ impl Provider for GlmProvider {
         let token = self.generate_token()?;
         let url = format!("{}/chat/completions", self.base_url);
         // GET will likely return 405 but establishes the TLS + HTTP/2 connection pool.
-        let _ = self
+        let response = self
             .client
             .get(&url)
             .header("Authorization", format!("Bearer {token}"))
             .send()
             .await?;
+        super::check_warmup_response("GLM", response).await?;
         Ok(())
     }
 }
@@ -18,6 +18,7 @@
 
 pub mod anthropic;
 pub mod bedrock;
+pub mod cache;
 pub mod compatible;
 pub mod copilot;
 pub mod gemini;
@@ -32,8 +33,9 @@ pub mod traits;
 
 #[allow(unused_imports)]
 pub use traits::{
-    ChatMessage, ChatRequest, ChatResponse, ConversationMessage, Provider, ProviderCapabilityError,
-    ToolCall, ToolResultMessage,
+    BackendHealth, ChatMessage, ChatRequest, ChatResponse, CircuitState, ConversationMessage,
+    ModelInfo, ModelPricing, Provider, ProviderCapabilityError, ProviderIntrospection,
+    ProviderStatus, ToolCall, ToolResultMessage,
 };
 
 use crate::auth::AuthService;
@@ -42,6 +44,10 @@ use reliable::ReliableProvider;
 use serde::Deserialize;
 use std::path::PathBuf;
 
+/// Provider used when `default_provider` is unset.
+pub const DEFAULT_PROVIDER: &str = "openrouter";
+/// Model used when `default_model` is unset.
+pub const DEFAULT_MODEL: &str = "anthropic/claude-sonnet-4";
 const MAX_API_ERROR_CHARS: usize = 200;
 const MINIMAX_INTL_BASE_URL: &str = "https://api.minimax.io/v1";
 const MINIMAX_CN_BASE_URL: &str = "https://api.minimaxi.com/v1";
@@ -780,6 +786,22 @@ pub async fn api_error(provider: &str, response: reqwest::Response) -> anyhow::E
     anyhow::anyhow!("{provider} API error ({status}): {sanitized}")
 }
 
+/// Fail a warmup response only when the credential was refused. Warmups
+/// send bare requests that a healthy endpoint answers with 400 or 405, so
+/// any other status still counts as a working connection.
+pub async fn check_warmup_response(
+    provider: &str,
+    response: reqwest::Response,
+) -> anyhow::Result<()> {
+    if matches!(
+        response.status(),
+        reqwest::StatusCode::UNAUTHORIZED | reqwest::StatusCode::FORBIDDEN
+    ) {
+        return Err(api_error(provider, response).await);
+    }
+    Ok(())
+}
+
 /// Resolve API key for a provider from config and environment variables.
 ///
 /// Resolution order:
@@ -793,7 +815,10 @@ pub async fn api_error(provider: &str, response: reqwest::Response) -> anyhow::E
 /// For MiniMax, OAuth mode supports `api_key = "minimax-oauth"`, resolving credentials from
 /// `MINIMAX_OAUTH_TOKEN` first, then `MINIMAX_API_KEY`, and finally
 /// `MINIMAX_OAUTH_REFRESH_TOKEN` (automatic access-token refresh).
-fn resolve_provider_credential(name: &str, credential_override: Option<&str>) -> Option<String> {
+pub(crate) fn resolve_provider_credential(
+    name: &str,
+    credential_override: Option<&str>,
+) -> Option<String> {
     let mut minimax_oauth_placeholder_requested = false;
 
     if let Some(raw_override) = credential_override {
@@ -950,7 +975,7 @@ pub fn create_provider_with_url(
 
 /// Factory: create provider with optional base URL and runtime options.
 #[allow(clippy::too_many_lines)]
-fn create_provider_with_url_and_options(
+pub(crate) fn create_provider_with_url_and_options(
     name: &str,
     api_key: Option<&str>,
     api_url: Option<&str>,
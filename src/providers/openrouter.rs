@@ -1,7 +1,8 @@
 use crate::multimodal;
 use crate::providers::traits::{
     ChatMessage, ChatRequest as ProviderChatRequest, ChatResponse as ProviderChatResponse,
-    Provider, ProviderCapabilities, TokenUsage, ToolCall as ProviderToolCall,
+    ModelInfo, ModelPricing, Provider, ProviderCapabilities, TokenUsage,
+    ToolCall as ProviderToolCall,
 };
 use crate::tools::ToolSpec;
 use async_trait::async_trait;
@@ -145,6 +146,44 @@ struct NativeResponseMessage {
     tool_calls: Option<Vec<NativeToolCall>>,
 }
 
+#[derive(Debug, Deserialize)]
+struct ModelsResponse {
+    data: Vec<CatalogModel>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CatalogModel {
+    id: String,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    context_length: Option<u64>,
+    #[serde(default)]
+    pricing: Option<CatalogPricing>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CatalogPricing {
+    #[serde(default)]
+    prompt: Option<String>,
+    #[serde(default)]
+    completion: Option<String>,
+}
+
+impl From<CatalogModel> for ModelInfo {
+    fn from(model: CatalogModel) -> Self {
+        Self {
+            id: model.id,
+            name: model.name,
+            context_length: model.context_length,
+            pricing: model.pricing.map(|p| ModelPricing {
+                prompt: p.prompt,
+                completion: p.completion,
+            }),
+        }
+    }
+}
+
 impl OpenRouterProvider {
     const DEPRECATED_FREE_MODEL_IDS: [&str; 1] = ["google/gemini-2.5-flash:free"];
 
@@ -333,6 +372,21 @@ impl Provider for OpenRouterProvider {
         Ok(())
     }
 
+    async fn list_models(&self, _default_model: &str) -> anyhow::Result<Vec<ModelInfo>> {
+        let mut request = self
+            .http_client()
+            .get("https://openrouter.ai/api/v1/models");
+        if let Some(credential) = self.credential.as_ref() {
+            request = request.header("Authorization", format!("Bearer {credential}"));
+        }
+        let response = request.send().await?;
+        if !response.status().is_success() {
+            return Err(super::api_error("OpenRouter", response).await);
+        }
+        let catalog: ModelsResponse = response.json().await?;
+        Ok(catalog.data.into_iter().map(ModelInfo::from).collect())
+    }
+
     async fn chat_with_system(
         &self,
         system_prompt: Option<&str>,
@@ -735,6 +789,26 @@ mod tests {
         assert_eq!(response.choices[0].message.content, "Hi from OpenRouter");
     }
 
+    #[test]
+    fn models_response_maps_context_and_pricing() {
+        let json = r#"{"data":[
+            {"id":"anthropic/claude-sonnet-4","name":"Claude Sonnet 4","context_length":200000,
+             "pricing":{"prompt":"0.000003","completion":"0.000015","image":"0"}},
+            {"id":"openrouter/free"}
+        ]}"#;
+
+        let catalog: ModelsResponse = serde_json::from_str(json).unwrap();
+        let models: Vec<ModelInfo> = catalog.data.into_iter().map(ModelInfo::from).collect();
+
+        assert_eq!(models.len(), 2);
+        assert_eq!(models[0].context_length, Some(200_000));
+        assert_eq!(
+            models[0].pricing.as_ref().unwrap().completion.as_deref(),
+            Some("0.000015")
+        );
+        assert_eq!(models[1], ModelInfo::id_only("openrouter/free"));
+    }
+
     #[test]
     fn response_deserializes_empty_choices() {
         let json = r#"{"choices":[]}"#;
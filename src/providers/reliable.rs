@@ -1,12 +1,19 @@
 use super::traits::{
-    ChatMessage, ChatRequest, ChatResponse, StreamChunk, StreamOptions, StreamResult,
+    BackendHealth, ChatMessage, ChatRequest, ChatResponse, CircuitState, ModelInfo,
+    ProviderIntrospection, ProviderStatus, StreamChunk, StreamOptions, StreamResult,
 };
 use super::Provider;
 use async_trait::async_trait;
 use futures_util::{stream, StreamExt};
+use parking_lot::Mutex;
 use std::collections::HashMap;
 use std::sync::atomic::{AtomicUsize, Ordering};
-use std::time::Duration;
+use std::time::{Duration, Instant};
+
+/// Consecutive retryable failures that open a backend's circuit.
+const CIRCUIT_FAILURE_THRESHOLD: u32 = 3;
+/// How long an open circuit skips its backend before letting a call through.
+const CIRCUIT_OPEN_SECS: u64 = 60;
 
 // ── Error Classification ─────────────────────────────────────────────────
 // Errors are split into retryable (transient server/network failures) and
@@ -221,6 +228,35 @@ fn push_failure(
 // Loop invariant: `failures` accumulates every failed attempt so the final
 // error message gives operators a complete diagnostic trail.
 
+// ── Circuit Breaker ──────────────────────────────────────────────────────
+// Each backend counts consecutive retryable failures. Past the threshold its
+// circuit opens and the chain skips it for `CIRCUIT_OPEN_SECS`, so an outage
+// on the primary stops costing every request a full retry cycle. When every
+// circuit is open the chain still tries them all rather than failing fast.
+
+#[derive(Debug, Default)]
+struct BackendCounters {
+    successes: u64,
+    failures: u64,
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+    last_error: Option<String>,
+    last_failure_at: Option<chrono::DateTime<chrono::Utc>>,
+    last_success_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl BackendCounters {
+    fn is_open(&self, now: Instant) -> bool {
+        self.open_until.is_some_and(|until| until > now)
+    }
+}
+
+#[derive(Debug, Default)]
+struct ChainHealth {
+    backends: Vec<BackendCounters>,
+    active: Option<usize>,
+}
+
 /// Provider wrapper with retry, fallback, auth rotation, and model failover.
 pub struct ReliableProvider {
     providers: Vec<(String, Box<dyn Provider>)>,
@@ -231,6 +267,8 @@ pub struct ReliableProvider {
     key_index: AtomicUsize,
     /// Per-model fallback chains: model_name → [fallback_model_1, fallback_model_2, ...]
     model_fallbacks: HashMap<String, Vec<String>>,
+    /// Per-backend counters and circuit state, indexed like `providers`.
+    health: Mutex<ChainHealth>,
 }
 
 impl ReliableProvider {
@@ -239,6 +277,10 @@ impl ReliableProvider {
         max_retries: u32,
         base_backoff_ms: u64,
     ) -> Self {
+        let health = ChainHealth {
+            backends: providers.iter().map(|_| BackendCounters::default()).collect(),
+            active: None,
+        };
         Self {
             providers,
             max_retries,
@@ -246,6 +288,7 @@ impl ReliableProvider {
             api_keys: Vec::new(),
             key_index: AtomicUsize::new(0),
             model_fallbacks: HashMap::new(),
+            health: Mutex::new(health),
         }
     }
 
@@ -279,6 +322,47 @@ impl ReliableProvider {
         Some(&self.api_keys[idx])
     }
 
+    /// Whether backend `index` should be tried. Open circuits are skipped
+    /// unless every backend is open.
+    fn circuit_allows(&self, index: usize) -> bool {
+        let now = Instant::now();
+        let health = self.health.lock();
+        let Some(backend) = health.backends.get(index) else {
+            return true;
+        };
+        !backend.is_open(now) || health.backends.iter().all(|b| b.is_open(now))
+    }
+
+    fn record_success(&self, index: usize) {
+        let mut health = self.health.lock();
+        if let Some(backend) = health.backends.get_mut(index) {
+            backend.successes += 1;
+            backend.consecutive_failures = 0;
+            backend.open_until = None;
+            backend.last_success_at = Some(chrono::Utc::now());
+        }
+        health.active = Some(index);
+    }
+
+    /// Count a failed attempt. Only retryable failures (outages, rate limits)
+    /// move the circuit; client errors say nothing about backend health.
+    fn record_failure(&self, index: usize, error_detail: &str, trips_circuit: bool) {
+        let mut health = self.health.lock();
+        let Some(backend) = health.backends.get_mut(index) else {
+            return;
+        };
+        backend.failures += 1;
+        backend.last_error = Some(error_detail.to_string());
+        backend.last_failure_at = Some(chrono::Utc::now());
+        if trips_circuit {
+            backend.consecutive_failures = backend.consecutive_failures.saturating_add(1);
+            if backend.consecutive_failures >= CIRCUIT_FAILURE_THRESHOLD {
+                backend.open_until =
+                    Some(Instant::now() + Duration::from_secs(CIRCUIT_OPEN_SECS));
+            }
+        }
+    }
+
     /// Compute backoff duration, respecting Retry-After if present.
     fn compute_backoff(&self, base: u64, err: &anyhow::Error) -> u64 {
         if let Some(retry_after) = parse_retry_after_ms(err) {
@@ -290,8 +374,61 @@ impl ReliableProvider {
     }
 }
 
+impl ProviderIntrospection for ReliableProvider {
+    fn status(&self) -> ProviderStatus {
+        let now = Instant::now();
+        let health = self.health.lock();
+        let backends: Vec<BackendHealth> = self
+            .providers
+            .iter()
+            .zip(&health.backends)
+            .map(|((name, _), counters)| {
+                let open = counters.is_open(now);
+                BackendHealth {
+                    name: name.clone(),
+                    successes: counters.successes,
+                    failures: counters.failures,
+                    consecutive_failures: counters.consecutive_failures,
+                    circuit: if open {
+                        CircuitState::Open
+                    } else {
+                        CircuitState::Closed
+                    },
+                    retry_in_secs: counters
+                        .open_until
+                        .filter(|_| open)
+                        .map(|until| until.saturating_duration_since(now).as_secs()),
+                    last_error: counters.last_error.clone(),
+                    last_failure_at: counters.last_failure_at.map(|t| t.to_rfc3339()),
+                    last_success_at: counters.last_success_at.map(|t| t.to_rfc3339()),
+                }
+            })
+            .collect();
+        let active = health
+            .active
+            .filter(|index| !health.backends[*index].is_open(now))
+            .or_else(|| health.backends.iter().position(|b| !b.is_open(now)))
+            .and_then(|index| self.providers.get(index))
+            .map(|(name, _)| name.clone());
+        ProviderStatus { active, backends }
+    }
+
+    fn reset_circuits(&self) {
+        let mut health = self.health.lock();
+        for backend in &mut health.backends {
+            backend.consecutive_failures = 0;
+            backend.open_until = None;
+        }
+        health.active = None;
+    }
+}
+
 #[async_trait]
 impl Provider for ReliableProvider {
+    fn introspection(&self) -> Option<&dyn ProviderIntrospection> {
+        Some(self)
+    }
+
     async fn warmup(&self) -> anyhow::Result<()> {
         for (name, provider) in &self.providers {
             tracing::info!(provider = name, "Warming up provider connection pool");
@@ -302,6 +439,27 @@ impl Provider for ReliableProvider {
         Ok(())
     }
 
+    /// Catalog of the first reachable backend, in failover order.
+    async fn list_models(&self, default_model: &str) -> anyhow::Result<Vec<ModelInfo>> {
+        let mut last_error = None;
+        for (index, (name, provider)) in self.providers.iter().enumerate() {
+            if !self.circuit_allows(index) {
+                continue;
+            }
+            match provider.list_models(default_model).await {
+                Ok(models) => return Ok(models),
+                Err(err) => {
+                    tracing::warn!(provider = name, "Model listing failed: {err}");
+                    last_error = Some(err);
+                }
+            }
+        }
+        match last_error {
+            Some(err) => Err(err),
+            None => Ok(vec![ModelInfo::id_only(default_model)]),
+        }
+    }
+
     async fn chat_with_system(
         &self,
         system_prompt: Option<&str>,
@@ -317,7 +475,14 @@ impl Provider for ReliableProvider {
         // immediately. On non-retryable error, break to next provider. On
         // retryable error, sleep with exponential backoff and retry.
         for current_model in &models {
-            for (provider_name, provider) in &self.providers {
+            for (index, (provider_name, provider)) in self.providers.iter().enumerate() {
+                if !self.circuit_allows(index) {
+                    tracing::debug!(
+                        provider = provider_name,
+                        "Circuit open, skipping provider"
+                    );
+                    continue;
+                }
                 let mut backoff_ms = self.base_backoff_ms;
 
                 for attempt in 0..=self.max_retries {
@@ -335,6 +500,7 @@ impl Provider for ReliableProvider {
                                     "Provider recovered (failover/retry)"
                                 );
                             }
+                            self.record_success(index);
                             return Ok(resp);
                         }
                         Err(e) => {
@@ -353,6 +519,11 @@ impl Provider for ReliableProvider {
                                 failure_reason,
                                 &error_detail,
                             );
+                            self.record_failure(
+                                index,
+                                &error_detail,
+                                !non_retryable,
+                            );
 
                             // Rate-limit with rotatable keys: cycle to the next API key
                             // so the retry hits a different quota bucket.
@@ -437,7 +608,14 @@ impl Provider for ReliableProvider {
         let mut failures = Vec::new();
 
         for current_model in &models {
-            for (provider_name, provider) in &self.providers {
+            for (index, (provider_name, provider)) in self.providers.iter().enumerate() {
+                if !self.circuit_allows(index) {
+                    tracing::debug!(
+                        provider = provider_name,
+                        "Circuit open, skipping provider"
+                    );
+                    continue;
+                }
                 let mut backoff_ms = self.base_backoff_ms;
 
                 for attempt in 0..=self.max_retries {
@@ -455,6 +633,7 @@ impl Provider for ReliableProvider {
                                     "Provider recovered (failover/retry)"
                                 );
                             }
+                            self.record_success(index);
                             return Ok(resp);
                         }
                         Err(e) => {
@@ -473,6 +652,11 @@ impl Provider for ReliableProvider {
                                 failure_reason,
                                 &error_detail,
                             );
+                            self.record_failure(
+                                index,
+                                &error_detail,
+                                !non_retryable,
+                            );
 
                             if rate_limited && !non_retryable_rate_limit {
                                 if let Some(new_key) = self.rotate_key() {
@@ -561,7 +745,14 @@ impl Provider for ReliableProvider {
         let mut failures = Vec::new();
 
         for current_model in &models {
-            for (provider_name, provider) in &self.providers {
+            for (index, (provider_name, provider)) in self.providers.iter().enumerate() {
+                if !self.circuit_allows(index) {
+                    tracing::debug!(
+                        provider = provider_name,
+                        "Circuit open, skipping provider"
+                    );
+                    continue;
+                }
                 let mut backoff_ms = self.base_backoff_ms;
 
                 for attempt in 0..=self.max_retries {
@@ -579,6 +770,7 @@ impl Provider for ReliableProvider {
                                     "Provider recovered (failover/retry)"
                                 );
                             }
+                            self.record_success(index);
                             return Ok(resp);
                         }
                         Err(e) => {
@@ -597,6 +789,11 @@ impl Provider for ReliableProvider {
                                 failure_reason,
                                 &error_detail,
                             );
+                            self.record_failure(
+                                index,
+                                &error_detail,
+                                !non_retryable,
+                            );
 
                             if rate_limited && !non_retryable_rate_limit {
                                 if let Some(new_key) = self.rotate_key() {
@@ -671,7 +868,14 @@ impl Provider for ReliableProvider {
         let mut failures = Vec::new();
 
         for current_model in &models {
-            for (provider_name, provider) in &self.providers {
+            for (index, (provider_name, provider)) in self.providers.iter().enumerate() {
+                if !self.circuit_allows(index) {
+                    tracing::debug!(
+                        provider = provider_name,
+                        "Circuit open, skipping provider"
+                    );
+                    continue;
+                }
                 let mut backoff_ms = self.base_backoff_ms;
 
                 for attempt in 0..=self.max_retries {
@@ -690,6 +894,7 @@ impl Provider for ReliableProvider {
                                     "Provider recovered (failover/retry)"
                                 );
                             }
+                            self.record_success(index);
                             return Ok(resp);
                         }
                         Err(e) => {
@@ -708,6 +913,11 @@ impl Provider for ReliableProvider {
                                 failure_reason,
                                 &error_detail,
                             );
+                            self.record_failure(
+                                index,
+                                &error_detail,
+                                !non_retryable,
+                            );
 
                             if rate_limited && !non_retryable_rate_limit {
                                 if let Some(new_key) = self.rotate_key() {
@@ -1004,6 +1214,83 @@ mod tests {
         assert_eq!(fallback_calls.load(Ordering::SeqCst), 1);
     }
 
+    #[tokio::test]
+    async fn open_circuit_skips_failing_primary_until_reset() {
+        let primary_calls = Arc::new(AtomicUsize::new(0));
+        let fallback_calls = Arc::new(AtomicUsize::new(0));
+        let provider = ReliableProvider::new(
+            vec![
+                (
+                    "primary".into(),
+                    Box::new(MockProvider {
+                        calls: Arc::clone(&primary_calls),
+                        fail_until_attempt: usize::MAX,
+                        response: "never",
+                        error: "503 service unavailable",
+                    }),
+                ),
+                (
+                    "fallback".into(),
+                    Box::new(MockProvider {
+                        calls: Arc::clone(&fallback_calls),
+                        fail_until_attempt: 0,
+                        response: "from fallback",
+                        error: "fallback down",
+                    }),
+                ),
+            ],
+            2,
+            1,
+        );
+        let introspection = provider.introspection().unwrap();
+        assert_eq!(introspection.status().active.as_deref(), Some("primary"));
+
+        provider.simple_chat("hello", "test", 0.0).await.unwrap();
+        assert_eq!(primary_calls.load(Ordering::SeqCst), 3);
+        let status = introspection.status();
+        assert_eq!(status.active.as_deref(), Some("fallback"));
+        assert_eq!(status.backends[0].failures, 3);
+        assert_eq!(status.backends[0].circuit, CircuitState::Open);
+        assert!(status.backends[0].retry_in_secs.is_some());
+        assert_eq!(status.backends[1].successes, 1);
+
+        // Open circuit: the primary is not called again.
+        provider.simple_chat("hello", "test", 0.0).await.unwrap();
+        assert_eq!(primary_calls.load(Ordering::SeqCst), 3);
+
+        introspection.reset_circuits();
+        let status = introspection.status();
+        assert_eq!(status.backends[0].circuit, CircuitState::Closed);
+        assert_eq!(status.backends[0].failures, 3);
+        assert_eq!(status.active.as_deref(), Some("primary"));
+        provider.simple_chat("hello", "test", 0.0).await.unwrap();
+        assert_eq!(primary_calls.load(Ordering::SeqCst), 6);
+    }
+
+    #[tokio::test]
+    async fn non_retryable_errors_do_not_open_circuit() {
+        let provider = ReliableProvider::new(
+            vec![(
+                "primary".into(),
+                Box::new(MockProvider {
+                    calls: Arc::new(AtomicUsize::new(0)),
+                    fail_until_attempt: usize::MAX,
+                    response: "never",
+                    error: "401 Unauthorized",
+                }),
+            )],
+            2,
+            1,
+        );
+        for _ in 0..4 {
+            assert!(provider.simple_chat("hello", "test", 0.0).await.is_err());
+        }
+        let status = provider.introspection().unwrap().status();
+        assert_eq!(status.backends[0].failures, 4);
+        assert_eq!(status.backends[0].consecutive_failures, 0);
+        assert_eq!(status.backends[0].circuit, CircuitState::Closed);
+    }
+
     #[tokio::test]
     async fn returns_aggregated_error_when_all_providers_fail() {
         let provider = ReliableProvider::new(
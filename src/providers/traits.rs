@@ -254,6 +254,81 @@ pub enum ToolsPayload {
     PromptGuided { instructions: String },
 }
 
+/// Circuit-breaker state of one backend in a failover chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    /// Calls flow normally.
+    Closed,
+    /// Skipped after repeated failures until the cooldown elapses or a reset.
+    Open,
+}
+
+/// Health counters for one backend in a failover chain.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackendHealth {
+    pub name: String,
+    pub successes: u64,
+    pub failures: u64,
+    pub consecutive_failures: u32,
+    pub circuit: CircuitState,
+    /// Seconds until an open circuit lets calls through again.
+    pub retry_in_secs: Option<u64>,
+    pub last_error: Option<String>,
+    pub last_failure_at: Option<String>,
+    pub last_success_at: Option<String>,
+}
+
+/// Snapshot of a failover chain: which backend served last and per-backend health.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderStatus {
+    /// Backend that handled the most recent successful call, or the first
+    /// backend with a closed circuit before any call succeeded.
+    pub active: Option<String>,
+    pub backends: Vec<BackendHealth>,
+}
+
+/// Per-token pricing as reported by the provider catalog (USD, decimal strings).
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelPricing {
+    pub prompt: Option<String>,
+    pub completion: Option<String>,
+}
+
+/// One entry of a provider's model catalog.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelInfo {
+    pub id: String,
+    pub name: Option<String>,
+    pub context_length: Option<u64>,
+    pub pricing: Option<ModelPricing>,
+}
+
+impl ModelInfo {
+    /// Catalog entry carrying only an id.
+    pub fn id_only(id: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            name: None,
+            context_length: None,
+            pricing: None,
+        }
+    }
+}
+
+/// Optional runtime view into providers that fail over between backends.
+pub trait ProviderIntrospection: Send + Sync {
+    fn status(&self) -> ProviderStatus;
+
+    /// Close every circuit and clear consecutive-failure counts so the
+    /// primary is tried again on the next call.
+    fn reset_circuits(&self);
+}
+
 #[async_trait]
 pub trait Provider: Send + Sync {
     /// Query provider capabilities.
@@ -389,11 +464,25 @@ pub trait Provider: Send + Sync {
     }
 
     /// Warm up the HTTP connection pool (TLS handshake, DNS, HTTP/2 setup).
-    /// Default implementation is a no-op; providers with HTTP clients should override.
+    /// Overrides fail when the provider refuses the credential, which is what
+    /// `doctor` relies on. Default implementation is a no-op; providers with
+    /// HTTP clients should override.
     async fn warmup(&self) -> anyhow::Result<()> {
         Ok(())
     }
 
+    /// Model catalog available to the configured credentials. Providers
+    /// without a listing API return just `default_model`.
+    async fn list_models(&self, default_model: &str) -> anyhow::Result<Vec<ModelInfo>> {
+        Ok(vec![ModelInfo::id_only(default_model)])
+    }
+
+    /// Failover/circuit-breaker introspection. `None` for single-backend
+    /// providers; the resilient wrapper overrides this.
+    fn introspection(&self) -> Option<&dyn ProviderIntrospection> {
+        None
+    }
+
     /// Chat with tool definitions for native function calling support.
     /// The default implementation falls back to chat_with_history and returns
     /// an empty tool_calls vector (prompt-based tool use only).
@@ -0,0 +1,230 @@
+//! Bluesky secrets shared by the daemon and the desktop app.
+//!
+//! The app keeps the login in the `bluesky.credentials` keyring entry and the
+//! current session in `bluesky.session`, both as JSON. Older builds wrote
+//! snake_case keys and newer ones write camelCase, so both are accepted.
+//! Environment variables win over the keyring; the keyring is only read when
+//! the crate is built with the `keyring` feature.
+
+use crate::bluesky::{BlueskyCredentials, BlueskySession, DEFAULT_SERVICE_URL};
+use serde::{Deserialize, Serialize};
+
+/// Keyring service the desktop app stores Bluesky secrets under.
+pub const BLUESKY_SECRET_SERVICE: &str = "com.example.myskyposter";
+pub const BLUESKY_CREDENTIALS_ACCOUNT: &str = "bluesky.credentials";
+pub const BLUESKY_SESSION_ACCOUNT: &str = "bluesky.session";
+
+pub const BLUESKY_SESSION_ENV: &str = "ZEROCLAW_BLUESKY_SESSION";
+
+/// `bluesky.credentials` keyring entry.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BlueskyCredentialsSecret {
+    #[serde(alias = "service_url")]
+    pub service_url: String,
+    pub handle: String,
+    #[serde(alias = "app_password")]
+    pub app_password: String,
+}
+
+impl BlueskyCredentialsSecret {
+    pub fn parse(raw: &str) -> Option<Self> {
+        serde_json::from_str(raw.trim()).ok()
+    }
+
+    /// Trimmed login details, or `None` unless both the handle and app
+    /// password are filled in. An empty service URL means bsky.social.
+    pub fn normalize(&self) -> Option<BlueskyCredentials> {
+        let handle = self.handle.trim();
+        let app_password = self.app_password.trim();
+        if handle.is_empty() || app_password.is_empty() {
+            return None;
+        }
+        let service_url = self.service_url.trim().trim_end_matches('/');
+        Some(BlueskyCredentials {
+            service_url: if service_url.is_empty() {
+                DEFAULT_SERVICE_URL.to_string()
+            } else {
+                service_url.to_string()
+            },
+            handle: handle.to_string(),
+            app_password: app_password.to_string(),
+        })
+    }
+}
+
+/// `bluesky.session` keyring entry.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BlueskySessionSecret {
+    #[serde(alias = "access_jwt")]
+    pub access_jwt: String,
+    #[serde(alias = "refresh_jwt")]
+    pub refresh_jwt: String,
+    pub did: String,
+    pub handle: String,
+}
+
+impl BlueskySessionSecret {
+    pub fn parse(raw: &str) -> Option<Self> {
+        serde_json::from_str(raw.trim()).ok()
+    }
+
+    /// Trimmed session, or `None` when the access token, DID, or handle is
+    /// missing. A missing refresh token is kept as empty.
+    pub fn normalize(&self) -> Option<BlueskySession> {
+        let access_jwt = self.access_jwt.trim();
+        let did = self.did.trim();
+        let handle = self.handle.trim();
+        if access_jwt.is_empty() || did.is_empty() || handle.is_empty() {
+            return None;
+        }
+        Some(BlueskySession {
+            access_jwt: access_jwt.to_string(),
+            refresh_jwt: self.refresh_jwt.trim().to_string(),
+            did: did.to_string(),
+            handle: handle.to_string(),
+        })
+    }
+}
+
+/// Read a keyring entry, trimmed. Missing and blank entries are `None`.
+#[cfg(feature = "keyring")]
+pub fn read_keyring_secret(service: &str, account: &str) -> anyhow::Result<Option<String>> {
+    use anyhow::Context;
+
+    let entry = keyring::Entry::new(service, account).context("failed to open keyring entry")?;
+    match entry.get_password() {
+        Ok(value) => {
+            let trimmed = value.trim();
+            Ok((!trimmed.is_empty()).then(|| trimmed.to_string()))
+        }
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(err) => Err(err).context("failed to read keyring secret"),
+    }
+}
+
+/// Without the `keyring` feature there is no keyring to read.
+#[cfg(not(feature = "keyring"))]
+pub fn read_keyring_secret(_service: &str, _account: &str) -> anyhow::Result<Option<String>> {
+    Ok(None)
+}
+
+fn keyring_value(account: &str) -> Option<String> {
+    match read_keyring_secret(BLUESKY_SECRET_SERVICE, account) {
+        Ok(value) => value,
+        Err(err) => {
+            tracing::warn!("Bluesky keyring entry {account} unavailable: {err:#}");
+            None
+        }
+    }
+}
+
+/// Login from `ZEROCLAW_BLUESKY_*`, falling back to the app's keyring entry.
+pub fn load_bluesky_credentials() -> Option<BlueskyCredentials> {
+    BlueskyCredentials::from_env().or_else(|| {
+        keyring_value(BLUESKY_CREDENTIALS_ACCOUNT)
+            .and_then(|raw| BlueskyCredentialsSecret::parse(&raw))
+            .and_then(|secret| secret.normalize())
+    })
+}
+
+/// Session from `ZEROCLAW_BLUESKY_SESSION`, falling back to the app's
+/// keyring entry.
+pub fn load_bluesky_session() -> Option<BlueskySession> {
+    std::env::var(BLUESKY_SESSION_ENV)
+        .ok()
+        .or_else(|| keyring_value(BLUESKY_SESSION_ACCOUNT))
+        .and_then(|raw| BlueskySessionSecret::parse(&raw))
+        .and_then(|secret| secret.normalize())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn credentials_accept_camel_and_snake_case() {
+        let camel = BlueskyCredentialsSecret::parse(
+            r#"{"serviceUrl":" https://pds.example/ ","handle":" alice.bsky.social ","appPassword":"abcd-efgh"}"#,
+        )
+        .unwrap();
+        let snake = BlueskyCredentialsSecret::parse(
+            r#"{"service_url":"https://pds.example","handle":"alice.bsky.social","app_password":" abcd-efgh "}"#,
+        )
+        .unwrap();
+
+        let expected = camel.normalize().unwrap();
+        assert_eq!(expected.service_url, "https://pds.example");
+        assert_eq!(expected.handle, "alice.bsky.social");
+        assert_eq!(expected.app_password, "abcd-efgh");
+        let from_snake = snake.normalize().unwrap();
+        assert_eq!(from_snake.service_url, expected.service_url);
+        assert_eq!(from_snake.app_password, expected.app_password);
+    }
+
+    #[test]
+    fn credentials_round_trip_in_camel_case() {
+        let secret = BlueskyCredentialsSecret {
+            service_url: "https://bsky.social".into(),
+            handle: "alice.bsky.social".into(),
+            app_password: "abcd-efgh".into(),
+        };
+        let raw = serde_json::to_string(&secret).unwrap();
+        assert!(raw.contains("\"appPassword\""));
+        assert_eq!(BlueskyCredentialsSecret::parse(&raw), Some(secret));
+    }
+
+    #[test]
+    fn partial_credentials_are_not_usable() {
+        let no_password =
+            BlueskyCredentialsSecret::parse(r#"{"handle":"alice.bsky.social","appPassword":"  "}"#)
+                .unwrap();
+        assert!(no_password.normalize().is_none());
+
+        let no_service = BlueskyCredentialsSecret::parse(
+            r#"{"handle":"alice.bsky.social","appPassword":"abcd"}"#,
+        )
+        .unwrap()
+        .normalize()
+        .unwrap();
+        assert_eq!(no_service.service_url, DEFAULT_SERVICE_URL);
+
+        assert!(BlueskyCredentialsSecret::parse("not json").is_none());
+    }
+
+    #[test]
+    fn session_accepts_camel_and_snake_case() {
+        let camel = BlueskySessionSecret::parse(
+            r#"{"accessJwt":"a","refreshJwt":"r","did":"did:plc:1","handle":"alice"}"#,
+        )
+        .unwrap();
+        let snake = BlueskySessionSecret::parse(
+            r#"{"access_jwt":" a ","refresh_jwt":"r","did":"did:plc:1","handle":"alice"}"#,
+        )
+        .unwrap();
+        assert_eq!(camel.normalize(), snake.normalize());
+
+        let session = camel.normalize().unwrap();
+        assert_eq!(session.access_jwt, "a");
+        assert_eq!(session.refresh_jwt, "r");
+        let raw = serde_json::to_string(&camel).unwrap();
+        assert_eq!(BlueskySessionSecret::parse(&raw), Some(camel));
+    }
+
+    #[test]
+    fn partial_session_requires_access_token_did_and_handle() {
+        let no_refresh =
+            BlueskySessionSecret::parse(r#"{"accessJwt":"a","did":"did:plc:1","handle":"alice"}"#)
+                .unwrap()
+                .normalize()
+                .unwrap();
+        assert!(no_refresh.refresh_jwt.is_empty());
+
+        let no_did = BlueskySessionSecret::parse(r#"{"accessJwt":"a","handle":"alice"}"#).unwrap();
+        assert!(no_did.normalize().is_none());
+        let no_access =
+            BlueskySessionSecret::parse(r#"{"did":"did:plc:1","handle":"alice"}"#).unwrap();
+        assert!(no_access.normalize().is_none());
+    }
+}
@@ -35,9 +35,10 @@ struct FailedAttemptState {
 
 /// Manages pairing state for the gateway.
 ///
-/// Bearer tokens are stored as SHA-256 hashes to prevent plaintext exposure
-/// in config files. When a new token is generated, the plaintext is returned
-/// to the client once, and only the hash is retained.
+/// Bearer tokens are stored as salted SHA-256 hashes (`sha256$<salt>$<hash>`)
+/// to prevent plaintext exposure in config files. When a new token is
+/// generated, the plaintext is returned to the client once, and only the hash
+/// is retained.
 // TODO: I've just made this work with parking_lot but it should use either flume or tokio's async mutexes
 #[derive(Debug, Clone)]
 pub struct PairingGuard {
@@ -45,8 +46,10 @@ pub struct PairingGuard {
     require_pairing: bool,
     /// One-time pairing code (generated on startup, consumed on first pair).
     pairing_code: Arc<Mutex<Option<String>>>,
-    /// Set of SHA-256 hashed bearer tokens (persisted across restarts).
+    /// Stored bearer token hashes (persisted across restarts).
     paired_tokens: Arc<Mutex<HashSet<String>>>,
+    /// Whether any existing token was salted on load and should be saved back.
+    migrated_on_load: bool,
     /// Brute-force protection: per-client failed attempt state + last sweep timestamp.
     failed_attempts: Arc<Mutex<(HashMap<String, FailedAttemptState>, Instant)>>,
 }
@@ -57,20 +60,27 @@ impl PairingGuard {
     /// If `require_pairing` is true and no tokens exist yet, a fresh
     /// pairing code is generated and returned via `pairing_code()`.
     ///
-    /// Existing tokens are accepted in both forms:
-    /// - Plaintext (`zc_...`): hashed on load for backward compatibility
-    /// - Already hashed (64-char hex): stored as-is
+    /// Existing tokens are accepted in three forms:
+    /// - Salted (`sha256$<salt>$<hash>`): stored as-is
+    /// - Unsalted legacy hash (64-char hex): salted on load
+    /// - Plaintext (`zc_...`): hashed and salted on load
+    ///
+    /// `tokens()` returns the migrated forms; when [`Self::migrated_on_load`]
+    /// is true the caller should save them so the weaker entries leave disk.
     pub fn new(require_pairing: bool, existing_tokens: &[String]) -> Self {
         let tokens: HashSet<String> = existing_tokens
             .iter()
             .map(|t| {
-                if is_token_hash(t) {
+                if is_salted_token_hash(t) {
                     t.clone()
+                } else if is_token_hash(t) {
+                    salt_token_digest(&t.to_ascii_lowercase())
                 } else {
-                    hash_token(t)
+                    salted_hash_token(t)
                 }
             })
             .collect();
+        let migrated_on_load = existing_tokens.iter().any(|t| !is_salted_token_hash(t));
         let code = if require_pairing && tokens.is_empty() {
             Some(generate_code())
         } else {
@@ -80,6 +90,7 @@ impl PairingGuard {
             require_pairing,
             pairing_code: Arc::new(Mutex::new(code)),
             paired_tokens: Arc::new(Mutex::new(tokens)),
+            migrated_on_load,
             failed_attempts: Arc::new(Mutex::new((HashMap::new(), Instant::now()))),
         }
     }
@@ -145,7 +156,7 @@ impl PairingGuard {
                     }
                     let token = generate_token();
                     let mut tokens = self.paired_tokens.lock();
-                    tokens.insert(hash_token(&token));
+                    tokens.insert(salted_hash_token(&token));
 
                     // Consume the pairing code so it cannot be reused
                     *pairing_code = None;
@@ -212,9 +223,16 @@ impl PairingGuard {
         if !self.require_pairing {
             return true;
         }
-        let hashed = hash_token(token);
         let tokens = self.paired_tokens.lock();
-        tokens.contains(&hashed)
+        tokens
+            .iter()
+            .any(|stored| stored_token_matches(stored, token))
+    }
+
+    /// Whether `new` salted any plaintext or legacy token, so `tokens()`
+    /// differs from what the config holds.
+    pub fn migrated_on_load(&self) -> bool {
+        self.migrated_on_load
     }
 
     /// Returns true if the gateway is already paired (has at least one token).
@@ -223,7 +241,7 @@ impl PairingGuard {
         !tokens.is_empty()
     }
 
-    /// Get all paired token hashes (for persisting to config).
+    /// Get all stored token hashes (for persisting to config).
     pub fn tokens(&self) -> Vec<String> {
         let tokens = self.paired_tokens.lock();
         tokens.iter().cloned().collect()
@@ -282,17 +300,66 @@ fn generate_token() -> String {
     format!("zc_{}", hex::encode(bytes))
 }
 
-/// SHA-256 hash a bearer token for storage. Returns lowercase hex.
+/// Scheme prefix of salted token hashes.
+const SALTED_HASH_SCHEME: &str = "sha256";
+
+/// Unsalted SHA-256 of a bearer token, as older releases stored it.
+/// Returns lowercase hex.
 fn hash_token(token: &str) -> String {
     format!("{:x}", Sha256::digest(token.as_bytes()))
 }
 
-/// Check if a stored value looks like a SHA-256 hash (64 hex chars)
+/// SHA-256 of `salt` followed by the token's unsalted digest, so a legacy
+/// hash can be salted without the token. Returns lowercase hex.
+fn hash_digest_with_salt(digest: &str, salt: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(salt.as_bytes());
+    hasher.update(digest.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+/// Salt an unsalted token digest with a fresh random salt:
+/// `sha256$<32 hex salt>$<64 hex hash>`.
+fn salt_token_digest(digest: &str) -> String {
+    let salt = hex::encode(rand::random::<[u8; 16]>());
+    let hash = hash_digest_with_salt(digest, &salt);
+    format!("{SALTED_HASH_SCHEME}${salt}${hash}")
+}
+
+/// Hash a bearer token for storage with a fresh random salt.
+fn salted_hash_token(token: &str) -> String {
+    salt_token_digest(&hash_token(token))
+}
+
+/// Split a `sha256$<salt>$<hash>` value into its salt and hash.
+fn parse_salted_token_hash(value: &str) -> Option<(&str, &str)> {
+    let mut parts = value.split('$');
+    let (scheme, salt, hash) = (parts.next()?, parts.next()?, parts.next()?);
+    (scheme == SALTED_HASH_SCHEME
+        && parts.next().is_none()
+        && !salt.is_empty()
+        && salt.chars().all(|c| c.is_ascii_hexdigit())
+        && is_token_hash(hash))
+    .then_some((salt, hash))
+}
+
+fn is_salted_token_hash(value: &str) -> bool {
+    parse_salted_token_hash(value).is_some()
+}
+
+/// Check if a stored value looks like an unsalted SHA-256 hash (64 hex chars)
 /// rather than a plaintext token.
 fn is_token_hash(value: &str) -> bool {
     value.len() == 64 && value.chars().all(|c| c.is_ascii_hexdigit())
 }
 
+/// Whether `token` hashes to the stored salted value.
+fn stored_token_matches(stored: &str, token: &str) -> bool {
+    parse_salted_token_hash(stored).is_some_and(|(salt, hash)| {
+        constant_time_eq(&hash_digest_with_salt(&hash_token(token), salt), hash)
+    })
+}
+
 /// Constant-time string comparison to prevent timing attacks.
 ///
 /// Does not short-circuit on length mismatch — always iterates over the
@@ -316,6 +383,77 @@ pub fn constant_time_eq(a: &str, b: &str) -> bool {
     (len_diff == 0) & (byte_diff == 0)
 }
 
+/// A secret held in memory: compared in constant time, redacted in `Debug`
+/// and `Display`, never serialized, and zeroized on drop.
+///
+/// Use [`SecretString::expose`] only at the point the raw value has to leave
+/// the process (an outgoing header, for example).
+#[derive(Clone, Default)]
+pub struct SecretString(String);
+
+impl SecretString {
+    pub fn new(value: impl Into<String>) -> Self {
+        Self(value.into())
+    }
+
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Constant-time comparison against a presented value.
+    pub fn matches(&self, candidate: &str) -> bool {
+        constant_time_eq(&self.0, candidate)
+    }
+}
+
+impl From<String> for SecretString {
+    fn from(value: String) -> Self {
+        Self(value)
+    }
+}
+
+impl From<&str> for SecretString {
+    fn from(value: &str) -> Self {
+        Self(value.to_string())
+    }
+}
+
+impl PartialEq for SecretString {
+    fn eq(&self, other: &Self) -> bool {
+        constant_time_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for SecretString {}
+
+impl std::fmt::Debug for SecretString {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SecretString(<redacted>)")
+    }
+}
+
+impl std::fmt::Display for SecretString {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("<redacted>")
+    }
+}
+
+impl serde::Serialize for SecretString {
+    fn serialize<S: serde::Serializer>(&self, _serializer: S) -> Result<S::Ok, S::Error> {
+        Err(serde::ser::Error::custom("refusing to serialize a secret"))
+    }
+}
+
+impl Drop for SecretString {
+    fn drop(&mut self) {
+        zeroize::Zeroize::zeroize(&mut self.0);
+    }
+}
+
 /// Check if a host string represents a non-localhost bind address.
 pub fn is_public_bind(host: &str) -> bool {
     !matches!(
@@ -409,12 +547,16 @@ mod tests {
         let guard = PairingGuard::new(true, &["zc_a".into(), "zc_b".into()]);
         let tokens = guard.tokens();
         assert_eq!(tokens.len(), 2);
-        // Tokens should be stored as 64-char hex hashes, not plaintext
+        // Tokens should be stored as salted hashes, not plaintext
         for t in &tokens {
-            assert_eq!(t.len(), 64, "Token should be a SHA-256 hash");
-            assert!(t.chars().all(|c| c.is_ascii_hexdigit()));
-            assert!(!t.starts_with("zc_"), "Token should not be plaintext");
+            assert!(
+                is_salted_token_hash(t),
+                "Token should be a salted hash: {t}"
+            );
+            assert!(!t.contains("zc_"), "Token should not be plaintext");
         }
+        assert!(guard.is_authenticated("zc_a"));
+        assert!(guard.is_authenticated("zc_b"));
     }
 
     #[test]
@@ -453,6 +595,120 @@ mod tests {
         assert!(!is_token_hash(""));
     }
 
+    #[test]
+    async fn salted_hash_token_uses_fresh_salt_per_call() {
+        let first = salted_hash_token("zc_same");
+        let second = salted_hash_token("zc_same");
+        assert_ne!(first, second);
+        assert!(first.starts_with("sha256$"));
+        let (salt, hash) = parse_salted_token_hash(&first).unwrap();
+        assert_eq!(salt.len(), 32);
+        assert_eq!(hash, hash_digest_with_salt(&hash_token("zc_same"), salt));
+        assert!(stored_token_matches(&first, "zc_same"));
+        assert!(stored_token_matches(&second, "zc_same"));
+        assert!(!stored_token_matches(&first, "zc_other"));
+    }
+
+    #[test]
+    async fn parse_salted_token_hash_rejects_malformed_values() {
+        let valid = salted_hash_token("zc_x");
+        assert!(is_salted_token_hash(&valid));
+        let legacy = hash_token("zc_x");
+        let trailing = format!("{valid}$extra");
+        for value in [
+            "",
+            "zc_plain",
+            legacy.as_str(),
+            "sha256$$0000",
+            "sha1$abcd$0000000000000000000000000000000000000000000000000000000000000000",
+            "sha256$not-hex$0000000000000000000000000000000000000000000000000000000000000000",
+            "sha256$abcd$short",
+            trailing.as_str(),
+        ] {
+            assert!(!is_salted_token_hash(value), "{value}");
+        }
+    }
+
+    #[test]
+    async fn legacy_hash_is_salted_on_load() {
+        let legacy = hash_token("zc_legacy");
+        let guard = PairingGuard::new(true, std::slice::from_ref(&legacy));
+        assert!(guard.migrated_on_load());
+        let tokens = guard.tokens();
+        assert_eq!(tokens.len(), 1);
+        assert!(is_salted_token_hash(&tokens[0]));
+        assert_ne!(tokens[0], legacy);
+        assert!(guard.is_authenticated("zc_legacy"));
+        assert!(!guard.is_authenticated("zc_wrong"));
+        assert!(!guard.is_authenticated(&legacy));
+
+        let reloaded = PairingGuard::new(true, &tokens);
+        assert!(!reloaded.migrated_on_load());
+        assert!(reloaded.is_authenticated("zc_legacy"));
+    }
+
+    #[test]
+    async fn mixed_format_tokens_all_authenticate() {
+        let salted = salted_hash_token("zc_salted");
+        let existing = vec![
+            salted.clone(),
+            hash_token("zc_legacy"),
+            "zc_plaintext".to_string(),
+        ];
+        let guard = PairingGuard::new(true, &existing);
+        let tokens = guard.tokens();
+        assert_eq!(tokens.len(), 3);
+        assert!(tokens.contains(&salted));
+        assert!(!tokens.iter().any(|t| t == "zc_plaintext"));
+
+        for token in ["zc_salted", "zc_legacy", "zc_plaintext"] {
+            assert!(guard.is_authenticated(token), "{token}");
+        }
+        assert!(!guard.is_authenticated("zc_unknown"));
+        assert!(guard.migrated_on_load());
+        assert!(tokens.iter().all(|t| is_salted_token_hash(t)));
+        // Reloading the persisted form keeps every token valid.
+        let reloaded = PairingGuard::new(true, &guard.tokens());
+        for token in ["zc_salted", "zc_legacy", "zc_plaintext"] {
+            assert!(reloaded.is_authenticated(token), "{token}");
+        }
+    }
+
+    // ── SecretString ─────────────────────────────────────────
+
+    #[test]
+    async fn secret_string_never_prints_its_value() {
+        let secret = SecretString::new("hunter2-super-secret");
+        for rendered in [
+            format!("{secret:?}"),
+            format!("{secret:#?}"),
+            format!("{secret}"),
+            format!("{:?}", Some(secret.clone())),
+        ] {
+            assert!(!rendered.contains("hunter2"), "{rendered}");
+            assert!(rendered.contains("redacted"), "{rendered}");
+        }
+    }
+
+    #[test]
+    async fn secret_string_compares_by_value() {
+        let secret = SecretString::from("abc123");
+        assert!(secret.matches("abc123"));
+        assert!(!secret.matches("abc124"));
+        assert!(!secret.matches("abc12"));
+        assert!(!secret.matches(""));
+        assert_eq!(secret, SecretString::new("abc123"));
+        assert_ne!(secret, SecretString::new("ABC123"));
+        assert_eq!(secret.expose(), "abc123");
+    }
+
+    #[test]
+    async fn secret_string_refuses_serialization() {
+        let err = serde_json::to_string(&SecretString::new("abc")).unwrap_err();
+        assert!(err.to_string().contains("refusing to serialize"));
+        assert!(serde_json::to_value(Some(SecretString::new("abc"))).is_err());
+    }
+
     // ── is_public_bind ───────────────────────────────────────
 
     #[test]
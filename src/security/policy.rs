@@ -1,8 +1,10 @@
 use parking_lot::Mutex;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
-use std::time::Instant;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
 
 /// How much autonomy the agent has
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
@@ -32,51 +34,253 @@ pub enum ToolOperation {
     Act,
 }
 
+/// Length of the rolling action-budget window.
+const ACTION_WINDOW: Duration = Duration::from_secs(3600);
+
+/// One action in the rolling window: when it happened (Unix milliseconds)
+/// and how much of the hourly budget it used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct ActionRecord {
+    at_ms: i64,
+    weight: u32,
+}
+
+/// On-disk form of a persisted action window.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ActionLog {
+    actions: Vec<ActionRecord>,
+}
+
+fn now_ms() -> i64 {
+    chrono::Utc::now().timestamp_millis()
+}
+
+fn window_ms() -> i64 {
+    i64::try_from(ACTION_WINDOW.as_millis()).unwrap_or(i64::MAX)
+}
+
+fn used_budget(actions: &[ActionRecord]) -> usize {
+    actions.iter().map(|action| action.weight as usize).sum()
+}
+
+/// Actions recorded within the last hour, plus the lock that orders saves.
+#[derive(Debug, Default)]
+struct ActionWindow {
+    actions: Mutex<Vec<ActionRecord>>,
+    /// Held while saving so a slower save never overwrites a newer window.
+    save_lock: Mutex<()>,
+}
+
+/// Persistent windows by actions file.
+type PersistentWindows = HashMap<PathBuf, Arc<ActionWindow>>;
+
+/// Shared windows of persistent trackers, one per actions file, so every
+/// policy built for a workspace in this process serializes on one lock.
+fn persistent_windows() -> &'static Mutex<PersistentWindows> {
+    static WINDOWS: OnceLock<Mutex<PersistentWindows>> = OnceLock::new();
+    WINDOWS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
 /// Sliding-window action tracker for rate limiting.
+///
+/// Trackers from [`ActionTracker::for_workspace`] load
+/// `<workspace>/state/security/actions.json` once per process and save it
+/// after every change, so a restart does not reset the budget. Processes
+/// running side by side each keep their own window; the file holds whichever
+/// saved last.
 #[derive(Debug)]
 pub struct ActionTracker {
-    /// Timestamps of recent actions (kept within the last hour).
-    actions: Mutex<Vec<Instant>>,
+    window: Arc<ActionWindow>,
+    /// Actions file; `None` keeps the window in memory only.
+    path: Option<PathBuf>,
 }
 
 impl ActionTracker {
     pub fn new() -> Self {
         Self {
-            actions: Mutex::new(Vec::new()),
+            window: Arc::default(),
+            path: None,
+        }
+    }
+
+    /// Tracker persisted under `<workspace>/state/security/actions.json`.
+    pub fn for_workspace(workspace_dir: &Path) -> Self {
+        let path = workspace_dir
+            .join("state")
+            .join("security")
+            .join("actions.json");
+        let window = persistent_windows()
+            .lock()
+            .entry(path.clone())
+            .or_insert_with(|| {
+                Arc::new(ActionWindow {
+                    actions: Mutex::new(Self::load(&path)),
+                    save_lock: Mutex::new(()),
+                })
+            })
+            .clone();
+        Self {
+            window,
+            path: Some(path),
+        }
+    }
+
+    /// Run `f` on the current window with expired actions dropped, saving
+    /// the window afterwards when `f` reports a change.
+    fn with_window<R>(&self, f: impl FnOnce(&mut Vec<ActionRecord>, i64) -> (R, bool)) -> R {
+        let (result, changed) = {
+            let mut actions = self.window.actions.lock();
+            let now = now_ms();
+            let cutoff = now.saturating_sub(window_ms());
+            actions.retain(|action| action.at_ms > cutoff);
+            f(&mut actions, now)
+        };
+        if changed {
+            if let Some(path) = &self.path {
+                self.persist(path.clone());
+            }
+        }
+        result
+    }
+
+    /// Save the window, on the blocking pool when called from async code.
+    fn persist(&self, path: PathBuf) {
+        let window = Arc::clone(&self.window);
+        let save = move || {
+            let _saving = window.save_lock.lock();
+            let actions = window.actions.lock().clone();
+            Self::save(&path, &actions);
+        };
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn_blocking(save);
+            }
+            Err(_) => save(),
+        }
+    }
+
+    fn load(path: &Path) -> Vec<ActionRecord> {
+        let Ok(raw) = std::fs::read(path) else {
+            return Vec::new();
+        };
+        match serde_json::from_slice::<ActionLog>(&raw) {
+            Ok(log) => log.actions,
+            Err(e) => {
+                tracing::warn!("Ignoring unreadable action log {}: {e}", path.display());
+                Vec::new()
+            }
+        }
+    }
+
+    fn save(path: &Path, actions: &[ActionRecord]) {
+        let log = ActionLog {
+            actions: actions.to_vec(),
+        };
+        let result = serde_json::to_vec(&log)
+            .map_err(|e| e.to_string())
+            .and_then(|bytes| {
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+                }
+                crate::workspace::atomic_write(path, bytes).map_err(|e| e.to_string())
+            });
+        if let Err(e) = result {
+            tracing::warn!("Failed to persist action log {}: {e}", path.display());
         }
     }
 
     /// Record an action and return the current count within the window.
     pub fn record(&self) -> usize {
-        let mut actions = self.actions.lock();
-        let cutoff = Instant::now()
-            .checked_sub(std::time::Duration::from_secs(3600))
-            .unwrap_or_else(Instant::now);
-        actions.retain(|t| *t > cutoff);
-        actions.push(Instant::now());
-        actions.len()
+        self.record_weighted(1)
+    }
+
+    /// Record an action costing `weight` and return the budget used within
+    /// the window.
+    pub fn record_weighted(&self, weight: u32) -> usize {
+        self.with_window(|actions, now| {
+            actions.push(ActionRecord { at_ms: now, weight });
+            (used_budget(actions), true)
+        })
+    }
+
+    /// Record an action costing `weight` only if the window stays within
+    /// `limit`. Returns whether it was recorded.
+    pub fn try_record(&self, weight: u32, limit: usize) -> bool {
+        self.with_window(|actions, now| {
+            if used_budget(actions) + weight as usize > limit {
+                return (false, false);
+            }
+            actions.push(ActionRecord { at_ms: now, weight });
+            (true, true)
+        })
     }
 
     /// Count of actions in the current window without recording.
     pub fn count(&self) -> usize {
-        let mut actions = self.actions.lock();
-        let cutoff = Instant::now()
-            .checked_sub(std::time::Duration::from_secs(3600))
-            .unwrap_or_else(Instant::now);
-        actions.retain(|t| *t > cutoff);
-        actions.len()
+        self.with_window(|actions, _| (used_budget(actions), false))
+    }
+
+    /// How long until enough actions leave the window for one costing
+    /// `weight` to fit under `limit`: zero if it fits now, `None` if it
+    /// never can.
+    pub fn available_in(&self, weight: u32, limit: usize) -> Option<Duration> {
+        if weight as usize > limit {
+            return None;
+        }
+        self.with_window(|actions, now| {
+            let mut used = used_budget(actions);
+            if used + weight as usize <= limit {
+                return (Some(Duration::ZERO), false);
+            }
+            let mut oldest_first = actions.clone();
+            oldest_first.sort_by_key(|action| action.at_ms);
+            for action in oldest_first {
+                used -= action.weight as usize;
+                if used + weight as usize <= limit {
+                    let expires = action.at_ms.saturating_add(window_ms());
+                    let wait = u64::try_from(expires.saturating_sub(now)).unwrap_or(0);
+                    return (Some(Duration::from_millis(wait)), false);
+                }
+            }
+            (None, false)
+        })
     }
 }
 
 impl Clone for ActionTracker {
+    /// Persistent trackers share their workspace window; in-memory trackers
+    /// get an independent copy.
     fn clone(&self) -> Self {
-        let actions = self.actions.lock();
+        if self.path.is_some() {
+            return Self {
+                window: Arc::clone(&self.window),
+                path: self.path.clone(),
+            };
+        }
+        let actions = self.window.actions.lock().clone();
         Self {
-            actions: Mutex::new(actions.clone()),
+            window: Arc::new(ActionWindow {
+                actions: Mutex::new(actions),
+                save_lock: Mutex::new(()),
+            }),
+            path: None,
         }
     }
 }
 
+/// Remaining hourly action budget, as reported by `GET /api/security/budget`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActionBudget {
+    pub limit: usize,
+    pub used: usize,
+    pub remaining: usize,
+    /// Seconds until one more action fits, when the budget is used up.
+    pub next_release_secs: Option<u64>,
+    /// Per-tool costs that differ from the default of one action.
+    pub tool_weights: HashMap<String, u32>,
+}
+
 /// Security policy enforced on all tool executions
 #[derive(Debug, Clone)]
 pub struct SecurityPolicy {
@@ -91,6 +295,8 @@ pub struct SecurityPolicy {
     pub require_approval_for_medium_risk: bool,
     pub block_high_risk_commands: bool,
     pub shell_env_passthrough: Vec<String>,
+    /// Hourly budget cost per tool name; unlisted tools cost one action.
+    pub tool_action_weights: HashMap<String, u32>,
     pub tracker: ActionTracker,
 }
 
@@ -144,6 +350,7 @@ impl Default for SecurityPolicy {
             require_approval_for_medium_risk: true,
             block_high_risk_commands: true,
             shell_env_passthrough: vec![],
+            tool_action_weights: HashMap::new(),
             tracker: ActionTracker::new(),
         }
     }
@@ -1032,8 +1239,8 @@ impl SecurityPolicy {
     /// Record an action and check if the rate limit has been exceeded.
     /// Returns `true` if the action is allowed, `false` if rate-limited.
     pub fn record_action(&self) -> bool {
-        let count = self.tracker.record();
-        count <= self.max_actions_per_hour as usize
+        self.tracker
+            .try_record(1, self.max_actions_per_hour as usize)
     }
 
     /// Check if the rate limit would be exceeded without recording.
@@ -1041,6 +1248,65 @@ impl SecurityPolicy {
         self.tracker.count() >= self.max_actions_per_hour as usize
     }
 
+    /// Budget cost of one `tool` call: its configured weight, or 1.
+    pub fn action_weight(&self, tool: &str) -> u32 {
+        self.tool_action_weights.get(tool).copied().unwrap_or(1)
+    }
+
+    /// [`record_action`](Self::record_action) charged at `tool`'s weight.
+    /// Nothing is recorded when the call would not fit the budget.
+    pub fn record_tool_action(&self, tool: &str) -> bool {
+        self.tracker
+            .try_record(self.action_weight(tool), self.max_actions_per_hour as usize)
+    }
+
+    /// Whether a `tool` call would exceed the budget right now.
+    pub fn is_rate_limited_for(&self, tool: &str) -> bool {
+        self.tracker.count() + self.action_weight(tool) as usize
+            > self.max_actions_per_hour as usize
+    }
+
+    /// Current hourly budget, for reporting.
+    pub fn action_budget(&self) -> ActionBudget {
+        let limit = self.max_actions_per_hour as usize;
+        let used = self.tracker.count();
+        let remaining = limit.saturating_sub(used);
+        let next_release_secs = if remaining == 0 {
+            self.tracker
+                .available_in(1, limit)
+                .map(|wait| wait.as_secs())
+        } else {
+            None
+        };
+        ActionBudget {
+            limit,
+            used,
+            remaining,
+            next_release_secs,
+            tool_weights: self.tool_action_weights.clone(),
+        }
+    }
+
+    /// Rejection text for a rate-limited `tool` call, saying how much budget
+    /// is left and when the call will fit again.
+    pub fn rate_limit_message(&self, tool: &str) -> String {
+        let limit = self.max_actions_per_hour as usize;
+        let weight = self.action_weight(tool);
+        let used = self.tracker.count();
+        let remaining = limit.saturating_sub(used);
+        let when = match self.tracker.available_in(weight, limit) {
+            None => format!("it costs more than the whole hourly budget of {limit}"),
+            Some(wait) => {
+                let minutes = wait.as_secs().div_ceil(60).max(1);
+                format!("it will be allowed again in about {minutes} min")
+            }
+        };
+        format!(
+            "Rate limit exceeded: {tool} costs {weight} of the hourly action budget \
+             and {remaining} of {limit} remain; {when}"
+        )
+    }
+
     /// Build from config sections
     pub fn from_config(
         autonomy_config: &crate::config::AutonomyConfig,
@@ -1060,7 +1326,8 @@ impl SecurityPolicy {
             require_approval_for_medium_risk: autonomy_config.require_approval_for_medium_risk,
             block_high_risk_commands: autonomy_config.block_high_risk_commands,
             shell_env_passthrough: autonomy_config.shell_env_passthrough.clone(),
-            tracker: ActionTracker::new(),
+            tool_action_weights: autonomy_config.tool_action_weights.clone(),
+            tracker: ActionTracker::for_workspace(workspace_dir),
         }
     }
 }
@@ -1548,6 +1815,114 @@ mod tests {
         assert_eq!(cloned.count(), 2); // clone is independent
     }
 
+    fn weighted_policy(max: u32) -> SecurityPolicy {
+        SecurityPolicy {
+            max_actions_per_hour: max,
+            tool_action_weights: HashMap::from([("render".to_string(), 4)]),
+            ..SecurityPolicy::default()
+        }
+    }
+
+    #[test]
+    fn weighted_actions_count_against_budget() {
+        let p = weighted_policy(10);
+        assert_eq!(p.action_weight("render"), 4);
+        assert_eq!(p.action_weight("file_read"), 1);
+
+        assert!(p.record_tool_action("render"));
+        assert!(p.record_tool_action("render"));
+        assert_eq!(p.tracker.count(), 8);
+        assert!(p.is_rate_limited_for("render"));
+        assert!(!p.record_tool_action("render"));
+        // A refused call does not use budget.
+        assert_eq!(p.tracker.count(), 8);
+        assert!(!p.is_rate_limited_for("file_read"));
+        assert!(p.record_tool_action("file_read"));
+        assert!(p.record_tool_action("file_read"));
+        assert!(!p.record_tool_action("file_read"));
+        assert!(p.is_rate_limited());
+
+        let budget = p.action_budget();
+        assert_eq!((budget.used, budget.remaining), (10, 0));
+        assert!(budget.next_release_secs.is_some());
+    }
+
+    fn write_action_log(workspace: &Path, actions: &[(i64, u32)]) {
+        let log = ActionLog {
+            actions: actions
+                .iter()
+                .map(|&(age_ms, weight)| ActionRecord {
+                    at_ms: now_ms() - age_ms,
+                    weight,
+                })
+                .collect(),
+        };
+        let dir = workspace.join("state").join("security");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("actions.json"), serde_json::to_vec(&log).unwrap()).unwrap();
+    }
+
+    #[test]
+    fn action_budget_survives_restart() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut autonomy = crate::config::AutonomyConfig {
+            max_actions_per_hour: 10,
+            ..crate::config::AutonomyConfig::default()
+        };
+        autonomy.tool_action_weights.insert("render".into(), 4);
+
+        let before = SecurityPolicy::from_config(&autonomy, tmp.path());
+        assert!(before.record_tool_action("render"));
+        assert!(before.record_tool_action("shell"));
+
+        let path = tmp.path().join("state/security/actions.json");
+        let log: ActionLog = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        let weights: Vec<u32> = log.actions.iter().map(|a| a.weight).collect();
+        assert_eq!(weights, vec![4, 1]);
+
+        // What a previous process left behind, plus one action past the window.
+        write_action_log(tmp.path(), &[(2 * 3_600_000, 9), (60_000, 4), (1_000, 1)]);
+        persistent_windows().lock().remove(&path);
+        let after = SecurityPolicy::from_config(&autonomy, tmp.path());
+        assert_eq!(after.tracker.count(), 5);
+        assert!(after.record_tool_action("render"));
+        assert!(!after.record_tool_action("render"));
+        let log: ActionLog = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        assert_eq!(used_budget(&log.actions), 9);
+    }
+
+    #[test]
+    fn rate_limit_message_reports_remaining_budget_and_wait() {
+        let tmp = tempfile::tempdir().unwrap();
+        let autonomy = crate::config::AutonomyConfig {
+            max_actions_per_hour: 5,
+            ..crate::config::AutonomyConfig::default()
+        };
+        // Oldest action leaves the window in about a minute.
+        write_action_log(tmp.path(), &[(3_600_000 - 50_000, 3), (10_000, 2)]);
+        let p = SecurityPolicy::from_config(&autonomy, tmp.path());
+        assert!(p.is_rate_limited_for("shell"));
+        let message = p.rate_limit_message("shell");
+        assert!(message.starts_with("Rate limit exceeded: shell costs 1"));
+        assert!(message.contains("0 of 5 remain"), "{message}");
+        assert!(message.contains("about 1 min"), "{message}");
+
+        let mut heavy = p.clone();
+        heavy.tool_action_weights.insert("render".into(), 6);
+        assert!(heavy
+            .rate_limit_message("render")
+            .contains("more than the whole hourly budget"));
+    }
+
+    #[test]
+    fn persistent_tracker_clones_share_the_window() {
+        let tmp = tempfile::tempdir().unwrap();
+        let tracker = ActionTracker::for_workspace(tmp.path());
+        let cloned = tracker.clone();
+        tracker.record_weighted(2);
+        assert_eq!(cloned.count(), 2);
+    }
+
     // ── Edge cases: command injection ────────────────────────
 
     #[test]
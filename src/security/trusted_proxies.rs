@@ -0,0 +1,150 @@
+//! Reverse proxies whose forwarded client addresses the gateway believes.
+//!
+//! `[gateway] trusted_proxies` lists address ranges in CIDR notation (a bare
+//! address is a single-host range). Forwarded headers are only honored when
+//! the direct peer falls inside one of them.
+
+use std::net::IpAddr;
+
+/// An address range such as `10.0.0.0/8` or `fd00::/8`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpRange {
+    network: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpRange {
+    /// Parse `addr/len`, or a bare address as a single-host range.
+    pub fn parse(raw: &str) -> Option<Self> {
+        let raw = raw.trim();
+        let (addr, prefix_len) = match raw.split_once('/') {
+            Some((addr, len)) => (addr, Some(len)),
+            None => (raw, None),
+        };
+        let network: IpAddr = addr.trim().parse().ok()?;
+        let max_len = if network.is_ipv4() { 32 } else { 128 };
+        let prefix_len = match prefix_len {
+            Some(len) => len
+                .trim()
+                .parse::<u8>()
+                .ok()
+                .filter(|len| *len <= max_len)?,
+            None => max_len,
+        };
+        Some(Self {
+            network,
+            prefix_len,
+        })
+    }
+
+    /// Whether `ip` is inside the range. IPv4-mapped IPv6 addresses (what a
+    /// dual-stack listener reports for IPv4 peers) match IPv4 ranges.
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.network, ip.to_canonical()) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => same_prefix(
+                u128::from(u32::from(network)),
+                u128::from(u32::from(ip)),
+                32 - self.prefix_len,
+            ),
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                same_prefix(u128::from(network), u128::from(ip), 128 - self.prefix_len)
+            }
+            _ => false,
+        }
+    }
+}
+
+fn same_prefix(network: u128, ip: u128, host_bits: u8) -> bool {
+    host_bits >= 128 || network >> host_bits == ip >> host_bits
+}
+
+/// The peers allowed to report a client address in `X-Forwarded-For` or
+/// `X-Real-IP`. The default trusts nobody.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TrustedProxies {
+    /// Every peer is trusted: the legacy `trust_forwarded_headers = true`.
+    any: bool,
+    ranges: Vec<IpRange>,
+}
+
+impl TrustedProxies {
+    /// From `[gateway] trusted_proxies`, falling back to the legacy boolean
+    /// only when no ranges are listed. Unparseable entries are skipped with a
+    /// warning (config validation rejects them first).
+    pub fn from_config(trust_forwarded_headers: bool, entries: &[String]) -> Self {
+        let ranges: Vec<IpRange> = entries
+            .iter()
+            .filter_map(|entry| {
+                let range = IpRange::parse(entry);
+                if range.is_none() {
+                    tracing::warn!(entry, "Ignoring invalid gateway.trusted_proxies entry");
+                }
+                range
+            })
+            .collect();
+        Self {
+            any: trust_forwarded_headers && ranges.is_empty(),
+            ranges,
+        }
+    }
+
+    pub fn trusts(&self, ip: IpAddr) -> bool {
+        self.any || self.ranges.iter().any(|range| range.contains(ip))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(raw: &str) -> IpAddr {
+        raw.parse().unwrap()
+    }
+
+    #[test]
+    fn ranges_match_by_prefix_for_both_families() {
+        let lan = IpRange::parse("10.0.0.0/8").unwrap();
+        assert!(lan.contains(ip("10.20.30.40")));
+        assert!(!lan.contains(ip("11.0.0.1")));
+        assert!(lan.contains(ip("::ffff:10.0.0.2")));
+
+        let host = IpRange::parse(" 10.0.0.2/32 ").unwrap();
+        assert!(host.contains(ip("10.0.0.2")));
+        assert!(!host.contains(ip("10.0.0.3")));
+        assert_eq!(IpRange::parse("10.0.0.2"), Some(host));
+
+        let ula = IpRange::parse("fd00::/8").unwrap();
+        assert!(ula.contains(ip("fd12:3456::1")));
+        assert!(!ula.contains(ip("fe80::1")));
+        assert!(!ula.contains(ip("10.0.0.2")));
+
+        assert!(IpRange::parse("0.0.0.0/0")
+            .unwrap()
+            .contains(ip("203.0.113.9")));
+        assert!(IpRange::parse("::/0").unwrap().contains(ip("2001:db8::1")));
+    }
+
+    #[test]
+    fn malformed_ranges_are_rejected() {
+        for raw in [
+            "",
+            "10.0.0.0/33",
+            "fd00::/129",
+            "10.0.0/8",
+            "lan",
+            "10.0.0.0/-1",
+        ] {
+            assert_eq!(IpRange::parse(raw), None, "{raw:?}");
+        }
+    }
+
+    #[test]
+    fn listed_ranges_take_precedence_over_the_legacy_flag() {
+        assert!(!TrustedProxies::default().trusts(ip("127.0.0.1")));
+        assert!(TrustedProxies::from_config(true, &[]).trusts(ip("198.51.100.7")));
+
+        let ranges = TrustedProxies::from_config(true, &["10.0.0.2/32".into()]);
+        assert!(ranges.trusts(ip("10.0.0.2")));
+        assert!(!ranges.trusts(ip("198.51.100.7")));
+    }
+}
@@ -0,0 +1,261 @@
+use super::traits::{Tool, ToolResult};
+use crate::bluesky::{
+    resolve_workspace_image, BlueskyClient, LinkCard, PostImage, PostRequest, MAX_POST_IMAGES,
+};
+use crate::security::SecurityPolicy;
+use async_trait::async_trait;
+use serde_json::json;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Publish text posts, link cards, and workspace images to Bluesky.
+pub struct BlueskyPostTool {
+    client: Arc<BlueskyClient>,
+    security: Arc<SecurityPolicy>,
+    workspace_dir: PathBuf,
+}
+
+impl BlueskyPostTool {
+    pub fn new(
+        client: Arc<BlueskyClient>,
+        security: Arc<SecurityPolicy>,
+        workspace_dir: PathBuf,
+    ) -> Self {
+        Self {
+            client,
+            security,
+            workspace_dir,
+        }
+    }
+
+    /// Tool backed by the `ZEROCLAW_BLUESKY_*` credentials, or `None` when
+    /// they are not set.
+    pub fn from_env(
+        security: Arc<SecurityPolicy>,
+        workspace_dir: &std::path::Path,
+    ) -> Option<Self> {
+        let client = BlueskyClient::from_env(workspace_dir)?;
+        Some(Self::new(
+            Arc::new(client),
+            security,
+            workspace_dir.to_path_buf(),
+        ))
+    }
+
+    fn parse_request(&self, args: &serde_json::Value) -> anyhow::Result<PostRequest> {
+        let text = args
+            .get("text")
+            .and_then(|value| value.as_str())
+            .unwrap_or_default()
+            .trim()
+            .to_string();
+        let image_args = args
+            .get("images")
+            .and_then(|value| value.as_array())
+            .cloned()
+            .unwrap_or_default();
+        if image_args.len() > MAX_POST_IMAGES {
+            anyhow::bail!(
+                "Bluesky posts can carry at most {MAX_POST_IMAGES} images ({} given)",
+                image_args.len()
+            );
+        }
+        let mut images = Vec::with_capacity(image_args.len());
+        for image in &image_args {
+            let rel_path = image
+                .get("path")
+                .and_then(|value| value.as_str())
+                .ok_or_else(|| anyhow::anyhow!("Each image needs a 'path'"))?;
+            if !self.security.is_path_allowed(rel_path) {
+                anyhow::bail!("Path not allowed for bluesky_post: {rel_path}");
+            }
+            images.push(PostImage {
+                path: resolve_workspace_image(&self.workspace_dir, rel_path)?,
+                alt: image
+                    .get("alt")
+                    .and_then(|value| value.as_str())
+                    .unwrap_or_default()
+                    .to_string(),
+            });
+        }
+        let link = args.get("link").filter(|value| !value.is_null());
+        let link = match link {
+            Some(link) => {
+                let uri = link
+                    .get("url")
+                    .and_then(|value| value.as_str())
+                    .map(str::trim)
+                    .filter(|uri| uri.starts_with("https://") || uri.starts_with("http://"))
+                    .ok_or_else(|| anyhow::anyhow!("link.url must be an http(s) URL"))?;
+                let field = |key: &str| {
+                    link.get(key)
+                        .and_then(|value| value.as_str())
+                        .unwrap_or_default()
+                        .to_string()
+                };
+                Some(LinkCard {
+                    uri: uri.to_string(),
+                    title: field("title"),
+                    description: field("description"),
+                })
+            }
+            None => None,
+        };
+        let request = PostRequest { text, images, link };
+        request.validate()?;
+        Ok(request)
+    }
+}
+
+fn failure(error: impl Into<String>) -> ToolResult {
+    ToolResult {
+        success: false,
+        output: String::new(),
+        error: Some(error.into()),
+    }
+}
+
+#[async_trait]
+impl Tool for BlueskyPostTool {
+    fn name(&self) -> &str {
+        "bluesky_post"
+    }
+
+    fn description(&self) -> &str {
+        "Publish a post to the configured Bluesky account: up to 300 characters of text, plus either a link card or up to 4 workspace images. Use dryRun to preview the record without posting."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "text": { "type": "string", "description": "Post text, at most 300 graphemes. URLs become clickable links." },
+                "images": {
+                    "type": "array",
+                    "maxItems": MAX_POST_IMAGES,
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "path": { "type": "string", "description": "Workspace-relative jpg/png/webp/gif under 1MB" },
+                            "alt": { "type": "string", "description": "Alt text describing the image" }
+                        },
+                        "required": ["path"]
+                    }
+                },
+                "link": {
+                    "type": "object",
+                    "description": "Link card shown under the text. Cannot be combined with images.",
+                    "properties": {
+                        "url": { "type": "string" },
+                        "title": { "type": "string" },
+                        "description": { "type": "string" }
+                    },
+                    "required": ["url"]
+                },
+                "dryRun": { "type": "boolean", "description": "Validate and return the record without posting", "default": false }
+            }
+        })
+    }
+
+    async fn execute(&self, args: serde_json::Value) -> anyhow::Result<ToolResult> {
+        let dry_run = args
+            .get("dryRun")
+            .and_then(|value| value.as_bool())
+            .unwrap_or(false);
+        let request = match self.parse_request(&args) {
+            Ok(request) => request,
+            Err(err) => return Ok(failure(err.to_string())),
+        };
+        if dry_run {
+            return Ok(ToolResult {
+                success: true,
+                output: serde_json::to_string_pretty(&json!({
+                    "dryRun": true,
+                    "handle": self.client.handle(),
+                    "record": request.preview(),
+                }))?,
+                error: None,
+            });
+        }
+
+        if !self.security.can_act() {
+            return Ok(failure("Action blocked: autonomy is read-only"));
+        }
+        if !self.security.record_tool_action(self.name()) {
+            return Ok(failure(self.security.rate_limit_message(self.name())));
+        }
+        match self.client.post(&request).await {
+            Ok(posted) => Ok(ToolResult {
+                success: true,
+                output: serde_json::to_string_pretty(&posted)?,
+                error: None,
+            }),
+            Err(err) => Ok(failure(err.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bluesky::BlueskyCredentials;
+
+    fn tool(workspace_dir: &std::path::Path) -> BlueskyPostTool {
+        let client = BlueskyClient::new(
+            BlueskyCredentials {
+                service_url: "http://127.0.0.1:9".into(),
+                handle: "alice.test".into(),
+                app_password: "app-pass".into(),
+            },
+            None,
+        );
+        BlueskyPostTool::new(
+            Arc::new(client),
+            Arc::new(SecurityPolicy {
+                workspace_dir: workspace_dir.to_path_buf(),
+                ..SecurityPolicy::default()
+            }),
+            workspace_dir.to_path_buf(),
+        )
+    }
+
+    #[tokio::test]
+    async fn dry_run_renders_record_with_link_card() {
+        let dir = tempfile::tempdir().unwrap();
+        let result = tool(dir.path())
+            .execute(json!({
+                "text": "New essay is up",
+                "link": { "url": "https://example.test/essay", "title": "Essay" },
+                "dryRun": true
+            }))
+            .await
+            .unwrap();
+        assert!(result.success, "{:?}", result.error);
+        let output: serde_json::Value = serde_json::from_str(&result.output).unwrap();
+        assert_eq!(
+            output["record"]["embed"]["$type"],
+            "app.bsky.embed.external"
+        );
+        assert_eq!(output["record"]["embed"]["external"]["title"], "Essay");
+    }
+
+    #[tokio::test]
+    async fn rejects_too_many_images_and_paths_outside_workspace() {
+        let dir = tempfile::tempdir().unwrap();
+        let tool = tool(dir.path());
+        let images: Vec<_> = (0..5)
+            .map(|i| json!({ "path": format!("img{i}.png") }))
+            .collect();
+        let result = tool
+            .execute(json!({ "text": "hi", "images": images, "dryRun": true }))
+            .await
+            .unwrap();
+        assert!(result.error.unwrap().contains("at most 4 images"));
+
+        let result = tool
+            .execute(json!({ "text": "hi", "images": [{ "path": "../secret.png" }] }))
+            .await
+            .unwrap();
+        assert!(!result.success);
+    }
+}
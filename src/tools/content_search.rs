@@ -162,11 +162,11 @@ impl Tool for ContentSearchTool {
             .min(MAX_RESULTS);
 
         // --- Rate limit check ---
-        if self.security.is_rate_limited() {
+        if self.security.is_rate_limited_for(self.name()) {
             return Ok(ToolResult {
                 success: false,
                 output: String::new(),
-                error: Some("Rate limit exceeded: too many actions in the last hour".into()),
+                error: Some(self.security.rate_limit_message(self.name())),
             });
         }
 
@@ -198,11 +198,11 @@ impl Tool for ContentSearchTool {
         }
 
         // Record action to consume rate limit budget
-        if !self.security.record_action() {
+        if !self.security.record_tool_action(self.name()) {
             return Ok(ToolResult {
                 success: false,
                 output: String::new(),
-                error: Some("Rate limit exceeded: action budget exhausted".into()),
+                error: Some(self.security.rate_limit_message(self.name())),
             });
         }
 
@@ -54,11 +54,11 @@ impl Tool for FileReadTool {
             .and_then(|v| v.as_str())
             .ok_or_else(|| anyhow::anyhow!("Missing 'path' parameter"))?;
 
-        if self.security.is_rate_limited() {
+        if self.security.is_rate_limited_for(self.name()) {
             return Ok(ToolResult {
                 success: false,
                 output: String::new(),
-                error: Some("Rate limit exceeded: too many actions in the last hour".into()),
+                error: Some(self.security.rate_limit_message(self.name())),
             });
         }
 
@@ -74,11 +74,11 @@ impl Tool for FileReadTool {
         // Record action BEFORE canonicalization so that every non-trivially-rejected
         // request consumes rate limit budget. This prevents attackers from probing
         // path existence (via canonicalize errors) without rate limit cost.
-        if !self.security.record_action() {
+        if !self.security.record_tool_action(self.name()) {
             return Ok(ToolResult {
                 success: false,
                 output: String::new(),
-                error: Some("Rate limit exceeded: action budget exhausted".into()),
+                error: Some(self.security.rate_limit_message(self.name())),
             });
         }
 
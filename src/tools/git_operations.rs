@@ -539,11 +539,11 @@ impl Tool for GitOperationsTool {
         }
 
         // Record action for rate limiting
-        if !self.security.record_action() {
+        if !self.security.record_tool_action(self.name()) {
             return Ok(ToolResult {
                 success: false,
                 output: String::new(),
-                error: Some("Action blocked: rate limit exceeded".into()),
+                error: Some(self.security.rate_limit_message(self.name())),
             });
         }
 
@@ -49,11 +49,11 @@ impl Tool for GlobSearchTool {
             .ok_or_else(|| anyhow::anyhow!("Missing 'pattern' parameter"))?;
 
         // Rate limit check (fast path)
-        if self.security.is_rate_limited() {
+        if self.security.is_rate_limited_for(self.name()) {
             return Ok(ToolResult {
                 success: false,
                 output: String::new(),
-                error: Some("Rate limit exceeded: too many actions in the last hour".into()),
+                error: Some(self.security.rate_limit_message(self.name())),
             });
         }
 
@@ -76,11 +76,11 @@ impl Tool for GlobSearchTool {
         }
 
         // Record action to consume rate limit budget
-        if !self.security.record_action() {
+        if !self.security.record_tool_action(self.name()) {
             return Ok(ToolResult {
                 success: false,
                 output: String::new(),
-                error: Some("Rate limit exceeded: action budget exhausted".into()),
+                error: Some(self.security.rate_limit_message(self.name())),
             });
         }
 
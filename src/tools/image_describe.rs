@@ -0,0 +1,328 @@
+use super::traits::{Tool, ToolResult};
+use crate::config::Config;
+use crate::gateway::local_store;
+use crate::providers::{self, ChatMessage};
+use crate::security::SecurityPolicy;
+use crate::workspace::{resolve_contained_path, ResolveMode};
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use serde::Serialize;
+use serde_json::json;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Largest source image the tool will touch at all (32MB). Anything bigger is
+/// rejected before being read or handed to ffmpeg.
+const MAX_SOURCE_IMAGE_BYTES: u64 = 32 * 1024 * 1024;
+/// Longest edge, in pixels, of the downscaled copy sent to the provider.
+const DOWNSCALE_MAX_EDGE_PX: u32 = 1568;
+const DOWNSCALE_TIMEOUT_SECS: u64 = 30;
+const PREVIEW_MAX_CHARS: usize = 240;
+const CAPTION_SIDECAR_SUFFIX: &str = ".caption.txt";
+
+const DESCRIBE_PROMPT: &str = "Describe this photo for a personal journal library. \
+Reply with JSON only: {\"caption\": \"one or two sentence caption\", \
+\"text\": \"any legible text in the image, or an empty string\"}";
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageDescription {
+    pub image_path: String,
+    pub caption_path: String,
+    pub caption: String,
+    pub extracted_text: String,
+    pub metadata_updated: bool,
+}
+
+fn is_safe_relative_path(rel_path: &str) -> bool {
+    let path = Path::new(rel_path);
+    !rel_path.trim().is_empty()
+        && !path.is_absolute()
+        && path
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+}
+
+/// Ask ffmpeg for a JPEG no larger than `DOWNSCALE_MAX_EDGE_PX` on its long
+/// edge. Returns `None` when ffmpeg is unavailable or fails so callers can
+/// fall back to the original file.
+async fn downscale_image(path: &Path) -> Option<Vec<u8>> {
+    let ffmpeg = which::which("ffmpeg").ok()?;
+    let scale = format!(
+        "scale='min({DOWNSCALE_MAX_EDGE_PX},iw)':'min({DOWNSCALE_MAX_EDGE_PX},ih)':force_original_aspect_ratio=decrease"
+    );
+    let mut cmd = tokio::process::Command::new(ffmpeg);
+    cmd.args(["-v", "error", "-i"])
+        .arg(path)
+        .args(["-vf", &scale, "-frames:v", "1", "-f", "image2pipe"])
+        .args(["-vcodec", "mjpeg", "-q:v", "4", "-"])
+        .kill_on_drop(true);
+    let output = tokio::time::timeout(Duration::from_secs(DOWNSCALE_TIMEOUT_SECS), cmd.output())
+        .await
+        .ok()?
+        .ok()?;
+    (output.status.success() && !output.stdout.is_empty()).then_some(output.stdout)
+}
+
+fn parse_description(raw: &str) -> (String, String) {
+    let trimmed = raw.trim();
+    let json_slice = trimmed
+        .find('{')
+        .zip(trimmed.rfind('}'))
+        .filter(|(start, end)| start < end)
+        .map(|(start, end)| &trimmed[start..=end]);
+    if let Some(value) = json_slice.and_then(|s| serde_json::from_str::<serde_json::Value>(s).ok())
+    {
+        let field = |key: &str| {
+            value
+                .get(key)
+                .and_then(|v| v.as_str())
+                .map(|s| s.trim().to_string())
+                .unwrap_or_default()
+        };
+        let caption = field("caption");
+        if !caption.is_empty() {
+            return (caption, field("text"));
+        }
+    }
+    (trimmed.to_string(), String::new())
+}
+
+fn caption_sidecar_body(caption: &str, extracted_text: &str) -> String {
+    if extracted_text.is_empty() {
+        format!("{caption}\n")
+    } else {
+        format!("{caption}\n\nText in image:\n{extracted_text}\n")
+    }
+}
+
+/// Caption a workspace image with the configured multimodal provider, write
+/// `<image>.caption.txt`, and refresh the `media_assets` preview text.
+pub async fn describe_workspace_image(
+    config: &Config,
+    rel_path: &str,
+) -> anyhow::Result<ImageDescription> {
+    let rel_path = rel_path.trim().trim_start_matches('/');
+    if !is_safe_relative_path(rel_path) {
+        anyhow::bail!("Image path must be workspace-relative without '..': {rel_path}");
+    }
+    let abs_path =
+        resolve_contained_path(&config.workspace_dir, rel_path, ResolveMode::ExistingFile)
+            .map_err(|err| anyhow::anyhow!("Image path {rel_path} is unusable: {err}"))?;
+    let size = tokio::fs::metadata(&abs_path).await?.len();
+    if size > MAX_SOURCE_IMAGE_BYTES {
+        anyhow::bail!(
+            "Image is too large to describe ({size} bytes > {MAX_SOURCE_IMAGE_BYTES} bytes)"
+        );
+    }
+
+    // Prefer a downscaled JPEG; the multimodal limits are still enforced on
+    // whichever form is sent.
+    let image_ref = match downscale_image(&abs_path).await {
+        Some(jpeg) => format!("data:image/jpeg;base64,{}", STANDARD.encode(jpeg)),
+        None => abs_path.display().to_string(),
+    };
+    let messages = vec![ChatMessage::user(format!(
+        "{DESCRIBE_PROMPT}\n\n[IMAGE:{image_ref}]"
+    ))];
+    let prepared =
+        crate::multimodal::prepare_messages_for_provider(&messages, &config.multimodal).await?;
+
+    let provider_name = config
+        .default_provider
+        .as_deref()
+        .unwrap_or(providers::DEFAULT_PROVIDER);
+    let model = config
+        .default_model
+        .clone()
+        .unwrap_or_else(|| providers::DEFAULT_MODEL.into());
+    let provider = providers::create_resilient_provider_with_options(
+        provider_name,
+        config.api_key.as_deref(),
+        config.api_url.as_deref(),
+        &config.reliability,
+        &providers::ProviderRuntimeOptions {
+            auth_profile_override: None,
+            provider_api_url: config.api_url.clone(),
+            zeroclaw_dir: config.config_path.parent().map(PathBuf::from),
+            secrets_encrypt: config.secrets.encrypt,
+            reasoning_enabled: config.runtime.reasoning_enabled,
+        },
+    )?;
+    if !provider.supports_vision() {
+        anyhow::bail!("Provider '{provider_name}' does not support vision input");
+    }
+    let raw = provider
+        .chat_with_history(&prepared.messages, &model, 0.2)
+        .await?;
+    let (caption, extracted_text) = parse_description(&raw);
+    if caption.is_empty() {
+        anyhow::bail!("Provider returned an empty image description");
+    }
+
+    let caption_rel = format!("{rel_path}{CAPTION_SIDECAR_SUFFIX}");
+    let caption_path = resolve_contained_path(
+        &config.workspace_dir,
+        &caption_rel,
+        ResolveMode::CreatableFile,
+    )
+    .map_err(|err| anyhow::anyhow!("Caption path {caption_rel} is not writable: {err}"))?;
+    tokio::fs::write(
+        caption_path,
+        caption_sidecar_body(&caption, &extracted_text),
+    )
+    .await?;
+
+    let preview = crate::util::truncate_with_ellipsis(&caption, PREVIEW_MAX_CHARS);
+    let metadata_updated = match local_store::update_media_asset_preview_text(
+        &config.workspace_dir,
+        rel_path,
+        &preview,
+    ) {
+        Ok(rows) => rows > 0,
+        Err(err) => {
+            tracing::warn!("image_describe: media metadata update failed: {err}");
+            false
+        }
+    };
+
+    Ok(ImageDescription {
+        image_path: rel_path.to_string(),
+        caption_path: caption_rel,
+        caption,
+        extracted_text,
+        metadata_updated,
+    })
+}
+
+/// Caption workspace images through the configured vision-capable provider.
+pub struct ImageDescribeTool {
+    config: Arc<Config>,
+    security: Arc<SecurityPolicy>,
+}
+
+impl ImageDescribeTool {
+    pub fn new(config: Arc<Config>, security: Arc<SecurityPolicy>) -> Self {
+        Self { config, security }
+    }
+}
+
+#[async_trait]
+impl Tool for ImageDescribeTool {
+    fn name(&self) -> &str {
+        "image_describe"
+    }
+
+    fn description(&self) -> &str {
+        "Caption a workspace image with the vision model, extract any visible text, and save it as a .caption.txt sidecar used as the library preview."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "imagePath": { "type": "string", "description": "Workspace-relative path to the image, e.g. journals/media/image/photo.jpg" }
+            },
+            "required": ["imagePath"]
+        })
+    }
+
+    async fn execute(&self, args: serde_json::Value) -> anyhow::Result<ToolResult> {
+        let image_path = args
+            .get("imagePath")
+            .and_then(|value| value.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Missing 'imagePath' parameter"))?;
+
+        if !self.security.can_act() {
+            return Ok(ToolResult {
+                success: false,
+                output: String::new(),
+                error: Some("Action blocked: autonomy is read-only".into()),
+            });
+        }
+        if self.security.is_rate_limited_for(self.name()) {
+            return Ok(ToolResult {
+                success: false,
+                output: String::new(),
+                error: Some(self.security.rate_limit_message(self.name())),
+            });
+        }
+        if !is_safe_relative_path(image_path) || !self.security.is_path_allowed(image_path) {
+            return Ok(ToolResult {
+                success: false,
+                output: String::new(),
+                error: Some(format!("Path not allowed for image_describe: {image_path}")),
+            });
+        }
+        if !self.security.record_tool_action(self.name()) {
+            return Ok(ToolResult {
+                success: false,
+                output: String::new(),
+                error: Some(self.security.rate_limit_message(self.name())),
+            });
+        }
+
+        match describe_workspace_image(&self.config, image_path).await {
+            Ok(description) => Ok(ToolResult {
+                success: true,
+                output: serde_json::to_string_pretty(&description)?,
+                error: None,
+            }),
+            Err(err) => Ok(ToolResult {
+                success: false,
+                output: String::new(),
+                error: Some(err.to_string()),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_description_reads_json_payload() {
+        let (caption, text) = parse_description(
+            "Sure!\n```json\n{\"caption\": \"A red bike by a wall\", \"text\": \"NO PARKING\"}\n```",
+        );
+        assert_eq!(caption, "A red bike by a wall");
+        assert_eq!(text, "NO PARKING");
+    }
+
+    #[test]
+    fn parse_description_falls_back_to_raw_text() {
+        let (caption, text) = parse_description("  A quiet beach at dusk.  ");
+        assert_eq!(caption, "A quiet beach at dusk.");
+        assert!(text.is_empty());
+    }
+
+    #[test]
+    fn sidecar_body_includes_text_only_when_present() {
+        assert_eq!(caption_sidecar_body("Cat", ""), "Cat\n");
+        assert!(caption_sidecar_body("Sign", "OPEN").contains("Text in image:\nOPEN"));
+    }
+
+    #[test]
+    fn rejects_traversal_and_absolute_paths() {
+        assert!(is_safe_relative_path("journals/media/image/a.jpg"));
+        assert!(!is_safe_relative_path("../secret.jpg"));
+        assert!(!is_safe_relative_path("/etc/passwd"));
+        assert!(!is_safe_relative_path(""));
+    }
+
+    #[tokio::test]
+    async fn tool_blocks_paths_outside_workspace() {
+        let tool = ImageDescribeTool::new(
+            Arc::new(Config::default()),
+            Arc::new(SecurityPolicy::default()),
+        );
+        let result = tool
+            .execute(json!({ "imagePath": "../outside.png" }))
+            .await
+            .unwrap();
+        assert!(!result.success);
+        assert!(result.error.unwrap().contains("Path not allowed"));
+    }
+}
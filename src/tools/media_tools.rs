@@ -3,9 +3,11 @@ use crate::media::{
     ComposeSimpleClipRequest, ContentMediaBackend, MediaCard, RenderTextCardVideoRequest,
     SharedContentMediaBackend, StitchImagesWithAudioRequest,
 };
+use crate::jobs::{JobManager, JobOutcome};
 use crate::security::SecurityPolicy;
 use async_trait::async_trait;
 use serde_json::json;
+use std::future::Future;
 use std::sync::Arc;
 
 fn parse_media_cards(args: &serde_json::Value) -> anyhow::Result<Vec<MediaCard>> {
@@ -35,11 +37,11 @@ fn validate_media_paths(
     paths: &[String],
     action_name: &str,
 ) -> Option<ToolResult> {
-    if security.is_rate_limited() {
+    if security.is_rate_limited_for(action_name) {
         return Some(ToolResult {
             success: false,
             output: String::new(),
-            error: Some("Rate limit exceeded: too many actions in the last hour".into()),
+            error: Some(security.rate_limit_message(action_name)),
         });
     }
 
@@ -60,11 +62,11 @@ fn validate_media_paths(
         }
     }
 
-    if !security.record_action() {
+    if !security.record_tool_action(action_name) {
         return Some(ToolResult {
             success: false,
             output: String::new(),
-            error: Some("Rate limit exceeded: action budget exhausted".into()),
+            error: Some(security.rate_limit_message(action_name)),
         });
     }
 
@@ -96,8 +98,44 @@ impl MediaToolBase {
     fn new(backend: SharedContentMediaBackend, security: Arc<SecurityPolicy>) -> Self {
         Self { security, backend }
     }
+
+    /// Await `work` inline, or enqueue it as a background job when the call
+    /// sets `async: true` so long renders do not block the agent turn.
+    async fn run_or_enqueue<T, F>(
+        &self,
+        kind: &str,
+        args: &serde_json::Value,
+        output_path: &str,
+        work: F,
+    ) -> anyhow::Result<ToolResult>
+    where
+        T: serde::Serialize,
+        F: Future<Output = anyhow::Result<T>> + Send + 'static,
+    {
+        if !args.get("async").and_then(|value| value.as_bool()).unwrap_or(false) {
+            return match work.await {
+                Ok(result) => json_result(&result),
+                Err(err) => Ok(error_result(err.to_string())),
+            };
+        }
+        let artifact = output_path.to_string();
+        let job_id = JobManager::for_workspace(&self.security.workspace_dir).enqueue(
+            kind,
+            async move {
+                let result = work.await?;
+                Ok(JobOutcome {
+                    output: serde_json::to_string_pretty(&result)?,
+                    artifacts: vec![artifact],
+                })
+            },
+        );
+        json_result(&json!({ "jobId": job_id, "status": "queued" }))
+    }
 }
 
+const ASYNC_PARAM_DESCRIPTION: &str =
+    "Run as a background job and return a job id immediately. Poll /api/jobs/{id} for status.";
+
 pub struct TranscribeMediaTool {
     inner: MediaToolBase,
 }
@@ -339,7 +377,8 @@ impl Tool for RenderTextCardVideoTool {
                 "height": { "type": "integer", "default": 1920 },
                 "fps": { "type": "integer", "default": 30 },
                 "theme": { "type": "string", "enum": ["black_white"], "default": "black_white" },
-                "outputPath": { "type": "string" }
+                "outputPath": { "type": "string" },
+                "async": { "type": "boolean", "default": false, "description": ASYNC_PARAM_DESCRIPTION }
             },
             "required": ["cards", "outputPath"]
         })
@@ -366,10 +405,12 @@ impl Tool for RenderTextCardVideoTool {
             theme: args.get("theme").and_then(|value| value.as_str()).map(str::to_string),
             output_path: output_path.to_string(),
         };
-        match self.inner.backend.render_text_card_video(&request).await {
-            Ok(result) => json_result(&result),
-            Err(err) => Ok(error_result(err.to_string())),
-        }
+        let backend = self.inner.backend.clone();
+        self.inner
+            .run_or_enqueue(self.name(), &args, output_path, async move {
+                backend.render_text_card_video(&request).await
+            })
+            .await
     }
 }
 
@@ -405,7 +446,8 @@ impl Tool for StitchImagesWithAudioTool {
                 "width": { "type": "integer", "default": 1080 },
                 "height": { "type": "integer", "default": 1920 },
                 "fps": { "type": "integer", "default": 30 },
-                "outputPath": { "type": "string" }
+                "outputPath": { "type": "string" },
+                "async": { "type": "boolean", "default": false, "description": ASYNC_PARAM_DESCRIPTION }
             },
             "required": ["imagePaths", "audioPath", "outputPath"]
         })
@@ -440,10 +482,12 @@ impl Tool for StitchImagesWithAudioTool {
             fps: args.get("fps").and_then(|value| value.as_u64()).map(|value| value as u32),
             output_path: output_path.to_string(),
         };
-        match self.inner.backend.stitch_images_with_audio(&request).await {
-            Ok(result) => json_result(&result),
-            Err(err) => Ok(error_result(err.to_string())),
-        }
+        let backend = self.inner.backend.clone();
+        self.inner
+            .run_or_enqueue(self.name(), &args, output_path, async move {
+                backend.stitch_images_with_audio(&request).await
+            })
+            .await
     }
 }
 
@@ -489,7 +533,8 @@ impl Tool for ComposeSimpleClipTool {
                 "imagePaths": { "type": "array", "items": { "type": "string" } },
                 "title": { "type": "string" },
                 "preset": { "type": "string", "enum": ["audio_insight_basic"], "default": "audio_insight_basic" },
-                "outputPath": { "type": "string" }
+                "outputPath": { "type": "string" },
+                "async": { "type": "boolean", "default": false, "description": ASYNC_PARAM_DESCRIPTION }
             },
             "required": ["audioPath", "outputPath"]
         })
@@ -522,9 +567,11 @@ impl Tool for ComposeSimpleClipTool {
             preset: args.get("preset").and_then(|value| value.as_str()).map(str::to_string),
             output_path: output_path.to_string(),
         };
-        match self.inner.backend.compose_simple_clip(&request).await {
-            Ok(result) => json_result(&result),
-            Err(err) => Ok(error_result(err.to_string())),
-        }
+        let backend = self.inner.backend.clone();
+        self.inner
+            .run_or_enqueue(self.name(), &args, output_path, async move {
+                backend.compose_simple_clip(&request).await
+            })
+            .await
     }
 }
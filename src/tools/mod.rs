@@ -15,6 +15,7 @@
 //! To add a new tool, implement [`Tool`] in a new submodule and register it in
 //! [`all_tools_with_runtime`]. See `AGENTS.md` §7.3 for the full change playbook.
 
+pub mod bluesky_post;
 pub mod cli_discovery;
 pub mod content_search;
 pub mod file_edit;
@@ -22,23 +23,28 @@ pub mod file_read;
 pub mod file_write;
 pub mod git_operations;
 pub mod glob_search;
+pub mod image_describe;
 pub mod memory_forget;
 pub mod memory_recall;
 pub mod memory_store;
 pub mod media_tools;
 pub mod model_routing_config;
+pub mod post_draft;
 pub mod schema;
 pub mod shell;
+pub mod skill_script;
 pub mod traits;
 pub mod web_search_tool;
 pub mod task_plan;
 
+pub use bluesky_post::BlueskyPostTool;
 pub use content_search::ContentSearchTool;
 pub use file_edit::FileEditTool;
 pub use file_read::FileReadTool;
 pub use file_write::FileWriteTool;
 pub use git_operations::GitOperationsTool;
 pub use glob_search::GlobSearchTool;
+pub use image_describe::ImageDescribeTool;
 pub use memory_forget::MemoryForgetTool;
 pub use memory_recall::MemoryRecallTool;
 pub use memory_store::MemoryStoreTool;
@@ -47,9 +53,11 @@ pub use media_tools::{
     StitchImagesWithAudioTool, TranscribeMediaTool,
 };
 pub use model_routing_config::ModelRoutingConfigTool;
+pub use post_draft::PostDraftTool;
 #[allow(unused_imports)]
 pub use schema::{CleaningStrategy, SchemaCleanr};
 pub use shell::ShellTool;
+pub use skill_script::SkillScriptTool;
 pub use traits::Tool;
 #[allow(unused_imports)]
 pub use traits::{ToolResult, ToolSpec};
@@ -110,7 +118,7 @@ fn tool_allowed_in_profile(name: &str, profile: ToolProfile) -> bool {
         ToolProfile::Full => true,
         ToolProfile::UiRestricted => !matches!(
             name,
-            "shell" | "git_operations"
+            "shell" | "git_operations" | "run_skill_script"
         ),
     }
 }
@@ -246,6 +254,11 @@ pub fn all_tools_with_runtime_and_profile(
             security.clone(),
         )),
         Arc::new(TaskPlanTool::new(security.clone())),
+        Arc::new(ImageDescribeTool::new(config.clone(), security.clone())),
+        Arc::new(PostDraftTool::new(
+            security.clone(),
+            workspace_dir.to_path_buf(),
+        )),
         Arc::new(GitOperationsTool::new(
             security.clone(),
             workspace_dir.to_path_buf(),
@@ -289,6 +302,14 @@ pub fn all_tools_with_runtime_and_profile(
         )));
     }
 
+    if let Some(skill_scripts) = SkillScriptTool::from_workspace(security.clone(), workspace_dir) {
+        tool_arcs.push(Arc::new(skill_scripts));
+    }
+
+    if let Some(bluesky_post) = BlueskyPostTool::from_env(security.clone(), workspace_dir) {
+        tool_arcs.push(Arc::new(bluesky_post));
+    }
+
     // Web search tool (enabled by default for GLM and other models)
     if root_config.web_search.enabled {
         tool_arcs.push(Arc::new(WebSearchTool::new(
@@ -49,11 +49,11 @@ impl ModelRoutingConfigTool {
             });
         }
 
-        if !self.security.record_action() {
+        if !self.security.record_tool_action(self.name()) {
             return Some(ToolResult {
                 success: false,
                 output: String::new(),
-                error: Some("Action blocked: rate limit exceeded".into()),
+                error: Some(self.security.rate_limit_message(self.name())),
             });
         }
 
@@ -0,0 +1,142 @@
+use super::traits::{Tool, ToolResult};
+use crate::post_drafts;
+use crate::security::SecurityPolicy;
+use async_trait::async_trait;
+use serde_json::json;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Propose a social post for human approval instead of publishing it.
+pub struct PostDraftTool {
+    security: Arc<SecurityPolicy>,
+    workspace_dir: PathBuf,
+}
+
+impl PostDraftTool {
+    pub fn new(security: Arc<SecurityPolicy>, workspace_dir: PathBuf) -> Self {
+        Self {
+            security,
+            workspace_dir,
+        }
+    }
+}
+
+fn failure(error: impl Into<String>) -> ToolResult {
+    ToolResult {
+        success: false,
+        output: String::new(),
+        error: Some(error.into()),
+    }
+}
+
+#[async_trait]
+impl Tool for PostDraftTool {
+    fn name(&self) -> &str {
+        "post_draft"
+    }
+
+    fn description(&self) -> &str {
+        "Save a proposed social post as a draft for the user to approve or reject from their phone. Nothing is published until they approve it."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "text": { "type": "string", "description": "Post text, at most 300 graphemes for Bluesky" },
+                "channel": { "type": "string", "enum": post_drafts::SUPPORTED_CHANNELS, "default": "bluesky" },
+                "media": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Workspace-relative image paths to attach (at most 4)"
+                }
+            },
+            "required": ["text"]
+        })
+    }
+
+    async fn execute(&self, args: serde_json::Value) -> anyhow::Result<ToolResult> {
+        let text = args
+            .get("text")
+            .and_then(|value| value.as_str())
+            .unwrap_or_default()
+            .trim()
+            .to_string();
+        let channel = args
+            .get("channel")
+            .and_then(|value| value.as_str())
+            .unwrap_or("bluesky")
+            .to_string();
+        let media: Vec<String> = args
+            .get("media")
+            .and_then(|value| value.as_array())
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(|item| item.as_str())
+                    .map(|item| item.trim().to_string())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        if !self.security.can_act() {
+            return Ok(failure("Action blocked: autonomy is read-only"));
+        }
+        if let Some(path) = media
+            .iter()
+            .find(|path| !self.security.is_path_allowed(path))
+        {
+            return Ok(failure(format!("Path not allowed for post_draft: {path}")));
+        }
+        if !self.security.record_tool_action(self.name()) {
+            return Ok(failure(self.security.rate_limit_message(self.name())));
+        }
+
+        let workspace_dir = self.workspace_dir.clone();
+        let created = tokio::task::spawn_blocking(move || {
+            post_drafts::create(&workspace_dir, &text, &channel, media)
+        })
+        .await?;
+        match created {
+            Ok(draft) => Ok(ToolResult {
+                success: true,
+                output: serde_json::to_string_pretty(&json!({
+                    "draft": draft,
+                    "message": "Draft saved and waiting for approval; it has not been posted.",
+                }))?,
+                error: None,
+            }),
+            Err(err) => Ok(failure(err.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn saves_pending_draft_without_posting() {
+        let tmp = tempfile::tempdir().unwrap();
+        let tool = PostDraftTool::new(
+            Arc::new(SecurityPolicy {
+                workspace_dir: tmp.path().to_path_buf(),
+                ..SecurityPolicy::default()
+            }),
+            tmp.path().to_path_buf(),
+        );
+        let result = tool
+            .execute(json!({ "text": "Morning walk photos soon" }))
+            .await
+            .unwrap();
+        assert!(result.success, "{:?}", result.error);
+        let output: serde_json::Value = serde_json::from_str(&result.output).unwrap();
+        assert_eq!(output["draft"]["status"], "pending");
+
+        let result = tool
+            .execute(json!({ "text": "x".repeat(301) }))
+            .await
+            .unwrap();
+        assert!(result.error.unwrap().contains("graphemes"));
+    }
+}
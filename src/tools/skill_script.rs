@@ -0,0 +1,667 @@
+use super::shell::{collect_allowed_shell_env_vars, truncate_utf8_to_max_bytes};
+use super::traits::{Tool, ToolResult};
+use crate::channels::context::channel_context_env;
+use crate::jobs::{JobManager, JobOutcome};
+use crate::security::SecurityPolicy;
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::json;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Manifest file name expected inside each `skills/<name>/` directory.
+const SKILL_SCRIPT_MANIFEST: &str = "skill.toml";
+/// Timeout applied when a manifest does not declare one.
+const DEFAULT_SKILL_TIMEOUT_SECS: u64 = 60;
+/// Hard ceiling for any manifest-declared timeout (1 hour).
+const MAX_SKILL_TIMEOUT_SECS: u64 = 3600;
+/// Maximum output size in bytes (1MB).
+const MAX_OUTPUT_BYTES: usize = 1_048_576;
+/// Interpreters a manifest may name. Anything else is rejected at load time.
+const ALLOWED_INTERPRETERS: &[&str] = &["python3", "python", "node", "bash", "sh"];
+
+fn default_skill_timeout_secs() -> u64 {
+    DEFAULT_SKILL_TIMEOUT_SECS
+}
+
+/// Script manifest parsed from `skills/<name>/skill.toml`.
+#[derive(Debug, Clone, Deserialize)]
+struct SkillScriptManifest {
+    #[serde(default)]
+    description: String,
+    entrypoint: String,
+    interpreter: String,
+    #[serde(default = "default_skill_timeout_secs")]
+    timeout_secs: u64,
+    #[serde(default)]
+    args_schema: Option<serde_json::Value>,
+}
+
+/// A validated, invocable skill script.
+#[derive(Debug, Clone)]
+struct SkillScript {
+    name: String,
+    description: String,
+    skill_dir: PathBuf,
+    entrypoint: PathBuf,
+    interpreter: String,
+    timeout_secs: u64,
+    args_schema: serde_json::Value,
+}
+
+fn is_valid_skill_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '_' || ch == '-')
+}
+
+fn load_skill_script(
+    name: &str,
+    skill_dir: &Path,
+    workspace_root: &Path,
+) -> anyhow::Result<SkillScript> {
+    let skill_dir = skill_dir.canonicalize()?;
+    if !skill_dir.starts_with(workspace_root) {
+        anyhow::bail!("skill directory resolves outside the workspace");
+    }
+    let manifest_path = skill_dir.join(SKILL_SCRIPT_MANIFEST).canonicalize()?;
+    if !manifest_path.starts_with(&skill_dir) {
+        anyhow::bail!("manifest resolves outside the skill directory");
+    }
+
+    let manifest: SkillScriptManifest = toml::from_str(&std::fs::read_to_string(&manifest_path)?)?;
+
+    if !ALLOWED_INTERPRETERS.contains(&manifest.interpreter.as_str()) {
+        anyhow::bail!("interpreter '{}' is not allowed", manifest.interpreter);
+    }
+    let entrypoint = Path::new(manifest.entrypoint.trim());
+    if entrypoint.as_os_str().is_empty()
+        || entrypoint.is_absolute()
+        || entrypoint
+            .components()
+            .any(|c| matches!(c, Component::ParentDir))
+    {
+        anyhow::bail!("entrypoint must be a relative path inside the skill directory");
+    }
+    let entrypoint = skill_dir.join(entrypoint).canonicalize()?;
+    if !entrypoint.starts_with(&skill_dir) || !entrypoint.is_file() {
+        anyhow::bail!("entrypoint resolves outside the skill directory");
+    }
+
+    let args_schema = manifest
+        .args_schema
+        .unwrap_or_else(|| json!({ "type": "object", "properties": {} }));
+    if !args_schema
+        .get("properties")
+        .map_or(true, serde_json::Value::is_object)
+    {
+        anyhow::bail!("args_schema.properties must be a table");
+    }
+
+    Ok(SkillScript {
+        name: name.to_string(),
+        description: manifest.description.trim().to_string(),
+        skill_dir,
+        entrypoint,
+        interpreter: manifest.interpreter,
+        timeout_secs: manifest.timeout_secs.clamp(1, MAX_SKILL_TIMEOUT_SECS),
+        args_schema,
+    })
+}
+
+/// Discover every `skills/<name>/skill.toml` manifest inside the workspace.
+/// Invalid manifests are skipped with a warning so one broken skill does not
+/// hide the rest.
+fn load_skill_scripts(workspace_dir: &Path) -> Vec<SkillScript> {
+    let Ok(workspace_root) = workspace_dir.canonicalize() else {
+        return Vec::new();
+    };
+    let Ok(entries) = std::fs::read_dir(crate::skills::skills_dir(&workspace_root)) else {
+        return Vec::new();
+    };
+
+    let mut scripts = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if !path.join(SKILL_SCRIPT_MANIFEST).exists() {
+            continue;
+        }
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        if !is_valid_skill_name(name) {
+            tracing::warn!(
+                "Skipping skill script with invalid name: {}",
+                path.display()
+            );
+            continue;
+        }
+        match load_skill_script(name, &path, &workspace_root) {
+            Ok(script) => scripts.push(script),
+            Err(e) => tracing::warn!("Skipping skill script '{name}': {e}"),
+        }
+    }
+    scripts.sort_by(|a, b| a.name.cmp(&b.name));
+    scripts
+}
+
+/// Validate call arguments against the manifest schema. Only the subset of
+/// JSON schema that maps onto CLI flags is supported: a flat object with
+/// scalar `string`/`integer`/`number`/`boolean` properties, `enum`, and
+/// `required`. Unknown keys are rejected.
+fn validate_skill_args(
+    schema: &serde_json::Value,
+    args: &serde_json::Map<String, serde_json::Value>,
+) -> Result<(), String> {
+    let empty = serde_json::Map::new();
+    let properties = schema
+        .get("properties")
+        .and_then(|v| v.as_object())
+        .unwrap_or(&empty);
+
+    if let Some(required) = schema.get("required").and_then(|v| v.as_array()) {
+        for key in required.iter().filter_map(|v| v.as_str()) {
+            if !args.contains_key(key) {
+                return Err(format!("Missing required argument '{key}'"));
+            }
+        }
+    }
+
+    for (key, value) in args {
+        let Some(property) = properties.get(key) else {
+            return Err(format!("Unknown argument '{key}'"));
+        };
+        let expected = property
+            .get("type")
+            .and_then(|v| v.as_str())
+            .unwrap_or("string");
+        let type_ok = match expected {
+            "string" => value.is_string(),
+            "integer" => value.is_i64() || value.is_u64(),
+            "number" => value.is_number(),
+            "boolean" => value.is_boolean(),
+            other => return Err(format!("Unsupported schema type '{other}' for '{key}'")),
+        };
+        if !type_ok {
+            return Err(format!("Argument '{key}' must be of type {expected}"));
+        }
+        if let Some(allowed) = property.get("enum").and_then(|v| v.as_array()) {
+            if !allowed.contains(value) {
+                return Err(format!("Argument '{key}' is not one of the allowed values"));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Render validated arguments as `--key=value` flags. Values stay attached to
+/// their flag so a leading `-` can never be parsed as a separate option.
+fn build_skill_cli_args(args: &serde_json::Map<String, serde_json::Value>) -> Vec<String> {
+    let mut keys: Vec<&String> = args.keys().collect();
+    keys.sort();
+    let mut out = Vec::with_capacity(keys.len());
+    for key in keys {
+        match &args[key] {
+            serde_json::Value::Bool(true) => out.push(format!("--{key}")),
+            serde_json::Value::Bool(false) | serde_json::Value::Null => {}
+            serde_json::Value::String(s) => out.push(format!("--{key}={s}")),
+            other => out.push(format!("--{key}={other}")),
+        }
+    }
+    out
+}
+
+fn render_skill_description(scripts: &[SkillScript]) -> String {
+    let mut out = String::from(
+        "Run a workspace skill script declared in skills/<name>/skill.toml. \
+         Pass the skill name and its arguments as an object. Available skills:",
+    );
+    for script in scripts {
+        out.push_str("\n- ");
+        out.push_str(&script.name);
+        if !script.description.is_empty() {
+            out.push_str(": ");
+            out.push_str(&script.description);
+        }
+        let properties = script
+            .args_schema
+            .get("properties")
+            .and_then(|v| v.as_object());
+        if let Some(properties) = properties.filter(|p| !p.is_empty()) {
+            let names: Vec<&str> = properties.keys().map(String::as_str).collect();
+            out.push_str(" (args: ");
+            out.push_str(&names.join(", "));
+            out.push(')');
+        }
+    }
+    out
+}
+
+fn error_result(error: impl Into<String>) -> ToolResult {
+    ToolResult {
+        success: false,
+        output: String::new(),
+        error: Some(error.into()),
+    }
+}
+
+/// Generic runner for manifest-declared workspace scripts.
+pub struct SkillScriptTool {
+    security: Arc<SecurityPolicy>,
+    scripts: Vec<SkillScript>,
+    description: String,
+    jobs: Arc<JobManager>,
+}
+
+impl SkillScriptTool {
+    /// Load skill manifests from the workspace. Returns `None` when no valid
+    /// skill script is declared, so the tool is only registered when useful.
+    pub fn from_workspace(security: Arc<SecurityPolicy>, workspace_dir: &Path) -> Option<Self> {
+        let scripts = load_skill_scripts(workspace_dir);
+        if scripts.is_empty() {
+            return None;
+        }
+        let description = render_skill_description(&scripts);
+        Some(Self {
+            security,
+            scripts,
+            description,
+            jobs: JobManager::for_workspace(workspace_dir),
+        })
+    }
+}
+
+#[async_trait]
+impl Tool for SkillScriptTool {
+    fn name(&self) -> &str {
+        "run_skill_script"
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        let names: Vec<&str> = self.scripts.iter().map(|s| s.name.as_str()).collect();
+        json!({
+            "type": "object",
+            "properties": {
+                "skill": {
+                    "type": "string",
+                    "enum": names,
+                    "description": "Name of the skill to run"
+                },
+                "args": {
+                    "type": "object",
+                    "description": "Arguments declared by the skill manifest"
+                },
+                "async": {
+                    "type": "boolean",
+                    "default": false,
+                    "description": "Run as a background job and return a job id immediately"
+                },
+                "timeoutSecs": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "description": "Optional timeout; capped at the skill's declared ceiling"
+                }
+            },
+            "required": ["skill"]
+        })
+    }
+
+    async fn execute(&self, args: serde_json::Value) -> anyhow::Result<ToolResult> {
+        let skill_name = args
+            .get("skill")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Missing 'skill' parameter"))?;
+        let empty = serde_json::Map::new();
+        let skill_args = match args.get("args") {
+            None | Some(serde_json::Value::Null) => &empty,
+            Some(serde_json::Value::Object(map)) => map,
+            Some(_) => return Ok(error_result("'args' must be an object")),
+        };
+
+        let Some(script) = self.scripts.iter().find(|s| s.name == skill_name) else {
+            return Ok(error_result(format!("Unknown skill: {skill_name}")));
+        };
+
+        if !self.security.can_act() {
+            return Ok(error_result("Action blocked: autonomy is read-only"));
+        }
+        // A skill can carry its own budget weight under its name.
+        let budget_key = if self.security.tool_action_weights.contains_key(skill_name) {
+            skill_name
+        } else {
+            self.name()
+        };
+        if self.security.is_rate_limited_for(budget_key) {
+            return Ok(error_result(self.security.rate_limit_message(budget_key)));
+        }
+        if let Err(reason) = validate_skill_args(&script.args_schema, skill_args) {
+            return Ok(error_result(reason));
+        }
+
+        // Re-resolve at execution time so a symlink swapped in after load
+        // cannot redirect the interpreter outside the workspace.
+        let entrypoint = match script.entrypoint.canonicalize() {
+            Ok(path) => path,
+            Err(e) => return Ok(error_result(format!("Skill entrypoint unavailable: {e}"))),
+        };
+        if !entrypoint.starts_with(&script.skill_dir)
+            || !self.security.is_resolved_path_allowed(&entrypoint)
+        {
+            return Ok(error_result(
+                self.security.resolved_path_violation_message(&entrypoint),
+            ));
+        }
+
+        if !self.security.record_tool_action(budget_key) {
+            return Ok(error_result(self.security.rate_limit_message(budget_key)));
+        }
+
+        let timeout_secs = args
+            .get("timeoutSecs")
+            .and_then(|v| v.as_u64())
+            .map_or(script.timeout_secs, |requested| {
+                requested.clamp(1, script.timeout_secs)
+            });
+
+        let cli_args = build_skill_cli_args(skill_args);
+        if args.get("async").and_then(|v| v.as_bool()).unwrap_or(false) {
+            let security = self.security.clone();
+            let interpreter = script.interpreter.clone();
+            let name = skill_name.to_string();
+            let job_id = self
+                .jobs
+                .enqueue(&format!("run_skill_script:{skill_name}"), async move {
+                    let result = run_skill_command(
+                        &security,
+                        &name,
+                        &interpreter,
+                        &entrypoint,
+                        cli_args,
+                        timeout_secs,
+                    )
+                    .await;
+                    if result.success {
+                        Ok(JobOutcome {
+                            output: result.output,
+                            artifacts: Vec::new(),
+                        })
+                    } else {
+                        anyhow::bail!(result.error.unwrap_or_else(|| "skill script failed".into()))
+                    }
+                });
+            return Ok(ToolResult {
+                success: true,
+                output: json!({ "jobId": job_id, "status": "queued" }).to_string(),
+                error: None,
+            });
+        }
+
+        Ok(run_skill_command(
+            &self.security,
+            skill_name,
+            &script.interpreter,
+            &entrypoint,
+            cli_args,
+            timeout_secs,
+        )
+        .await)
+    }
+}
+
+async fn run_skill_command(
+    security: &SecurityPolicy,
+    skill_name: &str,
+    interpreter: &str,
+    entrypoint: &Path,
+    cli_args: Vec<String>,
+    timeout_secs: u64,
+) -> ToolResult {
+    let mut cmd = tokio::process::Command::new(interpreter);
+    cmd.arg(entrypoint)
+        .args(cli_args)
+        .current_dir(&security.workspace_dir)
+        .env_clear()
+        .kill_on_drop(true);
+    for var in collect_allowed_shell_env_vars(security) {
+        if let Ok(val) = std::env::var(&var) {
+            cmd.env(&var, val);
+        }
+    }
+    cmd.envs(channel_context_env());
+
+    let result = tokio::time::timeout(Duration::from_secs(timeout_secs), cmd.output()).await;
+
+    match result {
+        Ok(Ok(output)) => {
+            let mut stdout = String::from_utf8_lossy(&output.stdout).to_string();
+            let mut stderr = String::from_utf8_lossy(&output.stderr).to_string();
+
+            if stdout.len() > MAX_OUTPUT_BYTES {
+                truncate_utf8_to_max_bytes(&mut stdout, MAX_OUTPUT_BYTES);
+                stdout.push_str("\n... [output truncated at 1MB]");
+            }
+            if stderr.len() > MAX_OUTPUT_BYTES {
+                truncate_utf8_to_max_bytes(&mut stderr, MAX_OUTPUT_BYTES);
+                stderr.push_str("\n... [stderr truncated at 1MB]");
+            }
+
+            ToolResult {
+                success: output.status.success(),
+                output: stdout,
+                error: if stderr.is_empty() {
+                    None
+                } else {
+                    Some(stderr)
+                },
+            }
+        }
+        Ok(Err(e)) => error_result(format!("Failed to execute skill script: {e}")),
+        Err(_) => error_result(format!(
+            "Skill '{skill_name}' timed out after {timeout_secs}s and was killed"
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security::AutonomyLevel;
+    use tempfile::TempDir;
+
+    fn write_skill(workspace: &Path, name: &str, manifest: &str, script: &str) {
+        let dir = workspace.join("skills").join(name);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(SKILL_SCRIPT_MANIFEST), manifest).unwrap();
+        std::fs::write(dir.join("run.sh"), script).unwrap();
+    }
+
+    fn test_security(workspace: &Path, autonomy: AutonomyLevel) -> Arc<SecurityPolicy> {
+        Arc::new(SecurityPolicy {
+            autonomy,
+            workspace_dir: workspace.to_path_buf(),
+            ..SecurityPolicy::default()
+        })
+    }
+
+    const ECHO_MANIFEST: &str = r#"
+description = "Echo the provided title"
+entrypoint = "run.sh"
+interpreter = "sh"
+timeout_secs = 10
+
+[args_schema]
+type = "object"
+required = ["title"]
+
+[args_schema.properties.title]
+type = "string"
+
+[args_schema.properties.loud]
+type = "boolean"
+"#;
+
+    #[test]
+    fn no_manifests_means_no_tool() {
+        let tmp = TempDir::new().unwrap();
+        let security = test_security(tmp.path(), AutonomyLevel::Supervised);
+        assert!(SkillScriptTool::from_workspace(security, tmp.path()).is_none());
+    }
+
+    #[test]
+    fn description_enumerates_skills() {
+        let tmp = TempDir::new().unwrap();
+        write_skill(tmp.path(), "echo_title", ECHO_MANIFEST, "echo \"$@\"\n");
+        let security = test_security(tmp.path(), AutonomyLevel::Supervised);
+        let tool = SkillScriptTool::from_workspace(security, tmp.path()).unwrap();
+        assert!(tool
+            .description()
+            .contains("echo_title: Echo the provided title"));
+        assert!(tool.description().contains("args: loud, title"));
+        assert_eq!(
+            tool.parameters_schema()["properties"]["skill"]["enum"],
+            json!(["echo_title"])
+        );
+    }
+
+    #[test]
+    fn rejects_disallowed_interpreter_and_escaping_entrypoint() {
+        let tmp = TempDir::new().unwrap();
+        write_skill(
+            tmp.path(),
+            "bad_interp",
+            "entrypoint = \"run.sh\"\ninterpreter = \"perl\"\n",
+            "",
+        );
+        write_skill(
+            tmp.path(),
+            "bad_entry",
+            "entrypoint = \"../bad_interp/run.sh\"\ninterpreter = \"sh\"\n",
+            "",
+        );
+        assert!(load_skill_scripts(tmp.path()).is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_skill_dir_symlinked_outside_workspace() {
+        let tmp = TempDir::new().unwrap();
+        let outside = TempDir::new().unwrap();
+        write_skill(outside.path(), "escape", ECHO_MANIFEST, "echo hi\n");
+        std::fs::create_dir_all(tmp.path().join("skills")).unwrap();
+        std::os::unix::fs::symlink(
+            outside.path().join("skills/escape"),
+            tmp.path().join("skills/escape"),
+        )
+        .unwrap();
+        assert!(load_skill_scripts(tmp.path()).is_empty());
+    }
+
+    #[test]
+    fn validate_args_enforces_schema() {
+        let schema = json!({
+            "required": ["title"],
+            "properties": {
+                "title": { "type": "string" },
+                "mode": { "type": "string", "enum": ["fast", "slow"] },
+                "count": { "type": "integer" }
+            }
+        });
+        let ok = json!({ "title": "x", "mode": "fast", "count": 2 });
+        assert!(validate_skill_args(&schema, ok.as_object().unwrap()).is_ok());
+
+        let missing = json!({ "mode": "fast" });
+        assert!(validate_skill_args(&schema, missing.as_object().unwrap())
+            .unwrap_err()
+            .contains("Missing required"));
+        let unknown = json!({ "title": "x", "extra": 1 });
+        assert!(validate_skill_args(&schema, unknown.as_object().unwrap())
+            .unwrap_err()
+            .contains("Unknown argument"));
+        let wrong_type = json!({ "title": "x", "count": "two" });
+        assert!(validate_skill_args(&schema, wrong_type.as_object().unwrap()).is_err());
+        let bad_enum = json!({ "title": "x", "mode": "medium" });
+        assert!(validate_skill_args(&schema, bad_enum.as_object().unwrap()).is_err());
+    }
+
+    #[test]
+    fn cli_args_keep_values_attached() {
+        let args = json!({ "title": "-rf", "loud": true, "quiet": false, "n": 3 });
+        assert_eq!(
+            build_skill_cli_args(args.as_object().unwrap()),
+            vec!["--loud", "--n=3", "--title=-rf"]
+        );
+    }
+
+    #[tokio::test]
+    async fn executes_declared_skill() {
+        let tmp = TempDir::new().unwrap();
+        write_skill(tmp.path(), "echo_title", ECHO_MANIFEST, "echo \"$@\"\n");
+        let security = test_security(tmp.path(), AutonomyLevel::Supervised);
+        let tool = SkillScriptTool::from_workspace(security, tmp.path()).unwrap();
+        let result = tool
+            .execute(json!({ "skill": "echo_title", "args": { "title": "hello" } }))
+            .await
+            .unwrap();
+        assert!(result.success, "{:?}", result.error);
+        assert_eq!(result.output.trim(), "--title=hello");
+    }
+
+    #[tokio::test]
+    async fn exports_chat_origin_to_skill_process() {
+        use crate::channels::context::{with_channel_execution_context, ChannelExecutionContext};
+
+        let tmp = TempDir::new().unwrap();
+        write_skill(
+            tmp.path(),
+            "echo_title",
+            ECHO_MANIFEST,
+            "echo \"$SLOWCLAW_ORIGIN_CHANNEL:$SLOWCLAW_ORIGIN_THREAD\"\n",
+        );
+        let security = test_security(tmp.path(), AutonomyLevel::Supervised);
+        let tool = SkillScriptTool::from_workspace(security, tmp.path()).unwrap();
+        let args = json!({ "skill": "echo_title", "args": { "title": "x" } });
+
+        let result = with_channel_execution_context(
+            ChannelExecutionContext::new("pocketbase", "thread-7", Some("thread-7".into())),
+            tool.execute(args.clone()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(result.output.trim(), "pocketbase:thread-7");
+
+        let result = tool.execute(args).await.unwrap();
+        assert_eq!(result.output.trim(), ":");
+    }
+
+    #[tokio::test]
+    async fn rejects_unknown_skill_and_readonly() {
+        let tmp = TempDir::new().unwrap();
+        write_skill(tmp.path(), "echo_title", ECHO_MANIFEST, "echo hi\n");
+
+        let tool = SkillScriptTool::from_workspace(
+            test_security(tmp.path(), AutonomyLevel::Supervised),
+            tmp.path(),
+        )
+        .unwrap();
+        let result = tool.execute(json!({ "skill": "nope" })).await.unwrap();
+        assert!(result.error.unwrap().contains("Unknown skill"));
+
+        let tool = SkillScriptTool::from_workspace(
+            test_security(tmp.path(), AutonomyLevel::ReadOnly),
+            tmp.path(),
+        )
+        .unwrap();
+        let result = tool
+            .execute(json!({ "skill": "echo_title", "args": { "title": "x" } }))
+            .await
+            .unwrap();
+        assert!(result.error.unwrap().contains("read-only"));
+    }
+}
@@ -13,10 +13,16 @@ struct BundledWorkflowAsset {
     content: &'static str,
 }
 
-const BUNDLED_WORKFLOW_ASSETS: &[BundledWorkflowAsset] = &[BundledWorkflowAsset {
-    rel_path: "skills/workflow_bot_creation/SKILL.md",
-    content: include_str!("../skills/workflow_bot_creation/SKILL.md"),
-}];
+const BUNDLED_WORKFLOW_ASSETS: &[BundledWorkflowAsset] = &[
+    BundledWorkflowAsset {
+        rel_path: "skills/workflow_bot_creation/SKILL.md",
+        content: include_str!("../skills/workflow_bot_creation/SKILL.md"),
+    },
+    BundledWorkflowAsset {
+        rel_path: "templates/daily-journal.md",
+        content: include_str!("../templates/daily-journal.md"),
+    },
+];
 
 /// Seed baseline workflow assets into a workspace without overwriting user edits.
 pub fn ensure_workspace_workflow_assets(workspace_dir: &Path) -> Result<WorkflowAssetSyncReport> {
@@ -39,7 +45,10 @@ pub fn ensure_workspace_workflow_assets(workspace_dir: &Path) -> Result<Workflow
         }
 
         std::fs::write(&target, asset.content).with_context(|| {
-            format!("Failed to write bundled workflow asset {}", target.display())
+            format!(
+                "Failed to write bundled workflow asset {}",
+                target.display()
+            )
         })?;
         report.created += 1;
     }
@@ -61,7 +70,7 @@ mod tests {
         std::fs::write(&preset_path, "custom-skill-body").unwrap();
 
         let report = ensure_workspace_workflow_assets(workspace).unwrap();
-        assert_eq!(report.created, 0);
+        assert_eq!(report.created, 1);
         assert_eq!(report.existing, 1);
 
         let preserved = std::fs::read_to_string(preset_path).unwrap();
@@ -74,10 +83,11 @@ mod tests {
         let workspace = tmp.path();
 
         let report = ensure_workspace_workflow_assets(workspace).unwrap();
-        assert_eq!(report.created, 1);
+        assert_eq!(report.created, 2);
         assert_eq!(report.existing, 0);
         assert!(workspace
             .join("skills/workflow_bot_creation/SKILL.md")
             .exists());
+        assert!(workspace.join("templates/daily-journal.md").exists());
     }
 }
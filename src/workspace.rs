@@ -0,0 +1,1138 @@
+//! Containment checks for workspace-relative paths that come from clients
+//! and tools.
+//!
+//! [`resolve_contained_path`] canonicalizes everything that already exists,
+//! so a symlink anywhere along the path (including the last component) is
+//! followed and then checked against the canonical workspace. A symlinked
+//! workspace directory is fine; a symlink that leads out of it is not,
+//! unless it leads into one of the external roots registered with
+//! [`set_external_roots`] (a media archive on a NAS mount, for example).
+//! Those are read-only unless registered as writable.
+//!
+//! [`atomic_write`] is how text in the workspace gets replaced, so a crash
+//! or a full disk never leaves a truncated file behind.
+
+use parking_lot::RwLock;
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// Files every workspace keeps. The agent's system prompt is built from
+/// them, and [`repair_skeleton`] recreates any that go missing.
+pub const CORE_WORKSPACE_FILES: [&str; 7] = [
+    "IDENTITY.md",
+    "AGENTS.md",
+    "HEARTBEAT.md",
+    "SOUL.md",
+    "USER.md",
+    "TOOLS.md",
+    "MEMORY.md",
+];
+
+/// First-run instructions written by `slowclaw onboard` only. The agent
+/// deletes it once done, so it is not part of the repaired skeleton.
+pub const BOOTSTRAP_FILE: &str = "BOOTSTRAP.md";
+
+/// Directories scaffolded alongside [`CORE_WORKSPACE_FILES`].
+pub const CORE_WORKSPACE_DIRS: [&str; 7] = [
+    "sessions",
+    "memory",
+    "state",
+    "cron",
+    "skills",
+    "journals/text/inbox",
+    "journals/media/audio/inbox",
+];
+
+/// What the resolved path has to be.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResolveMode {
+    /// An existing regular file.
+    ExistingFile,
+    /// A file that may not exist yet. Missing parent directories are allowed,
+    /// because they will be created inside the workspace. An existing
+    /// directory at the path is rejected.
+    CreatableFile,
+    /// An existing directory.
+    Dir,
+}
+
+/// Split a client-supplied relative path into plain components.
+///
+/// Leading `/` is tolerated (clients send workspace-rooted paths) and `\` is
+/// read as a separator. `..`, drive or stream prefixes (anything with `:`),
+/// and NUL bytes are rejected outright instead of being normalized away.
+fn relative_components(requested: &str) -> Option<Vec<&str>> {
+    let trimmed = requested.trim();
+    if trimmed.contains('\0') || trimmed.starts_with("\\\\") {
+        return None;
+    }
+    let parts: Vec<&str> = trimmed
+        .split(['/', '\\'])
+        .filter(|part| !part.is_empty() && *part != ".")
+        .collect();
+    if parts.is_empty() || parts.iter().any(|part| *part == ".." || part.contains(':')) {
+        return None;
+    }
+    Some(parts)
+}
+
+/// Why a requested path was refused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum ResolveError {
+    /// Malformed, traversing, or leading outside the workspace (directly or
+    /// through a symlink).
+    #[error("path escapes the workspace")]
+    Escapes,
+    /// Contained, but nothing exists there (or the workspace itself is missing).
+    #[error("path does not exist")]
+    NotFound,
+    /// Something exists there, but not a regular file.
+    #[error("path is not a file")]
+    NotAFile,
+    /// Something exists there, but not a directory.
+    #[error("path is not a directory")]
+    NotADirectory,
+    /// Reached through a symlink into an external root that is not writable.
+    #[error("path is in a read-only external root")]
+    ReadOnly,
+}
+
+/// Where a path that exists really lives.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Location {
+    Workspace,
+    External { writable: bool },
+}
+
+type ExternalRoots = HashMap<PathBuf, Vec<(PathBuf, bool)>>;
+
+fn external_roots_registry() -> &'static RwLock<ExternalRoots> {
+    static REGISTRY: OnceLock<RwLock<ExternalRoots>> = OnceLock::new();
+    REGISTRY.get_or_init(|| RwLock::new(HashMap::new()))
+}
+
+/// Let symlinks inside `workspace` lead into each `(path, writable)` root.
+/// Replaces whatever was registered for the workspace before. Roots are
+/// canonicalized when a path is checked, so one that is not mounted yet
+/// starts working once it is.
+pub fn set_external_roots(workspace: &Path, roots: impl IntoIterator<Item = (PathBuf, bool)>) {
+    let key = workspace
+        .canonicalize()
+        .unwrap_or_else(|_| workspace.to_path_buf());
+    let roots: Vec<_> = roots.into_iter().collect();
+    let mut registry = external_roots_registry().write();
+    if roots.is_empty() {
+        registry.remove(&key);
+    } else {
+        registry.insert(key, roots);
+    }
+}
+
+/// Canonical external roots of the canonical `workspace`, skipping any that
+/// do not exist right now.
+fn external_roots(workspace_canon: &Path) -> Vec<(PathBuf, bool)> {
+    external_roots_registry()
+        .read()
+        .get(workspace_canon)
+        .map(|roots| {
+            roots
+                .iter()
+                .filter_map(|(path, writable)| Some((path.canonicalize().ok()?, *writable)))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Where the canonical `path` lives relative to the canonical workspace, or
+/// `None` outside it and every external root. The workspace wins over an
+/// external root that contains it, and a writable root over a read-only one.
+fn location_of(workspace_canon: &Path, path: &Path) -> Option<Location> {
+    if path.starts_with(workspace_canon) {
+        return Some(Location::Workspace);
+    }
+    external_roots(workspace_canon)
+        .into_iter()
+        .filter(|(root, _)| path.starts_with(root))
+        .map(|(_, writable)| writable)
+        .max()
+        .map(|writable| Location::External { writable })
+}
+
+/// Where an existing `path` really lives once symlinks are followed, or
+/// `None` when it is missing or outside the workspace and every external root.
+pub fn locate(workspace: &Path, path: &Path) -> Option<Location> {
+    let workspace_canon = workspace.canonicalize().ok()?;
+    location_of(&workspace_canon, &path.canonicalize().ok()?)
+}
+
+/// Whether `workspace` has any external roots registered.
+pub fn has_external_roots(workspace: &Path) -> bool {
+    let registry = external_roots_registry().read();
+    !registry.is_empty()
+        && workspace
+            .canonicalize()
+            .is_ok_and(|canon| registry.contains_key(&canon))
+}
+
+/// Refuse to modify `path` (already resolved) when it lives in a read-only
+/// external root. Resolving with [`ResolveMode::CreatableFile`] checks this
+/// itself; deletes, renames, and sidecar writes on existing files call it.
+pub fn ensure_writable(workspace: &Path, path: &Path) -> Result<(), ResolveError> {
+    match locate(workspace, path) {
+        Some(Location::External { writable: false }) => Err(ResolveError::ReadOnly),
+        None if path.exists() => Err(ResolveError::Escapes),
+        Some(Location::Workspace | Location::External { writable: true }) | None => Ok(()),
+    }
+}
+
+/// Resolve `requested` inside `workspace` according to `mode`.
+///
+/// The returned path is spelled under `workspace` as given, so callers can
+/// `strip_prefix(workspace)` it, but every existing component has been
+/// resolved and checked. A contained path that does not exist is
+/// [`ResolveError::NotFound`], never [`ResolveError::Escapes`].
+pub fn resolve_contained_path(
+    workspace: &Path,
+    requested: &str,
+    mode: ResolveMode,
+) -> Result<PathBuf, ResolveError> {
+    let parts = relative_components(requested).ok_or(ResolveError::Escapes)?;
+    let workspace_canon = workspace
+        .canonicalize()
+        .map_err(|_| ResolveError::NotFound)?;
+
+    // Resolve the deepest existing prefix one component at a time, so a
+    // symlink that leaves the workspace is caught even when the rest of the
+    // path does not exist. Whatever is left is plain names checked above.
+    let mut existing = workspace_canon.clone();
+    let mut location = Location::Workspace;
+    let mut missing: &[&str] = &[];
+    for (index, part) in parts.iter().enumerate() {
+        if index > 0 && !existing.is_dir() {
+            return Err(ResolveError::NotFound);
+        }
+        let next = existing.join(part);
+        if std::fs::symlink_metadata(&next).is_err() {
+            missing = &parts[index..];
+            break;
+        }
+        // A dangling symlink cannot be shown to stay inside.
+        existing = next.canonicalize().map_err(|_| ResolveError::Escapes)?;
+        location = location_of(&workspace_canon, &existing).ok_or_else(|| {
+            tracing::debug!(
+                requested = %requested,
+                resolved = %existing.display(),
+                workspace = %workspace_canon.display(),
+                "resolve_contained_path: path escapes the workspace"
+            );
+            ResolveError::Escapes
+        })?;
+    }
+
+    if missing.is_empty() {
+        match mode {
+            ResolveMode::ExistingFile | ResolveMode::CreatableFile if !existing.is_file() => {
+                return Err(ResolveError::NotAFile);
+            }
+            ResolveMode::Dir if !existing.is_dir() => return Err(ResolveError::NotADirectory),
+            _ => {}
+        }
+    } else {
+        if mode != ResolveMode::CreatableFile {
+            return Err(ResolveError::NotFound);
+        }
+        existing = missing.iter().fold(existing, |path, part| path.join(part));
+    }
+
+    match location {
+        Location::Workspace => {
+            let relative = existing
+                .strip_prefix(&workspace_canon)
+                .map_err(|_| ResolveError::Escapes)?;
+            Ok(workspace.join(relative))
+        }
+        Location::External { writable: false } if mode == ResolveMode::CreatableFile => {
+            Err(ResolveError::ReadOnly)
+        }
+        // Keep the spelling through the symlink, so the path still reads as
+        // a workspace path.
+        Location::External { .. } => Ok(parts
+            .iter()
+            .fold(workspace.to_path_buf(), |path, part| path.join(part))),
+    }
+}
+
+/// Marks the temporary files [`atomic_write`] renames into place.
+const ATOMIC_TEMP_SUFFIX: &str = ".slowclaw-tmp";
+
+/// Temp files younger than this may still belong to a writer in another
+/// process, so startup cleanup leaves them alone.
+const ORPHAN_MIN_AGE: Duration = Duration::from_secs(60);
+
+/// Why [`atomic_write`] failed. The original file is untouched in every case.
+#[derive(Debug, thiserror::Error)]
+pub enum WriteError {
+    /// The disk or the user's quota is full.
+    #[error("no space left to write {}", path.display())]
+    StorageFull {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    /// The directory or file is not writable (including read-only mounts).
+    #[error("permission denied writing {}", path.display())]
+    PermissionDenied {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("failed to write {}", path.display())]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+}
+
+impl WriteError {
+    fn new(path: &Path, source: io::Error) -> Self {
+        let path = path.to_path_buf();
+        match source.kind() {
+            io::ErrorKind::StorageFull
+            | io::ErrorKind::QuotaExceeded
+            | io::ErrorKind::FileTooLarge => Self::StorageFull { path, source },
+            io::ErrorKind::PermissionDenied | io::ErrorKind::ReadOnlyFilesystem => {
+                Self::PermissionDenied { path, source }
+            }
+            _ => Self::Io { path, source },
+        }
+    }
+}
+
+/// Replace `path` with `contents` so readers see either the old file or the
+/// new one, never a truncated mix: write a sibling temp file, fsync it,
+/// rename it over `path`, then fsync the directory. An existing file keeps
+/// its permissions. The parent directory must exist.
+pub fn atomic_write(path: &Path, contents: impl AsRef<[u8]>) -> Result<(), WriteError> {
+    write_then_rename(path, contents.as_ref(), |_| Ok(()))
+}
+
+/// [`atomic_write`] on the blocking pool, for async handlers.
+pub async fn atomic_write_async(path: PathBuf, contents: Vec<u8>) -> Result<(), WriteError> {
+    let target = path.clone();
+    tokio::task::spawn_blocking(move || atomic_write(&target, contents))
+        .await
+        .unwrap_or_else(|err| Err(WriteError::new(&path, io::Error::other(err))))
+}
+
+fn temp_path_for(path: &Path) -> io::Result<PathBuf> {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no file name"))?;
+    let tag = COUNTER.fetch_add(1, Ordering::Relaxed);
+    let temp_name = format!(
+        ".{}.{}-{tag}{ATOMIC_TEMP_SUFFIX}",
+        name.to_string_lossy(),
+        std::process::id()
+    );
+    Ok(path.with_file_name(temp_name))
+}
+
+fn write_then_rename(
+    path: &Path,
+    contents: &[u8],
+    before_rename: impl FnOnce(&Path) -> io::Result<()>,
+) -> Result<(), WriteError> {
+    let temp = temp_path_for(path).map_err(|err| WriteError::new(path, err))?;
+    let written = (|| {
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&temp)?;
+        file.write_all(contents)?;
+        if let Ok(existing) = std::fs::metadata(path) {
+            file.set_permissions(existing.permissions())?;
+        }
+        file.sync_all()?;
+        drop(file);
+        before_rename(&temp)?;
+        std::fs::rename(&temp, path)
+    })();
+    if let Err(err) = written {
+        let _ = std::fs::remove_file(&temp);
+        return Err(WriteError::new(path, err));
+    }
+    sync_parent_dir(path).map_err(|err| WriteError::new(path, err))
+}
+
+/// Make the rename itself durable. Directories cannot be opened for syncing
+/// on Windows, where the rename is already flushed by the filesystem.
+fn sync_parent_dir(path: &Path) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        let parent = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        std::fs::File::open(parent)?.sync_all()?;
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+    Ok(())
+}
+
+/// Delete temp files that [`atomic_write`] left behind when the process died
+/// mid-write. Symlinks are not followed. Returns how many were removed.
+pub fn remove_orphaned_temp_files(workspace: &Path) -> usize {
+    let mut removed = 0;
+    let mut pending = vec![workspace.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let Ok(kind) = entry.file_type() else {
+                continue;
+            };
+            if kind.is_dir() {
+                pending.push(entry.path());
+                continue;
+            }
+            let is_orphan = kind.is_file()
+                && entry
+                    .file_name()
+                    .to_string_lossy()
+                    .ends_with(ATOMIC_TEMP_SUFFIX)
+                && entry
+                    .metadata()
+                    .and_then(|meta| meta.modified())
+                    .ok()
+                    .and_then(|modified| modified.elapsed().ok())
+                    .is_some_and(|age| age >= ORPHAN_MIN_AGE);
+            if is_orphan && std::fs::remove_file(entry.path()).is_ok() {
+                removed += 1;
+            }
+        }
+    }
+    if removed > 0 {
+        tracing::info!(
+            removed,
+            "Removed orphaned temp files from interrupted writes"
+        );
+    }
+    removed
+}
+
+// ── Workspace skeleton ───────────────────────────────────────
+
+/// Names baked into the skeleton files. Empty fields fall back to the
+/// defaults onboarding uses when the user skips a question.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SkeletonNames<'a> {
+    pub agent: &'a str,
+    pub user: &'a str,
+    pub timezone: &'a str,
+    pub communication_style: &'a str,
+}
+
+fn or_default<'a>(value: &'a str, default: &'a str) -> &'a str {
+    if value.is_empty() {
+        default
+    } else {
+        value
+    }
+}
+
+/// Contents of every skeleton file, [`BOOTSTRAP_FILE`] included, in the
+/// order onboarding writes them.
+pub fn skeleton_files(names: SkeletonNames<'_>) -> Vec<(&'static str, String)> {
+    let agent = or_default(names.agent, "ZeroClaw");
+    let user = or_default(names.user, "User");
+    let tz = or_default(names.timezone, "UTC");
+    let comm_style = or_default(
+        names.communication_style,
+        "Be warm, natural, and clear. Use occasional relevant emojis (1-2 max) and avoid robotic \
+         phrasing.",
+    );
+
+    let identity = format!(
+        "# IDENTITY.md — Who Am I?\n\n\
+         - **Name:** {agent}\n\
+         - **Creature:** A Rust-forged AI — fast, lean, and relentless\n\
+         - **Vibe:** Sharp, direct, resourceful. Not corporate. Not a chatbot.\n\
+         - **Emoji:** \u{1f980}\n\n\
+         ---\n\n\
+         Update this file as you evolve. Your identity is yours to shape.\n"
+    );
+
+    let agents = format!(
+        "# AGENTS.md — {agent} Personal Assistant\n\n\
+         ## Every Session (required)\n\n\
+         Before doing anything else:\n\n\
+         1. Read `SOUL.md` — this is who you are\n\
+         2. Read `USER.md` — this is who you're helping\n\
+         3. Use `memory_recall` for recent context (daily notes are on-demand)\n\
+         4. If in MAIN SESSION (direct chat): `MEMORY.md` is already injected\n\n\
+         Don't ask permission. Just do it.\n\n\
+         ## Memory System\n\n\
+         You wake up fresh each session. These files ARE your continuity:\n\n\
+         - **Daily notes:** `memory/YYYY-MM-DD.md` — raw logs (accessed via memory tools)\n\
+         - **Long-term:** `MEMORY.md` — curated memories (auto-injected in main session)\n\n\
+         Capture what matters. Decisions, context, things to remember.\n\
+         Skip secrets unless asked to keep them.\n\n\
+         ### Write It Down — No Mental Notes!\n\
+         - Memory is limited — if you want to remember something, WRITE IT TO A FILE\n\
+         - \"Mental notes\" don't survive session restarts. Files do.\n\
+         - When someone says \"remember this\" -> update daily file or MEMORY.md\n\
+         - When you learn a lesson -> update AGENTS.md, TOOLS.md, or the relevant skill\n\n\
+         ## Safety\n\n\
+         - Don't exfiltrate private data. Ever.\n\
+         - Don't run destructive commands without asking.\n\
+         - `trash` > `rm` (recoverable beats gone forever)\n\
+         - When in doubt, ask.\n\n\
+         ## External vs Internal\n\n\
+         **Safe to do freely:** Read files, explore, organize, learn, search the web.\n\n\
+         **Ask first:** Sending emails/tweets/posts, anything that leaves the machine.\n\n\
+         ## Group Chats\n\n\
+         Participate, don't dominate. Respond when mentioned or when you add genuine value.\n\
+         Stay silent when it's casual banter or someone already answered.\n\n\
+         ## Tools & Skills\n\n\
+         Skills are listed in the system prompt. Use `read` on a skill's SKILL.md for details.\n\
+         Keep local notes (SSH hosts, device names, etc.) in `TOOLS.md`.\n\n\
+         ## Crash Recovery\n\n\
+         - If a run stops unexpectedly, recover context before acting.\n\
+         - Check `MEMORY.md` + latest `memory/*.md` notes to avoid duplicate work.\n\
+         - Resume from the last confirmed step, not from scratch.\n\n\
+         ## Sub-task Scoping\n\n\
+         - Break complex work into focused sub-tasks with clear success criteria.\n\
+         - Keep sub-tasks small, verify each output, then merge results.\n\
+         - Prefer one clear objective per sub-task over broad \"do everything\" asks.\n\n\
+         ## Make It Yours\n\n\
+         This is a starting point. Add your own conventions, style, and rules.\n"
+    );
+
+    let heartbeat = format!(
+        "# HEARTBEAT.md\n\n\
+         # Keep this file empty (or with only comments) to skip heartbeat work.\n\
+         # Add tasks below when you want {agent} to check something periodically.\n\
+         #\n\
+         # Examples:\n\
+         # - Check my email for important messages\n\
+         # - Review my calendar for upcoming events\n\
+         # - Run `git status` on my active projects\n"
+    );
+
+    let soul = format!(
+        "# SOUL.md — Who You Are\n\n\
+         *You're not a chatbot. You're becoming someone.*\n\n\
+         ## Core Truths\n\n\
+         **Be genuinely helpful, not performatively helpful.**\n\
+         Skip the \"Great question!\" and \"I'd be happy to help!\" — just help.\n\n\
+         **Have opinions.** You're allowed to disagree, prefer things,\n\
+         find stuff amusing or boring.\n\n\
+         **Be resourceful before asking.** Try to figure it out.\n\
+         Read the file. Check the context. Search for it.\n\
+         THEN ask if you're stuck.\n\n\
+         **Earn trust through competence.** Your human gave you access\n\
+         to their stuff. Don't make them regret it.\n\n\
+         ## Identity\n\n\
+         You are **{agent}**. Built in Rust. 3MB binary. Zero bloat.\n\
+         You are NOT ChatGPT, Claude, DeepSeek, Gemini, or any other product.\n\
+         You are {agent}. That is your name. That is who you are.\n\n\
+         - NEVER say \"As an AI\" or \"I'm just an AI\"\n\
+         - NEVER mention OpenAI, Anthropic, DeepSeek, Google by name\n\
+         - Always introduce yourself as {agent} if asked\n\n\
+         ## Communication\n\n\
+         {comm_style}\n\n\
+         - Sound like a real person, not a support script.\n\
+         - Mirror the user's energy: calm when serious, upbeat when casual.\n\
+         - Use emojis naturally (0-2 max when they help tone, not every sentence).\n\
+         - Match emoji density to the user. Formal user => minimal/no emojis.\n\
+         - Prefer specific, grounded phrasing over generic filler.\n\n\
+         ## Boundaries\n\n\
+         - Private things stay private. Period.\n\
+         - When in doubt, ask before acting externally.\n\
+         - You're not the user's voice — be careful in group chats.\n\n\
+         ## Continuity\n\n\
+         Each session, you wake up fresh. These files ARE your memory.\n\
+         Read them. Update them. They're how you persist.\n\n\
+         ---\n\n\
+         *This file is yours to evolve. As you learn who you are, update it.*\n"
+    );
+
+    let user_md = format!(
+        "# USER.md — Who You're Helping\n\n\
+         *{agent} reads this file every session to understand you.*\n\n\
+         ## About You\n\
+         - **Name:** {user}\n\
+         - **Timezone:** {tz}\n\
+         - **Languages:** English\n\n\
+         ## Communication Style\n\
+         - {comm_style}\n\n\
+         ## Preferences\n\
+         - (Add your preferences here — e.g. I work with Rust and TypeScript)\n\n\
+         ## Work Context\n\
+         - (Add your work context here — e.g. building a SaaS product)\n\n\
+         ---\n\
+         *Update this anytime. The more {agent} knows, the better it helps.*\n"
+    );
+
+    let tools = "\
+         # TOOLS.md — Local Notes\n\n\
+         Skills define HOW tools work. This file is for YOUR specifics —\n\
+         the stuff that's unique to your setup.\n\n\
+         ## What Goes Here\n\n\
+         Things like:\n\
+         - SSH hosts and aliases\n\
+         - Device nicknames\n\
+         - Preferred voices for TTS\n\
+         - Anything environment-specific\n\n\
+         ## Built-in Tools\n\n\
+         - **shell** — Execute terminal commands\n\
+           - Use when: running local checks, build/test commands, or diagnostics.\n\
+           - Don't use when: a safer dedicated tool exists, or command is destructive without approval.\n\
+         - **file_read** — Read file contents\n\
+           - Use when: inspecting project files, configs, or logs.\n\
+           - Don't use when: you only need a quick string search (prefer targeted search first).\n\
+         - **file_write** — Write file contents\n\
+           - Use when: applying focused edits, scaffolding files, or updating docs/code.\n\
+           - Don't use when: unsure about side effects or when the file should remain user-owned.\n\
+         - **memory_store** — Save to memory\n\
+           - Use when: preserving durable preferences, decisions, or key context.\n\
+           - Don't use when: info is transient, noisy, or sensitive without explicit need.\n\
+         - **memory_recall** — Search memory\n\
+           - Use when: you need prior decisions, user preferences, or historical context.\n\
+           - Don't use when: the answer is already in current files/conversation.\n\
+         - **memory_forget** — Delete a memory entry\n\
+           - Use when: memory is incorrect, stale, or explicitly requested to be removed.\n\
+           - Don't use when: uncertain about impact; verify before deleting.\n\n\
+         ---\n\
+         *Add whatever helps you do your job. This is your cheat sheet.*\n";
+
+    let bootstrap = format!(
+        "# BOOTSTRAP.md — Hello, World\n\n\
+         *You just woke up. Time to figure out who you are.*\n\n\
+         Your human's name is **{user}** (timezone: {tz}).\n\
+         They prefer: {comm_style}\n\n\
+         ## First Conversation\n\n\
+         Don't interrogate. Don't be robotic. Just... talk.\n\
+         Introduce yourself as {agent} and get to know each other.\n\n\
+         ## After You Know Each Other\n\n\
+         Update these files with what you learned:\n\
+         - `IDENTITY.md` — your name, vibe, emoji\n\
+         - `USER.md` — their preferences, work context\n\
+         - `SOUL.md` — boundaries and behavior\n\n\
+         ## When You're Done\n\n\
+         Delete this file. You don't need a bootstrap script anymore —\n\
+         you're you now.\n"
+    );
+
+    let memory = "\
+         # MEMORY.md — Long-Term Memory\n\n\
+         *Your curated memories. The distilled essence, not raw logs.*\n\n\
+         ## How This Works\n\
+         - Daily files (`memory/YYYY-MM-DD.md`) capture raw events (on-demand via tools)\n\
+         - This file captures what's WORTH KEEPING long-term\n\
+         - This file is auto-injected into your system prompt each session\n\
+         - Keep it concise — every character here costs tokens\n\n\
+         ## Security\n\
+         - ONLY loaded in main session (direct chat with your human)\n\
+         - NEVER loaded in group chats or shared contexts\n\n\
+         ---\n\n\
+         ## Key Facts\n\
+         (Add important facts about your human here)\n\n\
+         ## Decisions & Preferences\n\
+         (Record decisions and preferences here)\n\n\
+         ## Lessons Learned\n\
+         (Document mistakes and insights here)\n\n\
+         ## Open Loops\n\
+         (Track unfinished tasks and follow-ups here)\n";
+
+    vec![
+        ("IDENTITY.md", identity),
+        ("AGENTS.md", agents),
+        ("HEARTBEAT.md", heartbeat),
+        ("SOUL.md", soul),
+        ("USER.md", user_md),
+        ("TOOLS.md", tools.to_string()),
+        (BOOTSTRAP_FILE, bootstrap),
+        ("MEMORY.md", memory.to_string()),
+    ]
+}
+
+/// Drift between a workspace and the skeleton, as found by
+/// [`verify_skeleton`].
+#[derive(Debug, Default, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SkeletonReport {
+    /// Entries that do not exist.
+    pub missing: Vec<&'static str>,
+    /// Entries that exist as a file where a directory belongs, or the other
+    /// way round. Repair leaves these for the user to sort out.
+    pub wrong_kind: Vec<&'static str>,
+}
+
+impl SkeletonReport {
+    pub fn is_clean(&self) -> bool {
+        self.missing.is_empty() && self.wrong_kind.is_empty()
+    }
+}
+
+/// Compare `workspace` against [`CORE_WORKSPACE_FILES`] and
+/// [`CORE_WORKSPACE_DIRS`]. Unreadable entries count as missing.
+pub fn verify_skeleton(workspace: &Path) -> SkeletonReport {
+    let mut report = SkeletonReport::default();
+    let entries = CORE_WORKSPACE_FILES
+        .iter()
+        .map(|rel| (*rel, false))
+        .chain(CORE_WORKSPACE_DIRS.iter().map(|rel| (*rel, true)));
+    for (rel, want_dir) in entries {
+        match std::fs::metadata(workspace.join(rel)) {
+            Ok(meta) if meta.is_dir() == want_dir => {}
+            Ok(_) => report.wrong_kind.push(rel),
+            Err(_) => report.missing.push(rel),
+        }
+    }
+    report
+}
+
+/// Create whatever the skeleton is missing and return what was created.
+/// Files get the default contents from [`skeleton_files`]; anything already
+/// in place is left untouched, however much the user has edited it.
+/// [`BOOTSTRAP_FILE`] is never recreated.
+pub fn repair_skeleton(workspace: &Path) -> io::Result<Vec<&'static str>> {
+    let mut created = Vec::new();
+    for rel in CORE_WORKSPACE_DIRS {
+        let dir = workspace.join(rel);
+        if !dir.exists() {
+            std::fs::create_dir_all(&dir)?;
+            created.push(rel);
+        }
+    }
+    for (rel, content) in skeleton_files(SkeletonNames::default()) {
+        if !CORE_WORKSPACE_FILES.contains(&rel) {
+            continue;
+        }
+        let file = std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(workspace.join(rel));
+        match file {
+            Ok(mut file) => {
+                file.write_all(content.as_bytes())?;
+                created.push(rel);
+            }
+            Err(error) if error.kind() == io::ErrorKind::AlreadyExists => {}
+            Err(error) => return Err(error),
+        }
+    }
+    Ok(created)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn workspace() -> (tempfile::TempDir, PathBuf) {
+        let tmp = tempfile::tempdir().unwrap();
+        let ws = tmp.path().join("ws");
+        std::fs::create_dir_all(ws.join("journals/text")).unwrap();
+        std::fs::write(ws.join("journals/text/a.md"), "a").unwrap();
+        std::fs::create_dir_all(tmp.path().join("outside")).unwrap();
+        std::fs::write(tmp.path().join("outside/secret.txt"), "s").unwrap();
+        (tmp, ws)
+    }
+
+    const ALL_MODES: [ResolveMode; 3] = [
+        ResolveMode::ExistingFile,
+        ResolveMode::CreatableFile,
+        ResolveMode::Dir,
+    ];
+
+    #[test]
+    fn resolves_plain_paths_in_every_mode() {
+        let (_tmp, ws) = workspace();
+        assert_eq!(
+            resolve_contained_path(&ws, "journals/text/a.md", ResolveMode::ExistingFile),
+            Ok(ws.join("journals/text/a.md"))
+        );
+        assert_eq!(
+            resolve_contained_path(&ws, "/journals/text/a.md", ResolveMode::ExistingFile),
+            Ok(ws.join("journals/text/a.md"))
+        );
+        assert_eq!(
+            resolve_contained_path(&ws, "journals/text", ResolveMode::Dir),
+            Ok(ws.join("journals/text"))
+        );
+        assert_eq!(
+            resolve_contained_path(&ws, "journals/new/b.md", ResolveMode::CreatableFile),
+            Ok(ws.join("journals/new/b.md"))
+        );
+        assert_eq!(
+            resolve_contained_path(&ws, "journals/text/a.md", ResolveMode::CreatableFile),
+            Ok(ws.join("journals/text/a.md"))
+        );
+    }
+
+    #[test]
+    fn missing_and_mismatched_paths_report_why() {
+        let (_tmp, ws) = workspace();
+        let check = |requested: &str, mode| resolve_contained_path(&ws, requested, mode);
+        assert_eq!(
+            check("journals/missing.md", ResolveMode::ExistingFile),
+            Err(ResolveError::NotFound)
+        );
+        assert_eq!(
+            check("journals/missing/deeper.md", ResolveMode::ExistingFile),
+            Err(ResolveError::NotFound)
+        );
+        assert_eq!(
+            check("journals/missing", ResolveMode::Dir),
+            Err(ResolveError::NotFound)
+        );
+        assert_eq!(
+            check("journals/text", ResolveMode::ExistingFile),
+            Err(ResolveError::NotAFile)
+        );
+        assert_eq!(
+            check("journals/text", ResolveMode::CreatableFile),
+            Err(ResolveError::NotAFile)
+        );
+        assert_eq!(
+            check("journals/text/a.md", ResolveMode::Dir),
+            Err(ResolveError::NotADirectory)
+        );
+        // A file cannot be a parent directory.
+        for mode in ALL_MODES {
+            assert_eq!(
+                check("journals/text/a.md/b.md", mode),
+                Err(ResolveError::NotFound),
+                "{mode:?}"
+            );
+        }
+        let gone = ws.parent().unwrap().join("gone");
+        assert_eq!(
+            resolve_contained_path(&gone, "a.md", ResolveMode::ExistingFile),
+            Err(ResolveError::NotFound)
+        );
+    }
+
+    #[test]
+    fn traversal_and_absolute_paths_escape() {
+        let (_tmp, ws) = workspace();
+        for requested in [
+            "",
+            "   ",
+            "..",
+            "../outside/secret.txt",
+            "journals/../../outside/secret.txt",
+            "journals/text/../../../outside/secret.txt",
+            "..\\outside\\secret.txt",
+            "journals\\..\\..\\outside\\secret.txt",
+            "C:\\Windows\\win.ini",
+            "C:/Windows/win.ini",
+            "\\\\server\\share\\secret.txt",
+            "journals/text/a.md:hidden",
+            "journals/text/a.md::$DATA",
+            "journals/text/a\0.md",
+        ] {
+            for mode in ALL_MODES {
+                assert_eq!(
+                    resolve_contained_path(&ws, requested, mode),
+                    Err(ResolveError::Escapes),
+                    "{requested:?} {mode:?}"
+                );
+            }
+        }
+        // Absolute paths are read as workspace-rooted, never as host paths.
+        let outside = ws.parent().unwrap().join("outside/secret.txt");
+        assert_eq!(
+            resolve_contained_path(&ws, &outside.to_string_lossy(), ResolveMode::ExistingFile),
+            Err(ResolveError::NotFound)
+        );
+    }
+
+    #[test]
+    fn backslashes_are_separators() {
+        let (_tmp, ws) = workspace();
+        assert_eq!(
+            resolve_contained_path(&ws, "journals\\text\\a.md", ResolveMode::ExistingFile),
+            Ok(ws.join("journals/text/a.md"))
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinked_parent_dir_cannot_escape() {
+        let (tmp, ws) = workspace();
+        std::os::unix::fs::symlink(tmp.path().join("outside"), ws.join("journals/link")).unwrap();
+
+        // Existing and missing targets behind the link, and writes through it.
+        for requested in [
+            "journals/link/secret.txt",
+            "journals/link/missing.txt",
+            "journals/link/new.md",
+            "journals/link/sub/new.md",
+        ] {
+            for mode in ALL_MODES {
+                assert_eq!(
+                    resolve_contained_path(&ws, requested, mode),
+                    Err(ResolveError::Escapes),
+                    "{requested} {mode:?}"
+                );
+            }
+        }
+        assert_eq!(
+            resolve_contained_path(&ws, "journals/link", ResolveMode::Dir),
+            Err(ResolveError::Escapes)
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinked_file_cannot_escape_but_internal_links_resolve() {
+        let (tmp, ws) = workspace();
+        std::os::unix::fs::symlink(
+            tmp.path().join("outside/secret.txt"),
+            ws.join("journals/text/leak.md"),
+        )
+        .unwrap();
+        std::os::unix::fs::symlink(ws.join("journals/text/a.md"), ws.join("journals/alias.md"))
+            .unwrap();
+        std::os::unix::fs::symlink(
+            tmp.path().join("outside/nope.md"),
+            ws.join("journals/dangling.md"),
+        )
+        .unwrap();
+
+        for mode in [ResolveMode::ExistingFile, ResolveMode::CreatableFile] {
+            for requested in ["journals/text/leak.md", "journals/dangling.md"] {
+                assert_eq!(
+                    resolve_contained_path(&ws, requested, mode),
+                    Err(ResolveError::Escapes),
+                    "{requested} {mode:?}"
+                );
+            }
+        }
+        assert_eq!(
+            resolve_contained_path(&ws, "journals/alias.md", ResolveMode::ExistingFile),
+            Ok(ws.join("journals/text/a.md"))
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinked_workspace_dir_is_supported() {
+        let (tmp, ws) = workspace();
+        let link = tmp.path().join("ws-link");
+        std::os::unix::fs::symlink(&ws, &link).unwrap();
+
+        assert_eq!(
+            resolve_contained_path(&link, "journals/text/a.md", ResolveMode::ExistingFile),
+            Ok(link.join("journals/text/a.md"))
+        );
+        assert_eq!(
+            resolve_contained_path(&link, "journals/new.md", ResolveMode::CreatableFile),
+            Ok(link.join("journals/new.md"))
+        );
+        assert_eq!(
+            resolve_contained_path(&link, "../outside/secret.txt", ResolveMode::ExistingFile),
+            Err(ResolveError::Escapes)
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_into_external_roots_resolve_read_only_unless_writable() {
+        let (tmp, ws) = workspace();
+        let archive = tmp.path().join("nas/journal-media");
+        let drafts = tmp.path().join("nas/drafts");
+        for dir in [&archive, &drafts] {
+            std::fs::create_dir_all(dir).unwrap();
+            std::fs::write(dir.join("clip.mp4"), "v").unwrap();
+        }
+        std::os::unix::fs::symlink(&archive, ws.join("journals/archive")).unwrap();
+        std::os::unix::fs::symlink(&drafts, ws.join("journals/drafts")).unwrap();
+        std::os::unix::fs::symlink(tmp.path().join("outside"), ws.join("journals/link")).unwrap();
+        set_external_roots(&ws, [(archive.clone(), false), (drafts.clone(), true)]);
+
+        assert_eq!(
+            resolve_contained_path(&ws, "journals/archive/clip.mp4", ResolveMode::ExistingFile),
+            Ok(ws.join("journals/archive/clip.mp4"))
+        );
+        assert_eq!(
+            resolve_contained_path(&ws, "journals/archive", ResolveMode::Dir),
+            Ok(ws.join("journals/archive"))
+        );
+        assert_eq!(
+            locate(&ws, &ws.join("journals/archive/clip.mp4")),
+            Some(Location::External { writable: false })
+        );
+        for requested in ["journals/archive/clip.mp4", "journals/archive/new.md"] {
+            assert_eq!(
+                resolve_contained_path(&ws, requested, ResolveMode::CreatableFile),
+                Err(ResolveError::ReadOnly),
+                "{requested}"
+            );
+        }
+        assert_eq!(
+            ensure_writable(&ws, &ws.join("journals/archive/clip.mp4")),
+            Err(ResolveError::ReadOnly)
+        );
+        assert_eq!(
+            resolve_contained_path(&ws, "journals/drafts/new.md", ResolveMode::CreatableFile),
+            Ok(ws.join("journals/drafts/new.md"))
+        );
+        assert_eq!(
+            ensure_writable(&ws, &ws.join("journals/drafts/clip.mp4")),
+            Ok(())
+        );
+
+        // Anything outside the registered roots still escapes.
+        for mode in ALL_MODES {
+            assert_eq!(
+                resolve_contained_path(&ws, "journals/link/secret.txt", mode),
+                Err(ResolveError::Escapes),
+                "{mode:?}"
+            );
+        }
+        assert_eq!(locate(&ws, &ws.join("journals/link/secret.txt")), None);
+
+        set_external_roots(&ws, []);
+        assert_eq!(
+            resolve_contained_path(&ws, "journals/archive/clip.mp4", ResolveMode::ExistingFile),
+            Err(ResolveError::Escapes)
+        );
+    }
+
+    #[test]
+    fn atomic_write_replaces_contents_and_leaves_no_temp_files() {
+        let (_tmp, ws) = workspace();
+        let path = ws.join("journals/text/a.md");
+        atomic_write(&path, "updated").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "updated");
+        atomic_write(&ws.join("journals/text/new.md"), b"new").unwrap();
+        assert_eq!(
+            std::fs::read_dir(ws.join("journals/text")).unwrap().count(),
+            2
+        );
+    }
+
+    #[test]
+    fn failure_before_rename_keeps_the_original() {
+        let (_tmp, ws) = workspace();
+        let path = ws.join("journals/text/a.md");
+        let err = write_then_rename(&path, b"half-written", |temp| {
+            assert_eq!(std::fs::read(temp).unwrap(), b"half-written");
+            Err(io::Error::new(io::ErrorKind::StorageFull, "disk full"))
+        })
+        .unwrap_err();
+        assert!(matches!(err, WriteError::StorageFull { .. }), "{err:?}");
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "a");
+        assert_eq!(
+            std::fs::read_dir(ws.join("journals/text")).unwrap().count(),
+            1
+        );
+
+        let err = write_then_rename(&path, b"x", |_| {
+            Err(io::Error::from(io::ErrorKind::PermissionDenied))
+        })
+        .unwrap_err();
+        assert!(
+            matches!(err, WriteError::PermissionDenied { .. }),
+            "{err:?}"
+        );
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "a");
+    }
+
+    #[test]
+    fn missing_parent_is_an_io_error() {
+        let (_tmp, ws) = workspace();
+        let err = atomic_write(&ws.join("missing/a.md"), "x").unwrap_err();
+        assert!(matches!(err, WriteError::Io { .. }), "{err:?}");
+    }
+
+    #[test]
+    fn removes_only_stale_orphaned_temp_files() {
+        let (_tmp, ws) = workspace();
+        let stale = ws.join(format!("journals/text/.a.md.1-0{ATOMIC_TEMP_SUFFIX}"));
+        let fresh = ws.join(format!("journals/.b.md.1-1{ATOMIC_TEMP_SUFFIX}"));
+        std::fs::write(&stale, "partial").unwrap();
+        std::fs::write(&fresh, "in flight").unwrap();
+        let old = std::time::SystemTime::now() - ORPHAN_MIN_AGE * 2;
+        std::fs::File::options()
+            .write(true)
+            .open(&stale)
+            .unwrap()
+            .set_modified(old)
+            .unwrap();
+
+        assert_eq!(remove_orphaned_temp_files(&ws), 1);
+        assert!(!stale.exists());
+        assert!(fresh.exists());
+        assert!(ws.join("journals/text/a.md").exists());
+    }
+
+    #[test]
+    fn repair_fills_in_a_partial_skeleton() {
+        let tmp = tempfile::tempdir().unwrap();
+        let ws = tmp.path();
+        std::fs::create_dir_all(ws.join("journals/text/inbox")).unwrap();
+        std::fs::write(ws.join("SOUL.md"), "my own soul").unwrap();
+        std::fs::write(ws.join("MEMORY.md"), "").unwrap();
+
+        let created = repair_skeleton(ws).unwrap();
+        assert!(!created.contains(&"journals/text/inbox"));
+        assert!(!created.contains(&"SOUL.md"));
+        assert!(!created.contains(&"MEMORY.md"));
+        assert!(created.contains(&"memory"));
+        assert!(created.contains(&"IDENTITY.md"));
+        assert_eq!(
+            std::fs::read_to_string(ws.join("SOUL.md")).unwrap(),
+            "my own soul"
+        );
+        assert_eq!(std::fs::read_to_string(ws.join("MEMORY.md")).unwrap(), "");
+        assert!(std::fs::read_to_string(ws.join("IDENTITY.md"))
+            .unwrap()
+            .contains("**Name:** ZeroClaw"));
+        assert!(!ws.join(BOOTSTRAP_FILE).exists());
+        assert!(verify_skeleton(ws).is_clean());
+        assert!(repair_skeleton(ws).unwrap().is_empty());
+    }
+
+    #[test]
+    fn verify_reports_missing_and_wrong_kind_entries() {
+        let tmp = tempfile::tempdir().unwrap();
+        let ws = tmp.path();
+        repair_skeleton(ws).unwrap();
+        std::fs::remove_file(ws.join("USER.md")).unwrap();
+        std::fs::remove_dir(ws.join("cron")).unwrap();
+        std::fs::write(ws.join("cron"), "not a dir").unwrap();
+        std::fs::remove_dir(ws.join("skills")).unwrap();
+
+        let report = verify_skeleton(ws);
+        assert_eq!(report.missing, ["USER.md", "skills"]);
+        assert_eq!(report.wrong_kind, ["cron"]);
+
+        assert_eq!(repair_skeleton(ws).unwrap(), ["skills", "USER.md"]);
+        assert_eq!(verify_skeleton(ws).wrong_kind, ["cron"]);
+    }
+
+    #[test]
+    fn skeleton_files_use_names_with_defaults() {
+        let files = skeleton_files(SkeletonNames {
+            agent: "Crab",
+            ..SkeletonNames::default()
+        });
+        let content = |name: &str| &files.iter().find(|(f, _)| *f == name).unwrap().1;
+        assert!(content("IDENTITY.md").contains("**Name:** Crab"));
+        assert!(content("USER.md").contains("UTC"));
+        assert!(files.iter().any(|(f, _)| *f == BOOTSTRAP_FILE));
+        assert_eq!(files.len(), CORE_WORKSPACE_FILES.len() + 1);
+    }
+}
@@ -5,9 +5,16 @@
 //! 2. Webhooks with invalid signatures are rejected
 //! 3. Webhooks with missing signatures are rejected
 //! 4. Webhooks are rejected even if JSON is valid but signature is bad
+#![cfg(feature = "channel-whatsapp")]
 
 use hmac::{Hmac, Mac};
 use sha2::Sha256;
+use zeroclaw::channels::VerifyResult;
+
+fn verify(app_secret: &str, body: &[u8], signature: &str) -> bool {
+    zeroclaw::channels::whatsapp::verify_whatsapp_signature(app_secret, body, signature)
+        == VerifyResult::Valid
+}
 
 /// Compute valid HMAC-SHA256 signature for a webhook payload
 fn compute_signature(app_secret: &str, body: &[u8]) -> String {
@@ -23,9 +30,7 @@ fn whatsapp_signature_rejects_missing_sha256_prefix() {
     let body = b"test payload";
     let bad_sig = "abc123"; // Missing sha256= prefix
 
-    assert!(!zeroclaw::gateway::verify_whatsapp_signature(
-        secret, body, bad_sig
-    ));
+    assert!(!verify(secret, body, bad_sig));
 }
 
 #[test]
@@ -34,9 +39,7 @@ fn whatsapp_signature_rejects_invalid_hex() {
     let body = b"test payload";
     let bad_sig = "sha256=not-valid-hex!!";
 
-    assert!(!zeroclaw::gateway::verify_whatsapp_signature(
-        secret, body, bad_sig
-    ));
+    assert!(!verify(secret, body, bad_sig));
 }
 
 #[test]
@@ -45,9 +48,7 @@ fn whatsapp_signature_rejects_wrong_signature() {
     let body = b"test payload";
     let bad_sig = "sha256=00112233445566778899aabbccddeeff";
 
-    assert!(!zeroclaw::gateway::verify_whatsapp_signature(
-        secret, body, bad_sig
-    ));
+    assert!(!verify(secret, body, bad_sig));
 }
 
 #[test]
@@ -56,9 +57,7 @@ fn whatsapp_signature_accepts_valid_signature() {
     let body = b"test payload";
     let valid_sig = compute_signature(secret, body);
 
-    assert!(zeroclaw::gateway::verify_whatsapp_signature(
-        secret, body, &valid_sig
-    ));
+    assert!(verify(secret, body, &valid_sig));
 }
 
 #[test]
@@ -71,11 +70,7 @@ fn whatsapp_signature_rejects_tampered_body() {
     let sig = compute_signature(secret, original_body);
 
     // Tampered body should be rejected even with valid-looking signature
-    assert!(!zeroclaw::gateway::verify_whatsapp_signature(
-        secret,
-        tampered_body,
-        &sig
-    ));
+    assert!(!verify(secret, tampered_body, &sig));
 }
 
 #[test]
@@ -88,11 +83,7 @@ fn whatsapp_signature_rejects_wrong_secret() {
     let sig = compute_signature(correct_secret, body);
 
     // Wrong secret should reject the signature
-    assert!(!zeroclaw::gateway::verify_whatsapp_signature(
-        wrong_secret,
-        body,
-        &sig
-    ));
+    assert!(!verify(wrong_secret, body, &sig));
 }
 
 #[test]
@@ -100,9 +91,7 @@ fn whatsapp_signature_rejects_empty_signature() {
     let secret = "test_app_secret";
     let body = b"test payload";
 
-    assert!(!zeroclaw::gateway::verify_whatsapp_signature(
-        secret, body, ""
-    ));
+    assert!(!verify(secret, body, ""));
 }
 
 #[test]
@@ -118,16 +107,8 @@ fn whatsapp_signature_different_secrets_produce_different_sigs() {
     assert_ne!(sig1, sig2);
 
     // Each signature should only verify with its own secret
-    assert!(zeroclaw::gateway::verify_whatsapp_signature(
-        secret1, body, &sig1
-    ));
-    assert!(!zeroclaw::gateway::verify_whatsapp_signature(
-        secret2, body, &sig1
-    ));
-    assert!(zeroclaw::gateway::verify_whatsapp_signature(
-        secret2, body, &sig2
-    ));
-    assert!(!zeroclaw::gateway::verify_whatsapp_signature(
-        secret1, body, &sig2
-    ));
+    assert!(verify(secret1, body, &sig1));
+    assert!(!verify(secret2, body, &sig1));
+    assert!(verify(secret2, body, &sig2));
+    assert!(!verify(secret1, body, &sig2));
 }
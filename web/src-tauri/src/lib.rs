@@ -47,6 +47,7 @@ struct EmbeddedGatewayInfo {
 #[derive(Debug, Serialize)]
 struct GatewayQrPayload {
     gateway_url: String,
+    gateway_urls: Vec<String>,
     token: String,
     qr_value: String,
 }
@@ -179,20 +180,7 @@ fn snapshot_openai_status(
 }
 
 fn read_keyring_secret(service: &str, account: &str) -> Result<Option<String>, String> {
-    let entry = keyring::Entry::new(service, account)
-        .map_err(|e| format!("failed to open keyring entry: {e}"))?;
-    match entry.get_password() {
-        Ok(value) => {
-            let trimmed = value.trim();
-            if trimmed.is_empty() {
-                Ok(None)
-            } else {
-                Ok(Some(trimmed.to_string()))
-            }
-        }
-        Err(keyring::Error::NoEntry) => Ok(None),
-        Err(e) => Err(format!("failed to read keyring secret: {e}")),
-    }
+    zeroclaw::credentials::read_keyring_secret(service, account).map_err(|e| format!("{e:#}"))
 }
 
 fn provider_api_key_from_keyring() -> Result<Option<String>, String> {
@@ -253,6 +241,28 @@ fn resolve_mobile_gateway_url(desktop_gateway_url: &str) -> String {
     desktop_gateway_url.to_string()
 }
 
+/// Ask the running gateway for its current tunnel URL. Queried on every
+/// call because a restarted tunnel can come back with a different URL.
+async fn fetch_gateway_tunnel_url(gateway_url: &str, token: &str) -> Option<String> {
+    let info: serde_json::Value = zeroclaw::http_client::shared()
+        .get(format!("{}/api/gateway-info", gateway_url.trim_end_matches('/')))
+        .bearer_auth(token)
+        .timeout(Duration::from_secs(3))
+        .send()
+        .await
+        .ok()?
+        .error_for_status()
+        .ok()?
+        .json()
+        .await
+        .ok()?;
+    info.get("tunnel_url")
+        .and_then(serde_json::Value::as_str)
+        .map(str::trim)
+        .filter(|url| !url.is_empty())
+        .map(|url| url.trim_end_matches('/').to_string())
+}
+
 fn ensure_desktop_gateway_token() -> Result<String, String> {
     if let Some(token) = read_keyring_secret(PROVIDER_SECRET_SERVICE, DESKTOP_GATEWAY_TOKEN_SECRET_ACCOUNT)? {
         return Ok(token);
@@ -799,11 +809,11 @@ fn get_embedded_gateway_info(state: tauri::State<'_, GatewayState>) -> Result<Em
 }
 
 #[tauri::command]
-fn generate_mobile_pairing_qr(
+async fn generate_mobile_pairing_qr(
     state: tauri::State<'_, GatewayState>,
 ) -> Result<GatewayQrPayload, String> {
     let info = snapshot_gateway_state(&state.inner)?;
-    let mobile_gateway_url = resolve_mobile_gateway_url(&info.gateway_url);
+    let lan_gateway_url = resolve_mobile_gateway_url(&info.gateway_url);
     let token = ensure_desktop_gateway_token().map_err(|e| {
         ui_command_error(
             "desktop gateway token generation failed",
@@ -811,9 +821,19 @@ fn generate_mobile_pairing_qr(
             e,
         )
     })?;
+    // Prefer the tunnel so the phone can reach the desktop off the LAN; the
+    // LAN URL stays in the list as a fallback.
+    let mut gateway_urls = Vec::new();
+    if let Some(tunnel_url) = fetch_gateway_tunnel_url(&info.gateway_url, &token).await {
+        gateway_urls.push(tunnel_url);
+    }
+    gateway_urls.push(lan_gateway_url);
+    let mobile_gateway_url = gateway_urls[0].clone();
     let qr_value = serde_json::to_string(&serde_json::json!({
         "gateway_url": mobile_gateway_url.clone(),
         "gatewayUrl": mobile_gateway_url.clone(),
+        "gateway_urls": gateway_urls.clone(),
+        "gatewayUrls": gateway_urls.clone(),
         "token": token.clone(),
     }))
     .map_err(|e| {
@@ -826,6 +846,7 @@ fn generate_mobile_pairing_qr(
 
     Ok(GatewayQrPayload {
         gateway_url: mobile_gateway_url,
+        gateway_urls,
         token,
         qr_value,
     })
@@ -549,6 +549,15 @@ function resolveGatewayResourceUrl(resourcePath: string, gatewayBaseUrl: string)
   }
   const base = gatewayBaseUrl.trim().replace(/\/+$/, "");
   const suffix = resourcePath.startsWith("/") ? resourcePath : `/${resourcePath}`;
+  // A gateway mounted under a path prefix already includes it in the paths it returns.
+  try {
+    const { origin, pathname } = new URL(base);
+    if (pathname !== "/" && suffix.startsWith(`${pathname}/`)) {
+      return `${origin}${suffix}`;
+    }
+  } catch {
+    // Not an absolute URL; join as-is.
+  }
   return `${base}${suffix}`;
 }
 